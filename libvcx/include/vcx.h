@@ -34,6 +34,9 @@ typedef enum
   unfulfilled,
   expired,
   revoked,
+  redirected,
+  rejected,
+  sending,
 } vcx_state_t;
 
 typedef enum
@@ -60,6 +63,100 @@ vcx_error_t vcx_agent_provision_async(vcx_command_handle_t command_handle,
                                    const char *json,
                                    void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
 
+// Same as vcx_agent_provision_async, the provisioning can be cancelled by vcx_cancel.
+// Cancelled provisioning fails with Cancelled error, restores the settings and deletes the wallet it created,
+// a wallet which existed before is only closed and reused when provisioning is started again.
+// Agency steps completed before the cancellation are not undone, the agent created for them is left unused.
+vcx_error_t vcx_agent_provision_cancellable(vcx_command_handle_t command_handle,
+                                         const char *json,
+                                         vcx_u32_t cancellation_handle,
+                                         void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Creates a token to cancel long running operations (*_cancellable functions).
+// Cancelled operation stops at the next request to the agency or the ledger and fails with Cancelled error,
+// HTTP requests already in flight stop waiting for the response.
+vcx_error_t vcx_create_cancellation_token(vcx_u32_t *cancellation_handle_p);
+
+// Cancels operations running with the token, the token stays cancelled.
+vcx_error_t vcx_cancel(vcx_u32_t cancellation_handle);
+
+// Releases the cancellation token, operations running with it can not be cancelled anymore.
+vcx_error_t vcx_cancellation_token_release(vcx_u32_t cancellation_handle);
+
+// Starts provisioning of an agent in the agency: creates the wallet with new keys and connects to the agency.
+// Returned data should be confirmed by the user before provisioning is finished by vcx_provision_cloud_agent_complete.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// config: configuration
+//
+// cb: Callback that provides provision preparation or error status
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_provision_cloud_agent_prepare(vcx_command_handle_t command_handle,
+                                           const char *config,
+                                           void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Finishes provisioning started by vcx_provision_cloud_agent_prepare: registers in the agency and creates the agent.
+// The wallet of the preparation is used and completed steps are recorded in it, the call can be repeated
+// with the same preparation if interrupted.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// config: configuration, the same as passed to vcx_provision_cloud_agent_prepare
+//
+// preparation: provision preparation returned by vcx_provision_cloud_agent_prepare
+//
+// cb: Callback that provides configuration or error status
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_provision_cloud_agent_complete(vcx_command_handle_t command_handle,
+                                            const char *config,
+                                            const char *preparation,
+                                            void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Provision an agent in agency which requires sponsored onboarding, populate configuration and wallet for this agent.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// config: configuration
+//
+// token: provision token given by the sponsor
+//     {"sponseeId": string, "sponsorId": string, "nonce": string, "timestamp": string, "sig": string, "sponsorVerKey": string}
+//
+// cb: Callback that provides configuration or error status
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_provision_agent_with_token(vcx_command_handle_t command_handle,
+                                        const char *config,
+                                        const char *token,
+                                        void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Provision an agent for an end user on behalf of the caller acting as a sponsor. Settings and wallet
+// of the caller are not affected, the user's wallet is optionally exported for the app to restore it.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// subject_config: provisioning configuration of the user, the same as for vcx_provision_agent. `wallet_name` is required,
+//     the wallet must not exist yet and it is deleted when provisioning or export fails. Optional fields:
+//     {"provision_token": {..}, "exported_wallet_path": string, "backup_key": string}
+//
+// cb: Callback that provides handoff bundle or error status
+//     {"config": {..}, "exported_wallet_path": string}
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_provision_agent_for(vcx_command_handle_t command_handle,
+                                 const char *subject_config,
+                                 void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
 // Update information on the agent (ie, comm method and type)
 //
 // #Params
@@ -110,6 +207,24 @@ vcx_error_t vcx_connection_create(vcx_command_handle_t command_handle,
                                const char *source_id,
                                void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_connection_handle_t));
 
+// -> Create a Connection object announcing custom service endpoint and routing keys instead of the provisioned agency ones
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// source_id: institution's personal identification for the user
+//
+// endpoint_json: {"service_endpoint": "https://edge.example.org/msg", "routing_keys": ["..."]}, routing_keys are optional
+//
+// cb: Callback that provides connection handle and error status of request
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_connection_create_with_endpoint(vcx_command_handle_t command_handle,
+                                             const char *source_id,
+                                             const char *endpoint_json,
+                                             void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_connection_handle_t));
+
 // Create a Connection object from the given invite_details that provides a pairwise connection.
 //
 // #Params
@@ -128,6 +243,43 @@ vcx_error_t vcx_connection_create_with_invite(vcx_command_handle_t command_handl
                                            const char *invite_details,
                                            void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_connection_handle_t));
 
+// Create a Connection object from invitation URL, as scanned from QR code.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// source_id: institution's personal identification for the user
+//
+// invite_url: URL carrying base64url encoded invitation in `c_i` or `oob` query parameter, or shortened link redirecting to it
+//
+// cb: Callback that provides connection handle and error status of request
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_connection_create_with_invite_url(vcx_command_handle_t command_handle,
+                                               const char *source_id,
+                                               const char *invite_url,
+                                               void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_connection_handle_t));
+
+// Create a Connection object from out-of-band invitation asking for a connection.
+// Credential offer attached to the invitation is turned into Credential object.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// source_id: institution's personal identification for the connection and the credential
+//
+// invite: out-of-band invitation, as returned by `vcx_issuer_get_oob_offer`
+//
+// cb: Callback that provides connection handle, credential handle (0 when no offer is attached) and error status of request
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_connection_create_with_oob_invite(vcx_command_handle_t command_handle,
+                                               const char *source_id,
+                                               const char *invite,
+                                               void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_connection_handle_t, vcx_credential_handle_t));
+
 // Delete a Connection object and release its handle
 //
 // #Params
@@ -190,6 +342,24 @@ vcx_error_t vcx_connection_invite_details(vcx_command_handle_t command_handle,
                                        vcx_bool_t abbreviated,
                                        void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
 
+// Get invitation of the connection encoded into URL, suitable to be rendered as QR code.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// connection_handle: was provided during creation. Used to identify connection object
+//
+// base_url: URL the encoded invitation is appended to as `c_i` query parameter
+//
+// cb: Callback that provides the invitation URL
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_connection_get_invite_url(vcx_command_handle_t command_handle,
+                                       vcx_connection_handle_t connection_handle,
+                                       const char *base_url,
+                                       void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
 /// Get the information about the connection state.
 ///
 /// Note: This method can be used for `aries` communication method only.
@@ -218,6 +388,12 @@ vcx_error_t vcx_connection_invite_details(vcx_command_handle_t command_handle,
 ///             "routingKeys": array<str> - Routing keys
 ///             "serviceEndpoint": <str> - Endpoint
 ///             "protocols": array<str> - The set of protocol supported by side. Is filled after DiscoveryFeatures process was completed.
+///          },
+///         "stats": { - messaging activity observed since the library was loaded
+///             "messagesSent": <int>
+///             "messagesReceived": <int> - messages downloaded from agency and handled
+///             "lastActivity": <Option<str>> - RFC 3339 time of the last message sent or received
+///             "pendingMessages": <int> - messages waiting at agency at the last download, less the ones handled since
 ///          }
 ///    }
 ///
@@ -251,6 +427,14 @@ vcx_error_t vcx_connection_serialize(vcx_command_handle_t command_handle,
                                   vcx_connection_handle_t connection_handle,
                                   void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
 
+// Synchronous variants of the connection functions, they run on the calling thread and return
+// results through output pointers. Returned strings are valid until the next `*_sync` call returning
+// a string in the same thread, error details are available by `vcx_get_current_error`.
+vcx_error_t vcx_connection_create_sync(const char *source_id, vcx_connection_handle_t *connection_handle_p);
+vcx_error_t vcx_connection_update_state_sync(vcx_connection_handle_t connection_handle, vcx_state_t *state_p);
+vcx_error_t vcx_connection_get_state_sync(vcx_connection_handle_t connection_handle, vcx_state_t *state_p);
+vcx_error_t vcx_connection_serialize_sync(vcx_connection_handle_t connection_handle, const char **serialized_data_p);
+
 // Checks for any state change in the connection and updates the state attribute
 //
 // #Params
@@ -266,6 +450,62 @@ vcx_error_t vcx_connection_update_state(vcx_command_handle_t command_handle,
                                      vcx_connection_handle_t connection_handle,
                                      void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_state_t));
 
+// Same as vcx_connection_update_state, the update can be cancelled by vcx_cancel.
+// Cancellation is observed until a received message starts being handled, the update is completed after that.
+// Cancelled update fails with Cancelled error and the connection is unchanged.
+vcx_error_t vcx_connection_update_state_cancellable(vcx_command_handle_t command_handle,
+                                                 vcx_connection_handle_t connection_handle,
+                                                 vcx_u32_t cancellation_handle,
+                                                 void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_state_t));
+
+/// Send trust ping requesting response to measure round trip time of the connection, returns without waiting for the response.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// connection_handle: connection to ping, must be in Accepted state
+///
+/// cb: Callback that provides feedback of the api call
+///
+/// #Returns
+/// Error code as a u32
+vcx_error_t vcx_connection_ping_and_measure(vcx_u32_t command_handle,
+                                          vcx_connection_handle_t connection_handle,
+                                          void (*cb)(vcx_command_handle_t, vcx_error_t));
+
+/// Check whether the response to the ping sent by vcx_connection_ping_and_measure arrived.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// connection_handle: connection the ping was sent over
+///
+/// cb: Callback that provides whether the response was received and round trip time in milliseconds
+///
+/// #Returns
+/// Error code as a u32
+vcx_error_t vcx_connection_get_ping_rtt(vcx_u32_t command_handle,
+                                      vcx_connection_handle_t connection_handle,
+                                      void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_bool_t, vcx_u32_t));
+
+/// Answer trust pings received on completed connection.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// connection_handle: connection to answer pings on, must be in Accepted state
+///
+/// cb: Callback that provides the number of answered pings
+///
+/// #Returns
+/// Error code as a u32
+vcx_error_t vcx_connection_answer_pings(vcx_u32_t command_handle,
+                                      vcx_connection_handle_t connection_handle,
+                                      void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_u32_t));
+
 /// Send trust ping message to the specified connection to prove that two agents have a functional pairwise channel.
 ///
 /// Note that this function is useful in case `aries` communication method is used.
@@ -288,6 +528,67 @@ vcx_error_t vcx_connection_send_ping(vcx_u32_t command_handle,
                                      const char* comment,
                                      void (*cb)(vcx_command_handle_t, vcx_error_t));
 
+/// Send `invite-action` message asking the counterparty to take an action, e.g. to start a presentation.
+/// The counterparty is asked to acknowledge the acceptance.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// connection_handle: connection to send message, it must be in Accepted state.
+///
+/// goal_code: identifier of the requested action, e.g. "present-proof".
+///
+/// cb: Callback that provides sent message
+///
+/// #Returns
+/// Error code as a u32
+vcx_error_t vcx_connection_send_invite_action(vcx_command_handle_t command_handle,
+                                              vcx_connection_handle_t connection_handle,
+                                              const char* goal_code,
+                                              void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+/// Accept received `invite-action` message, the inviter is acknowledged if it asked for it.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// connection_handle: connection the message was received on.
+///
+/// invite: received `invite-action` message.
+///
+/// cb: Callback that provides success or failure of request
+///
+/// #Returns
+/// Error code as a u32
+vcx_error_t vcx_connection_accept_invite_action(vcx_command_handle_t command_handle,
+                                                vcx_connection_handle_t connection_handle,
+                                                const char* invite,
+                                                void (*cb)(vcx_command_handle_t, vcx_error_t));
+
+/// Reject received `invite-action` message by sending problem report to the inviter.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// connection_handle: connection the message was received on.
+///
+/// invite: received `invite-action` message.
+///
+/// comment: (Optional) human-friendly reason of the rejection.
+///
+/// cb: Callback that provides success or failure of request
+///
+/// #Returns
+/// Error code as a u32
+vcx_error_t vcx_connection_reject_invite_action(vcx_command_handle_t command_handle,
+                                                vcx_connection_handle_t connection_handle,
+                                                const char* invite,
+                                                const char* comment,
+                                                void (*cb)(vcx_command_handle_t, vcx_error_t));
+
 /// Send discovery features message to the specified connection to discover which features it supports, and to what extent.
 ///
 /// Note that this function is useful in case `aries` communication method is used.
@@ -313,6 +614,22 @@ vcx_error_t vcx_connection_send_discovery_features(vcx_u32_t command_handle,
                                                    const char* comment,
                                                    void (*cb)(vcx_command_handle_t, vcx_error_t));
 
+// Get protocols the counterparty disclosed in response to vcx_connection_send_discovery_features
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// connection_handle: was provided during creation. Used to identify connection object
+//
+// cb: Callback that provides the list of protocols:
+//     [{"pid": "...", "family": "present-proof", "version": "1.0", "roles": ["Verifier"]}]
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_connection_get_their_supported_protocols(vcx_command_handle_t command_handle,
+                                                        vcx_connection_handle_t connection_handle,
+                                                        void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
 // Takes the Connection object and returns callers pw_did associated with this connection
 //
 // #Params
@@ -458,6 +775,73 @@ vcx_error_t vcx_credential_get_state(vcx_command_handle_t command_handle,
                                   vcx_credential_handle_t handle,
                                   void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_state_t));
 
+// Makes credential send the credential request on state update with connection handle,
+// so that the offer does not need to be accepted by separate call.
+//
+// #Params
+// handle: Credential handle that was provided during creation. Used to access credential object
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_credential_enable_auto_accept(vcx_credential_handle_t handle);
+
+// Retrieve attributes of the credential (from the offer preview until the credential is received).
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// credential_handle: credential handle that was provided during creation. Used to identify credential object
+//
+// cb: Callback that provides error status of api call, or returns the attributes in json format:
+//     [{"name":"photo","value":"iVBORw0KGgo...","mime-type":"image/png"}]
+//     Values of non-textual mime types are base64 encoded.
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_credential_get_attributes(vcx_command_handle_t command_handle,
+                                          vcx_credential_handle_t credential_handle,
+                                          void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Retrieve attributes previewed in the credential offer with labels in the requested locale,
+// taken from `~l10n` decorator of the offer. Attributes without such label are labeled by their name.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// credential_handle: credential handle that was provided during creation. Used to identify credential object
+//
+// locale: requested locale, e.g. "es" or "es-MX"
+//
+// cb: Callback that provides error status of api call, or returns the attributes in json format:
+//     [{"name":"age","label":"Edad","value":"25","mime-type":"text/plain"}]
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_credential_get_offer_preview(vcx_command_handle_t command_handle,
+                                             vcx_credential_handle_t credential_handle,
+                                             const char *locale,
+                                             void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Retrieve details of the credential offer to be shown to the holder before accepting it:
+// issuer, credential definition and schema of the offered credential, previewed attributes
+// and whether the credential will be revocable.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// credential_handle: credential handle that was provided during creation. Used to identify credential object
+//
+// cb: Callback that provides error status of api call, or returns the details in json format:
+//     {"issuer_did":"V4SGRU86Z58d6TV7PBUe6f","cred_def_id":"V4SGRU86Z58d6TV7PBUe6f:3:CL:31:tag1",
+//      "schema_id":"V4SGRU86Z58d6TV7PBUe6f:2:FaberVcx:83.23.62","schema_name":"FaberVcx","schema_version":"83.23.62",
+//      "attributes":[{"name":"age","value":"25","mime-type":"text/plain"}],"revocable":false}
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_credential_get_offer_details(vcx_command_handle_t command_handle,
+                                             vcx_credential_handle_t credential_handle,
+                                             void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
 // Releases the credential object by de-allocating memory
 //
 // #Params
@@ -467,6 +851,73 @@ vcx_error_t vcx_credential_get_state(vcx_command_handle_t command_handle,
 // Success
 vcx_error_t vcx_credential_release(vcx_credential_handle_t handle);
 
+// Set store the non-secret metadata of held credentials are mirrored to on every state change of a credential object,
+// so wallet UIs can list credentials without opening the wallet. Failures of the store are only logged.
+// Previously set store is replaced.
+//
+// context: pointer to some context that will be available in the callbacks.
+// store: called with the credential metadata json, replaces metadata stored for the same thread_id. Returns 0 on success.
+// remove: called with the thread_id of the credential which was deleted. Returns 0 on success.
+// #Returns
+// Error code as u32
+vcx_error_t vcx_set_credential_metadata_store(const void* context,
+                                              vcx_error_t (*store)(const void* context, const char* metadata),
+                                              vcx_error_t (*remove)(const void* context, const char* thread_id));
+
+// Remove store set by vcx_set_credential_metadata_store.
+vcx_error_t vcx_unset_credential_metadata_store();
+
+// Retrieves W3C Verifiable Credential stored in the wallet.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// credential_id: id of the stored credential.
+//
+// cb: Callback that provides json string of the credential and error status of request
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_w3c_credential_get(vcx_command_handle_t command_handle,
+                                   const char *credential_id,
+                                   void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Lists W3C Verifiable Credentials stored in the wallet.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// cb: Callback that provides json string of the credentials and error status of request
+//     [{"id": "<credential id>", "credential": {"@context": [...], "type": [...], ...}}]
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_w3c_credential_list(vcx_command_handle_t command_handle,
+                                    void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Rejects the credential offer, or stops waiting for the credential once the request is sent.
+// The issuer is informed by problem report on the issuance thread and the credential moves
+// into the finished state with failed credential status.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+//
+// connection_handle: Connection handle that identifies pairwise connection
+//
+// reason: human readable reason sent to the issuer in the problem report
+//
+// cb: Callback that provides error status of abandoning the exchange
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_credential_abandon(vcx_command_handle_t command_handle,
+                                   vcx_credential_handle_t credential_handle,
+                                   vcx_connection_handle_t connection_handle,
+                                   const char *reason,
+                                   void (*cb)(vcx_command_handle_t, vcx_error_t));
+
 // Send a credential request to the connection, called after having received a credential offer
 //
 // #params
@@ -523,6 +974,11 @@ vcx_error_t vcx_credential_serialize(vcx_command_handle_t command_handle,
                                   vcx_credential_handle_t handle,
                                   void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
 
+// Synchronous variants of the credential functions, see `vcx_connection_create_sync`.
+vcx_error_t vcx_credential_update_state_sync(vcx_credential_handle_t credential_handle, vcx_state_t *state_p);
+vcx_error_t vcx_credential_get_state_sync(vcx_credential_handle_t credential_handle, vcx_state_t *state_p);
+vcx_error_t vcx_credential_serialize_sync(vcx_credential_handle_t credential_handle, const char **serialized_data_p);
+
 // Checks for any state change in the credential and updates the state attribute.  If it detects a credential it
 // will store the credential in the wallet and update the state.
 //
@@ -539,6 +995,15 @@ vcx_error_t vcx_credential_update_state(vcx_command_handle_t command_handle,
                                      vcx_credential_handle_t credential_handle,
                                      void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_state_t));
 
+// Checks for a state change of the credential with messages received over the connection, the update can be
+// cancelled by vcx_cancel until a received message starts being handled. Cancelled update fails with Cancelled error
+// and the credential is unchanged.
+vcx_error_t vcx_credential_update_state_cancellable(vcx_command_handle_t command_handle,
+                                                 vcx_credential_handle_t credential_handle,
+                                                 vcx_connection_handle_t connection_handle,
+                                                 vcx_u32_t cancellation_handle,
+                                                 void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_state_t));
+
 // Create a new CredentialDef object that can create credential definitions on the ledger
 //
 // #Params
@@ -572,6 +1037,37 @@ vcx_error_t vcx_credentialdef_create(vcx_command_handle_t command_handle,
                                   vcx_payment_handle_t payment_handle,
                                   void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_credential_handle_t));
 
+// Runs vcx_credentialdef_create as a dry run: the credential definition transactions are built, signed and priced,
+// but they are not submitted to the ledger and no CredentialDef object is created.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// source_id: Enterprise's personal identification for the user.
+//
+// schema_id: The schema id given during the creation of the schema
+//
+// issuer_did: did corresponding to entity issuing a credential. Needs to have Trust Anchor permissions on ledger
+//
+// tag: way to create a unique credential def with the same schema and issuer did.
+//
+// config: revocation info
+//
+// payment_handle: future use (currently uses any address in wallet)
+//
+// cb: Callback that provides the prepared transactions json and error status of request.
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_credentialdef_dry_run(vcx_command_handle_t command_handle,
+                                   const char *source_id,
+                                   const char *schema_id,
+                                   const char *issuer_did,
+                                   const char *tag,
+                                   const char *config,
+                                   vcx_payment_handle_t payment_handle,
+                                   void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
 /// Create a new CredentialDef object that will be published by Endorser later.
 ///
 /// #Params
@@ -611,6 +1107,25 @@ vcx_error_t vcx_credentialdef_prepare_for_endorser(vcx_command_handle_t command_
                                                   const char *endorser,
                                                   void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_credential_handle_t, const char*, const char*, const char*));
 
+/// Prepares transactions of the CredentialDef object which was created by `vcx_credentialdef_prepare_for_endorser`
+/// but is not published yet, e.g. when the transactions have to be passed to another Endorser.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// credentialdef_handle: Credentialdef handle that was provided during creation. Used to access credentialdef object
+///
+/// endorser: DID of the Endorser that will submit the transaction.
+///
+/// cb: Callback that provides transactions (CredentialDef, Option<RevocRegDef>, Option<RevocRegEntry>) that should be passed to Endorser for publishing.
+///
+/// #Returns
+/// Error code as a u32
+vcx_error_t vcx_credentialdef_prepare_existing_for_endorser(vcx_command_handle_t command_handle,
+                                                           vcx_credentialdef_handle_t credentialdef_handle,
+                                                           const char *endorser,
+                                                           void (*cb)(vcx_command_handle_t, vcx_error_t, const char*, const char*, const char*));
+
 // Takes a json string representing a credentialdef object and recreates an object matching the json
 //
 // #Params
@@ -639,6 +1154,61 @@ vcx_error_t vcx_credentialdef_get_cred_def_id(vcx_command_handle_t command_handl
                                            vcx_credential_handle_t cred_def_handle,
                                            void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
 
+// Lists credential definitions created or prepared for an endorser by this issuer, as recorded in the wallet.
+//
+// #Params
+// cb: Callback that provides json array of the credential definitions and error status
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_credentialdef_list_created(vcx_command_handle_t command_handle,
+                                           void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Changes revocation parameters of a revocable credential definition.
+//
+// #Params
+// credentialdef_handle: CredDef handle that was provided during creation.
+//
+// revocation_details: json with optional `tails_file` and `max_creds`.
+//     New `max_creds` is used by the revocation registries created by following rotations.
+//     New `tails_file` location publishes a new revocation registry right away.
+//
+// cb: Callback that provides the serialized credential definition and error status
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_credentialdef_update_revocation_details(vcx_command_handle_t command_handle,
+                                                        vcx_credentialdef_handle_t credentialdef_handle,
+                                                        const char *revocation_details,
+                                                        void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Retrieves id, capacity and number of issued credentials of the active revocation registry of the credential definition.
+//
+// #Params
+// credentialdef_handle: CredDef handle that was provided during creation.
+//
+// cb: Callback that provides the usage json and error status
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_credentialdef_get_rev_reg_usage(vcx_command_handle_t command_handle,
+                                                vcx_credentialdef_handle_t credentialdef_handle,
+                                                void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Runs vcx_credentialdef_publish_revocations as a dry run: the transaction is prepared without being submitted
+// and the locally stored revocations are kept.
+//
+// #Params
+// credentialdef_handle: CredDef handle that was provided during creation.
+//
+// cb: Callback that provides the prepared transaction json and error status
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_credentialdef_dry_run_publish_revocations(vcx_command_handle_t command_handle,
+                                                          vcx_credentialdef_handle_t credentialdef_handle,
+                                                          void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
 // Retrieve the txn associated with paying for the credential_def
 //
 // #param
@@ -754,6 +1324,25 @@ vcx_error_t vcx_disclosed_proof_create_with_request(vcx_command_handle_t command
                                                  const char *proof_req,
                                                  void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_disclosed_proof_handle_t));
 
+// Create a proof answering proof request attached to out-of-band invitation, the proof is sent
+// to the service of the invitation without establishing a connection
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// source_id: Institution's identification for the proof, should be unique.
+//
+// oob_invitation: out-of-band invitation with the proof request attached
+//
+// cb: Callback that provides proof handle or error status
+//
+// #Returns
+// Error code as u32
+vcx_error_t vcx_disclosed_proof_create_from_oob(vcx_command_handle_t command_handle,
+                                             const char *source_id,
+                                             const char *oob_invitation,
+                                             void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_disclosed_proof_handle_t));
+
 // Takes a json string representing an disclosed proof object and recreates an object matching the json
 //
 // #Params
@@ -795,6 +1384,15 @@ vcx_error_t vcx_disclosed_proof_generate_proof(vcx_command_handle_t command_hand
                                             const char *self_attested_attrs,
                                             void (*cb)(vcx_command_handle_t, vcx_error_t));
 
+// Same as vcx_disclosed_proof_generate_proof, the generation can be cancelled by vcx_cancel.
+// Cancelled generation fails with Cancelled error, the proof can be generated again.
+vcx_error_t vcx_disclosed_proof_generate_proof_cancellable(vcx_command_handle_t command_handle,
+                                                        vcx_disclosed_proof_handle_t proof_handle,
+                                                        const char *selected_credentials,
+                                                        const char *self_attested_attrs,
+                                                        vcx_u32_t cancellation_handle,
+                                                        void (*cb)(vcx_command_handle_t, vcx_error_t));
+
 /// Declines presentation request.
 /// There are two ways of following interaction:
 ///     - Prover wants to propose using a different presentation - pass `proposal` parameter.
@@ -916,6 +1514,21 @@ vcx_error_t vcx_disclosed_proof_retrieve_credentials(vcx_command_handle_t comman
                                                   vcx_disclosed_proof_handle_t proof_handle,
                                                   void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
 
+// Takes the disclosed proof object and returns a UI friendly json preview of the associated proof request
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// handle: Proof handle that was provided during creation. Used to identify the disclosed proof object
+//
+// cb: Callback that provides json string of the proof request preview
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_disclosed_proof_get_request_preview(vcx_command_handle_t command_handle,
+                                                  vcx_disclosed_proof_handle_t proof_handle,
+                                                  void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
 // Send a proof to the connection, called after having received a proof request
 //
 // #params
@@ -983,13 +1596,15 @@ vcx_error_t vcx_disclosed_proof_get_reject_msg(vcx_command_handle_t command_hand
                                                void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
 
 // Redirects to an existing connection, if a connection already exists.
+// Answers the invitation of the new connection by a Redirect message carrying pairwise DIDs of the existing one,
+// the new connection moves to VcxStateRedirected state (Invitee only).
 //
 // #params
 // command_handle: command handle to map callback to API user context.
 //
-// connection_handle: Connection handle that identifies pairwise connection.
+// connection_handle: Connection handle of the new connection in VcxStateOfferSent state.
 //
-// redirect_connection_handle: Redirect connection handle, used to identify an existing connection.
+// redirect_connection_handle: Redirect connection handle, used to identify an existing connection in VcxStateAccepted state.
 //
 // cb: Callback that provides error status of a redirection.
 //
@@ -1001,13 +1616,15 @@ vcx_error_t vcx_connection_redirect(vcx_command_handle_t command_handle,
                                     void (*cb)(vcx_command_handle_t, vcx_error_t));
 
 // Gets the details of an existing connection.
+// Returns Redirect message sent (Invitee) or received (Inviter) by a connection in VcxStateRedirected state,
+// `their_did` is the inviter's pairwise DID of the existing connection.
 //
 // #params
 // command_handle: command handle to map callback to API user context.
 //
 // connection_handle: Connection handle that identifies pairwise connection.
 //
-// cb: Callback that provides error status of a redirection.
+// cb: Callback that provides json string of redirect details and error status.
 //
 // #Returns
 // Error code as u32
@@ -1015,6 +1632,22 @@ vcx_error_t vcx_connection_get_redirect_details(vcx_command_handle_t command_han
                                                 vcx_connection_handle_t connection_handle,
                                                 void (*cb)(vcx_command_handle_t, vcx_error_t, const char *));
 
+// Verifies again the `connection~sig` of the Connection Response received by the Invitee
+// against the recipient key of the accepted invitation. The signature age is not checked.
+//
+// #params
+// command_handle: command handle to map callback to API user context.
+//
+// connection_handle: Connection handle of the Invitee connection in VcxStateAccepted state.
+//
+// cb: Callback that provides error status of the verification.
+//
+// #Returns
+// Error code as u32
+vcx_error_t vcx_connection_verify_response_signature(vcx_command_handle_t command_handle,
+                                                     vcx_connection_handle_t connection_handle,
+                                                     void (*cb)(vcx_command_handle_t, vcx_error_t));
+
 // Takes the disclosed proof object and returns a json string of all its attributes
 //
 // #Params
@@ -1030,6 +1663,11 @@ vcx_error_t vcx_disclosed_proof_serialize(vcx_command_handle_t command_handle,
                                        vcx_disclosed_proof_handle_t proof_handle,
                                        void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
 
+// Synchronous variants of the disclosed proof functions, see `vcx_connection_create_sync`.
+vcx_error_t vcx_disclosed_proof_update_state_sync(vcx_disclosed_proof_handle_t proof_handle, vcx_state_t *state_p);
+vcx_error_t vcx_disclosed_proof_get_state_sync(vcx_disclosed_proof_handle_t proof_handle, vcx_state_t *state_p);
+vcx_error_t vcx_disclosed_proof_serialize_sync(vcx_disclosed_proof_handle_t proof_handle, const char **serialized_data_p);
+
 // Checks for any state change in the disclosed proof and updates the state attribute
 //
 // #Params
@@ -1045,6 +1683,15 @@ vcx_error_t vcx_disclosed_proof_update_state(vcx_command_handle_t command_handle
                                           vcx_disclosed_proof_handle_t proof_handle,
                                           void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_state_t));
 
+// Checks for a state change of the disclosed proof with messages received over the connection, the update can be
+// cancelled by vcx_cancel until a received message starts being handled. Cancelled update fails with Cancelled error
+// and the proof is unchanged.
+vcx_error_t vcx_disclosed_proof_update_state_cancellable(vcx_command_handle_t command_handle,
+                                                      vcx_disclosed_proof_handle_t proof_handle,
+                                                      vcx_connection_handle_t connection_handle,
+                                                      vcx_u32_t cancellation_handle,
+                                                      void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_state_t));
+
 const char *vcx_error_c_message(vcx_error_t error_code);
 
 // Retrieve information about a stored credential in user's wallet, including credential id and the credential itself.
@@ -1145,6 +1792,89 @@ vcx_error_t vcx_issuer_create_credential(vcx_command_handle_t command_handle,
                                       vcx_payment_handle_t price,
                                       void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_issuer_credential_handle_t));
 
+// Create a Issuer Credential object issuing W3C Verifiable Credential (JSON-LD) instead of Indy credential.
+// The credential is signed by the institution verkey (`institution_verkey` setting) with `SortedJsonEd25519Signature`
+// proof over the credential JSON, which is verified by libvcx only. Its issuer is `did:key` of the verkey.
+// No credential definition is needed and the credential is not revocable.
+// The messages announce `libvcx/sorted-json-vc-detail@v1.0` and `libvcx/sorted-json-vc@v1.0` formats.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// source_id: Enterprise's personal identification for the user.
+//
+// credential_data: claims of the credential subject, same format as in `vcx_issuer_create_credential`
+//
+// cb: Callback that provides credential handle and error status of request
+//
+// #Returns
+// Error code as a u32
+//
+// # Example credential_data -> "{"state":"UT"}"
+vcx_error_t vcx_issuer_create_w3c_credential(vcx_command_handle_t command_handle,
+                                          const char *source_id,
+                                          const char *credential_data,
+                                          void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_issuer_credential_handle_t));
+
+// Create a Credential Template describing credentials issued repeatedly with the same credential definition.
+// Fixed attributes are shared by all credentials, dynamic attributes are supplied for every credential
+// created by `vcx_issuer_create_credential_from_template`. Together they must cover the schema of the credential definition.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// source_id: Enterprise's personal identification for the user, given to credentials created from the template.
+//
+// cred_def_handle: handle of the published credential definition
+//
+// template_name: Name of the credentials created from the template - ex. Drivers Licence
+//
+// template_json: fixed attributes, names of dynamic attributes and optional rules for their values
+//
+// cb: Callback that provides credential template handle and error status of request
+//
+// #Returns
+// Error code as a u32
+//
+// # Example template_json -> "{"fixed": {"state": "UT"}, "dynamic": ["name", "age"], "rules": {"age": {"pattern": "^[0-9]+$", "max_length": 3}}}"
+vcx_error_t vcx_credential_template_create(vcx_command_handle_t command_handle,
+                                        const char *source_id,
+                                        vcx_credentialdef_handle_t cred_def_handle,
+                                        const char *template_name,
+                                        const char *template_json,
+                                        void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_u32_t));
+
+// Releases the credential template object by de-allocating memory.
+// Credentials created from the template are not affected.
+//
+// #Params
+// template_handle: Credential Template handle that was provided during creation.
+//
+// #Returns
+// Success
+vcx_error_t vcx_credential_template_release(vcx_u32_t template_handle);
+
+// Create a Issuer Credential object from Credential Template. The credential uses credential definition,
+// source id and name of the template, its data are fixed attributes of the template completed by `dynamic_values`.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// template_handle: Credential Template handle that was provided during creation.
+//
+// dynamic_values: values of all dynamic attributes of the template, they have to satisfy rules of the template
+//
+// cb: Callback that provides credential handle and error status of request
+//
+// #Returns
+// Error code as a u32
+//
+// # Example dynamic_values -> "{"name": "Alice", "age": "25"}"
+vcx_error_t vcx_issuer_create_credential_from_template(vcx_command_handle_t command_handle,
+                                                    vcx_u32_t template_handle,
+                                                    const char *dynamic_values,
+                                                    void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_issuer_credential_handle_t));
+
 // Takes a json string representing an issuer credential object and recreates an object matching the json
 //
 // #Params
@@ -1197,6 +1927,41 @@ vcx_error_t vcx_issuer_credential_get_state(vcx_command_handle_t command_handle,
                                          vcx_issuer_credential_handle_t credential_handle,
                                          void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_state_t));
 
+// Makes issuer credential send the credential as soon as the credential request is received
+// by state update, so that the credential does not need to be sent by separate call.
+//
+// #Params
+// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_issuer_credential_enable_auto_issue(vcx_issuer_credential_handle_t credential_handle);
+
+// Makes issuer credential send the credential with `~please_ack` decorator. The issuance is not finished
+// once the credential is sent, but once the holder acknowledges the credential (or reports a problem).
+// Credential status stays undefined until then, while the state is already VcxStateAccepted.
+//
+// #Params
+// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_issuer_credential_request_ack(vcx_issuer_credential_handle_t credential_handle);
+
+// Sets the time the credential offer is valid for. The offer is sent with `~timing` decorator with `expires_time`,
+// `update_state` moves the credential into VcxStateExpired once the time passes without a credential request
+// and a request received after the deadline is rejected by a problem report. The holder moves its credential into
+// VcxStateExpired as well when the offer was not accepted in time. Has to be called before the offer is sent.
+//
+// #Params
+// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+//
+// expires_in_secs: number of seconds the offer is valid after it is sent
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_issuer_credential_set_expiration(vcx_issuer_credential_handle_t credential_handle, vcx_u32_t expires_in_secs);
+
 // Releases the issuer credential object by deallocating memory
 //
 // #Params
@@ -1221,6 +1986,11 @@ vcx_error_t vcx_issuer_credential_serialize(vcx_command_handle_t command_handle,
                                          vcx_issuer_credential_handle_t credential_handle,
                                          void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
 
+// Synchronous variants of the issuer credential functions, see `vcx_connection_create_sync`.
+vcx_error_t vcx_issuer_credential_update_state_sync(vcx_issuer_credential_handle_t credential_handle, vcx_state_t *state_p);
+vcx_error_t vcx_issuer_credential_get_state_sync(vcx_issuer_credential_handle_t credential_handle, vcx_state_t *state_p);
+vcx_error_t vcx_issuer_credential_serialize_sync(vcx_issuer_credential_handle_t credential_handle, const char **serialized_data_p);
+
 // Checks for any state change in the credential and updates the state attribute
 //
 // #Params
@@ -1236,8 +2006,32 @@ vcx_error_t vcx_issuer_credential_update_state(vcx_command_handle_t command_hand
                                             vcx_issuer_credential_handle_t credential_handle,
                                             void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_state_t));
 
+// Withdraws the credential offer or stops the issuance before the credential is acknowledged.
+// The holder is informed by problem report on the issuance thread, over the connection the offer was sent over,
+// and the credential moves into the finished state with failed credential status.
+// The holder of an out-of-band offer who has not connected yet cannot be informed, the exchange is finished anyway.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+//
+// reason: human readable reason sent to the holder in the problem report
+//
+// cb: Callback that provides error status of abandoning the exchange and whether the holder was informed
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_issuer_credential_abandon(vcx_command_handle_t command_handle,
+                                          vcx_issuer_credential_handle_t credential_handle,
+                                          const char *reason,
+                                          void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_bool_t));
+
 // Send Credential that was requested by user
 //
+// A revocable credential takes its place in the revocation registry when it is built,
+// so the credential definition has to be loaded in this process.
+//
 // #Params
 // command_handle: command handle to map callback to user context.
 //
@@ -1254,6 +2048,22 @@ vcx_error_t vcx_issuer_send_credential(vcx_command_handle_t command_handle,
                                     vcx_connection_handle_t connection_handle,
                                     void (*cb)(vcx_command_handle_t, vcx_error_t));
 
+// Get out-of-band invitation to a new connection with the credential offer attached,
+// the invitee answers the offer once the connection is established
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+//
+// cb: provides out-of-band invitation and error status of the request
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_issuer_get_oob_offer(vcx_command_handle_t command_handle,
+                                  vcx_issuer_credential_handle_t credential_handle,
+                                  void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
 // Send a credential offer to user showing what will be included in the actual credential
 //
 // #Params
@@ -1316,6 +2126,19 @@ vcx_error_t vcx_issuer_get_credential_msg(vcx_command_handle_t command_handle,
 // Error code as a u32
 vcx_error_t vcx_ledger_get_fees(vcx_command_handle_t command_handle, void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
 
+// Estimates the amount of tokens needed to write a transaction of given type to the ledger
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// txn_type: ledger transaction type, e.g. "101" for schema, "102" for credential definition
+//
+// cb: Callback that provides the estimated fees, zero when the write is free
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_ledger_estimate_fees(vcx_command_handle_t command_handle, const char *txn_type, void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_u64_t));
+
 // Retrieve messages from the specified connection
 //
 // #params
@@ -1375,6 +2198,134 @@ vcx_error_t vcx_messages_update_status(vcx_command_handle_t command_handle,
                                     const char *msg_json,
                                     void (*cb)(vcx_command_handle_t, vcx_error_t));
 
+// Retrieve messages from the agent narrowed down to a thread and/or time range
+//
+// #params
+//
+// command_handle: command handle to map callback to user context.
+//
+// message_status: optional, comma separated - query for messages with the specified status
+//
+// uids: optional, comma separated - query for messages with the specified uids
+//
+// pw_dids: optional, comma separated - DID's pointing to specific connection
+//
+// filter_json: {"thread_id": "...", "from": "2020-05-01T00:00:00Z", "to": "2020-05-02T00:00:00Z"}, all fields optional
+//
+// cb: Callback that provides array of matching messages retrieved
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_messages_download_filtered(vcx_command_handle_t command_handle,
+                                          const char *message_status,
+                                          const char *uids,
+                                          const char *pw_dids,
+                                          const char *filter_json,
+                                          void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Count messages stored by agency for each message status
+//
+// #params
+//
+// command_handle: command handle to map callback to user context.
+//
+// pw_dids: optional, comma separated - DID's pointing to specific connection
+//
+// cb: Callback that provides number of messages by status code: {"MS-103": 2, "MS-106": 5}
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_messages_get_counts_by_status(vcx_command_handle_t command_handle,
+                                             const char *pw_dids,
+                                             void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Update the status of messages identified by their uids
+//
+// #params
+//
+// command_handle: command handle to map callback to user context.
+//
+// message_status: target status, any of MS-101 - MS-107
+//
+// uids: comma separated uids of messages to update
+//
+// cb: Callback that provides success or failure of request
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_messages_update_status_by_uids(vcx_command_handle_t command_handle,
+                                              const char *message_status,
+                                              const char *uids,
+                                              void (*cb)(vcx_command_handle_t, vcx_error_t));
+
+// Download messages received on all connections by a single agency request and update state
+// of all connection, credential and proof objects using them.
+//
+// #params
+//
+// command_handle: command handle to map callback to user context.
+//
+// cb: Callback that provides list of objects which changed state
+//     [{"object_type": "connection", "handle": 1, "prev_state": 2, "state": 4}, ...]
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_update_all_states(vcx_command_handle_t command_handle,
+                                  void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Try to deliver messages which could not be sent due to network errors and were kept
+// in the outbound queue (see `outbound_queue` config option).
+// Messages are delivered in the order they were queued.
+// Objects whose messages wait in the queue report state VcxStateSending (10), the ones whose
+// message was rejected by the receiver report state VcxStateRejected (9).
+//
+// #params
+// command_handle: command handle to map callback to user context.
+//
+// cb: Callback that provides number of messages still waiting for delivery
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_flush_outbound_queue(vcx_command_handle_t command_handle,
+                                  void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_u32_t));
+
+// Decrypt inbound message and update state of the credential or proof object
+// handling its thread (`~thread.thid`).
+//
+// #params
+//
+// command_handle: command handle to map callback to user context.
+//
+// message: inbound message as received on the endpoint, authcrypted by the counterparty of the connection the object is bound to
+//
+// cb: Callback that provides object which handled the message
+//     {"object_type": "proof", "handle": 1, "thread_id": "...", "state": 4}
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_handle_inbound_message(vcx_command_handle_t command_handle,
+                                       const char *message,
+                                       void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Register callback called whenever any connection, credential or proof object changes its state.
+// Previously registered callback is replaced.
+// Callback is called on the thread causing the state change and should return quickly.
+//
+// #params
+//
+// cb: Callback that provides the state change
+//     {"handle_type": "proof", "handle": 1, "old_state": 2, "new_state": 4, "thread_id": "..."}
+//
+// #Returns
+// Error code as u32
+vcx_error_t vcx_register_state_change_callback(void (*cb)(const char*));
+
+// Unregister callback set by vcx_register_state_change_callback.
+//
+// #Returns
+// Error code as u32
+vcx_error_t vcx_unregister_state_change_callback();
+
 // Create a new Proof object that requests a proof for an enterprise
 //
 // #Params
@@ -1402,6 +2353,29 @@ vcx_error_t vcx_proof_create(vcx_command_handle_t command_handle,
                           const char *name,
                           void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_proof_handle_t));
 
+// Create a new Proof object that requests DIF Presentation Exchange submission of W3C Verifiable Credentials
+// instead of Indy proof. The request announces `libvcx/sorted-json-pe-definitions@v1.0` format, the presentation
+// is signed by `SortedJsonEd25519Signature`, so only libvcx provers can answer it.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// source_id: Enterprise's personal identification for the user.
+//
+// presentation_definition: DIF Presentation Exchange presentation definition
+//
+// name: Name of the proof request - ex. Adult check
+//
+// cb: Callback that provides proof handle and error status of request.
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_proof_create_with_presentation_definition(vcx_command_handle_t command_handle,
+                                                          const char *source_id,
+                                                          const char *presentation_definition,
+                                                          const char *name,
+                                                          void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_proof_handle_t));
+
 // Takes a json string representing a proof object and recreates an object matching the json
 //
 // #Params
@@ -1441,6 +2415,20 @@ vcx_error_t vcx_proof_get_state(vcx_command_handle_t command_handle,
 // Success
 vcx_error_t vcx_proof_release(vcx_proof_handle_t proof_handle);
 
+// Makes the proof request expire the given number of seconds after it is sent. The request then carries
+// `~timing` decorator with `expires_time`, the prover refuses to answer the expired request and the proof
+// received after the deadline is rejected by a problem report. Disclosed proof not sent in time is moved into
+// VcxStateExpired by its `update_state`. Has to be called before the request is sent.
+//
+// #Params
+// proof_handle: Proof handle that was provided during creation. Used to access proof object
+//
+// expires_in_secs: number of seconds the request is valid after it is sent
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_proof_set_expiration(vcx_proof_handle_t proof_handle, vcx_u32_t expires_in_secs);
+
 // Sends a proof request to pairwise connection
 //
 // #Params
@@ -1459,6 +2447,99 @@ vcx_error_t vcx_proof_send_request(vcx_command_handle_t command_handle,
                                 vcx_connection_handle_t connection_handle,
                                 void (*cb)(vcx_command_handle_t, vcx_error_t));
 
+// Sends the pending proof request again, for example when the prover did not receive it.
+// The request is sent unchanged, so the prover answers it on the same thread.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// proof_handle: Proof handle that was provided during creation. Used to access proof object
+//
+// connection_handle: Connection handle the request was sent over
+//
+// cb: provides any error status of the proof_request
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_proof_resend_request(vcx_command_handle_t command_handle,
+                                     vcx_proof_handle_t proof_handle,
+                                     vcx_connection_handle_t connection_handle,
+                                     void (*cb)(vcx_command_handle_t, vcx_error_t));
+
+// Sends a revised proof request superseding the pending one. The revised request continues the thread
+// of the pending request, so the proof object and its handle stay the same. Presentations answering
+// the former request are refused by problem report and the proof keeps waiting for the presentation
+// answering the revised request.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// proof_handle: Proof handle that was provided during creation. Used to access proof object
+//
+// request_update: JSON with the parts of the request to replace (name, requested_attributes,
+//     requested_predicates, revocation_interval), the parts not set are kept. Presentation Exchange
+//     request can be revised only by its name, the revised request asks for a new challenge.
+//
+// cb: provides any error status of the proof_request
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_proof_update_request(vcx_command_handle_t command_handle,
+                                     vcx_proof_handle_t proof_handle,
+                                     const char *request_update,
+                                     void (*cb)(vcx_command_handle_t, vcx_error_t));
+
+// Verifies presentation against the proof request it answers without creating proof object or connection
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// request: aries presentation request message the presentation answers
+//
+// presentation: aries presentation message
+//
+// cb: Callback that provides presentation state (1 - valid, 2 - invalid) and error status of the verification
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_proof_verify_presentation_msg(vcx_command_handle_t command_handle,
+                                           const char *request,
+                                           const char *presentation,
+                                           void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_proof_state_t));
+
+// Verifies many presentations against the proof requests they answer, at most `verify_batch_parallelism`
+// (library config option, 4 by default) at once
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// items: JSON array of the request and presentation pairs
+//     [{"request": <aries presentation request message>, "presentation": <aries presentation message>}]
+//
+// cb: Callback that provides JSON array of results in the order of the items, either {"status": <presentation state>}
+//     or {"error_code": <code>, "error": <message>}, and error status of the batch
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_proof_verify_batch(vcx_command_handle_t command_handle,
+                                   const char *items,
+                                   void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Get out-of-band invitation with the proof request attached, answered by the prover without a connection
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// proof_handle: Proof handle that was provided during creation. Used to access proof object
+//
+// cb: provides out-of-band invitation and error status of the request
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_proof_get_oob_request(vcx_command_handle_t command_handle,
+                                   vcx_proof_handle_t proof_handle,
+                                   void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
 // Takes the proof object and returns a json string of all its attributes
 //
 // #Params
@@ -1474,6 +2555,11 @@ vcx_error_t vcx_proof_serialize(vcx_command_handle_t command_handle,
                              vcx_proof_handle_t proof_handle,
                              void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
 
+// Synchronous variants of the proof functions, see `vcx_connection_create_sync`.
+vcx_error_t vcx_proof_update_state_sync(vcx_proof_handle_t proof_handle, vcx_state_t *state_p);
+vcx_error_t vcx_proof_get_state_sync(vcx_proof_handle_t proof_handle, vcx_state_t *state_p);
+vcx_error_t vcx_proof_serialize_sync(vcx_proof_handle_t proof_handle, const char **serialized_data_p);
+
 // Takes the Proof object and returns a proof
 //
 // #Params
@@ -1489,6 +2575,58 @@ vcx_error_t vcx_get_proof_msg(vcx_command_handle_t command_handle,
                               vcx_proof_handle_t proof_handle,
                               void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
 
+// Get details of verified presentation: revealed values of every requested attribute together with
+// schema, credential definition, revocation registry and timestamp of the credential they come from.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// proof_handle: Proof handle that was provided during creation. Used to identify proof object
+//
+// cb: Callback that provides verification details json
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_proof_get_verification_details(vcx_command_handle_t command_handle,
+                                               vcx_proof_handle_t proof_handle,
+                                               void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Check revocation data of received presentation: timestamps of revocation registry states the prover used
+// are compared with non_revoked intervals of the request and with registry entries on the ledger.
+// Proof object state is not changed.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// proof_handle: Proof handle that was provided during creation. Used to identify proof object
+//
+// cb: Callback that provides list of revocation checks, one for every referent with non_revoked interval
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_proof_verify_revocation(vcx_command_handle_t command_handle,
+                                        vcx_proof_handle_t proof_handle,
+                                        void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Repeat verification of received presentation against ledger state at the given time.
+// Proof object state is not changed.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// proof_handle: Proof handle that was provided during creation. Used to identify proof object
+//
+// as_of: UTC timestamp the presentation should be verified at
+//
+// cb: Callback that provides presentation status the proof would have had at the given time
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_proof_verify_as_of(vcx_command_handle_t command_handle,
+                                   vcx_proof_handle_t proof_handle,
+                                   vcx_u64_t as_of,
+                                   void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_state_t));
+
 
 // Checks for any state change and updates the proof state attribute
 //
@@ -1544,6 +2682,34 @@ vcx_error_t vcx_schema_create(vcx_command_handle_t command_handle,
                            vcx_payment_handle_t payment_handle,
                            void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_schema_handle_t));
 
+// Runs vcx_schema_create as a dry run: the schema transaction is built, signed and priced,
+// but it's not submitted to the ledger and no Schema object is created.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// source_id: Enterprise's personal identification for the user.
+//
+// schema_name: Name of schema
+//
+// version: version of schema
+//
+// schema_data: list of attributes that will make up the schema
+//
+// payment_handle: future use (currently uses any address in the wallet)
+//
+// cb: Callback that provides the prepared transaction json and error status of request.
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_schema_dry_run(vcx_command_handle_t command_handle,
+                            const char *source_id,
+                            const char *schema_name,
+                            const char *version,
+                            const char *schema_data,
+                            vcx_payment_handle_t payment_handle,
+                            void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
 /// Create a new Schema object that will be published by Endorser later.
 ///
 /// #Params
@@ -1573,6 +2739,25 @@ vcx_error_t vcx_schema_prepare_for_endorser(vcx_command_handle_t command_handle,
                                            const char *endorser,
                                            void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_schema_handle_t, const char*));
 
+/// Prepares transaction of the Schema object which was created by `vcx_schema_prepare_for_endorser`
+/// but is not published yet, e.g. when the transaction has to be passed to another Endorser.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// schema_handle: Schema handle that was provided during creation. Used to access schema object
+///
+/// endorser: DID of the Endorser that will submit the transaction.
+///
+/// cb: Callback that provides Schema transaction that should be passed to Endorser for publishing.
+///
+/// #Returns
+/// Error code as a u32
+vcx_error_t vcx_schema_prepare_existing_for_endorser(vcx_command_handle_t command_handle,
+                                                    vcx_schema_handle_t schema_handle,
+                                                    const char *endorser,
+                                                    void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
 // Takes a json string representing a schema object and recreates an object matching the json
 //
 // #Params
@@ -1601,10 +2786,33 @@ vcx_error_t vcx_schema_deserialize(vcx_command_handle_t command_handle,
 //
 // #Returns
 // Error code as a u32
-vcx_error_t vcx_schema_get_attributes(vcx_command_handle_t command_handle,
-                                   const char *source_id,
-                                   const char *schema_id,
-                                   void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_schema_handle_t, const char*));
+vcx_error_t vcx_schema_get_attributes(vcx_command_handle_t command_handle,
+                                   const char *source_id,
+                                   const char *schema_id,
+                                   void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_schema_handle_t, const char*));
+
+// Retrieves name, version and attribute names of a schema on the ledger without creating a schema object.
+//
+// #Params
+// schema_id: id of the schema on the ledger
+//
+// cb: Callback contains the error status (if the schema cannot be found) and the schema as json string.
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_schema_get_attributes_from_ledger(vcx_command_handle_t command_handle,
+                                                  const char *schema_id,
+                                                  void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Lists schemas created or prepared for an endorser by this issuer, as recorded in the wallet.
+//
+// #Params
+// cb: Callback contains the error status and json array of the schemas.
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_schema_list_created(vcx_command_handle_t command_handle,
+                                    void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
 
 // Retrieve the txn associated with paying for the schema
 //
@@ -1781,6 +2989,25 @@ vcx_error_t vcx_wallet_close_search(vcx_command_handle_t command_handle,
                                  vcx_wallet_search_handle_t search_handle,
                                  void (*cb)(vcx_command_handle_t, vcx_error_t));
 
+// Lists anoncreds credentials held in the wallet
+//
+// #Params
+// filter_wql: (optional) WQL query on credential attributes and identifiers, all credentials are listed when NULL
+//
+// offset: number of matching credentials to skip
+//
+// limit: maximal number of credentials to return
+//
+// cb: Callback that provides {"total": <number of matching credentials>, "credentials": [<credential info>]} and error status
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_wallet_list_credentials(vcx_command_handle_t command_handle,
+                                        const char *filter_wql,
+                                        count_t offset,
+                                        count_t limit,
+                                        void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
 // Add a payment address to the wallet
 //
 // #params
@@ -1932,6 +3159,24 @@ vcx_error_t vcx_wallet_get_token_info(vcx_command_handle_t command_handle,
                                    vcx_payment_handle_t payment_handle,
                                    void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
 
+// Loads wallet storage plugin and calls its init function, which registers the storage type in libindy.
+// Registered type can then be used as "wallet_type" with "storage_config" and "storage_credentials" in configuration.
+// Alternatively "storage_library" and "storage_init_function" can be set in configuration to load the plugin
+// automatically whenever a wallet is created or opened.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+// path: path to the plugin library (e.g. "libindystrgpostgres.so")
+// init_fn: name of the plugin init function (e.g. "postgresstorage_init")
+// cb: Callback that provides the success/failure of the api call.
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_wallet_register_storage_library(vcx_command_handle_t command_handle,
+                                                const char *path,
+                                                const char *init_fn,
+                                                void (*cb)(vcx_command_handle_t, vcx_error_t));
+
 // Creates a new secure wallet and then imports its content
 // according to fields provided in import_config
 // Cannot be used if wallet is already opened (Especially if vcx_init has already been used).
@@ -1950,6 +3195,24 @@ vcx_error_t vcx_wallet_import(vcx_command_handle_t command_handle,
                            const char *config,
                            void (*cb)(vcx_command_handle_t, vcx_error_t));
 
+// Set provider of the wallet key, so the key does not have to be passed in `wallet_key` config option
+// and can be kept in a platform keystore (Secure Enclave, Android Keystore, ...) instead.
+// The provider is asked for the key every time the wallet is created, opened, imported or deleted,
+// unless wallet_key config option is set, the explicit key takes precedence.
+//
+// context: pointer to some context that will be available in the callbacks.
+// provide_key: called with the wallet name, returns the wallet key or NULL when the key is not available.
+//     The key has to stay valid until release_key is called.
+// release_key: (optional) called with the key returned by provide_key once the library has copied it.
+// #Returns
+// Error code as u32
+vcx_error_t vcx_set_wallet_key_provider(const void* context,
+                                        const char* (*provide_key)(const void* context, const char* wallet_name),
+                                        void (*release_key)(const void* context, const char* key));
+
+// Remove provider set by vcx_set_wallet_key_provider, the wallet key is taken from wallet_key config option again.
+vcx_error_t vcx_unset_wallet_key_provider();
+
 // Opens a storage search handle
 //
 // #Params
@@ -1958,14 +3221,15 @@ vcx_error_t vcx_wallet_import(vcx_command_handle_t command_handle,
 //
 // type_: type of record. (e.g. 'data', 'string', 'foobar', 'image')
 //
-// query_json: MongoDB style query to wallet record tags:
+// query_json: WQL query to wallet record tags:
 // {
 // "tagName": "tagValue",
-// $or: {
-// "tagName2": { $regex: 'pattern' },
-// "tagName3": { $gte: 123 },
-// },
+// "$or": [
+// {"tagName2": {"$like": "pattern%"}},
+// {"~tagName3": {"$gte": "123"}},
+// ],
 // }
+// supported operators: $and, $or, $not, $eq, $neq, $in, $like and $gt, $gte, $lt, $lte (unencrypted `~` tags only)
 // options_json:
 // {
 // retrieveRecords: (optional, true by default) If false only "counts" will be calculated,
@@ -1973,7 +3237,9 @@ vcx_error_t vcx_wallet_import(vcx_command_handle_t command_handle,
 // retrieveType: (optional, false by default) Retrieve record type,
 // retrieveValue: (optional, true by default) Retrieve record value,
 // retrieveTags: (optional, true by default) Retrieve record tags,
+// tagNames: (optional) Retrieve only the listed record tags, implies retrieveTags,
 // }
+// use vcx_wallet_search_next_records with page size as count to go through the results without loading them all
 // cb: Callback that any errors or a receipt of transfer
 //
 // #Returns
@@ -2120,6 +3386,17 @@ vcx_error_t vcx_get_logger(const void*  vcx_get_logger,
                                           vcx_u32_t line),
                            void (**flushFn)(const void*  context) );
 
+/// Set sink receiving structured log records (JSON with event, key-value fields and correlation id
+/// derived from thread id, connection handle and message type). Previously set sink is replaced.
+/// Sink receives records up to debug level regardless of the logger level, trace records only when the logger enables them.
+vcx_error_t vcx_set_structured_log_sink(const void* context,
+                                        void (*sinkFn)(const void* context,
+                                                       vcx_u32_t level,
+                                                       const char* record));
+
+/// Remove sink set by vcx_set_structured_log_sink.
+vcx_error_t vcx_unset_structured_log_sink();
+
 /// Get details for last occurred error.
 ///
 /// This function should be called in two places to handle both cases of error occurrence:
@@ -2140,6 +3417,25 @@ vcx_error_t vcx_get_logger(const void*  vcx_get_logger,
 ///
 vcx_error_t vcx_get_current_error(const char ** error_json_p);
 
+/// Get structured context of the last occurred error, it tells whether the failed operation may pass
+/// when repeated (e.g. agency unreachable) or the counterparty rejected the exchange.
+/// Problem report received while updating the state is recorded as well, although the update itself succeeds.
+///
+/// #Params
+/// * `details_json_p` - Reference that will contain error details (if any error has occurred before)
+///  in the format:
+/// {
+///     "error": str - name of the error kind
+///     "code": int - error code
+///     "message": str - human-readable error description
+///     "protocol": Optional<str> - Aries protocol of the object the error occurred on, e.g. "present-proof"
+///     "thread_id": Optional<str> - thread of the exchange
+///     "problem_code": Optional<str> - code of the problem report received from the counterparty
+///     "retryable": bool - the operation may pass if repeated later
+/// }
+///
+void vcx_get_last_error_details(const char ** details_json_p);
+
 /// Retrieve author agreement set on the Ledger
 ///
 /// #params
@@ -2153,6 +3449,109 @@ vcx_error_t vcx_get_current_error(const char ** error_json_p);
 vcx_error_t vcx_get_ledger_author_agreement(vcx_u32_t command_handle,
                                             void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
 
+/// Accept author agreement currently set on the Ledger, it is appended to all following write requests.
+///
+/// acc_mech_type: one of `aml` labels returned by vcx_get_ledger_author_agreement
+/// time_of_acceptance: UTC timestamp of the acceptance, 0 for the current time
+vcx_error_t vcx_accept_ledger_author_agreement(vcx_command_handle_t command_handle,
+                                               const char *acc_mech_type,
+                                               vcx_u64_t time_of_acceptance,
+                                               void (*cb)(vcx_command_handle_t, vcx_error_t));
+
+/// Creates handles for all objects saved in the wallet by the object persistence (config option `persist_objects`).
+/// Objects restored by a previous call keep their handles. Objects which cannot be restored are skipped.
+/// Saved state of an object is kept when its handle is released, it is deleted by `vcx_delete_object`.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// cb: Callback that provides handles of restored objects grouped by type
+///     e.g. {"connections":[1],"credentials":[],"issuer_credentials":[2],"proofs":[],"disclosed_proofs":[],"credential_templates":[]}
+///
+/// #Returns
+/// Error code as a u32
+vcx_error_t vcx_load_all_objects(vcx_u32_t command_handle,
+                                 void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+/// Deletes saved state of the object (see `vcx_load_all_objects`) and releases its handle.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+/// object_type: one of `connection`, `credential`, `credential_template`, `disclosed_proof`, `issuer_credential`, `proof`
+/// handle: handle of the object
+///
+/// cb: Callback that provides error status of the deletion
+///
+/// #Returns
+/// Error code as a u32
+vcx_error_t vcx_delete_object(vcx_command_handle_t command_handle,
+                              const char* object_type,
+                              vcx_u32_t handle,
+                              void (*cb)(vcx_command_handle_t, vcx_error_t));
+
+/// Get configuration of the provisioned agent kept by agency (name, logoUrl, notificationWebhookUrl, publicDid).
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// cb: Callback that provides JSON object of configuration option names and values, options which were never set are left out
+vcx_error_t vcx_get_agent_config(vcx_command_handle_t command_handle,
+                                 void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+/// Exports the agent (wallet, settings and all objects) into one encrypted file to be restored by vcx_agent_import_state.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+/// path: path of the file to export the agent to
+/// backup_key: key used to encrypt the file
+///
+/// cb: Callback that provides success or failure of command
+vcx_error_t vcx_agent_export_state(vcx_command_handle_t command_handle,
+                                   const char *path,
+                                   const char *backup_key,
+                                   void (*cb)(vcx_command_handle_t, vcx_error_t));
+
+/// Restores the agent exported by vcx_agent_export_state, no wallet can be opened when called.
+/// The agent webhook is registered with the agency again when webhook_url is set.
+/// On failure the restored objects are released and the wallet is closed, the imported wallet is kept.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+/// config: {"wallet_name":"","wallet_key":"","exported_wallet_path":"","backup_key":"","key_derivation":""}
+///
+/// cb: Callback that provides handles of restored objects grouped by type
+vcx_error_t vcx_agent_import_state(vcx_command_handle_t command_handle,
+                                   const char *config,
+                                   void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+/// Get counts and latencies of agency round trips, ledger requests, proof generation and message packing as JSON.
+/// Collected metrics are cleared when reset is set.
+vcx_error_t vcx_get_metrics(vcx_command_handle_t command_handle,
+                            vcx_bool_t reset,
+                            void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+/// Get report of protocols and modes (strict aries, protocol_type, payments, revocation) active with the current settings as JSON.
+vcx_error_t vcx_get_capabilities(vcx_command_handle_t command_handle,
+                                 void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+/// Opens ledger network next to the default pool. Schemas, credential definitions and revocation registries
+/// with identifiers qualified by the network name (e.g. "did:indy:<name>:...") are resolved on this network.
+///
+/// pool_config: (optional) runtime configuration of the pool
+vcx_error_t vcx_pool_add_network(vcx_command_handle_t command_handle,
+                                 const char *name,
+                                 const char *genesis_path,
+                                 const char *pool_config,
+                                 void (*cb)(vcx_command_handle_t, vcx_error_t));
+
+/// Closes ledger network opened by vcx_pool_add_network.
+vcx_error_t vcx_pool_close_network(const char *name);
+
 /// Set some accepted agreement as active.
 ///
 /// As result of succesfull call of this funciton appropriate metadata will be appended to each write request by `indy_append_txn_author_agreement_meta_to_request` libindy call.
@@ -2169,6 +3568,20 @@ vcx_error_t vcx_get_ledger_author_agreement(vcx_u32_t command_handle,
 /// Error code as a u32
 vcx_error_t vcx_set_active_txn_author_agreement_meta(const char *text, const char *version, const char *hash, const char *acc_mech_type, vcx_u64_t type_);
 
+/// Sets institution default localization attached as `~l10n` decorator to credential offers and basic messages.
+///
+/// #Params
+/// localization_json: locale of the institution and optional labels of credential attributes in other locales
+///     {
+///         "locale": "en",
+///         "catalogs": Optional<["https://example.com/catalog.json"]>,
+///         "labels": Optional<{"es": {"name": "Nombre", "age": "Edad"}}>
+///     }
+///
+/// #Returns
+/// Error code as u32
+vcx_error_t vcx_update_institution_localization(const char *localization_json);
+
 /// Endorse transaction to the ledger preserving an original author
 ///
 /// #params
@@ -2184,6 +3597,312 @@ vcx_error_t vcx_endorse_transaction(vcx_u32_t command_handle,
                                     const char* transaction,
                                     void (*cb)(vcx_command_handle_t, vcx_error_t));
 
+/// Export protocol object as a token protected by `handle_token_key` configuration value.
+/// The token can be passed to another process sharing the key and restored there by `vcx_handle_from_token`.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+/// object_type: one of `connection`, `credential`, `disclosed_proof`, `issuer_credential`, `proof`
+/// handle: handle of the object
+///
+/// cb: Callback that provides the token
+vcx_error_t vcx_handle_to_token(vcx_command_handle_t command_handle,
+                                const char* object_type,
+                                vcx_u32_t handle,
+                                void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+/// Restore protocol object from a token created by `vcx_handle_to_token`.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+/// object_type: one of `connection`, `credential`, `disclosed_proof`, `issuer_credential`, `proof`
+/// token: token holding the object
+///
+/// cb: Callback that provides handle of the restored object
+vcx_error_t vcx_handle_from_token(vcx_command_handle_t command_handle,
+                                  const char* object_type,
+                                  const char* token,
+                                  void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_u32_t));
+
+/// Find handle of an existing protocol object by its source id (the most recent one when source ids repeat).
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+/// object_type: one of `connection`, `credential`, `disclosed_proof`, `issuer_credential`, `proof`
+/// source_id: source id the object was created with
+///
+/// cb: Callback that provides handle of the object
+vcx_error_t vcx_get_handle_by_source_id(vcx_command_handle_t command_handle,
+                                        const char* object_type,
+                                        const char* source_id,
+                                        void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_u32_t));
+
+/// Find handle of an existing protocol object by id of its protocol thread.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+/// object_type: one of `credential`, `disclosed_proof`, `issuer_credential`, `proof`
+/// thread_id: id of the protocol thread
+///
+/// cb: Callback that provides handle of the object
+vcx_error_t vcx_get_handle_by_thread_id(vcx_command_handle_t command_handle,
+                                        const char* object_type,
+                                        const char* thread_id,
+                                        void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_u32_t));
+
+/// Decrypt payload of agency push notification (FCM, APNS) by keys of the opened wallet.
+///
+/// #params
+/// command_handle: command handle to map callback to user context.
+/// payload_raw: a pointer to first byte of the encrypted payload
+/// payload_len: length of the payload
+///
+/// cb: Callback that provides JSON with the A2A message and its metadata: message_type, thread_id,
+///     sender_verkey, recipient_verkey, connection_handle, object_type and handle of the object taking part in the thread
+vcx_error_t vcx_decrypt_push_payload(vcx_command_handle_t command_handle,
+                                     const unsigned char *payload_raw,
+                                     vcx_u32_t payload_len,
+                                     void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+/// Attach application defined metadata to a protocol object, it is kept in the serialized object.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+/// object_type: one of `connection`, `credential`, `disclosed_proof`, `issuer_credential`, `proof`
+/// handle: handle of the object
+/// key: metadata key
+/// value: metadata value, NULL removes the key
+///
+/// cb: Callback that provides success or failure of command
+vcx_error_t vcx_set_metadata(vcx_command_handle_t command_handle,
+                             const char* object_type,
+                             vcx_u32_t handle,
+                             const char* key,
+                             const char* value,
+                             void (*cb)(vcx_command_handle_t, vcx_error_t));
+
+/// Get metadata attached to a protocol object.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+/// object_type: one of `connection`, `credential`, `disclosed_proof`, `issuer_credential`, `proof`
+/// handle: handle of the object
+///
+/// cb: Callback that provides metadata as JSON object
+vcx_error_t vcx_get_metadata(vcx_command_handle_t command_handle,
+                             const char* object_type,
+                             vcx_u32_t handle,
+                             void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+/// Find handles of existing protocol objects having metadata `key` set to `value`.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+/// object_type: one of `connection`, `credential`, `disclosed_proof`, `issuer_credential`, `proof`
+/// key: metadata key
+/// value: metadata value
+///
+/// cb: Callback that provides JSON array of handles
+vcx_error_t vcx_find_handles_by_metadata(vcx_command_handle_t command_handle,
+                                         const char* object_type,
+                                         const char* key,
+                                         const char* value,
+                                         void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+/// Convert object serialized by an older library version into the current serialization format.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+/// serialized_object: serialized connection, credential, issuer credential, proof or disclosed proof
+/// target_version: "4.0" for connections, "2.0" for other objects
+///
+/// cb: Callback that provides the converted object
+vcx_error_t vcx_convert_serialized_object(vcx_command_handle_t command_handle,
+                                          const char* serialized_object,
+                                          const char* target_version,
+                                          void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+/// Remove objects fetched more than `max_age` seconds ago from the ledger cache, 0 removes all objects.
+vcx_error_t vcx_ledger_cache_purge(vcx_u64_t max_age);
+
+/// Fetch schemas, credential definitions and revocation registry definitions into the ledger cache.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+/// ids_json: {"schema_ids": [...], "cred_def_ids": [...], "rev_reg_def_ids": [...]}
+///
+/// cb: Callback that provides success or failure of command
+vcx_error_t vcx_ledger_cache_warm(vcx_command_handle_t command_handle,
+                                  const char* ids_json,
+                                  void (*cb)(vcx_command_handle_t, vcx_error_t));
+
+// Get messages sent and received within the protocol exchange of the object.
+// Messages are recorded only when `protocol_history` option of the library config is "true".
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+//
+// cb: Callback that provides JSON array of exchanged messages
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_issuer_credential_get_protocol_history(vcx_command_handle_t command_handle,
+                                                       vcx_issuer_credential_handle_t credential_handle,
+                                                       void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Get messages sent and received within the protocol exchange of the object.
+// Messages are recorded only when `protocol_history` option of the library config is "true".
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+//
+// cb: Callback that provides JSON array of exchanged messages
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_credential_get_protocol_history(vcx_command_handle_t command_handle,
+                                                vcx_credential_handle_t credential_handle,
+                                                void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Get messages sent and received within the protocol exchange of the object.
+// Messages are recorded only when `protocol_history` option of the library config is "true".
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// proof_handle: Proof handle that was provided during creation. Used to identify proof object
+//
+// cb: Callback that provides JSON array of exchanged messages
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_proof_get_protocol_history(vcx_command_handle_t command_handle,
+                                           vcx_proof_handle_t proof_handle,
+                                           void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Get messages sent and received within the protocol exchange of the object.
+// Messages are recorded only when `protocol_history` option of the library config is "true".
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// proof_handle: Proof handle that was provided during creation. Used to identify disclosed proof object
+//
+// cb: Callback that provides JSON array of exchanged messages
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_disclosed_proof_get_protocol_history(vcx_command_handle_t command_handle,
+                                                     vcx_disclosed_proof_handle_t proof_handle,
+                                                     void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Get the time the credential offer of the object expires at.
+// The expiration is set by the `~timing` decorator of the message.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+//
+// cb: Callback that provides the expiration time in RFC 3339 format, null pointer if it does not expire
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_issuer_credential_get_expiry(vcx_command_handle_t command_handle,
+                                             vcx_issuer_credential_handle_t credential_handle,
+                                             void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Get the time the received credential offer of the object expires at.
+// The expiration is set by the `~timing` decorator of the message.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+//
+// cb: Callback that provides the expiration time in RFC 3339 format, null pointer if it does not expire
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_credential_get_expiry(vcx_command_handle_t command_handle,
+                                      vcx_credential_handle_t credential_handle,
+                                      void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Get the time the proof request of the object expires at.
+// The expiration is set by the `~timing` decorator of the message.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// proof_handle: Proof handle that was provided during creation. Used to identify proof object
+//
+// cb: Callback that provides the expiration time in RFC 3339 format, null pointer if it does not expire
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_proof_get_expiry(vcx_command_handle_t command_handle,
+                                 vcx_proof_handle_t proof_handle,
+                                 void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Get the time the received proof request of the object expires at.
+// The expiration is set by the `~timing` decorator of the message.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// proof_handle: Proof handle that was provided during creation. Used to identify disclosed proof object
+//
+// cb: Callback that provides the expiration time in RFC 3339 format, null pointer if it does not expire
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_disclosed_proof_get_expiry(vcx_command_handle_t command_handle,
+                                           vcx_disclosed_proof_handle_t proof_handle,
+                                           void (*cb)(vcx_command_handle_t, vcx_error_t, const char*));
+
+// Check whether the issued credential waits for the acknowledgement of the holder.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+//
+// cb: Callback that provides true while the acknowledgement is awaited
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_issuer_credential_is_awaiting_ack(vcx_command_handle_t command_handle,
+                                                  vcx_issuer_credential_handle_t credential_handle,
+                                                  void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_bool_t));
+
+// Check whether the sent presentation waits for the acknowledgement of the verifier.
+//
+// #Params
+// command_handle: command handle to map callback to user context.
+//
+// proof_handle: Proof handle that was provided during creation. Used to identify disclosed proof object
+//
+// cb: Callback that provides true while the acknowledgement is awaited
+//
+// #Returns
+// Error code as a u32
+vcx_error_t vcx_disclosed_proof_is_awaiting_ack(vcx_command_handle_t command_handle,
+                                                vcx_disclosed_proof_handle_t proof_handle,
+                                                void (*cb)(vcx_command_handle_t, vcx_error_t, vcx_bool_t));
+
 #ifdef __cplusplus
 } // extern "C"
 #endif
@@ -0,0 +1,615 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde_json;
+
+use {connection, credential, disclosed_proof, issuer_credential, proof};
+use error::prelude::*;
+use messages::agent_utils::{self, ProvisionToken};
+use messages::validation;
+use settings;
+use utils::agent_context::{self, AgentContext};
+use utils::credential_store;
+use utils::libindy::wallet::{self, RestoreWalletConfigs};
+use utils::persistence::LoadedObjects;
+use utils::secret::SecretString;
+use utils::version_constants;
+
+static NEXT_AGENT_ID: AtomicUsize = AtomicUsize::new(1);
+
+///
+/// Identity with its own settings, wallet and agency connection, so that several of them can be
+/// active in one process at once. Library calls made within `Agent::run` use the agent's settings
+/// and wallet, and objects (connections, credentials, proofs, ...) created there can be accessed
+/// only from the same agent. State change subscriptions, credential metadata store and ledger cache
+/// are kept per agent too, and work spawned by the library keeps running as the agent.
+///
+/// Ledger pool is shared by all agents of the process.
+///
+pub struct Agent {
+    context: AgentContext,
+}
+
+impl Agent {
+    /// Opens the agent's wallet, `config` is the provisioned configuration (as passed to `vcx_init_with_config`).
+    pub fn new(config: &str) -> VcxResult<Agent> {
+        let agent = Agent::_with_empty_context();
+
+        agent.run(|| {
+            settings::process_config_string(config, true)?;
+            _open_wallet()
+        })?;
+
+        debug!("Agent::new >>> agent {} initialized", agent.id());
+        Ok(agent)
+    }
+
+    // agent with no settings and no wallet opened
+    fn _with_empty_context() -> Agent {
+        let agent_id = NEXT_AGENT_ID.fetch_add(1, Ordering::SeqCst) as u32;
+        Agent { context: AgentContext::new(agent_id) }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.context.agent_id
+    }
+
+    /// Runs `closure` as this agent on the current thread.
+    pub fn run<F, R>(&self, closure: F) -> R where F: FnOnce() -> R {
+        agent_context::with_context(&self.context, closure)
+    }
+
+    /// Closes the agent's wallet and drops its credential metadata store. Objects created by the agent should be released before.
+    pub fn close(self) -> VcxResult<()> {
+        self.run(|| {
+            credential_store::set_credential_metadata_store(None);
+            wallet::close_wallet()
+        })
+    }
+}
+
+// wallet described by the current settings
+fn _wallet_settings() -> (String, Option<String>, Option<String>, Option<String>) {
+    let settings = settings::snapshot();
+    (settings.get_value(settings::CONFIG_WALLET_NAME).unwrap_or(settings::DEFAULT_WALLET_NAME.to_string()),
+     settings.get_value(settings::CONFIG_WALLET_TYPE).ok(),
+     settings.get_value(settings::CONFIG_WALLET_STORAGE_CONFIG).ok(),
+     settings.get_value(settings::CONFIG_WALLET_STORAGE_CREDS).ok())
+}
+
+fn _open_wallet() -> VcxResult<()> {
+    let (wallet_name, wallet_type, storage_config, storage_creds) = _wallet_settings();
+    wallet::open_wallet(&wallet_name,
+                        wallet_type.as_ref().map(String::as_str),
+                        storage_config.as_ref().map(String::as_str),
+                        storage_creds.as_ref().map(String::as_str))?;
+    Ok(())
+}
+
+fn _delete_wallet() -> VcxResult<()> {
+    let (wallet_name, wallet_type, storage_config, storage_creds) = _wallet_settings();
+    wallet::delete_wallet(&wallet_name,
+                          wallet_type.as_ref().map(String::as_str),
+                          storage_config.as_ref().map(String::as_str),
+                          storage_creds.as_ref().map(String::as_str))
+}
+
+///
+/// Provisions an agent in agency which onboards only sponsored identities, `token` is the provision
+/// token handed out by the sponsor: `{"sponseeId", "sponsorId", "nonce", "timestamp", "sig", "sponsorVerKey"}`.
+/// Returns the provisioned configuration, the same as for `vcx_provision_agent`.
+///
+pub fn provision_with_token(config: &str, token: &str) -> VcxResult<String> {
+    let token = ProvisionToken::from_str(token)?;
+    agent_utils::connect_register_provision_with_token(config, token)
+}
+
+// options of `provision_for` given next to the provisioning config of the subject
+#[derive(Deserialize, Debug)]
+struct HandoffOptions {
+    wallet_name: Option<String>,
+    provision_token: Option<serde_json::Value>,
+    exported_wallet_path: Option<String>,
+    backup_key: Option<SecretString>,
+}
+
+/// Result of `provision_for` to be handed over to the app of the provisioned user.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HandoffBundle {
+    /// Provisioned configuration, the same as returned by `vcx_provision_agent`.
+    pub config: serde_json::Value,
+    /// File with the exported wallet and settings of the agent, restored by `import_state` on the device.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exported_wallet_path: Option<String>,
+}
+
+///
+/// Provisions an agent for an end user on behalf of the caller acting as a sponsor, e.g. backend of
+/// a white-label wallet onboarding its users. `subject_config` is the provisioning config of the user,
+/// the same as for `vcx_provision_agent`, `wallet_name` is required and the wallet must not exist yet.
+/// Optional fields:
+///     "provision_token": token for agency onboarding only sponsored identities, see `provision_with_token`
+///     "exported_wallet_path", "backup_key": the wallet of the user is exported into the file encrypted
+///         by the key and removed, the app restores it by `import_state`. Otherwise the wallet stays
+///         in its storage for the app to open it with the returned config.
+/// The wallet is deleted when provisioning or export fails, the agent possibly created in the agency
+/// meanwhile stays there. Settings and wallet of the caller are not affected. Returns `HandoffBundle` as JSON.
+///
+pub fn provision_for(subject_config: &str) -> VcxResult<String> {
+    trace!("provision_for >>>");
+
+    let options: HandoffOptions = serde_json::from_str(subject_config)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidConfiguration, format!("Cannot parse config: {}", err)))?;
+    let wallet_name = options.wallet_name
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidConfiguration, "wallet_name of the provisioned user has to be given"))?;
+    let token = match options.provision_token {
+        Some(token) => Some(ProvisionToken::from_str(&token.to_string())?),
+        None => None
+    };
+    let export = match (options.exported_wallet_path, options.backup_key) {
+        (Some(path), Some(backup_key)) => Some((path, backup_key)),
+        (None, None) => None,
+        _ => return Err(VcxError::from_msg(VcxErrorKind::InvalidConfiguration, "Both exported_wallet_path and backup_key are needed to hand off the wallet")),
+    };
+
+    // the subject is provisioned in the same environment as the sponsor, e.g. with mocked agency
+    let test_mode = settings::get_config_value(settings::CONFIG_ENABLE_TEST_MODE).ok();
+
+    let subject = Agent::_with_empty_context();
+    subject.run(|| {
+        if let Some(test_mode) = test_mode {
+            settings::set_config_value(settings::CONFIG_ENABLE_TEST_MODE, &test_mode);
+        }
+
+        let my_config = agent_utils::parse_config(subject_config)?;
+        agent_utils::set_config_values(&my_config);
+        let wallet_type = my_config.wallet_type.as_ref().map(String::as_str);
+        let storage_config = my_config.storage_config.as_ref().map(String::as_str);
+        let storage_credentials = my_config.storage_credentials.as_ref().map(String::as_str);
+
+        // the wallet is deleted on failure and handed off on success, so it must not be someone else's
+        if !settings::indy_mocks_enabled() && !wallet::create_wallet_if_missing(&wallet_name, wallet_type, storage_config, storage_credentials)? {
+            return Err(VcxError::from_msg(VcxErrorKind::DuplicationWallet, format!("Wallet \"{}\" of the provisioned user exists already", wallet_name)));
+        }
+
+        let result = _provision_and_hand_off(subject_config, token, export);
+        if result.is_err() && !settings::indy_mocks_enabled() {
+            if let Err(err) = wallet::delete_wallet(&wallet_name, wallet_type, storage_config, storage_credentials) {
+                warn!("Cannot delete wallet of failed provisioning: {}", err);
+            }
+        }
+        result
+    })
+}
+
+fn _provision_and_hand_off(subject_config: &str, token: Option<ProvisionToken>, export: Option<(String, SecretString)>) -> VcxResult<String> {
+    let config = match token {
+        Some(token) => agent_utils::connect_register_provision_with_token(subject_config, token)?,
+        None => agent_utils::connect_register_provision(subject_config)?,
+    };
+
+    let exported_wallet_path = match export {
+        Some((path, backup_key)) => {
+            settings::process_config_string(&config, true)?;
+            _open_wallet()
+                .and_then(|_| export_state(&path, backup_key.expose()))
+                .map_err(|err| err.extend("Cannot export wallet of the provisioned agent"))?;
+            _delete_wallet()?;
+            Some(path)
+        }
+        None => None
+    };
+
+    let bundle = HandoffBundle {
+        config: serde_json::from_str(&config)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize provisioned config: {}", err)))?,
+        exported_wallet_path,
+    };
+    debug!("provision_for <<< agent provisioned, wallet exported: {}", bundle.exported_wallet_path.is_some());
+    Ok(json!(bundle).to_string())
+}
+
+/// Options of the agent configuration kept by agency which are reported by `get_agent_config`.
+pub const AGENT_CONFIG_NAMES: &[&str] = &["name", "logoUrl", "notificationWebhookUrl", "publicDid"];
+
+///
+/// Registers `url` as the webhook the agency notifies about messages for the provisioned agent,
+/// the previous one is replaced. The url is kept in settings once the agency accepts it.
+///
+pub fn update_webhook_url(url: &str) -> VcxResult<()> {
+    trace!("update_webhook_url >>> url: {:?}", url);
+
+    validation::validate_url(url)?;
+    settings::get_config_value(settings::CONFIG_REMOTE_TO_SDK_DID)
+        .map_err(|err| err.map(VcxErrorKind::NoAgentInformation, "Cannot update webhook: agent is not provisioned"))?;
+
+    agent_utils::update_agent_webhook(url)?;
+    settings::set_config_value(settings::CONFIG_WEBHOOK_URL, url);
+    Ok(())
+}
+
+///
+/// Configuration of the provisioned agent kept by agency, e.g. `{"name": "Faber", "logoUrl": "https://..."}`.
+/// Options which were never set are left out, see `AGENT_CONFIG_NAMES`.
+///
+pub fn get_agent_config() -> VcxResult<HashMap<String, String>> {
+    trace!("get_agent_config >>>");
+    agent_utils::get_agent_configs(AGENT_CONFIG_NAMES)
+}
+
+static STATE_RECORD_TYPE: &str = "VcxAgentState";
+static STATE_RECORD_ID: &str = "state";
+
+// settings describing the wallet location and environment of the device, the importing side provides its own
+const DEVICE_SETTINGS: &[&str] = &[
+    settings::CONFIG_WALLET_NAME,
+    settings::CONFIG_WALLET_TYPE,
+    settings::CONFIG_WALLET_STORAGE_CONFIG,
+    settings::CONFIG_WALLET_STORAGE_LIBRARY,
+    settings::CONFIG_WALLET_STORAGE_INIT_FUNCTION,
+    settings::CONFIG_WALLET_HANDLE,
+    settings::CONFIG_WALLET_KEY_DERIVATION,
+    settings::CONFIG_EXPORTED_WALLET_PATH,
+    settings::CONFIG_GENESIS_PATH,
+    settings::CONFIG_ENABLE_TEST_MODE,
+];
+
+/// Settings and objects of the agent, kept in the exported wallet next to its keys and credentials.
+#[derive(Serialize, Deserialize, Debug)]
+struct AgentState {
+    libvcx_version: String,
+    settings: HashMap<String, String>,
+    connections: Vec<String>,
+    credentials: Vec<String>,
+    issuer_credentials: Vec<String>,
+    proofs: Vec<String>,
+    disclosed_proofs: Vec<String>,
+}
+
+fn _serialize_all<F>(handles: Vec<u32>, to_string: F) -> VcxResult<Vec<String>> where F: Fn(u32) -> VcxResult<String> {
+    handles.into_iter().map(to_string).collect()
+}
+
+fn _restore_all<F>(objects: &[String], from_string: F, restored: &mut Vec<u32>) -> VcxResult<()> where F: Fn(&str) -> VcxResult<u32> {
+    for object in objects {
+        restored.push(from_string(object)?);
+    }
+    Ok(())
+}
+
+fn _release_all(loaded: &LoadedObjects) {
+    loaded.connections.iter().for_each(|&handle| { connection::release(handle).ok(); });
+    loaded.credentials.iter().for_each(|&handle| { credential::release(handle).ok(); });
+    loaded.issuer_credentials.iter().for_each(|&handle| { issuer_credential::release(handle).ok(); });
+    loaded.proofs.iter().for_each(|&handle| { proof::release(handle).ok(); });
+    loaded.disclosed_proofs.iter().for_each(|&handle| { disclosed_proof::release(handle).ok(); });
+}
+
+///
+/// Exports the agent into one file encrypted by `backup_key`: the wallet together with the settings
+/// and all connections, credentials and proofs of the agent, to be restored by `import_state`
+/// on another device or by another libvcx version. Secret settings (keys, storage credentials) are not exported.
+///
+pub fn export_state(path: &str, backup_key: &str) -> VcxResult<()> {
+    trace!("export_state >>> path: {:?}, backup_key: ****", path);
+
+    let settings = settings::snapshot().iter()
+        .filter(|&(key, _)| !settings::is_secret_config_key(key) && !DEVICE_SETTINGS.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+
+    let state = AgentState {
+        libvcx_version: version_constants::VERSION.to_string(),
+        settings,
+        connections: _serialize_all(connection::get_handles(), connection::to_string)?,
+        credentials: _serialize_all(credential::get_handles(), credential::to_string)?,
+        issuer_credentials: _serialize_all(issuer_credential::get_handles(), issuer_credential::to_string)?,
+        proofs: _serialize_all(proof::get_handles(), proof::to_string)?,
+        disclosed_proofs: _serialize_all(disclosed_proof::get_handles(), disclosed_proof::to_string)?,
+    };
+    let state = serde_json::to_string(&state)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize agent state: {:?}", err)))?;
+
+    // the state travels inside the wallet export and is removed from the wallet right after
+    _delete_state_record()?;
+    wallet::add_record(STATE_RECORD_TYPE, STATE_RECORD_ID, &state, None)?;
+    let result = wallet::export(wallet::get_wallet_handle(), path, backup_key);
+    _delete_state_record()?;
+    result
+}
+
+fn _delete_state_record() -> VcxResult<()> {
+    match wallet::delete_record(STATE_RECORD_TYPE, STATE_RECORD_ID) {
+        Err(ref err) if err.kind() == VcxErrorKind::WalletRecordNotFound => Ok(()),
+        result => result
+    }
+}
+
+///
+/// Restores the agent exported by `export_state`: imports and opens the wallet, applies exported settings,
+/// recreates the objects and registers the webhook of the agent with the agency again.
+/// The agent itself stays provisioned in the agency, re-registration only points the agency notifications
+/// to `webhook_url` of the restored settings and is skipped when there is none.
+/// `config` is the same as for `vcx_wallet_import`, other settings given there take precedence over the exported ones.
+/// No wallet can be opened when called. Returns handles of the restored objects.
+/// On failure the restored objects are released, the settings are reverted and the wallet is closed,
+/// the imported wallet is kept.
+///
+pub fn import_state(config: &str) -> VcxResult<LoadedObjects> {
+    trace!("import_state >>>");
+
+    wallet::import(config)?;
+
+    let restore_config = RestoreWalletConfigs::from_str(config)?;
+    let (_, wallet_type, storage_config, storage_creds) = _wallet_settings();
+    wallet::open_wallet(&restore_config.wallet_name,
+                        wallet_type.as_ref().map(String::as_str),
+                        storage_config.as_ref().map(String::as_str),
+                        storage_creds.as_ref().map(String::as_str))?;
+
+    let mut loaded = LoadedObjects::default();
+    match _restore_state(config, &mut loaded) {
+        Ok(()) => {
+            debug!("import_state <<< {:?}", loaded);
+            Ok(loaded)
+        }
+        Err(err) => {
+            _release_all(&loaded);
+            wallet::close_wallet().ok();
+            Err(err)
+        }
+    }
+}
+
+fn _restore_state(config: &str, loaded: &mut LoadedObjects) -> VcxResult<()> {
+    let options = json!({"retrieveType": false, "retrieveValue": true, "retrieveTags": false}).to_string();
+    let record = wallet::get_record(STATE_RECORD_TYPE, STATE_RECORD_ID, &options)
+        .map_err(|err| err.extend("Imported wallet does not contain agent state, it was not exported by export_state"))?;
+    let record: serde_json::Value = serde_json::from_str(&record)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize agent state record: {:?}", err)))?;
+    let state: AgentState = record["value"].as_str()
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, "Agent state record has no value"))
+        .and_then(|state| serde_json::from_str(state)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize agent state: {:?}", err))))?;
+    debug!("import_state >>> restoring agent state exported by libvcx {}", state.libvcx_version);
+
+    // exported settings must not stay applied when the objects cannot be restored
+    let saved_settings = settings::save();
+    _apply_state(config, &state, loaded)
+        .map_err(|err| {
+            settings::restore(&saved_settings);
+            err
+        })
+}
+
+fn _apply_state(config: &str, state: &AgentState, loaded: &mut LoadedObjects) -> VcxResult<()> {
+    settings::process_config_string(&json!(state.settings).to_string(), false)?;
+    settings::process_config_string(config, false)?;
+
+    _restore_all(&state.connections, connection::from_string, &mut loaded.connections)?;
+    _restore_all(&state.credentials, credential::from_string, &mut loaded.credentials)?;
+    _restore_all(&state.issuer_credentials, issuer_credential::from_string, &mut loaded.issuer_credentials)?;
+    _restore_all(&state.proofs, proof::from_string, &mut loaded.proofs)?;
+    _restore_all(&state.disclosed_proofs, disclosed_proof::from_string, &mut loaded.disclosed_proofs)?;
+
+    if let Ok(webhook_url) = settings::get_config_value(settings::CONFIG_WEBHOOK_URL) {
+        agent_utils::update_agent_webhook(&webhook_url)
+            .map_err(|err| err.extend("Cannot register restored agent with the agency"))?;
+    }
+
+    // kept until everything is restored, so that a failed import can be repeated with the imported wallet
+    _delete_state_record()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::thread;
+
+    use connection;
+    use utils::devsetup::{SetupAgencyMock, SetupAriesMocks, SetupEmpty, TempFile};
+
+    use super::*;
+
+    fn _config(institution_name: &str) -> String {
+        json!({
+            "enable_test_mode": "true",
+            "wallet_name": format!("{}_wallet", institution_name),
+            "wallet_key": "key",
+            "institution_name": institution_name,
+            "institution_did": "2hoqvcwupRTUNkXn6ArYzs",
+            "institution_verkey": "vrWGArMA3toVoZrYGSAMjR2i9KjBS66bZWyWuYJJYPf",
+            "agency_did": "VsKV7grR1BUE29mG2Fm2kX",
+            "agency_verkey": "Hezce2UWMZ3wUhVkh2LfKSs8nDzWwzs2Win7EzNN3YaR",
+            "agency_endpoint": "http://localhost:8080",
+            "remote_to_sdk_did": "UJGjM6Cea2YVixjWwHN9wq",
+            "remote_to_sdk_verkey": "91qMFrZjXDoi2Vc8Mm14Ys112tEZdDegBZZoembFEATE",
+            "sdk_to_remote_did": "AB3JM851T4EQmhh8CdagSP",
+            "sdk_to_remote_verkey": "888MFrZjXDoi2Vc8Mm14Ys112tEZdDegBZZoembFEATE",
+            "protocol_type": "4.0",
+        }).to_string()
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_agents_have_separate_settings_and_objects() {
+        let _setup = SetupEmpty::init();
+
+        let faber = Agent::new(&_config("faber")).unwrap();
+        let alice = Agent::new(&_config("alice")).unwrap();
+        assert_ne!(faber.id(), alice.id());
+
+        let alice_thread = thread::spawn(move || {
+            let handle = alice.run(|| connection::create_connection("alice").unwrap());
+            alice.run(|| {
+                assert_eq!(settings::get_config_value(settings::CONFIG_INSTITUTION_NAME).unwrap(), "alice");
+                assert!(connection::is_valid_handle(handle));
+            });
+            (alice, handle)
+        });
+
+        let faber_handle = faber.run(|| connection::create_connection("faber").unwrap());
+        let (alice, alice_handle) = alice_thread.join().unwrap();
+
+        faber.run(|| {
+            assert_eq!(settings::get_config_value(settings::CONFIG_INSTITUTION_NAME).unwrap(), "faber");
+            assert!(connection::is_valid_handle(faber_handle));
+            assert!(!connection::is_valid_handle(alice_handle));
+            assert!(connection::get_source_id(alice_handle).is_err());
+        });
+
+        // objects of agents are not visible outside of them
+        assert!(!connection::is_valid_handle(faber_handle));
+        assert!(settings::get_config_value(settings::CONFIG_INSTITUTION_NAME).is_err());
+
+        alice.run(|| connection::release(alice_handle)).unwrap();
+        faber.run(|| connection::release(faber_handle)).unwrap();
+        faber.close().unwrap();
+        alice.close().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_export_import_state() {
+        let _setup = SetupAgencyMock::init();
+
+        settings::set_config_value(settings::CONFIG_INSTITUTION_NAME, "faber");
+        let handle = connection::create_connection("alice").unwrap();
+        connection::set_metadata(handle, "customer", Some("42")).unwrap();
+
+        let export_file = TempFile::prepare_path(&format!("agent_state_{}", ::utils::uuid::uuid()));
+        export_state(&export_file.path, settings::DEFAULT_WALLET_BACKUP_KEY).unwrap();
+
+        connection::release_all();
+        settings::set_config_value(settings::CONFIG_INSTITUTION_NAME, "other");
+        wallet::close_wallet().unwrap();
+
+        let wallet_name = format!("imported_agent_{}", ::utils::uuid::uuid());
+        let config = json!({
+            settings::CONFIG_WALLET_NAME: wallet_name,
+            settings::CONFIG_WALLET_KEY: settings::DEFAULT_WALLET_KEY,
+            settings::CONFIG_EXPORTED_WALLET_PATH: export_file.path,
+            settings::CONFIG_WALLET_BACKUP_KEY: settings::DEFAULT_WALLET_BACKUP_KEY,
+        }).to_string();
+        let loaded = import_state(&config).unwrap();
+
+        assert_eq!(loaded.connections.len(), 1);
+        assert_eq!(connection::get_source_id(loaded.connections[0]).unwrap(), "alice");
+        assert_eq!(connection::find_handles_by_metadata("customer", "42"), loaded.connections);
+        assert_eq!(settings::get_config_value(settings::CONFIG_INSTITUTION_NAME).unwrap(), "faber");
+        assert_eq!(settings::get_config_value(settings::CONFIG_WALLET_NAME).unwrap(), wallet_name);
+
+        // state record does not stay in the restored wallet
+        assert_eq!(wallet::get_record(STATE_RECORD_TYPE, STATE_RECORD_ID, "{}").unwrap_err().kind(), VcxErrorKind::WalletRecordNotFound);
+
+        connection::release_all();
+        wallet::delete_wallet(&wallet_name, None, None, None).unwrap();
+        settings::set_config_value(settings::CONFIG_WALLET_NAME, settings::DEFAULT_WALLET_NAME);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_import_state_reverts_settings_on_failure() {
+        let _setup = SetupAgencyMock::init();
+
+        let mut exported_settings = HashMap::new();
+        exported_settings.insert(settings::CONFIG_INSTITUTION_NAME.to_string(), "faber".to_string());
+        let state = AgentState {
+            libvcx_version: version_constants::VERSION.to_string(),
+            settings: exported_settings,
+            connections: vec!["not a connection".to_string()],
+            credentials: vec![],
+            issuer_credentials: vec![],
+            proofs: vec![],
+            disclosed_proofs: vec![],
+        };
+        wallet::add_record(STATE_RECORD_TYPE, STATE_RECORD_ID, &serde_json::to_string(&state).unwrap(), None).unwrap();
+        let export_file = TempFile::prepare_path(&format!("agent_state_{}", ::utils::uuid::uuid()));
+        wallet::export(wallet::get_wallet_handle(), &export_file.path, settings::DEFAULT_WALLET_BACKUP_KEY).unwrap();
+        _delete_state_record().unwrap();
+
+        settings::set_config_value(settings::CONFIG_INSTITUTION_NAME, "other");
+        wallet::close_wallet().unwrap();
+
+        let wallet_name = format!("imported_agent_{}", ::utils::uuid::uuid());
+        let config = json!({
+            settings::CONFIG_WALLET_NAME: wallet_name,
+            settings::CONFIG_WALLET_KEY: settings::DEFAULT_WALLET_KEY,
+            settings::CONFIG_EXPORTED_WALLET_PATH: export_file.path,
+            settings::CONFIG_WALLET_BACKUP_KEY: settings::DEFAULT_WALLET_BACKUP_KEY,
+        }).to_string();
+        import_state(&config).unwrap_err();
+
+        assert_eq!(settings::get_config_value(settings::CONFIG_INSTITUTION_NAME).unwrap(), "other");
+        assert!(connection::get_handles().is_empty());
+
+        wallet::delete_wallet(&wallet_name, None, None, None).unwrap();
+        settings::set_config_value(settings::CONFIG_WALLET_NAME, settings::DEFAULT_WALLET_NAME);
+    }
+
+    fn _subject_config() -> serde_json::Value {
+        json!({
+            "wallet_name": "provisioned_subject_wallet",
+            "agency_url": "http://www.whocares.org",
+            "agency_did": "Ab8TvZa3Q19VNkQVzAWVL7",
+            "agency_verkey": "5LXaR43B1aQyeh94VBP8LG1Sgvjk7aNfqiksBCSjwqbf",
+            "wallet_key": "test_key",
+            "protocol_type": "3.0"
+        })
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_provision_for_keeps_settings_of_sponsor() {
+        let _setup = SetupAriesMocks::init();
+
+        settings::set_config_value(settings::CONFIG_INSTITUTION_NAME, "sponsor");
+        let remote_to_sdk_did = settings::get_config_value(settings::CONFIG_REMOTE_TO_SDK_DID).unwrap();
+
+        let bundle: HandoffBundle = serde_json::from_str(&provision_for(&_subject_config().to_string()).unwrap()).unwrap();
+        assert_eq!(bundle.config["remote_to_sdk_did"], "DnEpUQJLupa5rKPkrKUpFd");
+        assert_eq!(bundle.exported_wallet_path, None);
+
+        assert_eq!(settings::get_config_value(settings::CONFIG_INSTITUTION_NAME).unwrap(), "sponsor");
+        assert_eq!(settings::get_config_value(settings::CONFIG_REMOTE_TO_SDK_DID).unwrap(), remote_to_sdk_did);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_provision_for_fails_for_incomplete_handoff_options() {
+        let _setup = SetupAriesMocks::init();
+
+        let mut config = _subject_config();
+        config["exported_wallet_path"] = json!("/tmp/handoff");
+        assert_eq!(provision_for(&config.to_string()).unwrap_err().kind(), VcxErrorKind::InvalidConfiguration);
+
+        let mut config = _subject_config();
+        config["backup_key"] = json!("backup_key");
+        assert_eq!(provision_for(&config.to_string()).unwrap_err().kind(), VcxErrorKind::InvalidConfiguration);
+
+        let mut config = _subject_config();
+        config.as_object_mut().unwrap().remove("wallet_name");
+        assert_eq!(provision_for(&config.to_string()).unwrap_err().kind(), VcxErrorKind::InvalidConfiguration);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_update_webhook_url() {
+        let _setup = SetupAriesMocks::init();
+
+        assert_eq!(update_webhook_url("not a url").unwrap_err().kind(), VcxErrorKind::InvalidUrl);
+
+        update_webhook_url("https://example.org/webhook").unwrap();
+        assert_eq!(settings::get_config_value(settings::CONFIG_WEBHOOK_URL).unwrap(), "https://example.org/webhook");
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_agent_config() {
+        let _setup = SetupAriesMocks::init();
+
+        let config = get_agent_config().unwrap();
+        assert_eq!(config.get("name").unwrap(), "faber");
+        assert_eq!(config.get("logoUrl").unwrap(), "https://example.org/faber.png");
+        assert!(config.get("publicDid").is_none());
+    }
+}
@@ -3,8 +3,10 @@ use std::ptr;
 use indy_sys::CommandHandle;
 use libc::c_char;
 
+use api::sync_result;
 use connection::*;
 use error::prelude::*;
+use utils::cancellation;
 use utils::cstring::CStringUtils;
 use utils::error;
 use utils::threadpool::spawn;
@@ -33,6 +35,9 @@ use aries::messages::a2a::A2AMessage;
             VcxStateType::VcxStateAccepted - once `Ack` messages is received.
                                              use `vcx_connection_update_state` or `vcx_connection_update_state_with_message` functions for state updates.
 
+            VcxStateType::VcxStateRedirected - once `ConnectionRedirect` message pointing to an existing connection is received.
+                                               use `vcx_connection_get_redirect_details` to find out which one.
+
             VcxStateType::VcxStateNone - once `vcx_connection_delete_connection` (delete Connection object) is called
                                             OR
                                         `ConnectionProblemReport` messages is received on state updates.
@@ -42,6 +47,8 @@ use aries::messages::a2a::A2AMessage;
 
             VcxStateType::VcxStateRequestReceived - once `vcx_connection_connect` (accept `ConnectionInvite` and send `ConnectionRequest` message) is called.
 
+            VcxStateType::VcxStateRedirected - once `vcx_connection_redirect` (answer `ConnectionInvite` by an existing connection) is called.
+
             VcxStateType::VcxStateAccepted - once `ConnectionResponse` messages is received.
                                              send `Ack` message if requested.
                                              use `vcx_connection_update_state` or `vcx_connection_update_state_with_message` functions for state updates.
@@ -60,6 +67,7 @@ use aries::messages::a2a::A2AMessage;
 
             VcxStateType::VcxStateOfferSent - received `ConnectionRequest` - VcxStateType::VcxStateRequestReceived
             VcxStateType::VcxStateOfferSent - received `ConnectionProblemReport` - VcxStateType::VcxStateNone
+            VcxStateType::VcxStateOfferSent - received `ConnectionRedirect` - VcxStateType::VcxStateRedirected
 
             VcxStateType::VcxStateRequestReceived - received `Ack` - VcxStateType::VcxStateAccepted
             VcxStateType::VcxStateRequestReceived - received `ConnectionProblemReport` - VcxStateType::VcxStateNone
@@ -73,6 +81,7 @@ use aries::messages::a2a::A2AMessage;
             VcxStateType::None - `vcx_connection_create_with_invite` - VcxStateType::VcxStateOfferSent
 
             VcxStateType::VcxStateOfferSent - `vcx_connection_connect` - VcxStateType::VcxStateRequestReceived
+            VcxStateType::VcxStateOfferSent - `vcx_connection_redirect` - VcxStateType::VcxStateRedirected
             VcxStateType::VcxStateOfferSent - received `ConnectionProblemReport` - VcxStateType::VcxStateNone
 
             VcxStateType::VcxStateRequestReceived - received `ConnectionResponse` - VcxStateType::VcxStateAccepted
@@ -89,6 +98,7 @@ use aries::messages::a2a::A2AMessage;
         ConnectionRequest - https://github.com/hyperledger/aries-rfcs/tree/master/features/0160-connection-protocol#1-connection-request
         ConnectionResponse - https://github.com/hyperledger/aries-rfcs/tree/master/features/0160-connection-protocol#2-connection-response
         ConnectionProblemReport - https://github.com/hyperledger/aries-rfcs/tree/master/features/0160-connection-protocol#error-message-example
+        ConnectionRedirect - sent instead of ConnectionRequest to reuse already established connection, see `vcx_connection_redirect`
         Ack - https://github.com/hyperledger/aries-rfcs/tree/master/features/0015-acks#explicit-acks
         Ping - https://github.com/hyperledger/aries-rfcs/tree/master/features/0048-trust-ping#messages
         PingResponse - https://github.com/hyperledger/aries-rfcs/tree/master/features/0048-trust-ping#messages
@@ -99,6 +109,7 @@ use aries::messages::a2a::A2AMessage;
 /// Delete a Connection object from the agency and release its handle.
 ///
 /// NOTE: This eliminates the connection and any ability to use it for any communication.
+/// Messages still waiting in the outbound queue for the connection are deleted as well.
 ///
 /// # Params
 /// command_handle: command handle to map callback to user context.
@@ -185,6 +196,55 @@ pub extern fn vcx_connection_create(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Create a Connection object announcing custom service endpoint and routing keys instead of the provisioned agency ones
+///
+/// # Params
+/// command_handle: command handle to map callback to user context.
+///
+/// source_id: institution's personal identification for the connection
+///
+/// endpoint_json: endpoint put into the invitation and DIDDoc of the connection
+///     {"service_endpoint": "https://edge.example.org/msg", "routing_keys": ["..."]}
+///     routing_keys are optional. Messages sent to the endpoint do not reach agency,
+///     they must be passed to the library by the caller (e.g. `vcx_connection_update_state_with_message`).
+///
+/// cb: Callback that provides connection handle and error status of request
+///
+/// # Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_connection_create_with_endpoint(command_handle: CommandHandle,
+                                                  source_id: *const c_char,
+                                                  endpoint_json: *const c_char,
+                                                  cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, connection_handle: u32)>) -> u32 {
+    info!("vcx_connection_create_with_endpoint >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(source_id, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(endpoint_json, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_connection_create_with_endpoint(command_handle: {}, source_id: {}, endpoint_json: {})", command_handle, source_id, endpoint_json);
+
+    spawn(move || {
+        match create_connection_with_endpoint(&source_id, &endpoint_json) {
+            Ok(handle) => {
+                trace!("vcx_connection_create_with_endpoint_cb(command_handle: {}, rc: {}, handle: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, handle, source_id);
+                cb(command_handle, error::SUCCESS.code_num, handle);
+            }
+            Err(x) => {
+                warn!("vcx_connection_create_with_endpoint_cb(command_handle: {}, rc: {}, handle: {}) source_id: {}",
+                      command_handle, x, 0, source_id);
+                cb(command_handle, x.into(), 0);
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Create a Connection object from the given invite_details that provides a pairwise connection.
 ///
 /// # Params
@@ -240,6 +300,100 @@ pub extern fn vcx_connection_create_with_invite(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Create a Connection object from invitation URL, as scanned from QR code.
+///
+/// # Params
+/// command_handle: command handle to map callback to user context.
+///
+/// source_id: institution's personal identification for the connection
+///
+/// invite_url: URL carrying base64url encoded invitation in `c_i` or `oob` query parameter.
+///     Shortened URLs are resolved by following the redirects.
+///
+/// cb: Callback that provides connection handle and error status of request
+///
+/// # Examples
+/// invite_url -> "https://example.com/invite?c_i=eyJAdHlwZSI6ImRpZDpzb3Y6QnpDYnNOWWhNcmpIaXFaRFRVQVNIZztzcGVjL2Nvbm5lY3Rpb25zLzEuMC9pbnZpdGF0aW9uIiwuLi59"
+///
+/// # Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_connection_create_with_invite_url(command_handle: CommandHandle,
+                                                    source_id: *const c_char,
+                                                    invite_url: *const c_char,
+                                                    cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, connection_handle: u32)>) -> u32 {
+    info!("vcx_connection_create_with_invite_url >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(source_id, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(invite_url, VcxErrorKind::InvalidOption);
+    trace!("vcx_connection_create_with_invite_url(command_handle: {}, source_id: {}, invite_url: {})", command_handle, source_id, invite_url);
+    spawn(move || {
+        match create_connection_with_invite_url(&source_id, &invite_url) {
+            Ok(handle) => {
+                trace!("vcx_connection_create_with_invite_url_cb(command_handle: {}, rc: {}, handle: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, handle, source_id);
+                cb(command_handle, error::SUCCESS.code_num, handle);
+            }
+            Err(x) => {
+                warn!("vcx_connection_create_with_invite_url_cb(command_handle: {}, rc: {}, handle: {}) source_id: {}",
+                      command_handle, x, 0, source_id);
+                cb(command_handle, x.into(), 0);
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Create a Connection object from out-of-band invitation asking for a connection.
+/// Credential offer attached to the invitation is turned into Credential object, the offer is answered
+/// by `vcx_credential_send_request` once the connection is established.
+///
+/// # Params
+/// command_handle: command handle to map callback to user context.
+///
+/// source_id: institution's personal identification for the connection and the credential
+///
+/// invite: out-of-band invitation with `handshake_protocols`, as returned by `vcx_issuer_get_oob_offer`
+///
+/// cb: Callback that provides connection handle, credential handle (0 when no offer is attached) and error status of request
+///
+/// # Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_connection_create_with_oob_invite(command_handle: CommandHandle,
+                                                    source_id: *const c_char,
+                                                    invite: *const c_char,
+                                                    cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, connection_handle: u32, credential_handle: u32)>) -> u32 {
+    info!("vcx_connection_create_with_oob_invite >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(source_id, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(invite, VcxErrorKind::InvalidOption);
+    trace!("vcx_connection_create_with_oob_invite(command_handle: {}, source_id: {})", command_handle, source_id);
+    spawn(move || {
+        match create_connection_with_oob_invite(&source_id, &invite) {
+            Ok((handle, credential_handle)) => {
+                trace!("vcx_connection_create_with_oob_invite_cb(command_handle: {}, rc: {}, handle: {}, credential_handle: {:?}) source_id: {}",
+                       command_handle, error::SUCCESS.message, handle, credential_handle, source_id);
+                cb(command_handle, error::SUCCESS.code_num, handle, credential_handle.unwrap_or(0));
+            }
+            Err(x) => {
+                warn!("vcx_connection_create_with_oob_invite_cb(command_handle: {}, rc: {}, handle: {}) source_id: {}",
+                      command_handle, x, 0, source_id);
+                cb(command_handle, x.into(), 0, 0);
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Establishes connection between institution and its user
 ///
 /// # Params
@@ -305,6 +459,22 @@ pub extern fn vcx_connection_connect(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Answers invitation of a connection created by `vcx_connection_create_with_invite` with an already established
+/// connection to the same institution instead of connecting (Invitee only).
+/// Redirect message announcing pairwise DIDs of the existing connection is sent to the inviter and the connection
+/// moves to VcxStateRedirected state.
+///
+/// #params
+/// command_handle: command handle to map callback to user context.
+///
+/// connection_handle: Connection handle of the new connection in VcxStateOfferSent state.
+///
+/// redirect_connection_handle: Connection handle of the existing connection in VcxStateAccepted state.
+///
+/// cb: Callback that provides error status of the redirection.
+///
+/// #Returns
+/// Error code as a u32
 #[no_mangle]
 pub extern fn vcx_connection_redirect(command_handle: CommandHandle,
                                       connection_handle: u32,
@@ -329,14 +499,50 @@ pub extern fn vcx_connection_redirect(command_handle: CommandHandle,
            command_handle, connection_handle, redirect_connection_handle, source_id);
 
     spawn(move || {
-        error!("Action not supported");
-        cb(command_handle, error::ACTION_NOT_SUPPORTED.code_num);
+        match redirect_connection(connection_handle, redirect_connection_handle) {
+            Ok(()) => {
+                trace!("vcx_connection_redirect_cb(command_handle: {}, rc: {}), source_id: {:?}",
+                       command_handle, error::SUCCESS.message, source_id);
+                cb(command_handle, error::SUCCESS.code_num);
+            }
+            Err(x) => {
+                warn!("vcx_connection_redirect_cb(command_handle: {}, rc: {}), source_id: {:?}",
+                      command_handle, x, source_id);
+                cb(command_handle, x.into());
+            }
+        };
+
         Ok(())
     });
 
     error::SUCCESS.code_num
 }
 
+/// Gets Redirect message sent (Invitee) or received (Inviter) by a connection in VcxStateRedirected state.
+/// Inviter should continue the relationship on its established connection having pairwise DID `their_did`,
+/// see `vcx_connection_get_pw_did`, with the counterparty `did` and `verkey`.
+///
+/// #params
+/// command_handle: command handle to map callback to user context.
+///
+/// connection_handle: Connection handle of the redirected connection.
+///
+/// cb: Callback that provides json string of redirect details
+///
+/// # Example
+/// details ->
+///     {
+///         "@type": "did:sov:BzCbsNYhMrjHiqZDTUASHg;spec/connections/1.0/redirect",
+///         "@id": "a6ba9f71-9c8c-4ef1-a6b0-bc3b4a2b4d8d",
+///         "did": "VsKV7grR1BUE29mG2Fm2kX",
+///         "verkey": "CnEDk9HrMnmiHXEV1WFgbVCRteYnPqsJwrTdcZaNhFVW",
+///         "their_did": "KC6NKcpXcpVnpjL8uKH3tV",
+///         "signature": "...",
+///         "~thread": {"thid": "<id of the invitation>"}
+///     }
+///
+/// #Returns
+/// Error code as a u32
 #[no_mangle]
 pub extern fn vcx_connection_get_redirect_details(command_handle: CommandHandle,
                                                   connection_handle: u32,
@@ -355,8 +561,70 @@ pub extern fn vcx_connection_get_redirect_details(command_handle: CommandHandle,
     }
 
     spawn(move || {
-        error!("Action not supported");
-        cb(command_handle, error::ACTION_NOT_SUPPORTED.code_num, ptr::null_mut());
+        match get_redirect_details(connection_handle) {
+            Ok(details) => {
+                trace!("vcx_connection_get_redirect_details_cb(command_handle: {}, rc: {}, details: {}), source_id: {:?}",
+                       command_handle, error::SUCCESS.message, details, source_id);
+                let details = CStringUtils::string_to_cstring(details);
+                cb(command_handle, error::SUCCESS.code_num, details.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_connection_get_redirect_details_cb(command_handle: {}, rc: {}, details: {}), source_id: {:?}",
+                      command_handle, x, "null", source_id);
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Verifies again the signature of the Connection Response (`connection~sig`) received by the Invitee.
+/// The signature must be made by the recipient key of the accepted invitation.
+/// Unlike on receipt, the signature age is not checked, so stored connections can be audited at any time.
+///
+/// #params
+/// command_handle: command handle to map callback to user context.
+///
+/// connection_handle: Connection handle of the Invitee connection in VcxStateAccepted state.
+///
+/// cb: Callback that provides error status of the verification.
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_connection_verify_response_signature(command_handle: CommandHandle,
+                                                      connection_handle: u32,
+                                                      cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32)>) -> u32 {
+    info!("vcx_connection_verify_response_signature >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = get_source_id(connection_handle).unwrap_or_default();
+    trace!("vcx_connection_verify_response_signature(command_handle: {}, connection_handle: {}), source_id: {:?}",
+           command_handle, connection_handle, source_id);
+
+    if !is_valid_handle(connection_handle) {
+        error!("vcx_connection_verify_response_signature - invalid handle");
+        return VcxError::from(VcxErrorKind::InvalidConnectionHandle).into();
+    }
+
+    spawn(move || {
+        match verify_response_signature(connection_handle) {
+            Ok(()) => {
+                trace!("vcx_connection_verify_response_signature_cb(command_handle: {}, rc: {}), source_id: {:?}",
+                       command_handle, error::SUCCESS.message, source_id);
+                cb(command_handle, error::SUCCESS.code_num);
+            }
+            Err(x) => {
+                warn!("vcx_connection_verify_response_signature_cb(command_handle: {}, rc: {}), source_id: {:?}",
+                      command_handle, x, source_id);
+                cb(command_handle, x.into());
+            }
+        };
+
         Ok(())
     });
 
@@ -513,6 +781,66 @@ pub extern fn vcx_connection_update_state(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Same as `vcx_connection_update_state`, the update can be cancelled by `vcx_cancel`.
+/// Cancellation is observed until a received message starts being handled, the update is completed after that.
+/// Cancelled update fails with Cancelled error and the connection is unchanged.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// connection_handle: was provided during creation. Used to identify connection object
+///
+/// cancellation_handle: token created by `vcx_create_cancellation_token`
+///
+/// cb: Callback that provides most current state of the connection and error status of request
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_connection_update_state_cancellable(command_handle: CommandHandle,
+                                                      connection_handle: u32,
+                                                      cancellation_handle: u32,
+                                                      cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, state: u32)>) -> u32 {
+    info!("vcx_connection_update_state_cancellable >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = get_source_id(connection_handle).unwrap_or_default();
+    trace!("vcx_connection_update_state_cancellable(command_handle: {}, connection_handle: {}, cancellation_handle: {}), source_id: {:?}",
+           command_handle, connection_handle, cancellation_handle, source_id);
+
+    if !is_valid_handle(connection_handle) {
+        error!("vcx_connection_update_state_cancellable - invalid handle");
+        return VcxError::from(VcxErrorKind::InvalidConnectionHandle).into();
+    }
+
+    let token = match cancellation::get_token(cancellation_handle) {
+        Ok(token) => token,
+        Err(err) => return err.into()
+    };
+
+    spawn(move || {
+        let rc = match cancellation::with_token(&token, || update_state(connection_handle)) {
+            Ok(x) => {
+                trace!("vcx_connection_update_state_cancellable_cb(command_handle: {}, rc: {}, connection_handle: {}, state: {}), source_id: {:?}",
+                       command_handle, error::SUCCESS.message, connection_handle, get_state(connection_handle), source_id);
+                x
+            }
+            Err(x) => {
+                warn!("vcx_connection_update_state_cancellable_cb(command_handle: {}, rc: {}, connection_handle: {}, state: {}), source_id: {:?}",
+                      command_handle, x, connection_handle, get_state(connection_handle), source_id);
+                x.into()
+            }
+        };
+        let state = get_state(connection_handle);
+        cb(command_handle, rc, state);
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Update the state of the connection based on the given message.
 ///
 /// #Params
@@ -545,6 +873,10 @@ pub extern fn vcx_connection_update_state_with_message(command_handle: CommandHa
         return VcxError::from(VcxErrorKind::InvalidConnectionHandle).into();
     }
 
+    if let Err(err) = ::utils::json::check_json_limits(message.as_bytes()) {
+        return err.into();
+    }
+
     let message: A2AMessage = match serde_json::from_str(&message) {
         Ok(x) => x,
         Err(_) => return VcxError::from(VcxErrorKind::InvalidJson).into(),
@@ -587,30 +919,535 @@ pub extern fn vcx_connection_update_state_with_message(command_handle: CommandHa
 ///
 /// connection_handle: Connection handle that was provided during creation. Used to access connection object
 ///
-/// cb: Callback that provides most current state of the connection and error status of request
+/// cb: Callback that provides most current state of the connection and error status of request
+///
+/// #Returns
+#[no_mangle]
+pub extern fn vcx_connection_get_state(command_handle: CommandHandle,
+                                       connection_handle: u32,
+                                       cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, state: u32)>) -> u32 {
+    info!("vcx_connection_get_state >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = get_source_id(connection_handle).unwrap_or_default();
+    trace!("vcx_connection_get_state(command_handle: {}, connection_handle: {}), source_id: {:?}",
+           command_handle, connection_handle, source_id);
+
+    if !is_valid_handle(connection_handle) {
+        error!("vcx_connection_get_state - invalid handle");
+        return VcxError::from(VcxErrorKind::InvalidConnectionHandle).into();
+    }
+
+    spawn(move || {
+        trace!("vcx_connection_get_state_cb(command_handle: {}, rc: {}, connection_handle: {}, state: {}), source_id: {:?}",
+               command_handle, error::SUCCESS.message, connection_handle, get_state(connection_handle), source_id);
+        cb(command_handle, error::SUCCESS.code_num, get_state(connection_handle));
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Synchronous variant of `vcx_connection_create`, runs on the calling thread.
+///
+/// # Params
+/// source_id: institution's personal identification for the connection
+///
+/// connection_handle_p: Reference that will contain handle of the created connection
+///
+/// # Returns
+/// Error code as a u32, details are available by `vcx_get_current_error`
+#[no_mangle]
+pub extern fn vcx_connection_create_sync(source_id: *const c_char,
+                                         connection_handle_p: *mut u32) -> u32 {
+    info!("vcx_connection_create_sync >>>");
+
+    check_useful_out_ptr!(connection_handle_p, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(source_id, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_connection_create_sync(source_id: {})", source_id);
+
+    sync_result::set_u32(create_connection(&source_id), connection_handle_p)
+}
+
+/// Synchronous variant of `vcx_connection_update_state`, runs on the calling thread.
+///
+/// # Params
+/// connection_handle: was provided during creation. Used to identify connection object
+///
+/// state_p: Reference that will contain the most current state of the connection
+///
+/// # Returns
+/// Error code as a u32, details are available by `vcx_get_current_error`
+#[no_mangle]
+pub extern fn vcx_connection_update_state_sync(connection_handle: u32,
+                                               state_p: *mut u32) -> u32 {
+    info!("vcx_connection_update_state_sync >>>");
+
+    check_useful_out_ptr!(state_p, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_connection_update_state_sync(connection_handle: {})", connection_handle);
+
+    if !is_valid_handle(connection_handle) {
+        error!("vcx_connection_update_state_sync - invalid handle");
+        return VcxError::from(VcxErrorKind::InvalidConnectionHandle).into();
+    }
+
+    let result = update_state(connection_handle).map(|_| get_state(connection_handle));
+    sync_result::set_u32(result, state_p)
+}
+
+/// Synchronous variant of `vcx_connection_get_state`.
+///
+/// # Params
+/// connection_handle: was provided during creation. Used to identify connection object
+///
+/// state_p: Reference that will contain the most current state of the connection
+///
+/// # Returns
+/// Error code as a u32, details are available by `vcx_get_current_error`
+#[no_mangle]
+pub extern fn vcx_connection_get_state_sync(connection_handle: u32,
+                                            state_p: *mut u32) -> u32 {
+    info!("vcx_connection_get_state_sync >>>");
+
+    check_useful_out_ptr!(state_p, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_connection_get_state_sync(connection_handle: {})", connection_handle);
+
+    if !is_valid_handle(connection_handle) {
+        error!("vcx_connection_get_state_sync - invalid handle");
+        return VcxError::from(VcxErrorKind::InvalidConnectionHandle).into();
+    }
+
+    sync_result::set_u32(Ok(get_state(connection_handle)), state_p)
+}
+
+/// Synchronous variant of `vcx_connection_serialize`.
+///
+/// # Params
+/// connection_handle: Connection handle that identifies pairwise connection
+///
+/// serialized_data_p: Reference that will contain json string of the connection's attributes.
+///     The string is valid until the next `*_sync` call returning a string in the same thread.
+///
+/// # Returns
+/// Error code as a u32, details are available by `vcx_get_current_error`
+#[no_mangle]
+pub extern fn vcx_connection_serialize_sync(connection_handle: u32,
+                                            serialized_data_p: *mut *const c_char) -> u32 {
+    info!("vcx_connection_serialize_sync >>>");
+
+    check_useful_out_ptr!(serialized_data_p, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_connection_serialize_sync(connection_handle: {})", connection_handle);
+
+    if !is_valid_handle(connection_handle) {
+        error!("vcx_connection_serialize_sync - invalid handle");
+        return VcxError::from(VcxErrorKind::InvalidConnectionHandle).into();
+    }
+
+    sync_result::set_c_str(to_string(connection_handle), serialized_data_p)
+}
+
+/// Get the invite details that were sent or can be sent to the remote side.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// connection_handle: was provided during creation. Used to identify connection object
+///
+/// abbreviated: deprecated, has not effect
+///
+/// cb: Callback that provides the json string of details
+///
+/// # Example
+/// details -> depends on communication method:
+///     aries:
+///      {
+///         "label": "Alice",
+///         "serviceEndpoint": "https://example.com/endpoint",
+///         "recipientKeys": ["8HH5gYEeNc3z7PYXmd54d4x6qAfCNrqQqEB3nS7Zfu7K"],
+///         "routingKeys": ["8HH5gYEeNc3z7PYXmd54d4x6qAfCNrqQqEB3nS7Zfu7K"],
+///         "protocols": [
+///             {"pid": "did:sov:BzCbsNYhMrjHiqZDTUASHg;spec/connections/1.0", "roles": "Invitee"},
+///             ...
+///         ] - optional array. The set of protocol supported by remote side. Is filled after DiscoveryFeatures process was completed.
+/////    }
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_connection_invite_details(command_handle: CommandHandle,
+                                            connection_handle: u32,
+                                            _abbreviated: bool,
+                                            cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, details: *const c_char)>) -> u32 {
+    info!("vcx_connection_invite_details >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = get_source_id(connection_handle).unwrap_or_default();
+    trace!("vcx_connection_invite_details(command_handle: {}, connection_handle: {}), source_id: {:?}",
+           command_handle, connection_handle, source_id);
+
+    if !is_valid_handle(connection_handle) {
+        error!("vcx_connection_get_state - invalid handle");
+        return VcxError::from(VcxErrorKind::InvalidConnectionHandle).into();
+    }
+
+    spawn(move || {
+        match get_invite_details(connection_handle) {
+            Ok(str) => {
+                trace!("vcx_connection_invite_details_cb(command_handle: {}, connection_handle: {}, rc: {}, details: {}), source_id: {:?}",
+                       command_handle, connection_handle, error::SUCCESS.message, str, source_id);
+                let msg = CStringUtils::string_to_cstring(str);
+                cb(command_handle, error::SUCCESS.code_num, msg.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_connection_invite_details_cb(command_handle: {}, connection_handle: {}, rc: {}, details: {}, source_id: {:?})",
+                      command_handle, connection_handle, x, "null", source_id);
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Get invitation of the connection encoded into URL, suitable to be rendered as QR code.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// connection_handle: was provided during creation. Used to identify connection object
+///
+/// base_url: URL the encoded invitation is appended to as `c_i` query parameter
+///
+/// cb: Callback that provides the invitation URL
+///
+/// # Example
+/// invite_url -> "https://example.com/invite?c_i=eyJAdHlwZSI6ImRpZDpzb3Y6QnpDYnNOWWhNcmpIaXFaRFRVQVNIZztzcGVjL2Nvbm5lY3Rpb25zLzEuMC9pbnZpdGF0aW9uIiwuLi59"
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_connection_get_invite_url(command_handle: CommandHandle,
+                                            connection_handle: u32,
+                                            base_url: *const c_char,
+                                            cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, invite_url: *const c_char)>) -> u32 {
+    info!("vcx_connection_get_invite_url >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(base_url, VcxErrorKind::InvalidOption);
+
+    let source_id = get_source_id(connection_handle).unwrap_or_default();
+    trace!("vcx_connection_get_invite_url(command_handle: {}, connection_handle: {}, base_url: {}), source_id: {:?}",
+           command_handle, connection_handle, base_url, source_id);
+
+    if !is_valid_handle(connection_handle) {
+        error!("vcx_connection_get_invite_url - invalid handle");
+        return VcxError::from(VcxErrorKind::InvalidConnectionHandle).into();
+    }
+
+    spawn(move || {
+        match get_invite_url(connection_handle, &base_url) {
+            Ok(invite_url) => {
+                trace!("vcx_connection_get_invite_url_cb(command_handle: {}, connection_handle: {}, rc: {}, invite_url: {}), source_id: {:?}",
+                       command_handle, connection_handle, error::SUCCESS.message, invite_url, source_id);
+                let invite_url = CStringUtils::string_to_cstring(invite_url);
+                cb(command_handle, error::SUCCESS.code_num, invite_url.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_connection_get_invite_url_cb(command_handle: {}, connection_handle: {}, rc: {}, source_id: {:?})",
+                      command_handle, connection_handle, x, source_id);
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Send a message to the specified connection
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// connection_handle: connection to use to send the message.
+///                    Was provided during creation. Used to identify connection object.
+///                    Note that connection must be in Accepted state.
+///
+/// msg: actual message to send
+///
+/// send_msg_options: deprecated, has not effect
+///
+/// cb: Callback that provides id of retrieved response message
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_connection_send_message(command_handle: CommandHandle,
+                                          connection_handle: u32,
+                                          msg: *const c_char,
+                                          _send_msg_options: *const c_char,
+                                          cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, msg_id: *const c_char)>) -> u32 {
+    info!("vcx_connection_send_message >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(msg, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_connection_send_message(command_handle: {}, connection_handle: {}, msg: {})",
+           command_handle, connection_handle, msg);
+
+    spawn(move || {
+        match send_generic_message(connection_handle, &msg) {
+            Ok(x) => {
+                trace!("vcx_connection_send_message_cb(command_handle: {}, rc: {}, msg_id: {})",
+                       command_handle, error::SUCCESS.message, x);
+
+                let msg_id = CStringUtils::string_to_cstring(x);
+                cb(command_handle, error::SUCCESS.code_num, msg_id.as_ptr());
+            }
+            Err(e) => {
+                warn!("vcx_connection_send_message_cb(command_handle: {}, rc: {})",
+                      command_handle, e);
+
+                cb(command_handle, e.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Send trust ping message to the specified connection to prove that two agents have a functional pairwise channel.
+///
+/// Note that this function is useful in case `aries` communication method is used.
+/// In other cases it returns ActionNotSupported error.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// connection_handle: connection to use to send ping message.
+///                    Was provided during creation. Used to identify connection object.
+///                    Note that connection must be in Accepted state.
+///
+/// comment: (Optional) human-friendly description of the ping.
+///
+/// cb: Callback that provides success or failure of request
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_connection_send_ping(command_handle: u32,
+                                       connection_handle: u32,
+                                       comment: *const c_char,
+                                       cb: Option<extern fn(xcommand_handle: u32, err: u32)>) -> u32 {
+    info!("vcx_connection_send_ping >>>");
+
+    check_useful_opt_c_str!(comment, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_connection_send_ping(command_handle: {}, connection_handle: {}, comment: {:?})",
+           command_handle, connection_handle, comment);
+
+    spawn(move || {
+        match send_ping(connection_handle, comment) {
+            Ok(()) => {
+                trace!("vcx_connection_send_ping(command_handle: {}, rc: {})",
+                       command_handle, error::SUCCESS.message);
+                cb(command_handle, error::SUCCESS.code_num);
+            }
+            Err(e) => {
+                warn!("vcx_connection_send_ping(command_handle: {}, rc: {})",
+                      command_handle, e);
+
+                cb(command_handle, e.into());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Send `invite-action` message asking the counterparty to take an action, e.g. to start a presentation.
+/// The counterparty is asked to acknowledge the acceptance, the acknowledgement and the rejection (problem report)
+/// are threaded to the sent message and can be found among downloaded messages.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// connection_handle: connection to send message, it must be in Accepted state.
+///
+/// goal_code: identifier of the requested action, e.g. "present-proof".
+///
+/// cb: Callback that provides sent message
+///
+/// # Example
+/// message ->
+///     {
+///         "@type": "did:sov:BzCbsNYhMrjHiqZDTUASHg;spec/invite-action/0.9/invite",
+///         "@id": "24a3a1c9-f3e8-4b97-a4ad-ffa1bdc1ee43",
+///         "goal_code": "present-proof",
+///         "ack_on": ["ACCEPT"]
+///     }
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_connection_send_invite_action(command_handle: CommandHandle,
+                                                connection_handle: u32,
+                                                goal_code: *const c_char,
+                                                cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, message: *const c_char)>) -> u32 {
+    info!("vcx_connection_send_invite_action >>>");
+
+    check_useful_c_str!(goal_code, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_connection_send_invite_action(command_handle: {}, connection_handle: {}, goal_code: {:?})",
+           command_handle, connection_handle, goal_code);
+
+    if !is_valid_handle(connection_handle) {
+        error!("vcx_connection_send_invite_action - invalid handle");
+        return VcxError::from(VcxErrorKind::InvalidConnectionHandle).into();
+    }
+
+    spawn(move || {
+        match send_invite_action(connection_handle, &goal_code) {
+            Ok(message) => {
+                trace!("vcx_connection_send_invite_action_cb(command_handle: {}, rc: {}, message: {})",
+                       command_handle, error::SUCCESS.message, message);
+                let message = CStringUtils::string_to_cstring(message);
+                cb(command_handle, error::SUCCESS.code_num, message.as_ptr());
+            }
+            Err(e) => {
+                warn!("vcx_connection_send_invite_action_cb(command_handle: {}, rc: {})",
+                      command_handle, e);
+                cb(command_handle, e.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Accept received `invite-action` message, the inviter is acknowledged if it asked for it.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// connection_handle: connection the message was received on.
+///
+/// invite: received `invite-action` message.
+///
+/// cb: Callback that provides success or failure of request
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_connection_accept_invite_action(command_handle: CommandHandle,
+                                                  connection_handle: u32,
+                                                  invite: *const c_char,
+                                                  cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32)>) -> u32 {
+    info!("vcx_connection_accept_invite_action >>>");
+
+    check_useful_c_str!(invite, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_connection_accept_invite_action(command_handle: {}, connection_handle: {}, invite: {})",
+           command_handle, connection_handle, invite);
+
+    if !is_valid_handle(connection_handle) {
+        error!("vcx_connection_accept_invite_action - invalid handle");
+        return VcxError::from(VcxErrorKind::InvalidConnectionHandle).into();
+    }
+
+    spawn(move || {
+        match accept_invite_action(connection_handle, &invite) {
+            Ok(()) => {
+                trace!("vcx_connection_accept_invite_action_cb(command_handle: {}, rc: {})",
+                       command_handle, error::SUCCESS.message);
+                cb(command_handle, error::SUCCESS.code_num);
+            }
+            Err(e) => {
+                warn!("vcx_connection_accept_invite_action_cb(command_handle: {}, rc: {})",
+                      command_handle, e);
+                cb(command_handle, e.into());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Reject received `invite-action` message by sending problem report to the inviter.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// connection_handle: connection the message was received on.
+///
+/// invite: received `invite-action` message.
+///
+/// comment: (Optional) human-friendly reason of the rejection.
+///
+/// cb: Callback that provides success or failure of request
 ///
 /// #Returns
+/// Error code as a u32
 #[no_mangle]
-pub extern fn vcx_connection_get_state(command_handle: CommandHandle,
-                                       connection_handle: u32,
-                                       cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, state: u32)>) -> u32 {
-    info!("vcx_connection_get_state >>>");
+pub extern fn vcx_connection_reject_invite_action(command_handle: CommandHandle,
+                                                  connection_handle: u32,
+                                                  invite: *const c_char,
+                                                  comment: *const c_char,
+                                                  cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32)>) -> u32 {
+    info!("vcx_connection_reject_invite_action >>>");
 
+    check_useful_c_str!(invite, VcxErrorKind::InvalidOption);
+    check_useful_opt_c_str!(comment, VcxErrorKind::InvalidOption);
     check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
 
-    let source_id = get_source_id(connection_handle).unwrap_or_default();
-    trace!("vcx_connection_get_state(command_handle: {}, connection_handle: {}), source_id: {:?}",
-           command_handle, connection_handle, source_id);
+    trace!("vcx_connection_reject_invite_action(command_handle: {}, connection_handle: {}, invite: {}, comment: {:?})",
+           command_handle, connection_handle, invite, comment);
 
     if !is_valid_handle(connection_handle) {
-        error!("vcx_connection_get_state - invalid handle");
+        error!("vcx_connection_reject_invite_action - invalid handle");
         return VcxError::from(VcxErrorKind::InvalidConnectionHandle).into();
     }
 
     spawn(move || {
-        trace!("vcx_connection_get_state_cb(command_handle: {}, rc: {}, connection_handle: {}, state: {}), source_id: {:?}",
-               command_handle, error::SUCCESS.message, connection_handle, get_state(connection_handle), source_id);
-        cb(command_handle, error::SUCCESS.code_num, get_state(connection_handle));
+        match reject_invite_action(connection_handle, &invite, comment) {
+            Ok(()) => {
+                trace!("vcx_connection_reject_invite_action_cb(command_handle: {}, rc: {})",
+                       command_handle, error::SUCCESS.message);
+                cb(command_handle, error::SUCCESS.code_num);
+            }
+            Err(e) => {
+                warn!("vcx_connection_reject_invite_action_cb(command_handle: {}, rc: {})",
+                      command_handle, e);
+                cb(command_handle, e.into());
+            }
+        };
 
         Ok(())
     });
@@ -618,63 +1455,43 @@ pub extern fn vcx_connection_get_state(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
-/// Get the invite details that were sent or can be sent to the remote side.
-///
-/// #Params
-/// command_handle: command handle to map callback to user context.
+/// Send trust ping requesting response to measure round trip time, returns without waiting for the response.
+/// Useful as a liveness check of the connection before sending large payloads.
+/// The round trip time is provided by `vcx_connection_get_ping_rtt` once the response arrives.
 ///
-/// connection_handle: was provided during creation. Used to identify connection object
+/// #params
 ///
-/// abbreviated: deprecated, has not effect
+/// command_handle: command handle to map callback to user context.
 ///
-/// cb: Callback that provides the json string of details
+/// connection_handle: connection to ping, it must be in Accepted state.
 ///
-/// # Example
-/// details -> depends on communication method:
-///     aries:
-///      {
-///         "label": "Alice",
-///         "serviceEndpoint": "https://example.com/endpoint",
-///         "recipientKeys": ["8HH5gYEeNc3z7PYXmd54d4x6qAfCNrqQqEB3nS7Zfu7K"],
-///         "routingKeys": ["8HH5gYEeNc3z7PYXmd54d4x6qAfCNrqQqEB3nS7Zfu7K"],
-///         "protocols": [
-///             {"pid": "did:sov:BzCbsNYhMrjHiqZDTUASHg;spec/connections/1.0", "roles": "Invitee"},
-///             ...
-///         ] - optional array. The set of protocol supported by remote side. Is filled after DiscoveryFeatures process was completed.
-/////    }
+/// cb: Callback that provides feedback of the api call.
 ///
 /// #Returns
 /// Error code as a u32
 #[no_mangle]
-pub extern fn vcx_connection_invite_details(command_handle: CommandHandle,
-                                            connection_handle: u32,
-                                            _abbreviated: bool,
-                                            cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, details: *const c_char)>) -> u32 {
-    info!("vcx_connection_invite_details >>>");
+pub extern fn vcx_connection_ping_and_measure(command_handle: u32,
+                                              connection_handle: u32,
+                                              cb: Option<extern fn(xcommand_handle: u32, err: u32)>) -> u32 {
+    info!("vcx_connection_ping_and_measure >>>");
 
     check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
 
-    let source_id = get_source_id(connection_handle).unwrap_or_default();
-    trace!("vcx_connection_invite_details(command_handle: {}, connection_handle: {}), source_id: {:?}",
-           command_handle, connection_handle, source_id);
-
-    if !is_valid_handle(connection_handle) {
-        error!("vcx_connection_get_state - invalid handle");
-        return VcxError::from(VcxErrorKind::InvalidConnectionHandle).into();
-    }
+    trace!("vcx_connection_ping_and_measure(command_handle: {}, connection_handle: {})",
+           command_handle, connection_handle);
 
     spawn(move || {
-        match get_invite_details(connection_handle) {
-            Ok(str) => {
-                trace!("vcx_connection_invite_details_cb(command_handle: {}, connection_handle: {}, rc: {}, details: {}), source_id: {:?}",
-                       command_handle, connection_handle, error::SUCCESS.message, str, source_id);
-                let msg = CStringUtils::string_to_cstring(str);
-                cb(command_handle, error::SUCCESS.code_num, msg.as_ptr());
+        match ping_and_measure(connection_handle) {
+            Ok(()) => {
+                trace!("vcx_connection_ping_and_measure_cb(command_handle: {}, rc: {})",
+                       command_handle, error::SUCCESS.message);
+                cb(command_handle, error::SUCCESS.code_num);
             }
-            Err(x) => {
-                warn!("vcx_connection_invite_details_cb(command_handle: {}, connection_handle: {}, rc: {}, details: {}, source_id: {:?})",
-                      command_handle, connection_handle, x, "null", source_id);
-                cb(command_handle, x.into(), ptr::null_mut());
+            Err(e) => {
+                warn!("vcx_connection_ping_and_measure_cb(command_handle: {}, rc: {})",
+                      command_handle, e);
+
+                cb(command_handle, e.into());
             }
         };
 
@@ -684,52 +1501,44 @@ pub extern fn vcx_connection_invite_details(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
-/// Send a message to the specified connection
+/// Check whether the response to the ping sent by `vcx_connection_ping_and_measure` arrived.
+/// The response is noticed only when this function is called, so the time is rounded up to the polling interval.
 ///
 /// #params
 ///
 /// command_handle: command handle to map callback to user context.
 ///
-/// connection_handle: connection to use to send the message.
-///                    Was provided during creation. Used to identify connection object.
-///                    Note that connection must be in Accepted state.
-///
-/// msg: actual message to send
-///
-/// send_msg_options: deprecated, has not effect
+/// connection_handle: connection the ping was sent over.
 ///
-/// cb: Callback that provides id of retrieved response message
+/// cb: Callback that provides whether the response was received and the round trip time in milliseconds.
+///     Error NoResponse (1114) is returned when no response arrives in 15 seconds.
 ///
 /// #Returns
 /// Error code as a u32
 #[no_mangle]
-pub extern fn vcx_connection_send_message(command_handle: CommandHandle,
+pub extern fn vcx_connection_get_ping_rtt(command_handle: u32,
                                           connection_handle: u32,
-                                          msg: *const c_char,
-                                          _send_msg_options: *const c_char,
-                                          cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, msg_id: *const c_char)>) -> u32 {
-    info!("vcx_connection_send_message >>>");
+                                          cb: Option<extern fn(xcommand_handle: u32, err: u32, received: bool, rtt_ms: u32)>) -> u32 {
+    info!("vcx_connection_get_ping_rtt >>>");
 
     check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
-    check_useful_c_str!(msg, VcxErrorKind::InvalidOption);
 
-    trace!("vcx_connection_send_message(command_handle: {}, connection_handle: {}, msg: {})",
-           command_handle, connection_handle, msg);
+    trace!("vcx_connection_get_ping_rtt(command_handle: {}, connection_handle: {})",
+           command_handle, connection_handle);
 
     spawn(move || {
-        match send_generic_message(connection_handle, &msg) {
-            Ok(x) => {
-                trace!("vcx_connection_send_message_cb(command_handle: {}, rc: {}, msg_id: {})",
-                       command_handle, error::SUCCESS.message, x);
-
-                let msg_id = CStringUtils::string_to_cstring(x);
-                cb(command_handle, error::SUCCESS.code_num, msg_id.as_ptr());
+        match get_ping_rtt(connection_handle) {
+            Ok(rtt) => {
+                let rtt_ms = rtt.map(|rtt| rtt.as_secs() as u32 * 1000 + rtt.subsec_millis()).unwrap_or(0);
+                trace!("vcx_connection_get_ping_rtt_cb(command_handle: {}, rc: {}, received: {}, rtt_ms: {})",
+                       command_handle, error::SUCCESS.message, rtt.is_some(), rtt_ms);
+                cb(command_handle, error::SUCCESS.code_num, rtt.is_some(), rtt_ms);
             }
             Err(e) => {
-                warn!("vcx_connection_send_message_cb(command_handle: {}, rc: {})",
+                warn!("vcx_connection_get_ping_rtt_cb(command_handle: {}, rc: {})",
                       command_handle, e);
 
-                cb(command_handle, e.into(), ptr::null_mut());
+                cb(command_handle, e.into(), false, 0);
             }
         };
 
@@ -739,50 +1548,43 @@ pub extern fn vcx_connection_send_message(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
-/// Send trust ping message to the specified connection to prove that two agents have a functional pairwise channel.
-///
-/// Note that this function is useful in case `aries` communication method is used.
-/// In other cases it returns ActionNotSupported error.
+/// Answer trust pings received on completed connection.
+/// Pings are not answered while messages of the connection are downloaded, call this regularly
+/// (e.g. together with updating state of objects using the connection) to keep the connection responsive.
 ///
 /// #params
 ///
 /// command_handle: command handle to map callback to user context.
 ///
-/// connection_handle: connection to use to send ping message.
-///                    Was provided during creation. Used to identify connection object.
-///                    Note that connection must be in Accepted state.
-///
-/// comment: (Optional) human-friendly description of the ping.
+/// connection_handle: connection to answer pings on, it must be in Accepted state.
 ///
-/// cb: Callback that provides success or failure of request
+/// cb: Callback that provides the number of answered pings.
 ///
 /// #Returns
 /// Error code as a u32
 #[no_mangle]
-pub extern fn vcx_connection_send_ping(command_handle: u32,
-                                       connection_handle: u32,
-                                       comment: *const c_char,
-                                       cb: Option<extern fn(xcommand_handle: u32, err: u32)>) -> u32 {
-    info!("vcx_connection_send_ping >>>");
+pub extern fn vcx_connection_answer_pings(command_handle: u32,
+                                          connection_handle: u32,
+                                          cb: Option<extern fn(xcommand_handle: u32, err: u32, answered: u32)>) -> u32 {
+    info!("vcx_connection_answer_pings >>>");
 
-    check_useful_opt_c_str!(comment, VcxErrorKind::InvalidOption);
     check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
 
-    trace!("vcx_connection_send_ping(command_handle: {}, connection_handle: {}, comment: {:?})",
-           command_handle, connection_handle, comment);
+    trace!("vcx_connection_answer_pings(command_handle: {}, connection_handle: {})",
+           command_handle, connection_handle);
 
     spawn(move || {
-        match send_ping(connection_handle, comment) {
-            Ok(()) => {
-                trace!("vcx_connection_send_ping(command_handle: {}, rc: {})",
-                       command_handle, error::SUCCESS.message);
-                cb(command_handle, error::SUCCESS.code_num);
+        match answer_pings(connection_handle) {
+            Ok(answered) => {
+                trace!("vcx_connection_answer_pings_cb(command_handle: {}, rc: {}, answered: {})",
+                       command_handle, error::SUCCESS.message, answered);
+                cb(command_handle, error::SUCCESS.code_num, answered);
             }
             Err(e) => {
-                warn!("vcx_connection_send_ping(command_handle: {}, rc: {})",
+                warn!("vcx_connection_answer_pings_cb(command_handle: {}, rc: {})",
                       command_handle, e);
 
-                cb(command_handle, e.into());
+                cb(command_handle, e.into(), 0);
             }
         };
 
@@ -1059,6 +1861,12 @@ pub extern fn vcx_connection_send_discovery_features(command_handle: u32,
 ///             "routingKeys": array<str> - Routing keys
 ///             "serviceEndpoint": <str> - Endpoint
 ///             "protocols": array<str> - The set of protocol supported by side. Is filled after DiscoveryFeatures process was completed.
+///          },
+///         "stats": { - messaging activity observed since the library was loaded
+///             "messagesSent": <int>
+///             "messagesReceived": <int> - messages downloaded from agency and handled
+///             "lastActivity": <Option<str>> - RFC 3339 time of the last message sent or received
+///             "pendingMessages": <int> - messages waiting at agency at the last download, less the ones handled since
 ///          }
 ///    }
 ///
@@ -1102,6 +1910,56 @@ pub extern fn vcx_connection_info(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Get protocols the counterparty disclosed in response to `vcx_connection_send_discovery_features`
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// connection_handle: was provided during creation. Used to identify connection object
+///
+/// cb: Callback that provides the list of protocols, NotReady error is returned until the counterparty discloses them
+///     [{"pid": "did:sov:BzCbsNYhMrjHiqZDTUASHg;spec/present-proof/1.0", "family": "present-proof", "version": "1.0", "roles": ["Verifier"]}]
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_connection_get_their_supported_protocols(command_handle: CommandHandle,
+                                                           connection_handle: u32,
+                                                           cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, protocols: *const c_char)>) -> u32 {
+    info!("vcx_connection_get_their_supported_protocols >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_connection_get_their_supported_protocols(command_handle: {}, connection_handle: {})",
+           command_handle, connection_handle);
+
+    if !is_valid_handle(connection_handle) {
+        error!("vcx_connection_get_their_supported_protocols - invalid handle");
+        return VcxError::from(VcxErrorKind::InvalidConnectionHandle).into();
+    }
+
+    spawn(move || {
+        match get_their_supported_protocols(connection_handle) {
+            Ok(protocols) => {
+                let protocols = json!(protocols).to_string();
+                trace!("vcx_connection_get_their_supported_protocols_cb(command_handle: {}, rc: {}, protocols: {})",
+                       command_handle, error::SUCCESS.message, protocols);
+                let protocols = CStringUtils::string_to_cstring(protocols);
+                cb(command_handle, error::SUCCESS.code_num, protocols.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_connection_get_their_supported_protocols_cb(command_handle: {}, rc: {})",
+                      command_handle, x);
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Retrieves pw_did from Connection object
 ///
 /// #Params
@@ -1206,6 +2064,9 @@ mod tests {
     use std::ptr;
 
     use api::{return_types_u32, VcxStateType};
+    use aries::messages::connection::invite::tests::_invitation;
+    use aries::messages::issuance::credential_offer::tests::_credential_offer;
+    use aries::messages::out_of_band::invitation::OutOfBandInvitation;
     use connection::tests::{build_test_connection_inviter_invited, build_test_connection_inviter_null, build_test_connection_inviter_requested};
     use utils::constants::{DELETE_CONNECTION_DECRYPTED_RESPONSE, GET_MESSAGES_DECRYPTED_RESPONSE};
     use utils::devsetup::*;
@@ -1231,6 +2092,26 @@ mod tests {
         assert!(cb.receive(TimeoutUtils::some_medium()).unwrap() > 0);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_connection_create_with_endpoint() {
+        let _setup = SetupAriesMocks::init();
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        let _rc = vcx_connection_create_with_endpoint(cb.command_handle,
+                                                      CString::new("test_create").unwrap().into_raw(),
+                                                      CString::new(r#"{"service_endpoint":"https://edge.example.org/msg"}"#).unwrap().into_raw(),
+                                                      Some(cb.get_callback()));
+        assert!(cb.receive(TimeoutUtils::some_medium()).unwrap() > 0);
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        let _rc = vcx_connection_create_with_endpoint(cb.command_handle,
+                                                      CString::new("test_create").unwrap().into_raw(),
+                                                      CString::new(r#"{"service_endpoint":"not a url"}"#).unwrap().into_raw(),
+                                                      Some(cb.get_callback()));
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap_err(), error::INVALID_URL.code_num);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_vcx_connection_create_fails() {
@@ -1281,6 +2162,59 @@ mod tests {
         assert!(invitation["@id"].is_string());
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_connection_invite_url() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = build_test_connection_inviter_invited();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        let rc = vcx_connection_get_invite_url(cb.command_handle, handle, CString::new("https://example.com/invite").unwrap().into_raw(), Some(cb.get_callback()));
+        assert_eq!(rc, error::SUCCESS.code_num);
+        let invite_url = cb.receive(TimeoutUtils::some_medium()).unwrap().unwrap();
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        let rc = vcx_connection_create_with_invite_url(cb.command_handle,
+                                                       CString::new("alice").unwrap().into_raw(),
+                                                       CString::new(invite_url).unwrap().into_raw(),
+                                                       Some(cb.get_callback()));
+        assert_eq!(rc, error::SUCCESS.code_num);
+        assert!(cb.receive(TimeoutUtils::some_medium()).unwrap() > 0);
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        let rc = vcx_connection_get_invite_url(cb.command_handle, 0, CString::new("https://example.com/invite").unwrap().into_raw(), Some(cb.get_callback()));
+        assert_eq!(rc, error::INVALID_CONNECTION_HANDLE.code_num);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_connection_create_with_oob_invite() {
+        let _setup = SetupAriesMocks::init();
+
+        let invitation = OutOfBandInvitation::create()
+            .set_connection_invitation(_invitation())
+            .set_request(_credential_offer().to_a2a_message()).unwrap();
+
+        let cb = return_types_u32::Return_U32_U32_U32::new().unwrap();
+        let rc = vcx_connection_create_with_oob_invite(cb.command_handle,
+                                                       CString::new("alice").unwrap().into_raw(),
+                                                       CString::new(json!(invitation).to_string()).unwrap().into_raw(),
+                                                       Some(cb.get_callback()));
+        assert_eq!(rc, error::SUCCESS.code_num);
+        let (handle, credential_handle) = cb.receive(TimeoutUtils::some_medium()).unwrap();
+        assert!(handle > 0);
+        assert!(credential_handle > 0);
+
+        let cb = return_types_u32::Return_U32_U32_U32::new().unwrap();
+        let rc = vcx_connection_create_with_oob_invite(cb.command_handle,
+                                                       CString::new("alice").unwrap().into_raw(),
+                                                       CString::new(json!(_invitation().to_a2a_message()).to_string()).unwrap().into_raw(),
+                                                       Some(cb.get_callback()));
+        assert_eq!(rc, error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap_err(), error::INVALID_JSON.code_num);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_vcx_connection_update_state() {
@@ -1349,6 +2283,30 @@ mod tests {
         cb.receive(TimeoutUtils::some_medium()).unwrap().unwrap();
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_connection_sync_functions() {
+        let _setup = SetupAriesMocks::init();
+
+        let mut handle: u32 = 0;
+        let rc = vcx_connection_create_sync(CString::new("test_create_sync").unwrap().into_raw(), &mut handle);
+        assert_eq!(rc, error::SUCCESS.code_num);
+        assert!(handle > 0);
+
+        let mut state: u32 = 0;
+        assert_eq!(vcx_connection_get_state_sync(handle, &mut state), error::SUCCESS.code_num);
+        assert_eq!(state, VcxStateType::VcxStateInitialized as u32);
+
+        let mut serialized: *const c_char = ptr::null();
+        assert_eq!(vcx_connection_serialize_sync(handle, &mut serialized), error::SUCCESS.code_num);
+        let serialized = CStringUtils::c_str_to_string(serialized).unwrap().unwrap();
+        assert!(serialized.contains("test_create_sync"));
+
+        assert_eq!(vcx_connection_get_state_sync(handle + 1, &mut state), error::INVALID_CONNECTION_HANDLE.code_num);
+        assert_eq!(vcx_connection_get_state_sync(handle, ptr::null_mut()), error::INVALID_OPTION.code_num);
+        assert_eq!(vcx_connection_create_sync(CString::new("test_create_sync_null").unwrap().into_raw(), ptr::null_mut()), error::INVALID_OPTION.code_num);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_vcx_connection_release() {
@@ -1401,6 +2359,31 @@ mod tests {
         assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap(), VcxStateType::VcxStateRequestReceived as u32)
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_connection_update_state_cancellable() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = build_test_connection_inviter_invited();
+        let state = get_state(handle);
+
+        let mut cancellation_handle: u32 = 0;
+        assert_eq!(::api::utils::vcx_create_cancellation_token(&mut cancellation_handle), error::SUCCESS.code_num);
+        assert_eq!(::api::utils::vcx_cancel(cancellation_handle), error::SUCCESS.code_num);
+
+        AgencyMockDecrypted::set_next_decrypted_response(GET_MESSAGES_DECRYPTED_RESPONSE);
+        AgencyMockDecrypted::set_next_decrypted_message(ARIES_CONNECTION_REQUEST);
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        let rc = vcx_connection_update_state_cancellable(cb.command_handle, handle, cancellation_handle, Some(cb.get_callback()));
+        assert_eq!(rc, error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap_err(), error::CANCELLED.code_num);
+        assert_eq!(get_state(handle), state);
+
+        assert_eq!(::api::utils::vcx_cancellation_token_release(cancellation_handle), error::SUCCESS.code_num);
+        AgencyMockDecrypted::clear_mocks();
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_vcx_connection_delete_connection() {
@@ -1473,4 +2456,46 @@ mod tests {
                                                    Some(cb.get_callback())), error::SUCCESS.code_num);
         cb.receive(TimeoutUtils::some_medium()).unwrap();
     }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_connection_ping_and_measure_fails_for_incomplete_connection() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = build_test_connection_inviter_requested();
+
+        let cb = return_types_u32::Return_U32::new().unwrap();
+        assert_eq!(vcx_connection_ping_and_measure(cb.command_handle, handle, Some(cb.get_callback())), error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap_err(), error::NOT_READY.code_num);
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_connection_answer_pings(cb.command_handle, handle, Some(cb.get_callback())), error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap_err(), error::NOT_READY.code_num);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_connection_verify_response_signature_fails_for_inviter() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = build_test_connection_inviter_requested();
+
+        let cb = return_types_u32::Return_U32::new().unwrap();
+        assert_eq!(vcx_connection_verify_response_signature(cb.command_handle, handle, Some(cb.get_callback())), error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap_err(), error::ACTION_NOT_SUPPORTED.code_num);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_connection_get_their_supported_protocols() {
+        let _setup = SetupAriesMocks::init();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_connection_get_their_supported_protocols(cb.command_handle, 0, Some(cb.get_callback())), error::INVALID_CONNECTION_HANDLE.code_num);
+
+        let handle = build_test_connection_inviter_requested();
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_connection_get_their_supported_protocols(cb.command_handle, handle, Some(cb.get_callback())), error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap_err(), error::NOT_READY.code_num);
+    }
 }
@@ -3,11 +3,15 @@ use std::ptr;
 use indy_sys::CommandHandle;
 use libc::c_char;
 
+use api::sync_result;
 use connection;
 use credential;
 use error::prelude::*;
+use utils::cancellation;
+use utils::credential_store::{self, CredentialMetadata, CredentialMetadataStore};
 use utils::cstring::CStringUtils;
 use utils::error;
+use utils::logger::CVoid;
 use utils::threadpool::spawn;
 
 /*
@@ -288,6 +292,67 @@ pub extern fn vcx_credential_create_with_msgid(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Rejects the credential offer, or stops waiting for the credential once the request is sent.
+/// The issuer is informed by problem report on the issuance thread and the credential moves
+/// into the finished state with failed credential status.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+///
+/// connection_handle: Connection handle that identifies pairwise connection
+///
+/// reason: human readable reason sent to the issuer in the problem report
+///
+/// cb: Callback that provides error status of abandoning the exchange
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_credential_abandon(command_handle: CommandHandle,
+                                     credential_handle: u32,
+                                     connection_handle: u32,
+                                     reason: *const c_char,
+                                     cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32)>) -> u32 {
+    info!("vcx_credential_abandon >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(reason, VcxErrorKind::InvalidOption);
+
+    if !credential::is_valid_handle(credential_handle) {
+        return VcxError::from(VcxErrorKind::InvalidCredentialHandle).into();
+    }
+
+    if !connection::is_valid_handle(connection_handle) {
+        return VcxError::from(VcxErrorKind::InvalidConnectionHandle).into();
+    }
+
+    let source_id = credential::get_source_id(credential_handle).unwrap_or_default();
+    trace!("vcx_credential_abandon(command_handle: {}, credential_handle: {}, connection_handle: {}, reason: {}) source_id: {}",
+           command_handle, credential_handle, connection_handle, reason, source_id);
+    spawn(move || {
+        let err = match credential::abandon(credential_handle, connection_handle, &reason) {
+            Ok(()) => {
+                trace!("vcx_credential_abandon_cb(command_handle: {}, credential_handle: {}, rc: {}) source_id: {}",
+                       command_handle, credential_handle, error::SUCCESS.message, source_id);
+                error::SUCCESS.code_num
+            }
+            Err(x) => {
+                warn!("vcx_credential_abandon_cb(command_handle: {}, credential_handle: {}, rc: {}) source_id: {}",
+                      command_handle, credential_handle, x, source_id);
+                x.into()
+            }
+        };
+
+        cb(command_handle, err);
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Approves the credential offer and submits a credential request. The result will be a credential stored in the prover's wallet.
 ///
 /// #params
@@ -557,6 +622,73 @@ pub extern fn vcx_v2_credential_update_state(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Same as `vcx_v2_credential_update_state`, the update can be cancelled by `vcx_cancel`.
+/// Cancellation is observed until a received message starts being handled, the update is completed after that.
+/// Cancelled update fails with Cancelled error and the credential is unchanged.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// credential_handle: Credential handle that was provided during creation.
+///
+/// connection_handle: Connection handle of the credential issuer
+///
+/// cancellation_handle: token created by `vcx_create_cancellation_token`
+///
+/// cb: Callback that provides most current state of the credential and error status of request
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_credential_update_state_cancellable(command_handle: CommandHandle,
+                                                      credential_handle: u32,
+                                                      connection_handle: u32,
+                                                      cancellation_handle: u32,
+                                                      cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, state: u32)>) -> u32 {
+    info!("vcx_credential_update_state_cancellable >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    if !credential::is_valid_handle(credential_handle) {
+        return VcxError::from(VcxErrorKind::InvalidCredentialHandle).into();
+    }
+
+    if !connection::is_valid_handle(connection_handle) {
+        return VcxError::from(VcxErrorKind::InvalidConnectionHandle).into();
+    }
+
+    let token = match cancellation::get_token(cancellation_handle) {
+        Ok(token) => token,
+        Err(err) => return err.into()
+    };
+
+    let source_id = credential::get_source_id(credential_handle).unwrap_or_default();
+    trace!("vcx_credential_update_state_cancellable(command_handle: {}, credential_handle: {}, connection_handle: {}, cancellation_handle: {}), source_id: {:?}",
+           command_handle, credential_handle, connection_handle, cancellation_handle, source_id);
+
+    spawn(move || {
+        let result = cancellation::with_token(&token, || credential::update_state(credential_handle, None, Some(connection_handle)))
+            .and_then(|_| credential::get_state(credential_handle));
+
+        match result {
+            Ok(s) => {
+                trace!("vcx_credential_update_state_cancellable_cb(command_handle: {}, rc: {}, state: {}), source_id: {:?}",
+                       command_handle, error::SUCCESS.message, s, source_id);
+                cb(command_handle, error::SUCCESS.code_num, s)
+            }
+            Err(e) => {
+                error!("vcx_credential_update_state_cancellable_cb(command_handle: {}, rc: {}, state: {}), source_id: {:?}",
+                       command_handle, e, 0, source_id);
+                cb(command_handle, e.into(), 0)
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Update the state of the credential based on the given message.
 ///
 /// #Params
@@ -716,6 +848,84 @@ pub extern fn vcx_credential_serialize(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Synchronous variant of `vcx_credential_update_state`, runs on the calling thread.
+///
+/// #Params
+/// credential_handle: was provided during creation. Used to identify credential object
+///
+/// state_p: Reference that will contain the most current state of the credential
+///
+/// #Returns
+/// Error code as a u32, details are available by `vcx_get_current_error`
+#[no_mangle]
+pub extern fn vcx_credential_update_state_sync(credential_handle: u32,
+                                               state_p: *mut u32) -> u32 {
+    info!("vcx_credential_update_state_sync >>>");
+
+    check_useful_out_ptr!(state_p, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_credential_update_state_sync(credential_handle: {})", credential_handle);
+
+    if !credential::is_valid_handle(credential_handle) {
+        return VcxError::from(VcxErrorKind::InvalidCredentialHandle).into();
+    }
+
+    let result = credential::update_state(credential_handle, None, None)
+        .and_then(|_| credential::get_state(credential_handle));
+    sync_result::set_u32(result, state_p)
+}
+
+/// Synchronous variant of `vcx_credential_get_state`.
+///
+/// #Params
+/// credential_handle: was provided during creation. Used to identify credential object
+///
+/// state_p: Reference that will contain the most current state of the credential
+///
+/// #Returns
+/// Error code as a u32, details are available by `vcx_get_current_error`
+#[no_mangle]
+pub extern fn vcx_credential_get_state_sync(credential_handle: u32,
+                                            state_p: *mut u32) -> u32 {
+    info!("vcx_credential_get_state_sync >>>");
+
+    check_useful_out_ptr!(state_p, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_credential_get_state_sync(credential_handle: {})", credential_handle);
+
+    if !credential::is_valid_handle(credential_handle) {
+        return VcxError::from(VcxErrorKind::InvalidCredentialHandle).into();
+    }
+
+    sync_result::set_u32(credential::get_state(credential_handle), state_p)
+}
+
+/// Synchronous variant of `vcx_credential_serialize`.
+///
+/// #Params
+/// credential_handle: was provided during creation. Used to identify credential object
+///
+/// serialized_data_p: Reference that will contain json string of the credential's attributes.
+///     The string is valid until the next `*_sync` call returning a string in the same thread.
+///
+/// #Returns
+/// Error code as a u32, details are available by `vcx_get_current_error`
+#[no_mangle]
+pub extern fn vcx_credential_serialize_sync(credential_handle: u32,
+                                            serialized_data_p: *mut *const c_char) -> u32 {
+    info!("vcx_credential_serialize_sync >>>");
+
+    check_useful_out_ptr!(serialized_data_p, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_credential_serialize_sync(credential_handle: {})", credential_handle);
+
+    if !credential::is_valid_handle(credential_handle) {
+        return VcxError::from(VcxErrorKind::InvalidCredentialHandle).into();
+    }
+
+    sync_result::set_c_str(credential::to_string(credential_handle), serialized_data_p)
+}
+
 /// Takes a json string representing an credential object and recreates an object matching the json
 ///
 /// #Params
@@ -761,6 +971,190 @@ pub extern fn vcx_credential_deserialize(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Makes credential send the credential request on state update with connection handle,
+/// so that the offer does not need to be accepted by separate call.
+///
+/// #Params
+/// handle: Credential handle that was provided during creation. Used to access credential object
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_credential_enable_auto_accept(handle: u32) -> u32 {
+    info!("vcx_credential_enable_auto_accept >>>");
+
+    let source_id = credential::get_source_id(handle).unwrap_or_default();
+    match credential::enable_auto_accept(handle) {
+        Ok(()) => {
+            trace!("vcx_credential_enable_auto_accept(handle: {}, rc: {}), source_id: {:?}",
+                   handle, error::SUCCESS.message, source_id);
+            error::SUCCESS.code_num
+        }
+        Err(e) => {
+            warn!("vcx_credential_enable_auto_accept(handle: {}, rc: {}), source_id: {:?}",
+                  handle, e, source_id);
+            e.into()
+        }
+    }
+}
+
+/// Retrieve attributes of the credential (from the offer preview until the credential is received).
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// credential_handle: credential handle that was provided during creation. Used to identify credential object
+///
+/// cb: Callback that provides error status of api call, or returns the attributes in json format:
+///     [{"name":"photo","value":"iVBORw0KGgo...","mime-type":"image/png"}]
+///     Values of non-textual mime types are base64 encoded.
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_credential_get_attributes(command_handle: CommandHandle,
+                                            credential_handle: u32,
+                                            cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, attributes: *const c_char)>) -> u32 {
+    info!("vcx_credential_get_attributes >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    if !credential::is_valid_handle(credential_handle) {
+        return VcxError::from(VcxErrorKind::InvalidCredentialHandle).into();
+    }
+
+    let source_id = credential::get_source_id(credential_handle).unwrap_or_default();
+    trace!("vcx_credential_get_attributes(command_handle: {}, credential_handle: {}) source_id: {})",
+           command_handle, credential_handle, source_id);
+
+    spawn(move || {
+        match credential::get_attributes(credential_handle) {
+            Ok(attributes) => {
+                let attributes = json!(attributes).to_string();
+                trace!("vcx_credential_get_attributes_cb(commmand_handle: {}, rc: {}, attributes: {}) source_id: {}",
+                       command_handle, error::SUCCESS.code_num, secret!(&attributes), source_id);
+                let attributes = CStringUtils::string_to_cstring(attributes);
+                cb(command_handle, error::SUCCESS.code_num, attributes.as_ptr());
+            }
+            Err(e) => {
+                error!("vcx_credential_get_attributes_cb(commmand_handle: {}, rc: {}, attributes: {}) source_id: {}",
+                       command_handle, e, "".to_string(), source_id);
+                cb(command_handle, e.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Retrieve attributes previewed in the credential offer with labels in the requested locale,
+/// taken from `~l10n` decorator of the offer. Attributes without such label are labeled by their name.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// credential_handle: credential handle that was provided during creation. Used to identify credential object
+///
+/// locale: requested locale, e.g. "es" or "es-MX"
+///
+/// cb: Callback that provides error status of api call, or returns the attributes in json format:
+///     [{"name":"age","label":"Edad","value":"25","mime-type":"text/plain"}]
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_credential_get_offer_preview(command_handle: CommandHandle,
+                                               credential_handle: u32,
+                                               locale: *const c_char,
+                                               cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, preview: *const c_char)>) -> u32 {
+    info!("vcx_credential_get_offer_preview >>>");
+
+    check_useful_c_str!(locale, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    if !credential::is_valid_handle(credential_handle) {
+        return VcxError::from(VcxErrorKind::InvalidCredentialHandle).into();
+    }
+
+    let source_id = credential::get_source_id(credential_handle).unwrap_or_default();
+    trace!("vcx_credential_get_offer_preview(command_handle: {}, credential_handle: {}, locale: {}) source_id: {})",
+           command_handle, credential_handle, locale, source_id);
+
+    spawn(move || {
+        match credential::get_offer_preview(credential_handle, &locale) {
+            Ok(preview) => {
+                let preview = json!(preview).to_string();
+                trace!("vcx_credential_get_offer_preview_cb(commmand_handle: {}, rc: {}, preview: {}) source_id: {}",
+                       command_handle, error::SUCCESS.code_num, secret!(&preview), source_id);
+                let preview = CStringUtils::string_to_cstring(preview);
+                cb(command_handle, error::SUCCESS.code_num, preview.as_ptr());
+            }
+            Err(e) => {
+                error!("vcx_credential_get_offer_preview_cb(commmand_handle: {}, rc: {}, preview: {}) source_id: {}",
+                       command_handle, e, "".to_string(), source_id);
+                cb(command_handle, e.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Retrieve details of the credential offer to be shown to the holder before accepting it:
+/// issuer, credential definition and schema of the offered credential, previewed attributes
+/// and whether the credential will be revocable.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// credential_handle: credential handle that was provided during creation. Used to identify credential object
+///
+/// cb: Callback that provides error status of api call, or returns the details in json format:
+///     {"issuer_did":"V4SGRU86Z58d6TV7PBUe6f","cred_def_id":"V4SGRU86Z58d6TV7PBUe6f:3:CL:31:tag1",
+///      "schema_id":"V4SGRU86Z58d6TV7PBUe6f:2:FaberVcx:83.23.62","schema_name":"FaberVcx","schema_version":"83.23.62",
+///      "attributes":[{"name":"age","value":"25","mime-type":"text/plain"}],"revocable":false}
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_credential_get_offer_details(command_handle: CommandHandle,
+                                               credential_handle: u32,
+                                               cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, details: *const c_char)>) -> u32 {
+    info!("vcx_credential_get_offer_details >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    if !credential::is_valid_handle(credential_handle) {
+        return VcxError::from(VcxErrorKind::InvalidCredentialHandle).into();
+    }
+
+    let source_id = credential::get_source_id(credential_handle).unwrap_or_default();
+    trace!("vcx_credential_get_offer_details(command_handle: {}, credential_handle: {}) source_id: {})",
+           command_handle, credential_handle, source_id);
+
+    spawn(move || {
+        match credential::get_offer_details(credential_handle) {
+            Ok(details) => {
+                let details = json!(details).to_string();
+                trace!("vcx_credential_get_offer_details_cb(commmand_handle: {}, rc: {}, details: {}) source_id: {}",
+                       command_handle, error::SUCCESS.code_num, secret!(&details), source_id);
+                let details = CStringUtils::string_to_cstring(details);
+                cb(command_handle, error::SUCCESS.code_num, details.as_ptr());
+            }
+            Err(e) => {
+                error!("vcx_credential_get_offer_details_cb(commmand_handle: {}, rc: {}, details: {}) source_id: {}",
+                       command_handle, e, "".to_string(), source_id);
+                cb(command_handle, e.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Releases the credential object by de-allocating memory
 ///
 /// #Params
@@ -808,6 +1202,187 @@ pub extern fn vcx_credential_get_payment_txn(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Get messages sent and received within the protocol exchange of the object.
+/// Messages are recorded only when `protocol_history` option of the library config is "true".
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+///
+/// cb: Callback that provides JSON array of exchanged messages
+///
+/// # Example history -> "[{"direction":"received","timestamp":1599834693,"message":{...}},{"direction":"sent","timestamp":1599834694,"message":{...}}]"
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_credential_get_protocol_history(command_handle: CommandHandle,
+                                                  credential_handle: u32,
+                                                  cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, history: *const c_char)>) -> u32 {
+    info!("vcx_credential_get_protocol_history >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = credential::get_source_id(credential_handle).unwrap_or_default();
+    trace!("vcx_credential_get_protocol_history(command_handle: {}, credential_handle: {}) source_id: {}",
+           command_handle, credential_handle, source_id);
+
+    if !credential::is_valid_handle(credential_handle) {
+        return VcxError::from(VcxErrorKind::InvalidCredentialHandle).into();
+    }
+
+    spawn(move || {
+        match credential::get_protocol_history(credential_handle) {
+            Ok(history) => {
+                trace!("vcx_credential_get_protocol_history_cb(command_handle: {}, rc: {}, credential_handle: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, credential_handle, source_id);
+                let history = CStringUtils::string_to_cstring(history);
+                cb(command_handle, error::SUCCESS.code_num, history.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_credential_get_protocol_history_cb(command_handle: {}, rc: {}, credential_handle: {}) source_id: {}",
+                      command_handle, x, credential_handle, source_id);
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Get the time the received credential offer of the object expires at.
+/// The expiration is set by the `~timing` decorator of the message.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+///
+/// cb: Callback that provides the expiration time in RFC 3339 format, null pointer if it does not expire
+///
+/// # Example expires_time -> "2020-09-11T14:31:33Z"
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_credential_get_expiry(command_handle: CommandHandle,
+                                        credential_handle: u32,
+                                        cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, expires_time: *const c_char)>) -> u32 {
+    info!("vcx_credential_get_expiry >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = credential::get_source_id(credential_handle).unwrap_or_default();
+    trace!("vcx_credential_get_expiry(command_handle: {}, credential_handle: {}) source_id: {}",
+           command_handle, credential_handle, source_id);
+
+    if !credential::is_valid_handle(credential_handle) {
+        return VcxError::from(VcxErrorKind::InvalidCredentialHandle).into();
+    }
+
+    spawn(move || {
+        match credential::get_expiry(credential_handle) {
+            Ok(expires_time) => {
+                trace!("vcx_credential_get_expiry_cb(command_handle: {}, rc: {}, credential_handle: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, credential_handle, source_id);
+                match expires_time {
+                    Some(expires_time) => {
+                        let expires_time = CStringUtils::string_to_cstring(expires_time);
+                        cb(command_handle, error::SUCCESS.code_num, expires_time.as_ptr());
+                    }
+                    None => cb(command_handle, error::SUCCESS.code_num, ptr::null_mut()),
+                }
+            }
+            Err(x) => {
+                warn!("vcx_credential_get_expiry_cb(command_handle: {}, rc: {}, credential_handle: {}) source_id: {}",
+                      command_handle, x, credential_handle, source_id);
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+// store implemented by the application, `context` is only handed back to its callbacks
+struct CallbackCredentialMetadataStore {
+    context: usize,
+    store: extern fn(context: *const CVoid, metadata: *const c_char) -> u32,
+    remove: extern fn(context: *const CVoid, thread_id: *const c_char) -> u32,
+}
+
+impl CallbackCredentialMetadataStore {
+    fn check(rc: u32, action: &str) -> VcxResult<()> {
+        match rc {
+            0 => Ok(()),
+            rc => Err(VcxError::from_msg(VcxErrorKind::UnknownError, format!("Application store failed to {} credential metadata, rc: {}", action, rc)))
+        }
+    }
+}
+
+impl CredentialMetadataStore for CallbackCredentialMetadataStore {
+    fn store(&self, metadata: &CredentialMetadata) -> VcxResult<()> {
+        let metadata = CStringUtils::string_to_cstring(json!(metadata).to_string());
+        Self::check((self.store)(self.context as *const CVoid, metadata.as_ptr()), "store")
+    }
+
+    fn remove(&self, thread_id: &str) -> VcxResult<()> {
+        let thread_id = CStringUtils::string_to_cstring(thread_id.to_string());
+        Self::check((self.remove)(self.context as *const CVoid, thread_id.as_ptr()), "remove")
+    }
+}
+
+/// Set store the non-secret metadata of held credentials are mirrored to on every state change of a credential object,
+/// so wallet UIs can list credentials without opening the wallet. Mirroring is best effort, failures of the store
+/// are only logged. Previously set store is replaced.
+///
+/// #Params
+/// context: pointer to some context that will be available in the callbacks.
+///
+/// store: called with the credential metadata json, replaces metadata stored for the same thread_id. Returns 0 on success.
+///
+/// # Example metadata -> {"source_id":"1","thread_id":"...","state":4,"cred_id":"...","cred_def_id":"...","issuer_did":"...","attributes":[{"name":"age","value":"25"}],"issued_at":1599834693,"updated_at":1599834693}
+///
+/// remove: called with the thread_id of the credential which was deleted. Returns 0 on success.
+///
+/// #Returns
+/// Error code as u32
+#[no_mangle]
+pub extern fn vcx_set_credential_metadata_store(context: *const CVoid,
+                                                store: Option<extern fn(context: *const CVoid, metadata: *const c_char) -> u32>,
+                                                remove: Option<extern fn(context: *const CVoid, thread_id: *const c_char) -> u32>) -> u32 {
+    info!("vcx_set_credential_metadata_store >>>");
+
+    check_useful_c_callback!(store, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(remove, VcxErrorKind::InvalidOption);
+
+    credential_store::set_credential_metadata_store(Some(Box::new(CallbackCredentialMetadataStore {
+        context: context as usize,
+        store,
+        remove,
+    })));
+
+    error::SUCCESS.code_num
+}
+
+/// Remove store set by `vcx_set_credential_metadata_store`.
+///
+/// #Returns
+/// Error code as u32
+#[no_mangle]
+pub extern fn vcx_unset_credential_metadata_store() -> u32 {
+    info!("vcx_unset_credential_metadata_store >>>");
+
+    credential_store::set_credential_metadata_store(None);
+
+    error::SUCCESS.code_num
+}
+
 #[cfg(test)]
 mod tests {
     extern crate serde_json;
@@ -968,6 +1543,30 @@ mod tests {
         assert_eq!(credential::get_state(handle_cred).unwrap(), VcxStateType::VcxStateAccepted as u32);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_credential_update_state_sync() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle_conn = ::connection::tests::build_test_connection_inviter_requested();
+        let handle_cred = _vcx_credential_create_with_offer_c_closure(ARIES_CREDENTIAL_OFFER).unwrap();
+
+        let cb = return_types_u32::Return_U32::new().unwrap();
+        assert_eq!(vcx_credential_send_request(cb.command_handle, handle_cred, handle_conn, 0, Some(cb.get_callback())), error::SUCCESS.code_num);
+        cb.receive(TimeoutUtils::some_medium()).unwrap();
+
+        AgencyMockDecrypted::set_next_decrypted_response(GET_MESSAGES_DECRYPTED_RESPONSE);
+        AgencyMockDecrypted::set_next_decrypted_message(ARIES_CREDENTIAL_RESPONSE);
+
+        // nothing is done when there is nowhere to write the result
+        assert_eq!(vcx_credential_update_state_sync(handle_cred, ptr::null_mut()), error::INVALID_OPTION.code_num);
+        assert_eq!(credential::get_state(handle_cred).unwrap(), VcxStateType::VcxStateOfferSent as u32);
+
+        let mut state: u32 = 0;
+        assert_eq!(vcx_credential_update_state_sync(handle_cred, &mut state), error::SUCCESS.code_num);
+        assert_eq!(state, VcxStateType::VcxStateAccepted as u32);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     #[cfg(feature = "to_restore")] // Missing implementation for v3 in generate_credential_request_msg
@@ -1016,4 +1615,65 @@ mod tests {
 
         assert_eq!(vcx_credential_release(handle), error::INVALID_CREDENTIAL_HANDLE.code_num);
     }
+
+    lazy_static! {
+        static ref MIRRORED_METADATA: ::std::sync::Mutex<Vec<String>> = Default::default();
+    }
+
+    extern fn _store_metadata(_context: *const CVoid, metadata: *const c_char) -> u32 {
+        let metadata = CStringUtils::c_str_to_string(metadata).unwrap().unwrap();
+        MIRRORED_METADATA.lock().unwrap().push(metadata);
+        0
+    }
+
+    extern fn _remove_metadata(_context: *const CVoid, _thread_id: *const c_char) -> u32 {
+        0
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_set_credential_metadata_store() {
+        let _setup = SetupAriesMocks::init();
+
+        assert_eq!(vcx_set_credential_metadata_store(ptr::null(), None, Some(_remove_metadata)), error::INVALID_OPTION.code_num);
+        assert_eq!(vcx_set_credential_metadata_store(ptr::null(), Some(_store_metadata), Some(_remove_metadata)), error::SUCCESS.code_num);
+
+        let handle = _vcx_credential_create_with_offer_c_closure(ARIES_CREDENTIAL_OFFER).unwrap();
+        let thread_id = credential::get_thread_id(handle).unwrap();
+
+        assert_eq!(vcx_unset_credential_metadata_store(), error::SUCCESS.code_num);
+
+        let mirrored = MIRRORED_METADATA.lock().unwrap();
+        assert!(mirrored.iter()
+            .map(|metadata| serde_json::from_str::<Value>(metadata).unwrap())
+            .any(|metadata| metadata["thread_id"] == json!(thread_id)));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_credential_enable_auto_accept() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = _vcx_credential_create_with_offer_c_closure(ARIES_CREDENTIAL_OFFER).unwrap();
+
+        assert_eq!(vcx_credential_enable_auto_accept(handle), error::SUCCESS.code_num);
+        assert_eq!(vcx_credential_enable_auto_accept(handle + 1), error::INVALID_CREDENTIAL_HANDLE.code_num);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_credential_get_attributes() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = _vcx_credential_create_with_offer_c_closure(ARIES_CREDENTIAL_OFFER).unwrap();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_credential_get_attributes(cb.command_handle, handle, Some(cb.get_callback())), error::SUCCESS.code_num);
+        let attributes = cb.receive(TimeoutUtils::some_medium()).unwrap().unwrap();
+        let attributes: ::serde_json::Value = ::serde_json::from_str(&attributes).unwrap();
+        assert!(!attributes.as_array().unwrap().is_empty());
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_credential_get_attributes(cb.command_handle, handle + 1, Some(cb.get_callback())), error::INVALID_CREDENTIAL_HANDLE.code_num);
+    }
 }
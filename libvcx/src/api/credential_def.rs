@@ -102,6 +102,91 @@ pub extern fn vcx_credentialdef_create(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Run `vcx_credentialdef_create` as a dry run: the credential definition transactions are built, signed and priced,
+/// but they are not submitted to the ledger and no CredentialDef object is created.
+/// Keys and tails are generated in a scratch wallet deleted afterwards, so the tails location is left untouched.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// source_id: Enterprise's personal identification for the user.
+///
+/// schema_id: The schema id given during the creation of the schema
+///
+/// issuer_did: did corresponding to entity issuing a credential. Needs to have Trust Anchor permissions on ledger
+///
+/// tag: way to create a unique credential def with the same schema and issuer did.
+///
+/// revocation details: type-specific configuration of credential definition revocation, same as in `vcx_credentialdef_create`
+///
+/// payment_handle: future use (currently uses any address in wallet)
+///
+/// cb: Callback that provides the prepared transactions and error status of request.
+///
+/// # Example dry_run -> {"cred_def_id":"...","rev_reg_id":null,"cred_def_txn":{"request":{...},"price":1,"payment":{...}},"rev_reg_def_txn":null,"rev_reg_delta_txn":null,"total_price":1}
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_credentialdef_dry_run(command_handle: CommandHandle,
+                                        source_id: *const c_char,
+                                        schema_id: *const c_char,
+                                        issuer_did: *const c_char,
+                                        tag: *const c_char,
+                                        revocation_details: *const c_char,
+                                        _payment_handle: u32,
+                                        cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, dry_run: *const c_char)>) -> u32 {
+    info!("vcx_credentialdef_dry_run >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(source_id, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(schema_id, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(tag, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(revocation_details, VcxErrorKind::InvalidOption);
+
+    let issuer_did: String = if !issuer_did.is_null() {
+        check_useful_c_str!(issuer_did, VcxErrorKind::InvalidOption);
+        issuer_did.to_owned()
+    } else {
+        match settings::get_config_value(settings::CONFIG_INSTITUTION_DID) {
+            Ok(x) => x,
+            Err(x) => return x.into(),
+        }
+    };
+
+    trace!("vcx_credentialdef_dry_run(command_handle: {}, source_id: {}, schema_id: {}, issuer_did: {}, tag: {}, revocation_details: {:?})",
+           command_handle,
+           source_id,
+           schema_id,
+           issuer_did,
+           tag,
+           revocation_details);
+
+    spawn(move || {
+        match credential_def::dry_run_credentialdef(source_id.clone(),
+                                                    issuer_did,
+                                                    schema_id,
+                                                    tag,
+                                                    revocation_details) {
+            Ok(x) => {
+                trace!("vcx_credentialdef_dry_run_cb(command_handle: {}, rc: {}, dry_run: {}), source_id: {:?}",
+                       command_handle, error::SUCCESS.message, x, source_id);
+                let x = CStringUtils::string_to_cstring(x);
+                cb(command_handle, error::SUCCESS.code_num, x.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_credentialdef_dry_run_cb(command_handle: {}, rc: {}, dry_run: {}), source_id: {:?}",
+                      command_handle, x, "null", source_id);
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Create a new CredentialDef object that will be published by Endorser later.
 ///
 /// Note that CredentialDef can't be used for credential issuing until it will be published on the ledger.
@@ -210,6 +295,67 @@ pub extern fn vcx_credentialdef_prepare_for_endorser(command_handle: CommandHand
     error::SUCCESS.code_num
 }
 
+/// Prepares transactions of the CredentialDef object which was created by `vcx_credentialdef_prepare_for_endorser`
+/// but is not published yet, e.g. when the transactions have to be passed to another Endorser.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// credentialdef_handle: Credentialdef handle that was provided during creation. Used to access credentialdef object
+///
+/// endorser: DID of the Endorser that will submit the transaction.
+///
+/// cb: Callback that provides transactions (CredentialDef, Option<RevocRegDef>, Option<RevocRegEntry>) that should be passed to Endorser for publishing.
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_credentialdef_prepare_existing_for_endorser(command_handle: CommandHandle,
+                                                              credentialdef_handle: u32,
+                                                              endorser: *const c_char,
+                                                              cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32,
+                                                                                   credentialdef_transaction: *const c_char,
+                                                                                   rev_reg_def_transaction: *const c_char,
+                                                                                   rev_reg_entry_transaction: *const c_char)>) -> u32 {
+    info!("vcx_credentialdef_prepare_existing_for_endorser >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(endorser, VcxErrorKind::InvalidOption);
+
+    let source_id = credential_def::get_source_id(credentialdef_handle).unwrap_or_default();
+    trace!("vcx_credentialdef_prepare_existing_for_endorser(command_handle: {}, credentialdef_handle: {}, endorser: {}) source_id: {}",
+           command_handle, credentialdef_handle, endorser, source_id);
+
+    if !credential_def::is_valid_handle(credentialdef_handle) {
+        return VcxError::from(VcxErrorKind::InvalidCredDefHandle).into();
+    };
+
+    spawn(move || {
+        match credential_def::prepare_for_endorser(credentialdef_handle, &endorser) {
+            Ok((cred_def_req, rev_reg_def_req, rev_reg_entry_req)) => {
+                trace!("vcx_credentialdef_prepare_existing_for_endorser(command_handle: {}, rc: {}, cred_def_req: {}, rev_reg_def_req: {:?}, rev_reg_entry_req: {:?}) source_id: {}",
+                       command_handle, error::SUCCESS.message, cred_def_req, rev_reg_def_req, rev_reg_entry_req, source_id);
+                let cred_def_req = CStringUtils::string_to_cstring(cred_def_req);
+                let rev_reg_def_req = rev_reg_def_req.map(CStringUtils::string_to_cstring);
+                let rev_reg_entry_req = rev_reg_entry_req.map(CStringUtils::string_to_cstring);
+
+                cb(command_handle, error::SUCCESS.code_num, cred_def_req.as_ptr(),
+                   rev_reg_def_req.as_ref().map(|def| def.as_ptr()).unwrap_or(ptr::null()),
+                   rev_reg_entry_req.as_ref().map(|entry| entry.as_ptr()).unwrap_or(ptr::null()));
+            }
+            Err(x) => {
+                warn!("vcx_credentialdef_prepare_existing_for_endorser(command_handle: {}, rc: {}) source_id: {}",
+                      command_handle, x, source_id);
+                cb(command_handle, x.into(), ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Takes the credentialdef object and returns a json string of all its attributes
 ///
 /// #Params
@@ -345,6 +491,45 @@ pub extern fn vcx_credentialdef_get_cred_def_id(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Lists credential definitions created or prepared for an endorser by this issuer, as recorded in the wallet.
+///
+/// #Params
+/// cb: Callback that provides json array of the credential definitions and error status
+///
+/// # Example
+/// cred_defs -> [{"cred_def_id":"2hoqvcwupRTUNkXn6ArYzs:3:CL:1697:tag","schema_id":"2hoqvcwupRTUNkXn6ArYzs:2:test-licence:4.4.4","issuer_did":"2hoqvcwupRTUNkXn6ArYzs","name":"Test Credential Definition","tag":"tag","source_id":"Test Source ID","rev_reg_id":null,"state":1}]
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_credentialdef_list_created(command_handle: CommandHandle,
+                                             cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, cred_defs: *const c_char)>) -> u32 {
+    info!("vcx_credentialdef_list_created >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    trace!("vcx_credentialdef_list_created(command_handle: {})", command_handle);
+
+    spawn(move || {
+        match credential_def::list_created_credential_defs() {
+            Ok(cred_defs) => {
+                trace!("vcx_credentialdef_list_created(command_handle: {}, rc: {}, cred_defs: {})",
+                       command_handle, error::SUCCESS.message, cred_defs);
+                let msg = CStringUtils::string_to_cstring(cred_defs);
+                cb(command_handle, error::SUCCESS.code_num, msg.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_credentialdef_list_created(command_handle: {}, rc: {}, cred_defs: {})",
+                      command_handle, x, "");
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Get the payment transaction information generated when paying the ledger fee
 ///
 /// #param
@@ -568,6 +753,108 @@ pub extern fn vcx_credentialdef_rotate_rev_reg_def(command_handle: CommandHandle
     error::SUCCESS.code_num
 }
 
+/// Changes revocation parameters of a revocable credential definition.
+///
+/// #Params
+/// credentialdef_handle: CredDef handle that was provided during creation.
+///
+/// revocation_details: json with optional `tails_file` and `max_creds`.
+///     New `max_creds` is used by the revocation registries created by following rotations.
+///     New `tails_file` location publishes a new revocation registry right away.
+///     Example: {"tails_file": "/tmp/new_tails", "max_creds": 1000}
+///
+/// cb: Callback that provides the serialized credential definition and error status
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_credentialdef_update_revocation_details(command_handle: CommandHandle,
+                                                          credentialdef_handle: u32,
+                                                          revocation_details: *const c_char,
+                                                          cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, credentialdef_state: *const c_char)>) -> u32 {
+    info!("vcx_credentialdef_update_revocation_details >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(revocation_details, VcxErrorKind::InvalidOption);
+
+    let source_id = credential_def::get_source_id(credentialdef_handle).unwrap_or_default();
+    trace!("vcx_credentialdef_update_revocation_details(command_handle: {}, credentialdef_handle: {}, revocation_details: {}) source_id: {}",
+           command_handle, credentialdef_handle, revocation_details, source_id);
+
+    if !credential_def::is_valid_handle(credentialdef_handle) {
+        return VcxError::from(VcxErrorKind::InvalidCredDefHandle).into();
+    }
+
+    spawn(move || {
+        match credential_def::update_revocation_details(credentialdef_handle, &revocation_details) {
+            Ok(x) => {
+                trace!("vcx_credentialdef_update_revocation_details(command_handle: {}, credentialdef_handle: {}, rc: {}, state: {}), source_id: {:?}",
+                       command_handle, credentialdef_handle, error::SUCCESS.message, x, source_id);
+                let msg = CStringUtils::string_to_cstring(x);
+                cb(command_handle, error::SUCCESS.code_num, msg.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_credentialdef_update_revocation_details(command_handle: {}, credentialdef_handle: {}, rc: {}, state: {}), source_id: {:?}",
+                      command_handle, credentialdef_handle, x, "null", source_id);
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Retrieves usage of the active revocation registry of the credential definition.
+///
+/// #Params
+/// credentialdef_handle: CredDef handle that was provided during creation.
+///
+/// cb: Callback that provides the usage json and error status
+///
+/// # Example
+/// usage -> {"rev_reg_id":"2hoqvcwupRTUNkXn6ArYzs:4:2hoqvcwupRTUNkXn6ArYzs:3:CL:1697:tag:CL_ACCUM:tag1","max_creds":100,"issued":42,"remaining":58,"next_max_creds":100}
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_credentialdef_get_rev_reg_usage(command_handle: CommandHandle,
+                                                  credentialdef_handle: u32,
+                                                  cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, usage: *const c_char)>) -> u32 {
+    info!("vcx_credentialdef_get_rev_reg_usage >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = credential_def::get_source_id(credentialdef_handle).unwrap_or_default();
+    trace!("vcx_credentialdef_get_rev_reg_usage(command_handle: {}, credentialdef_handle: {}) source_id: {}",
+           command_handle, credentialdef_handle, source_id);
+
+    if !credential_def::is_valid_handle(credentialdef_handle) {
+        return VcxError::from(VcxErrorKind::InvalidCredDefHandle).into();
+    }
+
+    spawn(move || {
+        match credential_def::get_rev_reg_usage(credentialdef_handle) {
+            Ok(x) => {
+                trace!("vcx_credentialdef_get_rev_reg_usage(command_handle: {}, credentialdef_handle: {}, rc: {}, usage: {}), source_id: {:?}",
+                       command_handle, credentialdef_handle, error::SUCCESS.message, x, source_id);
+                let msg = CStringUtils::string_to_cstring(x);
+                cb(command_handle, error::SUCCESS.code_num, msg.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_credentialdef_get_rev_reg_usage(command_handle: {}, credentialdef_handle: {}, rc: {}, usage: {}), source_id: {:?}",
+                      command_handle, credentialdef_handle, x, "null", source_id);
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 #[no_mangle]
 pub extern fn vcx_credentialdef_publish_revocations(command_handle: CommandHandle,
                                                     credentialdef_handle: u32,
@@ -605,6 +892,58 @@ pub extern fn vcx_credentialdef_publish_revocations(command_handle: CommandHandl
     error::SUCCESS.code_num
 }
 
+/// Run `vcx_credentialdef_publish_revocations` as a dry run: the transaction publishing the locally stored revocations
+/// is prepared without being submitted, and the local revocations are kept for the following publishing.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// credentialdef_handle: CredDef handle that was provided during creation.
+///
+/// cb: Callback that provides the prepared transaction and error status of request.
+///
+/// # Example dry_run -> {"request":{...},"price":1,"payment":{...}}
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_credentialdef_dry_run_publish_revocations(command_handle: CommandHandle,
+                                                            credentialdef_handle: u32,
+                                                            cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, dry_run: *const c_char)>) -> u32 {
+    info!("vcx_credentialdef_dry_run_publish_revocations >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = credential_def::get_source_id(credentialdef_handle).unwrap_or_default();
+
+    trace!("vcx_credentialdef_dry_run_publish_revocations(command_handle: {}, credentialdef_handle: {}) source_id: {}",
+           command_handle, credentialdef_handle, source_id);
+
+    if !credential_def::is_valid_handle(credentialdef_handle) {
+        return VcxError::from(VcxErrorKind::InvalidCredDefHandle).into();
+    }
+
+    spawn(move || {
+        match credential_def::dry_run_publish_revocations(credentialdef_handle) {
+            Ok(x) => {
+                trace!("vcx_credentialdef_dry_run_publish_revocations(command_handle: {}, credentialdef_handle: {}, rc: {}, dry_run: {})",
+                       command_handle, credentialdef_handle, error::SUCCESS.message, x);
+                let x = CStringUtils::string_to_cstring(x);
+                cb(command_handle, error::SUCCESS.code_num, x.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_credentialdef_dry_run_publish_revocations(command_handle: {}, credentialdef_handle: {}, rc: {}, dry_run: {})",
+                      command_handle, credentialdef_handle, x, "null");
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 #[cfg(test)]
 mod tests {
     extern crate serde_json;
@@ -656,6 +995,26 @@ mod tests {
         assert!(cb.receive(TimeoutUtils::some_medium()).is_err());
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_credentialdef_dry_run() {
+        let _setup = SetupAriesMocks::init();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_credentialdef_dry_run(cb.command_handle,
+                                             CString::new("Test Source ID").unwrap().into_raw(),
+                                             CString::new(SCHEMA_ID).unwrap().into_raw(),
+                                             CString::new("6vkhW3L28AophhA68SSzRS").unwrap().into_raw(),
+                                             CString::new("tag").unwrap().into_raw(),
+                                             CString::new("{}").unwrap().into_raw(),
+                                             0,
+                                             Some(cb.get_callback())), error::SUCCESS.code_num);
+        let dry_run = cb.receive(TimeoutUtils::some_medium()).unwrap().unwrap();
+        let dry_run: serde_json::Value = serde_json::from_str(&dry_run).unwrap();
+        assert!(dry_run["cred_def_txn"]["request"].is_object());
+        assert_eq!(dry_run["rev_reg_id"], serde_json::Value::Null);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_vcx_credentialdef_serialize() {
@@ -3,9 +3,11 @@ use std::ptr;
 use indy_sys::CommandHandle;
 use libc::c_char;
 
+use api::sync_result;
 use connection;
 use disclosed_proof;
 use error::prelude::*;
+use utils::cancellation;
 use utils::cstring::CStringUtils;
 use utils::error;
 use utils::threadpool::spawn;
@@ -103,6 +105,56 @@ pub extern fn vcx_disclosed_proof_create_with_request(command_handle: CommandHan
 }
 
 
+/// Create a Proof object answering proof request received attached to out-of-band invitation.
+/// The proof is sent to the service of the invitation without establishing a connection,
+/// so any `connection_handle` passed to `vcx_disclosed_proof_send_proof` is ignored.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// source_id: Institution's identification for the proof, should be unique.
+///
+/// oob_invitation: out-of-band invitation with the proof request attached, as returned by `vcx_proof_get_oob_request`
+///
+/// cb: Callback that provides proof handle or error status
+///
+/// #Returns
+/// Error code as u32
+#[no_mangle]
+pub extern fn vcx_disclosed_proof_create_from_oob(command_handle: CommandHandle,
+                                                  source_id: *const c_char,
+                                                  oob_invitation: *const c_char,
+                                                  cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, handle: u32)>) -> u32 {
+    info!("vcx_disclosed_proof_create_from_oob >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(source_id, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(oob_invitation, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_disclosed_proof_create_from_oob(command_handle: {}, source_id: {}, oob_invitation: {})",
+           command_handle, source_id, oob_invitation);
+
+    spawn(move || {
+        match disclosed_proof::create_proof_from_oob(&source_id, &oob_invitation) {
+            Ok(x) => {
+                trace!("vcx_disclosed_proof_create_from_oob_cb(command_handle: {}, rc: {}, handle: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, x, source_id);
+                cb(command_handle, error::SUCCESS.code_num, x);
+            }
+            Err(x) => {
+                error!("vcx_disclosed_proof_create_from_oob_cb(command_handle: {}, rc: {}, handle: {}) source_id: {}",
+                       command_handle, x, 0, source_id);
+                cb(command_handle, x.into(), 0);
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+
 /// Create a proof based off of a known message id for a given connection.
 ///
 /// #Params
@@ -539,6 +591,70 @@ pub extern fn vcx_v2_disclosed_proof_update_state(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Same as `vcx_v2_disclosed_proof_update_state`, the update can be cancelled by `vcx_cancel`.
+/// Cancellation is observed until a received message starts being handled, the update is completed after that.
+/// Cancelled update fails with Cancelled error and the disclosed proof is unchanged.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// proof_handle: Proof handle that was provided during creation. Used to identify the disclosed proof object
+///
+/// connection_handle: Connection handle of the verifier
+///
+/// cancellation_handle: token created by `vcx_create_cancellation_token`
+///
+/// cb: Callback that provides most current state of the disclosed proof and error status of request
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_disclosed_proof_update_state_cancellable(command_handle: CommandHandle,
+                                                           proof_handle: u32,
+                                                           connection_handle: u32,
+                                                           cancellation_handle: u32,
+                                                           cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, state: u32)>) -> u32 {
+    info!("vcx_disclosed_proof_update_state_cancellable >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    if !disclosed_proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidDisclosedProofHandle).into();
+    }
+
+    if !connection::is_valid_handle(connection_handle) {
+        return VcxError::from(VcxErrorKind::InvalidConnectionHandle).into();
+    }
+
+    let token = match cancellation::get_token(cancellation_handle) {
+        Ok(token) => token,
+        Err(err) => return err.into()
+    };
+
+    let source_id = disclosed_proof::get_source_id(proof_handle).unwrap_or_default();
+    trace!("vcx_disclosed_proof_update_state_cancellable(command_handle: {} proof_handle: {}, connection_handle: {}, cancellation_handle: {}) source_id: {}",
+           command_handle, proof_handle, connection_handle, cancellation_handle, source_id);
+
+    spawn(move || {
+        match cancellation::with_token(&token, || disclosed_proof::update_state(proof_handle, None, Some(connection_handle))) {
+            Ok(s) => {
+                trace!("vcx_disclosed_proof_update_state_cancellable_cb(command_handle: {}, rc: {}, state: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, s, source_id);
+                cb(command_handle, error::SUCCESS.code_num, s)
+            }
+            Err(e) => {
+                error!("vcx_disclosed_proof_update_state_cancellable_cb(command_handle: {}, rc: {}, state: {}) source_id: {}",
+                       command_handle, e, 0, source_id);
+                cb(command_handle, e.into(), 0)
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Checks for any state change from the given message and updates the state attribute
 ///
 /// #Params
@@ -638,6 +754,82 @@ pub extern fn vcx_disclosed_proof_serialize(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Synchronous variant of `vcx_disclosed_proof_update_state`, runs on the calling thread.
+///
+/// #Params
+/// proof_handle: was provided during creation. Used to identify disclosed proof object
+///
+/// state_p: Reference that will contain the most current state of the disclosed proof
+///
+/// #Returns
+/// Error code as a u32, details are available by `vcx_get_current_error`
+#[no_mangle]
+pub extern fn vcx_disclosed_proof_update_state_sync(proof_handle: u32,
+                                                    state_p: *mut u32) -> u32 {
+    info!("vcx_disclosed_proof_update_state_sync >>>");
+
+    check_useful_out_ptr!(state_p, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_disclosed_proof_update_state_sync(proof_handle: {})", proof_handle);
+
+    if !disclosed_proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidDisclosedProofHandle).into();
+    }
+
+    sync_result::set_u32(disclosed_proof::update_state(proof_handle, None, None), state_p)
+}
+
+/// Synchronous variant of `vcx_disclosed_proof_get_state`.
+///
+/// #Params
+/// proof_handle: was provided during creation. Used to identify disclosed proof object
+///
+/// state_p: Reference that will contain the most current state of the disclosed proof
+///
+/// #Returns
+/// Error code as a u32, details are available by `vcx_get_current_error`
+#[no_mangle]
+pub extern fn vcx_disclosed_proof_get_state_sync(proof_handle: u32,
+                                                 state_p: *mut u32) -> u32 {
+    info!("vcx_disclosed_proof_get_state_sync >>>");
+
+    check_useful_out_ptr!(state_p, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_disclosed_proof_get_state_sync(proof_handle: {})", proof_handle);
+
+    if !disclosed_proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidDisclosedProofHandle).into();
+    }
+
+    sync_result::set_u32(disclosed_proof::get_state(proof_handle), state_p)
+}
+
+/// Synchronous variant of `vcx_disclosed_proof_serialize`.
+///
+/// #Params
+/// proof_handle: was provided during creation. Used to identify disclosed proof object
+///
+/// serialized_data_p: Reference that will contain json string of the disclosed proof's attributes.
+///     The string is valid until the next `*_sync` call returning a string in the same thread.
+///
+/// #Returns
+/// Error code as a u32, details are available by `vcx_get_current_error`
+#[no_mangle]
+pub extern fn vcx_disclosed_proof_serialize_sync(proof_handle: u32,
+                                                 serialized_data_p: *mut *const c_char) -> u32 {
+    info!("vcx_disclosed_proof_serialize_sync >>>");
+
+    check_useful_out_ptr!(serialized_data_p, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_disclosed_proof_serialize_sync(proof_handle: {})", proof_handle);
+
+    if !disclosed_proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidDisclosedProofHandle).into();
+    }
+
+    sync_result::set_c_str(disclosed_proof::to_string(proof_handle), serialized_data_p)
+}
+
 /// Takes a json string representing an disclosed proof object and recreates an object matching the json
 ///
 /// #Params
@@ -695,6 +887,12 @@ pub extern fn vcx_disclosed_proof_deserialize(command_handle: CommandHandle,
 /// # Example
 /// credentials -> "{'attrs': {'attribute_0': [{'cred_info': {'schema_id': 'id', 'cred_def_id': 'id', 'attrs': {'attr_name': 'attr_value', ...}, 'referent': '914c7e11'}}]}}"
 ///
+/// Credentials matching an attribute requested by `names` carry the values of all the grouped attributes:
+/// credentials -> "{'attrs': {'attribute_1': [{'cred_info': {...}, 'values': {'street': 'Main', 'city': 'Prague'}}]}}"
+///
+/// For DIF Presentation Exchange request the W3C credentials satisfying each input descriptor are returned:
+/// credentials -> "{'input_descriptors': {'descriptor_id': [{'id': 'credential id', 'credential': {'@context': [...], 'credentialSubject': {...}, ...}}]}}"
+///
 /// #Returns
 /// Error code as a u32
 #[no_mangle]
@@ -734,6 +932,57 @@ pub extern fn vcx_disclosed_proof_retrieve_credentials(command_handle: CommandHa
     error::SUCCESS.code_num
 }
 
+/// Get UI friendly preview of the presentation request associated with proof object
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// handle: Proof handle that was provided during creation. Used to identify the disclosed proof object
+///
+/// cb: Callback that provides json string of the presentation request preview
+///
+/// # Example
+/// preview -> "{"name": "proof", "attributes": [{"referent": "attribute_0", "names": ["name"], "self_attest_allowed": false, "restrictions": [{"issuer_did": "V4SGRU86Z58d6TV7PBUe6f"}]}], "predicates": [{"referent": "predicate_0", "name": "age", "p_type": ">=", "p_value": 18, "description": "age is greater than or equal to 18", "restrictions": []}], "requires_non_revocation": false}"
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_disclosed_proof_get_request_preview(command_handle: CommandHandle,
+                                                      proof_handle: u32,
+                                                      cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, data: *const c_char)>) -> u32 {
+    info!("vcx_disclosed_proof_get_request_preview >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    if !disclosed_proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidDisclosedProofHandle).into();
+    }
+
+    let source_id = disclosed_proof::get_source_id(proof_handle).unwrap_or_default();
+    trace!("vcx_disclosed_proof_get_request_preview(command_handle: {}, proof_handle: {}) source_id: {}",
+           command_handle, proof_handle, source_id);
+
+    spawn(move || {
+        match disclosed_proof::get_presentation_request_preview(proof_handle) {
+            Ok(x) => {
+                trace!("vcx_disclosed_proof_get_request_preview_cb(command_handle: {}, rc: {}, data: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, x, source_id);
+                let msg = CStringUtils::string_to_cstring(x);
+                cb(command_handle, error::SUCCESS.code_num, msg.as_ptr());
+            }
+            Err(x) => {
+                error!("vcx_disclosed_proof_get_request_preview_cb(command_handle: {}, rc: {}, data: {}) source_id: {}",
+                       command_handle, x, 0, source_id);
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Accept proof request associated with proof object and generates a proof from the selected credentials and self attested attributes
 ///
 /// #Params
@@ -771,6 +1020,10 @@ pub extern fn vcx_disclosed_proof_retrieve_credentials(command_handle: CommandHa
 /// # Examples
 /// self_attested_attrs -> "{"self_attested_attr_0":"attested_val"}" | "{}"
 /// selected_credentials -> "{'attrs': {'attribute_0': {'credential': {'cred_info': {'cred_def_id': 'od', 'schema_id': 'id', 'referent': '0c212108-9433-4199-a21f-336a44164f38', 'attrs': {'attr_name': 'attr_value', ...}}}}}}"
+///
+/// For DIF Presentation Exchange request one of the retrieved credentials is selected for each input descriptor
+/// and self_attested_attrs are ignored, all selected credentials must be issued to the same subject:
+/// selected_credentials -> "{'input_descriptors': {'descriptor_id': {'id': 'credential id'}}}"
 /// cb: Callback that returns error status
 ///
 /// #Returns
@@ -815,6 +1068,70 @@ pub extern fn vcx_disclosed_proof_generate_proof(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Same as `vcx_disclosed_proof_generate_proof`, the generation can be cancelled by `vcx_cancel`.
+/// Cancelled generation fails with Cancelled error, the proof can be generated again.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// proof_handle: Proof handle that was provided during creation. Used to identify the disclosed proof object
+///
+/// selected_credentials: the same as `selected_credentials` of `vcx_disclosed_proof_generate_proof`
+///
+/// self_attested_attrs: the same as `self_attested_attrs` of `vcx_disclosed_proof_generate_proof`
+///
+/// cancellation_handle: token created by `vcx_create_cancellation_token`
+///
+/// cb: Callback that returns error status
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_disclosed_proof_generate_proof_cancellable(command_handle: CommandHandle,
+                                                             proof_handle: u32,
+                                                             selected_credentials: *const c_char,
+                                                             self_attested_attrs: *const c_char,
+                                                             cancellation_handle: u32,
+                                                             cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32)>) -> u32 {
+    info!("vcx_disclosed_proof_generate_proof_cancellable >>>");
+
+    check_useful_c_str!(selected_credentials, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(self_attested_attrs, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    if !disclosed_proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidDisclosedProofHandle).into();
+    }
+
+    let token = match cancellation::get_token(cancellation_handle) {
+        Ok(token) => token,
+        Err(err) => return err.into()
+    };
+
+    let source_id = disclosed_proof::get_source_id(proof_handle).unwrap_or_default();
+    trace!("vcx_disclosed_proof_generate_proof_cancellable(command_handle: {}, proof_handle: {}, selected_credentials: {}, self_attested_attrs: {}, cancellation_handle: {}) source_id: {}",
+           command_handle, proof_handle, selected_credentials, self_attested_attrs, cancellation_handle, source_id);
+
+    spawn(move || {
+        match cancellation::with_token(&token, || disclosed_proof::generate_proof(proof_handle, selected_credentials, self_attested_attrs)) {
+            Ok(_) => {
+                trace!("vcx_disclosed_proof_generate_proof_cancellable(command_handle: {}, rc: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, source_id);
+                cb(command_handle, error::SUCCESS.code_num);
+            }
+            Err(x) => {
+                error!("vcx_disclosed_proof_generate_proof_cancellable(command_handle: {}, rc: {}) source_id: {}",
+                       command_handle, x, source_id);
+                cb(command_handle, x.into());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Declines presentation request.
 /// There are two ways of following interaction:
 ///     - Prover wants to propose using a different presentation - pass `proposal` parameter.
@@ -948,6 +1265,160 @@ pub extern fn vcx_disclosed_proof_release(handle: u32) -> u32 {
     }
 }
 
+/// Get messages sent and received within the protocol exchange of the object.
+/// Messages are recorded only when `protocol_history` option of the library config is "true".
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// proof_handle: Proof handle that was provided during creation. Used to identify disclosed proof object
+///
+/// cb: Callback that provides JSON array of exchanged messages
+///
+/// # Example history -> "[{"direction":"received","timestamp":1599834693,"message":{...}},{"direction":"sent","timestamp":1599834694,"message":{...}}]"
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_disclosed_proof_get_protocol_history(command_handle: CommandHandle,
+                                                       proof_handle: u32,
+                                                       cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, history: *const c_char)>) -> u32 {
+    info!("vcx_disclosed_proof_get_protocol_history >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = disclosed_proof::get_source_id(proof_handle).unwrap_or_default();
+    trace!("vcx_disclosed_proof_get_protocol_history(command_handle: {}, proof_handle: {}) source_id: {}",
+           command_handle, proof_handle, source_id);
+
+    if !disclosed_proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidDisclosedProofHandle).into();
+    }
+
+    spawn(move || {
+        match disclosed_proof::get_protocol_history(proof_handle) {
+            Ok(history) => {
+                trace!("vcx_disclosed_proof_get_protocol_history_cb(command_handle: {}, rc: {}, proof_handle: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, proof_handle, source_id);
+                let history = CStringUtils::string_to_cstring(history);
+                cb(command_handle, error::SUCCESS.code_num, history.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_disclosed_proof_get_protocol_history_cb(command_handle: {}, rc: {}, proof_handle: {}) source_id: {}",
+                      command_handle, x, proof_handle, source_id);
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Get the time the received proof request of the object expires at.
+/// The expiration is set by the `~timing` decorator of the message.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// proof_handle: Proof handle that was provided during creation. Used to identify disclosed proof object
+///
+/// cb: Callback that provides the expiration time in RFC 3339 format, null pointer if it does not expire
+///
+/// # Example expires_time -> "2020-09-11T14:31:33Z"
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_disclosed_proof_get_expiry(command_handle: CommandHandle,
+                                             proof_handle: u32,
+                                             cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, expires_time: *const c_char)>) -> u32 {
+    info!("vcx_disclosed_proof_get_expiry >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = disclosed_proof::get_source_id(proof_handle).unwrap_or_default();
+    trace!("vcx_disclosed_proof_get_expiry(command_handle: {}, proof_handle: {}) source_id: {}",
+           command_handle, proof_handle, source_id);
+
+    if !disclosed_proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidDisclosedProofHandle).into();
+    }
+
+    spawn(move || {
+        match disclosed_proof::get_expiry(proof_handle) {
+            Ok(expires_time) => {
+                trace!("vcx_disclosed_proof_get_expiry_cb(command_handle: {}, rc: {}, proof_handle: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, proof_handle, source_id);
+                match expires_time {
+                    Some(expires_time) => {
+                        let expires_time = CStringUtils::string_to_cstring(expires_time);
+                        cb(command_handle, error::SUCCESS.code_num, expires_time.as_ptr());
+                    }
+                    None => cb(command_handle, error::SUCCESS.code_num, ptr::null_mut()),
+                }
+            }
+            Err(x) => {
+                warn!("vcx_disclosed_proof_get_expiry_cb(command_handle: {}, rc: {}, proof_handle: {}) source_id: {}",
+                      command_handle, x, proof_handle, source_id);
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Check whether the sent presentation waits for the acknowledgement of the verifier.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// proof_handle: Proof handle that was provided during creation. Used to identify disclosed proof object
+///
+/// cb: Callback that provides true while the acknowledgement is awaited
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_disclosed_proof_is_awaiting_ack(command_handle: CommandHandle,
+                                                  proof_handle: u32,
+                                                  cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, awaiting_ack: bool)>) -> u32 {
+    info!("vcx_disclosed_proof_is_awaiting_ack >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = disclosed_proof::get_source_id(proof_handle).unwrap_or_default();
+    trace!("vcx_disclosed_proof_is_awaiting_ack(command_handle: {}, proof_handle: {}) source_id: {}",
+           command_handle, proof_handle, source_id);
+
+    if !disclosed_proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidDisclosedProofHandle).into();
+    }
+
+    spawn(move || {
+        match disclosed_proof::is_awaiting_ack(proof_handle) {
+            Ok(awaiting_ack) => {
+                trace!("vcx_disclosed_proof_is_awaiting_ack_cb(command_handle: {}, rc: {}, awaiting_ack: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, awaiting_ack, source_id);
+                cb(command_handle, error::SUCCESS.code_num, awaiting_ack);
+            }
+            Err(x) => {
+                warn!("vcx_disclosed_proof_is_awaiting_ack_cb(command_handle: {}, rc: {}, proof_handle: {}) source_id: {}",
+                      command_handle, x, proof_handle, source_id);
+                cb(command_handle, x.into(), false);
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 #[cfg(test)]
 mod tests {
     extern crate serde_json;
@@ -958,7 +1429,7 @@ mod tests {
 
     use api::return_types_u32;
     use api::VcxStateType;
-    use utils::constants::{GET_MESSAGES_DECRYPTED_RESPONSE, V3_OBJECT_SERIALIZE_VERSION, CREDS_FROM_PROOF_REQ};
+    use utils::constants::{ARIES_PROVER_CREDENTIALS, ARIES_PROVER_SELF_ATTESTED_ATTRS, CREDS_FROM_PROOF_REQ, GET_MESSAGES_DECRYPTED_RESPONSE, V3_OBJECT_SERIALIZE_VERSION};
     use utils::devsetup::*;
     use utils::timeout::TimeoutUtils;
 
@@ -1004,6 +1475,29 @@ mod tests {
         assert_eq!(err, error::INVALID_JSON.code_num);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_disclosed_proof_create_from_oob() {
+        let _setup = SetupAriesMocks::init();
+
+        let invitation = json!(::aries::messages::out_of_band::invitation::tests::_oob_invitation()).to_string();
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_disclosed_proof_create_from_oob(cb.command_handle,
+                                                       CString::new("test_create_from_oob").unwrap().into_raw(),
+                                                       CString::new(invitation).unwrap().into_raw(),
+                                                       Some(cb.get_callback())), error::SUCCESS.code_num);
+        let handle = cb.receive(TimeoutUtils::some_medium()).unwrap();
+        assert_eq!(disclosed_proof::get_state(handle).unwrap(), VcxStateType::VcxStateRequestReceived as u32);
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_disclosed_proof_create_from_oob(cb.command_handle,
+                                                       CString::new("test_create_from_oob").unwrap().into_raw(),
+                                                       CString::new(BAD_PROOF_REQUEST).unwrap().into_raw(),
+                                                       Some(cb.get_callback())), error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap_err(), error::INVALID_JSON.code_num);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_create_with_msgid() {
@@ -1070,8 +1564,8 @@ mod tests {
         let cb = return_types_u32::Return_U32::new().unwrap();
         assert_eq!(vcx_disclosed_proof_generate_proof(cb.command_handle,
                                                       handle,
-                                                      CString::new("{}").unwrap().into_raw(),
-                                                      CString::new("{}").unwrap().into_raw(),
+                                                      CString::new(ARIES_PROVER_CREDENTIALS).unwrap().into_raw(),
+                                                      CString::new(ARIES_PROVER_SELF_ATTESTED_ATTRS).unwrap().into_raw(),
                                                       Some(cb.get_callback())), error::SUCCESS.code_num);
 
         let cb = return_types_u32::Return_U32_STR::new().unwrap();
@@ -1179,6 +1673,24 @@ mod tests {
         let _credentials = cb.receive(None).unwrap().unwrap();
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_disclosed_proof_get_request_preview() {
+        let _setup = SetupAriesMocks::init();
+        ::settings::set_config_value(::settings::CONFIG_PROTOCOL_TYPE, "4.0");
+
+        let proof_handle = _vcx_disclosed_proof_create_with_request_c_closure(ARIES_PROOF_REQUEST_PRESENTATION).unwrap();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_disclosed_proof_get_request_preview(cb.command_handle,
+                                                           proof_handle,
+                                                           Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        let preview = cb.receive(TimeoutUtils::some_medium()).unwrap().unwrap();
+        let preview: Value = serde_json::from_str(&preview).unwrap();
+        assert!(preview["attributes"].is_array());
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_vcx_disclosed_proof_generate_proof() {
@@ -1190,8 +1702,8 @@ mod tests {
         let cb = return_types_u32::Return_U32::new().unwrap();
         assert_eq!(vcx_disclosed_proof_generate_proof(cb.command_handle,
                                                       handle,
-                                                      CString::new("{}").unwrap().into_raw(),
-                                                      CString::new("{}").unwrap().into_raw(),
+                                                      CString::new(ARIES_PROVER_CREDENTIALS).unwrap().into_raw(),
+                                                      CString::new(ARIES_PROVER_SELF_ATTESTED_ATTRS).unwrap().into_raw(),
                                                       Some(cb.get_callback())), error::SUCCESS.code_num);
         cb.receive(TimeoutUtils::some_medium()).unwrap();
     }
@@ -3,7 +3,9 @@ use std::ptr;
 use indy_sys::CommandHandle;
 use libc::c_char;
 
+use api::sync_result;
 use connection;
+use credential_template;
 use error::prelude::*;
 use issuer_credential;
 use settings;
@@ -78,6 +80,8 @@ use utils::threadpool::spawn;
 /// # Example crendetial_data -> "{"state":"UT"}"
 /// Note, that value can be empty: "{"middle_name":""}"
 /// # Example credential_data -> "{"state":["UT"]}"  please note: this format is deprecated
+/// # Example credential_data -> "{"photo":{"value":"iVBORw0KGgo...","mime-type":"image/png"}}"
+/// Note, that values of non-textual mime types have to be base64 encoded.
 #[no_mangle]
 #[allow(unused_variables, unused_mut)]
 pub extern fn vcx_issuer_create_credential(command_handle: CommandHandle,
@@ -149,6 +153,258 @@ pub extern fn vcx_issuer_create_credential(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Create a Issuer Credential object issuing W3C Verifiable Credential (JSON-LD) instead of Indy credential.
+/// The credential is signed by the institution verkey (`institution_verkey` setting) with `SortedJsonEd25519Signature`
+/// proof over the credential JSON, which is verified by libvcx only. Its issuer is `did:key` of the verkey.
+/// No credential definition is needed and the credential is not revocable.
+/// The offer, request and credential messages carry `ld-proof-vc-detail-0` and `ld-proof-vc-0` attachments announced
+/// by `libvcx/sorted-json-vc-detail@v1.0` and `libvcx/sorted-json-vc@v1.0` format descriptors of issue-credential v2,
+/// so only libvcx holders take part in the exchange.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// source_id: Enterprise's personal identification for the user.
+///
+/// credential_data: claims of the credential subject, same format as in `vcx_issuer_create_credential`
+///
+/// cb: Callback that provides credential handle and error status of request
+///
+/// #Returns
+/// Error code as a u32
+///
+/// # Example credential_data -> "{"state":"UT"}"
+#[no_mangle]
+pub extern fn vcx_issuer_create_w3c_credential(command_handle: CommandHandle,
+                                               source_id: *const c_char,
+                                               credential_data: *const c_char,
+                                               cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, credential_handle: u32)>) -> u32 {
+    info!("vcx_issuer_create_w3c_credential >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(source_id, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(credential_data, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_issuer_create_w3c_credential(command_handle: {}, source_id: {}, credential_data: {})",
+           command_handle, source_id, secret!(&credential_data));
+
+    spawn(move || {
+        match issuer_credential::issuer_credential_create_w3c(source_id, credential_data) {
+            Ok(handle) => {
+                trace!("vcx_issuer_create_w3c_credential_cb(command_handle: {}, rc: {}, handle: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, handle, issuer_credential::get_source_id(handle).unwrap_or_default());
+                cb(command_handle, error::SUCCESS.code_num, handle);
+            }
+            Err(x) => {
+                warn!("vcx_issuer_create_w3c_credential_cb(command_handle: {}, rc: {}, handle: {})",
+                      command_handle, x, 0);
+                cb(command_handle, x.into(), 0);
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Create a Credential Template describing credentials issued repeatedly with the same credential definition.
+/// Fixed attributes are shared by all credentials, dynamic attributes are supplied for every credential
+/// created by `vcx_issuer_create_credential_from_template`. Together they must cover the schema of the credential definition.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// source_id: Enterprise's personal identification for the user, given to credentials created from the template.
+///
+/// cred_def_handle: handle of the published credential definition
+///
+/// template_name: Name of the credentials created from the template - ex. Drivers Licence
+///
+/// template_json: fixed attributes, names of dynamic attributes and optional rules for their values
+///
+/// cb: Callback that provides credential template handle and error status of request
+///
+/// #Returns
+/// Error code as a u32
+///
+/// # Example template_json -> "{"fixed": {"state": "UT"}, "dynamic": ["name", "age"], "rules": {"age": {"pattern": "^[0-9]+$", "max_length": 3}}}"
+#[no_mangle]
+pub extern fn vcx_credential_template_create(command_handle: CommandHandle,
+                                             source_id: *const c_char,
+                                             cred_def_handle: u32,
+                                             template_name: *const c_char,
+                                             template_json: *const c_char,
+                                             cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, template_handle: u32)>) -> u32 {
+    info!("vcx_credential_template_create >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(source_id, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(template_name, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(template_json, VcxErrorKind::InvalidOption);
+
+    if !::credential_def::is_valid_handle(cred_def_handle) {
+        return VcxError::from(VcxErrorKind::InvalidCredDefHandle).into();
+    }
+
+    if !::credential_def::check_is_published(cred_def_handle).unwrap_or(false) {
+        return VcxError::from_msg(VcxErrorKind::InvalidCredDefHandle, "Credential Definition is not in the Published State yet").into();
+    }
+
+    trace!("vcx_credential_template_create(command_handle: {}, source_id: {}, cred_def_handle: {}, template_name: {}, template_json: {})",
+           command_handle, source_id, cred_def_handle, template_name, secret!(&template_json));
+
+    spawn(move || {
+        match credential_template::create(source_id, cred_def_handle, template_name, template_json) {
+            Ok(handle) => {
+                trace!("vcx_credential_template_create_cb(command_handle: {}, rc: {}, handle: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, handle, credential_template::get_source_id(handle).unwrap_or_default());
+                cb(command_handle, error::SUCCESS.code_num, handle);
+            }
+            Err(x) => {
+                warn!("vcx_credential_template_create_cb(command_handle: {}, rc: {}, handle: {})",
+                      command_handle, x, 0);
+                cb(command_handle, x.into(), 0);
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Releases the credential template object by de-allocating memory.
+/// Credentials created from the template are not affected.
+///
+/// #Params
+/// template_handle: Credential Template handle that was provided during creation.
+///
+/// #Returns
+/// Success
+#[no_mangle]
+pub extern fn vcx_credential_template_release(template_handle: u32) -> u32 {
+    info!("vcx_credential_template_release >>>");
+
+    let source_id = credential_template::get_source_id(template_handle).unwrap_or_default();
+    match credential_template::release(template_handle) {
+        Ok(()) => {
+            trace!("vcx_credential_template_release(template_handle: {}, rc: {}), source_id: {}",
+                   template_handle, error::SUCCESS.message, source_id);
+            error::SUCCESS.code_num
+        }
+        Err(e) => {
+            warn!("vcx_credential_template_release(template_handle: {}, rc: {}), source_id: {}",
+                  template_handle, e, source_id);
+            e.into()
+        }
+    }
+}
+
+/// Create a Issuer Credential object from Credential Template. The credential uses credential definition,
+/// source id and name of the template, its data are fixed attributes of the template completed by `dynamic_values`.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// template_handle: Credential Template handle that was provided during creation.
+///
+/// dynamic_values: values of all dynamic attributes of the template, they have to satisfy rules of the template
+///
+/// cb: Callback that provides credential handle and error status of request
+///
+/// #Returns
+/// Error code as a u32
+///
+/// # Example dynamic_values -> "{"name": "Alice", "age": "25"}"
+#[no_mangle]
+pub extern fn vcx_issuer_create_credential_from_template(command_handle: CommandHandle,
+                                                         template_handle: u32,
+                                                         dynamic_values: *const c_char,
+                                                         cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, credential_handle: u32)>) -> u32 {
+    info!("vcx_issuer_create_credential_from_template >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(dynamic_values, VcxErrorKind::InvalidOption);
+
+    if !credential_template::is_valid_handle(template_handle) {
+        return VcxError::from(VcxErrorKind::InvalidCredentialTemplateHandle).into();
+    }
+
+    trace!("vcx_issuer_create_credential_from_template(command_handle: {}, template_handle: {}, dynamic_values: {})",
+           command_handle, template_handle, secret!(&dynamic_values));
+
+    spawn(move || {
+        match issuer_credential::issuer_credential_create_from_template(template_handle, dynamic_values) {
+            Ok(handle) => {
+                trace!("vcx_issuer_create_credential_from_template_cb(command_handle: {}, rc: {}, handle: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, handle, issuer_credential::get_source_id(handle).unwrap_or_default());
+                cb(command_handle, error::SUCCESS.code_num, handle);
+            }
+            Err(x) => {
+                warn!("vcx_issuer_create_credential_from_template_cb(command_handle: {}, rc: {}, handle: {})",
+                      command_handle, x, 0);
+                cb(command_handle, x.into(), 0);
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Get out-of-band invitation to a new connection with the credential offer attached, so that issuance begins
+/// as soon as the invitee connects. The credential moves to `VcxStateOfferSent`, the connection is established
+/// and the credential request is received by `vcx_issuer_credential_update_state`.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+///
+/// cb: provides out-of-band invitation and error status of the request
+///
+/// # Example invitation -> "{"@type": "https://didcomm.org/out-of-band/1.0/invitation", "@id": "...", "label": "Faber", "handshake_protocols": ["https://didcomm.org/connections/1.0"], "request~attach": [{"@id": "request-0", "mime-type": "application/json", "data": {"json": {...}}}], "services": [{"id": "#inline", "type": "did-communication", "recipientKeys": [...], "routingKeys": [...], "serviceEndpoint": "..."}]}"
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_issuer_get_oob_offer(command_handle: CommandHandle,
+                                       credential_handle: u32,
+                                       cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, invitation: *const c_char)>) -> u32 {
+    info!("vcx_issuer_get_oob_offer >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = issuer_credential::get_source_id(credential_handle).unwrap_or_default();
+    trace!("vcx_issuer_get_oob_offer(command_handle: {}, credential_handle: {}) source_id: {}",
+           command_handle, credential_handle, source_id);
+    if !issuer_credential::is_valid_handle(credential_handle) {
+        return VcxError::from(VcxErrorKind::InvalidIssuerCredentialHandle).into();
+    }
+
+    spawn(move || {
+        match issuer_credential::get_oob_offer(credential_handle) {
+            Ok(invitation) => {
+                trace!("vcx_issuer_get_oob_offer_cb(command_handle: {}, rc: {}, invitation: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, invitation, source_id);
+                let invitation = CStringUtils::string_to_cstring(invitation);
+                cb(command_handle, error::SUCCESS.code_num, invitation.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_issuer_get_oob_offer_cb(command_handle: {}, rc: {}, credential_handle: {}) source_id: {}",
+                      command_handle, x, credential_handle, source_id);
+                cb(command_handle, x.into(), ptr::null_mut())
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Send a credential offer to user showing what will be included in the actual credential
 ///
 /// #Params
@@ -268,6 +524,9 @@ pub extern fn vcx_issuer_get_credential_offer_msg(command_handle: CommandHandle,
 ///         3 - Request Received
 ///         4 - Issued
 ///
+///     Renewing credential whose predecessor could not be revoked once it was issued reports the revocation error,
+///     the revocation is retried by every update.
+///
 /// #Returns
 /// Error code as a u32
 #[no_mangle]
@@ -469,6 +728,9 @@ pub extern fn vcx_issuer_accept_credential(credential_handle: u32) -> u32 {
 
 /// Sends the credential to the end user (holder).
 ///
+/// A revocable credential takes its place in the revocation registry when it is built,
+/// so the credential definition has to be loaded in this process.
+///
 /// #Params
 /// command_handle: command handle to map callback to user context.
 ///
@@ -522,6 +784,59 @@ pub extern fn vcx_issuer_send_credential(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Withdraws the credential offer or stops the issuance before the credential is acknowledged.
+/// The holder is informed by problem report on the issuance thread, over the connection the offer was sent over,
+/// and the credential moves into the finished state with failed credential status.
+/// The holder of an out-of-band offer who has not connected yet cannot be informed, the exchange is finished anyway.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+///
+/// reason: human readable reason sent to the holder in the problem report
+///
+/// cb: Callback that provides error status of abandoning the exchange and whether the holder was informed
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_issuer_credential_abandon(command_handle: CommandHandle,
+                                            credential_handle: u32,
+                                            reason: *const c_char,
+                                            cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, notified: bool)>) -> u32 {
+    info!("vcx_issuer_credential_abandon >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(reason, VcxErrorKind::InvalidOption);
+
+    if !issuer_credential::is_valid_handle(credential_handle) {
+        return VcxError::from(VcxErrorKind::InvalidIssuerCredentialHandle).into();
+    }
+
+    let source_id = issuer_credential::get_source_id(credential_handle).unwrap_or_default();
+    trace!("vcx_issuer_credential_abandon(command_handle: {}, credential_handle: {}, reason: {}) source_id: {}",
+           command_handle, credential_handle, reason, source_id);
+    spawn(move || {
+        match issuer_credential::abandon(credential_handle, &reason) {
+            Ok(notified) => {
+                trace!("vcx_issuer_credential_abandon_cb(command_handle: {}, credential_handle: {}, rc: {}, notified: {}) source_id: {}",
+                       command_handle, credential_handle, error::SUCCESS.message, notified, source_id);
+                cb(command_handle, error::SUCCESS.code_num, notified);
+            }
+            Err(x) => {
+                warn!("vcx_issuer_credential_abandon_cb(command_handle: {}, credential_handle: {}, rc: {}) source_id: {}",
+                      command_handle, credential_handle, x, source_id);
+                cb(command_handle, x.into(), false);
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Gets the credential message that can be sent to the user
 ///
 /// #Params
@@ -626,6 +941,82 @@ pub extern fn vcx_issuer_credential_serialize(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Synchronous variant of `vcx_issuer_credential_update_state`, runs on the calling thread.
+///
+/// #Params
+/// credential_handle: was provided during creation. Used to identify issuer credential object
+///
+/// state_p: Reference that will contain the most current state of the issuer credential
+///
+/// #Returns
+/// Error code as a u32, details are available by `vcx_get_current_error`
+#[no_mangle]
+pub extern fn vcx_issuer_credential_update_state_sync(credential_handle: u32,
+                                                      state_p: *mut u32) -> u32 {
+    info!("vcx_issuer_credential_update_state_sync >>>");
+
+    check_useful_out_ptr!(state_p, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_issuer_credential_update_state_sync(credential_handle: {})", credential_handle);
+
+    if !issuer_credential::is_valid_handle(credential_handle) {
+        return VcxError::from(VcxErrorKind::InvalidIssuerCredentialHandle).into();
+    }
+
+    sync_result::set_u32(issuer_credential::update_state(credential_handle, None, None), state_p)
+}
+
+/// Synchronous variant of `vcx_issuer_credential_get_state`.
+///
+/// #Params
+/// credential_handle: was provided during creation. Used to identify issuer credential object
+///
+/// state_p: Reference that will contain the most current state of the issuer credential
+///
+/// #Returns
+/// Error code as a u32, details are available by `vcx_get_current_error`
+#[no_mangle]
+pub extern fn vcx_issuer_credential_get_state_sync(credential_handle: u32,
+                                                   state_p: *mut u32) -> u32 {
+    info!("vcx_issuer_credential_get_state_sync >>>");
+
+    check_useful_out_ptr!(state_p, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_issuer_credential_get_state_sync(credential_handle: {})", credential_handle);
+
+    if !issuer_credential::is_valid_handle(credential_handle) {
+        return VcxError::from(VcxErrorKind::InvalidIssuerCredentialHandle).into();
+    }
+
+    sync_result::set_u32(issuer_credential::get_state(credential_handle), state_p)
+}
+
+/// Synchronous variant of `vcx_issuer_credential_serialize`.
+///
+/// #Params
+/// credential_handle: was provided during creation. Used to identify issuer credential object
+///
+/// serialized_data_p: Reference that will contain json string of the issuer credential's attributes.
+///     The string is valid until the next `*_sync` call returning a string in the same thread.
+///
+/// #Returns
+/// Error code as a u32, details are available by `vcx_get_current_error`
+#[no_mangle]
+pub extern fn vcx_issuer_credential_serialize_sync(credential_handle: u32,
+                                                   serialized_data_p: *mut *const c_char) -> u32 {
+    info!("vcx_issuer_credential_serialize_sync >>>");
+
+    check_useful_out_ptr!(serialized_data_p, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_issuer_credential_serialize_sync(credential_handle: {})", credential_handle);
+
+    if !issuer_credential::is_valid_handle(credential_handle) {
+        return VcxError::from(VcxErrorKind::InvalidIssuerCredentialHandle).into();
+    }
+
+    sync_result::set_c_str(issuer_credential::to_string(credential_handle), serialized_data_p)
+}
+
 /// Takes a json string representing an issuer credential object and recreates an object matching the json
 ///
 /// #Params
@@ -670,6 +1061,92 @@ pub extern fn vcx_issuer_credential_deserialize(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Makes issuer credential send the credential as soon as the credential request is received
+/// by state update, so that the credential does not need to be sent by separate call.
+///
+/// #Params
+/// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_issuer_credential_enable_auto_issue(credential_handle: u32) -> u32 {
+    info!("vcx_issuer_credential_enable_auto_issue >>>");
+
+    let source_id = issuer_credential::get_source_id(credential_handle).unwrap_or_default();
+    match issuer_credential::enable_auto_issue(credential_handle) {
+        Ok(()) => {
+            trace!("vcx_issuer_credential_enable_auto_issue(credential_handle: {}, rc: {}), source_id: {}",
+                   credential_handle, error::SUCCESS.message, source_id);
+            error::SUCCESS.code_num
+        }
+        Err(e) => {
+            warn!("vcx_issuer_credential_enable_auto_issue(credential_handle: {}, rc: {}), source_id: {}",
+                  credential_handle, e, source_id);
+            e.into()
+        }
+    }
+}
+
+/// Makes issuer credential send the credential with `~please_ack` decorator. The issuance is not finished
+/// once the credential is sent, but once the holder acknowledges the credential (or reports a problem).
+/// Credential status stays undefined until then, while the state is already VcxStateAccepted.
+///
+/// #Params
+/// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_issuer_credential_request_ack(credential_handle: u32) -> u32 {
+    info!("vcx_issuer_credential_request_ack >>>");
+
+    let source_id = issuer_credential::get_source_id(credential_handle).unwrap_or_default();
+    match issuer_credential::request_ack(credential_handle) {
+        Ok(()) => {
+            trace!("vcx_issuer_credential_request_ack(credential_handle: {}, rc: {}), source_id: {}",
+                   credential_handle, error::SUCCESS.message, source_id);
+            error::SUCCESS.code_num
+        }
+        Err(e) => {
+            warn!("vcx_issuer_credential_request_ack(credential_handle: {}, rc: {}), source_id: {}",
+                  credential_handle, e, source_id);
+            e.into()
+        }
+    }
+}
+
+/// Sets the time the credential offer is valid for. The offer is sent with `~timing` decorator with `expires_time`,
+/// `update_state` moves the credential into VcxStateExpired once the time passes without a credential request
+/// and a request received after the deadline is rejected by a problem report. The holder moves its credential into
+/// VcxStateExpired as well when the offer was not accepted in time. Has to be called before the offer is sent.
+///
+/// #Params
+/// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+///
+/// expires_in_secs: number of seconds the offer is valid after it is sent
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_issuer_credential_set_expiration(credential_handle: u32, expires_in_secs: u32) -> u32 {
+    info!("vcx_issuer_credential_set_expiration >>>");
+
+    let source_id = issuer_credential::get_source_id(credential_handle).unwrap_or_default();
+    match issuer_credential::set_expiration(credential_handle, u64::from(expires_in_secs)) {
+        Ok(()) => {
+            trace!("vcx_issuer_credential_set_expiration(credential_handle: {}, expires_in_secs: {}, rc: {}), source_id: {}",
+                   credential_handle, expires_in_secs, error::SUCCESS.message, source_id);
+            error::SUCCESS.code_num
+        }
+        Err(e) => {
+            warn!("vcx_issuer_credential_set_expiration(credential_handle: {}, expires_in_secs: {}, rc: {}), source_id: {}",
+                  credential_handle, expires_in_secs, e, source_id);
+            e.into()
+        }
+    }
+}
+
 /// Releases the issuer credential object by deallocating memory
 ///
 /// #Params
@@ -798,6 +1275,161 @@ pub extern fn vcx_issuer_revoke_credential_local(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Get messages sent and received within the protocol exchange of the object.
+/// Messages are recorded only when `protocol_history` option of the library config is "true".
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+///
+/// cb: Callback that provides JSON array of exchanged messages
+///
+/// # Example history -> "[{"direction":"received","timestamp":1599834693,"message":{...}},{"direction":"sent","timestamp":1599834694,"message":{...}}]"
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_issuer_credential_get_protocol_history(command_handle: CommandHandle,
+                                                         credential_handle: u32,
+                                                         cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, history: *const c_char)>) -> u32 {
+    info!("vcx_issuer_credential_get_protocol_history >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = issuer_credential::get_source_id(credential_handle).unwrap_or_default();
+    trace!("vcx_issuer_credential_get_protocol_history(command_handle: {}, credential_handle: {}) source_id: {}",
+           command_handle, credential_handle, source_id);
+
+    if !issuer_credential::is_valid_handle(credential_handle) {
+        return VcxError::from(VcxErrorKind::InvalidIssuerCredentialHandle).into();
+    }
+
+    spawn(move || {
+        match issuer_credential::get_protocol_history(credential_handle) {
+            Ok(history) => {
+                trace!("vcx_issuer_credential_get_protocol_history_cb(command_handle: {}, rc: {}, credential_handle: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, credential_handle, source_id);
+                let history = CStringUtils::string_to_cstring(history);
+                cb(command_handle, error::SUCCESS.code_num, history.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_issuer_credential_get_protocol_history_cb(command_handle: {}, rc: {}, credential_handle: {}) source_id: {}",
+                      command_handle, x, credential_handle, source_id);
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Get the time the credential offer of the object expires at.
+/// The expiration is set by the `~timing` decorator of the message.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+///
+/// cb: Callback that provides the expiration time in RFC 3339 format, null pointer if it does not expire
+///
+/// # Example expires_time -> "2020-09-11T14:31:33Z"
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_issuer_credential_get_expiry(command_handle: CommandHandle,
+                                               credential_handle: u32,
+                                               cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, expires_time: *const c_char)>) -> u32 {
+    info!("vcx_issuer_credential_get_expiry >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = issuer_credential::get_source_id(credential_handle).unwrap_or_default();
+    trace!("vcx_issuer_credential_get_expiry(command_handle: {}, credential_handle: {}) source_id: {}",
+           command_handle, credential_handle, source_id);
+
+    if !issuer_credential::is_valid_handle(credential_handle) {
+        return VcxError::from(VcxErrorKind::InvalidIssuerCredentialHandle).into();
+    }
+
+    spawn(move || {
+        match issuer_credential::get_expiry(credential_handle) {
+            Ok(expires_time) => {
+                trace!("vcx_issuer_credential_get_expiry_cb(command_handle: {}, rc: {}, credential_handle: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, credential_handle, source_id);
+                match expires_time {
+                    Some(expires_time) => {
+                        let expires_time = CStringUtils::string_to_cstring(expires_time);
+                        cb(command_handle, error::SUCCESS.code_num, expires_time.as_ptr());
+                    }
+                    None => cb(command_handle, error::SUCCESS.code_num, ptr::null_mut()),
+                }
+            }
+            Err(x) => {
+                warn!("vcx_issuer_credential_get_expiry_cb(command_handle: {}, rc: {}, credential_handle: {}) source_id: {}",
+                      command_handle, x, credential_handle, source_id);
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Check whether the issued credential waits for the acknowledgement of the holder.
+/// The object reports `Accepted` state both before and after the acknowledgement arrives, this tells the two cases apart.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// credential_handle: Credential handle that was provided during creation. Used to identify credential object
+///
+/// cb: Callback that provides true while the acknowledgement is awaited
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_issuer_credential_is_awaiting_ack(command_handle: CommandHandle,
+                                                    credential_handle: u32,
+                                                    cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, awaiting_ack: bool)>) -> u32 {
+    info!("vcx_issuer_credential_is_awaiting_ack >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = issuer_credential::get_source_id(credential_handle).unwrap_or_default();
+    trace!("vcx_issuer_credential_is_awaiting_ack(command_handle: {}, credential_handle: {}) source_id: {}",
+           command_handle, credential_handle, source_id);
+
+    if !issuer_credential::is_valid_handle(credential_handle) {
+        return VcxError::from(VcxErrorKind::InvalidIssuerCredentialHandle).into();
+    }
+
+    spawn(move || {
+        match issuer_credential::is_awaiting_ack(credential_handle) {
+            Ok(awaiting_ack) => {
+                trace!("vcx_issuer_credential_is_awaiting_ack_cb(command_handle: {}, rc: {}, awaiting_ack: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, awaiting_ack, source_id);
+                cb(command_handle, error::SUCCESS.code_num, awaiting_ack);
+            }
+            Err(x) => {
+                warn!("vcx_issuer_credential_is_awaiting_ack_cb(command_handle: {}, rc: {}, credential_handle: {}) source_id: {}",
+                      command_handle, x, credential_handle, source_id);
+                cb(command_handle, x.into(), false);
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 #[cfg(test)]
 pub mod tests {
     extern crate serde_json;
@@ -818,7 +1450,7 @@ pub mod tests {
 
     static DEFAULT_CREDENTIAL_NAME: &str = "Credential Name Default";
     static DEFAULT_DID: &str = "8XFh8yBzrpJQmNyZzgoTqB";
-    static DEFAULT_ATTR: &str = "{\"attr\":\"value\"}";
+    static DEFAULT_ATTR: &str = CREDENTIAL_DATA;
 
     pub fn issuer_credential_state_accepted() -> String {
         json!({
@@ -909,6 +1541,81 @@ pub mod tests {
         assert!(handle > 0);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_issuer_create_credential_from_template() {
+        let _setup = SetupMocks::init();
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_credential_template_create(cb.command_handle,
+                                                  CString::new("1").unwrap().into_raw(),
+                                                  ::credential_def::tests::create_cred_def_fake(),
+                                                  CString::new(DEFAULT_CREDENTIAL_NAME).unwrap().into_raw(),
+                                                  CString::new(::credential_template::tests::_template()).unwrap().into_raw(),
+                                                  Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        let template_handle = cb.receive(TimeoutUtils::some_medium()).unwrap();
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_issuer_create_credential_from_template(cb.command_handle,
+                                                              template_handle,
+                                                              CString::new(r#"{"name": "Alice", "age": "25"}"#).unwrap().into_raw(),
+                                                              Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        let handle = cb.receive(TimeoutUtils::some_medium()).unwrap();
+        assert_eq!("1", issuer_credential::get_source_id(handle).unwrap());
+
+        assert_eq!(vcx_credential_template_release(template_handle), error::SUCCESS.code_num);
+        assert_eq!(vcx_credential_template_release(template_handle), error::INVALID_CREDENTIAL_TEMPLATE_HANDLE.code_num);
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_issuer_create_credential_from_template(cb.command_handle,
+                                                              template_handle,
+                                                              CString::new(r#"{"name": "Alice", "age": "25"}"#).unwrap().into_raw(),
+                                                              Some(cb.get_callback())),
+                   error::INVALID_CREDENTIAL_TEMPLATE_HANDLE.code_num);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_issuer_get_oob_offer() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = _vcx_issuer_create_credential_c_closure().unwrap();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_issuer_get_oob_offer(cb.command_handle, handle, Some(cb.get_callback())), error::SUCCESS.code_num);
+        let invitation = cb.receive(TimeoutUtils::some_medium()).unwrap().unwrap();
+        assert!(invitation.contains("out-of-band/1.0/invitation"));
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_issuer_get_oob_offer(cb.command_handle, 0, Some(cb.get_callback())), error::INVALID_ISSUER_CREDENTIAL_HANDLE.code_num);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_issuer_create_w3c_credential() {
+        let _setup = SetupStrictAriesMocks::init();
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_issuer_create_w3c_credential(cb.command_handle,
+                                                    CString::new(DEFAULT_CREDENTIAL_NAME).unwrap().into_raw(),
+                                                    CString::new(DEFAULT_ATTR).unwrap().into_raw(),
+                                                    Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap_err(), error::INVALID_CONFIGURATION.code_num);
+
+        settings::set_config_value(settings::CONFIG_INSTITUTION_VERKEY, settings::DEFAULT_VERKEY);
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_issuer_create_w3c_credential(cb.command_handle,
+                                                    CString::new(DEFAULT_CREDENTIAL_NAME).unwrap().into_raw(),
+                                                    CString::new(DEFAULT_ATTR).unwrap().into_raw(),
+                                                    Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        assert!(cb.receive(TimeoutUtils::some_medium()).unwrap() > 0);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_vcx_issuer_create_credential_fails() {
@@ -1136,4 +1843,26 @@ pub mod tests {
 
         assert_eq!(vcx_issuer_credential_release(handle), error::INVALID_ISSUER_CREDENTIAL_HANDLE.code_num);
     }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_issuer_credential_enable_auto_issue() {
+        let _setup = SetupStrictAriesMocks::init();
+
+        let handle = _vcx_issuer_create_credential_c_closure().unwrap();
+
+        assert_eq!(vcx_issuer_credential_enable_auto_issue(handle), error::SUCCESS.code_num);
+        assert_eq!(vcx_issuer_credential_enable_auto_issue(handle + 1), error::INVALID_ISSUER_CREDENTIAL_HANDLE.code_num);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_issuer_credential_request_ack() {
+        let _setup = SetupStrictAriesMocks::init();
+
+        let handle = _vcx_issuer_create_credential_c_closure().unwrap();
+
+        assert_eq!(vcx_issuer_credential_request_ack(handle), error::SUCCESS.code_num);
+        assert_eq!(vcx_issuer_credential_request_ack(handle + 1), error::INVALID_ISSUER_CREDENTIAL_HANDLE.code_num);
+    }
 }
@@ -1,9 +1,13 @@
+use std::sync::Arc;
+
 use libc::c_char;
+use log::Level;
 
 use error::prelude::*;
 use utils::cstring::CStringUtils;
 use utils::error::SUCCESS;
 use utils::logger::{CVoid, EnabledCB, FlushCB, LibvcxDefaultLogger, LibvcxLogger, LogCB, LOGGER_STATE};
+use utils::structured_log::{self, StructuredRecord};
 
 /// Set default logger implementation.
 ///
@@ -109,5 +113,58 @@ pub extern fn vcx_get_logger(context_p: *mut *const CVoid,
     res
 }
 
+/// Set sink receiving structured log records.
+///
+/// Records are emitted at protocol exchange points (messages sent and received, state changes) and carry
+/// correlation id derived from thread id, connection handle and message type, so that all records of
+/// one exchange can be grouped. Sink receives records up to debug level regardless of the logger level,
+/// trace records only when the logger enables them.
+/// Previously set sink is replaced.
+///
+/// #Params
+/// context: pointer to some context that will be available in the sink.
+/// sink: handler called with level (1 - error ... 5 - trace) and the record as JSON:
+///     {
+///         "timestamp": "2020-06-02T12:00:00.000000+00:00",
+///         "level": "DEBUG",
+///         "target": "vcx::aries::handlers::connection::connection",
+///         "event": "message_sent",
+///         "correlation_id": "{thread_id}:{connection_handle}:{message_type}",
+///         "thread_id": "...", (optional)
+///         "connection_handle": 1, (optional)
+///         "message_type": "trust_ping/1.0/ping", (optional)
+///         "fields": {"source_id": "alice", "state": 4}
+///     }
+///
+/// #Returns
+/// u32 Error Code
+#[no_mangle]
+pub extern fn vcx_set_structured_log_sink(context: *const CVoid,
+                                          sink: Option<extern fn(context: *const CVoid, level: u32, record: *const c_char)>) -> u32 {
+    info!("vcx_set_structured_log_sink >>>");
+
+    check_useful_c_callback!(sink, VcxErrorKind::InvalidOption);
+
+    // pointer is only handed back to the caller
+    let context = context as usize;
+    structured_log::set_sink(Some(Arc::new(move |record: &StructuredRecord| {
+        let level = record.level.parse::<Level>().map(|level| level as u32).unwrap_or(0);
+        let record = CStringUtils::string_to_cstring(json!(record).to_string());
+        sink(context as *const CVoid, level, record.as_ptr());
+    })));
+
+    SUCCESS.code_num
+}
+
+/// Remove sink set by `vcx_set_structured_log_sink`.
+///
+/// #Returns
+/// u32 Error Code
+#[no_mangle]
+pub extern fn vcx_unset_structured_log_sink() -> u32 {
+    info!("vcx_unset_structured_log_sink >>>");
 
+    structured_log::set_sink(None);
 
+    SUCCESS.code_num
+}
@@ -6,11 +6,13 @@ pub mod proof;
 pub mod credential_def;
 pub mod schema;
 pub mod credential;
+pub mod w3c_credential;
 pub mod disclosed_proof;
 pub mod wallet;
 pub mod logger;
 pub mod return_types_u32;
 mod filters;
+mod sync_result;
 
 use std::fmt;
 
@@ -79,6 +81,7 @@ enum_number!(VcxStateType
     VcxStateRevoked = 7,
     VcxStateRedirected = 8,
     VcxStateRejected = 9,
+    VcxStateSending = 10,
 });
 
 impl VcxStateType {
@@ -92,6 +95,9 @@ impl VcxStateType {
             5 => VcxStateType::VcxStateUnfulfilled,
             6 => VcxStateType::VcxStateExpired,
             7 => VcxStateType::VcxStateRevoked,
+            8 => VcxStateType::VcxStateRedirected,
+            9 => VcxStateType::VcxStateRejected,
+            10 => VcxStateType::VcxStateSending,
             _ => VcxStateType::VcxStateNone,
         }
     }
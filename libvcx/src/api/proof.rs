@@ -3,6 +3,7 @@ use std::ptr;
 use indy_sys::CommandHandle;
 use libc::c_char;
 
+use api::sync_result;
 use connection;
 use error::prelude::*;
 use proof;
@@ -91,9 +92,12 @@ use utils::threadpool::spawn;
 ///                        // If specified, prover must proof non-revocation
 ///                        // for date in this interval for each attribute
 ///                        // (can be overridden on attribute level)
+///                        // The interval is copied to attributes and predicates without their own one
 ///     from: Optional<u64> // timestamp of interval beginning
 ///     to: Optional<u64> // timestamp of interval beginning
 ///         // Requested time represented as a total number of seconds from Unix Epoch, Optional
+///         // `from` must not be greater than `to` nor lie in the future, `to` may lie in the future,
+///         // otherwise the call fails with InvalidRevocationTimestamp error
 /// # Examples config ->  "{}" | "{"to": 123} | "{"from": 100, "to": 123}"
 ///
 /// wql query: indy-sdk/docs/design/011-wallet-query-language/README.md
@@ -108,6 +112,8 @@ use utils::threadpool::spawn;
 ///         // the following keys can be used for every `attribute name` in credential.
 ///         "attr::<attribute name>::marker": "1", - to filter based on existence of a specific attribute
 ///         "attr::<attribute name>::value": <attribute raw value>, - to filter based on value of a specific attribute
+///         // attribute restrictions are checked again when the proof is verified, against the values revealed in it,
+///         // a value restriction on attribute which is not revealed fails the verification. Empty marker is taken as "1".
 ///
 /// cb: Callback that provides proof handle and error status of request.
 ///
@@ -154,6 +160,77 @@ pub extern fn vcx_proof_create(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Create a new Proof object that requests DIF Presentation Exchange submission of W3C Verifiable Credentials
+/// instead of Indy proof. The request carries `dif-presentation-definition-0` attachment announced by
+/// `libvcx/sorted-json-pe-definitions@v1.0` format descriptor, its challenge is a generated nonce the prover
+/// has to sign the presentation over by `SortedJsonEd25519Signature`, so only libvcx provers can answer it.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// source_id: Enterprise's personal identification for the user.
+///
+/// presentation_definition: DIF Presentation Exchange presentation definition
+///     {
+///         "id": string,
+///         "input_descriptors": [{
+///             "id": string,
+///             "schema": Optional<[{"uri": string}]>, // uri of a context or a type of the credential
+///             "constraints": Optional<{
+///                 "fields": [{
+///                     "path": [string], // JSONPath of the value: `$.a.b`, `$['a']` or `$.a[0]`
+///                     "filter": Optional<json schema>, // type, const, enum, pattern, minLength, maxLength,
+///                                                      // minimum, maximum, exclusiveMinimum, exclusiveMaximum
+///                 }]
+///             }>
+///         }]
+///     }
+///
+/// # Example presentation_definition -> "{"id":"1","input_descriptors":[{"id":"adult","constraints":{"fields":[{"path":["$.credentialSubject.age"],"filter":{"type":"number","minimum":18}}]}}]}"
+///
+/// name: Name of the proof request - ex. Adult check
+///
+/// cb: Callback that provides proof handle and error status of request.
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_proof_create_with_presentation_definition(command_handle: CommandHandle,
+                                                            source_id: *const c_char,
+                                                            presentation_definition: *const c_char,
+                                                            name: *const c_char,
+                                                            cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, proof_handle: u32)>) -> u32 {
+    info!("vcx_proof_create_with_presentation_definition >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(source_id, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(presentation_definition, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(name, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_proof_create_with_presentation_definition(command_handle: {}, source_id: {}, presentation_definition: {}, name: {})",
+           command_handle, source_id, presentation_definition, name);
+
+    spawn(move || {
+        let (rc, handle) = match proof::create_proof_with_presentation_definition(source_id, presentation_definition, name) {
+            Ok(x) => {
+                trace!("vcx_proof_create_with_presentation_definition_cb(command_handle: {}, rc: {}, handle: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, x, proof::get_source_id(x).unwrap_or_default());
+                (error::SUCCESS.code_num, x)
+            }
+            Err(x) => {
+                warn!("vcx_proof_create_with_presentation_definition_cb(command_handle: {}, rc: {}, handle: {})",
+                      command_handle, x, 0);
+                (x.into(), 0)
+            }
+        };
+        cb(command_handle, rc, handle);
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Query the agency for the received messages.
 /// Checks for any messages changing state in the object and updates the state attribute.
 ///
@@ -403,6 +480,82 @@ pub extern fn vcx_proof_serialize(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Synchronous variant of `vcx_proof_update_state`, runs on the calling thread.
+///
+/// #Params
+/// proof_handle: was provided during creation. Used to identify proof object
+///
+/// state_p: Reference that will contain the most current state of the proof
+///
+/// #Returns
+/// Error code as a u32, details are available by `vcx_get_current_error`
+#[no_mangle]
+pub extern fn vcx_proof_update_state_sync(proof_handle: u32,
+                                          state_p: *mut u32) -> u32 {
+    info!("vcx_proof_update_state_sync >>>");
+
+    check_useful_out_ptr!(state_p, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_proof_update_state_sync(proof_handle: {})", proof_handle);
+
+    if !proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidProofHandle).into();
+    }
+
+    sync_result::set_u32(proof::update_state(proof_handle, None, None), state_p)
+}
+
+/// Synchronous variant of `vcx_proof_get_state`.
+///
+/// #Params
+/// proof_handle: was provided during creation. Used to identify proof object
+///
+/// state_p: Reference that will contain the most current state of the proof
+///
+/// #Returns
+/// Error code as a u32, details are available by `vcx_get_current_error`
+#[no_mangle]
+pub extern fn vcx_proof_get_state_sync(proof_handle: u32,
+                                       state_p: *mut u32) -> u32 {
+    info!("vcx_proof_get_state_sync >>>");
+
+    check_useful_out_ptr!(state_p, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_proof_get_state_sync(proof_handle: {})", proof_handle);
+
+    if !proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidProofHandle).into();
+    }
+
+    sync_result::set_u32(proof::get_state(proof_handle), state_p)
+}
+
+/// Synchronous variant of `vcx_proof_serialize`.
+///
+/// #Params
+/// proof_handle: was provided during creation. Used to identify proof object
+///
+/// serialized_data_p: Reference that will contain json string of the proof's attributes.
+///     The string is valid until the next `*_sync` call returning a string in the same thread.
+///
+/// #Returns
+/// Error code as a u32, details are available by `vcx_get_current_error`
+#[no_mangle]
+pub extern fn vcx_proof_serialize_sync(proof_handle: u32,
+                                       serialized_data_p: *mut *const c_char) -> u32 {
+    info!("vcx_proof_serialize_sync >>>");
+
+    check_useful_out_ptr!(serialized_data_p, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_proof_serialize_sync(proof_handle: {})", proof_handle);
+
+    if !proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidProofHandle).into();
+    }
+
+    sync_result::set_c_str(proof::to_string(proof_handle), serialized_data_p)
+}
+
 /// Takes a json string representing a proof object and recreates an object matching the json
 ///
 /// #Params
@@ -473,6 +626,37 @@ pub extern fn vcx_proof_release(proof_handle: u32) -> u32 {
     }
 }
 
+/// Makes the proof request expire the given number of seconds after it is sent. The request then carries
+/// `~timing` decorator with `expires_time`, the prover refuses to answer the expired request and the proof
+/// received after the deadline is rejected by a problem report. Disclosed proof not sent in time is moved into
+/// VcxStateExpired by its `update_state`. Has to be called before the request is sent.
+///
+/// #Params
+/// proof_handle: Proof handle that was provided during creation. Used to access proof object
+///
+/// expires_in_secs: number of seconds the request is valid after it is sent
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_proof_set_expiration(proof_handle: u32, expires_in_secs: u32) -> u32 {
+    info!("vcx_proof_set_expiration >>>");
+
+    let source_id = proof::get_source_id(proof_handle).unwrap_or_default();
+    match proof::set_expiration(proof_handle, u64::from(expires_in_secs)) {
+        Ok(()) => {
+            trace!("vcx_proof_set_expiration(proof_handle: {}, expires_in_secs: {}, rc: {}), source_id: {}",
+                   proof_handle, expires_in_secs, error::SUCCESS.message, source_id);
+            error::SUCCESS.code_num
+        }
+        Err(e) => {
+            warn!("vcx_proof_set_expiration(proof_handle: {}, expires_in_secs: {}, rc: {}), source_id: {}",
+                  proof_handle, expires_in_secs, e, source_id);
+            e.into()
+        }
+    }
+}
+
 /// Sends a proof request to pairwise connection
 ///
 /// #Params
@@ -529,6 +713,124 @@ pub extern fn vcx_proof_send_request(command_handle: CommandHandle,
 }
 
 
+/// Sends the pending proof request again, for example when the prover did not receive it.
+/// The request is sent unchanged, so the prover answers it on the same thread.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// proof_handle: Proof handle that was provided during creation. Used to access proof object
+///
+/// connection_handle: Connection handle the request was sent over
+///
+/// cb: provides any error status of the proof_request
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_proof_resend_request(command_handle: CommandHandle,
+                                       proof_handle: u32,
+                                       connection_handle: u32,
+                                       cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32)>) -> u32 {
+    info!("vcx_proof_resend_request >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = proof::get_source_id(proof_handle).unwrap_or_default();
+    trace!("vcx_proof_resend_request(command_handle: {}, proof_handle: {}, connection_handle: {}) source_id: {}",
+           command_handle, proof_handle, connection_handle, source_id);
+    if !proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidProofHandle).into();
+    }
+
+    if !connection::is_valid_handle(connection_handle) {
+        return VcxError::from(VcxErrorKind::InvalidConnectionHandle).into();
+    }
+
+    spawn(move || {
+        let err = match proof::resend_request(proof_handle, connection_handle) {
+            Ok(()) => {
+                trace!("vcx_proof_resend_request_cb(command_handle: {}, rc: {}, proof_handle: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, proof_handle, source_id);
+                error::SUCCESS.code_num
+            }
+            Err(x) => {
+                warn!("vcx_proof_resend_request_cb(command_handle: {}, rc: {}, proof_handle: {}) source_id: {}",
+                      command_handle, x, proof_handle, source_id);
+                x.into()
+            }
+        };
+
+        cb(command_handle, err);
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Sends a revised proof request superseding the pending one. The revised request continues the thread
+/// of the pending request, so the proof object and its handle stay the same. Presentations answering
+/// the former request are refused by problem report and the proof keeps waiting for the presentation
+/// answering the revised request.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// proof_handle: Proof handle that was provided during creation. Used to access proof object
+///
+/// request_update: JSON with the parts of the request to replace, the parts not set are kept
+///     {
+///         "name": Optional<string>,
+///         "requested_attributes": Optional<array>, - the same as `requested_attrs` of `vcx_proof_create`
+///         "requested_predicates": Optional<array>, - the same as `requested_predicates` of `vcx_proof_create`
+///         "revocation_interval": Optional<object>, - the same as `revocation_interval` of `vcx_proof_create`
+///     }
+///     Presentation Exchange request can be revised only by its name, the revised request asks for a new challenge.
+///
+/// cb: provides any error status of the proof_request
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_proof_update_request(command_handle: CommandHandle,
+                                       proof_handle: u32,
+                                       request_update: *const c_char,
+                                       cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32)>) -> u32 {
+    info!("vcx_proof_update_request >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(request_update, VcxErrorKind::InvalidOption);
+
+    let source_id = proof::get_source_id(proof_handle).unwrap_or_default();
+    trace!("vcx_proof_update_request(command_handle: {}, proof_handle: {}, request_update: {}) source_id: {}",
+           command_handle, proof_handle, request_update, source_id);
+    if !proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidProofHandle).into();
+    }
+
+    spawn(move || {
+        let err = match proof::update_request(proof_handle, &request_update) {
+            Ok(()) => {
+                trace!("vcx_proof_update_request_cb(command_handle: {}, rc: {}, proof_handle: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, proof_handle, source_id);
+                error::SUCCESS.code_num
+            }
+            Err(x) => {
+                warn!("vcx_proof_update_request_cb(command_handle: {}, rc: {}, proof_handle: {}) source_id: {}",
+                      command_handle, x, proof_handle, source_id);
+                x.into()
+            }
+        };
+
+        cb(command_handle, err);
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Get the proof request message that can be sent to the specified connection
 ///
 /// #Params
@@ -580,107 +882,517 @@ pub extern fn vcx_proof_get_request_msg(command_handle: CommandHandle,
 }
 
 
+/// Verifies presentation against the proof request it answers without creating proof object or connection,
+/// for verifiers receiving and sending the messages on their own.
+///
 /// #Params
 /// command_handle: command handle to map callback to user context.
 ///
-/// proof_handle: Proof handle that was provided during creation. Used to identify proof object
+/// request: aries presentation request message the presentation answers, e.g. as returned by `vcx_proof_get_request_msg`
 ///
-/// connection_handle: Connection handle that identifies pairwise connection
+/// presentation: aries presentation message
 ///
-/// cb: Callback that provides Proof attributes and error status of sending the credential
+/// cb: Callback that provides presentation state (1 - valid, 2 - invalid) and error status of the verification
 ///
 /// #Returns
 /// Error code as a u32
-#[deprecated(
-since = "1.15.0",
-note = "Use vcx_get_proof_msg() instead. This api is similar, but requires an extra parameter (connection_handle) which is unnecessary and unused in the internals."
-)]
 #[no_mangle]
-pub extern fn vcx_get_proof(command_handle: CommandHandle,
-                            proof_handle: u32,
-                            _unused_connection_handle: u32,
-                            cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, proof_state: u32, response_data: *const c_char)>) -> u32 {
-    info!("vcx_get_proof >>>");
+pub extern fn vcx_proof_verify_presentation_msg(command_handle: CommandHandle,
+                                                request: *const c_char,
+                                                presentation: *const c_char,
+                                                cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, proof_state: u32)>) -> u32 {
+    info!("vcx_proof_verify_presentation_msg >>>");
 
     check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(request, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(presentation, VcxErrorKind::InvalidOption);
 
-    if let Some(err) = proof_to_cb(command_handle, proof_handle, cb).err() { return err.into(); }
+    trace!("vcx_proof_verify_presentation_msg(command_handle: {}, request: {}, presentation: {})",
+           command_handle, request, secret!(&presentation));
+
+    spawn(move || {
+        match proof::verify_presentation_msg(&request, &presentation) {
+            Ok(proof_state) => {
+                trace!("vcx_proof_verify_presentation_msg_cb(command_handle: {}, rc: {}, proof_state: {})",
+                       command_handle, error::SUCCESS.message, proof_state);
+                cb(command_handle, error::SUCCESS.code_num, proof_state);
+            }
+            Err(x) => {
+                warn!("vcx_proof_verify_presentation_msg_cb(command_handle: {}, rc: {}, proof_state: {})",
+                      command_handle, x, 0);
+                cb(command_handle, x.into(), 0);
+            }
+        };
+
+        Ok(())
+    });
 
     error::SUCCESS.code_num
 }
 
-/// Get Proof Msg
+/// Verifies many presentations against the proof requests they answer, the same way `vcx_proof_verify_presentation_msg` does.
+/// Presentations are verified concurrently, at most `verify_batch_parallelism` (library config option, 4 by default) at once.
 ///
-/// *Note* This replaces vcx_get_proof. You no longer need a connection handle.
 /// #Params
 /// command_handle: command handle to map callback to user context.
 ///
-/// proof_handle: Proof handle that was provided during creation. Used to identify proof object
+/// items: JSON array of the request and presentation pairs
+///     [{"request": <aries presentation request message>, "presentation": <aries presentation message>}]
 ///
-/// cb: Callback that provides Proof attributes and error status of sending the credential
+/// cb: Callback that provides JSON array of results in the order of the items and error status of the batch
+///
+/// # Example results -> "[{"status":1},{"error_code":1020,"error":"Presentation does not answer the Presentation Request"}]"
 ///
 /// #Returns
 /// Error code as a u32
 #[no_mangle]
-pub extern fn vcx_get_proof_msg(command_handle: CommandHandle,
-                                proof_handle: u32,
-                                cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, proof_state: u32, response_data: *const c_char)>) -> u32 {
-    info!("vcx_get_proof_msg >>>");
+pub extern fn vcx_proof_verify_batch(command_handle: CommandHandle,
+                                     items: *const c_char,
+                                     cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, results: *const c_char)>) -> u32 {
+    info!("vcx_proof_verify_batch >>>");
 
     check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(items, VcxErrorKind::InvalidOption);
 
-    if let Some(err) = proof_to_cb(command_handle, proof_handle, cb).err() { return err.into(); }
-
-    error::SUCCESS.code_num
-}
-
-fn proof_to_cb(command_handle: CommandHandle,
-               proof_handle: u32,
-               cb: extern fn(xcommand_handle: CommandHandle, err: u32, proof_state: u32, response_data: *const c_char))
-               -> VcxResult<()> {
-    proof_api_input_validation(command_handle, proof_handle)?;
+    trace!("vcx_proof_verify_batch(command_handle: {}, items: {})",
+           command_handle, secret!(&items));
 
     spawn(move || {
-        let source_id = proof::get_source_id(proof_handle).unwrap_or_default();
-        //update the state to see if proof has come, ignore any errors
-        let _ = proof::update_state(proof_handle, None, None);
-
-        match proof::get_proof(proof_handle) {
-            Ok(x) => {
-                trace!("vcx_get_proof_cb(command_handle: {}, proof_handle: {}, rc: {}, proof: {}) source_id: {}", command_handle, proof_handle, 0, x, source_id);
-                let msg = CStringUtils::string_to_cstring(x);
-                cb(command_handle, error::SUCCESS.code_num, proof::get_proof_state(proof_handle).unwrap_or(0), msg.as_ptr());
+        match proof::verify_batch(&items) {
+            Ok(results) => {
+                trace!("vcx_proof_verify_batch_cb(command_handle: {}, rc: {}, results: {})",
+                       command_handle, error::SUCCESS.message, results);
+                let results = CStringUtils::string_to_cstring(results);
+                cb(command_handle, error::SUCCESS.code_num, results.as_ptr());
             }
             Err(x) => {
-                warn!("vcx_get_proof_cb(command_handle: {}, proof_handle: {}, rc: {}, proof: {}) source_id: {}", command_handle, proof_handle, x, "null", source_id);
-                cb(command_handle, x.into(), proof::get_proof_state(proof_handle).unwrap_or(0), ptr::null_mut());
+                warn!("vcx_proof_verify_batch_cb(command_handle: {}, rc: {})",
+                      command_handle, x);
+                cb(command_handle, x.into(), ptr::null_mut());
             }
         };
 
         Ok(())
     });
 
-    Ok(())
-}
-
-fn proof_api_input_validation(command_handle: CommandHandle, proof_handle: u32) -> VcxResult<()> {
-    let source_id = proof::get_source_id(proof_handle).unwrap_or_default();
-    trace!("vcx_get_proof(command_handle: {}, proof_handle: {}) source_id: {}",
-           command_handle, proof_handle, source_id);
-
-    if !proof::is_valid_handle(proof_handle) { Err(VcxError::from(VcxErrorKind::InvalidProofHandle))?; }
-
-    Ok(())
-}
-
-#[allow(unused_variables)]
-pub extern fn vcx_proof_accepted(proof_handle: u32, response_data: *const c_char) -> u32 {
-    info!("vcx_proof_accepted >>>");
     error::SUCCESS.code_num
 }
 
-
-#[cfg(test)]
+/// Get out-of-band invitation with the proof request attached, the prover answers it without establishing a connection.
+/// The proof moves to `VcxStateOfferSent` and the presentation is received by `vcx_proof_update_state`.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// proof_handle: Proof handle that was provided during creation. Used to access proof object
+///
+/// cb: provides out-of-band invitation and error status of the request
+///
+/// # Example invitation -> "{"@type": "https://didcomm.org/out-of-band/1.0/invitation", "@id": "...", "label": "Faber", "request~attach": [{"@id": "request-0", "mime-type": "application/json", "data": {"json": {...}}}], "services": [{"id": "#inline", "type": "did-communication", "recipientKeys": [...], "routingKeys": [...], "serviceEndpoint": "..."}]}"
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_proof_get_oob_request(command_handle: CommandHandle,
+                                        proof_handle: u32,
+                                        cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, invitation: *const c_char)>) -> u32 {
+    info!("vcx_proof_get_oob_request >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = proof::get_source_id(proof_handle).unwrap_or_default();
+    trace!("vcx_proof_get_oob_request(command_handle: {}, proof_handle: {}) source_id: {}",
+           command_handle, proof_handle, source_id);
+    if !proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidProofHandle).into();
+    }
+
+    spawn(move || {
+        match proof::get_oob_proof_request(proof_handle) {
+            Ok(invitation) => {
+                trace!("vcx_proof_get_oob_request_cb(command_handle: {}, rc: {}, invitation: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, invitation, source_id);
+                let invitation = CStringUtils::string_to_cstring(invitation);
+                cb(command_handle, error::SUCCESS.code_num, invitation.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_proof_get_oob_request_cb(command_handle: {}, rc: {}, proof_handle: {}) source_id: {}",
+                      command_handle, x, proof_handle, source_id);
+                cb(command_handle, x.into(), ptr::null_mut())
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// proof_handle: Proof handle that was provided during creation. Used to identify proof object
+///
+/// connection_handle: Connection handle that identifies pairwise connection
+///
+/// cb: Callback that provides Proof attributes and error status of sending the credential
+///
+/// #Returns
+/// Error code as a u32
+#[deprecated(
+since = "1.15.0",
+note = "Use vcx_get_proof_msg() instead. This api is similar, but requires an extra parameter (connection_handle) which is unnecessary and unused in the internals."
+)]
+#[no_mangle]
+pub extern fn vcx_get_proof(command_handle: CommandHandle,
+                            proof_handle: u32,
+                            _unused_connection_handle: u32,
+                            cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, proof_state: u32, response_data: *const c_char)>) -> u32 {
+    info!("vcx_get_proof >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    if let Some(err) = proof_to_cb(command_handle, proof_handle, cb).err() { return err.into(); }
+
+    error::SUCCESS.code_num
+}
+
+/// Get Proof Msg
+///
+/// *Note* This replaces vcx_get_proof. You no longer need a connection handle.
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// proof_handle: Proof handle that was provided during creation. Used to identify proof object
+///
+/// cb: Callback that provides Proof attributes and error status of sending the credential
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_get_proof_msg(command_handle: CommandHandle,
+                                proof_handle: u32,
+                                cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, proof_state: u32, response_data: *const c_char)>) -> u32 {
+    info!("vcx_get_proof_msg >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    if let Some(err) = proof_to_cb(command_handle, proof_handle, cb).err() { return err.into(); }
+
+    error::SUCCESS.code_num
+}
+
+/// Get details of verified presentation: revealed values of every requested attribute together with
+/// schema, credential definition, revocation registry and timestamp of the credential they come from.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// proof_handle: Proof handle that was provided during creation. Used to identify proof object
+///
+/// cb: Callback that provides verification details json
+///
+/// # Example verification_details -> "{"presentation_status":1,"revocation_status":"NonRevoked","revealed_attrs":{"attribute_0":{"name":"name","raw":"alice","encoded":"1139481716457488690172217916278103335","identifier":{"schema_id":"...","cred_def_id":"...","rev_reg_id":"...","timestamp":1599834693,"revocation_checked":true}}},"revealed_attr_groups":{},"self_attested_attrs":{},"unrevealed_attrs":{},"predicates":{}}"
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_proof_get_verification_details(command_handle: CommandHandle,
+                                                 proof_handle: u32,
+                                                 cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, verification_details: *const c_char)>) -> u32 {
+    info!("vcx_proof_get_verification_details >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = proof::get_source_id(proof_handle).unwrap_or_default();
+    trace!("vcx_proof_get_verification_details(command_handle: {}, proof_handle: {}) source_id: {}",
+           command_handle, proof_handle, source_id);
+    if !proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidProofHandle).into();
+    }
+
+    spawn(move || {
+        match proof::get_verification_details(proof_handle) {
+            Ok(details) => {
+                trace!("vcx_proof_get_verification_details_cb(command_handle: {}, rc: {}, proof_handle: {}) source_id: {}",
+                       command_handle, error::SUCCESS.code_num, proof_handle, source_id);
+                let details = CStringUtils::string_to_cstring(details);
+                cb(command_handle, error::SUCCESS.code_num, details.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_proof_get_verification_details_cb(command_handle: {}, rc: {}, proof_handle: {}) source_id: {}",
+                      command_handle, x, proof_handle, source_id);
+                cb(command_handle, x.into(), ptr::null_mut())
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Check revocation data of received presentation: timestamps of revocation registry states the prover used
+/// are compared with non_revoked intervals of the request and with registry entries on the ledger.
+/// Proof object state is not changed.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// proof_handle: Proof handle that was provided during creation. Used to identify proof object
+///
+/// cb: Callback that provides list of revocation checks, one for every referent with non_revoked interval
+///
+/// # Example revocation_checks -> "[{"referent":"attribute_0","rev_reg_id":"...","timestamp":1599834693,"non_revoked":{"from":null,"to":1599834700},"latest_entry_timestamp":1599834698,"issues":["stale"]}]"
+///   issues: "missing_timestamp" - credential is revocable but the prover did not use any registry state,
+///           "out_of_interval" - registry state is outside of the requested interval,
+///           "stale" - registry was updated within the interval after the state used by the prover
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_proof_verify_revocation(command_handle: CommandHandle,
+                                          proof_handle: u32,
+                                          cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, revocation_checks: *const c_char)>) -> u32 {
+    info!("vcx_proof_verify_revocation >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = proof::get_source_id(proof_handle).unwrap_or_default();
+    trace!("vcx_proof_verify_revocation(command_handle: {}, proof_handle: {}) source_id: {}",
+           command_handle, proof_handle, source_id);
+    if !proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidProofHandle).into();
+    }
+
+    spawn(move || {
+        match proof::verify_revocation(proof_handle) {
+            Ok(checks) => {
+                trace!("vcx_proof_verify_revocation_cb(command_handle: {}, rc: {}, proof_handle: {}, checks: {}) source_id: {}",
+                       command_handle, error::SUCCESS.code_num, proof_handle, checks, source_id);
+                let checks = CStringUtils::string_to_cstring(checks);
+                cb(command_handle, error::SUCCESS.code_num, checks.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_proof_verify_revocation_cb(command_handle: {}, rc: {}, proof_handle: {}) source_id: {}",
+                      command_handle, x, proof_handle, source_id);
+                cb(command_handle, x.into(), ptr::null_mut())
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Repeat verification of received presentation against ledger state at the given time.
+/// Ledger objects used by the presentation have to be written before `as_of` and the revocation registry
+/// states the prover referred to have to precede `as_of`, registry updates published later do not affect the result.
+/// Proof object state is not changed.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// proof_handle: Proof handle that was provided during creation. Used to identify proof object
+///
+/// as_of: UTC timestamp the presentation should be verified at
+///
+/// cb: Callback that provides presentation status the proof would have had at the given time
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_proof_verify_as_of(command_handle: CommandHandle,
+                                     proof_handle: u32,
+                                     as_of: u64,
+                                     cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, proof_state: u32)>) -> u32 {
+    info!("vcx_proof_verify_as_of >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = proof::get_source_id(proof_handle).unwrap_or_default();
+    trace!("vcx_proof_verify_as_of(command_handle: {}, proof_handle: {}, as_of: {}) source_id: {}",
+           command_handle, proof_handle, as_of, source_id);
+    if !proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidProofHandle).into();
+    }
+
+    spawn(move || {
+        match proof::verify_presentation_as_of(proof_handle, as_of) {
+            Ok(proof_state) => {
+                trace!("vcx_proof_verify_as_of_cb(command_handle: {}, rc: {}, proof_handle: {}, proof_state: {}) source_id: {}",
+                       command_handle, error::SUCCESS.code_num, proof_handle, proof_state, source_id);
+                cb(command_handle, error::SUCCESS.code_num, proof_state);
+            }
+            Err(x) => {
+                warn!("vcx_proof_verify_as_of_cb(command_handle: {}, rc: {}, proof_handle: {}) source_id: {}",
+                      command_handle, x, proof_handle, source_id);
+                cb(command_handle, x.into(), 0)
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+fn proof_to_cb(command_handle: CommandHandle,
+               proof_handle: u32,
+               cb: extern fn(xcommand_handle: CommandHandle, err: u32, proof_state: u32, response_data: *const c_char))
+               -> VcxResult<()> {
+    proof_api_input_validation(command_handle, proof_handle)?;
+
+    spawn(move || {
+        let source_id = proof::get_source_id(proof_handle).unwrap_or_default();
+        //update the state to see if proof has come, ignore any errors
+        let _ = proof::update_state(proof_handle, None, None);
+
+        match proof::get_proof(proof_handle) {
+            Ok(x) => {
+                trace!("vcx_get_proof_cb(command_handle: {}, proof_handle: {}, rc: {}, proof: {}) source_id: {}", command_handle, proof_handle, 0, x, source_id);
+                let msg = CStringUtils::string_to_cstring(x);
+                cb(command_handle, error::SUCCESS.code_num, proof::get_proof_state(proof_handle).unwrap_or(0), msg.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_get_proof_cb(command_handle: {}, proof_handle: {}, rc: {}, proof: {}) source_id: {}", command_handle, proof_handle, x, "null", source_id);
+                cb(command_handle, x.into(), proof::get_proof_state(proof_handle).unwrap_or(0), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    Ok(())
+}
+
+fn proof_api_input_validation(command_handle: CommandHandle, proof_handle: u32) -> VcxResult<()> {
+    let source_id = proof::get_source_id(proof_handle).unwrap_or_default();
+    trace!("vcx_get_proof(command_handle: {}, proof_handle: {}) source_id: {}",
+           command_handle, proof_handle, source_id);
+
+    if !proof::is_valid_handle(proof_handle) { Err(VcxError::from(VcxErrorKind::InvalidProofHandle))?; }
+
+    Ok(())
+}
+
+#[allow(unused_variables)]
+pub extern fn vcx_proof_accepted(proof_handle: u32, response_data: *const c_char) -> u32 {
+    info!("vcx_proof_accepted >>>");
+    error::SUCCESS.code_num
+}
+
+
+/// Get messages sent and received within the protocol exchange of the object.
+/// Messages are recorded only when `protocol_history` option of the library config is "true".
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// proof_handle: Proof handle that was provided during creation. Used to identify proof object
+///
+/// cb: Callback that provides JSON array of exchanged messages
+///
+/// # Example history -> "[{"direction":"received","timestamp":1599834693,"message":{...}},{"direction":"sent","timestamp":1599834694,"message":{...}}]"
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_proof_get_protocol_history(command_handle: CommandHandle,
+                                             proof_handle: u32,
+                                             cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, history: *const c_char)>) -> u32 {
+    info!("vcx_proof_get_protocol_history >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = proof::get_source_id(proof_handle).unwrap_or_default();
+    trace!("vcx_proof_get_protocol_history(command_handle: {}, proof_handle: {}) source_id: {}",
+           command_handle, proof_handle, source_id);
+
+    if !proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidProofHandle).into();
+    }
+
+    spawn(move || {
+        match proof::get_protocol_history(proof_handle) {
+            Ok(history) => {
+                trace!("vcx_proof_get_protocol_history_cb(command_handle: {}, rc: {}, proof_handle: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, proof_handle, source_id);
+                let history = CStringUtils::string_to_cstring(history);
+                cb(command_handle, error::SUCCESS.code_num, history.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_proof_get_protocol_history_cb(command_handle: {}, rc: {}, proof_handle: {}) source_id: {}",
+                      command_handle, x, proof_handle, source_id);
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Get the time the proof request of the object expires at.
+/// The expiration is set by the `~timing` decorator of the message.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// proof_handle: Proof handle that was provided during creation. Used to identify proof object
+///
+/// cb: Callback that provides the expiration time in RFC 3339 format, null pointer if it does not expire
+///
+/// # Example expires_time -> "2020-09-11T14:31:33Z"
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_proof_get_expiry(command_handle: CommandHandle,
+                                   proof_handle: u32,
+                                   cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, expires_time: *const c_char)>) -> u32 {
+    info!("vcx_proof_get_expiry >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let source_id = proof::get_source_id(proof_handle).unwrap_or_default();
+    trace!("vcx_proof_get_expiry(command_handle: {}, proof_handle: {}) source_id: {}",
+           command_handle, proof_handle, source_id);
+
+    if !proof::is_valid_handle(proof_handle) {
+        return VcxError::from(VcxErrorKind::InvalidProofHandle).into();
+    }
+
+    spawn(move || {
+        match proof::get_expiry(proof_handle) {
+            Ok(expires_time) => {
+                trace!("vcx_proof_get_expiry_cb(command_handle: {}, rc: {}, proof_handle: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, proof_handle, source_id);
+                match expires_time {
+                    Some(expires_time) => {
+                        let expires_time = CStringUtils::string_to_cstring(expires_time);
+                        cb(command_handle, error::SUCCESS.code_num, expires_time.as_ptr());
+                    }
+                    None => cb(command_handle, error::SUCCESS.code_num, ptr::null_mut()),
+                }
+            }
+            Err(x) => {
+                warn!("vcx_proof_get_expiry_cb(command_handle: {}, rc: {}, proof_handle: {}) source_id: {}",
+                      command_handle, x, proof_handle, source_id);
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+#[cfg(test)]
 mod tests {
     use std::ffi::CString;
     use std::ptr;
@@ -741,6 +1453,32 @@ mod tests {
                    error::INVALID_OPTION.code_num);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_create_proof_with_presentation_definition() {
+        let _setup = SetupAriesMocks::init();
+
+        let definition = json!({"id": "1", "input_descriptors": [{"id": "adult", "constraints": {"fields": [{"path": ["$.credentialSubject.age"], "filter": {"type": "number", "minimum": 18}}]}}]});
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_proof_create_with_presentation_definition(cb.command_handle,
+                                                                 CString::new("test_create").unwrap().into_raw(),
+                                                                 CString::new(definition.to_string()).unwrap().into_raw(),
+                                                                 CString::new("Adult").unwrap().into_raw(),
+                                                                 Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        assert!(cb.receive(TimeoutUtils::some_medium()).unwrap() > 0);
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_proof_create_with_presentation_definition(cb.command_handle,
+                                                                 CString::new("test_create").unwrap().into_raw(),
+                                                                 CString::new(json!({"id": "1", "input_descriptors": []}).to_string()).unwrap().into_raw(),
+                                                                 CString::new("Adult").unwrap().into_raw(),
+                                                                 Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap_err(), error::INVALID_JSON.code_num);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_vcx_proof_get_request_msg() {
@@ -754,6 +1492,52 @@ mod tests {
         let _msg = cb.receive(TimeoutUtils::some_medium()).unwrap().unwrap();
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_proof_verify_presentation_msg() {
+        let _setup = SetupAriesMocks::init();
+        let _mock_builder = MockBuilder::init().
+            set_mock_result_for_validate_indy_proof(Ok(true));
+
+        let proof_handle = create_proof_util().unwrap();
+        let request = proof::generate_proof_request_msg(proof_handle).unwrap();
+        let thread_id = proof::get_thread_id(proof_handle).unwrap();
+        let presentation = mockdata_proof::ARIES_PROOF_PRESENTATION.replace("4e62363d-6348-4b59-9d98-a86497f9301b", &thread_id);
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_proof_verify_presentation_msg(cb.command_handle,
+                                                     CString::new(request).unwrap().into_raw(),
+                                                     CString::new(presentation).unwrap().into_raw(),
+                                                     Some(cb.get_callback())), error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap(), ProofStateType::ProofValidated as u32);
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_proof_verify_presentation_msg(cb.command_handle,
+                                                     CString::new("{}").unwrap().into_raw(),
+                                                     CString::new("{}").unwrap().into_raw(),
+                                                     Some(cb.get_callback())), error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap_err(), error::INVALID_JSON.code_num);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_proof_get_oob_request() {
+        let _setup = SetupAriesMocks::init();
+
+        let proof_handle = create_proof_util().unwrap();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_proof_get_oob_request(cb.command_handle, proof_handle, Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        let invitation = cb.receive(TimeoutUtils::some_medium()).unwrap().unwrap();
+        assert!(invitation.contains("out-of-band/1.0/invitation"));
+        assert_eq!(proof::get_state(proof_handle).unwrap(), VcxStateType::VcxStateOfferSent as u32);
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_proof_get_oob_request(cb.command_handle, 0, Some(cb.get_callback())),
+                   error::INVALID_PROOF_HANDLE.code_num);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_vcx_proof_serialize() {
@@ -770,6 +1554,25 @@ mod tests {
         println!("Serialize proof: {:?}", _ser);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_proof_sync_functions() {
+        let _setup = SetupAriesMocks::init();
+
+        let proof_handle = create_proof_util().unwrap();
+
+        let mut state: u32 = 0;
+        assert_eq!(vcx_proof_get_state_sync(proof_handle, &mut state), error::SUCCESS.code_num);
+        assert_eq!(state, VcxStateType::VcxStateInitialized as u32);
+
+        let mut serialized: *const c_char = ptr::null();
+        assert_eq!(vcx_proof_serialize_sync(proof_handle, &mut serialized), error::SUCCESS.code_num);
+        let serialized = CStringUtils::c_str_to_string(serialized).unwrap().unwrap();
+        assert_eq!(proof::from_string(&serialized).map(|handle| proof::get_state(handle).unwrap()).unwrap(), state);
+
+        assert_eq!(vcx_proof_serialize_sync(proof_handle + 1, &mut serialized), error::INVALID_PROOF_HANDLE.code_num);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_vcx_proof_deserialize_succeeds() {
@@ -800,6 +1603,24 @@ mod tests {
         assert_eq!(state, VcxStateType::VcxStateInitialized as u32);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_proof_set_expiration() {
+        let _setup = SetupAriesMocks::init();
+        settings::set_config_value(settings::CONFIG_PROTOCOL_TYPE, "4.0");
+
+        let proof_handle = create_proof_util().unwrap();
+        assert_eq!(vcx_proof_set_expiration(proof_handle, 3600), error::SUCCESS.code_num);
+
+        let connection_handle = build_test_connection_inviter_requested();
+        proof::send_proof_request(proof_handle, connection_handle).unwrap();
+
+        let request: ::serde_json::Value = ::serde_json::from_str(&proof::generate_proof_request_msg(proof_handle).unwrap()).unwrap();
+        assert!(request["~timing"]["expires_time"].is_string());
+
+        assert_eq!(vcx_proof_set_expiration(proof_handle, 3600), error::NOT_READY.code_num);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_vcx_proof_send_request() {
@@ -835,6 +1656,35 @@ mod tests {
         assert_eq!(proof::get_state(proof_handle).unwrap(), VcxStateType::VcxStateAccepted as u32);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_proof_get_protocol_history() {
+        let _setup = SetupAriesMocks::init();
+        let _mock_builder = MockBuilder::init().
+            set_mock_result_for_validate_indy_proof(Ok(true));
+        settings::set_config_value(settings::CONFIG_PROTOCOL_HISTORY, "true");
+
+        let proof_handle = create_proof_util().unwrap();
+        let connection_handle = build_test_connection_inviter_requested();
+
+        proof::send_proof_request(proof_handle, connection_handle).unwrap();
+        proof::update_state(proof_handle, Some(mockdata_proof::ARIES_PROOF_PRESENTATION.to_string()), Some(connection_handle)).unwrap();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_proof_get_protocol_history(cb.command_handle, proof_handle, Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        let history = cb.receive(TimeoutUtils::some_medium()).unwrap().unwrap();
+        let history: ::serde_json::Value = ::serde_json::from_str(&history).unwrap();
+
+        assert_eq!(json!("sent"), history[0]["direction"]);
+        assert!(history[0]["message"]["@type"].as_str().unwrap().ends_with("present-proof/1.0/request-presentation"));
+        assert_eq!(json!("received"), history[1]["direction"]);
+        assert!(history[1]["message"]["@type"].as_str().unwrap().ends_with("present-proof/1.0/presentation"));
+
+        assert_eq!(vcx_proof_get_protocol_history(cb.command_handle, 0, Some(cb.get_callback())),
+                   error::INVALID_PROOF_HANDLE.code_num);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_get_proof_fails_when_not_ready_with_proof() {
@@ -871,6 +1721,44 @@ mod tests {
         assert_eq!(vcx_proof_release(proof_handle), error::INVALID_PROOF_HANDLE.code_num);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_proof_get_verification_details() {
+        let _setup = SetupAriesMocks::init();
+
+        let proof_handle = proof::from_string(mockdata_proof::SERIALIZIED_PROOF_REVOKED).unwrap();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_proof_get_verification_details(cb.command_handle,
+                                                      proof_handle,
+                                                      Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        let details = cb.receive(TimeoutUtils::some_medium()).unwrap().unwrap();
+        let details: ::serde_json::Value = ::serde_json::from_str(&details).unwrap();
+        assert_eq!(details["revocation_status"], json!("Revoked"));
+
+        assert_eq!(vcx_proof_get_verification_details(cb.command_handle, 0, Some(cb.get_callback())),
+                   error::INVALID_PROOF_HANDLE.code_num);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_proof_verify_as_of() {
+        let _setup = SetupAriesMocks::init();
+        let _mock_builder = MockBuilder::init().
+            set_mock_result_for_validate_indy_proof(Ok(true));
+
+        let proof_handle = proof::from_string(mockdata_proof::SERIALIZIED_PROOF_REVOKED).unwrap();
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_proof_verify_as_of(cb.command_handle, proof_handle, ::std::u64::MAX, Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap(), 1);
+
+        assert_eq!(vcx_proof_verify_as_of(cb.command_handle, 0, ::std::u64::MAX, Some(cb.get_callback())),
+                   error::INVALID_PROOF_HANDLE.code_num);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_vcx_connection_get_state() {
@@ -111,6 +111,38 @@ impl Return_U32_U32 {
     }
 }
 
+#[allow(non_camel_case_types)]
+pub struct Return_U32_U32_U32 {
+    pub command_handle: CommandHandle,
+    pub receiver: Receiver<(u32, u32, u32)>,
+}
+
+impl Return_U32_U32_U32 {
+    pub fn new() -> Result<Return_U32_U32_U32, u32> {
+        let (sender, receiver) = channel();
+        let closure: Box<dyn FnMut(u32, u32, u32) + Send> = Box::new(move |err, arg1, arg2| {
+            sender.send((err, arg1, arg2)).unwrap_or_else(log_error);
+        });
+
+        let command_handle = insert_closure(closure, callback::CALLBACKS_U32_U32_U32.deref());
+
+        Ok(Return_U32_U32_U32 {
+            command_handle,
+            receiver,
+        })
+    }
+
+    pub fn get_callback(&self) -> extern fn(command_handle: CommandHandle, arg1: u32, arg2: u32, arg3: u32) {
+        callback::call_cb_u32_u32_u32
+    }
+
+    pub fn receive(&self, timeout: Option<Duration>) -> Result<(u32, u32), u32> {
+        let (err, arg1, arg2) = receive(&self.receiver, timeout)?;
+
+        map_indy_error((arg1, arg2), err)
+    }
+}
+
 #[allow(non_camel_case_types)]
 pub struct Return_U32_STR {
     pub command_handle: CommandHandle,
@@ -82,6 +82,79 @@ pub extern fn vcx_schema_create(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Run `vcx_schema_create` as a dry run: the schema transaction is built, signed and priced,
+/// but it's not submitted to the ledger and no Schema object is created.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// source_id: Enterprise's personal identification for the user.
+///
+/// schema_name: Name of schema
+///
+/// version: Version of schema
+///
+/// schema_data: A list of attributes that will make up the schema, represented
+///    as a string containing a JSON array.
+///
+/// # Example schema_data -> "["attr1", "attr2", "attr3"]"
+///
+/// payment_handle: Reserved for future use (currently uses any address in the wallet)
+///
+/// cb: Callback that provides the prepared transaction and error status of request.
+///
+/// # Example dry_run -> {"schema_id":"2hoqvcwupRTUNkXn6ArYzs:2:test-licence:4.4.4","schema_txn":{"request":{...},"price":1,"payment":{...}},"total_price":1}
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_schema_dry_run(command_handle: CommandHandle,
+                                 source_id: *const c_char,
+                                 schema_name: *const c_char,
+                                 version: *const c_char,
+                                 schema_data: *const c_char,
+                                 _payment_handle: u32,
+                                 cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, dry_run: *const c_char)>) -> u32 {
+    info!("vcx_schema_dry_run >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(schema_name, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(version, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(source_id, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(schema_data, VcxErrorKind::InvalidOption);
+
+    let issuer_did = match settings::get_config_value(settings::CONFIG_INSTITUTION_DID) {
+        Ok(x) => x,
+        Err(x) => return x.into()
+    };
+    trace!(target: "vcx", "vcx_schema_dry_run(command_handle: {}, source_id: {}, schema_name: {},  schema_data: {})",
+           command_handle, source_id, schema_name, schema_data);
+
+    spawn(move || {
+        match schema::dry_run_schema(&source_id,
+                                     issuer_did,
+                                     schema_name,
+                                     version,
+                                     schema_data) {
+            Ok(x) => {
+                trace!(target: "vcx", "vcx_schema_dry_run_cb(command_handle: {}, rc: {}, dry_run: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, x, source_id);
+                let x = CStringUtils::string_to_cstring(x);
+                cb(command_handle, error::SUCCESS.code_num, x.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_schema_dry_run_cb(command_handle: {}, rc: {}, dry_run: {}) source_id: {}",
+                      command_handle, x, "null", source_id);
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Create a new Schema object that will be published by Endorser later.
 ///
 /// Note that Schema can't be used for credential issuing until it will be published on the ledger.
@@ -157,6 +230,60 @@ pub extern fn vcx_schema_prepare_for_endorser(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Prepares transaction of the Schema object which was created by `vcx_schema_prepare_for_endorser`
+/// but is not published yet, e.g. when the transaction has to be passed to another Endorser.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// schema_handle: Schema handle that was provided during creation. Used to access schema object
+///
+/// endorser: DID of the Endorser that will submit the transaction.
+///
+/// cb: Callback that provides Schema transaction that should be passed to Endorser for publishing.
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_schema_prepare_existing_for_endorser(command_handle: CommandHandle,
+                                                       schema_handle: u32,
+                                                       endorser: *const c_char,
+                                                       cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32,
+                                                                            schema_transaction: *const c_char)>) -> u32 {
+    info!("vcx_schema_prepare_existing_for_endorser >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(endorser, VcxErrorKind::InvalidOption);
+
+    let source_id = schema::get_source_id(schema_handle).unwrap_or_default();
+    trace!("vcx_schema_prepare_existing_for_endorser(command_handle: {}, schema_handle: {}, endorser: {}) source_id: {}",
+           command_handle, schema_handle, endorser, source_id);
+
+    if !schema::is_valid_handle(schema_handle) {
+        return VcxError::from(VcxErrorKind::InvalidSchemaHandle).into();
+    };
+
+    spawn(move || {
+        match schema::prepare_for_endorser(schema_handle, &endorser) {
+            Ok(transaction) => {
+                trace!("vcx_schema_prepare_existing_for_endorser(command_handle: {}, rc: {}, transaction: {}) source_id: {}",
+                       command_handle, error::SUCCESS.message, transaction, source_id);
+                let transaction = CStringUtils::string_to_cstring(transaction);
+                cb(command_handle, error::SUCCESS.code_num, transaction.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_schema_prepare_existing_for_endorser(command_handle: {}, rc: {}, transaction: {}) source_id: {}",
+                      command_handle, x, "", source_id);
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Takes the schema object and returns a json string of all its attributes
 ///
 /// #Params
@@ -367,6 +494,88 @@ pub extern fn vcx_schema_get_attributes(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Retrieves name, version and attribute names of a schema on the ledger without creating a schema object.
+///
+/// #Params
+/// schema_id: id of the schema on the ledger
+///
+/// cb: Callback contains the error status (if the schema cannot be found) and the schema as json string.
+///
+/// # Example
+/// schema -> {"schema_id":"2hoqvcwupRTUNkXn6ArYzs:2:test-licence:4.4.4","name":"test-licence","version":"4.4.4","attr_names":["height","name","sex","age"]}
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_schema_get_attributes_from_ledger(command_handle: CommandHandle,
+                                                    schema_id: *const c_char,
+                                                    cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, schema_json: *const c_char)>) -> u32 {
+    info!("vcx_schema_get_attributes_from_ledger >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(schema_id, VcxErrorKind::InvalidOption);
+    trace!("vcx_schema_get_attributes_from_ledger(command_handle: {}, schema_id: {})", command_handle, schema_id);
+
+    spawn(move || {
+        match schema::get_schema_attrs_from_ledger(&schema_id) {
+            Ok(schema_json) => {
+                trace!("vcx_schema_get_attributes_from_ledger_cb(command_handle: {}, rc: {}, schema_json: {})",
+                       command_handle, error::SUCCESS.message, schema_json);
+                let msg = CStringUtils::string_to_cstring(schema_json);
+                cb(command_handle, error::SUCCESS.code_num, msg.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_schema_get_attributes_from_ledger_cb(command_handle: {}, rc: {}, schema_json: {})",
+                      command_handle, x, "");
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Lists schemas created or prepared for an endorser by this issuer, as recorded in the wallet.
+///
+/// #Params
+/// cb: Callback contains the error status and json array of the schemas.
+///
+/// # Example
+/// schemas -> [{"schema_id":"2hoqvcwupRTUNkXn6ArYzs:2:test-licence:4.4.4","name":"test-licence","version":"4.4.4","attr_names":["height","name","sex","age"],"source_id":"Test Source ID","state":1}]
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_schema_list_created(command_handle: CommandHandle,
+                                      cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, schemas: *const c_char)>) -> u32 {
+    info!("vcx_schema_list_created >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    trace!("vcx_schema_list_created(command_handle: {})", command_handle);
+
+    spawn(move || {
+        match schema::list_created_schemas() {
+            Ok(schemas) => {
+                trace!("vcx_schema_list_created_cb(command_handle: {}, rc: {}, schemas: {})",
+                       command_handle, error::SUCCESS.message, schemas);
+                let msg = CStringUtils::string_to_cstring(schemas);
+                cb(command_handle, error::SUCCESS.code_num, msg.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_schema_list_created_cb(command_handle: {}, rc: {}, schemas: {})",
+                      command_handle, x, "");
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Retrieve the txn associated with paying for the schema
 ///
 /// #param
@@ -584,6 +793,26 @@ mod tests {
         assert!(handle > 0)
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_schema_dry_run() {
+        let _setup = SetupAriesMocks::init();
+
+        let (_, schema_name, schema_version, data) = prepare_schema_data();
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_schema_dry_run(cb.command_handle,
+                                      CString::new("Test Source ID").unwrap().into_raw(),
+                                      CString::new(schema_name).unwrap().into_raw(),
+                                      CString::new(schema_version).unwrap().into_raw(),
+                                      CString::new(data).unwrap().into_raw(),
+                                      0,
+                                      Some(cb.get_callback())), error::SUCCESS.code_num);
+        let dry_run = cb.receive(TimeoutUtils::some_medium()).unwrap().unwrap();
+        let dry_run: serde_json::Value = serde_json::from_str(&dry_run).unwrap();
+        assert_eq!(dry_run["schema_id"], json!(SCHEMA_ID));
+        assert!(dry_run["schema_txn"]["request"].is_object());
+    }
+
     #[cfg(feature = "pool_tests")]
     #[test]
     fn test_vcx_create_schema_with_pool() {
@@ -668,6 +897,21 @@ mod tests {
         assert_eq!(schema_as_json["data"].to_string(), data);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_schema_get_attributes_from_ledger() {
+        let _setup = SetupAriesMocks::init();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_schema_get_attributes_from_ledger(cb.command_handle,
+                                                         CString::new(SCHEMA_ID).unwrap().into_raw(),
+                                                         Some(cb.get_callback())), error::SUCCESS.code_num);
+        let schema_json = cb.receive(TimeoutUtils::some_short()).unwrap().unwrap();
+        let schema_json: serde_json::Value = serde_json::from_str(&schema_json).unwrap();
+        assert_eq!(schema_json["schema_id"], json!(SCHEMA_ID));
+        assert_eq!(schema_json["attr_names"].as_array().unwrap().len(), 4);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_get_payment_txn() {
@@ -731,6 +975,23 @@ mod tests {
         assert_eq!(expected_schema_transaction, schema_transaction);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_schema_prepare_existing_for_endorser() {
+        let _setup = SetupAriesMocks::init();
+
+        let did = settings::get_config_value(settings::CONFIG_INSTITUTION_DID).unwrap();
+        let (handle, _) = schema::prepare_schema_for_endorser("testid", did, "name".to_string(), "1.0".to_string(), "[\"name\"]".to_string(), "V4SGRU86Z58d6TV7PBUe6f".to_string()).unwrap();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_schema_prepare_existing_for_endorser(cb.command_handle,
+                                                            handle,
+                                                            CString::new("V4SGRU86Z58d6TV7PBUe6f").unwrap().into_raw(),
+                                                            Some(cb.get_callback())), error::SUCCESS.code_num);
+        let schema_transaction = cb.receive(TimeoutUtils::some_short()).unwrap().unwrap();
+        assert_eq!(schema_transaction, ::utils::constants::REQUEST_WITH_ENDORSER);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_vcx_schema_get_state() {
@@ -0,0 +1,58 @@
+//!
+//! Output parameters of the synchronous (`*_sync`) API functions.
+//!
+//! These functions run on the calling thread and return the error code directly, the result is
+//! written through an output pointer. Error details are available by `vcx_get_current_error` as
+//! for the asynchronous functions.
+//!
+
+use std::cell::RefCell;
+use std::ffi::CString;
+
+use libc::c_char;
+
+use error::prelude::*;
+use utils::cstring::CStringUtils;
+use utils::error;
+
+thread_local! {
+    static RESULT_C_STR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+/// Writes `result` to `value_p`, returns the error code.
+pub fn set_u32(result: VcxResult<u32>, value_p: *mut u32) -> u32 {
+    if value_p.is_null() {
+        return VcxError::from_msg(VcxErrorKind::InvalidOption, "Invalid pointer has been passed").into();
+    }
+
+    match result {
+        Ok(value) => {
+            unsafe { *value_p = value };
+            error::SUCCESS.code_num
+        }
+        Err(err) => err.into()
+    }
+}
+
+/// Writes `result` to `value_p`, returns the error code.
+///
+/// The string is owned by the library and stays valid until the next synchronous call returning
+/// a string in the same thread.
+pub fn set_c_str(result: VcxResult<String>, value_p: *mut *const c_char) -> u32 {
+    if value_p.is_null() {
+        return VcxError::from_msg(VcxErrorKind::InvalidOption, "Invalid pointer has been passed").into();
+    }
+
+    let value = match result {
+        Ok(value) => CStringUtils::string_to_cstring(value),
+        Err(err) => return err.into()
+    };
+
+    RESULT_C_STR.with(|result| {
+        let mut result = result.borrow_mut();
+        unsafe { *value_p = value.as_ptr() };
+        *result = Some(value);
+    });
+
+    error::SUCCESS.code_num
+}
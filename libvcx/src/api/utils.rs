@@ -1,16 +1,24 @@
 use std::ptr;
+use std::sync::Arc;
 use std::thread;
+use std::time::Duration;
 
 use indy_sys::CommandHandle;
 use libc::c_char;
 use serde_json;
 
+use api::sync_result;
 use error::prelude::*;
 use messages;
+use migration;
+use settings;
+use utils::cancellation;
 use utils::constants::*;
 use utils::cstring::CStringUtils;
 use utils::error;
+use utils::events::{self, StateChangeEvent};
 use utils::httpclient::AgencyMock;
+use utils::libindy::ledger_cache::{self, LedgerCacheWarmUp};
 use utils::libindy::payments;
 use utils::threadpool::spawn;
 
@@ -40,7 +48,7 @@ pub extern fn vcx_provision_agent(config: *const c_char) -> *mut c_char {
         }
     };
 
-    trace!("vcx_provision_agent(config: {})", config);
+    trace!("vcx_provision_agent(config: {})", settings::redact_config(&config));
 
     match messages::agent_utils::connect_register_provision(&config) {
         Err(e) => {
@@ -89,6 +97,269 @@ pub extern fn vcx_agent_provision_async(command_handle: CommandHandle,
             }
             Ok(s) => {
                 trace!("vcx_agent_provision_async_cb(command_handle: {}, rc: {}, config: {})",
+                       command_handle, error::SUCCESS.message, settings::redact_config(&s));
+                let msg = CStringUtils::string_to_cstring(s);
+                cb(command_handle, 0, msg.as_ptr());
+            }
+        }
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Same as `vcx_agent_provision_async`, the provisioning can be cancelled by `vcx_cancel`.
+/// Cancelled provisioning fails with Cancelled error, restores the settings and deletes the wallet it created,
+/// a wallet which existed before is only closed and reused when provisioning is started again.
+/// Agency steps completed before the cancellation are not undone, the agent created for them is left unused.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// config: configuration
+///
+/// cancellation_handle: token created by `vcx_create_cancellation_token`
+///
+/// cb: Callback that provides configuration or error status
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_agent_provision_cancellable(command_handle: CommandHandle,
+                                              config: *const c_char,
+                                              cancellation_handle: u32,
+                                              cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, config: *const c_char)>) -> u32 {
+    info!("vcx_agent_provision_cancellable >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(config, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_agent_provision_cancellable(command_handle: {}, json: {}, cancellation_handle: {})",
+           command_handle, config, cancellation_handle);
+
+    let token = match cancellation::get_token(cancellation_handle) {
+        Ok(token) => token,
+        Err(err) => return err.into()
+    };
+
+    thread::spawn(move || {
+        match cancellation::with_token(&token, || messages::agent_utils::connect_register_provision(&config)) {
+            Err(e) => {
+                error!("vcx_agent_provision_cancellable_cb(command_handle: {}, rc: {}, config: NULL", command_handle, e);
+                cb(command_handle, e.into(), ptr::null_mut());
+            }
+            Ok(s) => {
+                trace!("vcx_agent_provision_cancellable_cb(command_handle: {}, rc: {}, config: {})",
+                       command_handle, error::SUCCESS.message, settings::redact_config(&s));
+                let msg = CStringUtils::string_to_cstring(s);
+                cb(command_handle, 0, msg.as_ptr());
+            }
+        }
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Creates a token to cancel long running operations (`*_cancellable` functions).
+///
+/// Cancelled operation stops at the next request to the agency or the ledger and fails with Cancelled error,
+/// HTTP requests already in flight stop waiting for the response.
+///
+/// #Params
+/// cancellation_handle_p: Reference that will contain handle of the token
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_create_cancellation_token(cancellation_handle_p: *mut u32) -> u32 {
+    info!("vcx_create_cancellation_token >>>");
+
+    sync_result::set_u32(cancellation::create_token(), cancellation_handle_p)
+}
+
+/// Cancels operations running with the token, the token stays cancelled.
+///
+/// #Params
+/// cancellation_handle: token created by `vcx_create_cancellation_token`
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_cancel(cancellation_handle: u32) -> u32 {
+    info!("vcx_cancel >>>");
+    trace!("vcx_cancel(cancellation_handle: {})", cancellation_handle);
+
+    match cancellation::cancel(cancellation_handle) {
+        Ok(()) => error::SUCCESS.code_num,
+        Err(err) => err.into()
+    }
+}
+
+/// Releases the cancellation token, operations running with it can not be cancelled anymore.
+///
+/// #Params
+/// cancellation_handle: token created by `vcx_create_cancellation_token`
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_cancellation_token_release(cancellation_handle: u32) -> u32 {
+    info!("vcx_cancellation_token_release >>>");
+    trace!("vcx_cancellation_token_release(cancellation_handle: {})", cancellation_handle);
+
+    match cancellation::release(cancellation_handle) {
+        Ok(()) => error::SUCCESS.code_num,
+        Err(err) => err.into()
+    }
+}
+
+/// Provision an agent in agency which requires sponsored onboarding, populate configuration and wallet for this agent.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// config: configuration
+///
+/// token: provision token given by the sponsor
+///     {
+///         "sponseeId": string,
+///         "sponsorId": string,
+///         "nonce": string,
+///         "timestamp": string,
+///         "sig": string, // signature of the sponsor over nonce, timestamp and sponseeId
+///         "sponsorVerKey": string
+///     }
+///
+/// cb: Callback that provides configuration or error status
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_provision_agent_with_token(command_handle: CommandHandle,
+                                             config: *const c_char,
+                                             token: *const c_char,
+                                             cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, config: *const c_char)>) -> u32 {
+    info!("vcx_provision_agent_with_token >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(config, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(token, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_provision_agent_with_token(command_handle: {}, config: {}, token: {})",
+           command_handle, settings::redact_config(&config), token);
+
+    thread::spawn(move || {
+        match ::agent::provision_with_token(&config, &token) {
+            Err(e) => {
+                error!("vcx_provision_agent_with_token_cb(command_handle: {}, rc: {}, config: NULL", command_handle, e);
+                cb(command_handle, e.into(), ptr::null_mut());
+            }
+            Ok(s) => {
+                trace!("vcx_provision_agent_with_token_cb(command_handle: {}, rc: {}, config: {})",
+                       command_handle, error::SUCCESS.message, settings::redact_config(&s));
+                let msg = CStringUtils::string_to_cstring(s);
+                cb(command_handle, 0, msg.as_ptr());
+            }
+        }
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Provision an agent for an end user on behalf of the caller acting as a sponsor. Settings and wallet
+/// of the caller are not affected, the user's wallet is optionally exported for the app to restore it.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// subject_config: provisioning configuration of the user, the same as for vcx_provision_agent. `wallet_name` is required,
+///     the wallet must not exist yet and it is deleted when provisioning or export fails. Optional fields:
+///     {
+///         "provision_token": {..}, // token for agency onboarding only sponsored identities, see vcx_provision_agent_with_token
+///         "exported_wallet_path": string, // the wallet is exported into this file and removed
+///         "backup_key": string // key the exported wallet is encrypted by, required with exported_wallet_path
+///     }
+///
+/// cb: Callback that provides handoff bundle or error status
+///     {
+///         "config": {..}, // provisioned configuration of the user
+///         "exported_wallet_path": string // present when the wallet was exported, restored by vcx_import_state
+///     }
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_provision_agent_for(command_handle: CommandHandle,
+                                      subject_config: *const c_char,
+                                      cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, bundle: *const c_char)>) -> u32 {
+    info!("vcx_provision_agent_for >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(subject_config, VcxErrorKind::InvalidOption);
+
+    // config holds wallet and backup keys of the user
+    trace!("vcx_provision_agent_for(command_handle: {})", command_handle);
+
+    thread::spawn(move || {
+        match ::agent::provision_for(&subject_config) {
+            Err(e) => {
+                error!("vcx_provision_agent_for_cb(command_handle: {}, rc: {}, bundle: NULL", command_handle, e);
+                cb(command_handle, e.into(), ptr::null_mut());
+            }
+            Ok(s) => {
+                trace!("vcx_provision_agent_for_cb(command_handle: {}, rc: {})",
+                       command_handle, error::SUCCESS.message);
+                let msg = CStringUtils::string_to_cstring(s);
+                cb(command_handle, 0, msg.as_ptr());
+            }
+        }
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Starts provisioning of an agent in the agency: creates the wallet with new keys and connects to the agency.
+/// Returned data should be confirmed by the user before provisioning is finished by vcx_provision_cloud_agent_complete.
+/// The call can be repeated if interrupted, nothing is created in the agency yet.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// config: configuration
+///
+/// cb: Callback that provides provision preparation or error status
+///     {
+///         "agency_url": string,
+///         "agency_did": string,
+///         "agency_verkey": string, // key the agency authenticated its response with
+///         "agency_pw_did": string, // pairwise DID of the agency for this agent
+///         "agency_pw_verkey": string,
+///         "sdk_to_remote_did": string, // new DID of this agent
+///         "sdk_to_remote_verkey": string,
+///         "wallet_name": string
+///     }
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_provision_cloud_agent_prepare(command_handle: CommandHandle,
+                                                config: *const c_char,
+                                                cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, preparation: *const c_char)>) -> u32 {
+    info!("vcx_provision_cloud_agent_prepare >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(config, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_provision_cloud_agent_prepare(command_handle: {}, config: {})",
+           command_handle, settings::redact_config(&config));
+
+    thread::spawn(move || {
+        match messages::agent_utils::provision_cloud_agent_prepare(&config) {
+            Err(e) => {
+                error!("vcx_provision_cloud_agent_prepare_cb(command_handle: {}, rc: {}, preparation: NULL", command_handle, e);
+                cb(command_handle, e.into(), ptr::null_mut());
+            }
+            Ok(s) => {
+                trace!("vcx_provision_cloud_agent_prepare_cb(command_handle: {}, rc: {}, preparation: {})",
                        command_handle, error::SUCCESS.message, s);
                 let msg = CStringUtils::string_to_cstring(s);
                 cb(command_handle, 0, msg.as_ptr());
@@ -99,6 +370,53 @@ pub extern fn vcx_agent_provision_async(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Finishes provisioning started by vcx_provision_cloud_agent_prepare: registers in the agency and creates the agent.
+/// The wallet of the preparation is used and completed steps are recorded in it, the call can be repeated
+/// with the same preparation if interrupted.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// config: configuration, the same as passed to vcx_provision_cloud_agent_prepare
+///
+/// preparation: provision preparation returned by vcx_provision_cloud_agent_prepare
+///
+/// cb: Callback that provides configuration or error status
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_provision_cloud_agent_complete(command_handle: CommandHandle,
+                                                 config: *const c_char,
+                                                 preparation: *const c_char,
+                                                 cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, config: *const c_char)>) -> u32 {
+    info!("vcx_provision_cloud_agent_complete >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(config, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(preparation, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_provision_cloud_agent_complete(command_handle: {}, config: {}, preparation: {})",
+           command_handle, settings::redact_config(&config), preparation);
+
+    thread::spawn(move || {
+        match messages::agent_utils::provision_cloud_agent_complete(&config, &preparation) {
+            Err(e) => {
+                error!("vcx_provision_cloud_agent_complete_cb(command_handle: {}, rc: {}, config: NULL", command_handle, e);
+                cb(command_handle, e.into(), ptr::null_mut());
+            }
+            Ok(s) => {
+                trace!("vcx_provision_cloud_agent_complete_cb(command_handle: {}, rc: {}, config: {})",
+                       command_handle, error::SUCCESS.message, settings::redact_config(&s));
+                let msg = CStringUtils::string_to_cstring(s);
+                cb(command_handle, 0, msg.as_ptr());
+            }
+        }
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Update information on the agent (ie, comm method and type)
 ///
 /// #Params
@@ -183,6 +501,49 @@ pub extern fn vcx_ledger_get_fees(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Estimates the amount of tokens needed to write a transaction of given type to the ledger
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// txn_type: ledger transaction type, e.g. "101" for schema, "102" for credential definition,
+///           "113" for revocation registry definition, "114" for revocation registry entry, "10001" for transfer
+///
+/// cb: Callback that provides the estimated fees, zero when the write is free
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_ledger_estimate_fees(command_handle: CommandHandle,
+                                       txn_type: *const c_char,
+                                       cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, fees: u64)>) -> u32 {
+    info!("vcx_ledger_estimate_fees >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(txn_type, VcxErrorKind::InvalidOption);
+    trace!("vcx_ledger_estimate_fees(command_handle: {}, txn_type: {})",
+           command_handle, txn_type);
+
+    spawn(move || {
+        match ::utils::libindy::ledger::estimate_fees(&txn_type) {
+            Ok(x) => {
+                trace!("vcx_ledger_estimate_fees_cb(command_handle: {}, rc: {}, fees: {})",
+                       command_handle, error::SUCCESS.message, x);
+                cb(command_handle, error::SUCCESS.code_num, x);
+            }
+            Err(e) => {
+                warn!("vcx_ledger_estimate_fees_cb(command_handle: {}, rc: {}, fees: {})",
+                      command_handle, e, 0);
+                cb(command_handle, e.into(), 0);
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 #[no_mangle]
 pub extern fn vcx_set_next_agency_response(message_index: u32) {
     info!("vcx_set_next_agency_response >>>");
@@ -417,64 +778,808 @@ pub extern fn vcx_messages_update_status(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
-/// Set the pool handle before calling vcx_init_minimal
+/// Retrieve messages from the agent narrowed down to a thread and/or time range
 ///
 /// #params
 ///
-/// handle: pool handle that libvcx should use
+/// command_handle: command handle to map callback to user context.
 ///
-/// #Returns
-/// Error code as u32
-#[no_mangle]
-pub extern fn vcx_pool_set_handle(handle: i32) -> i32 {
-    if handle <= 0 { ::utils::libindy::pool::set_pool_handle(None); } else { ::utils::libindy::pool::set_pool_handle(Some(handle)); }
-
-    handle
-}
-
-/// Gets minimal request price for performing an action in case the requester can perform this action.
+/// message_status: optional, comma separated - query for messages with the specified status (MS-101 - MS-107)
 ///
-/// # Params
-/// action_json: {
-///     "auth_type": ledger transaction alias or associated value,
-///     "auth_action": type of an action.,
-///     "field": transaction field,
-///     "old_value": (Optional) old value of a field, which can be changed to a new_value (mandatory for EDIT action),
-///     "new_value": (Optional) new value that can be used to fill the field,
-/// }
-/// requester_info_json: (Optional) {
-///     "role": string - role of a user which can sign transaction.
-///     "count": string - count of users.
-///     "is_owner": bool - if user is an owner of transaction.
-/// } otherwise context info will be used
+/// uids: optional, comma separated - query for messages with the specified uids
 ///
-/// # Return
-/// "price": u64 - tokens amount required for action performing
+/// pw_dids: optional, comma separated - DID's pointing to specific connection
+///
+/// filter_json: restrictions applied to the messages, all fields are optional
+///     {"thread_id": "...", "from": "2020-05-01T00:00:00Z", "to": "2020-05-02T00:00:00Z"}
+///     `from` and `to` are compared with the time agency last updated the message,
+///     messages without such time are left out when either bound is set
+///
+/// cb: Callback that provides array of matching messages retrieved, in the format of `vcx_messages_download`
+///
+/// #Returns
+/// Error code as a u32
 #[no_mangle]
-pub extern fn vcx_get_request_price(command_handle: CommandHandle,
-                                    action_json: *const c_char,
-                                    requester_info_json: *const c_char,
-                                    cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, price: u64)>) -> u32 {
-    info!("vcx_get_request_price >>>");
+pub extern fn vcx_messages_download_filtered(command_handle: CommandHandle,
+                                             message_status: *const c_char,
+                                             uids: *const c_char,
+                                             pw_dids: *const c_char,
+                                             filter_json: *const c_char,
+                                             cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, messages: *const c_char)>) -> u32 {
+    info!("vcx_messages_download_filtered >>>");
 
     check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
-    check_useful_c_str!(action_json, VcxErrorKind::InvalidOption);
-    check_useful_opt_c_str!(requester_info_json, VcxErrorKind::InvalidOption);
-
-    trace!(target: "vcx", "vcx_get_request_price(command_handle: {}, action_json: {}, requester_info_json: {:?})",
-           command_handle, action_json, requester_info_json);
+    check_useful_c_str!(filter_json, VcxErrorKind::InvalidOption);
 
-    spawn(move || {
-        match payments::get_request_price(action_json, requester_info_json) {
-            Ok(x) => {
+    let message_status = if !message_status.is_null() {
+        check_useful_c_str!(message_status, VcxErrorKind::InvalidOption);
+        Some(message_status.split(',').map(|s| s.trim().to_string()).collect::<Vec<String>>())
+    } else {
+        None
+    };
+
+    let uids = if !uids.is_null() {
+        check_useful_c_str!(uids, VcxErrorKind::InvalidOption);
+        Some(uids.split(',').map(|s| s.trim().to_string()).collect::<Vec<String>>())
+    } else {
+        None
+    };
+
+    let pw_dids = if !pw_dids.is_null() {
+        check_useful_c_str!(pw_dids, VcxErrorKind::InvalidOption);
+        Some(pw_dids.split(',').map(|s| s.trim().to_string()).collect::<Vec<String>>())
+    } else {
+        None
+    };
+
+    let filter: ::messages::MessageFilter = match serde_json::from_str(&filter_json) {
+        Ok(filter) => filter,
+        Err(err) => return VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize message filter: {}", err)).into()
+    };
+
+    trace!("vcx_messages_download_filtered(command_handle: {}, message_status: {:?}, uids: {:?}, filter: {:?})",
+           command_handle, message_status, uids, filter);
+
+    spawn(move || {
+        match ::messages::download_messages_filtered(pw_dids, message_status, uids, &filter) {
+            Ok(messages) => {
+                let messages = json!(messages).to_string();
+                trace!("vcx_messages_download_filtered_cb(command_handle: {}, rc: {}, messages: {})",
+                       command_handle, error::SUCCESS.message, messages);
+
+                let messages = CStringUtils::string_to_cstring(messages);
+                cb(command_handle, error::SUCCESS.code_num, messages.as_ptr());
+            }
+            Err(e) => {
+                warn!("vcx_messages_download_filtered_cb(command_handle: {}, rc: {}, messages: {})",
+                      command_handle, e, "null");
+
+                cb(command_handle, e.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Count messages stored by agency for each message status
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// pw_dids: optional, comma separated - DID's pointing to specific connection
+///
+/// cb: Callback that provides number of messages by status code, statuses without messages are omitted
+///     {"MS-103": 2, "MS-106": 5}
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_messages_get_counts_by_status(command_handle: CommandHandle,
+                                                pw_dids: *const c_char,
+                                                cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, counts: *const c_char)>) -> u32 {
+    info!("vcx_messages_get_counts_by_status >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    let pw_dids = if !pw_dids.is_null() {
+        check_useful_c_str!(pw_dids, VcxErrorKind::InvalidOption);
+        Some(pw_dids.split(',').map(|s| s.trim().to_string()).collect::<Vec<String>>())
+    } else {
+        None
+    };
+
+    trace!("vcx_messages_get_counts_by_status(command_handle: {}, pw_dids: {:?})", command_handle, pw_dids);
+
+    spawn(move || {
+        match ::messages::get_message_counts_by_status(pw_dids) {
+            Ok(counts) => {
+                let counts = json!(counts).to_string();
+                trace!("vcx_messages_get_counts_by_status_cb(command_handle: {}, rc: {}, counts: {})",
+                       command_handle, error::SUCCESS.message, counts);
+
+                let counts = CStringUtils::string_to_cstring(counts);
+                cb(command_handle, error::SUCCESS.code_num, counts.as_ptr());
+            }
+            Err(e) => {
+                warn!("vcx_messages_get_counts_by_status_cb(command_handle: {}, rc: {})",
+                      command_handle, e);
+
+                cb(command_handle, e.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Update the status of messages identified by their uids, without knowing the connections they belong to
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// message_status: target message status, any of MS-101 - MS-107 (e.g. MS-105 - Rejected, MS-106 - Reviewed)
+///
+/// uids: comma separated uids of messages to update
+///
+/// cb: Callback that provides success or failure of request
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_messages_update_status_by_uids(command_handle: CommandHandle,
+                                                 message_status: *const c_char,
+                                                 uids: *const c_char,
+                                                 cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32)>) -> u32 {
+    info!("vcx_messages_update_status_by_uids >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(message_status, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(uids, VcxErrorKind::InvalidOption);
+
+    let status_code: ::messages::MessageStatusCode = match serde_json::from_value(json!(message_status)) {
+        Ok(status_code) => status_code,
+        Err(err) => return VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Cannot parse message status code: {}", err)).into()
+    };
+    let uids: Vec<String> = uids.split(',').map(|s| s.trim().to_string()).collect();
+
+    trace!("vcx_messages_update_status_by_uids(command_handle: {}, message_status: {:?}, uids: {:?})",
+           command_handle, status_code, uids);
+
+    spawn(move || {
+        match ::messages::update_messages_status(uids, status_code) {
+            Ok(()) => {
+                trace!("vcx_messages_update_status_by_uids_cb(command_handle: {}, rc: {})",
+                       command_handle, error::SUCCESS.message);
+
+                cb(command_handle, error::SUCCESS.code_num);
+            }
+            Err(e) => {
+                warn!("vcx_messages_update_status_by_uids_cb(command_handle: {}, rc: {})",
+                      command_handle, e);
+
+                cb(command_handle, e.into());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Download messages received on all connections by a single agency request and update state
+/// of all connection, credential and proof objects using them.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// cb: Callback that provides list of objects which changed state
+///     [{"object_type": "connection", "handle": 1, "prev_state": 2, "state": 4}, ...]
+///     object_type is one of `connection`, `credential`, `issuer_credential`, `disclosed_proof`, `proof`
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_update_all_states(command_handle: CommandHandle,
+                                    cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, changes: *const c_char)>) -> u32 {
+    info!("vcx_update_all_states >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    trace!("vcx_update_all_states(command_handle: {})", command_handle);
+
+    spawn(move || {
+        match ::messages::update_all_states() {
+            Ok(changes) => {
+                let changes = json!(changes).to_string();
+                trace!("vcx_update_all_states_cb(command_handle: {}, rc: {}, changes: {})",
+                       command_handle, error::SUCCESS.message, changes);
+
+                let changes = CStringUtils::string_to_cstring(changes);
+                cb(command_handle, error::SUCCESS.code_num, changes.as_ptr());
+            }
+            Err(e) => {
+                warn!("vcx_update_all_states_cb(command_handle: {}, rc: {})",
+                      command_handle, e);
+
+                cb(command_handle, e.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Try to deliver messages which could not be sent due to network errors and were kept
+/// in the outbound queue (see `outbound_queue` config option).
+/// Messages are delivered in the order they were queued.
+/// Objects whose messages wait in the queue report state VcxStateSending (10), the ones whose
+/// message was rejected by the receiver report state VcxStateRejected (9).
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// cb: Callback that provides number of messages still waiting for delivery
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_flush_outbound_queue(command_handle: CommandHandle,
+                                       cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, pending: u32)>) -> u32 {
+    info!("vcx_flush_outbound_queue >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    trace!("vcx_flush_outbound_queue(command_handle: {})", command_handle);
+
+    spawn(move || {
+        match ::outbound_queue::flush() {
+            Ok(pending) => {
+                trace!("vcx_flush_outbound_queue_cb(command_handle: {}, rc: {}, pending: {})",
+                       command_handle, error::SUCCESS.message, pending);
+                cb(command_handle, error::SUCCESS.code_num, pending);
+            }
+            Err(e) => {
+                warn!("vcx_flush_outbound_queue_cb(command_handle: {}, rc: {})",
+                      command_handle, e);
+                cb(command_handle, e.into(), 0);
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Decrypt inbound message and update state of the credential or proof object
+/// handling its thread (`~thread.thid`).
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// message: inbound message as received on the endpoint, authcrypted by the counterparty of the connection the object is bound to
+///
+/// cb: Callback that provides object which handled the message
+///     {"object_type": "proof", "handle": 1, "thread_id": "...", "state": 4}
+///     object_type is one of `credential`, `issuer_credential`, `disclosed_proof`, `proof`
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_handle_inbound_message(command_handle: CommandHandle,
+                                         message: *const c_char,
+                                         cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, result: *const c_char)>) -> u32 {
+    info!("vcx_handle_inbound_message >>>");
+
+    check_useful_c_str!(message, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    trace!("vcx_handle_inbound_message(command_handle: {}, message: {})", command_handle, secret!(&message));
+
+    spawn(move || {
+        match ::aries::utils::message_router::handle_inbound_message(&message) {
+            Ok(routed) => {
+                let routed = json!(routed).to_string();
+                trace!("vcx_handle_inbound_message_cb(command_handle: {}, rc: {}, result: {})",
+                       command_handle, error::SUCCESS.message, routed);
+
+                let routed = CStringUtils::string_to_cstring(routed);
+                cb(command_handle, error::SUCCESS.code_num, routed.as_ptr());
+            }
+            Err(e) => {
+                warn!("vcx_handle_inbound_message_cb(command_handle: {}, rc: {})",
+                      command_handle, e);
+
+                cb(command_handle, e.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Register callback called whenever any connection, credential or proof object changes its state.
+/// Previously registered callback is replaced.
+/// Callback is called on the thread causing the state change and should return quickly.
+///
+/// #params
+///
+/// cb: Callback that provides the state change
+///     {"handle_type": "proof", "handle": 1, "old_state": 2, "new_state": 4, "thread_id": "..."}
+///     handle_type is one of `connection`, `credential`, `issuer_credential`, `disclosed_proof`, `proof`
+///
+/// #Returns
+/// Error code as u32
+#[no_mangle]
+pub extern fn vcx_register_state_change_callback(cb: Option<extern fn(event: *const c_char)>) -> u32 {
+    info!("vcx_register_state_change_callback >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    events::set_callback(Some(Arc::new(move |event: &StateChangeEvent| {
+        let event = CStringUtils::string_to_cstring(json!(event).to_string());
+        cb(event.as_ptr());
+    })));
+
+    error::SUCCESS.code_num
+}
+
+/// Unregister callback set by `vcx_register_state_change_callback`.
+///
+/// #Returns
+/// Error code as u32
+#[no_mangle]
+pub extern fn vcx_unregister_state_change_callback() -> u32 {
+    info!("vcx_unregister_state_change_callback >>>");
+
+    events::set_callback(None);
+
+    error::SUCCESS.code_num
+}
+
+/// Set the pool handle before calling vcx_init_minimal
+///
+/// #params
+///
+/// handle: pool handle that libvcx should use
+///
+/// #Returns
+/// Error code as u32
+#[no_mangle]
+pub extern fn vcx_pool_set_handle(handle: i32) -> i32 {
+    if handle <= 0 { ::utils::libindy::pool::set_pool_handle(None); } else { ::utils::libindy::pool::set_pool_handle(Some(handle)); }
+
+    handle
+}
+
+/// Gets minimal request price for performing an action in case the requester can perform this action.
+///
+/// # Params
+/// action_json: {
+///     "auth_type": ledger transaction alias or associated value,
+///     "auth_action": type of an action.,
+///     "field": transaction field,
+///     "old_value": (Optional) old value of a field, which can be changed to a new_value (mandatory for EDIT action),
+///     "new_value": (Optional) new value that can be used to fill the field,
+/// }
+/// requester_info_json: (Optional) {
+///     "role": string - role of a user which can sign transaction.
+///     "count": string - count of users.
+///     "is_owner": bool - if user is an owner of transaction.
+/// } otherwise context info will be used
+///
+/// # Return
+/// "price": u64 - tokens amount required for action performing
+#[no_mangle]
+pub extern fn vcx_get_request_price(command_handle: CommandHandle,
+                                    action_json: *const c_char,
+                                    requester_info_json: *const c_char,
+                                    cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, price: u64)>) -> u32 {
+    info!("vcx_get_request_price >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(action_json, VcxErrorKind::InvalidOption);
+    check_useful_opt_c_str!(requester_info_json, VcxErrorKind::InvalidOption);
+
+    trace!(target: "vcx", "vcx_get_request_price(command_handle: {}, action_json: {}, requester_info_json: {:?})",
+           command_handle, action_json, requester_info_json);
+
+    spawn(move || {
+        match payments::get_request_price(action_json, requester_info_json) {
+            Ok(x) => {
                 trace!(target: "vcx", "vcx_get_request_price(command_handle: {}, rc: {}, handle: {})",
                        command_handle, error::SUCCESS.message, x);
                 cb(command_handle, error::SUCCESS.code_num, x);
             }
-            Err(x) => {
-                warn!("vcx_get_request_price(command_handle: {}, rc: {}, handle: {})",
-                      command_handle, x, 0);
-                cb(command_handle, x.into(), 0);
+            Err(x) => {
+                warn!("vcx_get_request_price(command_handle: {}, rc: {}, handle: {})",
+                      command_handle, x, 0);
+                cb(command_handle, x.into(), 0);
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Endorse transaction to the ledger preserving an original author
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+/// transaction: transaction to endorse
+///
+/// cb: Callback that provides success or failure of command
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_endorse_transaction(command_handle: CommandHandle,
+                                      transaction: *const c_char,
+                                      cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32)>) -> u32 {
+    info!("vcx_endorse_transaction >>>");
+
+    check_useful_c_str!(transaction, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    trace!("vcx_endorse_transaction(command_handle: {}, transaction: {})",
+           command_handle, transaction);
+
+    spawn(move || {
+        match ::utils::libindy::ledger::endorse_transaction(&transaction) {
+            Ok(()) => {
+                trace!("vcx_endorse_transaction(command_handle: {}, rc: {})",
+                       command_handle, error::SUCCESS.message);
+
+                cb(command_handle, error::SUCCESS.code_num);
+            }
+            Err(e) => {
+                warn!("vcx_endorse_transaction(command_handle: {}, rc: {})",
+                      command_handle, e);
+
+                cb(command_handle, e.into());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Remove ledger objects (schemas, credential definitions, revocation registry definitions)
+/// from the in-memory ledger cache enabled by `ledger_cache_ttl` configuration value.
+///
+/// #params
+///
+/// max_age: only objects fetched more than `max_age` seconds ago are removed, 0 removes all objects
+///
+/// #Returns
+/// Error code as u32
+#[no_mangle]
+pub extern fn vcx_ledger_cache_purge(max_age: u64) -> u32 {
+    info!("vcx_ledger_cache_purge >>>");
+    trace!("vcx_ledger_cache_purge(max_age: {})", max_age);
+
+    ledger_cache::purge(Some(Duration::from_secs(max_age)));
+
+    error::SUCCESS.code_num
+}
+
+/// Fetch ledger objects into the ledger cache, so that following proof generation or verification
+/// does not need to wait for the ledger.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+/// ids_json: identifiers of objects to fetch
+///     {
+///         "schema_ids": Optional<[string]>,
+///         "cred_def_ids": Optional<[string]>,
+///         "rev_reg_def_ids": Optional<[string]>,
+///     }
+///
+/// cb: Callback that provides success or failure of command
+///
+/// #Returns
+/// Error code as u32
+#[no_mangle]
+pub extern fn vcx_ledger_cache_warm(command_handle: CommandHandle,
+                                    ids_json: *const c_char,
+                                    cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32)>) -> u32 {
+    info!("vcx_ledger_cache_warm >>>");
+
+    check_useful_c_str!(ids_json, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    trace!("vcx_ledger_cache_warm(command_handle: {}, ids_json: {})",
+           command_handle, ids_json);
+
+    let ids: LedgerCacheWarmUp = match serde_json::from_str(&ids_json) {
+        Ok(ids) => ids,
+        Err(err) => return VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize ledger object ids: {}", err)).into()
+    };
+
+    spawn(move || {
+        match ledger_cache::warm(&ids) {
+            Ok(()) => {
+                trace!("vcx_ledger_cache_warm(command_handle: {}, rc: {})",
+                       command_handle, error::SUCCESS.message);
+
+                cb(command_handle, error::SUCCESS.code_num);
+            }
+            Err(e) => {
+                warn!("vcx_ledger_cache_warm(command_handle: {}, rc: {})",
+                      command_handle, e);
+
+                cb(command_handle, e.into());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+fn _handle_to_token(object_type: &str, handle: u32) -> VcxResult<String> {
+    match object_type {
+        "connection" => ::connection::to_token(handle),
+        "credential" => ::credential::to_token(handle),
+        "disclosed_proof" => ::disclosed_proof::to_token(handle),
+        "issuer_credential" => ::issuer_credential::to_token(handle),
+        "proof" => ::proof::to_token(handle),
+        _ => Err(VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Unsupported object type: {}", object_type)))
+    }
+}
+
+fn _handle_from_token(object_type: &str, token: &str) -> VcxResult<u32> {
+    match object_type {
+        "connection" => ::connection::from_token(token),
+        "credential" => ::credential::from_token(token),
+        "disclosed_proof" => ::disclosed_proof::from_token(token),
+        "issuer_credential" => ::issuer_credential::from_token(token),
+        "proof" => ::proof::from_token(token),
+        _ => Err(VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Unsupported object type: {}", object_type)))
+    }
+}
+
+/// Export protocol object as a token protected by `handle_token_key` configuration value.
+/// The token can be passed to another process sharing the key and restored there by `vcx_handle_from_token`.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+/// object_type: one of `connection`, `credential`, `disclosed_proof`, `issuer_credential`, `proof`
+/// handle: handle of the object
+///
+/// cb: Callback that provides the token
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_handle_to_token(command_handle: CommandHandle,
+                                  object_type: *const c_char,
+                                  handle: u32,
+                                  cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, token: *const c_char)>) -> u32 {
+    info!("vcx_handle_to_token >>>");
+
+    check_useful_c_str!(object_type, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    trace!("vcx_handle_to_token(command_handle: {}, object_type: {}, handle: {})",
+           command_handle, object_type, handle);
+
+    spawn(move || {
+        match _handle_to_token(&object_type, handle) {
+            Ok(token) => {
+                trace!("vcx_handle_to_token_cb(command_handle: {}, rc: {})",
+                       command_handle, error::SUCCESS.message);
+                let token = CStringUtils::string_to_cstring(token);
+                cb(command_handle, error::SUCCESS.code_num, token.as_ptr());
+            }
+            Err(e) => {
+                warn!("vcx_handle_to_token_cb(command_handle: {}, rc: {})",
+                      command_handle, e);
+                cb(command_handle, e.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Restore protocol object from a token created by `vcx_handle_to_token`.
+/// Fails when the token was modified or created with a different `handle_token_key`.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+/// object_type: one of `connection`, `credential`, `disclosed_proof`, `issuer_credential`, `proof`
+/// token: token holding the object
+///
+/// cb: Callback that provides handle of the restored object
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_handle_from_token(command_handle: CommandHandle,
+                                    object_type: *const c_char,
+                                    token: *const c_char,
+                                    cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, handle: u32)>) -> u32 {
+    info!("vcx_handle_from_token >>>");
+
+    check_useful_c_str!(object_type, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(token, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    trace!("vcx_handle_from_token(command_handle: {}, object_type: {})",
+           command_handle, object_type);
+
+    spawn(move || {
+        match _handle_from_token(&object_type, &token) {
+            Ok(handle) => {
+                trace!("vcx_handle_from_token_cb(command_handle: {}, rc: {}, handle: {})",
+                       command_handle, error::SUCCESS.message, handle);
+                cb(command_handle, error::SUCCESS.code_num, handle);
+            }
+            Err(e) => {
+                warn!("vcx_handle_from_token_cb(command_handle: {}, rc: {})",
+                      command_handle, e);
+                cb(command_handle, e.into(), 0);
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+fn _handle_by_source_id(object_type: &str, source_id: &str) -> VcxResult<u32> {
+    match object_type {
+        "connection" => ::connection::get_handle_by_source_id(source_id),
+        "credential" => ::credential::get_handle_by_source_id(source_id),
+        "disclosed_proof" => ::disclosed_proof::get_handle_by_source_id(source_id),
+        "issuer_credential" => ::issuer_credential::get_handle_by_source_id(source_id),
+        "proof" => ::proof::get_handle_by_source_id(source_id),
+        _ => Err(VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Unsupported object type: {}", object_type)))
+    }
+}
+
+fn _handle_by_thread_id(object_type: &str, thread_id: &str) -> VcxResult<u32> {
+    match object_type {
+        "credential" => ::credential::get_handle_by_thread_id(thread_id),
+        "disclosed_proof" => ::disclosed_proof::get_handle_by_thread_id(thread_id),
+        "issuer_credential" => ::issuer_credential::get_handle_by_thread_id(thread_id),
+        "proof" => ::proof::get_handle_by_thread_id(thread_id),
+        _ => Err(VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Unsupported object type: {}", object_type)))
+    }
+}
+
+fn _set_metadata(object_type: &str, handle: u32, key: &str, value: Option<&str>) -> VcxResult<()> {
+    match object_type {
+        "connection" => ::connection::set_metadata(handle, key, value),
+        "credential" => ::credential::set_metadata(handle, key, value),
+        "disclosed_proof" => ::disclosed_proof::set_metadata(handle, key, value),
+        "issuer_credential" => ::issuer_credential::set_metadata(handle, key, value),
+        "proof" => ::proof::set_metadata(handle, key, value),
+        _ => Err(VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Unsupported object type: {}", object_type)))
+    }
+}
+
+fn _get_metadata(object_type: &str, handle: u32) -> VcxResult<String> {
+    match object_type {
+        "connection" => ::connection::get_metadata(handle),
+        "credential" => ::credential::get_metadata(handle),
+        "disclosed_proof" => ::disclosed_proof::get_metadata(handle),
+        "issuer_credential" => ::issuer_credential::get_metadata(handle),
+        "proof" => ::proof::get_metadata(handle),
+        _ => Err(VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Unsupported object type: {}", object_type)))
+    }
+}
+
+fn _handles_by_metadata(object_type: &str, key: &str, value: &str) -> VcxResult<Vec<u32>> {
+    match object_type {
+        "connection" => Ok(::connection::find_handles_by_metadata(key, value)),
+        "credential" => Ok(::credential::find_handles_by_metadata(key, value)),
+        "disclosed_proof" => Ok(::disclosed_proof::find_handles_by_metadata(key, value)),
+        "issuer_credential" => Ok(::issuer_credential::find_handles_by_metadata(key, value)),
+        "proof" => Ok(::proof::find_handles_by_metadata(key, value)),
+        _ => Err(VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Unsupported object type: {}", object_type)))
+    }
+}
+
+/// Find handle of an existing protocol object by its source id.
+/// When more objects have the same source id, the most recently created or updated one is returned.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+/// object_type: one of `connection`, `credential`, `disclosed_proof`, `issuer_credential`, `proof`
+/// source_id: source id the object was created with
+///
+/// cb: Callback that provides handle of the object
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_get_handle_by_source_id(command_handle: CommandHandle,
+                                          object_type: *const c_char,
+                                          source_id: *const c_char,
+                                          cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, handle: u32)>) -> u32 {
+    info!("vcx_get_handle_by_source_id >>>");
+
+    check_useful_c_str!(object_type, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(source_id, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    trace!("vcx_get_handle_by_source_id(command_handle: {}, object_type: {}, source_id: {})",
+           command_handle, object_type, source_id);
+
+    spawn(move || {
+        match _handle_by_source_id(&object_type, &source_id) {
+            Ok(handle) => {
+                trace!("vcx_get_handle_by_source_id_cb(command_handle: {}, rc: {}, handle: {})",
+                       command_handle, error::SUCCESS.message, handle);
+                cb(command_handle, error::SUCCESS.code_num, handle);
+            }
+            Err(e) => {
+                warn!("vcx_get_handle_by_source_id_cb(command_handle: {}, rc: {})",
+                      command_handle, e);
+                cb(command_handle, e.into(), 0);
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Find handle of an existing protocol object by id of the protocol thread it takes part in,
+/// e.g. `thread_id` of an event passed to the state change callback or webhook.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+/// object_type: one of `credential`, `disclosed_proof`, `issuer_credential`, `proof`
+/// thread_id: id of the protocol thread
+///
+/// cb: Callback that provides handle of the object
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_get_handle_by_thread_id(command_handle: CommandHandle,
+                                          object_type: *const c_char,
+                                          thread_id: *const c_char,
+                                          cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, handle: u32)>) -> u32 {
+    info!("vcx_get_handle_by_thread_id >>>");
+
+    check_useful_c_str!(object_type, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(thread_id, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    trace!("vcx_get_handle_by_thread_id(command_handle: {}, object_type: {}, thread_id: {})",
+           command_handle, object_type, thread_id);
+
+    spawn(move || {
+        match _handle_by_thread_id(&object_type, &thread_id) {
+            Ok(handle) => {
+                trace!("vcx_get_handle_by_thread_id_cb(command_handle: {}, rc: {}, handle: {})",
+                       command_handle, error::SUCCESS.message, handle);
+                cb(command_handle, error::SUCCESS.code_num, handle);
+            }
+            Err(e) => {
+                warn!("vcx_get_handle_by_thread_id_cb(command_handle: {}, rc: {})",
+                      command_handle, e);
+                cb(command_handle, e.into(), 0);
             }
         };
 
@@ -484,39 +1589,107 @@ pub extern fn vcx_get_request_price(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
-/// Endorse transaction to the ledger preserving an original author
+/// Decrypt payload of agency push notification (FCM, APNS) so that application can preview the message
+/// and pass it to the object it belongs to. Payload is unpacked by keys of the opened wallet.
 ///
 /// #Params
 /// command_handle: command handle to map callback to user context.
-/// transaction: transaction to endorse
+/// payload_raw: a pointer to first byte of the encrypted payload
+/// payload_len: length of the payload
 ///
-/// cb: Callback that provides success or failure of command
+/// cb: Callback that provides the message with its metadata
+///
+/// # Example payload ->
+///     {
+///         "message": {...} - A2A message,
+///         "message_type": "issue-credential/1.0/offer-credential",
+///         "thread_id": Optional<str>,
+///         "sender_verkey": Optional<str> - none for anoncrypted payload,
+///         "recipient_verkey": str,
+///         "connection_handle": Optional<int> - connection with the recipient pairwise key,
+///         "object_type": Optional<str> - `credential`, `disclosed_proof`, `issuer_credential` or `proof`,
+///         "handle": Optional<int> - handle of the object taking part in the thread
+///     }
+///
+/// `object_type` and `handle` are resolved only for message authcrypted by the counterparty of `connection_handle`
+/// and only to an object of that connection.
 ///
 /// #Returns
 /// Error code as a u32
 #[no_mangle]
-pub extern fn vcx_endorse_transaction(command_handle: CommandHandle,
-                                      transaction: *const c_char,
-                                      cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32)>) -> u32 {
-    info!("vcx_endorse_transaction >>>");
+pub extern fn vcx_decrypt_push_payload(command_handle: CommandHandle,
+                                       payload_raw: *const u8,
+                                       payload_len: u32,
+                                       cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, payload: *const c_char)>) -> u32 {
+    info!("vcx_decrypt_push_payload >>>");
 
-    check_useful_c_str!(transaction, VcxErrorKind::InvalidOption);
     check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
-    trace!("vcx_endorse_transaction(command_handle: {}, transaction: {})",
-           command_handle, transaction);
+    check_useful_c_byte_array!(payload_raw, payload_len, VcxErrorKind::InvalidOption, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_decrypt_push_payload(command_handle: {}, payload_len: {})", command_handle, payload_len);
 
     spawn(move || {
-        match ::utils::libindy::ledger::endorse_transaction(&transaction) {
+        match messages::decrypt_push_payload(&payload_raw).and_then(|payload| payload.to_string()) {
+            Ok(payload) => {
+                trace!("vcx_decrypt_push_payload_cb(command_handle: {}, rc: {}, payload: {})",
+                       command_handle, error::SUCCESS.message, payload);
+                let payload = CStringUtils::string_to_cstring(payload);
+                cb(command_handle, error::SUCCESS.code_num, payload.as_ptr());
+            }
+            Err(e) => {
+                warn!("vcx_decrypt_push_payload_cb(command_handle: {}, rc: {})",
+                      command_handle, e);
+                cb(command_handle, e.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Attach application defined metadata to a protocol object.
+/// Metadata is kept in the serialized object and can be used to find the object by `vcx_find_handles_by_metadata`.
+/// Key `network` selects the ledger network (see `vcx_pool_add_network`) used for unqualified identifiers of the object.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+/// object_type: one of `connection`, `credential`, `disclosed_proof`, `issuer_credential`, `proof`
+/// handle: handle of the object
+/// key: metadata key
+/// value: metadata value, NULL removes the key
+///
+/// cb: Callback that provides success or failure of command
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_set_metadata(command_handle: CommandHandle,
+                               object_type: *const c_char,
+                               handle: u32,
+                               key: *const c_char,
+                               value: *const c_char,
+                               cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32)>) -> u32 {
+    info!("vcx_set_metadata >>>");
+
+    check_useful_c_str!(object_type, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(key, VcxErrorKind::InvalidOption);
+    check_useful_opt_c_str!(value, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    trace!("vcx_set_metadata(command_handle: {}, object_type: {}, handle: {}, key: {}, value: {:?})",
+           command_handle, object_type, handle, key, value);
+
+    spawn(move || {
+        match _set_metadata(&object_type, handle, &key, value.as_ref().map(String::as_str)) {
             Ok(()) => {
-                trace!("vcx_endorse_transaction(command_handle: {}, rc: {})",
+                trace!("vcx_set_metadata_cb(command_handle: {}, rc: {})",
                        command_handle, error::SUCCESS.message);
-
                 cb(command_handle, error::SUCCESS.code_num);
             }
             Err(e) => {
-                warn!("vcx_endorse_transaction(command_handle: {}, rc: {})",
+                warn!("vcx_set_metadata_cb(command_handle: {}, rc: {})",
                       command_handle, e);
-
                 cb(command_handle, e.into());
             }
         };
@@ -527,6 +1700,150 @@ pub extern fn vcx_endorse_transaction(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Get metadata attached to a protocol object by `vcx_set_metadata`.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+/// object_type: one of `connection`, `credential`, `disclosed_proof`, `issuer_credential`, `proof`
+/// handle: handle of the object
+///
+/// cb: Callback that provides metadata as JSON object: {"key": "value", ...}
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_get_metadata(command_handle: CommandHandle,
+                               object_type: *const c_char,
+                               handle: u32,
+                               cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, metadata: *const c_char)>) -> u32 {
+    info!("vcx_get_metadata >>>");
+
+    check_useful_c_str!(object_type, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    trace!("vcx_get_metadata(command_handle: {}, object_type: {}, handle: {})",
+           command_handle, object_type, handle);
+
+    spawn(move || {
+        match _get_metadata(&object_type, handle) {
+            Ok(metadata) => {
+                trace!("vcx_get_metadata_cb(command_handle: {}, rc: {}, metadata: {})",
+                       command_handle, error::SUCCESS.message, metadata);
+                let metadata = CStringUtils::string_to_cstring(metadata);
+                cb(command_handle, error::SUCCESS.code_num, metadata.as_ptr());
+            }
+            Err(e) => {
+                warn!("vcx_get_metadata_cb(command_handle: {}, rc: {})",
+                      command_handle, e);
+                cb(command_handle, e.into(), ptr::null());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Find handles of existing protocol objects having metadata `key` set to `value`.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+/// object_type: one of `connection`, `credential`, `disclosed_proof`, `issuer_credential`, `proof`
+/// key: metadata key
+/// value: metadata value
+///
+/// cb: Callback that provides JSON array of handles, empty when no object matches
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_find_handles_by_metadata(command_handle: CommandHandle,
+                                           object_type: *const c_char,
+                                           key: *const c_char,
+                                           value: *const c_char,
+                                           cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, handles: *const c_char)>) -> u32 {
+    info!("vcx_find_handles_by_metadata >>>");
+
+    check_useful_c_str!(object_type, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(key, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(value, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    trace!("vcx_find_handles_by_metadata(command_handle: {}, object_type: {}, key: {}, value: {})",
+           command_handle, object_type, key, value);
+
+    spawn(move || {
+        match _handles_by_metadata(&object_type, &key, &value) {
+            Ok(handles) => {
+                let handles = json!(handles).to_string();
+                trace!("vcx_find_handles_by_metadata_cb(command_handle: {}, rc: {}, handles: {})",
+                       command_handle, error::SUCCESS.message, handles);
+                let handles = CStringUtils::string_to_cstring(handles);
+                cb(command_handle, error::SUCCESS.code_num, handles.as_ptr());
+            }
+            Err(e) => {
+                warn!("vcx_find_handles_by_metadata_cb(command_handle: {}, rc: {})",
+                      command_handle, e);
+                cb(command_handle, e.into(), ptr::null());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Convert object serialized by an older library version into the current serialization format,
+/// so that it can be deserialized by the respective `vcx_*_deserialize` function.
+///
+/// Legacy proprietary connections get metadata `reestablish` set to "true", they have to be established again over aries.
+/// Accepted invitee connections keep the peer's keys and endpoint, other connections start over with fresh keys.
+/// Proprietary credentials are converted once stored in the wallet. Other proprietary objects (exchanges in progress,
+/// issuer credentials and proofs) cannot be continued over aries and fail with `ActionNotSupported`.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+/// serialized_object: serialized connection, credential, issuer credential, proof or disclosed proof
+/// target_version: serialization version to convert into, "4.0" for connections and "2.0" for other objects
+///
+/// cb: Callback that provides the object in the target serialization format
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_convert_serialized_object(command_handle: CommandHandle,
+                                            serialized_object: *const c_char,
+                                            target_version: *const c_char,
+                                            cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, converted: *const c_char)>) -> u32 {
+    info!("vcx_convert_serialized_object >>>");
+
+    check_useful_c_str!(serialized_object, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(target_version, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    trace!("vcx_convert_serialized_object(command_handle: {}, target_version: {})",
+           command_handle, target_version);
+
+    spawn(move || {
+        match migration::convert_object(&serialized_object, &target_version) {
+            Ok(converted) => {
+                trace!("vcx_convert_serialized_object_cb(command_handle: {}, rc: {})",
+                       command_handle, error::SUCCESS.message);
+                let converted = CStringUtils::string_to_cstring(converted);
+                cb(command_handle, error::SUCCESS.code_num, converted.as_ptr());
+            }
+            Err(e) => {
+                warn!("vcx_convert_serialized_object_cb(command_handle: {}, rc: {})",
+                      command_handle, e);
+                cb(command_handle, e.into(), ptr::null());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 #[cfg(test)]
 mod tests {
     use std::ffi::CString;
@@ -574,6 +1891,53 @@ mod tests {
         let _config: serde_json::Value = serde_json::from_str(&result.unwrap()).unwrap();
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_provision_agent_with_token() {
+        let _setup = SetupAriesMocks::init();
+
+        let token = ::messages::agent_utils::tests::_provision_token();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        let rc = vcx_provision_agent_with_token(cb.command_handle,
+                                                CString::new(CONFIG_V3).unwrap().into_raw(),
+                                                CString::new(json!(token).to_string()).unwrap().into_raw(),
+                                                Some(cb.get_callback()));
+        assert_eq!(rc, error::SUCCESS.code_num);
+        let config: serde_json::Value = serde_json::from_str(&cb.receive(TimeoutUtils::some_short()).unwrap().unwrap()).unwrap();
+        assert_eq!(json!("DnEpUQJLupa5rKPkrKUpFd"), config["remote_to_sdk_did"]);
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        let rc = vcx_provision_agent_with_token(cb.command_handle,
+                                                CString::new(CONFIG_V3).unwrap().into_raw(),
+                                                CString::new("{}").unwrap().into_raw(),
+                                                Some(cb.get_callback()));
+        assert_eq!(rc, error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_short()).unwrap_err(), error::INVALID_JSON.code_num);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_provision_cloud_agent_prepare_complete() {
+        let _setup = SetupAriesMocks::init();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        let rc = vcx_provision_cloud_agent_prepare(cb.command_handle,
+                                                   CString::new(CONFIG_V3).unwrap().into_raw(),
+                                                   Some(cb.get_callback()));
+        assert_eq!(rc, error::SUCCESS.code_num);
+        let preparation = cb.receive(TimeoutUtils::some_short()).unwrap().unwrap();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        let rc = vcx_provision_cloud_agent_complete(cb.command_handle,
+                                                    CString::new(CONFIG_V3).unwrap().into_raw(),
+                                                    CString::new(preparation).unwrap().into_raw(),
+                                                    Some(cb.get_callback()));
+        assert_eq!(rc, error::SUCCESS.code_num);
+        let config: serde_json::Value = serde_json::from_str(&cb.receive(TimeoutUtils::some_short()).unwrap().unwrap()).unwrap();
+        assert_eq!(json!("DnEpUQJLupa5rKPkrKUpFd"), config["remote_to_sdk_did"]);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_create_agent_fails() {
@@ -661,5 +2025,201 @@ mod tests {
                    error::SUCCESS.code_num);
         cb.receive(TimeoutUtils::some_medium()).unwrap();
     }
-}
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_messages_download_filtered() {
+        let _setup = SetupAriesMocks::init();
+
+        let filter = CString::new(r#"{"thread_id":"thid"}"#).unwrap().into_raw();
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_messages_download_filtered(cb.command_handle, ptr::null_mut(), ptr::null_mut(), ptr::null_mut(), filter, Some(cb.get_callback())), error::SUCCESS.code_num);
+        let messages = cb.receive(TimeoutUtils::some_medium()).unwrap().unwrap();
+        assert_eq!(messages, "[]");
+
+        let filter = CString::new("not json").unwrap().into_raw();
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_messages_download_filtered(cb.command_handle, ptr::null_mut(), ptr::null_mut(), ptr::null_mut(), filter, Some(cb.get_callback())), error::INVALID_JSON.code_num);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_messages_get_counts_by_status() {
+        let _setup = SetupAriesMocks::init();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_messages_get_counts_by_status(cb.command_handle, ptr::null_mut(), Some(cb.get_callback())), error::SUCCESS.code_num);
+        let counts = cb.receive(TimeoutUtils::some_medium()).unwrap().unwrap();
+        assert_eq!(counts, "{}");
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_messages_update_status_by_uids() {
+        let _setup = SetupAriesMocks::init();
+
+        let status = CString::new("MS-105").unwrap().into_raw();
+        let uids = CString::new("uid1, uid2").unwrap().into_raw();
+        let cb = return_types_u32::Return_U32::new().unwrap();
+        assert_eq!(vcx_messages_update_status_by_uids(cb.command_handle, status, uids, Some(cb.get_callback())), error::SUCCESS.code_num);
+        cb.receive(TimeoutUtils::some_medium()).unwrap();
+
+        let status = CString::new("MS-999").unwrap().into_raw();
+        let uids = CString::new("uid1").unwrap().into_raw();
+        let cb = return_types_u32::Return_U32::new().unwrap();
+        assert_eq!(vcx_messages_update_status_by_uids(cb.command_handle, status, uids, Some(cb.get_callback())), error::INVALID_OPTION.code_num);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_handle_token_round_trip() {
+        let _setup = SetupAriesMocks::init();
+        ::settings::set_config_value(::settings::CONFIG_HANDLE_TOKEN_KEY, "secret");
+
+        let connection_handle = ::connection::tests::build_test_connection_inviter_requested();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_handle_to_token(cb.command_handle,
+                                       CString::new("connection").unwrap().into_raw(),
+                                       connection_handle,
+                                       Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        let token = cb.receive(TimeoutUtils::some_medium()).unwrap().unwrap();
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_handle_from_token(cb.command_handle,
+                                         CString::new("connection").unwrap().into_raw(),
+                                         CString::new(token.clone()).unwrap().into_raw(),
+                                         Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        let restored_handle = cb.receive(TimeoutUtils::some_medium()).unwrap();
+        assert_eq!(::connection::to_string(restored_handle).unwrap(), ::connection::to_string(connection_handle).unwrap());
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_handle_from_token(cb.command_handle,
+                                         CString::new("proof").unwrap().into_raw(),
+                                         CString::new(token).unwrap().into_raw(),
+                                         Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap_err(), error::INVALID_HANDLE_TOKEN.code_num);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_ledger_cache_warm_and_purge() {
+        let _setup = SetupAriesMocks::init();
+
+        let ids = json!({"schema_ids": [SCHEMA_ID], "cred_def_ids": [CRED_DEF_ID]}).to_string();
+
+        let cb = return_types_u32::Return_U32::new().unwrap();
+        assert_eq!(vcx_ledger_cache_warm(cb.command_handle,
+                                         CString::new(ids.clone()).unwrap().into_raw(),
+                                         Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap_err(), error::INVALID_CONFIGURATION.code_num);
+
+        ::settings::set_config_value(::settings::CONFIG_LEDGER_CACHE_TTL, "60");
+        let cb = return_types_u32::Return_U32::new().unwrap();
+        assert_eq!(vcx_ledger_cache_warm(cb.command_handle,
+                                         CString::new(ids).unwrap().into_raw(),
+                                         Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        cb.receive(TimeoutUtils::some_medium()).unwrap();
+
+        let cb = return_types_u32::Return_U32::new().unwrap();
+        assert_eq!(vcx_ledger_cache_warm(cb.command_handle,
+                                         CString::new("[]").unwrap().into_raw(),
+                                         Some(cb.get_callback())),
+                   error::INVALID_JSON.code_num);
+
+        assert_eq!(vcx_ledger_cache_purge(0), error::SUCCESS.code_num);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_update_all_states() {
+        let _setup = SetupAriesMocks::init();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_update_all_states(cb.command_handle, Some(cb.get_callback())), error::SUCCESS.code_num);
+        let changes = cb.receive(TimeoutUtils::some_medium()).unwrap().unwrap();
+        ::serde_json::from_str::<Vec<::messages::dispatch::StateChange>>(&changes).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_flush_outbound_queue() {
+        let _setup = SetupAgencyMock::init();
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_flush_outbound_queue(cb.command_handle, Some(cb.get_callback())), error::SUCCESS.code_num);
+        assert_eq!(0, cb.receive(TimeoutUtils::some_medium()).unwrap());
+    }
+
+    extern "C" fn _state_change_cb(_event: *const c_char) {}
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_register_state_change_callback() {
+        let _setup = SetupAriesMocks::init();
+
+        assert_eq!(vcx_register_state_change_callback(None), error::INVALID_OPTION.code_num);
+        assert_eq!(vcx_register_state_change_callback(Some(_state_change_cb)), error::SUCCESS.code_num);
+        assert_eq!(vcx_unregister_state_change_callback(), error::SUCCESS.code_num);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_handle_inbound_message_fails_for_unroutable_message() {
+        let _setup = SetupAriesMocks::init();
+
+        let message = json!({
+            "message": json!(::aries::messages::ack::tests::_ack().to_a2a_message()).to_string(),
+            "recipient_verkey": "GJ1SzoWzavQYfNL9XkaJdrQejfztN4XqdsiV4ct3LXKL",
+            "sender_verkey": "7y118tRW2EMJn18qs9MY5NJWYW2PLwV5QpaLyfoLHtgF",
+        }).to_string();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_handle_inbound_message(cb.command_handle,
+                                              CString::new(message).unwrap().into_raw(),
+                                              Some(cb.get_callback())), error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap_err(), error::UNROUTABLE_MESSAGE.code_num);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_handle_by_source_and_thread_id() {
+        let _setup = SetupAriesMocks::init();
+
+        let connection_handle = ::connection::tests::build_test_connection_inviter_requested();
+        let source_id = ::connection::get_source_id(connection_handle).unwrap();
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_get_handle_by_source_id(cb.command_handle,
+                                               CString::new("connection").unwrap().into_raw(),
+                                               CString::new(source_id).unwrap().into_raw(),
+                                               Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap(), connection_handle);
+
+        let credential_handle = ::credential::credential_create_with_offer("test_get_handle_by_thread_id", ::utils::mockdata::mockdata_credex::ARIES_CREDENTIAL_OFFER).unwrap();
+        let thread_id = ::credential::get_thread_id(credential_handle).unwrap();
+
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_get_handle_by_thread_id(cb.command_handle,
+                                               CString::new("credential").unwrap().into_raw(),
+                                               CString::new(thread_id.clone()).unwrap().into_raw(),
+                                               Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap(), credential_handle);
+
+        ::credential::release(credential_handle).unwrap();
+        let cb = return_types_u32::Return_U32_U32::new().unwrap();
+        assert_eq!(vcx_get_handle_by_thread_id(cb.command_handle,
+                                               CString::new("credential").unwrap().into_raw(),
+                                               CString::new(thread_id).unwrap().into_raw(),
+                                               Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap_err(), error::INVALID_CREDENTIAL_HANDLE.code_num);
+    }
+}
@@ -3,12 +3,17 @@ use std::ffi::CString;
 use indy::{CommandHandle, INVALID_WALLET_HANDLE};
 use libc::c_char;
 
+use api_rust::library;
+use aries::messages::localization::Localization;
 use error::prelude::*;
 use settings;
+use utils::capabilities;
 use utils::cstring::CStringUtils;
 use utils::error;
 use utils::libindy::{ledger, pool, wallet};
-use utils::libindy::pool::{init_pool, is_pool_open};
+use utils::libindy::pool::is_pool_open;
+use utils::metrics;
+use utils::persistence;
 use utils::threadpool::spawn;
 use utils::version_constants;
 
@@ -36,8 +41,8 @@ pub extern fn vcx_init_with_config(command_handle: CommandHandle,
     check_useful_c_str!(config,VcxErrorKind::InvalidOption);
     check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
 
-    trace!("vcx_init(command_handle: {}, config: {:?})",
-           command_handle, config);
+    trace!("vcx_init(command_handle: {}, config: {})",
+           command_handle, settings::redact_config(&config));
 
     if config == "ENABLE_TEST_MODE" {
         settings::set_config_value(settings::CONFIG_ENABLE_TEST_MODE, "true");
@@ -76,24 +81,15 @@ pub extern fn vcx_init_core(config: *const c_char) -> u32 {
     info!("libvcx version: {}{}", version_constants::VERSION, version_constants::REVISION);
 
     check_useful_c_str!(config, VcxErrorKind::InvalidOption);
-    info!("vcx_init_with_config :: config = {}", config);
+    info!("vcx_init_with_config :: config = {}", settings::redact_config(&config));
 
-    if config == "ENABLE_TEST_MODE" {
-        settings::set_config_value(settings::CONFIG_ENABLE_TEST_MODE, "true");
-        settings::set_defaults();
-    } else {
-        match settings::process_config_string(&config, true) {
-            Err(e) => {
-                error!("Invalid configuration specified: {}", e);
-                return e.into();
-            }
-            Ok(_) => (),
+    match library::init_core(&config) {
+        Ok(()) => error::SUCCESS.code_num,
+        Err(e) => {
+            error!("Invalid configuration specified: {}", e);
+            e.into()
         }
-    };
-
-    settings::log_settings();
-    ::utils::threadpool::init();
-    error::SUCCESS.code_num
+    }
 }
 
 /// Opens pool based on vcx configuration previously set via vcx_init_core
@@ -112,18 +108,13 @@ pub extern fn vcx_open_pool(command_handle: CommandHandle, cb: extern fn(xcomman
         error!("vcx_open_pool :: Pool connection is already open.");
         return VcxError::from_msg(VcxErrorKind::AlreadyInitialized, "Pool connection is already open.").into();
     }
-    let path = match settings::get_config_value(settings::CONFIG_GENESIS_PATH) {
-        Ok(result) => result,
-        Err(_) => {
-            error!("vcx_open_pool :: Failed to init pool because CONFIG_GENESIS_PATH was not set");
-            return error::INVALID_CONFIGURATION.code_num;
-        }
-    };
-    let pool_name = settings::get_config_value(settings::CONFIG_POOL_NAME).unwrap_or(settings::DEFAULT_POOL_NAME.to_string());
-    let pool_config = settings::get_config_value(settings::CONFIG_POOL_CONFIG).ok();
+    if settings::get_config_value(settings::CONFIG_GENESIS_PATH).is_err() {
+        error!("vcx_open_pool :: Failed to init pool because CONFIG_GENESIS_PATH was not set");
+        return error::INVALID_CONFIGURATION.code_num;
+    }
 
     spawn(move || {
-        match init_pool(&pool_name, &path, pool_config.as_ref().map(String::as_str)) {
+        match library::open_pool() {
             Ok(()) => {
                 info!("vcx_open_pool :: Vcx Pool Init Successful");
                 cb(command_handle, error::SUCCESS.code_num)
@@ -139,6 +130,79 @@ pub extern fn vcx_open_pool(command_handle: CommandHandle, cb: extern fn(xcomman
     error::SUCCESS.code_num
 }
 
+/// Opens ledger network next to the pool opened by vcx_open_pool. Schemas, credential definitions and revocation
+/// registries with identifiers qualified by the network name (e.g. "did:indy:<name>:...") are resolved on this network.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// name: name of the network, e.g. "sovrin" or "sovrin:staging"
+///
+/// genesis_path: path to the genesis transactions of the network
+///
+/// pool_config: (optional) runtime configuration of the pool
+///
+/// cb: Callback that provides error status of opening
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_pool_add_network(command_handle: CommandHandle,
+                                   name: *const c_char,
+                                   genesis_path: *const c_char,
+                                   pool_config: *const c_char,
+                                   cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32)>) -> u32 {
+    info!("vcx_pool_add_network >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(name, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(genesis_path, VcxErrorKind::InvalidOption);
+    check_useful_opt_c_str!(pool_config, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_pool_add_network(command_handle: {}, name: {}, genesis_path: {}, pool_config: {:?})",
+           command_handle, name, genesis_path, pool_config);
+
+    spawn(move || {
+        match pool::add_network(&name, &genesis_path, pool_config.as_ref().map(String::as_str)) {
+            Ok(()) => {
+                trace!("vcx_pool_add_network_cb(command_handle: {}, rc: {})", command_handle, error::SUCCESS.message);
+                cb(command_handle, error::SUCCESS.code_num);
+            }
+            Err(x) => {
+                warn!("vcx_pool_add_network_cb(command_handle: {}, rc: {})", command_handle, x);
+                cb(command_handle, x.into());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Closes ledger network opened by vcx_pool_add_network.
+///
+/// #Params
+/// name: name of the network
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_pool_close_network(name: *const c_char) -> u32 {
+    info!("vcx_pool_close_network >>>");
+
+    check_useful_c_str!(name, VcxErrorKind::InvalidOption);
+    trace!("vcx_pool_close_network(name: {})", name);
+
+    match pool::close_network(&name) {
+        Ok(()) => error::SUCCESS.code_num,
+        Err(x) => {
+            warn!("vcx_pool_close_network(name: {}, rc: {})", name, x);
+            x.into()
+        }
+    }
+}
+
 /// Opens wallet based on vcx configuration previously set via vcx_init_core
 ///
 /// #Params
@@ -155,7 +219,8 @@ pub extern fn vcx_open_wallet(command_handle: CommandHandle, cb: extern fn(xcomm
         error!("vcx_open_wallet :: Wallet was already initialized.");
         return VcxError::from_msg(VcxErrorKind::AlreadyInitialized, "Wallet was already initialized").into();
     }
-    let wallet_name = match settings::get_config_value(settings::CONFIG_WALLET_NAME) {
+    let settings = settings::snapshot();
+    let wallet_name = match settings.get_value(settings::CONFIG_WALLET_NAME) {
         Ok(x) => x,
         Err(_) => {
             error!("vcx_open_wallet :: Value of setting {} was not set.", settings::CONFIG_WALLET_NAME);
@@ -163,9 +228,9 @@ pub extern fn vcx_open_wallet(command_handle: CommandHandle, cb: extern fn(xcomm
         }
     };
 
-    let wallet_type = settings::get_config_value(settings::CONFIG_WALLET_TYPE).ok();
-    let storage_config = settings::get_config_value(settings::CONFIG_WALLET_STORAGE_CONFIG).ok();
-    let storage_creds = settings::get_config_value(settings::CONFIG_WALLET_STORAGE_CREDS).ok();
+    let wallet_type = settings.get_value(settings::CONFIG_WALLET_TYPE).ok();
+    let storage_config = settings.get_value(settings::CONFIG_WALLET_STORAGE_CONFIG).ok();
+    let storage_creds = settings.get_value(settings::CONFIG_WALLET_STORAGE_CREDS).ok();
 
     spawn(move || {
         match wallet::open_wallet(&wallet_name, wallet_type.as_ref().map(String::as_str),
@@ -253,66 +318,13 @@ fn _finish_init(command_handle: CommandHandle, cb: extern fn(xcommand_handle: Co
         error!("Library was already initialized");
         return VcxError::from_msg(VcxErrorKind::AlreadyInitialized, "Library was already initialized").into();
     }
-    // Wallet name was already validated
-    let wallet_name = match settings::get_config_value(settings::CONFIG_WALLET_NAME) {
-        Ok(x) => x,
-        Err(_) => {
-            trace!("Using default wallet: {}", settings::DEFAULT_WALLET_NAME.to_string());
-            settings::set_config_value(settings::CONFIG_WALLET_NAME, settings::DEFAULT_WALLET_NAME);
-            settings::DEFAULT_WALLET_NAME.to_string()
-        }
-    };
 
     spawn(move || {
-        info!("_finish_init: initializing pool");
-        match settings::get_config_value(settings::CONFIG_GENESIS_PATH).ok() {
-            Some(path) => {
-                let pool_name = settings::get_config_value(settings::CONFIG_POOL_NAME)
-                    .unwrap_or(settings::DEFAULT_POOL_NAME.to_string());
-                let pool_config = settings::get_config_value(settings::CONFIG_POOL_CONFIG).ok();
-                match init_pool(&pool_name, &path, pool_config.as_ref().map(String::as_str)) {
-                    Ok(()) => (),
-                    Err(e) => {
-                        error!("Init Pool Error {}.", e);
-                        cb(command_handle, e.into());
-                        return Ok(());
-                    }
-                }
-            },
-            None => {
-                warn!("Skipping pool initialization because config {} was not provided", settings::CONFIG_GENESIS_PATH);
-            }
-        };
-
-        let wallet_type = settings::get_config_value(settings::CONFIG_WALLET_TYPE).ok();
-        let storage_config = settings::get_config_value(settings::CONFIG_WALLET_STORAGE_CONFIG).ok();
-        let storage_creds = settings::get_config_value(settings::CONFIG_WALLET_STORAGE_CREDS).ok();
-        info!("_finish_init: opening wallet");
-        match wallet::open_wallet(&wallet_name, wallet_type.as_ref().map(String::as_str),
-                                  storage_config.as_ref().map(String::as_str), storage_creds.as_ref().map(String::as_str)) {
-            Ok(_) => debug!("Init Wallet Successful"),
+        match library::open_pool_and_wallet() {
+            Ok(()) => cb(command_handle, error::SUCCESS.code_num),
             Err(e) => {
-                error!("Init Wallet Error {}.", e);
+                error!("_finish_init: Vcx Init Error {}.", e);
                 cb(command_handle, e.into());
-                return Ok(());
-            }
-        }
-
-        info!("_finish_init: getting and setting webhook url");
-        match settings::get_config_value(settings::CONFIG_WEBHOOK_URL) {
-            Ok(webhook_url) => match ::messages::agent_utils::update_agent_webhook(&webhook_url) {
-                Ok(()) => {
-                    info!("Agent webhook url updated on init, webhook_url={}", webhook_url);
-                    cb(command_handle, error::SUCCESS.code_num);
-                }
-                Err(e) => {
-                    error!("Error updating agent webhook on init (did you provision an agent?): {}", e);
-                    cb(command_handle, e.into());
-                }
-            }
-            Err(e) => {
-                debug!("webhook_url was not updated in agency: {}", e);
-                cb(command_handle, error::SUCCESS.code_num);
             }
         }
         Ok(())
@@ -338,7 +350,7 @@ pub extern fn vcx_init_minimal(config: *const c_char) -> u32 {
     // todo: Consider deprecating this, we now have more fine-grained init functions - vcx_init_core, vcx_open_wallet, vcx_open_pool
     check_useful_c_str!(config,VcxErrorKind::InvalidOption);
 
-    trace!("vcx_init_minimal(config: {:?})", config);
+    trace!("vcx_init_minimal(config: {})", settings::redact_config(&config));
 
     if config == "ENABLE_TEST_MODE" {
         settings::set_config_value(settings::CONFIG_ENABLE_TEST_MODE, "true");
@@ -391,45 +403,7 @@ pub extern fn vcx_shutdown(delete: bool) -> u32 {
     info!("vcx_shutdown >>>");
     trace!("vcx_shutdown(delete: {})", delete);
 
-    match wallet::close_wallet() {
-        Ok(()) => {}
-        Err(_) => {}
-    };
-
-    match pool::close() {
-        Ok(()) => {}
-        Err(_) => {}
-    };
-
-    ::schema::release_all();
-    ::connection::release_all();
-    ::issuer_credential::release_all();
-    ::credential_def::release_all();
-    ::proof::release_all();
-    ::disclosed_proof::release_all();
-    ::credential::release_all();
-
-    if delete {
-        let pool_name = settings::get_config_value(settings::CONFIG_POOL_NAME)
-            .unwrap_or(settings::DEFAULT_POOL_NAME.to_string());
-
-        let wallet_name = settings::get_config_value(settings::CONFIG_WALLET_NAME)
-            .unwrap_or(settings::DEFAULT_WALLET_NAME.to_string());
-
-        let wallet_type = settings::get_config_value(settings::CONFIG_WALLET_TYPE).ok();
-
-        match wallet::delete_wallet(&wallet_name, wallet_type.as_ref().map(String::as_str), None, None) {
-            Ok(()) => (),
-            Err(_) => (),
-        };
-
-        match pool::delete(&pool_name) {
-            Ok(()) => (),
-            Err(_) => (),
-        };
-    }
-
-    settings::clear_config();
+    library::shutdown(delete);
     trace!("vcx_shutdown(delete: {})", delete);
     error::SUCCESS.code_num
 }
@@ -470,7 +444,38 @@ pub extern fn vcx_update_institution_info(name: *const c_char, logo_url: *const
     error::SUCCESS.code_num
 }
 
-/// Update agency webhook url setting
+/// Sets institution default localization attached as `~l10n` decorator to credential offers and basic messages.
+///
+/// #Params
+/// localization_json: locale of the institution and optional labels of credential attributes in other locales
+///     {
+///         "locale": "en",
+///         "catalogs": Optional<["https://example.com/catalog.json"]>,
+///         "labels": Optional<{"es": {"name": "Nombre", "age": "Edad"}}>
+///     }
+///
+/// #Returns
+/// Error code as u32
+#[no_mangle]
+pub extern fn vcx_update_institution_localization(localization_json: *const c_char) -> u32 {
+    info!("vcx_update_institution_localization >>>");
+
+    check_useful_c_str!(localization_json, VcxErrorKind::InvalidOption);
+    trace!("vcx_update_institution_localization(localization_json: {})", localization_json);
+
+    match Localization::from_str(&localization_json) {
+        Ok(_) => {
+            settings::set_config_value(::settings::CONFIG_INSTITUTION_L10N, &localization_json);
+            error::SUCCESS.code_num
+        }
+        Err(err) => {
+            warn!("vcx_update_institution_localization(localization_json: {}, rc: {})", localization_json, err);
+            err.into()
+        }
+    }
+}
+
+/// Update agency webhook url setting, the url is kept in settings once the agency accepts it
 ///
 /// #Params
 ///
@@ -493,10 +498,8 @@ pub extern fn vcx_update_webhook_url(command_handle: CommandHandle,
 
     trace!("vcx_update_webhook(webhook_url: {})", notification_webhook_url);
 
-    settings::set_config_value(::settings::CONFIG_WEBHOOK_URL, &notification_webhook_url);
-
     spawn(move || {
-        match ::messages::agent_utils::update_agent_webhook(&notification_webhook_url[..]) {
+        match ::agent::update_webhook_url(&notification_webhook_url) {
             Ok(()) => {
                 trace!("vcx_update_webhook_url_cb(command_handle: {}, rc: {})",
                        command_handle, error::SUCCESS.message);
@@ -517,6 +520,52 @@ pub extern fn vcx_update_webhook_url(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Get configuration of the provisioned agent kept by agency
+///
+/// #Params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// cb: Callback that provides JSON object of configuration option names and values,
+///     options which were never set are left out
+///
+/// # Example config -> "{"name":"Faber","logoUrl":"https://example.org/faber.png","notificationWebhookUrl":"https://example.org/webhook"}"
+///
+/// #Returns
+/// Error code as u32
+#[no_mangle]
+pub extern fn vcx_get_agent_config(command_handle: CommandHandle,
+                                   cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, config: *const c_char)>) -> u32 {
+    info!("vcx_get_agent_config >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_get_agent_config(command_handle: {})", command_handle);
+
+    spawn(move || {
+        match ::agent::get_agent_config() {
+            Ok(config) => {
+                let config = json!(config).to_string();
+                trace!("vcx_get_agent_config_cb(command_handle: {}, rc: {}, config: {})",
+                       command_handle, error::SUCCESS.message, config);
+
+                let config = CStringUtils::string_to_cstring(config);
+                cb(command_handle, error::SUCCESS.code_num, config.as_ptr());
+            }
+            Err(err) => {
+                warn!("vcx_get_agent_config_cb(command_handle: {}, rc: {})",
+                      command_handle, err);
+
+                cb(command_handle, err.into(), ::std::ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Retrieve author agreement and acceptance mechanisms set on the Ledger
 ///
 /// #params
@@ -561,6 +610,291 @@ pub extern fn vcx_get_ledger_author_agreement(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Creates handles for all objects saved in the wallet by the object persistence (config option `persist_objects`).
+///
+/// Should be called after the wallet is opened, before the objects are used. Objects restored by a previous call
+/// keep their handles. Objects which cannot be restored are skipped.
+/// Saved state of an object is kept when its handle is released, it is deleted by `vcx_delete_object`.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// cb: Callback that provides handles of restored objects
+///
+/// # Example handles -> "{"connections":[1],"credentials":[],"issuer_credentials":[2],"proofs":[],"disclosed_proofs":[],"credential_templates":[]}"
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_load_all_objects(command_handle: CommandHandle,
+                                   cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, handles: *const c_char)>) -> u32 {
+    info!("vcx_load_all_objects >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_load_all_objects(command_handle: {})", command_handle);
+
+    spawn(move || {
+        match persistence::load_all_objects().and_then(|loaded| loaded.to_string()) {
+            Ok(handles) => {
+                trace!("vcx_load_all_objects_cb(command_handle: {}, rc: {}, handles: {})",
+                       command_handle, error::SUCCESS.message, handles);
+
+                let handles = CStringUtils::string_to_cstring(handles);
+                cb(command_handle, error::SUCCESS.code_num, handles.as_ptr());
+            }
+            Err(err) => {
+                error!("vcx_load_all_objects_cb(command_handle: {}, rc: {})",
+                       command_handle, err);
+                cb(command_handle, err.into(), ::std::ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Deletes saved state of the object (see `vcx_load_all_objects`) and releases its handle.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// object_type: one of `connection`, `credential`, `credential_template`, `disclosed_proof`, `issuer_credential`, `proof`
+///
+/// handle: handle of the object
+///
+/// cb: Callback that provides error status of the deletion
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_delete_object(command_handle: CommandHandle,
+                                object_type: *const c_char,
+                                handle: u32,
+                                cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32)>) -> u32 {
+    info!("vcx_delete_object >>>");
+
+    check_useful_c_str!(object_type, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_delete_object(command_handle: {}, object_type: {}, handle: {})", command_handle, object_type, handle);
+
+    spawn(move || {
+        match persistence::delete_object(&object_type, handle) {
+            Ok(()) => {
+                trace!("vcx_delete_object_cb(command_handle: {}, rc: {})",
+                       command_handle, error::SUCCESS.message);
+                cb(command_handle, error::SUCCESS.code_num);
+            }
+            Err(err) => {
+                error!("vcx_delete_object_cb(command_handle: {}, rc: {})",
+                       command_handle, err);
+                cb(command_handle, err.into());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Exports the agent into one encrypted file to be restored by `vcx_agent_import_state` on another device
+/// or by another libvcx version: the wallet, settings except keys and storage credentials,
+/// and all connections, credentials and proofs.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+/// path: path of the file to export the agent to
+/// backup_key: key used to encrypt the file
+///
+/// cb: Callback that provides success or failure of command
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_agent_export_state(command_handle: CommandHandle,
+                                     path: *const c_char,
+                                     backup_key: *const c_char,
+                                     cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32)>) -> u32 {
+    info!("vcx_agent_export_state >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(path, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(backup_key, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_agent_export_state(command_handle: {}, path: {}, backup_key: ****)", command_handle, path);
+
+    spawn(move || {
+        match ::agent::export_state(&path, &backup_key) {
+            Ok(()) => {
+                trace!("vcx_agent_export_state_cb(command_handle: {}, rc: {})", command_handle, error::SUCCESS.message);
+                cb(command_handle, error::SUCCESS.code_num);
+            }
+            Err(err) => {
+                error!("vcx_agent_export_state_cb(command_handle: {}, rc: {})", command_handle, err);
+                cb(command_handle, err.into());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Restores the agent exported by `vcx_agent_export_state`: imports and opens the wallet, applies exported settings,
+/// recreates the objects and registers the agent webhook with the agency again.
+/// The agent stays provisioned in the agency, re-registration only points agency notifications to the restored
+/// `webhook_url` and is skipped when there is none.
+/// Cannot be used when a wallet is already opened. On failure the restored objects are released and the wallet
+/// is closed, the imported wallet is kept.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+/// config: the same as for `vcx_wallet_import`,
+///     {"wallet_name":"","wallet_key":"","exported_wallet_path":"","backup_key":"","key_derivation":""}
+///     other settings can be added, they take precedence over the exported ones
+///
+/// cb: Callback that provides handles of restored objects grouped by type
+///     e.g. {"connections":[1],"credentials":[],"issuer_credentials":[2],"proofs":[],"disclosed_proofs":[],"credential_templates":[]}
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_agent_import_state(command_handle: CommandHandle,
+                                     config: *const c_char,
+                                     cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, handles: *const c_char)>) -> u32 {
+    info!("vcx_agent_import_state >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(config, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_agent_import_state(command_handle: {}, config: ****)", command_handle);
+
+    spawn(move || {
+        match ::agent::import_state(&config).and_then(|loaded| loaded.to_string()) {
+            Ok(handles) => {
+                trace!("vcx_agent_import_state_cb(command_handle: {}, rc: {}, handles: {})",
+                       command_handle, error::SUCCESS.message, handles);
+
+                let handles = CStringUtils::string_to_cstring(handles);
+                cb(command_handle, error::SUCCESS.code_num, handles.as_ptr());
+            }
+            Err(err) => {
+                error!("vcx_agent_import_state_cb(command_handle: {}, rc: {})",
+                       command_handle, err);
+                cb(command_handle, err.into(), ::std::ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Get counts and latencies of agency round trips, ledger requests, proof generation and message packing.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// reset: whether to clear collected metrics once they are returned
+///
+/// cb: Callback that provides the metrics collected since the library was loaded or since the last reset
+///
+/// # Example metrics -> "{"bucket_bounds_ms":[1,5,10,25,50,100,250,500,1000,5000,10000],"operations":{"agency_request":{"count":2,"errors":0,"total_ms":180,"min_ms":80,"max_ms":100,"histogram":[0,0,0,0,1,1,0,0,0,0,0,0]}}}"
+///     histogram contains count of operations per latency bucket, the last one counts operations slower than all bounds
+///     operations are `agency_request`, `ledger_request`, `proof_generation`, `pack_message`, `unpack_message`
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_get_metrics(command_handle: CommandHandle,
+                              reset: bool,
+                              cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, metrics: *const c_char)>) -> u32 {
+    info!("vcx_get_metrics >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_get_metrics(command_handle: {}, reset: {})", command_handle, reset);
+
+    spawn(move || {
+        let metrics = if reset { metrics::take() } else { metrics::snapshot() };
+
+        match metrics.to_string() {
+            Ok(metrics) => {
+                trace!("vcx_get_metrics_cb(command_handle: {}, rc: {}, metrics: {})",
+                       command_handle, error::SUCCESS.message, metrics);
+
+                let metrics = CStringUtils::string_to_cstring(metrics);
+                cb(command_handle, error::SUCCESS.code_num, metrics.as_ptr());
+            }
+            Err(err) => {
+                error!("vcx_get_metrics_cb(command_handle: {}, rc: {})",
+                       command_handle, err);
+                cb(command_handle, err.into(), ::std::ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Get report of protocols and modes active with the current settings.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// cb: Callback that provides the capability report
+///
+/// # Example capabilities -> "{"protocol_type":"4.0","aries":true,"strict_aries":true,"proprietary_messages":false,"payments":{"enabled":true,"payment_method":"null"},"revocation":{"tails_server_url":"https://tails.example.com"},"protocols":[{"pid":"did:sov:BzCbsNYhMrjHiqZDTUASHg;spec/connections/1.0"}],"disabled_protocols":[]}"
+///     strict_aries is set by protocol_type 4.0, proprietary messages and serialized objects are rejected then
+///     protocols are the aries protocols disclosed to counterparties, `actors` and `disabled_protocols` taken into account
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_get_capabilities(command_handle: CommandHandle,
+                                   cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, capabilities: *const c_char)>) -> u32 {
+    info!("vcx_get_capabilities >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_get_capabilities(command_handle: {})", command_handle);
+
+    spawn(move || {
+        match capabilities::get_capabilities().to_string() {
+            Ok(capabilities) => {
+                trace!("vcx_get_capabilities_cb(command_handle: {}, rc: {}, capabilities: {})",
+                       command_handle, error::SUCCESS.message, capabilities);
+
+                let capabilities = CStringUtils::string_to_cstring(capabilities);
+                cb(command_handle, error::SUCCESS.code_num, capabilities.as_ptr());
+            }
+            Err(err) => {
+                error!("vcx_get_capabilities_cb(command_handle: {}, rc: {})",
+                       command_handle, err);
+                cb(command_handle, err.into(), ::std::ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Set some accepted agreement as active.
 ///
 /// As result of successful call of this function appropriate metadata will be appended to each write request.
@@ -597,6 +931,58 @@ pub extern fn vcx_set_active_txn_author_agreement_meta(text: *const c_char,
     }
 }
 
+/// Accept author agreement currently set on the Ledger.
+///
+/// Agreement text and version are fetched from the Ledger and, together with the acceptance mechanism and time,
+/// appended to each following write request (schema, credential definition, revocation registry).
+/// Call it again when write requests fail with TxnAuthorAgreementRequired (1116) error, e.g. after the agreement changed.
+///
+/// #params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// acc_mech_type: mechanism how user has accepted the agreement, one of `aml` labels returned by `vcx_get_ledger_author_agreement`
+///
+/// time_of_acceptance: UTC timestamp when user has accepted the agreement, 0 for the current time
+///
+/// cb: Callback that provides error status of the acceptance
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_accept_ledger_author_agreement(command_handle: CommandHandle,
+                                                 acc_mech_type: *const c_char,
+                                                 time_of_acceptance: u64,
+                                                 cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32)>) -> u32 {
+    info!("vcx_accept_ledger_author_agreement >>>");
+
+    check_useful_c_str!(acc_mech_type, VcxErrorKind::InvalidOption);
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_accept_ledger_author_agreement(command_handle: {}, acc_mech_type: {:?}, time_of_acceptance: {})",
+           command_handle, acc_mech_type, time_of_acceptance);
+
+    spawn(move || {
+        let time_of_acceptance = Some(time_of_acceptance).filter(|time| *time != 0);
+        match ::utils::author_agreement::accept_ledger_txn_author_agreement(&acc_mech_type, time_of_acceptance) {
+            Ok(()) => {
+                trace!("vcx_accept_ledger_author_agreement_cb(command_handle: {}, rc: {})",
+                       command_handle, error::SUCCESS.message);
+                cb(command_handle, error::SUCCESS.code_num);
+            }
+            Err(err) => {
+                error!("vcx_accept_ledger_author_agreement_cb(command_handle: {}, rc: {})",
+                       command_handle, err);
+                cb(command_handle, err.into());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 #[no_mangle]
 pub extern fn vcx_mint_tokens(seed: *const c_char, fees: *const c_char) {
     info!("vcx_mint_tokens >>>");
@@ -652,6 +1038,40 @@ pub extern fn vcx_get_current_error(error_json_p: *mut *const c_char) {
     trace!("vcx_get_current_error: <<<");
 }
 
+/// Get structured context of the last occurred error, it tells whether the failed operation may pass
+/// when repeated (e.g. agency unreachable) or the counterparty rejected the exchange.
+/// Problem report received while updating the state is recorded as well, although the update itself succeeds.
+///
+/// The error is stored the same way as for `vcx_get_current_error`.
+///
+/// #Params
+/// * `details_json_p` - Reference that will contain error details (if any error has occurred before)
+///  in the format:
+/// {
+///     "error": str - name of the error kind
+///     "code": int - error code
+///     "message": str - human-readable error description
+///     "protocol": Optional<str> - Aries protocol of the object the error occurred on, e.g. "present-proof"
+///     "thread_id": Optional<str> - thread of the exchange
+///     "problem_code": Optional<str> - code of the problem report received from the counterparty
+///     "retryable": bool - the operation may pass if repeated later
+/// }
+///
+#[no_mangle]
+pub extern fn vcx_get_last_error_details(details_json_p: *mut *const c_char) {
+    trace!("vcx_get_last_error_details >>> details_json_p: {:?}", details_json_p);
+
+    if details_json_p.is_null() {
+        warn!("vcx_get_last_error_details: Invalid pointer has been passed");
+        return;
+    }
+
+    let details = get_current_error_details_c_json();
+    unsafe { *details_json_p = details };
+
+    trace!("vcx_get_last_error_details: <<<");
+}
+
 #[cfg(test)]
 mod tests {
     use std::ptr;
@@ -739,7 +1159,7 @@ mod tests {
         assert_ne!(get_pool_handle().unwrap(), 0);
     }
 
-    #[cfg(feature = "pool_tests")]
+    #[cfg(all(feature = "pool_tests", feature = "payments"))]
     #[test]
     fn test_init_with_file_no_payment_method() {
         let _setup = SetupEmpty::init();
@@ -1006,7 +1426,7 @@ mod tests {
         let data = r#"["name","male"]"#;
         let connection = ::connection::tests::build_test_connection_inviter_invited();
         let credentialdef = ::credential_def::create_and_publish_credentialdef("SID".to_string(), "NAME".to_string(), "4fUDR9R7fjwELRvH9JT6HH".to_string(), "id".to_string(), "tag".to_string(), "{}".to_string()).unwrap();
-        let issuer_credential = ::issuer_credential::issuer_credential_create(credentialdef, "1".to_string(), "8XFh8yBzrpJQmNyZzgoTqB".to_owned(), "credential_name".to_string(), "{\"attr\":\"value\"}".to_owned(), 1).unwrap();
+        let issuer_credential = ::issuer_credential::issuer_credential_create(credentialdef, "1".to_string(), "8XFh8yBzrpJQmNyZzgoTqB".to_owned(), "credential_name".to_string(), ::utils::constants::CREDENTIAL_DATA.to_owned(), 1).unwrap();
         let proof = ::proof::create_proof("1".to_string(), "[]".to_string(), "[]".to_string(), r#"{"support_revocation":false}"#.to_string(), "Optional".to_owned()).unwrap();
         let schema = ::schema::create_and_publish_schema("5", "VsKV7grR1BUE29mG2Fm2kX".to_string(), "name".to_string(), "0.1".to_string(), data.to_string()).unwrap();
         let disclosed_proof = ::disclosed_proof::create_proof("id", ::utils::mockdata::mockdata_proof::ARIES_PROOF_REQUEST_PRESENTATION).unwrap();
@@ -1069,9 +1489,21 @@ mod tests {
 
     #[test]
     #[cfg(feature = "general_test")]
-    fn test_vcx_update_institution_webhook() {
+    fn test_vcx_update_institution_localization() {
         let _setup = SetupDefaults::init();
 
+        let localization = json!({"locale": "en", "labels": {"es": {"name": "Nombre"}}}).to_string();
+        assert_eq!(error::SUCCESS.code_num, vcx_update_institution_localization(CString::new(localization).unwrap().into_raw()));
+        assert_eq!(Some("Nombre".to_string()), Localization::institution().unwrap().unwrap().label("es", "name"));
+
+        assert_eq!(error::INVALID_JSON.code_num, vcx_update_institution_localization(CString::new("{}").unwrap().into_raw()));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_update_institution_webhook() {
+        let _setup = SetupAriesMocks::init();
+
         let webhook_url = "http://www.evernym.com";
         assert_ne!(webhook_url, &settings::get_config_value(::settings::CONFIG_WEBHOOK_URL).unwrap());
 
@@ -1079,10 +1511,30 @@ mod tests {
         assert_eq!(error::SUCCESS.code_num, vcx_update_webhook_url(cb.command_handle,
                                                                    CString::new(webhook_url.to_string()).unwrap().into_raw(),
                                                                    Some(cb.get_callback())));
+        cb.receive(TimeoutUtils::some_medium()).unwrap();
+
+        assert_eq!(webhook_url, &settings::get_config_value(::settings::CONFIG_WEBHOOK_URL).unwrap());
 
+        let cb = return_types_u32::Return_U32::new().unwrap();
+        assert_eq!(error::SUCCESS.code_num, vcx_update_webhook_url(cb.command_handle,
+                                                                   CString::new("not a url").unwrap().into_raw(),
+                                                                   Some(cb.get_callback())));
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).unwrap_err(), error::INVALID_URL.code_num);
         assert_eq!(webhook_url, &settings::get_config_value(::settings::CONFIG_WEBHOOK_URL).unwrap());
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_get_agent_config() {
+        let _setup = SetupAriesMocks::init();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(error::SUCCESS.code_num, vcx_get_agent_config(cb.command_handle, Some(cb.get_callback())));
+        let config = cb.receive(TimeoutUtils::some_medium()).unwrap().unwrap();
+        let config: ::serde_json::Value = ::serde_json::from_str(&config).unwrap();
+        assert_eq!(config["name"], json!("faber"));
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn get_current_error_works_for_no_error() {
@@ -1126,6 +1578,56 @@ mod tests {
         ::std::thread::sleep(::std::time::Duration::from_secs(1));
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn get_last_error_details_works() {
+        let _setup = SetupDefaults::init();
+
+        ::error::reset_current_error();
+
+        vcx_get_last_error_details(ptr::null_mut());
+
+        let mut details_json_p: *const c_char = ptr::null();
+        vcx_get_last_error_details(&mut details_json_p);
+        assert_eq!(None, CStringUtils::c_str_to_string(details_json_p).unwrap());
+
+        let err = VcxError::from_msg(VcxErrorKind::PostMessageFailed, "agency unreachable")
+            .with_protocol("present-proof")
+            .with_thread_id("thread-1");
+        let code: u32 = err.into();
+        assert_eq!(error::POST_MSG_FAILURE.code_num, code);
+
+        vcx_get_last_error_details(&mut details_json_p);
+        let details: ::serde_json::Value = ::serde_json::from_str(&CStringUtils::c_str_to_string(details_json_p).unwrap().unwrap()).unwrap();
+        assert_eq!(details["code"], json!(code));
+        assert_eq!(details["protocol"], json!("present-proof"));
+        assert_eq!(details["thread_id"], json!("thread-1"));
+        assert_eq!(details["problem_code"], ::serde_json::Value::Null);
+        assert_eq!(details["retryable"], json!(true));
+
+        let err = ::aries::messages::error::ProblemReport::create()
+            .set_description(12)
+            .to_error(VcxErrorKind::InvalidProof, "presentation rejected");
+        let _: u32 = err.into();
+
+        vcx_get_last_error_details(&mut details_json_p);
+        let details: ::serde_json::Value = ::serde_json::from_str(&CStringUtils::c_str_to_string(details_json_p).unwrap().unwrap()).unwrap();
+        assert_eq!(details["problem_code"], json!("12"));
+        assert_eq!(details["retryable"], json!(false));
+
+        assert!(!VcxError::from(VcxErrorKind::NotReady).is_retryable());
+        assert!(VcxError::from(VcxErrorKind::Cancelled).is_retryable());
+
+        ::aries::messages::error::ProblemReport::create()
+            .set_description(7)
+            .record_received("issue-credential");
+
+        vcx_get_last_error_details(&mut details_json_p);
+        let details: ::serde_json::Value = ::serde_json::from_str(&CStringUtils::c_str_to_string(details_json_p).unwrap().unwrap()).unwrap();
+        assert_eq!(details["problem_code"], json!("7"));
+        assert_eq!(details["protocol"], json!("issue-credential"));
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_vcx_set_active_txn_author_agreement_meta() {
@@ -1171,6 +1673,41 @@ mod tests {
         assert_eq!(::utils::constants::DEFAULT_AUTHOR_AGREEMENT, agreement.unwrap());
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_accept_ledger_author_agreement() {
+        let _setup = SetupAriesMocks::init();
+
+        let cb = return_types_u32::Return_U32::new().unwrap();
+        assert_eq!(vcx_accept_ledger_author_agreement(cb.command_handle,
+                                                      CString::new("acceptance mechanism label1").unwrap().into_raw(),
+                                                      0,
+                                                      Some(cb.get_callback())), error::SUCCESS.code_num);
+        cb.receive(TimeoutUtils::some_short()).unwrap();
+
+        let meta = ::utils::author_agreement::get_txn_author_agreement().unwrap().unwrap();
+        assert_eq!(meta.version, Some("1.0.0".to_string()));
+        assert!(meta.time_of_acceptance > 0);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_pool_add_network() {
+        let _setup = SetupMocks::init();
+
+        let cb = return_types_u32::Return_U32::new().unwrap();
+        assert_eq!(vcx_pool_add_network(cb.command_handle,
+                                        CString::new("vcx_test_network").unwrap().into_raw(),
+                                        CString::new("genesis.txn").unwrap().into_raw(),
+                                        ptr::null(),
+                                        Some(cb.get_callback())), error::SUCCESS.code_num);
+        cb.receive(TimeoutUtils::some_short()).unwrap();
+        assert!(pool::network_names().contains(&"vcx_test_network".to_string()));
+
+        assert_eq!(vcx_pool_close_network(CString::new("vcx_test_network").unwrap().into_raw()), error::SUCCESS.code_num);
+        assert_eq!(vcx_pool_close_network(CString::new("vcx_test_network").unwrap().into_raw()), error::NO_POOL_OPEN.code_num);
+    }
+
     #[cfg(feature = "pool_tests")]
     fn get_settings() -> String {
         json!({
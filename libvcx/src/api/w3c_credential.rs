@@ -0,0 +1,129 @@
+use std::ptr;
+
+use indy_sys::CommandHandle;
+use libc::c_char;
+
+use error::prelude::*;
+use utils::cstring::CStringUtils;
+use utils::error;
+use utils::threadpool::spawn;
+use w3c_credential;
+
+/*
+    W3C Verifiable Credentials (JSON-LD) received by the holder are stored in the wallet once
+    the credential exchange started by `vcx_credential_create_with_offer` finishes.
+    The id of a stored credential is the `cred_id` of the credential object.
+*/
+
+/// Retrieves W3C Verifiable Credential stored in the wallet.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// credential_id: id of the stored credential.
+///
+/// cb: Callback that provides json string of the credential and error status of request
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_w3c_credential_get(command_handle: CommandHandle,
+                                     credential_id: *const c_char,
+                                     cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, credential: *const c_char)>) -> u32 {
+    info!("vcx_w3c_credential_get >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(credential_id, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_w3c_credential_get(command_handle: {}, credential_id: {})", command_handle, credential_id);
+
+    spawn(move || {
+        match w3c_credential::get(&credential_id) {
+            Ok(credential) => {
+                trace!("vcx_w3c_credential_get_cb(command_handle: {}, rc: {}, credential: {})",
+                       command_handle, error::SUCCESS.message, secret!(&credential));
+                let credential = CStringUtils::string_to_cstring(credential);
+                cb(command_handle, error::SUCCESS.code_num, credential.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_w3c_credential_get_cb(command_handle: {}, rc: {}, credential: {})",
+                      command_handle, x, "null");
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+/// Lists W3C Verifiable Credentials stored in the wallet.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+///
+/// cb: Callback that provides json string of the credentials and error status of request
+///
+/// # Example credentials -> [{"id": "<credential id>", "credential": {"@context": [...], "type": [...], ...}}]
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_w3c_credential_list(command_handle: CommandHandle,
+                                      cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, credentials: *const c_char)>) -> u32 {
+    info!("vcx_w3c_credential_list >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_w3c_credential_list(command_handle: {})", command_handle);
+
+    spawn(move || {
+        match w3c_credential::list() {
+            Ok(credentials) => {
+                trace!("vcx_w3c_credential_list_cb(command_handle: {}, rc: {}, credentials: {})",
+                       command_handle, error::SUCCESS.message, secret!(&credentials));
+                let credentials = CStringUtils::string_to_cstring(credentials);
+                cb(command_handle, error::SUCCESS.code_num, credentials.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_w3c_credential_list_cb(command_handle: {}, rc: {}, credentials: {})",
+                      command_handle, x, "null");
+                cb(command_handle, x.into(), ptr::null_mut());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use api::return_types_u32;
+    use utils::devsetup::SetupLibraryWallet;
+    use utils::timeout::TimeoutUtils;
+    use aries::messages::issuance::w3c::tests::_w3c_credential;
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_vcx_w3c_credential_get_and_list() {
+        let _setup = SetupLibraryWallet::init();
+
+        let id = w3c_credential::store(&_w3c_credential()).unwrap();
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_w3c_credential_get(cb.command_handle, CString::new(id.as_str()).unwrap().into_raw(), Some(cb.get_callback())), error::SUCCESS.code_num);
+        assert_eq!(json!(_w3c_credential())["credentialSubject"], ::serde_json::from_str::<::serde_json::Value>(&cb.receive(TimeoutUtils::some_medium()).unwrap().unwrap()).unwrap()["credentialSubject"]);
+
+        let cb = return_types_u32::Return_U32_STR::new().unwrap();
+        assert_eq!(vcx_w3c_credential_list(cb.command_handle, Some(cb.get_callback())), error::SUCCESS.code_num);
+        let credentials: ::serde_json::Value = ::serde_json::from_str(&cb.receive(TimeoutUtils::some_medium()).unwrap().unwrap()).unwrap();
+        assert_eq!(json!(id), credentials[0]["id"]);
+    }
+}
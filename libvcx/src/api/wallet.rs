@@ -1,4 +1,5 @@
 use std::ptr::null;
+use std::sync::Arc;
 use std::thread;
 
 use indy::{CommandHandle, SearchHandle, WalletHandle};
@@ -7,6 +8,10 @@ use libc::c_char;
 use error::prelude::*;
 use utils::cstring::CStringUtils;
 use utils::error;
+use utils::key_provider;
+use utils::logger::CVoid;
+use utils::secret::SecretString;
+use utils::libindy::anoncreds;
 use utils::libindy::payments::{create_address, get_wallet_token_info, pay_a_payee, sign_with_address, verify_with_address};
 use utils::libindy::wallet::{export, get_wallet_handle, import};
 use utils::libindy::wallet;
@@ -678,14 +683,15 @@ pub extern fn vcx_wallet_send_tokens(command_handle: CommandHandle,
 ///
 /// type_: type of record. (e.g. 'data', 'string', 'foobar', 'image')
 ///
-/// query_json: MongoDB style query to wallet record tags:
+/// query_json: WQL query to wallet record tags:
 ///  {
 ///    "tagName": "tagValue",
-///    $or: {
-///      "tagName2": { $regex: 'pattern' },
-///      "tagName3": { $gte: 123 },
-///    },
+///    "$or": [
+///      {"tagName2": {"$like": "pattern%"}},
+///      {"~tagName3": {"$gte": "123"}},
+///    ],
 ///  }
+///  supported operators: $and, $or, $not, $eq, $neq, $in, $like and $gt, $gte, $lt, $lte (unencrypted `~` tags only)
 /// options_json:
 ///  {
 ///    retrieveRecords: (optional, true by default) If false only "counts" will be calculated,
@@ -693,7 +699,9 @@ pub extern fn vcx_wallet_send_tokens(command_handle: CommandHandle,
 ///    retrieveType: (optional, false by default) Retrieve record type,
 ///    retrieveValue: (optional, true by default) Retrieve record value,
 ///    retrieveTags: (optional, false by default) Retrieve record tags,
+///    tagNames: (optional) Retrieve only the listed record tags, implies retrieveTags,
 ///  }
+///  use vcx_wallet_search_next_records with page size as count to go through the results without loading them all
 /// cb: Callback that any errors or a receipt of transfer
 ///
 /// #Returns
@@ -838,6 +846,61 @@ pub extern fn vcx_wallet_close_search(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Lists anoncreds credentials held in the wallet
+///
+/// #Params
+///
+/// command_handle: command handle to map callback to user context.
+///
+/// filter_wql: (optional) WQL query on credential attributes and identifiers, e.g. {"schema_id": "..."}.
+///     All credentials are listed when NULL.
+///
+/// offset: number of matching credentials to skip
+///
+/// limit: maximal number of credentials to return
+///
+/// cb: Callback that provides the page of credentials and error status
+///
+/// #Returns
+/// Error code as a u32
+///
+/// # Example
+/// credentials -> {"total": 12, "credentials": [{"referent": "cred_id", "attrs": {"name": "Alex"}, "schema_id": "...", "cred_def_id": "...", "rev_reg_id": null, "cred_rev_id": null}]}
+#[no_mangle]
+pub extern fn vcx_wallet_list_credentials(command_handle: CommandHandle,
+                                          filter_wql: *const c_char,
+                                          offset: usize,
+                                          limit: usize,
+                                          cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32, credentials: *const c_char)>) -> u32 {
+    info!("vcx_wallet_list_credentials >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_opt_c_str!(filter_wql, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_wallet_list_credentials(command_handle: {}, filter_wql: {:?}, offset: {}, limit: {})",
+           command_handle, filter_wql, offset, limit);
+
+    spawn(move || {
+        match anoncreds::list_credentials(filter_wql.as_ref().map(String::as_str), offset, limit) {
+            Ok(x) => {
+                trace!("vcx_wallet_list_credentials(command_handle: {}, rc: {}, credentials: {})",
+                       command_handle, error::SUCCESS.message, secret!(&x));
+                let msg = CStringUtils::string_to_cstring(x);
+                cb(command_handle, error::SUCCESS.code_num, msg.as_ptr());
+            }
+            Err(x) => {
+                warn!("vcx_wallet_list_credentials(command_handle: {}, rc: {}, credentials: {})",
+                      command_handle, x, "null");
+                cb(command_handle, x.into(), null());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Exports opened wallet
 ///
 /// Note this endpoint is EXPERIMENTAL. Function signature and behaviour may change
@@ -887,6 +950,51 @@ pub extern fn vcx_wallet_export(command_handle: CommandHandle,
     error::SUCCESS.code_num
 }
 
+/// Loads wallet storage plugin and calls its init function, which registers the storage type in libindy.
+/// Registered type can then be used as "wallet_type" with "storage_config" and "storage_credentials" in configuration.
+/// Alternatively "storage_library" and "storage_init_function" can be set in configuration to load the plugin
+/// automatically whenever a wallet is created or opened.
+///
+/// #Params
+/// command_handle: command handle to map callback to user context.
+/// path: path to the plugin library (e.g. "libindystrgpostgres.so")
+/// init_fn: name of the plugin init function (e.g. "postgresstorage_init")
+/// cb: Callback that provides the success/failure of the api call.
+///
+/// #Returns
+/// Error code as a u32
+#[no_mangle]
+pub extern fn vcx_wallet_register_storage_library(command_handle: CommandHandle,
+                                                  path: *const c_char,
+                                                  init_fn: *const c_char,
+                                                  cb: Option<extern fn(xcommand_handle: CommandHandle, err: u32)>) -> u32 {
+    info!("vcx_wallet_register_storage_library >>>");
+
+    check_useful_c_callback!(cb, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(path, VcxErrorKind::InvalidOption);
+    check_useful_c_str!(init_fn, VcxErrorKind::InvalidOption);
+
+    trace!("vcx_wallet_register_storage_library(command_handle: {}, path: {}, init_fn: {})",
+           command_handle, path, init_fn);
+
+    spawn(move || {
+        match wallet::register_storage_library(&path, &init_fn) {
+            Ok(()) => {
+                trace!("vcx_wallet_register_storage_library(command_handle: {}, rc: {})", command_handle, error::SUCCESS.message);
+                cb(command_handle, error::SUCCESS.code_num);
+            }
+            Err(e) => {
+                warn!("vcx_wallet_register_storage_library(command_handle: {}, rc: {})", command_handle, e);
+                cb(command_handle, e.into());
+            }
+        };
+
+        Ok(())
+    });
+
+    error::SUCCESS.code_num
+}
+
 /// Creates a new secure wallet and then imports its content
 /// according to fields provided in import_config
 /// Cannot be used if wallet is already opened (Especially if vcx_init has already been used).
@@ -978,6 +1086,67 @@ pub extern fn vcx_wallet_set_handle(handle: WalletHandle) -> WalletHandle {
     wallet::set_wallet_handle(handle)
 }
 
+/// Set provider of the wallet key, so the key does not have to be passed in `wallet_key` config option
+/// and can be kept in a platform keystore (Secure Enclave, Android Keystore, ...) instead.
+/// The provider is asked for the key every time the wallet is created, opened, imported or deleted,
+/// unless `wallet_key` config option is set, the explicit key takes precedence.
+/// Previously set provider is replaced.
+///
+/// #Params
+/// context: pointer to some context that will be available in the callbacks.
+///
+/// provide_key: called with the wallet name, returns the wallet key or null pointer when the key is not available
+///     (for example when the user did not authorize the access). The key has to stay valid until `release_key` is called.
+///
+/// release_key: (optional) called with the key returned by `provide_key` once the library has copied it,
+///     so the application can wipe and free it.
+///
+/// #Returns
+/// Error code as u32
+#[no_mangle]
+pub extern fn vcx_set_wallet_key_provider(context: *const CVoid,
+                                          provide_key: Option<extern fn(context: *const CVoid, wallet_name: *const c_char) -> *const c_char>,
+                                          release_key: Option<extern fn(context: *const CVoid, key: *const c_char)>) -> u32 {
+    info!("vcx_set_wallet_key_provider >>>");
+
+    check_useful_c_callback!(provide_key, VcxErrorKind::InvalidOption);
+
+    // pointer is only handed back to the caller
+    let context = context as usize;
+    key_provider::set_provider(Some(Arc::new(move |wallet_name: &str| {
+        let wallet_name = CStringUtils::string_to_cstring(wallet_name.to_string());
+        let key = provide_key(context as *const CVoid, wallet_name.as_ptr());
+        if key.is_null() {
+            return Err(VcxError::from_msg(VcxErrorKind::MissingWalletKey, "Wallet key was not provided by key provider"));
+        }
+
+        let copied = CStringUtils::c_str_to_string(key);
+        if let Some(release_key) = release_key {
+            release_key(context as *const CVoid, key);
+        }
+
+        match copied {
+            Ok(Some(key)) => Ok(SecretString::new(key)),
+            _ => Err(VcxError::from_msg(VcxErrorKind::MissingWalletKey, "Wallet key provided by key provider is not valid UTF-8 string"))
+        }
+    })));
+
+    error::SUCCESS.code_num
+}
+
+/// Remove provider set by `vcx_set_wallet_key_provider`, the wallet key is taken from `wallet_key` config option again.
+///
+/// #Returns
+/// Error code as u32
+#[no_mangle]
+pub extern fn vcx_unset_wallet_key_provider() -> u32 {
+    info!("vcx_unset_wallet_key_provider >>>");
+
+    key_provider::set_provider(None);
+
+    error::SUCCESS.code_num
+}
+
 #[cfg(test)]
 pub mod tests {
     extern crate serde_json;
@@ -1348,6 +1517,20 @@ pub mod tests {
         cb.receive(TimeoutUtils::some_medium()).unwrap();
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_wallet_register_storage_library_fails_for_missing_library() {
+        let _setup = SetupLibraryWallet::init();
+
+        let path = CStringUtils::string_to_cstring("libmissingstorage.so".to_string());
+        let init_fn = CStringUtils::string_to_cstring("missingstorage_init".to_string());
+
+        let cb = return_types_u32::Return_U32::new().unwrap();
+        assert_eq!(vcx_wallet_register_storage_library(cb.command_handle, path.as_ptr(), init_fn.as_ptr(), Some(cb.get_callback())),
+                   error::SUCCESS.code_num);
+        assert_eq!(cb.receive(TimeoutUtils::some_medium()).err(), Some(error::WALLET_STORAGE_PLUGIN.code_num));
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_wallet_import_export() {
@@ -0,0 +1,94 @@
+use api::VcxStateType;
+use error::prelude::*;
+
+protocol_object!(
+    /// Pairwise aries connection with another agent.
+    Connection, connection, InvalidConnectionHandle
+);
+
+impl Connection {
+    /// Connection on the inviter side, `connect` creates the invitation.
+    pub fn create(source_id: &str) -> VcxResult<Connection> {
+        ::connection::create_connection(source_id).map(Connection::own)
+    }
+
+    /// Connection on the invitee side accepting aries `invitation`, `connect` sends the connection request.
+    pub fn create_with_invite(source_id: &str, invitation: &str) -> VcxResult<Connection> {
+        ::connection::create_connection_with_invite(source_id, invitation).map(Connection::own)
+    }
+
+    /// Same as `create_with_invite` with invitation carried by URL (`c_i` or `oob` parameter, or a shortened link).
+    pub fn create_with_invite_url(source_id: &str, invite_url: &str) -> VcxResult<Connection> {
+        ::connection::create_connection_with_invite_url(source_id, invite_url).map(Connection::own)
+    }
+
+    /// Starts the connection protocol, returns invitation on the inviter side.
+    pub fn connect(&self) -> VcxResult<Option<String>> {
+        ::connection::connect(self.handle)
+    }
+
+    /// Processes messages received from the agency and returns the resulting state.
+    pub fn update_state(&self) -> VcxResult<VcxStateType> {
+        ::connection::update_state(self.handle)?;
+        self.state()
+    }
+
+    pub fn invite_details(&self) -> VcxResult<String> {
+        ::connection::get_invite_details(self.handle)
+    }
+
+    pub fn their_pw_did(&self) -> VcxResult<String> {
+        ::connection::get_their_pw_did(self.handle)
+    }
+
+    pub fn pw_did(&self) -> VcxResult<String> {
+        ::connection::get_pw_did(self.handle)
+    }
+
+    /// Sends `message`, JSON of an aries message is sent as it is, anything else as basic message.
+    pub fn send_message(&self, message: &str) -> VcxResult<()> {
+        ::connection::send_generic_message(self.handle, message).map(|_| ())
+    }
+
+    pub fn send_ping(&self, comment: Option<String>) -> VcxResult<()> {
+        ::connection::send_ping(self.handle, comment)
+    }
+
+    /// Deletes pairwise agent of the connection at the agency, see `connection::delete_connection`.
+    pub fn delete(self) -> VcxResult<()> {
+        ::connection::delete_connection(self.handle)?;
+        // the handle was released by the deletion
+        ::std::mem::forget(self);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use utils::devsetup::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_connection_releases_handle_when_dropped() {
+        let _setup = SetupAriesMocks::init();
+
+        let connection = Connection::create("test_connection").unwrap();
+        let handle = connection.handle();
+        assert_eq!(connection.state().unwrap(), VcxStateType::VcxStateInitialized);
+        assert_eq!(connection.source_id().unwrap(), "test_connection");
+        assert_eq!(connection.thread_id().unwrap(), "");
+
+        let serialized = connection.serialize().unwrap();
+        drop(connection);
+        assert!(!::connection::is_valid_handle(handle));
+
+        let connection = Connection::deserialize(&serialized).unwrap();
+        let handle = connection.into_handle();
+        assert!(::connection::is_valid_handle(handle));
+
+        let connection = Connection::from_handle(handle).unwrap();
+        assert_eq!(connection.source_id().unwrap(), "test_connection");
+        assert_eq!(Connection::from_handle(0).unwrap_err().kind(), VcxErrorKind::InvalidConnectionHandle);
+    }
+}
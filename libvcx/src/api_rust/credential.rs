@@ -0,0 +1,51 @@
+use api::VcxStateType;
+use api_rust::Connection;
+use error::prelude::*;
+
+protocol_object!(
+    /// Holder side of the aries issue-credential protocol.
+    Credential, credential, InvalidCredentialHandle
+);
+
+impl Credential {
+    /// Credential accepting `offer`, as returned by `Credential::offers`.
+    pub fn create_with_offer(source_id: &str, offer: &str) -> VcxResult<Credential> {
+        ::credential::credential_create_with_offer(source_id, offer).map(Credential::own)
+    }
+
+    /// Credential accepting offer `msg_id` received over `connection`, returns the offer as well.
+    pub fn create_with_msgid(source_id: &str, connection: &Connection, msg_id: &str) -> VcxResult<(Credential, String)> {
+        ::credential::credential_create_with_msgid(source_id, connection.handle(), msg_id)
+            .map(|(handle, offer)| (Credential::own(handle), offer))
+    }
+
+    /// Credential offers pending on `connection`, as JSON array.
+    pub fn offers(connection: &Connection) -> VcxResult<String> {
+        ::credential::get_credential_offer_messages(connection.handle())
+    }
+
+    pub fn send_request(&self, connection: &Connection) -> VcxResult<()> {
+        ::credential::send_credential_request(self.handle, connection.handle()).map(|_| ())
+    }
+
+    /// Processes messages received over `connection` and returns the resulting state.
+    pub fn update_state(&self, connection: &Connection) -> VcxResult<VcxStateType> {
+        ::credential::update_state(self.handle, None, Some(connection.handle()))?;
+        self.state()
+    }
+
+    /// Processes `message` received outside of the agency and returns the resulting state.
+    pub fn update_state_with_message(&self, message: &str) -> VcxResult<VcxStateType> {
+        ::credential::update_state(self.handle, Some(message.to_string()), None)?;
+        self.state()
+    }
+
+    /// Received credential, available once the state is `VcxStateAccepted`.
+    pub fn credential(&self) -> VcxResult<String> {
+        ::credential::get_credential(self.handle)
+    }
+
+    pub fn is_revoked(&self) -> VcxResult<bool> {
+        ::credential::is_revoked(self.handle, false)
+    }
+}
@@ -0,0 +1,84 @@
+use error::prelude::*;
+
+handle_object!(
+    /// Credential definition published to the ledger, with revocation registry when revocation is supported.
+    CredentialDef, credential_def, InvalidCredDefHandle
+);
+
+impl CredentialDef {
+    pub fn cred_def_id(&self) -> VcxResult<String> {
+        ::credential_def::get_cred_def_id(self.handle)
+    }
+
+    pub fn rev_reg_id(&self) -> VcxResult<Option<String>> {
+        ::credential_def::get_rev_reg_id(self.handle)
+    }
+
+    pub fn tails_file(&self) -> VcxResult<Option<String>> {
+        ::credential_def::get_tails_file(self.handle)
+    }
+
+    /// Publishes revocations of credentials revoked by `IssuerCredential::revoke_local`.
+    pub fn publish_revocations(&self) -> VcxResult<()> {
+        ::credential_def::publish_revocations(self.handle)
+    }
+}
+
+/// Builds and publishes `CredentialDef`, the institution DID is used as the issuer unless set.
+pub struct CredentialDefBuilder {
+    source_id: String,
+    schema_id: String,
+    name: String,
+    tag: String,
+    issuer_did: Option<String>,
+    revocation: Option<(String, u32)>,
+}
+
+impl CredentialDefBuilder {
+    pub fn new(source_id: &str, schema_id: &str) -> CredentialDefBuilder {
+        CredentialDefBuilder {
+            source_id: source_id.to_string(),
+            schema_id: schema_id.to_string(),
+            name: source_id.to_string(),
+            tag: String::from("tag1"),
+            issuer_did: None,
+            revocation: None,
+        }
+    }
+
+    pub fn name(mut self, name: &str) -> CredentialDefBuilder {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn tag(mut self, tag: &str) -> CredentialDefBuilder {
+        self.tag = tag.to_string();
+        self
+    }
+
+    pub fn issuer_did(mut self, issuer_did: &str) -> CredentialDefBuilder {
+        self.issuer_did = Some(issuer_did.to_string());
+        self
+    }
+
+    /// Supports revocation by a registry of `max_creds` credentials with tails file stored in `tails_dir`.
+    pub fn revocation(mut self, tails_dir: &str, max_creds: u32) -> CredentialDefBuilder {
+        self.revocation = Some((tails_dir.to_string(), max_creds));
+        self
+    }
+
+    pub fn publish(self) -> VcxResult<CredentialDef> {
+        let issuer_did = match self.issuer_did {
+            Some(issuer_did) => issuer_did,
+            None => super::institution_did()?
+        };
+
+        let revocation_details = match self.revocation {
+            Some((tails_file, max_creds)) => json!({"support_revocation": true, "tails_file": tails_file, "max_creds": max_creds}),
+            None => json!({"support_revocation": false})
+        };
+
+        ::credential_def::create_and_publish_credentialdef(self.source_id, self.name, issuer_did, self.schema_id, self.tag, revocation_details.to_string())
+            .map(CredentialDef::own)
+    }
+}
@@ -0,0 +1,56 @@
+use api::VcxStateType;
+use api_rust::Connection;
+use error::prelude::*;
+
+protocol_object!(
+    /// Prover side of the aries present-proof protocol.
+    DisclosedProof, disclosed_proof, InvalidDisclosedProofHandle
+);
+
+impl DisclosedProof {
+    /// Proof answering `request`, as returned by `DisclosedProof::requests`.
+    pub fn create_with_request(source_id: &str, request: &str) -> VcxResult<DisclosedProof> {
+        ::disclosed_proof::create_proof(source_id, request).map(DisclosedProof::own)
+    }
+
+    /// Proof answering request `msg_id` received over `connection`, returns the request as well.
+    pub fn create_with_msgid(source_id: &str, connection: &Connection, msg_id: &str) -> VcxResult<(DisclosedProof, String)> {
+        ::disclosed_proof::create_proof_with_msgid(source_id, connection.handle(), msg_id)
+            .map(|(handle, request)| (DisclosedProof::own(handle), request))
+    }
+
+    /// Proof requests pending on `connection`, as JSON array.
+    pub fn requests(connection: &Connection) -> VcxResult<String> {
+        ::disclosed_proof::get_proof_request_messages(connection.handle())
+    }
+
+    /// Credentials of the wallet matching the requested attributes and predicates.
+    pub fn retrieve_credentials(&self) -> VcxResult<String> {
+        ::disclosed_proof::retrieve_credentials(self.handle)
+    }
+
+    /// Generates the presentation from credentials selected out of `retrieve_credentials`.
+    pub fn generate_proof(&self, selected_credentials: &str, self_attested_attrs: &str) -> VcxResult<()> {
+        ::disclosed_proof::generate_proof(self.handle, selected_credentials.to_string(), self_attested_attrs.to_string()).map(|_| ())
+    }
+
+    pub fn send_proof(&self, connection: &Connection) -> VcxResult<()> {
+        ::disclosed_proof::send_proof(self.handle, connection.handle()).map(|_| ())
+    }
+
+    pub fn reject(&self, connection: &Connection) -> VcxResult<()> {
+        ::disclosed_proof::reject_proof(self.handle, connection.handle()).map(|_| ())
+    }
+
+    /// Processes messages received over `connection` and returns the resulting state.
+    pub fn update_state(&self, connection: &Connection) -> VcxResult<VcxStateType> {
+        ::disclosed_proof::update_state(self.handle, None, Some(connection.handle()))?;
+        self.state()
+    }
+
+    /// Processes `message` received outside of the agency and returns the resulting state.
+    pub fn update_state_with_message(&self, message: &str) -> VcxResult<VcxStateType> {
+        ::disclosed_proof::update_state(self.handle, Some(message.to_string()), None)?;
+        self.state()
+    }
+}
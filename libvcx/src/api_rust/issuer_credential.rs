@@ -0,0 +1,80 @@
+use api::VcxStateType;
+use api_rust::{Connection, CredentialDef};
+use error::prelude::*;
+
+protocol_object!(
+    /// Issuer side of the aries issue-credential protocol.
+    IssuerCredential, issuer_credential, InvalidIssuerCredentialHandle
+);
+
+impl IssuerCredential {
+    /// Sends credential offer over `connection`.
+    pub fn send_offer(&self, connection: &Connection, comment: Option<String>) -> VcxResult<()> {
+        ::issuer_credential::send_credential_offer(self.handle, connection.handle(), comment).map(|_| ())
+    }
+
+    /// Sends the credential once the credential request was received.
+    pub fn send_credential(&self, connection: &Connection) -> VcxResult<()> {
+        ::issuer_credential::send_credential(self.handle, connection.handle()).map(|_| ())
+    }
+
+    /// Processes messages received over `connection` and returns the resulting state.
+    pub fn update_state(&self, connection: &Connection) -> VcxResult<VcxStateType> {
+        ::issuer_credential::update_state(self.handle, None, Some(connection.handle()))?;
+        self.state()
+    }
+
+    /// Processes `message` received outside of the agency and returns the resulting state.
+    pub fn update_state_with_message(&self, message: &str) -> VcxResult<VcxStateType> {
+        ::issuer_credential::update_state(self.handle, Some(message.to_string()), None)?;
+        self.state()
+    }
+
+    /// Revokes the issued credential and publishes the revocation.
+    pub fn revoke(&self) -> VcxResult<()> {
+        ::issuer_credential::revoke_credential(self.handle)
+    }
+
+    /// Revokes the issued credential in the wallet, `CredentialDef::publish_revocations` publishes it.
+    pub fn revoke_local(&self) -> VcxResult<()> {
+        ::issuer_credential::revoke_credential_local(self.handle)
+    }
+}
+
+/// Builds `IssuerCredential` of `CredentialDef`, `data` is JSON object of attribute names and values.
+pub struct IssuerCredentialBuilder<'a> {
+    source_id: String,
+    cred_def: &'a CredentialDef,
+    data: String,
+    name: String,
+    price: u64,
+}
+
+impl<'a> IssuerCredentialBuilder<'a> {
+    pub fn new(source_id: &str, cred_def: &'a CredentialDef, data: &str) -> IssuerCredentialBuilder<'a> {
+        IssuerCredentialBuilder {
+            source_id: source_id.to_string(),
+            cred_def,
+            data: data.to_string(),
+            name: source_id.to_string(),
+            price: 0,
+        }
+    }
+
+    pub fn name(mut self, name: &str) -> IssuerCredentialBuilder<'a> {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn price(mut self, price: u64) -> IssuerCredentialBuilder<'a> {
+        self.price = price;
+        self
+    }
+
+    pub fn build(self) -> VcxResult<IssuerCredential> {
+        let issuer_did = super::institution_did().unwrap_or_default();
+
+        ::issuer_credential::issuer_credential_create(self.cred_def.handle(), self.source_id, issuer_did, self.name, self.data, self.price)
+            .map(IssuerCredential::own)
+    }
+}
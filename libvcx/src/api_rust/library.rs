@@ -0,0 +1,119 @@
+use indy::INVALID_WALLET_HANDLE;
+
+use error::prelude::*;
+use settings;
+use utils::cancellation;
+use utils::libindy::{ledger_cache, pool, wallet};
+use utils::threadpool;
+
+/// Applies `config` and prepares the library thread pool, neither the wallet nor the pool is opened.
+///
+/// "ENABLE_TEST_MODE" instead of configuration initializes the library with mocked wallet, ledger and agency.
+pub fn init_core(config: &str) -> VcxResult<()> {
+    if config == "ENABLE_TEST_MODE" {
+        settings::set_config_value(settings::CONFIG_ENABLE_TEST_MODE, "true");
+        settings::set_defaults();
+    } else {
+        settings::process_config_string(config, true)?;
+    }
+
+    settings::log_settings();
+    threadpool::init();
+    Ok(())
+}
+
+/// Opens the pool (when `genesis_path` is configured), additional ledger networks and the wallet configured
+/// by `init_core`, then updates webhook url of the agent when `webhook_url` is configured.
+pub fn open_pool_and_wallet() -> VcxResult<()> {
+    if wallet::get_wallet_handle() != INVALID_WALLET_HANDLE {
+        return Err(VcxError::from_msg(VcxErrorKind::AlreadyInitialized, "Library was already initialized"));
+    }
+
+    let wallet_name = settings::get_config_value(settings::CONFIG_WALLET_NAME)
+        .unwrap_or_else(|_| {
+            trace!("Using default wallet: {}", settings::DEFAULT_WALLET_NAME);
+            settings::set_config_value(settings::CONFIG_WALLET_NAME, settings::DEFAULT_WALLET_NAME);
+            settings::DEFAULT_WALLET_NAME.to_string()
+        });
+
+    match settings::get_config_value(settings::CONFIG_GENESIS_PATH).ok() {
+        Some(path) => open_pool_with_genesis(&path)?,
+        None => warn!("Skipping pool initialization because config {} was not provided", settings::CONFIG_GENESIS_PATH)
+    }
+    pool::init_networks()?;
+
+    let settings = settings::snapshot();
+    let wallet_type = settings.get_value(settings::CONFIG_WALLET_TYPE).ok();
+    let storage_config = settings.get_value(settings::CONFIG_WALLET_STORAGE_CONFIG).ok();
+    let storage_creds = settings.get_value(settings::CONFIG_WALLET_STORAGE_CREDS).ok();
+    wallet::open_wallet(&wallet_name, wallet_type.as_ref().map(String::as_str),
+                        storage_config.as_ref().map(String::as_str), storage_creds.as_ref().map(String::as_str))?;
+
+    match settings::get_config_value(settings::CONFIG_WEBHOOK_URL) {
+        Ok(webhook_url) => {
+            ::messages::agent_utils::update_agent_webhook(&webhook_url)
+                .map_err(|err| err.extend("Cannot update agent webhook url (did you provision an agent?)"))?;
+            info!("Agent webhook url updated on init, webhook_url={}", webhook_url);
+        }
+        Err(err) => debug!("webhook_url was not updated in agency: {}", err)
+    }
+
+    Ok(())
+}
+
+/// Initializes the library with `config` as `vcx_init_with_config` does.
+pub fn init(config: &str) -> VcxResult<()> {
+    init_core(config)?;
+    open_pool_and_wallet()
+}
+
+/// Opens the pool configured by `genesis_path`, `pool_name` and `pool_config` settings.
+pub fn open_pool() -> VcxResult<()> {
+    let path = settings::get_config_value(settings::CONFIG_GENESIS_PATH)
+        .map_err(|_| VcxError::from_msg(VcxErrorKind::InvalidConfiguration, "Cannot open pool, genesis_path is not configured"))?;
+
+    open_pool_with_genesis(&path)?;
+    pool::init_networks()
+}
+
+fn open_pool_with_genesis(genesis_path: &str) -> VcxResult<()> {
+    let settings = settings::snapshot();
+    let pool_name = settings.get_value(settings::CONFIG_POOL_NAME).unwrap_or(settings::DEFAULT_POOL_NAME.to_string());
+    let pool_config = settings.get_value(settings::CONFIG_POOL_CONFIG).ok();
+
+    pool::init_pool(&pool_name, genesis_path, pool_config.as_ref().map(String::as_str))
+}
+
+/// Closes the wallet and pool and releases all objects, `delete` deletes the wallet and pool configuration as well.
+pub fn shutdown(delete: bool) {
+    wallet::close_wallet().ok();
+    pool::close().ok();
+    pool::close_networks();
+
+    ::schema::release_all();
+    ::connection::release_all();
+    ::issuer_credential::release_all();
+    ::credential_template::release_all();
+    ::credential_def::release_all();
+    ::proof::release_all();
+    ::disclosed_proof::release_all();
+    ::credential::release_all();
+    cancellation::release_all();
+    ledger_cache::purge(None);
+
+    if delete {
+        let settings = settings::snapshot();
+        let pool_name = settings.get_value(settings::CONFIG_POOL_NAME)
+            .unwrap_or(settings::DEFAULT_POOL_NAME.to_string());
+
+        let wallet_name = settings.get_value(settings::CONFIG_WALLET_NAME)
+            .unwrap_or(settings::DEFAULT_WALLET_NAME.to_string());
+
+        let wallet_type = settings.get_value(settings::CONFIG_WALLET_TYPE).ok();
+
+        wallet::delete_wallet(&wallet_name, wallet_type.as_ref().map(String::as_str), None, None).ok();
+        pool::delete(&pool_name).ok();
+    }
+
+    settings::clear_config();
+}
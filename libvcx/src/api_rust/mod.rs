@@ -0,0 +1,154 @@
+//!
+//! Rust API of the library, for applications embedding it without the C interface.
+//!
+//! Objects own their handle and release it when dropped, results are returned directly instead of
+//! through callbacks. Calls block the calling thread, unlike the C interface nothing is spawned on
+//! the library thread pool.
+//!
+//! ```ignore
+//! use vcx::api_rust::{library, Connection, ProofBuilder};
+//!
+//! library::init(&config)?;
+//!
+//! let connection = Connection::create("alice")?;
+//! connection.connect()?;
+//! let invite = connection.invite_details()?;
+//! // ... once alice accepted the invitation
+//! connection.update_state()?;
+//!
+//! let proof = ProofBuilder::new("degree", "Degree")
+//!     .requested_attributes(r#"[{"name": "degree"}]"#)
+//!     .build()?;
+//! proof.send_request(&connection)?;
+//! ```
+//!
+//! The C interface and this API are both thin layers over the object modules (`connection`, `credential`, ...)
+//! which own the state of the objects. Handles of the C interface can be taken over by `from_handle` and
+//! handed back by `into_handle`, so both interfaces can be used side by side.
+//!
+
+use error::prelude::*;
+use api::VcxStateType;
+
+macro_rules! handle_object {
+    ($(#[$doc:meta])* $name:ident, $module:ident, $invalid_handle:ident) => {
+        $(#[$doc])*
+        #[derive(Debug)]
+        pub struct $name {
+            handle: u32,
+        }
+
+        impl $name {
+            fn own(handle: u32) -> $name {
+                $name { handle }
+            }
+
+            /// Takes ownership of `handle` created by the C interface, it is released when the object is dropped.
+            pub fn from_handle(handle: u32) -> ::error::VcxResult<$name> {
+                if ::$module::is_valid_handle(handle) {
+                    Ok($name::own(handle))
+                } else {
+                    Err(::error::VcxError::from_msg(::error::VcxErrorKind::$invalid_handle, format!("Invalid {} handle: {}", stringify!($name), handle)))
+                }
+            }
+
+            pub fn handle(&self) -> u32 {
+                self.handle
+            }
+
+            /// Gives up ownership of the handle, it stays valid for the C interface.
+            pub fn into_handle(self) -> u32 {
+                let handle = self.handle;
+                ::std::mem::forget(self);
+                handle
+            }
+
+            pub fn deserialize(serialized: &str) -> ::error::VcxResult<$name> {
+                ::$module::from_string(serialized).map($name::own)
+            }
+
+            pub fn serialize(&self) -> ::error::VcxResult<String> {
+                ::$module::to_string(self.handle)
+            }
+
+            pub fn source_id(&self) -> ::error::VcxResult<String> {
+                ::$module::get_source_id(self.handle)
+            }
+        }
+
+        impl Drop for $name {
+            fn drop(&mut self) {
+                ::$module::release(self.handle).ok();
+            }
+        }
+    };
+}
+
+macro_rules! protocol_object {
+    ($(#[$doc:meta])* $name:ident, $module:ident, $invalid_handle:ident) => {
+        handle_object!($(#[$doc])* $name, $module, $invalid_handle);
+
+        impl $name {
+            pub fn thread_id(&self) -> ::error::VcxResult<String> {
+                ::$module::get_thread_id(self.handle)
+            }
+
+            pub fn state(&self) -> ::error::VcxResult<::api::VcxStateType> {
+                ::api_rust::IntoState::into_state(::$module::get_state(self.handle))
+            }
+
+            /// Sets metadata `key` to `value`, `None` removes the key.
+            pub fn set_metadata(&self, key: &str, value: Option<&str>) -> ::error::VcxResult<()> {
+                ::$module::set_metadata(self.handle, key, value)
+            }
+
+            pub fn metadata(&self) -> ::error::VcxResult<::utils::metadata::Metadata> {
+                ::serde_json::from_str(&::$module::get_metadata(self.handle)?)
+                    .map_err(|err| ::error::VcxError::from_msg(::error::VcxErrorKind::InvalidJson, format!("Cannot deserialize metadata: {}", err)))
+            }
+        }
+    };
+}
+
+pub mod library;
+pub mod connection;
+pub mod schema;
+pub mod credential_def;
+pub mod issuer_credential;
+pub mod credential;
+pub mod proof;
+pub mod disclosed_proof;
+
+pub use self::connection::Connection;
+pub use self::credential::Credential;
+pub use self::credential_def::{CredentialDef, CredentialDefBuilder};
+pub use self::disclosed_proof::DisclosedProof;
+pub use self::issuer_credential::{IssuerCredential, IssuerCredentialBuilder};
+pub use self::proof::{Proof, ProofBuilder};
+pub use self::schema::{Schema, SchemaBuilder};
+
+fn state(code: u32) -> VcxResult<VcxStateType> {
+    ::serde_json::from_value(json!(code))
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidState, format!("Unknown state {}: {}", code, err)))
+}
+
+// connection state is read infallibly, states of other objects may fail on invalid handle
+trait IntoState {
+    fn into_state(self) -> VcxResult<VcxStateType>;
+}
+
+impl IntoState for u32 {
+    fn into_state(self) -> VcxResult<VcxStateType> {
+        state(self)
+    }
+}
+
+impl IntoState for VcxResult<u32> {
+    fn into_state(self) -> VcxResult<VcxStateType> {
+        self.and_then(state)
+    }
+}
+
+fn institution_did() -> VcxResult<String> {
+    ::settings::get_config_value(::settings::CONFIG_INSTITUTION_DID)
+}
@@ -0,0 +1,78 @@
+use api::VcxStateType;
+use api_rust::Connection;
+use error::prelude::*;
+
+protocol_object!(
+    /// Verifier side of the aries present-proof protocol.
+    Proof, proof, InvalidProofHandle
+);
+
+impl Proof {
+    pub fn send_request(&self, connection: &Connection) -> VcxResult<()> {
+        ::proof::send_proof_request(self.handle, connection.handle()).map(|_| ())
+    }
+
+    /// Processes messages received over `connection` and returns the resulting state.
+    pub fn update_state(&self, connection: &Connection) -> VcxResult<VcxStateType> {
+        ::proof::update_state(self.handle, None, Some(connection.handle()))?;
+        self.state()
+    }
+
+    /// Processes `message` received outside of the agency and returns the resulting state.
+    pub fn update_state_with_message(&self, message: &str) -> VcxResult<VcxStateType> {
+        ::proof::update_state(self.handle, Some(message.to_string()), None)?;
+        self.state()
+    }
+
+    /// Result of the verification, see `ProofStateType`.
+    pub fn proof_state(&self) -> VcxResult<u32> {
+        ::proof::get_proof_state(self.handle)
+    }
+
+    /// Received presentation, available once the state is `VcxStateAccepted`.
+    pub fn proof(&self) -> VcxResult<String> {
+        ::proof::get_proof(self.handle)
+    }
+}
+
+/// Builds `Proof` requesting attributes and predicates in format of `vcx_proof_create`.
+pub struct ProofBuilder {
+    source_id: String,
+    name: String,
+    requested_attributes: String,
+    requested_predicates: String,
+    revocation_interval: String,
+}
+
+impl ProofBuilder {
+    pub fn new(source_id: &str, name: &str) -> ProofBuilder {
+        ProofBuilder {
+            source_id: source_id.to_string(),
+            name: name.to_string(),
+            requested_attributes: String::from("[]"),
+            requested_predicates: String::from("[]"),
+            revocation_interval: String::from("{}"),
+        }
+    }
+
+    pub fn requested_attributes(mut self, requested_attributes: &str) -> ProofBuilder {
+        self.requested_attributes = requested_attributes.to_string();
+        self
+    }
+
+    pub fn requested_predicates(mut self, requested_predicates: &str) -> ProofBuilder {
+        self.requested_predicates = requested_predicates.to_string();
+        self
+    }
+
+    /// Requests non-revocation of the credentials in interval between `from` and `to` timestamps.
+    pub fn revocation_interval(mut self, from: Option<u64>, to: Option<u64>) -> ProofBuilder {
+        self.revocation_interval = json!({"from": from, "to": to}).to_string();
+        self
+    }
+
+    pub fn build(self) -> VcxResult<Proof> {
+        ::proof::create_proof(self.source_id, self.requested_attributes, self.requested_predicates, self.revocation_interval, self.name)
+            .map(Proof::own)
+    }
+}
@@ -0,0 +1,58 @@
+use error::prelude::*;
+
+handle_object!(
+    /// Schema published to the ledger.
+    Schema, schema, InvalidSchemaHandle
+);
+
+impl Schema {
+    pub fn schema_id(&self) -> VcxResult<String> {
+        ::schema::get_schema_id(self.handle)
+    }
+}
+
+/// Builds and publishes `Schema`, the institution DID is used as the issuer unless set.
+pub struct SchemaBuilder {
+    source_id: String,
+    name: String,
+    version: String,
+    attributes: Vec<String>,
+    issuer_did: Option<String>,
+}
+
+impl SchemaBuilder {
+    pub fn new(source_id: &str, name: &str, version: &str) -> SchemaBuilder {
+        SchemaBuilder {
+            source_id: source_id.to_string(),
+            name: name.to_string(),
+            version: version.to_string(),
+            attributes: Vec::new(),
+            issuer_did: None,
+        }
+    }
+
+    pub fn attribute(mut self, name: &str) -> SchemaBuilder {
+        self.attributes.push(name.to_string());
+        self
+    }
+
+    pub fn attributes(mut self, names: &[&str]) -> SchemaBuilder {
+        self.attributes.extend(names.iter().map(|name| name.to_string()));
+        self
+    }
+
+    pub fn issuer_did(mut self, issuer_did: &str) -> SchemaBuilder {
+        self.issuer_did = Some(issuer_did.to_string());
+        self
+    }
+
+    pub fn publish(self) -> VcxResult<Schema> {
+        let issuer_did = match self.issuer_did {
+            Some(issuer_did) => issuer_did,
+            None => super::institution_did()?
+        };
+
+        ::schema::create_and_publish_schema(&self.source_id, issuer_did, self.name, self.version, json!(self.attributes).to_string())
+            .map(Schema::own)
+    }
+}
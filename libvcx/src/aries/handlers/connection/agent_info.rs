@@ -1,18 +1,24 @@
+use log::Level;
+
 use std::collections::HashMap;
 
 use connection::create_agent_keys;
 use error::prelude::*;
+use messages::{delivery_stats, dispatch};
 use messages::get_message::{get_connection_messages, Message};
 use messages::MessageStatusCode;
 use messages::update_connection::send_delete_connection_message;
 use messages::update_message::{UIDsByConn, update_messages as update_messages_status};
 use settings;
 use settings::ProtocolTypes;
-use utils::httpclient;
+use outbound_queue;
 use utils::libindy::signus::create_and_store_my_did;
+use utils::qualifier;
 use aries::messages::a2a::A2AMessage;
 use aries::messages::connection::did_doc::DidDoc;
+use aries::messages::connection::did_peer;
 use aries::utils::encryption_envelope::EncryptionEnvelope;
+use messages::validation::validate_url;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentInfo {
@@ -20,6 +26,34 @@ pub struct AgentInfo {
     pub pw_vk: String,
     pub agent_did: String,
     pub agent_vk: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<EndpointOverride>,
+}
+
+/**
+Service endpoint and routing keys announced in the DIDDoc in place of the provisioned agency ones,
+e.g. when edge agent receives messages of the connection on its own HTTP endpoint.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EndpointOverride {
+    pub service_endpoint: String,
+    #[serde(default)]
+    pub routing_keys: Vec<String>,
+}
+
+impl EndpointOverride {
+    pub fn from_json(endpoint_json: &str) -> VcxResult<EndpointOverride> {
+        let endpoint: EndpointOverride = ::serde_json::from_str(endpoint_json)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize endpoint: {}", err)))?;
+
+        validate_url(&endpoint.service_endpoint)?;
+
+        if endpoint.routing_keys.iter().any(|key| key.is_empty()) {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidJson, "Routing keys must not be empty"));
+        }
+
+        Ok(endpoint)
+    }
 }
 
 impl Default for AgentInfo {
@@ -29,6 +63,7 @@ impl Default for AgentInfo {
             pw_vk: String::new(),
             agent_did: String::new(),
             agent_vk: String::new(),
+            endpoint: None,
         }
     }
 }
@@ -38,9 +73,9 @@ impl AgentInfo {
     Create connection agent in one's agency
      */
     pub fn create_agent(&self) -> VcxResult<AgentInfo> {
-        trace!("Agent::create_agent >>>");
+        log_event!(Level::Trace, "Agent::create_agent");
 
-        let method_name = settings::get_config_value(settings::CONFIG_DID_METHOD).ok();
+        let method_name = settings::get_did_method();
         let (pw_did, pw_vk) = create_and_store_my_did(None, method_name.as_ref().map(String::as_str))?;
 
         /*
@@ -49,18 +84,30 @@ impl AgentInfo {
         */
         let (agent_did, agent_vk) = create_agent_keys("", &pw_did, &pw_vk)?;
 
-        Ok(AgentInfo { pw_did, pw_vk, agent_did, agent_vk })
+        Ok(AgentInfo { pw_did, pw_vk, agent_did, agent_vk, endpoint: self.endpoint.clone() })
+    }
+
+    pub fn with_endpoint(endpoint: EndpointOverride) -> AgentInfo {
+        AgentInfo { endpoint: Some(endpoint), ..AgentInfo::default() }
     }
 
     /**
-    Builds one's agency's URL endpoint
+    Builds one's agency's URL endpoint, unless the connection overrides it
      */
     pub fn agency_endpoint(&self) -> VcxResult<String> {
+        if let Some(ref endpoint) = self.endpoint {
+            return Ok(endpoint.service_endpoint.clone());
+        }
+
         settings::get_config_value(settings::CONFIG_AGENCY_ENDPOINT)
             .map(|str| format!("{}/agency/msg", str))
     }
 
     pub fn routing_keys(&self) -> VcxResult<Vec<String>> {
+        if let Some(ref endpoint) = self.endpoint {
+            return Ok(endpoint.routing_keys.clone());
+        }
+
         let agency_vk = settings::get_config_value(settings::CONFIG_AGENCY_VERKEY)?;
         Ok(vec![self.agent_vk.to_string(), agency_vk])
     }
@@ -69,19 +116,43 @@ impl AgentInfo {
         vec![self.pw_vk.to_string()]
     }
 
+    /**
+    DID to be shared with the counterparty, `did:peer:2` embeds keys and endpoint of the agent.
+    Otherwise the DID is qualified by the configured `did_method`, DIDs of connections created before it was set included
+     */
+    pub fn pairwise_did(&self) -> VcxResult<String> {
+        if settings::is_did_peer_method_set() {
+            return did_peer::generate_did_peer_2(&self.recipient_keys(), &self.routing_keys()?, &self.agency_endpoint()?);
+        }
+
+        match settings::get_did_method() {
+            Some(method) => Ok(qualifier::qualify_did(&self.pw_did, &method)),
+            None => Ok(self.pw_did.to_string())
+        }
+    }
+
     pub fn update_message_status(&self, uid: String) -> VcxResult<()> {
-        trace!("Agent::update_message_status >>> uid: {:?}", uid);
+        log_event!(Level::Trace, "Agent::update_message_status", uid = uid);
+
+        dispatch::forget_prefetched_message(&self.pw_did, &uid);
 
         let messages_to_update = vec![UIDsByConn {
             pairwise_did: self.pw_did.clone(),
             uids: vec![uid],
         }];
 
-        update_messages_status(MessageStatusCode::Reviewed, messages_to_update)
+        update_messages_status(MessageStatusCode::Reviewed, messages_to_update)?;
+        delivery_stats::record_received(&self.pw_did);
+        Ok(())
     }
 
     pub fn get_messages(&self) -> VcxResult<HashMap<String, A2AMessage>> {
-        trace!("Agent::get_messages >>>");
+        log_event!(Level::Trace, "Agent::get_messages");
+
+        if let Some(messages) = dispatch::get_prefetched_messages(&self.pw_did) {
+            delivery_stats::record_pending(&self.pw_did, messages.len());
+            return Ok(messages);
+        }
 
         let messages = get_connection_messages(&self.pw_did,
                                                &self.pw_vk,
@@ -91,13 +162,14 @@ impl AgentInfo {
                                                Some(vec![MessageStatusCode::Received]),
                                                &Some(ProtocolTypes::V2))?;
 
-        debug!("Agent::get_messages >>> obtained messages: {:?}", messages);
+        log_event!(Level::Debug, "Agent::get_messages", message_uids = messages.iter().map(|message| &message.uid).collect::<Vec<_>>());
 
         let mut a2a_messages: HashMap<String, A2AMessage> = HashMap::new();
 
         for message in messages {
             a2a_messages.insert(message.uid.clone(), self.decode_message(&message)?);
         }
+        delivery_stats::record_pending(&self.pw_did, a2a_messages.len());
 
         #[cfg(feature = "warnlog_fetched_messages")]
         {
@@ -110,7 +182,7 @@ impl AgentInfo {
     }
 
     pub fn get_message_by_id(&self, msg_id: &str) -> VcxResult<A2AMessage> {
-        trace!("Agent::get_message_by_id >>> msg_id: {:?}", msg_id);
+        log_event!(Level::Trace, "Agent::get_message_by_id", msg_id = msg_id);
 
         let mut messages = get_connection_messages(&self.pw_did,
                                                    &self.pw_vk,
@@ -131,18 +203,25 @@ impl AgentInfo {
     }
 
     pub fn decode_message(&self, message: &Message) -> VcxResult<A2AMessage> {
-        trace!("Agent::decode_message >>> message = {:?}", json!(&message).to_string());
+        log_event!(Level::Trace, "Agent::decode_message", uid = message.uid);
 
-        EncryptionEnvelope::open(message.payload()?)
+        match EncryptionEnvelope::open_with_sender(message.payload()?)? {
+            // redirect is accepted only from the key it announces, see `Redirect::sender_verkey`
+            (A2AMessage::ConnectionRedirect(redirect), sender_verkey) => {
+                Ok(A2AMessage::ConnectionRedirect(redirect.set_sender_verkey(sender_verkey)))
+            }
+            (message, _) => Ok(message)
+        }
     }
 
     /**
     Sends authenticated message to connection counterparty
      */
     pub fn send_message(&self, message: &A2AMessage, did_dod: &DidDoc) -> VcxResult<()> {
-        trace!("Agent::send_message >>> message: {:?}, did_doc: {:?}", message, did_dod);
+        log_event!(Level::Trace, "Agent::send_message", message_type = message.type_name());
         let envelope = EncryptionEnvelope::create(&message, Some(&self.pw_vk), &did_dod)?;
-        httpclient::post_message(&envelope.0, &did_dod.get_endpoint())?;
+        outbound_queue::post_or_enqueue(&envelope.0, &did_dod.get_endpoint(), message.thread_id(), Some(&self.pw_did))?;
+        delivery_stats::record_sent(&self.pw_did);
         Ok(())
     }
 
@@ -150,17 +229,20 @@ impl AgentInfo {
     Sends anonymous message to connection counterparty
      */
     pub fn send_message_anonymously(message: &A2AMessage, did_dod: &DidDoc) -> VcxResult<()> {
-        trace!("Agent::send_message_anonymously >>> message: {:?}, did_doc: {:?}", message, did_dod);
+        log_event!(Level::Trace, "Agent::send_message_anonymously", message_type = message.type_name());
         let envelope = EncryptionEnvelope::create(&message, None, &did_dod)?;
-        httpclient::post_message(&envelope.0, &did_dod.get_endpoint())?;
-        Ok(())
+        outbound_queue::post_or_enqueue(&envelope.0, &did_dod.get_endpoint(), message.thread_id(), None)
     }
 
     /**
-    Sends message to one's agency signalling resources related to this connection agent can be deleted.
+    Sends message to one's agency signalling resources related to this connection agent can be deleted,
+    and drops messages still queued for the counterparty.
      */
     pub fn delete(&self) -> VcxResult<()> {
-        trace!("Agent::delete >>>");
-        send_delete_connection_message(&self.pw_did, &self.pw_vk, &self.agent_did, &self.agent_vk)
+        log_event!(Level::Trace, "Agent::delete");
+        send_delete_connection_message(&self.pw_did, &self.pw_vk, &self.agent_did, &self.agent_vk)?;
+        outbound_queue::purge_connection(&self.pw_did)?;
+        delivery_stats::forget(&self.pw_did);
+        Ok(())
     }
 }
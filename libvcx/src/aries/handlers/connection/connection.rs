@@ -1,20 +1,38 @@
 use std::collections::HashMap;
 
+use log::Level;
+
+use api::VcxStateType;
 use error::prelude::*;
+use messages::delivery_stats::{self, DeliveryStats};
 use messages::get_message::Message;
-use aries::handlers::connection::agent_info::AgentInfo;
+use outbound_queue;
+use aries::handlers::connection::agent_info::{AgentInfo, EndpointOverride};
 use aries::handlers::connection::invitee::state_machine::{InviteeState, SmConnectionInvitee};
 use aries::handlers::connection::inviter::state_machine::{InviterState, SmConnectionInviter};
 use aries::handlers::connection::messages::DidExchangeMessages;
+use aries::handlers::connection::util::handle_ping;
 use aries::messages::a2a::A2AMessage;
+use aries::messages::ack::{Ack, AckStatus};
+use aries::messages::a2a::protocol_registry::is_family_enabled;
 use aries::messages::basic_message::message::BasicMessage;
 use aries::messages::connection::did_doc::DidDoc;
 use aries::messages::connection::invite::Invitation;
+use aries::messages::connection::redirect::Redirect;
 use aries::messages::discovery::disclose::ProtocolDescriptor;
+use aries::messages::error::ProblemReport;
+use aries::messages::invite_action::invite::{AckOn, Invite};
+use aries::messages::trust_ping::ping::Ping;
+use utils::cancellation;
+use utils::metadata::{self, Metadata};
+use utils::structured_log::{self, Correlation};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Connection {
-    connection_sm: SmConnection
+    connection_sm: SmConnection,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Metadata::is_empty")]
+    metadata: Metadata,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +51,7 @@ pub enum SmConnectionState {
 struct ConnectionInfo {
     my: SideConnectionInfo,
     their: Option<SideConnectionInfo>,
+    stats: DeliveryStats,
 }
 
 #[derive(Debug, Serialize)]
@@ -57,20 +76,45 @@ impl Connection {
     Create Inviter connection state machine
      */
     pub fn create(source_id: &str) -> Connection {
-        trace!("Connection::create >>> source_id: {}", source_id);
+        log_event!(Level::Trace, "Connection::create", source_id = source_id);
+
+        Connection {
+            connection_sm: SmConnection::Inviter(SmConnectionInviter::new(source_id)),
+            metadata: Metadata::new(),
+        }
+    }
+
+    /**
+    Create Inviter connection state machine announcing custom endpoint and routing keys
+     */
+    pub fn create_with_endpoint(source_id: &str, endpoint: EndpointOverride) -> Connection {
+        log_event!(Level::Trace, "Connection::create_with_endpoint", source_id = source_id);
+
+        Connection {
+            connection_sm: SmConnection::Inviter(SmConnectionInviter::new_with_endpoint(source_id, endpoint)),
+            metadata: Metadata::new(),
+        }
+    }
+
+    /**
+    Create Inviter connection state machine reusing already provisioned pairwise agent
+     */
+    pub fn create_with_agent_info(source_id: &str, agent_info: AgentInfo) -> Connection {
+        log_event!(Level::Trace, "Connection::create_with_agent_info", source_id = source_id);
 
         Connection {
-            connection_sm: SmConnection::Inviter(SmConnectionInviter::new(source_id))
+            connection_sm: SmConnection::Inviter(SmConnectionInviter::new_with_agent_info(source_id, agent_info)),
+            metadata: Metadata::new(),
         }
     }
 
     pub fn from_parts(source_id: String, agent_info: AgentInfo, state: SmConnectionState) -> Connection {
         match state {
             SmConnectionState::Inviter(state) => {
-                Connection { connection_sm: SmConnection::Inviter(SmConnectionInviter::from(source_id, agent_info, state)) }
+                Connection { connection_sm: SmConnection::Inviter(SmConnectionInviter::from(source_id, agent_info, state)), metadata: Metadata::new() }
             }
             SmConnectionState::Invitee(state) => {
-                Connection { connection_sm: SmConnection::Invitee(SmConnectionInvitee::from(source_id, agent_info, state)) }
+                Connection { connection_sm: SmConnection::Invitee(SmConnectionInvitee::from(source_id, agent_info, state)), metadata: Metadata::new() }
             }
         }
     }
@@ -79,10 +123,11 @@ impl Connection {
     Create Invitee connection state machine
      */
     pub fn create_with_invite(source_id: &str, invitation: Invitation) -> VcxResult<Connection> {
-        trace!("Connection::create_with_invite >>> source_id: {}", source_id);
+        log_event!(Level::Trace, "Connection::create_with_invite", source_id = source_id);
 
         let mut connection = Connection {
-            connection_sm: SmConnection::Invitee(SmConnectionInvitee::new(source_id))
+            connection_sm: SmConnection::Invitee(SmConnectionInvitee::new(source_id)),
+            metadata: Metadata::new(),
         };
 
         connection.process_invite(invitation)?;
@@ -112,6 +157,23 @@ impl Connection {
         }
     }
 
+    /// Thread of the connection exchange, see `thread_id` of the state machines.
+    pub fn get_thread_id(&self) -> Option<String> {
+        match &self.connection_sm {
+            SmConnection::Inviter(sm_inviter) => sm_inviter.thread_id(),
+            SmConnection::Invitee(sm_invitee) => sm_invitee.thread_id(),
+        }
+    }
+
+    /// State reported to the user, `VcxStateSending` while the request or response of the connection waits in the outbound queue.
+    pub fn reported_state(&self) -> u32 {
+        let thread_id = match &self.connection_sm {
+            SmConnection::Inviter(sm_inviter) => sm_inviter.pending_thread_id(),
+            SmConnection::Invitee(sm_invitee) => sm_invitee.pending_thread_id(),
+        };
+        outbound_queue::sending_state(self.state(), &thread_id.unwrap_or_default())
+    }
+
     pub fn agent_info(&self) -> &AgentInfo {
         match &self.connection_sm {
             SmConnection::Inviter(sm_inviter) => {
@@ -167,6 +229,18 @@ impl Connection {
         }.to_string()
     }
 
+    pub fn get_metadata(&self) -> &Metadata { &self.metadata }
+
+    pub fn set_metadata(&mut self, key: &str, value: Option<&str>) -> VcxResult<()> {
+        metadata::set(&mut self.metadata, key, value)
+    }
+
+    /// Restores metadata of a connection put together from its serialized parts.
+    pub fn with_metadata(mut self, metadata: Metadata) -> Connection {
+        self.metadata = metadata;
+        self
+    }
+
     pub fn get_protocols(&self) -> Vec<ProtocolDescriptor> {
         match &self.connection_sm {
             SmConnection::Inviter(sm_inviter) => {
@@ -211,11 +285,22 @@ impl Connection {
         }
     }
 
+    /**
+    Replaces DIDDoc of the counterparty kept by the state, used when the connection is restored from its serialized form.
+    DIDDoc of states which derive it from the invitation is left as it is.
+     */
+    pub fn restore_their_did_doc(&mut self, did_doc: DidDoc) {
+        match &mut self.connection_sm {
+            SmConnection::Inviter(sm_inviter) => sm_inviter.set_their_did_doc(did_doc),
+            SmConnection::Invitee(sm_invitee) => sm_invitee.set_their_did_doc(did_doc),
+        }
+    }
+
     /**
     Invitee operation
      */
     pub fn process_invite(&mut self, invitation: Invitation) -> VcxResult<()> {
-        trace!("Connection::process_invite >>> invitation: {:?}", invitation);
+        log_event!(Level::Trace, "Connection::process_invite", invitation_id = invitation.id.0);
         self.step(DidExchangeMessages::InvitationReceived(invitation))
     }
 
@@ -224,7 +309,7 @@ impl Connection {
     If called on Invitee, returns error
      */
     pub fn get_invite_details(&self) -> Option<String> {
-        trace!("Connection::get_invite_details >>>");
+        log_event!(Level::Trace, "Connection::get_invite_details");
         match &self.connection_sm {
             SmConnection::Inviter(sm_inviter) => {
                 sm_inviter.get_invitation().map(|invitation| {
@@ -253,10 +338,75 @@ impl Connection {
     If called on Invitee, creates connection agent and send connection request using info from connection invitation
      */
     pub fn connect(&mut self) -> VcxResult<()> {
-        trace!("Connection::connect >>> source_id: {}", self.source_id());
+        log_event!(Level::Trace, "Connection::connect", source_id = self.source_id().to_string());
         self.step(DidExchangeMessages::Connect())
     }
 
+    /**
+    Invitee operation
+    Instead of connecting, answers invitation of an institution we are already connected with by `existing` connection.
+    Redirect message announcing DIDs of the existing relationship is sent and signed by its keys.
+     */
+    pub fn redirect(&mut self, existing: &Connection) -> VcxResult<()> {
+        log_event!(Level::Trace, "Connection::redirect", source_id = self.source_id().to_string(), existing = existing.source_id().to_string());
+
+        if let SmConnection::Inviter(_) = self.connection_sm {
+            return Err(VcxError::from_msg(VcxErrorKind::ActionNotSupported, "Only Invitee connection can be redirected"));
+        }
+
+        if self.state() != VcxStateType::VcxStateOfferSent as u32 {
+            return Err(VcxError::from_msg(VcxErrorKind::NotReady, "Cannot redirect: invitation is not pending"));
+        }
+
+        if existing.state() != VcxStateType::VcxStateAccepted as u32 {
+            return Err(VcxError::from_msg(VcxErrorKind::NotReady, "Cannot redirect: existing connection is not established"));
+        }
+
+        let existing_agent_info = existing.agent_info().clone();
+        let redirect = Redirect::create()
+            .set_did(existing_agent_info.pw_did.clone())
+            .set_verkey(existing_agent_info.pw_vk.clone())
+            .set_their_did(existing.remote_did()?);
+
+        self.step(DidExchangeMessages::SendRedirect((redirect, existing_agent_info)))
+    }
+
+    /**
+    Returns Redirect message sent (Invitee) or received (Inviter) by redirected connection.
+    Inviter should look up its connection with `did` equal to `their_did` of the redirect and check that `verkey`
+    is the key of the counterparty there.
+     */
+    pub fn get_redirect_details(&self) -> VcxResult<String> {
+        log_event!(Level::Trace, "Connection::get_redirect_details");
+
+        let redirect = match &self.connection_sm {
+            SmConnection::Inviter(sm_inviter) => sm_inviter.get_redirect(),
+            SmConnection::Invitee(sm_invitee) => sm_invitee.get_redirect(),
+        };
+
+        redirect
+            .ok_or(VcxError::from_msg(VcxErrorKind::NotReady, "Connection is not redirected"))?
+            .to_string()
+    }
+
+    /**
+    Invitee operation
+    Verifies again the `connection~sig` of the Connection Response received from the inviter
+    against the recipient key of the invitation.
+     */
+    pub fn verify_response_signature(&self) -> VcxResult<()> {
+        log_event!(Level::Trace, "Connection::verify_response_signature");
+
+        match &self.connection_sm {
+            SmConnection::Inviter(_) => {
+                Err(VcxError::from_msg(VcxErrorKind::ActionNotSupported, "Connection Response is signed by Inviter, there is nothing to verify"))
+            }
+            SmConnection::Invitee(sm_invitee) => {
+                sm_invitee.verify_response_signature()
+            }
+        }
+    }
+
     /**
     Tries to update state of connection state machine in 3 steps:
       1. find relevant message in agency,
@@ -264,7 +414,7 @@ impl Connection {
       3. update state of used message in agency to "Reviewed".
      */
     pub fn update_state(&mut self) -> VcxResult<()> {
-        trace!("Connection::update_state >>>");
+        log_event!(Level::Trace, "Connection::update_state");
 
         if self.is_in_null_state() {
             warn!("Connection::update_state :: update state on connection in null state is ignored");
@@ -272,24 +422,26 @@ impl Connection {
         }
 
         let messages = self.get_messages()?;
-        trace!("Connection::update_state >>> retrieved messages {:?}", messages);
+        log_event!(Level::Trace, "Connection::update_state", message_uids = messages.keys().collect::<Vec<_>>());
 
         if let Some((uid, message)) = self.find_message_to_handle(messages) {
-            trace!("Connection::update_state >>> handling message uid: {:?}", uid);
+            let _correlation = structured_log::enter(Correlation::new().message(&message));
+            log_event!(Level::Trace, "message_found", uid = uid, agent = "pairwise");
             self.update_state_with_message(&message)?;
             self.agent_info().clone().update_message_status(uid)?;
         } else if let SmConnection::Inviter(sm_inviter) = &self.connection_sm {
-            trace!("Connection::update_state >>> Inviter found no message to handel on main connection agent. Will check bootstrap agent.");
+            log_event!(Level::Trace, "Connection::update_state", note = "Inviter found no message to handel on main connection agent. Will check bootstrap agent.");
             if let Some((messages, bootstrap_agent_info)) = sm_inviter.get_bootstrap_agent_messages()? {
                 if let Some((uid, message)) = self.find_message_to_handle(messages) {
-                    trace!("Connection::update_state >>> handling message found on bootstrap agent uid: {:?}", uid);
+                    let _correlation = structured_log::enter(Correlation::new().message(&message));
+                    log_event!(Level::Trace, "message_found", uid = uid, agent = "bootstrap");
                     self.update_state_with_message(&message)?;
                     bootstrap_agent_info.update_message_status(uid)?;
                 }
             }
         }
 
-        trace!("Connection::update_state >>> done");
+        log_event!(Level::Trace, "Connection::update_state", note = "done");
         Ok(())
     }
 
@@ -297,12 +449,14 @@ impl Connection {
     Perform state machine transition using supplied message.
      */
     pub fn update_state_with_message(&mut self, message: &A2AMessage) -> VcxResult<()> {
-        trace!("Connection: update_state_with_message: {:?}", message);
+        let _correlation = structured_log::enter(Correlation::new().message(message));
+        log_event!(Level::Debug, "message_received", source_id = self.source_id(), state = self.state());
         if self.is_in_null_state() {
             warn!("Connection::update_state_with_message :: update state on connection in null state is ignored");
             return Ok(());
         }
 
+        cancellation::commit();
         self.handle_message(message.clone().into())?;
 
         Ok(())
@@ -312,7 +466,7 @@ impl Connection {
     Perform state machine transition using supplied message.
      */
     pub fn handle_message(&mut self, message: DidExchangeMessages) -> VcxResult<()> {
-        trace!("Connection: handle_message >>> {:?}", message);
+        log_event!(Level::Trace, "Connection::handle_message");
         self.step(message)
     }
 
@@ -320,7 +474,7 @@ impl Connection {
     Updates status of a message (received from connection counterparty) in agency.
      */
     pub fn update_message_status(&self, uid: String) -> VcxResult<()> {
-        trace!("Connection::update_message_status >>> uid: {:?}", uid);
+        log_event!(Level::Trace, "Connection::update_message_status", uid = uid);
         self.agent_info().update_message_status(uid)
     }
 
@@ -328,24 +482,73 @@ impl Connection {
     Get messages received from connection counterparty.
      */
     pub fn get_messages(&self) -> VcxResult<HashMap<String, A2AMessage>> {
-        trace!("Connection: get_messages >>>");
-        match &self.connection_sm {
-            SmConnection::Inviter(sm_inviter) => {
-                let messages = sm_inviter.agent_info().get_messages()?;
-                Ok(messages)
-            }
-            SmConnection::Invitee(sm_invitee) => {
-                let messages = sm_invitee.agent_info().get_messages()?;
-                Ok(messages)
+        log_event!(Level::Trace, "Connection::get_messages");
+        let messages = match &self.connection_sm {
+            SmConnection::Inviter(sm_inviter) => sm_inviter.agent_info().get_messages()?,
+            SmConnection::Invitee(sm_invitee) => sm_invitee.agent_info().get_messages()?
+        };
+        Ok(self.reject_disabled_protocols(messages))
+    }
+
+    /**
+    Answer pings received on completed connection, returns the number of answered pings.
+    Answered pings are marked as reviewed at the agency. Pings completing the connection are left to `update_state`.
+     */
+    pub fn answer_pings(&self) -> VcxResult<u32> {
+        log_event!(Level::Trace, "Connection::answer_pings");
+
+        if self.state() != VcxStateType::VcxStateAccepted as u32 {
+            return Err(VcxError::from_msg(VcxErrorKind::NotReady, "Cannot answer pings: Connection is not established yet"));
+        }
+
+        let did_doc = self.their_did_doc()
+            .ok_or(VcxError::from_msg(VcxErrorKind::NotReady, "Cannot answer pings: DIDDoc of the counterparty is unknown"))?;
+
+        let mut answered = 0;
+        for (uid, message) in self.get_messages()? {
+            if let A2AMessage::Ping(ping) = message {
+                handle_ping(&ping, self.agent_info(), &did_doc)?;
+                self.update_message_status(uid)?;
+                answered += 1;
             }
         }
+        Ok(answered)
+    }
+
+    /**
+    Answer messages of protocols disabled by configuration with Problem Report and drop them.
+     */
+    fn reject_disabled_protocols(&self, messages: HashMap<String, A2AMessage>) -> HashMap<String, A2AMessage> {
+        messages
+            .into_iter()
+            .filter(|(uid, message)| {
+                let family = message.family();
+                if is_family_enabled(&family) {
+                    return true;
+                }
+
+                warn!("Connection::reject_disabled_protocols >>> rejecting message of disabled protocol: {:?}", family);
+                if let Some(thread_id) = message.thread_id() {
+                    let problem_report = ProblemReport::create()
+                        .set_comment(format!("Protocol {} is not supported", family.id()))
+                        .set_thread_id(&thread_id);
+                    if let Err(err) = self.send_message(&problem_report.to_a2a_message()) {
+                        warn!("Cannot send Problem Report for rejected message: {}", err);
+                    }
+                }
+                if let Err(err) = self.update_message_status(uid.to_string()) {
+                    warn!("Cannot update status of rejected message: {}", err);
+                }
+                false
+            })
+            .collect()
     }
 
     /**
     Get messages received from connection counterparty by id.
      */
     pub fn get_message_by_id(&self, msg_id: &str) -> VcxResult<A2AMessage> {
-        trace!("Connection: get_message_by_id >>>");
+        log_event!(Level::Trace, "Connection::get_message_by_id");
         self.agent_info().get_message_by_id(msg_id)
     }
 
@@ -369,7 +572,8 @@ impl Connection {
     Sends authenticated message to connection counterparty
      */
     pub fn send_message(&self, message: &A2AMessage) -> VcxResult<()> {
-        trace!("Connection::send_message >>> message: {:?}", message);
+        let _correlation = structured_log::enter(Correlation::new().message(message));
+        log_event!(Level::Debug, "message_sent", source_id = self.source_id(), state = self.state());
 
         let did_doc = self.their_did_doc()
             .ok_or(VcxError::from_msg(VcxErrorKind::NotReady, "Cannot send message: Remote Connection information is not set"))?;
@@ -379,7 +583,7 @@ impl Connection {
     }
 
     pub fn send_message_to_self_endpoint(message: &A2AMessage, did_doc: &DidDoc) -> VcxResult<()> {
-        trace!("Connection::send_message_to_self_endpoint >>> message: {:?}, did_doc: {:?}", message, did_doc);
+        log_event!(Level::Trace, "Connection::send_message_to_self_endpoint", message_type = message.type_name());
 
         AgentInfo::send_message_anonymously(message, did_doc)
     }
@@ -397,19 +601,100 @@ impl Connection {
     }
 
     pub fn send_generic_message(&self, message: &str) -> VcxResult<String> {
-        trace!("Connection::send_generic_message >>> message: {:?}", message);
+        log_event!(Level::Trace, "Connection::send_generic_message");
 
         let message = Connection::parse_generic_message(message);
         self.send_message(&message).map(|_| String::new())
     }
 
     pub fn send_ping(&mut self, comment: Option<String>) -> VcxResult<()> {
-        trace!("Connection::send_ping >>> comment: {:?}", comment);
+        log_event!(Level::Trace, "Connection::send_ping");
         self.handle_message(DidExchangeMessages::SendPing(comment))
     }
 
+    /**
+    Sends ping requesting response on completed connection, returns id of the ping the response is threaded to.
+     */
+    pub fn send_ping_requesting_response(&self, comment: Option<String>) -> VcxResult<String> {
+        log_event!(Level::Trace, "Connection::send_ping_requesting_response");
+
+        if self.state() != VcxStateType::VcxStateAccepted as u32 {
+            return Err(VcxError::from_msg(VcxErrorKind::NotReady, "Cannot ping: Connection is not established yet"));
+        }
+
+        let ping = Ping::create()
+            .request_response()
+            .set_comment(comment);
+
+        self.send_message(&ping.to_a2a_message())?;
+        Ok(ping.id.0)
+    }
+
+    /**
+    Finds uid of the response to the ping with given id among received messages.
+     */
+    pub fn find_ping_response(&self, ping_id: &str) -> VcxResult<Option<String>> {
+        let uid = self.get_messages()?
+            .into_iter()
+            .find(|(_, message)| match message {
+                A2AMessage::PingResponse(response) => response.thread.thid.as_ref().map(String::as_str) == Some(ping_id),
+                _ => false
+            })
+            .map(|(uid, _)| uid);
+        Ok(uid)
+    }
+
+    /**
+    Asks counterparty on completed connection to take action identified by `goal_code`, returns sent message.
+     */
+    pub fn send_invite_action(&self, goal_code: &str, ack_on: Option<Vec<AckOn>>) -> VcxResult<Invite> {
+        log_event!(Level::Trace, "Connection::send_invite_action", goal_code = goal_code);
+
+        if self.state() != VcxStateType::VcxStateAccepted as u32 {
+            return Err(VcxError::from_msg(VcxErrorKind::NotReady, "Cannot send invite action: Connection is not established yet"));
+        }
+
+        let invite = Invite::create()
+            .set_goal_code(goal_code.to_string())
+            .set_ack_on(ack_on);
+
+        self.send_message(&invite.to_a2a_message())?;
+        Ok(invite)
+    }
+
+    /**
+    Accepts received invitation to take action, the inviter is acknowledged only if it asked for it.
+     */
+    pub fn accept_invite_action(&self, invite: &Invite) -> VcxResult<()> {
+        log_event!(Level::Trace, "Connection::accept_invite_action");
+
+        let ack_requested = invite.ack_on.as_ref()
+            .map(|ack_on| ack_on.contains(&AckOn::Accept))
+            .unwrap_or(false);
+
+        if ack_requested {
+            let ack = Ack::create()
+                .set_status(AckStatus::Ok)
+                .set_thread_id(&invite.thread_id());
+            self.send_message(&A2AMessage::InviteForActionAck(ack))?;
+        }
+        Ok(())
+    }
+
+    /**
+    Rejects received invitation to take action by Problem Report.
+     */
+    pub fn reject_invite_action(&self, invite: &Invite, comment: Option<String>) -> VcxResult<()> {
+        log_event!(Level::Trace, "Connection::reject_invite_action");
+
+        let problem_report = ProblemReport::create()
+            .set_comment(comment.unwrap_or(format!("Invitation to {} was rejected", invite.goal_code)))
+            .set_thread_id(&invite.thread_id());
+        self.send_message(&problem_report.to_a2a_message())
+    }
+
     pub fn delete(&self) -> VcxResult<()> {
-        trace!("Connection: delete >>> {:?}", self.source_id());
+        log_event!(Level::Trace, "Connection::delete", source_id = self.source_id());
         self.agent_info().delete()
     }
 
@@ -426,12 +711,12 @@ impl Connection {
     }
 
     pub fn send_discovery_features(&mut self, query: Option<String>, comment: Option<String>) -> VcxResult<()> {
-        trace!("Connection::send_discovery_features_query >>> query: {:?}, comment: {:?}", query, comment);
+        log_event!(Level::Trace, "Connection::send_discovery_features_query");
         self.handle_message(DidExchangeMessages::DiscoverFeatures((query, comment)))
     }
 
     pub fn get_connection_info(&self) -> VcxResult<String> {
-        trace!("Connection::get_connection_info >>>");
+        log_event!(Level::Trace, "Connection::get_connection_info");
 
         let agent_info = self.agent_info().clone();
 
@@ -455,7 +740,9 @@ impl Connection {
             None => None
         };
 
-        let connection_info = ConnectionInfo { my: current, their: remote };
+        let stats = delivery_stats::get(&agent_info.pw_did);
+
+        let connection_info = ConnectionInfo { my: current, their: remote, stats };
 
         let connection_info_json = serde_json::to_string(&connection_info)
             .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidState, format!("Cannot serialize ConnectionInfo: {:?}", err)))?;
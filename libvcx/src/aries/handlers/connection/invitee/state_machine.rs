@@ -1,3 +1,5 @@
+use log::Level;
+
 use std::collections::HashMap;
 
 use api::VcxStateType;
@@ -6,13 +8,16 @@ use aries::handlers::connection::agent_info::AgentInfo;
 use aries::handlers::connection::invitee::states::complete::CompleteState;
 use aries::handlers::connection::invitee::states::invited::InvitedState;
 use aries::handlers::connection::invitee::states::null::NullState;
+use aries::handlers::connection::invitee::states::redirected::RedirectedState;
 use aries::handlers::connection::invitee::states::requested::RequestedState;
 use aries::handlers::connection::messages::DidExchangeMessages;
 use aries::messages::a2a::A2AMessage;
 use aries::messages::a2a::protocol_registry::ProtocolRegistry;
 use aries::messages::connection::did_doc::DidDoc;
+use aries::messages::connection::did_key;
 use aries::messages::connection::invite::Invitation;
 use aries::messages::connection::problem_report::{ProblemCode, ProblemReport};
+use aries::messages::connection::redirect::Redirect;
 use aries::messages::connection::request::Request;
 use aries::messages::discovery::disclose::ProtocolDescriptor;
 
@@ -29,6 +34,7 @@ pub enum InviteeState {
     Invited(InvitedState),
     Requested(RequestedState),
     Completed(CompleteState),
+    Redirected(RedirectedState),
 }
 
 impl InviteeState {
@@ -38,8 +44,14 @@ impl InviteeState {
             InviteeState::Invited(_) => VcxStateType::VcxStateOfferSent as u32,
             InviteeState::Requested(_) => VcxStateType::VcxStateRequestReceived as u32,
             InviteeState::Completed(_) => VcxStateType::VcxStateAccepted as u32,
+            InviteeState::Redirected(_) => VcxStateType::VcxStateRedirected as u32,
         }
     }
+
+    /// Connection with the inviter described by `did_doc` which was established without the DID exchange, e.g. by the legacy proprietary protocol.
+    pub fn completed(did_doc: DidDoc) -> InviteeState {
+        InviteeState::Completed(CompleteState { did_doc, protocols: None, response: None, invitation_key: None })
+    }
 }
 
 impl SmConnectionInvitee {
@@ -86,8 +98,25 @@ impl SmConnectionInvitee {
         &self.state
     }
 
+    /// Thread of the request awaiting the response, the request may still wait in the outbound queue.
+    pub fn pending_thread_id(&self) -> Option<String> {
+        match self.state {
+            InviteeState::Requested(ref state) => Some(state.request.id.0.clone()),
+            _ => None
+        }
+    }
+
+    /// Thread of the connection exchange, known once the request is sent.
+    pub fn thread_id(&self) -> Option<String> {
+        match self.state {
+            InviteeState::Requested(ref state) => Some(state.request.id.0.clone()),
+            InviteeState::Completed(ref state) => state.response.as_ref().and_then(|response| response.thread.thid.clone()),
+            _ => None
+        }
+    }
+
     pub fn step(self, message: DidExchangeMessages) -> VcxResult<SmConnectionInvitee> {
-        trace!("SmConnectionInvitee::step >>> message: {:?}", message);
+        log_event!(Level::Trace, "SmConnectionInvitee::step");
         let SmConnectionInvitee { source_id, agent_info, state } = self;
 
         let (new_state, agent_info) =
@@ -102,6 +131,22 @@ impl SmConnectionInvitee {
             InviteeState::Invited(ref state) => Some(DidDoc::from(state.invitation.clone())),
             InviteeState::Requested(ref state) => Some(state.did_doc.clone()),
             InviteeState::Completed(ref state) => Some(state.did_doc.clone()),
+            InviteeState::Redirected(ref state) => Some(DidDoc::from(state.invitation.clone())),
+        }
+    }
+
+    pub fn set_their_did_doc(&mut self, did_doc: DidDoc) {
+        match self.state {
+            InviteeState::Requested(ref mut state) => state.did_doc = did_doc,
+            InviteeState::Completed(ref mut state) => state.did_doc = did_doc,
+            _ => {}
+        }
+    }
+
+    pub fn verify_response_signature(&self) -> VcxResult<()> {
+        match self.state {
+            InviteeState::Completed(ref state) => state.verify_response_signature(),
+            _ => Err(VcxError::from_msg(VcxErrorKind::NotReady, "Connection response is not received yet"))
         }
     }
 
@@ -112,6 +157,13 @@ impl SmConnectionInvitee {
         }
     }
 
+    pub fn get_redirect(&self) -> Option<&Redirect> {
+        match self.state {
+            InviteeState::Redirected(ref state) => Some(&state.redirect),
+            _ => None
+        }
+    }
+
     pub fn find_message_to_handle(&self, messages: HashMap<String, A2AMessage>) -> Option<(String, A2AMessage)> {
         for (uid, message) in messages {
             if self.can_handle_message(&message) {
@@ -149,15 +201,15 @@ impl SmConnectionInvitee {
             InviteeState::Requested(_) => {
                 match message {
                     A2AMessage::ConnectionResponse(_) => {
-                        debug!("Invitee received ConnectionResponse message");
+                        log_event!(Level::Debug, "message_received", message_type = "ConnectionResponse");
                         true
                     }
                     A2AMessage::ConnectionProblemReport(_) => {
-                        debug!("Invitee received ProblemReport message");
+                        log_event!(Level::Debug, "message_received", message_type = "ProblemReport");
                         true
                     }
                     _ => {
-                        debug!("Invitee received unexpected message: {:?}", message);
+                        log_event!(Level::Debug, "unexpected_message", message_type = message.type_name());
                         false
                     }
                 }
@@ -165,29 +217,29 @@ impl SmConnectionInvitee {
             InviteeState::Completed(_) => {
                 match message {
                     A2AMessage::Ping(_) => {
-                        debug!("Ping message received");
+                        log_event!(Level::Debug, "message_received", message_type = "Ping");
                         true
                     }
                     A2AMessage::PingResponse(_) => {
-                        debug!("PingResponse message received");
+                        log_event!(Level::Debug, "message_received", message_type = "PingResponse");
                         true
                     }
                     A2AMessage::Query(_) => {
-                        debug!("Query message received");
+                        log_event!(Level::Debug, "message_received", message_type = "Query");
                         true
                     }
                     A2AMessage::Disclose(_) => {
-                        debug!("Disclose message received");
+                        log_event!(Level::Debug, "message_received", message_type = "Disclose");
                         true
                     }
                     _ => {
-                        debug!("Unexpected message received in Completed state: {:?}", message);
+                        log_event!(Level::Debug, "unexpected_message", state = "Completed", message_type = message.type_name());
                         false
                     }
                 }
             }
             _ => {
-                debug!("Unexpected message received: message: {:?}", message);
+                log_event!(Level::Debug, "unexpected_message", message_type = message.type_name());
                 false
             }
         }
@@ -211,13 +263,21 @@ impl SmConnectionInvitee {
                         agent_info = agent_info.create_agent()?;
                         let request = Request::create()
                             .set_label(source_id.to_string())
-                            .set_did(agent_info.pw_did.to_string())
+                            .set_did(agent_info.pairwise_did()?)
                             .set_service_endpoint(agent_info.agency_endpoint()?)
-                            .set_keys(agent_info.recipient_keys(), agent_info.routing_keys()?);
+                            .set_keys(agent_info.recipient_keys(), did_key::encode_outbound_keys(agent_info.routing_keys()?)?);
 
                         agent_info.send_message(&request.to_a2a_message(), &DidDoc::from(state.invitation.clone()))?;
                         InviteeState::Requested((state, request).into())
                     }
+                    DidExchangeMessages::SendRedirect((redirect, existing_agent_info)) => {
+                        let redirect = redirect
+                            .set_thread_id(&state.invitation.id.0)
+                            .sign()?;
+
+                        existing_agent_info.send_message(&redirect.to_a2a_message(), &DidDoc::from(state.invitation.clone()))?;
+                        InviteeState::Redirected((state, redirect).into())
+                    }
                     DidExchangeMessages::ProblemReportReceived(problem_report) => {
                         InviteeState::Null((state, problem_report).into())
                     }
@@ -229,9 +289,9 @@ impl SmConnectionInvitee {
             InviteeState::Requested(state) => {
                 match message {
                     DidExchangeMessages::ExchangeResponseReceived(response) => {
-                        match state.handle_connection_response(response, &agent_info) {
-                            Ok(response) => {
-                                InviteeState::Completed((state, response).into())
+                        match state.handle_connection_response(response.clone(), &agent_info) {
+                            Ok(decoded_response) => {
+                                InviteeState::Completed((state, response, decoded_response).into())
                             }
                             Err(err) => {
                                 let problem_report = ProblemReport::create()
@@ -254,6 +314,9 @@ impl SmConnectionInvitee {
             InviteeState::Completed(state) => {
                 state.handle_message(message, &agent_info)?
             }
+            InviteeState::Redirected(state) => {
+                InviteeState::Redirected(state)
+            }
         };
         Ok((new_state, agent_info))
     }
@@ -266,6 +329,7 @@ pub mod test {
     use aries::messages::ack::tests::_ack;
     use aries::messages::connection::invite::tests::_invitation;
     use aries::messages::connection::problem_report::tests::_problem_report;
+    use aries::messages::connection::redirect::tests::_redirect;
     use aries::messages::connection::request::tests::_request;
     use aries::messages::connection::response::tests::_signed_response;
     use aries::messages::discovery::disclose::tests::_disclose;
@@ -383,6 +447,28 @@ pub mod test {
                 did_exchange_sm = did_exchange_sm.step(DidExchangeMessages::Connect()).unwrap();
 
                 assert_match!(InviteeState::Requested(_), did_exchange_sm.state);
+                match did_exchange_sm.state {
+                    InviteeState::Requested(ref state) => assert_eq!(Some(state.request.id.0.clone()), did_exchange_sm.pending_thread_id()),
+                    _ => panic!("Unexpected state")
+                }
+            }
+
+            #[test]
+            #[cfg(feature = "general_test")]
+            fn test_did_exchange_handle_send_redirect_message_from_invited_state() {
+                let _setup = AgencyModeSetup::init();
+
+                let mut did_exchange_sm = invitee_sm().to_invitee_invited_state();
+                let invitation_id = did_exchange_sm.get_invitation().unwrap().id.0.clone();
+
+                did_exchange_sm = did_exchange_sm.step(DidExchangeMessages::SendRedirect((_redirect(), AgentInfo::default()))).unwrap();
+
+                assert_match!(InviteeState::Redirected(_), did_exchange_sm.state);
+                assert_eq!(VcxStateType::VcxStateRedirected as u32, did_exchange_sm.state());
+
+                let redirect = did_exchange_sm.get_redirect().unwrap();
+                assert_eq!(Some(invitation_id), redirect.thread.thid);
+                redirect.verify().unwrap();
             }
 
             #[test]
@@ -426,6 +512,23 @@ pub mod test {
                 assert_match!(InviteeState::Completed(_), did_exchange_sm.state);
             }
 
+            #[test]
+            #[cfg(feature = "general_test")]
+            fn test_did_exchange_verify_response_signature() {
+                let _setup = AgencyModeSetup::init();
+
+                let did_exchange_sm = invitee_sm().to_invitee_requested_state();
+                assert_eq!(did_exchange_sm.verify_response_signature().unwrap_err().kind(), VcxErrorKind::NotReady);
+
+                let mut did_exchange_sm = did_exchange_sm.step(DidExchangeMessages::ExchangeResponseReceived(_response("GJ1SzoWzavQYfNL9XkaJdrQejfztN4XqdsiV4ct3LXKL"))).unwrap();
+                did_exchange_sm.verify_response_signature().unwrap();
+
+                if let InviteeState::Completed(ref mut state) = did_exchange_sm.state {
+                    state.invitation_key = Some(String::from("CnEDk9HrMnmiHXEV1WFgbVCRteYnPqsJwrTdcZaNhFVW"));
+                }
+                assert_eq!(did_exchange_sm.verify_response_signature().unwrap_err().kind(), VcxErrorKind::InvalidJson);
+            }
+
             #[test]
             #[cfg(feature = "general_test")]
             fn test_did_exchange_handle_invalid_response_message_from_requested_state() {
@@ -1,10 +1,13 @@
-use error::VcxResult;
+use log::Level;
+
+use error::prelude::*;
 use aries::handlers::connection::agent_info::AgentInfo;
 use aries::handlers::connection::invitee::state_machine::InviteeState;
 use aries::handlers::connection::messages::DidExchangeMessages;
 use aries::handlers::connection::util::handle_ping;
 use aries::messages::a2a::protocol_registry::ProtocolRegistry;
 use aries::messages::connection::did_doc::DidDoc;
+use aries::messages::connection::response::SignedResponse;
 use aries::messages::discovery::disclose::{Disclose, ProtocolDescriptor};
 use aries::messages::discovery::query::Query;
 use aries::messages::trust_ping::ping::Ping;
@@ -13,16 +16,28 @@ use aries::messages::trust_ping::ping::Ping;
 pub struct CompleteState {
     pub did_doc: DidDoc,
     pub protocols: Option<Vec<ProtocolDescriptor>>,
+    #[serde(default)]
+    pub response: Option<SignedResponse>,
+    #[serde(default)]
+    pub invitation_key: Option<String>,
 }
 
 impl From<(CompleteState, Vec<ProtocolDescriptor>)> for CompleteState {
     fn from((state, protocols): (CompleteState, Vec<ProtocolDescriptor>)) -> CompleteState {
-        trace!("ConnectionInvitee: transit state from CompleteState to CompleteState");
-        CompleteState { did_doc: state.did_doc, protocols: Some(protocols) }
+        log_event!(Level::Trace, "state_transition", from = "CompleteState", to = "CompleteState");
+        CompleteState { protocols: Some(protocols), ..state }
     }
 }
 
 impl CompleteState {
+    /// Checks the stored connection response was signed by the key of the invitation.
+    pub fn verify_response_signature(&self) -> VcxResult<()> {
+        match (&self.response, &self.invitation_key) {
+            (Some(response), Some(invitation_key)) => response.verify(invitation_key),
+            _ => Err(VcxError::from_msg(VcxErrorKind::NotReady, "Connection response was not stored for the Connection"))
+        }
+    }
+
     pub fn handle_message(self, message: DidExchangeMessages, agent_info: &AgentInfo) -> VcxResult<InviteeState> {
         Ok(match message {
             DidExchangeMessages::SendPing(comment) => {
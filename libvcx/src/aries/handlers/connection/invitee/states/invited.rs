@@ -1,8 +1,12 @@
+use log::Level;
+
 use aries::handlers::connection::invitee::states::null::NullState;
+use aries::handlers::connection::invitee::states::redirected::RedirectedState;
 use aries::handlers::connection::invitee::states::requested::RequestedState;
 use aries::messages::connection::did_doc::DidDoc;
 use aries::messages::connection::invite::Invitation;
 use aries::messages::connection::problem_report::ProblemReport;
+use aries::messages::connection::redirect::Redirect;
 use aries::messages::connection::request::Request;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,16 +14,23 @@ pub struct InvitedState {
     pub invitation: Invitation
 }
 
+impl From<(InvitedState, Redirect)> for RedirectedState {
+    fn from((state, redirect): (InvitedState, Redirect)) -> RedirectedState {
+        log_event!(Level::Trace, "state_transition", from = "InvitedState", to = "RedirectedState");
+        RedirectedState { invitation: state.invitation, redirect }
+    }
+}
+
 impl From<(InvitedState, ProblemReport)> for NullState {
     fn from((_state, _error): (InvitedState, ProblemReport)) -> NullState {
-        trace!("ConnectionInvitee: transit state from InvitedState to NullState");
+        log_event!(Level::Trace, "state_transition", from = "InvitedState", to = "NullState");
         NullState {}
     }
 }
 
 impl From<(InvitedState, Request)> for RequestedState {
     fn from((state, request): (InvitedState, Request)) -> RequestedState {
-        trace!("ConnectionInvitee: transit state from InvitedState to RequestedState");
+        log_event!(Level::Trace, "state_transition", from = "InvitedState", to = "RequestedState");
         RequestedState { request, did_doc: DidDoc::from(state.invitation) }
     }
 }
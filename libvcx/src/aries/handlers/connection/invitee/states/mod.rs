@@ -1,4 +1,5 @@
 pub(super) mod null;
 pub(super) mod invited;
 pub(super) mod requested;
-pub(super) mod complete;
\ No newline at end of file
+pub(super) mod complete;
+pub(super) mod redirected;
\ No newline at end of file
@@ -1,3 +1,5 @@
+use log::Level;
+
 use aries::handlers::connection::invitee::states::invited::InvitedState;
 use aries::messages::connection::invite::Invitation;
 
@@ -6,7 +8,7 @@ pub struct NullState {}
 
 impl From<(NullState, Invitation)> for InvitedState {
     fn from((_state, invitation): (NullState, Invitation)) -> InvitedState {
-        trace!("ConnectionInvitee: transit state from NullState to InvitedState");
+        log_event!(Level::Trace, "state_transition", from = "NullState", to = "InvitedState");
         InvitedState { invitation }
     }
 }
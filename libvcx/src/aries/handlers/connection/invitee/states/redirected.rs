@@ -0,0 +1,8 @@
+use aries::messages::connection::invite::Invitation;
+use aries::messages::connection::redirect::Redirect;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedirectedState {
+    pub invitation: Invitation,
+    pub redirect: Redirect,
+}
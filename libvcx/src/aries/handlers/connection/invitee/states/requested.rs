@@ -1,3 +1,5 @@
+use log::Level;
+
 use error::prelude::*;
 use aries::handlers::connection::agent_info::AgentInfo;
 use aries::handlers::connection::invitee::states::complete::CompleteState;
@@ -18,21 +20,26 @@ pub struct RequestedState {
 
 impl From<(RequestedState, ProblemReport)> for NullState {
     fn from((_state, _error): (RequestedState, ProblemReport)) -> NullState {
-        trace!("ConnectionInvitee: transit state from RequestedState to NullState");
+        log_event!(Level::Trace, "state_transition", from = "RequestedState", to = "NullState");
         NullState {}
     }
 }
 
-impl From<(RequestedState, Response)> for CompleteState {
-    fn from((_state, response): (RequestedState, Response)) -> CompleteState {
-        trace!("ConnectionInvitee: transit state from RequestedState to CompleteState");
-        CompleteState { did_doc: response.connection.did_doc, protocols: None }
+impl From<(RequestedState, SignedResponse, Response)> for CompleteState {
+    fn from((state, signed_response, response): (RequestedState, SignedResponse, Response)) -> CompleteState {
+        log_event!(Level::Trace, "state_transition", from = "RequestedState", to = "CompleteState");
+        CompleteState {
+            did_doc: response.connection.did_doc,
+            protocols: None,
+            response: Some(signed_response),
+            invitation_key: state.did_doc.recipient_keys().get(0).cloned(),
+        }
     }
 }
 
 impl RequestedState {
     pub fn handle_connection_response(&self, response: SignedResponse, agent_info: &AgentInfo) -> VcxResult<Response> {
-        trace!("ConnectionInvitee:handle_connection_response >>> response: {:?}, agent_info: {:?}", response, agent_info);
+        log_event!(Level::Trace, "ConnectionInvitee::handle_connection_response");
 
         let remote_vk: String = self.did_doc.recipient_keys().get(0).cloned()
             .ok_or(VcxError::from_msg(VcxErrorKind::InvalidState, "Cannot handle Response: Remote Verkey not found"))?;
@@ -1,18 +1,23 @@
+use log::Level;
+
 use std::collections::HashMap;
 
 use api::VcxStateType;
 use error::prelude::*;
-use aries::handlers::connection::agent_info::AgentInfo;
+use aries::handlers::connection::agent_info::{AgentInfo, EndpointOverride};
 use aries::handlers::connection::inviter::states::complete::CompleteState;
 use aries::handlers::connection::inviter::states::invited::InvitedState;
 use aries::handlers::connection::inviter::states::null::NullState;
+use aries::handlers::connection::inviter::states::redirected::RedirectedState;
 use aries::handlers::connection::inviter::states::responded::RespondedState;
 use aries::handlers::connection::messages::DidExchangeMessages;
 use aries::messages::a2a::A2AMessage;
 use aries::messages::a2a::protocol_registry::ProtocolRegistry;
 use aries::messages::connection::did_doc::DidDoc;
+use aries::messages::connection::did_key;
 use aries::messages::connection::invite::Invitation;
 use aries::messages::connection::problem_report::{ProblemCode, ProblemReport};
+use aries::messages::connection::redirect::Redirect;
 use aries::messages::discovery::disclose::ProtocolDescriptor;
 use aries::messages::trust_ping::ping::Ping;
 
@@ -29,6 +34,7 @@ pub enum InviterState {
     Invited(InvitedState),
     Responded(RespondedState),
     Completed(CompleteState),
+    Redirected(RedirectedState),
 }
 
 impl InviterState {
@@ -38,8 +44,14 @@ impl InviterState {
             InviterState::Invited(_) => VcxStateType::VcxStateOfferSent as u32,
             InviterState::Responded(_) => VcxStateType::VcxStateRequestReceived as u32,
             InviterState::Completed(_) => VcxStateType::VcxStateAccepted as u32,
+            InviterState::Redirected(_) => VcxStateType::VcxStateRedirected as u32,
         }
     }
+
+    /// Connection whose `invitation` was sent without the DID exchange, e.g. by the legacy proprietary protocol.
+    pub fn invited(invitation: Invitation) -> InviterState {
+        InviterState::Invited(InvitedState { invitation })
+    }
 }
 
 impl SmConnectionInviter {
@@ -82,15 +94,28 @@ impl SmConnectionInviter {
         &self.state
     }
 
+    /// Thread of the response awaiting the acknowledgement, the response may still wait in the outbound queue.
+    pub fn pending_thread_id(&self) -> Option<String> {
+        match self.state {
+            InviterState::Responded(ref state) => state.response.thread.thid.clone(),
+            _ => None
+        }
+    }
+
+    /// Thread of the connection exchange, known once the request was answered and until the connection completes.
+    pub fn thread_id(&self) -> Option<String> {
+        self.pending_thread_id()
+    }
+
     pub fn step(self, message: DidExchangeMessages) -> VcxResult<SmConnectionInviter> {
-        trace!("SmConnectionInviter::step >>> message: {:?}", message);
+        log_event!(Level::Trace, "SmConnectionInviter::step");
         let SmConnectionInviter { source_id, agent_info, state } = self;
 
-        trace!("SmConnectionInviter::step :: current state = {:?}", &state);
+        log_event!(Level::Trace, "SmConnectionInviter::step", current_state = state.code());
         let (new_state, agent_info) =
             SmConnectionInviter::inviter_step(state, message, &source_id, agent_info)?;
 
-        trace!("SmConnectionInviter::step :: new state = {:?}", &new_state);
+        log_event!(Level::Trace, "SmConnectionInviter::step", new_state = new_state.code());
         Ok(SmConnectionInviter { source_id, agent_info, state: new_state })
     }
 
@@ -100,6 +125,15 @@ impl SmConnectionInviter {
             InviterState::Invited(ref _state) => None,
             InviterState::Responded(ref state) => Some(state.did_doc.clone()),
             InviterState::Completed(ref state) => Some(state.did_doc.clone()),
+            InviterState::Redirected(ref _state) => None,
+        }
+    }
+
+    pub fn set_their_did_doc(&mut self, did_doc: DidDoc) {
+        match self.state {
+            InviterState::Responded(ref mut state) => state.did_doc = did_doc,
+            InviterState::Completed(ref mut state) => state.did_doc = did_doc,
+            _ => {}
         }
     }
 
@@ -110,6 +144,13 @@ impl SmConnectionInviter {
         }
     }
 
+    pub fn get_redirect(&self) -> Option<&Redirect> {
+        match self.state {
+            InviterState::Redirected(ref state) => Some(&state.redirect),
+            _ => None
+        }
+    }
+
     pub fn find_message_to_handle(&self, messages: HashMap<String, A2AMessage>) -> Option<(String, A2AMessage)> {
         for (uid, message) in messages {
             if self.can_handle_message(&message) {
@@ -161,20 +202,38 @@ impl SmConnectionInviter {
         SmConnectionInviter::_build_inviter(source_id)
     }
 
+    pub fn new_with_endpoint(source_id: &str, endpoint: EndpointOverride) -> Self {
+        SmConnectionInviter {
+            agent_info: AgentInfo::with_endpoint(endpoint),
+            ..SmConnectionInviter::_build_inviter(source_id)
+        }
+    }
+
+    pub fn new_with_agent_info(source_id: &str, agent_info: AgentInfo) -> Self {
+        SmConnectionInviter {
+            agent_info,
+            ..SmConnectionInviter::_build_inviter(source_id)
+        }
+    }
+
     pub fn can_handle_message(&self, message: &A2AMessage) -> bool {
         match self.state {
             InviterState::Invited(_) => {
                 match message {
                     A2AMessage::ConnectionRequest(_) => {
-                        debug!("Inviter received ConnectionRequest message");
+                        log_event!(Level::Debug, "message_received", message_type = "ConnectionRequest");
+                        true
+                    }
+                    A2AMessage::ConnectionRedirect(_) => {
+                        log_event!(Level::Debug, "message_received", message_type = "ConnectionRedirect");
                         true
                     }
                     A2AMessage::ConnectionProblemReport(_) => {
-                        debug!("Inviter received ProblemReport message");
+                        log_event!(Level::Debug, "message_received", message_type = "ProblemReport");
                         true
                     }
                     _ => {
-                        debug!("Inviter received unexpected message: {:?}", message);
+                        log_event!(Level::Debug, "unexpected_message", message_type = message.type_name());
                         false
                     }
                 }
@@ -182,23 +241,23 @@ impl SmConnectionInviter {
             InviterState::Responded(_) => {
                 match message {
                     A2AMessage::Ack(_) => {
-                        debug!("Ack message received");
+                        log_event!(Level::Debug, "message_received", message_type = "Ack");
                         true
                     }
                     A2AMessage::Ping(_) => {
-                        debug!("Ping message received");
+                        log_event!(Level::Debug, "message_received", message_type = "Ping");
                         true
                     }
                     A2AMessage::PingResponse(_) => {
-                        debug!("PingResponse message received");
+                        log_event!(Level::Debug, "message_received", message_type = "PingResponse");
                         true
                     }
                     A2AMessage::ConnectionProblemReport(_) => {
-                        debug!("ProblemReport message received");
+                        log_event!(Level::Debug, "message_received", message_type = "ProblemReport");
                         true
                     }
                     _ => {
-                        debug!("Unexpected message received in Responded state: {:?}", message);
+                        log_event!(Level::Debug, "unexpected_message", state = "Responded", message_type = message.type_name());
                         false
                     }
                 }
@@ -206,29 +265,29 @@ impl SmConnectionInviter {
             InviterState::Completed(_) => {
                 match message {
                     A2AMessage::Ping(_) => {
-                        debug!("Ping message received");
+                        log_event!(Level::Debug, "message_received", message_type = "Ping");
                         true
                     }
                     A2AMessage::PingResponse(_) => {
-                        debug!("PingResponse message received");
+                        log_event!(Level::Debug, "message_received", message_type = "PingResponse");
                         true
                     }
                     A2AMessage::Query(_) => {
-                        debug!("Query message received");
+                        log_event!(Level::Debug, "message_received", message_type = "Query");
                         true
                     }
                     A2AMessage::Disclose(_) => {
-                        debug!("Disclose message received");
+                        log_event!(Level::Debug, "message_received", message_type = "Disclose");
                         true
                     }
                     _ => {
-                        debug!("Unexpected message received in Completed state: {:?}", message);
+                        log_event!(Level::Debug, "unexpected_message", state = "Completed", message_type = message.type_name());
                         false
                     }
                 }
             }
             _ => {
-                debug!("Unexpected message received: message: {:?}", message);
+                log_event!(Level::Debug, "unexpected_message", message_type = message.type_name());
                 false
             }
         }
@@ -244,8 +303,8 @@ impl SmConnectionInviter {
                         let invite: Invitation = Invitation::create()
                             .set_label(source_id.to_string())
                             .set_service_endpoint(agent_info.agency_endpoint()?)
-                            .set_recipient_keys(agent_info.recipient_keys())
-                            .set_routing_keys(agent_info.routing_keys()?);
+                            .set_recipient_keys(did_key::encode_outbound_keys(agent_info.recipient_keys())?)
+                            .set_routing_keys(did_key::encode_outbound_keys(agent_info.routing_keys()?)?);
 
                         InviterState::Invited((state, invite).into())
                     }
@@ -256,8 +315,10 @@ impl SmConnectionInviter {
             }
             InviterState::Invited(state) => {
                 match message {
-                    DidExchangeMessages::ExchangeRequestReceived(request) => {
-                        match state.handle_connection_request(&request, &agent_info) {
+                    DidExchangeMessages::ExchangeRequestReceived(mut request) => {
+                        let result = request.connection.resolve_did_doc()
+                            .and_then(|_| state.handle_connection_request(&request, &agent_info));
+                        match result {
                             Ok((response, new_agent_info)) => {
                                 let prev_agent_info = agent_info.clone();
                                 agent_info = new_agent_info;
@@ -274,6 +335,20 @@ impl SmConnectionInviter {
                             }
                         }
                     }
+                    DidExchangeMessages::RedirectReceived(redirect) => {
+                        match redirect.verify_for_invitation(&state.invitation.id.0) {
+                            Ok(()) => {
+                                InviterState::Redirected((state, redirect).into())
+                            }
+                            Err(err) => {
+                                let problem_report = ProblemReport::create()
+                                    .set_problem_code(ProblemCode::RequestProcessingError)
+                                    .set_explain(err.to_string())
+                                    .set_thread_id(&redirect.id.0);
+                                InviterState::Null((state, problem_report).into())
+                            }
+                        }
+                    }
                     DidExchangeMessages::ProblemReportReceived(problem_report) => {
                         InviterState::Null((state, problem_report).into())
                     }
@@ -314,6 +389,9 @@ impl SmConnectionInviter {
             InviterState::Completed(state) => {
                 state.handle_message(message, &agent_info)?
             }
+            InviterState::Redirected(state) => {
+                InviterState::Redirected(state)
+            }
         };
         Ok((new_state, agent_info))
     }
@@ -324,8 +402,10 @@ impl SmConnectionInviter {
 pub mod test {
     use utils::devsetup::SetupAriesMocks;
     use aries::messages::ack::tests::_ack;
+    use aries::messages::connection::did_peer;
     use aries::messages::connection::invite::tests::_invitation;
     use aries::messages::connection::problem_report::tests::_problem_report;
+    use aries::messages::connection::redirect::tests::_redirect;
     use aries::messages::connection::request::tests::_request;
     use aries::messages::connection::response::tests::_signed_response;
     use aries::messages::discovery::disclose::tests::_disclose;
@@ -443,6 +523,24 @@ pub mod test {
                 assert_match!(InviterState::Null(_), did_exchange_sm.state);
             }
 
+            #[test]
+            #[cfg(feature = "general_test")]
+            fn test_did_exchange_handle_exchange_request_with_did_peer_from_invited_state() {
+                let _setup = AgencyModeSetup::init();
+
+                let mut did_exchange_sm = inviter_sm().to_inviter_invited_state();
+
+                let mut request = _request();
+                request.connection.did = did_peer::generate_did_peer_2(&request.connection.did_doc.recipient_keys(),
+                                                                       &request.connection.did_doc.routing_keys(),
+                                                                       &request.connection.did_doc.get_endpoint()).unwrap();
+                request.connection.did_doc = DidDoc::default();
+
+                did_exchange_sm = did_exchange_sm.step(DidExchangeMessages::ExchangeRequestReceived(request)).unwrap();
+
+                assert_match!(InviterState::Responded(_), did_exchange_sm.state);
+            }
+
             #[test]
             #[cfg(feature = "general_test")]
             fn test_did_exchange_handle_problem_report_message_from_invited_state() {
@@ -455,6 +553,57 @@ pub mod test {
                 assert_match!(InviterState::Null(_), did_exchange_sm.state);
             }
 
+            #[test]
+            #[cfg(feature = "general_test")]
+            fn test_did_exchange_handle_redirect_message_from_invited_state() {
+                let _setup = AgencyModeSetup::init();
+
+                let did_exchange_sm = inviter_sm().to_inviter_invited_state();
+                let invitation_id = did_exchange_sm.get_invitation().unwrap().id.0.clone();
+                let redirect = _redirect().set_thread_id(&invitation_id).sign().unwrap();
+                let sender_verkey = Some(redirect.verkey.clone());
+
+                let did_exchange_sm = did_exchange_sm
+                    .step(DidExchangeMessages::RedirectReceived(redirect.set_sender_verkey(sender_verkey))).unwrap();
+
+                assert_match!(InviterState::Redirected(_), did_exchange_sm.state);
+                assert_eq!(VcxStateType::VcxStateRedirected as u32, did_exchange_sm.state());
+                assert_eq!(_redirect().their_did, did_exchange_sm.get_redirect().unwrap().their_did);
+            }
+
+            #[test]
+            #[cfg(feature = "general_test")]
+            fn test_did_exchange_rejects_redirect_of_other_invitation_or_sender() {
+                let _setup = AgencyModeSetup::init();
+
+                // signed for another invitation
+                let redirect = _redirect().sign().unwrap();
+                let sender_verkey = Some(redirect.verkey.clone());
+                let did_exchange_sm = inviter_sm().to_inviter_invited_state()
+                    .step(DidExchangeMessages::RedirectReceived(redirect.set_sender_verkey(sender_verkey))).unwrap();
+                assert_match!(InviterState::Null(_), did_exchange_sm.state);
+
+                // sent by other key than the announced one
+                let did_exchange_sm = inviter_sm().to_inviter_invited_state();
+                let invitation_id = did_exchange_sm.get_invitation().unwrap().id.0.clone();
+                let redirect = _redirect().set_thread_id(&invitation_id).sign().unwrap()
+                    .set_sender_verkey(Some(String::from("GJ1SzoWzavQYfNL9XkaJdrQejfztN4XqdsiV4ct3LXKL")));
+                let did_exchange_sm = did_exchange_sm.step(DidExchangeMessages::RedirectReceived(redirect)).unwrap();
+                assert_match!(InviterState::Null(_), did_exchange_sm.state);
+            }
+
+            #[test]
+            #[cfg(feature = "general_test")]
+            fn test_did_exchange_handle_invalid_redirect_message_from_invited_state() {
+                let _setup = AgencyModeSetup::init();
+
+                let redirect = Redirect { signature: String::from("not base64!"), .._redirect() };
+                let did_exchange_sm = inviter_sm().to_inviter_invited_state()
+                    .step(DidExchangeMessages::RedirectReceived(redirect)).unwrap();
+
+                assert_match!(InviterState::Null(_), did_exchange_sm.state);
+            }
+
             #[test]
             #[cfg(feature = "general_test")]
             fn test_did_exchange_handle_other_messages_from_invited_state() {
@@ -1,3 +1,5 @@
+use log::Level;
+
 use error::VcxResult;
 use aries::handlers::connection::agent_info::AgentInfo;
 use aries::handlers::connection::inviter::state_machine::InviterState;
@@ -17,7 +19,7 @@ pub struct CompleteState {
 
 impl From<(CompleteState, Vec<ProtocolDescriptor>)> for CompleteState {
     fn from((state, protocols): (CompleteState, Vec<ProtocolDescriptor>)) -> CompleteState {
-        trace!("ConnectionInviter: transit state from CompleteState to CompleteState");
+        log_event!(Level::Trace, "state_transition", from = "CompleteState", to = "CompleteState");
         CompleteState { did_doc: state.did_doc, protocols: Some(protocols) }
     }
 }
@@ -1,9 +1,14 @@
+use log::Level;
+
 use error::prelude::*;
 use aries::handlers::connection::agent_info::AgentInfo;
 use aries::handlers::connection::inviter::states::null::NullState;
+use aries::handlers::connection::inviter::states::redirected::RedirectedState;
 use aries::handlers::connection::inviter::states::responded::RespondedState;
+use aries::messages::connection::did_key;
 use aries::messages::connection::invite::Invitation;
 use aries::messages::connection::problem_report::ProblemReport;
+use aries::messages::connection::redirect::Redirect;
 use aries::messages::connection::request::Request;
 use aries::messages::connection::response::{Response, SignedResponse};
 
@@ -12,16 +17,23 @@ pub struct InvitedState {
     pub invitation: Invitation
 }
 
+impl From<(InvitedState, Redirect)> for RedirectedState {
+    fn from((state, redirect): (InvitedState, Redirect)) -> RedirectedState {
+        log_event!(Level::Trace, "state_transition", from = "InvitedState", to = "RedirectedState");
+        RedirectedState { invitation: state.invitation, redirect }
+    }
+}
+
 impl From<(InvitedState, ProblemReport)> for NullState {
     fn from((_state, _error): (InvitedState, ProblemReport)) -> NullState {
-        trace!("ConnectionInviter: transit state from InvitedState to NullState");
+        log_event!(Level::Trace, "state_transition", from = "InvitedState", to = "NullState");
         NullState {}
     }
 }
 
 impl From<(InvitedState, Request, SignedResponse, AgentInfo)> for RespondedState {
     fn from((_state, request, response, prev_agent_info): (InvitedState, Request, SignedResponse, AgentInfo)) -> RespondedState {
-        trace!("ConnectionInviter: transit state from InvitedState to RespondedState");
+        log_event!(Level::Trace, "state_transition", from = "InvitedState", to = "RespondedState");
         RespondedState { response, did_doc: request.connection.did_doc, prev_agent_info }
     }
 }
@@ -29,7 +41,7 @@ impl From<(InvitedState, Request, SignedResponse, AgentInfo)> for RespondedState
 impl InvitedState {
     pub fn handle_connection_request(&self, request: &Request,
                                      agent_info: &AgentInfo) -> VcxResult<(SignedResponse, AgentInfo)> {
-        trace!("ConnectionInviter:handle_connection_request >>> request: {:?}, agent_info: {:?}", request, agent_info);
+        log_event!(Level::Trace, "ConnectionInviter::handle_connection_request");
 
         request.connection.did_doc.validate()?;
 
@@ -39,9 +51,9 @@ impl InvitedState {
         let new_agent_info: AgentInfo = agent_info.create_agent()?;
 
         let response = Response::create()
-            .set_did(new_agent_info.pw_did.to_string())
+            .set_did(new_agent_info.pairwise_did()?)
             .set_service_endpoint(new_agent_info.agency_endpoint()?)
-            .set_keys(new_agent_info.recipient_keys(), new_agent_info.routing_keys()?)
+            .set_keys(new_agent_info.recipient_keys(), did_key::encode_outbound_keys(new_agent_info.routing_keys()?)?)
             .ask_for_ack();
 
         let signed_response = response.clone()
@@ -1,3 +1,5 @@
+use log::Level;
+
 use error::prelude::*;
 use aries::handlers::connection::agent_info::AgentInfo;
 use aries::handlers::connection::inviter::states::complete::CompleteState;
@@ -20,28 +22,28 @@ pub struct RespondedState {
 
 impl From<(RespondedState, ProblemReport)> for NullState {
     fn from((_state, _error): (RespondedState, ProblemReport)) -> NullState {
-        trace!("ConnectionInviter: transit state from RespondedState to NullState");
+        log_event!(Level::Trace, "state_transition", from = "RespondedState", to = "NullState");
         NullState {}
     }
 }
 
 impl From<(RespondedState, Ack)> for CompleteState {
     fn from((state, _ack): (RespondedState, Ack)) -> CompleteState {
-        trace!("ConnectionInviter: transit state from RespondedState to CompleteState");
+        log_event!(Level::Trace, "state_transition", from = "RespondedState", to = "CompleteState");
         CompleteState { did_doc: state.did_doc, protocols: None }
     }
 }
 
 impl From<(RespondedState, Ping)> for CompleteState {
     fn from((state, _ping): (RespondedState, Ping)) -> CompleteState {
-        trace!("ConnectionInviter: transit state from RespondedState to CompleteState");
+        log_event!(Level::Trace, "state_transition", from = "RespondedState", to = "CompleteState");
         CompleteState { did_doc: state.did_doc, protocols: None }
     }
 }
 
 impl From<(RespondedState, PingResponse)> for CompleteState {
     fn from((state, _ping_response): (RespondedState, PingResponse)) -> CompleteState {
-        trace!("ConnectionInviter: transit state from RespondedState to CompleteState");
+        log_event!(Level::Trace, "state_transition", from = "RespondedState", to = "CompleteState");
         CompleteState { did_doc: state.did_doc, protocols: None }
     }
 }
@@ -1,7 +1,9 @@
+use aries::handlers::connection::agent_info::AgentInfo;
 use aries::messages::a2a::A2AMessage;
 use aries::messages::ack::Ack;
 use aries::messages::connection::invite::Invitation;
 use aries::messages::connection::problem_report::ProblemReport;
+use aries::messages::connection::redirect::Redirect;
 use aries::messages::connection::request::Request;
 use aries::messages::connection::response::SignedResponse;
 use aries::messages::discovery::disclose::Disclose;
@@ -17,6 +19,8 @@ pub enum DidExchangeMessages {
     ExchangeResponseReceived(SignedResponse),
     AckReceived(Ack),
     ProblemReportReceived(ProblemReport),
+    SendRedirect((Redirect, AgentInfo)),
+    RedirectReceived(Redirect),
     SendPing(Option<String>),
     PingReceived(Ping),
     PingResponseReceived(PingResponse),
@@ -56,6 +60,9 @@ impl From<A2AMessage> for DidExchangeMessages {
             A2AMessage::ConnectionProblemReport(report) => {
                 DidExchangeMessages::ProblemReportReceived(report)
             }
+            A2AMessage::ConnectionRedirect(redirect) => {
+                DidExchangeMessages::RedirectReceived(redirect)
+            }
             _ => {
                 DidExchangeMessages::Unknown
             }
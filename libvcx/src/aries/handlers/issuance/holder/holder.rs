@@ -1,5 +1,8 @@
 // Holder
 
+use log::Level;
+
+use api::VcxStateType;
 use connection;
 use error::prelude::*;
 use aries::handlers::issuance::holder::state_machine::HolderSM;
@@ -7,38 +10,99 @@ use aries::handlers::issuance::messages::CredentialIssuanceMessage;
 use aries::messages::a2a::A2AMessage;
 use aries::messages::issuance::credential::Credential;
 use aries::messages::issuance::credential_offer::CredentialOffer;
+use aries::messages::issuance::CredentialValue;
+use utils::cancellation;
+use utils::credential_store::CredentialMetadata;
+use utils::metadata::{self, Metadata};
+use utils::structured_log::{self, Correlation};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Holder {
-    holder_sm: HolderSM
+    holder_sm: HolderSM,
+    #[serde(default)]
+    auto_accept: bool,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Metadata::is_empty")]
+    metadata: Metadata,
 }
 
 impl Holder {
     pub fn create(credential_offer: CredentialOffer, source_id: &str) -> VcxResult<Holder> {
-        trace!("Holder::holder_create_credential >>> credential_offer: {:?}, source_id: {:?}", credential_offer, source_id);
+        log_event!(Level::Trace, "Holder::holder_create_credential", source_id = source_id);
 
         let holder_sm = HolderSM::new(credential_offer, source_id.to_string());
 
-        Ok(Holder { holder_sm })
+        Ok(Holder { holder_sm, auto_accept: false, metadata: Metadata::new() })
+    }
+
+    pub fn from_stored_credential(source_id: &str, thread_id: &str, cred_id: &str) -> Holder {
+        log_event!(Level::Trace, "Holder::from_stored_credential", source_id = source_id, cred_id = cred_id);
+
+        let holder_sm = HolderSM::finished(source_id.to_string(), thread_id.to_string(), cred_id.to_string());
+        Holder { holder_sm, auto_accept: false, metadata: Metadata::new() }
     }
 
     pub fn send_request(&mut self, connection_handle: u32) -> VcxResult<()> {
         self.step(CredentialIssuanceMessage::CredentialRequestSend(connection_handle))
     }
 
+    /// Rejects the offer, or stops waiting for the credential, informing the issuer by problem report with `reason`.
+    pub fn abandon(&mut self, connection_handle: u32, reason: &str) -> VcxResult<()> {
+        if self.holder_sm.is_terminal_state() {
+            return Err(VcxError::from_msg(VcxErrorKind::NotReady, "Credential Issuance is already finished"));
+        }
+        self.step(CredentialIssuanceMessage::Abandon(connection_handle, reason.to_string()))
+    }
+
     pub fn update_state(&mut self, msg: Option<String>, connection_handle: Option<u32>) -> VcxResult<()> {
+        // no message is expected from the issuer until the offer is accepted
+        if self.holder_sm.is_expired() {
+            self.holder_sm = self.holder_sm.clone().handle_message(CredentialIssuanceMessage::CheckExpiration)?;
+            return Ok(());
+        }
+
         match msg {
             Some(msg) => {
                 let message: A2AMessage = ::serde_json::from_str(&msg)
                     .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Cannot update state: Message deserialization failed: {:?}", err)))?;
 
-                self.step(message.into())
+                let connection_handle = connection_handle.unwrap_or(self.holder_sm.get_connection_handle());
+                let _correlation = structured_log::enter(Correlation::new().connection_handle(Some(connection_handle)).message(&message));
+                log_event!(Level::Debug, "message_received", source_id = self.get_source_id(), state = self.get_status());
+
+                if self.holder_sm.is_processed(&message) {
+                    log_event!(Level::Debug, "Holder::update_state", note = "message was already processed");
+                    return Ok(());
+                }
+
+                let prev_state = self.get_status();
+                cancellation::commit();
+                self.holder_sm = self.holder_sm.clone().receive_message(message.clone())?;
+
+                // message of a thread no object knows yet is kept for the object of the thread
+                if self.get_status() == prev_state && !self.holder_sm.is_terminal_state() && connection_handle != 0 {
+                    connection::queue_message(connection_handle, message)
+                        .unwrap_or_else(|err| warn!("Holder::update_state >>> cannot queue message: {}", err));
+                }
             }
             None => {
                 self.holder_sm = self.holder_sm.clone().update_state(connection_handle)?;
-                Ok(())
             }
         }
+
+        // offer is the only state without connection, so the request goes to the connection given by caller
+        if self.auto_accept && self.get_status() == VcxStateType::VcxStateRequestReceived as u32 {
+            if let Some(connection_handle) = connection_handle {
+                self.send_request(connection_handle)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Credential request is sent by `update_state` for the received offer,
+    /// the credential is acknowledged once stored when the issuer asks for it.
+    pub fn set_auto_accept(&mut self, auto_accept: bool) {
+        self.auto_accept = auto_accept;
     }
 
     pub fn get_status(&self) -> u32 {
@@ -49,10 +113,66 @@ impl Holder {
         self.holder_sm.get_source_id()
     }
 
+    pub fn get_metadata(&self) -> &Metadata { &self.metadata }
+
+    pub fn set_metadata(&mut self, key: &str, value: Option<&str>) -> VcxResult<()> {
+        metadata::set(&mut self.metadata, key, value)
+    }
+
     pub fn get_credential(&self) -> VcxResult<(String, A2AMessage)> {
         self.holder_sm.get_credential()
     }
 
+    pub fn get_thread_id(&self) -> String {
+        self.holder_sm.get_thread_id()
+    }
+
+    /// Connection the exchange runs over, `None` before the request is sent and once finished.
+    pub fn get_connection_handle(&self) -> Option<u32> {
+        Some(self.holder_sm.get_connection_handle()).filter(|handle| *handle != 0)
+    }
+
+    /// Time the received offer expires at, in RFC 3339 format.
+    pub fn get_expiry(&self) -> Option<String> {
+        self.holder_sm.get_offer()
+            .and_then(|offer| offer.timing.as_ref())
+            .and_then(|timing| timing.expires_time.clone())
+    }
+
+    /// JSON array of messages sent and received within the exchange, empty unless `protocol_history` option is enabled.
+    pub fn get_protocol_history(&self) -> VcxResult<String> {
+        self.holder_sm.history().to_string()
+    }
+
+    pub fn get_metadata(&self) -> CredentialMetadata {
+        self.holder_sm.get_metadata()
+    }
+
+    pub fn get_attachment(&self) -> VcxResult<String> {
+        self.holder_sm.get_attachment()
+    }
+
+    pub fn get_offer(&self) -> VcxResult<CredentialOffer> {
+        self.holder_sm.get_offer().cloned()
+            .ok_or(VcxError::from_msg(VcxErrorKind::NotReady, "Credential offer is not available"))
+    }
+
+    pub fn get_attributes(&self) -> VcxResult<Vec<CredentialValue>> {
+        self.holder_sm.get_attributes()
+    }
+
+    pub fn get_cred_def_id(&self) -> VcxResult<String> {
+        self.holder_sm.get_cred_def_id()
+    }
+
+    pub fn get_rev_reg_id(&self) -> VcxResult<String> {
+        self.holder_sm.get_rev_reg_id()
+    }
+
+    pub fn is_revoked(&self, use_cache: bool) -> VcxResult<bool> {
+        self.holder_sm.is_revoked(use_cache)
+    }
+
     pub fn delete_credential(&self) -> VcxResult<()> {
         self.holder_sm.delete_credential()
     }
@@ -1,20 +1,33 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+
+use log::Level;
 
 use api::VcxStateType;
 use connection;
 use error::prelude::*;
 use utils::libindy::anoncreds::{self, libindy_prover_delete_credential, libindy_prover_store_credential, libindy_prover_create_credential_req, get_cred_def_json};
 use aries::handlers::issuance::messages::CredentialIssuanceMessage;
+use aries::handlers::processed_messages::ProcessedMessages;
+use aries::handlers::protocol_history::{self, ProtocolHistory};
 use aries::messages::a2a::A2AMessage;
 use aries::messages::error::ProblemReport;
 use aries::messages::issuance::credential::Credential;
+use aries::messages::issuance::CredentialValue;
 use aries::messages::issuance::credential_ack::CredentialAck;
 use aries::messages::issuance::credential_offer::CredentialOffer;
 use aries::messages::issuance::credential_request::CredentialRequest;
+use aries::messages::issuance::format::CredentialFormat;
+use aries::messages::issuance::w3c::{LdProofVcDetail, W3cCredential};
+use aries::messages::connection::did_key;
 use aries::messages::status::Status;
+use aries::messages::timing::Timing;
 use aries::handlers::issuance::holder::states::offer_received::OfferReceivedState;
 use aries::handlers::issuance::holder::states::request_sent::RequestSentState;
 use aries::handlers::issuance::holder::states::finished::FinishedHolderState;
+use utils::cancellation;
+use utils::credential_store::CredentialMetadata;
+use utils::libindy::cache;
+use utils::structured_log::{self, Correlation};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum HolderState {
@@ -47,6 +60,12 @@ pub struct HolderSM {
     state: HolderState,
     source_id: String,
     thread_id: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "ProcessedMessages::is_empty")]
+    processed_messages: ProcessedMessages,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "ProtocolHistory::is_empty")]
+    history: ProtocolHistory,
 }
 
 impl HolderSM {
@@ -55,6 +74,26 @@ impl HolderSM {
             thread_id: offer.id.0.clone(),
             state: HolderState::OfferReceived(OfferReceivedState::new(offer)),
             source_id,
+            processed_messages: ProcessedMessages::default(),
+            history: ProtocolHistory::default(),
+        }
+    }
+
+    /// Holder of a credential already stored in the wallet, e.g. issued by the legacy proprietary protocol.
+    pub fn finished(source_id: String, thread_id: String, cred_id: String) -> Self {
+        HolderSM {
+            thread_id,
+            state: HolderState::Finished(FinishedHolderState {
+                cred_id: Some(cred_id),
+                credential: None,
+                status: Status::Success,
+                rev_reg_def_json: None,
+                offer: None,
+                issued_at: None,
+            }),
+            source_id,
+            processed_messages: ProcessedMessages::default(),
+            history: ProtocolHistory::default(),
         }
     }
 
@@ -69,6 +108,7 @@ impl HolderSM {
             HolderState::Finished(ref status) => {
                 match status.status {
                     Status::Success => VcxStateType::VcxStateAccepted as u32,
+                    Status::Expired(_) => VcxStateType::VcxStateExpired as u32,
                     _ => VcxStateType::VcxStateNone as u32,
                 }
             }
@@ -76,18 +116,21 @@ impl HolderSM {
     }
 
     pub fn update_state(mut self, connection_handle: Option<u32>) -> VcxResult<Self> {
-        trace!("Holder::update_state >>> ");
+        log_event!(Level::Trace, "Holder::update_state");
 
         if self.is_terminal_state() { return Ok(self); }
 
         let conn_handle = connection_handle.unwrap_or(self.state.get_connection_handle());
         self.state.set_connection_handle(conn_handle);
 
-        let messages = connection::get_messages(conn_handle)?;
+        let messages = self.processed_messages.filter_new(conn_handle, connection::get_thread_messages(conn_handle, &self.get_thread_id())?);
 
         match self.find_message_to_handle(messages) {
             Some((uid, msg)) => {
-                let state = self.handle_message(msg.into())?;
+                let _correlation = structured_log::enter(Correlation::new().connection_handle(Some(conn_handle)).message(&msg));
+                log_event!(Level::Debug, "message_received", source_id = self.get_source_id(), state = self.state(), uid = uid);
+                cancellation::commit();
+                let state = self.receive_message(msg)?;
                 connection::update_message_status(conn_handle, uid)?;
                 Ok(state)
             }
@@ -96,7 +139,7 @@ impl HolderSM {
     }
 
     fn find_message_to_handle(&self, messages: HashMap<String, A2AMessage>) -> Option<(String, A2AMessage)> {
-        trace!("Holder::find_message_to_handle >>> messages: {:?}", messages);
+        log_event!(Level::Trace, "Holder::find_message_to_handle", message_uids = messages.keys().collect::<Vec<_>>());
 
         for (uid, message) in messages {
             match self.state {
@@ -127,22 +170,63 @@ impl HolderSM {
         None
     }
 
-    pub fn step(state: HolderState, source_id: String, thread_id: String) -> Self {
-        HolderSM { state, source_id, thread_id }
+    pub fn step(state: HolderState, source_id: String, thread_id: String, processed_messages: ProcessedMessages, history: ProtocolHistory) -> Self {
+        HolderSM { state, source_id, thread_id, processed_messages, history }
+    }
+
+    pub fn is_processed(&self, message: &A2AMessage) -> bool {
+        self.processed_messages.contains(message)
+    }
+
+    pub fn history(&self) -> &ProtocolHistory {
+        &self.history
     }
 
     pub fn handle_message(self, cim: CredentialIssuanceMessage) -> VcxResult<HolderSM> {
-        trace!("Holder::handle_message >>> cim: {:?}", cim);
+        self._handle_message(cim, None)
+    }
+
+    /// Handles message received from the issuer, the message is marked as processed once it moves the exchange.
+    pub fn receive_message(self, message: A2AMessage) -> VcxResult<HolderSM> {
+        self._handle_message(message.clone().into(), Some(message))
+    }
+
+    fn _handle_message(self, cim: CredentialIssuanceMessage, received: Option<A2AMessage>) -> VcxResult<HolderSM> {
+        let prev_state = (self.state(), self.is_terminal_state());
+        let sent = protocol_history::capture();
+
+        let mut holder_sm = self.step_message(cim)?;
+
+        if let Some(message) = received {
+            if (holder_sm.state(), holder_sm.is_terminal_state()) != prev_state {
+                holder_sm.processed_messages.insert(&message);
+                holder_sm.history.record_received(&message);
+            }
+        }
+        holder_sm.history.record_sent(sent.take());
+
+        Ok(holder_sm)
+    }
+
+    fn step_message(self, cim: CredentialIssuanceMessage) -> VcxResult<HolderSM> {
+        log_event!(Level::Trace, "Holder::handle_message");
 
-        let HolderSM { state, source_id, thread_id } = self;
+        let HolderSM { state, source_id, thread_id, processed_messages, history } = self;
         let state = match state {
             HolderState::OfferReceived(state_data) => match cim {
+                CredentialIssuanceMessage::CheckExpiration if state_data.is_expired() => {
+                    HolderState::Finished(state_data.expire())
+                }
+                CredentialIssuanceMessage::CredentialRequestSend(_) if state_data.is_expired() => {
+                    return Err(VcxError::from_msg(VcxErrorKind::ExpiredMessage, "Credential offer is expired"));
+                }
                 CredentialIssuanceMessage::CredentialRequestSend(connection_handle) => {
                     let request = _make_credential_request(connection_handle, &state_data.offer);
                     match request {
                         Ok((cred_request, req_meta, cred_def_json)) => {
                             let cred_request = cred_request
-                                .set_thread_id(&thread_id);
+                                .set_thread_id(&thread_id)
+                                .set_timing(Timing::create().set_out_time());
                             connection::send_message(connection_handle, cred_request.to_a2a_message())?;
                             HolderState::RequestSent((state_data, req_meta, cred_def_json, connection_handle).into())
                         }
@@ -155,6 +239,10 @@ impl HolderSM {
                         }
                     }
                 }
+                CredentialIssuanceMessage::Abandon(connection_handle, reason) => {
+                    let problem_report = _decline(connection_handle, reason, &thread_id)?;
+                    HolderState::Finished((state_data, problem_report).into())
+                }
                 _ => {
                     warn!("Credential Issuance can only start on holder side with Credential Offer");
                     HolderState::OfferReceived(state_data)
@@ -183,6 +271,11 @@ impl HolderSM {
                     }
                 }
                 CredentialIssuanceMessage::ProblemReport(problem_report) => {
+                    problem_report.record_received("issue-credential");
+                    HolderState::Finished((state_data, problem_report).into())
+                }
+                CredentialIssuanceMessage::Abandon(connection_handle, reason) => {
+                    let problem_report = _decline(connection_handle, reason, &thread_id)?;
                     HolderState::Finished((state_data, problem_report).into())
                 }
                 _ => {
@@ -195,7 +288,7 @@ impl HolderSM {
                 HolderState::Finished(state_data)
             }
         };
-        Ok(HolderSM::step(state, source_id, thread_id))
+        Ok(HolderSM::step(state, source_id, thread_id, processed_messages, history))
     }
 
     pub fn credential_status(&self) -> u32 {
@@ -205,6 +298,14 @@ impl HolderSM {
         }
     }
 
+    /// Offer expired before the credential was requested.
+    pub fn is_expired(&self) -> bool {
+        match self.state {
+            HolderState::OfferReceived(ref state) => state.is_expired(),
+            _ => false
+        }
+    }
+
     pub fn is_terminal_state(&self) -> bool {
         match self.state {
             HolderState::Finished(_) => true,
@@ -212,9 +313,16 @@ impl HolderSM {
         }
     }
 
+    pub fn get_connection_handle(&self) -> u32 {
+        self.state.get_connection_handle()
+    }
+
     pub fn get_credential(&self) -> VcxResult<(String, A2AMessage)> {
         match self.state {
             HolderState::Finished(ref state) => {
+                if state.status.problem_report().is_some() {
+                    return Err(state.status.to_error(VcxErrorKind::InvalidState, "Cannot get credential: Credential Issuance failed"));
+                }
                 let cred_id = state.cred_id.clone().ok_or(VcxError::from_msg(VcxErrorKind::InvalidState, "Cannot get credential: Credential Id not found"))?;
                 let credential = state.credential.clone().ok_or(VcxError::from_msg(VcxErrorKind::InvalidState, "Cannot get credential: Credential not found"))?;
                 Ok((cred_id, credential.to_a2a_message()))
@@ -223,12 +331,143 @@ impl HolderSM {
         }
     }
 
+    pub fn get_thread_id(&self) -> String {
+        self.thread_id.clone()
+    }
+
+    pub fn get_offer(&self) -> Option<&CredentialOffer> {
+        match self.state {
+            HolderState::OfferReceived(ref state) => Some(&state.offer),
+            HolderState::RequestSent(ref state) => state.offer.as_ref(),
+            HolderState::Finished(ref state) => state.offer.as_ref()
+        }
+    }
+
+    pub fn get_metadata(&self) -> CredentialMetadata {
+        let offer = self.get_offer();
+        let (cred_id, issued_at) = match self.state {
+            HolderState::Finished(ref state) => (state.cred_id.clone(), state.issued_at),
+            _ => (None, None)
+        };
+
+        let cred_def_id = offer
+            .and_then(|offer| offer.offers_attach.content().ok())
+            .and_then(|offer| _parse_cred_def_from_cred_offer(&offer).ok());
+
+        CredentialMetadata {
+            source_id: self.source_id.clone(),
+            thread_id: self.thread_id.clone(),
+            state: self.state(),
+            cred_id,
+            issuer_did: cred_def_id.as_ref().and_then(|id| id.split(':').next().map(String::from)),
+            cred_def_id,
+            comment: offer.and_then(|offer| offer.comment.clone()),
+            attributes: offer.map(|offer| offer.credential_preview.attributes.clone()).unwrap_or_default(),
+            issued_at,
+            updated_at: ::time::get_time().sec as u64,
+        }
+    }
+
+    pub fn get_attachment(&self) -> VcxResult<String> {
+        match self.state {
+            HolderState::Finished(ref state) => {
+                let credential = state.credential.as_ref().ok_or(VcxError::from_msg(VcxErrorKind::InvalidState, "Cannot get credential attachment: Credential not found"))?;
+                credential.credentials_attach.content()
+            }
+            _ => Err(VcxError::from_msg(VcxErrorKind::NotReady, "Cannot get credential attachment: Credential Issuance is not finished yet"))
+        }
+    }
+
+    pub fn get_attributes(&self) -> VcxResult<Vec<CredentialValue>> {
+        let preview = self.get_metadata().attributes;
+
+        let credential = match self.state {
+            HolderState::Finished(_) => self.get_attachment()?,
+            _ => return Ok(preview)
+        };
+
+        if self.is_w3c_credential() {
+            return Ok(W3cCredential::from_str(&credential)?.credential_subject.into_iter()
+                .filter(|(name, _)| name != "id")
+                .map(|(name, value)| {
+                    let _type = preview.iter().find(|attribute| attribute.name == name).and_then(|attribute| attribute._type.clone());
+                    CredentialValue { name, value: value.as_str().map(String::from).unwrap_or_else(|| value.to_string()), _type }
+                })
+                .collect());
+        }
+
+        // issued values are authoritative, preview only tells their mime types
+        let credential: IndyCredential = serde_json::from_str(&credential)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Invalid Credential Json: {:?}", err)))?;
+
+        Ok(credential.values.into_iter()
+            .map(|(name, value)| {
+                let _type = preview.iter().find(|attribute| attribute.name == name).and_then(|attribute| attribute._type.clone());
+                CredentialValue { name, value: value.raw, _type }
+            })
+            .collect())
+    }
+
+    /// Offered or issued credential is W3C Verifiable Credential rather than Indy credential.
+    pub fn is_w3c_credential(&self) -> bool {
+        match self.state {
+            HolderState::Finished(FinishedHolderState { credential: Some(ref credential), .. }) => credential.format() == CredentialFormat::LdProofVc,
+            _ => self.get_offer().map(|offer| offer.format() == CredentialFormat::LdProofVc).unwrap_or(false)
+        }
+    }
+
+    pub fn get_cred_def_id(&self) -> VcxResult<String> {
+        if self.is_w3c_credential() {
+            return Err(VcxError::from_msg(VcxErrorKind::ActionNotSupported, "Cannot get credential definition id: W3C Credential has no credential definition"));
+        }
+
+        match self.state {
+            HolderState::Finished(_) => {
+                let credential: IndyCredential = serde_json::from_str(&self.get_attachment()?)
+                    .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Invalid Credential Json: {:?}", err)))?;
+                Ok(credential.cred_def_id)
+            }
+            _ => self.get_metadata().cred_def_id
+                .ok_or(VcxError::from_msg(VcxErrorKind::InvalidState, "Cannot get credential definition id: Credential Offer does not contain it"))
+        }
+    }
+
+    pub fn get_rev_reg_id(&self) -> VcxResult<String> {
+        match self.state {
+            HolderState::Finished(_) => _parse_rev_reg_id_from_credential(&self.get_attachment()?)?
+                .ok_or(VcxError::from_msg(VcxErrorKind::InvalidRevocationDetails, "Cannot get revocation registry id: credential is not revocable")),
+            _ => Err(VcxError::from_msg(VcxErrorKind::NotReady, "Cannot get revocation registry id: Credential Issuance is not finished yet"))
+        }
+    }
+
+    pub fn is_revoked(&self, use_cache: bool) -> VcxResult<bool> {
+        log_event!(Level::Trace, "Holder::is_revoked", use_cache = use_cache);
+
+        if self.is_w3c_credential() {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidRevocationDetails, "Cannot check revocation status: W3C Credential is not revocable"));
+        }
+
+        match self.state {
+            HolderState::Finished(ref state) => {
+                let cred_id = state.cred_id.clone().ok_or(VcxError::from_msg(VcxErrorKind::InvalidState, "Cannot check revocation status: credential id not found"))?;
+                match _get_revocation_ids(&cred_id)? {
+                    (Some(rev_reg_id), Some(cred_rev_id)) => anoncreds::is_cred_revoked(&rev_reg_id, &cred_rev_id, use_cache),
+                    _ => Err(VcxError::from_msg(VcxErrorKind::InvalidRevocationDetails, "Cannot check revocation status: credential is not revocable"))
+                }
+            }
+            _ => Err(VcxError::from_msg(VcxErrorKind::NotReady, "Cannot check revocation status: credential issuance is not finished yet"))
+        }
+    }
+
     pub fn delete_credential(&self) -> VcxResult<()> {
-        trace!("Holder::delete_credential");
+        log_event!(Level::Trace, "Holder::delete_credential");
 
         match self.state {
             HolderState::Finished(ref state) => {
                 let cred_id = state.cred_id.clone().ok_or(VcxError::from_msg(VcxErrorKind::InvalidState, "Cannot get credential: credential id not found"))?;
+                if self.is_w3c_credential() {
+                    return ::w3c_credential::delete(&cred_id);
+                }
                 _delete_credential(&cred_id)
             }
             _ => Err(VcxError::from_msg(VcxErrorKind::NotReady, "Cannot delete credential: credential issuance is not finished yet"))
@@ -236,8 +475,19 @@ impl HolderSM {
     }
 }
 
+#[derive(Deserialize, Debug)]
+struct IndyCredential {
+    cred_def_id: String,
+    values: BTreeMap<String, IndyCredentialValue>,
+}
+
+#[derive(Deserialize, Debug)]
+struct IndyCredentialValue {
+    raw: String,
+}
+
 fn _parse_cred_def_from_cred_offer(cred_offer: &str) -> VcxResult<String> {
-    trace!("Holder::_parse_cred_def_from_cred_offer >>> cred_offer: {:?}", cred_offer);
+    log_event!(Level::Trace, "Holder::_parse_cred_def_from_cred_offer");
 
     let parsed_offer: serde_json::Value = serde_json::from_str(cred_offer)
         .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Invalid Credential Offer Json: {:?}", err)))?;
@@ -249,7 +499,7 @@ fn _parse_cred_def_from_cred_offer(cred_offer: &str) -> VcxResult<String> {
 }
 
 fn _parse_rev_reg_id_from_credential(credential: &str) -> VcxResult<Option<String>> {
-    trace!("Holder::_parse_rev_reg_id_from_credential >>>");
+    log_event!(Level::Trace, "Holder::_parse_rev_reg_id_from_credential");
 
     let parsed_credential: serde_json::Value = serde_json::from_str(credential)
         .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Invalid Credential Json: {}, err: {:?}", credential, err)))?;
@@ -261,7 +511,11 @@ fn _parse_rev_reg_id_from_credential(credential: &str) -> VcxResult<Option<Strin
 
 fn _store_credential(credential: &Credential,
                      req_meta: &str, cred_def_json: &str) -> VcxResult<(String, Option<String>)> {
-    trace!("Holder::_store_credential >>>");
+    log_event!(Level::Trace, "Holder::_store_credential");
+
+    if credential.format() == CredentialFormat::LdProofVc {
+        return _store_ld_proof_vc(credential, req_meta).map(|cred_id| (cred_id, None));
+    }
 
     let credential_json = credential.credentials_attach.content()?;
     let rev_reg_id = _parse_rev_reg_id_from_credential(&credential_json)?;
@@ -280,10 +534,44 @@ fn _store_credential(credential: &Credential,
     Ok((cred_id, rev_reg_def_json))
 }
 
+fn _store_ld_proof_vc(credential: &Credential, requested: &str) -> VcxResult<String> {
+    log_event!(Level::Trace, "Holder::_store_ld_proof_vc");
+
+    let requested = LdProofVcDetail::from_str(requested)?;
+    let credential = W3cCredential::from_str(&credential.credentials_attach.content()?)?;
+    credential.verify()?;
+
+    let mut issued = credential.clone();
+    issued.proof = None;
+    if issued != requested.credential {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidCredential, "Issued W3C Credential does not match the requested one"));
+    }
+
+    ::w3c_credential::store(&credential)
+}
+
+fn _get_revocation_ids(cred_id: &str) -> VcxResult<(Option<String>, Option<String>)> {
+    log_event!(Level::Trace, "Holder::_get_revocation_ids", cred_id = cred_id);
+
+    let cred_info = anoncreds::libindy_prover_get_credential(cred_id)?;
+    let cred_info: serde_json::Value = serde_json::from_str(&cred_info)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Invalid Credential Info Json: {:?}", err)))?;
+
+    Ok((cred_info["rev_reg_id"].as_str().map(String::from), cred_info["cred_rev_id"].as_str().map(String::from)))
+}
+
 fn _delete_credential(cred_id: &str) -> VcxResult<()> {
-    trace!("Holder::_delete_credential >>> cred_id: {}", cred_id);
+    log_event!(Level::Trace, "Holder::_delete_credential", cred_id = cred_id);
 
-    libindy_prover_delete_credential(cred_id)
+    // revocation ids are known only while the credential is in the wallet
+    let revocation_ids = _get_revocation_ids(cred_id).ok();
+
+    libindy_prover_delete_credential(cred_id)?;
+
+    if let Some((Some(rev_reg_id), Some(cred_rev_id))) = revocation_ids {
+        cache::delete_rev_reg_cache(&rev_reg_id, &cred_rev_id);
+    }
+    Ok(())
 }
 
     pub fn create_credential_request(cred_def_id: &str, prover_did: &str, cred_offer: &str) -> VcxResult<(String, String, String, String)> {
@@ -296,7 +584,13 @@ fn _delete_credential(cred_id: &str) -> VcxResult<()> {
     }
 
 fn _make_credential_request(conn_handle: u32, offer: &CredentialOffer) -> VcxResult<(CredentialRequest, String, String)> {
-    trace!("Holder::_make_credential_request >>> conn_handle: {:?}, offer: {:?}", conn_handle, offer);
+    log_event!(Level::Trace, "Holder::_make_credential_request", conn_handle = conn_handle);
+
+    offer.check_answerable()?;
+
+    if offer.format() == CredentialFormat::LdProofVc {
+        return _make_ld_proof_vc_request(conn_handle, offer);
+    }
 
     let my_did = connection::get_pw_did(conn_handle)?;
     let cred_offer = offer.offers_attach.content()?;
@@ -305,6 +599,25 @@ fn _make_credential_request(conn_handle: u32, offer: &CredentialOffer) -> VcxRes
     Ok((CredentialRequest::create().set_requests_attach(req)?, req_meta, cred_def_json))
 }
 
+// the requested detail is kept in place of the request metadata to check the issued credential against
+fn _make_ld_proof_vc_request(conn_handle: u32, offer: &CredentialOffer) -> VcxResult<(CredentialRequest, String, String)> {
+    let offered = LdProofVcDetail::from_str(&offer.offers_attach.content()?)?;
+    let holder_did = did_key::encode(&connection::get_pw_verkey(conn_handle)?)?;
+
+    let requested = LdProofVcDetail::create(offered.credential.set_subject_id(&holder_did));
+    Ok((CredentialRequest::create().set_ld_proof_vc_detail(&requested)?, json!(requested).to_string(), String::new()))
+}
+
+/// Informs the issuer the holder rejects the offer or does not wait for the credential anymore.
+fn _decline(connection_handle: u32, reason: String, thread_id: &str) -> VcxResult<ProblemReport> {
+    let problem_report = ProblemReport::create()
+        .set_comment(reason)
+        .set_thread_id(thread_id);
+
+    connection::send_message(connection_handle, problem_report.to_a2a_message())?;
+    Ok(problem_report)
+}
+
 #[cfg(test)]
 mod test {
     use utils::devsetup::SetupAriesMocks;
@@ -314,6 +627,8 @@ mod test {
     use aries::messages::issuance::credential_proposal::tests::_credential_proposal;
     use aries::messages::issuance::credential_request::tests::_credential_request;
     use aries::messages::issuance::test::{_ack, _problem_report};
+    use aries::messages::issuance::w3c::tests::_w3c_credential;
+    use aries::messages::timing::Timing;
     use aries::test::source_id;
 
     use super::*;
@@ -335,6 +650,29 @@ mod test {
         }
     }
 
+    mod get_metadata {
+        use super::*;
+
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_get_metadata_keeps_offer_preview_across_states() {
+            let _setup = SetupAriesMocks::init();
+
+            let holder_sm = _holder_sm();
+            let metadata = holder_sm.get_metadata();
+            assert_eq!(VcxStateType::VcxStateRequestReceived as u32, metadata.state);
+            assert_eq!(_credential_offer().credential_preview.attributes, metadata.attributes);
+            assert!(metadata.cred_id.is_none());
+
+            let holder_sm = holder_sm.to_finished_state();
+            let metadata = holder_sm.get_metadata();
+            assert_eq!(VcxStateType::VcxStateAccepted as u32, metadata.state);
+            assert_eq!(_credential_offer().credential_preview.attributes, metadata.attributes);
+            assert!(metadata.cred_id.is_some());
+            assert!(metadata.issued_at.is_some());
+        }
+    }
+
     mod new {
         use super::*;
 
@@ -387,6 +725,25 @@ mod test {
             assert_eq!(Status::Failed(ProblemReport::default()).code(), holder_sm.credential_status());
         }
 
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_holder_handle_expired_offer() {
+            let _setup = SetupAriesMocks::init();
+
+            let expired_offer = _credential_offer()
+                .set_timing(Timing::create().set_expires_time(String::from("2019-01-23T18:03:27Z")));
+
+            let mut holder_sm = HolderSM::new(expired_offer, source_id());
+            assert!(holder_sm.is_expired());
+            assert_eq!(VcxErrorKind::ExpiredMessage,
+                       holder_sm.clone().handle_message(CredentialIssuanceMessage::CredentialRequestSend(mock_connection())).unwrap_err().kind());
+
+            holder_sm = holder_sm.handle_message(CredentialIssuanceMessage::CheckExpiration).unwrap();
+            assert_match!(HolderState::Finished(_), holder_sm.state);
+            assert_eq!(VcxStateType::VcxStateExpired as u32, holder_sm.state());
+            assert!(!_holder_sm().is_expired());
+        }
+
         #[test]
         #[cfg(feature = "general_test")]
         fn test_issuer_handle_other_messages_from_offer_received_state() {
@@ -401,6 +758,18 @@ mod test {
             assert_match!(HolderState::OfferReceived(_), holder_sm.state);
         }
 
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_holder_handle_abandon_message_from_offer_received_state() {
+            let _setup = SetupAriesMocks::init();
+
+            let mut holder_sm = _holder_sm();
+            holder_sm = holder_sm.handle_message(CredentialIssuanceMessage::Abandon(mock_connection(), String::from("Offer is not wanted"))).unwrap();
+
+            assert_match!(HolderState::Finished(_), holder_sm.state);
+            assert_eq!(Status::Failed(ProblemReport::default()).code(), holder_sm.credential_status());
+        }
+
         #[test]
         #[cfg(feature = "general_test")]
         fn test_issuer_handle_credential_message_from_request_sent_state() {
@@ -414,6 +783,47 @@ mod test {
             assert_eq!(Status::Success.code(), holder_sm.credential_status());
         }
 
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_holder_receives_w3c_credential() {
+            let _setup = SetupAriesMocks::init();
+
+            let conn_handle = mock_connection();
+            let offered = LdProofVcDetail::create(_w3c_credential());
+            let offer = CredentialOffer::create().set_ld_proof_vc_detail(&offered).unwrap();
+
+            let mut holder_sm = HolderSM::new(offer, source_id());
+            assert!(holder_sm.is_w3c_credential());
+            holder_sm = holder_sm.handle_message(CredentialIssuanceMessage::CredentialRequestSend(conn_handle)).unwrap();
+            assert_match!(HolderState::RequestSent(_), holder_sm.state);
+
+            let holder_did = did_key::encode(&connection::get_pw_verkey(conn_handle).unwrap()).unwrap();
+            let issued = offered.credential.set_subject_id(&holder_did).sign().unwrap();
+            holder_sm = holder_sm.handle_message(CredentialIssuanceMessage::Credential(Credential::create().set_ld_proof_vc(&issued).unwrap())).unwrap();
+
+            assert_eq!(Status::Success.code(), holder_sm.credential_status());
+            assert_eq!(vec!["age", "name"], holder_sm.get_attributes().unwrap().into_iter().map(|attribute| attribute.name).collect::<Vec<String>>());
+            assert_eq!(holder_sm.get_cred_def_id().unwrap_err().kind(), VcxErrorKind::ActionNotSupported);
+        }
+
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_holder_rejects_w3c_credential_different_from_request() {
+            let _setup = SetupAriesMocks::init();
+
+            let conn_handle = mock_connection();
+            let offered = LdProofVcDetail::create(_w3c_credential());
+            let offer = CredentialOffer::create().set_ld_proof_vc_detail(&offered).unwrap();
+
+            let mut holder_sm = HolderSM::new(offer, source_id());
+            holder_sm = holder_sm.handle_message(CredentialIssuanceMessage::CredentialRequestSend(conn_handle)).unwrap();
+
+            let issued = offered.credential.set_subject_id("did:key:other").sign().unwrap();
+            holder_sm = holder_sm.handle_message(CredentialIssuanceMessage::Credential(Credential::create().set_ld_proof_vc(&issued).unwrap())).unwrap();
+
+            assert_eq!(Status::Failed(ProblemReport::default()).code(), holder_sm.credential_status());
+        }
+
         #[test]
         #[cfg(feature = "general_test")]
         fn test_issuer_handle_invalid_credential_message_from_request_sent_state() {
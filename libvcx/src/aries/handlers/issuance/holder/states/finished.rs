@@ -1,4 +1,5 @@
 use aries::messages::issuance::credential::Credential;
+use aries::messages::issuance::credential_offer::CredentialOffer;
 use aries::messages::status::Status;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -7,4 +8,8 @@ pub struct FinishedHolderState {
     pub credential: Option<Credential>,
     pub status: Status,
     pub rev_reg_def_json: Option<String>,
+    #[serde(default)]
+    pub offer: Option<CredentialOffer>,
+    #[serde(default)]
+    pub issued_at: Option<u64>,
 }
@@ -1,3 +1,5 @@
+use log::Level;
+
 use aries::handlers::issuance::holder::states::finished::FinishedHolderState;
 use aries::handlers::issuance::holder::states::request_sent::RequestSentState;
 use aries::messages::error::ProblemReport;
@@ -16,27 +18,49 @@ impl OfferReceivedState {
             offer,
         }
     }
+
+    pub fn is_expired(&self) -> bool {
+        self.offer.check_answerable().is_err()
+    }
+
+    /// Offer expired before it was accepted, the issuer knows that already so nothing is sent.
+    pub fn expire(self) -> FinishedHolderState {
+        let expires_time = self.offer.timing.as_ref()
+            .and_then(|timing| timing.expires_time.clone())
+            .unwrap_or_default();
+
+        let problem_report = ProblemReport::create()
+            .set_comment(format!("Credential offer expired at {}", expires_time))
+            .set_thread_id(&self.offer.id.0);
+
+        let mut state: FinishedHolderState = (self, problem_report.clone()).into();
+        state.status = Status::Expired(problem_report);
+        state
+    }
 }
 
 impl From<(OfferReceivedState, String, String, u32)> for RequestSentState {
-    fn from((_state, req_meta, cred_def_json, connection_handle): (OfferReceivedState, String, String, u32)) -> Self {
-        trace!("SM is now in RequestSent state");
+    fn from((state, req_meta, cred_def_json, connection_handle): (OfferReceivedState, String, String, u32)) -> Self {
+        log_event!(Level::Trace, "state_transition", to = "RequestSent");
         RequestSentState {
             req_meta,
             cred_def_json,
             connection_handle,
+            offer: Some(state.offer),
         }
     }
 }
 
 impl From<(OfferReceivedState, ProblemReport)> for FinishedHolderState {
-    fn from((_state, problem_report): (OfferReceivedState, ProblemReport)) -> Self {
-        trace!("SM is now in Finished state");
+    fn from((state, problem_report): (OfferReceivedState, ProblemReport)) -> Self {
+        log_event!(Level::Trace, "state_transition", to = "Finished");
         FinishedHolderState {
             cred_id: None,
             credential: None,
             status: Status::Failed(problem_report),
             rev_reg_def_json: None,
+            offer: Some(state.offer),
+            issued_at: None,
         }
     }
 }
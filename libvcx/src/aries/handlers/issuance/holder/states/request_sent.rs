@@ -1,5 +1,8 @@
+use log::Level;
+
 use aries::handlers::issuance::holder::states::finished::FinishedHolderState;
 use aries::messages::issuance::credential::Credential;
+use aries::messages::issuance::credential_offer::CredentialOffer;
 use aries::messages::error::ProblemReport;
 use aries::messages::status::Status;
 
@@ -8,28 +11,34 @@ pub struct RequestSentState {
     pub req_meta: String,
     pub cred_def_json: String,
     pub connection_handle: u32,
+    #[serde(default)]
+    pub offer: Option<CredentialOffer>,
 }
 
 impl From<(RequestSentState, String, Credential, Option<String>)> for FinishedHolderState {
-    fn from((_, cred_id, credential, rev_reg_def_json): (RequestSentState, String, Credential, Option<String>)) -> Self {
-        trace!("SM is now in Finished state");
+    fn from((state, cred_id, credential, rev_reg_def_json): (RequestSentState, String, Credential, Option<String>)) -> Self {
+        log_event!(Level::Trace, "state_transition", to = "Finished");
         FinishedHolderState {
             cred_id: Some(cred_id),
             credential: Some(credential),
             status: Status::Success,
             rev_reg_def_json,
+            offer: state.offer,
+            issued_at: Some(::time::get_time().sec as u64),
         }
     }
 }
 
 impl From<(RequestSentState, ProblemReport)> for FinishedHolderState {
-    fn from((_, problem_report): (RequestSentState, ProblemReport)) -> Self {
-        trace!("SM is now in Finished state");
+    fn from((state, problem_report): (RequestSentState, ProblemReport)) -> Self {
+        log_event!(Level::Trace, "state_transition", to = "Finished");
         FinishedHolderState {
             cred_id: None,
             credential: None,
             status: Status::Failed(problem_report),
             rev_reg_def_json: None,
+            offer: state.offer,
+            issued_at: None,
         }
     }
 }
@@ -1,40 +1,160 @@
+use log::Level;
+
+use api::VcxStateType;
 use error::prelude::*;
-use aries::handlers::issuance::issuer::state_machine::IssuerSM;
+use settings;
+use aries::handlers::issuance::issuer::state_machine::{IssuerSM, RenewalInfo};
+use aries::handlers::issuance::issuer::utils::credential_subject;
 use aries::handlers::issuance::messages::CredentialIssuanceMessage;
 use aries::messages::a2a::A2AMessage;
+use aries::messages::connection::invite::Invitation;
+use aries::messages::out_of_band::invitation::OutOfBandInvitation;
+use utils::metadata::{self, Metadata};
+use utils::structured_log::{self, Correlation};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Issuer {
-    issuer_sm: IssuerSM
+    issuer_sm: IssuerSM,
+    #[serde(default)]
+    auto_issue: bool,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Metadata::is_empty")]
+    metadata: Metadata,
 }
 
 impl Issuer {
     pub fn create(cred_def_handle: u32, credential_data: &str, source_id: &str) -> VcxResult<Issuer> {
-        trace!("Issuer::issuer_create_credential >>> cred_def_handle: {:?}, credential_data: {:?}, source_id: {:?}", cred_def_handle, credential_data, source_id);
+        log_event!(Level::Trace, "Issuer::issuer_create_credential", cred_def_handle = cred_def_handle, source_id = source_id);
 
         let cred_def_id = ::credential_def::get_cred_def_id(cred_def_handle)?;
-        let rev_reg_id = ::credential_def::get_rev_reg_id(cred_def_handle)?;
-        let tails_file = ::credential_def::get_tails_file(cred_def_handle)?;
-        let issuer_sm = IssuerSM::new(&cred_def_id, credential_data, rev_reg_id, tails_file, source_id);
-        Ok(Issuer { issuer_sm })
+        let revocable = ::credential_def::get_rev_reg_id(cred_def_handle)?.is_some();
+        let issuer_sm = IssuerSM::new(&cred_def_id, credential_data, revocable, source_id);
+        Ok(Issuer { issuer_sm, auto_issue: false, metadata: Metadata::new() })
+    }
+
+    /// W3C credential signed by the institution verkey, no credential definition is needed.
+    pub fn create_w3c(credential_data: &str, source_id: &str) -> VcxResult<Issuer> {
+        log_event!(Level::Trace, "Issuer::create_w3c", source_id = source_id);
+
+        let issuer_key = settings::get_config_value(settings::CONFIG_INSTITUTION_VERKEY)?;
+        credential_subject(credential_data)?;
+
+        let issuer_sm = IssuerSM::new_w3c(&issuer_key, credential_data, source_id);
+        Ok(Issuer { issuer_sm, auto_issue: false, metadata: Metadata::new() })
+    }
+
+    pub fn create_renewal(&self, cred_def_handle: u32, credential_data: &str, source_id: &str, revoke_old: bool) -> VcxResult<Issuer> {
+        log_event!(Level::Trace, "Issuer::create_renewal", cred_def_handle = cred_def_handle, source_id = source_id, revoke_old = revoke_old);
+
+        if !self.issuer_sm.is_terminal_state() {
+            return Err(VcxError::from_msg(VcxErrorKind::NotReady, "Cannot renew credential: Credential Issuance is not finished yet"));
+        }
+
+        let revoke_on_success = if revoke_old {
+            Some(self.issuer_sm.get_revocation_info()
+                .ok_or(VcxError::from_msg(VcxErrorKind::InvalidRevocationDetails, "Cannot renew credential: credential is not revocable"))?)
+        } else {
+            None
+        };
+
+        let renewal = RenewalInfo {
+            parent_thread_id: self.issuer_sm.get_thread_id(),
+            revoke_on_success,
+            revocation_error: None,
+        };
+
+        let issuer = Issuer::create(cred_def_handle, credential_data, source_id)?;
+        Ok(Issuer { issuer_sm: issuer.issuer_sm.set_renewal(renewal), auto_issue: false, metadata: Metadata::new() })
     }
 
     pub fn send_credential_offer(&mut self, connection_handle: u32, comment: Option<String>) -> VcxResult<()> {
         self.step(CredentialIssuanceMessage::CredentialInit(connection_handle, comment))
     }
 
+    /// Prepares the offer to be passed by out-of-band invitation of `connection_handle` instead of sending it.
+    pub fn prepare_out_of_band_offer(&mut self, connection_handle: u32, comment: Option<String>) -> VcxResult<()> {
+        self.step(CredentialIssuanceMessage::PrepareOutOfBandOffer(connection_handle, comment))
+    }
+
+    pub fn get_out_of_band_offer(&self) -> VcxResult<OutOfBandInvitation> {
+        log_event!(Level::Trace, "Issuer::get_out_of_band_offer");
+
+        let offer = self.issuer_sm.get_out_of_band_offer()
+            .ok_or(VcxError::from_msg(VcxErrorKind::NotReady, "Credential offer is not prepared to be passed out-of-band"))?;
+
+        let invite_details = ::connection::get_invite_details(self.issuer_sm.get_connection_handle())?;
+        let connection_invitation: Invitation = ::serde_json::from_str(&invite_details)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize invitation: {}", err)))?;
+
+        OutOfBandInvitation::create()
+            .set_label(settings::get_config_value(settings::CONFIG_INSTITUTION_NAME).ok())
+            .set_connection_invitation(connection_invitation)
+            .set_request(offer.to_a2a_message())
+    }
+
     pub fn send_credential(&mut self, connection_handle: u32) -> VcxResult<()> {
         self.step(CredentialIssuanceMessage::CredentialSend(connection_handle))
     }
 
+    /// Withdraws the offer, or stops the issuance, informing the holder by problem report with `reason`
+    /// over the connection the exchange runs on. Returns whether the holder could be informed,
+    /// it cannot be when the holder of an out-of-band offer has not connected yet.
+    pub fn abandon(&mut self, reason: &str) -> VcxResult<bool> {
+        if self.issuer_sm.is_terminal_state() {
+            return Err(VcxError::from_msg(VcxErrorKind::NotReady, "Credential Issuance is already finished"));
+        }
+        if self.issuer_sm.state() == VcxStateType::VcxStateInitialized as u32 {
+            return Err(VcxError::from_msg(VcxErrorKind::NotReady, "Credential offer is not sent yet"));
+        }
+        let connection_handle = self.issuer_sm.get_connection_handle();
+        let notified = ::connection::is_established(connection_handle);
+        self.step(CredentialIssuanceMessage::Abandon(connection_handle, reason.to_string()))?;
+        Ok(notified)
+    }
+
     pub fn get_state(&self) -> VcxResult<u32> {
         Ok(self.issuer_sm.state())
     }
 
+    pub fn get_thread_id(&self) -> String {
+        self.issuer_sm.get_thread_id()
+    }
+
+    /// Connection the exchange runs over, `None` before the offer is sent and once finished.
+    pub fn get_connection_handle(&self) -> Option<u32> {
+        Some(self.issuer_sm.get_connection_handle()).filter(|handle| *handle != 0)
+    }
+
+    /// Offer expires `expires_in_secs` after it is sent, has to be called before the offer is sent.
+    pub fn set_expiration(&mut self, expires_in_secs: u64) -> VcxResult<()> {
+        self.issuer_sm.set_expiration(expires_in_secs)
+    }
+
+    /// Time the sent offer expires at, in RFC 3339 format.
+    pub fn get_expiry(&self) -> Option<String> {
+        self.issuer_sm.get_expiry()
+    }
+
+    /// Credential was sent with `~please_ack` and the holder has not acknowledged it yet.
+    pub fn is_awaiting_ack(&self) -> bool {
+        self.issuer_sm.is_awaiting_ack()
+    }
+
+    /// JSON array of messages sent and received within the exchange, empty unless `protocol_history` option is enabled.
+    pub fn get_protocol_history(&self) -> VcxResult<String> {
+        self.issuer_sm.history().to_string()
+    }
+
     pub fn get_source_id(&self) -> VcxResult<String> {
         Ok(self.issuer_sm.get_source_id())
     }
 
+    pub fn get_metadata(&self) -> &Metadata { &self.metadata }
+
+    pub fn set_metadata(&mut self, key: &str, value: Option<&str>) -> VcxResult<()> {
+        metadata::set(&mut self.metadata, key, value)
+    }
+
     pub fn revoke_credential(&self, publish: bool) -> VcxResult<()> {
         self.issuer_sm.revoke(publish)
     }
@@ -45,13 +165,56 @@ impl Issuer {
                 let message: A2AMessage = ::serde_json::from_str(&msg)
                     .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Cannot deserialize Message: {:?}", err)))?;
 
-                self.step(message.into())
+                let connection_handle = connection_handle.unwrap_or(self.issuer_sm.get_connection_handle());
+                let _correlation = structured_log::enter(Correlation::new().connection_handle(Some(connection_handle)).message(&message));
+                log_event!(Level::Debug, "message_received", source_id = self.issuer_sm.get_source_id(), state = self.issuer_sm.state());
+
+                if self.issuer_sm.is_processed(&message) {
+                    log_event!(Level::Debug, "Issuer::update_status", note = "message was already processed");
+                    return Ok(());
+                }
+
+                let prev_state = self.issuer_sm.state();
+                self.issuer_sm = self.issuer_sm.clone().receive_message(message.clone())?;
+
+                // message of a thread no object knows yet is kept for the object of the thread
+                if self.issuer_sm.state() == prev_state && !self.issuer_sm.is_terminal_state() && connection_handle != 0 {
+                    ::connection::queue_message(connection_handle, message)
+                        .unwrap_or_else(|err| warn!("Issuer::update_status >>> cannot queue message: {}", err));
+                }
             }
             None => {
+                // connection of out-of-band offer is established only after the invitee accepts the invitation
+                if self.issuer_sm.get_out_of_band_offer().is_some() {
+                    let connection_handle = self.issuer_sm.get_connection_handle();
+                    if ::connection::get_state(connection_handle) != VcxStateType::VcxStateAccepted as u32 {
+                        ::connection::update_state(connection_handle)?;
+                    }
+                }
                 self.issuer_sm = self.issuer_sm.clone().update_state(connection_handle)?;
-                Ok(())
             }
         }
+
+        if self.auto_issue && self.issuer_sm.state() == VcxStateType::VcxStateRequestReceived as u32 {
+            let connection_handle = connection_handle.unwrap_or(self.issuer_sm.get_connection_handle());
+            self.send_credential(connection_handle)?;
+        }
+
+        // the issuance itself is finished, the error is reported until the renewed credential is revoked
+        if let Some(err) = self.issuer_sm.get_renewal_revocation_error() {
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Credential is sent right after the request is received by `update_status`.
+    pub fn set_auto_issue(&mut self, auto_issue: bool) {
+        self.auto_issue = auto_issue;
+    }
+
+    /// Issued credential asks the holder for ack, the issuance is finished once the ack is received.
+    pub fn set_please_ack(&mut self, please_ack: bool) {
+        self.issuer_sm = self.issuer_sm.clone().set_please_ack(please_ack);
     }
 
     pub fn get_credential_status(&self) -> VcxResult<u32> {
@@ -1,30 +1,41 @@
 use std::collections::HashMap;
 
+use failure::Fail;
+use log::Level;
+
 use api::VcxStateType;
-use connection::{get_messages, send_message};
+use connection::send_message;
 use connection;
 use error::{VcxError, VcxErrorKind, VcxResult};
 use utils::libindy::anoncreds::{self, libindy_issuer_create_credential_offer};
 use aries::handlers::issuance::messages::CredentialIssuanceMessage;
+use aries::handlers::processed_messages::ProcessedMessages;
+use aries::handlers::protocol_history::{self, ProtocolHistory};
 use aries::messages::a2a::A2AMessage;
 use aries::messages::error::ProblemReport;
 use aries::messages::issuance::credential::Credential;
 use aries::messages::issuance::credential_offer::CredentialOffer;
 use aries::messages::issuance::credential_request::CredentialRequest;
-use aries::messages::mime_type::MimeType;
+use aries::messages::issuance::format::CredentialFormat;
+use aries::messages::issuance::w3c::{LdProofVcDetail, W3cCredential};
+use aries::messages::localization::Localization;
 use aries::messages::status::Status;
+use aries::messages::timing::Timing;
 use aries::handlers::issuance::issuer::states::initial::InitialState;
 use aries::handlers::issuance::issuer::states::offer_sent::OfferSentState;
 use aries::handlers::issuance::issuer::states::requested_received::RequestReceivedState;
 use aries::handlers::issuance::issuer::states::credential_sent::CredentialSentState;
 use aries::handlers::issuance::issuer::states::finished::FinishedState;
-use aries::handlers::issuance::issuer::utils::encode_attributes;
+use aries::handlers::issuance::issuer::utils::{credential_subject, encode_attributes, parse_attribute_value};
+use utils::structured_log::{self, Correlation};
 
 // Possible Transitions:
 // Initial -> OfferSent
 // Initial -> Finished
-// OfferSent -> CredentialSent
+// OfferSent -> RequestReceived
 // OfferSent -> Finished
+// RequestReceived -> CredentialSent (credential asks for ack)
+// RequestReceived -> Finished
 // CredentialSent -> Finished
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum IssuerState {
@@ -73,36 +84,167 @@ impl IssuerState {
     }
 }
 
+// Renewal of previously issued credential: the new offer references the old thread
+// and the old credential may be revoked once the new one is issued
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RenewalInfo {
+    pub parent_thread_id: String,
+    pub revoke_on_success: Option<RevocationInfoV1>,
+    // why the old credential is not revoked yet, the revocation is retried by following updates
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revocation_error: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct IssuerSM {
     state: IssuerState,
     source_id: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    renewal: Option<RenewalInfo>,
+    #[serde(default)]
+    please_ack: bool,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "ProcessedMessages::is_empty")]
+    processed_messages: ProcessedMessages,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "ProtocolHistory::is_empty")]
+    history: ProtocolHistory,
 }
 
 impl IssuerSM {
-    pub fn new(cred_def_id: &str, credential_data: &str, rev_reg_id: Option<String>, tails_file: Option<String>, source_id: &str) -> Self {
+    pub fn new(cred_def_id: &str, credential_data: &str, revocable: bool, source_id: &str) -> Self {
+        IssuerSM {
+            state: IssuerState::Initial(InitialState::new(cred_def_id, credential_data, revocable)),
+            source_id: source_id.to_string(),
+            renewal: None,
+            please_ack: false,
+            processed_messages: ProcessedMessages::default(),
+            history: ProtocolHistory::default(),
+        }
+    }
+
+    /// Issuer of W3C credential signed by `issuer_key`, the offer and the credential carry `libvcx/sorted-json-vc` attachments.
+    pub fn new_w3c(issuer_key: &str, credential_data: &str, source_id: &str) -> Self {
         IssuerSM {
-            state: IssuerState::Initial(InitialState::new(cred_def_id, credential_data, rev_reg_id, tails_file)),
+            state: IssuerState::Initial(InitialState::new_w3c(issuer_key, credential_data)),
             source_id: source_id.to_string(),
+            renewal: None,
+            please_ack: false,
+            processed_messages: ProcessedMessages::default(),
+            history: ProtocolHistory::default(),
         }
     }
 
+    /// Credential is sent with `~please_ack` and the exchange waits for the holder's ack in `CredentialSent` state.
+    pub fn set_please_ack(mut self, please_ack: bool) -> Self {
+        self.please_ack = please_ack;
+        self
+    }
+
+    /// Offer expires `expires_in_secs` after it is sent, `credential_offer_expiry` option is used if not set.
+    pub fn set_expiration(&mut self, expires_in_secs: u64) -> VcxResult<()> {
+        match self.state {
+            IssuerState::Initial(ref mut state) => {
+                state.expires_in_secs = Some(expires_in_secs);
+                Ok(())
+            }
+            _ => Err(VcxError::from_msg(VcxErrorKind::NotReady, "Credential offer is already sent"))
+        }
+    }
+
+    /// Time the sent offer expires at, in RFC 3339 format.
+    pub fn get_expiry(&self) -> Option<String> {
+        match self.state {
+            IssuerState::OfferSent(ref state) => state.expires_time.clone(),
+            _ => None
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        match self.state {
+            IssuerState::OfferSent(ref state) => state.is_expired(),
+            _ => false
+        }
+    }
+
+    pub fn set_renewal(mut self, renewal: RenewalInfo) -> Self {
+        self.renewal = Some(renewal);
+        self
+    }
+
     pub fn get_source_id(&self) -> String {
         self.source_id.clone()
     }
 
-    pub fn step(state: IssuerState, source_id: String) -> Self {
+    /// Failure of revoking the renewed credential once this one was issued, `None` when there is nothing left to revoke.
+    pub fn get_renewal_revocation_error(&self) -> Option<VcxError> {
+        self.renewal.as_ref()
+            .filter(|renewal| renewal.revoke_on_success.is_some())
+            .and_then(|renewal| renewal.revocation_error.as_ref().map(|err| {
+                VcxError::from_msg(VcxErrorKind::InvalidRevocationEntry,
+                                   format!("Credential is issued, but the renewed credential from thread {} is not revoked: {}", renewal.parent_thread_id, err))
+            }))
+    }
+
+    pub fn get_thread_id(&self) -> String {
+        self.state.thread_id()
+    }
+
+    pub fn get_connection_handle(&self) -> u32 {
+        self.state.get_connection_handle()
+    }
+
+    pub fn get_revocation_info(&self) -> Option<RevocationInfoV1> {
+        match self.state {
+            IssuerState::CredentialSent(ref state) => state.revocation_info_v1.clone(),
+            IssuerState::Finished(ref state) => state.revocation_info_v1.clone(),
+            _ => None
+        }
+    }
+
+    /// Offer waiting to be put into out-of-band invitation, the invitee answers it over the connection of the issuer.
+    pub fn get_out_of_band_offer(&self) -> Option<CredentialOffer> {
+        match self.state {
+            IssuerState::OfferSent(ref state) => state.out_of_band_offer.clone(),
+            _ => None
+        }
+    }
+
+    pub fn is_awaiting_ack(&self) -> bool {
+        match self.state {
+            IssuerState::CredentialSent(_) => true,
+            _ => false
+        }
+    }
+
+    pub fn step(state: IssuerState, source_id: String, renewal: Option<RenewalInfo>, please_ack: bool, processed_messages: ProcessedMessages, history: ProtocolHistory) -> Self {
         IssuerSM {
             state,
             source_id,
+            renewal,
+            please_ack,
+            processed_messages,
+            history,
         }
     }
 
+    /// Checks whether the message was already handled, e.g. it was redelivered by the agency.
+    pub fn is_processed(&self, message: &A2AMessage) -> bool {
+        self.processed_messages.contains(message)
+    }
+
+    pub fn history(&self) -> &ProtocolHistory {
+        &self.history
+    }
+
     pub fn revoke(&self, publish: bool) -> VcxResult<()> {
-        trace!("Issuer::revoke >>> publish={}", publish);
-        match &self.state {
-            IssuerState::Finished(state) => {
-                match &state.revocation_info_v1 {
+        log_event!(Level::Trace, "Issuer::revoke", publish = publish);
+        match self.state {
+            IssuerState::CredentialSent(CredentialSentState { ref revocation_info_v1, .. }) |
+            IssuerState::Finished(FinishedState { ref revocation_info_v1, .. }) => {
+                match revocation_info_v1 {
                     Some(rev_info) => {
                         if let (Some(cred_rev_id), Some(rev_reg_id), Some(tails_file)) = (&rev_info.cred_rev_id, &rev_info.rev_reg_id, &rev_info.tails_file) {
                             if publish {
@@ -124,27 +266,41 @@ impl IssuerSM {
     }
 
     pub fn update_state(mut self, connection_handle: Option<u32>) -> VcxResult<Self> {
-        trace!("Issuer::update_state >>> ", );
+        log_event!(Level::Trace, "Issuer::update_state");
 
-        if self.is_terminal_state() { return Ok(self); }
+        if self.is_terminal_state() {
+            if let IssuerState::Finished(FinishedState { status: Status::Success, .. }) = self.state {
+                _revoke_renewed_credential(&mut self.renewal);
+            }
+            return Ok(self);
+        }
 
         let conn_handle = connection_handle.unwrap_or(self.state.get_connection_handle());
         self.state.set_connection_handle(conn_handle);
 
-        let messages = get_messages(conn_handle)?;
+        let messages = self.processed_messages.filter_new(conn_handle, connection::get_thread_messages(conn_handle, &self.get_thread_id())?);
 
-        match self.find_message_to_handle(messages) {
+        let issuer_sm = match self.find_message_to_handle(messages) {
             Some((uid, msg)) => {
-                let state = self.handle_message(msg.into())?;
+                let _correlation = structured_log::enter(Correlation::new().connection_handle(Some(conn_handle)).message(&msg));
+                log_event!(Level::Debug, "message_received", source_id = self.get_source_id(), state = self.state(), uid = uid);
+                let state = self.receive_message(msg)?;
                 connection::update_message_status(conn_handle, uid)?;
-                Ok(state)
+                state
             }
-            None => Ok(self)
+            None => self
+        };
+
+        // request which arrived in time has moved the issuer past the offer, even if it is handled after the offer expired,
+        // so only an offer still waiting for the request expires here
+        if issuer_sm.is_expired() {
+            return issuer_sm.handle_message(CredentialIssuanceMessage::CheckExpiration);
         }
+        Ok(issuer_sm)
     }
 
     fn find_message_to_handle(&self, messages: HashMap<String, A2AMessage>) -> Option<(String, A2AMessage)> {
-        trace!("Issuer::find_message_to_handle >>> messages: {:?}", messages);
+        log_event!(Level::Trace, "Issuer::find_message_to_handle", message_uids = messages.keys().collect::<Vec<_>>());
 
         for (uid, message) in messages {
             match self.state {
@@ -209,6 +365,7 @@ impl IssuerSM {
             IssuerState::Finished(ref status) => {
                 match status.status {
                     Status::Success => VcxStateType::VcxStateAccepted as u32,
+                    Status::Expired(_) => VcxStateType::VcxStateExpired as u32,
                     _ => VcxStateType::VcxStateNone as u32,
                 }
             }
@@ -216,19 +373,54 @@ impl IssuerSM {
     }
 
     pub fn handle_message(self, cim: CredentialIssuanceMessage) -> VcxResult<IssuerSM> {
-        trace!("IssuerSM::handle_message >>> cim: {:?}", cim);
+        self._handle_message(cim, None)
+    }
+
+    /// Handles message received from the holder, the message is marked as processed once it moves the exchange.
+    pub fn receive_message(self, message: A2AMessage) -> VcxResult<IssuerSM> {
+        self._handle_message(message.clone().into(), Some(message))
+    }
+
+    fn _handle_message(self, cim: CredentialIssuanceMessage, received: Option<A2AMessage>) -> VcxResult<IssuerSM> {
+        let prev_state = (self.state(), self.is_terminal_state());
+        let sent = protocol_history::capture();
+
+        let mut issuer_sm = self.step_message(cim)?;
+
+        if let Some(message) = received {
+            if (issuer_sm.state(), issuer_sm.is_terminal_state()) != prev_state {
+                issuer_sm.processed_messages.insert(&message);
+                issuer_sm.history.record_received(&message);
+            }
+        }
+        issuer_sm.history.record_sent(sent.take());
 
-        let IssuerSM { state, source_id } = self;
+        Ok(issuer_sm)
+    }
+
+    fn step_message(self, cim: CredentialIssuanceMessage) -> VcxResult<IssuerSM> {
+        log_event!(Level::Trace, "IssuerSM::handle_message");
+
+        let IssuerSM { state, source_id, mut renewal, please_ack, processed_messages, history } = self;
+        let was_finished = match state {
+            IssuerState::Finished(_) => true,
+            _ => false
+        };
         let state = match state {
             IssuerState::Initial(state_data) => match cim {
                 CredentialIssuanceMessage::CredentialInit(connection_handle, comment) => {
-                    let cred_offer = libindy_issuer_create_credential_offer(&state_data.cred_def_id)?;
-                    let cred_offer_msg = CredentialOffer::create()
-                        .set_offers_attach(&cred_offer)?
-                        .set_comment(comment);
-                    let cred_offer_msg = _append_credential_preview(cred_offer_msg, &state_data.credential_json)?;
+                    let (cred_offer, cred_offer_msg) = _build_credential_offer_msg(&state_data, &renewal, comment)?;
                     send_message(connection_handle, cred_offer_msg.to_a2a_message())?;
-                    IssuerState::OfferSent((state_data, cred_offer, connection_handle, cred_offer_msg.id).into())
+                    let mut state: OfferSentState = (state_data, cred_offer, connection_handle, cred_offer_msg.id.clone()).into();
+                    state.expires_time = cred_offer_msg.timing.and_then(|timing| timing.expires_time);
+                    IssuerState::OfferSent(state)
+                }
+                CredentialIssuanceMessage::PrepareOutOfBandOffer(connection_handle, comment) => {
+                    let (cred_offer, cred_offer_msg) = _build_credential_offer_msg(&state_data, &renewal, comment)?;
+                    let mut state: OfferSentState = (state_data, cred_offer, connection_handle, cred_offer_msg.id.clone()).into();
+                    state.expires_time = cred_offer_msg.timing.clone().and_then(|timing| timing.expires_time);
+                    state.out_of_band_offer = Some(cred_offer_msg);
+                    IssuerState::OfferSent(state)
                 }
                 _ => {
                     warn!("Credential Issuance can only start on issuer side with init");
@@ -236,9 +428,27 @@ impl IssuerSM {
                 }
             }
             IssuerState::OfferSent(state_data) => match cim {
+                CredentialIssuanceMessage::CheckExpiration if state_data.is_expired() => {
+                    let problem_report = state_data.expiration_report();
+                    if ::settings::is_problem_report_on_expiry_enabled() && state_data.out_of_band_offer.is_none() {
+                        send_message(state_data.connection_handle, problem_report.to_a2a_message())
+                            .unwrap_or_else(|err| warn!("Cannot inform holder about expired offer: {}", err));
+                    }
+                    IssuerState::Finished(state_data.expire(problem_report))
+                }
+                CredentialIssuanceMessage::CredentialRequest(ref request) if state_data.is_expired_at(request.arrival_time()) => {
+                    let problem_report = state_data.expiration_report();
+                    send_message(state_data.connection_handle, problem_report.to_a2a_message())
+                        .unwrap_or_else(|err| warn!("Cannot inform holder about expired offer: {}", err));
+                    IssuerState::Finished(state_data.expire(problem_report))
+                }
                 CredentialIssuanceMessage::CredentialRequest(request) => {
                     IssuerState::RequestReceived((state_data, request).into())
                 }
+                CredentialIssuanceMessage::Abandon(_, reason) => {
+                    let problem_report = _abandon(state_data.connection_handle, reason, &state_data.thread_id)?;
+                    IssuerState::Finished((state_data, problem_report).into())
+                }
                 CredentialIssuanceMessage::CredentialProposal(_) => {
                     let problem_report = ProblemReport::create()
                         .set_comment(String::from("CredentialProposal is not supported"))
@@ -248,6 +458,7 @@ impl IssuerSM {
                     IssuerState::Finished((state_data, problem_report).into())
                 }
                 CredentialIssuanceMessage::ProblemReport(problem_report) => {
+                    problem_report.record_received("issue-credential");
                     IssuerState::Finished((state_data, problem_report).into())
                 }
                 _ => {
@@ -255,14 +466,22 @@ impl IssuerSM {
                     IssuerState::OfferSent(state_data)
                 }
             },
-            IssuerState::RequestReceived(state_data) => match cim {
+            IssuerState::RequestReceived(mut state_data) => match cim {
                 CredentialIssuanceMessage::CredentialSend(connection_handle) => {
-                    let credential_msg = _create_credential(&state_data.request, &state_data.rev_reg_id, &state_data.tails_file, &state_data.offer, &state_data.cred_data);
+                    let credential_msg = _reserve_rev_reg_slot(&mut state_data)
+                        .and_then(|_| _create_credential(&state_data.request, &state_data.rev_reg_id, &state_data.tails_file, &state_data.offer, &state_data.cred_data));
                     match credential_msg {
                         Ok((credential_msg, cred_rev_id)) => {
-                            let credential_msg = credential_msg.set_thread_id(&state_data.thread_id);
+                            let mut credential_msg = credential_msg.set_thread_id(&state_data.thread_id);
+                            if please_ack {
+                                credential_msg = credential_msg.ask_for_ack();
+                            }
                             send_message(connection_handle, credential_msg.to_a2a_message())?;
-                            IssuerState::Finished((state_data, cred_rev_id).into())
+                            if please_ack {
+                                IssuerState::CredentialSent((state_data, cred_rev_id).into())
+                            } else {
+                                IssuerState::Finished((state_data, cred_rev_id).into())
+                            }
                         }
                         Err(err) => {
                             let problem_report = ProblemReport::create()
@@ -274,20 +493,29 @@ impl IssuerSM {
                         }
                     }
                 }
+                CredentialIssuanceMessage::Abandon(_, reason) => {
+                    let problem_report = _abandon(state_data.connection_handle, reason, &state_data.thread_id)?;
+                    IssuerState::Finished((state_data, problem_report).into())
+                }
                 _ => {
                     warn!("In this state Credential Issuance can accept only CredentialSend");
                     IssuerState::RequestReceived(state_data)
                 }
             }
             IssuerState::CredentialSent(state_data) => match cim {
-                CredentialIssuanceMessage::ProblemReport(_problem_report) => {
+                CredentialIssuanceMessage::ProblemReport(problem_report) => {
+                    problem_report.record_received("issue-credential");
                     info!("Interaction closed with failure");
-                    IssuerState::Finished(state_data.into())
+                    IssuerState::Finished((state_data, problem_report).into())
                 }
                 CredentialIssuanceMessage::CredentialAck(_ack) => {
                     info!("Interaction closed with success");
                     IssuerState::Finished(state_data.into())
                 }
+                CredentialIssuanceMessage::Abandon(_, reason) => {
+                    let problem_report = _abandon(state_data.connection_handle, reason, &state_data.thread_id)?;
+                    IssuerState::Finished((state_data, problem_report).into())
+                }
                 _ => {
                     warn!("In this state Credential Issuance can accept only Ack and Problem Report");
                     IssuerState::CredentialSent(state_data)
@@ -299,11 +527,20 @@ impl IssuerSM {
             }
         };
 
-        Ok(IssuerSM::step(state, source_id))
+        if let IssuerState::Finished(FinishedState { status: Status::Success, .. }) = state {
+            _revoke_renewed_credential(&mut renewal);
+        }
+        if let IssuerState::Finished(ref state_data) = state {
+            if !was_finished {
+                _release_rev_reg_slot(state_data);
+            }
+        }
+
+        Ok(IssuerSM::step(state, source_id, renewal, please_ack, processed_messages, history))
     }
 
     pub fn credential_status(&self) -> u32 {
-        trace!("Issuer::credential_status >>>");
+        log_event!(Level::Trace, "Issuer::credential_status");
 
         match self.state {
             IssuerState::Finished(ref state) => state.status.code(),
@@ -320,8 +557,65 @@ impl IssuerSM {
 }
 
 
+/// Informs the holder the issuer does not continue the exchange, over the connection the exchange runs on.
+/// The holder of an out-of-band offer may have not connected yet, there is nobody to inform then.
+fn _abandon(connection_handle: u32, reason: String, thread_id: &str) -> VcxResult<ProblemReport> {
+    let problem_report = ProblemReport::create()
+        .set_comment(reason)
+        .set_thread_id(thread_id);
+
+    if connection::is_established(connection_handle) {
+        send_message(connection_handle, problem_report.to_a2a_message())?;
+    }
+    Ok(problem_report)
+}
+
+fn _revoke_renewed_credential(renewal: &mut Option<RenewalInfo>) {
+    if let Some(ref mut renewal) = *renewal {
+        if let Some(rev_info) = renewal.revoke_on_success.take() {
+            log_event!(Level::Trace, "Issuer::_revoke_renewed_credential", parent_thread_id = renewal.parent_thread_id);
+            let revoked = match (&rev_info.cred_rev_id, &rev_info.rev_reg_id, &rev_info.tails_file) {
+                (Some(cred_rev_id), Some(rev_reg_id), Some(tails_file)) => anoncreds::revoke_credential(tails_file, rev_reg_id, cred_rev_id).map(|_| ()),
+                _ => Err(VcxError::from_msg(VcxErrorKind::InvalidRevocationDetails, format!("Missing data to revoke renewed credential. rev_info={:?}", rev_info)))
+            };
+            match revoked {
+                Ok(()) => renewal.revocation_error = None,
+                Err(err) => {
+                    warn!("Cannot revoke renewed credential from thread {}: {}", renewal.parent_thread_id, err);
+                    renewal.revocation_error = Some(Fail::find_root_cause(&err).to_string());
+                    renewal.revoke_on_success = Some(rev_info);
+                }
+            }
+        }
+    }
+}
+
+// place in the active revocation registry is taken only by credentials which are actually built
+fn _reserve_rev_reg_slot(state_data: &mut RequestReceivedState) -> VcxResult<()> {
+    if state_data.revocable && state_data.rev_reg_id.is_none() {
+        let offer: serde_json::Value = serde_json::from_str(&state_data.offer)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize credential offer: {}", err)))?;
+        let cred_def_id = offer["cred_def_id"].as_str()
+            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, "Credential offer does not contain `cred_def_id`"))?;
+
+        let (rev_reg_id, tails_file) = ::credential_def::reserve_rev_reg_by_id(cred_def_id)?;
+        state_data.rev_reg_id = rev_reg_id;
+        state_data.tails_file = tails_file;
+    }
+    Ok(())
+}
+
+// exchange finished without the credential, e.g. abandoned or expired, gives back the place it reserved
+fn _release_rev_reg_slot(state_data: &FinishedState) {
+    if let Some(RevocationInfoV1 { cred_rev_id: None, rev_reg_id: Some(ref rev_reg_id), .. }) = state_data.revocation_info_v1 {
+        if let Err(err) = ::credential_def::release_rev_reg_slot(rev_reg_id) {
+            warn!("Cannot release place reserved in revocation registry {}: {}", rev_reg_id, err);
+        }
+    }
+}
+
 fn _append_credential_preview(cred_offer_msg: CredentialOffer, credential_json: &str) -> VcxResult<CredentialOffer> {
-    trace!("Issuer::_append_credential_preview >>> cred_offer_msg: {:?}, credential_json: {:?}", cred_offer_msg, credential_json);
+    log_event!(Level::Trace, "Issuer::_append_credential_preview");
 
     let cred_values: serde_json::Value = serde_json::from_str(credential_json)
         .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Can't deserialize credential preview json. credential_json={} error={:?}", credential_json, err)))?;
@@ -332,18 +626,51 @@ fn _append_credential_preview(cred_offer_msg: CredentialOffer, credential_json:
     let mut new_offer = cred_offer_msg;
     for item in values_map.iter() {
         let (key, value) = item;
-        new_offer = new_offer.add_credential_preview_data(
-            key,
-            value.as_str()
-                .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Problem adding credential preview data {}:{:?}", key, value.as_str())))?,
-            MimeType::Plain,
-        )?;
+        let (value, mime_type) = parse_attribute_value(key, value)?;
+        new_offer = new_offer.add_credential_preview_data(key, &value, mime_type)?;
     }
     Ok(new_offer)
 }
 
+fn _build_credential_offer_msg(state_data: &InitialState, renewal: &Option<RenewalInfo>, comment: Option<String>) -> VcxResult<(String, CredentialOffer)> {
+    let (cred_offer, cred_offer_msg) = _create_credential_offer(state_data)?;
+    let mut cred_offer_msg = cred_offer_msg.set_comment(comment);
+    if let Some(ref renewal) = renewal {
+        cred_offer_msg = cred_offer_msg.set_parent_thread_id(&renewal.parent_thread_id);
+    }
+    let mut cred_offer_msg = _append_credential_preview(cred_offer_msg, &state_data.credential_json)?;
+    if let Some(localization) = Localization::institution()? {
+        cred_offer_msg = cred_offer_msg.set_l10n(localization);
+    }
+    if let Some(expires_in_secs) = state_data.expires_in_secs.or(::settings::get_credential_offer_expiry()) {
+        cred_offer_msg = cred_offer_msg.set_timing(Timing::create().set_out_time().set_expires_in(expires_in_secs));
+    }
+    Ok((cred_offer, cred_offer_msg))
+}
+
+fn _create_credential_offer(state_data: &InitialState) -> VcxResult<(String, CredentialOffer)> {
+    log_event!(Level::Trace, "Issuer::_create_credential_offer", cred_def_id = state_data.cred_def_id);
+
+    match state_data.w3c_issuer_key {
+        Some(ref issuer_key) => {
+            let credential = W3cCredential::create(issuer_key, credential_subject(&state_data.credential_json)?)?;
+            let detail = LdProofVcDetail::create(credential);
+            Ok((json!(detail).to_string(), CredentialOffer::create().set_ld_proof_vc_detail(&detail)?))
+        }
+        None => {
+            let cred_offer = libindy_issuer_create_credential_offer(&state_data.cred_def_id)?;
+            let cred_offer_msg = CredentialOffer::create().set_offers_attach(&cred_offer)?;
+            Ok((cred_offer, cred_offer_msg))
+        }
+    }
+}
+
 fn _create_credential(request: &CredentialRequest, rev_reg_id: &Option<String>, tails_file: &Option<String>, offer: &str, cred_data: &str) -> VcxResult<(Credential, Option<String>)> {
-    trace!("Issuer::_create_credential >>> request: {:?}, rev_reg_id: {:?}, tails_file: {:?}, offer: {:?}, cred_data: {:?}", request, rev_reg_id, tails_file, offer, cred_data);
+    log_event!(Level::Trace, "Issuer::_create_credential", rev_reg_id = rev_reg_id);
+
+    if request.format() == CredentialFormat::LdProofVc {
+        return _create_ld_proof_vc(request, offer);
+    }
 
     let request = &request.requests_attach.content()?;
 
@@ -359,10 +686,21 @@ fn _create_credential(request: &CredentialRequest, rev_reg_id: &Option<String>,
     Ok((credential, cred_rev_id))
 }
 
+// W3C credentials are not revocable, so there is no credential revocation id
+fn _create_ld_proof_vc(request: &CredentialRequest, offer: &str) -> VcxResult<(Credential, Option<String>)> {
+    let offered = LdProofVcDetail::from_str(offer)?;
+    let requested = LdProofVcDetail::from_str(&request.requests_attach.content()?)?;
+    requested.ensure_requested(&offered)?;
+
+    let credential = requested.credential.sign()?;
+    Ok((Credential::create().set_ld_proof_vc(&credential)?, None))
+}
+
 #[cfg(test)]
 pub mod test {
     use utils::devsetup::SetupAriesMocks;
     use aries::handlers::connection::tests::mock_connection;
+    use connection::tests::build_test_connection_inviter_invited;
     use aries::messages::issuance::credential::tests::_credential;
     use aries::messages::issuance::credential_offer::tests::_credential_offer;
     use aries::messages::issuance::credential_proposal::tests::_credential_proposal;
@@ -373,7 +711,7 @@ pub mod test {
     use super::*;
 
     fn _issuer_sm() -> IssuerSM {
-        IssuerSM::new("test", &json!({"name": "alice"}).to_string(), None, None, &source_id())
+        IssuerSM::new("test", &json!({"name": "alice"}).to_string(), false, &source_id())
     }
 
     impl IssuerSM {
@@ -388,6 +726,15 @@ pub mod test {
             self
         }
 
+        fn to_credential_sent_state(mut self) -> IssuerSM {
+            let conn_handle = mock_connection();
+            self = self.set_please_ack(true);
+            self = self.handle_message(CredentialIssuanceMessage::CredentialInit(conn_handle, None)).unwrap();
+            self = self.handle_message(CredentialIssuanceMessage::CredentialRequest(_credential_request())).unwrap();
+            self = self.handle_message(CredentialIssuanceMessage::CredentialSend(conn_handle)).unwrap();
+            self
+        }
+
         fn to_finished_state(mut self) -> IssuerSM {
             let conn_handle = mock_connection();
             self = self.handle_message(CredentialIssuanceMessage::CredentialInit(conn_handle, None)).unwrap();
@@ -436,6 +783,25 @@ pub mod test {
             assert_match!(IssuerState::OfferSent(_), issuer_sm.state);
         }
 
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_issuer_handle_prepare_out_of_band_offer_message_from_initial_state() {
+            let _setup = SetupAriesMocks::init();
+
+            let mut issuer_sm = _issuer_sm();
+            assert!(issuer_sm.get_out_of_band_offer().is_none());
+
+            issuer_sm = issuer_sm.handle_message(CredentialIssuanceMessage::PrepareOutOfBandOffer(mock_connection(), None)).unwrap();
+
+            assert_match!(IssuerState::OfferSent(_), issuer_sm.state);
+            assert_eq!(mock_connection(), issuer_sm.get_connection_handle());
+            assert_eq!(issuer_sm.get_thread_id(), issuer_sm.get_out_of_band_offer().unwrap().id.0);
+
+            issuer_sm = issuer_sm.handle_message(CredentialIssuanceMessage::CredentialRequest(_credential_request())).unwrap();
+            assert_match!(IssuerState::RequestReceived(_), issuer_sm.state);
+            assert!(issuer_sm.get_out_of_band_offer().is_none());
+        }
+
         #[test]
         #[cfg(feature = "general_test")]
         fn test_issuer_handle_other_messages_from_initial_state() {
@@ -462,6 +828,27 @@ pub mod test {
             assert_match!(IssuerState::RequestReceived(_), issuer_sm.state);
         }
 
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_issuer_checks_offer_expiration_at_request_arrival() {
+            let _setup = SetupAriesMocks::init();
+
+            let offer_sent = || {
+                let mut issuer_sm = _issuer_sm().handle_message(CredentialIssuanceMessage::CredentialInit(mock_connection(), None)).unwrap();
+                if let IssuerState::OfferSent(ref mut state) = issuer_sm.state {
+                    state.expires_time = Some(String::from("2019-01-23T18:03:27Z"));
+                }
+                issuer_sm
+            };
+            let sent_at = |out_time: &str| _credential_request().set_timing(Timing { out_time: Some(out_time.to_string()), ..Timing::default() });
+
+            let issuer_sm = offer_sent().handle_message(CredentialIssuanceMessage::CredentialRequest(sent_at("2019-01-23T18:00:00Z"))).unwrap();
+            assert_match!(IssuerState::RequestReceived(_), issuer_sm.state);
+
+            let issuer_sm = offer_sent().handle_message(CredentialIssuanceMessage::CredentialRequest(sent_at("2019-01-23T18:05:00Z"))).unwrap();
+            assert_match!(IssuerState::Finished(FinishedState { status: Status::Expired(_), .. }), issuer_sm.state);
+        }
+
         #[test]
         #[cfg(feature = "general_test")]
         fn test_issuer_handle_credential_proposal_message_from_offer_sent_state() {
@@ -488,6 +875,36 @@ pub mod test {
             assert_eq!(Status::Failed(ProblemReport::default()).code(), issuer_sm.credential_status());
         }
 
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_issuer_handle_abandon_message_from_offer_sent_state() {
+            let _setup = SetupAriesMocks::init();
+
+            let mut issuer_sm = _issuer_sm();
+            issuer_sm = issuer_sm.handle_message(CredentialIssuanceMessage::Abandon(mock_connection(), String::from("Offer is withdrawn"))).unwrap();
+            assert_match!(IssuerState::Initial(_), issuer_sm.state);
+
+            issuer_sm = issuer_sm.handle_message(CredentialIssuanceMessage::CredentialInit(mock_connection(), None)).unwrap();
+            issuer_sm = issuer_sm.handle_message(CredentialIssuanceMessage::Abandon(mock_connection(), String::from("Offer is withdrawn"))).unwrap();
+
+            assert_match!(IssuerState::Finished(_), issuer_sm.state);
+            assert_eq!(Status::Failed(ProblemReport::default()).code(), issuer_sm.credential_status());
+        }
+
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_issuer_handle_abandon_message_over_not_established_connection() {
+            let _setup = SetupAriesMocks::init();
+
+            let mut issuer_sm = _issuer_sm();
+            issuer_sm = issuer_sm.handle_message(CredentialIssuanceMessage::CredentialInit(mock_connection(), None)).unwrap();
+            issuer_sm.state.set_connection_handle(build_test_connection_inviter_invited());
+            issuer_sm = issuer_sm.handle_message(CredentialIssuanceMessage::Abandon(mock_connection(), String::from("Offer is withdrawn"))).unwrap();
+
+            assert_match!(IssuerState::Finished(_), issuer_sm.state);
+            assert_eq!(Status::Failed(ProblemReport::default()).code(), issuer_sm.credential_status());
+        }
+
         #[test]
         #[cfg(feature = "general_test")]
         fn test_issuer_handle_other_messages_from_offer_sent_state() {
@@ -530,6 +947,53 @@ pub mod test {
             assert_eq!(Status::Failed(ProblemReport::default()).code(), issuer_sm.credential_status());
         }
 
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_issuer_issues_w3c_credential() {
+            let _setup = SetupAriesMocks::init();
+
+            let conn_handle = mock_connection();
+            let mut issuer_sm = IssuerSM::new_w3c("GJ1SzoWzavQYfNL9XkaJdrQejfztN4XqdsiV4ct3LXKL", &json!({"name": "alice"}).to_string(), &source_id());
+            issuer_sm = issuer_sm.handle_message(CredentialIssuanceMessage::CredentialInit(conn_handle, None)).unwrap();
+
+            let offered = match issuer_sm.state {
+                IssuerState::OfferSent(ref state) => LdProofVcDetail::from_str(&state.offer).unwrap(),
+                _ => panic!("Credential Offer is not sent")
+            };
+            assert_eq!(json!({"name": "alice"}), json!(offered.credential.credential_subject));
+
+            let requested = LdProofVcDetail::create(offered.credential.set_subject_id("did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK"));
+            let request = CredentialRequest::create().set_ld_proof_vc_detail(&requested).unwrap();
+
+            issuer_sm = issuer_sm.handle_message(CredentialIssuanceMessage::CredentialRequest(request)).unwrap();
+            issuer_sm = issuer_sm.handle_message(CredentialIssuanceMessage::CredentialSend(conn_handle)).unwrap();
+
+            assert_match!(IssuerState::Finished(_), issuer_sm.state);
+            assert_eq!(Status::Success.code(), issuer_sm.credential_status());
+        }
+
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_issuer_rejects_w3c_credential_request_different_from_offer() {
+            let _setup = SetupAriesMocks::init();
+
+            let conn_handle = mock_connection();
+            let mut issuer_sm = IssuerSM::new_w3c("GJ1SzoWzavQYfNL9XkaJdrQejfztN4XqdsiV4ct3LXKL", &json!({"name": "alice"}).to_string(), &source_id());
+            issuer_sm = issuer_sm.handle_message(CredentialIssuanceMessage::CredentialInit(conn_handle, None)).unwrap();
+
+            let mut requested = match issuer_sm.state {
+                IssuerState::OfferSent(ref state) => LdProofVcDetail::from_str(&state.offer).unwrap(),
+                _ => panic!("Credential Offer is not sent")
+            };
+            requested.credential.credential_subject.insert(String::from("name"), json!("mallory"));
+            let request = CredentialRequest::create().set_ld_proof_vc_detail(&requested).unwrap();
+
+            issuer_sm = issuer_sm.handle_message(CredentialIssuanceMessage::CredentialRequest(request)).unwrap();
+            issuer_sm = issuer_sm.handle_message(CredentialIssuanceMessage::CredentialSend(conn_handle)).unwrap();
+
+            assert_eq!(Status::Failed(ProblemReport::default()).code(), issuer_sm.credential_status());
+        }
+
         #[test]
         #[cfg(feature = "general_test")]
         fn test_issuer_handle_other_messages_from_request_received_state() {
@@ -548,7 +1012,57 @@ pub mod test {
             assert_match!(IssuerState::Finished(_), issuer_sm.state);
         }
 
-        // TRANSITIONS TO/FROM CREDENTIAL SENT STATE AREN'T POSSIBLE NOW
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_issuer_handle_credential_send_message_asking_for_ack() {
+            let _setup = SetupAriesMocks::init();
+
+            let issuer_sm = _issuer_sm().to_credential_sent_state();
+
+            assert_match!(IssuerState::CredentialSent(_), issuer_sm.state);
+            assert!(issuer_sm.is_awaiting_ack());
+            assert!(issuer_sm.get_revocation_info().is_some());
+            assert_eq!(Status::Undefined.code(), issuer_sm.credential_status());
+        }
+
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_issuer_handle_ack_message_from_credential_sent_state() {
+            let _setup = SetupAriesMocks::init();
+
+            let mut issuer_sm = _issuer_sm().to_credential_sent_state();
+            issuer_sm = issuer_sm.handle_message(CredentialIssuanceMessage::CredentialAck(_ack())).unwrap();
+
+            assert_match!(IssuerState::Finished(_), issuer_sm.state);
+            assert!(!issuer_sm.is_awaiting_ack());
+            assert_eq!(Status::Success.code(), issuer_sm.credential_status());
+        }
+
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_issuer_handle_problem_report_message_from_credential_sent_state() {
+            let _setup = SetupAriesMocks::init();
+
+            let mut issuer_sm = _issuer_sm().to_credential_sent_state();
+            issuer_sm = issuer_sm.handle_message(CredentialIssuanceMessage::ProblemReport(_problem_report())).unwrap();
+
+            assert_match!(IssuerState::Finished(_), issuer_sm.state);
+            assert_eq!(Status::Failed(ProblemReport::default()).code(), issuer_sm.credential_status());
+        }
+
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_issuer_handle_other_messages_from_credential_sent_state() {
+            let _setup = SetupAriesMocks::init();
+
+            let mut issuer_sm = _issuer_sm().to_credential_sent_state();
+
+            issuer_sm = issuer_sm.handle_message(CredentialIssuanceMessage::CredentialSend(mock_connection())).unwrap();
+            assert_match!(IssuerState::CredentialSent(_), issuer_sm.state);
+
+            issuer_sm = issuer_sm.handle_message(CredentialIssuanceMessage::CredentialRequest(_credential_request())).unwrap();
+            assert_match!(IssuerState::CredentialSent(_), issuer_sm.state);
+        }
 
         #[test]
         #[cfg(feature = "general_test")]
@@ -696,6 +1210,39 @@ pub mod test {
         fn test_issuer_find_message_to_handle_from_credential_sent_state() {
             let _setup = SetupAriesMocks::init();
 
+            let issuer = _issuer_sm().to_credential_sent_state();
+
+            // Ack
+            {
+                let messages = map!(
+                    "key_1".to_string() => A2AMessage::CredentialOffer(_credential_offer()),
+                    "key_2".to_string() => A2AMessage::CredentialRequest(_credential_request()),
+                    "key_3".to_string() => A2AMessage::CredentialAck(_ack())
+                );
+
+                let (uid, message) = issuer.find_message_to_handle(messages).unwrap();
+                assert_eq!("key_3", uid);
+                assert_match!(A2AMessage::CredentialAck(_), message);
+            }
+
+            // Problem Report
+            {
+                let messages = map!(
+                    "key_1".to_string() => A2AMessage::CredentialOffer(_credential_offer()),
+                    "key_2".to_string() => A2AMessage::CommonProblemReport(_problem_report())
+                );
+
+                let (uid, message) = issuer.find_message_to_handle(messages).unwrap();
+                assert_eq!("key_2", uid);
+                assert_match!(A2AMessage::CommonProblemReport(_), message);
+            }
+        }
+
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_issuer_find_message_to_handle_from_finished_state() {
+            let _setup = SetupAriesMocks::init();
+
             let issuer = _issuer_sm().to_finished_state();
 
             // No messages
@@ -714,6 +1261,43 @@ pub mod test {
         }
     }
 
+    mod renewal {
+        use super::*;
+
+        fn _renewal() -> RenewalInfo {
+            RenewalInfo {
+                parent_thread_id: "parent".to_string(),
+                revoke_on_success: Some(RevocationInfoV1 { cred_rev_id: None, rev_reg_id: None, tails_file: None }),
+                revocation_error: None,
+            }
+        }
+
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_issuer_keeps_failed_revocation_of_renewed_credential() {
+            let _setup = SetupAriesMocks::init();
+
+            let issuer_sm = _issuer_sm().set_renewal(_renewal()).to_finished_state();
+            assert_match!(IssuerState::Finished(_), issuer_sm.state);
+
+            let err = issuer_sm.get_renewal_revocation_error().unwrap();
+            assert_eq!(VcxErrorKind::InvalidRevocationEntry, err.kind());
+
+            // revocation is retried by following updates
+            let issuer_sm = issuer_sm.update_state(None).unwrap();
+            assert!(issuer_sm.get_renewal_revocation_error().is_some());
+            assert!(issuer_sm.renewal.as_ref().unwrap().revoke_on_success.is_some());
+        }
+
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_issuer_without_renewal_has_no_revocation_error() {
+            let _setup = SetupAriesMocks::init();
+
+            assert!(_issuer_sm().to_finished_state().get_renewal_revocation_error().is_none());
+        }
+    }
+
     mod get_state {
         use super::*;
 
@@ -725,6 +1309,7 @@ pub mod test {
             assert_eq!(VcxStateType::VcxStateInitialized as u32, _issuer_sm().state());
             assert_eq!(VcxStateType::VcxStateOfferSent as u32, _issuer_sm().to_offer_sent_state().state());
             assert_eq!(VcxStateType::VcxStateRequestReceived as u32, _issuer_sm().to_request_received_state().state());
+            assert_eq!(VcxStateType::VcxStateAccepted as u32, _issuer_sm().to_credential_sent_state().state());
             assert_eq!(VcxStateType::VcxStateAccepted as u32, _issuer_sm().to_finished_state().state());
         }
     }
@@ -1,7 +1,11 @@
+use log::Level;
+
 use aries::handlers::issuance::issuer::state_machine::RevocationInfoV1;
 use aries::handlers::issuance::issuer::states::finished::FinishedState;
+use aries::messages::error::ProblemReport;
 use aries::messages::status::Status;
 
+// credential was sent with `~please_ack`, the exchange is finished by the holder's ack
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CredentialSentState {
     pub connection_handle: u32,
@@ -12,7 +16,7 @@ pub struct CredentialSentState {
 
 impl From<CredentialSentState> for FinishedState {
     fn from(state: CredentialSentState) -> Self {
-        trace!("SM is now in Finished state");
+        log_event!(Level::Trace, "state_transition", to = "Finished");
         FinishedState {
             cred_id: None,
             thread_id: state.thread_id,
@@ -20,4 +24,16 @@ impl From<CredentialSentState> for FinishedState {
             status: Status::Success,
         }
     }
-}
\ No newline at end of file
+}
+
+impl From<(CredentialSentState, ProblemReport)> for FinishedState {
+    fn from((state, err): (CredentialSentState, ProblemReport)) -> Self {
+        log_event!(Level::Trace, "state_transition", to = "Finished");
+        FinishedState {
+            cred_id: None,
+            thread_id: state.thread_id,
+            revocation_info_v1: state.revocation_info_v1,
+            status: Status::Failed(err),
+        }
+    }
+}
@@ -1,3 +1,6 @@
+use log::Level;
+
+use aries::handlers::issuance::issuer::state_machine::RevocationInfoV1;
 use aries::handlers::issuance::issuer::states::finished::FinishedState;
 use aries::handlers::issuance::issuer::states::offer_sent::OfferSentState;
 use aries::messages::a2a::MessageId;
@@ -7,28 +10,56 @@ use aries::messages::status::Status;
 pub struct InitialState {
     pub cred_def_id: String,
     pub credential_json: String,
+    // registry slot is reserved once the credential is built, records created before reserved it right away
     pub rev_reg_id: Option<String>,
     pub tails_file: Option<String>,
+    #[serde(default)]
+    pub revocable: bool,
+    // issuer verkey of W3C credential, Indy credential of `cred_def_id` is issued if not set
+    #[serde(default)]
+    pub w3c_issuer_key: Option<String>,
+    // offer expires `expires_in_secs` after it is sent
+    #[serde(default)]
+    pub expires_in_secs: Option<u64>,
 }
 
 impl InitialState {
-    pub fn new(cred_def_id: &str, credential_json: &str, rev_reg_id: Option<String>, tails_file: Option<String>) -> Self {
+    pub fn new(cred_def_id: &str, credential_json: &str, revocable: bool) -> Self {
         InitialState {
             cred_def_id: cred_def_id.to_string(),
             credential_json: credential_json.to_string(),
-            rev_reg_id,
-            tails_file,
+            rev_reg_id: None,
+            tails_file: None,
+            revocable,
+            w3c_issuer_key: None,
+            expires_in_secs: None,
+        }
+    }
+
+    pub fn new_w3c(issuer_key: &str, credential_json: &str) -> Self {
+        InitialState {
+            cred_def_id: String::new(),
+            credential_json: credential_json.to_string(),
+            rev_reg_id: None,
+            tails_file: None,
+            revocable: false,
+            w3c_issuer_key: Some(issuer_key.to_string()),
+            expires_in_secs: None,
         }
     }
 }
 
 impl From<InitialState> for FinishedState {
-    fn from(_state: InitialState) -> Self {
-        trace!("SM is now in Finished state");
+    fn from(state: InitialState) -> Self {
+        log_event!(Level::Trace, "state_transition", to = "Finished");
         FinishedState {
             cred_id: None,
             thread_id: String::new(),
-            revocation_info_v1: None,
+            revocation_info_v1: state.rev_reg_id.map(|rev_reg_id| RevocationInfoV1 {
+                cred_rev_id: None,
+                rev_reg_id: Some(rev_reg_id),
+                tails_file: state.tails_file,
+            }),
             status: Status::Undefined,
         }
     }
@@ -36,14 +67,17 @@ impl From<InitialState> for FinishedState {
 
 impl From<(InitialState, String, u32, MessageId)> for OfferSentState {
     fn from((state, offer, connection_handle, sent_id): (InitialState, String, u32, MessageId)) -> Self {
-        trace!("SM is now in OfferSent state");
+        log_event!(Level::Trace, "state_transition", to = "OfferSent");
         OfferSentState {
             offer,
             cred_data: state.credential_json,
             rev_reg_id: state.rev_reg_id,
             tails_file: state.tails_file,
+            revocable: state.revocable,
             connection_handle,
             thread_id: sent_id.0,
+            out_of_band_offer: None,
+            expires_time: None,
         }
     }
 }
\ No newline at end of file
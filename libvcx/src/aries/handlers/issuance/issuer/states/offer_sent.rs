@@ -1,9 +1,14 @@
+use chrono::{DateTime, Utc};
+use log::Level;
+
 use aries::handlers::issuance::issuer::state_machine::RevocationInfoV1;
 use aries::handlers::issuance::issuer::states::finished::FinishedState;
 use aries::handlers::issuance::issuer::states::requested_received::RequestReceivedState;
 use aries::messages::error::ProblemReport;
+use aries::messages::issuance::credential_offer::CredentialOffer;
 use aries::messages::issuance::credential_request::CredentialRequest;
 use aries::messages::status::Status;
+use aries::messages::timing::Timing;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct OfferSentState {
@@ -11,13 +16,49 @@ pub struct OfferSentState {
     pub cred_data: String,
     pub rev_reg_id: Option<String>,
     pub tails_file: Option<String>,
+    #[serde(default)]
+    pub revocable: bool,
     pub connection_handle: u32,
     pub thread_id: String,
+    /// Offer delivered by out-of-band invitation, it is not sent over the connection.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub out_of_band_offer: Option<CredentialOffer>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_time: Option<String>,
+}
+
+impl OfferSentState {
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(Utc::now())
+    }
+
+    /// Whether the offer had expired at `time`, e.g. when the request answering it arrived.
+    pub fn is_expired_at(&self, time: DateTime<Utc>) -> bool {
+        match self.expires_time {
+            Some(ref expires_time) => Timing::create().set_expires_time(expires_time.clone()).check_not_expired_at(time).is_err(),
+            None => false
+        }
+    }
+
+    /// Problem report the holder is informed by when the offer expires.
+    pub fn expiration_report(&self) -> ProblemReport {
+        ProblemReport::create()
+            .set_comment(format!("Credential offer expired at {}", self.expires_time.clone().unwrap_or_default()))
+            .set_thread_id(&self.thread_id)
+    }
+
+    pub fn expire(self, problem_report: ProblemReport) -> FinishedState {
+        let mut state: FinishedState = (self, problem_report.clone()).into();
+        state.status = Status::Expired(problem_report);
+        state
+    }
 }
 
 impl From<OfferSentState> for FinishedState {
     fn from(state: OfferSentState) -> Self {
-        trace!("SM is now in Finished state");
+        log_event!(Level::Trace, "state_transition", to = "Finished");
         FinishedState {
             cred_id: None,
             thread_id: state.thread_id,
@@ -33,12 +74,13 @@ impl From<OfferSentState> for FinishedState {
 
 impl From<(OfferSentState, CredentialRequest)> for RequestReceivedState {
     fn from((state, request): (OfferSentState, CredentialRequest)) -> Self {
-        trace!("SM is now in Request Received state");
+        log_event!(Level::Trace, "state_transition", to = "RequestReceived");
         RequestReceivedState {
             offer: state.offer,
             cred_data: state.cred_data,
             rev_reg_id: state.rev_reg_id,
             tails_file: state.tails_file,
+            revocable: state.revocable,
             connection_handle: state.connection_handle,
             request,
             thread_id: state.thread_id,
@@ -48,7 +90,7 @@ impl From<(OfferSentState, CredentialRequest)> for RequestReceivedState {
 
 impl From<(OfferSentState, ProblemReport)> for FinishedState {
     fn from((state, err): (OfferSentState, ProblemReport)) -> Self {
-        trace!("SM is now in Finished state");
+        log_event!(Level::Trace, "state_transition", to = "Finished");
         FinishedState {
             cred_id: None,
             thread_id: state.thread_id,
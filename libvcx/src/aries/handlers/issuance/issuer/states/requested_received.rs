@@ -1,7 +1,8 @@
+use log::Level;
+
 use aries::handlers::issuance::issuer::state_machine::RevocationInfoV1;
 use aries::handlers::issuance::issuer::states::credential_sent::CredentialSentState;
 use aries::handlers::issuance::issuer::states::finished::FinishedState;
-use aries::messages::a2a::MessageId;
 use aries::messages::error::ProblemReport;
 use aries::messages::issuance::credential_request::CredentialRequest;
 use aries::messages::status::Status;
@@ -12,18 +13,20 @@ pub struct RequestReceivedState {
     pub cred_data: String,
     pub rev_reg_id: Option<String>,
     pub tails_file: Option<String>,
+    #[serde(default)]
+    pub revocable: bool,
     pub connection_handle: u32,
     pub request: CredentialRequest,
     pub thread_id: String,
 }
 
-impl From<(RequestReceivedState, MessageId)> for CredentialSentState {
-    fn from((state, _sent_id): (RequestReceivedState, MessageId)) -> Self {
-        trace!("SM is now in CredentialSent state");
+impl From<(RequestReceivedState, Option<String>)> for CredentialSentState {
+    fn from((state, cred_rev_id): (RequestReceivedState, Option<String>)) -> Self {
+        log_event!(Level::Trace, "state_transition", to = "CredentialSent");
         CredentialSentState {
             connection_handle: state.connection_handle,
             revocation_info_v1: Some(RevocationInfoV1 {
-                cred_rev_id: None,
+                cred_rev_id,
                 rev_reg_id: state.rev_reg_id,
                 tails_file: state.tails_file,
             }),
@@ -34,7 +37,7 @@ impl From<(RequestReceivedState, MessageId)> for CredentialSentState {
 
 impl From<(RequestReceivedState, Option<String>)> for FinishedState {
     fn from((state, cred_rev_id): (RequestReceivedState, Option<String>)) -> Self {
-        trace!("SM is now in Finished state");
+        log_event!(Level::Trace, "state_transition", to = "Finished");
         FinishedState {
             cred_id: None,
             thread_id: state.thread_id,
@@ -50,7 +53,7 @@ impl From<(RequestReceivedState, Option<String>)> for FinishedState {
 
 impl From<(RequestReceivedState, ProblemReport)> for FinishedState {
     fn from((state, err): (RequestReceivedState, ProblemReport)) -> Self {
-        trace!("SM is now in Finished state");
+        log_event!(Level::Trace, "state_transition", to = "Finished");
         FinishedState {
             cred_id: None,
             thread_id: state.thread_id,
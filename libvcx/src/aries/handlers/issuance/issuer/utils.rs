@@ -1,11 +1,67 @@
 use std::collections::HashMap;
 
+use aries::messages::mime_type::MimeType;
 use error::{VcxError, VcxErrorKind, VcxResult};
 use utils::error;
 use utils::openssl::encode;
 
+/// Parses value of credential attribute given as `"value"`, `["value"]` (old style)
+/// or `{"value": "..", "mime-type": ".."}`, values of non textual mime types must be base64 encoded.
+pub fn parse_attribute_value(attr: &str, attr_data: &serde_json::Value) -> VcxResult<(String, MimeType)> {
+    match attr_data {
+        // old style input such as {"address2":["101 Wilson Lane"]}
+        serde_json::Value::Array(array_type) => {
+            let attrib_value: &str = match array_type.get(0).and_then(serde_json::Value::as_str) {
+                Some(x) => x,
+                None => {
+                    warn!("Cannot encode attribute: {}", error::INVALID_ATTRIBUTES_STRUCTURE.message);
+                    return Err(VcxError::from_msg(VcxErrorKind::InvalidAttributesStructure, "Attribute value not found"));
+                }
+            };
+
+            warn!("Old attribute format detected. See vcx_issuer_create_credential api for additional information.");
+            Ok((attrib_value.to_string(), MimeType::Plain))
+        }
+
+        // new style input such as {"address2":"101 Wilson Lane"}
+        serde_json::Value::String(str_type) => Ok((str_type.to_string(), MimeType::Plain)),
+
+        // typed input such as {"photo":{"value":"iVBORw0KGgo=","mime-type":"image/png"}}
+        serde_json::Value::Object(object) => {
+            let value = object.get("value").and_then(serde_json::Value::as_str)
+                .ok_or(VcxError::from_msg(VcxErrorKind::InvalidAttributesStructure, format!("Value of attribute {} not found", attr)))?;
+            let mime_type = object.get("mime-type").and_then(serde_json::Value::as_str)
+                .map(MimeType::from)
+                .unwrap_or_default();
+
+            if !mime_type.is_text() {
+                ::base64::decode(value)
+                    .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidAttributesStructure,
+                                                      format!("Value of attribute {} of type {} is not base64 encoded: {}", attr, mime_type.to_string(), err)))?;
+            }
+            Ok((value.to_string(), mime_type))
+        }
+
+        // anything else is an error
+        _ => {
+            warn!("Invalid Json for Attribute data");
+            Err(VcxError::from_msg(VcxErrorKind::InvalidJson, "Invalid Json for Attribute data"))
+        }
+    }
+}
+
+/// Claims of W3C credential subject, raw values of the attributes.
+pub fn credential_subject(attributes: &str) -> VcxResult<serde_json::Map<String, serde_json::Value>> {
+    let attributes: HashMap<String, serde_json::Value> = serde_json::from_str(attributes)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize credential attributes: {}", err)))?;
+
+    attributes.iter()
+        .map(|(attr, attr_data)| parse_attribute_value(attr, attr_data).map(|(value, _)| (attr.to_string(), serde_json::Value::String(value))))
+        .collect()
+}
+
 pub fn encode_attributes(attributes: &str) -> VcxResult<String> {
-    let mut attributes: HashMap<String, serde_json::Value> = serde_json::from_str(attributes)
+    let attributes: HashMap<String, serde_json::Value> = serde_json::from_str(attributes)
         .map_err(|err| {
             warn!("Invalid Json for Attribute data");
             VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize credential attributes: {}", err))
@@ -13,30 +69,8 @@ pub fn encode_attributes(attributes: &str) -> VcxResult<String> {
 
     let mut dictionary = HashMap::new();
 
-    for (attr, attr_data) in attributes.iter_mut() {
-        let first_attr: &str = match attr_data {
-            // old style input such as {"address2":["101 Wilson Lane"]}
-            serde_json::Value::Array(array_type) => {
-                let attrib_value: &str = match array_type.get(0).and_then(serde_json::Value::as_str) {
-                    Some(x) => x,
-                    None => {
-                        warn!("Cannot encode attribute: {}", error::INVALID_ATTRIBUTES_STRUCTURE.message);
-                        return Err(VcxError::from_msg(VcxErrorKind::InvalidAttributesStructure, "Attribute value not found"));
-                    }
-                };
-
-                warn!("Old attribute format detected. See vcx_issuer_create_credential api for additional information.");
-                attrib_value
-            }
-
-            // new style input such as {"address2":"101 Wilson Lane"}
-            serde_json::Value::String(str_type) => str_type,
-            // anything else is an error
-            _ => {
-                warn!("Invalid Json for Attribute data");
-                return Err(VcxError::from_msg(VcxErrorKind::InvalidJson, "Invalid Json for Attribute data"));
-            }
-        };
+    for (attr, attr_data) in attributes.iter() {
+        let (first_attr, _) = parse_attribute_value(attr, attr_data)?;
 
         let encoded = encode(&first_attr)?;
         let attrib_values = json!({
@@ -54,7 +88,6 @@ pub fn encode_attributes(attributes: &str) -> VcxResult<String> {
         })
 }
 
-
 #[cfg(test)]
 pub mod tests {
     use serde_json::Value;
@@ -92,7 +125,7 @@ pub mod tests {
         let tag = "test_tag";
         let config = "{support_revocation: false}";
 
-        libindy_create_and_store_credential_def(&issuer_did, SCHEMAS_JSON, tag, None, config).unwrap();
+        libindy_create_and_store_credential_def(get_wallet_handle(), &issuer_did, SCHEMAS_JSON, tag, None, config).unwrap();
     }
 
     #[test]
@@ -318,4 +351,20 @@ pub mod tests {
         let results: Value = serde_json::from_str(&results_json).unwrap();
         assert_eq!(expected, results);
     }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_encode_attributes_with_mime_type() {
+        let _setup = SetupDefaults::init();
+
+        let results_json = encode_attributes(r#"{"photo": {"value": "iVBORw0KGgo=", "mime-type": "image/png"}}"#).unwrap();
+        let results: Value = serde_json::from_str(&results_json).unwrap();
+        assert_eq!(results["photo"]["raw"], json!("iVBORw0KGgo="));
+
+        let (value, mime_type) = parse_attribute_value("photo", &json!({"value": "iVBORw0KGgo=", "mime-type": "image/png"})).unwrap();
+        assert_eq!((value.as_str(), mime_type), ("iVBORw0KGgo=", MimeType::Other("image/png".to_string())));
+
+        let err = encode_attributes(r#"{"photo": {"value": "not base64!", "mime-type": "image/png"}}"#).unwrap_err();
+        assert_eq!(err.kind(), VcxErrorKind::InvalidAttributesStructure);
+    }
 }
@@ -9,6 +9,7 @@ use aries::messages::issuance::credential_request::CredentialRequest;
 #[derive(Debug, Clone)]
 pub enum CredentialIssuanceMessage {
     CredentialInit(u32, Option<String>),
+    PrepareOutOfBandOffer(u32, Option<String>),
     CredentialSend(u32),
     CredentialProposal(CredentialProposal),
     CredentialOffer(CredentialOffer),
@@ -17,6 +18,9 @@ pub enum CredentialIssuanceMessage {
     Credential(Credential),
     CredentialAck(CredentialAck),
     ProblemReport(ProblemReport),
+    CheckExpiration,
+    // connection handle and reason the exchange is abandoned for
+    Abandon(u32, String),
     Unknown,
 }
 
@@ -1,3 +1,5 @@
 pub mod connection;
 pub mod issuance;
-pub mod proof_presentation;
\ No newline at end of file
+pub mod proof_presentation;
+pub mod processed_messages;
+pub mod protocol_history;
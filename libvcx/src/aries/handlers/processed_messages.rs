@@ -0,0 +1,81 @@
+use log::Level;
+
+use std::collections::HashMap;
+
+use connection;
+use aries::messages::a2a::A2AMessage;
+
+const MAX_PROCESSED_MESSAGES: usize = 100;
+
+///
+/// Ids of messages already handled by a state machine. Agencies redeliver messages after timeouts,
+/// redelivered messages are ignored instead of being handled again.
+///
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ProcessedMessages(Vec<String>);
+
+impl ProcessedMessages {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, message: &A2AMessage) -> bool {
+        match message.message_id() {
+            Some(id) => self.0.contains(&id),
+            None => false
+        }
+    }
+
+    pub fn insert(&mut self, message: &A2AMessage) {
+        if let Some(id) = message.message_id() {
+            if self.0.contains(&id) { return; }
+            if self.0.len() >= MAX_PROCESSED_MESSAGES {
+                self.0.remove(0);
+            }
+            self.0.push(id);
+        }
+    }
+
+    /// Drops messages handled before, they are marked as reviewed in the agency so they are not downloaded again.
+    pub fn filter_new(&self, connection_handle: u32, messages: HashMap<String, A2AMessage>) -> HashMap<String, A2AMessage> {
+        messages.into_iter()
+            .filter(|(uid, message)| {
+                if !self.contains(message) { return true; }
+
+                log_event!(Level::Debug, "ProcessedMessages::filter_new", ignoring_redelivered_message = uid);
+                connection::update_message_status(connection_handle, uid.clone())
+                    .unwrap_or_else(|err| warn!("Cannot update status of redelivered message {}: {}", uid, err));
+                false
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use aries::messages::a2a::MessageId;
+    use aries::messages::ack::tests::_ack;
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_processed_messages() {
+        let mut processed = ProcessedMessages::default();
+        assert!(processed.is_empty());
+        assert!(!processed.contains(&A2AMessage::Ack(_ack())));
+
+        processed.insert(&A2AMessage::Ack(_ack()));
+        processed.insert(&A2AMessage::Ack(_ack()));
+        assert!(processed.contains(&A2AMessage::Ack(_ack())));
+        assert_eq!(1, processed.0.len());
+
+        for i in 0..MAX_PROCESSED_MESSAGES {
+            let mut ack = _ack();
+            ack.id = MessageId(i.to_string());
+            processed.insert(&A2AMessage::Ack(ack));
+        }
+        assert_eq!(MAX_PROCESSED_MESSAGES, processed.0.len());
+        assert!(!processed.contains(&A2AMessage::Ack(_ack())));
+    }
+}
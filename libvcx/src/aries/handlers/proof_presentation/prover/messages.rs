@@ -3,11 +3,12 @@ use aries::messages::error::ProblemReport;
 use aries::messages::proof_presentation::presentation::Presentation;
 use aries::messages::proof_presentation::presentation_ack::PresentationAck;
 use aries::messages::proof_presentation::presentation_proposal::PresentationPreview;
-use aries::messages::proof_presentation::presentation_request::PresentationRequestData;
+use aries::messages::proof_presentation::presentation_request::{PresentationRequest, PresentationRequestData};
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub enum ProverMessages {
     PresentationRequestReceived(PresentationRequestData),
+    PresentationRequestSuperseded(PresentationRequest),
     RejectPresentationRequest((u32, String)),
     SetPresentation(Presentation),
     PreparePresentation((String, String)),
@@ -15,6 +16,7 @@ pub enum ProverMessages {
     PresentationAckReceived(PresentationAck),
     PresentationRejectReceived(ProblemReport),
     ProposePresentation((u32, PresentationPreview)),
+    CheckExpiration,
     Unknown,
 }
 
@@ -27,6 +29,9 @@ impl From<A2AMessage> for ProverMessages {
             A2AMessage::CommonProblemReport(report) => {
                 ProverMessages::PresentationRejectReceived(report)
             }
+            A2AMessage::PresentationRequest(request) => {
+                ProverMessages::PresentationRequestSuperseded(request)
+            }
             _ => {
                 ProverMessages::Unknown
             }
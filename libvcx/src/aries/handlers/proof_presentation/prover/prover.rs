@@ -1,84 +1,132 @@
 use std::convert::TryInto;
 
-use ::{connection, settings};
+use log::Level;
+
+use ::{connection, disclosed_proof_utils, settings};
 use error::prelude::*;
 use messages::proofs::proof_message::ProofMessage;
+use messages::proofs::proof_request::ProofRequestData;
+use utils::cancellation;
 use utils::libindy::anoncreds;
 use aries::handlers::proof_presentation::prover::messages::ProverMessages;
 use aries::messages::a2a::A2AMessage;
+use aries::messages::proof_presentation::format::PresentationFormat;
 use aries::messages::proof_presentation::presentation::Presentation;
 use aries::messages::proof_presentation::presentation_proposal::PresentationPreview;
 use aries::messages::proof_presentation::presentation_request::PresentationRequest;
+use aries::messages::proof_presentation::request_preview::PresentationRequestPreview;
 use aries::handlers::proof_presentation::prover::state_machine::ProverSM;
+use utils::metadata::{self, Metadata};
+use utils::structured_log::{self, Correlation};
 
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Prover {
-    prover_sm: ProverSM
+    prover_sm: ProverSM,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Metadata::is_empty")]
+    metadata: Metadata,
 }
 
 impl Prover {
     pub fn create(source_id: &str, presentation_request: PresentationRequest) -> VcxResult<Prover> {
-        trace!("Prover::create >>> source_id: {}, presentation_request: {:?}", source_id, presentation_request);
+        log_event!(Level::Trace, "Prover::create", source_id = source_id);
         Ok(Prover {
             prover_sm: ProverSM::new(presentation_request, source_id.to_string()),
+            metadata: Metadata::new(),
         })
     }
 
     pub fn state(&self) -> u32 { self.prover_sm.state() }
 
     pub fn presentation_status(&self) -> u32 {
-        trace!("Prover::presentation_state >>>");
+        log_event!(Level::Trace, "Prover::presentation_state");
         self.prover_sm.presentation_status()
     }
 
     pub fn retrieve_credentials(&self) -> VcxResult<String> {
-        trace!("Prover::retrieve_credentials >>>");
+        log_event!(Level::Trace, "Prover::retrieve_credentials");
         let presentation_request = self.presentation_request_data()?;
-        anoncreds::libindy_prover_get_credentials_for_proof_req(&presentation_request)
+
+        if self.presentation_format() == PresentationFormat::PresentationExchange {
+            return disclosed_proof_utils::retrieve_presentation_exchange_credentials(&presentation_request);
+        }
+
+        let credentials = anoncreds::libindy_prover_get_credentials_for_proof_req(&presentation_request)?;
+
+        let proof_req: ProofRequestData = ::serde_json::from_str(&presentation_request)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize presentation request: {}", err)))?;
+        let credentials = disclosed_proof_utils::add_attribute_group_values(&credentials, &proof_req)?;
+        disclosed_proof_utils::add_predicates_section(&credentials, &proof_req)
     }
 
     pub fn generate_presentation(&mut self, credentials: String, self_attested_attrs: String) -> VcxResult<()> {
-        trace!("Prover::generate_presentation >>> credentials: {}, self_attested_attrs: {:?}", credentials, self_attested_attrs);
+        log_event!(Level::Trace, "Prover::generate_presentation");
         self.step(ProverMessages::PreparePresentation((credentials, self_attested_attrs)))
     }
 
     pub fn generate_presentation_msg(&self) -> VcxResult<String> {
-        trace!("Prover::generate_presentation_msg >>>");
+        log_event!(Level::Trace, "Prover::generate_presentation_msg");
         let proof = self.prover_sm.presentation()?.to_owned();
         Ok(json!(proof).to_string())
     }
 
     pub fn set_presentation(&mut self, presentation: Presentation) -> VcxResult<()> {
-        trace!("Prover::set_presentation >>>");
+        log_event!(Level::Trace, "Prover::set_presentation");
         self.step(ProverMessages::SetPresentation(presentation))
     }
 
     pub fn send_presentation(&mut self, connection_handle: u32) -> VcxResult<()> {
-        trace!("Prover::send_presentation >>>");
+        log_event!(Level::Trace, "Prover::send_presentation");
         self.step(ProverMessages::SendPresentation(connection_handle))
     }
 
     pub fn update_state(&mut self, message: Option<&str>, connection_handle: Option<u32>) -> VcxResult<()> {
-        trace!("Prover::update_state >>> connection_handle: {:?}, message: {:?}", connection_handle, message);
+        log_event!(Level::Trace, "Prover::update_state", connection_handle = connection_handle);
+
+        // no message is expected from the verifier until the presentation is sent
+        if self.prover_sm.is_expired() {
+            return self.step(ProverMessages::CheckExpiration);
+        }
 
-        if !self.prover_sm.has_transitions() { 
-            trace!("Prover::update_state >> found no available transition");
+        // request not answered yet can be superseded by the verifier, it's looked for over the given connection
+        let awaits_superseding_request = connection_handle.is_some() && !self.prover_sm.is_finished();
+        if !self.prover_sm.has_transitions() && !awaits_superseding_request {
+            log_event!(Level::Trace, "Prover::update_state", note = "found no available transition");
             return Ok(());
         }
 
-        let connection_handle = connection_handle.unwrap_or(self.prover_sm.connection_handle()?);
+        let connection_handle = match connection_handle {
+            Some(connection_handle) => connection_handle,
+            None => self.prover_sm.connection_handle()?
+        };
         self.prover_sm.set_connection_handle(connection_handle);
+        let _correlation = structured_log::enter(Correlation::new().connection_handle(Some(connection_handle)));
 
         if let Some(message_) = message {
-            return self.update_state_with_message(message_);
+            let prev_state = self.state();
+            self.update_state_with_message(message_)?;
+
+            // message of a thread no object knows yet is kept for the object of the thread
+            if self.state() == prev_state && self.prover_sm.has_transitions() {
+                if let Ok(a2a_message) = ::serde_json::from_str::<A2AMessage>(message_) {
+                    if self.prover_sm.is_processed(&a2a_message) { return Ok(()); }
+
+                    connection::queue_message(connection_handle, a2a_message)
+                        .unwrap_or_else(|err| warn!("Prover::update_state >>> cannot queue message: {}", err));
+                }
+            }
+            return Ok(());
         }
 
-        let messages = connection::get_messages(connection_handle)?;
-        trace!("Prover::update_state >>> found messages: {:?}", messages);
+        let messages = self.prover_sm.processed_messages().filter_new(connection_handle, connection::get_thread_messages(connection_handle, &self.prover_sm.thread_id())?);
+        log_event!(Level::Trace, "Prover::update_state", message_uids = messages.keys().collect::<Vec<_>>());
 
         if let Some((uid, message)) = self.prover_sm.find_message_to_handle(messages) {
-            self.handle_message(message.into())?;
+            let _correlation = structured_log::enter(Correlation::new().message(&message));
+            log_event!(Level::Debug, "message_received", source_id = self.get_source_id(), state = self.state(), uid = uid);
+            cancellation::commit();
+            self.prover_sm = self.prover_sm.clone().receive_message(message)?;
             connection::update_message_status(connection_handle, uid)?;
         };
 
@@ -86,23 +134,30 @@ impl Prover {
     }
 
     pub fn update_state_with_message(&mut self, message: &str) -> VcxResult<()> {
-        trace!("Prover::update_state_with_message >>> message: {:?}", message);
-
         let a2a_message: A2AMessage = ::serde_json::from_str(&message)
             .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Cannot updated state with message: Message deserialization failed: {:?}", err)))?;
 
-        self.handle_message(a2a_message.into())?;
+        let _correlation = structured_log::enter(Correlation::new().message(&a2a_message));
+        log_event!(Level::Debug, "message_received", source_id = self.get_source_id(), state = self.state());
+
+        if self.prover_sm.is_processed(&a2a_message) {
+            log_event!(Level::Debug, "Prover::update_state_with_message", note = "message was already processed");
+            return Ok(());
+        }
+
+        cancellation::commit();
+        self.prover_sm = self.prover_sm.clone().receive_message(a2a_message)?;
 
         Ok(())
     }
 
     pub fn handle_message(&mut self, message: ProverMessages) -> VcxResult<()> {
-        trace!("Prover::handle_message >>> message: {:?}", message);
+        log_event!(Level::Trace, "Prover::handle_message");
         self.step(message)
     }
 
     pub fn get_presentation_request(connection_handle: u32, msg_id: &str) -> VcxResult<PresentationRequest> {
-        trace!("Prover::get_presentation_request >>> connection_handle: {:?}, msg_id: {:?}", connection_handle, msg_id);
+        log_event!(Level::Trace, "Prover::get_presentation_request", connection_handle = connection_handle, msg_id = msg_id);
 
         let message = connection::get_message_by_id(connection_handle, msg_id.to_string())?;
 
@@ -118,7 +173,7 @@ impl Prover {
     }
 
     pub fn get_presentation_request_messages(connection_handle: u32) -> VcxResult<Vec<A2AMessage>> {
-        trace!("Prover::get_presentation_request_messages >>> connection_handle: {:?}", connection_handle);
+        log_event!(Level::Trace, "Prover::get_presentation_request_messages", connection_handle = connection_handle);
 
         let presentation_requests: Vec<A2AMessage> =
             connection::get_messages(connection_handle)?
@@ -138,15 +193,49 @@ impl Prover {
         self.prover_sm.presentation_request().request_presentations_attach.content()
     }
 
+    pub fn presentation_format(&self) -> PresentationFormat {
+        self.prover_sm.presentation_request().format()
+    }
+
+    pub fn presentation_request_preview(&self) -> VcxResult<PresentationRequestPreview> {
+        self.prover_sm.presentation_request().preview()
+    }
+
     pub fn get_source_id(&self) -> String { self.prover_sm.source_id() }
 
+    pub fn get_thread_id(&self) -> String { self.prover_sm.thread_id() }
+
+    pub fn get_connection_handle(&self) -> Option<u32> { self.prover_sm.connection_handle().ok() }
+
+    pub fn get_metadata(&self) -> &Metadata { &self.metadata }
+
+    pub fn set_metadata(&mut self, key: &str, value: Option<&str>) -> VcxResult<()> {
+        metadata::set(&mut self.metadata, key, value)
+    }
+
+    /// Presentation was sent and the verifier has not acknowledged it yet.
+    pub fn is_awaiting_ack(&self) -> bool {
+        self.prover_sm.is_awaiting_ack()
+    }
+
+    /// Time the received presentation request expires at, in RFC 3339 format.
+    pub fn get_expiry(&self) -> Option<String> {
+        self.prover_sm.presentation_request().timing.as_ref()
+            .and_then(|timing| timing.expires_time.clone())
+    }
+
+    /// JSON array of messages sent and received within the exchange, empty unless `protocol_history` option is enabled.
+    pub fn get_protocol_history(&self) -> VcxResult<String> {
+        self.prover_sm.history().to_string()
+    }
+
     pub fn step(&mut self, message: ProverMessages) -> VcxResult<()> {
         self.prover_sm = self.prover_sm.clone().step(message)?;
         Ok(())
     }
 
     pub fn decline_presentation_request(&mut self, connection_handle: u32, reason: Option<String>, proposal: Option<String>) -> VcxResult<()> {
-        trace!("Prover::decline_presentation_request >>> connection_handle: {}, reason: {:?}, proposal: {:?}", connection_handle, reason, proposal);
+        log_event!(Level::Trace, "Prover::decline_presentation_request", connection_handle = connection_handle);
         match (reason, proposal) {
             (Some(reason), None) => {
                 self.step(ProverMessages::RejectPresentationRequest((connection_handle, reason)))
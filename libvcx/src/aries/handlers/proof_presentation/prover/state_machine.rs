@@ -1,8 +1,12 @@
+use log::Level;
+
 use std::collections::HashMap;
 
 use api::VcxStateType;
 use connection;
 use error::prelude::*;
+use aries::handlers::processed_messages::ProcessedMessages;
+use aries::handlers::protocol_history::{self, ProtocolHistory};
 use aries::handlers::proof_presentation::prover::messages::ProverMessages;
 use aries::messages::a2a::A2AMessage;
 use aries::messages::error::ProblemReport;
@@ -10,6 +14,7 @@ use aries::messages::proof_presentation::presentation::Presentation;
 use aries::messages::proof_presentation::presentation_proposal::{PresentationPreview, PresentationProposal};
 use aries::messages::proof_presentation::presentation_request::PresentationRequest;
 use aries::messages::status::Status;
+use aries::messages::timing::Timing;
 use aries::handlers::proof_presentation::prover::states::initial::InitialState;
 use aries::handlers::proof_presentation::prover::states::presentation_prepared::PresentationPreparedState;
 use aries::handlers::proof_presentation::prover::states::presentation_prepared_failed::PresentationPreparationFailedState;
@@ -23,20 +28,39 @@ pub struct ProverSM {
     source_id: String,
     thread_id: String,
     state: ProverState,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "ProcessedMessages::is_empty")]
+    processed_messages: ProcessedMessages,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "ProtocolHistory::is_empty")]
+    history: ProtocolHistory,
 }
 
 impl ProverSM {
     pub fn new(presentation_request: PresentationRequest, source_id: String) -> ProverSM {
-        ProverSM { source_id, thread_id: presentation_request.id.0.clone(), state: ProverState::Initiated(InitialState { presentation_request }) }
+        ProverSM { source_id, thread_id: presentation_request.thread_id(), state: ProverState::Initiated(InitialState { presentation_request }), processed_messages: ProcessedMessages::default(), history: ProtocolHistory::default() }
+    }
+
+    pub fn is_processed(&self, message: &A2AMessage) -> bool {
+        self.processed_messages.contains(message)
+    }
+
+    pub fn processed_messages(&self) -> &ProcessedMessages {
+        &self.processed_messages
+    }
+
+    pub fn history(&self) -> &ProtocolHistory {
+        &self.history
     }
 }
 
 // Possible Transitions:
 //
 // Initial -> PresentationPrepared, PresentationPreparationFailedState, Finished
-// PresentationPrepared -> PresentationSent, Finished
+// PresentationPrepared -> PresentationSent (presentation asks for ack), PresentationPreparationFailedState (request expired), Finished
 // PresentationPreparationFailedState -> Finished
 // PresentationSent -> Finished
+// any but Finished -> Initial (request superseded by the verifier)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ProverState {
     Initiated(InitialState),
@@ -48,7 +72,18 @@ pub enum ProverState {
 
 impl ProverSM {
     pub fn find_message_to_handle(&self, messages: HashMap<String, A2AMessage>) -> Option<(String, A2AMessage)> {
-        trace!("Prover::find_message_to_handle >>> messages: {:?}", messages);
+        log_event!(Level::Trace, "Prover::find_message_to_handle", message_uids = messages.keys().collect::<Vec<_>>());
+
+        // superseding request is handled first, problem report refusing presentation of the former request is not final then
+        let superseding_request = messages.iter()
+            .find(|&(_, message)| match *message {
+                A2AMessage::PresentationRequest(ref request) => self.is_superseded_by(request),
+                _ => false
+            })
+            .map(|(uid, message)| (uid.clone(), message.clone()));
+        if superseding_request.is_some() {
+            return superseding_request;
+        }
 
         for (uid, message) in messages {
             match self.state {
@@ -91,9 +126,44 @@ impl ProverSM {
     }
 
     pub fn step(self, message: ProverMessages) -> VcxResult<ProverSM> {
-        trace!("ProverSM::step >>> message: {:?}", message);
+        self._step(message, None)
+    }
+
+    /// Handles message received from the verifier, the message is marked as processed once it moves the exchange.
+    pub fn receive_message(self, message: A2AMessage) -> VcxResult<ProverSM> {
+        self._step(message.clone().into(), Some(message))
+    }
+
+    fn _step(self, message: ProverMessages, received: Option<A2AMessage>) -> VcxResult<ProverSM> {
+        let prev_state = (self.state(), self.has_transitions(), self.presentation_request().id.clone());
+        let sent = protocol_history::capture();
 
-        let ProverSM { source_id, state, thread_id } = self;
+        let mut prover_sm = self.step_message(message)?;
+
+        if let Some(message) = received {
+            if (prover_sm.state(), prover_sm.has_transitions(), prover_sm.presentation_request().id.clone()) != prev_state {
+                prover_sm.processed_messages.insert(&message);
+                prover_sm.history.record_received(&message);
+            }
+        }
+        prover_sm.history.record_sent(sent.take());
+
+        Ok(prover_sm)
+    }
+
+    fn step_message(self, message: ProverMessages) -> VcxResult<ProverSM> {
+        log_event!(Level::Trace, "ProverSM::step");
+
+        if let ProverMessages::PresentationRequestSuperseded(ref presentation_request) = message {
+            if self.is_superseded_by(presentation_request) {
+                // anything prepared or sent answers the former request, the revised one is to be presented from scratch
+                let ProverSM { source_id, thread_id, processed_messages, history, .. } = self;
+                let state = ProverState::Initiated(InitialState { presentation_request: presentation_request.clone() });
+                return Ok(ProverSM { source_id, state, thread_id, processed_messages, history });
+            }
+        }
+
+        let ProverSM { source_id, state, thread_id, processed_messages, history } = self;
 
         let state = match state {
             ProverState::Initiated(state) => {
@@ -105,13 +175,14 @@ impl ProverSM {
                     ProverMessages::PreparePresentation((credentials, self_attested_attrs)) => {
                         match state.build_presentation(&credentials, &self_attested_attrs) {
                             Ok(presentation) => {
-                                let presentation = Presentation::create()
+                                let presentation = presentation
                                     .ask_for_ack()
-                                    .set_thread_id(&thread_id)
-                                    .set_presentations_attach(presentation)?;
+                                    .set_thread_id(&thread_id);
 
                                 ProverState::PresentationPrepared((state, presentation).into())
                             }
+                            // nothing was prepared, the request can still be presented
+                            Err(err) if err.kind() == VcxErrorKind::Cancelled => return Err(err),
                             Err(err) => {
                                 let problem_report =
                                     ProblemReport::create()
@@ -130,18 +201,38 @@ impl ProverSM {
                         Self::_handle_presentation_proposal(connection_handle, preview, &state.presentation_request, &thread_id)?;
                         ProverState::Finished(state.into())
                     }
+                    ProverMessages::CheckExpiration if state.presentation_request.check_answerable().is_err() => {
+                        ProverState::Finished(FinishedState::expired(state.presentation_request))
+                    }
                     _ => {
                         ProverState::Initiated(state)
                     }
                 }
             }
-            ProverState::PresentationPrepared(state) => {
+            ProverState::PresentationPrepared(mut state) => {
                 match message {
                     ProverMessages::SendPresentation(connection_handle) => {
+                        if let Err(err) = state.presentation_request.check_answerable() {
+                            let problem_report = ProblemReport::create()
+                                .set_comment(err.to_string())
+                                .set_thread_id(&thread_id);
+
+                            // verifier is informed the same way as about failed preparation
+                            let state = ProverState::PresentationPreparationFailed((state, problem_report).into());
+                            return ProverSM { source_id, state, thread_id, processed_messages, history }.step_message(ProverMessages::SendPresentation(connection_handle));
+                        }
+
+                        // verifier checks expiration of its request against the time the presentation was sent
+                        state.presentation.timing = Some(Timing::create().set_out_time());
+
                         match state.presentation_request.service.clone() {
                             None => {
                                 connection::send_message(connection_handle, state.presentation.to_a2a_message())?;
-                                ProverState::PresentationSent((state, connection_handle).into())
+                                if state.presentation.please_ack.is_some() {
+                                    ProverState::PresentationSent((state, connection_handle).into())
+                                } else {
+                                    ProverState::Finished((state, connection_handle).into())
+                                }
                             }
                             Some(service) => {
                                 connection::send_message_to_self_endpoint(state.presentation.to_a2a_message(), &service.into())?;
@@ -157,6 +248,9 @@ impl ProverSM {
                         Self::_handle_presentation_proposal(connection_handle, preview, &state.presentation_request, &thread_id)?;
                         ProverState::Finished(state.into())
                     }
+                    ProverMessages::CheckExpiration if state.presentation_request.check_answerable().is_err() => {
+                        ProverState::Finished(FinishedState::expired(state.presentation_request))
+                    }
                     _ => {
                         ProverState::PresentationPrepared(state)
                     }
@@ -187,6 +281,7 @@ impl ProverSM {
                         ProverState::Finished((state, ack).into())
                     }
                     ProverMessages::PresentationRejectReceived(problem_report) => {
+                        problem_report.record_received("present-proof");
                         ProverState::Finished((state, problem_report).into())
                     }
                     ProverMessages::RejectPresentationRequest(_) => {
@@ -200,7 +295,7 @@ impl ProverSM {
             ProverState::Finished(state) => ProverState::Finished(state)
         };
 
-        Ok(ProverSM { source_id, state, thread_id })
+        Ok(ProverSM { source_id, state, thread_id, processed_messages, history })
     }
 
     fn _handle_reject_presentation_request(connection_handle: u32, reason: &str, presentation_request: &PresentationRequest, thread_id: &str) -> VcxResult<()> {
@@ -229,8 +324,22 @@ impl ProverSM {
         Ok(())
     }
 
+    /// Request revised by the verifier on the thread of the pending one, to be presented instead of it.
+    pub fn is_superseded_by(&self, presentation_request: &PresentationRequest) -> bool {
+        !self.is_finished() && presentation_request.thread_id() == self.thread_id && presentation_request.id != self.presentation_request().id
+    }
+
+    pub fn is_finished(&self) -> bool {
+        match self.state {
+            ProverState::Finished(_) => true,
+            _ => false
+        }
+    }
+
     pub fn source_id(&self) -> String { self.source_id.clone() }
 
+    pub fn thread_id(&self) -> String { self.thread_id.clone() }
+
     pub fn state(&self) -> u32 {
         match self.state {
             ProverState::Initiated(_) => VcxStateType::VcxStateRequestReceived as u32,
@@ -240,14 +349,31 @@ impl ProverSM {
             ProverState::Finished(ref status) => {
                 match status.status {
                     Status::Success => VcxStateType::VcxStateAccepted as u32,
+                    Status::Expired(_) => VcxStateType::VcxStateExpired as u32,
                     _ => VcxStateType::VcxStateNone as u32,
                 }
             }
         }
     }
 
+    /// Request expired before the presentation was sent.
+    pub fn is_expired(&self) -> bool {
+        match self.state {
+            ProverState::Initiated(ref state) => state.presentation_request.check_answerable().is_err(),
+            ProverState::PresentationPrepared(ref state) => state.presentation_request.check_answerable().is_err(),
+            _ => false
+        }
+    }
+
+    pub fn is_awaiting_ack(&self) -> bool {
+        match self.state {
+            ProverState::PresentationSent(_) => true,
+            _ => false
+        }
+    }
+
     pub fn has_transitions(&self) -> bool {
-        trace!("Prover::states::has_transitions >> state: {:?}", self.state);
+        log_event!(Level::Trace, "Prover::states::has_transitions", state = self.state());
         match self.state {
             ProverState::Initiated(_) => false,
             ProverState::PresentationPrepared(_) => true,
@@ -314,8 +440,9 @@ pub mod test {
     use aries::handlers::connection::tests::mock_connection;
     use aries::messages::proof_presentation::presentation::tests::_presentation;
     use aries::messages::proof_presentation::presentation_proposal::tests::{_presentation_preview, _presentation_proposal};
-    use aries::messages::proof_presentation::presentation_request::tests::{_presentation_request, _presentation_request_with_service};
+    use aries::messages::proof_presentation::presentation_request::tests::{_presentation_request, _presentation_request_with_service, thread_id};
     use aries::messages::proof_presentation::test::{_ack, _problem_report};
+    use aries::messages::timing::Timing;
     use aries::test::source_id;
 
     use super::*;
@@ -466,6 +593,58 @@ pub mod test {
             assert_match!(ProverState::PresentationSent(_), prover_sm.state);
         }
 
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_prover_refuses_expired_presentation_request() {
+            let _setup = SetupAriesMocks::init();
+
+            let expired_request = _presentation_request()
+                .set_timing(Timing::create().set_expires_time(String::from("2019-01-23T18:03:27Z")));
+
+            let mut prover_sm = ProverSM::new(expired_request.clone(), source_id());
+            prover_sm = prover_sm.step(ProverMessages::PreparePresentation((_credentials(), _self_attested()))).unwrap();
+            assert_match!(ProverState::PresentationPreparationFailed(_), prover_sm.state);
+
+            let mut prover_sm = ProverSM::new(expired_request, source_id());
+            prover_sm = prover_sm.step(ProverMessages::SetPresentation(_presentation())).unwrap();
+            prover_sm = prover_sm.step(ProverMessages::SendPresentation(mock_connection())).unwrap();
+            assert_match!(ProverState::Finished(_), prover_sm.state);
+            assert_eq!(VcxStateType::VcxStateNone as u32, prover_sm.state());
+        }
+
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_prover_handle_expired_presentation_request() {
+            let _setup = SetupAriesMocks::init();
+
+            let expired_request = _presentation_request()
+                .set_timing(Timing::create().set_expires_time(String::from("2019-01-23T18:03:27Z")));
+
+            let mut prover_sm = ProverSM::new(expired_request, source_id());
+            assert!(prover_sm.is_expired());
+
+            prover_sm = prover_sm.step(ProverMessages::CheckExpiration).unwrap();
+            assert_match!(ProverState::Finished(_), prover_sm.state);
+            assert_eq!(VcxStateType::VcxStateExpired as u32, prover_sm.state());
+
+            let prover_sm = _prover_sm().step(ProverMessages::CheckExpiration).unwrap();
+            assert_match!(ProverState::Initiated(_), prover_sm.state);
+        }
+
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_prover_handle_send_presentation_not_asking_for_ack_from_presentation_prepared_state() {
+            let _setup = SetupAriesMocks::init();
+
+            let mut prover_sm = _prover_sm();
+            prover_sm = prover_sm.step(ProverMessages::SetPresentation(Presentation { please_ack: None, .._presentation() })).unwrap();
+            prover_sm = prover_sm.step(ProverMessages::SendPresentation(mock_connection())).unwrap();
+
+            assert_match!(ProverState::Finished(_), prover_sm.state);
+            assert!(!prover_sm.is_awaiting_ack());
+            assert_eq!(VcxStateType::VcxStateAccepted as u32, prover_sm.state());
+        }
+
         #[test]
         #[cfg(feature = "general_test")]
         fn test_prover_handle_send_presentation_message_from_presentation_prepared_state_for_presentation_request_contains_service_decorator() {
@@ -601,6 +780,40 @@ pub mod test {
             assert_match!(ProverState::PresentationSent(_), prover_sm.state);
         }
 
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_prover_handle_superseding_presentation_request() {
+            let _setup = SetupAriesMocks::init();
+
+            let revised_request = _presentation_request()
+                .set_id(String::from("revised"))
+                .set_thread_id(&thread_id());
+
+            // request on another thread does not supersede the pending one
+            let other_request = _presentation_request()
+                .set_id(String::from("other"))
+                .set_thread_id("other");
+
+            let mut prover_sm = _prover_sm().to_presentation_sent_state();
+            prover_sm = prover_sm.receive_message(A2AMessage::PresentationRequest(other_request)).unwrap();
+            assert_match!(ProverState::PresentationSent(_), prover_sm.state);
+
+            let message = A2AMessage::PresentationRequest(revised_request.clone());
+            prover_sm = prover_sm.receive_message(message.clone()).unwrap();
+            assert_match!(ProverState::Initiated(_), prover_sm.state);
+            assert_eq!(&revised_request, prover_sm.presentation_request());
+            assert_eq!(thread_id(), prover_sm.thread_id());
+            assert!(prover_sm.is_processed(&message));
+
+            // the revised request is presented on the original thread
+            prover_sm = prover_sm.to_presentation_sent_state();
+            assert_eq!(Some(thread_id()), prover_sm.presentation().unwrap().thread.thid);
+
+            let mut prover_sm = _prover_sm().to_finished_state();
+            prover_sm = prover_sm.receive_message(A2AMessage::PresentationRequest(revised_request)).unwrap();
+            assert_match!(ProverState::Finished(_), prover_sm.state);
+        }
+
         #[test]
         #[cfg(feature = "general_test")]
         fn test_prover_handle_messages_from_finished_state() {
@@ -697,6 +910,23 @@ pub mod test {
                 assert_match!(A2AMessage::CommonProblemReport(_), message);
             }
 
+            // Superseding Presentation Request is handled before Problem Report
+            {
+                let revised_request = _presentation_request()
+                    .set_id(String::from("revised"))
+                    .set_thread_id(&thread_id());
+
+                let messages = map!(
+                    "key_1".to_string() => A2AMessage::CommonProblemReport(_problem_report()),
+                    "key_2".to_string() => A2AMessage::PresentationRequest(revised_request),
+                    "key_3".to_string() => A2AMessage::CommonProblemReport(_problem_report())
+                );
+
+                let (uid, message) = prover.find_message_to_handle(messages).unwrap();
+                assert_eq!("key_2", uid);
+                assert_match!(A2AMessage::PresentationRequest(_), message);
+            }
+
             // No messages for different Thread ID
             {
                 let messages = map!(
@@ -1,3 +1,6 @@
+use log::Level;
+
+use aries::messages::error::ProblemReport;
 use aries::messages::proof_presentation::presentation::Presentation;
 use aries::messages::proof_presentation::presentation_request::PresentationRequest;
 use aries::messages::status::Status;
@@ -12,9 +15,29 @@ pub struct FinishedState {
 }
 
 
+impl FinishedState {
+    /// Request expired before the presentation was sent, the verifier knows that already so nothing is sent.
+    pub fn expired(presentation_request: PresentationRequest) -> FinishedState {
+        let expires_time = presentation_request.timing.as_ref()
+            .and_then(|timing| timing.expires_time.clone())
+            .unwrap_or_default();
+
+        let problem_report = ProblemReport::create()
+            .set_comment(format!("Presentation request expired at {}", expires_time))
+            .set_thread_id(&presentation_request.thread_id());
+
+        FinishedState {
+            connection_handle: 0,
+            presentation_request,
+            presentation: Default::default(),
+            status: Status::Expired(problem_report),
+        }
+    }
+}
+
 impl From<InitialState> for FinishedState {
     fn from(state: InitialState) -> Self {
-        trace!("transit state from InitialState to FinishedState");
+        log_event!(Level::Trace, "state_transition", from = "InitialState", to = "FinishedState");
         FinishedState {
             connection_handle: 0,
             presentation_request: state.presentation_request,
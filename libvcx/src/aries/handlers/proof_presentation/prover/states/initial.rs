@@ -1,8 +1,11 @@
-use disclosed_proof_utils::generate_indy_proof;
+use log::Level;
+
+use disclosed_proof_utils::{generate_indy_proof, generate_presentation_exchange};
 use error::prelude::*;
 use aries::handlers::proof_presentation::prover::states::presentation_prepared::PresentationPreparedState;
 use aries::handlers::proof_presentation::prover::states::presentation_prepared_failed::PresentationPreparationFailedState;
 use aries::messages::error::ProblemReport;
+use aries::messages::proof_presentation::format::PresentationFormat;
 use aries::messages::proof_presentation::presentation::Presentation;
 use aries::messages::proof_presentation::presentation_request::PresentationRequest;
 
@@ -13,16 +16,24 @@ pub struct InitialState {
 
 
 impl InitialState {
-    pub fn build_presentation(&self, credentials: &str, self_attested_attrs: &str) -> VcxResult<String> {
-        generate_indy_proof(credentials,
-                                            self_attested_attrs,
-                                            &self.presentation_request.request_presentations_attach.content()?)
+    pub fn build_presentation(&self, credentials: &str, self_attested_attrs: &str) -> VcxResult<Presentation> {
+        self.presentation_request.check_answerable()?;
+
+        let presentation_request = self.presentation_request.request_presentations_attach.content()?;
+        match self.presentation_request.format() {
+            PresentationFormat::Indy => {
+                Presentation::create().set_presentations_attach(generate_indy_proof(credentials, self_attested_attrs, &presentation_request)?)
+            }
+            PresentationFormat::PresentationExchange => {
+                Presentation::create().set_verifiable_presentation(&generate_presentation_exchange(credentials, &presentation_request)?)
+            }
+        }
     }
 }
 
 impl From<(InitialState, ProblemReport)> for PresentationPreparationFailedState {
     fn from((state, problem_report): (InitialState, ProblemReport)) -> Self {
-        trace!("transit state from InitialState to PresentationPreparationFailedState");
+        log_event!(Level::Trace, "state_transition", from = "InitialState", to = "PresentationPreparationFailedState");
         PresentationPreparationFailedState {
             presentation_request: state.presentation_request,
             problem_report,
@@ -32,7 +43,7 @@ impl From<(InitialState, ProblemReport)> for PresentationPreparationFailedState
 
 impl From<(InitialState, Presentation)> for PresentationPreparedState {
     fn from((state, presentation): (InitialState, Presentation)) -> Self {
-        trace!("transit state from InitialState to PresentationPreparedState");
+        log_event!(Level::Trace, "state_transition", from = "InitialState", to = "PresentationPreparedState");
         PresentationPreparedState {
             presentation_request: state.presentation_request,
             presentation,
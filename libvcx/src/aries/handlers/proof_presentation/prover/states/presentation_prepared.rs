@@ -1,5 +1,9 @@
+use log::Level;
+
 use aries::handlers::proof_presentation::prover::states::finished::FinishedState;
+use aries::handlers::proof_presentation::prover::states::presentation_prepared_failed::PresentationPreparationFailedState;
 use aries::handlers::proof_presentation::prover::states::presentation_sent::PresentationSentState;
+use aries::messages::error::ProblemReport;
 use aries::messages::proof_presentation::presentation::Presentation;
 use aries::messages::proof_presentation::presentation_request::PresentationRequest;
 use aries::messages::status::Status;
@@ -12,7 +16,7 @@ pub struct PresentationPreparedState {
 
 impl From<(PresentationPreparedState, u32)> for PresentationSentState {
     fn from((state, connection_handle): (PresentationPreparedState, u32)) -> Self {
-        trace!("transit state from PresentationPreparedState to PresentationSentState");
+        log_event!(Level::Trace, "state_transition", from = "PresentationPreparedState", to = "PresentationSentState");
         PresentationSentState {
             presentation_request: state.presentation_request,
             presentation: state.presentation,
@@ -21,9 +25,19 @@ impl From<(PresentationPreparedState, u32)> for PresentationSentState {
     }
 }
 
+impl From<(PresentationPreparedState, ProblemReport)> for PresentationPreparationFailedState {
+    fn from((state, problem_report): (PresentationPreparedState, ProblemReport)) -> Self {
+        log_event!(Level::Trace, "state_transition", from = "PresentationPreparedState", to = "PresentationPreparationFailedState");
+        PresentationPreparationFailedState {
+            presentation_request: state.presentation_request,
+            problem_report,
+        }
+    }
+}
+
 impl From<PresentationPreparedState> for FinishedState {
     fn from(state: PresentationPreparedState) -> Self {
-        trace!("transit state from PresentationPreparedState to FinishedState");
+        log_event!(Level::Trace, "state_transition", from = "PresentationPreparedState", to = "FinishedState");
         FinishedState {
             connection_handle: 0,
             presentation_request: state.presentation_request,
@@ -32,3 +46,16 @@ impl From<PresentationPreparedState> for FinishedState {
         }
     }
 }
+
+// presentation which does not ask for ack is not acknowledged by the verifier
+impl From<(PresentationPreparedState, u32)> for FinishedState {
+    fn from((state, connection_handle): (PresentationPreparedState, u32)) -> Self {
+        log_event!(Level::Trace, "state_transition", from = "PresentationPreparedState", to = "FinishedState");
+        FinishedState {
+            connection_handle,
+            presentation_request: state.presentation_request,
+            presentation: state.presentation,
+            status: Status::Success,
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use log::Level;
+
 use aries::handlers::proof_presentation::prover::states::finished::FinishedState;
 use aries::messages::error::ProblemReport;
 use aries::messages::proof_presentation::presentation::Presentation;
@@ -12,7 +14,7 @@ pub struct PresentationPreparationFailedState {
 
 impl From<(PresentationPreparationFailedState, u32)> for FinishedState {
     fn from((state, connection_handle): (PresentationPreparationFailedState, u32)) -> Self {
-        trace!("transit state from PresentationPreparationFailedState to FinishedState");
+        log_event!(Level::Trace, "state_transition", from = "PresentationPreparationFailedState", to = "FinishedState");
         FinishedState {
             presentation_request: state.presentation_request,
             presentation: Presentation::create(),
@@ -1,3 +1,5 @@
+use log::Level;
+
 use aries::handlers::proof_presentation::prover::states::finished::FinishedState;
 use aries::messages::error::ProblemReport;
 use aries::messages::proof_presentation::presentation::Presentation;
@@ -14,7 +16,7 @@ pub struct PresentationSentState {
 
 impl From<(PresentationSentState, PresentationAck)> for FinishedState {
     fn from((state, _ack): (PresentationSentState, PresentationAck)) -> Self {
-        trace!("transit state from PresentationSentState to FinishedState");
+        log_event!(Level::Trace, "state_transition", from = "PresentationSentState", to = "FinishedState");
         FinishedState {
             connection_handle: state.connection_handle,
             presentation_request: state.presentation_request,
@@ -26,7 +28,7 @@ impl From<(PresentationSentState, PresentationAck)> for FinishedState {
 
 impl From<(PresentationSentState, ProblemReport)> for FinishedState {
     fn from((state, problem_report): (PresentationSentState, ProblemReport)) -> Self {
-        trace!("transit state from PresentationSentState to FinishedState");
+        log_event!(Level::Trace, "state_transition", from = "PresentationSentState", to = "FinishedState");
         FinishedState {
             connection_handle: state.connection_handle,
             presentation_request: state.presentation_request,
@@ -2,13 +2,18 @@ use aries::messages::a2a::A2AMessage;
 use aries::messages::error::ProblemReport;
 use aries::messages::proof_presentation::presentation::Presentation;
 use aries::messages::proof_presentation::presentation_proposal::PresentationProposal;
+use aries::messages::proof_presentation::presentation_request::PresentationRequestData;
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub enum VerifierMessages {
     SendPresentationRequest(u32),
+    PrepareOutOfBandRequest(u32),
+    ResendPresentationRequest(u32),
+    UpdatePresentationRequest(PresentationRequestData),
     VerifyPresentation(Presentation),
     PresentationProposalReceived(PresentationProposal),
     PresentationRejectReceived(ProblemReport),
+    CheckExpiration,
     Unknown,
 }
 
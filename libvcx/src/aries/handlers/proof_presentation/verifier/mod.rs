@@ -1,4 +1,5 @@
 pub mod verifier;
 mod messages;
 mod state_machine;
-mod states;
\ No newline at end of file
+mod states;
+mod verification_details;
\ No newline at end of file
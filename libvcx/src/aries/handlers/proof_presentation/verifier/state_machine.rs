@@ -1,28 +1,95 @@
+use log::Level;
+
 use std::collections::HashMap;
 
 use api::VcxStateType;
 use connection::{get_pw_did, get_their_pw_verkey};
 use connection;
 use error::prelude::*;
+use aries::handlers::processed_messages::ProcessedMessages;
+use aries::handlers::protocol_history::{self, ProtocolHistory};
 use aries::handlers::proof_presentation::verifier::messages::VerifierMessages;
 use aries::messages::a2a::A2AMessage;
 use aries::messages::error::ProblemReport;
+use aries::messages::proof_presentation::format::PresentationFormat;
 use aries::messages::proof_presentation::presentation::Presentation;
+use aries::messages::proof_presentation::presentation_exchange::PresentationDefinition;
 use aries::messages::proof_presentation::presentation_request::{PresentationRequest, PresentationRequestData};
 use aries::messages::status::Status;
+use aries::messages::timing::Timing;
 use aries::handlers::proof_presentation::verifier::states::initial::InitialState;
 use aries::handlers::proof_presentation::verifier::states::presentation_request_sent::PresentationRequestSentState;
 use aries::handlers::proof_presentation::verifier::states::finished::FinishedState;
+use aries::handlers::proof_presentation::verifier::verification_details::{build_verification_details, VerificationDetails};
+use proof_utils::{get_revocation_checks, RevocationCheck, validate_indy_proof_as_of};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct VerifierSM {
     source_id: String,
     state: VerifierState,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "ProcessedMessages::is_empty")]
+    processed_messages: ProcessedMessages,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "ProtocolHistory::is_empty")]
+    history: ProtocolHistory,
 }
 
 impl VerifierSM {
     pub fn new(presentation_request: PresentationRequestData, source_id: String) -> VerifierSM {
-        VerifierSM { source_id, state: VerifierState::Initiated(InitialState { presentation_request_data: presentation_request }) }
+        VerifierSM { source_id, state: VerifierState::Initiated(InitialState { presentation_request_data: presentation_request, expires_in_secs: None, presentation_definition: None }), processed_messages: ProcessedMessages::default(), history: ProtocolHistory::default() }
+    }
+
+    /// Verifier requesting DIF Presentation Exchange submission for `presentation_definition`.
+    pub fn new_presentation_exchange(presentation_request: PresentationRequestData, presentation_definition: PresentationDefinition, source_id: String) -> VerifierSM {
+        VerifierSM {
+            source_id,
+            state: VerifierState::Initiated(InitialState {
+                presentation_request_data: presentation_request,
+                expires_in_secs: None,
+                presentation_definition: Some(presentation_definition),
+            }),
+            processed_messages: ProcessedMessages::default(),
+            history: ProtocolHistory::default(),
+        }
+    }
+
+    /// Time the sent request expires at, in RFC 3339 format.
+    pub fn get_expiry(&self) -> Option<String> {
+        match self.state {
+            VerifierState::PresentationRequestSent(ref state) => state.presentation_request.timing.as_ref().and_then(|timing| timing.expires_time.clone()),
+            _ => None
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        match self.state {
+            VerifierState::PresentationRequestSent(ref state) => state.is_expired(),
+            _ => false
+        }
+    }
+
+    /// Presentation request expires `expires_in_secs` after it is sent, later presentations are rejected.
+    pub fn set_expiration(&mut self, expires_in_secs: u64) -> VcxResult<()> {
+        match self.state {
+            VerifierState::Initiated(ref mut state) => {
+                state.expires_in_secs = Some(expires_in_secs);
+                Ok(())
+            }
+            _ => Err(VcxError::from_msg(VcxErrorKind::NotReady, "Presentation request is already sent"))
+        }
+    }
+
+    pub fn is_processed(&self, message: &A2AMessage) -> bool {
+        self.processed_messages.contains(message)
+    }
+
+    pub fn processed_messages(&self) -> &ProcessedMessages {
+        &self.processed_messages
+    }
+
+    pub fn history(&self) -> &ProtocolHistory {
+        &self.history
     }
 }
 
@@ -42,7 +109,7 @@ pub enum RevocationStatus {
 
 impl VerifierSM {
     pub fn find_message_to_handle(&self, messages: HashMap<String, A2AMessage>) -> Option<(String, A2AMessage)> {
-        trace!("VerifierSM::find_message_to_handle >>> messages: {:?}", messages);
+        log_event!(Level::Trace, "VerifierSM::find_message_to_handle", message_uids = messages.keys().collect::<Vec<_>>());
 
         for (uid, message) in messages {
             match self.state {
@@ -79,9 +146,35 @@ impl VerifierSM {
     }
 
     pub fn step(self, message: VerifierMessages) -> VcxResult<VerifierSM> {
-        trace!("VerifierSM::step >>> message: {:?}", message);
+        self._step(message, None)
+    }
+
+    /// Handles message received from the prover, the message is marked as processed once it moves the exchange.
+    pub fn receive_message(self, message: A2AMessage) -> VcxResult<VerifierSM> {
+        self._step(message.clone().into(), Some(message))
+    }
+
+    fn _step(self, message: VerifierMessages, received: Option<A2AMessage>) -> VcxResult<VerifierSM> {
+        let prev_state = self.state.clone();
+        let sent = protocol_history::capture();
 
-        let VerifierSM { source_id, state } = self;
+        let mut verifier_sm = self.step_message(message)?;
+
+        if let Some(message) = received {
+            if verifier_sm.state != prev_state {
+                verifier_sm.processed_messages.insert(&message);
+                verifier_sm.history.record_received(&message);
+            }
+        }
+        verifier_sm.history.record_sent(sent.take());
+
+        Ok(verifier_sm)
+    }
+
+    fn step_message(self, message: VerifierMessages) -> VcxResult<VerifierSM> {
+        log_event!(Level::Trace, "VerifierSM::step");
+
+        let VerifierSM { source_id, state, processed_messages, history } = self;
 
         let state = match state {
             VerifierState::Initiated(state) => {
@@ -97,14 +190,31 @@ impl VerifierSM {
                         let title = format!("{} wants you to share {}",
                                             ::settings::get_config_value(::settings::CONFIG_INSTITUTION_NAME)?, presentation_request.name);
 
-                        let presentation_request =
-                            PresentationRequest::create()
-                                .set_comment(title)
-                                .set_request_presentations_attach(&presentation_request)?;
+                        let mut presentation_request =
+                            state.attach_request(PresentationRequest::create().set_comment(title), &presentation_request)?;
+
+                        if let Some(expires_in_secs) = state.expires_in_secs.or(::settings::get_proof_request_expiry()) {
+                            presentation_request = presentation_request.set_timing(Timing::create().set_out_time().set_expires_in(expires_in_secs));
+                        }
 
                         connection::send_message(connection_handle, presentation_request.to_a2a_message())?;
                         VerifierState::PresentationRequestSent((state, presentation_request, connection_handle).into())
                     }
+                    VerifierMessages::PrepareOutOfBandRequest(connection_handle) => {
+                        // the prover is not known yet, the request is passed out-of-band and answered to the service of the connection
+                        let title = format!("{} wants you to share {}",
+                                            ::settings::get_config_value(::settings::CONFIG_INSTITUTION_NAME)?, state.presentation_request_data.name);
+
+                        let mut presentation_request =
+                            state.attach_request(PresentationRequest::create().set_comment(title), &state.presentation_request_data)?
+                                .set_service(Some(connection::get_service(connection_handle)?));
+
+                        if let Some(expires_in_secs) = state.expires_in_secs.or(::settings::get_proof_request_expiry()) {
+                            presentation_request = presentation_request.set_timing(Timing::create().set_out_time().set_expires_in(expires_in_secs));
+                        }
+
+                        VerifierState::PresentationRequestSent((state, presentation_request, connection_handle).into())
+                    }
                     _ => {
                         VerifierState::Initiated(state)
                     }
@@ -112,18 +222,75 @@ impl VerifierSM {
             }
             VerifierState::PresentationRequestSent(state) => {
                 match message {
+                    VerifierMessages::CheckExpiration if state.is_expired() => {
+                        let problem_report = state.expiration_report();
+                        if ::settings::is_problem_report_on_expiry_enabled() {
+                            state.send_message(problem_report.to_a2a_message())
+                                .unwrap_or_else(|err| warn!("Cannot inform prover about expired request: {}", err));
+                        }
+                        VerifierState::Finished(state.expire(problem_report))
+                    }
+                    VerifierMessages::ResendPresentationRequest(connection_handle) => {
+                        state.ensure_resendable()?;
+                        if connection_handle != state.connection_handle {
+                            return Err(VcxError::from_msg(VcxErrorKind::InvalidConnectionHandle,
+                                                          format!("Presentation request can be resent only over connection {} it was sent over", state.connection_handle)));
+                        }
+                        connection::send_message(connection_handle, state.presentation_request.to_a2a_message())?;
+                        VerifierState::PresentationRequestSent(state)
+                    }
+                    VerifierMessages::UpdatePresentationRequest(presentation_request_data) => {
+                        state.ensure_resendable()?;
+                        let my_did = get_pw_did(state.connection_handle)?;
+                        let remote_did = get_their_pw_verkey(state.connection_handle)?;
+
+                        let presentation_request_data = match state.presentation_definition {
+                            Some(_) => presentation_request_data,
+                            None => presentation_request_data.set_format_version_for_did(&my_did, &remote_did)?
+                        };
+
+                        // Presentation Exchange request revised without name keeps the title
+                        let title = match presentation_request_data.name.as_str() {
+                            "" => state.presentation_request.comment.clone().unwrap_or_default(),
+                            name => format!("{} wants you to share {}", ::settings::get_config_value(::settings::CONFIG_INSTITUTION_NAME)?, name)
+                        };
+
+                        // new request supersedes the pending one, the prover answers it on the original thread
+                        let mut presentation_request =
+                            state.attach_request(PresentationRequest::create().set_comment(title), &presentation_request_data)?
+                                .set_thread_id(&state.presentation_request.thread_id());
+
+                        if let Some(expires_in_secs) = state.expires_in_secs.or(::settings::get_proof_request_expiry()) {
+                            presentation_request = presentation_request.set_timing(Timing::create().set_out_time().set_expires_in(expires_in_secs));
+                        }
+
+                        connection::send_message(state.connection_handle, presentation_request.to_a2a_message())?;
+
+                        let mut superseded_requests = state.superseded_requests.clone();
+                        superseded_requests.push(state.presentation_request.clone());
+                        VerifierState::PresentationRequestSent(PresentationRequestSentState { presentation_request, superseded_requests, ..state })
+                    }
                     VerifierMessages::VerifyPresentation(presentation) => {
                         match state.verify_presentation(&presentation) {
                             Ok(()) => {
                                 VerifierState::Finished((state, presentation, RevocationStatus::NonRevoked).into())
                             }
+                            Err(ref err) if err.kind() == VcxErrorKind::ExpiredMessage => {
+                                let problem_report = state.expiration_report();
+                                state.send_message(problem_report.to_a2a_message())
+                                    .unwrap_or_else(|err| warn!("Cannot inform prover about expired request: {}", err));
+                                VerifierState::Finished(state.expire(problem_report))
+                            }
+                            Err(_) if state.answers_superseded_request(&presentation) => {
+                                VerifierState::PresentationRequestSent(state.reject_late_presentation(&presentation)?)
+                            }
                             Err(err) => {
                                 let problem_report =
                                     ProblemReport::create()
                                         .set_comment(err.to_string())
-                                        .set_thread_id(&state.presentation_request.id.0);
+                                        .set_thread_id(&state.presentation_request.thread_id());
 
-                                connection::send_message(state.connection_handle, problem_report.to_a2a_message())?;
+                                state.send_message(problem_report.to_a2a_message())?;
                                 match err.kind() {
                                     VcxErrorKind::InvalidProof => {
                                         VerifierState::Finished((state, presentation, RevocationStatus::Revoked).into())
@@ -134,15 +301,16 @@ impl VerifierSM {
                         }
                     }
                     VerifierMessages::PresentationRejectReceived(problem_report) => {
+                        problem_report.record_received("present-proof");
                         VerifierState::Finished((state, problem_report).into())
                     }
                     VerifierMessages::PresentationProposalReceived(_) => { // TODO: handle Presentation Proposal
                         let problem_report =
                             ProblemReport::create()
                                 .set_comment(String::from("PresentationProposal is not supported"))
-                                .set_thread_id(&state.presentation_request.id.0);
+                                .set_thread_id(&state.presentation_request.thread_id());
 
-                        connection::send_message(state.connection_handle, problem_report.to_a2a_message())?;
+                        state.send_message(problem_report.to_a2a_message())?;
                         VerifierState::Finished((state, problem_report).into())
                     }
                     _ => {
@@ -153,12 +321,12 @@ impl VerifierSM {
             VerifierState::Finished(state) => VerifierState::Finished(state)
         };
 
-        Ok(VerifierSM { source_id, state })
+        Ok(VerifierSM { source_id, state, processed_messages, history })
     }
 
     pub fn source_id(&self) -> String { self.source_id.clone() }
 
-    pub fn thread_id(&self) -> String { self.presentation_request().map(|request| request.id.0.clone()).unwrap_or_default() }
+    pub fn thread_id(&self) -> String { self.presentation_request().map(|request| request.thread_id()).unwrap_or_default() }
 
     pub fn state(&self) -> u32 {
         match self.state {
@@ -167,6 +335,7 @@ impl VerifierSM {
             VerifierState::Finished(ref status) => {
                 match status.status {
                     Status::Success => VcxStateType::VcxStateAccepted as u32,
+                    Status::Expired(_) => VcxStateType::VcxStateExpired as u32,
                     _ => VcxStateType::VcxStateNone as u32,
                 }
             }
@@ -221,18 +390,93 @@ impl VerifierSM {
     pub fn presentation_request(&self) -> VcxResult<PresentationRequest> {
         match self.state {
             VerifierState::Initiated(ref state) => {
-                PresentationRequest::create().set_request_presentations_attach(&state.presentation_request_data)
+                state.attach_request(PresentationRequest::create(), &state.presentation_request_data)
             }
             VerifierState::PresentationRequestSent(ref state) => Ok(state.presentation_request.clone()),
             VerifierState::Finished(ref state) => Ok(state.presentation_request.clone()),
         }
     }
 
+    /// Data of the Indy proof request, to be revised by a superseding request.
+    pub fn presentation_request_data(&self) -> VcxResult<PresentationRequestData> {
+        self._ensure_indy_format("updating the request")?;
+        self.presentation_request()?.request_data()
+    }
+
+    fn _ensure_indy_format(&self, action: &str) -> VcxResult<()> {
+        if self.presentation_request()?.format() == PresentationFormat::PresentationExchange {
+            return Err(VcxError::from_msg(VcxErrorKind::ActionNotSupported, format!("Presentation Exchange does not support {}", action)));
+        }
+        Ok(())
+    }
+
     pub fn presentation(&self) -> VcxResult<Presentation> {
         match self.state {
             VerifierState::Finished(ref state) => {
                 state.presentation.clone()
-                    .ok_or(VcxError::from(VcxErrorKind::InvalidProofHandle))
+                    .ok_or_else(|| state.status.to_error(VcxErrorKind::InvalidProofHandle, "Presentation was not received"))
+            }
+            _ => Err(VcxError::from_msg(VcxErrorKind::NotReady, "Presentation is not received yet"))
+        }
+    }
+
+    pub fn verification_details(&self) -> VcxResult<VerificationDetails> {
+        self._ensure_indy_format("verification details")?;
+
+        match self.state {
+            VerifierState::Finished(ref state) => {
+                let presentation = state.presentation.as_ref()
+                    .ok_or(VcxError::from_msg(VcxErrorKind::NotReady, "Presentation was not verified"))?;
+
+                build_verification_details(&state.presentation_request.request_presentations_attach.content()?,
+                                           &presentation.presentations_attach.content()?,
+                                           self.presentation_status(),
+                                           state.revocation_status.clone())
+            }
+            _ => Err(VcxError::from_msg(VcxErrorKind::NotReady, "Presentation is not received yet"))
+        }
+    }
+
+    /// Repeats verification of the received presentation against ledger state at `as_of` without changing the state.
+    pub fn verify_presentation_as_of(&self, as_of: u64) -> VcxResult<u32> {
+        self._ensure_indy_format("verification as of a past time")?;
+
+        match self.state {
+            VerifierState::Finished(ref state) => {
+                let presentation = state.presentation.as_ref()
+                    .ok_or(VcxError::from_msg(VcxErrorKind::NotReady, "Presentation was not verified"))?;
+
+                let valid = match validate_indy_proof_as_of(&presentation.presentations_attach.content()?,
+                                                            &state.presentation_request.request_presentations_attach.content()?,
+                                                            as_of) {
+                    Ok(valid) => valid,
+                    Err(ref err) if err.kind() == VcxErrorKind::InvalidProof => false,
+                    Err(err) => return Err(err)
+                };
+
+                if valid {
+                    Ok(Status::Success.code())
+                } else {
+                    let problem_report = ProblemReport::create().set_comment(format!("Presentation is not valid as of {}", as_of));
+                    Ok(Status::Failed(problem_report).code())
+                }
+            }
+            _ => Err(VcxError::from_msg(VcxErrorKind::NotReady, "Presentation is not received yet"))
+        }
+    }
+
+    /// Checks revocation registry states used in the received presentation against the request and the ledger at `now`.
+    pub fn revocation_checks(&self, now: u64) -> VcxResult<Vec<RevocationCheck>> {
+        self._ensure_indy_format("revocation checks")?;
+
+        match self.state {
+            VerifierState::Finished(ref state) => {
+                let presentation = state.presentation.as_ref()
+                    .ok_or(VcxError::from_msg(VcxErrorKind::NotReady, "Presentation was not verified"))?;
+
+                get_revocation_checks(&presentation.presentations_attach.content()?,
+                                      &state.presentation_request.request_presentations_attach.content()?,
+                                      now)
             }
             _ => Err(VcxError::from_msg(VcxErrorKind::NotReady, "Presentation is not received yet"))
         }
@@ -246,7 +490,7 @@ pub mod test {
     use aries::messages::proof_presentation::presentation::tests::_presentation;
     use aries::messages::proof_presentation::presentation_proposal::tests::_presentation_proposal;
     use aries::messages::proof_presentation::presentation_request::tests::_presentation_request;
-    use aries::messages::proof_presentation::presentation_request::tests::_presentation_request_data;
+    use aries::messages::proof_presentation::presentation_request::tests::{_presentation_request_data, thread_id};
     use aries::messages::proof_presentation::test::{_ack, _problem_report};
     use aries::test::source_id;
 
@@ -356,6 +600,30 @@ pub mod test {
             assert_eq!(Status::Failed(ProblemReport::create()).code(), verifier_sm.presentation_status());
         }
 
+        #[test]
+        #[cfg(feature = "general_test")]
+        fn test_verifier_expires_superseding_request() {
+            let _setup = SetupAriesMocks::init();
+            let _mock_builder = MockBuilder::init().
+                set_mock_result_for_validate_indy_proof(Ok(true));
+
+            let mut verifier_sm = _verifier_sm();
+            verifier_sm.set_expiration(1).unwrap();
+            verifier_sm = verifier_sm.to_presentation_request_sent_state();
+            verifier_sm = verifier_sm.step(VerifierMessages::UpdatePresentationRequest(_presentation_request_data())).unwrap();
+            assert_eq!(thread_id(), verifier_sm.thread_id());
+
+            ::std::thread::sleep(::std::time::Duration::from_millis(2000));
+
+            // presentation answering both requests comes after the revised one expired, it's not answered as late
+            let presentation = A2AMessage::Presentation(_presentation());
+            verifier_sm = verifier_sm.receive_message(presentation.clone()).unwrap();
+
+            assert_match!(VerifierState::Finished(_), verifier_sm.state);
+            assert_eq!(VcxStateType::VcxStateExpired as u32, verifier_sm.state());
+            assert!(verifier_sm.is_processed(&presentation));
+        }
+
         //    #[test]
         //    fn test_prover_handle_verify_presentation_message_from_presentation_request_sent_state_for_invalid_presentation() {
         //        let _setup = Setup::init();
@@ -1,14 +1,51 @@
+use log::Level;
+
 use aries::handlers::proof_presentation::verifier::states::presentation_request_sent::PresentationRequestSentState;
+use error::prelude::*;
+use aries::messages::proof_presentation::presentation_exchange::{PresentationDefinition, PresentationExchangeRequest};
 use aries::messages::proof_presentation::presentation_request::{PresentationRequest, PresentationRequestData};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct InitialState {
-    pub presentation_request_data: PresentationRequestData
+    pub presentation_request_data: PresentationRequestData,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_in_secs: Option<u64>,
+    // DIF Presentation Exchange is requested instead of Indy proof, the nonce of the request data is its challenge
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presentation_definition: Option<PresentationDefinition>,
+}
+
+impl InitialState {
+    pub fn attach_request(&self, presentation_request: PresentationRequest, presentation_request_data: &PresentationRequestData) -> VcxResult<PresentationRequest> {
+        attach_request(self.presentation_definition.as_ref(), presentation_request, presentation_request_data)
+    }
+}
+
+/// Attaches DIF Presentation Exchange request if `presentation_definition` is set, Indy proof request otherwise.
+pub fn attach_request(presentation_definition: Option<&PresentationDefinition>,
+                      presentation_request: PresentationRequest,
+                      presentation_request_data: &PresentationRequestData) -> VcxResult<PresentationRequest> {
+    match presentation_definition {
+        Some(presentation_definition) => {
+            let request = PresentationExchangeRequest::create(presentation_definition.clone(), &presentation_request_data.nonce);
+            presentation_request.set_presentation_exchange_request(&request)
+        }
+        None => presentation_request.set_request_presentations_attach(presentation_request_data)
+    }
 }
 
 impl From<(InitialState, PresentationRequest, u32)> for PresentationRequestSentState {
-    fn from((_state, presentation_request, connection_handle): (InitialState, PresentationRequest, u32)) -> Self {
-        trace!("transit state from InitialState to PresentationRequestSentState");
-        PresentationRequestSentState { connection_handle, presentation_request }
+    fn from((state, presentation_request, connection_handle): (InitialState, PresentationRequest, u32)) -> Self {
+        log_event!(Level::Trace, "state_transition", from = "InitialState", to = "PresentationRequestSentState");
+        PresentationRequestSentState {
+            connection_handle,
+            presentation_request,
+            expires_in_secs: state.expires_in_secs,
+            presentation_definition: state.presentation_definition,
+            superseded_requests: Vec::new(),
+            late_presentations: Vec::new(),
+        }
     }
 }
@@ -1,44 +1,123 @@
+use log::Level;
+
 use connection;
-use error::{VcxError, VcxErrorKind, VcxResult};
+use error::prelude::*;
 use aries::handlers::proof_presentation::verifier::states::finished::FinishedState;
+use aries::handlers::proof_presentation::verifier::states::initial::attach_request;
 use aries::handlers::proof_presentation::verifier::state_machine::RevocationStatus;
 use aries::messages::a2a::A2AMessage;
 use aries::messages::error::ProblemReport;
 use aries::messages::proof_presentation::presentation::Presentation;
 use aries::messages::proof_presentation::presentation_ack::PresentationAck;
-use aries::messages::proof_presentation::presentation_request::PresentationRequest;
+use aries::messages::proof_presentation::presentation_exchange::PresentationDefinition;
+use aries::messages::proof_presentation::presentation_request::{PresentationRequest, PresentationRequestData};
 use aries::messages::status::Status;
-use proof_utils::validate_indy_proof;
+use proof_utils::{validate_presentation, validate_presentation_content};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct PresentationRequestSentState {
     pub connection_handle: u32,
     pub presentation_request: PresentationRequest,
+    // validity of the request, superseding requests are valid for the same time
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_in_secs: Option<u64>,
+    // superseding requests ask for the same DIF Presentation Exchange submission, with a new challenge
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presentation_definition: Option<PresentationDefinition>,
+    // requests replaced by revised ones on the same thread
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub superseded_requests: Vec<PresentationRequest>,
+    // ids of presentations answering superseded requests, the prover was informed by problem report
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub late_presentations: Vec<String>,
 }
 
 
 impl PresentationRequestSentState {
-    pub fn verify_presentation(&self, presentation: &Presentation) -> VcxResult<()> {
-        let valid = validate_indy_proof(&presentation.presentations_attach.content()?,
-                                               &self.presentation_request.request_presentations_attach.content()?)?;
+    pub fn attach_request(&self, presentation_request: PresentationRequest, presentation_request_data: &PresentationRequestData) -> VcxResult<PresentationRequest> {
+        attach_request(self.presentation_definition.as_ref(), presentation_request, presentation_request_data)
+    }
 
-        if !valid {
-            return Err(VcxError::from_msg(VcxErrorKind::InvalidProof, "Presentation verification failed"));
-        }
+    pub fn verify_presentation(&self, presentation: &Presentation) -> VcxResult<()> {
+        validate_presentation(&self.presentation_request, presentation)?;
 
         if presentation.please_ack.is_some() {
-            let ack = PresentationAck::create().set_thread_id(&self.presentation_request.id.0);
-            connection::send_message(self.connection_handle, A2AMessage::PresentationAck(ack))?;
+            let ack = PresentationAck::create().set_thread_id(&self.presentation_request.thread_id());
+            self.send_message(A2AMessage::PresentationAck(ack))?;
         }
 
         Ok(())
     }
+
+    /// Presentation which does not answer the pending request but one of the requests it superseded.
+    pub fn answers_superseded_request(&self, presentation: &Presentation) -> bool {
+        self.superseded_requests.iter()
+            .any(|request| validate_presentation_content(request, presentation).is_ok())
+    }
+
+    /// Rejects presentation answering superseded request, the prover is expected to answer the pending one.
+    pub fn reject_late_presentation(mut self, presentation: &Presentation) -> VcxResult<PresentationRequestSentState> {
+        let problem_report =
+            ProblemReport::create()
+                .set_comment(String::from("Presentation answers superseded request, the revised request is to be answered"))
+                .set_thread_id(&self.presentation_request.thread_id());
+
+        self.send_message(problem_report.to_a2a_message())?;
+        self.late_presentations.push(presentation.id.0.clone());
+        Ok(self)
+    }
+
+    /// Request passed out-of-band has no prover to be sent to, expired request is not sent again.
+    pub fn ensure_resendable(&self) -> VcxResult<()> {
+        if self.presentation_request.service.is_some() {
+            return Err(VcxError::from_msg(VcxErrorKind::NotReady, "Presentation request was passed out-of-band"));
+        }
+        if self.is_expired() {
+            return Err(VcxError::from_msg(VcxErrorKind::ExpiredMessage, "Presentation request is expired"));
+        }
+        Ok(())
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.presentation_request.check_answerable().is_err()
+    }
+
+    /// Problem report the prover is informed by when the request expires.
+    pub fn expiration_report(&self) -> ProblemReport {
+        let expires_time = self.presentation_request.timing.as_ref()
+            .and_then(|timing| timing.expires_time.clone())
+            .unwrap_or_default();
+
+        ProblemReport::create()
+            .set_comment(format!("Presentation request expired at {}", expires_time))
+            .set_thread_id(&self.presentation_request.thread_id())
+    }
+
+    pub fn expire(self, problem_report: ProblemReport) -> FinishedState {
+        let mut state: FinishedState = (self, problem_report.clone()).into();
+        state.status = Status::Expired(problem_report);
+        state
+    }
+
+    /// Prover answering request passed out-of-band has no connection to be answered through, so nothing is sent.
+    pub fn send_message(&self, message: A2AMessage) -> VcxResult<()> {
+        if self.presentation_request.service.is_some() {
+            log_event!(Level::Debug, "PresentationRequestSentState::send_message", note = "request was passed out-of-band, skipping message", message_type = message.type_name());
+            return Ok(());
+        }
+
+        connection::send_message(self.connection_handle, message)
+    }
 }
 
 
 impl From<(PresentationRequestSentState, Presentation, RevocationStatus)> for FinishedState {
     fn from((state, presentation, was_revoked): (PresentationRequestSentState, Presentation, RevocationStatus)) -> Self {
-        trace!("transit state from PresentationRequestSentState to FinishedState");
+        log_event!(Level::Trace, "state_transition", from = "PresentationRequestSentState", to = "FinishedState");
         FinishedState {
             connection_handle: state.connection_handle,
             presentation_request: state.presentation_request,
@@ -51,7 +130,7 @@ impl From<(PresentationRequestSentState, Presentation, RevocationStatus)> for Fi
 
 impl From<(PresentationRequestSentState, ProblemReport)> for FinishedState {
     fn from((state, problem_report): (PresentationRequestSentState, ProblemReport)) -> Self {
-        trace!("transit state from PresentationRequestSentState to FinishedState");
+        log_event!(Level::Trace, "state_transition", from = "PresentationRequestSentState", to = "FinishedState");
         FinishedState {
             connection_handle: state.connection_handle,
             presentation_request: state.presentation_request,
@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use error::prelude::*;
+use messages::proofs::proof_request::ProofRequestData;
+use aries::handlers::proof_presentation::verifier::state_machine::RevocationStatus;
+
+/// Outcome of presentation verification broken down per requested attribute and predicate.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VerificationDetails {
+    pub presentation_status: u32,
+    pub revocation_status: Option<RevocationStatus>,
+    pub revealed_attrs: BTreeMap<String, RevealedAttributeDetails>,
+    pub revealed_attr_groups: BTreeMap<String, RevealedAttributeGroupDetails>,
+    pub self_attested_attrs: BTreeMap<String, String>,
+    pub unrevealed_attrs: BTreeMap<String, CredentialIdentifier>,
+    pub predicates: BTreeMap<String, PredicateDetails>,
+}
+
+/// Ledger objects the credential used for an attribute was issued against.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CredentialIdentifier {
+    pub schema_id: String,
+    pub cred_def_id: String,
+    pub rev_reg_id: Option<String>,
+    pub timestamp: Option<u64>,
+    pub revocation_checked: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RevealedAttributeDetails {
+    pub name: Option<String>,
+    pub raw: String,
+    pub encoded: String,
+    pub identifier: CredentialIdentifier,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RevealedAttributeGroupDetails {
+    pub values: BTreeMap<String, String>,
+    pub identifier: CredentialIdentifier,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PredicateDetails {
+    pub name: Option<String>,
+    pub p_type: Option<String>,
+    pub p_value: Option<i32>,
+    pub identifier: CredentialIdentifier,
+}
+
+pub fn build_verification_details(proof_req_json: &str,
+                                  proof_json: &str,
+                                  presentation_status: u32,
+                                  revocation_status: Option<RevocationStatus>) -> VcxResult<VerificationDetails> {
+    let proof_req: ProofRequestData = ::serde_json::from_str(proof_req_json)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize proof request: {}", err)))?;
+
+    let proof: Value = ::serde_json::from_str(proof_json)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize libindy proof: {}", err)))?;
+
+    let identifiers = _identifiers(&proof)?;
+    let requested_proof = &proof["requested_proof"];
+
+    let mut details = VerificationDetails {
+        presentation_status,
+        revocation_status,
+        revealed_attrs: BTreeMap::new(),
+        revealed_attr_groups: BTreeMap::new(),
+        self_attested_attrs: BTreeMap::new(),
+        unrevealed_attrs: BTreeMap::new(),
+        predicates: BTreeMap::new(),
+    };
+
+    for (referent, attr) in _entries(&requested_proof["revealed_attrs"]) {
+        details.revealed_attrs.insert(referent.to_string(), RevealedAttributeDetails {
+            name: proof_req.requested_attributes.get(referent).and_then(|attr| attr.name.clone()),
+            raw: attr["raw"].as_str().unwrap_or_default().to_string(),
+            encoded: attr["encoded"].as_str().unwrap_or_default().to_string(),
+            identifier: _identifier_for(referent, attr, &identifiers)?,
+        });
+    }
+
+    for (referent, group) in _entries(&requested_proof["revealed_attr_groups"]) {
+        let values = _entries(&group["values"])
+            .map(|(name, value)| (name.to_string(), value["raw"].as_str().unwrap_or_default().to_string()))
+            .collect();
+        details.revealed_attr_groups.insert(referent.to_string(), RevealedAttributeGroupDetails {
+            values,
+            identifier: _identifier_for(referent, group, &identifiers)?,
+        });
+    }
+
+    for (referent, value) in _entries(&requested_proof["self_attested_attrs"]) {
+        details.self_attested_attrs.insert(referent.to_string(), value.as_str().unwrap_or_default().to_string());
+    }
+
+    for (referent, attr) in _entries(&requested_proof["unrevealed_attrs"]) {
+        details.unrevealed_attrs.insert(referent.to_string(), _identifier_for(referent, attr, &identifiers)?);
+    }
+
+    for (referent, predicate) in _entries(&requested_proof["predicates"]) {
+        let requested = proof_req.requested_predicates.get(referent);
+        details.predicates.insert(referent.to_string(), PredicateDetails {
+            name: requested.map(|predicate| predicate.name.clone()),
+            p_type: requested.map(|predicate| predicate.p_type.clone()),
+            p_value: requested.map(|predicate| predicate.p_value),
+            identifier: _identifier_for(referent, predicate, &identifiers)?,
+        });
+    }
+
+    Ok(details)
+}
+
+fn _entries<'a>(value: &'a Value) -> Box<dyn Iterator<Item=(&'a String, &'a Value)> + 'a> {
+    match value.as_object() {
+        Some(map) => Box::new(map.iter()),
+        None => Box::new(::std::iter::empty())
+    }
+}
+
+fn _identifiers(proof: &Value) -> VcxResult<Vec<CredentialIdentifier>> {
+    proof["identifiers"].as_array()
+        .map(|identifiers| identifiers.iter()
+            .map(|identifier| {
+                match (identifier["schema_id"].as_str(), identifier["cred_def_id"].as_str()) {
+                    (Some(schema_id), Some(cred_def_id)) => {
+                        let rev_reg_id = identifier["rev_reg_id"].as_str().map(String::from);
+                        let timestamp = identifier["timestamp"].as_u64();
+                        Ok(CredentialIdentifier {
+                            schema_id: schema_id.to_string(),
+                            cred_def_id: cred_def_id.to_string(),
+                            revocation_checked: rev_reg_id.is_some() && timestamp.is_some(),
+                            rev_reg_id,
+                            timestamp,
+                        })
+                    }
+                    _ => Err(VcxError::from_msg(VcxErrorKind::InvalidProofCredentialData, "Cannot get identifiers"))
+                }
+            })
+            .collect())
+        .unwrap_or(Ok(Vec::new()))
+}
+
+fn _identifier_for(referent: &str, value: &Value, identifiers: &[CredentialIdentifier]) -> VcxResult<CredentialIdentifier> {
+    value["sub_proof_index"].as_u64()
+        .and_then(|index| identifiers.get(index as usize))
+        .cloned()
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidProofCredentialData,
+                                  format!("Cannot find credential identifier for `{}`", referent)))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use utils::constants::{CRED_DEF_ID, REV_REG_ID, SCHEMA_ID};
+
+    use super::*;
+
+    fn _proof_req() -> String {
+        json!({
+            "nonce": "123432421212",
+            "name": "proof_req_1",
+            "version": "0.1",
+            "requested_attributes": {
+                "name_1": {"name": "name"},
+                "address_2": {"names": ["street", "city"]},
+                "nickname_3": {"name": "nickname"}
+            },
+            "requested_predicates": {
+                "age_4": {"name": "age", "p_type": ">=", "p_value": 18}
+            }
+        }).to_string()
+    }
+
+    fn _proof() -> String {
+        json!({
+            "proof": {},
+            "requested_proof": {
+                "revealed_attrs": {"name_1": {"sub_proof_index": 0, "raw": "alice", "encoded": "123"}},
+                "revealed_attr_groups": {"address_2": {"sub_proof_index": 1, "values": {"street": {"raw": "Main", "encoded": "1"}, "city": {"raw": "Prague", "encoded": "2"}}}},
+                "self_attested_attrs": {"nickname_3": "al"},
+                "unrevealed_attrs": {},
+                "predicates": {"age_4": {"sub_proof_index": 0}}
+            },
+            "identifiers": [
+                {"schema_id": SCHEMA_ID, "cred_def_id": CRED_DEF_ID, "rev_reg_id": REV_REG_ID, "timestamp": 1000},
+                {"schema_id": SCHEMA_ID, "cred_def_id": CRED_DEF_ID, "rev_reg_id": null, "timestamp": null}
+            ]
+        }).to_string()
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_build_verification_details() {
+        let details = build_verification_details(&_proof_req(), &_proof(), 1, Some(RevocationStatus::NonRevoked)).unwrap();
+
+        let name = &details.revealed_attrs["name_1"];
+        assert_eq!(name.name, Some("name".to_string()));
+        assert_eq!(name.raw, "alice");
+        assert_eq!(name.identifier.rev_reg_id, Some(REV_REG_ID.to_string()));
+        assert_eq!(name.identifier.timestamp, Some(1000));
+        assert!(name.identifier.revocation_checked);
+
+        let address = &details.revealed_attr_groups["address_2"];
+        assert_eq!(address.values["city"], "Prague");
+        assert!(!address.identifier.revocation_checked);
+
+        assert_eq!(details.self_attested_attrs["nickname_3"], "al");
+
+        let age = &details.predicates["age_4"];
+        assert_eq!(age.p_type, Some(">=".to_string()));
+        assert_eq!(age.identifier.cred_def_id, CRED_DEF_ID);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_build_verification_details_fails_for_unknown_sub_proof() {
+        let proof = json!({
+            "requested_proof": {"revealed_attrs": {"name_1": {"sub_proof_index": 3, "raw": "alice", "encoded": "123"}}},
+            "identifiers": []
+        }).to_string();
+
+        let err = build_verification_details(&_proof_req(), &proof, 1, None).unwrap_err();
+        assert_eq!(err.kind(), VcxErrorKind::InvalidProofCredentialData);
+    }
+}
@@ -1,14 +1,33 @@
+use log::Level;
+
 use ::{connection};
 use error::prelude::*;
 use aries::handlers::proof_presentation::verifier::messages::VerifierMessages;
 use aries::handlers::proof_presentation::verifier::state_machine::VerifierSM;
 use aries::messages::a2a::A2AMessage;
+use aries::messages::out_of_band::invitation::OutOfBandInvitation;
+use aries::messages::proof_presentation::format::PresentationFormat;
 use aries::messages::proof_presentation::presentation::Presentation;
+use aries::messages::proof_presentation::presentation_exchange::PresentationDefinition;
 use aries::messages::proof_presentation::presentation_request::*;
+use utils::metadata::{self, Metadata};
+use utils::structured_log::{self, Correlation};
+
+/// Parts of the pending request replaced by a superseding request, the parts not set are kept.
+#[derive(Deserialize, Debug, Default)]
+pub struct PresentationRequestUpdate {
+    pub name: Option<String>,
+    pub requested_attributes: Option<::serde_json::Value>,
+    pub requested_predicates: Option<::serde_json::Value>,
+    pub revocation_interval: Option<::serde_json::Value>,
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Verifier {
-    verifier_sm: VerifierSM
+    verifier_sm: VerifierSM,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Metadata::is_empty")]
+    metadata: Metadata,
 }
 
 impl Verifier {
@@ -17,8 +36,7 @@ impl Verifier {
                   requested_predicates: String,
                   revocation_details: String,
                   name: String) -> VcxResult<Verifier> {
-        trace!("Verifier::create >>> source_id: {:?}, requested_attrs: {:?}, requested_predicates: {:?}, revocation_details: {:?}, name: {:?}",
-               source_id, requested_attrs, requested_predicates, revocation_details, name);
+        log_event!(Level::Trace, "Verifier::create", source_id = source_id);
 
         let presentation_request =
             PresentationRequestData::create()
@@ -26,75 +44,228 @@ impl Verifier {
                 .set_requested_attributes(requested_attrs)?
                 .set_requested_predicates(requested_predicates)?
                 .set_not_revoked_interval(revocation_details)?
+                .propagate_not_revoked_interval()
                 .set_nonce()?;
 
+        presentation_request.validate_not_revoked_intervals(::time::get_time().sec as u64)?;
+
         Ok(Verifier {
             verifier_sm: VerifierSM::new(presentation_request, source_id),
+            metadata: Metadata::new(),
+        })
+    }
+
+    /// Verifier requesting DIF Presentation Exchange submission of W3C credentials instead of Indy proof.
+    pub fn create_with_presentation_definition(source_id: String, presentation_definition: String, name: String) -> VcxResult<Verifier> {
+        log_event!(Level::Trace, "Verifier::create_with_presentation_definition", source_id = source_id);
+
+        let presentation_definition = PresentationDefinition::from_str(&presentation_definition)?;
+
+        let presentation_request =
+            PresentationRequestData::create()
+                .set_name(name)
+                .set_nonce()?;
+
+        Ok(Verifier {
+            verifier_sm: VerifierSM::new_presentation_exchange(presentation_request, presentation_definition, source_id),
+            metadata: Metadata::new(),
         })
     }
 
     pub fn get_source_id(&self) -> String { self.verifier_sm.source_id() }
 
+    pub fn get_thread_id(&self) -> String { self.verifier_sm.thread_id() }
+
+    pub fn get_connection_handle(&self) -> Option<u32> { self.verifier_sm.connection_handle().ok() }
+
+    pub fn get_metadata(&self) -> &Metadata { &self.metadata }
+
+    pub fn set_metadata(&mut self, key: &str, value: Option<&str>) -> VcxResult<()> {
+        metadata::set(&mut self.metadata, key, value)
+    }
+
+    /// Time the sent presentation request expires at, in RFC 3339 format.
+    pub fn get_expiry(&self) -> Option<String> {
+        self.verifier_sm.get_expiry()
+    }
+
+    /// JSON array of messages sent and received within the exchange, empty unless `protocol_history` option is enabled.
+    pub fn get_protocol_history(&self) -> VcxResult<String> {
+        self.verifier_sm.history().to_string()
+    }
+
     pub fn state(&self) -> u32 {
-        trace!("Verifier::state >>>");
+        log_event!(Level::Trace, "Verifier::state");
         self.verifier_sm.state()
     }
 
     pub fn presentation_status(&self) -> u32 {
-        trace!("Verifier::presentation_state >>>");
+        log_event!(Level::Trace, "Verifier::presentation_state");
         self.verifier_sm.presentation_status()
     }
 
     pub fn update_state(&mut self, message: Option<&str>, connection_handle: Option<u32>) -> VcxResult<()> {
-        trace!("Verifier::update_state >>> message: {:?}", message);
+        log_event!(Level::Trace, "Verifier::update_state");
 
         if !self.verifier_sm.has_transitions() { return Ok(()); }
 
         let connection_handle = connection_handle.unwrap_or(self.verifier_sm.connection_handle()?);
         self.verifier_sm.set_connection_handle(connection_handle);
+        let _correlation = structured_log::enter(Correlation::new().connection_handle(Some(connection_handle)));
 
         if let Some(message_) = message {
-            return self.update_state_with_message(message_);
+            let prev_state = self.state();
+            self.update_state_with_message(message_)?;
+
+            // message of a thread no object knows yet is kept for the object of the thread
+            if self.state() == prev_state && self.verifier_sm.has_transitions() {
+                if let Ok(a2a_message) = ::serde_json::from_str::<A2AMessage>(message_) {
+                    if self.verifier_sm.is_processed(&a2a_message) { return Ok(()); }
+
+                    connection::queue_message(connection_handle, a2a_message)
+                        .unwrap_or_else(|err| warn!("Verifier::update_state >>> cannot queue message: {}", err));
+                }
+            }
+        } else {
+            let messages = self.verifier_sm.processed_messages().filter_new(connection_handle, connection::get_thread_messages(connection_handle, &self.verifier_sm.thread_id())?);
+
+            if let Some((uid, message)) = self.verifier_sm.find_message_to_handle(messages) {
+                let _correlation = structured_log::enter(Correlation::new().message(&message));
+                log_event!(Level::Debug, "message_received", source_id = self.get_source_id(), state = self.state(), uid = uid);
+                self.verifier_sm = self.verifier_sm.clone().receive_message(message)?;
+                connection::update_message_status(connection_handle, uid)?;
+            };
         }
 
-        let messages = connection::get_messages(connection_handle)?;
-
-        if let Some((uid, message)) = self.verifier_sm.find_message_to_handle(messages) {
-            self.handle_message(message.into())?;
-            connection::update_message_status(connection_handle, uid)?;
-        };
-
+        // presentation received before the request expired is handled above
+        if self.verifier_sm.is_expired() {
+            self.step(VerifierMessages::CheckExpiration)?;
+        }
         Ok(())
     }
 
     pub fn update_state_with_message(&mut self, message: &str) -> VcxResult<()> {
-        trace!("Verifier::update_state_with_message >>> message: {:?}", message);
-
         let message: A2AMessage = ::serde_json::from_str(&message)
             .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Cannot update state with message: Message deserialization failed: {:?}", err)))?;
 
-        self.handle_message(message.into())?;
+        let _correlation = structured_log::enter(Correlation::new().message(&message));
+        log_event!(Level::Debug, "message_received", source_id = self.get_source_id(), state = self.state());
+
+        if self.verifier_sm.is_processed(&message) {
+            log_event!(Level::Debug, "Verifier::update_state_with_message", note = "message was already processed");
+            return Ok(());
+        }
+
+        self.verifier_sm = self.verifier_sm.clone().receive_message(message)?;
 
         Ok(())
     }
 
     pub fn handle_message(&mut self, message: VerifierMessages) -> VcxResult<()> {
-        trace!("Verifier::handle_message >>> message: {:?}", message);
+        log_event!(Level::Trace, "Verifier::handle_message");
         self.step(message)
     }
 
     pub fn verify_presentation(&mut self, presentation: Presentation) -> VcxResult<()> {
-        trace!("Verifier::verify_presentation >>> presentation: {:?}", presentation);
+        log_event!(Level::Trace, "Verifier::verify_presentation");
         self.step(VerifierMessages::VerifyPresentation(presentation))
     }
 
+    pub fn set_expiration(&mut self, expires_in_secs: u64) -> VcxResult<()> {
+        log_event!(Level::Trace, "Verifier::set_expiration", expires_in_secs = expires_in_secs);
+        self.verifier_sm.set_expiration(expires_in_secs)
+    }
+
     pub fn send_presentation_request(&mut self, connection_handle: u32) -> VcxResult<()> {
-        trace!("Verifier::send_presentation_request >>> connection_handle: {:?}", connection_handle);
+        log_event!(Level::Trace, "Verifier::send_presentation_request", connection_handle = connection_handle);
         self.step(VerifierMessages::SendPresentationRequest(connection_handle))
     }
 
+    /// Sends the pending request again, `connection_handle` has to be the connection the request was sent over.
+    pub fn resend_request(&mut self, connection_handle: u32) -> VcxResult<()> {
+        log_event!(Level::Trace, "Verifier::resend_request", connection_handle = connection_handle);
+
+        if !self.verifier_sm.has_transitions() {
+            return Err(VcxError::from_msg(VcxErrorKind::NotReady, "There is no pending presentation request"));
+        }
+        self.step(VerifierMessages::ResendPresentationRequest(connection_handle))
+    }
+
+    /// Replaces the pending request by a revised one on the same thread. Presentations answering the former request
+    /// are refused by problem report, the verifier keeps waiting for the presentation answering the revised one.
+    pub fn update_request(&mut self, request_update: &str) -> VcxResult<()> {
+        log_event!(Level::Trace, "Verifier::update_request");
+
+        if !self.verifier_sm.has_transitions() {
+            return Err(VcxError::from_msg(VcxErrorKind::NotReady, "There is no pending presentation request"));
+        }
+
+        let request_update: PresentationRequestUpdate = ::serde_json::from_str(request_update)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize presentation request update: {}", err)))?;
+
+        if self.verifier_sm.presentation_request()?.format() == PresentationFormat::PresentationExchange {
+            return self._update_presentation_exchange_request(request_update);
+        }
+
+        let mut presentation_request = self.verifier_sm.presentation_request_data()?;
+        if let Some(name) = request_update.name {
+            presentation_request = presentation_request.set_name(name);
+        }
+        if let Some(requested_attributes) = request_update.requested_attributes {
+            presentation_request = presentation_request.set_requested_attributes(requested_attributes.to_string())?;
+        }
+        if let Some(requested_predicates) = request_update.requested_predicates {
+            presentation_request = presentation_request.set_requested_predicates(requested_predicates.to_string())?;
+        }
+        if let Some(revocation_interval) = request_update.revocation_interval {
+            presentation_request = presentation_request.set_not_revoked_interval(revocation_interval.to_string())?;
+        }
+
+        let presentation_request = presentation_request
+            .propagate_not_revoked_interval()
+            .set_nonce()?;
+        presentation_request.validate_not_revoked_intervals(::time::get_time().sec as u64)?;
+
+        self.step(VerifierMessages::UpdatePresentationRequest(presentation_request))
+    }
+
+    // the presentation definition is kept, the superseding request asks for a new challenge to be signed
+    fn _update_presentation_exchange_request(&mut self, request_update: PresentationRequestUpdate) -> VcxResult<()> {
+        if request_update.requested_attributes.is_some() || request_update.requested_predicates.is_some() || request_update.revocation_interval.is_some() {
+            return Err(VcxError::from_msg(VcxErrorKind::ActionNotSupported, "Presentation Exchange request can be revised only by its name"));
+        }
+
+        let presentation_request =
+            PresentationRequestData::create()
+                .set_name(request_update.name.unwrap_or_default())
+                .set_nonce()?;
+
+        self.step(VerifierMessages::UpdatePresentationRequest(presentation_request))
+    }
+
+    /// Prepares the request to be passed out-of-band, the presentation is answered to the service of `connection_handle`.
+    pub fn prepare_out_of_band_request(&mut self, connection_handle: u32) -> VcxResult<()> {
+        log_event!(Level::Trace, "Verifier::prepare_out_of_band_request", connection_handle = connection_handle);
+        self.step(VerifierMessages::PrepareOutOfBandRequest(connection_handle))
+    }
+
+    pub fn get_out_of_band_request(&self) -> VcxResult<OutOfBandInvitation> {
+        log_event!(Level::Trace, "Verifier::get_out_of_band_request");
+
+        let presentation_request = self.verifier_sm.presentation_request()?;
+        let service = presentation_request.service.clone()
+            .ok_or(VcxError::from_msg(VcxErrorKind::NotReady, "Presentation request is not prepared to be passed out-of-band"))?;
+
+        let invitation = OutOfBandInvitation::create()
+            .set_label(::settings::get_config_value(::settings::CONFIG_INSTITUTION_NAME).ok())
+            .set_request(presentation_request.to_a2a_message())?
+            .set_service(service);
+
+        Ok(invitation)
+    }
+
     pub fn generate_presentation_request_msg(&self) -> VcxResult<String> {
-        trace!("Verifier::generate_presentation_request_msg >>>");
+        log_event!(Level::Trace, "Verifier::generate_presentation_request_msg");
 
         let proof_request = self.verifier_sm.presentation_request()?;
 
@@ -102,12 +273,34 @@ impl Verifier {
     }
 
     pub fn get_presentation(&self) -> VcxResult<String> {
-        trace!("Verifier::get_presentation >>>");
+        log_event!(Level::Trace, "Verifier::get_presentation");
 
         let proof = self.verifier_sm.presentation()?.to_a2a_message();
         Ok(json!(proof).to_string())
     }
 
+    pub fn get_verification_details(&self) -> VcxResult<String> {
+        log_event!(Level::Trace, "Verifier::get_verification_details");
+
+        let details = self.verifier_sm.verification_details()?;
+        ::serde_json::to_string(&details)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize verification details: {}", err)))
+    }
+
+    pub fn verify_presentation_as_of(&self, as_of: u64) -> VcxResult<u32> {
+        log_event!(Level::Trace, "Verifier::verify_presentation_as_of", as_of = as_of);
+
+        self.verifier_sm.verify_presentation_as_of(as_of)
+    }
+
+    pub fn verify_revocation(&self) -> VcxResult<String> {
+        log_event!(Level::Trace, "Verifier::verify_revocation");
+
+        let checks = self.verifier_sm.revocation_checks(::time::get_time().sec as u64)?;
+        ::serde_json::to_string(&checks)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize revocation checks: {}", err)))
+    }
+
     pub fn step(&mut self, message: VerifierMessages) -> VcxResult<()> {
         self.verifier_sm = self.verifier_sm.clone().step(message)?;
         Ok(())
@@ -125,6 +318,13 @@ pub mod tests {
     use super::*;
     use utils::mockdata::mockdata_proof::ARIES_PROOF_PRESENTATION;
     use utils::mockdata::mock_settings::MockBuilder;
+    use aries::messages::connection::did_key;
+    use aries::messages::issuance::w3c::W3cCredential;
+    use aries::messages::issuance::w3c::tests::_credential_subject;
+    use aries::messages::proof_presentation::presentation_exchange::{PresentationExchangeRequest, VerifiablePresentation};
+    use aries::messages::proof_presentation::presentation_exchange::tests::_presentation_definition;
+    use aries::messages::status::Status;
+    use aries::messages::error::ProblemReport;
 
     #[test]
     #[cfg(feature = "general_test")]
@@ -150,6 +350,30 @@ pub mod tests {
         assert_eq!(proof.state(), VcxStateType::VcxStateAccepted as u32);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_redelivered_presentation_is_ignored() {
+        let _setup = SetupAriesMocks::init();
+        let _mock_builder = MockBuilder::init().
+            set_mock_result_for_validate_indy_proof(Ok(true));
+
+        let connection_handle = build_test_connection_inviter_requested();
+
+        let mut proof = Verifier::create("1".to_string(),
+                                         REQUESTED_ATTRS.to_owned(),
+                                         REQUESTED_PREDICATES.to_owned(),
+                                         r#"{"support_revocation":false}"#.to_string(),
+                                         "Optional".to_owned()).unwrap();
+
+        proof.send_presentation_request(connection_handle).unwrap();
+        proof.update_state_with_message(ARIES_PROOF_PRESENTATION).unwrap();
+        assert_eq!(proof.state(), VcxStateType::VcxStateAccepted as u32);
+
+        let presentation: A2AMessage = ::serde_json::from_str(ARIES_PROOF_PRESENTATION).unwrap();
+        let proof: Verifier = ::serde_json::from_str(&::serde_json::to_string(&proof).unwrap()).unwrap();
+        assert!(proof.verifier_sm.is_processed(&presentation));
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_send_presentation_request() {
@@ -168,6 +392,101 @@ pub mod tests {
         assert_eq!(proof.state(), VcxStateType::VcxStateOfferSent as u32);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_expired_presentation_request() {
+        let _setup = SetupAriesMocks::init();
+
+        let connection_handle = build_test_connection_inviter_requested();
+
+        let mut proof = Verifier::create("1".to_string(),
+                                         REQUESTED_ATTRS.to_owned(),
+                                         REQUESTED_PREDICATES.to_owned(),
+                                         r#"{"support_revocation":false}"#.to_string(),
+                                         "Optional".to_owned()).unwrap();
+        assert_eq!(proof.get_expiry(), None);
+
+        proof.set_expiration(0).unwrap();
+        proof.send_presentation_request(connection_handle).unwrap();
+        assert!(proof.get_expiry().is_some());
+        assert_eq!(proof.set_expiration(10).unwrap_err().kind(), VcxErrorKind::NotReady);
+
+        proof.update_state(None, Some(connection_handle)).unwrap();
+        assert_eq!(proof.state(), VcxStateType::VcxStateExpired as u32);
+        assert_eq!(proof.presentation_status(), Status::Expired(ProblemReport::default()).code());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_update_presentation_request() {
+        let _setup = SetupAriesMocks::init();
+
+        let connection_handle = build_test_connection_inviter_requested();
+
+        let mut proof = Verifier::create("1".to_string(),
+                                         REQUESTED_ATTRS.to_owned(),
+                                         REQUESTED_PREDICATES.to_owned(),
+                                         r#"{"support_revocation":false}"#.to_string(),
+                                         "Optional".to_owned()).unwrap();
+
+        assert_eq!(proof.update_request("{}").unwrap_err().kind(), VcxErrorKind::NotReady);
+
+        proof.send_presentation_request(connection_handle).unwrap();
+        let thread_id = proof.get_thread_id();
+        let request = proof.verifier_sm.presentation_request().unwrap();
+
+        proof.resend_request(connection_handle).unwrap();
+        assert_eq!(proof.verifier_sm.presentation_request().unwrap(), request);
+        assert_eq!(proof.resend_request(build_test_connection_inviter_requested()).unwrap_err().kind(), VcxErrorKind::InvalidConnectionHandle);
+
+        proof.update_request(r#"{"requested_attributes": [{"name": "age"}]}"#).unwrap();
+        assert_eq!(proof.state(), VcxStateType::VcxStateOfferSent as u32);
+        assert_eq!(proof.get_thread_id(), thread_id);
+
+        let updated_request = proof.verifier_sm.presentation_request().unwrap();
+        assert_eq!(request.thread, None);
+        assert_eq!(updated_request.thread.as_ref().and_then(|thread| thread.thid.clone()), Some(thread_id));
+
+        let updated_data = updated_request.request_data().unwrap();
+        let former_data = request.request_data().unwrap();
+        assert_eq!(updated_data.requested_attributes["attribute_0"].name, Some("age".to_string()));
+        assert_eq!(updated_data.requested_predicates, former_data.requested_predicates);
+        assert_ne!(updated_data.nonce, former_data.nonce);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_out_of_band_presentation_request() {
+        let _setup = SetupAriesMocks::init();
+        let _mock_builder = MockBuilder::init().
+            set_mock_result_for_validate_indy_proof(Ok(true));
+
+        let connection_handle = connection::create_connection("test_out_of_band_presentation_request").unwrap();
+        connection::connect(connection_handle).unwrap();
+
+        let mut proof = Verifier::create("1".to_string(),
+                                         REQUESTED_ATTRS.to_owned(),
+                                         REQUESTED_PREDICATES.to_owned(),
+                                         r#"{"support_revocation":false}"#.to_string(),
+                                         "Optional".to_owned()).unwrap();
+
+        assert_eq!(proof.get_out_of_band_request().unwrap_err().kind(), VcxErrorKind::NotReady);
+
+        proof.prepare_out_of_band_request(connection_handle).unwrap();
+        assert_eq!(proof.state(), VcxStateType::VcxStateOfferSent as u32);
+
+        let invitation = proof.get_out_of_band_request().unwrap();
+        assert_eq!(invitation.service(), Some(connection::get_service(connection_handle).unwrap()));
+        match invitation.request().unwrap() {
+            A2AMessage::PresentationRequest(request) => assert_eq!(request.id.0, proof.get_thread_id()),
+            message => panic!("unexpected message: {:?}", message)
+        }
+
+        // the prover has no connection, so the presentation is not acknowledged
+        proof.update_state_with_message(ARIES_PROOF_PRESENTATION).unwrap();
+        assert_eq!(proof.state(), VcxStateType::VcxStateAccepted as u32);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_update_state_with_reject_message() {
@@ -186,4 +505,102 @@ pub mod tests {
         proof.update_state(Some(PROOF_REJECT_RESPONSE_STR_V2), Some(connection_handle)).unwrap();
         assert_eq!(proof.state(), VcxStateType::VcxStateNone as u32);
     }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_presentation_exchange_verification() {
+        let _setup = SetupAriesMocks::init();
+
+        let connection_handle = build_test_connection_inviter_requested();
+
+        let mut proof = Verifier::create_with_presentation_definition("1".to_string(),
+                                                                      json!(_presentation_definition()).to_string(),
+                                                                      "Adult".to_owned()).unwrap();
+
+        proof.send_presentation_request(connection_handle).unwrap();
+        assert_eq!(proof.state(), VcxStateType::VcxStateOfferSent as u32);
+
+        let presentation_request: PresentationRequest = ::serde_json::from_str(&proof.generate_presentation_request_msg().unwrap()).unwrap();
+        assert_eq!(PresentationFormat::PresentationExchange, presentation_request.format());
+        let request = PresentationExchangeRequest::from_str(&presentation_request.request_presentations_attach.content().unwrap()).unwrap();
+
+        let holder = did_key::encode("CnEDk9HrMnmiHXEV1WFgbVCRteYnPqsJwrTdcZaNhFVW").unwrap();
+        let credential = W3cCredential::create("GJ1SzoWzavQYfNL9XkaJdrQejfztN4XqdsiV4ct3LXKL", _credential_subject()).unwrap()
+            .set_subject_id(&holder)
+            .sign().unwrap();
+        let verifiable_presentation = VerifiablePresentation::create(&request.presentation_definition.id, vec![(String::from("adult"), credential)]).unwrap()
+            .sign(&request.options).unwrap();
+
+        let presentation = Presentation::create()
+            .set_verifiable_presentation(&verifiable_presentation).unwrap()
+            .set_thread_id(&presentation_request.id.0);
+        proof.verify_presentation(presentation).unwrap();
+
+        assert_eq!(proof.state(), VcxStateType::VcxStateAccepted as u32);
+        assert_eq!(proof.presentation_status(), Status::Success.code());
+        assert_eq!(proof.get_verification_details().unwrap_err().kind(), VcxErrorKind::ActionNotSupported);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_presentation_exchange_update_request() {
+        let _setup = SetupAriesMocks::init();
+
+        let connection_handle = build_test_connection_inviter_requested();
+
+        let mut proof = Verifier::create_with_presentation_definition("1".to_string(),
+                                                                      json!(_presentation_definition()).to_string(),
+                                                                      "Adult".to_owned()).unwrap();
+        proof.send_presentation_request(connection_handle).unwrap();
+        let former_request: PresentationRequest = ::serde_json::from_str(&proof.generate_presentation_request_msg().unwrap()).unwrap();
+
+        assert_eq!(proof.update_request(r#"{"requested_attributes": [{"name": "age"}]}"#).unwrap_err().kind(), VcxErrorKind::ActionNotSupported);
+        proof.update_request("{}").unwrap();
+
+        let revised_request: PresentationRequest = ::serde_json::from_str(&proof.generate_presentation_request_msg().unwrap()).unwrap();
+        assert_eq!(PresentationFormat::PresentationExchange, revised_request.format());
+        assert_eq!(former_request.comment, revised_request.comment);
+        assert_eq!(former_request.thread_id(), revised_request.thread_id());
+
+        let holder = did_key::encode("CnEDk9HrMnmiHXEV1WFgbVCRteYnPqsJwrTdcZaNhFVW").unwrap();
+        let credential = W3cCredential::create("GJ1SzoWzavQYfNL9XkaJdrQejfztN4XqdsiV4ct3LXKL", _credential_subject()).unwrap()
+            .set_subject_id(&holder)
+            .sign().unwrap();
+        let presentation_for = |request: &PresentationRequest| {
+            let request = PresentationExchangeRequest::from_str(&request.request_presentations_attach.content().unwrap()).unwrap();
+            let verifiable_presentation = VerifiablePresentation::create(&request.presentation_definition.id, vec![(String::from("adult"), credential.clone())]).unwrap()
+                .sign(&request.options).unwrap();
+            let presentation = Presentation::create()
+                .set_verifiable_presentation(&verifiable_presentation).unwrap()
+                .set_thread_id(&former_request.thread_id());
+            json!(A2AMessage::Presentation(presentation)).to_string()
+        };
+
+        // presentation signed over the challenge of the former request is refused as late
+        proof.update_state_with_message(&presentation_for(&former_request)).unwrap();
+        assert_eq!(proof.state(), VcxStateType::VcxStateOfferSent as u32);
+
+        proof.update_state_with_message(&presentation_for(&revised_request)).unwrap();
+        assert_eq!(proof.state(), VcxStateType::VcxStateAccepted as u32);
+        assert_eq!(proof.presentation_status(), Status::Success.code());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_presentation_exchange_rejects_indy_presentation() {
+        let _setup = SetupAriesMocks::init();
+        let _mock_builder = MockBuilder::init().
+            set_mock_result_for_validate_indy_proof(Ok(true));
+
+        let connection_handle = build_test_connection_inviter_requested();
+
+        let mut proof = Verifier::create_with_presentation_definition("1".to_string(),
+                                                                      json!(_presentation_definition()).to_string(),
+                                                                      "Adult".to_owned()).unwrap();
+
+        proof.send_presentation_request(connection_handle).unwrap();
+        proof.update_state_with_message(ARIES_PROOF_PRESENTATION).unwrap();
+
+        assert_eq!(proof.state(), VcxStateType::VcxStateNone as u32);
+    }
 }
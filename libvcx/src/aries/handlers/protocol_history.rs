@@ -0,0 +1,126 @@
+use std::cell::RefCell;
+
+use error::prelude::*;
+use settings;
+use aries::messages::a2a::A2AMessage;
+
+thread_local! {
+    static SENT_MESSAGES: RefCell<Vec<Vec<A2AMessage>>> = RefCell::new(Vec::new());
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub direction: Direction,
+    pub timestamp: u64,
+    pub message: A2AMessage,
+}
+
+///
+/// Messages exchanged within a protocol, recorded only when `protocol_history` option is enabled.
+/// The history is a part of the serialized object, so it can be audited after the exchange is finished.
+///
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ProtocolHistory(Vec<HistoryEntry>);
+
+impl ProtocolHistory {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn entries(&self) -> &Vec<HistoryEntry> {
+        &self.0
+    }
+
+    pub fn to_string(&self) -> VcxResult<String> {
+        ::serde_json::to_string(&self.0)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize protocol history: {:?}", err)))
+    }
+
+    pub fn record_received(&mut self, message: &A2AMessage) {
+        self.record(Direction::Received, message.clone())
+    }
+
+    pub fn record_sent(&mut self, messages: Vec<A2AMessage>) {
+        for message in messages {
+            self.record(Direction::Sent, message)
+        }
+    }
+
+    fn record(&mut self, direction: Direction, message: A2AMessage) {
+        if !settings::is_protocol_history_enabled() { return; }
+
+        self.0.push(HistoryEntry { direction, timestamp: ::time::get_time().sec as u64, message })
+    }
+}
+
+pub struct SentMessages(());
+
+impl SentMessages {
+    /// Messages sent by the current thread since `capture` was called.
+    pub fn take(&self) -> Vec<A2AMessage> {
+        SENT_MESSAGES.with(|scopes| {
+            scopes.borrow_mut().last_mut()
+                .map(|messages| messages.drain(..).collect())
+                .unwrap_or_default()
+        })
+    }
+}
+
+impl Drop for SentMessages {
+    fn drop(&mut self) {
+        SENT_MESSAGES.with(|scopes| { scopes.borrow_mut().pop(); });
+    }
+}
+
+/// Collects messages sent by the current thread until the returned guard is dropped.
+pub fn capture() -> SentMessages {
+    SENT_MESSAGES.with(|scopes| scopes.borrow_mut().push(Vec::new()));
+    SentMessages(())
+}
+
+/// Called for every message sent to a connection or a service.
+pub fn message_sent(message: &A2AMessage) {
+    if !settings::is_protocol_history_enabled() { return; }
+
+    SENT_MESSAGES.with(|scopes| {
+        if let Some(messages) = scopes.borrow_mut().last_mut() {
+            messages.push(message.clone());
+        }
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use utils::devsetup::SetupDefaults;
+    use aries::messages::ack::tests::_ack;
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_protocol_history_is_recorded_only_when_enabled() {
+        let _setup = SetupDefaults::init();
+
+        let mut history = ProtocolHistory::default();
+        history.record_received(&A2AMessage::Ack(_ack()));
+        assert!(history.is_empty());
+
+        settings::set_config_value(settings::CONFIG_PROTOCOL_HISTORY, "true");
+
+        let sent = capture();
+        message_sent(&A2AMessage::Ack(_ack()));
+        history.record_received(&A2AMessage::Ack(_ack()));
+        history.record_sent(sent.take());
+
+        let directions: Vec<Direction> = history.entries().iter().map(|entry| entry.direction.clone()).collect();
+        assert_eq!(vec![Direction::Received, Direction::Sent], directions);
+        assert!(sent.take().is_empty());
+    }
+}
@@ -12,6 +12,7 @@ pub enum MessageFamilies {
     TrustPing,
     DiscoveryFeatures,
     Basicmessage,
+    InviteAction,
     Unknown(String),
 }
 
@@ -30,6 +31,7 @@ impl MessageFamilies {
             MessageFamilies::TrustPing => "1.0",
             MessageFamilies::DiscoveryFeatures => "1.0",
             MessageFamilies::Basicmessage => "1.0",
+            MessageFamilies::InviteAction => "0.9",
             MessageFamilies::Unknown(_) => "1.0"
         }
     }
@@ -50,6 +52,7 @@ impl MessageFamilies {
             MessageFamilies::TrustPing => Some((Actors::Sender, Actors::Receiver)),
             MessageFamilies::DiscoveryFeatures => Some((Actors::Sender, Actors::Receiver)),
             MessageFamilies::Basicmessage => Some((Actors::Sender, Actors::Receiver)),
+            MessageFamilies::InviteAction => Some((Actors::Inviter, Actors::Invitee)),
             MessageFamilies::Unknown(_) => None
         }
     }
@@ -68,6 +71,7 @@ impl From<String> for MessageFamilies {
             "trust_ping" => MessageFamilies::TrustPing,
             "discover-features" => MessageFamilies::DiscoveryFeatures,
             "basicmessage" => MessageFamilies::Basicmessage,
+            "invite-action" => MessageFamilies::InviteAction,
             family @ _ => MessageFamilies::Unknown(family.to_string())
         }
     }
@@ -86,6 +90,7 @@ impl ::std::string::ToString for MessageFamilies {
             MessageFamilies::TrustPing => "trust_ping".to_string(),
             MessageFamilies::DiscoveryFeatures => "discover-features".to_string(),
             MessageFamilies::Basicmessage => "basicmessage".to_string(),
+            MessageFamilies::InviteAction => "invite-action".to_string(),
             MessageFamilies::Unknown(family) => family.to_string()
         }
     }
@@ -13,6 +13,7 @@ use aries::messages::connection::invite::Invitation;
 use aries::messages::connection::request::Request;
 use aries::messages::connection::response::SignedResponse;
 use aries::messages::connection::problem_report::ProblemReport as ConnectionProblemReport;
+use aries::messages::connection::redirect::Redirect;
 use aries::messages::trust_ping::ping::Ping;
 use aries::messages::trust_ping::ping_response::PingResponse;
 use aries::messages::forward::Forward;
@@ -33,6 +34,8 @@ use aries::messages::discovery::disclose::Disclose;
 
 use aries::messages::basic_message::message::BasicMessage;
 
+use aries::messages::invite_action::invite::Invite as InviteForAction;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum A2AMessage {
     /// routing
@@ -43,6 +46,7 @@ pub enum A2AMessage {
     ConnectionRequest(Request),
     ConnectionResponse(SignedResponse),
     ConnectionProblemReport(ConnectionProblemReport),
+    ConnectionRedirect(Redirect),
 
     /// trust ping
     Ping(Ping),
@@ -72,6 +76,10 @@ pub enum A2AMessage {
     /// basic message
     BasicMessage(BasicMessage),
 
+    /// invite action
+    InviteForAction(InviteForAction),
+    InviteForActionAck(Ack),
+
     /// Any Raw Message
     Generic(Value),
 }
@@ -114,6 +122,11 @@ impl<'de> Deserialize<'de> for A2AMessage {
                     .map(|msg| A2AMessage::ConnectionResponse(msg))
                     .map_err(de::Error::custom)
             }
+            (MessageFamilies::Connections, A2AMessage::CONNECTION_REDIRECT) => {
+                Redirect::deserialize(value)
+                    .map(|msg| A2AMessage::ConnectionRedirect(msg))
+                    .map_err(de::Error::custom)
+            }
             (MessageFamilies::TrustPing, A2AMessage::PING) => {
                 Ping::deserialize(value)
                     .map(|msg| A2AMessage::Ping(msg))
@@ -199,6 +212,16 @@ impl<'de> Deserialize<'de> for A2AMessage {
                     .map(|msg| A2AMessage::BasicMessage(msg))
                     .map_err(de::Error::custom)
             }
+            (MessageFamilies::InviteAction, A2AMessage::INVITE_FOR_ACTION) => {
+                InviteForAction::deserialize(value)
+                    .map(|msg| A2AMessage::InviteForAction(msg))
+                    .map_err(de::Error::custom)
+            }
+            (MessageFamilies::InviteAction, A2AMessage::ACK) => {
+                Ack::deserialize(value)
+                    .map(|msg| A2AMessage::InviteForActionAck(msg))
+                    .map_err(de::Error::custom)
+            }
             (_, other_type) => {
                 warn!("Unexpected @type field structure: {}", other_type);
                 Ok(A2AMessage::Generic(value))
@@ -222,6 +245,7 @@ impl Serialize for A2AMessage {
             A2AMessage::ConnectionRequest(msg) => set_a2a_message_type(msg, MessageFamilies::Connections, A2AMessage::CONNECTION_REQUEST),
             A2AMessage::ConnectionResponse(msg) => set_a2a_message_type(msg, MessageFamilies::Connections, A2AMessage::CONNECTION_RESPONSE),
             A2AMessage::ConnectionProblemReport(msg) => set_a2a_message_type(msg, MessageFamilies::Connections, A2AMessage::CONNECTION_PROBLEM_REPORT),
+            A2AMessage::ConnectionRedirect(msg) => set_a2a_message_type(msg, MessageFamilies::Connections, A2AMessage::CONNECTION_REDIRECT),
             A2AMessage::Ping(msg) => set_a2a_message_type(msg, MessageFamilies::TrustPing, A2AMessage::PING),
             A2AMessage::PingResponse(msg) => set_a2a_message_type(msg, MessageFamilies::TrustPing, A2AMessage::PING_RESPONSE),
             A2AMessage::Ack(msg) => set_a2a_message_type(msg, MessageFamilies::Notification, A2AMessage::ACK),
@@ -238,6 +262,8 @@ impl Serialize for A2AMessage {
             A2AMessage::Query(msg) => set_a2a_message_type(msg, MessageFamilies::DiscoveryFeatures, A2AMessage::QUERY),
             A2AMessage::Disclose(msg) => set_a2a_message_type(msg, MessageFamilies::DiscoveryFeatures, A2AMessage::DISCLOSE),
             A2AMessage::BasicMessage(msg) => set_a2a_message_type(msg, MessageFamilies::Basicmessage, A2AMessage::BASIC_MESSAGE),
+            A2AMessage::InviteForAction(msg) => set_a2a_message_type(msg, MessageFamilies::InviteAction, A2AMessage::INVITE_FOR_ACTION),
+            A2AMessage::InviteForActionAck(msg) => set_a2a_message_type(msg, MessageFamilies::InviteAction, A2AMessage::ACK),
             A2AMessage::Generic(msg) => Ok(msg.clone())
         }.map_err(ser::Error::custom)?;
 
@@ -273,11 +299,65 @@ impl Default for MessageId {
 }
 
 impl A2AMessage {
+    pub fn family(&self) -> MessageFamilies {
+        match self {
+            A2AMessage::Forward(_) => MessageFamilies::Routing,
+            A2AMessage::ConnectionInvitation(_) |
+            A2AMessage::ConnectionRequest(_) |
+            A2AMessage::ConnectionResponse(_) |
+            A2AMessage::ConnectionProblemReport(_) |
+            A2AMessage::ConnectionRedirect(_) => MessageFamilies::Connections,
+            A2AMessage::Ping(_) |
+            A2AMessage::PingResponse(_) => MessageFamilies::TrustPing,
+            A2AMessage::Ack(_) => MessageFamilies::Notification,
+            A2AMessage::CommonProblemReport(_) => MessageFamilies::ReportProblem,
+            A2AMessage::CredentialProposal(_) |
+            A2AMessage::CredentialOffer(_) |
+            A2AMessage::CredentialRequest(_) |
+            A2AMessage::Credential(_) |
+            A2AMessage::CredentialAck(_) => MessageFamilies::CredentialIssuance,
+            A2AMessage::PresentationProposal(_) |
+            A2AMessage::PresentationRequest(_) |
+            A2AMessage::Presentation(_) |
+            A2AMessage::PresentationAck(_) => MessageFamilies::PresentProof,
+            A2AMessage::Query(_) |
+            A2AMessage::Disclose(_) => MessageFamilies::DiscoveryFeatures,
+            A2AMessage::BasicMessage(_) => MessageFamilies::Basicmessage,
+            A2AMessage::InviteForAction(_) |
+            A2AMessage::InviteForActionAck(_) => MessageFamilies::InviteAction,
+            A2AMessage::Generic(value) => {
+                serde_json::from_value::<MessageType>(value["@type"].clone())
+                    .map(|message_type| message_type.family)
+                    .unwrap_or_default()
+            }
+        }
+    }
+
+    pub fn message_id(&self) -> Option<String> {
+        let value = serde_json::to_value(self).ok()?;
+        value["@id"].as_str().map(String::from)
+    }
+
+    pub fn thread_id(&self) -> Option<String> {
+        let value = serde_json::to_value(self).ok()?;
+        value["~thread"]["thid"].as_str()
+            .or(value["@id"].as_str())
+            .map(String::from)
+    }
+
+    /// `{family}/{version}/{name}` of the message, e.g. `issue-credential/1.0/offer-credential`.
+    pub fn type_name(&self) -> Option<String> {
+        let value = serde_json::to_value(self).ok()?;
+        let message_type: MessageType = serde_json::from_value(value["@type"].clone()).ok()?;
+        Some(format!("{}/{}/{}", message_type.family.to_string(), message_type.version, message_type.type_))
+    }
+
     const FORWARD: &'static str = "forward";
     const CONNECTION_INVITATION: &'static str = "invitation";
     const CONNECTION_REQUEST: &'static str = "request";
     const CONNECTION_RESPONSE: &'static str = "response";
     const CONNECTION_PROBLEM_REPORT: &'static str = "problem_report";
+    const CONNECTION_REDIRECT: &'static str = "redirect";
     const PING: &'static str = "ping";
     const PING_RESPONSE: &'static str = "ping_response";
     const ACK: &'static str = "ack";
@@ -292,6 +372,7 @@ impl A2AMessage {
     const QUERY: &'static str = "query";
     const DISCLOSE: &'static str = "disclose";
     const BASIC_MESSAGE: &'static str = "message";
+    const INVITE_FOR_ACTION: &'static str = "invite";
 }
 
 #[macro_export]
@@ -5,16 +5,52 @@ use settings::Actors;
 use aries::messages::a2a::message_family::MessageFamilies;
 use aries::messages::discovery::disclose::ProtocolDescriptor;
 
+/// Prefix of protocol identifiers used by newer agents, queries using it are matched as well.
+pub const DIDCOMM_ORG_PREFIX: &str = "https://didcomm.org/";
+
+/// Registry of protocols, versions and roles this agent supports, queried by Discover Features.
 pub struct ProtocolRegistry {
     protocols: Vec<ProtocolDescriptor>
 }
 
+/// Protocol disclosed by the counterparty, with its identifier split into parts.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SupportedProtocol {
+    pub pid: String,
+    pub family: String,
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roles: Option<Vec<Actors>>,
+}
+
+impl From<ProtocolDescriptor> for SupportedProtocol {
+    fn from(descriptor: ProtocolDescriptor) -> SupportedProtocol {
+        let (family, version) = {
+            let mut parts = _strip_prefix(&descriptor.pid).trim_end_matches('/').rsplitn(2, '/');
+            let version = parts.next().unwrap_or_default().to_string();
+            match parts.next() {
+                Some(family) => (family.to_string(), version),
+                None => (version, String::new())
+            }
+        };
+        SupportedProtocol { pid: descriptor.pid, family, version, roles: descriptor.roles }
+    }
+}
+
+fn _strip_prefix(pid: &str) -> &str {
+    match pid.find(";spec/") {
+        Some(index) => &pid[index + ";spec/".len()..],
+        None if pid.starts_with(DIDCOMM_ORG_PREFIX) => &pid[DIDCOMM_ORG_PREFIX.len()..],
+        None => pid
+    }
+}
+
 impl ProtocolRegistry {
     pub fn init() -> ProtocolRegistry {
         let mut registry = ProtocolRegistry { protocols: Vec::new() };
         let actors = ::settings::get_actors();
 
-        for family in MessageFamilies::iter() {
+        for family in MessageFamilies::iter().filter(is_family_enabled) {
             match family {
                 family @ MessageFamilies::Routing |
                 family @ MessageFamilies::ReportProblem |
@@ -24,6 +60,7 @@ impl ProtocolRegistry {
                 family @ MessageFamilies::PresentProof |
                 family @ MessageFamilies::TrustPing |
                 family @ MessageFamilies::Basicmessage |
+                family @ MessageFamilies::InviteAction |
                 family @ MessageFamilies::DiscoveryFeatures => registry.add_protocol(&actors, family),
                 MessageFamilies::Signature => {}
                 MessageFamilies::Unknown(_) => {}
@@ -60,7 +97,17 @@ impl ProtocolRegistry {
             Some(query_) if query_ == "*" => self.protocols.clone(),
             Some(query_) => {
                 match Regex::new(query_) {
-                    Ok(re) => self.protocols.iter().filter(|protocol| re.is_match(&protocol.pid)).cloned().collect(),
+                    Ok(re) => self.protocols.iter().filter_map(|protocol| {
+                        if re.is_match(&protocol.pid) {
+                            return Some(protocol.clone());
+                        }
+                        // answer in the form the counterparty asked with
+                        let pid = format!("{}{}", DIDCOMM_ORG_PREFIX, _strip_prefix(&protocol.pid));
+                        if query_.starts_with(DIDCOMM_ORG_PREFIX) && re.is_match(&pid) {
+                            return Some(ProtocolDescriptor { pid, roles: protocol.roles.clone() });
+                        }
+                        None
+                    }).collect(),
                     Err(_) => vec![]
                 }
             }
@@ -73,6 +120,15 @@ impl ProtocolRegistry {
     }
 }
 
+/// Families listed in `disabled_protocols` config option are neither advertised nor dispatched.
+/// Problem reports are always processed so failures of other exchanges are still delivered.
+pub fn is_family_enabled(family: &MessageFamilies) -> bool {
+    match family {
+        MessageFamilies::ReportProblem => true,
+        family => !::settings::get_disabled_protocols().contains(&family.to_string())
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use utils::devsetup::SetupEmpty;
@@ -187,6 +243,53 @@ pub mod tests {
         assert_eq!(expected_protocols, protocols);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_protocols_for_query_works_for_didcomm_org_prefix() {
+        let _setup = SetupEmpty::init();
+
+        let registry: ProtocolRegistry = ProtocolRegistry::init();
+
+        let protocols = registry.get_protocols_for_query(Some("https://didcomm.org/present-proof/"));
+        let expected_protocols = vec![
+            ProtocolDescriptor { pid: "https://didcomm.org/present-proof/1.0".to_string(), roles: None },
+        ];
+        assert_eq!(expected_protocols, protocols);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_supported_protocol_from_descriptor() {
+        let _setup = SetupEmpty::init();
+
+        let protocol = SupportedProtocol::from(ProtocolDescriptor { pid: MessageFamilies::PresentProof.id(), roles: Some(vec![Actors::Prover]) });
+        assert_eq!(protocol, SupportedProtocol {
+            pid: MessageFamilies::PresentProof.id(),
+            family: "present-proof".to_string(),
+            version: "1.0".to_string(),
+            roles: Some(vec![Actors::Prover]),
+        });
+
+        let protocol = SupportedProtocol::from(ProtocolDescriptor { pid: "https://didcomm.org/out-of-band/1.1".to_string(), roles: None });
+        assert_eq!((protocol.family.as_str(), protocol.version.as_str()), ("out-of-band", "1.1"));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_protocol_registry_init_skips_disabled_protocols() {
+        let _setup = SetupEmpty::init();
+
+        ::settings::set_config_value(::settings::CONFIG_DISABLED_PROTOCOLS, &json!(["issue-credential", "report-problem"]).to_string());
+
+        assert!(!is_family_enabled(&MessageFamilies::CredentialIssuance));
+        assert!(is_family_enabled(&MessageFamilies::ReportProblem));
+        assert!(is_family_enabled(&MessageFamilies::PresentProof));
+
+        let registry: ProtocolRegistry = ProtocolRegistry::init();
+        assert!(registry.get_protocols_for_query(Some("did:sov:BzCbsNYhMrjHiqZDTUASHg;spec/issue-credential")).is_empty());
+        assert!(!registry.get_protocols_for_query(Some("did:sov:BzCbsNYhMrjHiqZDTUASHg;spec/present-proof")).is_empty());
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_get_protocols_for_query_works_for_limited_actors() {
@@ -1,8 +1,19 @@
-use std::str::from_utf8;
+use std::io::{self, Cursor, Read, Write};
 
+use base64;
+use openssl::sha::Sha256;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json;
 
 use error::{VcxError, VcxErrorKind, VcxResult};
+use settings;
+use utils::httpclient::{HttpRequest, HttpResponse, RequestOptions};
+use utils::openssl::HashingWriter;
+use utils::timeout::TimeoutUtils;
+
+// multiple of 3 bytes, so that the base64 chunks can be concatenated without padding in between
+const CHUNK_SIZE: usize = 3 * 16 * 1024;
+const BASE64_CHUNK_SIZE: usize = CHUNK_SIZE / 3 * 4;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct Attachments(pub Vec<Attachment>);
@@ -16,6 +27,13 @@ impl Attachments {
         self.0.get(0)
     }
 
+    pub fn get_by_id(&self, id: &AttachmentId) -> Option<&Attachment> {
+        self.0.iter().find(|attachment| match attachment {
+            Attachment::JSON(ref json) => &json.id == id,
+            Attachment::Blank => false
+        })
+    }
+
     pub fn add(&mut self, attachment: Attachment) {
         self.0.push(attachment);
     }
@@ -53,36 +71,95 @@ pub struct Json {
     data: AttachmentData,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+///
+/// `@id` of the attachment. Messages without format descriptors tell the format by the well-known ids,
+/// senders announcing formats by descriptors are free to choose any other id.
+///
+#[derive(Debug, Clone, PartialEq)]
 pub enum AttachmentId {
-    #[serde(rename = "libindy-cred-offer-0")]
     CredentialOffer,
-    #[serde(rename = "libindy-cred-request-0")]
     CredentialRequest,
-    #[serde(rename = "libindy-cred-0")]
     Credential,
-    #[serde(rename = "libindy-request-presentation-0")]
     PresentationRequest,
-    #[serde(rename = "libindy-presentation-0")]
     Presentation,
+    LdProofVcDetail,
+    LdProofVc,
+    PresentationDefinition,
+    PresentationSubmission,
+    OutOfBandRequest,
+    Other(String),
+}
+
+impl AttachmentId {
+    pub fn as_str(&self) -> &str {
+        match self {
+            AttachmentId::CredentialOffer => "libindy-cred-offer-0",
+            AttachmentId::CredentialRequest => "libindy-cred-request-0",
+            AttachmentId::Credential => "libindy-cred-0",
+            AttachmentId::PresentationRequest => "libindy-request-presentation-0",
+            AttachmentId::Presentation => "libindy-presentation-0",
+            AttachmentId::LdProofVcDetail => "ld-proof-vc-detail-0",
+            AttachmentId::LdProofVc => "ld-proof-vc-0",
+            AttachmentId::PresentationDefinition => "dif-presentation-definition-0",
+            AttachmentId::PresentationSubmission => "dif-presentation-submission-0",
+            AttachmentId::OutOfBandRequest => "request-0",
+            AttachmentId::Other(id) => id,
+        }
+    }
+}
+
+impl<'a> From<&'a str> for AttachmentId {
+    fn from(id: &'a str) -> AttachmentId {
+        match id {
+            "libindy-cred-offer-0" => AttachmentId::CredentialOffer,
+            "libindy-cred-request-0" => AttachmentId::CredentialRequest,
+            "libindy-cred-0" => AttachmentId::Credential,
+            "libindy-request-presentation-0" => AttachmentId::PresentationRequest,
+            "libindy-presentation-0" => AttachmentId::Presentation,
+            "ld-proof-vc-detail-0" => AttachmentId::LdProofVcDetail,
+            "ld-proof-vc-0" => AttachmentId::LdProofVc,
+            "dif-presentation-definition-0" => AttachmentId::PresentationDefinition,
+            "dif-presentation-submission-0" => AttachmentId::PresentationSubmission,
+            "request-0" => AttachmentId::OutOfBandRequest,
+            other => AttachmentId::Other(other.to_string()),
+        }
+    }
+}
+
+impl Serialize for AttachmentId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for AttachmentId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let id = String::deserialize(deserializer)?;
+        Ok(AttachmentId::from(id.as_str()))
+    }
 }
 
 impl Json {
     pub fn new(id: AttachmentId, json: serde_json::Value, encoding: AttachmentEncoding) -> VcxResult<Json> {
         let data: AttachmentData = match encoding {
             AttachmentEncoding::Base64 => {
-                AttachmentData::Base64(
-                    base64::encode(&
-                        match json {
-                            ::serde_json::Value::Object(obj) => {
-                                serde_json::to_string(&obj)
-                                    .map_err(|_| VcxError::from_msg(VcxErrorKind::InvalidJson, "Invalid Attachment Json".to_string()))?
-                            }
-                            ::serde_json::Value::String(str) => str,
-                            val => return Err(VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Unsupported Json value: {:?}", val)))
-                        }
-                    )
-                )
+                let json = match json {
+                    ::serde_json::Value::Object(obj) => {
+                        serde_json::to_string(&obj)
+                            .map_err(|_| VcxError::from_msg(VcxErrorKind::InvalidJson, "Invalid Attachment Json".to_string()))?
+                    }
+                    ::serde_json::Value::String(str) => str,
+                    val => return Err(VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Unsupported Json value: {:?}", val)))
+                };
+                AttachmentData::from_bytes(json.as_bytes())?
+            }
+            AttachmentEncoding::Json => {
+                let json = match json {
+                    ::serde_json::Value::String(str) => serde_json::from_str(&str)
+                        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Invalid Attachment Json: {:?}", err)))?,
+                    val => val
+                };
+                AttachmentData::from_json(json)
             }
         };
         Ok(Json {
@@ -91,37 +168,255 @@ impl Json {
         })
     }
 
+    // inlined content is checked against `max_json_size` by its encoded size before it is decoded,
+    // linked content is limited while it is downloaded
     pub fn get_data(&self) -> VcxResult<String> {
-        let data = self.data.get_bytes()?;
-        from_utf8(data.as_slice())
-            .map(|s| s.to_string())
+        let max_size = settings::get_max_json_size() as u64;
+        if self.data.inlined_size().map(|size| size > max_size).unwrap_or(false) {
+            return Err(VcxError::from_msg(VcxErrorKind::JsonLimitExceeded,
+                                          format!("Attachment exceeds maximal allowed size {} bytes", max_size)));
+        }
+
+        let bytes = self.data.get_bytes()?;
+        ::utils::json::check_json_limits(&bytes)?;
+        String::from_utf8(bytes)
             .map_err(|_| VcxError::from_msg(VcxErrorKind::IOError, "Wrong bytes in attachment".to_string()))
     }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub enum AttachmentEncoding {
-    Base64
+    Base64,
+    Json,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum AttachmentData {
-    #[serde(rename = "base64")]
-    Base64(String)
+///
+/// Content of the attachment, inlined as `base64` or `json`, or referenced by `links`
+/// which are verified against `sha256` (hex) of the content.
+///
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct AttachmentData {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base64: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
 }
 
 impl AttachmentData {
+    pub fn from_bytes(bytes: &[u8]) -> VcxResult<AttachmentData> {
+        AttachmentData::from_reader(&mut Cursor::new(bytes))
+    }
+
+    ///
+    /// Inlines content of `reader` as base64, content above the streaming threshold gets its `sha256`.
+    /// The content is read and encoded in chunks, only the encoded form carried by the message is kept.
+    ///
+    pub fn from_reader<R: Read>(reader: &mut R) -> VcxResult<AttachmentData> {
+        let mut base64 = Vec::new();
+        let (size, sha256) = encode_base64(reader, &mut base64)?;
+
+        // base64 alphabet is ascii
+        let base64 = String::from_utf8(base64)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::IOError, format!("Cannot encode attachment: {:?}", err)))?;
+        let sha256 = if size > settings::get_attachment_streaming_threshold() as u64 { Some(sha256) } else { None };
+        Ok(AttachmentData { base64: Some(base64), sha256, ..AttachmentData::default() })
+    }
+
+    pub fn from_json(json: serde_json::Value) -> AttachmentData {
+        AttachmentData { json: Some(json), ..AttachmentData::default() }
+    }
+
+    // size of the decoded inlined content, computed without decoding it
+    fn inlined_size(&self) -> Option<u64> {
+        if let Some(ref base64) = self.base64 {
+            return Some(base64.trim_end_matches('=').len() as u64 * 3 / 4);
+        }
+        self.json.as_ref().map(|json| json.to_string().len() as u64)
+    }
+
     pub fn get_bytes(&self) -> VcxResult<Vec<u8>> {
-        match self {
-            AttachmentData::Base64(s) => {
-                base64::decode(s).map_err(|_| VcxError::from_msg(VcxErrorKind::IOError, "Wrong bytes in attachment"))
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    ///
+    /// Writes the content into `writer` and returns its size. Base64 content above the streaming threshold
+    /// and linked content are decoded and copied in chunks, so that they are never held in memory as a whole.
+    ///
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> VcxResult<u64> {
+        if let Some(ref base64) = self.base64 {
+            return self._write_base64(base64, writer);
+        }
+
+        if let Some(ref json) = self.json {
+            let json = serde_json::to_vec(json)
+                .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize Attachment json: {:?}", err)))?;
+            writer.write_all(&json)
+                .map_err(|err| VcxError::from_msg(VcxErrorKind::IOError, format!("Cannot write attachment: {:?}", err)))?;
+            return Ok(json.len() as u64);
+        }
+
+        if let Some(ref links) = self.links {
+            return self._write_links(links, writer);
+        }
+
+        Err(VcxError::from_msg(VcxErrorKind::InvalidJson, "Attachment data contains neither base64, json nor links"))
+    }
+
+    fn _write_base64<W: Write>(&self, base64: &str, writer: &mut W) -> VcxResult<u64> {
+        if base64.len() <= settings::get_attachment_streaming_threshold() && self.sha256.is_none() {
+            let bytes = base64::decode(base64).map_err(|_| VcxError::from_msg(VcxErrorKind::IOError, "Wrong bytes in attachment"))?;
+            writer.write_all(&bytes)
+                .map_err(|err| VcxError::from_msg(VcxErrorKind::IOError, format!("Cannot write attachment: {:?}", err)))?;
+            return Ok(bytes.len() as u64);
+        }
+
+        let mut decoder = Base64ChunkReader { base64: base64.as_bytes(), position: 0, decoded: Vec::new(), offset: 0 };
+        _copy_verified(&mut decoder, writer, self.sha256.as_ref().map(String::as_str))
+    }
+
+    // links are tried in order until one of them answers, its content is streamed into `writer` while it is hashed,
+    // so on error the caller has to discard whatever was written, as it may be incomplete or not match `sha256`
+    fn _write_links<W: Write>(&self, links: &[String], writer: &mut W) -> VcxResult<u64> {
+        let sha256 = self.sha256.as_ref()
+            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, "Linked attachment has no sha256 to verify the content"))?;
+
+        let mut last_error = VcxError::from_msg(VcxErrorKind::InvalidJson, "Attachment data contains no links");
+        for link in links {
+            debug!("Downloading attachment from: \"{}\"", link);
+            match _download(link) {
+                Ok(mut response) => return _copy_verified_limited(&mut response, writer, sha256),
+                Err(err) => {
+                    warn!("Cannot download attachment from {}: {}", link, err);
+                    last_error = err;
+                }
+            }
+        }
+        Err(last_error)
+    }
+}
+
+fn _copy_verified_limited<W: Write>(response: &mut HttpResponse, writer: &mut W, sha256: &str) -> VcxResult<u64> {
+    let mut hashing_writer = HashingWriter::new(writer);
+    let size = response.copy_limited(&mut hashing_writer, settings::get_max_json_size() as u64)?;
+    _check_sha256(&hashing_writer.finish(), sha256)?;
+    Ok(size)
+}
+
+// links come from the counterparty, the shared client connects only to public hosts over `https`, redirects included
+fn _download(link: &str) -> VcxResult<HttpResponse> {
+    let response = HttpRequest::get(link)?
+        .public_hosts_only()
+        .options(RequestOptions::from_settings().timeout(TimeoutUtils::long_timeout()))
+        .send()
+        .map_err(|failure| failure.into_error().extend("Could not download attachment"))?;
+
+    if !response.is_success() {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidHttpResponse, format!("Attachment download failed with status: {}", response.status)));
+    }
+    Ok(response)
+}
+
+fn _read_chunk<R: Read>(reader: &mut R, chunk: &mut [u8]) -> VcxResult<usize> {
+    let mut filled = 0;
+    while filled < chunk.len() {
+        match reader.read(&mut chunk[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(ref err) if err.kind() == ::std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(VcxError::from_msg(VcxErrorKind::IOError, format!("Cannot read attachment: {:?}", err)))
+        }
+    }
+    Ok(filled)
+}
+
+/// Base64 encodes content of `reader` into `writer` chunk by chunk, returns size and `sha256` (hex) of the content.
+pub fn encode_base64<R: Read, W: Write>(reader: &mut R, writer: &mut W) -> VcxResult<(u64, String)> {
+    let mut hasher = Sha256::new();
+    let mut size: u64 = 0;
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    let mut encoded = String::with_capacity(BASE64_CHUNK_SIZE);
+
+    loop {
+        let read = _read_chunk(reader, &mut chunk)?;
+        if read == 0 { break; }
+
+        size += read as u64;
+        hasher.update(&chunk[..read]);
+
+        encoded.clear();
+        base64::encode_config_buf(&chunk[..read], base64::STANDARD, &mut encoded);
+        writer.write_all(encoded.as_bytes())
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::IOError, format!("Cannot write attachment: {:?}", err)))?;
+    }
+
+    Ok((size, _to_hex(&hasher.finish())))
+}
+
+// copies `reader` into `writer`, the hash is checked once everything is copied
+fn _copy_verified<R: Read, W: Write>(reader: &mut R, writer: &mut W, sha256: Option<&str>) -> VcxResult<u64> {
+    let mut hashing_writer = HashingWriter::new(writer);
+    let size = io::copy(reader, &mut hashing_writer)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::IOError, format!("Cannot copy attachment: {:?}", err)))?;
+
+    if let Some(expected) = sha256 {
+        _check_sha256(&hashing_writer.finish(), expected)?;
+    }
+    Ok(size)
+}
+
+fn _check_sha256(hash: &[u8], expected: &str) -> VcxResult<()> {
+    let actual = _to_hex(hash);
+    if actual != expected.to_lowercase() {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidAttributesStructure,
+                                      format!("Attachment sha256 mismatch, expected: {}, actual: {}", expected, actual)));
+    }
+    Ok(())
+}
+
+fn _to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Decodes base64 content chunk by chunk.
+struct Base64ChunkReader<'a> {
+    base64: &'a [u8],
+    position: usize,
+    decoded: Vec<u8>,
+    offset: usize,
+}
+
+impl<'a> Read for Base64ChunkReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> ::std::io::Result<usize> {
+        if self.offset == self.decoded.len() {
+            if self.position == self.base64.len() {
+                return Ok(0);
             }
+
+            let end = ::std::cmp::min(self.position + BASE64_CHUNK_SIZE, self.base64.len());
+            self.decoded.clear();
+            self.offset = 0;
+            base64::decode_config_buf(&self.base64[self.position..end], base64::STANDARD, &mut self.decoded)
+                .map_err(|err| ::std::io::Error::new(::std::io::ErrorKind::InvalidData, err))?;
+            self.position = end;
         }
+
+        let read = ::std::cmp::min(buf.len(), self.decoded.len() - self.offset);
+        buf[..read].copy_from_slice(&self.decoded[self.offset..self.offset + read]);
+        self.offset += read;
+        Ok(read)
     }
 }
 
 #[cfg(test)]
 pub mod tests {
+    use utils::devsetup::SetupDefaults;
+
     use super::*;
 
     fn _json() -> serde_json::Value {
@@ -134,6 +429,10 @@ pub mod tests {
         let json_attachment: Json = Json::new(AttachmentId::Credential, _json(), AttachmentEncoding::Base64).unwrap();
         assert_eq!(vec![123, 34, 102, 105, 101, 108, 100, 34, 58, 34, 118, 97, 108, 117, 101, 34, 125], json_attachment.data.get_bytes().unwrap());
         assert_eq!(_json().to_string(), json_attachment.get_data().unwrap());
+
+        let json_attachment: Json = Json::new(AttachmentId::Credential, _json(), AttachmentEncoding::Json).unwrap();
+        assert_eq!(Some(_json()), json_attachment.data.json);
+        assert_eq!(_json().to_string(), json_attachment.get_data().unwrap());
     }
 
     #[test]
@@ -154,6 +453,105 @@ pub mod tests {
             let mut attachments = Attachments::new();
             attachments.add_json_attachment(AttachmentId::Credential, _json(), AttachmentEncoding::Base64).unwrap();
             assert_eq!(_json().to_string(), attachments.content().unwrap());
+            assert!(attachments.get_by_id(&AttachmentId::Credential).is_some());
+            assert!(attachments.get_by_id(&AttachmentId::Presentation).is_none());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_attachment_data_serialization() {
+        let attachment: Attachment = serde_json::from_value(json!({
+            "@id": "libindy-cred-0",
+            "mime-type": "application/json",
+            "data": {"base64": "eyJmaWVsZCI6InZhbHVlIn0="}
+        })).unwrap();
+        assert_eq!(json!(attachment)["data"], json!({"base64": "eyJmaWVsZCI6InZhbHVlIn0="}));
+
+        let attachment: Attachment = serde_json::from_value(json!({
+            "@id": "libindy-cred-0",
+            "mime-type": "application/json",
+            "data": {"json": {"field": "value"}}
+        })).unwrap();
+        match attachment {
+            Attachment::JSON(json) => assert_eq!(_json().to_string(), json.get_data().unwrap()),
+            Attachment::Blank => panic!("Json attachment expected")
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_large_attachment_is_processed_in_chunks() {
+        let _setup = SetupDefaults::init();
+        settings::set_config_value(settings::CONFIG_ATTACHMENT_STREAMING_THRESHOLD, "1024");
+
+        let content: Vec<u8> = (0..3 * CHUNK_SIZE + 7).map(|i| (i % 251) as u8).collect();
+
+        let data = AttachmentData::from_bytes(&content).unwrap();
+        assert_eq!(data.base64, Some(base64::encode(&content)));
+        assert_eq!(data.sha256, Some(_to_hex(&::openssl::sha::sha256(&content))));
+
+        let mut written = Vec::new();
+        assert_eq!(content.len() as u64, data.write_to(&mut written).unwrap());
+        assert_eq!(content, written);
+
+        let small = AttachmentData::from_bytes(b"small").unwrap();
+        assert_eq!(small.sha256, None);
+        assert_eq!(b"small".to_vec(), small.get_bytes().unwrap());
+
+        let tampered = AttachmentData { sha256: Some(_to_hex(&::openssl::sha::sha256(b"other"))), ..data };
+        assert_eq!(tampered.get_bytes().unwrap_err().kind(), VcxErrorKind::InvalidAttributesStructure);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_copy_verified_checks_hash() {
+        let content = b"linked content".to_vec();
+        let sha256 = _to_hex(&::openssl::sha::sha256(&content));
+
+        let mut written = Vec::new();
+        _copy_verified(&mut Cursor::new(&content), &mut written, Some(&sha256.to_uppercase())).unwrap();
+        assert_eq!(content, written);
+
+        let mut written = Vec::new();
+        _copy_verified(&mut Cursor::new(b"tampered content".to_vec()), &mut written, Some(&sha256)).unwrap_err();
+
+        let links = AttachmentData { links: Some(vec!["https://example.com/attachment".to_string()]), ..AttachmentData::default() };
+        assert_eq!(links.get_bytes().unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_attachment_id_serialization() {
+        assert_eq!(json!("ld-proof-vc-0"), json!(AttachmentId::LdProofVc));
+        assert_eq!(AttachmentId::LdProofVc, serde_json::from_value(json!("ld-proof-vc-0")).unwrap());
+        assert_eq!(AttachmentId::Other("0".to_string()), serde_json::from_value(json!("0")).unwrap());
+        assert_eq!(json!("0"), json!(AttachmentId::Other("0".to_string())));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_links_are_restricted() {
+        let _setup = SetupDefaults::init();
+
+        let sha256 = _to_hex(&::openssl::sha::sha256(b"content"));
+        for link in &["http://example.com/attachment", "file:///etc/passwd", "https://127.0.0.1/attachment", "https://[::1]/attachment"] {
+            let links = AttachmentData { links: Some(vec![link.to_string()]), sha256: Some(sha256.clone()), ..AttachmentData::default() };
+            assert_eq!(links.get_bytes().unwrap_err().kind(), VcxErrorKind::InvalidUrl);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_data_respects_size_limit() {
+        let _setup = SetupDefaults::init();
+
+        let data = AttachmentData::from_bytes(b"12345").unwrap();
+        assert_eq!(Some(5), data.inlined_size());
+        assert_eq!(Some(2), AttachmentData::from_json(json!(1)).inlined_size());
+
+        settings::set_config_value(settings::CONFIG_MAX_JSON_SIZE, "10");
+        let attachment = Json::new(AttachmentId::Credential, json!({"field": "value exceeding the limit"}), AttachmentEncoding::Base64).unwrap();
+        assert_eq!(attachment.get_data().unwrap_err().kind(), VcxErrorKind::JsonLimitExceeded);
+    }
+}
@@ -29,8 +29,10 @@ impl BasicMessage {
         self
     }
 
+    /// Institution locale, `en` when the institution localization is not configured.
     pub fn set_default_localization(mut self) -> Self {
-        self.l10n = Some(Localization::default());
+        let localization = Localization::institution().ok().and_then(|localization| localization).unwrap_or_default();
+        self.l10n = Some(localization.for_names(&[]));
         self
     }
 
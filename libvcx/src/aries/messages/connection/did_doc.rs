@@ -1,3 +1,4 @@
+use aries::messages::connection::did_key;
 use aries::messages::connection::invite::Invitation;
 
 use error::prelude::*;
@@ -100,7 +101,7 @@ impl DidDoc {
                         id: key_id,
                         type_: String::from(KEY_TYPE),
                         controller: self.id.clone(),
-                        public_key_base_58: key.clone(),
+                        public_key_base_58: did_key::decode(key).unwrap_or(key.clone()),
                     });
 
                 self.authentication.push(
@@ -170,12 +171,18 @@ impl DidDoc {
     }
 
     fn validate_recipient_key(&self, key: &str) -> VcxResult<()> {
+        if did_key::is_did_key(key) {
+            return did_key::decode(key).map(|_| ());
+        }
+
         let public_key = self.validate_public_key(key)?;
         self.validate_authentication(&public_key.id)
     }
 
     fn validate_routing_key(&self, key: &str) -> VcxResult<()> {
-        if DidDoc::_key_parts(key).len() == 2 {
+        if did_key::is_did_key(key) {
+            did_key::decode(key)?;
+        } else if DidDoc::_key_parts(key).len() == 2 {
             self.validate_public_key(key)?;
         } else {
             validate_verkey(key)?;
@@ -254,6 +261,10 @@ impl DidDoc {
     }
 
     fn key_for_reference(&self, key_reference: &str) -> String {
+        if did_key::is_did_key(key_reference) {
+            return did_key::decode(key_reference).unwrap_or(key_reference.to_string());
+        }
+
         let id = DidDoc::_parse_key_reference(key_reference);
 
         self.public_key.iter().find(|key_| key_.id == id.to_string() || key_.public_key_base_58 == id.to_string())
@@ -522,4 +533,35 @@ pub mod tests {
 
         assert_eq!(did_doc, DidDoc::from(_invitation()))
     }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_did_doc_works_for_did_key_references() {
+        let did_key_1 = did_key::encode(&_key_1()).unwrap();
+        let did_key_2 = did_key::encode(&_key_2()).unwrap();
+
+        let did_doc = DidDoc {
+            context: String::from(CONTEXT),
+            id: _id(),
+            public_key: vec![],
+            authentication: vec![],
+            service: vec![Service {
+                service_endpoint: _service_endpoint(),
+                recipient_keys: vec![did_key_1.clone()],
+                routing_keys: vec![did_key_2.clone()],
+                ..Default::default()
+            }],
+        };
+        did_doc.validate().unwrap();
+        assert_eq!(did_doc.recipient_keys(), vec![_key_1()]);
+        assert_eq!(did_doc.routing_keys(), vec![_key_2()]);
+
+        let invitation = _invitation()
+            .set_recipient_keys(vec![did_key_1])
+            .set_routing_keys(vec![did_key_2]);
+        let did_doc = DidDoc::from(invitation);
+        did_doc.validate().unwrap();
+        assert_eq!(did_doc.recipient_keys(), vec![_key_1()]);
+        assert_eq!(did_doc.routing_keys(), vec![_key_2()]);
+    }
 }
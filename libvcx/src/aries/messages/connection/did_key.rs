@@ -0,0 +1,106 @@
+extern crate rust_base58;
+
+use self::rust_base58::{FromBase58, ToBase58};
+use error::prelude::*;
+use messages::validation::validate_verkey;
+use settings;
+
+pub const DID_KEY_PREFIX: &str = "did:key:";
+const MULTIBASE_BASE58_BTC: char = 'z';
+const ED25519_PUB_MULTICODEC: [u8; 2] = [0xed, 0x01];
+
+pub fn is_did_key(key: &str) -> bool {
+    key.starts_with(DID_KEY_PREFIX)
+}
+
+/// Encodes Ed25519 verkey as multibase (base58btc) multicodec key, the method specific id of `did:key`.
+pub fn verkey_to_multibase(verkey: &str) -> VcxResult<String> {
+    validate_verkey(verkey)?;
+    let mut key = ED25519_PUB_MULTICODEC.to_vec();
+    key.extend(verkey.from_base58()
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::NotBase58, format!("Invalid verkey: {}", err)))?);
+    Ok(format!("{}{}", MULTIBASE_BASE58_BTC, key.to_base58()))
+}
+
+pub fn multibase_to_verkey(multibase: &str) -> VcxResult<String> {
+    if !multibase.starts_with(MULTIBASE_BASE58_BTC) {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidDid, format!("Unsupported multibase encoding of key: {}", multibase)));
+    }
+
+    let key = multibase[1..].from_base58()
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::NotBase58, format!("Invalid multibase key: {}", err)))?;
+
+    if !key.starts_with(&ED25519_PUB_MULTICODEC) {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidDid, format!("Key {} is not Ed25519 public key", multibase)));
+    }
+
+    validate_verkey(&key[ED25519_PUB_MULTICODEC.len()..].to_base58())
+}
+
+/// Converts raw verkey into `did:key`, keys which already are `did:key` are returned as is.
+pub fn encode(key: &str) -> VcxResult<String> {
+    if is_did_key(key) {
+        return Ok(key.to_string());
+    }
+    Ok(format!("{}{}", DID_KEY_PREFIX, verkey_to_multibase(key)?))
+}
+
+/// Converts `did:key` (optionally with key fragment) into raw verkey, other keys are returned as is.
+pub fn decode(key: &str) -> VcxResult<String> {
+    if !is_did_key(key) {
+        return Ok(key.to_string());
+    }
+    let multibase = key[DID_KEY_PREFIX.len()..].split('#').next().unwrap_or_default();
+    multibase_to_verkey(multibase)
+}
+
+/// Encodes keys we advertise to counterparties as `did:key` if enabled by `did_key_encoding` setting.
+pub fn encode_outbound_keys(keys: Vec<String>) -> VcxResult<Vec<String>> {
+    if !settings::is_did_key_encoding_set() {
+        return Ok(keys);
+    }
+    keys.iter().map(|key| encode(key)).collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use aries::messages::connection::did_doc::tests::*;
+    use utils::devsetup::SetupDefaults;
+
+    use super::*;
+
+    pub fn _did_key_1() -> String {
+        encode(&_key_1()).unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_did_key_round_trip() {
+        let did_key = encode(&_key_1()).unwrap();
+        assert!(did_key.starts_with("did:key:z6Mk"));
+        assert_eq!(encode(&did_key).unwrap(), did_key);
+
+        assert_eq!(decode(&did_key).unwrap(), _key_1());
+        assert_eq!(decode(&format!("{}#{}", did_key, &did_key[DID_KEY_PREFIX.len()..])).unwrap(), _key_1());
+        assert_eq!(decode(&_key_1()).unwrap(), _key_1());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_did_key_decode_fails_for_unsupported_keys() {
+        assert_eq!(decode("did:key:6Mkabc").unwrap_err().kind(), VcxErrorKind::InvalidDid);
+        // X25519 key
+        assert_eq!(decode("did:key:z6LSbysY2xFMRpGMhb7tFTLMpeuPRaqaWM1yECx2AtzE3KCc").unwrap_err().kind(), VcxErrorKind::InvalidDid);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_encode_outbound_keys() {
+        let _setup = SetupDefaults::init();
+
+        assert_eq!(encode_outbound_keys(_recipient_keys()).unwrap(), _recipient_keys());
+
+        settings::set_config_value(settings::CONFIG_DID_KEY_ENCODING, "true");
+        assert_eq!(encode_outbound_keys(_recipient_keys()).unwrap(), vec![_did_key_1()]);
+    }
+}
@@ -0,0 +1,143 @@
+use serde_json::Value;
+
+use error::prelude::*;
+use aries::messages::connection::did_doc::DidDoc;
+use aries::messages::connection::did_key;
+use messages::validation::validate_verkey;
+
+pub const DID_PEER_PREFIX: &str = "did:peer:";
+const NUMALGO_2: char = '2';
+const PURPOSE_VERIFICATION: char = 'V';
+const PURPOSE_ENCRYPTION: char = 'E';
+const PURPOSE_SERVICE: char = 'S';
+const SERVICE_TYPE_DIDCOMM: &str = "dm";
+
+pub fn is_did_peer(did: &str) -> bool {
+    did.starts_with(DID_PEER_PREFIX)
+}
+
+/// Generates `did:peer:2` DID (numalgo 2) embedding the given keys and DIDComm service endpoint.
+pub fn generate_did_peer_2(recipient_keys: &[String], routing_keys: &[String], service_endpoint: &str) -> VcxResult<String> {
+    let mut did = format!("{}{}", DID_PEER_PREFIX, NUMALGO_2);
+
+    for key in recipient_keys {
+        did.push('.');
+        did.push(PURPOSE_VERIFICATION);
+        did.push_str(&did_key::verkey_to_multibase(key)?);
+    }
+
+    let service = json!({
+        "t": SERVICE_TYPE_DIDCOMM,
+        "s": service_endpoint,
+        "r": routing_keys.iter().map(|key| did_key::encode(key)).collect::<VcxResult<Vec<String>>>()?,
+        "a": ["didcomm/aip1", "didcomm/aip2;env=rfc19"]
+    });
+    did.push('.');
+    did.push(PURPOSE_SERVICE);
+    did.push_str(&base64::encode_config(service.to_string().as_bytes(), base64::URL_SAFE_NO_PAD));
+
+    Ok(did)
+}
+
+/// Builds DIDDoc of `did:peer:2` DID.
+/// Only Ed25519 verification keys are used as recipient keys, X25519 keys cannot be used for Aries envelopes.
+pub fn resolve_did_peer(did: &str) -> VcxResult<DidDoc> {
+    if !is_did_peer(did) {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidDid, format!("{} is not did:peer DID", did)));
+    }
+
+    let method_specific_id = &did[DID_PEER_PREFIX.len()..];
+    if !method_specific_id.starts_with(NUMALGO_2) {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidDid, format!("Unsupported did:peer numalgo: {}", did)));
+    }
+
+    let mut recipient_keys = Vec::new();
+    let mut service: Option<Value> = None;
+
+    for element in method_specific_id[1..].split('.').filter(|element| !element.is_empty()) {
+        let purpose = element.chars().next();
+        let value = &element[purpose.map(char::len_utf8).unwrap_or_default()..];
+        match purpose {
+            Some(PURPOSE_VERIFICATION) => recipient_keys.push(did_key::multibase_to_verkey(value)?),
+            Some(PURPOSE_ENCRYPTION) => continue,
+            Some(PURPOSE_SERVICE) if service.is_none() => {
+                let decoded = base64::decode_config(value, base64::URL_SAFE_NO_PAD)
+                    .or(base64::decode_config(value, base64::URL_SAFE))
+                    .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidDid, format!("Cannot decode did:peer service: {}", err)))?;
+                service = Some(::serde_json::from_slice(&decoded)
+                    .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidDid, format!("Cannot deserialize did:peer service: {}", err)))?);
+            }
+            Some(PURPOSE_SERVICE) => continue,
+            _ => return Err(VcxError::from_msg(VcxErrorKind::InvalidDid, format!("Unsupported did:peer element: {}", element)))
+        }
+    }
+
+    let service = service
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidDid, format!("{} does not contain service", did)))?;
+
+    let service_endpoint = service["s"].as_str().or(service["serviceEndpoint"].as_str())
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidDid, "did:peer service does not contain endpoint"))?;
+
+    let routing_keys = service["r"].as_array().or(service["routingKeys"].as_array())
+        .map(|keys| keys.iter()
+            .filter_map(|key| key.as_str())
+            .map(|key| did_key::decode(key).and_then(|key| validate_verkey(&key)))
+            .collect::<VcxResult<Vec<String>>>())
+        .unwrap_or(Ok(Vec::new()))?;
+
+    let mut did_doc = DidDoc::default();
+    did_doc.set_id(did.to_string());
+    did_doc.set_service_endpoint(service_endpoint.to_string());
+    did_doc.set_keys(recipient_keys, routing_keys);
+    Ok(did_doc)
+}
+
+/// Returns DIDDoc to be used for the DID received in connection protocol.
+/// DIDDoc of `did:peer` DID can be omitted from the message, it is resolved from the DID itself then.
+pub fn resolve_connection_did_doc(did: &str, did_doc: &DidDoc) -> VcxResult<DidDoc> {
+    if is_did_peer(did) && did_doc.recipient_keys().is_empty() {
+        return resolve_did_peer(did);
+    }
+    Ok(did_doc.clone())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use aries::messages::connection::did_doc::tests::*;
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_did_peer_round_trip() {
+        let did = generate_did_peer_2(&_recipient_keys(), &_routing_keys(), &_service_endpoint()).unwrap();
+        assert!(did.starts_with("did:peer:2.Vz6Mk"));
+
+        let did_doc = resolve_did_peer(&did).unwrap();
+        assert_eq!(did_doc.id, did);
+        assert_eq!(did_doc.recipient_keys(), _recipient_keys());
+        assert_eq!(did_doc.routing_keys(), _routing_keys());
+        assert_eq!(did_doc.get_endpoint(), _service_endpoint());
+        did_doc.validate().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_resolve_did_peer_fails_for_unsupported_did() {
+        assert_eq!(resolve_did_peer("did:sov:VsKV7grR1BUE29mG2Fm2kX").unwrap_err().kind(), VcxErrorKind::InvalidDid);
+        assert_eq!(resolve_did_peer("did:peer:0z6MkpTHR8VNsBxYAAWHut2Geadd9jSwuBV8xRoAnwWsdvktH").unwrap_err().kind(), VcxErrorKind::InvalidDid);
+        assert_eq!(resolve_did_peer("did:peer:2.Vz6MkpTHR8VNsBxYAAWHut2Geadd9jSwuBV8xRoAnwWsdvktH").unwrap_err().kind(), VcxErrorKind::InvalidDid);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_resolve_connection_did_doc() {
+        let did = generate_did_peer_2(&_recipient_keys(), &[], &_service_endpoint()).unwrap();
+
+        let resolved = resolve_connection_did_doc(&did, &DidDoc::default()).unwrap();
+        assert_eq!(resolved.recipient_keys(), _recipient_keys());
+
+        assert_eq!(resolve_connection_did_doc(&did, &_did_doc()).unwrap(), _did_doc());
+        assert_eq!(resolve_connection_did_doc(&_id(), &_did_doc()).unwrap(), _did_doc());
+    }
+}
@@ -1,6 +1,9 @@
 pub mod did_doc;
+pub mod did_key;
+pub mod did_peer;
 pub mod invite;
 pub mod problem_report;
+pub mod redirect;
 pub mod request;
 pub mod response;
 pub mod service;
@@ -0,0 +1,152 @@
+use base64;
+
+use error::prelude::*;
+use messages::thread::Thread;
+use utils::libindy::crypto;
+use aries::messages::a2a::{A2AMessage, MessageId};
+
+///
+/// Sent by the invitee in place of a connection request when it is already connected with the inviter.
+/// Carries the invitee's side of the existing pairwise relationship (`did`, `verkey`) and the inviter's DID
+/// in it (`their_did`), so the inviter can keep using the existing channel.
+///
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct Redirect {
+    #[serde(rename = "@id")]
+    pub id: MessageId,
+    pub did: String,
+    pub verkey: String,
+    pub their_did: String,
+    pub signature: String,
+    #[serde(rename = "~thread")]
+    pub thread: Thread,
+    /// Key the envelope of the received redirect was authcrypted by, not part of the message.
+    #[serde(skip)]
+    pub sender_verkey: Option<String>,
+}
+
+impl Redirect {
+    pub fn create() -> Redirect {
+        Redirect::default()
+    }
+
+    pub fn set_did(mut self, did: String) -> Redirect {
+        self.did = did;
+        self
+    }
+
+    pub fn set_verkey(mut self, verkey: String) -> Redirect {
+        self.verkey = verkey;
+        self
+    }
+
+    pub fn set_their_did(mut self, their_did: String) -> Redirect {
+        self.their_did = their_did;
+        self
+    }
+
+    pub fn set_sender_verkey(mut self, sender_verkey: Option<String>) -> Redirect {
+        self.sender_verkey = sender_verkey;
+        self
+    }
+
+    // binds the redirect to the invitation it answers, the inviter checks the thread id against its invitation
+    // and the envelope sender against `verkey` (see `verify_for_invitation`) before accepting it
+    fn _sig_data(&self) -> Vec<u8> {
+        format!("{}:{}:{}", self.thread.thid.as_ref().map(String::as_str).unwrap_or_default(), self.did, self.their_did).into_bytes()
+    }
+
+    /// Signs thread id and DIDs by `verkey`, has to be called after all fields are set.
+    pub fn sign(mut self) -> VcxResult<Redirect> {
+        let signature = crypto::sign(&self.verkey, &self._sig_data())?;
+        self.signature = base64::encode_config(&signature, base64::URL_SAFE);
+        Ok(self)
+    }
+
+    /// Checks that the redirect was signed by the key of the existing relationship it announces.
+    pub fn verify(&self) -> VcxResult<()> {
+        let signature = base64::decode_config(&self.signature.as_bytes(), base64::URL_SAFE)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidRedirectDetail, format!("Cannot decode Redirect signature: {:?}", err)))?;
+
+        if !crypto::verify(&self.verkey, &self._sig_data(), &signature)? {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidRedirectDetail, "Redirect signature is invalid for announced verkey"));
+        }
+        Ok(())
+    }
+
+    /// Checks that the redirect answers `invitation_id`, was authcrypted by the key it announces and is signed by it.
+    pub fn verify_for_invitation(&self, invitation_id: &str) -> VcxResult<()> {
+        if self.thread.thid.as_ref().map(String::as_str) != Some(invitation_id) {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidRedirectDetail, "Redirect does not answer the pending invitation"));
+        }
+        if self.sender_verkey.as_ref() != Some(&self.verkey) {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidRedirectDetail, "Redirect was not sent by the key it announces"));
+        }
+        self.verify()
+    }
+
+    pub fn to_string(&self) -> VcxResult<String> {
+        ::serde_json::to_string(self)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize Redirect: {:?}", err)))
+    }
+}
+
+threadlike!(Redirect);
+a2a_message!(Redirect, ConnectionRedirect);
+
+#[cfg(test)]
+pub mod tests {
+    use utils::devsetup::SetupIndyMocks;
+    use aries::messages::connection::response::tests::_thread;
+
+    use super::*;
+
+    fn _did() -> String {
+        String::from("VsKV7grR1BUE29mG2Fm2kX")
+    }
+
+    fn _verkey() -> String {
+        String::from("CnEDk9HrMnmiHXEV1WFgbVCRteYnPqsJwrTdcZaNhFVW")
+    }
+
+    fn _their_did() -> String {
+        String::from("KC6NKcpXcpVnpjL8uKH3tV")
+    }
+
+    pub fn _redirect() -> Redirect {
+        Redirect {
+            id: MessageId::id(),
+            did: _did(),
+            verkey: _verkey(),
+            their_did: _their_did(),
+            signature: String::new(),
+            thread: _thread(),
+            sender_verkey: None,
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_redirect_build_works() {
+        let redirect: Redirect = Redirect::default()
+            .set_did(_did())
+            .set_verkey(_verkey())
+            .set_their_did(_their_did())
+            .set_thread_id(&_thread().thid.unwrap());
+
+        assert_eq!(_redirect(), redirect);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_redirect_sign_and_verify_works() {
+        let _setup = SetupIndyMocks::init();
+
+        let redirect = _redirect().sign().unwrap();
+        assert!(!redirect.signature.is_empty());
+        redirect.verify().unwrap();
+
+        let redirect = Redirect { signature: String::from("not base64!"), ..redirect };
+        assert_eq!(redirect.verify().unwrap_err().kind(), VcxErrorKind::InvalidRedirectDetail);
+    }
+}
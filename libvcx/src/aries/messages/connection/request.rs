@@ -1,5 +1,7 @@
+use error::prelude::*;
 use aries::messages::a2a::{A2AMessage, MessageId};
 use aries::messages::connection::did_doc::*;
+use aries::messages::connection::did_peer;
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
 pub struct Request {
@@ -14,9 +16,18 @@ pub struct ConnectionData {
     #[serde(rename = "DID")]
     pub did: String,
     #[serde(rename = "DIDDoc")]
+    #[serde(default)]
     pub did_doc: DidDoc,
 }
 
+impl ConnectionData {
+    /// DIDDoc of `did:peer` DID may be omitted by the sender, fill it in from the DID then.
+    pub fn resolve_did_doc(&mut self) -> VcxResult<()> {
+        self.did_doc = did_peer::resolve_connection_did_doc(&self.did, &self.did_doc)?;
+        Ok(())
+    }
+}
+
 impl Request {
     pub fn create() -> Request {
         Request::default()
@@ -1,14 +1,10 @@
-use base64;
-use time;
-
 use error::prelude::*;
 use messages::thread::Thread;
-use utils::libindy::crypto;
 use aries::messages::a2a::{A2AMessage, MessageId};
-use aries::messages::a2a::message_family::MessageFamilies;
-use aries::messages::a2a::message_type::MessageType;
 use aries::messages::ack::PleaseAck;
 use aries::messages::connection::did_doc::*;
+use aries::messages::connection::did_peer;
+use aries::messages::signature::FieldSignature;
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
 pub struct Response {
@@ -27,9 +23,18 @@ pub struct ConnectionData {
     #[serde(rename = "DID")]
     pub did: String,
     #[serde(rename = "DIDDoc")]
+    #[serde(default)]
     pub did_doc: DidDoc,
 }
 
+impl ConnectionData {
+    /// DIDDoc of `did:peer` DID may be omitted by the sender, fill it in from the DID then.
+    pub fn resolve_did_doc(&mut self) -> VcxResult<()> {
+        self.did_doc = did_peer::resolve_connection_did_doc(&self.did, &self.did_doc)?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
 pub struct SignedResponse {
     #[serde(rename = "@id")]
@@ -37,21 +42,12 @@ pub struct SignedResponse {
     #[serde(rename = "~thread")]
     pub thread: Thread,
     #[serde(rename = "connection~sig")]
-    pub connection_sig: ConnectionSignature,
+    pub connection_sig: FieldSignature,
     #[serde(rename = "~please_ack")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub please_ack: Option<PleaseAck>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
-pub struct ConnectionSignature {
-    #[serde(rename = "@type")]
-    pub msg_type: MessageType,
-    pub signature: String,
-    pub sig_data: String,
-    pub signer: String,
-}
-
 impl Response {
     pub fn create() -> Response {
         Response::default()
@@ -74,26 +70,7 @@ impl Response {
     }
 
     pub fn encode(&self, key: &str) -> VcxResult<SignedResponse> {
-        let connection_data = json!(self.connection).to_string();
-
-        let now: u64 = time::get_time().sec as u64;
-
-        let mut sig_data = now.to_be_bytes().to_vec();
-
-        sig_data.extend(connection_data.as_bytes());
-
-        let signature = crypto::sign(key, &sig_data)?;
-
-        let sig_data = base64::encode_config(&sig_data, base64::URL_SAFE);
-
-        let signature = base64::encode_config(&signature, base64::URL_SAFE);
-
-        let connection_sig = ConnectionSignature {
-            signature,
-            sig_data,
-            signer: key.to_string(),
-            ..Default::default()
-        };
+        let connection_sig = FieldSignature::sign(&self.connection, key)?;
 
         let signed_response = SignedResponse {
             id: self.id.clone(),
@@ -111,22 +88,8 @@ threadlike!(Response);
 
 impl SignedResponse {
     pub fn decode(self, key: &str) -> VcxResult<Response> {
-        let signature = base64::decode_config(&self.connection_sig.signature.as_bytes(), base64::URL_SAFE)
-            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot decode ConnectionResponse: {:?}", err)))?;
-
-        let sig_data = base64::decode_config(&self.connection_sig.sig_data.as_bytes(), base64::URL_SAFE)
-            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot decode ConnectionResponse: {:?}", err)))?;
-
-        if !crypto::verify(&key, &sig_data, &signature)? {
-            return Err(VcxError::from_msg(VcxErrorKind::InvalidJson, "ConnectionResponse signature is invalid for original Invite recipient key"));
-        }
-
-        //TODO check sig_data.signer
-
-        let sig_data = &sig_data[8..];
-
-        let connection: ConnectionData = ::serde_json::from_slice(&sig_data)
-            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, err.to_string()))?;
+        let mut connection: ConnectionData = self.connection_sig.decode(key)?;
+        connection.resolve_did_doc()?;
 
         Ok(Response {
             id: self.id,
@@ -135,21 +98,15 @@ impl SignedResponse {
             please_ack: self.please_ack,
         })
     }
-}
-
-a2a_message!(SignedResponse, ConnectionResponse);
 
-impl Default for ConnectionSignature {
-    fn default() -> ConnectionSignature {
-        ConnectionSignature {
-            msg_type: MessageType::build(MessageFamilies::Signature, "ed25519Sha512_single"),
-            signature: String::new(),
-            sig_data: String::new(),
-            signer: String::new(),
-        }
+    /// Checks the `connection~sig` again without the freshness check, e.g. for a stored response.
+    pub fn verify(&self, key: &str) -> VcxResult<()> {
+        self.connection_sig.verify(key).map(|_| ())
     }
 }
 
+a2a_message!(SignedResponse, ConnectionResponse);
+
 #[cfg(test)]
 pub mod tests {
     use utils::libindy::tests::test_setup;
@@ -189,7 +146,7 @@ pub mod tests {
         SignedResponse {
             id: MessageId::id(),
             thread: _thread(),
-            connection_sig: ConnectionSignature {
+            connection_sig: FieldSignature {
                 signature: String::from("yeadfeBWKn09j5XU3ITUE3gPbUDmPNeblviyjrOIDdVMT5WZ8wxMCxQ3OpAnmq1o-Gz0kWib9zr0PLsbGc2jCA=="),
                 sig_data: String::from("MTU3MTg0NzQwM3siZGlkIjoiVnNLVjdnclIxQlVFMjltRzJGbTJrWCIsImRpZF9kb2MiOnsiQGNvbnRleHQiOiJodHRwczovL3czaWQub3JnL2RpZC92MSIsImF1dGhlbnRpY2F0aW9uIjpbeyJwdWJsaWNLZXkiOiJWc0tWN2dyUjFCVUUyOW1HMkZtMmtYIzEiLCJ0eXBlIjoiRWQyNTUxOVNpZ25hdHVyZUF1dGhlbnRpY2F0aW9uMjAxOCJ9XSwiaWQiOiJWc0tWN2dyUjFCVUUyOW1HMkZtMmtYIiwicHVibGljS2V5IjpbeyJpZCI6IjEiLCJvd25lciI6IlZzS1Y3Z3JSMUJVRTI5bUcyRm0ya1giLCJwdWJsaWNLZXlCYXNlNTgiOiI3SjNYczhLUVV0U2ZNenB0ZVVLcThiNDg5bzdENFB4QVkxSjFKQUxDNDF6ayIsInR5cGUiOiJFZDI1NTE5VmVyaWZpY2F0aW9uS2V5MjAxOCJ9LHsiaWQiOiIyIiwib3duZXIiOiJWc0tWN2dyUjFCVUUyOW1HMkZtMmtYIiwicHVibGljS2V5QmFzZTU4IjoiSGV6Y2UyVVdNWjN3VWhWa2gyTGZLU3M4bkR6V3d6czJXaW43RXpOTjNZYVIiLCJ0eXBlIjoiRWQyNTUxOVZlcmlmaWNhdGlvbktleTIwMTgifSx7ImlkIjoiMyIsIm93bmVyIjoiVnNLVjdnclIxQlVFMjltRzJGbTJrWCIsInB1YmxpY0tleUJhc2U1OCI6IjNMWXV4SkJKa25nRGJ2Smo0emp4MTNEQlVkWjJQOTZlTnlid2QybjlMOUFVIiwidHlwZSI6IkVkMjU1MTlWZXJpZmljYXRpb25LZXkyMDE4In1dLCJzZXJ2aWNlIjpbeyJpZCI6ImRpZDpleGFtcGxlOjEyMzQ1Njc4OWFiY2RlZmdoaTtkaWQtY29tbXVuaWNhdGlvbiIsInByaW9yaXR5IjowLCJyZWNpcGllbnRLZXlzIjpbIlZzS1Y3Z3JSMUJVRTI5bUcyRm0ya1gjMSJdLCJyb3V0aW5nS2V5cyI6WyJWc0tWN2dyUjFCVUUyOW1HMkZtMmtYIzIiLCJWc0tWN2dyUjFCVUUyOW1HMkZtMmtYIzMiXSwic2VydmljZUVuZHBvaW50IjoiaHR0cDovL2xvY2FsaG9zdDo4MDgwIiwidHlwZSI6ImRpZC1jb21tdW5pY2F0aW9uIn1dfX0="),
                 signer: _key(),
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use error::prelude::*;
 use messages::thread::Thread;
 use aries::messages::a2a::{A2AMessage, MessageId};
 
@@ -52,6 +53,30 @@ impl ProblemReport {
         self.comment = Some(comment);
         self
     }
+
+    /// Error of an operation which failed because the counterparty reported this problem,
+    /// repeating the operation won't help.
+    pub fn to_error(&self, kind: VcxErrorKind, msg: &str) -> VcxError {
+        let msg = match self.comment {
+            Some(ref comment) => format!("{}: {}", msg, comment),
+            None => msg.to_string(),
+        };
+        let mut err = VcxError::from_msg(kind, msg).with_retryable(false);
+        if let Some(ref description) = self.description {
+            err = err.with_problem_code(description.code);
+        }
+        if let Some(ref thid) = self.thread.thid {
+            err = err.with_thread_id(thid);
+        }
+        err
+    }
+
+    /// Problem report received from the counterparty finishes the exchange without failing the operation
+    /// which processed it, so it is made available through `vcx_get_last_error_details` this way.
+    pub fn record_received(&self, protocol: &str) {
+        let err = self.to_error(VcxErrorKind::InvalidState, "Counterparty reported problem").with_protocol(protocol);
+        ::error::set_current_error(&err);
+    }
 }
 
 threadlike!(ProblemReport);
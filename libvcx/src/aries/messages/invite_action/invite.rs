@@ -0,0 +1,100 @@
+use messages::thread::Thread;
+use aries::messages::a2a::{A2AMessage, MessageId};
+
+/// Asks the counterparty to take an action identified by `goal_code`, e.g. to start a presentation.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct Invite {
+    #[serde(rename = "@id")]
+    pub id: MessageId,
+    pub goal_code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ack_on: Option<Vec<AckOn>>,
+    #[serde(rename = "~thread")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread: Option<Thread>,
+}
+
+/// Outcomes of the invitation the inviter wants to be acknowledged.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum AckOn {
+    #[serde(rename = "ACCEPT")]
+    Accept,
+    #[serde(rename = "OUTCOME")]
+    Outcome,
+}
+
+impl Invite {
+    pub fn create() -> Invite {
+        Invite::default()
+    }
+
+    pub fn set_goal_code(mut self, goal_code: String) -> Invite {
+        self.goal_code = goal_code;
+        self
+    }
+
+    pub fn set_ack_on(mut self, ack_on: Option<Vec<AckOn>>) -> Invite {
+        self.ack_on = ack_on;
+        self
+    }
+
+    pub fn set_thread_id(mut self, id: &str) -> Invite {
+        self.thread = Some(Thread::new().set_thid(id.to_string()));
+        self
+    }
+
+    /// Id of the thread the answer to the invitation belongs to.
+    pub fn thread_id(&self) -> String {
+        self.thread.as_ref().and_then(|thread| thread.thid.clone()).unwrap_or(self.id.0.clone())
+    }
+}
+
+a2a_message!(Invite, InviteForAction);
+
+#[cfg(test)]
+pub mod tests {
+    use aries::messages::connection::response::tests::*;
+
+    use super::*;
+
+    fn _goal_code() -> String {
+        String::from("present-proof")
+    }
+
+    pub fn _invite() -> Invite {
+        Invite {
+            id: MessageId::id(),
+            goal_code: _goal_code(),
+            ack_on: Some(vec![AckOn::Accept]),
+            thread: None,
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_invite_build_works() {
+        let invite: Invite = Invite::default()
+            .set_goal_code(_goal_code())
+            .set_ack_on(Some(vec![AckOn::Accept]));
+
+        assert_eq!(_invite(), invite);
+        assert_eq!(MessageId::id().0, invite.thread_id());
+
+        let invite = invite.set_thread_id(&_thread_id());
+        assert_eq!(_thread_id(), invite.thread_id());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_invite_serialization() {
+        let invite: A2AMessage = ::serde_json::from_value(json!({
+            "@type": "did:sov:BzCbsNYhMrjHiqZDTUASHg;spec/invite-action/0.9/invite",
+            "@id": "testid",
+            "goal_code": "present-proof",
+            "ack_on": ["ACCEPT"]
+        })).unwrap();
+
+        assert_eq!(A2AMessage::InviteForAction(_invite()), invite);
+        assert_eq!(json!(invite)["@type"], json!("did:sov:BzCbsNYhMrjHiqZDTUASHg;spec/invite-action/0.9/invite"));
+    }
+}
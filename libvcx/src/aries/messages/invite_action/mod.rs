@@ -0,0 +1 @@
+pub mod invite;
\ No newline at end of file
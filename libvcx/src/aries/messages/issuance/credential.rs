@@ -2,7 +2,9 @@ use error::{VcxResult};
 use messages::thread::Thread;
 use aries::messages::a2a::{A2AMessage, MessageId};
 use aries::messages::ack::PleaseAck;
-use aries::messages::attachment::{AttachmentId, Attachments};
+use aries::messages::attachment::{AttachmentEncoding, AttachmentId, Attachments};
+use aries::messages::issuance::format::{CredentialFormat, FormatDescriptor};
+use aries::messages::issuance::w3c::W3cCredential;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
 pub struct Credential {
@@ -10,6 +12,9 @@ pub struct Credential {
     pub id: MessageId,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub comment: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub formats: Vec<FormatDescriptor>,
     #[serde(rename = "credentials~attach")]
     pub credentials_attach: Attachments,
     #[serde(rename = "~thread")]
@@ -33,6 +38,16 @@ impl Credential {
         self.credentials_attach.add_base64_encoded_json_attachment(AttachmentId::Credential, ::serde_json::Value::String(credential))?;
         Ok(self)
     }
+
+    pub fn set_ld_proof_vc(mut self, credential: &W3cCredential) -> VcxResult<Credential> {
+        self.credentials_attach.add_json_attachment(AttachmentId::LdProofVc, json!(credential), AttachmentEncoding::Json)?;
+        self.formats.push(CredentialFormat::LdProofVc.credential_descriptor());
+        Ok(self)
+    }
+
+    pub fn format(&self) -> CredentialFormat {
+        CredentialFormat::from_message(&self.formats, &self.credentials_attach)
+    }
 }
 
 please_ack!(Credential);
@@ -64,6 +79,7 @@ pub mod tests {
         Credential {
             id: MessageId::id(),
             comment: Some(_comment()),
+            formats: vec![],
             thread: thread(),
             credentials_attach: attachment,
             please_ack: None,
@@ -1,9 +1,13 @@
-use error::{VcxResult};
+use error::prelude::*;
 use messages::thread::Thread;
 use aries::messages::a2a::{A2AMessage, MessageId};
-use aries::messages::attachment::{AttachmentId, Attachments};
+use aries::messages::attachment::{AttachmentEncoding, AttachmentId, Attachments};
 use aries::messages::issuance::CredentialPreviewData;
+use aries::messages::issuance::format::{CredentialFormat, FormatDescriptor};
+use aries::messages::issuance::w3c::LdProofVcDetail;
+use aries::messages::localization::Localization;
 use aries::messages::mime_type::MimeType;
+use aries::messages::timing::Timing;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
 pub struct CredentialOffer {
@@ -12,11 +16,20 @@ pub struct CredentialOffer {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub comment: Option<String>,
     pub credential_preview: CredentialPreviewData,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub formats: Vec<FormatDescriptor>,
     #[serde(rename = "offers~attach")]
     pub offers_attach: Attachments,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "~thread")]
     pub thread: Option<Thread>,
+    #[serde(rename = "~timing")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timing: Option<Timing>,
+    #[serde(rename = "~l10n")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l10n: Option<Localization>,
 }
 
 impl CredentialOffer {
@@ -39,6 +52,17 @@ impl CredentialOffer {
         Ok(self)
     }
 
+    /// Offers W3C credential `detail` instead of Indy credential.
+    pub fn set_ld_proof_vc_detail(mut self, detail: &LdProofVcDetail) -> VcxResult<CredentialOffer> {
+        self.offers_attach.add_json_attachment(AttachmentId::LdProofVcDetail, json!(detail), AttachmentEncoding::Json)?;
+        self.formats.push(CredentialFormat::LdProofVc.offer_descriptor());
+        Ok(self)
+    }
+
+    pub fn format(&self) -> CredentialFormat {
+        CredentialFormat::from_message(&self.formats, &self.offers_attach)
+    }
+
     pub fn set_credential_preview_data(mut self, credential_preview: CredentialPreviewData) -> VcxResult<CredentialOffer> {
         self.credential_preview = credential_preview;
         Ok(self)
@@ -49,13 +73,26 @@ impl CredentialOffer {
         Ok(self)
     }
 
+    /// Attaches `localization` with labels of the previewed attributes only.
+    pub fn set_l10n(mut self, localization: Localization) -> Self {
+        let names: Vec<&str> = self.credential_preview.attributes.iter().map(|attribute| attribute.name.as_str()).collect();
+        self.l10n = Some(localization.for_names(&names));
+        self
+    }
+
     pub fn set_thread_id(mut self, id: &str) -> Self {
         self.thread = Some(Thread::new().set_thid(id.to_string()));
         self
     }
+
+    pub fn set_parent_thread_id(mut self, id: &str) -> Self {
+        self.thread = Some(self.thread.unwrap_or_default().set_pthid(id.to_string()));
+        self
+    }
 }
 
 a2a_message!(CredentialOffer);
+timing!(CredentialOffer);
 
 #[cfg(test)]
 pub mod tests {
@@ -100,8 +137,11 @@ pub mod tests {
             id: MessageId::id(),
             comment: _comment(),
             credential_preview: _preview_data(),
+            formats: vec![],
             offers_attach: attachment,
             thread: Some(_thread()),
+            timing: None,
+            l10n: None,
         }
     }
 
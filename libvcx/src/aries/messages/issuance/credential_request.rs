@@ -1,7 +1,10 @@
 use error::VcxResult;
 use messages::thread::Thread;
 use aries::messages::a2a::{A2AMessage, MessageId};
-use aries::messages::attachment::{AttachmentId, Attachments};
+use aries::messages::attachment::{AttachmentEncoding, AttachmentId, Attachments};
+use aries::messages::issuance::format::{CredentialFormat, FormatDescriptor};
+use aries::messages::issuance::w3c::LdProofVcDetail;
+use aries::messages::timing::Timing;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
 pub struct CredentialRequest {
@@ -9,10 +12,16 @@ pub struct CredentialRequest {
     pub id: MessageId,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub comment: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub formats: Vec<FormatDescriptor>,
     #[serde(rename = "requests~attach")]
     pub requests_attach: Attachments,
     #[serde(rename = "~thread")]
     pub thread: Thread,
+    #[serde(rename = "~timing")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timing: Option<Timing>,
 }
 
 impl CredentialRequest {
@@ -29,9 +38,21 @@ impl CredentialRequest {
         self.requests_attach.add_base64_encoded_json_attachment(AttachmentId::CredentialRequest, ::serde_json::Value::String(credential_request))?;
         Ok(self)
     }
+
+    /// Requests W3C credential `detail` with the subject filled in by the holder.
+    pub fn set_ld_proof_vc_detail(mut self, detail: &LdProofVcDetail) -> VcxResult<CredentialRequest> {
+        self.requests_attach.add_json_attachment(AttachmentId::LdProofVcDetail, json!(detail), AttachmentEncoding::Json)?;
+        self.formats.push(CredentialFormat::LdProofVc.request_descriptor());
+        Ok(self)
+    }
+
+    pub fn format(&self) -> CredentialFormat {
+        CredentialFormat::from_message(&self.formats, &self.requests_attach)
+    }
 }
 
 threadlike!(CredentialRequest);
+timing!(CredentialRequest);
 a2a_message!(CredentialRequest);
 
 #[cfg(test)]
@@ -58,8 +79,10 @@ pub mod tests {
         CredentialRequest {
             id: MessageId::id(),
             comment: Some(_comment()),
+            formats: vec![],
             requests_attach: attachment,
             thread: thread(),
+            timing: None,
         }
     }
 
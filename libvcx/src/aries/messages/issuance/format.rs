@@ -0,0 +1,123 @@
+use aries::messages::attachment::{AttachmentId, Attachments};
+
+pub const INDY_CRED_ABSTRACT_FORMAT: &str = "hlindy/cred-abstract@v2.0";
+pub const INDY_CRED_REQUEST_FORMAT: &str = "hlindy/cred-req@v2.0";
+pub const INDY_CRED_FORMAT: &str = "hlindy/cred@v2.0";
+// W3C credentials with `SortedJsonEd25519Signature` proof (see `w3c::SORTED_JSON_ED25519_SIGNATURE`) are not
+// `aries/ld-proof-vc` ones, whose Linked Data proofs other agents would expect, so they have libvcx formats.
+pub const LD_PROOF_VC_DETAIL_FORMAT: &str = "libvcx/sorted-json-vc-detail@v1.0";
+pub const LD_PROOF_VC_FORMAT: &str = "libvcx/sorted-json-vc@v1.0";
+
+///
+/// Format descriptor of issue-credential v2: tells which format the attachment `attach_id` is in.
+///
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct FormatDescriptor {
+    pub attach_id: String,
+    pub format: String,
+}
+
+impl FormatDescriptor {
+    pub fn new(attach_id: AttachmentId, format: &str) -> FormatDescriptor {
+        FormatDescriptor {
+            attach_id: attach_id.as_str().to_string(),
+            format: format.to_string(),
+        }
+    }
+}
+
+///
+/// Format of issue-credential attachments. It is told by the v2 format descriptors when the message has them,
+/// otherwise by the well-known attachment ids of v1 messages.
+///
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum CredentialFormat {
+    Indy,
+    LdProofVc,
+}
+
+impl Default for CredentialFormat {
+    fn default() -> CredentialFormat {
+        CredentialFormat::Indy
+    }
+}
+
+impl CredentialFormat {
+    pub fn from_message(formats: &[FormatDescriptor], attachments: &Attachments) -> CredentialFormat {
+        if !formats.is_empty() {
+            return if formats.iter().any(|descriptor| descriptor.format == LD_PROOF_VC_DETAIL_FORMAT || descriptor.format == LD_PROOF_VC_FORMAT) {
+                CredentialFormat::LdProofVc
+            } else {
+                CredentialFormat::Indy
+            };
+        }
+
+        if attachments.get_by_id(&AttachmentId::LdProofVcDetail).is_some() || attachments.get_by_id(&AttachmentId::LdProofVc).is_some() {
+            CredentialFormat::LdProofVc
+        } else {
+            CredentialFormat::Indy
+        }
+    }
+
+    pub fn offer_descriptor(&self) -> FormatDescriptor {
+        match self {
+            CredentialFormat::Indy => FormatDescriptor::new(AttachmentId::CredentialOffer, INDY_CRED_ABSTRACT_FORMAT),
+            CredentialFormat::LdProofVc => FormatDescriptor::new(AttachmentId::LdProofVcDetail, LD_PROOF_VC_DETAIL_FORMAT),
+        }
+    }
+
+    pub fn request_descriptor(&self) -> FormatDescriptor {
+        match self {
+            CredentialFormat::Indy => FormatDescriptor::new(AttachmentId::CredentialRequest, INDY_CRED_REQUEST_FORMAT),
+            CredentialFormat::LdProofVc => FormatDescriptor::new(AttachmentId::LdProofVcDetail, LD_PROOF_VC_DETAIL_FORMAT),
+        }
+    }
+
+    pub fn credential_descriptor(&self) -> FormatDescriptor {
+        match self {
+            CredentialFormat::Indy => FormatDescriptor::new(AttachmentId::Credential, INDY_CRED_FORMAT),
+            CredentialFormat::LdProofVc => FormatDescriptor::new(AttachmentId::LdProofVc, LD_PROOF_VC_FORMAT),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use aries::messages::attachment::AttachmentEncoding;
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_credential_format_from_attachments() {
+        let mut attachments = Attachments::new();
+        assert_eq!(CredentialFormat::Indy, CredentialFormat::from_message(&[], &attachments));
+
+        attachments.add_base64_encoded_json_attachment(AttachmentId::CredentialOffer, json!("{}")).unwrap();
+        assert_eq!(CredentialFormat::Indy, CredentialFormat::from_message(&[], &attachments));
+
+        let mut attachments = Attachments::new();
+        attachments.add_json_attachment(AttachmentId::LdProofVc, json!({}), AttachmentEncoding::Json).unwrap();
+        assert_eq!(CredentialFormat::LdProofVc, CredentialFormat::from_message(&[], &attachments));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_credential_format_from_descriptors() {
+        let mut attachments = Attachments::new();
+        attachments.add_json_attachment(AttachmentId::Other("0".to_string()), json!({}), AttachmentEncoding::Json).unwrap();
+
+        let descriptor = FormatDescriptor { attach_id: "0".to_string(), format: LD_PROOF_VC_DETAIL_FORMAT.to_string() };
+        assert_eq!(CredentialFormat::LdProofVc, CredentialFormat::from_message(&[descriptor], &attachments));
+
+        let descriptor = FormatDescriptor { attach_id: "0".to_string(), format: INDY_CRED_ABSTRACT_FORMAT.to_string() };
+        assert_eq!(CredentialFormat::Indy, CredentialFormat::from_message(&[descriptor], &attachments));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_format_descriptor_serialization() {
+        assert_eq!(json!({"attach_id": "ld-proof-vc-0", "format": "libvcx/sorted-json-vc@v1.0"}),
+                   json!(CredentialFormat::LdProofVc.credential_descriptor()));
+    }
+}
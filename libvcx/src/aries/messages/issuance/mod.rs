@@ -1,13 +1,15 @@
 use aries::messages::a2a::message_type::MessageType;
 use aries::messages::a2a::message_family::MessageFamilies;
 use aries::messages::mime_type::MimeType;
-use error::VcxResult;
+use error::prelude::*;
 
 pub mod credential;
 pub mod credential_offer;
 pub mod credential_proposal;
 pub mod credential_request;
 pub mod credential_ack;
+pub mod format;
+pub mod w3c;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct CredentialPreviewData {
@@ -30,6 +32,18 @@ impl CredentialPreviewData {
                     _type: None,
                 }
             }
+            mime_type => {
+                if !mime_type.is_text() {
+                    ::base64::decode(value)
+                        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidAttributesStructure,
+                                                          format!("Value of attribute {} of type {} is not base64 encoded: {}", name, mime_type.to_string(), err)))?;
+                }
+                CredentialValue {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                    _type: Some(mime_type),
+                }
+            }
         };
         self.attributes.push(data_value);
         Ok(self)
@@ -0,0 +1,299 @@
+use std::collections::BTreeMap;
+
+use base64;
+use chrono::{SecondsFormat, Utc};
+use openssl::sha::sha256;
+use serde_json::{self, Map, Value};
+
+use error::prelude::*;
+use utils::libindy::crypto;
+use aries::messages::connection::did_key;
+
+pub const W3C_CREDENTIALS_CONTEXT: &str = "https://www.w3.org/2018/credentials/v1";
+pub const SECURITY_CONTEXT: &str = "https://w3id.org/security/v2";
+pub const VERIFIABLE_CREDENTIAL_TYPE: &str = "VerifiableCredential";
+// Ed25519 signature of the document JSON with sorted keys. It is not Ed25519Signature2018, which
+// signs URDNA2015 canonicalization of the RDF dataset, so the proofs are verified by libvcx only.
+pub const SORTED_JSON_ED25519_SIGNATURE: &str = "SortedJsonEd25519Signature";
+pub const ASSERTION_METHOD_PURPOSE: &str = "assertionMethod";
+
+// detached JWS with unencoded payload (RFC 7797)
+const JWS_HEADER: &str = r#"{"alg":"EdDSA","b64":false,"crit":["b64"]}"#;
+
+///
+/// W3C Verifiable Credential (Data Model 1.0) issued by a `did:key` issuer.
+/// Attributes of the credential are the claims of `credentialSubject`, `id` of the subject is the holder DID.
+///
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct W3cCredential {
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "type")]
+    pub types: Vec<String>,
+    pub issuer: String,
+    #[serde(rename = "issuanceDate")]
+    pub issuance_date: String,
+    #[serde(rename = "credentialSubject")]
+    pub credential_subject: Map<String, Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof: Option<LdProof>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct LdProof {
+    #[serde(rename = "type")]
+    pub proof_type: String,
+    pub created: String,
+    #[serde(rename = "verificationMethod")]
+    pub verification_method: String,
+    #[serde(rename = "proofPurpose")]
+    pub proof_purpose: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub challenge: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jws: Option<String>,
+}
+
+/// `libvcx/sorted-json-vc-detail@v1.0` attachment: the credential to be issued and the requested proof.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct LdProofVcDetail {
+    pub credential: W3cCredential,
+    pub options: LdProofVcDetailOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct LdProofVcDetailOptions {
+    #[serde(rename = "proofType")]
+    pub proof_type: String,
+}
+
+impl W3cCredential {
+    pub fn create(issuer_verkey: &str, credential_subject: Map<String, Value>) -> VcxResult<W3cCredential> {
+        Ok(W3cCredential {
+            context: vec![W3C_CREDENTIALS_CONTEXT.to_string(), SECURITY_CONTEXT.to_string()],
+            id: None,
+            types: vec![VERIFIABLE_CREDENTIAL_TYPE.to_string()],
+            issuer: did_key::encode(issuer_verkey)?,
+            issuance_date: _now(),
+            credential_subject,
+            proof: None,
+        })
+    }
+
+    pub fn from_str(credential: &str) -> VcxResult<W3cCredential> {
+        serde_json::from_str(credential)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize W3C Credential: {:?}", err)))
+    }
+
+    pub fn set_subject_id(mut self, subject_id: &str) -> W3cCredential {
+        self.credential_subject.insert(String::from("id"), Value::String(subject_id.to_string()));
+        self
+    }
+
+    pub fn subject_id(&self) -> Option<&str> {
+        self.credential_subject.get("id").and_then(Value::as_str)
+    }
+
+    /// Adds `SortedJsonEd25519Signature` proof made by the issuer key, which must be held by the wallet.
+    pub fn sign(mut self) -> VcxResult<W3cCredential> {
+        self.proof = None;
+        let proof = LdProof::create(&json!(self), &self.issuer, ASSERTION_METHOD_PURPOSE, None, None)?;
+
+        self.proof = Some(proof);
+        Ok(self)
+    }
+
+    /// Checks that the credential is signed by its issuer.
+    pub fn verify(&self) -> VcxResult<()> {
+        let proof = self.proof.as_ref()
+            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidProof, "W3C Credential has no proof"))?;
+
+        let mut document = self.clone();
+        document.proof = None;
+
+        proof.verify(&json!(document), &self.issuer)
+    }
+}
+
+impl LdProof {
+    /// `SortedJsonEd25519Signature` proof of `document` made by the key of `did:key` `controller`.
+    pub fn create(document: &Value, controller: &str, proof_purpose: &str, challenge: Option<String>, domain: Option<String>) -> VcxResult<LdProof> {
+        let verkey = did_key::decode(controller)?;
+
+        let mut proof = LdProof {
+            proof_type: SORTED_JSON_ED25519_SIGNATURE.to_string(),
+            created: _now(),
+            verification_method: format!("{}#{}", controller, did_key::verkey_to_multibase(&verkey)?),
+            proof_purpose: proof_purpose.to_string(),
+            challenge,
+            domain,
+            jws: None,
+        };
+
+        let signature = crypto::sign(&verkey, &_signing_input(document, &proof)?)?;
+        proof.jws = Some(format!("{}..{}", _base64(JWS_HEADER.as_bytes()), _base64(&signature)));
+        Ok(proof)
+    }
+
+    /// Checks that the proof of `document` (without `proof` field) is made by `did:key` `controller`.
+    pub fn verify(&self, document: &Value, controller: &str) -> VcxResult<()> {
+        if self.proof_type != SORTED_JSON_ED25519_SIGNATURE {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidProof, format!("Unsupported Linked Data proof type: {}", self.proof_type)));
+        }
+
+        if self.verification_method.split('#').next() != Some(controller) {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidProof, format!("Linked Data proof is not made by {}", controller)));
+        }
+
+        let jws = self.jws.as_ref()
+            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidProof, "Linked Data proof has no jws"))?;
+
+        let signature = match jws.split("..").collect::<Vec<&str>>().as_slice() {
+            [header, signature] if *header == _base64(JWS_HEADER.as_bytes()) => {
+                base64::decode_config(signature, base64::URL_SAFE_NO_PAD)
+                    .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidProof, format!("Cannot decode Linked Data proof jws: {:?}", err)))?
+            }
+            _ => return Err(VcxError::from_msg(VcxErrorKind::InvalidProof, "Unsupported Linked Data proof jws"))
+        };
+
+        let mut proof_options = self.clone();
+        proof_options.jws = None;
+
+        let verkey = did_key::decode(controller)?;
+        if !crypto::verify(&verkey, &_signing_input(document, &proof_options)?, &signature)? {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidProof, "Linked Data proof signature is invalid"));
+        }
+        Ok(())
+    }
+}
+
+impl LdProofVcDetail {
+    pub fn create(credential: W3cCredential) -> LdProofVcDetail {
+        LdProofVcDetail {
+            credential,
+            options: LdProofVcDetailOptions { proof_type: SORTED_JSON_ED25519_SIGNATURE.to_string() },
+        }
+    }
+
+    pub fn from_str(detail: &str) -> VcxResult<LdProofVcDetail> {
+        serde_json::from_str(detail)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize W3C Credential detail: {:?}", err)))
+    }
+
+    ///
+    /// Checks that the requested credential is the offered one, the holder is only allowed
+    /// to fill in `id` of the subject.
+    ///
+    pub fn ensure_requested(&self, offered: &LdProofVcDetail) -> VcxResult<()> {
+        let mut requested = self.clone();
+        requested.credential.credential_subject.remove("id");
+
+        let mut offered = offered.clone();
+        offered.credential.credential_subject.remove("id");
+
+        if requested != offered {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidCredentialRequest, "Requested W3C Credential does not match the offered one"));
+        }
+        Ok(())
+    }
+}
+
+fn _now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn _base64(data: &[u8]) -> String {
+    base64::encode_config(data, base64::URL_SAFE_NO_PAD)
+}
+
+// hash of the proof options followed by hash of the document, signed as unencoded JWS payload
+fn _signing_input(document: &Value, proof_options: &LdProof) -> VcxResult<Vec<u8>> {
+    let mut data = sha256(_canonicalize(&json!(proof_options))?.as_bytes()).to_vec();
+    data.extend_from_slice(&sha256(_canonicalize(document)?.as_bytes()));
+
+    let mut signing_input = format!("{}.", _base64(JWS_HEADER.as_bytes())).into_bytes();
+    signing_input.extend(data);
+    Ok(signing_input)
+}
+
+fn _canonicalize(value: &Value) -> VcxResult<String> {
+    fn _sorted(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                let sorted: BTreeMap<&String, Value> = map.iter().map(|(key, value)| (key, _sorted(value))).collect();
+                json!(sorted)
+            }
+            Value::Array(values) => Value::Array(values.iter().map(_sorted).collect()),
+            value => value.clone()
+        }
+    }
+
+    serde_json::to_string(&_sorted(value))
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot canonicalize Linked Data document: {:?}", err)))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use utils::libindy::tests::test_setup;
+
+    use super::*;
+
+    pub fn _credential_subject() -> Map<String, Value> {
+        json!({"name": "Alice", "age": "25"}).as_object().unwrap().clone()
+    }
+
+    pub fn _w3c_credential() -> W3cCredential {
+        W3cCredential::create("GJ1SzoWzavQYfNL9XkaJdrQejfztN4XqdsiV4ct3LXKL", _credential_subject()).unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_w3c_credential_serialization() {
+        let credential = _w3c_credential();
+
+        let json = json!(credential);
+        assert_eq!(json["@context"], json!([W3C_CREDENTIALS_CONTEXT, SECURITY_CONTEXT]));
+        assert_eq!(json["type"], json!([VERIFIABLE_CREDENTIAL_TYPE]));
+        assert!(json["issuer"].as_str().unwrap().starts_with("did:key:z"));
+        assert_eq!(json["credentialSubject"], json!({"name": "Alice", "age": "25"}));
+        assert_eq!(credential, W3cCredential::from_str(&json.to_string()).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_w3c_credential_sign_verify_works() {
+        let setup = test_setup::key();
+
+        let credential = W3cCredential::create(&setup.key, _credential_subject()).unwrap()
+            .set_subject_id("did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK")
+            .sign().unwrap();
+        credential.verify().unwrap();
+
+        let mut tampered = credential.clone();
+        tampered.credential_subject.insert(String::from("age"), json!("18"));
+        assert_eq!(tampered.verify().unwrap_err().kind(), VcxErrorKind::InvalidProof);
+
+        let mut unsigned = credential.clone();
+        unsigned.proof = None;
+        assert_eq!(unsigned.verify().unwrap_err().kind(), VcxErrorKind::InvalidProof);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_ld_proof_vc_detail_ensure_requested() {
+        let offered = LdProofVcDetail::create(_w3c_credential());
+
+        let requested = LdProofVcDetail::create(offered.credential.clone().set_subject_id("did:key:holder"));
+        requested.ensure_requested(&offered).unwrap();
+
+        let mut changed = requested.clone();
+        changed.credential.credential_subject.insert(String::from("age"), json!("18"));
+        assert_eq!(changed.ensure_requested(&offered).unwrap_err().kind(), VcxErrorKind::InvalidCredentialRequest);
+    }
+}
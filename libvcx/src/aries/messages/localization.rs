@@ -1,16 +1,118 @@
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+use std::collections::BTreeMap;
+
+use error::prelude::*;
+use settings;
+
+pub static DEFAULT_LOCALE: &str = "en";
+
+///
+/// `~l10n` decorator. `locale` is the language the message is written in, `catalogs` are URIs of message catalogs
+/// and `labels` carry inline translations of field and attribute names: `{"es": {"name": "Nombre"}}`.
+///
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Localization {
-    locale: Locales
+    pub locale: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub catalogs: Option<Vec<String>>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, BTreeMap<String, String>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum Locales {
-    #[serde(rename = "en")]
-    En,
+impl Default for Localization {
+    fn default() -> Localization {
+        Localization {
+            locale: DEFAULT_LOCALE.to_string(),
+            catalogs: None,
+            labels: BTreeMap::new(),
+        }
+    }
 }
 
-impl Default for Locales {
-    fn default() -> Locales {
-        Locales::En
+impl Localization {
+    pub fn from_str(localization: &str) -> VcxResult<Localization> {
+        let localization: Localization = ::serde_json::from_str(localization)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize Localization: {:?}", err)))?;
+
+        if localization.locale.is_empty() {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidJson, "Localization locale is empty"));
+        }
+        Ok(localization)
+    }
+
+    /// Institution default set by `CONFIG_INSTITUTION_L10N`.
+    pub fn institution() -> VcxResult<Option<Localization>> {
+        match settings::get_config_value(settings::CONFIG_INSTITUTION_L10N) {
+            Ok(localization) => Localization::from_str(&localization).map(Some),
+            Err(_) => Ok(None)
+        }
+    }
+
+    /// Keeps labels of `names` only, so that a message does not carry translations unrelated to it.
+    pub fn for_names(mut self, names: &[&str]) -> Localization {
+        for labels in self.labels.values_mut() {
+            labels.retain(|name, _| names.contains(&name.as_str()));
+        }
+        self.labels.retain(|_, labels| !labels.is_empty());
+        self
+    }
+
+    ///
+    /// Label of `name` in `locale`, falling back to the language of the locale (`es` for `es-MX`).
+    /// Names in the message locale are their own labels.
+    ///
+    pub fn label(&self, locale: &str, name: &str) -> Option<String> {
+        let language = locale.split(|c| c == '-' || c == '_').next().unwrap_or(locale);
+
+        self.labels.get(locale)
+            .or_else(|| self.labels.get(language))
+            .and_then(|labels| labels.get(name))
+            .cloned()
+            .or_else(|| if self.locale == locale || self.locale == language { Some(name.to_string()) } else { None })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    pub fn _localization() -> Localization {
+        Localization::from_str(&json!({
+            "locale": "en",
+            "labels": {
+                "es": {"name": "Nombre", "age": "Edad"},
+                "fr": {"name": "Nom"}
+            }
+        }).to_string()).unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_localization_serialization() {
+        assert_eq!(json!({"locale": "en"}), json!(Localization::default()));
+        assert_eq!(Localization::default(), Localization::from_str(r#"{"locale": "en"}"#).unwrap());
+        assert_eq!(Localization::from_str(r#"{"locale": ""}"#).unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_localization_label() {
+        let localization = _localization();
+
+        assert_eq!(Some("Nombre".to_string()), localization.label("es", "name"));
+        assert_eq!(Some("Edad".to_string()), localization.label("es-MX", "age"));
+        assert_eq!(Some("age".to_string()), localization.label("en", "age"));
+        assert_eq!(None, localization.label("fr", "age"));
+        assert_eq!(None, localization.label("de", "name"));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_localization_for_names() {
+        let localization = _localization().for_names(&["age"]);
+
+        assert_eq!(Some("Edad".to_string()), localization.label("es", "age"));
+        assert_eq!(None, localization.label("es", "name"));
+        assert!(!localization.labels.contains_key("fr"));
+    }
+}
@@ -1,7 +1,19 @@
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum MimeType {
-    #[serde(rename = "text/plain")]
-    Plain
+    Plain,
+    Other(String),
+}
+
+impl MimeType {
+    /// Values of textual types are used as they are, others are base64 encoded.
+    pub fn is_text(&self) -> bool {
+        match self {
+            MimeType::Plain => true,
+            MimeType::Other(mime_type) => mime_type.starts_with("text/") || mime_type == "application/json"
+        }
+    }
 }
 
 impl Default for MimeType {
@@ -9,3 +21,61 @@ impl Default for MimeType {
         MimeType::Plain
     }
 }
+
+impl<'a> From<&'a str> for MimeType {
+    fn from(mime_type: &'a str) -> MimeType {
+        match mime_type {
+            "text/plain" => MimeType::Plain,
+            mime_type => MimeType::Other(mime_type.to_string())
+        }
+    }
+}
+
+impl ::std::string::ToString for MimeType {
+    fn to_string(&self) -> String {
+        match self {
+            MimeType::Plain => "text/plain".to_string(),
+            MimeType::Other(mime_type) => mime_type.to_string()
+        }
+    }
+}
+
+impl Serialize for MimeType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for MimeType {
+    fn deserialize<D>(deserializer: D) -> Result<MimeType, D::Error> where D: Deserializer<'de> {
+        let mime_type = String::deserialize(deserializer)?;
+        if mime_type.is_empty() {
+            return Err(de::Error::custom("Empty mime type"));
+        }
+        Ok(MimeType::from(mime_type.as_str()))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_mime_type_serialization() {
+        assert_eq!(json!(MimeType::Plain), json!("text/plain"));
+        assert_eq!(json!(MimeType::Other("image/png".to_string())), json!("image/png"));
+
+        assert_eq!(::serde_json::from_str::<MimeType>(r#""text/plain""#).unwrap(), MimeType::Plain);
+        assert_eq!(::serde_json::from_str::<MimeType>(r#""image/png""#).unwrap(), MimeType::Other("image/png".to_string()));
+        assert!(::serde_json::from_str::<MimeType>(r#""""#).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_mime_type_is_text() {
+        assert!(MimeType::Plain.is_text());
+        assert!(MimeType::from("text/html").is_text());
+        assert!(!MimeType::from("image/png").is_text());
+    }
+}
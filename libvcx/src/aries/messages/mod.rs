@@ -2,6 +2,9 @@
 pub mod a2a;
 #[macro_use]
 pub mod ack;
+#[macro_use]
+pub mod timing;
+pub mod signature;
 pub mod connection;
 pub mod error;
 pub mod forward;
@@ -13,4 +16,6 @@ pub mod proof_presentation;
 pub mod discovery;
 pub mod trust_ping;
 pub mod basic_message;
+pub mod invite_action;
+pub mod out_of_band;
 pub mod localization;
\ No newline at end of file
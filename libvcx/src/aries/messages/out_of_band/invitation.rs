@@ -0,0 +1,224 @@
+use serde_json;
+
+use error::prelude::*;
+use aries::messages::a2a::{A2AMessage, MessageId};
+use aries::messages::attachment::{AttachmentEncoding, AttachmentId, Attachments};
+use aries::messages::connection::invite::Invitation;
+use aries::messages::connection::service::Service;
+
+pub const OUT_OF_BAND_INVITATION_TYPE: &str = "https://didcomm.org/out-of-band/1.0/invitation";
+pub const CONNECTIONS_PROTOCOL: &str = "https://didcomm.org/connections/1.0";
+const DID_COMMUNICATION_SERVICE_TYPE: &str = "did-communication";
+
+///
+/// Out-of-band invitation carrying a message the receiver answers without establishing a connection,
+/// e.g. presentation request to be answered straight to the service of the invitation.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct OutOfBandInvitation {
+    #[serde(rename = "@type")]
+    pub type_: String,
+    #[serde(rename = "@id")]
+    pub id: MessageId,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub handshake_protocols: Vec<String>,
+    #[serde(rename = "request~attach")]
+    #[serde(default)]
+    pub request_attach: Attachments,
+    #[serde(default)]
+    pub services: Vec<OutOfBandService>,
+}
+
+/// Service of the invitation, either inlined or referenced by public DID.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum OutOfBandService {
+    Inline(InlineService),
+    Did(String),
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InlineService {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    #[serde(default)]
+    pub recipient_keys: Vec<String>,
+    #[serde(default)]
+    pub routing_keys: Vec<String>,
+    pub service_endpoint: String,
+}
+
+impl Default for OutOfBandInvitation {
+    fn default() -> OutOfBandInvitation {
+        OutOfBandInvitation {
+            type_: OUT_OF_BAND_INVITATION_TYPE.to_string(),
+            id: MessageId::new(),
+            label: None,
+            handshake_protocols: Vec::new(),
+            request_attach: Attachments::new(),
+            services: Vec::new(),
+        }
+    }
+}
+
+impl OutOfBandInvitation {
+    pub fn create() -> OutOfBandInvitation {
+        OutOfBandInvitation::default()
+    }
+
+    pub fn from_str(invitation: &str) -> VcxResult<OutOfBandInvitation> {
+        let invitation: OutOfBandInvitation = serde_json::from_str(invitation)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize Out-of-band invitation: {}", err)))?;
+
+        if !invitation.type_.contains("out-of-band/") {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Message of type {} is not Out-of-band invitation", invitation.type_)));
+        }
+
+        Ok(invitation)
+    }
+
+    pub fn set_label(mut self, label: Option<String>) -> OutOfBandInvitation {
+        self.label = label;
+        self
+    }
+
+    /// Asks the receiver to establish connection to the service of the invitation before answering the request.
+    pub fn set_connection_invitation(mut self, invitation: Invitation) -> OutOfBandInvitation {
+        self.handshake_protocols = vec![CONNECTIONS_PROTOCOL.to_string()];
+        self.set_service(Service::create()
+            .set_recipient_keys(invitation.recipient_keys)
+            .set_routing_keys(invitation.routing_keys)
+            .set_service_endpoint(invitation.service_endpoint))
+    }
+
+    pub fn set_request(mut self, request: A2AMessage) -> VcxResult<OutOfBandInvitation> {
+        self.request_attach.add_json_attachment(AttachmentId::OutOfBandRequest, json!(request), AttachmentEncoding::Json)?;
+        Ok(self)
+    }
+
+    pub fn set_service(mut self, service: Service) -> OutOfBandInvitation {
+        self.services.push(OutOfBandService::Inline(InlineService {
+            id: String::from("#inline"),
+            type_: DID_COMMUNICATION_SERVICE_TYPE.to_string(),
+            recipient_keys: service.recipient_keys,
+            routing_keys: service.routing_keys.unwrap_or_default(),
+            service_endpoint: service.service_endpoint,
+        }));
+        self
+    }
+
+    /// Message attached to the invitation.
+    pub fn request(&self) -> VcxResult<A2AMessage> {
+        let request = self.request_attach.content()
+            .map_err(|err| err.extend("Out-of-band invitation does not contain request"))?;
+
+        serde_json::from_str(&request)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize Out-of-band request: {}", err)))
+    }
+
+    /// Connection invitation to the service of the invitation, if the invitation asks for a connection.
+    pub fn connection_invitation(&self) -> Option<Invitation> {
+        if !self.handshake_protocols.iter().any(|protocol| protocol.contains("connections/1.0")) {
+            return None;
+        }
+
+        self.service()
+            .map(|service| Invitation::create()
+                .set_id(self.id.0.clone())
+                .set_label(self.label.clone().unwrap_or_default())
+                .set_recipient_keys(service.recipient_keys)
+                .set_routing_keys(service.routing_keys.unwrap_or_default())
+                .set_service_endpoint(service.service_endpoint))
+    }
+
+    /// First inline service of the invitation, services referenced by DID are not resolved.
+    pub fn service(&self) -> Option<Service> {
+        self.services.iter()
+            .filter_map(|service| match service {
+                OutOfBandService::Inline(service) => Some(service),
+                OutOfBandService::Did(_) => None
+            })
+            .next()
+            .map(|service| Service::create()
+                .set_recipient_keys(service.recipient_keys.clone())
+                .set_routing_keys(service.routing_keys.clone())
+                .set_service_endpoint(service.service_endpoint.clone()))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use aries::messages::connection::invite::tests::_invitation;
+    use aries::messages::connection::service::tests::_service;
+    use aries::messages::proof_presentation::presentation_request::tests::_presentation_request;
+
+    use super::*;
+
+    pub fn _oob_invitation() -> OutOfBandInvitation {
+        OutOfBandInvitation::create()
+            .set_label(Some(String::from("Faber")))
+            .set_request(_presentation_request().to_a2a_message()).unwrap()
+            .set_service(_service())
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_oob_invitation_round_trip() {
+        let invitation = _oob_invitation();
+        let json = json!(invitation);
+
+        assert_eq!(json!(OUT_OF_BAND_INVITATION_TYPE), json["@type"]);
+        assert_eq!(json!("#inline"), json["services"][0]["id"]);
+        assert_eq!(json!("did-communication"), json["services"][0]["type"]);
+
+        let invitation = OutOfBandInvitation::from_str(&json.to_string()).unwrap();
+        assert_eq!(_presentation_request().to_a2a_message(), invitation.request().unwrap());
+        assert_eq!(Some(_service()), invitation.service());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_oob_invitation_without_inline_service() {
+        let invitation = json!({
+            "@type": OUT_OF_BAND_INVITATION_TYPE,
+            "@id": "testid",
+            "services": ["did:sov:LjgpST2rjsoxYegQDRm7EL"]
+        }).to_string();
+
+        let invitation = OutOfBandInvitation::from_str(&invitation).unwrap();
+        assert_eq!(None, invitation.service());
+        assert_eq!(VcxErrorKind::InvalidJson, invitation.request().unwrap_err().kind());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_oob_invitation_with_connection_invitation() {
+        assert_eq!(None, _oob_invitation().connection_invitation());
+
+        let invitation = OutOfBandInvitation::create()
+            .set_label(Some(String::from("Faber")))
+            .set_connection_invitation(_invitation());
+        let json = json!(invitation);
+        assert_eq!(json!([CONNECTIONS_PROTOCOL]), json["handshake_protocols"]);
+
+        let invitation = OutOfBandInvitation::from_str(&json.to_string()).unwrap();
+        let connection_invitation = invitation.connection_invitation().unwrap();
+        assert_eq!(_invitation().recipient_keys, connection_invitation.recipient_keys);
+        assert_eq!(_invitation().routing_keys, connection_invitation.routing_keys);
+        assert_eq!(_invitation().service_endpoint, connection_invitation.service_endpoint);
+        assert_eq!("Faber", connection_invitation.label);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_oob_invitation_rejects_other_messages() {
+        let message = json!({"@type": "https://didcomm.org/connections/1.0/invitation", "@id": "testid"}).to_string();
+        assert_eq!(VcxErrorKind::InvalidJson, OutOfBandInvitation::from_str(&message).unwrap_err().kind());
+    }
+}
@@ -0,0 +1,94 @@
+use aries::messages::attachment::{AttachmentId, Attachments};
+use aries::messages::issuance::format::FormatDescriptor;
+
+pub const INDY_PROOF_REQUEST_FORMAT: &str = "hlindy/proof-req@v2.0";
+pub const INDY_PROOF_FORMAT: &str = "hlindy/proof@v2.0";
+// presentations are signed by `SortedJsonEd25519Signature` (see `w3c::SORTED_JSON_ED25519_SIGNATURE`), which agents
+// answering `dif/presentation-exchange` requests do not make, so the exchange has libvcx formats.
+pub const PRESENTATION_DEFINITION_FORMAT: &str = "libvcx/sorted-json-pe-definitions@v1.0";
+pub const PRESENTATION_SUBMISSION_FORMAT: &str = "libvcx/sorted-json-pe-submission@v1.0";
+
+///
+/// Format of present-proof attachments. It is told by the v2 format descriptors when the message has them,
+/// otherwise by the well-known attachment ids of v1 messages.
+///
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum PresentationFormat {
+    Indy,
+    PresentationExchange,
+}
+
+impl Default for PresentationFormat {
+    fn default() -> PresentationFormat {
+        PresentationFormat::Indy
+    }
+}
+
+impl PresentationFormat {
+    pub fn from_message(formats: &[FormatDescriptor], attachments: &Attachments) -> PresentationFormat {
+        if !formats.is_empty() {
+            return if formats.iter().any(|descriptor| descriptor.format == PRESENTATION_DEFINITION_FORMAT || descriptor.format == PRESENTATION_SUBMISSION_FORMAT) {
+                PresentationFormat::PresentationExchange
+            } else {
+                PresentationFormat::Indy
+            };
+        }
+
+        if attachments.get_by_id(&AttachmentId::PresentationDefinition).is_some() || attachments.get_by_id(&AttachmentId::PresentationSubmission).is_some() {
+            PresentationFormat::PresentationExchange
+        } else {
+            PresentationFormat::Indy
+        }
+    }
+
+    pub fn request_descriptor(&self) -> FormatDescriptor {
+        match self {
+            PresentationFormat::Indy => FormatDescriptor::new(AttachmentId::PresentationRequest, INDY_PROOF_REQUEST_FORMAT),
+            PresentationFormat::PresentationExchange => FormatDescriptor::new(AttachmentId::PresentationDefinition, PRESENTATION_DEFINITION_FORMAT),
+        }
+    }
+
+    pub fn presentation_descriptor(&self) -> FormatDescriptor {
+        match self {
+            PresentationFormat::Indy => FormatDescriptor::new(AttachmentId::Presentation, INDY_PROOF_FORMAT),
+            PresentationFormat::PresentationExchange => FormatDescriptor::new(AttachmentId::PresentationSubmission, PRESENTATION_SUBMISSION_FORMAT),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use aries::messages::attachment::AttachmentEncoding;
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_presentation_format_from_attachments() {
+        let mut attachments = Attachments::new();
+        assert_eq!(PresentationFormat::Indy, PresentationFormat::from_message(&[], &attachments));
+
+        attachments.add_base64_encoded_json_attachment(AttachmentId::PresentationRequest, json!("{}")).unwrap();
+        assert_eq!(PresentationFormat::Indy, PresentationFormat::from_message(&[], &attachments));
+
+        let mut attachments = Attachments::new();
+        attachments.add_json_attachment(AttachmentId::PresentationSubmission, json!({}), AttachmentEncoding::Json).unwrap();
+        assert_eq!(PresentationFormat::PresentationExchange, PresentationFormat::from_message(&[], &attachments));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_presentation_format_from_descriptors() {
+        let mut attachments = Attachments::new();
+        attachments.add_json_attachment(AttachmentId::Other("0".to_string()), json!({}), AttachmentEncoding::Json).unwrap();
+
+        let descriptor = FormatDescriptor { attach_id: "0".to_string(), format: PRESENTATION_DEFINITION_FORMAT.to_string() };
+        assert_eq!(PresentationFormat::PresentationExchange, PresentationFormat::from_message(&[descriptor], &attachments));
+
+        let descriptor = FormatDescriptor { attach_id: "0".to_string(), format: INDY_PROOF_REQUEST_FORMAT.to_string() };
+        assert_eq!(PresentationFormat::Indy, PresentationFormat::from_message(&[descriptor], &attachments));
+
+        assert_eq!(json!({"attach_id": "dif-presentation-definition-0", "format": "libvcx/sorted-json-pe-definitions@v1.0"}),
+                   json!(PresentationFormat::PresentationExchange.request_descriptor()));
+    }
+}
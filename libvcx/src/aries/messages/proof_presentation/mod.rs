@@ -2,6 +2,9 @@ pub mod presentation_proposal;
 pub mod presentation_request;
 pub mod presentation;
 pub mod presentation_ack;
+pub mod request_preview;
+pub mod format;
+pub mod presentation_exchange;
 
 #[cfg(test)]
 pub mod test {
@@ -5,7 +5,11 @@ use messages::proofs::proof_message::ProofMessage;
 use messages::thread::Thread;
 use aries::messages::a2a::{A2AMessage, MessageId};
 use aries::messages::ack::PleaseAck;
-use aries::messages::attachment::{AttachmentId, Attachments};
+use aries::messages::attachment::{AttachmentEncoding, AttachmentId, Attachments};
+use aries::messages::issuance::format::FormatDescriptor;
+use aries::messages::proof_presentation::format::PresentationFormat;
+use aries::messages::proof_presentation::presentation_exchange::VerifiablePresentation;
+use aries::messages::timing::Timing;
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
 pub struct Presentation {
@@ -13,6 +17,9 @@ pub struct Presentation {
     pub id: MessageId,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub comment: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub formats: Vec<FormatDescriptor>,
     #[serde(rename = "presentations~attach")]
     pub presentations_attach: Attachments,
     #[serde(rename = "~thread")]
@@ -20,6 +27,9 @@ pub struct Presentation {
     #[serde(rename = "~please_ack")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub please_ack: Option<PleaseAck>,
+    #[serde(rename = "~timing")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timing: Option<Timing>,
 }
 
 impl Presentation {
@@ -36,10 +46,22 @@ impl Presentation {
         self.presentations_attach.add_base64_encoded_json_attachment(AttachmentId::Presentation, ::serde_json::Value::String(presentations))?;
         Ok(self)
     }
+
+    /// Presents DIF Presentation Exchange submission instead of Indy proof.
+    pub fn set_verifiable_presentation(mut self, presentation: &VerifiablePresentation) -> VcxResult<Presentation> {
+        self.presentations_attach.add_json_attachment(AttachmentId::PresentationSubmission, json!(presentation), AttachmentEncoding::Json)?;
+        self.formats.push(PresentationFormat::PresentationExchange.presentation_descriptor());
+        Ok(self)
+    }
+
+    pub fn format(&self) -> PresentationFormat {
+        PresentationFormat::from_message(&self.formats, &self.presentations_attach)
+    }
 }
 
 please_ack!(Presentation);
 threadlike!(Presentation);
+timing!(Presentation);
 a2a_message!(Presentation);
 
 impl TryInto<Presentation> for ProofMessage {
@@ -85,9 +107,11 @@ pub mod tests {
         Presentation {
             id: MessageId::id(),
             comment: Some(_comment()),
+            formats: vec![],
             presentations_attach: attachment,
             thread: thread(),
             please_ack: Some(PleaseAck {}),
+            timing: None,
         }
     }
 
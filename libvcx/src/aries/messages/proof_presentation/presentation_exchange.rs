@@ -0,0 +1,559 @@
+use regex::Regex;
+use serde_json::{self, Map, Value};
+
+use error::prelude::*;
+use aries::messages::issuance::w3c::{LdProof, SECURITY_CONTEXT, W3C_CREDENTIALS_CONTEXT, W3cCredential};
+
+pub const PRESENTATION_SUBMISSION_CONTEXT: &str = "https://identity.foundation/presentation-exchange/submission/v1";
+pub const VERIFIABLE_PRESENTATION_TYPE: &str = "VerifiablePresentation";
+pub const PRESENTATION_SUBMISSION_TYPE: &str = "PresentationSubmission";
+pub const AUTHENTICATION_PURPOSE: &str = "authentication";
+// claim format of the submitted credentials, they have `SortedJsonEd25519Signature` proofs instead of `ldp_vc` Linked Data ones
+pub const SORTED_JSON_VC_FORMAT: &str = "libvcx_sorted_json_vc";
+
+///
+/// DIF Presentation Exchange (v1) presentation definition: which credentials the verifier asks for.
+/// Constraint filters support the `type`, `const`, `enum`, `pattern`, `minLength`, `maxLength`,
+/// `minimum`, `maximum`, `exclusiveMinimum` and `exclusiveMaximum` keywords, numeric strings are
+/// compared as numbers. A field with any other keyword is never satisfied.
+///
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct PresentationDefinition {
+    pub id: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+    pub input_descriptors: Vec<InputDescriptor>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct InputDescriptor {
+    pub id: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub schema: Vec<InputDescriptorSchema>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub constraints: Option<Constraints>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct InputDescriptorSchema {
+    pub uri: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Constraints {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit_disclosure: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Field {
+    pub path: Vec<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<Value>,
+}
+
+/// `libvcx/sorted-json-pe-definitions@v1.0` attachment of the presentation request.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct PresentationExchangeRequest {
+    pub options: PresentationExchangeOptions,
+    pub presentation_definition: PresentationDefinition,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct PresentationExchangeOptions {
+    pub challenge: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct PresentationSubmission {
+    pub id: String,
+    pub definition_id: String,
+    pub descriptor_map: Vec<DescriptorMapEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct DescriptorMapEntry {
+    pub id: String,
+    pub format: String,
+    pub path: String,
+}
+
+///
+/// `libvcx/sorted-json-pe-submission@v1.0` attachment of the presentation: W3C credentials
+/// of a single `holder` with `authentication` proof over the request challenge made by the holder.
+///
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct VerifiablePresentation {
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+    #[serde(rename = "type")]
+    pub types: Vec<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub holder: Option<String>,
+    #[serde(rename = "verifiableCredential")]
+    pub verifiable_credential: Vec<W3cCredential>,
+    pub presentation_submission: PresentationSubmission,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof: Option<LdProof>,
+}
+
+impl PresentationDefinition {
+    pub fn from_str(definition: &str) -> VcxResult<PresentationDefinition> {
+        let definition: PresentationDefinition = serde_json::from_str(definition)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize Presentation Definition: {:?}", err)))?;
+        definition.validate()?;
+        Ok(definition)
+    }
+
+    /// Checks that the definition asks for something this library can present and verify.
+    pub fn validate(&self) -> VcxResult<()> {
+        if self.input_descriptors.is_empty() {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidJson, "Presentation Definition has no input descriptors"));
+        }
+
+        for (index, descriptor) in self.input_descriptors.iter().enumerate() {
+            if self.input_descriptors[..index].iter().any(|other| other.id == descriptor.id) {
+                return Err(VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Presentation Definition has duplicate input descriptor {}", descriptor.id)));
+            }
+
+            if descriptor.constraints.as_ref().and_then(|constraints| constraints.limit_disclosure.as_ref()).map(String::as_str) == Some("required") {
+                return Err(VcxError::from_msg(VcxErrorKind::ActionNotSupported, format!("Input descriptor {} requires selective disclosure which is not supported", descriptor.id)));
+            }
+
+            for path in descriptor.fields().iter().flat_map(|field| field.path.iter()) {
+                if _parse_path(path).is_none() {
+                    return Err(VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Unsupported field path {} of input descriptor {}", path, descriptor.id)));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl InputDescriptor {
+    pub fn fields(&self) -> Vec<&Field> {
+        self.constraints.as_ref().map(|constraints| constraints.fields.iter().collect()).unwrap_or_default()
+    }
+
+    /// Credential satisfies the schema and all the fields of the descriptor.
+    pub fn matches(&self, credential: &W3cCredential) -> bool {
+        let schema_matches = self.schema.is_empty() || self.schema.iter().any(|schema| _schema_matches(&schema.uri, credential));
+        let credential = json!(credential);
+        schema_matches && self.fields().iter().all(|field| field.matches(&credential))
+    }
+
+    ///
+    /// WQL query preselecting wallet credentials by claims of the credential subject the descriptor
+    /// constrains. It is a superset of matching credentials, check them with `matches`.
+    ///
+    pub fn wallet_query(&self) -> Value {
+        let mut query: Vec<Value> = Vec::new();
+
+        for field in self.fields().into_iter().filter(|field| field.path.len() == 1) {
+            if let Some(claim) = _subject_claim(&field.path[0]) {
+                query.push(_tag_query(&format!("attr::{}::marker", claim), "1"));
+                if let Some(value) = field.filter.as_ref().and_then(|filter| filter.get("const")).and_then(Value::as_str) {
+                    query.push(_tag_query(&format!("attr::{}::value", claim), value));
+                }
+            }
+        }
+
+        match query.len() {
+            0 => json!({}),
+            1 => query.remove(0),
+            _ => json!({"$and": query})
+        }
+    }
+}
+
+impl Field {
+    /// Value at the first path resolvable in `credential` satisfies the filter.
+    pub fn matches(&self, credential: &Value) -> bool {
+        let value = self.path.iter()
+            .filter_map(|path| _resolve_path(credential, path))
+            .next();
+
+        match (value, &self.filter) {
+            (Some(value), Some(filter)) => _satisfies_filter(value, filter),
+            (Some(_), None) => true,
+            (None, _) => false
+        }
+    }
+}
+
+impl PresentationExchangeRequest {
+    pub fn create(presentation_definition: PresentationDefinition, challenge: &str) -> PresentationExchangeRequest {
+        PresentationExchangeRequest {
+            options: PresentationExchangeOptions { challenge: challenge.to_string(), domain: None },
+            presentation_definition,
+        }
+    }
+
+    pub fn from_str(request: &str) -> VcxResult<PresentationExchangeRequest> {
+        serde_json::from_str(request)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize Presentation Exchange request: {:?}", err)))
+    }
+}
+
+impl VerifiablePresentation {
+    /// Presentation of `credentials` submitted for the input descriptors of `definition_id`, paired by descriptor id.
+    pub fn create(definition_id: &str, credentials: Vec<(String, W3cCredential)>) -> VcxResult<VerifiablePresentation> {
+        let mut verifiable_credential: Vec<W3cCredential> = Vec::new();
+        let mut descriptor_map = Vec::new();
+
+        for (descriptor_id, credential) in credentials {
+            let index = match verifiable_credential.iter().position(|presented| presented == &credential) {
+                Some(index) => index,
+                None => {
+                    verifiable_credential.push(credential);
+                    verifiable_credential.len() - 1
+                }
+            };
+
+            descriptor_map.push(DescriptorMapEntry {
+                id: descriptor_id,
+                format: SORTED_JSON_VC_FORMAT.to_string(),
+                path: format!("$.verifiableCredential[{}]", index),
+            });
+        }
+
+        let mut holders = verifiable_credential.iter().map(W3cCredential::subject_id);
+        let holder = holders.next().and_then(|holder| holder);
+        if holder.is_none() || holders.any(|other| other != holder) {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidProofCredentialData, "Presented credentials must be issued to the same subject"));
+        }
+        let holder = holder.map(String::from);
+
+        Ok(VerifiablePresentation {
+            context: vec![W3C_CREDENTIALS_CONTEXT.to_string(), SECURITY_CONTEXT.to_string(), PRESENTATION_SUBMISSION_CONTEXT.to_string()],
+            types: vec![VERIFIABLE_PRESENTATION_TYPE.to_string(), PRESENTATION_SUBMISSION_TYPE.to_string()],
+            holder,
+            verifiable_credential,
+            presentation_submission: PresentationSubmission {
+                id: ::utils::uuid::uuid(),
+                definition_id: definition_id.to_string(),
+                descriptor_map,
+            },
+            proof: None,
+        })
+    }
+
+    pub fn from_str(presentation: &str) -> VcxResult<VerifiablePresentation> {
+        serde_json::from_str(presentation)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize Verifiable Presentation: {:?}", err)))
+    }
+
+    /// Adds `SortedJsonEd25519Signature` `authentication` proof of the holder, its key must be held by the wallet.
+    pub fn sign(mut self, options: &PresentationExchangeOptions) -> VcxResult<VerifiablePresentation> {
+        let holder = self.holder.clone()
+            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidProofCredentialData, "Verifiable Presentation has no holder"))?;
+
+        self.proof = None;
+        let proof = LdProof::create(&json!(self), &holder, AUTHENTICATION_PURPOSE, Some(options.challenge.clone()), options.domain.clone())?;
+
+        self.proof = Some(proof);
+        Ok(self)
+    }
+
+    ///
+    /// Checks the presentation against the request: the holder proof over the request challenge,
+    /// issuer proofs of the credentials, credentials being issued to the holder and every input
+    /// descriptor being satisfied by the credential submitted for it.
+    ///
+    pub fn verify(&self, request: &PresentationExchangeRequest) -> VcxResult<()> {
+        let holder = self.holder.as_ref()
+            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidProof, "Verifiable Presentation has no holder"))?;
+
+        let proof = self.proof.as_ref()
+            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidProof, "Verifiable Presentation has no proof"))?;
+
+        if proof.proof_purpose != AUTHENTICATION_PURPOSE || proof.challenge.as_ref() != Some(&request.options.challenge) ||
+            (request.options.domain.is_some() && proof.domain != request.options.domain) {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidProof, "Verifiable Presentation proof is not made for the request"));
+        }
+
+        let mut document = self.clone();
+        document.proof = None;
+        let document = json!(document);
+        proof.verify(&document, holder)?;
+
+        let definition = &request.presentation_definition;
+        if self.presentation_submission.definition_id != definition.id {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidProof, format!("Presentation submission is made for definition {}", self.presentation_submission.definition_id)));
+        }
+
+        for descriptor in definition.input_descriptors.iter() {
+            let credential = self.presentation_submission.descriptor_map.iter()
+                .find(|entry| entry.id == descriptor.id && entry.format == SORTED_JSON_VC_FORMAT)
+                .and_then(|entry| _resolve_path(&document, &entry.path))
+                .ok_or(VcxError::from_msg(VcxErrorKind::InvalidProof, format!("No credential is submitted for input descriptor {}", descriptor.id)))?;
+
+            let credential: W3cCredential = serde_json::from_value(credential.clone())
+                .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidProof, format!("Invalid credential is submitted for input descriptor {}: {:?}", descriptor.id, err)))?;
+
+            credential.verify()?;
+
+            if credential.subject_id() != Some(holder.as_str()) {
+                return Err(VcxError::from_msg(VcxErrorKind::InvalidProof, format!("Credential submitted for input descriptor {} is not issued to the holder", descriptor.id)));
+            }
+
+            if !descriptor.matches(&credential) {
+                return Err(VcxError::from_msg(VcxErrorKind::InvalidProof, format!("Credential submitted for input descriptor {} does not satisfy it", descriptor.id)));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+// subset of JSONPath: `$.a.b`, `$['a']["b"]` and `$.a[0]`, no wildcards, slices or filters
+fn _parse_path(path: &str) -> Option<Vec<PathSegment>> {
+    lazy_static! {
+        static ref SEGMENT: Regex = Regex::new(r#"^(?:\.([A-Za-z0-9_@$-]+)|\['([^']*)'\]|\["([^"]*)"\]|\[(\d+)\])"#).unwrap();
+    }
+
+    if !path.starts_with('$') {
+        return None;
+    }
+
+    let mut rest = &path[1..];
+    let mut segments = Vec::new();
+
+    while !rest.is_empty() {
+        let captures = SEGMENT.captures(rest)?;
+        let segment = match (captures.get(1).or(captures.get(2)).or(captures.get(3)), captures.get(4)) {
+            (Some(key), _) => PathSegment::Key(key.as_str().to_string()),
+            (None, Some(index)) => PathSegment::Index(index.as_str().parse().ok()?),
+            (None, None) => return None
+        };
+        segments.push(segment);
+        rest = &rest[captures.get(0)?.end()..];
+    }
+
+    Some(segments)
+}
+
+fn _resolve_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    _parse_path(path)?.iter()
+        .fold(Some(value), |value, segment| match segment {
+            PathSegment::Key(key) => value?.get(key.as_str()),
+            PathSegment::Index(index) => value?.get(*index),
+        })
+}
+
+fn _subject_claim(path: &str) -> Option<String> {
+    match _parse_path(path)?.as_slice() {
+        [PathSegment::Key(subject), PathSegment::Key(claim)] if subject == "credentialSubject" && claim != "id" => Some(claim.to_string()),
+        _ => None
+    }
+}
+
+fn _tag_query(tag: &str, value: &str) -> Value {
+    let mut query = Map::new();
+    query.insert(tag.to_string(), Value::String(value.to_string()));
+    Value::Object(query)
+}
+
+// schema uri names a context or a type of the credential, types may be given as `<vocabulary>#<type>`
+fn _schema_matches(uri: &str, credential: &W3cCredential) -> bool {
+    credential.context.iter().any(|context| context == uri) ||
+        credential.types.iter().any(|_type| uri == _type || uri.ends_with(&format!("#{}", _type)))
+}
+
+fn _number(value: &Value) -> Option<f64> {
+    value.as_f64().or_else(|| value.as_str().and_then(|value| value.trim().parse().ok()))
+}
+
+fn _satisfies_filter(value: &Value, filter: &Value) -> bool {
+    let filter = match filter.as_object() {
+        Some(filter) => filter,
+        None => return false
+    };
+
+    filter.iter().all(|(keyword, expected)| {
+        match keyword.as_str() {
+            "type" => match expected.as_str() {
+                Some("string") => value.is_string(),
+                Some("number") => _number(value).is_some(),
+                Some("integer") => _number(value).map(|number| number.fract() == 0.0).unwrap_or(false),
+                Some("boolean") => value.is_boolean(),
+                Some("array") => value.is_array(),
+                Some("object") => value.is_object(),
+                Some("null") => value.is_null(),
+                _ => false
+            },
+            "const" => value == expected,
+            "enum" => expected.as_array().map(|values| values.contains(value)).unwrap_or(false),
+            "pattern" => match (value.as_str(), expected.as_str()) {
+                (Some(value), Some(pattern)) => Regex::new(pattern).map(|pattern| pattern.is_match(value)).unwrap_or(false),
+                _ => false
+            },
+            "minLength" | "maxLength" => match (value.as_str(), expected.as_u64()) {
+                (Some(value), Some(length)) => {
+                    let value_length = value.chars().count() as u64;
+                    if keyword == "minLength" { value_length >= length } else { value_length <= length }
+                }
+                _ => false
+            },
+            "minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum" => match (_number(value), expected.as_f64()) {
+                (Some(value), Some(limit)) => match keyword.as_str() {
+                    "minimum" => value >= limit,
+                    "maximum" => value <= limit,
+                    "exclusiveMinimum" => value > limit,
+                    _ => value < limit,
+                },
+                _ => false
+            },
+            _ => false
+        }
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use utils::libindy::crypto;
+    use utils::devsetup::SetupLibraryWallet;
+    use aries::messages::connection::did_key;
+    use aries::messages::issuance::w3c::tests::_credential_subject;
+
+    use super::*;
+
+    pub fn _presentation_definition() -> PresentationDefinition {
+        serde_json::from_value(json!({
+            "id": "32f54163-7166-48f1-93d8-ff217bdb0653",
+            "input_descriptors": [{
+                "id": "adult",
+                "schema": [{"uri": "https://www.w3.org/2018/credentials#VerifiableCredential"}],
+                "constraints": {
+                    "fields": [
+                        {"path": ["$.credentialSubject.name"], "filter": {"type": "string", "const": "Alice"}},
+                        {"path": ["$.credentialSubject.age", "$.credentialSubject.years"], "filter": {"type": "number", "minimum": 18}}
+                    ]
+                }
+            }]
+        })).unwrap()
+    }
+
+    fn _credential() -> W3cCredential {
+        W3cCredential::create("GJ1SzoWzavQYfNL9XkaJdrQejfztN4XqdsiV4ct3LXKL", _credential_subject()).unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_parse_path() {
+        assert_eq!(Some(vec![PathSegment::Key("credentialSubject".to_string()), PathSegment::Key("name".to_string())]), _parse_path("$.credentialSubject.name"));
+        assert_eq!(Some(vec![PathSegment::Key("credentialSubject".to_string()), PathSegment::Key("given name".to_string())]), _parse_path("$['credentialSubject'][\"given name\"]"));
+        assert_eq!(Some(vec![PathSegment::Key("type".to_string()), PathSegment::Index(0)]), _parse_path("$.type[0]"));
+        assert_eq!(None, _parse_path("$.type[*]"));
+        assert_eq!(None, _parse_path("credentialSubject.name"));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_input_descriptor_matches() {
+        let descriptor = _presentation_definition().input_descriptors.remove(0);
+        assert!(descriptor.matches(&_credential()));
+
+        let mut young = _credential();
+        young.credential_subject.insert(String::from("age"), json!("17"));
+        assert!(!descriptor.matches(&young));
+
+        let mut other = _credential();
+        other.credential_subject.insert(String::from("name"), json!("Bob"));
+        assert!(!descriptor.matches(&other));
+
+        let mut no_age = _credential();
+        no_age.credential_subject.remove("age");
+        assert!(!descriptor.matches(&no_age));
+        no_age.credential_subject.insert(String::from("years"), json!(30));
+        assert!(descriptor.matches(&no_age));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_input_descriptor_wallet_query() {
+        let descriptor = _presentation_definition().input_descriptors.remove(0);
+        assert_eq!(json!({"$and": [{"attr::name::marker": "1"}, {"attr::name::value": "Alice"}]}), descriptor.wallet_query());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_presentation_definition_validate() {
+        _presentation_definition().validate().unwrap();
+
+        let mut definition = _presentation_definition();
+        definition.input_descriptors[0].constraints.as_mut().unwrap().limit_disclosure = Some(String::from("required"));
+        assert_eq!(definition.validate().unwrap_err().kind(), VcxErrorKind::ActionNotSupported);
+
+        let mut definition = _presentation_definition();
+        definition.input_descriptors[0].constraints.as_mut().unwrap().fields[0].path = vec![String::from("$..name")];
+        assert_eq!(definition.validate().unwrap_err().kind(), VcxErrorKind::InvalidJson);
+
+        let mut definition = _presentation_definition();
+        definition.input_descriptors.clear();
+        assert_eq!(definition.validate().unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_verifiable_presentation_sign_verify_works() {
+        let _setup = SetupLibraryWallet::init();
+
+        let issuer_key = crypto::create_key(None).unwrap();
+        let holder = did_key::encode(&crypto::create_key(None).unwrap()).unwrap();
+
+        let credential = W3cCredential::create(&issuer_key, _credential_subject()).unwrap()
+            .set_subject_id(&holder)
+            .sign().unwrap();
+
+        let request = PresentationExchangeRequest::create(_presentation_definition(), "1234567890");
+
+        let presentation = VerifiablePresentation::create(&request.presentation_definition.id, vec![(String::from("adult"), credential.clone())]).unwrap()
+            .sign(&request.options).unwrap();
+        assert_eq!(Some(holder.clone()), presentation.holder);
+        presentation.verify(&request).unwrap();
+
+        let other_request = PresentationExchangeRequest::create(_presentation_definition(), "0987654321");
+        assert_eq!(presentation.verify(&other_request).unwrap_err().kind(), VcxErrorKind::InvalidProof);
+
+        let mut tampered = presentation.clone();
+        tampered.verifiable_credential[0].credential_subject.insert(String::from("age"), json!("30"));
+        assert_eq!(tampered.verify(&request).unwrap_err().kind(), VcxErrorKind::InvalidProof);
+
+        let mut unsatisfied_request = request.clone();
+        unsatisfied_request.presentation_definition.input_descriptors[0].id = String::from("other");
+        assert_eq!(presentation.verify(&unsatisfied_request).unwrap_err().kind(), VcxErrorKind::InvalidProof);
+    }
+}
@@ -1,10 +1,15 @@
 use std::convert::TryInto;
 
 use error::prelude::*;
+use messages::thread::Thread;
 pub use messages::proofs::proof_request::{ProofRequestData, ProofRequestMessage, ProofRequestVersion};
 use aries::messages::a2a::{A2AMessage, MessageId};
-use aries::messages::attachment::{AttachmentId, Attachments};
+use aries::messages::attachment::{AttachmentEncoding, AttachmentId, Attachments};
 use aries::messages::connection::service::Service;
+use aries::messages::issuance::format::FormatDescriptor;
+use aries::messages::proof_presentation::format::PresentationFormat;
+use aries::messages::proof_presentation::presentation_exchange::PresentationExchangeRequest;
+use aries::messages::timing::Timing;
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
 pub struct PresentationRequest {
@@ -12,11 +17,20 @@ pub struct PresentationRequest {
     pub id: MessageId,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub comment: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub formats: Vec<FormatDescriptor>,
     #[serde(rename = "request_presentations~attach")]
     pub request_presentations_attach: Attachments,
     #[serde(rename = "~service")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub service: Option<Service>,
+    #[serde(rename = "~timing")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timing: Option<Timing>,
+    #[serde(rename = "~thread")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread: Option<Thread>,
 }
 
 impl PresentationRequest {
@@ -39,10 +53,41 @@ impl PresentationRequest {
         Ok(self)
     }
 
+    /// Requests DIF Presentation Exchange submission instead of Indy proof.
+    pub fn set_presentation_exchange_request(mut self, request: &PresentationExchangeRequest) -> VcxResult<PresentationRequest> {
+        self.request_presentations_attach.add_json_attachment(AttachmentId::PresentationDefinition, json!(request), AttachmentEncoding::Json)?;
+        self.formats.push(PresentationFormat::PresentationExchange.request_descriptor());
+        Ok(self)
+    }
+
+    pub fn format(&self) -> PresentationFormat {
+        PresentationFormat::from_message(&self.formats, &self.request_presentations_attach)
+    }
+
     pub fn set_service(mut self, service: Option<Service>) -> Self {
         self.service = service;
         self
     }
+
+    /// Request superseding an earlier one continues its thread.
+    pub fn set_thread_id(mut self, id: &str) -> Self {
+        self.thread = Some(Thread::new().set_thid(id.to_string()));
+        self
+    }
+
+    /// Thread of the exchange, started by the first request and kept by the requests superseding it.
+    pub fn thread_id(&self) -> String {
+        self.thread.as_ref()
+            .and_then(|thread| thread.thid.clone())
+            .unwrap_or_else(|| self.id.0.clone())
+    }
+
+    /// Attached request data, Indy proof request only.
+    pub fn request_data(&self) -> VcxResult<PresentationRequestData> {
+        ::serde_json::from_str(&self.request_presentations_attach.content()?)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize Presentation Request data: {}", err)))
+    }
+
     pub fn to_json(&self) -> VcxResult<String> {
         serde_json::to_string(self)
             .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot serialize PresentationRequest: {}", err)))
@@ -50,6 +95,7 @@ impl PresentationRequest {
 }
 
 a2a_message!(PresentationRequest);
+timing!(PresentationRequest);
 
 impl TryInto<PresentationRequest> for ProofRequestMessage {
     type Error = VcxError;
@@ -75,7 +121,7 @@ impl TryInto<ProofRequestMessage> for PresentationRequest {
             )?
             .type_version("1.0")?
             .proof_data_version("0.1")?
-            .set_thread_id(self.id.0.clone())?
+            .set_thread_id(self.thread_id())?
             .set_service(self.service)?
             .clone();
 
@@ -119,8 +165,11 @@ pub mod tests {
         PresentationRequest {
             id: MessageId::id(),
             comment: Some(_comment()),
+            formats: vec![],
             request_presentations_attach: _attachment(),
             service: None,
+            timing: None,
+            thread: None,
         }
     }
 
@@ -128,8 +177,11 @@ pub mod tests {
         PresentationRequest {
             id: MessageId::id(),
             comment: Some(_comment()),
+            formats: vec![],
             request_presentations_attach: _attachment(),
             service: Some(_service()),
+            timing: None,
+            thread: None,
         }
     }
 
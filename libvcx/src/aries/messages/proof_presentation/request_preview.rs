@@ -0,0 +1,273 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use error::prelude::*;
+use messages::proofs::proof_request::{NonRevokedInterval, ProofRequestData, Restrictions};
+use aries::messages::proof_presentation::presentation_request::PresentationRequest;
+
+/// UI friendly view of a presentation request.
+/// Items are ordered by referent and every item carries its effective revocation requirement.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct PresentationRequestPreview {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub attributes: Vec<AttributeGroupPreview>,
+    pub predicates: Vec<PredicatePreview>,
+    pub requires_non_revocation: bool,
+}
+
+/// Attributes which must be disclosed together from a single credential.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct AttributeGroupPreview {
+    pub referent: String,
+    pub names: Vec<String>,
+    pub self_attest_allowed: bool,
+    pub restrictions: Vec<RestrictionPreview>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub non_revoked: Option<NonRevokedInterval>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct PredicatePreview {
+    pub referent: String,
+    pub name: String,
+    pub p_type: String,
+    pub p_value: i32,
+    pub description: String,
+    pub restrictions: Vec<RestrictionPreview>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub non_revoked: Option<NonRevokedInterval>,
+}
+
+/// One alternative of restrictions: a credential is acceptable if it satisfies all conditions of any alternative.
+pub type RestrictionPreview = BTreeMap<String, String>;
+
+impl PresentationRequest {
+    pub fn preview(&self) -> VcxResult<PresentationRequestPreview> {
+        let request_data: ProofRequestData = ::serde_json::from_str(&self.request_presentations_attach.content()?)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize presentation request data: {}", err)))?;
+
+        let mut attributes: Vec<AttributeGroupPreview> = request_data.requested_attributes.iter()
+            .map(|(referent, attr)| Ok(AttributeGroupPreview {
+                referent: referent.to_string(),
+                names: attr.names.clone()
+                    .or(attr.name.clone().map(|name| vec![name]))
+                    .unwrap_or_default(),
+                self_attest_allowed: attr.self_attest_allowed.unwrap_or(attr.restrictions.is_none()),
+                restrictions: _restrictions_preview(&attr.restrictions)?,
+                non_revoked: attr.non_revoked.clone().or(request_data.non_revoked.clone()),
+            }))
+            .collect::<VcxResult<_>>()?;
+        attributes.sort_by(|a, b| a.referent.cmp(&b.referent));
+
+        let mut predicates: Vec<PredicatePreview> = request_data.requested_predicates.iter()
+            .map(|(referent, predicate)| Ok(PredicatePreview {
+                referent: referent.to_string(),
+                name: predicate.name.clone(),
+                p_type: predicate.p_type.clone(),
+                p_value: predicate.p_value,
+                description: format!("{} {} {}", predicate.name, _predicate_type_description(&predicate.p_type), predicate.p_value),
+                restrictions: _restrictions_preview(&predicate.restrictions)?,
+                non_revoked: predicate.non_revoked.clone().or(request_data.non_revoked.clone()),
+            }))
+            .collect::<VcxResult<_>>()?;
+        predicates.sort_by(|a, b| a.referent.cmp(&b.referent));
+
+        let requires_non_revocation = attributes.iter().any(|attr| attr.non_revoked.is_some()) ||
+            predicates.iter().any(|predicate| predicate.non_revoked.is_some());
+
+        Ok(PresentationRequestPreview {
+            name: request_data.name,
+            comment: self.comment.clone(),
+            attributes,
+            predicates,
+            requires_non_revocation,
+        })
+    }
+}
+
+fn _predicate_type_description(p_type: &str) -> &str {
+    match p_type {
+        ">=" => "is greater than or equal to",
+        ">" => "is greater than",
+        "<=" => "is less than or equal to",
+        "<" => "is less than",
+        other => other
+    }
+}
+
+fn _restrictions_preview(restrictions: &Option<Restrictions>) -> VcxResult<Vec<RestrictionPreview>> {
+    match restrictions {
+        None => Ok(Vec::new()),
+        Some(Restrictions::V1(filters)) => {
+            Ok(filters.iter()
+                .map(|filter| _value_to_restriction(&json!(filter)))
+                .filter(|restriction| !restriction.is_empty())
+                .collect())
+        }
+        Some(Restrictions::V2(value)) => _wql_to_restrictions(value)
+    }
+}
+
+// WQL query as alternatives of conditions which all have to hold, `$not` has no such form
+fn _wql_to_restrictions(query: &Value) -> VcxResult<Vec<RestrictionPreview>> {
+    match query {
+        Value::Array(alternatives) => {
+            let mut restrictions = Vec::new();
+            for alternative in alternatives {
+                restrictions.extend(_wql_to_restrictions(alternative)?);
+            }
+            Ok(restrictions)
+        }
+        Value::Object(map) => {
+            let mut restrictions = vec![RestrictionPreview::new()];
+            for (key, value) in map {
+                restrictions = match key.as_str() {
+                    "$or" => _all_of(&restrictions, &_wql_to_restrictions(value)?),
+                    "$and" => {
+                        let conditions = value.as_array()
+                            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidProofRequest, "Value of `$and` restriction must be an array"))?;
+                        for condition in conditions {
+                            restrictions = _all_of(&restrictions, &_wql_to_restrictions(condition)?);
+                        }
+                        restrictions
+                    }
+                    "$not" => return Err(VcxError::from_msg(VcxErrorKind::InvalidProofRequest, "Restrictions using `$not` cannot be previewed")),
+                    _ => _all_of(&restrictions, &[_value_to_restriction(&json!({key.as_str(): value}))]),
+                };
+            }
+            Ok(restrictions)
+        }
+        _ => Ok(Vec::new())
+    }
+}
+
+// alternatives satisfying both sets of alternatives, ones with contradicting conditions are dropped
+fn _all_of(left: &[RestrictionPreview], right: &[RestrictionPreview]) -> Vec<RestrictionPreview> {
+    let mut restrictions = Vec::new();
+    for left in left {
+        for right in right {
+            let contradicts = right.iter().any(|(key, value)| left.get(key).map(|other| other != value).unwrap_or(false));
+            if !contradicts {
+                let mut restriction = left.clone();
+                restriction.extend(right.iter().map(|(key, value)| (key.clone(), value.clone())));
+                restrictions.push(restriction);
+            }
+        }
+    }
+    restrictions
+}
+
+fn _value_to_restriction(value: &Value) -> RestrictionPreview {
+    value.as_object()
+        .map(|map| map.iter()
+            .filter(|(_, value)| !value.is_null())
+            .map(|(key, value)| {
+                let value = match value {
+                    Value::String(value) => value.to_string(),
+                    other => other.to_string()
+                };
+                (key.to_string(), value)
+            })
+            .collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use aries::messages::proof_presentation::presentation_request::PresentationRequestData;
+
+    use super::*;
+
+    fn _presentation_request(requested_attributes: Value, requested_predicates: Value, non_revoked: Option<Value>) -> PresentationRequest {
+        let mut data = PresentationRequestData::default()
+            .set_requested_attributes(requested_attributes.to_string()).unwrap()
+            .set_requested_predicates(requested_predicates.to_string()).unwrap();
+        if let Some(non_revoked) = non_revoked {
+            data = data.set_not_revoked_interval(non_revoked.to_string()).unwrap();
+        }
+
+        PresentationRequest::create()
+            .set_comment("comment".to_string())
+            .set_request_presentations_attach(&data).unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_presentation_request_preview() {
+        let presentation_request = _presentation_request(
+            json!([
+                {"names": ["name", "surname"], "restrictions": {"$or": [{"issuer_did": "V4SGRU86Z58d6TV7PBUe6f"}, {"schema_name": "passport", "cred_def_id": "V4SGRU86Z58d6TV7PBUe6f:3:CL:1:tag"}]}},
+                {"name": "nickname", "self_attest_allowed": true}
+            ]),
+            json!([{"name": "age", "p_type": ">=", "p_value": 18}]),
+            Some(json!({"to": 100})),
+        );
+
+        let preview = presentation_request.preview().unwrap();
+
+        assert_eq!(Some("comment".to_string()), preview.comment);
+        assert!(preview.requires_non_revocation);
+
+        assert_eq!(2, preview.attributes.len());
+        let group = preview.attributes.iter().find(|attr| attr.names.len() == 2).unwrap();
+        assert_eq!(vec!["name".to_string(), "surname".to_string()], group.names);
+        assert!(!group.self_attest_allowed);
+        assert_eq!(2, group.restrictions.len());
+        assert_eq!(Some(&"passport".to_string()), group.restrictions[1].get("schema_name"));
+        assert_eq!(Some(100), group.non_revoked.as_ref().unwrap().to);
+
+        assert_eq!(1, preview.predicates.len());
+        assert_eq!("age is greater than or equal to 18", preview.predicates[0].description);
+        assert!(preview.predicates[0].restrictions.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_presentation_request_preview_translates_and() {
+        let presentation_request = _presentation_request(
+            json!([
+                {"name": "name", "restrictions": {"$and": [
+                    {"schema_name": "passport"},
+                    {"$or": [{"issuer_did": "V4SGRU86Z58d6TV7PBUe6f"}, {"issuer_did": "2hoqvcwupRTUNkXn6ArYzs"}]}
+                ]}}
+            ]),
+            json!([]),
+            None,
+        );
+
+        let preview = presentation_request.preview().unwrap();
+
+        let restrictions = &preview.attributes[0].restrictions;
+        assert_eq!(2, restrictions.len());
+        assert!(restrictions.iter().all(|restriction| restriction.get("schema_name") == Some(&"passport".to_string())));
+        assert_eq!(Some(&"V4SGRU86Z58d6TV7PBUe6f".to_string()), restrictions[0].get("issuer_did"));
+        assert_eq!(Some(&"2hoqvcwupRTUNkXn6ArYzs".to_string()), restrictions[1].get("issuer_did"));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_presentation_request_preview_rejects_not() {
+        let presentation_request = _presentation_request(
+            json!([{"name": "name", "restrictions": {"$not": {"issuer_did": "V4SGRU86Z58d6TV7PBUe6f"}}}]),
+            json!([]),
+            None,
+        );
+
+        assert_eq!(presentation_request.preview().unwrap_err().kind(), VcxErrorKind::InvalidProofRequest);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_presentation_request_preview_without_revocation() {
+        let presentation_request = _presentation_request(json!([{"name": "name"}]), json!([]), None);
+
+        let preview = presentation_request.preview().unwrap();
+
+        assert!(!preview.requires_non_revocation);
+        assert!(preview.attributes[0].self_attest_allowed);
+        assert!(preview.predicates.is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+use base64;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use time;
+
+use error::prelude::*;
+use settings;
+use utils::libindy::crypto;
+use aries::messages::a2a::message_family::MessageFamilies;
+use aries::messages::a2a::message_type::MessageType;
+
+const TIMESTAMP_LEN: usize = 8;
+
+///
+/// `ed25519Sha512_single` signature of a message field (`<field>~sig`).
+/// `sig_data` is a 64-bit big-endian unix timestamp followed by the json of the signed field.
+///
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct FieldSignature {
+    #[serde(rename = "@type")]
+    pub msg_type: MessageType,
+    pub signature: String,
+    pub sig_data: String,
+    pub signer: String,
+}
+
+impl FieldSignature {
+    pub fn sign<T: Serialize>(value: &T, key: &str) -> VcxResult<FieldSignature> {
+        let mut sig_data = _now().to_be_bytes().to_vec();
+
+        sig_data.extend(json!(value).to_string().as_bytes());
+
+        let signature = crypto::sign(key, &sig_data)?;
+
+        Ok(FieldSignature {
+            signature: base64::encode_config(&signature, base64::URL_SAFE),
+            sig_data: base64::encode_config(&sig_data, base64::URL_SAFE),
+            signer: key.to_string(),
+            ..Default::default()
+        })
+    }
+
+    /// Checks that the field was signed by `key`, returns the signed data without the timestamp.
+    pub fn verify(&self, key: &str) -> VcxResult<Vec<u8>> {
+        if self.signer != key {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Field signature signer {} does not match expected key {}", self.signer, key)));
+        }
+
+        let signature = _decode(&self.signature)?;
+        let sig_data = _decode(&self.sig_data)?;
+
+        if !crypto::verify(key, &sig_data, &signature)? {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidJson, "Field signature is invalid"));
+        }
+
+        if sig_data.len() < TIMESTAMP_LEN {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidJson, "Field signature data does not contain timestamp"));
+        }
+
+        Ok(sig_data[TIMESTAMP_LEN..].to_vec())
+    }
+
+    /// Unix time the field was signed at.
+    pub fn timestamp(&self) -> VcxResult<u64> {
+        let sig_data = _decode(&self.sig_data)?;
+
+        if sig_data.len() < TIMESTAMP_LEN {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidJson, "Field signature data does not contain timestamp"));
+        }
+
+        let mut timestamp = [0u8; TIMESTAMP_LEN];
+        timestamp.copy_from_slice(&sig_data[..TIMESTAMP_LEN]);
+        Ok(u64::from_be_bytes(timestamp))
+    }
+
+    ///
+    /// Rejects signatures dated ahead of the local clock by more than `CONFIG_SIGNATURE_CLOCK_SKEW`
+    /// and, when `CONFIG_SIGNATURE_MAX_AGE` is set, signatures older than that.
+    ///
+    pub fn check_freshness(&self) -> VcxResult<()> {
+        _check_freshness(self.timestamp()?, _now(), settings::get_signature_clock_skew(), settings::get_signature_max_age())
+    }
+
+    /// Verifies the signature and its freshness and parses the signed field.
+    pub fn decode<T: DeserializeOwned>(&self, key: &str) -> VcxResult<T> {
+        let data = self.verify(key)?;
+        self.check_freshness()?;
+
+        ::serde_json::from_slice(&data)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize signed field: {:?}", err)))
+    }
+}
+
+impl Default for FieldSignature {
+    fn default() -> FieldSignature {
+        FieldSignature {
+            msg_type: MessageType::build(MessageFamilies::Signature, "ed25519Sha512_single"),
+            signature: String::new(),
+            sig_data: String::new(),
+            signer: String::new(),
+        }
+    }
+}
+
+fn _now() -> u64 {
+    time::get_time().sec as u64
+}
+
+fn _decode(value: &str) -> VcxResult<Vec<u8>> {
+    base64::decode_config(value.as_bytes(), base64::URL_SAFE)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot decode field signature: {:?}", err)))
+}
+
+fn _check_freshness(timestamp: u64, now: u64, clock_skew: u64, max_age: Option<u64>) -> VcxResult<()> {
+    if timestamp > now.saturating_add(clock_skew) {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Field signature timestamp {} is ahead of local time {}", timestamp, now)));
+    }
+
+    if let Some(max_age) = max_age {
+        if timestamp.saturating_add(max_age).saturating_add(clock_skew) < now {
+            return Err(VcxError::from_msg(VcxErrorKind::ExpiredMessage, format!("Field signature timestamp {} is older than {} seconds", timestamp, max_age)));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use utils::libindy::tests::test_setup;
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_field_signature_sign_verify_works() {
+        let setup = test_setup::key();
+
+        let signature = FieldSignature::sign(&json!({"did": "VsKV7grR1BUE29mG2Fm2kX"}), &setup.key).unwrap();
+
+        assert_eq!(json!({"did": "VsKV7grR1BUE29mG2Fm2kX"}), signature.decode::<::serde_json::Value>(&setup.key).unwrap());
+        assert!(signature.timestamp().unwrap() <= _now());
+        assert_eq!(signature.verify("CnEDk9HrMnmiHXEV1WFgbVCRteYnPqsJwrTdcZaNhFVW").unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_field_signature_check_freshness() {
+        _check_freshness(1000, 1000, 300, None).unwrap();
+        _check_freshness(1200, 1000, 300, None).unwrap();
+        _check_freshness(0, 1000, 300, None).unwrap();
+        _check_freshness(600, 1000, 300, Some(100)).unwrap();
+
+        assert_eq!(_check_freshness(1301, 1000, 300, None).unwrap_err().kind(), VcxErrorKind::InvalidJson);
+        assert_eq!(_check_freshness(599, 1000, 300, Some(100)).unwrap_err().kind(), VcxErrorKind::ExpiredMessage);
+    }
+}
@@ -1,3 +1,4 @@
+use error::prelude::*;
 use aries::messages::error::ProblemReport;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -6,6 +7,8 @@ pub enum Status {
     Success,
     Failed(ProblemReport),
     Declined,
+    // the exchange was not finished before its offer or request expired
+    Expired(ProblemReport),
 }
 
 impl Status {
@@ -17,7 +20,24 @@ impl Status {
                 error!("Process Failed: {:?}", err);
                 2
             }
-            Status::Declined => 3
+            Status::Declined => 3,
+            Status::Expired(_) => 2,
         }
     }
-}
\ No newline at end of file
+
+    pub fn problem_report(&self) -> Option<&ProblemReport> {
+        match self {
+            Status::Failed(problem_report) | Status::Expired(problem_report) => Some(problem_report),
+            _ => None
+        }
+    }
+
+    /// Error of an operation unavailable because the exchange did not succeed, carrying the problem
+    /// report the exchange failed with.
+    pub fn to_error(&self, kind: VcxErrorKind, msg: &str) -> VcxError {
+        match self.problem_report() {
+            Some(problem_report) => problem_report.to_error(kind, msg),
+            None => VcxError::from_msg(kind, msg.to_string()),
+        }
+    }
+}
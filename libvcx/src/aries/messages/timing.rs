@@ -0,0 +1,186 @@
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+
+use error::prelude::*;
+
+///
+/// `~timing` decorator, times are ISO 8601 / RFC 3339 UTC timestamps.
+/// The receiver is expected not to continue the protocol once `expires_time` has passed.
+///
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct Timing {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub in_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub out_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stale_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delay_milli: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wait_until_time: Option<String>,
+}
+
+impl Timing {
+    pub fn create() -> Timing {
+        Timing::default()
+    }
+
+    pub fn set_out_time(mut self) -> Timing {
+        self.out_time = Some(_format(Utc::now()));
+        self
+    }
+
+    pub fn set_expires_in(mut self, expires_in_secs: u64) -> Timing {
+        self.expires_time = Some(_format(Utc::now() + Duration::seconds(expires_in_secs as i64)));
+        self
+    }
+
+    pub fn set_expires_time(mut self, expires_time: String) -> Timing {
+        self.expires_time = Some(expires_time);
+        self
+    }
+
+    pub fn set_delay_milli(mut self, delay_milli: u64) -> Timing {
+        self.delay_milli = Some(delay_milli);
+        self
+    }
+
+    /// Fails with `ExpiredMessage` if the message arriving at `arrival_time` expired before it could be processed,
+    /// the receiver has to wait `delay_milli` after the arrival before processing it.
+    pub fn check_not_expired(&self, arrival_time: DateTime<Utc>) -> VcxResult<()> {
+        self.check_not_expired_at(self.processing_time(arrival_time))
+    }
+
+    /// Earliest time the message arriving at `arrival_time` can be processed at, according to `delay_milli`.
+    pub fn processing_time(&self, arrival_time: DateTime<Utc>) -> DateTime<Utc> {
+        arrival_time + Duration::milliseconds(self.delay_milli.unwrap_or(0) as i64)
+    }
+
+    /// Fails with `ExpiredMessage` if `expires_time` had passed at `time`, e.g. when the answer arrived.
+    /// Unparseable `expires_time` is treated as expired.
+    pub fn check_not_expired_at(&self, time: DateTime<Utc>) -> VcxResult<()> {
+        let expires_time = match self.expires_time {
+            Some(ref expires_time) => expires_time,
+            None => return Ok(())
+        };
+
+        let expired = DateTime::parse_from_rfc3339(expires_time)
+            .map(|expires_time| expires_time.with_timezone(&Utc) <= time)
+            .unwrap_or(true);
+
+        if expired {
+            return Err(VcxError::from_msg(VcxErrorKind::ExpiredMessage, format!("Message expired at {}", expires_time)));
+        }
+        Ok(())
+    }
+}
+
+/// Time the message was sent at by `out_time`, messages without it or sent "in the future" are taken as arriving now.
+pub fn arrival_time(timing: Option<&Timing>) -> DateTime<Utc> {
+    let now = Utc::now();
+    timing
+        .and_then(|timing| timing.out_time.as_ref())
+        .and_then(|out_time| DateTime::parse_from_rfc3339(out_time).ok())
+        .map(|out_time| out_time.with_timezone(&Utc))
+        .filter(|out_time| *out_time < now)
+        .unwrap_or(now)
+}
+
+fn _format(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[macro_export]
+macro_rules! timing (($type:ident) => (
+    impl $type {
+        pub fn set_timing(mut self, timing: Timing) -> $type {
+            self.timing = Some(timing);
+            self
+        }
+
+        /// Checks `expires_time` of `~timing` decorator against the arrival time and `delay_milli` of the message,
+        /// messages without the decorator never expire.
+        pub fn check_not_expired(&self) -> VcxResult<()> {
+            match self.timing {
+                Some(ref timing) => timing.check_not_expired(self.arrival_time()),
+                None => Ok(())
+            }
+        }
+
+        /// Checks that the message can still be answered: it had not expired when it could be processed after its arrival,
+        /// nor has it expired since.
+        pub fn check_answerable(&self) -> VcxResult<()> {
+            self.check_not_expired()?;
+            match self.timing {
+                Some(ref timing) => timing.check_not_expired_at(::chrono::Utc::now()),
+                None => Ok(())
+            }
+        }
+
+        /// Time the message was sent at, by `out_time` of `~timing` decorator.
+        pub fn arrival_time(&self) -> ::chrono::DateTime<::chrono::Utc> {
+            ::aries::messages::timing::arrival_time(self.timing.as_ref())
+        }
+    }
+));
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_timing_serialization() {
+        let timing = Timing::create()
+            .set_expires_time(String::from("2019-01-23T18:03:27Z"))
+            .set_delay_milli(1000);
+
+        assert_eq!(json!({"expires_time": "2019-01-23T18:03:27Z", "delay_milli": 1000}), json!(timing));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_timing_check_not_expired() {
+        let now = Utc::now();
+        Timing::create().check_not_expired(now).unwrap();
+        Timing::create().set_out_time().set_expires_in(60).check_not_expired(now).unwrap();
+        Timing::create().set_expires_time(String::from("2100-01-01T00:00:00+01:00")).check_not_expired(now).unwrap();
+
+        let expired = Timing::create().set_expires_time(String::from("2019-01-23T18:03:27Z"));
+        assert_eq!(expired.check_not_expired(now).unwrap_err().kind(), VcxErrorKind::ExpiredMessage);
+
+        let invalid = Timing::create().set_expires_time(String::from("tomorrow"));
+        assert_eq!(invalid.check_not_expired(now).unwrap_err().kind(), VcxErrorKind::ExpiredMessage);
+
+        let arrived_before_expiry = DateTime::parse_from_rfc3339("2019-01-23T18:00:00Z").unwrap().with_timezone(&Utc);
+        expired.check_not_expired(arrived_before_expiry).unwrap();
+        expired.check_not_expired_at(arrived_before_expiry).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_timing_check_not_expired_honors_delay() {
+        let arrival_time = DateTime::parse_from_rfc3339("2019-01-23T18:00:00Z").unwrap().with_timezone(&Utc);
+        let timing = Timing::create().set_expires_time(String::from("2019-01-23T18:00:10Z"));
+
+        timing.clone().set_delay_milli(5_000).check_not_expired(arrival_time).unwrap();
+
+        let delayed_past_expiry = timing.set_delay_milli(10_000);
+        assert_eq!(delayed_past_expiry.check_not_expired(arrival_time).unwrap_err().kind(), VcxErrorKind::ExpiredMessage);
+        assert_eq!(_format(delayed_past_expiry.processing_time(arrival_time)), "2019-01-23T18:00:10Z");
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_arrival_time() {
+        let sent = Timing { out_time: Some(String::from("2019-01-23T18:03:27Z")), ..Timing::default() };
+        assert_eq!(_format(arrival_time(Some(&sent))), "2019-01-23T18:03:27Z");
+
+        let before = Utc::now();
+        let future = Timing { out_time: Some(String::from("2100-01-01T00:00:00Z")), ..Timing::default() };
+        assert!(arrival_time(Some(&future)) >= before);
+        assert!(arrival_time(None) >= before);
+    }
+}
@@ -566,6 +566,9 @@ pub mod test {
 
         let faber_connection_info = faber.connection_info();
         assert!(faber_connection_info["their"]["protocols"].as_array().unwrap().len() > 0);
+        assert!(faber_connection_info["stats"]["messagesSent"].as_u64().unwrap() > 0);
+        assert!(faber_connection_info["stats"]["messagesReceived"].as_u64().unwrap() > 0);
+        assert!(faber_connection_info["stats"]["lastActivity"].is_string());
     }
 
     #[cfg(feature = "pool_tests")]
@@ -0,0 +1,96 @@
+use serde_json::Value;
+use url::Url;
+
+use error::prelude::*;
+use aries::messages::a2a::A2AMessage;
+use aries::messages::connection::service::Service;
+
+pub const DIDCOMM_SCHEME: &str = "didcomm";
+pub const MESSAGE_QUERY_PARAM: &str = "d_m";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DeepLinks {
+    pub didcomm: String,
+    pub https: String,
+}
+
+/// Wraps credential offer or presentation request together with the sender's `~service` decorator into deep links.
+/// `https_base_url` is the fallback page opened when no wallet handles the `didcomm://` scheme.
+pub fn create_deep_links(message: &A2AMessage, service: &Service, https_base_url: &str) -> VcxResult<DeepLinks> {
+    trace!("create_deep_links >>> message: {:?}, service: {:?}, https_base_url: {}", message, service, https_base_url);
+
+    let mut message: Value = json!(message);
+    message["~service"] = json!(service);
+
+    let encoded = base64::encode_config(message.to_string().as_bytes(), base64::URL_SAFE);
+
+    let mut https = Url::parse(https_base_url)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidUrl, format!("Invalid deep link base url: {}", err)))?;
+    https.query_pairs_mut().append_pair(MESSAGE_QUERY_PARAM, &encoded);
+
+    Ok(DeepLinks {
+        didcomm: format!("{}://aries_message?{}={}", DIDCOMM_SCHEME, MESSAGE_QUERY_PARAM, encoded),
+        https: https.into_string(),
+    })
+}
+
+/// Extracts message wrapped into `didcomm://` or `https` deep link.
+pub fn parse_deep_link(link: &str) -> VcxResult<A2AMessage> {
+    trace!("parse_deep_link >>> link: {}", link);
+
+    let url = Url::parse(link)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidUrl, format!("Invalid deep link: {}", err)))?;
+
+    let encoded = url.query_pairs()
+        .find(|(key, _)| key == MESSAGE_QUERY_PARAM)
+        .map(|(_, value)| value.into_owned())
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidUrl, format!("Deep link does not contain `{}` parameter", MESSAGE_QUERY_PARAM)))?;
+
+    let decoded = base64::decode_config(encoded.as_bytes(), base64::URL_SAFE)
+        .or(base64::decode(encoded.as_bytes()))
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::EncodeError, format!("Cannot decode deep link message: {}", err)))?;
+
+    ::serde_json::from_slice(&decoded)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize deep link message: {}", err)))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use aries::messages::connection::service::tests::_service;
+    use aries::messages::proof_presentation::presentation_request::tests::_presentation_request;
+    use utils::devsetup::SetupEmpty;
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_deep_link_round_trip() {
+        let _setup = SetupEmpty::init();
+
+        let request = _presentation_request().set_service(None);
+        let links = create_deep_links(&request.to_a2a_message(), &_service(), "https://example.com/wallet").unwrap();
+
+        assert!(links.didcomm.starts_with("didcomm://"));
+        assert!(links.https.starts_with("https://example.com/wallet?d_m="));
+
+        for link in vec![links.didcomm, links.https] {
+            match parse_deep_link(&link).unwrap() {
+                A2AMessage::PresentationRequest(parsed) => {
+                    assert_eq!(parsed.id, request.id);
+                    assert_eq!(parsed.service, Some(_service()));
+                }
+                other => panic!("Unexpected message: {:?}", other)
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_parse_deep_link_fails_for_invalid_links() {
+        let _setup = SetupEmpty::init();
+
+        assert_eq!(parse_deep_link("not a link").unwrap_err().kind(), VcxErrorKind::InvalidUrl);
+        assert_eq!(parse_deep_link("https://example.com?c_i=abc").unwrap_err().kind(), VcxErrorKind::InvalidUrl);
+        assert_eq!(create_deep_links(&_presentation_request().to_a2a_message(), &_service(), "example").unwrap_err().kind(), VcxErrorKind::InvalidUrl);
+    }
+}
@@ -1,9 +1,13 @@
+use std::borrow::Cow;
+
 use aries::messages::a2a::A2AMessage;
 use aries::messages::connection::did_doc::DidDoc;
 use aries::messages::forward::Forward;
 use error::prelude::*;
+use utils::json::check_json_limits;
 use utils::httpclient::AgencyMockDecrypted;
 use utils::libindy::crypto;
+use utils::secret::SecretBytes;
 
 #[derive(Debug)]
 pub struct EncryptionEnvelope(pub Vec<u8>);
@@ -25,13 +29,14 @@ impl EncryptionEnvelope {
                             pw_verkey: Option<&str>,
                             did_doc: &DidDoc) -> VcxResult<Vec<u8>> {
         let message = match message {
-            A2AMessage::Generic(message_) => message_.to_string(),
-            message => json!(message).to_string()
-        };
+            A2AMessage::Generic(message_) => ::serde_json::to_vec(message_),
+            message => ::serde_json::to_vec(message)
+        }.map(SecretBytes::from)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize A2A message: {}", err)))?;
 
         let receiver_keys = json!(did_doc.recipient_keys()).to_string();
 
-        crypto::pack_message(pw_verkey, &receiver_keys, message.as_bytes())
+        crypto::pack_message(pw_verkey, &receiver_keys, &message)
     }
 
     fn wrap_into_forward_messages(mut message: Vec<u8>,
@@ -55,38 +60,67 @@ impl EncryptionEnvelope {
                          routing_key: &str) -> VcxResult<Vec<u8>> {
         let message = A2AMessage::Forward(Forward::new(to.to_string(), message)?);
 
-        let message = json!(message).to_string();
+        let message = ::serde_json::to_vec(&message)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize Forward message: {}", err)))?;
         let receiver_keys = json!(vec![routing_key]).to_string();
 
-        crypto::pack_message(None, &receiver_keys, message.as_bytes())
+        crypto::pack_message(None, &receiver_keys, &message)
     }
 
     pub fn open(payload: Vec<u8>) -> VcxResult<A2AMessage> {
+        EncryptionEnvelope::open_with_sender(payload).map(|(message, _)| message)
+    }
+
+    /// Opens envelope, returns the message together with the key of its sender if the envelope was authcrypted.
+    pub fn open_with_sender(payload: Vec<u8>) -> VcxResult<(A2AMessage, Option<String>)> {
         trace!("EncryptionEnvelope::open >>> payload: {:?}", payload);
 
-        let message = if AgencyMockDecrypted::has_decrypted_mock_messages() {
+        if AgencyMockDecrypted::has_decrypted_mock_messages() {
             trace!("EncryptionEnvelope::open >>> returning decrypted mock message");
-            AgencyMockDecrypted::get_next_decrypted_message()
-        } else {
-            let unpacked_msg = crypto::unpack_message(&payload)?;
+            return Ok((EncryptionEnvelope::_parse_message(&AgencyMockDecrypted::get_next_decrypted_message())?, None));
+        }
 
-            let _message: ::serde_json::Value = ::serde_json::from_slice(unpacked_msg.as_slice())
-                .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize message: {}", err)))?;
+        let unpacked_msg = crypto::unpack_message(&payload)?;
+        let unpacked: UnpackedEnvelope = ::serde_json::from_slice(&unpacked_msg)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot find `message` field on response: {}", err)))?;
+        Ok((EncryptionEnvelope::_parse_message(&unpacked.message)?, unpacked.sender_verkey))
+    }
 
-            _message["message"].as_str()
-                .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, "Cannot find `message` field"))?.to_string()
-        };
+    /// Opens authcrypted envelope, returns the message together with the key of its sender.
+    pub fn open_authenticated(payload: &[u8]) -> VcxResult<(A2AMessage, String)> {
+        trace!("EncryptionEnvelope::open_authenticated >>> payload length: {}", payload.len());
+
+        let unpacked_msg = crypto::unpack_message(payload)?;
+        let unpacked: UnpackedEnvelope = ::serde_json::from_slice(&unpacked_msg)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize unpacked envelope: {}", err)))?;
+
+        let sender_verkey = unpacked.sender_verkey
+            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidMessagePack, "Message is not authcrypted, sender is unknown"))?;
+
+        Ok((EncryptionEnvelope::_parse_message(&unpacked.message)?, sender_verkey))
+    }
+
+    // limits apply to the unpacked message, the envelope is larger by its encryption and encoding
+    fn _parse_message(message: &str) -> VcxResult<A2AMessage> {
+        check_json_limits(message.as_bytes())?;
         // if ::std::env::var("VCX_LOG_DECRYPTED_MESSAGES").unwrap_or("true".to_string()) == "true"
         // {
         //     warn!("Raw decrypted message: {}", message);
         // }
 
-        Ok(::serde_json::from_str(&message)
+        Ok(::serde_json::from_str(message)
             .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize A2A message: {}", err)))?
         )
     }
 }
 
+#[derive(Deserialize)]
+struct UnpackedEnvelope<'a> {
+    #[serde(borrow)]
+    message: Cow<'a, str>,
+    sender_verkey: Option<String>,
+}
+
 #[cfg(test)]
 pub mod tests {
     use aries::messages::ack::tests::_ack;
@@ -162,4 +196,26 @@ pub mod tests {
 
         assert_eq!(ack, EncryptionEnvelope::open(message_2).unwrap());
     }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_encryption_envelope_limits_unpacked_message() {
+        _setup();
+        let setup = test_setup::key();
+
+        let message = A2AMessage::Ack(_ack());
+        let message_size = ::serde_json::to_string(&message).unwrap().len();
+
+        // envelope is larger than the limit, the message it carries is not
+        ::settings::set_config_value(::settings::CONFIG_MAX_JSON_SIZE, &message_size.to_string());
+        let envelope = EncryptionEnvelope::create(&message, Some(&setup.key), &_did_doc_4()).unwrap();
+        assert!(envelope.0.len() > message_size);
+        assert_eq!(message, EncryptionEnvelope::open(envelope.0).unwrap());
+
+        ::settings::set_config_value(::settings::CONFIG_MAX_JSON_SIZE, &(message_size - 1).to_string());
+        let envelope = EncryptionEnvelope::create(&message, Some(&setup.key), &_did_doc_4()).unwrap();
+        assert_eq!(EncryptionEnvelope::open(envelope.0).unwrap_err().kind(), VcxErrorKind::JsonLimitExceeded);
+
+        ::settings::set_config_value(::settings::CONFIG_MAX_JSON_SIZE, &::settings::DEFAULT_MAX_JSON_SIZE.to_string());
+    }
 }
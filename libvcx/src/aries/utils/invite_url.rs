@@ -0,0 +1,187 @@
+use serde_json::Value;
+use url::Url;
+
+use error::prelude::*;
+use aries::messages::a2a::MessageId;
+use aries::messages::connection::invite::Invitation;
+use utils::httpclient::{HttpRequest, RequestOptions};
+use utils::json::check_json_limits;
+use utils::timeout::TimeoutUtils;
+use settings;
+
+pub const CONNECTION_INVITATION_QUERY_PARAM: &str = "c_i";
+pub const OUT_OF_BAND_QUERY_PARAM: &str = "oob";
+
+// URL shorteners answer with a single redirect, a longer chain is not followed
+const MAX_SHORT_URL_REDIRECTS: usize = 3;
+
+/// Builds `<base_url>?c_i=<base64url encoded invitation>` URL, suitable to be rendered as QR code.
+pub fn create_invite_url(invitation: &Invitation, base_url: &str) -> VcxResult<String> {
+    trace!("create_invite_url >>> invitation: {:?}, base_url: {}", invitation, base_url);
+
+    let mut url = Url::parse(base_url)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidUrl, format!("Invalid invitation base url: {}", err)))?;
+
+    let invitation = json!(invitation.to_a2a_message()).to_string();
+    url.query_pairs_mut().append_pair(CONNECTION_INVITATION_QUERY_PARAM, &base64::encode_config(invitation.as_bytes(), base64::URL_SAFE));
+
+    Ok(url.into_string())
+}
+
+/// Extracts connection invitation from `?c_i=` or `?oob=` URL.
+/// Shortened URLs without these parameters are resolved by following at most 3 https redirects,
+/// the invitation may also be served directly as the response body, within configured JSON limits.
+pub fn parse_invite_url(invite_url: &str) -> VcxResult<Invitation> {
+    trace!("parse_invite_url >>> invite_url: {}", invite_url);
+
+    let url = Url::parse(invite_url)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidUrl, format!("Invalid invitation url: {}", err)))?;
+
+    if let Some(invitation) = _invitation_from_query(&url)? {
+        return Ok(invitation);
+    }
+
+    let (resolved_url, body) = _resolve_short_url(&url)?;
+    if let Some(invitation) = _invitation_from_query(&resolved_url)? {
+        return Ok(invitation);
+    }
+
+    check_json_limits(body.as_bytes())?;
+    let invitation: Value = ::serde_json::from_str(&body)
+        .map_err(|_| VcxError::from_msg(VcxErrorKind::InvalidUrl, format!("Url \"{}\" does not contain connection invitation", invite_url)))?;
+    _parse_invitation(invitation)
+}
+
+fn _invitation_from_query(url: &Url) -> VcxResult<Option<Invitation>> {
+    let encoded = url.query_pairs()
+        .find(|(key, _)| key == CONNECTION_INVITATION_QUERY_PARAM || key == OUT_OF_BAND_QUERY_PARAM)
+        .map(|(_, value)| value.into_owned());
+
+    let encoded = match encoded {
+        Some(encoded) => encoded,
+        None => return Ok(None)
+    };
+
+    let decoded = base64::decode_config(encoded.as_bytes(), base64::URL_SAFE)
+        .or(base64::decode(encoded.as_bytes()))
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::EncodeError, format!("Cannot decode invitation: {}", err)))?;
+
+    check_json_limits(&decoded)?;
+    let invitation: Value = ::serde_json::from_slice(&decoded)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize invitation: {}", err)))?;
+
+    _parse_invitation(invitation).map(Some)
+}
+
+fn _parse_invitation(invitation: Value) -> VcxResult<Invitation> {
+    let is_out_of_band = invitation["@type"].as_str()
+        .map(|type_| type_.contains("out-of-band/"))
+        .unwrap_or(false);
+
+    if is_out_of_band {
+        return _from_out_of_band(&invitation);
+    }
+
+    ::serde_json::from_value(invitation)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Invitation has invalid structure: {}", err)))
+}
+
+// Only out-of-band invitations carrying inline DIDComm service can be mapped onto connection invitation
+fn _from_out_of_band(invitation: &Value) -> VcxResult<Invitation> {
+    let service = invitation["services"].as_array()
+        .and_then(|services| services.iter().find(|service| service.is_object()))
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, "Out-of-band invitation does not contain inline service"))?;
+
+    let keys = |field: &str| -> Vec<String> {
+        service[field].as_array()
+            .map(|keys| keys.iter().filter_map(|key| key.as_str().map(String::from)).collect())
+            .unwrap_or_default()
+    };
+
+    let service_endpoint = service["serviceEndpoint"].as_str()
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, "Out-of-band invitation service does not contain `serviceEndpoint`"))?;
+
+    Ok(Invitation::create()
+        .set_id(invitation["@id"].as_str().map(String::from).unwrap_or_else(|| MessageId::new().0))
+        .set_label(invitation["label"].as_str().unwrap_or_default().to_string())
+        .set_recipient_keys(keys("recipientKeys"))
+        .set_routing_keys(keys("routingKeys"))
+        .set_service_endpoint(service_endpoint.to_string()))
+}
+
+fn _resolve_short_url(url: &Url) -> VcxResult<(Url, String)> {
+    debug!("Resolving shortened invitation url: \"{}\"", url);
+
+    // only https redirects are followed by the request, the final location is available on the response
+    let mut response = HttpRequest::get(url.as_str())?
+        .header("Accept", "application/json")
+        .options(RequestOptions::from_settings().timeout(TimeoutUtils::long_timeout()))
+        .follow_redirects(MAX_SHORT_URL_REDIRECTS)
+        .send()
+        .map_err(|failure| failure.into_error().extend("Could not resolve invitation url"))?;
+
+    if !response.is_success() {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidHttpResponse, format!("Resolving invitation url failed with status: {}", response.status)));
+    }
+
+    // body is only ever parsed as invitation JSON, so it is read up to the same size limit
+    let body = response.text_limited(settings::get_max_json_size() as u64)?;
+
+    Ok((response.url, body))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use aries::messages::connection::invite::tests::_invitation;
+    use utils::devsetup::SetupEmpty;
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_invite_url_round_trip() {
+        let _setup = SetupEmpty::init();
+
+        let url = create_invite_url(&_invitation(), "https://example.com/invite").unwrap();
+        assert!(url.starts_with("https://example.com/invite?c_i="));
+        assert_eq!(parse_invite_url(&url).unwrap(), _invitation());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_parse_out_of_band_invite_url() {
+        let _setup = SetupEmpty::init();
+
+        let invitation = _invitation();
+        let oob = json!({
+            "@type": "https://didcomm.org/out-of-band/1.0/invitation",
+            "@id": invitation.id.0,
+            "label": invitation.label,
+            "handshake_protocols": ["https://didcomm.org/connections/1.0"],
+            "services": [{
+                "id": "#inline",
+                "type": "did-communication",
+                "recipientKeys": invitation.recipient_keys,
+                "routingKeys": invitation.routing_keys,
+                "serviceEndpoint": invitation.service_endpoint
+            }]
+        }).to_string();
+        let url = format!("https://example.com?oob={}", base64::encode_config(oob.as_bytes(), base64::URL_SAFE));
+
+        assert_eq!(parse_invite_url(&url).unwrap(), invitation);
+
+        let oob = json!({"@type": "https://didcomm.org/out-of-band/1.0/invitation", "services": ["did:sov:LjgpST2rjsoxYegQDRm7EL"]}).to_string();
+        let url = format!("https://example.com?oob={}", base64::encode_config(oob.as_bytes(), base64::URL_SAFE));
+        assert_eq!(parse_invite_url(&url).unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_invite_url_fails_for_invalid_input() {
+        let _setup = SetupEmpty::init();
+
+        assert_eq!(create_invite_url(&_invitation(), "example").unwrap_err().kind(), VcxErrorKind::InvalidUrl);
+        assert_eq!(parse_invite_url("not a url").unwrap_err().kind(), VcxErrorKind::InvalidUrl);
+        assert_eq!(parse_invite_url("https://example.com?c_i=%%%").unwrap_err().kind(), VcxErrorKind::EncodeError);
+    }
+}
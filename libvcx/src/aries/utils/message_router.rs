@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use aries::messages::a2a::protocol_registry::is_family_enabled;
+use aries::utils::encryption_envelope::EncryptionEnvelope;
+use error::prelude::*;
+
+lazy_static! {
+    static ref ROUTES: Mutex<HashMap<String, Route>> = Default::default();
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectType {
+    IssuerCredential,
+    Credential,
+    Proof,
+    DisclosedProof,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Route {
+    pub object_type: ObjectType,
+    pub handle: u32,
+}
+
+/// Result of applying inbound message to the object handling its thread.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RoutedMessage {
+    pub object_type: ObjectType,
+    pub handle: u32,
+    pub thread_id: String,
+    pub state: u32,
+}
+
+impl Route {
+    fn thread_id(&self) -> Option<String> {
+        match self.object_type {
+            ObjectType::IssuerCredential => ::issuer_credential::get_thread_id(self.handle),
+            ObjectType::Credential => ::credential::get_thread_id(self.handle),
+            ObjectType::Proof => ::proof::get_thread_id(self.handle),
+            ObjectType::DisclosedProof => ::disclosed_proof::get_thread_id(self.handle),
+        }.ok()
+    }
+
+    fn connection_handle(&self) -> VcxResult<Option<u32>> {
+        match self.object_type {
+            ObjectType::IssuerCredential => ::issuer_credential::get_connection_handle(self.handle),
+            ObjectType::Credential => ::credential::get_connection_handle(self.handle),
+            ObjectType::Proof => ::proof::get_connection_handle(self.handle),
+            ObjectType::DisclosedProof => ::disclosed_proof::get_connection_handle(self.handle),
+        }
+    }
+
+    fn update_state(&self, message: &str) -> VcxResult<u32> {
+        let message = Some(message.to_string());
+        match self.object_type {
+            ObjectType::IssuerCredential => ::issuer_credential::update_state(self.handle, message, None)
+                .and_then(|_| ::issuer_credential::get_state(self.handle)),
+            ObjectType::Credential => ::credential::update_state(self.handle, message, None)
+                .and_then(|_| ::credential::get_state(self.handle)),
+            ObjectType::Proof => ::proof::update_state(self.handle, message, None)
+                .and_then(|_| ::proof::get_state(self.handle)),
+            ObjectType::DisclosedProof => ::disclosed_proof::update_state(self.handle, message, None)
+                .and_then(|_| ::disclosed_proof::get_state(self.handle)),
+        }
+    }
+}
+
+fn _all_routes() -> Vec<Route> {
+    let routes = |object_type: ObjectType, handles: Vec<u32>| -> Vec<Route> {
+        handles.into_iter().map(|handle| Route { object_type, handle }).collect()
+    };
+
+    let mut all = routes(ObjectType::IssuerCredential, ::issuer_credential::get_handles());
+    all.extend(routes(ObjectType::Credential, ::credential::get_handles()));
+    all.extend(routes(ObjectType::Proof, ::proof::get_handles()));
+    all.extend(routes(ObjectType::DisclosedProof, ::disclosed_proof::get_handles()));
+    all
+}
+
+// thread ids are assigned by state transitions and objects come and go,
+// so registry is rebuilt from living objects whenever it does not know the thread
+fn _refresh_routes() {
+    let routes: HashMap<String, Route> = _all_routes().into_iter()
+        .filter_map(|route| route.thread_id()
+            .filter(|thread_id| !thread_id.is_empty())
+            .map(|thread_id| (thread_id, route)))
+        .collect();
+
+    *ROUTES.lock().unwrap() = routes;
+}
+
+/// Finds object handling given thread.
+pub fn find_route(thread_id: &str) -> Option<Route> {
+    let cached = ROUTES.lock().unwrap().get(thread_id).cloned();
+
+    if let Some(route) = cached {
+        if route.thread_id().as_ref().map(String::as_str) == Some(thread_id) {
+            return Some(route);
+        }
+    }
+
+    _refresh_routes();
+    ROUTES.lock().unwrap().get(thread_id).cloned()
+}
+
+fn _check_sender(route: &Route, sender_verkey: &str) -> VcxResult<()> {
+    let connection_handle = route.connection_handle()?
+        .ok_or(VcxError::from_msg(VcxErrorKind::UnroutableMessage, format!("{:?} is not bound to connection", route)))?;
+
+    let their_vk = ::connection::get_their_pw_verkey(connection_handle)?;
+    if their_vk != sender_verkey {
+        return Err(VcxError::from_msg(VcxErrorKind::UnroutableMessage,
+                                      format!("Message of thread handled by {:?} is not sent by its connection counterparty", route)));
+    }
+    Ok(())
+}
+
+/**
+Decrypts authcrypted inbound message, finds the object handling its `~thread.thid`
+and updates its state with the message.
+Message has to be sent by the counterparty of the connection the object is bound to
+and belong to enabled protocol.
+ */
+pub fn handle_inbound_message(message: &str) -> VcxResult<RoutedMessage> {
+    trace!("handle_inbound_message >>> message: {}", secret!(message));
+
+    let (a2a_message, sender_verkey) = EncryptionEnvelope::open_authenticated(message.as_bytes())?;
+
+    let family = a2a_message.family();
+    if !is_family_enabled(&family) {
+        return Err(VcxError::from_msg(VcxErrorKind::ActionNotSupported, format!("Protocol {} is disabled", family.id())));
+    }
+
+    let thread_id = a2a_message.thread_id()
+        .ok_or(VcxError::from_msg(VcxErrorKind::UnroutableMessage, "Inbound message does not contain thread id"))?;
+
+    let route = find_route(&thread_id)
+        .ok_or(VcxError::from_msg(VcxErrorKind::UnroutableMessage, format!("No object is handling thread {}", thread_id)))?;
+
+    _check_sender(&route, &sender_verkey)?;
+
+    debug!("handle_inbound_message >>> routing message of thread {} to {:?}", thread_id, route);
+
+    let state = route.update_state(&json!(a2a_message).to_string())?;
+
+    Ok(RoutedMessage { object_type: route.object_type, handle: route.handle, thread_id, state })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use api::VcxStateType;
+    use aries::messages::error::ProblemReport;
+    use connection::tests::build_test_connection_inviter_requested;
+    use utils::constants::{REQUESTED_ATTRS, REQUESTED_PREDICATES};
+    use utils::devsetup::SetupAriesMocks;
+
+    use super::*;
+
+    fn _unpacked(message: &A2AMessage, sender_verkey: Option<&str>) -> String {
+        json!({
+            "message": json!(message).to_string(),
+            "recipient_verkey": "GJ1SzoWzavQYfNL9XkaJdrQejfztN4XqdsiV4ct3LXKL",
+            "sender_verkey": sender_verkey,
+        }).to_string()
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_handle_inbound_message_routes_message_by_thread() {
+        let _setup = SetupAriesMocks::init();
+
+        let connection_handle = build_test_connection_inviter_requested();
+        let handle = ::proof::create_proof("1".to_string(),
+                                           REQUESTED_ATTRS.to_owned(),
+                                           REQUESTED_PREDICATES.to_owned(),
+                                           r#"{"support_revocation":false}"#.to_string(),
+                                           "Optional".to_owned()).unwrap();
+        ::proof::send_proof_request(handle, connection_handle).unwrap();
+        let thread_id = ::proof::get_thread_id(handle).unwrap();
+
+        assert_eq!(find_route(&thread_id), Some(Route { object_type: ObjectType::Proof, handle }));
+
+        let problem_report = ProblemReport::create()
+            .set_comment(String::from("declined"))
+            .set_thread_id(&thread_id)
+            .to_a2a_message();
+
+        let sender_verkey = ::connection::get_their_pw_verkey(connection_handle).unwrap();
+        let routed = handle_inbound_message(&_unpacked(&problem_report, Some(&sender_verkey))).unwrap();
+        assert_eq!(routed, RoutedMessage { object_type: ObjectType::Proof, handle, thread_id, state: VcxStateType::VcxStateNone as u32 });
+        assert_eq!(::proof::get_state(handle).unwrap(), VcxStateType::VcxStateNone as u32);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_handle_inbound_message_fails_for_unknown_thread() {
+        let _setup = SetupAriesMocks::init();
+
+        let problem_report = ProblemReport::create()
+            .set_thread_id("unknown-thread")
+            .to_a2a_message();
+
+        assert_eq!(handle_inbound_message(&_unpacked(&problem_report, Some("key"))).unwrap_err().kind(), VcxErrorKind::UnroutableMessage);
+        assert_eq!(handle_inbound_message("not json").unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_handle_inbound_message_rejects_message_not_sent_by_counterparty() {
+        let _setup = SetupAriesMocks::init();
+
+        let connection_handle = build_test_connection_inviter_requested();
+        let handle = ::proof::create_proof("1".to_string(),
+                                           REQUESTED_ATTRS.to_owned(),
+                                           REQUESTED_PREDICATES.to_owned(),
+                                           r#"{"support_revocation":false}"#.to_string(),
+                                           "Optional".to_owned()).unwrap();
+        ::proof::send_proof_request(handle, connection_handle).unwrap();
+        let thread_id = ::proof::get_thread_id(handle).unwrap();
+
+        let problem_report = ProblemReport::create()
+            .set_thread_id(&thread_id)
+            .to_a2a_message();
+
+        assert_eq!(handle_inbound_message(&json!(problem_report).to_string()).unwrap_err().kind(), VcxErrorKind::InvalidJson);
+        assert_eq!(handle_inbound_message(&_unpacked(&problem_report, None)).unwrap_err().kind(), VcxErrorKind::InvalidMessagePack);
+        assert_eq!(handle_inbound_message(&_unpacked(&problem_report, Some("7y118tRW2EMJn18qs9MY5NJWYW2PLwV5QpaLyfoLHtgF"))).unwrap_err().kind(), VcxErrorKind::UnroutableMessage);
+        assert_eq!(::proof::get_state(handle).unwrap(), VcxStateType::VcxStateOfferSent as u32);
+    }
+}
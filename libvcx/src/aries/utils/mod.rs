@@ -1 +1,4 @@
-pub mod encryption_envelope;
\ No newline at end of file
+pub mod encryption_envelope;
+pub mod deep_link;
+pub mod invite_url;
+pub mod message_router;
@@ -1,23 +1,62 @@
 use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use serde_json;
 
-use aries::handlers::connection::agent_info::AgentInfo;
+use api::VcxStateType;
+use aries::handlers::connection::agent_info::{AgentInfo, EndpointOverride};
 use aries::handlers::connection::connection::{Connection, SmConnectionState};
+use aries::handlers::protocol_history;
 use aries::messages::a2a::A2AMessage;
+use aries::messages::a2a::protocol_registry::SupportedProtocol;
 use aries::messages::connection::did_doc::DidDoc;
 use aries::messages::connection::invite::Invitation as InvitationV3;
+use aries::messages::connection::redirect::Redirect;
+use aries::messages::connection::service::Service;
+use aries::messages::invite_action::invite::{AckOn, Invite as InviteForAction};
+use aries::messages::out_of_band::invitation::OutOfBandInvitation;
+use aries::utils::invite_url;
 use error::prelude::*;
 use messages;
 use messages::get_message::Message;
 use messages::SerializableObjectWithState;
+use migration;
 use settings;
 use settings::ProtocolTypes;
+use utils::agent_context;
 use utils::error;
-use utils::object_cache::ObjectCache;
+use utils::events;
+use utils::handle_token;
+use utils::json::check_json_limits;
+use utils::libindy::pool;
+use utils::metadata::Metadata;
+use utils::object_cache::{ObjectCache, ObjectKeys};
+use utils::persistence;
+use utils::structured_log::{self, Correlation};
+use utils::timeout::TimeoutUtils;
 
 lazy_static! {
-    static ref CONNECTION_MAP: ObjectCache<Connection> = ObjectCache::<Connection>::new("connections-cache");
+    static ref CONNECTION_MAP: ObjectCache<Connection> = ObjectCache::<Connection>::with_index("connections-cache", |connection| ObjectKeys {
+        source_id: Some(connection.get_source_id()),
+        thread_id: None,
+        metadata: connection.get_metadata().clone(),
+    }).with_scope(pool::enter_object_network);
+    static ref PENDING_MESSAGES: Mutex<HashMap<(Option<u32>, u32), Vec<PendingMessage>>> = Default::default();
+    static ref PING_MEASUREMENTS: Mutex<HashMap<(Option<u32>, u32), PingMeasurement>> = Default::default();
+}
+
+const MAX_PENDING_MESSAGES: usize = 100;
+const PENDING_MESSAGE_UID_PREFIX: &str = "pending:";
+const PENDING_MESSAGE_TTL_SECS: u64 = 24 * 60 * 60;
+
+// queued messages are kept per agent, see `agent::Agent`
+fn _pending_key(handle: u32) -> (Option<u32>, u32) {
+    (agent_context::current_agent_id(), handle)
+}
+
+fn _get_mut<F, R>(handle: u32, closure: F) -> VcxResult<R> where F: Fn(&mut Connection) -> VcxResult<R> {
+    persistence::get_mut(&CONNECTION_MAP, events::CONNECTION, handle, Connection::reported_state, Connection::get_thread_id, to_string, closure)
 }
 
 pub fn create_agent_keys(source_id: &str, pw_did: &str, pw_verkey: &str) -> VcxResult<(String, String)> {
@@ -77,22 +116,68 @@ pub fn get_pw_verkey(handle: u32) -> VcxResult<String> {
     })
 }
 
+/// Service through which the counterparty can respond to messages sent out of the connection, e.g. via deep links.
+pub fn get_service(handle: u32) -> VcxResult<Service> {
+    CONNECTION_MAP.get(handle, |connection| {
+        let agent_info = connection.agent_info();
+        Ok(Service::create()
+            .set_service_endpoint(agent_info.agency_endpoint()?)
+            .set_recipient_keys(agent_info.recipient_keys())
+            .set_routing_keys(agent_info.routing_keys()?))
+    })
+}
+
 pub fn get_state(handle: u32) -> u32 {
     trace!("get_state >>> handle = {:?}", handle);
     CONNECTION_MAP.get(handle, |connection| {
-        Ok(connection.state())
+        Ok(connection.reported_state())
     }).unwrap_or(0)
 }
 
+/// Thread of the connection exchange, empty when not known, see `Connection::get_thread_id`.
+pub fn get_thread_id(handle: u32) -> VcxResult<String> {
+    CONNECTION_MAP.get(handle, |connection| {
+        Ok(connection.get_thread_id().unwrap_or_default())
+    })
+}
+
 pub fn get_source_id(handle: u32) -> VcxResult<String> {
     CONNECTION_MAP.get(handle, |connection| {
         Ok(connection.get_source_id())
     })
 }
 
+/// Handle of the connection with given source id, the most recent one when there are more of them.
+pub fn get_handle_by_source_id(source_id: &str) -> VcxResult<u32> {
+    CONNECTION_MAP.find_by_source_id(source_id)
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidConnectionHandle, format!("No connection with source id: {}", source_id)))
+}
+
+/// Handle of the connection whose pairwise verkey is `verkey`.
+pub fn find_handle_by_verkey(verkey: &str) -> Option<u32> {
+    get_handles().into_iter()
+        .find(|handle| CONNECTION_MAP.get(*handle, |connection| Ok(connection.agent_info().pw_vk == verkey)).unwrap_or(false))
+}
+
+/// Sets metadata `key` of the connection to `value`, or removes the key when no value is given.
+pub fn set_metadata(handle: u32, key: &str, value: Option<&str>) -> VcxResult<()> {
+    _get_mut(handle, |connection| connection.set_metadata(key, value))
+}
+
+/// Metadata of the connection as JSON object.
+pub fn get_metadata(handle: u32) -> VcxResult<String> {
+    CONNECTION_MAP.get(handle, |connection| {
+        Ok(json!(connection.get_metadata()).to_string())
+    })
+}
+
+/// Handles of the connections having metadata `key` set to `value`.
+pub fn find_handles_by_metadata(key: &str, value: &str) -> Vec<u32> {
+    CONNECTION_MAP.find_by_metadata(key, value)
+}
+
 fn store_connection(connection: Connection) -> VcxResult<u32> {
-    CONNECTION_MAP.add(connection)
-        .or(Err(VcxError::from(VcxErrorKind::CreateConnection)))
+    persistence::add(&CONNECTION_MAP, events::CONNECTION, connection, to_string)
 }
 
 pub fn create_connection(source_id: &str) -> VcxResult<u32> {
@@ -101,8 +186,30 @@ pub fn create_connection(source_id: &str) -> VcxResult<u32> {
     return store_connection(connection);
 }
 
+/// Creates and connects connection for an out-of-band exchange, which is then handed over to `attach`.
+/// The connection is released when it cannot be connected or attached, so that it does not outlive a failed exchange.
+pub fn create_out_of_band_connection<F>(source_id: &str, attach: F) -> VcxResult<()> where F: FnOnce(u32) -> VcxResult<()> {
+    let connection_handle = create_connection(source_id)?;
+    let result = connect(connection_handle).and_then(|_| attach(connection_handle));
+    if result.is_err() {
+        release(connection_handle).ok();
+    }
+    result
+}
+
+/// Creates connection announcing `endpoint_json` (`{"service_endpoint": "..", "routing_keys": [..]}`)
+/// in its DIDDoc instead of the provisioned agency endpoint.
+/// Messages delivered to such endpoint do not reach agency, they have to be passed to the library by the caller.
+pub fn create_connection_with_endpoint(source_id: &str, endpoint_json: &str) -> VcxResult<u32> {
+    trace!("create_connection_with_endpoint >>> source_id: {}, endpoint_json: {}", source_id, endpoint_json);
+    let endpoint = EndpointOverride::from_json(endpoint_json)?;
+    let connection = Connection::create_with_endpoint(source_id, endpoint);
+    store_connection(connection)
+}
+
 pub fn create_connection_with_invite(source_id: &str, details: &str) -> VcxResult<u32> {
     debug!("create connection {} with invite {}", source_id, details);
+    check_json_limits(details.as_bytes())?;
     if let Some(invitation) = serde_json::from_str::<InvitationV3>(details).ok() {
         let connection = Connection::create_with_invite(source_id, invitation)?;
         store_connection(connection)
@@ -111,76 +218,282 @@ pub fn create_connection_with_invite(source_id: &str, details: &str) -> VcxResul
     }
 }
 
+///
+/// Creates connection from out-of-band invitation asking for a connection. Credential offer attached to the invitation
+/// is turned into credential object, which is returned together with the connection. The offer is answered by
+/// sending credential request over the connection once it is established.
+///
+pub fn create_connection_with_oob_invite(source_id: &str, invitation: &str) -> VcxResult<(u32, Option<u32>)> {
+    debug!("create connection {} with out-of-band invite {}", source_id, invitation);
+    check_json_limits(invitation.as_bytes())?;
+
+    let invitation = OutOfBandInvitation::from_str(invitation)?;
+    let connection_invitation = invitation.connection_invitation()
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, "Out-of-band invitation does not ask for connection"))?;
+
+    let connection = Connection::create_with_invite(source_id, connection_invitation)?;
+    let connection_handle = store_connection(connection)?;
+
+    let credential_handle = match invitation.request() {
+        Ok(A2AMessage::CredentialOffer(offer)) => Some(::credential::credential_create_with_offer(source_id, &json!(offer).to_string())?),
+        _ => None
+    };
+
+    Ok((connection_handle, credential_handle))
+}
+
+/// Creates connection from invitation URL carrying `c_i` or `oob` parameter, or a shortened link redirecting to one.
+pub fn create_connection_with_invite_url(source_id: &str, invite_url: &str) -> VcxResult<u32> {
+    debug!("create connection {} with invite url {}", source_id, invite_url);
+    let invitation = invite_url::parse_invite_url(invite_url)?;
+    let connection = Connection::create_with_invite(source_id, invitation)?;
+    store_connection(connection)
+}
+
 pub fn send_generic_message(connection_handle: u32, msg: &str) -> VcxResult<String> {
+    let _correlation = structured_log::enter(Correlation::new().connection_handle(Some(connection_handle)));
     CONNECTION_MAP.get(connection_handle, |connection| {
         connection.send_generic_message(msg)
     })
 }
 
 pub fn update_state_with_message(handle: u32, message: A2AMessage) -> VcxResult<u32> {
-    CONNECTION_MAP.get_mut(handle, |connection| {
+    _get_mut(handle, |connection| {
         connection.update_state_with_message(&message)?;
         Ok(error::SUCCESS.code_num)
     })
 }
 
 pub fn update_state(handle: u32) -> VcxResult<u32> {
-    CONNECTION_MAP.get_mut(handle, |connection| {
+    _get_mut(handle, |connection| {
         connection.update_state()?;
         Ok(error::SUCCESS.code_num)
     })
 }
 
+///
+/// Deletes pairwise agent of the connection at the agency and releases the connection.
+/// Connection holding messages not processed by any state machine yet (see `get_pending_messages`)
+/// is not deleted, so that the messages are not lost. Messages the outbound queue holds for the counterparty
+/// are deleted with the connection. Pairwise DID and keys stay in the wallet as libindy provides no way to remove them.
+///
 pub fn delete_connection(handle: u32) -> VcxResult<u32> {
+    let pending_messages = get_pending_messages(handle)?;
+    if !pending_messages.is_empty() {
+        return Err(VcxError::from_msg(VcxErrorKind::UndeliveredMessages,
+                                      format!("Connection {} has {} messages which were not processed yet", handle, pending_messages.len())));
+    }
+
+    let old_state = get_state(handle);
+
     CONNECTION_MAP.get_mut(handle, |connection| {
-        connection.delete()?;
-        Ok(error::SUCCESS.code_num)
-    })
-        .map(|_| error::SUCCESS.code_num)
-        .or(Err(VcxError::from(VcxErrorKind::DeleteConnection)))
-        .and(release(handle))
-        .and_then(|_| Ok(error::SUCCESS.code_num))
+        connection.delete()
+    }).or(Err(VcxError::from(VcxErrorKind::DeleteConnection)))?;
+
+    persistence::remove(events::CONNECTION, handle)?;
+    release(handle)?;
+    events::notify(events::CONNECTION, handle, old_state, VcxStateType::VcxStateNone as u32, None);
+
+    Ok(error::SUCCESS.code_num)
 }
 
 pub fn connect(handle: u32) -> VcxResult<Option<String>> {
-    CONNECTION_MAP.get_mut(handle, |connection| {
+    _get_mut(handle, |connection| {
         connection.connect()?;
         Ok(connection.get_invite_details())
     })
 }
 
-pub fn to_string(handle: u32) -> VcxResult<String> {
+/// Answers invitation of connection `handle` by the DIDs of already established connection `existing_handle`
+/// with the same institution, instead of creating another pairwise relationship.
+pub fn redirect_connection(handle: u32, existing_handle: u32) -> VcxResult<()> {
+    let existing = CONNECTION_MAP.get(existing_handle, |connection| Ok(connection.clone()))
+        .or(Err(VcxError::from(VcxErrorKind::InvalidConnectionHandle)))?;
+
+    _get_mut(handle, |connection| {
+        connection.redirect(&existing)
+    })
+}
+
+pub fn get_redirect_details(handle: u32) -> VcxResult<String> {
     CONNECTION_MAP.get(handle, |connection| {
-        let (state, data, source_id) = connection.to_owned().into();
-        let object = SerializableObjectWithState::V3 { data, state, source_id };
+        connection.get_redirect_details()
+    })
+}
 
-        ::serde_json::to_string(&object)
-            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidState, format!("Cannot serialize Connection: {:?}", err)))
+pub fn verify_response_signature(handle: u32) -> VcxResult<()> {
+    CONNECTION_MAP.get(handle, |connection| {
+        connection.verify_response_signature()
     })
 }
 
+/// Handle of the established connection the redirected connection `handle` points to (Inviter side),
+/// `None` if no such connection is loaded or the counterparty key does not match the redirect.
+pub fn find_redirected_connection(handle: u32) -> VcxResult<Option<u32>> {
+    let redirect: Redirect = serde_json::from_str(&get_redirect_details(handle)?)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidRedirectDetail, format!("Cannot deserialize Redirect: {:?}", err)))?;
+
+    let found = get_handles()
+        .into_iter()
+        .filter(|existing| *existing != handle)
+        .find(|existing| {
+            CONNECTION_MAP.get(*existing, |connection| {
+                Ok(connection.state() == VcxStateType::VcxStateAccepted as u32 &&
+                    connection.agent_info().pw_did == redirect.their_did &&
+                    connection.remote_did().ok().as_ref() == Some(&redirect.did) &&
+                    connection.remote_vk().ok().as_ref() == Some(&redirect.verkey))
+            }).unwrap_or(false)
+        });
+    Ok(found)
+}
+
+/// Message queued by `queue_message` which was not processed yet.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct PendingMessage {
+    uid: String,
+    message: A2AMessage,
+    // messages queued by versions not recording the time expire one TTL after they are loaded
+    #[serde(default = "_now")]
+    queued_at: u64,
+}
+
+impl PendingMessage {
+    fn is_expired(&self, now: u64) -> bool {
+        self.queued_at + PENDING_MESSAGE_TTL_SECS <= now
+    }
+
+    fn belongs_to_thread(&self, thread_id: &str) -> bool {
+        self.message.thread_id().map(|thid| thid == thread_id).unwrap_or(false)
+    }
+}
+
+fn _now() -> u64 {
+    ::time::get_time().sec as u64
+}
+
+// "4.0" extends "3.0" format of `SerializableObjectWithState` by queued messages and DIDDoc of the counterparty
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "version")]
+enum SerializedConnection {
+    #[serde(rename = "4.0")]
+    V4 {
+        data: AgentInfo,
+        state: SmConnectionState,
+        source_id: String,
+        #[serde(default)]
+        pending_messages: Vec<PendingMessage>,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        their_did_doc: Option<DidDoc>,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Metadata::is_empty")]
+        metadata: Metadata,
+    }
+}
+
+pub fn to_string(handle: u32) -> VcxResult<String> {
+    let pending_messages = PENDING_MESSAGES.lock().unwrap().get(&_pending_key(handle))
+        .cloned()
+        .unwrap_or_default();
+
+    CONNECTION_MAP.get(handle, |connection| _serialize(connection, pending_messages.clone()))
+}
+
+fn _serialize(connection: &Connection, pending_messages: Vec<PendingMessage>) -> VcxResult<String> {
+    let their_did_doc = connection.their_did_doc();
+    let metadata = connection.get_metadata().clone();
+    let (state, data, source_id) = connection.to_owned().into();
+    let object = SerializedConnection::V4 { data, state, source_id, pending_messages, their_did_doc, metadata };
+
+    ::serde_json::to_string(&object)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidState, format!("Cannot serialize Connection: {:?}", err)))
+}
+
+/// Accepts every format produced by `to_string` so far ("1.0" to "4.0") and connections of the legacy proprietary protocol.
 pub fn from_string(connection_data: &str) -> VcxResult<u32> {
-    let object: SerializableObjectWithState<AgentInfo, SmConnectionState> = ::serde_json::from_str(connection_data)
+    let (connection, pending_messages) = _deserialize(connection_data, !settings::is_strict_aries_protocol_set())?;
+
+    let handle = CONNECTION_MAP.add(connection)?;
+    if !pending_messages.is_empty() {
+        PENDING_MESSAGES.lock().unwrap().insert(_pending_key(handle), pending_messages);
+    }
+    Ok(handle)
+}
+
+/// Converts connection serialized in any format accepted by `from_string` into the current one ("4.0").
+pub fn upgrade_serialized(connection_data: &str) -> VcxResult<String> {
+    let (connection, pending_messages) = _deserialize(connection_data, true)?;
+    _serialize(&connection, pending_messages)
+}
+
+fn _deserialize(connection_data: &str, allow_proprietary: bool) -> VcxResult<(Connection, Vec<PendingMessage>)> {
+    let object: serde_json::Value = ::serde_json::from_str(connection_data)
         .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize Connection: {:?}", err)))?;
 
-    let handle = match object {
-        SerializableObjectWithState::V3 { data, state, source_id } => {
-            CONNECTION_MAP.add((state, data, source_id).into())?
+    match object["version"].as_str() {
+        Some("4.0") => {
+            let SerializedConnection::V4 { data, state, source_id, pending_messages, their_did_doc, metadata } = ::serde_json::from_value(object)
+                .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize Connection: {:?}", err)))?;
+            let mut connection = Connection::from((state, data, source_id)).with_metadata(metadata);
+            if let Some(their_did_doc) = their_did_doc {
+                connection.restore_their_did_doc(their_did_doc);
+            }
+            Ok((connection, pending_messages))
         }
-        _ => return Err(VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Unexpected format of serialized connection: {:?}", object)))
-    };
-    Ok(handle)
+        _ => Ok((_migrate_connection(object, allow_proprietary)?, Vec::new()))
+    }
+}
+
+// "2.0" did not store source id, "1.0" did not store protocol state, so such connections start over from the beginning
+fn _migrate_connection(object: serde_json::Value, allow_proprietary: bool) -> VcxResult<Connection> {
+    if migration::is_proprietary_connection(&object) {
+        if !allow_proprietary {
+            return Err(VcxError::from_msg(VcxErrorKind::ActionNotSupported,
+                                          "Connection of the proprietary protocol cannot be loaded with strict aries protocol_type \"4.0\", convert it by vcx_convert_serialized_object first"));
+        }
+        return migration::convert_proprietary_connection(&object["data"]);
+    }
+
+    let object: SerializableObjectWithState<AgentInfo, SmConnectionState> = ::serde_json::from_value(object)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize Connection: {:?}", err)))?;
+
+    match object {
+        SerializableObjectWithState::V3 { data, state, source_id } => Ok((state, data, source_id).into()),
+        SerializableObjectWithState::V2 { data, state } => Ok((state, data, String::new()).into()),
+        SerializableObjectWithState::V1 { data } => Ok(Connection::create_with_agent_info("", data)),
+    }
+}
+
+/// Serializes the object into a token which can be turned back into a handle by another process via `from_token`.
+pub fn to_token(handle: u32) -> VcxResult<String> {
+    handle_token::seal("connection", &to_string(handle)?)
+}
+
+pub fn from_token(token: &str) -> VcxResult<u32> {
+    from_string(&handle_token::unseal("connection", token)?)
 }
 
 pub fn release(handle: u32) -> VcxResult<()> {
+    PENDING_MESSAGES.lock().unwrap().remove(&_pending_key(handle));
+    PING_MEASUREMENTS.lock().unwrap().remove(&_pending_key(handle));
     CONNECTION_MAP.release(handle)
-        .or(Err(VcxError::from(VcxErrorKind::InvalidConnectionHandle)))
+        .or(Err(VcxError::from(VcxErrorKind::InvalidConnectionHandle)))?;
+    persistence::forget(events::CONNECTION, handle);
+    Ok(())
 }
 
 pub fn release_all() {
+    persistence::forget_all(&CONNECTION_MAP, events::CONNECTION);
+    let agent_id = agent_context::current_agent_id();
+    PENDING_MESSAGES.lock().unwrap().retain(|&(agent, _), _| agent != agent_id);
+    PING_MEASUREMENTS.lock().unwrap().retain(|&(agent, _), _| agent != agent_id);
     CONNECTION_MAP.drain().ok();
 }
 
+pub fn get_handles() -> Vec<u32> {
+    CONNECTION_MAP.handles().unwrap_or_default()
+}
+
 pub fn get_invite_details(handle: u32) -> VcxResult<String> {
     CONNECTION_MAP.get(handle, |connection| {
         return connection.get_invite_details()
@@ -188,6 +501,14 @@ pub fn get_invite_details(handle: u32) -> VcxResult<String> {
     }).or(Err(VcxError::from(VcxErrorKind::InvalidConnectionHandle)))
 }
 
+/// Returns invitation of the connection encoded into `<base_url>?c_i=...` URL.
+pub fn get_invite_url(handle: u32, base_url: &str) -> VcxResult<String> {
+    let invite_details = get_invite_details(handle)?;
+    let invitation: InvitationV3 = serde_json::from_str(&invite_details)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize invitation: {}", err)))?;
+    invite_url::create_invite_url(&invitation, base_url)
+}
+
 impl Into<(SmConnectionState, AgentInfo, String)> for Connection {
     fn into(self) -> (SmConnectionState, AgentInfo, String) {
         (self.state_object(), self.agent_info().to_owned(), self.source_id())
@@ -200,18 +521,113 @@ impl From<(SmConnectionState, AgentInfo, String)> for Connection {
     }
 }
 
+/// Returns messages received by the agency together with all messages queued by `queue_message`.
 pub fn get_messages(handle: u32) -> VcxResult<HashMap<String, A2AMessage>> {
-    CONNECTION_MAP.get_mut(handle, |connection| {
+    _get_messages(handle, |_| true)
+}
+
+/// Returns messages received by the agency together with messages queued by `queue_message` for the thread.
+pub fn get_thread_messages(handle: u32, thread_id: &str) -> VcxResult<HashMap<String, A2AMessage>> {
+    _get_messages(handle, |pending| pending.belongs_to_thread(thread_id))
+}
+
+fn _get_messages<F>(handle: u32, filter: F) -> VcxResult<HashMap<String, A2AMessage>> where F: Fn(&PendingMessage) -> bool {
+    let mut messages = CONNECTION_MAP.get_mut(handle, |connection| {
         connection.get_messages()
-    })
+    })?;
+
+    if let Some(pending) = PENDING_MESSAGES.lock().unwrap().get_mut(&_pending_key(handle)) {
+        _expire_pending_messages(handle, pending);
+        messages.extend(pending.iter()
+            .filter(|pending| filter(pending))
+            .map(|pending| (pending.uid.clone(), pending.message.clone())));
+    }
+    Ok(messages)
 }
 
 pub fn update_message_status(handle: u32, uid: String) -> VcxResult<()> {
+    if uid.starts_with(PENDING_MESSAGE_UID_PREFIX) {
+        if let Some(pending) = PENDING_MESSAGES.lock().unwrap().get_mut(&_pending_key(handle)) {
+            pending.retain(|pending| pending.uid != uid);
+        }
+        return Ok(());
+    }
+
     CONNECTION_MAP.get_mut(handle, |connection| {
         connection.update_message_status(uid.clone())
     })
 }
 
+///
+/// Keeps message given to a state machine which does not belong to the thread of any object yet
+/// (e.g. credential arriving before the holder object was restored).
+/// Queued messages are offered by `get_thread_messages` to the object of their thread only
+/// and expire after `PENDING_MESSAGE_TTL_SECS`. Messages of known threads are not queued,
+/// the object of the thread receives them with its own updates.
+///
+pub fn queue_message(handle: u32, message: A2AMessage) -> VcxResult<()> {
+    if !is_valid_handle(handle) {
+        return Err(VcxError::from(VcxErrorKind::InvalidConnectionHandle));
+    }
+
+    let thread_id = message.thread_id()
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidMessages, "Message without thread cannot be queued"))?;
+    if _is_known_thread(&thread_id) {
+        debug!("connection::queue_message >>> thread {} is known, message is not queued", thread_id);
+        return Ok(());
+    }
+
+    let mut pending_messages = PENDING_MESSAGES.lock().unwrap();
+    let pending = pending_messages.entry(_pending_key(handle)).or_insert_with(Vec::new);
+    _expire_pending_messages(handle, pending);
+
+    if pending.iter().any(|pending| pending.message == message) {
+        return Ok(());
+    }
+    if pending.len() >= MAX_PENDING_MESSAGES {
+        let dropped = pending.remove(0);
+        warn!("connection::queue_message >>> queue of connection {} is full, dropping message {}", handle, dropped.uid);
+    }
+
+    debug!("connection::queue_message >>> queueing message {:?} for connection {}", message, handle);
+    pending.push(PendingMessage {
+        uid: format!("{}{}", PENDING_MESSAGE_UID_PREFIX, ::utils::uuid::uuid()),
+        message,
+        queued_at: _now(),
+    });
+    Ok(())
+}
+
+fn _is_known_thread(thread_id: &str) -> bool {
+    ::issuer_credential::get_handle_by_thread_id(thread_id).is_ok() ||
+        ::credential::get_handle_by_thread_id(thread_id).is_ok() ||
+        ::proof::get_handle_by_thread_id(thread_id).is_ok() ||
+        ::disclosed_proof::get_handle_by_thread_id(thread_id).is_ok()
+}
+
+fn _expire_pending_messages(handle: u32, pending: &mut Vec<PendingMessage>) {
+    let now = _now();
+    let queued = pending.len();
+    pending.retain(|pending| !pending.is_expired(now));
+    if pending.len() < queued {
+        warn!("connection::_expire_pending_messages >>> {} queued messages of connection {} expired", queued - pending.len(), handle);
+    }
+}
+
+/// Messages queued for the connection in the order they were received.
+pub fn get_pending_messages(handle: u32) -> VcxResult<Vec<A2AMessage>> {
+    if !is_valid_handle(handle) {
+        return Err(VcxError::from(VcxErrorKind::InvalidConnectionHandle));
+    }
+
+    Ok(PENDING_MESSAGES.lock().unwrap().get_mut(&_pending_key(handle))
+        .map(|pending| {
+            _expire_pending_messages(handle, pending);
+            pending.iter().map(|pending| pending.message.clone()).collect()
+        })
+        .unwrap_or_default())
+}
+
 pub fn get_message_by_id(handle: u32, msg_id: String) -> VcxResult<A2AMessage> {
     CONNECTION_MAP.get_mut(handle, |connection| {
         connection.get_message_by_id(&msg_id)
@@ -225,14 +641,25 @@ pub fn decode_message(handle: u32, message: Message) -> VcxResult<A2AMessage> {
 }
 
 pub fn send_message(handle: u32, message: A2AMessage) -> VcxResult<()> {
-    trace!("connection::send_message >>>");
+    let _correlation = structured_log::enter(Correlation::new().connection_handle(Some(handle)));
     CONNECTION_MAP.get_mut(handle, |connection| {
         connection.send_message(&message)
-    })
+    })?;
+    protocol_history::message_sent(&message);
+    Ok(())
 }
 
 pub fn send_message_to_self_endpoint(message: A2AMessage, did_doc: &DidDoc) -> VcxResult<()> {
-    Connection::send_message_to_self_endpoint(&message, did_doc)
+    Connection::send_message_to_self_endpoint(&message, did_doc)?;
+    protocol_history::message_sent(&message);
+    Ok(())
+}
+
+/// Checks whether the connection is established, so that messages can be sent over it.
+pub fn is_established(handle: u32) -> bool {
+    CONNECTION_MAP.get(handle, |connection| {
+        Ok(connection.state() == VcxStateType::VcxStateAccepted as u32)
+    }).unwrap_or(false)
 }
 
 pub fn is_v3_connection(connection_handle: u32) -> VcxResult<bool> {
@@ -247,12 +674,112 @@ pub fn send_ping(connection_handle: u32, comment: Option<String>) -> VcxResult<(
     })
 }
 
+/// Answers pings received on completed connection, returns the number of answered pings.
+pub fn answer_pings(handle: u32) -> VcxResult<u32> {
+    CONNECTION_MAP.get(handle, |connection| {
+        connection.answer_pings()
+    })
+}
+
+/// Ping sent by `ping_and_measure` waiting for its response.
+struct PingMeasurement {
+    ping_id: String,
+    sent_at: Instant,
+}
+
+///
+/// Pings connection counterparty requesting response, returns without waiting for it.
+/// Round trip time is provided by `get_ping_rtt` once the response arrives.
+/// Measurement started earlier on the connection is abandoned.
+///
+pub fn ping_and_measure(handle: u32) -> VcxResult<()> {
+    trace!("ping_and_measure >>> handle: {}", handle);
+
+    let ping_id = CONNECTION_MAP.get(handle, |connection| {
+        connection.send_ping_requesting_response(None)
+    })?;
+    PING_MEASUREMENTS.lock().unwrap().insert(_pending_key(handle), PingMeasurement { ping_id, sent_at: Instant::now() });
+    Ok(())
+}
+
+///
+/// Checks agency for response to the ping sent by `ping_and_measure`, returns the round trip time
+/// or `None` when the response has not arrived yet. Response is noticed only when this is called,
+/// so the time is rounded up to the interval the caller polls in.
+/// Measurement fails with NoResponse when no response arrives in the medium timeout.
+///
+pub fn get_ping_rtt(handle: u32) -> VcxResult<Option<Duration>> {
+    trace!("get_ping_rtt >>> handle: {}", handle);
+
+    let (ping_id, sent_at) = PING_MEASUREMENTS.lock().unwrap().get(&_pending_key(handle))
+        .map(|measurement| (measurement.ping_id.clone(), measurement.sent_at))
+        .ok_or(VcxError::from_msg(VcxErrorKind::NotReady, format!("No ping measurement was started on connection {}", handle)))?;
+
+    let response_uid = CONNECTION_MAP.get(handle, |connection| {
+        connection.find_ping_response(&ping_id)
+    })?;
+
+    match response_uid {
+        Some(uid) => {
+            let rtt = sent_at.elapsed();
+            PING_MEASUREMENTS.lock().unwrap().remove(&_pending_key(handle));
+            update_message_status(handle, uid)
+                .unwrap_or_else(|err| warn!("get_ping_rtt >>> cannot update status of ping response: {}", err));
+            trace!("get_ping_rtt <<< rtt: {:?}", rtt);
+            Ok(Some(rtt))
+        }
+        None if sent_at.elapsed() >= TimeoutUtils::medium_timeout() => {
+            PING_MEASUREMENTS.lock().unwrap().remove(&_pending_key(handle));
+            Err(VcxError::from_msg(VcxErrorKind::NoResponse, format!("No response to ping {} received", ping_id)))
+        }
+        None => Ok(None)
+    }
+}
+
+/// Protocols the counterparty disclosed in response to `send_discovery_features`.
+pub fn get_their_supported_protocols(handle: u32) -> VcxResult<Vec<SupportedProtocol>> {
+    CONNECTION_MAP.get(handle, |connection| {
+        connection.get_remote_protocols()
+            .map(|protocols| protocols.into_iter().map(SupportedProtocol::from).collect())
+            .ok_or(VcxError::from_msg(VcxErrorKind::NotReady, "Counterparty has not disclosed its protocols yet"))
+    })
+}
+
 pub fn send_discovery_features(connection_handle: u32, query: Option<String>, comment: Option<String>) -> VcxResult<()> {
     CONNECTION_MAP.get_mut(connection_handle, |connection| {
         connection.send_discovery_features(query.clone(), comment.clone())
     })
 }
 
+/// Sends `invite-action` message asking the counterparty to take action identified by `goal_code`
+/// and to acknowledge the acceptance, returns the sent message.
+pub fn send_invite_action(handle: u32, goal_code: &str) -> VcxResult<String> {
+    let _correlation = structured_log::enter(Correlation::new().connection_handle(Some(handle)));
+    CONNECTION_MAP.get(handle, |connection| {
+        let invite = connection.send_invite_action(goal_code, Some(vec![AckOn::Accept]))?;
+        Ok(json!(invite.to_a2a_message()).to_string())
+    })
+}
+
+fn _parse_invite_action(invite: &str) -> VcxResult<InviteForAction> {
+    serde_json::from_str(invite)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize invite action: {:?}", err)))
+}
+
+pub fn accept_invite_action(handle: u32, invite: &str) -> VcxResult<()> {
+    let invite = _parse_invite_action(invite)?;
+    CONNECTION_MAP.get(handle, |connection| {
+        connection.accept_invite_action(&invite)
+    })
+}
+
+pub fn reject_invite_action(handle: u32, invite: &str, comment: Option<String>) -> VcxResult<()> {
+    let invite = _parse_invite_action(invite)?;
+    CONNECTION_MAP.get(handle, |connection| {
+        connection.reject_invite_action(&invite, comment.clone())
+    })
+}
+
 pub fn get_connection_info(handle: u32) -> VcxResult<String> {
     CONNECTION_MAP.get(handle, |connection| {
         connection.get_connection_info()
@@ -267,8 +794,14 @@ pub mod tests {
     use serde_json::Value;
 
     use api::VcxStateType;
+    use aries::messages::a2a::message_family::MessageFamilies;
+    use aries::messages::connection::invite::tests::_invitation;
+    use aries::messages::discovery::disclose::{Disclose, ProtocolDescriptor};
+    use aries::messages::issuance::credential_offer::tests::_credential_offer;
+    use aries::messages::trust_ping::ping::Ping;
     use messages::get_message::download_messages;
     use messages::MessageStatusCode;
+    use settings::Actors;
     use utils::constants::*;
     use utils::constants;
     use utils::devsetup::*;
@@ -341,6 +874,7 @@ pub mod tests {
         connect(handle).unwrap();
         assert_eq!(get_pw_did(handle).unwrap(), constants::DID);
         assert_eq!(get_pw_verkey(handle).unwrap(), constants::VERKEY);
+        assert_eq!(get_service(handle).unwrap().recipient_keys, vec![constants::VERKEY.to_string()]);
 
         AgencyMockDecrypted::set_next_decrypted_response(constants::GET_MESSAGES_DECRYPTED_RESPONSE);
         AgencyMockDecrypted::set_next_decrypted_message(ARIES_CONNECTION_REQUEST);
@@ -359,6 +893,111 @@ pub mod tests {
         assert!(release(handle).is_err());
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_create_connection_with_endpoint() {
+        let _setup = SetupAriesMocks::init();
+
+        let endpoint = json!({"service_endpoint": "https://edge.example.org/msg", "routing_keys": [constants::VERKEY]}).to_string();
+        let handle = create_connection_with_endpoint("test_create_connection_with_endpoint", &endpoint).unwrap();
+        connect(handle).unwrap();
+
+        let service = get_service(handle).unwrap();
+        assert_eq!(service.service_endpoint, "https://edge.example.org/msg");
+        assert_eq!(service.routing_keys, Some(vec![constants::VERKEY.to_string()]));
+
+        let invitation: InvitationV3 = serde_json::from_str(&get_invite_details(handle).unwrap()).unwrap();
+        assert_eq!(invitation.service_endpoint, "https://edge.example.org/msg");
+
+        let endpoint = json!({"service_endpoint": "not a url"}).to_string();
+        assert_eq!(create_connection_with_endpoint("test", &endpoint).unwrap_err().kind(), VcxErrorKind::InvalidUrl);
+        assert_eq!(create_connection_with_endpoint("test", "{}").unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_pings_are_answered_on_completed_connection() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = build_test_connection_inviter_requested();
+        assert_eq!(ping_and_measure(handle).unwrap_err().kind(), VcxErrorKind::NotReady);
+        assert_eq!(get_ping_rtt(handle).unwrap_err().kind(), VcxErrorKind::NotReady);
+        assert_eq!(answer_pings(handle).unwrap_err().kind(), VcxErrorKind::NotReady);
+
+        update_state_with_message(handle, serde_json::from_str(ARIES_CONNECTION_ACK).unwrap()).unwrap();
+        assert_eq!(get_state(handle), VcxStateType::VcxStateAccepted as u32);
+
+        let ping = Ping::create().request_response().to_a2a_message();
+        AgencyMockDecrypted::set_next_decrypted_response(constants::GET_MESSAGES_DECRYPTED_RESPONSE);
+        AgencyMockDecrypted::set_next_decrypted_message(&json!(ping).to_string());
+        assert_eq!(answer_pings(handle).unwrap(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_ping_and_measure_returns_before_response() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = build_test_connection_inviter_requested();
+        update_state_with_message(handle, serde_json::from_str(ARIES_CONNECTION_ACK).unwrap()).unwrap();
+
+        ping_and_measure(handle).unwrap();
+        assert_eq!(get_ping_rtt(handle).unwrap(), None);
+
+        release(handle).unwrap();
+        assert_eq!(get_ping_rtt(handle).unwrap_err().kind(), VcxErrorKind::NotReady);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_their_supported_protocols() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = build_test_connection_inviter_requested();
+        update_state_with_message(handle, serde_json::from_str(ARIES_CONNECTION_ACK).unwrap()).unwrap();
+        assert_eq!(get_their_supported_protocols(handle).unwrap_err().kind(), VcxErrorKind::NotReady);
+
+        let disclose = Disclose::create()
+            .set_protocols(vec![ProtocolDescriptor { pid: MessageFamilies::PresentProof.id(), roles: Some(vec![Actors::Verifier]) }])
+            .set_thread_id("query-id".to_string());
+        update_state_with_message(handle, disclose.to_a2a_message()).unwrap();
+
+        let protocols = get_their_supported_protocols(handle).unwrap();
+        assert_eq!(protocols, vec![SupportedProtocol {
+            pid: MessageFamilies::PresentProof.id(),
+            family: "present-proof".to_string(),
+            version: "1.0".to_string(),
+            roles: Some(vec![Actors::Verifier]),
+        }]);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_invite_action() {
+        let _setup = SetupAriesMocks::init();
+
+        let inviter = build_test_connection_inviter_invited();
+        assert_eq!(send_invite_action(inviter, "present-proof").unwrap_err().kind(), VcxErrorKind::NotReady);
+
+        let inviter = build_test_connection_inviter_requested();
+        update_state_with_message(inviter, serde_json::from_str(ARIES_CONNECTION_ACK).unwrap()).unwrap();
+        let invite = send_invite_action(inviter, "present-proof").unwrap();
+
+        let message: A2AMessage = serde_json::from_str(&invite).unwrap();
+        match message {
+            A2AMessage::InviteForAction(ref invite) => {
+                assert_eq!(invite.goal_code, "present-proof");
+                assert_eq!(invite.ack_on, Some(vec![AckOn::Accept]));
+            }
+            other => panic!("Unexpected message: {:?}", other)
+        }
+
+        let invitee = from_string(CONNECTION_SM_INVITEE_COMPLETED).unwrap();
+        accept_invite_action(invitee, &invite).unwrap();
+        reject_invite_action(invitee, &invite, Some("not now".to_string())).unwrap();
+        assert_eq!(accept_invite_action(invitee, "{}").unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_create_drop_create() {
@@ -446,7 +1085,10 @@ pub mod tests {
         let reserialized = to_string(handle_conn).unwrap();
         let reserialized_object: Value = serde_json::from_str(&reserialized).unwrap();
 
-        assert_eq!(original_object, reserialized_object);
+        assert_eq!(reserialized_object["version"], json!("4.0"));
+        assert_eq!(original_object["data"], reserialized_object["data"]);
+        assert_eq!(original_object["state"], reserialized_object["state"]);
+        assert_eq!(original_object["source_id"], reserialized_object["source_id"]);
     }
 
     #[test]
@@ -496,6 +1138,78 @@ pub mod tests {
         assert_eq!(first_string, second_string);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_serialize_keeps_pending_messages_and_did_doc() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = from_string(CONNECTION_SM_INVITER_COMPLETED).unwrap();
+        let message = ::aries::messages::ack::tests::_ack().to_a2a_message();
+        queue_message(handle, message.clone()).unwrap();
+
+        let serialized = to_string(handle).unwrap();
+        let object: Value = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(object["version"], json!("4.0"));
+        assert!(object["their_did_doc"].is_object());
+
+        release(handle).unwrap();
+
+        let handle = from_string(&serialized).unwrap();
+        assert_eq!(get_pending_messages(handle).unwrap(), vec![message]);
+        assert_eq!(get_state(handle), VcxStateType::VcxStateAccepted as u32);
+        assert_eq!(to_string(handle).unwrap(), serialized);
+
+        release(handle).unwrap();
+
+        let mut object = object;
+        object["their_did_doc"]["id"] = json!("restored");
+        let handle = from_string(&object.to_string()).unwrap();
+        let restored: Value = serde_json::from_str(&to_string(handle).unwrap()).unwrap();
+        assert_eq!(restored["their_did_doc"]["id"], json!("restored"));
+
+        release(handle).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_serialize_keeps_metadata() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = from_string(CONNECTION_SM_INVITER_COMPLETED).unwrap();
+        set_metadata(handle, "customer", Some("42")).unwrap();
+
+        let serialized = to_string(handle).unwrap();
+        release(handle).unwrap();
+
+        let handle = from_string(&serialized).unwrap();
+        assert_eq!(get_metadata(handle).unwrap(), json!({"customer": "42"}).to_string());
+        assert_eq!(find_handles_by_metadata("customer", "42"), vec![handle]);
+
+        release(handle).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_deserialize_older_versions() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = from_string(CONNECTION_SM_INVITER_COMPLETED).unwrap();
+        let (state, data, _source_id): (SmConnectionState, AgentInfo, String) = CONNECTION_MAP.get(handle, |connection| Ok(connection.to_owned().into())).unwrap();
+        release(handle).unwrap();
+
+        let v2 = serde_json::to_string(&SerializableObjectWithState::V2 { data: data.clone(), state }).unwrap();
+        let handle = from_string(&v2).unwrap();
+        assert_eq!(get_source_id(handle).unwrap(), "");
+        assert_eq!(get_state(handle), VcxStateType::VcxStateAccepted as u32);
+        release(handle).unwrap();
+
+        let v1 = serde_json::to_string(&SerializableObjectWithState::<AgentInfo, SmConnectionState>::V1 { data }).unwrap();
+        let handle = from_string(&v1).unwrap();
+        assert_eq!(get_pw_did(handle).unwrap(), "2ZHFFhzA2XtTD6hJqzL7ux");
+        assert_eq!(get_state(handle), VcxStateType::VcxStateInitialized as u32);
+        release(handle).unwrap();
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_retry_connection() {
@@ -539,6 +1253,72 @@ pub mod tests {
         connect(handle_2).unwrap();
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_create_with_oob_invite() {
+        let _setup = SetupAriesMocks::init();
+
+        let invitation = OutOfBandInvitation::create()
+            .set_connection_invitation(_invitation())
+            .set_request(_credential_offer().to_a2a_message()).unwrap();
+
+        let (handle, credential_handle) = create_connection_with_oob_invite("alice", &json!(invitation).to_string()).unwrap();
+        assert_eq!(get_state(handle), VcxStateType::VcxStateOfferSent as u32);
+        assert_eq!(::credential::get_state(credential_handle.unwrap()).unwrap(), VcxStateType::VcxStateRequestReceived as u32);
+
+        let invitation = OutOfBandInvitation::create().set_connection_invitation(_invitation());
+        let (_, credential_handle) = create_connection_with_oob_invite("alice", &json!(invitation).to_string()).unwrap();
+        assert_eq!(credential_handle, None);
+
+        let invitation = OutOfBandInvitation::create()
+            .set_request(_credential_offer().to_a2a_message()).unwrap();
+        assert_eq!(create_connection_with_oob_invite("alice", &json!(invitation).to_string()).unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_redirect_connection() {
+        let _setup = SetupAriesMocks::init();
+
+        let existing = from_string(CONNECTION_SM_INVITEE_COMPLETED).unwrap();
+        let handle = create_connection_with_invite("alice", ARIES_CONNECTION_INVITATION).unwrap();
+
+        assert_eq!(redirect_connection(existing, handle).unwrap_err().kind(), VcxErrorKind::NotReady);
+        assert_eq!(get_redirect_details(handle).unwrap_err().kind(), VcxErrorKind::NotReady);
+
+        redirect_connection(handle, existing).unwrap();
+        assert_eq!(get_state(handle), VcxStateType::VcxStateRedirected as u32);
+
+        let redirect: Redirect = serde_json::from_str(&get_redirect_details(handle).unwrap()).unwrap();
+        assert_eq!(redirect.did, get_pw_did(existing).unwrap());
+        assert_eq!(redirect.verkey, get_pw_verkey(existing).unwrap());
+        assert_eq!(redirect.their_did, get_their_pw_did(existing).unwrap());
+
+        // inviter accepts redirect signed by the announced key
+        let inviter = build_test_connection_inviter_invited();
+        update_state_with_message(inviter, redirect.to_a2a_message()).unwrap();
+        assert_eq!(get_state(inviter), VcxStateType::VcxStateRedirected as u32);
+        assert_eq!(find_redirected_connection(inviter).unwrap(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_invite_url_round_trip() {
+        let _setup = SetupAriesMocks::init();
+
+        let inviter = build_test_connection_inviter_invited();
+        let url = get_invite_url(inviter, "https://example.com/invite").unwrap();
+        assert!(url.starts_with("https://example.com/invite?c_i="));
+
+        let invitation: InvitationV3 = serde_json::from_str(&get_invite_details(inviter).unwrap()).unwrap();
+        assert_eq!(invite_url::parse_invite_url(&url).unwrap(), invitation);
+
+        let invitee = create_connection_with_invite_url("alice", &url).unwrap();
+        connect(invitee).unwrap();
+
+        assert!(get_invite_url(build_test_connection_inviter_null(), "https://example.com").is_err());
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_process_acceptance_message() {
@@ -560,6 +1340,69 @@ pub mod tests {
         }).unwrap();
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_queue_message() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = build_test_connection_inviter_requested();
+        let message = ::aries::messages::ack::tests::_ack().to_a2a_message();
+
+        assert!(get_pending_messages(handle).unwrap().is_empty());
+
+        queue_message(handle, message.clone()).unwrap();
+        queue_message(handle, message.clone()).unwrap();
+        assert_eq!(get_pending_messages(handle).unwrap(), vec![message.clone()]);
+
+        let uid = PENDING_MESSAGES.lock().unwrap().get(&_pending_key(handle)).unwrap()[0].uid.clone();
+        update_message_status(handle, uid).unwrap();
+        assert!(get_pending_messages(handle).unwrap().is_empty());
+
+        queue_message(handle, message.clone()).unwrap();
+        assert_eq!(delete_connection(handle).unwrap_err().kind(), VcxErrorKind::UndeliveredMessages);
+        assert!(is_valid_handle(handle));
+
+        release(handle).unwrap();
+        assert_eq!(get_pending_messages(handle).unwrap_err().kind(), VcxErrorKind::InvalidConnectionHandle);
+        assert_eq!(queue_message(handle, message).unwrap_err().kind(), VcxErrorKind::InvalidConnectionHandle);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_queued_messages_are_routed_by_thread_and_expire() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = build_test_connection_inviter_requested();
+        let message = ::aries::messages::ack::tests::_ack().set_thread_id("unknown_thread").to_a2a_message();
+
+        queue_message(handle, message.clone()).unwrap();
+        assert_eq!(get_thread_messages(handle, "unknown_thread").unwrap().values().cloned().collect::<Vec<_>>(), vec![message]);
+        assert!(get_thread_messages(handle, "other_thread").unwrap().is_empty());
+
+        PENDING_MESSAGES.lock().unwrap().get_mut(&_pending_key(handle)).unwrap()[0].queued_at = 0;
+        assert!(get_pending_messages(handle).unwrap().is_empty());
+        assert!(get_thread_messages(handle, "unknown_thread").unwrap().is_empty());
+
+        release(handle).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_queue_message_skips_message_of_known_thread() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = build_test_connection_inviter_requested();
+        let credential_handle = ::credential::credential_create_with_offer("test", ::utils::mockdata::mockdata_credex::ARIES_CREDENTIAL_OFFER).unwrap();
+        let thread_id = ::credential::get_thread_id(credential_handle).unwrap();
+
+        let message = ::aries::messages::ack::tests::_ack().set_thread_id(&thread_id).to_a2a_message();
+        queue_message(handle, message).unwrap();
+        assert!(get_pending_messages(handle).unwrap().is_empty());
+
+        ::credential::release(credential_handle).unwrap();
+        release(handle).unwrap();
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_send_generic_message_fails_with_invalid_connection() {
@@ -3,17 +3,39 @@ use serde_json;
 use aries::{
     handlers::issuance::holder::holder::Holder,
     messages::issuance::credential_offer::CredentialOffer,
+    messages::issuance::CredentialValue,
+    messages::issuance::format::CredentialFormat,
+    messages::issuance::w3c::LdProofVcDetail,
+    messages::localization::Localization,
+    messages::mime_type::MimeType,
 };
 use error::prelude::*;
+use outbound_queue;
 use settings::indy_mocks_enabled;
 use utils::constants::GET_MESSAGES_DECRYPTED_RESPONSE;
+use utils::credential_store;
 use utils::error;
+use utils::events;
 use utils::httpclient::AgencyMockDecrypted;
+use utils::handle_token;
+use utils::json::check_json_limits;
+use utils::libindy::anoncreds;
+use utils::libindy::pool;
 use utils::mockdata::mockdata_credex::ARIES_CREDENTIAL_OFFER;
-use utils::object_cache::ObjectCache;
+use utils::object_cache::{ObjectCache, ObjectKeys};
+use utils::persistence;
+use utils::qualifier;
 
 lazy_static! {
-    static ref HANDLE_MAP: ObjectCache<Holder> = ObjectCache::<Holder>::new("credentials-cache");
+    static ref HANDLE_MAP: ObjectCache<Holder> = ObjectCache::<Holder>::with_index("credentials-cache", |credential| ObjectKeys {
+        source_id: Some(credential.get_source_id()),
+        thread_id: Some(credential.get_thread_id()).filter(|thread_id| !thread_id.is_empty()),
+        metadata: credential.get_metadata().clone(),
+    }).with_scope(pool::enter_object_network);
+}
+
+fn _get_mut<F, R>(handle: u32, closure: F) -> VcxResult<R> where F: Fn(&mut Holder) -> VcxResult<R> {
+    persistence::get_mut(&HANDLE_MAP, events::CREDENTIAL, handle, |credential| outbound_queue::sending_state(credential.get_status(), &credential.get_thread_id()), |credential| Some(credential.get_thread_id()), to_string, closure)
 }
 
 // This enum is left only to avoid making breaking serialization changes
@@ -57,12 +79,14 @@ fn create_credential(source_id: &str, offer: &str) -> VcxResult<Option<Holder>>
 pub fn credential_create_with_offer(source_id: &str, offer: &str) -> VcxResult<u32> {
     trace!("credential_create_with_offer >>> source_id: {}, offer: {}", source_id, secret!(&offer));
 
+    check_json_limits(offer.as_bytes())?;
     let cred_offer: CredentialOffer = serde_json::from_str(offer)
         .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson,
                                           format!("Strict `aries` protocol is enabled. Can not parse `aries` formatted Credential Offer: {}", err)))?;
 
     let holder = Holder::create(cred_offer, source_id)?;
-    return HANDLE_MAP.add(holder);
+    credential_store::mirror_credential_metadata(&holder.get_metadata());
+    persistence::add(&HANDLE_MAP, events::CREDENTIAL, holder, to_string)
 }
 
 pub fn credential_create_with_msgid(source_id: &str, connection_handle: u32, msg_id: &str) -> VcxResult<(u32, String)> {
@@ -73,16 +97,23 @@ pub fn credential_create_with_msgid(source_id: &str, connection_handle: u32, msg
 
     let credential = create_credential(source_id, &offer)?
         .ok_or(VcxError::from_msg(VcxErrorKind::InvalidConnectionHandle, format!("Connection can not be used for Proprietary Issuance protocol")))?;
+    credential_store::mirror_credential_metadata(&credential.get_metadata());
 
-    let handle = HANDLE_MAP.add(credential)?;
+    let handle = persistence::add(&HANDLE_MAP, events::CREDENTIAL, credential, to_string)?;
 
     debug!("inserting credential {} into handle map", source_id);
     Ok((handle, offer))
 }
 
 pub fn update_state(handle: u32, message: Option<String>, connection_handle: Option<u32>) -> VcxResult<u32> {
-    HANDLE_MAP.get_mut(handle, |credential| {
+    if let Some(ref message) = message { check_json_limits(message.as_bytes())?; }
+
+    _get_mut(handle, |credential| {
+        let prev_state = credential.get_status();
         credential.update_state(message.clone(), connection_handle)?;
+        if credential.get_status() != prev_state {
+            credential_store::mirror_credential_metadata(&credential.get_metadata());
+        }
         Ok(error::SUCCESS.code_num)
     })
 }
@@ -93,20 +124,32 @@ pub fn get_credential(handle: u32) -> VcxResult<String> {
     })
 }
 
+pub fn is_revoked(handle: u32, use_cache: bool) -> VcxResult<bool> {
+    trace!("Credential::is_revoked >>> credential_handle: {}, use_cache: {}", handle, use_cache);
+
+    HANDLE_MAP.get(handle, |credential| {
+        credential.is_revoked(use_cache)
+    }).map_err(handle_err)
+}
+
+/// Deletes the credential from the wallet together with its cached revocation state and persisted object,
+/// and releases the handle.
 pub fn delete_credential(handle: u32) -> VcxResult<u32> {
-    let source_id = get_source_id(handle).unwrap_or_default();
+    let source_id = get_source_id(handle)?;
     trace!("Credential::delete_credential >>> credential_handle: {}, source_id: {}", handle, source_id);
 
     HANDLE_MAP.get(handle, |credential| {
         trace!("Deleting a credential: credential_handle {}, source_id {}", handle, source_id);
 
         credential.delete_credential()?;
-        Ok(error::SUCCESS.code_num)
-    })
-        .map(|_| error::SUCCESS.code_num)
-        .or(Err(VcxError::from(VcxErrorKind::InvalidCredentialHandle)))
-        .and(release(handle))
-        .and_then(|_| Ok(error::SUCCESS.code_num))
+        credential_store::remove_credential_metadata(&credential.get_thread_id());
+        Ok(())
+    }).map_err(handle_err)?;
+
+    persistence::remove(events::CREDENTIAL, handle)?;
+    release(handle)?;
+
+    Ok(error::SUCCESS.code_num)
 }
 
 pub fn get_credential_offer(handle: u32) -> VcxResult<String> {
@@ -117,28 +160,37 @@ pub fn get_credential_offer(handle: u32) -> VcxResult<String> {
 
 pub fn get_state(handle: u32) -> VcxResult<u32> {
     HANDLE_MAP.get(handle, |credential| {
-        Ok(credential.get_status())
+        Ok(outbound_queue::sending_state(credential.get_status(), &credential.get_thread_id()))
     }).map_err(handle_err)
 }
 
 /// #Returns
 /// Credential request message serialized as String
 pub fn generate_credential_request_msg(handle: u32, _my_pw_did: &str, _their_pw_did: &str) -> VcxResult<String> {
-    HANDLE_MAP.get_mut(handle, |_credential| {
+    _get_mut(handle, |_credential| {
         Err(VcxError::from_msg(VcxErrorKind::ActionNotSupported, "This action is not implemented yet")) // TODO: implement
     }).map_err(handle_err)
 }
 
 pub fn send_credential_request(handle: u32, connection_handle: u32) -> VcxResult<u32> {
     trace!("Credential::send_credential_request >>> credential_handle: {}, connection_handle: {}", handle, connection_handle);
-    HANDLE_MAP.get_mut(handle, |credential| {
+    _get_mut(handle, |credential| {
         credential.send_request(connection_handle)?;
+        credential_store::mirror_credential_metadata(&credential.get_metadata());
         let new_credential = credential.clone(); // TODO: Why are we doing this exactly?
         *credential = new_credential;
         Ok(error::SUCCESS.code_num)
     }).map_err(handle_err)
 }
 
+/// Rejects the offer or stops waiting for the credential, the issuer is informed by problem report with `reason`.
+pub fn abandon(handle: u32, connection_handle: u32, reason: &str) -> VcxResult<()> {
+    trace!("Credential::abandon >>> credential_handle: {}, connection_handle: {}, reason: {}", handle, connection_handle, reason);
+    _get_mut(handle, |credential| {
+        credential.abandon(connection_handle, reason)
+    }).map_err(handle_err)
+}
+
 fn get_credential_offer_msg(connection_handle: u32, msg_id: &str) -> VcxResult<String> {
     trace!("get_credential_offer_msg >>> connection_handle: {}, msg_id: {}", connection_handle, msg_id);
 
@@ -165,23 +217,184 @@ pub fn get_credential_offer_messages(connection_handle: u32) -> VcxResult<String
     Ok(json!(credential_offers).to_string())
 }
 
+/// Makes `update_state` with connection handle send the credential request for the received offer.
+pub fn enable_auto_accept(handle: u32) -> VcxResult<()> {
+    HANDLE_MAP.get_mut(handle, |credential| {
+        credential.set_auto_accept(true);
+        Ok(())
+    }).map_err(handle_err)
+}
+
+/// Attributes of the issued credential (previewed in the offer until the credential is received),
+/// each with its `mime-type` (`text/plain` when the issuer did not set any).
+/// Values of non textual types are base64 encoded.
+pub fn get_attributes(handle: u32) -> VcxResult<Vec<CredentialValue>> {
+    HANDLE_MAP.get(handle, |credential| {
+        let attributes = credential.get_attributes()?.into_iter()
+            .map(|attribute| CredentialValue { _type: Some(attribute._type.unwrap_or_default()), ..attribute })
+            .collect();
+        Ok(attributes)
+    }).map_err(handle_err)
+}
+
+/// Previewed attribute of the credential offer with its label in the requested locale.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OfferPreviewAttribute {
+    pub name: String,
+    pub label: String,
+    pub value: String,
+    #[serde(rename = "mime-type")]
+    pub mime_type: MimeType,
+}
+
+///
+/// Attributes previewed in the offer labeled by the issuer's `~l10n` decorator.
+/// Attributes without a label in `locale` are labeled by their name.
+///
+pub fn get_offer_preview(handle: u32, locale: &str) -> VcxResult<Vec<OfferPreviewAttribute>> {
+    HANDLE_MAP.get(handle, |credential| {
+        let offer = credential.get_offer()?;
+        let localization = offer.l10n.unwrap_or_default();
+
+        let preview = offer.credential_preview.attributes.into_iter()
+            .map(|attribute| OfferPreviewAttribute {
+                label: localization.label(locale, &attribute.name).unwrap_or(attribute.name.clone()),
+                name: attribute.name,
+                value: attribute.value,
+                mime_type: attribute._type.unwrap_or_default(),
+            })
+            .collect();
+        Ok(preview)
+    }).map_err(handle_err)
+}
+
+/// Details of the credential offer to be shown to the holder before accepting it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OfferDetails {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuer_did: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cred_def_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub attributes: Vec<CredentialValue>,
+    pub revocable: bool,
+}
+
+///
+/// Issuer, credential definition and schema of the offered credential together with the previewed attributes.
+/// Whether the credential will be revocable is told by its credential definition on the ledger.
+/// W3C credentials have neither credential definition nor schema and are never revocable.
+///
+pub fn get_offer_details(handle: u32) -> VcxResult<OfferDetails> {
+    HANDLE_MAP.get(handle, |credential| {
+        let offer = credential.get_offer()?;
+
+        let mut details = OfferDetails {
+            issuer_did: None,
+            cred_def_id: None,
+            schema_id: None,
+            schema_name: None,
+            schema_version: None,
+            comment: offer.comment.clone(),
+            attributes: offer.credential_preview.attributes.iter().cloned()
+                .map(|attribute| CredentialValue { _type: Some(attribute._type.unwrap_or_default()), ..attribute })
+                .collect(),
+            revocable: false,
+        };
+
+        if offer.format() == CredentialFormat::LdProofVc {
+            let detail = LdProofVcDetail::from_str(&offer.offers_attach.content()?)?;
+            details.issuer_did = Some(detail.credential.issuer);
+            return Ok(details);
+        }
+
+        let indy_offer: serde_json::Value = serde_json::from_str(&offer.offers_attach.content()?)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Invalid Credential Offer Json: {:?}", err)))?;
+
+        let cred_def_id = indy_offer["cred_def_id"].as_str()
+            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, "Invalid Credential Offer Json: cred_def_id not found"))?;
+        details.issuer_did = qualifier::unqualify(cred_def_id).split(':').next().map(String::from);
+        details.cred_def_id = Some(cred_def_id.to_string());
+
+        if let Some(schema_id) = indy_offer["schema_id"].as_str() {
+            // <issuer did>:2:<name>:<version>
+            let parts: Vec<String> = qualifier::unqualify(schema_id).split(':').map(String::from).collect();
+            if parts.len() == 4 {
+                details.schema_name = Some(parts[2].clone());
+                details.schema_version = Some(parts[3].clone());
+            }
+            details.schema_id = Some(schema_id.to_string());
+        }
+
+        details.revocable = match credential.get_rev_reg_id() {
+            Ok(_) => true,
+            Err(ref err) if err.kind() == VcxErrorKind::InvalidRevocationDetails => false,
+            Err(_) => {
+                let (_, cred_def_json) = anoncreds::get_cred_def_json(cred_def_id)?;
+                let cred_def: serde_json::Value = serde_json::from_str(&cred_def_json)
+                    .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Invalid Credential Definition Json: {:?}", err)))?;
+                !cred_def["value"]["revocation"].is_null()
+            }
+        };
+
+        Ok(details)
+    }).map_err(handle_err)
+}
+
+/// Indy credential json attached to the received credential message.
+pub fn get_attachment(handle: u32) -> VcxResult<String> {
+    HANDLE_MAP.get(handle, |credential| {
+        credential.get_attachment()
+    }).map_err(handle_err)
+}
+
+pub fn get_cred_def_id(handle: u32) -> VcxResult<String> {
+    HANDLE_MAP.get(handle, |credential| {
+        credential.get_cred_def_id()
+    }).map_err(handle_err)
+}
+
+/// Fails with `InvalidRevocationDetails` for credentials which are not revocable.
+pub fn get_rev_reg_id(handle: u32) -> VcxResult<String> {
+    HANDLE_MAP.get(handle, |credential| {
+        credential.get_rev_reg_id()
+    }).map_err(handle_err)
+}
+
 pub fn release(handle: u32) -> VcxResult<()> {
-    HANDLE_MAP.release(handle).map_err(handle_err)
+    HANDLE_MAP.release(handle).map_err(handle_err)?;
+    persistence::forget(events::CREDENTIAL, handle);
+    Ok(())
 }
 
 pub fn release_all() {
+    persistence::forget_all(&HANDLE_MAP, events::CREDENTIAL);
     HANDLE_MAP.drain().ok();
 }
 
+pub fn get_handles() -> Vec<u32> {
+    HANDLE_MAP.handles().unwrap_or_default()
+}
+
 pub fn is_valid_handle(handle: u32) -> bool {
     HANDLE_MAP.has_handle(handle)
 }
 
 pub fn to_string(handle: u32) -> VcxResult<String> {
-    HANDLE_MAP.get(handle, |credential| {
-        serde_json::to_string(&Credentials::V3(credential.clone()))
-            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidState, format!("cannot serialize Credential credentialect: {:?}", err)))
-    })
+    HANDLE_MAP.get(handle, serialize)
+}
+
+/// Serializes the credential in the format read by `from_string`.
+pub fn serialize(credential: &Holder) -> VcxResult<String> {
+    serde_json::to_string(&Credentials::V3(credential.clone()))
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidState, format!("cannot serialize Credential credentialect: {:?}", err)))
 }
 
 pub fn get_source_id(handle: u32) -> VcxResult<String> {
@@ -190,6 +403,60 @@ pub fn get_source_id(handle: u32) -> VcxResult<String> {
     }).map_err(handle_err)
 }
 
+pub fn get_thread_id(handle: u32) -> VcxResult<String> {
+    HANDLE_MAP.get(handle, |credential| {
+        Ok(credential.get_thread_id())
+    }).map_err(handle_err)
+}
+
+/// Connection the exchange of the object runs over, if any.
+pub fn get_connection_handle(handle: u32) -> VcxResult<Option<u32>> {
+    HANDLE_MAP.get(handle, |credential| {
+        Ok(credential.get_connection_handle())
+    }).map_err(handle_err)
+}
+
+/// Time the offer or request of the exchange expires at, in RFC 3339 format, `None` if it does not expire.
+pub fn get_expiry(handle: u32) -> VcxResult<Option<String>> {
+    HANDLE_MAP.get(handle, |credential| {
+        Ok(credential.get_expiry())
+    }).map_err(handle_err)
+}
+
+pub fn get_protocol_history(handle: u32) -> VcxResult<String> {
+    HANDLE_MAP.get(handle, |credential| {
+        credential.get_protocol_history()
+    }).map_err(handle_err)
+}
+
+/// Handle of the credential with given source id, the most recent one when there are more of them.
+pub fn get_handle_by_source_id(source_id: &str) -> VcxResult<u32> {
+    HANDLE_MAP.find_by_source_id(source_id)
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidCredentialHandle, format!("No credential with source id: {}", source_id)))
+}
+
+pub fn get_handle_by_thread_id(thread_id: &str) -> VcxResult<u32> {
+    HANDLE_MAP.find_by_thread_id(thread_id)
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidCredentialHandle, format!("No credential with thread id: {}", thread_id)))
+}
+
+/// Sets metadata `key` of the credential to `value`, or removes the key when no value is given.
+pub fn set_metadata(handle: u32, key: &str, value: Option<&str>) -> VcxResult<()> {
+    _get_mut(handle, |credential| credential.set_metadata(key, value)).map_err(handle_err)
+}
+
+/// Metadata of the credential as JSON object.
+pub fn get_metadata(handle: u32) -> VcxResult<String> {
+    HANDLE_MAP.get(handle, |credential| {
+        Ok(json!(credential.get_metadata()).to_string())
+    }).map_err(handle_err)
+}
+
+/// Handles of the credentials having metadata `key` set to `value`.
+pub fn find_handles_by_metadata(key: &str, value: &str) -> Vec<u32> {
+    HANDLE_MAP.find_by_metadata(key, value)
+}
+
 pub fn from_string(credential_data: &str) -> VcxResult<u32> {
     let credential: Credentials = serde_json::from_str(credential_data)
         .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize Credential: {:?}", err)))?;
@@ -199,6 +466,15 @@ pub fn from_string(credential_data: &str) -> VcxResult<u32> {
     }
 }
 
+/// Serializes the object into a token which can be turned back into a handle by another process via `from_token`.
+pub fn to_token(handle: u32) -> VcxResult<String> {
+    handle_token::seal("credential", &to_string(handle)?)
+}
+
+pub fn from_token(token: &str) -> VcxResult<u32> {
+    from_string(&handle_token::unseal("credential", token)?)
+}
+
 pub fn is_payment_required(handle: u32) -> VcxResult<bool> {
     HANDLE_MAP.get(handle, |_| {
         Ok(false)
@@ -215,7 +491,10 @@ pub fn get_credential_status(handle: u32) -> VcxResult<u32> {
 pub mod tests {
     use api::VcxStateType;
     use aries::messages::issuance::credential::Credential as Credential;
+    use aries::messages::issuance::w3c::tests::_w3c_credential;
+    use aries::messages::mime_type::MimeType;
     use connection;
+    use settings;
     use utils::devsetup::*;
     use utils::mockdata::mockdata_credex::{ARIES_CREDENTIAL_RESPONSE, CREDENTIAL_SM_FINISHED, CREDENTIAL_SM_OFFER_RECEIVED};
 
@@ -239,6 +518,107 @@ pub mod tests {
         assert!(handle > 0);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_attributes_with_mime_types() {
+        let _setup = SetupDefaults::init();
+
+        let offer: CredentialOffer = serde_json::from_str(ARIES_CREDENTIAL_OFFER).unwrap();
+        let offer = offer.add_credential_preview_data("photo", "iVBORw0KGgo=", MimeType::from("image/png")).unwrap();
+        let handle = credential_create_with_offer("test_get_attributes_with_mime_types", &json!(offer).to_string()).unwrap();
+
+        let attributes = get_attributes(handle).unwrap();
+        assert!(attributes.iter().all(|attribute| attribute._type.is_some()));
+
+        let photo = attributes.iter().find(|attribute| attribute.name == "photo").unwrap();
+        assert_eq!(photo.value, "iVBORw0KGgo=");
+        assert_eq!(photo._type, Some(MimeType::Other("image/png".to_string())));
+
+        assert_eq!(get_attributes(0).unwrap_err().kind(), VcxErrorKind::InvalidCredentialHandle);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_offer_preview() {
+        let _setup = SetupDefaults::init();
+
+        let offer: CredentialOffer = serde_json::from_str(ARIES_CREDENTIAL_OFFER).unwrap();
+        let name = offer.credential_preview.attributes[0].name.clone();
+        let localization = Localization::from_str(&json!({"locale": "en", "labels": {"es": {name.clone(): "Etiqueta"}}}).to_string()).unwrap();
+        let offer = offer.set_l10n(localization);
+
+        let handle = credential_create_with_offer("test_get_offer_preview", &json!(offer).to_string()).unwrap();
+
+        let preview = get_offer_preview(handle, "es-ES").unwrap();
+        assert_eq!(preview[0].label, "Etiqueta");
+        assert_eq!(preview[0].name, name);
+
+        let preview = get_offer_preview(handle, "de").unwrap();
+        assert_eq!(preview[0].label, name);
+
+        assert_eq!(get_offer_preview(0, "es").unwrap_err().kind(), VcxErrorKind::InvalidCredentialHandle);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_offer_details() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = credential_create_with_offer("test_get_offer_details", ARIES_CREDENTIAL_OFFER).unwrap();
+
+        let details = get_offer_details(handle).unwrap();
+        assert_eq!(details.issuer_did, Some(String::from("V4SGRU86Z58d6TV7PBUe6f")));
+        assert_eq!(details.cred_def_id, Some(String::from("V4SGRU86Z58d6TV7PBUe6f:3:CL:31:tag1")));
+        assert_eq!(details.schema_name, Some(String::from("FaberVcx")));
+        assert_eq!(details.schema_version, Some(String::from("83.23.62")));
+        assert_eq!(details.attributes[0].name, "age");
+        assert_eq!(details.attributes[0]._type, Some(MimeType::Plain));
+        assert!(!details.revocable);
+
+        let offer = CredentialOffer::create()
+            .set_ld_proof_vc_detail(&LdProofVcDetail::create(_w3c_credential())).unwrap();
+        let handle = credential_create_with_offer("test_get_offer_details", &json!(offer.to_a2a_message()).to_string()).unwrap();
+
+        let details = get_offer_details(handle).unwrap();
+        assert_eq!(details.issuer_did, Some(_w3c_credential().issuer));
+        assert_eq!(details.cred_def_id, None);
+        assert!(!details.revocable);
+
+        assert_eq!(get_offer_details(0).unwrap_err().kind(), VcxErrorKind::InvalidCredentialHandle);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_issued_credential_data() {
+        let _setup = SetupDefaults::init();
+
+        let handle = from_string(CREDENTIAL_SM_FINISHED).unwrap();
+
+        let attachment: serde_json::Value = serde_json::from_str(&get_attachment(handle).unwrap()).unwrap();
+        assert_eq!(attachment["cred_def_id"], json!("V4SGRU86Z58d6TV7PBUe6f:3:CL:156:tag1"));
+
+        let attributes = get_attributes(handle).unwrap();
+        let sex = attributes.iter().find(|attribute| attribute.name == "sex").unwrap();
+        assert_eq!(sex.value, "female");
+        assert_eq!(sex._type, Some(MimeType::Plain));
+
+        assert_eq!(get_cred_def_id(handle).unwrap(), "V4SGRU86Z58d6TV7PBUe6f:3:CL:156:tag1");
+        assert_eq!(get_rev_reg_id(handle).unwrap(), "V4SGRU86Z58d6TV7PBUe6f:4:V4SGRU86Z58d6TV7PBUe6f:3:CL:156:tag1:CL_ACCUM:tag1");
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_credential_data_before_issuance() {
+        let _setup = SetupDefaults::init();
+
+        let handle = credential_create_with_offer("test_get_credential_data_before_issuance", ARIES_CREDENTIAL_OFFER).unwrap();
+
+        assert_eq!(get_attachment(handle).unwrap_err().kind(), VcxErrorKind::NotReady);
+        assert_eq!(get_rev_reg_id(handle).unwrap_err().kind(), VcxErrorKind::NotReady);
+        assert!(get_cred_def_id(handle).is_ok());
+        assert_eq!(get_cred_def_id(0).unwrap_err().kind(), VcxErrorKind::InvalidCredentialHandle);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_credential_create_with_bad_offer() {
@@ -248,6 +628,16 @@ pub mod tests {
         assert_eq!(err.kind(), VcxErrorKind::InvalidJson);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_credential_create_with_oversized_offer() {
+        let _setup = SetupDefaults::init();
+        settings::set_config_value(settings::CONFIG_MAX_JSON_SIZE, "100");
+
+        let err = credential_create_with_offer("test_credential_create_with_oversized_offer", ARIES_CREDENTIAL_OFFER).unwrap_err();
+        assert_eq!(err.kind(), VcxErrorKind::JsonLimitExceeded);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_credential_serialize_deserialize() {
@@ -301,6 +691,26 @@ pub mod tests {
         let _credential_struct: Credential = serde_json::from_str(msg_value.to_string().as_str()).unwrap();
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_update_state_sends_request_when_auto_accept_is_enabled() {
+        let _setup = SetupStrictAriesMocks::init();
+
+        let handle_conn = connection::tests::build_test_connection_inviter_requested();
+        let offer = _get_offer(handle_conn);
+
+        let handle_cred = credential_create_with_offer("TEST_CREDENTIAL", &offer).unwrap();
+        enable_auto_accept(handle_cred).unwrap();
+
+        AgencyMockDecrypted::set_next_decrypted_response(GET_MESSAGES_DECRYPTED_RESPONSE);
+        AgencyMockDecrypted::set_next_decrypted_message(ARIES_CREDENTIAL_RESPONSE);
+
+        update_state(handle_cred, None, Some(handle_conn)).unwrap();
+        assert_eq!(get_state(handle_cred).unwrap(), VcxStateType::VcxStateOfferSent as u32);
+
+        assert_eq!(enable_auto_accept(0).unwrap_err().kind(), VcxErrorKind::InvalidCredentialHandle);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     #[cfg(feature = "to_restore")] // todo: generate_credential_request_msg is not implemented for v3
@@ -355,4 +765,16 @@ pub mod tests {
         let cred_value: serde_json::Value = serde_json::from_str(&cred_string).unwrap();
         let _credential_struct: Credential = serde_json::from_str(cred_value.to_string().as_str()).unwrap();
     }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_is_revoked() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = from_string(CREDENTIAL_SM_FINISHED).unwrap();
+        assert_eq!(is_revoked(handle, false).unwrap(), false);
+
+        let handle = from_string(CREDENTIAL_SM_OFFER_RECEIVED).unwrap();
+        assert_eq!(is_revoked(handle, false).unwrap_err().kind(), VcxErrorKind::NotReady);
+    }
 }
@@ -1,3 +1,4 @@
+use indy::WalletHandle;
 use serde_json;
 
 use api::PublicEntityStateType;
@@ -7,8 +8,14 @@ use utils::constants::DEFAULT_SERIALIZE_VERSION;
 use utils::libindy::anoncreds;
 use utils::libindy::cache::update_rev_reg_ids_cache;
 use utils::libindy::ledger;
-use utils::libindy::payments::PaymentTxn;
+use utils::libindy::payments::{LedgerTxn, PaymentTxn};
+use utils::libindy::pool;
+use utils::libindy::wallet;
 use utils::object_cache::ObjectCache;
+use utils::tails;
+
+static CREDENTIALDEF_RECORD_TYPE: &str = "VcxCredentialDef";
+const LIST_PAGE_SIZE: usize = 50;
 
 lazy_static! {
     static ref CREDENTIALDEF_MAP: ObjectCache<CredentialDef> = ObjectCache::<CredentialDef>::new("credential-defs-cache");
@@ -24,6 +31,15 @@ struct RevocationRegistry {
     tag: u32,
     rev_reg_def_payment_txn: Option<PaymentTxn>,
     rev_reg_delta_payment_txn: Option<PaymentTxn>,
+    #[serde(default = "_issued_unknown")]
+    issued: u32,
+}
+
+// marks registries stored before issued credentials were counted, see `CredentialDef::from_str`
+const ISSUED_UNKNOWN: u32 = ::std::u32::MAX;
+
+fn _issued_unknown() -> u32 {
+    ISSUED_UNKNOWN
 }
 
 #[derive(Clone, Deserialize, Debug, Serialize, PartialEq)]
@@ -37,9 +53,42 @@ pub struct CredentialDef {
     rev_reg: Option<RevocationRegistry>,
     #[serde(default)]
     state: PublicEntityStateType,
+    // kept until the definition is published, so that its transaction can be prepared for an endorser again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cred_def_json: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    schema_id: Option<String>,
+    // capacity of the registry created by the next rotation, the active registry keeps its own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    next_max_creds: Option<u32>,
+}
+
+/// Credential definition created by this issuer, as listed by `list_created_credential_defs`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CredentialDefInfo {
+    pub cred_def_id: String,
+    pub schema_id: Option<String>,
+    pub issuer_did: Option<String>,
+    pub name: String,
+    pub tag: String,
+    pub source_id: String,
+    /// Currently active revocation registry, `None` for non revocable definitions.
+    pub rev_reg_id: Option<String>,
+    pub state: PublicEntityStateType,
+}
+
+/// Usage of the active revocation registry of a credential definition.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RevRegUsage {
+    pub rev_reg_id: String,
+    pub max_creds: u32,
+    pub issued: u32,
+    pub remaining: u32,
+    /// Capacity of the registries created by the following rotations.
+    pub next_max_creds: u32,
 }
 
-#[derive(Deserialize, Debug, Serialize)]
+#[derive(Clone, Deserialize, Debug, Serialize)]
 pub struct RevocationDetails {
     pub support_revocation: Option<bool>,
     pub tails_file: Option<String>,
@@ -48,9 +97,18 @@ pub struct RevocationDetails {
 
 impl CredentialDef {
     pub fn from_str(data: &str) -> VcxResult<CredentialDef> {
-        ObjectWithVersion::deserialize(data)
+        let mut cred_def = ObjectWithVersion::deserialize(data)
             .map(|obj: ObjectWithVersion<CredentialDef>| obj.data)
-            .map_err(|err| err.map(VcxErrorKind::CreateCredDef, "Cannot deserialize CredentialDefinition"))
+            .map_err(|err| err.map(VcxErrorKind::CreateCredDef, "Cannot deserialize CredentialDefinition"))?;
+
+        // older records do not tell how many credentials their registry holds, so it is taken as full
+        // and the next issuance starts a new registry instead of overflowing it
+        if let Some(ref mut rev_reg) = cred_def.rev_reg {
+            if rev_reg.issued == ISSUED_UNKNOWN {
+                rev_reg.issued = rev_reg.max_creds;
+            }
+        }
+        Ok(cred_def)
     }
 
     pub fn to_string(&self) -> VcxResult<String> {
@@ -117,6 +175,8 @@ impl CredentialDef {
     }
 
     fn update_state(&mut self) -> VcxResult<u32> {
+        let was_published = self.state == PublicEntityStateType::Published;
+
         if let Some(ref rev_reg_id) = self.get_rev_reg_id() {
             if let (Ok(_), Ok(_), Ok(_)) = (anoncreds::get_cred_def_json(&self.id),
                                             anoncreds::get_rev_reg_def_json(rev_reg_id),
@@ -129,19 +189,136 @@ impl CredentialDef {
             }
         }
 
+        if self.state == PublicEntityStateType::Published {
+            self.cred_def_json = None;
+            if !was_published {
+                self.save_record();
+            }
+        }
+
         Ok(self.state as u32)
     }
 
+    // records of created definitions only serve listing, failing to write one must not fail the definition itself
+    fn save_record(&self) {
+        let info = CredentialDefInfo {
+            cred_def_id: self.id.clone(),
+            schema_id: self.schema_id.clone(),
+            issuer_did: self.issuer_did.clone(),
+            name: self.name.clone(),
+            tag: self.tag.clone(),
+            source_id: self.source_id.clone(),
+            rev_reg_id: self.get_rev_reg_id().cloned(),
+            state: self.state,
+        };
+
+        let result = serde_json::to_string(&info)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize credential definition record: {:?}", err)))
+            .and_then(|value| {
+                match wallet::update_record_value(CREDENTIALDEF_RECORD_TYPE, &self.id, &value) {
+                    Err(ref err) if err.kind() == VcxErrorKind::WalletRecordNotFound => {
+                        let tags = json!({"schema_id": self.schema_id, "tag": self.tag}).to_string();
+                        wallet::add_record(CREDENTIALDEF_RECORD_TYPE, &self.id, &value, Some(&tags))
+                    }
+                    result => result
+                }
+            });
+
+        if let Err(err) = result {
+            warn!("Cannot store record of credential definition {}: {}", self.id, err);
+        }
+    }
+
     fn get_state(&self) -> u32 { self.state as u32 }
 
-    fn rotate_rev_reg(&mut self) -> VcxResult<RevocationRegistry> {
-        let (tails_file, max_creds, issuer_did) = (self.get_tails_file(), self.get_max_creds(), self.issuer_did.as_ref());
+    fn prepare_for_endorser(&self, endorser: &str) -> VcxResult<(String, Option<String>, Option<String>)> {
+        if self.state == PublicEntityStateType::Published {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidState, format!("Credential Definition {} is already published", self.id)));
+        }
+
+        let issuer_did = self.issuer_did.as_ref()
+            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidState, format!("Credential Definition {} has no issuer DID", self.id)))?;
+        let cred_def_json = self.cred_def_json.as_ref()
+            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidState, format!("Credential Definition {} was not prepared for an endorser", self.id)))?;
+
+        let rev_reg = self.rev_reg.as_ref()
+            .map(|rev_reg| (rev_reg.rev_reg_id.as_str(), rev_reg.rev_reg_def.as_str(), rev_reg.rev_reg_entry.as_str()));
+
+        _build_requests_for_endorser(issuer_did, cred_def_json, rev_reg, endorser)
+    }
+
+    fn is_rev_reg_full(&self) -> bool {
+        match &self.rev_reg {
+            Some(rev_reg) => rev_reg.issued >= rev_reg.max_creds,
+            None => false
+        }
+    }
+
+    fn reserve_rev_reg_slot(&mut self) -> (Option<String>, Option<String>) {
+        match self.rev_reg {
+            Some(ref mut rev_reg) => {
+                rev_reg.issued += 1;
+                (Some(rev_reg.rev_reg_id.clone()), Some(rev_reg.tails_file.clone()))
+            }
+            None => (None, None)
+        }
+    }
+
+    // place reserved in a registry which was rotated meanwhile is not given back
+    fn release_rev_reg_slot(&mut self, rev_reg_id: &str) -> bool {
+        match self.rev_reg {
+            Some(ref mut rev_reg) if rev_reg.rev_reg_id == rev_reg_id && rev_reg.issued > 0 => {
+                rev_reg.issued -= 1;
+                true
+            }
+            _ => false
+        }
+    }
+
+    fn get_rev_reg_usage(&self) -> VcxResult<RevRegUsage> {
+        let rev_reg = self.rev_reg.as_ref()
+            .ok_or(VcxError::from_msg(VcxErrorKind::RevRegDefNotFound, "No revocation registry definitions associated with this credential definition"))?;
+
+        Ok(RevRegUsage {
+            rev_reg_id: rev_reg.rev_reg_id.clone(),
+            max_creds: rev_reg.max_creds,
+            issued: rev_reg.issued,
+            remaining: rev_reg.max_creds.saturating_sub(rev_reg.issued),
+            next_max_creds: self.next_max_creds.unwrap_or(rev_reg.max_creds),
+        })
+    }
+
+    // new tails location needs a new registry definition, so the registry is rotated right away
+    fn update_revocation_details(&mut self, tails_file: Option<String>, max_creds: Option<u32>) -> VcxResult<Option<RevocationRegistry>> {
+        if self.rev_reg.is_none() {
+            return Err(VcxError::from_msg(VcxErrorKind::RevRegDefNotFound, "No revocation registry definitions associated with this credential definition"));
+        }
+        if max_creds == Some(0) {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidRevocationDetails, "Invalid RevocationDetails: `max_creds` must be greater than 0"));
+        }
+
+        if max_creds.is_some() {
+            self.next_max_creds = max_creds;
+        }
+
+        match tails_file {
+            Some(ref tails_file) if Some(tails_file) != self.get_tails_file().as_ref() => Ok(Some(self.rotate_rev_reg(Some(tails_file))?)),
+            _ => Ok(None)
+        }
+    }
+
+    // publishes a new revocation registry, stored in `tails_file` if given or next to the tails of the active registry
+    fn rotate_rev_reg(&mut self, tails_file: Option<&str>) -> VcxResult<RevocationRegistry> {
+        let tails_file = tails_file.map(String::from).or(self.get_tails_file());
+        let max_creds = self.next_max_creds.or(self.get_max_creds());
+        let issuer_did = self.issuer_did.as_ref();
         match (&mut self.rev_reg, &tails_file, &max_creds, &issuer_did) {
             (Some(rev_reg), Some(tails_file), Some(max_creds), Some(issuer_did)) => {
                 let tag = format!("tag{}", rev_reg.tag + 1);
                 let (rev_reg_id, rev_reg_def, rev_reg_entry) =
                     anoncreds::generate_rev_reg(&issuer_did, &self.id, &tails_file, *max_creds, tag.as_str())
                         .map_err(|err| err.map(VcxErrorKind::CreateRevRegDef, "Cannot create revocation registry defintion"))?;
+                let rev_reg_def = tails::publish_tails_file(&tails_file, &rev_reg_def)?;
                 let rev_reg_def_payment_txn = anoncreds::publish_rev_reg_def(&issuer_did, &rev_reg_def)
                     .map_err(|err| err.map(VcxErrorKind::CreateCredDef, "Cannot publish revocation registry defintion"))?;
 
@@ -157,8 +334,11 @@ impl CredentialDef {
                     tag: rev_reg.tag + 1,
                     rev_reg_delta_payment_txn,
                     rev_reg_def_payment_txn,
+                    issued: 0,
                 };
                 self.rev_reg = Some(new_rev_reg.clone());
+                self.next_max_creds = None;
+                self.save_record();
 
                 Ok(new_rev_reg)
             }
@@ -172,17 +352,19 @@ fn _parse_revocation_details(revocation_details: &str) -> VcxResult<RevocationDe
         .to_vcx(VcxErrorKind::InvalidRevocationDetails, "Cannot deserialize RevocationDeltas")
 }
 
-fn _create_credentialdef(issuer_did: &str,
+fn _create_credentialdef(wallet_handle: WalletHandle,
+                         issuer_did: &str,
                          schema_id: &str,
                          tag: &str,
                          revocation_details: &RevocationDetails) -> VcxResult<(String, String, Option<String>, Option<String>, Option<String>)> {
     let (_, schema_json) = anoncreds::get_schema_json(&schema_id)?;
 
-    let (cred_def_id, cred_def_json) = anoncreds::generate_cred_def(issuer_did,
-                                                                    &schema_json,
-                                                                    tag,
-                                                                    None,
-                                                                    revocation_details.support_revocation)?;
+    let (cred_def_id, cred_def_json) = anoncreds::generate_cred_def_in(wallet_handle,
+                                                                       issuer_did,
+                                                                       &schema_json,
+                                                                       tag,
+                                                                       None,
+                                                                       revocation_details.support_revocation)?;
 
     let (rev_reg_id, rev_reg_def, rev_reg_entry) = match revocation_details.support_revocation {
         Some(true) => {
@@ -196,7 +378,7 @@ fn _create_credentialdef(issuer_did: &str,
                 .ok_or(VcxError::from_msg(VcxErrorKind::InvalidRevocationDetails, "Invalid RevocationDetails: `max_creds` field not found"))?;
 
             let (rev_reg_id, rev_reg_def, rev_reg_entry) =
-                anoncreds::generate_rev_reg(&issuer_did, &cred_def_id, &tails_file, max_creds, "tag1")
+                anoncreds::generate_rev_reg_in(wallet_handle, &issuer_did, &cred_def_id, &tails_file, max_creds, "tag1")
                     .map_err(|err| err.map(VcxErrorKind::CreateCredDef, "Cannot create CredentialDefinition"))?;
 
             (Some(rev_reg_id), Some(rev_reg_def), Some(rev_reg_entry))
@@ -207,6 +389,41 @@ fn _create_credentialdef(issuer_did: &str,
     Ok((cred_def_id, cred_def_json, rev_reg_id, rev_reg_def, rev_reg_entry))
 }
 
+fn _publish_tails_file(revocation_details: &RevocationDetails, rev_reg_def: Option<String>) -> VcxResult<Option<String>> {
+    match (rev_reg_def, &revocation_details.tails_file) {
+        (Some(rev_reg_def), Some(tails_file)) => Ok(Some(tails::publish_tails_file(tails_file, &rev_reg_def)?)),
+        (rev_reg_def, _) => Ok(rev_reg_def)
+    }
+}
+
+// Credential Definition request and Revocation related requests, signed by the issuer and ready to be submitted by `endorser`
+fn _build_requests_for_endorser(issuer_did: &str,
+                                cred_def_json: &str,
+                                rev_reg: Option<(&str, &str, &str)>,
+                                endorser: &str) -> VcxResult<(String, Option<String>, Option<String>)> {
+    let cred_def_req = anoncreds::build_cred_def_request(issuer_did, cred_def_json)?;
+    let cred_def_req = ledger::set_endorser(&cred_def_req, endorser)?;
+
+    let (rev_reg_def_req, rev_reg_delta_req) = match rev_reg {
+        Some((rev_reg_id, rev_reg_def, rev_reg_entry)) => {
+            let rev_reg_def_req =
+                anoncreds::build_rev_reg_request(issuer_did, rev_reg_def)
+                    .map_err(|err| err.map(VcxErrorKind::CreateCredDef, "Cannot create CredentialDefinition"))?;
+
+            let rev_reg_delta_req = anoncreds::build_rev_reg_delta_request(issuer_did, rev_reg_id, rev_reg_entry)
+                .map_err(|err| err.map(VcxErrorKind::InvalidRevocationEntry, "Cannot post RevocationEntry"))?;
+
+            let rev_reg_delta_req = ledger::set_endorser(&rev_reg_delta_req, endorser)?;
+            let rev_reg_def_req = ledger::set_endorser(&rev_reg_def_req, endorser)?;
+
+            (Some(rev_reg_def_req), Some(rev_reg_delta_req))
+        }
+        None => (None, None)
+    };
+
+    Ok((cred_def_req, rev_reg_def_req, rev_reg_delta_req))
+}
+
 pub fn prepare_credentialdef_for_endorser(source_id: String,
                                           name: String,
                                           issuer_did: String,
@@ -220,28 +437,16 @@ pub fn prepare_credentialdef_for_endorser(source_id: String,
     let revocation_details: RevocationDetails = _parse_revocation_details(&revocation_details)?;
 
     // Creates Credential Definition and Revocation Definition in wallet
-    let (cred_def_id, cred_def_json, rev_reg_id, rev_reg_def, rev_reg_entry) = _create_credentialdef(&issuer_did, &schema_id, &tag, &revocation_details)?;
-
-    // Creates Credential Definition request
-    let cred_def_req = anoncreds::build_cred_def_request(&issuer_did, &cred_def_json)?;
-    let cred_def_req = ledger::set_endorser(&cred_def_req, &endorser)?;
-
-    // Creates Revocation related requests
-    let (rev_reg_def_req, rev_reg_delta_req) = match (&rev_reg_id, &rev_reg_def, &rev_reg_entry) {
-        (Some(ref rev_reg_id), Some(ref rev_reg_def), Some(ref rev_reg_entry)) => {
-            let rev_reg_def_req =
-                anoncreds::build_rev_reg_request(&issuer_did, &rev_reg_def)
-                    .map_err(|err| err.map(VcxErrorKind::CreateCredDef, "Cannot create CredentialDefinition"))?;
-
-            let rev_reg_delta_req = anoncreds::build_rev_reg_delta_request(&issuer_did, &rev_reg_id, &rev_reg_entry)
-                .map_err(|err| err.map(VcxErrorKind::InvalidRevocationEntry, "Cannot post RevocationEntry"))?;
-
-            let rev_reg_delta_req = ledger::set_endorser(&rev_reg_delta_req, &endorser)?;
-            let rev_reg_def_req = ledger::set_endorser(&rev_reg_def_req, &endorser)?;
-
-            (Some(rev_reg_def_req), Some(rev_reg_delta_req))
-        }
-        _ => (None, None)
+    let (cred_def_id, cred_def_json, rev_reg_id, rev_reg_def, rev_reg_entry) = _create_credentialdef(wallet::get_wallet_handle(), &issuer_did, &schema_id, &tag, &revocation_details)?;
+    let rev_reg_def = _publish_tails_file(&revocation_details, rev_reg_def)?;
+
+    // Creates Credential Definition and Revocation related requests
+    let (cred_def_req, rev_reg_def_req, rev_reg_delta_req) = {
+        let rev_reg = match (&rev_reg_id, &rev_reg_def, &rev_reg_entry) {
+            (Some(ref rev_reg_id), Some(ref rev_reg_def), Some(ref rev_reg_entry)) => Some((rev_reg_id.as_str(), rev_reg_def.as_str(), rev_reg_entry.as_str())),
+            _ => None
+        };
+        _build_requests_for_endorser(&issuer_did, &cred_def_json, rev_reg, &endorser)?
     };
 
     let rev_reg = match (rev_reg_id, rev_reg_def, rev_reg_entry, revocation_details.tails_file, revocation_details.max_creds) {
@@ -255,6 +460,7 @@ pub fn prepare_credentialdef_for_endorser(source_id: String,
                 tag: 1,
                 rev_reg_def_payment_txn: None,
                 rev_reg_delta_payment_txn: None,
+                issued: 0,
             })
         }
         _ => None
@@ -269,13 +475,52 @@ pub fn prepare_credentialdef_for_endorser(source_id: String,
         cred_def_payment_txn: None,
         rev_reg,
         state: PublicEntityStateType::Built,
+        cred_def_json: Some(cred_def_json),
+        schema_id: Some(schema_id),
+        next_max_creds: None,
     };
+    cred_def.save_record();
 
     let handle = CREDENTIALDEF_MAP.add(cred_def).or(Err(VcxError::from(VcxErrorKind::CreateCredDef)))?;
 
     Ok((handle, cred_def_req, rev_reg_def_req, rev_reg_delta_req))
 }
 
+/// Runs `create_and_publish_credentialdef` as a dry run: the credential definition transactions are built,
+/// signed and priced but not submitted. Keys of the credential definition (and revocation registry with its tails)
+/// are generated in a scratch wallet which is deleted afterwards, so neither the wallet nor the tails location
+/// are touched and no handle is created.
+pub fn dry_run_credentialdef(source_id: String,
+                             issuer_did: String,
+                             schema_id: String,
+                             tag: String,
+                             revocation_details: String) -> VcxResult<String> {
+    trace!("dry_run_credentialdef >>> source_id: {}, issuer_did: {}, schema_id: {}, revocation_details: {}",
+           source_id, issuer_did, schema_id, revocation_details);
+
+    let revocation_details: RevocationDetails = _parse_revocation_details(&revocation_details)?;
+
+    let publication = _publish_credentialdef(&issuer_did, &schema_id, &tag, &revocation_details, true)?;
+
+    let (rev_reg_id, rev_reg_def_txn, rev_reg_delta_txn) = match publication.rev_reg {
+        Some(rev_reg) => (Some(rev_reg.rev_reg_id), Some(rev_reg.rev_reg_def_txn), Some(rev_reg.rev_reg_delta_txn)),
+        None => (None, None, None)
+    };
+
+    let total_price = publication.cred_def_txn.price
+        + rev_reg_def_txn.as_ref().map(|txn| txn.price).unwrap_or(0)
+        + rev_reg_delta_txn.as_ref().map(|txn| txn.price).unwrap_or(0);
+
+    Ok(json!({
+        "cred_def_id": publication.cred_def_id,
+        "rev_reg_id": rev_reg_id,
+        "cred_def_txn": publication.cred_def_txn,
+        "rev_reg_def_txn": rev_reg_def_txn,
+        "rev_reg_delta_txn": rev_reg_delta_txn,
+        "total_price": total_price,
+    }).to_string())
+}
+
 pub fn create_and_publish_credentialdef(source_id: String,
                                         name: String,
                                         issuer_did: String,
@@ -287,37 +532,20 @@ pub fn create_and_publish_credentialdef(source_id: String,
 
     let revocation_details: RevocationDetails = _parse_revocation_details(&revocation_details)?;
 
-    // Creates Credential Definition and Revocation Definition in wallet
-    let (cred_def_id, cred_def_json, rev_reg_id, rev_reg_def, rev_reg_entry) = _create_credentialdef(&issuer_did, &schema_id, &tag, &revocation_details)?;
-
-    // Publish Credential Definition on the ledger
-    let cred_def_payment_txn = anoncreds::publish_cred_def(&issuer_did, &cred_def_json)?;
-
-    // Publish Revocation related requests on the ledger
-    let (rev_def_payment, rev_delta_payment) = match (&rev_reg_id, &rev_reg_def, &rev_reg_entry) {
-        (Some(ref rev_reg_id), Some(ref rev_reg_def), Some(ref rev_reg_entry)) => {
-            let rev_def_payment = anoncreds::publish_rev_reg_def(&issuer_did, &rev_reg_def)
-                .map_err(|err| err.map(VcxErrorKind::CreateCredDef, "Cannot create CredentialDefinition"))?;
-
-            let (rev_delta_payment, _) = anoncreds::publish_rev_reg_delta(&issuer_did, &rev_reg_id, &rev_reg_entry)
-                .map_err(|err| err.map(VcxErrorKind::InvalidRevocationEntry, "Cannot post RevocationEntry"))?;
-
-            (rev_def_payment, rev_delta_payment)
-        }
-        _ => (None, None)
-    };
+    let publication = _publish_credentialdef(&issuer_did, &schema_id, &tag, &revocation_details, false)?;
 
-    let rev_reg = match (rev_reg_id, rev_reg_def, rev_reg_entry, revocation_details.tails_file, revocation_details.max_creds) {
-        (Some(rev_reg_id), Some(rev_reg_def), Some(rev_reg_entry), Some(tails_file), Some(max_creds)) => {
+    let rev_reg = match (publication.rev_reg, revocation_details.tails_file, revocation_details.max_creds) {
+        (Some(rev_reg), Some(tails_file), Some(max_creds)) => {
             Some(RevocationRegistry {
-                rev_reg_id,
-                rev_reg_def,
-                rev_reg_entry,
+                rev_reg_id: rev_reg.rev_reg_id,
+                rev_reg_def: rev_reg.rev_reg_def,
+                rev_reg_entry: rev_reg.rev_reg_entry,
                 tails_file,
                 max_creds,
                 tag: 1,
-                rev_reg_def_payment_txn: rev_def_payment,
-                rev_reg_delta_payment_txn: rev_delta_payment,
+                rev_reg_def_payment_txn: rev_reg.rev_reg_def_txn.payment,
+                rev_reg_delta_payment_txn: rev_reg.rev_reg_delta_txn.payment,
+                issued: 0,
             })
         }
         _ => None
@@ -327,22 +555,96 @@ pub fn create_and_publish_credentialdef(source_id: String,
         source_id,
         name,
         tag,
-        id: cred_def_id,
+        id: publication.cred_def_id,
         issuer_did: Some(issuer_did),
-        cred_def_payment_txn,
+        cred_def_payment_txn: publication.cred_def_txn.payment,
         rev_reg,
         state: PublicEntityStateType::Published,
+        cred_def_json: None,
+        schema_id: Some(schema_id),
+        next_max_creds: None,
     };
+    cred_def.save_record();
 
     let handle = CREDENTIALDEF_MAP.add(cred_def).or(Err(VcxError::from(VcxErrorKind::CreateCredDef)))?;
 
     Ok(handle)
 }
 
+// credential definition with its revocation registry written by `_publish_credentialdef`,
+// the transactions are only prepared when it was a dry run
+struct CredDefPublication {
+    cred_def_id: String,
+    cred_def_txn: LedgerTxn,
+    rev_reg: Option<RevRegPublication>,
+}
+
+struct RevRegPublication {
+    rev_reg_id: String,
+    rev_reg_def: String,
+    rev_reg_entry: String,
+    rev_reg_def_txn: LedgerTxn,
+    rev_reg_delta_txn: LedgerTxn,
+}
+
+fn _publish_credentialdef(issuer_did: &str,
+                          schema_id: &str,
+                          tag: &str,
+                          revocation_details: &RevocationDetails,
+                          dry_run: bool) -> VcxResult<CredDefPublication> {
+    // keys of a dry run are generated in a scratch wallet, with the tails next to it
+    let scratch = if dry_run { Some(wallet::ScratchWallet::create()?) } else { None };
+    let mut revocation_details = revocation_details.clone();
+    let wallet_handle = match scratch {
+        Some(ref scratch) => {
+            if revocation_details.tails_file.is_some() {
+                revocation_details.tails_file = Some(scratch.dir.to_string_lossy().to_string());
+            }
+            scratch.handle
+        }
+        None => wallet::get_wallet_handle()
+    };
+
+    // Creates Credential Definition and Revocation Definition in wallet
+    let (cred_def_id, cred_def_json, rev_reg_id, rev_reg_def, rev_reg_entry) = _create_credentialdef(wallet_handle, issuer_did, schema_id, tag, &revocation_details)?;
+    let rev_reg_def = if dry_run { rev_reg_def } else { _publish_tails_file(&revocation_details, rev_reg_def)? };
+
+    // Written to the network recorded in the issuer DID
+    let _network = pool::enter_network_of(issuer_did)?;
+
+    // Publish Credential Definition on the ledger
+    let cred_def_txn = anoncreds::write_cred_def(issuer_did, &cred_def_json, dry_run)?;
+
+    // Publish Revocation related requests on the ledger
+    let rev_reg = match (rev_reg_id, rev_reg_def, rev_reg_entry) {
+        (Some(rev_reg_id), Some(rev_reg_def), Some(rev_reg_entry)) => {
+            let rev_reg_def_txn = anoncreds::write_rev_reg_def(issuer_did, &rev_reg_def, dry_run)
+                .map_err(|err| err.map(VcxErrorKind::CreateCredDef, "Cannot create CredentialDefinition"))?;
+
+            let rev_reg_delta_txn = anoncreds::write_rev_reg_delta(issuer_did, &rev_reg_id, &rev_reg_entry, dry_run)
+                .map_err(|err| err.map(VcxErrorKind::InvalidRevocationEntry, "Cannot post RevocationEntry"))?;
+
+            Some(RevRegPublication { rev_reg_id, rev_reg_def, rev_reg_entry, rev_reg_def_txn, rev_reg_delta_txn })
+        }
+        _ => None
+    };
+
+    Ok(CredDefPublication { cred_def_id, cred_def_txn, rev_reg })
+}
+
 pub fn publish_revocations(handle: u32) -> VcxResult<()> {
+    _publish_revocations(handle, false).map(|_| ())
+}
+
+/// Runs `publish_revocations` as a dry run: the transaction is prepared without being submitted
+/// and the local revocation cache is left untouched.
+pub fn dry_run_publish_revocations(handle: u32) -> VcxResult<String> {
+    _publish_revocations(handle, true).map(|txn| json!(txn).to_string())
+}
+
+fn _publish_revocations(handle: u32, dry_run: bool) -> VcxResult<LedgerTxn> {
     if let Some(rev_reg_id) = get_rev_reg_id(handle)? {
-        anoncreds::publish_local_revocations(rev_reg_id.as_str())?;
-        Ok(())
+        anoncreds::write_local_revocations(rev_reg_id.as_str(), dry_run)
     } else {
         Err(VcxError::from(VcxErrorKind::InvalidCredDefHandle))
     }
@@ -381,12 +683,28 @@ pub fn get_cred_def_id(handle: u32) -> VcxResult<String> {
     })
 }
 
+/// Fails for definitions created before their schema was recorded with them.
+pub fn get_schema_id(handle: u32) -> VcxResult<String> {
+    CREDENTIALDEF_MAP.get(handle, |c| {
+        c.schema_id.clone()
+            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidSchema, "Schema of the credential definition is not known"))
+    })
+}
+
 pub fn get_rev_reg_id(handle: u32) -> VcxResult<Option<String>> {
     CREDENTIALDEF_MAP.get(handle, |c| {
         Ok(c.get_rev_reg_id().cloned())
     })
 }
 
+pub fn get_active_rev_reg_id(handle: u32) -> VcxResult<String> {
+    CREDENTIALDEF_MAP.get(handle, |c| {
+        c.get_rev_reg_id()
+            .cloned()
+            .ok_or(VcxError::from_msg(VcxErrorKind::RevRegDefNotFound, "No revocation registry definitions associated with this credential definition"))
+    })
+}
+
 pub fn get_tails_file(handle: u32) -> VcxResult<Option<String>> {
     CREDENTIALDEF_MAP.get(handle, |c| {
         Ok(c.get_tails_file())
@@ -433,6 +751,61 @@ pub fn get_state(handle: u32) -> VcxResult<u32> {
     })
 }
 
+/// Builds the transactions of a not yet published credential definition again, signed by the issuer
+/// and ready to be submitted by `endorser`. Revocation related transactions are returned only for revocable definitions.
+pub fn prepare_for_endorser(handle: u32, endorser: &str) -> VcxResult<(String, Option<String>, Option<String>)> {
+    CREDENTIALDEF_MAP.get(handle, |s| {
+        s.prepare_for_endorser(endorser)
+    })
+}
+
+/// Credential definitions created or prepared for an endorser by this issuer, read from the wallet.
+pub fn list_created_credential_defs() -> VcxResult<String> {
+    trace!("list_created_credential_defs >>>");
+
+    let options = json!({"retrieveType": false, "retrieveValue": true, "retrieveTags": false}).to_string();
+    let mut cred_defs: Vec<CredentialDefInfo> = Vec::new();
+
+    for record in wallet::search_records(CREDENTIALDEF_RECORD_TYPE, "{}", &options, LIST_PAGE_SIZE)? {
+        let record = record?;
+        let value = record["value"].as_str()
+            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Credential definition record {:?} has no value", record["id"])))?;
+        cred_defs.push(serde_json::from_str(value)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize credential definition record: {:?}", err)))?);
+    }
+
+    serde_json::to_string(&cred_defs)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize credential definitions: {:?}", err)))
+}
+
+/// Returns json with the id, capacity and number of issued credentials of the active revocation registry.
+pub fn get_rev_reg_usage(handle: u32) -> VcxResult<String> {
+    CREDENTIALDEF_MAP.get(handle, |s| {
+        let usage = s.get_rev_reg_usage()?;
+        serde_json::to_string(&usage)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize revocation registry usage: {:?}", err)))
+    })
+}
+
+///
+/// Amends revocation parameters of a revocable credential definition, `revocation_details` may contain
+/// `tails_file` and `max_creds`. New `max_creds` is used by registries created by the following rotations,
+/// new `tails_file` location publishes a new registry immediately. Returns the serialized credential definition.
+///
+pub fn update_revocation_details(handle: u32, revocation_details: &str) -> VcxResult<String> {
+    let revocation_details = _parse_revocation_details(revocation_details)?;
+    if revocation_details.support_revocation == Some(false) {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidRevocationDetails, "Revocation support of a credential definition cannot be turned off"));
+    }
+
+    CREDENTIALDEF_MAP.get_mut(handle, |s| {
+        if let Some(new_rev_reg) = s.update_revocation_details(revocation_details.tails_file.clone(), revocation_details.max_creds)? {
+            update_rev_reg_ids_cache(&s.id, &new_rev_reg.rev_reg_id)?;
+        }
+        s.to_string()
+    })
+}
+
 pub fn check_is_published(handle: u32) -> VcxResult<bool> {
     CREDENTIALDEF_MAP.get_mut(handle, |s| {
         Ok(PublicEntityStateType::Published == s.state)
@@ -443,7 +816,7 @@ pub fn rotate_rev_reg_def(handle: u32) -> VcxResult<String> {
     CREDENTIALDEF_MAP.get_mut(handle, |s| {
         match &s.issuer_did {
             Some(_) => {
-                let new_rev_reg = s.rotate_rev_reg()?;
+                let new_rev_reg = s.rotate_rev_reg(None)?;
                 match update_rev_reg_ids_cache(&s.id, &new_rev_reg.rev_reg_id) {
                     Ok(()) => s.to_string(),
                     Err(err) => Err(err)
@@ -455,6 +828,41 @@ pub fn rotate_rev_reg_def(handle: u32) -> VcxResult<String> {
     })
 }
 
+/// Reserves place for one more credential in the active revocation registry and returns its id and tails file.
+/// Once the registry reaches `max_creds`, a new registry is created and published and becomes the active one.
+pub fn reserve_rev_reg(handle: u32) -> VcxResult<(Option<String>, Option<String>)> {
+    CREDENTIALDEF_MAP.get_mut(handle, |s| {
+        if s.is_rev_reg_full() {
+            let new_rev_reg = s.rotate_rev_reg(None)?;
+            update_rev_reg_ids_cache(&s.id, &new_rev_reg.rev_reg_id)?;
+        }
+        Ok(s.reserve_rev_reg_slot())
+    })
+}
+
+/// Handle of the loaded credential definition `cred_def_id`.
+pub fn find_handle_by_id(cred_def_id: &str) -> VcxResult<u32> {
+    CREDENTIALDEF_MAP.handles()?.into_iter()
+        .find(|handle| CREDENTIALDEF_MAP.get(*handle, |s| Ok(s.id == cred_def_id)).unwrap_or(false))
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidCredDefHandle, format!("Credential definition {} is not loaded", cred_def_id)))
+}
+
+/// Same as `reserve_rev_reg` for the loaded credential definition `cred_def_id`.
+pub fn reserve_rev_reg_by_id(cred_def_id: &str) -> VcxResult<(Option<String>, Option<String>)> {
+    let handle = find_handle_by_id(cred_def_id)
+        .map_err(|err| err.extend(format!("Credential definition {} has to be loaded to issue revocable credentials of it", cred_def_id)))?;
+    reserve_rev_reg(handle)
+}
+
+/// Gives back place reserved in the active revocation registry `rev_reg_id` for a credential which was not issued.
+pub fn release_rev_reg_slot(rev_reg_id: &str) -> VcxResult<()> {
+    for handle in CREDENTIALDEF_MAP.handles()? {
+        if CREDENTIALDEF_MAP.get_mut(handle, |s| Ok(s.release_rev_reg_slot(rev_reg_id)))? {
+            break;
+        }
+    }
+    Ok(())
+}
 
 #[cfg(test)]
 pub mod tests {
@@ -542,6 +950,196 @@ pub mod tests {
         assert!(payment.amount > 0);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_prepare_for_endorser_with_handle() {
+        let _setup = SetupAriesMocks::init();
+
+        let (handle, _, _, _) = prepare_credentialdef_for_endorser("1".to_string(),
+                                                                   CREDENTIAL_DEF_NAME.to_string(),
+                                                                   ISSUER_DID.to_string(),
+                                                                   SCHEMA_ID.to_string(),
+                                                                   "tag".to_string(),
+                                                                   "{}".to_string(),
+                                                                   "V4SGRU86Z58d6TV7PBUe6f".to_string()).unwrap();
+
+        let (cred_def_req, rev_reg_def_req, rev_reg_delta_req) = prepare_for_endorser(handle, "V4SGRU86Z58d6TV7PBUe6f").unwrap();
+        assert_eq!(cred_def_req, ::utils::constants::REQUEST_WITH_ENDORSER);
+        assert!(rev_reg_def_req.is_none());
+        assert!(rev_reg_delta_req.is_none());
+
+        // definition is restored together with the handle
+        let handle = from_string(&to_string(handle).unwrap()).unwrap();
+        prepare_for_endorser(handle, "V4SGRU86Z58d6TV7PBUe6f").unwrap();
+
+        assert_eq!(update_state(handle).unwrap(), PublicEntityStateType::Published as u32);
+        assert_eq!(prepare_for_endorser(handle, "V4SGRU86Z58d6TV7PBUe6f").unwrap_err().kind(), VcxErrorKind::InvalidState);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_dry_run_cred_def() {
+        let _setup = SetupAriesMocks::init();
+
+        let dry_run = dry_run_credentialdef("1".to_string(),
+                                            ISSUER_DID.to_string(),
+                                            SCHEMA_ID.to_string(),
+                                            "tag".to_string(),
+                                            "{}".to_string()).unwrap();
+        let dry_run: serde_json::Value = serde_json::from_str(&dry_run).unwrap();
+
+        assert!(dry_run["cred_def_txn"]["request"].is_object());
+        assert!(dry_run["rev_reg_def_txn"].is_null());
+        assert_eq!(dry_run["total_price"], json!(0));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_reserve_rev_reg_rotates_full_registry() {
+        let _setup = SetupAriesMocks::init();
+
+        let revocation_details = json!({"support_revocation": true, "tails_file": get_temp_dir_path("tails_file.txt").to_str().unwrap(), "max_creds": 2});
+        let handle = create_and_publish_credentialdef("1".to_string(),
+                                                      CREDENTIAL_DEF_NAME.to_string(),
+                                                      ISSUER_DID.to_string(),
+                                                      SCHEMA_ID.to_string(),
+                                                      "tag".to_string(),
+                                                      revocation_details.to_string()).unwrap();
+
+        for _ in 0..3 {
+            let (rev_reg_id, tails_file) = reserve_rev_reg(handle).unwrap();
+            assert_eq!(rev_reg_id.unwrap(), get_active_rev_reg_id(handle).unwrap());
+            assert!(tails_file.is_some());
+        }
+
+        let rev_reg = CREDENTIALDEF_MAP.get(handle, |c| Ok(c.rev_reg.clone().unwrap())).unwrap();
+        assert_eq!(rev_reg.tag, 2);
+        assert_eq!(rev_reg.issued, 1);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_reserve_and_release_rev_reg_slot_by_id() {
+        let _setup = SetupAriesMocks::init();
+
+        let revocation_details = json!({"support_revocation": true, "tails_file": get_temp_dir_path("tails_file.txt").to_str().unwrap(), "max_creds": 2});
+        let handle = create_and_publish_credentialdef("1".to_string(),
+                                                      CREDENTIAL_DEF_NAME.to_string(),
+                                                      ISSUER_DID.to_string(),
+                                                      SCHEMA_ID.to_string(),
+                                                      "tag".to_string(),
+                                                      revocation_details.to_string()).unwrap();
+        let cred_def_id = get_cred_def_id(handle).unwrap();
+
+        let (rev_reg_id, _) = reserve_rev_reg_by_id(&cred_def_id).unwrap();
+        let issued = || CREDENTIALDEF_MAP.get(handle, |c| Ok(c.rev_reg.as_ref().unwrap().issued)).unwrap();
+        assert_eq!(issued(), 1);
+
+        release_rev_reg_slot(&rev_reg_id.unwrap()).unwrap();
+        assert_eq!(issued(), 0);
+
+        assert_eq!(reserve_rev_reg_by_id("unknown").unwrap_err().kind(), VcxErrorKind::InvalidCredDefHandle);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_legacy_rev_reg_is_taken_as_full() {
+        let _setup = SetupAriesMocks::init();
+
+        let revocation_details = json!({"support_revocation": true, "tails_file": get_temp_dir_path("tails_file.txt").to_str().unwrap(), "max_creds": 2});
+        let handle = create_and_publish_credentialdef("1".to_string(),
+                                                      CREDENTIAL_DEF_NAME.to_string(),
+                                                      ISSUER_DID.to_string(),
+                                                      SCHEMA_ID.to_string(),
+                                                      "tag".to_string(),
+                                                      revocation_details.to_string()).unwrap();
+
+        let mut serialized: serde_json::Value = serde_json::from_str(&to_string(handle).unwrap()).unwrap();
+        serialized["data"]["rev_reg"].as_object_mut().unwrap().remove("issued");
+        let legacy = CredentialDef::from_str(&serialized.to_string()).unwrap();
+
+        assert!(legacy.is_rev_reg_full());
+        assert_eq!(legacy.get_rev_reg_usage().unwrap().issued, 2);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_update_revocation_details() {
+        let _setup = SetupAriesMocks::init();
+
+        let revocation_details = json!({"support_revocation": true, "tails_file": get_temp_dir_path("tails_file.txt").to_str().unwrap(), "max_creds": 2});
+        let handle = create_and_publish_credentialdef("1".to_string(),
+                                                      CREDENTIAL_DEF_NAME.to_string(),
+                                                      ISSUER_DID.to_string(),
+                                                      SCHEMA_ID.to_string(),
+                                                      "tag".to_string(),
+                                                      revocation_details.to_string()).unwrap();
+        reserve_rev_reg(handle).unwrap();
+
+        let usage: RevRegUsage = serde_json::from_str(&get_rev_reg_usage(handle).unwrap()).unwrap();
+        assert_eq!((usage.max_creds, usage.issued, usage.remaining, usage.next_max_creds), (2, 1, 1, 2));
+
+        // capacity change applies to the next registry only
+        update_revocation_details(handle, &json!({"max_creds": 5}).to_string()).unwrap();
+        let usage: RevRegUsage = serde_json::from_str(&get_rev_reg_usage(handle).unwrap()).unwrap();
+        assert_eq!((usage.max_creds, usage.issued, usage.remaining, usage.next_max_creds), (2, 1, 1, 5));
+
+        // new tails location rotates the registry
+        let tails_file = get_temp_dir_path("tails_file_rotated.txt").to_str().unwrap().to_string();
+        update_revocation_details(handle, &json!({"tails_file": tails_file}).to_string()).unwrap();
+        let usage: RevRegUsage = serde_json::from_str(&get_rev_reg_usage(handle).unwrap()).unwrap();
+        assert_eq!((usage.max_creds, usage.issued, usage.remaining, usage.next_max_creds), (5, 0, 5, 5));
+        assert_eq!(get_tails_file(handle).unwrap(), Some(tails_file));
+
+        assert_eq!(update_revocation_details(handle, &json!({"max_creds": 0}).to_string()).unwrap_err().kind(), VcxErrorKind::InvalidRevocationDetails);
+        assert_eq!(update_revocation_details(handle, &json!({"support_revocation": false}).to_string()).unwrap_err().kind(), VcxErrorKind::InvalidRevocationDetails);
+
+        let handle = create_cred_def_fake();
+        assert_eq!(get_rev_reg_usage(handle).unwrap_err().kind(), VcxErrorKind::RevRegDefNotFound);
+        assert_eq!(update_revocation_details(handle, &json!({"max_creds": 5}).to_string()).unwrap_err().kind(), VcxErrorKind::RevRegDefNotFound);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_active_rev_reg_id_fails_for_non_revocable_cred_def() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle = create_cred_def_fake();
+        assert_eq!(reserve_rev_reg(handle).unwrap(), (None, None));
+        assert_eq!(get_active_rev_reg_id(handle).unwrap_err().kind(), VcxErrorKind::RevRegDefNotFound);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_list_created_credential_defs() {
+        let _setup = SetupLibraryWallet::init();
+
+        let mut cred_def = CredentialDef {
+            id: "4fUDR9R7fjwELRvH9JT6HH:3:CL:1766:tag".to_string(),
+            tag: "tag".to_string(),
+            name: CREDENTIAL_DEF_NAME.to_string(),
+            source_id: "source".to_string(),
+            issuer_did: Some(ISSUER_DID.to_string()),
+            cred_def_payment_txn: None,
+            rev_reg: None,
+            state: PublicEntityStateType::Built,
+            cred_def_json: Some("{}".to_string()),
+            schema_id: Some(SCHEMA_ID.to_string()),
+            next_max_creds: None,
+        };
+        cred_def.save_record();
+
+        cred_def.state = PublicEntityStateType::Published;
+        cred_def.save_record();
+
+        let cred_defs: Vec<CredentialDefInfo> = serde_json::from_str(&list_created_credential_defs().unwrap()).unwrap();
+        assert_eq!(cred_defs.len(), 1);
+        assert_eq!(cred_defs[0].cred_def_id, cred_def.id);
+        assert_eq!(cred_defs[0].schema_id, Some(SCHEMA_ID.to_string()));
+        assert_eq!(cred_defs[0].rev_reg_id, None);
+        assert_eq!(cred_defs[0].state, PublicEntityStateType::Published);
+    }
+
     #[cfg(feature = "pool_tests")]
     #[test]
     fn test_get_credential_def() {
@@ -0,0 +1,356 @@
+use std::collections::BTreeMap;
+
+use regex::Regex;
+use serde_json;
+
+use credential_def;
+use error::prelude::*;
+use issuer_credential;
+use messages::ObjectWithVersion;
+use utils::constants::DEFAULT_SERIALIZE_VERSION;
+use utils::events;
+use utils::object_cache::ObjectCache;
+use utils::persistence;
+
+lazy_static! {
+    static ref CREDENTIAL_TEMPLATE_MAP: ObjectCache<CredentialTemplate> = ObjectCache::<CredentialTemplate>::new("credential-templates-cache");
+}
+
+/// Constraint checked on the value of a dynamic attribute when an offer is minted from the template.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct AttributeRule {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+}
+
+/// Template definition supplied by the issuer:
+/// `{"fixed": {<name>: <value>}, "dynamic": [<name>], "rules": {<name>: {"pattern": <regex>, "max_length": <n>}}}`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+struct TemplateDefinition {
+    #[serde(default)]
+    fixed: BTreeMap<String, String>,
+    #[serde(default)]
+    dynamic: Vec<String>,
+    #[serde(default)]
+    rules: BTreeMap<String, AttributeRule>,
+}
+
+///
+/// Credential offer defined once by the issuer: credential definition, attribute values shared by all
+/// credentials and names of attributes supplied for every offer together with rules for their values.
+/// The credential definition is referred to by its id, so that the template outlives its handle.
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CredentialTemplate {
+    source_id: String,
+    name: String,
+    cred_def_id: String,
+    schema_id: String,
+    fixed_attributes: BTreeMap<String, String>,
+    dynamic_attributes: Vec<String>,
+    rules: BTreeMap<String, AttributeRule>,
+}
+
+impl CredentialTemplate {
+    fn create(source_id: &str, cred_def_handle: u32, name: &str, template: &str) -> VcxResult<CredentialTemplate> {
+        let template: TemplateDefinition = serde_json::from_str(template)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize credential template: {}", err)))?;
+
+        let overlapping: Vec<&String> = template.dynamic.iter()
+            .filter(|name| template.fixed.contains_key(name.as_str()))
+            .collect();
+        if !overlapping.is_empty() {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidAttributesStructure,
+                                          format!("Attributes are declared both fixed and dynamic: {:?}", overlapping)));
+        }
+
+        if let Some(name) = template.rules.keys().find(|name| !template.dynamic.contains(name)) {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidAttributesStructure,
+                                          format!("Rule is defined for attribute {} which is not dynamic", name)));
+        }
+
+        for (name, rule) in template.rules.iter() {
+            if let Some(ref pattern) = rule.pattern {
+                _whole_value_regex(name, pattern)?;
+            }
+        }
+
+        let cred_def_id = credential_def::get_cred_def_id(cred_def_handle)?;
+        let schema_id = credential_def::get_schema_id(cred_def_handle)?;
+
+        // completeness is checked the same way as for credential data passed to `issuer_credential_create`
+        let mut attributes: BTreeMap<&String, &str> = template.fixed.iter().map(|(name, value)| (name, value.as_str())).collect();
+        attributes.extend(template.dynamic.iter().map(|name| (name, "")));
+
+        let diff = issuer_credential::diff_credential_attributes(&schema_id, &json!(attributes).to_string())?;
+        if !diff.is_empty() {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidAttributesStructure,
+                                          format!("Template attributes do not match schema {}: missing {:?}, extra {:?}", schema_id, diff.missing, diff.extra)));
+        }
+
+        Ok(CredentialTemplate {
+            source_id: source_id.to_string(),
+            name: name.to_string(),
+            cred_def_id,
+            schema_id,
+            fixed_attributes: template.fixed,
+            dynamic_attributes: template.dynamic,
+            rules: template.rules,
+        })
+    }
+
+    fn build_credential_data(&self, dynamic_values: &str) -> VcxResult<String> {
+        let dynamic_values: BTreeMap<String, String> = serde_json::from_str(dynamic_values)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize dynamic attribute values: {}", err)))?;
+
+        let missing: Vec<&String> = self.dynamic_attributes.iter().filter(|name| !dynamic_values.contains_key(name.as_str())).collect();
+        let extra: Vec<&String> = dynamic_values.keys().filter(|name| !self.dynamic_attributes.contains(name)).collect();
+        if !missing.is_empty() || !extra.is_empty() {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidAttributesStructure,
+                                          format!("Values do not match dynamic attributes of template {}: missing {:?}, extra {:?}", self.name, missing, extra)));
+        }
+
+        for (name, rule) in self.rules.iter() {
+            let value = &dynamic_values[name];
+
+            if let Some(max_length) = rule.max_length {
+                if value.chars().count() > max_length {
+                    return Err(VcxError::from_msg(VcxErrorKind::InvalidAttributesStructure,
+                                                  format!("Value of attribute {} is longer than {} characters", name, max_length)));
+                }
+            }
+
+            if let Some(ref pattern) = rule.pattern {
+                if !_whole_value_regex(name, pattern)?.is_match(value) {
+                    return Err(VcxError::from_msg(VcxErrorKind::InvalidAttributesStructure,
+                                                  format!("Value of attribute {} does not match pattern {}", name, pattern)));
+                }
+            }
+        }
+
+        let mut credential_data = self.fixed_attributes.clone();
+        credential_data.extend(dynamic_values);
+
+        Ok(json!(credential_data).to_string())
+    }
+
+    fn to_string(&self) -> VcxResult<String> {
+        ObjectWithVersion::new(DEFAULT_SERIALIZE_VERSION, self.to_owned())
+            .serialize()
+            .map_err(|err| err.extend("Cannot serialize Credential Template"))
+    }
+
+    fn from_str(data: &str) -> VcxResult<CredentialTemplate> {
+        ObjectWithVersion::deserialize(data)
+            .map(|obj: ObjectWithVersion<CredentialTemplate>| obj.data)
+            .map_err(|err| err.extend("Cannot deserialize Credential Template"))
+    }
+}
+
+// pattern has to match the whole value, not just its part
+fn _whole_value_regex(name: &str, pattern: &str) -> VcxResult<Regex> {
+    Regex::new(&format!("^(?:{})$", pattern))
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Invalid pattern of attribute {}: {}", name, err)))
+}
+
+///
+/// Creates template of credentials issued with the credential definition, `template` lists values of fixed
+/// attributes, names of dynamic attributes and optional rules for dynamic values. Together fixed and dynamic
+/// attributes must cover the schema of the credential definition.
+///
+pub fn create(source_id: String, cred_def_handle: u32, name: String, template: String) -> VcxResult<u32> {
+    trace!("credential_template::create >>> source_id: {}, cred_def_handle: {}, name: {}, template: {}",
+           source_id, cred_def_handle, name, secret!(&template));
+
+    let template = CredentialTemplate::create(&source_id, cred_def_handle, &name, &template)?;
+    persistence::add(&CREDENTIAL_TEMPLATE_MAP, events::CREDENTIAL_TEMPLATE, template, to_string)
+}
+
+/// Merges dynamic values with fixed attributes of the template into credential data.
+pub fn build_credential_data(handle: u32, dynamic_values: &str) -> VcxResult<String> {
+    CREDENTIAL_TEMPLATE_MAP.get(handle, |template| {
+        template.build_credential_data(dynamic_values)
+    }).map_err(handle_err)
+}
+
+/// Handle of the credential definition of the template, the definition has to be loaded.
+pub fn get_cred_def_handle(handle: u32) -> VcxResult<u32> {
+    credential_def::find_handle_by_id(&get_cred_def_id(handle)?)
+}
+
+pub fn get_cred_def_id(handle: u32) -> VcxResult<String> {
+    CREDENTIAL_TEMPLATE_MAP.get(handle, |template| {
+        Ok(template.cred_def_id.clone())
+    }).map_err(handle_err)
+}
+
+pub fn get_name(handle: u32) -> VcxResult<String> {
+    CREDENTIAL_TEMPLATE_MAP.get(handle, |template| {
+        Ok(template.name.clone())
+    }).map_err(handle_err)
+}
+
+pub fn get_source_id(handle: u32) -> VcxResult<String> {
+    CREDENTIAL_TEMPLATE_MAP.get(handle, |template| {
+        Ok(template.source_id.clone())
+    }).map_err(handle_err)
+}
+
+pub fn is_valid_handle(handle: u32) -> bool {
+    CREDENTIAL_TEMPLATE_MAP.has_handle(handle)
+}
+
+pub fn to_string(handle: u32) -> VcxResult<String> {
+    CREDENTIAL_TEMPLATE_MAP.get(handle, |template| {
+        template.to_string()
+    }).map_err(handle_err)
+}
+
+pub fn from_string(template_data: &str) -> VcxResult<u32> {
+    let template = CredentialTemplate::from_str(template_data)?;
+    CREDENTIAL_TEMPLATE_MAP.add(template)
+}
+
+pub fn release(handle: u32) -> VcxResult<()> {
+    CREDENTIAL_TEMPLATE_MAP.release(handle)
+        .or(Err(VcxError::from(VcxErrorKind::InvalidCredentialTemplateHandle)))?;
+    persistence::forget(events::CREDENTIAL_TEMPLATE, handle);
+    Ok(())
+}
+
+pub fn release_all() {
+    persistence::forget_all(&CREDENTIAL_TEMPLATE_MAP, events::CREDENTIAL_TEMPLATE);
+    CREDENTIAL_TEMPLATE_MAP.drain().ok();
+}
+
+fn handle_err(err: VcxError) -> VcxError {
+    if err.kind() == VcxErrorKind::InvalidHandle {
+        VcxError::from(VcxErrorKind::InvalidCredentialTemplateHandle)
+    } else {
+        err
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use credential_def::tests::create_cred_def_fake;
+    use utils::devsetup::SetupMocks;
+
+    use super::*;
+
+    pub fn _template() -> String {
+        json!({
+            "fixed": {"sex": "female", "height": "170"},
+            "dynamic": ["name", "age"],
+            "rules": {"age": {"pattern": "^[0-9]+$", "max_length": 3}}
+        }).to_string()
+    }
+
+    pub fn create_template_fake() -> u32 {
+        create("1".to_string(), create_cred_def_fake(), "Employee ID".to_string(), _template()).unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_create_credential_template() {
+        let _setup = SetupMocks::init();
+
+        let cred_def_handle = create_cred_def_fake();
+        let handle = create("1".to_string(), cred_def_handle, "Employee ID".to_string(), _template()).unwrap();
+
+        assert_eq!(credential_def::get_cred_def_id(cred_def_handle).unwrap(), get_cred_def_id(handle).unwrap());
+        assert_eq!(get_cred_def_id(handle).unwrap(), credential_def::get_cred_def_id(get_cred_def_handle(handle).unwrap()).unwrap());
+        assert_eq!("Employee ID", get_name(handle).unwrap());
+        assert_eq!("1", get_source_id(handle).unwrap());
+
+        release(handle).unwrap();
+        assert_eq!(VcxErrorKind::InvalidCredentialTemplateHandle, release(handle).unwrap_err().kind());
+        assert_eq!(VcxErrorKind::InvalidCredentialTemplateHandle, to_string(handle).unwrap_err().kind());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_create_credential_template_validates_attributes() {
+        let _setup = SetupMocks::init();
+
+        let cred_def_handle = create_cred_def_fake();
+
+        let incomplete = json!({"fixed": {"sex": "female"}, "dynamic": ["name", "age"]}).to_string();
+        assert_eq!(VcxErrorKind::InvalidAttributesStructure,
+                   create("1".to_string(), cred_def_handle, "name".to_string(), incomplete).unwrap_err().kind());
+
+        let unknown = json!({"fixed": {"sex": "female", "height": "170", "eyes": "blue"}, "dynamic": ["name", "age"]}).to_string();
+        assert_eq!(VcxErrorKind::InvalidAttributesStructure,
+                   create("1".to_string(), cred_def_handle, "name".to_string(), unknown).unwrap_err().kind());
+
+        let overlapping = json!({"fixed": {"sex": "female", "height": "170", "age": "20"}, "dynamic": ["name", "age"]}).to_string();
+        assert_eq!(VcxErrorKind::InvalidAttributesStructure,
+                   create("1".to_string(), cred_def_handle, "name".to_string(), overlapping).unwrap_err().kind());
+
+        let invalid_pattern = json!({"fixed": {"sex": "female", "height": "170"}, "dynamic": ["name", "age"], "rules": {"age": {"pattern": "[0-9"}}}).to_string();
+        assert_eq!(VcxErrorKind::InvalidOption,
+                   create("1".to_string(), cred_def_handle, "name".to_string(), invalid_pattern).unwrap_err().kind());
+
+        assert_eq!(VcxErrorKind::InvalidJson,
+                   create("1".to_string(), cred_def_handle, "name".to_string(), "{".to_string()).unwrap_err().kind());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_build_credential_data() {
+        let _setup = SetupMocks::init();
+
+        let handle = create_template_fake();
+
+        let credential_data = build_credential_data(handle, &json!({"name": "Alice", "age": "25"}).to_string()).unwrap();
+        let credential_data: serde_json::Value = serde_json::from_str(&credential_data).unwrap();
+        assert_eq!(json!({"sex": "female", "height": "170", "name": "Alice", "age": "25"}), credential_data);
+
+        assert_eq!(VcxErrorKind::InvalidAttributesStructure,
+                   build_credential_data(handle, &json!({"name": "Alice"}).to_string()).unwrap_err().kind());
+        assert_eq!(VcxErrorKind::InvalidAttributesStructure,
+                   build_credential_data(handle, &json!({"name": "Alice", "age": "25", "sex": "male"}).to_string()).unwrap_err().kind());
+        assert_eq!(VcxErrorKind::InvalidAttributesStructure,
+                   build_credential_data(handle, &json!({"name": "Alice", "age": "twenty"}).to_string()).unwrap_err().kind());
+        assert_eq!(VcxErrorKind::InvalidAttributesStructure,
+                   build_credential_data(handle, &json!({"name": "Alice", "age": "1000"}).to_string()).unwrap_err().kind());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_credential_template_pattern_matches_whole_value() {
+        let _setup = SetupMocks::init();
+
+        let template = json!({
+            "fixed": {"sex": "female", "height": "170"},
+            "dynamic": ["name", "age"],
+            "rules": {"age": {"pattern": "[0-9]+"}, "name": {"pattern": "Alice|Bob"}}
+        }).to_string();
+        let handle = create("1".to_string(), create_cred_def_fake(), "Employee ID".to_string(), template).unwrap();
+
+        build_credential_data(handle, &json!({"name": "Bob", "age": "25"}).to_string()).unwrap();
+        assert_eq!(VcxErrorKind::InvalidAttributesStructure,
+                   build_credential_data(handle, &json!({"name": "Alice", "age": "age 25"}).to_string()).unwrap_err().kind());
+        assert_eq!(VcxErrorKind::InvalidAttributesStructure,
+                   build_credential_data(handle, &json!({"name": "Alicea", "age": "25"}).to_string()).unwrap_err().kind());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_credential_template_serialization() {
+        let _setup = SetupMocks::init();
+
+        let handle = create_template_fake();
+        let serialized = to_string(handle).unwrap();
+        assert!(serialized.contains(&get_cred_def_id(handle).unwrap()));
+        assert!(!serialized.contains("cred_def_handle"));
+
+        let new_handle = from_string(&serialized).unwrap();
+        assert_eq!(serialized, to_string(new_handle).unwrap());
+        assert_eq!(get_cred_def_handle(handle).unwrap(), get_cred_def_handle(new_handle).unwrap());
+    }
+}
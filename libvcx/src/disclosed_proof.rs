@@ -4,25 +4,44 @@ use serde_json;
 
 use aries::{
     handlers::proof_presentation::prover::prover::Prover,
+    messages::a2a::A2AMessage,
+    messages::out_of_band::invitation::OutOfBandInvitation,
+    messages::proof_presentation::format::PresentationFormat,
     messages::proof_presentation::presentation_request::PresentationRequest,
 };
 use connection;
+use disclosed_proof_utils;
 use error::prelude::*;
 use messages::{
     get_message::Message,
     payload::Payloads,
 };
-use messages::proofs::proof_request::ProofRequestMessage;
+use messages::proofs::proof_request::{ProofRequestData, ProofRequestMessage};
+use outbound_queue;
 use settings;
 use settings::indy_mocks_enabled;
+use utils::cancellation;
 use utils::constants::GET_MESSAGES_DECRYPTED_RESPONSE;
 use utils::error;
+use utils::events;
 use utils::httpclient::AgencyMockDecrypted;
+use utils::handle_token;
+use utils::json::check_json_limits;
+use utils::libindy::pool;
 use utils::mockdata::mockdata_proof::ARIES_PROOF_REQUEST_PRESENTATION;
-use utils::object_cache::ObjectCache;
+use utils::object_cache::{ObjectCache, ObjectKeys};
+use utils::persistence;
 
 lazy_static! {
-    static ref HANDLE_MAP: ObjectCache<Prover> = ObjectCache::<Prover>::new("disclosed-proofs-cache");
+    static ref HANDLE_MAP: ObjectCache<Prover> = ObjectCache::<Prover>::with_index("disclosed-proofs-cache", |proof| ObjectKeys {
+        source_id: Some(proof.get_source_id()),
+        thread_id: Some(proof.get_thread_id()).filter(|thread_id| !thread_id.is_empty()),
+        metadata: proof.get_metadata().clone(),
+    }).with_scope(pool::enter_object_network);
+}
+
+fn _get_mut<F, R>(handle: u32, closure: F) -> VcxResult<R> where F: Fn(&mut Prover) -> VcxResult<R> {
+    persistence::get_mut(&HANDLE_MAP, events::DISCLOSED_PROOF, handle, |proof| outbound_queue::sending_state(proof.state(), &proof.get_thread_id()), |proof| Some(proof.get_thread_id()), to_string, closure)
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -44,12 +63,38 @@ pub fn create_proof(source_id: &str, proof_req: &str) -> VcxResult<u32> {
     trace!("create_proof >>> source_id: {}, proof_req: {}", source_id, proof_req);
     debug!("creating disclosed proof with id: {}", source_id);
 
+    check_json_limits(proof_req.as_bytes())?;
     let presentation_request: PresentationRequest = serde_json::from_str(proof_req)
         .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson,
                                           format!("Strict `aries` protocol is enabled. Can not parse `aries` formatted Presentation Request: {}", err)))?;
 
     let proof = Prover::create(source_id, presentation_request)?;
-    HANDLE_MAP.add(proof)
+    persistence::add(&HANDLE_MAP, events::DISCLOSED_PROOF, proof, to_string)
+}
+
+///
+/// Creates proof answering presentation request received attached to out-of-band invitation,
+/// the proof is sent straight to the service of the invitation without establishing a connection.
+///
+pub fn create_proof_from_oob(source_id: &str, oob_invitation: &str) -> VcxResult<u32> {
+    trace!("create_proof_from_oob >>> source_id: {}, oob_invitation: {}", source_id, oob_invitation);
+    debug!("creating disclosed proof from out-of-band invitation with id: {}", source_id);
+
+    check_json_limits(oob_invitation.as_bytes())?;
+    let invitation = OutOfBandInvitation::from_str(oob_invitation)?;
+
+    let presentation_request = match invitation.request()? {
+        A2AMessage::PresentationRequest(presentation_request) => presentation_request,
+        message => return Err(VcxError::from_msg(VcxErrorKind::InvalidJson,
+                                                 format!("Out-of-band invitation does not contain Presentation Request: {:?}", message)))
+    };
+
+    let service = presentation_request.service.clone().or(invitation.service())
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, "Out-of-band invitation does not contain service to answer to"))?;
+    let presentation_request = presentation_request.set_service(Some(service));
+
+    let proof = Prover::create(source_id, presentation_request)?;
+    persistence::add(&HANDLE_MAP, events::DISCLOSED_PROOF, proof, to_string)
 }
 
 pub fn create_proof_with_msgid(source_id: &str, connection_handle: u32, msg_id: &str) -> VcxResult<(u32, String)> {
@@ -65,7 +110,7 @@ pub fn create_proof_with_msgid(source_id: &str, connection_handle: u32, msg_id:
 
     let proof = Prover::create(source_id, presentation_request)?;
 
-    let handle = HANDLE_MAP.add(proof)?;
+    let handle = persistence::add(&HANDLE_MAP, events::DISCLOSED_PROOF, proof, to_string)?;
 
     debug!("inserting disclosed proof {} into handle map", source_id);
     Ok((handle, proof_request))
@@ -73,14 +118,16 @@ pub fn create_proof_with_msgid(source_id: &str, connection_handle: u32, msg_id:
 
 pub fn get_state(handle: u32) -> VcxResult<u32> {
     HANDLE_MAP.get(handle, |proof| {
-        Ok(proof.state())
+        Ok(outbound_queue::sending_state(proof.state(), &proof.get_thread_id()))
     }).or(Err(VcxError::from(VcxErrorKind::InvalidConnectionHandle)))
 }
 
 pub fn update_state(handle: u32, message: Option<String>, connection_handle: Option<u32>) -> VcxResult<u32> {
-    HANDLE_MAP.get_mut(handle, |proof| {
+    if let Some(ref message) = message { check_json_limits(message.as_bytes())?; }
+
+    _get_mut(handle, |proof| {
         proof.update_state(message.as_ref().map(String::as_str), connection_handle)?;
-        Ok(proof.state())
+        Ok(outbound_queue::sending_state(proof.state(), &proof.get_thread_id()))
     })
 }
 
@@ -100,14 +147,30 @@ pub fn from_string(proof_data: &str) -> VcxResult<u32> {
     }
 }
 
+/// Serializes the object into a token which can be turned back into a handle by another process via `from_token`.
+pub fn to_token(handle: u32) -> VcxResult<String> {
+    handle_token::seal("disclosed_proof", &to_string(handle)?)
+}
+
+pub fn from_token(token: &str) -> VcxResult<u32> {
+    from_string(&handle_token::unseal("disclosed_proof", token)?)
+}
+
 pub fn release(handle: u32) -> VcxResult<()> {
-    HANDLE_MAP.release(handle).map_err(handle_err)
+    HANDLE_MAP.release(handle).map_err(handle_err)?;
+    persistence::forget(events::DISCLOSED_PROOF, handle);
+    Ok(())
 }
 
 pub fn release_all() {
+    persistence::forget_all(&HANDLE_MAP, events::DISCLOSED_PROOF);
     HANDLE_MAP.drain().ok();
 }
 
+pub fn get_handles() -> Vec<u32> {
+    HANDLE_MAP.handles().unwrap_or_default()
+}
+
 pub fn generate_proof_msg(handle: u32) -> VcxResult<String> {
     HANDLE_MAP.get(handle, |proof| {
         proof.generate_presentation_msg()
@@ -115,7 +178,7 @@ pub fn generate_proof_msg(handle: u32) -> VcxResult<String> {
 }
 
 pub fn send_proof(handle: u32, connection_handle: u32) -> VcxResult<u32> {
-    HANDLE_MAP.get_mut(handle, |proof| {
+    _get_mut(handle, |proof| {
         proof.send_presentation(connection_handle)?;
         let new_proof = proof.clone();
         *proof = new_proof;
@@ -124,14 +187,14 @@ pub fn send_proof(handle: u32, connection_handle: u32) -> VcxResult<u32> {
 }
 
 pub fn generate_reject_proof_msg(handle: u32) -> VcxResult<String> {
-    HANDLE_MAP.get_mut(handle, |_| {
+    _get_mut(handle, |_| {
         Err(VcxError::from_msg(VcxErrorKind::ActionNotSupported,
                                "Action generate_reject_proof_msg is not implemented for V3 disclosed proof."))
     })
 }
 
 pub fn reject_proof(handle: u32, connection_handle: u32) -> VcxResult<u32> {
-    HANDLE_MAP.get_mut(handle, |proof| {
+    _get_mut(handle, |proof| {
         proof.decline_presentation_request(connection_handle, Some(String::from("Presentation Request was rejected")), None)?;
         let new_proof = proof.clone();
         *proof = new_proof;
@@ -139,15 +202,29 @@ pub fn reject_proof(handle: u32, connection_handle: u32) -> VcxResult<u32> {
     })
 }
 
+fn _proof_request_data(proof: &Prover) -> VcxResult<ProofRequestData> {
+    if proof.presentation_format() == PresentationFormat::PresentationExchange {
+        return Err(VcxError::from_msg(VcxErrorKind::ActionNotSupported, "Presentation Exchange request is not Indy proof request"));
+    }
+
+    serde_json::from_str(&proof.presentation_request_data()?)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize proof request: {}", err)))
+}
+
 pub fn generate_proof(handle: u32, credentials: String, self_attested_attrs: String) -> VcxResult<u32> {
-    HANDLE_MAP.get_mut(handle, |proof| {
+    _get_mut(handle, |proof| {
+        // credentials selected for Presentation Exchange are checked against input descriptors while presenting
+        if proof.presentation_format() == PresentationFormat::Indy {
+            disclosed_proof_utils::validate_selected_credentials(&credentials, &self_attested_attrs, &_proof_request_data(proof)?)?;
+        }
+        cancellation::check()?;
         proof.generate_presentation(credentials.clone(), self_attested_attrs.clone())?;
         Ok(error::SUCCESS.code_num)
     }).map(|_| error::SUCCESS.code_num)
 }
 
 pub fn decline_presentation_request(handle: u32, connection_handle: u32, reason: Option<String>, proposal: Option<String>) -> VcxResult<u32> {
-    HANDLE_MAP.get_mut(handle, |proof| {
+    _get_mut(handle, |proof| {
         proof.decline_presentation_request(connection_handle, reason.clone(), proposal.clone())?;
         let new_proof = proof.clone();
         *proof = new_proof;
@@ -167,6 +244,21 @@ pub fn get_proof_request_data(handle: u32) -> VcxResult<String> {
     })
 }
 
+pub fn get_unresolved_attributes(handle: u32, credentials: &str) -> VcxResult<String> {
+    HANDLE_MAP.get(handle, |proof| {
+        let unresolved = disclosed_proof_utils::get_unresolved_attributes(credentials, &_proof_request_data(proof)?)?;
+        Ok(json!(unresolved).to_string())
+    })
+}
+
+pub fn get_presentation_request_preview(handle: u32) -> VcxResult<String> {
+    HANDLE_MAP.get(handle, |proof| {
+        let preview = proof.presentation_request_preview()?;
+        serde_json::to_string(&preview)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize presentation request preview: {}", err)))
+    })
+}
+
 pub fn is_valid_handle(handle: u32) -> bool {
     HANDLE_MAP.has_handle(handle)
 }
@@ -218,6 +310,66 @@ pub fn get_source_id(handle: u32) -> VcxResult<String> {
     }).map_err(handle_err)
 }
 
+pub fn get_thread_id(handle: u32) -> VcxResult<String> {
+    HANDLE_MAP.get(handle, |proof| {
+        Ok(proof.get_thread_id())
+    }).map_err(handle_err)
+}
+
+/// Connection the exchange of the object runs over, if any.
+pub fn get_connection_handle(handle: u32) -> VcxResult<Option<u32>> {
+    HANDLE_MAP.get(handle, |proof| {
+        Ok(proof.get_connection_handle())
+    }).map_err(handle_err)
+}
+
+/// Time the offer or request of the exchange expires at, in RFC 3339 format, `None` if it does not expire.
+pub fn get_expiry(handle: u32) -> VcxResult<Option<String>> {
+    HANDLE_MAP.get(handle, |proof| {
+        Ok(proof.get_expiry())
+    }).map_err(handle_err)
+}
+
+pub fn is_awaiting_ack(handle: u32) -> VcxResult<bool> {
+    HANDLE_MAP.get(handle, |proof| {
+        Ok(proof.is_awaiting_ack())
+    }).map_err(handle_err)
+}
+
+pub fn get_protocol_history(handle: u32) -> VcxResult<String> {
+    HANDLE_MAP.get(handle, |proof| {
+        proof.get_protocol_history()
+    }).map_err(handle_err)
+}
+
+/// Handle of the disclosed proof with given source id, the most recent one when there are more of them.
+pub fn get_handle_by_source_id(source_id: &str) -> VcxResult<u32> {
+    HANDLE_MAP.find_by_source_id(source_id)
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidDisclosedProofHandle, format!("No disclosed proof with source id: {}", source_id)))
+}
+
+pub fn get_handle_by_thread_id(thread_id: &str) -> VcxResult<u32> {
+    HANDLE_MAP.find_by_thread_id(thread_id)
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidDisclosedProofHandle, format!("No disclosed proof with thread id: {}", thread_id)))
+}
+
+/// Sets metadata `key` of the disclosed proof to `value`, or removes the key when no value is given.
+pub fn set_metadata(handle: u32, key: &str, value: Option<&str>) -> VcxResult<()> {
+    _get_mut(handle, |proof| proof.set_metadata(key, value)).map_err(handle_err)
+}
+
+/// Metadata of the disclosed proof as JSON object.
+pub fn get_metadata(handle: u32) -> VcxResult<String> {
+    HANDLE_MAP.get(handle, |proof| {
+        Ok(json!(proof.get_metadata()).to_string())
+    }).map_err(handle_err)
+}
+
+/// Handles of the disclosed proofs having metadata `key` set to `value`.
+pub fn find_handles_by_metadata(key: &str, value: &str) -> Vec<u32> {
+    HANDLE_MAP.find_by_metadata(key, value)
+}
+
 pub fn get_presentation_status(handle: u32) -> VcxResult<u32> {
     HANDLE_MAP.get(handle, |proof| {
         Ok(proof.presentation_status())
@@ -245,6 +397,10 @@ mod tests {
     use utils::mockdata::mock_settings::MockBuilder;
     use utils::mockdata::mockdata_proof;
     use utils::mockdata::mockdata_proof::{ARIES_PROOF_PRESENTATION_ACK, ARIES_PROOF_REQUEST_PRESENTATION};
+    use aries::messages::connection::service::tests::_service;
+    use aries::messages::out_of_band::invitation::tests::_oob_invitation;
+    use aries::messages::proof_presentation::presentation::tests::_presentation;
+    use aries::messages::proof_presentation::presentation_request::tests::_presentation_request;
 
     use super::*;
 
@@ -264,6 +420,25 @@ mod tests {
         assert!(create_proof("1", ARIES_PROOF_REQUEST_PRESENTATION).unwrap() > 0);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_create_proof_from_oob() {
+        let _setup = SetupAriesMocks::init();
+
+        let invitation = json!(_oob_invitation()).to_string();
+        let handle = create_proof_from_oob("1", &invitation).unwrap();
+        assert_eq!(VcxStateType::VcxStateRequestReceived as u32, get_state(handle).unwrap());
+
+        let invitation = json!(OutOfBandInvitation::create()
+            .set_request(_presentation_request().to_a2a_message()).unwrap()).to_string();
+        assert_eq!(create_proof_from_oob("1", &invitation).unwrap_err().kind(), VcxErrorKind::InvalidJson);
+
+        let invitation = json!(OutOfBandInvitation::create()
+            .set_request(_presentation().to_a2a_message()).unwrap()
+            .set_service(_service())).to_string();
+        assert_eq!(create_proof_from_oob("1", &invitation).unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_create_fails() {
@@ -292,7 +467,7 @@ mod tests {
         let _mock_builder = MockBuilder::init().
             set_mock_generate_indy_proof("{\"selected\":\"credentials\"}");
 
-        generate_proof(handle_proof, String::from("{\"selected\":\"credentials\"}"), "{}".to_string()).unwrap();
+        generate_proof(handle_proof, ARIES_PROVER_CREDENTIALS.to_string(), ARIES_PROVER_SELF_ATTESTED_ATTRS.to_string()).unwrap();
         send_proof(handle_proof, connection_h).unwrap();
         assert_eq!(VcxStateType::VcxStateOfferSent as u32, get_state(handle_proof).unwrap());
 
@@ -377,6 +552,37 @@ mod tests {
         assert_ne!(handle, handle_2);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_presentation_request_preview() {
+        let _setup = SetupAriesMocks::init();
+        ::settings::set_config_value(::settings::CONFIG_PROTOCOL_TYPE, "4.0");
+
+        let handle = create_proof("id", ARIES_PROOF_REQUEST_PRESENTATION).unwrap();
+
+        let preview = get_presentation_request_preview(handle).unwrap();
+        let preview: Value = serde_json::from_str(&preview).unwrap();
+        assert!(preview["attributes"].as_array().unwrap().len() > 0);
+        assert!(preview["predicates"].is_array());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_generate_proof_reports_unresolved_attributes() {
+        let _setup = SetupAriesMocks::init();
+        ::settings::set_config_value(::settings::CONFIG_PROTOCOL_TYPE, "4.0");
+
+        let handle = create_proof("id", ARIES_PROOF_REQUEST_PRESENTATION).unwrap();
+
+        assert_eq!(get_unresolved_attributes(handle, ARIES_PROVER_CREDENTIALS).unwrap(), json!(["attribute_3"]).to_string());
+
+        let err = generate_proof(handle, ARIES_PROVER_CREDENTIALS.to_string(), "{}".to_string()).unwrap_err();
+        assert_eq!(err.kind(), VcxErrorKind::InvalidProofCredentialData);
+        assert_eq!(VcxStateType::VcxStateRequestReceived as u32, get_state(handle).unwrap());
+
+        generate_proof(handle, ARIES_PROVER_CREDENTIALS.to_string(), ARIES_PROVER_SELF_ATTESTED_ATTRS.to_string()).unwrap();
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_deserialize_fails() {
@@ -1,8 +1,10 @@
 use std::collections::HashMap;
 
-use serde_json::Value;
+use serde_json::{Map, Value};
 
 use error::prelude::*;
+use aries::messages::issuance::w3c::W3cCredential;
+use aries::messages::proof_presentation::presentation_exchange::{PresentationExchangeRequest, VerifiablePresentation};
 use messages::proofs::{
     proof_message::CredInfoProver,
     proof_request::{
@@ -12,40 +14,60 @@ use messages::proofs::{
 };
 use settings;
 use utils::libindy::anoncreds;
-use utils::libindy::anoncreds::{get_rev_reg_def_json, get_rev_reg_delta_json};
+use utils::libindy::anoncreds::{get_accumulated_rev_reg_delta_json, get_rev_reg_def_json, get_rev_reg_delta_json};
 use utils::libindy::cache::{get_rev_reg_cache, RevRegCache, RevState, set_rev_reg_cache};
 use utils::mockdata::mock_settings::get_mock_generate_indy_proof;
+use utils::parallel;
+use utils::tails;
+use w3c_credential;
+
+// distinct values in the order of their first occurrence
+fn _distinct<'a, I>(ids: I) -> Vec<String> where I: Iterator<Item=&'a String> {
+    let mut distinct: Vec<String> = Vec::new();
+    for id in ids {
+        if !distinct.contains(id) {
+            distinct.push(id.to_string());
+        }
+    }
+    distinct
+}
 
 fn build_schemas_json_prover(credentials_identifiers: &Vec<CredInfoProver>) -> VcxResult<String> {
-    let mut rtn: Value = json!({});
+    let schema_ids = _distinct(credentials_identifiers.iter().map(|cred_info| &cred_info.schema_id));
 
-    for ref cred_info in credentials_identifiers {
-        if rtn.get(&cred_info.schema_id).is_none() {
-            let (_, schema_json) = anoncreds::get_schema_json(&cred_info.schema_id)
-                .map_err(|err| err.map(VcxErrorKind::InvalidSchema, "Cannot get schema"))?;
+    let schemas = parallel::try_map(schema_ids, |schema_id| {
+        let (_, schema_json) = anoncreds::get_schema_json(&schema_id)
+            .map_err(|err| err.map(VcxErrorKind::InvalidSchema, "Cannot get schema"))?;
 
-            let schema_json = serde_json::from_str(&schema_json)
-                .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidSchema, format!("Cannot deserialize schema: {}", err)))?;
+        let schema_json: Value = serde_json::from_str(&schema_json)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidSchema, format!("Cannot deserialize schema: {}", err)))?;
 
-            rtn[cred_info.schema_id.to_owned()] = schema_json;
-        }
+        Ok((schema_id, schema_json))
+    })?;
+
+    let mut rtn: Value = json!({});
+    for (schema_id, schema_json) in schemas {
+        rtn[schema_id] = schema_json;
     }
     Ok(rtn.to_string())
 }
 
 fn build_cred_defs_json_prover(credentials_identifiers: &Vec<CredInfoProver>) -> VcxResult<String> {
-    let mut rtn: Value = json!({});
+    let cred_def_ids = _distinct(credentials_identifiers.iter().map(|cred_info| &cred_info.cred_def_id));
 
-    for ref cred_info in credentials_identifiers {
-        if rtn.get(&cred_info.cred_def_id).is_none() {
-            let (_, credential_def) = anoncreds::get_cred_def_json(&cred_info.cred_def_id)
-                .map_err(|err| err.map(VcxErrorKind::InvalidProofCredentialData, "Cannot get credential definition"))?;
+    let credential_defs = parallel::try_map(cred_def_ids, |cred_def_id| {
+        let (_, credential_def) = anoncreds::get_cred_def_json(&cred_def_id)
+            .map_err(|err| err.map(VcxErrorKind::InvalidProofCredentialData, "Cannot get credential definition"))?;
 
-            let credential_def = serde_json::from_str(&credential_def)
-                .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidProofCredentialData, format!("Cannot deserialize credential definition: {}", err)))?;
+        let credential_def: Value = serde_json::from_str(&credential_def)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidProofCredentialData, format!("Cannot deserialize credential definition: {}", err)))?;
 
-            rtn[cred_info.cred_def_id.to_owned()] = credential_def;
-        }
+        Ok((cred_def_id, credential_def))
+    })?;
+
+    let mut rtn: Value = json!({});
+    for (cred_def_id, credential_def) in credential_defs {
+        rtn[cred_def_id] = credential_def;
     }
     Ok(rtn.to_string())
 }
@@ -94,6 +116,154 @@ pub fn credential_def_identifiers(credentials: &str, proof_req: &ProofRequestDat
     Ok(rtn)
 }
 
+fn _selected_referents(credentials: &str) -> VcxResult<Vec<String>> {
+    let credentials: Value = serde_json::from_str(credentials)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize credentials: {}", err)))?;
+
+    Ok(credentials["attrs"].as_object()
+        .map(|attrs| attrs.iter()
+            .filter(|(_, value)| value["credential"].is_object())
+            .map(|(referent, _)| referent.to_string())
+            .collect())
+        .unwrap_or_default())
+}
+
+/// Returns referents of requested attributes which are not covered by any selected credential
+/// and therefore must be provided as self attested values.
+pub fn get_unresolved_attributes(credentials: &str, proof_req: &ProofRequestData) -> VcxResult<Vec<String>> {
+    let selected = _selected_referents(credentials)?;
+
+    let mut unresolved: Vec<String> = proof_req.requested_attributes.keys()
+        .filter(|referent| !selected.contains(referent))
+        .cloned()
+        .collect();
+    unresolved.sort();
+    Ok(unresolved)
+}
+
+fn _is_attribute_group(referent: &str, proof_req: &ProofRequestData) -> bool {
+    proof_req.requested_attributes.get(referent)
+        .map_or(false, |attribute| attribute.names.is_some())
+}
+
+pub fn validate_selected_credentials(credentials: &str, self_attested_attrs: &str, proof_req: &ProofRequestData) -> VcxResult<()> {
+    let self_attested_attrs: HashMap<String, String> = serde_json::from_str(self_attested_attrs)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize self attested attributes: {}", err)))?;
+
+    // several attributes requested by `names` have to be proven from a single credential
+    let mut self_attested_groups: Vec<&String> = self_attested_attrs.keys()
+        .filter(|referent| _is_attribute_group(referent, proof_req))
+        .collect();
+    self_attested_groups.sort();
+
+    if !self_attested_groups.is_empty() {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidProofCredentialData,
+                                      format!("Requested attribute groups {:?} cannot be self attested", self_attested_groups)));
+    }
+
+    let missing_attributes: Vec<String> = get_unresolved_attributes(credentials, proof_req)?
+        .into_iter()
+        .filter(|referent| !self_attested_attrs.contains_key(referent))
+        .collect();
+
+    if !missing_attributes.is_empty() {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidProofCredentialData,
+                                      format!("Requested attributes {:?} are neither covered by selected credentials nor self attested", missing_attributes)));
+    }
+
+    let selected = _selected_referents(credentials)?;
+    let mut missing_predicates: Vec<&String> = proof_req.requested_predicates.keys()
+        .filter(|referent| !selected.contains(referent))
+        .collect();
+    missing_predicates.sort();
+
+    if !missing_predicates.is_empty() {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidProofCredentialData,
+                                      format!("Requested predicates {:?} are not covered by selected credentials", missing_predicates)));
+    }
+
+    Ok(())
+}
+
+fn _satisfies_predicate(raw_value: Option<&str>, p_type: &str, p_value: i32) -> bool {
+    let value = match raw_value.and_then(|value| value.parse::<i64>().ok()) {
+        Some(value) => value,
+        None => return false
+    };
+    let p_value = i64::from(p_value);
+
+    match p_type {
+        ">=" => value >= p_value,
+        "<=" => value <= p_value,
+        ">" => value > p_value,
+        "<" => value < p_value,
+        _ => false
+    }
+}
+
+fn _credential_attr_value<'a>(credential: &'a Value, name: &str) -> Option<&'a str> {
+    let name = name.replace(" ", "").to_lowercase();
+    credential["cred_info"]["attrs"].as_object()
+        .and_then(|attrs| attrs.iter().find(|(attr, _)| attr.replace(" ", "").to_lowercase() == name))
+        .and_then(|(_, value)| value.as_str())
+}
+
+/// Adds `values` of grouped attributes to credentials retrieved for attributes requested by `names`,
+/// so that the holder sees all the values which would be revealed from the credential together.
+pub fn add_attribute_group_values(retrieved_credentials: &str, proof_req: &ProofRequestData) -> VcxResult<String> {
+    let mut credentials: Value = serde_json::from_str(retrieved_credentials)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize retrieved credentials: {}", err)))?;
+
+    for (referent, attribute) in proof_req.requested_attributes.iter() {
+        let names = match attribute.names {
+            Some(ref names) => names,
+            None => continue
+        };
+
+        if let Some(matching) = credentials["attrs"].get_mut(referent).and_then(Value::as_array_mut) {
+            for credential in matching.iter_mut() {
+                let values: Map<String, Value> = names.iter()
+                    .map(|name| (name.to_string(), json!(_credential_attr_value(credential, name))))
+                    .collect();
+                credential["values"] = Value::Object(values);
+            }
+        }
+    }
+
+    Ok(credentials.to_string())
+}
+
+/// Adds `predicates` section to credentials retrieved for proof request.
+/// For every requested predicate it lists the matching credentials (also present under `attrs`)
+/// flagged with `satisfied` telling whether the raw attribute value meets the predicate threshold.
+/// Credentials are returned unchanged when no predicates are requested.
+pub fn add_predicates_section(retrieved_credentials: &str, proof_req: &ProofRequestData) -> VcxResult<String> {
+    let mut credentials: Value = serde_json::from_str(retrieved_credentials)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize retrieved credentials: {}", err)))?;
+
+    if !credentials.is_object() || proof_req.requested_predicates.is_empty() {
+        return Ok(retrieved_credentials.to_string());
+    }
+
+    let mut predicates = json!({});
+    for (referent, predicate) in proof_req.requested_predicates.iter() {
+        let matching: Vec<Value> = credentials["attrs"][referent].as_array()
+            .map(|matching| matching.iter()
+                .map(|credential| {
+                    let mut credential = credential.clone();
+                    let satisfied = _satisfies_predicate(_credential_attr_value(&credential, &predicate.name), &predicate.p_type, predicate.p_value);
+                    credential["satisfied"] = json!(satisfied);
+                    credential
+                })
+                .collect())
+            .unwrap_or_default();
+        predicates[referent] = json!(matching);
+    }
+    credentials["predicates"] = predicates;
+
+    Ok(credentials.to_string())
+}
+
 fn _get_revocation_interval(attr_name: &str, proof_req: &ProofRequestData) -> VcxResult<Option<NonRevokedInterval>> {
     if let Some(attr) = proof_req.requested_attributes.get(attr_name) {
         Ok(attr.non_revoked.clone().or(proof_req.non_revoked.clone().or(None)))
@@ -105,110 +275,147 @@ fn _get_revocation_interval(attr_name: &str, proof_req: &ProofRequestData) -> Vc
     }
 }
 
+///
+/// Builds revocation states of the credentials, `{rev_reg_id: {timestamp: rev_state}}`, and sets timestamps
+/// of the credential infos. Attributes with different revocation intervals get states of the same registry
+/// at different timestamps, attributes with the same interval share one state.
+///
 pub fn build_rev_states_json(credentials_identifiers: &mut Vec<CredInfoProver>) -> VcxResult<String> {
-    let mut rtn: Value = json!({});
-    let mut timestamps: HashMap<String, u64> = HashMap::new();
+    // states of one registry are built on one thread as they share the cached deltas, registries are processed in parallel
+    let mut requests: Vec<(String, Vec<RevStateRequest>)> = Vec::new();
 
     for cred_info in credentials_identifiers.iter_mut() {
+        if let (Some(rev_reg_id), Some(_)) = (&cred_info.rev_reg_id, &cred_info.cred_rev_id) {
+            cred_info.tails_file = tails::get_tails_dir(rev_reg_id, cred_info.tails_file.as_ref().map(String::as_str))?;
+        }
+
         if let (Some(rev_reg_id), Some(cred_rev_id), Some(tails_file)) =
         (&cred_info.rev_reg_id, &cred_info.cred_rev_id, &cred_info.tails_file) {
-            if rtn.get(&rev_reg_id).is_none() { // Does this make sense in case cred_info's for same rev_reg_ids have different revocation intervals
-                let (from, to) = if let Some(ref interval) = cred_info.revocation_interval
+            let position = match requests.iter().position(|(id, _)| id == rev_reg_id) {
+                Some(position) => position,
+                None => {
+                    requests.push((rev_reg_id.to_string(), Vec::new()));
+                    requests.len() - 1
+                }
+            };
+
+            let registry_requests = &mut requests[position].1;
+            if !registry_requests.iter().any(|request| request.interval == cred_info.revocation_interval) {
+                registry_requests.push(RevStateRequest {
+                    cred_rev_id: cred_rev_id.to_string(),
+                    tails_file: tails_file.to_string(),
+                    interval: cred_info.revocation_interval.clone(),
+                });
+            }
+        }
+    }
+
+    let states = parallel::try_map(requests, |(rev_reg_id, requests)| {
+        let states = requests.into_iter()
+            .map(|request| {
+                let (from, to) = if let Some(ref interval) = request.interval
                 { (interval.from, interval.to) } else { (None, None) };
 
-                let cache = get_rev_reg_cache(&rev_reg_id, &cred_rev_id);
-
-                let (rev_state_json, timestamp) =
-                    if let (Some(cached_rev_state), Some(to)) = (cache.rev_state, to) {
-                        if cached_rev_state.timestamp >= from.unwrap_or(0)
-                            && cached_rev_state.timestamp <= to {
-                            (cached_rev_state.value, cached_rev_state.timestamp)
-                        } else {
-                            let from = match from {
-                                Some(from) if from >= cached_rev_state.timestamp => {
-                                    Some(cached_rev_state.timestamp)
-                                }
-                                _ => None
-                            };
-
-                            let (_, rev_reg_def_json) = get_rev_reg_def_json(&rev_reg_id)?;
-
-                            let (rev_reg_id, rev_reg_delta_json, timestamp) = get_rev_reg_delta_json(
-                                &rev_reg_id,
-                                from,
-                                Some(to),
-                            )?;
-
-                            let rev_state_json = anoncreds::libindy_prover_update_revocation_state(
-                                &rev_reg_def_json,
-                                &cached_rev_state.value,
-                                &rev_reg_delta_json,
-                                &cred_rev_id,
-                                &tails_file,
-                            )?;
-
-                            if timestamp > cached_rev_state.timestamp {
-                                let new_cache = RevRegCache {
-                                    rev_state: Some(RevState {
-                                        timestamp,
-                                        value: rev_state_json.clone(),
-                                    })
-                                };
-                                set_rev_reg_cache(&rev_reg_id, &cred_rev_id, &new_cache);
-                            }
-
-                            (rev_state_json, timestamp)
-                        }
-                    } else {
-                        let (_, rev_reg_def_json) = get_rev_reg_def_json(&rev_reg_id)?;
-
-                        let (rev_reg_id, rev_reg_delta_json, timestamp) = get_rev_reg_delta_json(
-                            &rev_reg_id,
-                            None,
-                            to,
-                        )?;
-
-                        let rev_state_json = anoncreds::libindy_prover_create_revocation_state(
-                            &rev_reg_def_json,
-                            &rev_reg_delta_json,
-                            &cred_rev_id,
-                            &tails_file,
-                        )?;
-
-                        let new_cache = RevRegCache {
-                            rev_state: Some(RevState {
-                                timestamp,
-                                value: rev_state_json.clone(),
-                            })
-                        };
-                        set_rev_reg_cache(&rev_reg_id, &cred_rev_id, &new_cache);
-
-                        (rev_state_json, timestamp)
-                    };
+                let (rev_state_json, timestamp) = _get_rev_state(&rev_reg_id, &request.cred_rev_id, &request.tails_file, from, to)?;
 
                 let rev_state_json: Value = serde_json::from_str(&rev_state_json)
                     .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize RevocationState: {}", err)))?;
 
-                // TODO: proover should be able to create multiple states of same revocation policy for different timestamps
-                // see ticket IS-1108
-                rtn[rev_reg_id.to_string()] = json!({timestamp.to_string(): rev_state_json});
-                cred_info.timestamp = Some(timestamp);
+                Ok((request.interval, rev_state_json, timestamp))
+            })
+            .collect::<VcxResult<Vec<_>>>()?;
+        Ok((rev_reg_id, states))
+    })?;
 
-                // Cache timestamp for future attributes that have the same rev_reg_id
-                timestamps.insert(rev_reg_id.to_string(), timestamp);
-            }
+    let mut rtn: Value = json!({});
+    let mut timestamps: HashMap<(String, Option<NonRevokedInterval>), u64> = HashMap::new();
 
-            // If the rev_reg_id is already in the map, timestamp may not be updated on cred_info
-            // All further credential info gets the same timestamp as the first one
-            if cred_info.timestamp.is_none() {
-                cred_info.timestamp = timestamps.get(rev_reg_id).cloned();
-            }
+    for (rev_reg_id, states) in states {
+        for (interval, rev_state_json, timestamp) in states {
+            rtn[rev_reg_id.as_str()][timestamp.to_string()] = rev_state_json;
+            timestamps.insert((rev_reg_id.clone(), interval), timestamp);
+        }
+    }
+
+    for cred_info in credentials_identifiers.iter_mut() {
+        if let (Some(rev_reg_id), Some(_), Some(_)) = (&cred_info.rev_reg_id, &cred_info.cred_rev_id, &cred_info.tails_file) {
+            cred_info.timestamp = timestamps.get(&(rev_reg_id.to_string(), cred_info.revocation_interval.clone())).cloned();
         }
     }
 
     Ok(rtn.to_string())
 }
 
+struct RevStateRequest {
+    cred_rev_id: String,
+    tails_file: String,
+    interval: Option<NonRevokedInterval>,
+}
+
+// Revocation state of the credential valid within the interval and its timestamp. The most recent state is cached,
+// it is used when it falls into the interval and updated when the interval ends after it.
+fn _get_rev_state(rev_reg_id: &str, cred_rev_id: &str, tails_file: &str, from: Option<u64>, to: Option<u64>) -> VcxResult<(String, u64)> {
+    let cache = get_rev_reg_cache(rev_reg_id, cred_rev_id);
+    let cached_timestamp = cache.rev_state.as_ref().map(|rev_state| rev_state.timestamp);
+
+    if let (Some(cached_rev_state), Some(to)) = (&cache.rev_state, to) {
+        if cached_rev_state.timestamp >= from.unwrap_or(0) && cached_rev_state.timestamp <= to {
+            return Ok((cached_rev_state.value.clone(), cached_rev_state.timestamp));
+        }
+    }
+
+    let (_, rev_reg_def_json) = get_rev_reg_def_json(rev_reg_id)?;
+
+    let (rev_state_json, timestamp) = match (&cache.rev_state, to) {
+        // cached state precedes the interval, only the changes made since are applied
+        (Some(cached_rev_state), Some(to)) if cached_rev_state.timestamp < to => {
+            let from = match from {
+                Some(from) if from >= cached_rev_state.timestamp => Some(cached_rev_state.timestamp),
+                _ => None
+            };
+
+            let (_, rev_reg_delta_json, timestamp) = match from {
+                Some(_) => get_rev_reg_delta_json(rev_reg_id, from, Some(to))?,
+                None => get_accumulated_rev_reg_delta_json(rev_reg_id, Some(to))?
+            };
+
+            let rev_state_json = anoncreds::libindy_prover_update_revocation_state(
+                &rev_reg_def_json,
+                &cached_rev_state.value,
+                &rev_reg_delta_json,
+                cred_rev_id,
+                tails_file,
+            )?;
+            (rev_state_json, timestamp)
+        }
+        _ => {
+            let (_, rev_reg_delta_json, timestamp) = get_accumulated_rev_reg_delta_json(rev_reg_id, to)?;
+
+            let rev_state_json = anoncreds::libindy_prover_create_revocation_state(
+                &rev_reg_def_json,
+                &rev_reg_delta_json,
+                cred_rev_id,
+                tails_file,
+            )?;
+            (rev_state_json, timestamp)
+        }
+    };
+
+    // states older than the cached one are requested by intervals in the past and are not cached
+    if cached_timestamp.map(|cached_timestamp| timestamp > cached_timestamp).unwrap_or(true) {
+        let new_cache = RevRegCache {
+            rev_state: Some(RevState {
+                timestamp,
+                value: rev_state_json.clone(),
+            }),
+            revocation_status: cache.revocation_status,
+        };
+        set_rev_reg_cache(rev_reg_id, cred_rev_id, &new_cache);
+    }
+
+    Ok((rev_state_json, timestamp))
+}
+
 pub fn build_requested_credentials_json(credentials_identifiers: &Vec<CredInfoProver>,
                                         self_attested_attrs: &str,
                                         proof_req: &ProofRequestData) -> VcxResult<String> {
@@ -277,6 +484,59 @@ pub fn generate_indy_proof(credentials: &str, self_attested_attrs: &str, proof_r
     Ok(proof)
 }
 
+///
+/// W3C credentials of the wallet satisfying input descriptors of DIF Presentation Exchange request:
+/// `{"input_descriptors": {"<descriptor id>": [{"id": <credential id>, "credential": <credential>}, ...]}}`
+///
+pub fn retrieve_presentation_exchange_credentials(request_json: &str) -> VcxResult<String> {
+    trace!("retrieve_presentation_exchange_credentials >>> request_json: {}", request_json);
+
+    let request = PresentationExchangeRequest::from_str(request_json)?;
+    request.presentation_definition.validate()?;
+
+    let mut descriptors = Map::new();
+    for descriptor in request.presentation_definition.input_descriptors.iter() {
+        let credentials: Vec<Value> = w3c_credential::search(&descriptor.wallet_query().to_string())?
+            .into_iter()
+            .filter(|(_, credential)| descriptor.matches(credential))
+            .map(|(id, credential)| json!({"id": id, "credential": credential}))
+            .collect();
+        descriptors.insert(descriptor.id.clone(), json!(credentials));
+    }
+
+    Ok(json!({"input_descriptors": descriptors}).to_string())
+}
+
+///
+/// Verifiable Presentation of credentials selected for input descriptors of DIF Presentation Exchange request,
+/// `credentials` pick one of the retrieved credentials per descriptor: `{"input_descriptors": {"<descriptor id>": {"id": <credential id>}}}`
+///
+pub fn generate_presentation_exchange(credentials: &str, request_json: &str) -> VcxResult<VerifiablePresentation> {
+    trace!("generate_presentation_exchange >>> credentials: {}", secret!(&credentials));
+
+    let request = PresentationExchangeRequest::from_str(request_json)?;
+    request.presentation_definition.validate()?;
+
+    let selected: Value = serde_json::from_str(credentials)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize selected credentials: {}", err)))?;
+
+    let mut presented = Vec::new();
+    for descriptor in request.presentation_definition.input_descriptors.iter() {
+        let id = selected["input_descriptors"][descriptor.id.as_str()]["id"].as_str()
+            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidProofCredentialData, format!("No credential is selected for input descriptor {}", descriptor.id)))?;
+
+        let credential = W3cCredential::from_str(&w3c_credential::get(id)?)?;
+        if !descriptor.matches(&credential) {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidProofCredentialData, format!("Credential {} does not satisfy input descriptor {}", id, descriptor.id)));
+        }
+
+        presented.push((descriptor.id.clone(), credential));
+    }
+
+    VerifiablePresentation::create(&request.presentation_definition.id, presented)?
+        .sign(&request.options)
+}
+
 #[cfg(test)]
 pub mod tests {
     use connection;
@@ -777,7 +1037,8 @@ pub mod tests {
             rev_state: Some(RevState {
                 timestamp: current_timestamp,
                 value: cached_rev_state.clone(),
-            })
+            }),
+            revocation_status: None,
         };
         set_rev_reg_cache(&rev_reg_id, &rev_id, &cached_data);
 
@@ -831,7 +1092,8 @@ pub mod tests {
             rev_state: Some(RevState {
                 timestamp: cached_timestamp,
                 value: cached_rev_state.clone(),
-            })
+            }),
+            revocation_status: None,
         };
         let rev_id = cred_rev_id.unwrap();
         set_rev_reg_cache(&rev_reg_id, &rev_id, &cached_data);
@@ -886,7 +1148,8 @@ pub mod tests {
             rev_state: Some(RevState {
                 timestamp: cached_timestamp,
                 value: cached_rev_state.clone(),
-            })
+            }),
+            revocation_status: None,
         };
         let rev_id = cred_rev_id.unwrap();
         set_rev_reg_cache(&rev_reg_id, &rev_id, &cached_data);
@@ -912,6 +1175,54 @@ pub mod tests {
         assert_ne!(cache_rev_state_value.to_string(), state.values().next().unwrap().to_string());
     }
 
+    #[cfg(feature = "pool_tests")]
+    #[test]
+    fn test_build_rev_states_json_real_multiple_intervals() {
+        let _setup = SetupLibraryWalletPoolZeroFees::init();
+
+        let attrs = r#"["address1","address2","city","state","zip"]"#;
+        let (schema_id, _, cred_def_id, _, _, _, _, cred_id, rev_reg_id, cred_rev_id) =
+            ::utils::libindy::anoncreds::tests::create_and_store_credential(attrs, true);
+        let rev_reg_id = rev_reg_id.unwrap();
+        let cred_rev_id = cred_rev_id.unwrap();
+
+        ::std::thread::sleep(::std::time::Duration::from_millis(2000));
+        let before_revocation = time::get_time().sec as u64;
+        ::std::thread::sleep(::std::time::Duration::from_millis(2000));
+        ::utils::libindy::anoncreds::revoke_credential(get_temp_dir_path(TEST_TAILS_FILE).to_str().unwrap(), &rev_reg_id, &cred_rev_id).unwrap();
+        ::std::thread::sleep(::std::time::Duration::from_millis(1000));
+
+        let cred_info = |requested_attr: &str, to: u64| CredInfoProver {
+            requested_attr: requested_attr.to_string(),
+            referent: cred_id.clone(),
+            schema_id: schema_id.clone(),
+            cred_def_id: cred_def_id.clone(),
+            rev_reg_id: Some(rev_reg_id.clone()),
+            cred_rev_id: Some(cred_rev_id.clone()),
+            tails_file: Some(get_temp_dir_path(TEST_TAILS_FILE).to_str().unwrap().to_string()),
+            revocation_interval: Some(NonRevokedInterval { from: None, to: Some(to) }),
+            timestamp: None,
+        };
+        let now = time::get_time().sec as u64;
+        let mut cred_infos = vec![
+            cred_info("address1", before_revocation),
+            cred_info("city", now),
+            cred_info("zip", before_revocation),
+        ];
+
+        let states = build_rev_states_json(cred_infos.as_mut()).unwrap();
+        let states: Value = serde_json::from_str(&states).unwrap();
+        let states: HashMap<String, Value> = serde_json::from_value(states[&rev_reg_id].clone()).unwrap();
+        assert_eq!(states.len(), 2);
+
+        let before = cred_infos[0].timestamp.unwrap();
+        let after = cred_infos[1].timestamp.unwrap();
+        assert!(before < after);
+        assert_eq!(cred_infos[2].timestamp, Some(before));
+        assert!(states.contains_key(&before.to_string()));
+        assert!(states.contains_key(&after.to_string()));
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_get_credential_intervals_from_proof_req() {
@@ -947,4 +1258,136 @@ pub mod tests {
         // No interval provided for attribute or proof req
         assert_eq!(_get_revocation_interval("address1_1", &proof_req_no_interval()).unwrap(), None);
     }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_validate_selected_credentials() {
+        let _setup = SetupDefaults::init();
+
+        let proof_req: ProofRequestData = serde_json::from_value(json!({
+            "nonce": "123432421212",
+            "name": "proof_req_1",
+            "version": "0.1",
+            "requested_attributes": {
+                "name_1": { "name": "name" },
+                "nickname_2": { "name": "nickname" }
+            },
+            "requested_predicates": {
+                "age_3": { "name": "age", "p_type": ">=", "p_value": 18 }
+            }
+        })).unwrap();
+        let credential = json!({"credential": {"cred_info": {"referent": "cred_1"}}});
+        let credentials = json!({"attrs": {"name_1": credential, "age_3": credential}}).to_string();
+
+        assert_eq!(get_unresolved_attributes(&credentials, &proof_req).unwrap(), vec!["nickname_2".to_string()]);
+        assert_eq!(get_unresolved_attributes("{}", &proof_req).unwrap(), vec!["name_1".to_string(), "nickname_2".to_string()]);
+
+        validate_selected_credentials(&credentials, r#"{"nickname_2": "Al"}"#, &proof_req).unwrap();
+        assert_eq!(validate_selected_credentials(&credentials, "{}", &proof_req).unwrap_err().kind(), VcxErrorKind::InvalidProofCredentialData);
+
+        let credentials = json!({"attrs": {"name_1": credential}}).to_string();
+        assert_eq!(validate_selected_credentials(&credentials, r#"{"nickname_2": "Al"}"#, &proof_req).unwrap_err().kind(), VcxErrorKind::InvalidProofCredentialData);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_attribute_groups() {
+        let _setup = SetupDefaults::init();
+
+        let proof_req: ProofRequestData = serde_json::from_value(json!({
+            "nonce": "123432421212",
+            "name": "proof_req_1",
+            "version": "0.1",
+            "requested_attributes": {
+                "name_1": { "name": "name" },
+                "address_2": { "names": ["street", "City"] }
+            },
+            "requested_predicates": {}
+        })).unwrap();
+        let credential = json!({"cred_info": {"referent": "cred_1", "attrs": {"name": "alice", "street": "Main", "city": "Prague"}}, "interval": null});
+        let retrieved = json!({"attrs": {"name_1": [credential], "address_2": [credential]}}).to_string();
+
+        let credentials: Value = serde_json::from_str(&add_attribute_group_values(&retrieved, &proof_req).unwrap()).unwrap();
+        assert_eq!(credentials["attrs"]["address_2"][0]["values"], json!({"street": "Main", "City": "Prague"}));
+        assert!(credentials["attrs"]["name_1"][0].get("values").is_none());
+
+        let selected = json!({"attrs": {"name_1": {"credential": credential}, "address_2": {"credential": credential}}}).to_string();
+        validate_selected_credentials(&selected, "{}", &proof_req).unwrap();
+
+        let credential_identifiers = credential_def_identifiers(&json!({"attrs": {"address_2": {"credential": {"cred_info": {"referent": "cred_1", "schema_id": SCHEMA_ID, "cred_def_id": CRED_DEF_ID}}}}}).to_string(), &proof_req).unwrap();
+        let requested_credentials: Value = serde_json::from_str(&build_requested_credentials_json(&credential_identifiers, "{}", &proof_req).unwrap()).unwrap();
+        assert_eq!(requested_credentials["requested_attributes"]["address_2"], json!({"cred_id": "cred_1", "revealed": true, "timestamp": null}));
+
+        let selected = json!({"attrs": {"name_1": {"credential": credential}}}).to_string();
+        assert_eq!(validate_selected_credentials(&selected, r#"{"address_2": "Main, Prague"}"#, &proof_req).unwrap_err().kind(), VcxErrorKind::InvalidProofCredentialData);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_add_predicates_section() {
+        let _setup = SetupDefaults::init();
+
+        let proof_req: ProofRequestData = serde_json::from_value(json!({
+            "nonce": "123432421212",
+            "name": "proof_req_1",
+            "version": "0.1",
+            "requested_attributes": {
+                "name_1": { "name": "name" }
+            },
+            "requested_predicates": {
+                "age_2": { "name": "Age", "p_type": ">=", "p_value": 18 },
+                "height_3": { "name": "height", "p_type": "<", "p_value": 180 }
+            }
+        })).unwrap();
+        let adult = json!({"cred_info": {"referent": "cred_1", "attrs": {"name": "alice", "age": "20", "height": "not measured"}}, "interval": null});
+        let child = json!({"cred_info": {"referent": "cred_2", "attrs": {"name": "bob", "age": "12"}}, "interval": null});
+        let retrieved = json!({"attrs": {"name_1": [adult, child], "age_2": [adult, child], "height_3": [adult]}}).to_string();
+
+        let credentials: Value = serde_json::from_str(&add_predicates_section(&retrieved, &proof_req).unwrap()).unwrap();
+
+        assert_eq!(credentials["attrs"]["age_2"].as_array().unwrap().len(), 2);
+        assert_eq!(credentials["predicates"]["age_2"][0]["cred_info"]["referent"], json!("cred_1"));
+        assert_eq!(credentials["predicates"]["age_2"][0]["satisfied"], json!(true));
+        assert_eq!(credentials["predicates"]["age_2"][1]["satisfied"], json!(false));
+        assert_eq!(credentials["predicates"]["height_3"][0]["satisfied"], json!(false));
+        assert!(credentials["predicates"].get("name_1").is_none());
+
+        let credentials: Value = serde_json::from_str(&add_predicates_section("{}", &proof_req).unwrap()).unwrap();
+        assert_eq!(credentials["predicates"]["age_2"], json!([]));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_presentation_exchange_retrieve_and_generate() {
+        use aries::messages::connection::did_key;
+        use aries::messages::issuance::w3c::tests::_credential_subject;
+        use aries::messages::proof_presentation::presentation_exchange::tests::_presentation_definition;
+        use utils::libindy::crypto;
+
+        let _setup = SetupLibraryWallet::init();
+
+        let issuer_key = crypto::create_key(None).unwrap();
+        let holder = did_key::encode(&crypto::create_key(None).unwrap()).unwrap();
+
+        let adult = W3cCredential::create(&issuer_key, _credential_subject()).unwrap().set_subject_id(&holder).sign().unwrap();
+        let mut child = adult.clone();
+        child.credential_subject.insert(String::from("age"), json!("12"));
+        let child = child.sign().unwrap();
+
+        let adult_id = w3c_credential::store(&adult).unwrap();
+        w3c_credential::store(&child).unwrap();
+
+        let request = PresentationExchangeRequest::create(_presentation_definition(), "1234567890");
+        let request_json = json!(request).to_string();
+
+        let retrieved: Value = serde_json::from_str(&retrieve_presentation_exchange_credentials(&request_json).unwrap()).unwrap();
+        assert_eq!(json!([{"id": adult_id, "credential": adult}]), retrieved["input_descriptors"]["adult"]);
+
+        let selected = json!({"input_descriptors": {"adult": {"id": adult_id}}}).to_string();
+        let presentation = generate_presentation_exchange(&selected, &request_json).unwrap();
+        assert_eq!(Some(holder), presentation.holder);
+        presentation.verify(&request).unwrap();
+
+        assert_eq!(generate_presentation_exchange("{}", &request_json).unwrap_err().kind(), VcxErrorKind::InvalidProofCredentialData);
+    }
 }
@@ -10,7 +10,7 @@ use utils::cstring::CStringUtils;
 use utils::error;
 
 pub mod prelude {
-    pub use super::{err_msg, get_current_error_c_json, VcxError, VcxErrorExt, VcxErrorKind, VcxResult, VcxResultExt};
+    pub use super::{err_msg, get_current_error_c_json, get_current_error_details_c_json, VcxError, VcxErrorExt, VcxErrorKind, VcxResult, VcxResultExt};
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Fail)]
@@ -22,8 +22,12 @@ pub enum VcxErrorKind {
     InvalidConfiguration,
     #[fail(display = "Obj was not found with handle")]
     InvalidHandle,
+    #[fail(display = "Handle token is invalid or was tampered with")]
+    InvalidHandleToken,
     #[fail(display = "Invalid JSON string")]
     InvalidJson,
+    #[fail(display = "JSON exceeds maximal allowed size or nesting depth")]
+    JsonLimitExceeded,
     #[fail(display = "Invalid Option")]
     InvalidOption,
     #[fail(display = "Invalid MessagePack")]
@@ -44,6 +48,10 @@ pub enum VcxErrorKind {
     AlreadyInitialized,
     #[fail(display = "Action is not supported")]
     ActionNotSupported,
+    #[fail(display = "Protocol message has expired")]
+    ExpiredMessage,
+    #[fail(display = "Operation was cancelled")]
+    Cancelled,
 
     // Connection
     #[fail(display = "Could not create connection")]
@@ -74,6 +82,8 @@ pub enum VcxErrorKind {
     CredDefAlreadyCreated,
     #[fail(display = "Invalid Credential Definition handle")]
     InvalidCredDefHandle,
+    #[fail(display = "Invalid Credential Template handle")]
+    InvalidCredentialTemplateHandle,
     #[fail(display = "No revocation delta found in storage for this revocation registry. Were any credentials locally revoked?")]
     RevDeltaNotFound,
 
@@ -112,6 +122,8 @@ pub enum VcxErrorKind {
     InvalidDisclosedProofHandle,
     #[fail(display = "Proof had invalid format")]
     InvalidProof,
+    #[fail(display = "Presentation does not satisfy proof request restrictions")]
+    ProofRestrictionsMismatch,
     #[fail(display = "Schema was invalid or corrupt")]
     InvalidSchema,
     #[fail(display = "The Proof received does not have valid credentials listed.")]
@@ -146,6 +158,8 @@ pub enum VcxErrorKind {
     NoPoolOpen,
     #[fail(display = "Message failed in post")]
     PostMessageFailed,
+    #[fail(display = "Ledger requires acceptance of its current transaction author agreement")]
+    TxnAuthorAgreementRequired,
 
     // Wallet
     #[fail(display = "Error Creating a wallet")]
@@ -172,6 +186,8 @@ pub enum VcxErrorKind {
     WalletAlreadyOpen,
     #[fail(display = "Configuration is missing wallet key")]
     MissingWalletKey,
+    #[fail(display = "Wallet storage plugin could not be loaded")]
+    WalletStoragePlugin,
     #[fail(display = "Attempted to add a Master Secret that already existed in wallet")]
     DuplicationMasterSecret,
     #[fail(display = "Attempted to add a DID to wallet when that DID already exists in wallet")]
@@ -208,6 +224,12 @@ pub enum VcxErrorKind {
     NoEndpoint,
     #[fail(display = "Error Retrieving messages from API")]
     InvalidMessages,
+    #[fail(display = "No object is handling the thread of the message")]
+    UnroutableMessage,
+    #[fail(display = "Connection has messages which were not processed yet")]
+    UndeliveredMessages,
+    #[fail(display = "Connection counterparty did not respond in time")]
+    NoResponse,
 
     #[fail(display = "Common error {}", 0)]
     Common(u32),
@@ -219,9 +241,37 @@ pub enum VcxErrorKind {
     NoAgentInformation,
 }
 
+impl VcxErrorKind {
+    /// Errors of this kind may pass if the operation is repeated later.
+    /// `NotReady` is not among them, it is reported for operations the object is in wrong state for.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            VcxErrorKind::PostMessageFailed
+            | VcxErrorKind::PoolLedgerConnect
+            | VcxErrorKind::TimeoutLibindy
+            | VcxErrorKind::NoResponse
+            | VcxErrorKind::Cancelled => true,
+            _ => false
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct VcxError {
-    inner: Context<VcxErrorKind>
+    inner: Context<VcxErrorKind>,
+    details: ErrorDetails,
+}
+
+/// Context of the error reported to the caller by `vcx_get_last_error_details`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ErrorDetails {
+    /// Aries protocol of the object the error occurred on.
+    pub protocol: Option<String>,
+    pub thread_id: Option<String>,
+    /// Code of the problem report received from the counterparty.
+    pub problem_code: Option<String>,
+    // overrides the retryability implied by the error kind
+    retryable: Option<bool>,
 }
 
 impl Fail for VcxError {
@@ -254,7 +304,7 @@ impl fmt::Display for VcxError {
 impl VcxError {
     pub fn from_msg<D>(kind: VcxErrorKind, msg: D) -> VcxError
         where D: fmt::Display + fmt::Debug + Send + Sync + 'static {
-        VcxError { inner: Context::new(msg).context(kind) }
+        VcxError { inner: Context::new(msg).context(kind), details: ErrorDetails::default() }
     }
 
     pub fn kind(&self) -> VcxErrorKind {
@@ -264,12 +314,41 @@ impl VcxError {
     pub fn extend<D>(self, msg: D) -> VcxError
         where D: fmt::Display + fmt::Debug + Send + Sync + 'static {
         let kind = self.kind();
-        VcxError { inner: self.inner.map(|_| msg).context(kind) }
+        VcxError { inner: self.inner.map(|_| msg).context(kind), details: self.details }
     }
 
     pub fn map<D>(self, kind: VcxErrorKind, msg: D) -> VcxError
         where D: fmt::Display + fmt::Debug + Send + Sync + 'static {
-        VcxError { inner: self.inner.map(|_| msg).context(kind) }
+        VcxError { inner: self.inner.map(|_| msg).context(kind), details: self.details }
+    }
+
+    pub fn details(&self) -> &ErrorDetails {
+        &self.details
+    }
+
+    /// The operation may pass if repeated later, as implied by the error kind unless set by `with_retryable`.
+    pub fn is_retryable(&self) -> bool {
+        self.details.retryable.unwrap_or_else(|| self.kind().is_retryable())
+    }
+
+    pub fn with_retryable(mut self, retryable: bool) -> VcxError {
+        self.details.retryable = Some(retryable);
+        self
+    }
+
+    pub fn with_protocol(mut self, protocol: &str) -> VcxError {
+        self.details.protocol = Some(protocol.to_string());
+        self
+    }
+
+    pub fn with_thread_id(mut self, thread_id: &str) -> VcxError {
+        self.details.thread_id = Some(thread_id.to_string());
+        self
+    }
+
+    pub fn with_problem_code<D: fmt::Display>(mut self, problem_code: D) -> VcxError {
+        self.details.problem_code = Some(problem_code.to_string());
+        self
     }
 }
 
@@ -286,7 +365,7 @@ impl From<VcxErrorKind> for VcxError {
 
 impl From<Context<VcxErrorKind>> for VcxError {
     fn from(inner: Context<VcxErrorKind>) -> VcxError {
-        VcxError { inner }
+        VcxError { inner, details: ErrorDetails::default() }
     }
 }
 
@@ -335,6 +414,7 @@ impl From<VcxErrorKind> for u32 {
             VcxErrorKind::InvalidProofHandle => error::INVALID_PROOF_HANDLE.code_num,
             VcxErrorKind::InvalidDisclosedProofHandle => error::INVALID_DISCLOSED_PROOF_HANDLE.code_num,
             VcxErrorKind::InvalidProof => error::INVALID_PROOF.code_num,
+            VcxErrorKind::ProofRestrictionsMismatch => error::PROOF_RESTRICTIONS_MISMATCH.code_num,
             VcxErrorKind::InvalidSchema => error::INVALID_SCHEMA.code_num,
             VcxErrorKind::InvalidProofCredentialData => error::INVALID_PROOF_CREDENTIAL_DATA.code_num,
             VcxErrorKind::CreateProof => error::CREATE_PROOF_ERROR.code_num,
@@ -387,6 +467,16 @@ impl From<VcxErrorKind> for u32 {
             VcxErrorKind::NoAgentInformation => error::NO_AGENT_INFO.code_num,
             VcxErrorKind::RevRegDefNotFound => error::REV_REG_DEF_NOT_FOUND.code_num,
             VcxErrorKind::RevDeltaNotFound => error::REV_DELTA_NOT_FOUND.code_num,
+            VcxErrorKind::JsonLimitExceeded => error::JSON_LIMIT_EXCEEDED.code_num,
+            VcxErrorKind::InvalidHandleToken => error::INVALID_HANDLE_TOKEN.code_num,
+            VcxErrorKind::UnroutableMessage => error::UNROUTABLE_MESSAGE.code_num,
+            VcxErrorKind::UndeliveredMessages => error::UNDELIVERED_MESSAGES.code_num,
+            VcxErrorKind::NoResponse => error::NO_RESPONSE.code_num,
+            VcxErrorKind::WalletStoragePlugin => error::WALLET_STORAGE_PLUGIN.code_num,
+            VcxErrorKind::TxnAuthorAgreementRequired => error::TXN_AUTHOR_AGREEMENT_REQUIRED.code_num,
+            VcxErrorKind::ExpiredMessage => error::EXPIRED_MESSAGE.code_num,
+            VcxErrorKind::InvalidCredentialTemplateHandle => error::INVALID_CREDENTIAL_TEMPLATE_HANDLE.code_num,
+            VcxErrorKind::Cancelled => error::CANCELLED.code_num,
         }
     }
 }
@@ -419,11 +509,27 @@ impl<E> VcxErrorExt for E where E: Fail
 
 thread_local! {
     pub static CURRENT_ERROR_C_JSON: RefCell<Option<CString>> = RefCell::new(None);
+    pub static CURRENT_ERROR_DETAILS_C_JSON: RefCell<Option<CString>> = RefCell::new(None);
 }
 
 pub fn reset_current_error() {
     CURRENT_ERROR_C_JSON.with(|error| {
         error.replace(None);
+    });
+    CURRENT_ERROR_DETAILS_C_JSON.with(|details| {
+        details.replace(None);
+    })
+}
+
+pub fn error_details_json(err: &VcxError) -> ::serde_json::Value {
+    json!({
+        "error": err.kind().to_string(),
+        "code": u32::from(err.kind()),
+        "message": err.to_string(),
+        "protocol": err.details().protocol,
+        "thread_id": err.details().thread_id,
+        "problem_code": err.details().problem_code,
+        "retryable": err.is_retryable(),
     })
 }
 
@@ -438,6 +544,12 @@ pub fn set_current_error(err: &VcxError) {
         error.replace(Some(CStringUtils::string_to_cstring(error_json)));
     })
         .map_err(|err| error!("Thread local variable access failed with: {:?}", err)).ok();
+
+    CURRENT_ERROR_DETAILS_C_JSON.try_with(|details| {
+        let details_json = error_details_json(err).to_string();
+        details.replace(Some(CStringUtils::string_to_cstring(details_json)));
+    })
+        .map_err(|err| error!("Thread local variable access failed with: {:?}", err)).ok();
 }
 
 pub fn get_current_error_c_json() -> *const c_char {
@@ -450,3 +562,14 @@ pub fn get_current_error_c_json() -> *const c_char {
 
     value
 }
+
+pub fn get_current_error_details_c_json() -> *const c_char {
+    let mut value = ptr::null();
+
+    CURRENT_ERROR_DETAILS_C_JSON.try_with(|details|
+        details.borrow().as_ref().map(|details| value = details.as_ptr())
+    )
+        .map_err(|err| error!("Thread local variable access failed with: {:?}", err)).ok();
+
+    value
+}
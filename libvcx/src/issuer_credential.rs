@@ -1,12 +1,35 @@
+use std::collections::BTreeMap;
+
 use serde_json;
+use serde_json::Value;
 
+use api::VcxStateType;
 use aries::handlers::issuance::issuer::issuer::Issuer;
+use connection;
+use credential_def;
+use credential_template;
 use error::prelude::*;
+use outbound_queue;
+use schema;
+use settings;
 use utils::error;
-use utils::object_cache::ObjectCache;
+use utils::events;
+use utils::handle_token;
+use utils::json::check_json_limits;
+use utils::libindy::pool;
+use utils::object_cache::{ObjectCache, ObjectKeys};
+use utils::persistence;
 
 lazy_static! {
-    static ref ISSUER_CREDENTIAL_MAP: ObjectCache<Issuer> = ObjectCache::<Issuer>::new("issuer-credentials-cache");
+    static ref ISSUER_CREDENTIAL_MAP: ObjectCache<Issuer> = ObjectCache::<Issuer>::with_index("issuer-credentials-cache", |credential| ObjectKeys {
+        source_id: credential.get_source_id().ok(),
+        thread_id: Some(credential.get_thread_id()).filter(|thread_id| !thread_id.is_empty()),
+        metadata: credential.get_metadata().clone(),
+    }).with_scope(pool::enter_object_network);
+}
+
+fn _get_mut<F, R>(handle: u32, closure: F) -> VcxResult<R> where F: Fn(&mut Issuer) -> VcxResult<R> {
+    persistence::get_mut(&ISSUER_CREDENTIAL_MAP, events::ISSUER_CREDENTIAL, handle, |credential| outbound_queue::sending_state(credential.get_state().unwrap_or_default(), &credential.get_thread_id()), |credential| Some(credential.get_thread_id()), to_string, closure)
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -16,6 +39,103 @@ enum IssuerCredentials {
     V3(Issuer),
 }
 
+/// Difference between attributes of the schema and attributes of credential data supplied by issuer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct CredentialAttributesDiff {
+    pub missing: Vec<String>,
+    pub extra: Vec<String>,
+    /// Attribute names of credential data which are empty or consist of spaces only.
+    #[serde(default)]
+    pub empty: Vec<String>,
+}
+
+impl CredentialAttributesDiff {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.empty.is_empty()
+    }
+}
+
+// libindy compares attribute names case insensitively and ignoring spaces
+fn _normalize_attr_name(name: &str) -> String {
+    name.replace(" ", "").to_lowercase()
+}
+
+fn _parse_credential_data(credential_data: &str) -> VcxResult<BTreeMap<String, Value>> {
+    serde_json::from_str(credential_data)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize credential attributes: {}", err)))
+}
+
+fn _diff_attributes(schema_attrs: &[String], credential_data: &BTreeMap<String, Value>) -> CredentialAttributesDiff {
+    let schema_names: Vec<String> = schema_attrs.iter().map(|name| _normalize_attr_name(name)).collect();
+    let data_names: Vec<String> = credential_data.keys().map(|name| _normalize_attr_name(name)).collect();
+
+    CredentialAttributesDiff {
+        missing: schema_attrs.iter()
+            .filter(|name| !data_names.contains(&_normalize_attr_name(name)))
+            .cloned()
+            .collect(),
+        extra: credential_data.keys()
+            .filter(|name| !name.trim().is_empty())
+            .filter(|name| !schema_names.contains(&_normalize_attr_name(name)))
+            .cloned()
+            .collect(),
+        empty: credential_data.keys()
+            .filter(|name| name.trim().is_empty())
+            .cloned()
+            .collect(),
+    }
+}
+
+// credential definitions deserialized from state saved before the schema id was kept cannot be checked
+fn _check_credential_data(cred_def_handle: u32, credential_data: &str) -> VcxResult<()> {
+    let schema_id = match credential_def::get_schema_id(cred_def_handle) {
+        Ok(schema_id) => schema_id,
+        Err(ref err) if err.kind() == VcxErrorKind::InvalidSchema => {
+            warn!("Credential data are not checked against schema: {}", err);
+            return Ok(());
+        }
+        Err(err) => return Err(err)
+    };
+
+    let diff = diff_credential_attributes(&schema_id, credential_data)?;
+    if !diff.is_empty() {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidAttributesStructure,
+                                      format!("Credential data do not match attributes of schema {}: {}", schema_id, json!(diff))));
+    }
+
+    Ok(())
+}
+
+/// Reports schema attributes missing in credential data and credential data attributes unknown to the schema,
+/// so that mismatches are caught before the credential offer is built.
+pub fn diff_credential_attributes(schema_id: &str, credential_data: &str) -> VcxResult<CredentialAttributesDiff> {
+    trace!("diff_credential_attributes >>> schema_id: {}, credential_data: {}", schema_id, secret!(&credential_data));
+
+    let schema_attrs = schema::get_attr_names(schema_id)?;
+    Ok(_diff_attributes(&schema_attrs, &_parse_credential_data(credential_data)?))
+}
+
+/// Adds schema attributes missing in credential data with empty values.
+/// Fails when credential data contains attributes which are not part of the schema.
+pub fn fill_missing_attributes(schema_id: &str, credential_data: &str) -> VcxResult<String> {
+    trace!("fill_missing_attributes >>> schema_id: {}, credential_data: {}", schema_id, secret!(&credential_data));
+
+    let schema_attrs = schema::get_attr_names(schema_id)?;
+    let mut credential_data = _parse_credential_data(credential_data)?;
+    let diff = _diff_attributes(&schema_attrs, &credential_data);
+
+    if !diff.extra.is_empty() {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidAttributesStructure,
+                                      format!("Credential data contains attributes not defined in schema: {:?}", diff.extra)));
+    }
+
+    for name in diff.missing {
+        credential_data.insert(name, json!(""));
+    }
+
+    Ok(json!(credential_data).to_string())
+}
+
 pub fn issuer_credential_create(cred_def_handle: u32,
                                 source_id: String,
                                 issuer_did: String,
@@ -25,20 +145,44 @@ pub fn issuer_credential_create(cred_def_handle: u32,
     trace!("issuer_credential_create >>> cred_def_handle: {}, source_id: {}, issuer_did: {}, credential_name: {}, credential_data: {}, price: {}",
            cred_def_handle, source_id, issuer_did, credential_name, secret!(&credential_data), price);
 
+    _check_credential_data(cred_def_handle, &credential_data)?;
+
     let issuer = Issuer::create(cred_def_handle, &credential_data, &source_id)?;
-    ISSUER_CREDENTIAL_MAP.add(issuer)
+    persistence::add(&ISSUER_CREDENTIAL_MAP, events::ISSUER_CREDENTIAL, issuer, to_string)
+}
+
+/// Creates credential with the credential definition and name of the template, dynamic values complete its fixed attributes.
+pub fn issuer_credential_create_from_template(template_handle: u32, dynamic_values: String) -> VcxResult<u32> {
+    trace!("issuer_credential_create_from_template >>> template_handle: {}, dynamic_values: {}", template_handle, secret!(&dynamic_values));
+
+    let credential_data = credential_template::build_credential_data(template_handle, &dynamic_values)?;
+    let cred_def_handle = credential_template::get_cred_def_handle(template_handle)?;
+    let source_id = credential_template::get_source_id(template_handle)?;
+    let name = credential_template::get_name(template_handle)?;
+    let issuer_did = settings::get_config_value(settings::CONFIG_INSTITUTION_DID)?;
+
+    issuer_credential_create(cred_def_handle, source_id, issuer_did, name, credential_data, 0)
+}
+
+pub fn issuer_credential_create_w3c(source_id: String, credential_data: String) -> VcxResult<u32> {
+    trace!("issuer_credential_create_w3c >>> source_id: {}, credential_data: {}", source_id, secret!(&credential_data));
+
+    let issuer = Issuer::create_w3c(&credential_data, &source_id)?;
+    persistence::add(&ISSUER_CREDENTIAL_MAP, events::ISSUER_CREDENTIAL, issuer, to_string)
 }
 
 pub fn update_state(handle: u32, message: Option<String>, connection_handle: Option<u32>) -> VcxResult<u32> {
-    ISSUER_CREDENTIAL_MAP.get_mut(handle, |credential| {
+    if let Some(ref message) = message { check_json_limits(message.as_bytes())?; }
+
+    _get_mut(handle, |credential| {
         credential.update_status(message.clone(), connection_handle)?;
-        credential.get_state()
+        credential.get_state().map(|state| outbound_queue::sending_state(state, &credential.get_thread_id()))
     })
 }
 
 pub fn get_state(handle: u32) -> VcxResult<u32> {
     ISSUER_CREDENTIAL_MAP.get(handle, |credential| {
-        credential.get_state()
+        credential.get_state().map(|state| outbound_queue::sending_state(state, &credential.get_thread_id()))
     })
 }
 
@@ -48,15 +192,45 @@ pub fn get_credential_status(handle: u32) -> VcxResult<u32> {
     })
 }
 
+/// Makes `update_state` send the credential as soon as the credential request is received.
+pub fn enable_auto_issue(handle: u32) -> VcxResult<()> {
+    ISSUER_CREDENTIAL_MAP.get_mut(handle, |credential| {
+        credential.set_auto_issue(true);
+        Ok(())
+    }).or(Err(VcxError::from(VcxErrorKind::InvalidIssuerCredentialHandle)))
+}
+
+/// Makes the credential ask for ack, `update_state` keeps waiting for the ack after the credential is sent.
+pub fn request_ack(handle: u32) -> VcxResult<()> {
+    ISSUER_CREDENTIAL_MAP.get_mut(handle, |credential| {
+        credential.set_please_ack(true);
+        Ok(())
+    }).or(Err(VcxError::from(VcxErrorKind::InvalidIssuerCredentialHandle)))
+}
+
+/// Makes the credential offer expire `expires_in_secs` after it is sent.
+pub fn set_expiration(handle: u32, expires_in_secs: u64) -> VcxResult<()> {
+    _get_mut(handle, |credential| {
+        credential.set_expiration(expires_in_secs)
+    })
+}
+
 pub fn release(handle: u32) -> VcxResult<()> {
     ISSUER_CREDENTIAL_MAP.release(handle)
-        .or(Err(VcxError::from(VcxErrorKind::InvalidIssuerCredentialHandle)))
+        .or(Err(VcxError::from(VcxErrorKind::InvalidIssuerCredentialHandle)))?;
+    persistence::forget(events::ISSUER_CREDENTIAL, handle);
+    Ok(())
 }
 
 pub fn release_all() {
+    persistence::forget_all(&ISSUER_CREDENTIAL_MAP, events::ISSUER_CREDENTIAL);
     ISSUER_CREDENTIAL_MAP.drain().ok();
 }
 
+pub fn get_handles() -> Vec<u32> {
+    ISSUER_CREDENTIAL_MAP.handles().unwrap_or_default()
+}
+
 pub fn is_valid_handle(handle: u32) -> bool {
     ISSUER_CREDENTIAL_MAP.has_handle(handle)
 }
@@ -77,14 +251,23 @@ pub fn from_string(credential_data: &str) -> VcxResult<u32> {
     }
 }
 
+/// Serializes the object into a token which can be turned back into a handle by another process via `from_token`.
+pub fn to_token(handle: u32) -> VcxResult<String> {
+    handle_token::seal("issuer_credential", &to_string(handle)?)
+}
+
+pub fn from_token(token: &str) -> VcxResult<u32> {
+    from_string(&handle_token::unseal("issuer_credential", token)?)
+}
+
 pub fn generate_credential_offer_msg(handle: u32) -> VcxResult<(String, String)> {
-    ISSUER_CREDENTIAL_MAP.get_mut(handle, |_| {
+    _get_mut(handle, |_| {
         Err(VcxError::from_msg(VcxErrorKind::ActionNotSupported, "Not implemented yet"))
     })
 }
 
 pub fn send_credential_offer(handle: u32, connection_handle: u32, comment: Option<String>) -> VcxResult<u32> {
-    ISSUER_CREDENTIAL_MAP.get_mut(handle, |credential| {
+    _get_mut(handle, |credential| {
         credential.send_credential_offer(connection_handle, comment.clone())?;
         let new_credential = credential.clone();
         *credential = new_credential;
@@ -92,28 +275,76 @@ pub fn send_credential_offer(handle: u32, connection_handle: u32, comment: Optio
     })
 }
 
+///
+/// Returns out-of-band invitation to a new connection with the credential offer attached, the invitee answers
+/// the offer once the connection is established. The connection is created with the source id of the credential
+/// and updated by `update_state` of the credential until it is established.
+///
+pub fn get_oob_offer(handle: u32) -> VcxResult<String> {
+    _get_mut(handle, |credential| {
+        if credential.get_state()? == VcxStateType::VcxStateInitialized as u32 {
+            connection::create_out_of_band_connection(&credential.get_source_id()?, |connection_handle| {
+                credential.prepare_out_of_band_offer(connection_handle, None)
+            })?;
+        }
+
+        let invitation = credential.get_out_of_band_offer()?;
+        Ok(json!(invitation).to_string())
+    })
+}
+
 pub fn generate_credential_msg(handle: u32, _my_pw_did: &str) -> VcxResult<String> {
-    ISSUER_CREDENTIAL_MAP.get_mut(handle, |_| {
+    _get_mut(handle, |_| {
         Err(VcxError::from_msg(VcxErrorKind::ActionNotSupported, "Not implemented yet")) // TODO: implement
     })
 }
 
 pub fn send_credential(handle: u32, connection_handle: u32) -> VcxResult<u32> {
-    ISSUER_CREDENTIAL_MAP.get_mut(handle, |credential| {
+    _get_mut(handle, |credential| {
         credential.send_credential(connection_handle)?;
         Ok(error::SUCCESS.code_num)
     })
 }
 
+/// Withdraws the offer or stops the issuance, the holder is informed by problem report with `reason`.
+/// Returns whether the holder was informed.
+pub fn abandon(handle: u32, reason: &str) -> VcxResult<bool> {
+    _get_mut(handle, |credential| {
+        credential.abandon(reason)
+    })
+}
+
+/// Starts issuance of a credential replacing the one issued under `handle`.
+/// The offer is sent over `connection_handle` referencing the previous thread as parent,
+/// and when `revoke_old` is set, the previous credential is revoked once the new one is issued.
+/// If that revocation fails, `update_state` of the new credential returns the error and retries it.
+pub fn renew_credential(handle: u32,
+                        cred_def_handle: u32,
+                        source_id: String,
+                        credential_data: String,
+                        connection_handle: u32,
+                        comment: Option<String>,
+                        revoke_old: bool) -> VcxResult<u32> {
+    trace!("renew_credential >>> handle: {}, cred_def_handle: {}, source_id: {}, credential_data: {}, connection_handle: {}, revoke_old: {}",
+           handle, cred_def_handle, source_id, secret!(&credential_data), connection_handle, revoke_old);
+
+    let mut issuer = ISSUER_CREDENTIAL_MAP.get(handle, |credential| {
+        credential.create_renewal(cred_def_handle, &credential_data, &source_id, revoke_old)
+    }).map_err(|err| if err.kind() == VcxErrorKind::InvalidHandle { VcxError::from(VcxErrorKind::InvalidIssuerCredentialHandle) } else { err })?;
+
+    issuer.send_credential_offer(connection_handle, comment)?;
+    persistence::add(&ISSUER_CREDENTIAL_MAP, events::ISSUER_CREDENTIAL, issuer, to_string)
+}
+
 pub fn revoke_credential(handle: u32) -> VcxResult<()> {
     trace!("revoke_credential >>> handle: {}", handle);
-    ISSUER_CREDENTIAL_MAP.get_mut(handle, |credential| {
+    _get_mut(handle, |credential| {
         credential.revoke_credential(true)
     })
 }
 
 pub fn revoke_credential_local(handle: u32) -> VcxResult<()> {
-    ISSUER_CREDENTIAL_MAP.get_mut(handle, |credential| {
+    _get_mut(handle, |credential| {
         credential.revoke_credential(false)
     })
 }
@@ -138,6 +369,66 @@ pub fn get_source_id(handle: u32) -> VcxResult<String> {
     })
 }
 
+pub fn get_thread_id(handle: u32) -> VcxResult<String> {
+    ISSUER_CREDENTIAL_MAP.get(handle, |credential| {
+        Ok(credential.get_thread_id())
+    })
+}
+
+/// Connection the exchange of the object runs over, if any.
+pub fn get_connection_handle(handle: u32) -> VcxResult<Option<u32>> {
+    ISSUER_CREDENTIAL_MAP.get(handle, |credential| {
+        Ok(credential.get_connection_handle())
+    })
+}
+
+/// Time the offer or request of the exchange expires at, in RFC 3339 format, `None` if it does not expire.
+pub fn get_expiry(handle: u32) -> VcxResult<Option<String>> {
+    ISSUER_CREDENTIAL_MAP.get(handle, |credential| {
+        Ok(credential.get_expiry())
+    })
+}
+
+pub fn is_awaiting_ack(handle: u32) -> VcxResult<bool> {
+    ISSUER_CREDENTIAL_MAP.get(handle, |credential| {
+        Ok(credential.is_awaiting_ack())
+    })
+}
+
+pub fn get_protocol_history(handle: u32) -> VcxResult<String> {
+    ISSUER_CREDENTIAL_MAP.get(handle, |credential| {
+        credential.get_protocol_history()
+    })
+}
+
+/// Handle of the issuer credential with given source id, the most recent one when there are more of them.
+pub fn get_handle_by_source_id(source_id: &str) -> VcxResult<u32> {
+    ISSUER_CREDENTIAL_MAP.find_by_source_id(source_id)
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidIssuerCredentialHandle, format!("No issuer credential with source id: {}", source_id)))
+}
+
+pub fn get_handle_by_thread_id(thread_id: &str) -> VcxResult<u32> {
+    ISSUER_CREDENTIAL_MAP.find_by_thread_id(thread_id)
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidIssuerCredentialHandle, format!("No issuer credential with thread id: {}", thread_id)))
+}
+
+/// Sets metadata `key` of the issuer credential to `value`, or removes the key when no value is given.
+pub fn set_metadata(handle: u32, key: &str, value: Option<&str>) -> VcxResult<()> {
+    _get_mut(handle, |credential| credential.set_metadata(key, value))
+}
+
+/// Metadata of the issuer credential as JSON object.
+pub fn get_metadata(handle: u32) -> VcxResult<String> {
+    ISSUER_CREDENTIAL_MAP.get(handle, |credential| {
+        Ok(json!(credential.get_metadata()).to_string())
+    })
+}
+
+/// Handles of the issuer credentials having metadata `key` set to `value`.
+pub fn find_handles_by_metadata(key: &str, value: &str) -> Vec<u32> {
+    ISSUER_CREDENTIAL_MAP.find_by_metadata(key, value)
+}
+
 #[cfg(test)]
 pub mod tests {
     use ::{issuer_credential, settings};
@@ -176,7 +467,7 @@ pub mod tests {
         let tag = "test_tag";
         let config = "{support_revocation: false}";
 
-        libindy_create_and_store_credential_def(&issuer_did, SCHEMAS_JSON, tag, None, config).unwrap();
+        libindy_create_and_store_credential_def(get_wallet_handle(), &issuer_did, SCHEMAS_JSON, tag, None, config).unwrap();
     }
 
     fn _issuer_credential_create() -> u32 {
@@ -184,7 +475,7 @@ pub mod tests {
                                  "1".to_string(),
                                  "8XFh8yBzrpJQmNyZzgoTqB".to_owned(),
                                  "credential_name".to_string(),
-                                 "{\"attr\":\"value\"}".to_owned(),
+                                 CREDENTIAL_DATA.to_owned(),
                                  1).unwrap()
     }
 
@@ -220,6 +511,46 @@ pub mod tests {
         assert_eq!(get_state(handle_cred).unwrap(), VcxStateType::VcxStateOfferSent as u32);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_oob_offer() {
+        let _setup = SetupAriesMocks::init();
+
+        let handle_cred = _issuer_credential_create();
+
+        let invitation: Value = serde_json::from_str(&get_oob_offer(handle_cred).unwrap()).unwrap();
+        assert_eq!(get_state(handle_cred).unwrap(), VcxStateType::VcxStateOfferSent as u32);
+        assert_eq!(invitation["handshake_protocols"], json!(["https://didcomm.org/connections/1.0"]));
+        assert_eq!(invitation["request~attach"][0]["data"]["json"]["@id"], json!(get_thread_id(handle_cred).unwrap()));
+        assert!(invitation["services"][0].is_object());
+
+        // the offer is prepared only once
+        let invitation_again: Value = serde_json::from_str(&get_oob_offer(handle_cred).unwrap()).unwrap();
+        assert_eq!(invitation["request~attach"], invitation_again["request~attach"]);
+
+        let handle_conn = build_test_connection_inviter_requested();
+        let handle_cred = _issuer_credential_create();
+        send_credential_offer(handle_cred, handle_conn, None).unwrap();
+        assert_eq!(get_oob_offer(handle_cred).unwrap_err().kind(), VcxErrorKind::NotReady);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_renew_credential() {
+        let _setup = SetupStrictAriesMocks::init();
+
+        let handle_conn = build_test_connection_inviter_requested();
+
+        let handle_cred = _issuer_credential_create();
+        assert_eq!(renew_credential(handle_cred, create_cred_def_fake(), "2".to_string(), "{\"attr\":\"value\"}".to_string(), handle_conn, None, false).unwrap_err().kind(),
+                   VcxErrorKind::NotReady);
+
+        let handle_cred = from_string(::utils::mockdata::mockdata_credex::CREDENTIAL_ISSUER_SM_FINISHED).unwrap();
+        let handle_renewed = renew_credential(handle_cred, create_cred_def_fake(), "2".to_string(), "{\"attr\":\"value\"}".to_string(), handle_conn, None, true).unwrap();
+        assert_eq!(get_state(handle_renewed).unwrap(), VcxStateType::VcxStateOfferSent as u32);
+        assert!(to_string(handle_renewed).unwrap().contains("cb54e2f9-ee17-488c-9bc7-d70c29cff802"));
+    }
+
     #[cfg(feature = "pool_tests")]
     #[cfg(feature = "to_restore")]
     #[test]
@@ -314,6 +645,23 @@ pub mod tests {
         assert_eq!(get_state(handle_cred).unwrap(), VcxStateType::VcxStateRequestReceived as u32);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_update_state_issues_credential_when_auto_issue_is_enabled() {
+        let _setup = SetupStrictAriesMocks::init();
+
+        let handle_conn = build_test_connection_inviter_requested();
+        let handle_cred = _issuer_credential_create();
+        enable_auto_issue(handle_cred).unwrap();
+
+        assert_eq!(send_credential_offer(handle_cred, handle_conn, None).unwrap(), error::SUCCESS.code_num);
+
+        issuer_credential::update_state(handle_cred, Some(ARIES_CREDENTIAL_REQUEST.to_string()), Some(handle_conn)).unwrap();
+        assert_eq!(get_state(handle_cred).unwrap(), VcxStateType::VcxStateAccepted as u32);
+
+        assert_eq!(enable_auto_issue(0).unwrap_err().kind(), VcxErrorKind::InvalidIssuerCredentialHandle);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_update_state_with_bad_message() {
@@ -383,4 +731,66 @@ pub mod tests {
     }
 
     // todo: Write test which will use use credetial definition supporting revocation, then actually revoke credential
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_diff_credential_attributes() {
+        let _setup = SetupMocks::init();
+
+        let diff = diff_credential_attributes(SCHEMA_ID, r#"{"Name": "alice", "sex": ["female"], "nickname": "al"}"#).unwrap();
+        assert_eq!(diff.missing, vec!["height".to_string(), "age".to_string()]);
+        assert_eq!(diff.extra, vec!["nickname".to_string()]);
+        assert!(!diff.is_empty());
+
+        let data = r#"{"height": "170", "name": "alice", "sex": "female", "age": "25"}"#;
+        assert!(diff_credential_attributes(SCHEMA_ID, data).unwrap().is_empty());
+        assert_eq!(diff_credential_attributes(SCHEMA_ID, "[]").unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_fill_missing_attributes() {
+        let _setup = SetupMocks::init();
+
+        let filled = fill_missing_attributes(SCHEMA_ID, r#"{"name": "alice", "sex": "female"}"#).unwrap();
+        let filled: Value = serde_json::from_str(&filled).unwrap();
+        assert_eq!(filled, json!({"name": "alice", "sex": "female", "height": "", "age": ""}));
+        assert!(diff_credential_attributes(SCHEMA_ID, &filled.to_string()).unwrap().is_empty());
+
+        let err = fill_missing_attributes(SCHEMA_ID, r#"{"nickname": "al"}"#).unwrap_err();
+        assert_eq!(err.kind(), VcxErrorKind::InvalidAttributesStructure);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_issuer_credential_create_checks_schema_attributes() {
+        let _setup = SetupMocks::init();
+
+        let cred_def_handle = create_cred_def_fake();
+        let create = |data: &str| issuer_credential_create(cred_def_handle, "1".to_string(), "8XFh8yBzrpJQmNyZzgoTqB".to_owned(),
+                                                           "credential_name".to_string(), data.to_string(), 1);
+
+        create(CREDENTIAL_DATA).unwrap();
+        create(r#"{"Height": ["170"], "name": "alice", "sex": "female", "age": {"value": "25"}}"#).unwrap();
+
+        let err = create(r#"{"name": "alice", "sex": "female", "age": "25", "nickname": "al", " ": "x"}"#).unwrap_err();
+        assert_eq!(err.kind(), VcxErrorKind::InvalidAttributesStructure);
+        assert!(err.to_string().contains(&json!({"missing": ["height"], "extra": ["nickname"], "empty": [" "]}).to_string()));
+
+        assert_eq!(create("[]").unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_issuer_credential_create_from_template() {
+        let _setup = SetupMocks::init();
+
+        let template_handle = ::credential_template::tests::create_template_fake();
+
+        let handle = issuer_credential_create_from_template(template_handle, r#"{"name": "alice", "age": "25"}"#.to_string()).unwrap();
+        assert_eq!(get_source_id(handle).unwrap(), "1");
+
+        let err = issuer_credential_create_from_template(template_handle, r#"{"name": "alice"}"#.to_string()).unwrap_err();
+        assert_eq!(err.kind(), VcxErrorKind::InvalidAttributesStructure);
+    }
 }
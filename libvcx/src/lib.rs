@@ -37,14 +37,20 @@ pub mod settings;
 pub mod messages;
 
 pub mod api;
+pub mod api_rust;
 pub mod connection;
 pub mod issuer_credential;
+pub mod credential_template;
 pub mod proof;
 pub mod schema;
 pub mod credential_def;
 pub mod error;
 pub mod credential;
+pub mod w3c_credential;
+pub mod outbound_queue;
 pub mod disclosed_proof;
+pub mod agent;
+pub mod migration;
 
 pub mod aries;
 mod proof_utils;
@@ -1,15 +1,20 @@
+use std::collections::HashMap;
+
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 
 use error::prelude::*;
 use messages::{A2AMessage, A2AMessageKinds, A2AMessageV2, parse_response_from_agency, prepare_message_for_agency};
 use messages::message_type::MessageTypes;
+use messages::update_profile::GetConfigs;
 use settings;
 use utils::{constants, error, httpclient};
 use utils::httpclient::{AgencyMockDecrypted};
-use utils::libindy::{anoncreds, wallet};
+use utils::libindy::{anoncreds, crypto, wallet};
 use utils::libindy::signus::create_and_store_my_did;
+use utils::mockdata::mockdata_agency;
 use utils::option_util::get_or_default;
+use utils::secret::SecretString;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Connect {
@@ -85,6 +90,71 @@ pub struct CreateAgentResponse {
     from_vk: String,
 }
 
+///
+/// Token issued by a sponsor to its sponsee, agencies requiring sponsored onboarding verify the
+/// signature of the sponsor over `nonce`, `timestamp` and `sponseeId` before creating an agent.
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProvisionToken {
+    #[serde(rename = "sponseeId")]
+    pub sponsee_id: String,
+    #[serde(rename = "sponsorId")]
+    pub sponsor_id: String,
+    pub nonce: String,
+    pub timestamp: String,
+    pub sig: String,
+    #[serde(rename = "sponsorVerKey")]
+    pub sponsor_vk: String,
+}
+
+impl ProvisionToken {
+    pub fn from_str(token: &str) -> VcxResult<ProvisionToken> {
+        ::serde_json::from_str(token)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot parse provision token: {}", err)))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RequesterKeys {
+    #[serde(rename = "fromDID")]
+    from_did: String,
+    #[serde(rename = "fromVerKey")]
+    from_vk: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProvisionAgent {
+    #[serde(rename = "@type")]
+    msg_type: MessageTypes,
+    #[serde(rename = "requesterKeys")]
+    requester_keys: RequesterKeys,
+    #[serde(rename = "provisionToken")]
+    provision_token: ProvisionToken,
+}
+
+impl ProvisionAgent {
+    fn build(from_did: &str, from_vk: &str, provision_token: ProvisionToken) -> ProvisionAgent {
+        ProvisionAgent {
+            msg_type: MessageTypes::build(A2AMessageKinds::ProvisionAgent),
+            requester_keys: RequesterKeys {
+                from_did: from_did.to_string(),
+                from_vk: from_vk.to_string(),
+            },
+            provision_token,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProvisionAgentResponse {
+    #[serde(rename = "@type")]
+    msg_type: MessageTypes,
+    #[serde(rename = "selfDID")]
+    from_did: String,
+    #[serde(rename = "agentVerKey")]
+    from_vk: String,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ComMethodUpdated {
     #[serde(rename = "@type")]
@@ -152,16 +222,18 @@ pub struct Config {
     pub agency_did: String,
     agency_verkey: String,
     wallet_name: Option<String>,
-    wallet_key: String,
-    wallet_type: Option<String>,
-    agent_seed: Option<String>,
-    enterprise_seed: Option<String>,
+    wallet_key: SecretString,
+    pub wallet_type: Option<String>,
+    agent_seed: Option<SecretString>,
+    enterprise_seed: Option<SecretString>,
     wallet_key_derivation: Option<String>,
     name: Option<String>,
     logo: Option<String>,
     path: Option<String>,
-    storage_config: Option<String>,
-    storage_credentials: Option<String>,
+    pub storage_config: Option<String>,
+    pub storage_credentials: Option<String>,
+    storage_library: Option<String>,
+    storage_init_function: Option<String>,
     pool_config: Option<String>,
     did_method: Option<String>,
     communication_method: Option<String>,
@@ -178,12 +250,14 @@ pub fn set_config_values(my_config: &Config) {
     settings::set_config_value(settings::CONFIG_AGENCY_DID, &my_config.agency_did);
     settings::set_config_value(settings::CONFIG_AGENCY_VERKEY, &my_config.agency_verkey);
     settings::set_config_value(settings::CONFIG_REMOTE_TO_SDK_VERKEY, &my_config.agency_verkey);
-    settings::set_config_value(settings::CONFIG_WALLET_KEY, &my_config.wallet_key);
+    settings::set_config_value(settings::CONFIG_WALLET_KEY, my_config.wallet_key.expose());
 
     settings::set_opt_config_value(settings::CONFIG_WALLET_KEY_DERIVATION, &my_config.wallet_key_derivation);
     settings::set_opt_config_value(settings::CONFIG_WALLET_TYPE, &my_config.wallet_type);
     settings::set_opt_config_value(settings::CONFIG_WALLET_STORAGE_CONFIG, &my_config.storage_config);
     settings::set_opt_config_value(settings::CONFIG_WALLET_STORAGE_CREDS, &my_config.storage_credentials);
+    settings::set_opt_config_value(settings::CONFIG_WALLET_STORAGE_LIBRARY, &my_config.storage_library);
+    settings::set_opt_config_value(settings::CONFIG_WALLET_STORAGE_INIT_FUNCTION, &my_config.storage_init_function);
     settings::set_opt_config_value(settings::CONFIG_POOL_CONFIG, &my_config.pool_config);
     settings::set_opt_config_value(settings::CONFIG_DID_METHOD, &my_config.did_method);
     settings::set_opt_config_value(settings::COMMUNICATION_METHOD, &my_config.communication_method);
@@ -195,35 +269,49 @@ fn _create_issuer_keys(my_did: &str, my_vk: &str, my_config: &Config) -> VcxResu
         Ok((my_did.to_string(), my_vk.to_string()))
     } else {
         create_and_store_my_did(
-            my_config.enterprise_seed.as_ref().map(String::as_str),
+            my_config.enterprise_seed.as_ref().map(SecretString::expose),
             my_config.did_method.as_ref().map(String::as_str),
         )
     }
 }
 
-pub fn configure_wallet(my_config: &Config) -> VcxResult<(String, String, String)> {
+// tells whether the wallet was created by this call
+fn _init_wallet(my_config: &Config) -> VcxResult<(String, bool)> {
     let wallet_name = get_or_default(&my_config.wallet_name, settings::DEFAULT_WALLET_NAME);
+    let wallet_type = my_config.wallet_type.as_ref().map(String::as_str);
+    let storage_config = my_config.storage_config.as_ref().map(String::as_str);
+    let storage_credentials = my_config.storage_credentials.as_ref().map(String::as_str);
 
-    wallet::init_wallet(
-        &wallet_name,
-        my_config.wallet_type.as_ref().map(String::as_str),
-        my_config.storage_config.as_ref().map(String::as_str),
-        my_config.storage_credentials.as_ref().map(String::as_str),
-    )?;
+    let created = if settings::indy_mocks_enabled() {
+        false
+    } else {
+        wallet::create_wallet_if_missing(&wallet_name, wallet_type, storage_config, storage_credentials)?
+    };
+    wallet::open_wallet(&wallet_name, wallet_type, storage_config, storage_credentials)?;
     trace!("initialized wallet");
 
+    Ok((wallet_name, created))
+}
+
+pub fn configure_wallet(my_config: &Config) -> VcxResult<(String, String, String)> {
+    _configure_wallet(my_config).map(|(my_did, my_vk, wallet_name, _)| (my_did, my_vk, wallet_name))
+}
+
+fn _configure_wallet(my_config: &Config) -> VcxResult<(String, String, String, bool)> {
+    let (wallet_name, created) = _init_wallet(my_config)?;
+
     // If MS is already in wallet then just continue
     anoncreds::libindy_prover_create_master_secret(::settings::DEFAULT_LINK_SECRET_ALIAS).ok();
 
     let (my_did, my_vk) = create_and_store_my_did(
-        my_config.agent_seed.as_ref().map(String::as_str),
+        my_config.agent_seed.as_ref().map(SecretString::expose),
         my_config.did_method.as_ref().map(String::as_str),
     )?;
 
     settings::set_config_value(settings::CONFIG_INSTITUTION_DID, &my_did);
     settings::set_config_value(settings::CONFIG_SDK_TO_REMOTE_VERKEY, &my_vk);
 
-    Ok((my_did, my_vk, wallet_name))
+    Ok((my_did, my_vk, wallet_name, created))
 }
 
 pub fn get_final_config(my_did: &str,
@@ -264,6 +352,12 @@ pub fn get_final_config(my_did: &str,
     if let Some(_storage_credentials) = &my_config.storage_credentials {
         final_config["storage_credentials"] = json!(_storage_credentials);
     }
+    if let Some(storage_library) = &my_config.storage_library {
+        final_config["storage_library"] = json!(storage_library);
+    }
+    if let Some(storage_init_function) = &my_config.storage_init_function {
+        final_config["storage_init_function"] = json!(storage_init_function);
+    }
     if let Some(_pool_config) = &my_config.pool_config {
         final_config["pool_config"] = json!(_pool_config);
     }
@@ -288,23 +382,29 @@ pub fn parse_config(config: &str) -> VcxResult<Config> {
                 format!("Cannot parse config: {}", err),
             )
         )?;
+    settings::validate_protocol_mode(&my_config.protocol_type,
+                                     my_config.communication_method.as_ref().map(String::as_str),
+                                     my_config.use_latest_protocols.as_ref().map(String::as_str))?;
     Ok(my_config)
 }
 
 pub fn connect_register_provision(config: &str) -> VcxResult<String> {
-    debug!("connect_register_provision >>> config: {:?}", config);
+    debug!("connect_register_provision >>> config: {}", settings::redact_config(config));
     let my_config = parse_config(config)?;
+    let saved_settings = settings::save();
 
     trace!("***Configuring Library");
     set_config_values(&my_config);
 
     trace!("***Configuring Wallet");
-    let (my_did, my_vk, wallet_name) = configure_wallet(&my_config)?;
+    let (my_did, my_vk, wallet_name, created) = _configure_wallet(&my_config)
+        .map_err(|err| _restore_settings_if_cancelled(err, &saved_settings))?;
 
     debug!("connect_register_provision:: Final settings: {:?}", settings::settings_as_string());
 
     trace!("Connecting to Agency");
-    let (agent_did, agent_vk) = onboarding_v2(&my_did, &my_vk, &my_config.agency_did)?;
+    let (agent_did, agent_vk) = onboarding_v2(&my_did, &my_vk, &my_config.agency_did)
+        .map_err(|err| _release_wallet_if_cancelled(err, &wallet_name, created, &my_config, &saved_settings))?;
 
     let config = get_final_config(&my_did, &my_vk, &agent_did, &agent_vk, &wallet_name, &my_config)?;
 
@@ -313,6 +413,273 @@ pub fn connect_register_provision(config: &str) -> VcxResult<String> {
     Ok(config)
 }
 
+///
+/// Provisions an agent in agency requiring sponsored onboarding (agent-provisioning 0.7), the agent
+/// is created in a single step in exchange for the `token` given by the sponsor.
+///
+pub fn connect_register_provision_with_token(config: &str, token: ProvisionToken) -> VcxResult<String> {
+    debug!("connect_register_provision_with_token >>> config: {}, sponsor: {:?}", settings::redact_config(config), token.sponsor_id);
+    let my_config = parse_config(config)?;
+    let saved_settings = settings::save();
+
+    trace!("***Configuring Library");
+    set_config_values(&my_config);
+
+    trace!("***Configuring Wallet");
+    let (my_did, my_vk, wallet_name, created) = _configure_wallet(&my_config)
+        .map_err(|err| _restore_settings_if_cancelled(err, &saved_settings))?;
+
+    trace!("Provisioning Agent with token");
+    let (agent_did, agent_vk) = provision_agent_v07(&my_did, &my_vk, &my_config.agency_did, token)
+        .map_err(|err| _release_wallet_if_cancelled(err, &wallet_name, created, &my_config, &saved_settings))?;
+
+    let config = get_final_config(&my_did, &my_vk, &agent_did, &agent_vk, &wallet_name, &my_config)?;
+
+    wallet::close_wallet()?;
+
+    Ok(config)
+}
+
+fn _restore_settings_if_cancelled(err: VcxError, saved_settings: &settings::Settings) -> VcxError {
+    if err.kind() == VcxErrorKind::Cancelled {
+        settings::restore(saved_settings);
+    }
+    err
+}
+
+// cancelled provisioning leaves the library as it was before: settings applied for it are restored and the wallet
+// created for it is deleted, a wallet which existed before is only closed and provisioning started again reuses it.
+// Agency steps completed before the cancellation (connection, sign up, agent creation) are not undone.
+fn _release_wallet_if_cancelled(err: VcxError, wallet_name: &str, created: bool, my_config: &Config, saved_settings: &settings::Settings) -> VcxError {
+    if err.kind() == VcxErrorKind::Cancelled {
+        if created {
+            if let Err(err) = wallet::delete_wallet(wallet_name,
+                                                    my_config.wallet_type.as_ref().map(String::as_str),
+                                                    my_config.storage_config.as_ref().map(String::as_str),
+                                                    my_config.storage_credentials.as_ref().map(String::as_str)) {
+                warn!("Cannot delete wallet of cancelled provisioning: {}", err);
+            }
+        } else {
+            wallet::close_wallet().ok();
+        }
+    }
+    _restore_settings_if_cancelled(err, saved_settings)
+}
+
+fn provision_agent_v07(my_did: &str, my_vk: &str, agency_did: &str, token: ProvisionToken) -> VcxResult<(String, String)> {
+    let message = A2AMessage::Version2(
+        A2AMessageV2::ProvisionAgent(ProvisionAgent::build(my_did, my_vk, token))
+    );
+
+    AgencyMockDecrypted::set_next_decrypted_response(constants::AGENT_PROVISIONED_DECRYPTED);
+    let mut response = send_message_to_agency(&message, agency_did)?;
+
+    let response: ProvisionAgentResponse =
+        match response.remove(0) {
+            A2AMessage::Version2(A2AMessageV2::ProvisionAgentResponse(resp)) => resp,
+            _ => return Err(VcxError::from_msg(VcxErrorKind::InvalidHttpResponse, "Message does not match any variant of ProvisionAgentResponse"))
+        };
+
+    Ok((response.from_did, response.from_vk))
+}
+
+///
+/// Data of provisioning started by `provision_cloud_agent_prepare`, to be confirmed by the user
+/// (the agency is the one expected) before the cloud agent is created by `provision_cloud_agent_complete`.
+/// `agency_verkey` is the key the agency authenticated its connect response with.
+/// Steps completed in the agency are kept in the prepared wallet, so interrupted completion resumes
+/// where it stopped instead of registering again.
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProvisionPreparation {
+    pub agency_url: String,
+    pub agency_did: String,
+    pub agency_verkey: String,
+    pub agency_pw_did: String,
+    pub agency_pw_verkey: String,
+    pub sdk_to_remote_did: String,
+    pub sdk_to_remote_verkey: String,
+    pub wallet_name: String,
+    #[serde(default)]
+    pub signed_up: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_did: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_verkey: Option<String>,
+}
+
+const PROVISION_RECORD_TYPE: &str = "provision_preparation";
+
+impl ProvisionPreparation {
+    pub fn from_str(preparation: &str) -> VcxResult<ProvisionPreparation> {
+        ::serde_json::from_str(preparation)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot parse provision preparation: {}", err)))
+    }
+
+    // progress stored in the prepared wallet by previous attempt takes precedence over the given preparation
+    fn load_progress(self) -> VcxResult<ProvisionPreparation> {
+        if settings::indy_mocks_enabled() { return Ok(self); }
+
+        let options = json!({"retrieveType": false, "retrieveValue": true, "retrieveTags": false}).to_string();
+        let record = match wallet::get_record(PROVISION_RECORD_TYPE, &self.sdk_to_remote_did, &options) {
+            Ok(record) => record,
+            Err(ref err) if err.kind() == VcxErrorKind::WalletRecordNotFound => return Ok(self),
+            Err(err) => return Err(err)
+        };
+        let record: Value = ::serde_json::from_str(&record)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize provision record: {}", err)))?;
+        let stored = record["value"].as_str()
+            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, "Provision record has no value"))
+            .and_then(ProvisionPreparation::from_str)?;
+
+        if stored.agency_pw_did != self.agency_pw_did {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidConfiguration,
+                                          "Wallet was prepared for another provisioning"));
+        }
+        Ok(stored)
+    }
+
+    fn store_progress(&self) -> VcxResult<()> {
+        let value = json!(self).to_string();
+        match wallet::update_record_value(PROVISION_RECORD_TYPE, &self.sdk_to_remote_did, &value) {
+            Err(ref err) if err.kind() == VcxErrorKind::WalletRecordNotFound =>
+                wallet::add_record(PROVISION_RECORD_TYPE, &self.sdk_to_remote_did, &value, None),
+            result => result
+        }
+    }
+}
+
+///
+/// First phase of provisioning: creates the wallet with new keys and connects to the agency.
+/// Nothing is created in the agency yet, so the phase can be repeated if interrupted.
+/// Returns `ProvisionPreparation` as JSON.
+///
+pub fn provision_cloud_agent_prepare(config: &str) -> VcxResult<String> {
+    debug!("provision_cloud_agent_prepare >>> config: {}", settings::redact_config(config));
+    let my_config = parse_config(config)?;
+
+    trace!("***Configuring Library");
+    set_config_values(&my_config);
+
+    trace!("***Configuring Wallet");
+    let (my_did, my_vk, wallet_name) = configure_wallet(&my_config)?;
+
+    trace!("Connecting to Agency");
+    AgencyMockDecrypted::set_next_decrypted_response(constants::CONNECTED_RESPONSE_DECRYPTED);
+    let connected = _connect_verified(&my_did, &my_vk, &my_config.agency_did, &my_config.agency_verkey);
+
+    wallet::close_wallet()?;
+
+    let (agency_pw_did, agency_pw_vk) = connected?;
+
+    let preparation = ProvisionPreparation {
+        agency_url: my_config.agency_url.clone(),
+        agency_did: my_config.agency_did.clone(),
+        agency_verkey: my_config.agency_verkey.clone(),
+        agency_pw_did,
+        agency_pw_verkey: agency_pw_vk,
+        sdk_to_remote_did: my_did,
+        sdk_to_remote_verkey: my_vk,
+        wallet_name,
+        signed_up: false,
+        agent_did: None,
+        agent_verkey: None,
+    };
+
+    Ok(json!(preparation).to_string())
+}
+
+///
+/// Second phase of provisioning: registers in the agency and creates the cloud agent for the keys
+/// of `preparation` returned by `provision_cloud_agent_prepare` for the same `config`.
+/// The wallet of `preparation` is used, each completed step is recorded in it, so the phase can be
+/// repeated with the same `preparation` if interrupted.
+/// Returns the provisioned configuration.
+///
+pub fn provision_cloud_agent_complete(config: &str, preparation: &str) -> VcxResult<String> {
+    debug!("provision_cloud_agent_complete >>> config: {}, preparation: {:?}", settings::redact_config(config), preparation);
+    let my_config = parse_config(config)?;
+    let preparation = ProvisionPreparation::from_str(preparation)?;
+
+    if preparation.agency_did != my_config.agency_did || preparation.agency_verkey != my_config.agency_verkey {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidConfiguration,
+                                      format!("Provisioning was prepared for another agency: {}", preparation.agency_did)));
+    }
+
+    trace!("***Configuring Library");
+    set_config_values(&my_config);
+    settings::set_config_value(settings::CONFIG_INSTITUTION_DID, &preparation.sdk_to_remote_did);
+    settings::set_config_value(settings::CONFIG_SDK_TO_REMOTE_VERKEY, &preparation.sdk_to_remote_verkey);
+    settings::set_config_value(settings::CONFIG_REMOTE_TO_SDK_VERKEY, &preparation.agency_pw_verkey);
+    settings::set_config_value(settings::CONFIG_WALLET_NAME, &preparation.wallet_name);
+
+    trace!("***Opening Wallet");
+    wallet::open_wallet(&preparation.wallet_name,
+                        my_config.wallet_type.as_ref().map(String::as_str),
+                        my_config.storage_config.as_ref().map(String::as_str),
+                        my_config.storage_credentials.as_ref().map(String::as_str))?;
+
+    let config = _complete_provisioning(preparation, &my_config);
+
+    wallet::close_wallet()?;
+
+    config
+}
+
+fn _complete_provisioning(preparation: ProvisionPreparation, my_config: &Config) -> VcxResult<String> {
+    let mut preparation = preparation.load_progress()?;
+
+    if !preparation.signed_up {
+        trace!("Signing up in Agency");
+        sign_up_v2(&preparation.agency_pw_did)?;
+        preparation.signed_up = true;
+        preparation.store_progress()?;
+    }
+
+    let (agent_did, agent_vk) = match (preparation.agent_did.clone(), preparation.agent_verkey.clone()) {
+        (Some(agent_did), Some(agent_vk)) => (agent_did, agent_vk),
+        _ => {
+            trace!("Creating Agent");
+            let (agent_did, agent_vk) = create_agent_only_v2(&preparation.agency_pw_did)?;
+            preparation.agent_did = Some(agent_did.clone());
+            preparation.agent_verkey = Some(agent_vk.clone());
+            preparation.store_progress()?;
+            (agent_did, agent_vk)
+        }
+    };
+
+    get_final_config(&preparation.sdk_to_remote_did, &preparation.sdk_to_remote_verkey, &agent_did, &agent_vk, &preparation.wallet_name, my_config)
+}
+
+// the connect response has to be authenticated by the expected agency key, not just name the agency DID
+fn _connect_verified(my_did: &str, my_vk: &str, agency_did: &str, agency_verkey: &str) -> VcxResult<(String, String)> {
+    let message = A2AMessage::Version2(
+        A2AMessageV2::Connect(Connect::build(my_did, my_vk))
+    );
+    let data = prepare_message_for_agency(&message, agency_did, &settings::get_protocol_type())?;
+
+    let response = httpclient::post_u8(&data)
+        .map_err(|err| err.map(VcxErrorKind::InvalidHttpResponse, error::INVALID_HTTP_RESPONSE.message))?;
+
+    if !settings::agency_mocks_enabled() && !AgencyMockDecrypted::has_decrypted_mock_responses() {
+        let unpacked = crypto::unpack_message(&response[..])?;
+        let unpacked: Value = ::serde_json::from_slice(&unpacked)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize agency response: {}", err)))?;
+        if unpacked["sender_verkey"].as_str() != Some(agency_verkey) {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidHttpResponse,
+                                          format!("Agency response is not authenticated by agency verkey: {}", agency_verkey)));
+        }
+    }
+
+    match parse_response_from_agency(&response, &settings::get_protocol_type())?.remove(0) {
+        A2AMessage::Version2(A2AMessageV2::ConnectResponse(ConnectResponse { from_vk: agency_pw_vk, from_did: agency_pw_did, .. })) => {
+            settings::set_config_value(settings::CONFIG_REMOTE_TO_SDK_VERKEY, &agency_pw_vk);
+            Ok((agency_pw_did, agency_pw_vk))
+        }
+        _ => Err(VcxError::from_msg(VcxErrorKind::InvalidHttpResponse, "Message does not match any variant of ConnectResponse"))
+    }
+}
+
 pub fn connect_v2(my_did: &str, my_vk: &str, agency_did: &str) -> VcxResult<(String, String)> {
     /* STEP 1 - CONNECT */
     let message = A2AMessage::Version2(
@@ -341,13 +708,22 @@ fn onboarding_v2(my_did: &str, my_vk: &str, agency_did: &str) -> VcxResult<(Stri
     AgencyMockDecrypted::set_next_decrypted_response(constants::CONNECTED_RESPONSE_DECRYPTED);
     let (agency_pw_did, _) = connect_v2(my_did, my_vk, agency_did)?;
 
+    create_agent_v2(&agency_pw_did)
+}
+
+fn create_agent_v2(agency_pw_did: &str) -> VcxResult<(String, String)> {
+    sign_up_v2(agency_pw_did)?;
+    create_agent_only_v2(agency_pw_did)
+}
+
+fn sign_up_v2(agency_pw_did: &str) -> VcxResult<()> {
     /* STEP 2 - REGISTER */
     let message = A2AMessage::Version2(
         A2AMessageV2::SignUp(SignUp::build())
     );
 
     AgencyMockDecrypted::set_next_decrypted_response(constants::REGISTER_RESPONSE_DECRYPTED);
-    let mut response = send_message_to_agency(&message, &agency_pw_did)?;
+    let mut response = send_message_to_agency(&message, agency_pw_did)?;
 
     let _response: SignUpResponse =
         match response.remove(0) {
@@ -355,12 +731,16 @@ fn onboarding_v2(my_did: &str, my_vk: &str, agency_did: &str) -> VcxResult<(Stri
             _ => return Err(VcxError::from_msg(VcxErrorKind::InvalidHttpResponse, "Message does not match any variant of SignUpResponse"))
         };
 
+    Ok(())
+}
+
+fn create_agent_only_v2(agency_pw_did: &str) -> VcxResult<(String, String)> {
     /* STEP 3 - CREATE AGENT */
     let message = A2AMessage::Version2(
         A2AMessageV2::CreateAgent(CreateAgent::build())
     );
     AgencyMockDecrypted::set_next_decrypted_response(constants::AGENT_CREATED_DECRYPTED);
-    let mut response = send_message_to_agency(&message, &agency_pw_did)?;
+    let mut response = send_message_to_agency(&message, agency_pw_did)?;
 
     let response: CreateAgentResponse =
         match response.remove(0) {
@@ -405,6 +785,27 @@ fn update_agent_webhook_v2(to_did: &str, com_method: ComMethod) -> VcxResult<()>
     Ok(())
 }
 
+/// Values of the agent configuration options `names` kept by agency, options which are not set are left out.
+pub fn get_agent_configs(names: &[&str]) -> VcxResult<HashMap<String, String>> {
+    info!("get_agent_configs >>> names: {:?}", names);
+
+    let to_did = settings::get_config_value(settings::CONFIG_REMOTE_TO_SDK_DID)
+        .map_err(|err| err.map(VcxErrorKind::NoAgentInformation, "Cannot get agent configuration: agent is not provisioned"))?;
+
+    let message = A2AMessage::Version2(
+        A2AMessageV2::GetConfigs(GetConfigs::build(names))
+    );
+    AgencyMockDecrypted::set_next_decrypted_response(mockdata_agency::AGENCY_CONFIGS);
+    let mut response = send_message_to_agency(&message, &to_did)?;
+
+    match response.pop() {
+        Some(A2AMessage::Version2(A2AMessageV2::GetConfigsResponse(response))) => {
+            Ok(response.configs.into_iter().map(|config| (config.name, config.value)).collect())
+        }
+        _ => Err(VcxError::from_msg(VcxErrorKind::InvalidHttpResponse, "Message does not match any variant of GetConfigsResponse"))
+    }
+}
+
 pub fn send_message_to_agency(message: &A2AMessage, did: &str) -> VcxResult<Vec<A2AMessage>> {
     let data = prepare_message_for_agency(message, &did, &settings::get_protocol_type())?;
 
@@ -415,13 +816,25 @@ pub fn send_message_to_agency(message: &A2AMessage, did: &str) -> VcxResult<Vec<
 }
 
 #[cfg(test)]
-mod tests {
+pub mod tests {
     use std::env;
 
     use api::vcx::vcx_shutdown;
-    use messages::agent_utils::{ComMethodType, Config, configure_wallet, connect_register_provision, update_agent_webhook};
+    use error::prelude::*;
+    use messages::agent_utils::{ComMethodType, Config, configure_wallet, connect_register_provision, connect_register_provision_with_token, provision_cloud_agent_complete, provision_cloud_agent_prepare, ProvisionPreparation, ProvisionToken, update_agent_webhook};
     use utils::devsetup::{SetupAriesMocks, SetupDefaults, SetupLibraryAgencyV2};
 
+    pub fn _provision_token() -> ProvisionToken {
+        ProvisionToken {
+            sponsee_id: String::from("sponsee-123"),
+            sponsor_id: String::from("evernym-test-sponsor"),
+            nonce: String::from("123456"),
+            timestamp: String::from("2020-08-18T14:55:13.120211+00:00"),
+            sig: String::from("ZkejifRr3txh7NrKokC5l2A2YcWHDP0GMpPHiJlZmMAqA1bPO7KOKT9lBnAOAz91AtqIvSy8G1bBTnAhRB/rBQ=="),
+            sponsor_vk: String::from("GJ1SzoWzavQYfNL9XkaJdrQejfztN4XqdsiV4ct3LXKL"),
+        }
+    }
+
     #[test]
     #[cfg(feature = "agency")]
     fn test_connect_register_provision_config_path() {
@@ -498,6 +911,112 @@ mod tests {
         assert_eq!(expected, ::serde_json::from_str::<serde_json::Value>(&result).unwrap());
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_provision_cloud_agent_prepare_complete() {
+        let _setup = SetupAriesMocks::init();
+
+        let config = json!({
+            "agency_url": "http://www.whocares.org",
+            "agency_did": "Ab8TvZa3Q19VNkQVzAWVL7",
+            "agency_verkey": "5LXaR43B1aQyeh94VBP8LG1Sgvjk7aNfqiksBCSjwqbf",
+            "wallet_key": "test_key",
+            "protocol_type": "3.0"
+        }).to_string();
+
+        let preparation = provision_cloud_agent_prepare(&config).unwrap();
+        let preparation = ProvisionPreparation::from_str(&preparation).unwrap();
+        assert_eq!("Ab8TvZa3Q19VNkQVzAWVL7", preparation.agency_did);
+        assert_eq!("XSasL1cESeSJ2v9wMYeXBf", preparation.agency_pw_did); // taken from mock constants::CONNECTED_RESPONSE_DECRYPTED
+        assert_eq!("FhrSrYtQcw3p9xwf7NYemf", preparation.sdk_to_remote_did);
+
+        let result = provision_cloud_agent_complete(&config, &json!(preparation).to_string()).unwrap();
+        let expected = connect_register_provision(&config).unwrap();
+
+        assert_eq!(::serde_json::from_str::<serde_json::Value>(&expected).unwrap(),
+                   ::serde_json::from_str::<serde_json::Value>(&result).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_provision_cloud_agent_complete_resumes_completed_steps() {
+        let _setup = SetupAriesMocks::init();
+
+        let config = json!({
+            "agency_url": "http://www.whocares.org",
+            "agency_did": "Ab8TvZa3Q19VNkQVzAWVL7",
+            "agency_verkey": "5LXaR43B1aQyeh94VBP8LG1Sgvjk7aNfqiksBCSjwqbf",
+            "wallet_key": "test_key",
+            "wallet_name": "provision_resume",
+        }).to_string();
+        let preparation = provision_cloud_agent_prepare(&config).unwrap();
+
+        let mut preparation = ProvisionPreparation::from_str(&preparation).unwrap();
+        assert!(!preparation.signed_up);
+        preparation.signed_up = true;
+        preparation.agent_did = Some("AgentDid11111111111111".to_string());
+        preparation.agent_verkey = Some("AgentVerkey1111111111111111111111111111111111".to_string());
+
+        let result = provision_cloud_agent_complete(&config, &json!(preparation).to_string()).unwrap();
+        let result = ::serde_json::from_str::<serde_json::Value>(&result).unwrap();
+        assert_eq!("AgentDid11111111111111", result["remote_to_sdk_did"]);
+        assert_eq!("provision_resume", result["wallet_name"]);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_provision_cloud_agent_complete_fails_for_other_agency() {
+        let _setup = SetupAriesMocks::init();
+
+        let config = json!({
+            "agency_url": "http://www.whocares.org",
+            "agency_did": "Ab8TvZa3Q19VNkQVzAWVL7",
+            "agency_verkey": "5LXaR43B1aQyeh94VBP8LG1Sgvjk7aNfqiksBCSjwqbf",
+            "wallet_key": "test_key",
+        });
+        let preparation = provision_cloud_agent_prepare(&config.to_string()).unwrap();
+
+        let mut other_config = config.clone();
+        other_config["agency_did"] = json!("VsKV7grR1BUE29mG2Fm2kX");
+
+        let err = provision_cloud_agent_complete(&other_config.to_string(), &preparation).unwrap_err();
+        assert_eq!(VcxErrorKind::InvalidConfiguration, err.kind());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_connect_register_provision_with_token() {
+        let _setup = SetupAriesMocks::init();
+
+        let config = json!({
+            "agency_url": "http://www.whocares.org",
+            "agency_did": "Ab8TvZa3Q19VNkQVzAWVL7",
+            "agency_verkey": "5LXaR43B1aQyeh94VBP8LG1Sgvjk7aNfqiksBCSjwqbf",
+            "wallet_key": "test_key",
+            "protocol_type": "3.0"
+        });
+
+        let result = connect_register_provision_with_token(&config.to_string(), _provision_token()).unwrap();
+        let result: serde_json::Value = ::serde_json::from_str(&result).unwrap();
+
+        // taken from mock constants::AGENT_PROVISIONED_DECRYPTED
+        assert_eq!(json!("DnEpUQJLupa5rKPkrKUpFd"), result["remote_to_sdk_did"]);
+        assert_eq!(json!("7y118tRW2EMJn18qs9MY5NJWYW2PLwV5QpaLyfoLHtgF"), result["remote_to_sdk_verkey"]);
+        assert_eq!(json!("Ab8TvZa3Q19VNkQVzAWVL7"), result["agency_did"]);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_provision_token_serialization() {
+        let token = json!(_provision_token());
+        assert_eq!(json!("sponsee-123"), token["sponseeId"]);
+        assert_eq!(json!("evernym-test-sponsor"), token["sponsorId"]);
+        assert_eq!(json!("GJ1SzoWzavQYfNL9XkaJdrQejfztN4XqdsiV4ct3LXKL"), token["sponsorVerKey"]);
+
+        assert_eq!(_provision_token(), ProvisionToken::from_str(&token.to_string()).unwrap());
+        assert_eq!(VcxErrorKind::InvalidJson, ProvisionToken::from_str(r#"{"sponseeId": "sponsee-123"}"#).unwrap_err().kind());
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_method_type_serialization() {
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::Utc;
+
+lazy_static! {
+    static ref DELIVERY_STATS: Mutex<HashMap<String, DeliveryStats>> = Default::default();
+}
+
+/// Messaging activity of the pairwise connection observed since the library was loaded.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeliveryStats {
+    /// Messages handed over to the counterparty endpoint or to the outbound queue.
+    pub messages_sent: u64,
+    /// Messages downloaded from the agency and marked as reviewed once handled.
+    pub messages_received: u64,
+    /// RFC 3339 time of the last message sent or received.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_activity: Option<String>,
+    /// Messages waiting at the agency when they were downloaded last time, less the ones handled since.
+    pub pending_messages: u64,
+}
+
+fn _update<F>(pw_did: &str, closure: F) where F: FnOnce(&mut DeliveryStats) {
+    let mut stats = DELIVERY_STATS.lock().unwrap();
+    closure(stats.entry(pw_did.to_string()).or_insert_with(DeliveryStats::default));
+}
+
+pub fn record_sent(pw_did: &str) {
+    _update(pw_did, |stats| {
+        stats.messages_sent += 1;
+        stats.last_activity = Some(Utc::now().to_rfc3339());
+    });
+}
+
+pub fn record_received(pw_did: &str) {
+    _update(pw_did, |stats| {
+        stats.messages_received += 1;
+        stats.pending_messages = stats.pending_messages.saturating_sub(1);
+        stats.last_activity = Some(Utc::now().to_rfc3339());
+    });
+}
+
+pub fn record_pending(pw_did: &str, count: usize) {
+    _update(pw_did, |stats| stats.pending_messages = count as u64);
+}
+
+pub fn get(pw_did: &str) -> DeliveryStats {
+    DELIVERY_STATS.lock().unwrap().get(pw_did).cloned().unwrap_or_default()
+}
+
+pub fn forget(pw_did: &str) {
+    DELIVERY_STATS.lock().unwrap().remove(pw_did);
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_delivery_stats() {
+        let pw_did = "DeliveryStatsPwDid";
+        assert_eq!(DeliveryStats::default(), get(pw_did));
+
+        record_pending(pw_did, 2);
+        record_sent(pw_did);
+        record_received(pw_did);
+
+        let stats = get(pw_did);
+        assert_eq!(1, stats.messages_sent);
+        assert_eq!(1, stats.messages_received);
+        assert_eq!(1, stats.pending_messages);
+        assert!(stats.last_activity.is_some());
+
+        record_received(pw_did);
+        record_received(pw_did);
+        assert_eq!(0, get(pw_did).pending_messages);
+
+        forget(pw_did);
+        assert_eq!(DeliveryStats::default(), get(pw_did));
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use aries::messages::a2a::A2AMessage;
+use error::prelude::*;
+use messages::get_message::download_messages;
+use messages::MessageStatusCode;
+use utils::agent_context;
+
+lazy_static! {
+    static ref PREFETCHED_MESSAGES: Mutex<HashMap<(Option<u32>, String), HashMap<String, A2AMessage>>> = Default::default();
+}
+
+/// State change of an object caused by `update_all_states`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StateChange {
+    pub object_type: String,
+    pub handle: u32,
+    pub prev_state: u32,
+    pub state: u32,
+}
+
+// messages are prefetched per agent, see `agent::Agent`
+fn _key(pw_did: &str) -> (Option<u32>, String) {
+    (agent_context::current_agent_id(), pw_did.to_string())
+}
+
+fn _forget_prefetched_messages() {
+    let agent_id = agent_context::current_agent_id();
+    PREFETCHED_MESSAGES.lock().unwrap().retain(|&(agent, _), _| agent != agent_id);
+}
+
+/// Messages downloaded by `update_all_states` for the pairwise DID, they are used instead of asking agency again.
+pub fn get_prefetched_messages(pw_did: &str) -> Option<HashMap<String, A2AMessage>> {
+    PREFETCHED_MESSAGES.lock().unwrap().get(&_key(pw_did)).cloned()
+}
+
+/// Drops prefetched message once it is handled so that it is not offered to another object.
+pub fn forget_prefetched_message(pw_did: &str, uid: &str) {
+    if let Some(messages) = PREFETCHED_MESSAGES.lock().unwrap().get_mut(&_key(pw_did)) {
+        messages.remove(uid);
+    }
+}
+
+fn _prefetch_messages(pw_dids: Vec<String>) -> VcxResult<()> {
+    let mut prefetched: HashMap<String, HashMap<String, A2AMessage>> = pw_dids.iter()
+        .map(|pw_did| (pw_did.to_string(), HashMap::new()))
+        .collect();
+
+    let downloaded = download_messages(Some(pw_dids), Some(vec![MessageStatusCode::Received.to_string()]), None)?;
+
+    for connection_messages in downloaded {
+        let messages = prefetched.entry(connection_messages.pairwise_did).or_insert_with(HashMap::new);
+        for message in connection_messages.msgs {
+            match message.decode_aries_message() {
+                Some(a2a_message) => { messages.insert(message.uid, a2a_message); }
+                None => warn!("update_all_states >>> skipping message {} which cannot be decoded", message.uid)
+            }
+        }
+    }
+
+    _forget_prefetched_messages();
+    PREFETCHED_MESSAGES.lock().unwrap().extend(prefetched.into_iter().map(|(pw_did, messages)| (_key(&pw_did), messages)));
+    Ok(())
+}
+
+fn _update_states<S, U>(object_type: &str, handles: Vec<u32>, get_state: S, update_state: U) -> Vec<StateChange>
+    where S: Fn(u32) -> VcxResult<u32>, U: Fn(u32) -> VcxResult<u32> {
+    handles.into_iter()
+        .filter_map(|handle| {
+            let prev_state = get_state(handle).ok()?;
+            if let Err(err) = update_state(handle) {
+                warn!("update_all_states >>> cannot update state of {} {}: {}", object_type, handle, err);
+            }
+            let state = get_state(handle).ok()?;
+            if state != prev_state {
+                Some(StateChange { object_type: object_type.to_string(), handle, prev_state, state })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/**
+Downloads messages received on all pairwise connections by a single agency request
+and lets every connection, credential and proof object handle them.
+
+Returns list of objects which changed state.
+ */
+pub fn update_all_states() -> VcxResult<Vec<StateChange>> {
+    trace!("update_all_states >>>");
+
+    let connection_handles = ::connection::get_handles();
+    let pw_dids: Vec<String> = connection_handles.iter()
+        .filter_map(|handle| ::connection::get_pw_did(*handle).ok())
+        .filter(|pw_did| !pw_did.is_empty())
+        .collect();
+
+    if !pw_dids.is_empty() {
+        _prefetch_messages(pw_dids)?;
+    }
+
+    // connections first, so that other protocols can use connections completed by this update
+    let mut changes = _update_states("connection", connection_handles, |handle| Ok(::connection::get_state(handle)), ::connection::update_state);
+    changes.extend(_update_states("credential", ::credential::get_handles(), ::credential::get_state, |handle| ::credential::update_state(handle, None, None)));
+    changes.extend(_update_states("issuer_credential", ::issuer_credential::get_handles(), ::issuer_credential::get_state, |handle| ::issuer_credential::update_state(handle, None, None)));
+    changes.extend(_update_states("disclosed_proof", ::disclosed_proof::get_handles(), ::disclosed_proof::get_state, |handle| ::disclosed_proof::update_state(handle, None, None)));
+    changes.extend(_update_states("proof", ::proof::get_handles(), ::proof::get_state, |handle| ::proof::update_state(handle, None, None)));
+
+    _forget_prefetched_messages();
+
+    trace!("update_all_states <<< changes: {:?}", changes);
+    Ok(changes)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use aries::messages::ack::tests::_ack;
+    use utils::devsetup::SetupAriesMocks;
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_prefetched_messages() {
+        let _setup = SetupAriesMocks::init();
+
+        let mut messages = HashMap::new();
+        messages.insert("uid1".to_string(), _ack().to_a2a_message());
+        PREFETCHED_MESSAGES.lock().unwrap().insert(_key("pw_did"), messages.clone());
+
+        assert_eq!(get_prefetched_messages("pw_did").unwrap(), messages);
+        assert!(get_prefetched_messages("other_did").is_none());
+
+        forget_prefetched_message("pw_did", "uid1");
+        assert!(get_prefetched_messages("pw_did").unwrap().is_empty());
+
+        _forget_prefetched_messages();
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_update_all_states_works_without_objects() {
+        let _setup = SetupAriesMocks::init();
+        ::connection::release_all();
+        ::credential::release_all();
+        ::issuer_credential::release_all();
+        ::disclosed_proof::release_all();
+        ::proof::release_all();
+
+        assert_eq!(update_all_states().unwrap(), vec![]);
+    }
+}
@@ -1,7 +1,13 @@
+use std::collections::BTreeMap;
+
+use chrono::prelude::*;
+use serde::{Deserialize, Deserializer};
+
+use aries::messages::a2a::A2AMessage as AriesMessage;
 use error::{VcxError, VcxErrorKind, VcxResult};
 use messages::{A2AMessage, A2AMessageKinds, A2AMessageV2, GeneralMessage, get_messages, MessageStatusCode, parse_response_from_agency, prepare_message_for_agency, prepare_message_for_agent, RemoteMessageType};
 use messages::message_type::MessageTypes;
-use messages::payload::Payloads;
+use messages::payload::{Payloads, PayloadV1};
 use settings;
 use settings::ProtocolTypes;
 use utils::{constants, httpclient};
@@ -135,7 +141,7 @@ impl GetMessagesBuilder {
 
         let data = self.prepare_request()?;
 
-        let response = httpclient::post_u8(&data)?;
+        let response = httpclient::post_u8_idempotent(&data)?;
 
         self.parse_response(response)
     }
@@ -161,7 +167,7 @@ impl GetMessagesBuilder {
 
         let data = self.prepare_download_request()?;
 
-        let response = httpclient::post_u8(&data)?;
+        let response = httpclient::post_u8_idempotent(&data)?;
 
         if settings::agency_mocks_enabled() && response.len() == 0 {
             return Ok(Vec::new());
@@ -204,13 +210,13 @@ impl GetMessagesBuilder {
         };
 
         msgs
-            .iter()
-            .map(|connection| {
-                ::utils::libindy::signus::get_local_verkey(&connection.pairwise_did)
-                    .map(|vk| MessageByConnection {
-                        pairwise_did: connection.pairwise_did.clone(),
-                        msgs: connection.msgs.iter().map(|message| message.decrypt(&vk)).collect(),
-                    })
+            .into_iter()
+            .map(|connection| -> VcxResult<MessageByConnection> {
+                let vk = ::utils::libindy::signus::get_local_verkey(&connection.pairwise_did)?;
+                Ok(MessageByConnection {
+                    pairwise_did: connection.pairwise_did,
+                    msgs: connection.msgs.into_iter().map(|message| message.decrypt(&vk)).collect(),
+                })
             })
             .collect()
     }
@@ -272,12 +278,19 @@ pub struct Message {
     #[serde(rename = "type")]
     pub msg_type: RemoteMessageType,
     pub ref_msg_id: Option<String>,
-    #[serde(skip_deserializing)]
+    #[serde(default, deserialize_with = "_deserialize_delivery_details")]
     pub delivery_details: Vec<DeliveryDetails>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub decrypted_payload: Option<String>,
 }
 
+// agencies differ in what they report here and details are informative only,
+// so they must never make the whole message unreadable
+fn _deserialize_delivery_details<'de, D>(deserializer: D) -> Result<Vec<DeliveryDetails>, D::Error> where D: Deserializer<'de> {
+    let value = ::serde_json::Value::deserialize(deserializer)?;
+    Ok(::serde_json::from_value(value).unwrap_or_default())
+}
+
 #[macro_export]
 macro_rules! convert_aries_message {
     ($a2a_msg:ident, $kind:ident) => (
@@ -293,26 +306,42 @@ impl Message {
         }
     }
 
-    pub fn decrypt(&self, vk: &str) -> Message {
+    pub fn decrypt(mut self, vk: &str) -> Message {
         // TODO: must be Result
-        let mut new_message = self.clone();
         if let Some(ref payload) = self.payload {
+            // strict aries mode decodes only aries messages, proprietary ones are left with null payload
             let decrypted_payload = match payload {
-                MessagePayload::V2(payload) => Payloads::decrypt_payload_v2(&vk, &payload)
-                    .map(Payloads::PayloadV2)
+                MessagePayload::V2(payload) if !settings::is_strict_aries_protocol_set() => Payloads::decrypt_payload_v2(&vk, &payload)
+                    .map(Payloads::PayloadV2),
+                MessagePayload::V2(_) => Err(VcxError::from(VcxErrorKind::ActionNotSupported))
             };
 
             // todo: are all these branches still even possible?
-            if let Ok(decrypted_payload) = decrypted_payload {
-                new_message.decrypted_payload = ::serde_json::to_string(&decrypted_payload).ok();
+            let decrypted_payload = if let Ok(decrypted_payload) = decrypted_payload {
+                ::serde_json::to_string(&decrypted_payload).ok()
             } else if let Ok(decrypted_payload) = self._decrypt_v3_message() {
-                new_message.decrypted_payload = ::serde_json::to_string(&json!(decrypted_payload)).ok()
+                ::serde_json::to_string(&json!(decrypted_payload)).ok()
             } else {
-                new_message.decrypted_payload = ::serde_json::to_string(&json!(null)).ok();
-            }
+                ::serde_json::to_string(&json!(null)).ok()
+            };
+            self.decrypted_payload = decrypted_payload;
         }
-        new_message.payload = None;
-        new_message
+        self.payload = None;
+        self
+    }
+
+    /// Aries message carried by the decrypted payload.
+    pub fn decode_aries_message(&self) -> Option<AriesMessage> {
+        self.decrypted_payload.as_ref()
+            .and_then(|payload| ::serde_json::from_str::<PayloadV1>(payload).ok())
+            .and_then(|payload| ::serde_json::from_str::<AriesMessage>(&payload.msg).ok())
+    }
+
+    /// The latest time agency reported a delivery of the message.
+    pub fn last_updated(&self) -> Option<DateTime<Utc>> {
+        self.delivery_details.iter()
+            .filter_map(|details| _parse_date_time(&details.last_updated_date_time).ok())
+            .max()
     }
 
     fn _decrypt_v3_message(&self) -> VcxResult<::messages::payload::PayloadV1> {
@@ -322,16 +351,15 @@ impl Message {
 
         let a2a_message = EncryptionEnvelope::open(self.payload()?)?;
 
-        let (kind, msg) = match a2a_message.clone() {
-            A2AMessage::PresentationRequest(_) => (PayloadKinds::ProofRequest, json!(&a2a_message).to_string()),
-            A2AMessage::CredentialOffer(_) => (PayloadKinds::CredOffer, json!(&a2a_message).to_string()),
-            A2AMessage::Credential(_) => (PayloadKinds::Cred, json!(&a2a_message).to_string()),
-            A2AMessage::Presentation(_) => (PayloadKinds::Proof, json!(&a2a_message).to_string()),
-            msg => {
-                let msg = json!(&msg).to_string();
-                (PayloadKinds::Other(String::from("aries")), msg)
-            }
+        let kind = match a2a_message {
+            A2AMessage::PresentationRequest(_) => PayloadKinds::ProofRequest,
+            A2AMessage::CredentialOffer(_) => PayloadKinds::CredOffer,
+            A2AMessage::Credential(_) => PayloadKinds::Cred,
+            A2AMessage::Presentation(_) => PayloadKinds::Proof,
+            _ => PayloadKinds::Other(String::from("aries"))
         };
+        let msg = ::serde_json::to_string(&a2a_message)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize A2A message: {}", err)))?;
 
         Ok(PayloadV1 {
             type_: PayloadTypes::build_v1(kind, "json"),
@@ -418,6 +446,81 @@ pub fn download_messages(pairwise_dids: Option<Vec<String>>, status_codes: Optio
     Ok(response)
 }
 
+/// Restrictions applied to downloaded messages on top of the agency query.
+///
+/// `from` and `to` are RFC 3339 date times compared with the time agency last updated the message.
+/// Messages agency does not report the time for are left out when either bound is set.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct MessageFilter {
+    pub thread_id: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+fn _parse_date_time(value: &str) -> VcxResult<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|date_time| date_time.with_timezone(&Utc))
+        .or_else(|_| Utc.datetime_from_str(value, "%Y-%m-%d %H:%M:%S%.fZ"))
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Cannot parse date time {}: {}", value, err)))
+}
+
+impl MessageFilter {
+    fn matches(&self, message: &Message, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> bool {
+        if let Some(ref thread_id) = self.thread_id {
+            if message.decode_aries_message().and_then(|message| message.thread_id()).as_ref() != Some(thread_id) {
+                return false;
+            }
+        }
+
+        if from.is_none() && to.is_none() {
+            return true;
+        }
+
+        match message.last_updated() {
+            Some(last_updated) => from.map_or(true, |from| last_updated >= from) && to.map_or(true, |to| last_updated <= to),
+            None => false
+        }
+    }
+}
+
+pub fn download_messages_filtered(pairwise_dids: Option<Vec<String>>, status_codes: Option<Vec<String>>, uids: Option<Vec<String>>, filter: &MessageFilter) -> VcxResult<Vec<MessageByConnection>> {
+    trace!("download_messages_filtered >>> pairwise_dids: {:?}, status_codes: {:?}, uids: {:?}, filter: {:?}",
+           pairwise_dids, status_codes, uids, filter);
+
+    let from = filter.from.as_ref().map(|from| _parse_date_time(from)).transpose()?;
+    let to = filter.to.as_ref().map(|to| _parse_date_time(to)).transpose()?;
+
+    let messages = download_messages(pairwise_dids, status_codes, uids)?
+        .into_iter()
+        .map(|connection| MessageByConnection {
+            pairwise_did: connection.pairwise_did,
+            msgs: connection.msgs.into_iter().filter(|message| filter.matches(message, from, to)).collect(),
+        })
+        .filter(|connection| !connection.msgs.is_empty())
+        .collect();
+
+    Ok(messages)
+}
+
+/// Number of messages stored by agency for each status code (`MS-103` etc.), payloads are not downloaded.
+pub fn get_message_counts_by_status(pairwise_dids: Option<Vec<String>>) -> VcxResult<BTreeMap<String, usize>> {
+    trace!("get_message_counts_by_status >>> pairwise_dids: {:?}", pairwise_dids);
+
+    let downloaded = get_messages()
+        .pairwise_dids(pairwise_dids)?
+        .include_edge_payload("Y")?
+        .version(&Some(::settings::get_protocol_type()))?
+        .download_messages()?;
+
+    let mut counts = BTreeMap::new();
+    for message in downloaded.iter().flat_map(|connection| connection.msgs.iter()) {
+        *counts.entry(message.status_code.to_string()).or_insert(0) += 1;
+    }
+
+    trace!("get_message_counts_by_status <<< counts: {:?}", counts);
+    Ok(counts)
+}
+
 pub fn download_agent_messages(status_codes: Option<Vec<String>>, uids: Option<Vec<String>>) -> VcxResult<Vec<Message>> {
     trace!("download_messages >>> status_codes: {:?}, uids: {:?}", status_codes, uids);
 
@@ -446,12 +549,79 @@ mod tests {
     #[cfg(feature = "agency_pool_tests")]
     use std::time::Duration;
 
+    use aries::messages::ack::tests::_ack;
     use connection::send_generic_message;
     use utils::constants::{GET_ALL_MESSAGES_RESPONSE, GET_MESSAGES_RESPONSE};
     use utils::devsetup::*;
 
     use super::*;
 
+    fn _aries_message(last_updated: Option<&str>) -> Message {
+        let payload = json!({
+            "@type": {"name": "aries", "ver": "1.0", "fmt": "json"},
+            "@msg": json!(_ack().to_a2a_message()).to_string()
+        }).to_string();
+
+        Message {
+            status_code: MessageStatusCode::Received,
+            payload: None,
+            sender_did: String::new(),
+            uid: "uid1".to_string(),
+            msg_type: ::messages::RemoteMessageType::Other("aries".to_string()),
+            ref_msg_id: None,
+            delivery_details: last_updated.into_iter()
+                .map(|last_updated| DeliveryDetails { to: "did".to_string(), status_code: "MDS-102".to_string(), last_updated_date_time: last_updated.to_string() })
+                .collect(),
+            decrypted_payload: Some(payload),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_decode_aries_message() {
+        let _setup = SetupAriesMocks::init();
+
+        let message = _aries_message(None);
+        assert_eq!(message.decode_aries_message(), Some(_ack().to_a2a_message()));
+
+        let message = Message { decrypted_payload: Some("null".to_string()), ..message };
+        assert_eq!(message.decode_aries_message(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_message_without_delivery_details_is_parsed() {
+        let _setup = SetupDefaults::init();
+
+        let message: Message = ::serde_json::from_str(r#"{"statusCode":"MS-103","payload":null,"senderDID":"","uid":"uid1","type":"aries","refMsgId":null}"#).unwrap();
+        assert!(message.delivery_details.is_empty());
+
+        let message: Message = ::serde_json::from_str(r#"{"statusCode":"MS-103","payload":null,"senderDID":"","uid":"uid1","type":"aries","refMsgId":null,"deliveryDetails":[{"to":"did","statusCode":"MDS-102","lastUpdatedDateTime":"2020-05-01 10:00:00.000Z"}]}"#).unwrap();
+        assert_eq!(message.last_updated(), Some(Utc.ymd(2020, 5, 1).and_hms(10, 0, 0)));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_message_filter() {
+        let _setup = SetupAriesMocks::init();
+
+        let thread_id = _ack().to_a2a_message().thread_id();
+        let message = _aries_message(Some("2020-05-01T10:00:00Z"));
+        let date = |value: &str| Some(_parse_date_time(value).unwrap());
+
+        assert!(MessageFilter::default().matches(&message, None, None));
+        assert!(MessageFilter { thread_id: thread_id.clone(), ..MessageFilter::default() }.matches(&message, None, None));
+        assert!(!MessageFilter { thread_id: Some("other".to_string()), ..MessageFilter::default() }.matches(&message, None, None));
+
+        assert!(MessageFilter::default().matches(&message, date("2020-05-01T00:00:00Z"), date("2020-05-02T00:00:00Z")));
+        assert!(!MessageFilter::default().matches(&message, date("2020-05-02T00:00:00Z"), None));
+        assert!(!MessageFilter::default().matches(&message, None, date("2020-04-30T00:00:00Z")));
+        assert!(!MessageFilter::default().matches(&_aries_message(None), date("2020-05-01T00:00:00Z"), None));
+
+        let filter = MessageFilter { from: Some("yesterday".to_string()), ..MessageFilter::default() };
+        assert_eq!(download_messages_filtered(None, None, None, &filter).unwrap_err().kind(), VcxErrorKind::InvalidOption);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     #[cfg(feature = "to_restore")]
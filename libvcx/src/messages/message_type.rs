@@ -66,6 +66,7 @@ pub struct MessageTypeV2 {
 pub enum MessageFamilies {
     Routing,
     Onboarding,
+    AgentProvisioning,
     Pairwise,
     Configs,
     CredentialExchange,
@@ -77,6 +78,7 @@ impl MessageFamilies {
         match self {
             MessageFamilies::Routing => "1.0",
             MessageFamilies::Onboarding => "1.0",
+            MessageFamilies::AgentProvisioning => "0.7",
             MessageFamilies::Pairwise => "1.0",
             MessageFamilies::Configs => "1.0",
             MessageFamilies::CredentialExchange => "1.0",
@@ -90,6 +92,7 @@ impl From<String> for MessageFamilies {
         match family.as_str() {
             "routing" => MessageFamilies::Routing,
             "onboarding" => MessageFamilies::Onboarding,
+            "agent-provisioning" => MessageFamilies::AgentProvisioning,
             "pairwise" => MessageFamilies::Pairwise,
             "configs" => MessageFamilies::Configs,
             "credential-exchange" => MessageFamilies::CredentialExchange,
@@ -103,6 +106,7 @@ impl ::std::string::ToString for MessageFamilies {
         match self {
             MessageFamilies::Routing => "routing".to_string(),
             MessageFamilies::Onboarding => "onboarding".to_string(),
+            MessageFamilies::AgentProvisioning => "agent-provisioning".to_string(),
             MessageFamilies::Pairwise => "pairwise".to_string(),
             MessageFamilies::CredentialExchange => "credential_exchange".to_string(),
             MessageFamilies::Configs => "configs".to_string(),
@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::u8;
 
 use log;
@@ -8,16 +9,17 @@ use error::prelude::*;
 use settings;
 use settings::ProtocolTypes;
 use utils::httpclient::AgencyMockDecrypted;
+use utils::json::check_json_limits;
 use utils::libindy::crypto;
 
-use self::agent_utils::{ComMethodUpdated, Connect, ConnectResponse, CreateAgent, CreateAgentResponse, SignUp, SignUpResponse, UpdateComMethod};
+use self::agent_utils::{ComMethodUpdated, Connect, ConnectResponse, CreateAgent, CreateAgentResponse, ProvisionAgent, ProvisionAgentResponse, SignUp, SignUpResponse, UpdateComMethod};
 use self::create_key::{CreateKey, CreateKeyBuilder, CreateKeyResponse};
 use self::get_message::{GetMessages, GetMessagesBuilder, GetMessagesResponse, MessagesByConnections};
 use self::message_type::*;
 use self::proofs::proof_request::ProofRequestMessage;
 use self::update_connection::{DeleteConnectionBuilder, UpdateConnection, UpdateConnectionResponse};
 use self::update_message::{UpdateMessageStatusByConnections, UpdateMessageStatusByConnectionsResponse};
-use self::update_profile::{UpdateConfigs, UpdateConfigsResponse, UpdateProfileDataBuilder};
+use self::update_profile::{GetConfigs, GetConfigsResponse, UpdateConfigs, UpdateConfigsResponse, UpdateProfileDataBuilder};
 
 pub mod create_key;
 pub mod validation;
@@ -29,9 +31,14 @@ pub mod update_connection;
 pub mod update_message;
 pub mod message_type;
 pub mod payload;
+pub mod dispatch;
+pub mod delivery_stats;
+pub mod push_notification;
 #[macro_use]
 pub mod thread;
 
+pub use self::push_notification::decrypt_push_payload;
+
 #[derive(Debug, Serialize)]
 #[serde(untagged)]
 pub enum A2AMessageV2 {
@@ -45,6 +52,8 @@ pub enum A2AMessageV2 {
     SignUpResponse(SignUpResponse),
     CreateAgent(CreateAgent),
     CreateAgentResponse(CreateAgentResponse),
+    ProvisionAgent(ProvisionAgent),
+    ProvisionAgentResponse(ProvisionAgentResponse),
 
     /// PW Connection
     CreateKey(CreateKey),
@@ -66,6 +75,8 @@ pub enum A2AMessageV2 {
     /// config
     UpdateConfigs(UpdateConfigs),
     UpdateConfigsResponse(UpdateConfigsResponse),
+    GetConfigs(GetConfigs),
+    GetConfigsResponse(GetConfigsResponse),
     UpdateComMethod(UpdateComMethod),
     ComMethodUpdated(ComMethodUpdated),
 }
@@ -110,6 +121,16 @@ impl<'de> Deserialize<'de> for A2AMessageV2 {
                     .map(A2AMessageV2::SignUpResponse)
                     .map_err(de::Error::custom)
             }
+            "CREATE_AGENT" if message_type.family == MessageFamilies::AgentProvisioning => {
+                ProvisionAgent::deserialize(value)
+                    .map(A2AMessageV2::ProvisionAgent)
+                    .map_err(de::Error::custom)
+            }
+            "AGENT_CREATED" if message_type.family == MessageFamilies::AgentProvisioning => {
+                ProvisionAgentResponse::deserialize(value)
+                    .map(A2AMessageV2::ProvisionAgentResponse)
+                    .map_err(de::Error::custom)
+            }
             "CREATE_AGENT" => {
                 CreateAgent::deserialize(value)
                     .map(A2AMessageV2::CreateAgent)
@@ -190,6 +211,16 @@ impl<'de> Deserialize<'de> for A2AMessageV2 {
                     .map(A2AMessageV2::UpdateConfigsResponse)
                     .map_err(de::Error::custom)
             }
+            "GET_CONFIGS" => {
+                GetConfigs::deserialize(value)
+                    .map(A2AMessageV2::GetConfigs)
+                    .map_err(de::Error::custom)
+            }
+            "CONFIGS" => {
+                GetConfigsResponse::deserialize(value)
+                    .map(A2AMessageV2::GetConfigsResponse)
+                    .map_err(de::Error::custom)
+            }
             "UPDATE_COM_METHOD" => {
                 UpdateComMethod::deserialize(value)
                     .map(A2AMessageV2::UpdateComMethod)
@@ -424,6 +455,8 @@ pub enum A2AMessageKinds {
     SignedUp,
     CreateAgent,
     AgentCreated,
+    ProvisionAgent,
+    AgentProvisioned,
     CreateKey,
     KeyCreated,
     CreateMessage,
@@ -438,6 +471,8 @@ pub enum A2AMessageKinds {
     UpdateConnectionStatus,
     UpdateConfigs,
     ConfigsUpdated,
+    GetConfigs,
+    Configs,
     UpdateComMethod,
     ComMethodUpdated,
     SendRemoteMessage,
@@ -452,6 +487,8 @@ impl A2AMessageKinds {
             A2AMessageKinds::Connected => MessageFamilies::Onboarding,
             A2AMessageKinds::CreateAgent => MessageFamilies::Onboarding,
             A2AMessageKinds::AgentCreated => MessageFamilies::Onboarding,
+            A2AMessageKinds::ProvisionAgent => MessageFamilies::AgentProvisioning,
+            A2AMessageKinds::AgentProvisioned => MessageFamilies::AgentProvisioning,
             A2AMessageKinds::SignUp => MessageFamilies::Onboarding,
             A2AMessageKinds::SignedUp => MessageFamilies::Onboarding,
             A2AMessageKinds::CreateKey => MessageFamilies::Pairwise,
@@ -468,6 +505,8 @@ impl A2AMessageKinds {
             A2AMessageKinds::MessageStatusUpdatedByConnections => MessageFamilies::Pairwise,
             A2AMessageKinds::UpdateConfigs => MessageFamilies::Configs,
             A2AMessageKinds::ConfigsUpdated => MessageFamilies::Configs,
+            A2AMessageKinds::GetConfigs => MessageFamilies::Configs,
+            A2AMessageKinds::Configs => MessageFamilies::Configs,
             A2AMessageKinds::UpdateComMethod => MessageFamilies::Configs,
             A2AMessageKinds::ComMethodUpdated => MessageFamilies::Configs,
             A2AMessageKinds::SendRemoteMessage => MessageFamilies::Routing,
@@ -482,6 +521,8 @@ impl A2AMessageKinds {
             A2AMessageKinds::Connected => "CONNECTED".to_string(),
             A2AMessageKinds::CreateAgent => "CREATE_AGENT".to_string(),
             A2AMessageKinds::AgentCreated => "AGENT_CREATED".to_string(),
+            A2AMessageKinds::ProvisionAgent => "CREATE_AGENT".to_string(),
+            A2AMessageKinds::AgentProvisioned => "AGENT_CREATED".to_string(),
             A2AMessageKinds::SignUp => "SIGNUP".to_string(),
             A2AMessageKinds::SignedUp => "SIGNED_UP".to_string(),
             A2AMessageKinds::CreateKey => "CREATE_KEY".to_string(),
@@ -498,6 +539,8 @@ impl A2AMessageKinds {
             A2AMessageKinds::UpdateConnectionStatus => "UPDATE_CONN_STATUS".to_string(),
             A2AMessageKinds::UpdateConfigs => "UPDATE_CONFIGS".to_string(),
             A2AMessageKinds::ConfigsUpdated => "CONFIGS_UPDATED".to_string(),
+            A2AMessageKinds::GetConfigs => "GET_CONFIGS".to_string(),
+            A2AMessageKinds::Configs => "CONFIGS".to_string(),
             A2AMessageKinds::UpdateComMethod => "UPDATE_COM_METHOD".to_string(),
             A2AMessageKinds::ComMethodUpdated => "COM_METHOD_UPDATED".to_string(),
             A2AMessageKinds::SendRemoteMessage => "SEND_REMOTE_MSG".to_string(),
@@ -516,8 +559,9 @@ pub fn prepare_message_for_agency(message: &A2AMessage, agency_did: &str, versio
 }
 
 fn pack_for_agency_v2(message: &A2AMessage, agency_did: &str) -> VcxResult<Vec<u8>> {
-    let agent_vk = settings::get_config_value(settings::CONFIG_REMOTE_TO_SDK_VERKEY)?;
-    let my_vk = settings::get_config_value(settings::CONFIG_SDK_TO_REMOTE_VERKEY)?;
+    let settings = settings::snapshot();
+    let agent_vk = settings.get_value(settings::CONFIG_REMOTE_TO_SDK_VERKEY)?;
+    let my_vk = settings.get_value(settings::CONFIG_SDK_TO_REMOTE_VERKEY)?;
 
     let message = ::serde_json::to_string(&message)
         .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize A2A message: {}", err)))?;
@@ -541,24 +585,37 @@ fn parse_response_from_agency(response: &Vec<u8>, version: &ProtocolTypes) -> Vc
     }
 }
 
-pub fn parse_message_from_response(response: &Vec<u8>) -> VcxResult<String> {
-    let unpacked_msg = crypto::unpack_message(&response[..])?;
+#[derive(Deserialize)]
+struct UnpackedMessage<'a> {
+    #[serde(borrow)]
+    message: Cow<'a, str>,
+}
 
-    let message: Value = ::serde_json::from_slice(unpacked_msg.as_slice())
-        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize response: {}", err)))?;
+/// Extracts `message` field of unpacked envelope and checks it against `max_json_size` and `max_json_depth`.
+/// The field is borrowed from the buffer whenever it contains no escape sequences, no intermediate JSON tree is built.
+pub fn parse_unpacked_message(unpacked_msg: &[u8]) -> VcxResult<Cow<str>> {
+    let message = ::serde_json::from_slice::<UnpackedMessage>(unpacked_msg)
+        .map(|unpacked| unpacked.message)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot find `message` field on response: {}", err)))?;
+    check_json_limits(message.as_bytes())?;
+    Ok(message)
+}
 
-    Ok(message["message"].as_str()
-        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, "Cannot find `message` field on response"))?.to_string())
+pub fn parse_message_from_response(response: &Vec<u8>) -> VcxResult<String> {
+    let unpacked_msg = crypto::unpack_message(&response[..])?;
+    parse_unpacked_message(&unpacked_msg).map(Cow::into_owned)
 }
 
 fn parse_response_from_agency_v2(response: &Vec<u8>) -> VcxResult<Vec<A2AMessage>> {
     trace!("parse_response_from_agency_v2 >>> response = {:?}", response);
 
-    let message: String = if AgencyMockDecrypted::has_decrypted_mock_responses() {
+    let unpacked_msg;
+    let message: Cow<str> = if AgencyMockDecrypted::has_decrypted_mock_responses() {
         warn!("parse_response_from_agency_v2 >> retrieving decrypted mock response");
-        AgencyMockDecrypted::get_next_decrypted_response()
+        Cow::Owned(AgencyMockDecrypted::get_next_decrypted_response())
     } else {
-        parse_message_from_response(response)?
+        unpacked_msg = crypto::unpack_message(&response[..])?;
+        parse_unpacked_message(&unpacked_msg)?
     };
 
     trace!("AgencyComm Inbound V2 A2AMessage: {}", message);
@@ -755,6 +812,10 @@ pub fn update_data() -> UpdateProfileDataBuilder { UpdateProfileDataBuilder::cre
 
 pub fn get_messages() -> GetMessagesBuilder { GetMessagesBuilder::create() }
 
+pub use self::dispatch::update_all_states;
+pub use self::get_message::{download_messages_filtered, get_message_counts_by_status, MessageFilter};
+pub use self::update_message::update_messages_status;
+
 pub fn proof_request() -> ProofRequestMessage { ProofRequestMessage::create() }
 
 #[cfg(test)]
@@ -784,4 +845,21 @@ pub mod tests {
         println!("new bundle: {:?}", buf);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_parse_unpacked_message() {
+        let _setup = SetupDefaults::init();
+
+        let unpacked = json!({"message": "{\"@type\":\"ping\"}", "sender_verkey": "key"}).to_string();
+        let message = parse_unpacked_message(unpacked.as_bytes()).unwrap();
+        assert_eq!("{\"@type\":\"ping\"}", message);
+
+        let unpacked = br#"{"message": "plain"}"#;
+        match parse_unpacked_message(unpacked).unwrap() {
+            Cow::Borrowed(message) => assert_eq!("plain", message),
+            Cow::Owned(_) => panic!("Message without escape sequences should be borrowed")
+        }
+
+        assert_eq!(VcxErrorKind::InvalidJson, parse_unpacked_message(br#"{"sender_verkey": "key"}"#).unwrap_err().kind());
+    }
 }
@@ -2,9 +2,11 @@ use serde_json::Value;
 
 use error::{VcxError, VcxErrorKind, VcxResult};
 use messages::get_message::MessagePayload;
+use messages::parse_unpacked_message;
 use messages::message_type::*;
 use messages::thread::Thread;
 use settings::{get_protocol_type, ProtocolTypes};
+use utils::json::check_json_limits;
 use utils::libindy::crypto;
 
 #[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
@@ -91,12 +93,7 @@ impl Payloads {
             .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidState, err))?;
 
         let unpacked_msg = crypto::unpack_message(&payload)?;
-
-        let message: ::serde_json::Value = ::serde_json::from_slice(unpacked_msg.as_slice())
-            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize payload: {}", err)))?;
-
-        let message = message["message"].as_str()
-            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, "Cannot find `message` field"))?.to_string();
+        let message = parse_unpacked_message(&unpacked_msg)?;
 
         let mut my_payload: PayloadV2 = serde_json::from_str(&message)
             .map_err(|err| {
@@ -117,11 +114,12 @@ impl Payloads {
 
         let unpacked_msg = crypto::unpack_message(&payload)?;
 
-        let message: ::serde_json::Value = ::serde_json::from_slice(unpacked_msg.as_slice())
+        let message: ::serde_json::Value = ::serde_json::from_slice(&unpacked_msg)
             .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize payload: {}", err)))?;
 
         let message = message["message"].as_str()
             .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, "Cannot find `message` field"))?.to_string();
+        check_json_limits(message.as_bytes())?;
 
         let my_payload: PayloadV12 = serde_json::from_str(&message)
             .map_err(|err| {
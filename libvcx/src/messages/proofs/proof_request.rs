@@ -12,6 +12,10 @@ use utils::qualifier;
 static PROOF_REQUEST: &str = "PROOF_REQUEST";
 static PROOF_DATA: &str = "proof_request_data";
 pub const PROOF_REQUEST_V2: &str = "2.0";
+/// Beginning of revocation interval may be ahead of the local clock by this many seconds to tolerate skew against the ledger clock.
+pub const NON_REVOKED_CLOCK_SKEW_SECS: u64 = 600;
+// larger timestamps would lie thousands of years ahead, they are milliseconds passed instead of seconds
+const MAX_TIMESTAMP_SECS: u64 = 100_000_000_000;
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, PartialOrd)]
 struct ProofType {
@@ -47,6 +51,14 @@ pub struct AttrInfo {
     pub self_attest_allowed: Option<bool>,
 }
 
+// attribute restrictions are enforced when the proof is verified, so the malformed ones are refused upfront
+fn _validate_attr_restrictions(restrictions: &Option<Restrictions>) -> VcxResult<()> {
+    match restrictions {
+        Some(Restrictions::V2(query)) => ::proof_utils::validate_attr_restrictions(query),
+        _ => Ok(())
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Filter {
     pub schema_id: Option<String>,
@@ -80,6 +92,34 @@ pub struct NonRevokedInterval {
     pub to: Option<u64>,
 }
 
+impl NonRevokedInterval {
+    /// `from` must not follow `to` nor lie in the future of `now`. `to` may lie in the future,
+    /// the prover then proves non-revocation by the latest state of the revocation registry.
+    pub fn validate(&self, now: u64) -> VcxResult<()> {
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                return Err(VcxError::from_msg(VcxErrorKind::InvalidRevocationTimestamp,
+                                              format!("Invalid revocation interval: `from` ({}) must not be greater than `to` ({})", from, to)));
+            }
+        }
+
+        for timestamp in self.from.iter().chain(self.to.iter()) {
+            if *timestamp > MAX_TIMESTAMP_SECS {
+                return Err(VcxError::from_msg(VcxErrorKind::InvalidRevocationTimestamp,
+                                              format!("Invalid revocation interval: timestamp {} is not in seconds since epoch", timestamp)));
+            }
+        }
+
+        if let Some(from) = self.from {
+            if from > now + NON_REVOKED_CLOCK_SKEW_SECS {
+                return Err(VcxError::from_msg(VcxErrorKind::InvalidRevocationTimestamp,
+                                              format!("Invalid revocation interval: `from` ({}) is in the future", from)));
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct ProofRequestData {
     pub nonce: String,
@@ -203,11 +243,11 @@ impl ProofRequestMessage {
                 }
                 (Some(_), Some(_)) => {
                     return Err(VcxError::from_msg(VcxErrorKind::InvalidProofRequest,
-                                                  format!("Proof Request validation failed: there is empty requested attribute: {:?}", attrs)));
+                                                  format!("Proof request validation failed: there is a requested attribute with both name and names: {:?}", attrs)));
                 }
                 (None, None) => {
                     return Err(VcxError::from_msg(VcxErrorKind::InvalidProofRequest,
-                                                  format!("Proof request validation failed: there is a requested attribute with both name and names: {:?}", attrs)));
+                                                  format!("Proof Request validation failed: there is empty requested attribute: {:?}", attrs)));
                 }
             };
 
@@ -257,12 +297,12 @@ impl ProofRequestMessage {
                         .into_iter()
                         .map(|filter| {
                             Filter {
-                                schema_id: filter.schema_id.as_ref().and_then(|schema_id| anoncreds::libindy_to_unqualified(&schema_id).ok()),
-                                schema_issuer_did: filter.schema_issuer_did.as_ref().and_then(|schema_issuer_did| anoncreds::libindy_to_unqualified(&schema_issuer_did).ok()),
+                                schema_id: filter.schema_id.as_ref().map(|schema_id| qualifier::unqualify(schema_id)),
+                                schema_issuer_did: filter.schema_issuer_did.as_ref().map(|schema_issuer_did| qualifier::unqualify(schema_issuer_did)),
                                 schema_name: filter.schema_name,
                                 schema_version: filter.schema_version,
-                                issuer_did: filter.issuer_did.as_ref().and_then(|issuer_did| anoncreds::libindy_to_unqualified(&issuer_did).ok()),
-                                cred_def_id: filter.cred_def_id.as_ref().and_then(|cred_def_id| anoncreds::libindy_to_unqualified(&cred_def_id).ok()),
+                                issuer_did: filter.issuer_did.as_ref().map(|issuer_did| qualifier::unqualify(issuer_did)),
+                                cred_def_id: filter.cred_def_id.as_ref().map(|cred_def_id| qualifier::unqualify(cred_def_id)),
                             }
                         })
                         .collect()
@@ -344,6 +384,10 @@ impl ProofRequestData {
         let requested_attributes: Vec<AttrInfo> = ::serde_json::from_str(&requested_attrs)
             .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Invalid Requested Attributes: {:?}, err: {:?}", requested_attrs, err)))?;
 
+        for attribute in requested_attributes.iter() {
+            _validate_attr_restrictions(&attribute.restrictions)?;
+        }
+
         self.requested_attributes = requested_attributes
             .into_iter()
             .enumerate()
@@ -356,6 +400,10 @@ impl ProofRequestData {
         let requested_predicates: Vec<PredicateInfo> = ::serde_json::from_str(&requested_predicates)
             .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Invalid Requested Attributes: {:?}, err: {:?}", requested_predicates, err)))?;
 
+        for predicate in requested_predicates.iter() {
+            _validate_attr_restrictions(&predicate.restrictions)?;
+        }
+
         self.requested_predicates = requested_predicates
             .into_iter()
             .enumerate()
@@ -376,6 +424,30 @@ impl ProofRequestData {
         Ok(self)
     }
 
+    /// Copies the request wide revocation interval to the attributes and predicates which do not define their own one.
+    pub fn propagate_not_revoked_interval(mut self) -> ProofRequestData {
+        if let Some(ref non_revoked) = self.non_revoked {
+            for attribute in self.requested_attributes.values_mut() {
+                attribute.non_revoked = attribute.non_revoked.take().or(Some(non_revoked.clone()));
+            }
+            for predicate in self.requested_predicates.values_mut() {
+                predicate.non_revoked = predicate.non_revoked.take().or(Some(non_revoked.clone()));
+            }
+        }
+        self
+    }
+
+    pub fn validate_not_revoked_intervals(&self, now: u64) -> VcxResult<()> {
+        let intervals = self.non_revoked.iter()
+            .chain(self.requested_attributes.values().filter_map(|attribute| attribute.non_revoked.as_ref()))
+            .chain(self.requested_predicates.values().filter_map(|predicate| predicate.non_revoked.as_ref()));
+
+        for interval in intervals {
+            interval.validate(now)?;
+        }
+        Ok(())
+    }
+
     pub fn set_format_version_for_did(mut self, my_did: &str, remote_did: &str) -> VcxResult<ProofRequestData> {
         if my_did.is_empty() || remote_did.is_empty() {
             return Err(VcxError::from(VcxErrorKind::InvalidDid));
@@ -385,7 +457,7 @@ impl ProofRequestData {
             let proof_request_json = serde_json::to_string(&self)
                 .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot serialize ProofRequestData: {:?}", err)))?;
 
-            let proof_request_json = anoncreds::libindy_to_unqualified(&proof_request_json)?;
+            let proof_request_json = qualifier::unqualify(&proof_request_json);
 
             self = serde_json::from_str(&proof_request_json)
                 .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize ProofRequestData: {:?}", err)))?;
@@ -428,7 +500,7 @@ impl Default for ProofRequestVersion {
 mod tests {
     use messages::proof_request;
     use utils::constants::{REQUESTED_ATTRS, REQUESTED_PREDICATES};
-    use utils::devsetup::SetupDefaults;
+    use utils::devsetup::{SetupDefaults, SetupIndyMocks};
 
     use super::*;
 
@@ -556,4 +628,34 @@ mod tests {
 
         let _proof_req: ProofRequestData = serde_json::from_str(::utils::constants::INDY_PROOF_REQ_JSON).unwrap();
     }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_not_revoked_interval_is_propagated_and_validated() {
+        let _setup = SetupIndyMocks::init();
+
+        let requested_attrs = json!([
+            {"name": "name", "restrictions": [{"cred_def_id": "V4SGRU86Z58d6TV7PBUe6f:3:CL:1015:tag"}]},
+            {"name": "age", "non_revoked": {"from": 10, "to": 20}}
+        ]).to_string();
+        let request = ProofRequestData::create()
+            .set_requested_attributes(requested_attrs).unwrap()
+            .set_requested_predicates(json!([{"name": "height", "p_type": ">=", "p_value": 10}]).to_string()).unwrap()
+            .set_not_revoked_interval(json!({"to": 1000}).to_string()).unwrap()
+            .propagate_not_revoked_interval();
+
+        let global = Some(NonRevokedInterval { from: None, to: Some(1000) });
+        assert_eq!(request.requested_attributes["attribute_0"].non_revoked, global);
+        assert_eq!(request.requested_attributes["attribute_1"].non_revoked, Some(NonRevokedInterval { from: Some(10), to: Some(20) }));
+        assert_eq!(request.requested_predicates["predicate_0"].non_revoked, global);
+
+        request.validate_not_revoked_intervals(1000).unwrap();
+        // end of interval may lie in the future, its beginning only within the clock skew
+        request.validate_not_revoked_intervals(100).unwrap();
+        NonRevokedInterval { from: Some(700), to: None }.validate(100).unwrap();
+        assert_eq!(NonRevokedInterval { from: Some(701), to: None }.validate(100).unwrap_err().kind(), VcxErrorKind::InvalidRevocationTimestamp);
+        assert_eq!(NonRevokedInterval { from: Some(20), to: Some(10) }.validate(1000).unwrap_err().kind(), VcxErrorKind::InvalidRevocationTimestamp);
+        // timestamps in milliseconds
+        assert_eq!(NonRevokedInterval { from: None, to: Some(1_600_000_000_000) }.validate(1_600_000_000).unwrap_err().kind(), VcxErrorKind::InvalidRevocationTimestamp);
+    }
 }
@@ -0,0 +1,199 @@
+use aries::messages::a2a::A2AMessage;
+use error::prelude::*;
+use utils::events;
+use utils::json::check_json_limits;
+use utils::libindy::crypto;
+
+// payloads are forwarded by the agency and possibly by mediators of the device
+const MAX_FORWARD_DEPTH: usize = 4;
+
+#[derive(Deserialize, Debug)]
+struct UnpackedPayload {
+    message: String,
+    recipient_verkey: String,
+    sender_verkey: Option<String>,
+}
+
+/// Message delivered by push notification together with what is needed to preview it and to pass it
+/// to the object it belongs to.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct PushPayload {
+    pub message: A2AMessage,
+    /// `{family}/{version}/{name}` of the message
+    pub message_type: Option<String>,
+    pub thread_id: Option<String>,
+    /// Key of the counterparty, none for anoncrypted message.
+    pub sender_verkey: Option<String>,
+    pub recipient_verkey: String,
+    /// Connection the message was sent to, by its pairwise key.
+    pub connection_handle: Option<u32>,
+    /// Type of the protocol object taking part in the thread of the message, as used by `vcx_get_handle_by_thread_id`.
+    /// Resolved only for message authcrypted by the counterparty of `connection_handle` to the object of that connection.
+    pub object_type: Option<String>,
+    pub handle: Option<u32>,
+}
+
+impl PushPayload {
+    pub fn to_string(&self) -> VcxResult<String> {
+        ::serde_json::to_string(self)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize push payload: {:?}", err)))
+    }
+}
+
+fn _unpack(payload: &[u8]) -> VcxResult<UnpackedPayload> {
+    let unpacked = crypto::unpack_message(payload)?;
+    ::serde_json::from_slice(&unpacked)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize unpacked push payload: {}", err)))
+}
+
+// object of the thread which belongs to `connection_handle`
+fn _find_object(thread_id: &str, connection_handle: u32) -> Option<(&'static str, u32)> {
+    let finders: [(&'static str, fn(&str) -> VcxResult<u32>, fn(u32) -> VcxResult<Option<u32>>); 4] = [
+        (events::CREDENTIAL, ::credential::get_handle_by_thread_id, ::credential::get_connection_handle),
+        (events::DISCLOSED_PROOF, ::disclosed_proof::get_handle_by_thread_id, ::disclosed_proof::get_connection_handle),
+        (events::ISSUER_CREDENTIAL, ::issuer_credential::get_handle_by_thread_id, ::issuer_credential::get_connection_handle),
+        (events::PROOF, ::proof::get_handle_by_thread_id, ::proof::get_connection_handle),
+    ];
+    finders.iter()
+        .filter_map(|&(object_type, find, get_connection_handle)| {
+            find(thread_id).ok()
+                .filter(|handle| get_connection_handle(*handle).ok() == Some(Some(connection_handle)))
+                .map(|handle| (object_type, handle))
+        })
+        .next()
+}
+
+// connection the message was sent to by its counterparty, by the recipient key and the authcrypt sender
+fn _find_connection(recipient_verkey: &str, sender_verkey: Option<&str>) -> Option<u32> {
+    let sender_verkey = sender_verkey?;
+    ::connection::find_handle_by_verkey(recipient_verkey)
+        .filter(|handle| ::connection::get_their_pw_verkey(*handle).map(|verkey| verkey == sender_verkey).unwrap_or(false))
+}
+
+///
+/// Decrypts payload of agency push notification (FCM, APNS) packed by anoncrypt or authcrypt,
+/// forward messages wrapping it are unpacked as well. The wallet has to be opened.
+/// Object of the message thread is resolved only when the message was authcrypted by the counterparty
+/// of the connection it was sent to and the object belongs to that connection, so that anyone knowing
+/// a thread id cannot point the app at the object.
+///
+pub fn decrypt_push_payload(payload: &[u8]) -> VcxResult<PushPayload> {
+    trace!("decrypt_push_payload >>> payload length: {}", payload.len());
+
+    let mut unpacked = _unpack(payload)?;
+    let mut depth = 0;
+    let message = loop {
+        check_json_limits(unpacked.message.as_bytes())?;
+        let message: A2AMessage = ::serde_json::from_str(&unpacked.message)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize A2A message: {}", err)))?;
+
+        match message {
+            A2AMessage::Forward(ref forward) if depth < MAX_FORWARD_DEPTH => {
+                let inner = ::serde_json::to_vec(&forward.msg)
+                    .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize forwarded message: {}", err)))?;
+                unpacked = _unpack(&inner)?;
+                depth += 1;
+            }
+            A2AMessage::Forward(_) => {
+                return Err(VcxError::from_msg(VcxErrorKind::InvalidMessagePack, "Push payload is forwarded too many times"));
+            }
+            message => break message
+        }
+    };
+
+    let thread_id = message.thread_id();
+    let object = match (thread_id.as_ref(), _find_connection(&unpacked.recipient_verkey, unpacked.sender_verkey.as_ref().map(String::as_str))) {
+        (Some(thread_id), Some(connection_handle)) => _find_object(thread_id, connection_handle),
+        _ => None
+    };
+
+    let payload = PushPayload {
+        message_type: message.type_name(),
+        thread_id,
+        sender_verkey: unpacked.sender_verkey,
+        connection_handle: ::connection::find_handle_by_verkey(&unpacked.recipient_verkey),
+        recipient_verkey: unpacked.recipient_verkey,
+        object_type: object.map(|(object_type, _)| object_type.to_string()),
+        handle: object.map(|(_, handle)| handle),
+        message,
+    };
+
+    debug!("decrypt_push_payload <<< type: {:?}, thread_id: {:?}, handle: {:?}", payload.message_type, payload.thread_id, payload.handle);
+    Ok(payload)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use aries::messages::issuance::credential_offer::tests::_credential_offer;
+    use utils::devsetup::{SetupAriesMocks, SetupStrictAriesMocks};
+    use utils::mockdata::mockdata_credex::ARIES_CREDENTIAL_OFFER;
+
+    use super::*;
+
+    fn _unpacked(message: &A2AMessage, recipient_verkey: &str, sender_verkey: Option<&str>) -> Vec<u8> {
+        json!({
+            "message": json!(message).to_string(),
+            "recipient_verkey": recipient_verkey,
+            "sender_verkey": sender_verkey,
+        }).to_string().into_bytes()
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_decrypt_push_payload_finds_object_of_sending_connection() {
+        let _setup = SetupStrictAriesMocks::init();
+
+        let connection_handle = ::connection::tests::build_test_connection_inviter_requested();
+        let recipient_verkey = ::connection::get_pw_verkey(connection_handle).unwrap();
+        let sender_verkey = ::connection::get_their_pw_verkey(connection_handle).unwrap();
+
+        let handle = ::credential::credential_create_with_offer("test_decrypt_push_payload", ARIES_CREDENTIAL_OFFER).unwrap();
+        ::credential::send_credential_request(handle, connection_handle).unwrap();
+        let thread_id = ::credential::get_thread_id(handle).unwrap();
+
+        let message = A2AMessage::CredentialOffer(_credential_offer().set_thread_id(&thread_id));
+        let payload = decrypt_push_payload(&_unpacked(&message, &recipient_verkey, Some(&sender_verkey))).unwrap();
+
+        assert_eq!(payload.message, message);
+        assert_eq!(payload.message_type, Some("issue-credential/1.0/offer-credential".to_string()));
+        assert_eq!(payload.thread_id, Some(thread_id));
+        assert_eq!(payload.sender_verkey, Some(sender_verkey));
+        assert_eq!(payload.connection_handle, Some(connection_handle));
+        assert_eq!(payload.object_type, Some(events::CREDENTIAL.to_string()));
+        assert_eq!(payload.handle, Some(handle));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_decrypt_push_payload_does_not_resolve_object_for_other_sender() {
+        let _setup = SetupStrictAriesMocks::init();
+
+        let connection_handle = ::connection::tests::build_test_connection_inviter_requested();
+        let recipient_verkey = ::connection::get_pw_verkey(connection_handle).unwrap();
+
+        let handle = ::credential::credential_create_with_offer("test_decrypt_push_payload", ARIES_CREDENTIAL_OFFER).unwrap();
+        ::credential::send_credential_request(handle, connection_handle).unwrap();
+        let message = A2AMessage::CredentialOffer(_credential_offer().set_thread_id(&::credential::get_thread_id(handle).unwrap()));
+
+        for sender_verkey in vec![None, Some("7y118tRW2EMJn18qs9MY5NJWYW2PLwV5QpaLyfoLHtgF")] {
+            let payload = decrypt_push_payload(&_unpacked(&message, &recipient_verkey, sender_verkey)).unwrap();
+            assert_eq!(payload.connection_handle, Some(connection_handle));
+            assert_eq!(payload.object_type, None);
+            assert_eq!(payload.handle, None);
+        }
+
+        // object of another connection
+        let payload = decrypt_push_payload(&_unpacked(&message, "GJ1SzoWzavQYfNL9XkaJdrQejfztN4XqdsiV4ct3LXKL", Some("7y118tRW2EMJn18qs9MY5NJWYW2PLwV5QpaLyfoLHtgF"))).unwrap();
+        assert_eq!(payload.connection_handle, None);
+        assert_eq!(payload.handle, None);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_decrypt_push_payload_fails_for_invalid_payload() {
+        let _setup = SetupAriesMocks::init();
+
+        assert_eq!(decrypt_push_payload(b"{}").unwrap_err().kind(), VcxErrorKind::InvalidJson);
+        assert_eq!(decrypt_push_payload(br#"{"message":"not a message","recipient_verkey":"key"}"#).unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+}
@@ -4,8 +4,8 @@ use serde_json::Value;
 use error::prelude::*;
 use messages::{A2AMessage, A2AMessageKinds, A2AMessageV2, delete_connection, GeneralMessage, parse_response_from_agency, prepare_message_for_agent};
 use messages::message_type::MessageTypes;
+use outbound_queue;
 use settings;
-use utils::httpclient;
 
 #[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -91,9 +91,10 @@ impl DeleteConnectionBuilder {
 
         let data = self.prepare_request()?;
 
-        let response = httpclient::post_u8(&data)?;
-
-        self.parse_response(&response)
+        match outbound_queue::post_to_agency_or_enqueue(&data)? {
+            Some(response) => self.parse_response(&response),
+            None => Ok(())
+        }
     }
 
     fn parse_response(&self, response: &Vec<u8>) -> VcxResult<()> {
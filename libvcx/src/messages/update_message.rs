@@ -1,8 +1,10 @@
 use error::{VcxError, VcxErrorKind, VcxResult};
-use messages::{A2AMessage, A2AMessageKinds, A2AMessageV2, MessageStatusCode, parse_response_from_agency, prepare_message_for_agency};
+use messages::{A2AMessage, A2AMessageKinds, A2AMessageV2, get_messages, MessageStatusCode, parse_response_from_agency, prepare_message_for_agency};
+use messages::get_message::MessageByConnection;
 use messages::message_type::MessageTypes;
+use outbound_queue;
 use settings;
-use utils::{constants, httpclient};
+use utils::constants;
 use utils::httpclient::AgencyMock;
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
@@ -76,9 +78,11 @@ impl UpdateMessageStatusByConnectionsBuilder {
 
         let data = self.prepare_request()?;
 
-        let response = httpclient::post_u8(&data)?;
-
-        self.parse_response(&response)
+        // status update is delivered later when the agency is unreachable, so that handled messages are not handled again
+        match outbound_queue::post_to_agency_or_enqueue(&data)? {
+            Some(response) => self.parse_response(&response),
+            None => Ok(())
+        }
     }
 
     fn prepare_request(&mut self) -> VcxResult<Vec<u8>> {
@@ -142,6 +146,53 @@ pub fn update_messages(status_code: MessageStatusCode, uids_by_conns: Vec<UIDsBy
         .send_secure()
 }
 
+///
+/// Sets status of messages identified only by their uids, connections they belong to are looked up at agency.
+/// Any status code (`MS-101` to `MS-107`) can be set, e.g. to mark messages reviewed or rejected.
+///
+pub fn update_messages_status(uids: Vec<String>, status_code: MessageStatusCode) -> VcxResult<()> {
+    trace!("update_messages_status >>> uids: {:?}, status_code: {:?}", uids, status_code);
+
+    if uids.is_empty() {
+        return Ok(());
+    }
+
+    if settings::agency_mocks_enabled() {
+        trace!("update_messages_status >>> agency mocks enabled, returning empty response");
+        return Ok(());
+    };
+
+    let downloaded = get_messages()
+        .uid(Some(uids.clone()))?
+        .include_edge_payload("Y")?
+        .version(&Some(settings::get_protocol_type()))?
+        .download_messages()?;
+
+    let uids_by_conns = _group_uids_by_connection(&uids, downloaded)?;
+
+    update_messages(status_code, uids_by_conns)
+}
+
+fn _group_uids_by_connection(uids: &[String], downloaded: Vec<MessageByConnection>) -> VcxResult<Vec<UIDsByConn>> {
+    let uids_by_conns: Vec<UIDsByConn> = downloaded.into_iter()
+        .map(|connection| UIDsByConn {
+            pairwise_did: connection.pairwise_did,
+            uids: connection.msgs.into_iter().map(|message| message.uid).filter(|uid| uids.contains(uid)).collect(),
+        })
+        .filter(|connection| !connection.uids.is_empty())
+        .collect();
+
+    let unknown: Vec<&String> = uids.iter()
+        .filter(|uid| !uids_by_conns.iter().any(|connection| connection.uids.contains(uid)))
+        .collect();
+
+    if !unknown.is_empty() {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidMessages, format!("Agency does not know messages {:?}", unknown)));
+    }
+
+    Ok(uids_by_conns)
+}
+
 #[cfg(test)]
 mod tests {
     #[cfg(any(feature = "agency_pool_tests"))]
@@ -152,7 +203,9 @@ mod tests {
     use connection::send_generic_message;
     use messages::get_message::download_messages;
     use messages::MessageStatusCode;
-    use messages::update_message::{UIDsByConn, update_agency_messages, UpdateMessageStatusByConnectionsBuilder};
+    use messages::get_message::{Message, MessageByConnection};
+    use messages::update_message::{_group_uids_by_connection, UIDsByConn, update_agency_messages, UpdateMessageStatusByConnectionsBuilder};
+    use error::VcxErrorKind;
     use utils::devsetup::{SetupAriesMocks, SetupLibraryAgencyV2};
     use utils::httpclient::AgencyMockDecrypted;
     use utils::mockdata::mockdata_agency::AGENCY_MSG_STATUS_UPDATED_BY_CONNS;
@@ -165,6 +218,29 @@ mod tests {
         UpdateMessageStatusByConnectionsBuilder::create().parse_response(&Vec::from("<something_ecrypted>")).unwrap();
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_group_uids_by_connection() {
+        let _setup = SetupAriesMocks::init();
+
+        let message = |uid: &str| -> Message {
+            ::serde_json::from_value(json!({"statusCode": "MS-103", "payload": null, "senderDID": "", "uid": uid, "type": "aries", "refMsgId": null})).unwrap()
+        };
+        let downloaded = vec![
+            MessageByConnection { pairwise_did: "did1".to_string(), msgs: vec![message("uid1"), message("uid2")] },
+            MessageByConnection { pairwise_did: "did2".to_string(), msgs: vec![message("uid3")] },
+        ];
+
+        let grouped = _group_uids_by_connection(&["uid1".to_string(), "uid3".to_string()], downloaded.clone()).unwrap();
+        assert_eq!(grouped, vec![
+            UIDsByConn { pairwise_did: "did1".to_string(), uids: vec!["uid1".to_string()] },
+            UIDsByConn { pairwise_did: "did2".to_string(), uids: vec!["uid3".to_string()] },
+        ]);
+
+        let err = _group_uids_by_connection(&["uid1".to_string(), "uid4".to_string()], downloaded).unwrap_err();
+        assert_eq!(err.kind(), VcxErrorKind::InvalidMessages);
+    }
+
     #[cfg(feature = "agency_pool_tests")]
     #[test]
     fn test_update_agency_messages() {
@@ -16,8 +16,8 @@ pub struct UpdateProfileDataBuilder {
 
 #[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 pub struct ConfigOption {
-    name: String,
-    value: String,
+    pub name: String,
+    pub value: String,
 }
 
 #[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
@@ -33,6 +33,30 @@ pub struct UpdateConfigsResponse {
     msg_type: MessageTypes,
 }
 
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
+pub struct GetConfigs {
+    #[serde(rename = "@type")]
+    msg_type: MessageTypes,
+    configs: Vec<String>,
+}
+
+impl GetConfigs {
+    pub fn build(names: &[&str]) -> GetConfigs {
+        GetConfigs {
+            msg_type: MessageTypes::build(A2AMessageKinds::GetConfigs),
+            configs: names.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+pub struct GetConfigsResponse {
+    #[serde(rename = "@type")]
+    msg_type: MessageTypes,
+    #[serde(default)]
+    pub configs: Vec<ConfigOption>,
+}
+
 impl UpdateProfileDataBuilder {
     pub fn create() -> UpdateProfileDataBuilder {
         trace!("UpdateProfileData::create_message >>>");
@@ -97,7 +121,7 @@ impl UpdateProfileDataBuilder {
 
         let data = self.prepare_request()?;
 
-        let response = httpclient::post_u8(&data)?;
+        let response = httpclient::post_u8_idempotent(&data)?;
 
         self.parse_response(response)
     }
@@ -4,7 +4,9 @@ extern crate rust_base58;
 use url::Url;
 
 use error::prelude::*;
+use settings;
 use settings::Actors;
+use utils::libindy::pool::NetworkConfig;
 use utils::qualifier;
 
 use self::openssl::bn::BigNum;
@@ -66,6 +68,32 @@ pub fn validate_actors(actors: &str) -> VcxResult<Vec<Actors>> {
         .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Invalid actors: {:?}", err)))
 }
 
+pub fn validate_disabled_protocols(protocols: &str) -> VcxResult<Vec<String>> {
+    ::serde_json::from_str(&protocols)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Invalid disabled protocols: {:?}", err)))
+}
+
+pub fn validate_networks(networks: &str) -> VcxResult<Vec<NetworkConfig>> {
+    ::serde_json::from_str(&networks)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidConfiguration, format!("Invalid networks: {:?}", err)))
+}
+
+pub fn validate_did_method(method: &str) -> VcxResult<String> {
+    if !method.is_empty() && method.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()) {
+        Ok(method.to_string())
+    } else {
+        Err(VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Invalid DID method: {}", method)))
+    }
+}
+
+pub fn validate_pairwise_did_method(method: &str) -> VcxResult<String> {
+    if method == settings::PAIRWISE_DID_METHOD_SOV || method == settings::PAIRWISE_DID_METHOD_PEER {
+        Ok(method.to_string())
+    } else {
+        Err(VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Unsupported pairwise DID method: {}", method)))
+    }
+}
+
 pub fn validate_phone_number(p_num: &str) -> VcxResult<String> {
     Ok(String::from(p_num))
 }
@@ -170,4 +198,15 @@ mod tests {
 
         assert_eq!(validate_payment_method("").unwrap_err().kind(), VcxErrorKind::MissingPaymentMethod);
     }
-}
\ No newline at end of file
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_did_method_validation() {
+        let _setup = SetupDefaults::init();
+
+        validate_did_method("sov").unwrap();
+        validate_did_method("indy").unwrap();
+        assert_eq!(validate_did_method("").unwrap_err().kind(), VcxErrorKind::InvalidOption);
+        assert_eq!(validate_did_method("did:sov").unwrap_err().kind(), VcxErrorKind::InvalidOption);
+    }
+}
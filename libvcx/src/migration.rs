@@ -0,0 +1,327 @@
+use serde_json;
+use serde_json::Value;
+
+use {connection, credential};
+use aries::handlers::connection::agent_info::AgentInfo;
+use aries::handlers::connection::connection::{Connection, SmConnectionState};
+use aries::handlers::connection::invitee::state_machine::InviteeState;
+use aries::handlers::connection::inviter::state_machine::InviterState;
+use aries::handlers::issuance::holder::holder::Holder;
+use aries::messages::connection::did_doc::DidDoc;
+use aries::messages::connection::did_key;
+use aries::messages::connection::invite::Invitation;
+use api::VcxStateType;
+use error::prelude::*;
+use utils::constants::V3_OBJECT_SERIALIZE_VERSION;
+use utils::metadata::{self, Metadata};
+
+const CONNECTION_SERIALIZE_VERSION: &str = "4.0";
+
+#[derive(Debug, PartialEq)]
+enum ObjectType {
+    Connection,
+    Credential,
+    IssuerCredential,
+    Proof,
+    DisclosedProof,
+}
+
+impl ObjectType {
+    fn name(&self) -> &'static str {
+        match self {
+            ObjectType::Connection => "Connection",
+            ObjectType::Credential => "Credential",
+            ObjectType::IssuerCredential => "IssuerCredential",
+            ObjectType::Proof => "Proof",
+            ObjectType::DisclosedProof => "DisclosedProof",
+        }
+    }
+
+    fn serialize_version(&self) -> &'static str {
+        match self {
+            ObjectType::Connection => CONNECTION_SERIALIZE_VERSION,
+            _ => V3_OBJECT_SERIALIZE_VERSION,
+        }
+    }
+}
+
+///
+/// Converts object serialized by an older library version into the aries serialization format, so that it
+/// can be loaded by `from_string` of the respective module.
+///
+/// `target_version` must be the current format of the object type: "4.0" for connections and "2.0" for
+/// credentials and proofs. Objects already in that format are returned as they are (connections are re-serialized).
+///
+/// Objects of the legacy proprietary protocol are converted only where the aries state can be derived from them:
+/// connections are marked by `metadata::REESTABLISH` to be established again (accepted invitee connections keep
+/// the peer's keys and endpoint, others start over with fresh keys) and received credentials become finished
+/// holder objects referencing the credential in the wallet.
+/// Exchanges in progress and issuer or verifier side objects cannot be continued over aries and are rejected
+/// with `ActionNotSupported`.
+///
+pub fn convert_object(serialized: &str, target_version: &str) -> VcxResult<String> {
+    trace!("convert_object >>> target_version: {}", target_version);
+
+    let object: Value = serde_json::from_str(serialized)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize object: {:?}", err)))?;
+
+    let object_type = _object_type(&object)?;
+
+    if object_type.serialize_version() != target_version {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidOption,
+                                      format!("{} cannot be converted into version {:?}, supported version is {:?}",
+                                              object_type.name(), target_version, object_type.serialize_version())));
+    }
+
+    match object_type {
+        ObjectType::Connection => connection::upgrade_serialized(serialized),
+        _ if object["version"].as_str() == Some(V3_OBJECT_SERIALIZE_VERSION) => Ok(serialized.to_string()),
+        ObjectType::Credential => _convert_proprietary_credential(&object["data"]),
+        _ => Err(VcxError::from_msg(VcxErrorKind::ActionNotSupported,
+                                    format!("{} of the proprietary protocol cannot be converted, the exchange has to be done again over aries", object_type.name())))
+    }
+}
+
+fn _object_type(object: &Value) -> VcxResult<ObjectType> {
+    let data = &object["data"];
+
+    let object_type =
+        if data["holder_sm"].is_object() || data["credential_offer"].is_string() || data["credential_offer"].is_object() {
+            ObjectType::Credential
+        } else if data["issuer_sm"].is_object() || data["credential_attributes"].is_string() {
+            ObjectType::IssuerCredential
+        } else if data["verifier_sm"].is_object() || data["requested_attrs"].is_string() {
+            ObjectType::Proof
+        } else if data["prover_sm"].is_object() || data["link_secret_alias"].is_string() {
+            ObjectType::DisclosedProof
+        } else if data["pw_did"].is_string() {
+            ObjectType::Connection
+        } else {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidJson, "Cannot recognize type of the serialized object"));
+        };
+
+    Ok(object_type)
+}
+
+fn _convert_proprietary_credential(data: &Value) -> VcxResult<String> {
+    let cred_id = data["cred_id"].as_str().unwrap_or_default();
+
+    if data["state"].as_u64() != Some(VcxStateType::VcxStateAccepted as u64) || cred_id.is_empty() {
+        return Err(VcxError::from_msg(VcxErrorKind::ActionNotSupported,
+                                      "Credential of the proprietary protocol can be converted only once it is stored in the wallet"));
+    }
+
+    let source_id = data["source_id"].as_str().unwrap_or_default();
+    let thread_id = data["msg_uid"].as_str().unwrap_or_default();
+
+    credential::serialize(&Holder::from_stored_credential(source_id, thread_id, cred_id))
+}
+
+/// Whether the connection was serialized by the legacy proprietary protocol implementation.
+pub fn is_proprietary_connection(object: &Value) -> bool {
+    object["version"].as_str() == Some("1.0") && object["data"]["pw_verkey"].is_string()
+}
+
+///
+/// Builds aries connection from `data` of the legacy proprietary connection.
+///
+/// The peer still speaks the proprietary protocol, so the connection is marked by `metadata::REESTABLISH`.
+/// The connection gets the aries state equivalent to the proprietary one:
+/// - sent invitation (OfferSent) becomes inviter in Invited state keeping the pairwise keys and agent the invitation refers to,
+/// - received invitation (RequestReceived) becomes invitee in Invited state holding the invitation,
+/// - accepted connection where we are the invitee keeps the pairwise keys and agent and gets completed with
+///   the inviter's keys and endpoint, so that the peer stays known,
+/// - initialized connection starts over with fresh keys.
+///
+pub fn convert_proprietary_connection(data: &Value) -> VcxResult<Connection> {
+    let field = |name: &str| data[name].as_str().unwrap_or_default().to_string();
+
+    let source_id = field("source_id");
+    let mut reestablish = Metadata::new();
+    reestablish.insert(metadata::REESTABLISH.to_string(), "true".to_string());
+
+    let agent_info = AgentInfo {
+        pw_did: field("pw_did"),
+        pw_vk: field("pw_verkey"),
+        agent_did: field("agent_did"),
+        agent_vk: field("agent_vk"),
+        endpoint: None,
+    };
+    let invite_detail = &data["invite_detail"];
+
+    let connection = match data["state"].as_u64() {
+        Some(state) if state == VcxStateType::VcxStateOfferSent as u64 => {
+            let invitation = Invitation::create()
+                .set_label(source_id.clone())
+                .set_service_endpoint(agent_info.agency_endpoint()?)
+                .set_recipient_keys(did_key::encode_outbound_keys(agent_info.recipient_keys())?)
+                .set_routing_keys(did_key::encode_outbound_keys(agent_info.routing_keys()?)?);
+            Connection::from_parts(source_id, agent_info, SmConnectionState::Inviter(InviterState::invited(invitation)))
+        }
+        Some(state) if state == VcxStateType::VcxStateRequestReceived as u64 => {
+            let mut invitation = Invitation::create();
+            if let Some(id) = invite_detail["connReqId"].as_str().filter(|id| !id.is_empty()) {
+                invitation = invitation.set_id(id.to_string());
+            }
+            let invitation = invitation
+                .set_label(invite_detail["senderDetail"]["name"].as_str().unwrap_or_default().to_string())
+                .set_service_endpoint(invite_detail["senderAgencyDetail"]["endpoint"].as_str().unwrap_or_default().to_string())
+                .set_recipient_keys(vec![invite_detail["senderDetail"]["verKey"].as_str().unwrap_or_default().to_string()])
+                .set_routing_keys(_legacy_routing_keys(invite_detail));
+            Connection::create_with_invite(&source_id, invitation)?
+        }
+        Some(state) if state == VcxStateType::VcxStateAccepted as u64 => {
+            let their_pw_did = field("their_pw_did");
+
+            if their_pw_did.is_empty() || invite_detail["senderDetail"]["DID"].as_str() != Some(their_pw_did.as_str()) {
+                return Err(VcxError::from_msg(VcxErrorKind::ActionNotSupported,
+                                              "Accepted inviter connection of the proprietary protocol cannot be converted, endpoint of the invitee is unknown"));
+            }
+
+            let mut did_doc = DidDoc::default();
+            did_doc.set_id(their_pw_did);
+            did_doc.set_service_endpoint(invite_detail["senderAgencyDetail"]["endpoint"].as_str().unwrap_or_default().to_string());
+            did_doc.set_keys(vec![field("their_pw_verkey")], _legacy_routing_keys(invite_detail));
+
+            Connection::from_parts(source_id, agent_info, SmConnectionState::Invitee(InviteeState::completed(did_doc)))
+        }
+        _ => Connection::create(&source_id)
+    };
+
+    Ok(connection.with_metadata(reestablish))
+}
+
+fn _legacy_routing_keys(invite_detail: &Value) -> Vec<String> {
+    [&invite_detail["senderDetail"]["agentKeyDlgProof"]["agentDelegatedKey"], &invite_detail["senderAgencyDetail"]["verKey"]]
+        .iter()
+        .filter_map(|key| key.as_str())
+        .filter(|key| !key.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use utils::constants::{DEFAULT_CONNECTION, PROOF_OFFER_SENT};
+    use utils::devsetup::*;
+
+    fn _accepted_invitee_connection() -> String {
+        json!({
+            "version": "1.0",
+            "data": {
+                "source_id": "legacy",
+                "pw_did": "8XFh8yBzrpJQmNyZzgoTqB",
+                "pw_verkey": "EkVTa7SCJ5SntpYyX7CSb2pcBhiVGT9kWSagA8a9T69A",
+                "state": 4,
+                "invite_detail": {
+                    "senderDetail": {
+                        "DID": "KC6NKcpXcpVnpjL8uKH3tV",
+                        "verKey": "Av4ZDAKgpniTnxLukLQFZ2DbdNqPguiWcDa2zsPn1Kp4",
+                        "agentKeyDlgProof": { "agentDID": "", "agentDelegatedKey": "BDSmVkzxRYGE4HKyMKxd1H", "signature": "" }
+                    },
+                    "senderAgencyDetail": { "DID": "VsKV7grR1BUE29mG2Fm2kX", "verKey": "Hezce2UWMZ3wUhVkh2LfKSs8nDzWwzs2Win7EzNN3YaR", "endpoint": "http://localhost:8080" }
+                },
+                "agent_did": "U5LXs4U7P9msh647kToezy",
+                "agent_vk": "FktSZg8idAVzyQZrdUppK6FTrfAzW3wWVzAjJAfdUvJq",
+                "their_pw_did": "KC6NKcpXcpVnpjL8uKH3tV",
+                "their_pw_verkey": "Av4ZDAKgpniTnxLukLQFZ2DbdNqPguiWcDa2zsPn1Kp4"
+            }
+        }).to_string()
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_convert_proprietary_connection_starts_over() {
+        let _setup = SetupAriesMocks::init();
+
+        let converted = convert_object(DEFAULT_CONNECTION, "4.0").unwrap();
+        let object: Value = serde_json::from_str(&converted).unwrap();
+        assert_eq!(object["version"], "4.0");
+        assert_eq!(object["source_id"], "test_vcx_connection_deserialialize_succeeds");
+        assert_ne!(object["data"]["pw_vk"], "EkVTa7SCJ5SntpYyX7CSb2pcBhiVGT9kWSagA8a9T69A");
+
+        let handle = connection::from_string(&converted).unwrap();
+        assert_eq!(connection::get_state(handle), VcxStateType::VcxStateInitialized as u32);
+        assert_eq!(serde_json::from_str::<Value>(&connection::get_metadata(handle).unwrap()).unwrap()[metadata::REESTABLISH], "true");
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_convert_proprietary_accepted_invitee_connection() {
+        let _setup = SetupAriesMocks::init();
+
+        let converted = convert_object(&_accepted_invitee_connection(), "4.0").unwrap();
+
+        let handle = connection::from_string(&converted).unwrap();
+        assert_eq!(connection::get_state(handle), VcxStateType::VcxStateAccepted as u32);
+        assert_eq!(connection::get_their_pw_verkey(handle).unwrap(), "Av4ZDAKgpniTnxLukLQFZ2DbdNqPguiWcDa2zsPn1Kp4");
+        assert_eq!(serde_json::from_str::<Value>(&connection::get_metadata(handle).unwrap()).unwrap()[metadata::REESTABLISH], "true");
+
+        // loading the legacy connection directly gives the same result
+        let handle = connection::from_string(&_accepted_invitee_connection()).unwrap();
+        assert_eq!(connection::get_state(handle), VcxStateType::VcxStateAccepted as u32);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_convert_proprietary_connection_keeps_invitation() {
+        let _setup = SetupAriesMocks::init();
+
+        let mut object: Value = serde_json::from_str(&_accepted_invitee_connection()).unwrap();
+        object["data"]["state"] = json!(VcxStateType::VcxStateOfferSent as u32);
+
+        let handle = connection::from_string(&object.to_string()).unwrap();
+        assert_eq!(connection::get_state(handle), VcxStateType::VcxStateOfferSent as u32);
+        assert_eq!(connection::get_pw_verkey(handle).unwrap(), "EkVTa7SCJ5SntpYyX7CSb2pcBhiVGT9kWSagA8a9T69A");
+
+        object["data"]["state"] = json!(VcxStateType::VcxStateRequestReceived as u32);
+
+        let handle = connection::from_string(&object.to_string()).unwrap();
+        assert_eq!(connection::get_state(handle), VcxStateType::VcxStateOfferSent as u32);
+        assert_eq!(connection::get_their_pw_verkey(handle).unwrap(), "Av4ZDAKgpniTnxLukLQFZ2DbdNqPguiWcDa2zsPn1Kp4");
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_strict_aries_requires_conversion_of_proprietary_connection() {
+        let _setup = SetupAriesMocks::init();
+        ::settings::set_config_value(::settings::CONFIG_PROTOCOL_TYPE, "4.0");
+
+        assert_eq!(connection::from_string(DEFAULT_CONNECTION).unwrap_err().kind(), VcxErrorKind::ActionNotSupported);
+
+        let converted = convert_object(DEFAULT_CONNECTION, "4.0").unwrap();
+        connection::from_string(&converted).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_convert_proprietary_credential() {
+        let _setup = SetupAriesMocks::init();
+
+        let legacy = json!({
+            "version": "1.0",
+            "data": { "source_id": "legacy", "state": 4, "credential_offer": {}, "msg_uid": "thread", "cred_id": "cred_id" }
+        }).to_string();
+
+        let handle = credential::from_string(&convert_object(&legacy, "2.0").unwrap()).unwrap();
+        assert_eq!(credential::get_state(handle).unwrap(), VcxStateType::VcxStateAccepted as u32);
+        assert_eq!(credential::get_source_id(handle).unwrap(), "legacy");
+
+        let pending = json!({
+            "version": "1.0",
+            "data": { "source_id": "legacy", "state": 3, "credential_offer": {}, "msg_uid": "thread", "cred_id": null }
+        }).to_string();
+        assert_eq!(convert_object(&pending, "2.0").unwrap_err().kind(), VcxErrorKind::ActionNotSupported);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_convert_object_rejects_unsupported() {
+        let _setup = SetupAriesMocks::init();
+
+        assert_eq!(convert_object(PROOF_OFFER_SENT, "2.0").unwrap_err().kind(), VcxErrorKind::ActionNotSupported);
+        assert_eq!(convert_object(DEFAULT_CONNECTION, "2.0").unwrap_err().kind(), VcxErrorKind::InvalidOption);
+        assert_eq!(convert_object(r#"{"version":"1.0","data":{}}"#, "2.0").unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+}
@@ -0,0 +1,428 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::Utc;
+use indy::WalletHandle;
+use serde_json;
+
+use api::VcxStateType;
+use error::prelude::*;
+use settings;
+use utils::httpclient::{self, PostFailure, RequestOptions};
+use utils::libindy::wallet;
+use utils::uuid;
+
+static OUTBOUND_MESSAGE_RECORD_TYPE: &str = "VcxOutboundMessage";
+const LIST_PAGE_SIZE: usize = 50;
+// messages queued by older versions have no status, so pending ones are those which did not fail
+static PENDING_QUERY: &str = r#"{"$not": {"status": "failed"}}"#;
+static FAILED_QUERY: &str = r#"{"status": "failed"}"#;
+// rejected messages are kept for a while, so that their threads are reported as rejected after restart
+const REJECTED_MESSAGE_TTL_SECS: i64 = 30 * 24 * 60 * 60;
+
+lazy_static! {
+    static ref LAST_SEQ: Mutex<u64> = Default::default();
+    // delivery status of threads by wallet, so that states are reported without searching the wallet
+    static ref THREADS: Mutex<HashMap<WalletHandle, HashMap<String, ThreadStatus>>> = Default::default();
+}
+
+///
+/// Packed message which could not be delivered to `endpoint`, kept in the wallet until
+/// `flush` delivers it.
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct OutboundMessage {
+    endpoint: String,
+    // base64 of the packed message
+    message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct QueuedMessage {
+    id: String,
+    seq: u64,
+    thread_id: String,
+    failed: bool,
+    message: Option<OutboundMessage>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct ThreadStatus {
+    pending: usize,
+    failed: bool,
+}
+
+///
+/// Posts packed `message` to `endpoint`. If the outbound queue is enabled and the message cannot
+/// be delivered due to network error, it's stored for later delivery instead of failing.
+/// Messages sent over a connection are queued under its pairwise DID `pw_did`, see `purge_connection`.
+///
+pub fn post_or_enqueue(message: &Vec<u8>, endpoint: &str, thread_id: Option<String>, pw_did: Option<&str>) -> VcxResult<()> {
+    _post_or_enqueue(message, endpoint, thread_id, pw_did, &RequestOptions::from_settings()).map(|_| ())
+}
+
+///
+/// Posts packed `message` to the agency like `httpclient::post_u8_idempotent`, for requests which
+/// may be repeated and whose response carries no data. The message is queued on network error
+/// as by `post_or_enqueue`, `None` is returned then.
+///
+pub fn post_to_agency_or_enqueue(message: &Vec<u8>) -> VcxResult<Option<Vec<u8>>> {
+    let endpoint = format!("{}/agency/msg", settings::get_config_value(settings::CONFIG_AGENCY_ENDPOINT)?);
+    _post_or_enqueue(message, &endpoint, None, None, &RequestOptions::idempotent())
+}
+
+fn _post_or_enqueue(message: &Vec<u8>, endpoint: &str, thread_id: Option<String>, pw_did: Option<&str>, options: &RequestOptions) -> VcxResult<Option<Vec<u8>>> {
+    match httpclient::try_post_message(message, endpoint, options) {
+        Ok(response) => Ok(Some(response)),
+        Err(PostFailure::Transient(err)) if settings::is_outbound_queue_enabled() => {
+            warn!("Message to {} could not be delivered ({}), queueing it for later delivery", endpoint, err);
+            enqueue(message, endpoint, thread_id, pw_did).map(|_| None)
+        }
+        Err(failure) => Err(failure.into_error())
+    }
+}
+
+/// Stores packed `message` for later delivery to `endpoint`, returns id of the queued message.
+pub fn enqueue(message: &Vec<u8>, endpoint: &str, thread_id: Option<String>, pw_did: Option<&str>) -> VcxResult<String> {
+    trace!("outbound_queue::enqueue >>> endpoint: {}, thread_id: {:?}, pw_did: {:?}", endpoint, thread_id, pw_did);
+
+    let id = uuid::uuid();
+    let thread_id = thread_id.unwrap_or_default();
+
+    let value = serde_json::to_string(&OutboundMessage { endpoint: endpoint.to_string(), message: ::base64::encode(message) })
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize outbound message: {:?}", err)))?;
+
+    let tags = json!({
+        "endpoint": endpoint,
+        "thread_id": thread_id,
+        "pw_did": pw_did.unwrap_or_default(),
+        "status": "pending",
+        // zero padded, so that the tags are ordered as numbers
+        "seq": format!("{:020}", _next_seq()),
+    });
+
+    wallet::add_record(OUTBOUND_MESSAGE_RECORD_TYPE, &id, &value, Some(&tags.to_string()))?;
+    _update_thread(&thread_id, |status| status.pending += 1);
+    Ok(id)
+}
+
+///
+/// Tries to deliver queued messages in the order they were queued. Messages to endpoints which
+/// are still unreachable stay in the queue. Messages rejected by the receiver are not delivered
+/// again, neither are the following messages of their threads, objects of the threads report
+/// state `VcxStateRejected` then. Returns number of messages left in the queue.
+///
+pub fn flush() -> VcxResult<u32> {
+    trace!("outbound_queue::flush >>>");
+
+    let mut unreachable: Vec<String> = Vec::new();
+    let mut failed_threads = _expire_rejected()?;
+    let mut pending = 0;
+
+    for queued in _search(PENDING_QUERY)? {
+        if !queued.thread_id.is_empty() && failed_threads.contains(&queued.thread_id) {
+            _reject(&queued, "an earlier message of its thread was rejected");
+            continue;
+        }
+
+        let message = match queued.message {
+            Some(ref message) => message,
+            None => {
+                _reject(&queued, "it cannot be decoded");
+                continue;
+            }
+        };
+
+        if unreachable.contains(&message.endpoint) {
+            pending += 1;
+            continue;
+        }
+
+        let packed = match ::base64::decode(&message.message) {
+            Ok(packed) => packed,
+            Err(err) => {
+                _reject(&queued, &format!("it cannot be decoded: {:?}", err));
+                continue;
+            }
+        };
+
+        match httpclient::try_post_message(&packed, &message.endpoint, &RequestOptions::from_settings()) {
+            Ok(_) => {
+                wallet::delete_record(OUTBOUND_MESSAGE_RECORD_TYPE, &queued.id)?;
+                _update_thread(&queued.thread_id, |status| status.pending = status.pending.saturating_sub(1));
+            }
+            Err(PostFailure::Transient(err)) => {
+                warn!("Endpoint {} is still unreachable: {}", message.endpoint, err);
+                unreachable.push(message.endpoint.clone());
+                pending += 1;
+            }
+            Err(PostFailure::Permanent(err)) => {
+                _reject(&queued, &format!("it was rejected by {}: {}", message.endpoint, err));
+                failed_threads.push(queued.thread_id.clone());
+            }
+        }
+    }
+
+    Ok(pending)
+}
+
+// rejected message is kept as failed until it expires, so that its thread is reported as rejected after restart as well
+fn _reject(queued: &QueuedMessage, reason: &str) {
+    error!("Queued message {} is not delivered as {}", queued.id, reason);
+
+    let tags = json!({"status": "failed", "~failed_at": format!("{:020}", Utc::now().timestamp())}).to_string();
+    if let Err(err) = wallet::add_record_tags(OUTBOUND_MESSAGE_RECORD_TYPE, &queued.id, &tags) {
+        warn!("Cannot mark queued message {} as failed: {}", queued.id, err);
+    }
+    _update_thread(&queued.thread_id, |status| {
+        status.pending = status.pending.saturating_sub(1);
+        status.failed = true;
+    });
+}
+
+// deletes expired rejected messages, returns threads of the remaining ones
+fn _expire_rejected() -> VcxResult<Vec<String>> {
+    let expired_before = format!("{:020}", Utc::now().timestamp() - REJECTED_MESSAGE_TTL_SECS);
+    let expired = json!({"status": "failed", "~failed_at": {"$lt": expired_before}}).to_string();
+
+    for queued in _search(&expired)? {
+        wallet::delete_record(OUTBOUND_MESSAGE_RECORD_TYPE, &queued.id)?;
+    }
+
+    Ok(_search(FAILED_QUERY)?.into_iter()
+        .map(|queued| queued.thread_id)
+        .filter(|thread_id| !thread_id.is_empty())
+        .collect())
+}
+
+/// Deletes messages queued by the connection with pairwise DID `pw_did`, pending and rejected ones alike.
+pub fn purge_connection(pw_did: &str) -> VcxResult<()> {
+    trace!("outbound_queue::purge_connection >>> pw_did: {}", pw_did);
+
+    for queued in _search(&json!({"pw_did": pw_did}).to_string())? {
+        wallet::delete_record(OUTBOUND_MESSAGE_RECORD_TYPE, &queued.id)?;
+    }
+
+    // statuses of the threads are loaded again from the remaining messages
+    forget_wallet(wallet::get_wallet_handle());
+    Ok(())
+}
+
+/// Number of messages waiting for delivery.
+pub fn pending_count() -> VcxResult<u32> {
+    Ok(_search(PENDING_QUERY)?.len() as u32)
+}
+
+/// Checks whether a message of the protocol thread waits for delivery.
+pub fn is_pending(thread_id: &str) -> bool {
+    _thread_status(thread_id).map(|status| status.pending > 0).unwrap_or(false)
+}
+
+///
+/// State reported for object in `state`, objects whose messages wait for delivery are reported
+/// as `VcxStateSending`, the ones whose message was rejected by the receiver as `VcxStateRejected`.
+///
+pub fn sending_state(state: u32, thread_id: &str) -> u32 {
+    match _thread_status(thread_id) {
+        Some(ref status) if status.failed => VcxStateType::VcxStateRejected as u32,
+        Some(ref status) if status.pending > 0 => VcxStateType::VcxStateSending as u32,
+        _ => state
+    }
+}
+
+fn _thread_status(thread_id: &str) -> Option<ThreadStatus> {
+    if !settings::is_outbound_queue_enabled() || thread_id.is_empty() {
+        return None;
+    }
+
+    let wallet_handle = wallet::get_wallet_handle();
+    if let Some(threads) = THREADS.lock().unwrap().get(&wallet_handle) {
+        return threads.get(thread_id).cloned();
+    }
+
+    // wallet is searched without holding the lock, statuses loaded meanwhile by another thread are kept
+    let loaded = _load_threads().ok()?;
+    THREADS.lock().unwrap()
+        .entry(wallet_handle)
+        .or_insert(loaded)
+        .get(thread_id)
+        .cloned()
+}
+
+/// Drops delivery statuses loaded for closed wallet.
+pub fn forget_wallet(wallet_handle: WalletHandle) {
+    THREADS.lock().unwrap().remove(&wallet_handle);
+}
+
+fn _load_threads() -> VcxResult<HashMap<String, ThreadStatus>> {
+    let mut threads: HashMap<String, ThreadStatus> = HashMap::new();
+    for queued in _search("{}")? {
+        let status = threads.entry(queued.thread_id).or_insert_with(ThreadStatus::default);
+        if queued.failed { status.failed = true; } else { status.pending += 1; }
+    }
+    Ok(threads)
+}
+
+// applies the change to the loaded statuses only, the others are loaded from the wallet when needed
+fn _update_thread<F>(thread_id: &str, update: F) where F: FnOnce(&mut ThreadStatus) {
+    if thread_id.is_empty() { return; }
+
+    let mut threads = THREADS.lock().unwrap();
+    if let Some(threads) = threads.get_mut(&wallet::get_wallet_handle()) {
+        update(threads.entry(thread_id.to_string()).or_insert_with(ThreadStatus::default));
+    }
+}
+
+fn _next_seq() -> u64 {
+    let mut last = LAST_SEQ.lock().unwrap();
+    *last = cmp::max(Utc::now().timestamp_nanos() as u64, *last + 1);
+    *last
+}
+
+// queued messages in the order they were queued, undecodable ones have no message
+fn _search(query: &str) -> VcxResult<Vec<QueuedMessage>> {
+    let options = json!({"retrieveType": false, "retrieveValue": true, "retrieveTags": true}).to_string();
+    let mut messages = Vec::new();
+
+    for record in wallet::search_records(OUTBOUND_MESSAGE_RECORD_TYPE, query, &options, LIST_PAGE_SIZE)? {
+        let record = record?;
+        let id = record["id"].as_str().unwrap_or_default().to_string();
+        let tag = |name: &str| record["tags"][name].as_str().map(String::from);
+
+        let message: Option<OutboundMessage> = serde_json::from_str(record["value"].as_str().unwrap_or_default())
+            .map_err(|err| warn!("Cannot deserialize outbound message {}: {:?}", id, err))
+            .ok();
+
+        messages.push(QueuedMessage {
+            seq: tag("seq").and_then(|seq| seq.parse().ok()).unwrap_or_default(),
+            thread_id: tag("thread_id").unwrap_or_default(),
+            failed: tag("status").as_ref().map(String::as_str) == Some("failed"),
+            message,
+            id,
+        });
+    }
+
+    messages.sort_by_key(|queued| queued.seq);
+    Ok(messages)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use utils::devsetup::SetupAgencyMock;
+    use utils::httpclient::HttpClientMockResponse;
+
+    use super::*;
+
+    fn _network_error() -> VcxResult<Vec<u8>> {
+        Err(VcxError::from_msg(VcxErrorKind::PostMessageFailed, "Could not connect"))
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_outbound_queue_keeps_undelivered_messages() {
+        let _setup = SetupAgencyMock::init();
+        settings::set_config_value(settings::CONFIG_OUTBOUND_QUEUE, "true");
+
+        HttpClientMockResponse::set_next_response(_network_error());
+        post_or_enqueue(&b"message".to_vec(), "http://localhost:8080", Some(String::from("thread-1")), None).unwrap();
+
+        assert_eq!(1, pending_count().unwrap());
+        assert!(is_pending("thread-1"));
+        assert!(!is_pending("thread-2"));
+        assert_eq!(VcxStateType::VcxStateSending as u32, sending_state(VcxStateType::VcxStateOfferSent as u32, "thread-1"));
+
+        HttpClientMockResponse::set_next_response(_network_error());
+        assert_eq!(1, flush().unwrap());
+
+        HttpClientMockResponse::set_next_response(Ok(Vec::new()));
+        assert_eq!(0, flush().unwrap());
+        assert!(!is_pending("thread-1"));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_outbound_queue_keeps_order_of_messages() {
+        let _setup = SetupAgencyMock::init();
+
+        let ids: Vec<String> = (0..5)
+            .map(|_| enqueue(&b"message".to_vec(), "http://localhost:8080", Some(String::from("thread-1")), None).unwrap())
+            .collect();
+
+        let queued: Vec<String> = _search("{}").unwrap().into_iter().map(|queued| queued.id).collect();
+        assert_eq!(ids, queued);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_outbound_queue_reports_rejected_messages() {
+        let _setup = SetupAgencyMock::init();
+        settings::set_config_value(settings::CONFIG_OUTBOUND_QUEUE, "true");
+
+        enqueue(&b"message".to_vec(), "http://localhost:8080", Some(String::from("thread-1")), None).unwrap();
+        wallet::add_record(OUTBOUND_MESSAGE_RECORD_TYPE, "undecodable", "not a message", Some(&json!({"thread_id": "thread-2"}).to_string())).unwrap();
+        assert_eq!(VcxStateType::VcxStateSending as u32, sending_state(VcxStateType::VcxStateOfferSent as u32, "thread-1"));
+
+        HttpClientMockResponse::set_next_response(Err(VcxError::from_msg(VcxErrorKind::InvalidHttpResponse, "Bad request")));
+        assert_eq!(0, flush().unwrap());
+
+        assert_eq!(0, pending_count().unwrap());
+        assert_eq!(VcxStateType::VcxStateRejected as u32, sending_state(VcxStateType::VcxStateOfferSent as u32, "thread-1"));
+        assert_eq!(VcxStateType::VcxStateRejected as u32, sending_state(VcxStateType::VcxStateOfferSent as u32, "thread-2"));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_outbound_queue_stops_thread_after_rejected_message() {
+        let _setup = SetupAgencyMock::init();
+        settings::set_config_value(settings::CONFIG_OUTBOUND_QUEUE, "true");
+
+        enqueue(&b"message".to_vec(), "http://localhost:8080", Some(String::from("thread-1")), None).unwrap();
+        enqueue(&b"message".to_vec(), "http://localhost:8080", Some(String::from("thread-1")), None).unwrap();
+
+        HttpClientMockResponse::set_next_response(Err(VcxError::from_msg(VcxErrorKind::InvalidHttpResponse, "Bad request")));
+        assert_eq!(0, flush().unwrap());
+        assert_eq!(2, _search(FAILED_QUERY).unwrap().len());
+
+        // later messages of the thread are not delivered either
+        enqueue(&b"message".to_vec(), "http://localhost:8080", Some(String::from("thread-1")), None).unwrap();
+        assert_eq!(0, flush().unwrap());
+        assert_eq!(3, _search(FAILED_QUERY).unwrap().len());
+
+        // expired rejected messages are deleted
+        let expired = json!({"status": "failed", "~failed_at": format!("{:020}", 0)}).to_string();
+        for queued in _search(FAILED_QUERY).unwrap() {
+            wallet::add_record_tags(OUTBOUND_MESSAGE_RECORD_TYPE, &queued.id, &expired).unwrap();
+        }
+        assert!(_expire_rejected().unwrap().is_empty());
+        assert!(_search("{}").unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_outbound_queue_purges_messages_of_connection() {
+        let _setup = SetupAgencyMock::init();
+        settings::set_config_value(settings::CONFIG_OUTBOUND_QUEUE, "true");
+
+        enqueue(&b"message".to_vec(), "http://localhost:8080", Some(String::from("thread-1")), Some("pw-did-1")).unwrap();
+        enqueue(&b"message".to_vec(), "http://localhost:8080", Some(String::from("thread-2")), Some("pw-did-2")).unwrap();
+        assert!(is_pending("thread-1"));
+
+        purge_connection("pw-did-1").unwrap();
+
+        assert_eq!(1, pending_count().unwrap());
+        assert!(!is_pending("thread-1"));
+        assert!(is_pending("thread-2"));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_outbound_queue_disabled() {
+        let _setup = SetupAgencyMock::init();
+
+        HttpClientMockResponse::set_next_response(_network_error());
+        let err = post_or_enqueue(&b"message".to_vec(), "http://localhost:8080", None, None).unwrap_err();
+        assert_eq!(VcxErrorKind::PostMessageFailed, err.kind());
+        assert_eq!(0, pending_count().unwrap());
+    }
+}
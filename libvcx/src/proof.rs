@@ -1,12 +1,37 @@
 use serde_json;
 
+use api::VcxStateType;
 use aries::handlers::proof_presentation::verifier::verifier::Verifier;
+use aries::messages::error::ProblemReport;
+use aries::messages::proof_presentation::presentation::Presentation;
+use aries::messages::proof_presentation::presentation_request::PresentationRequest;
+use aries::messages::status::Status;
+use connection;
 use error::prelude::*;
+use messages::proofs::proof_request::{AttrInfo, NonRevokedInterval, PredicateInfo, ProofRequestData, Restrictions};
+use messages::validation;
+use outbound_queue;
+use settings;
+use proof_utils::validate_presentation;
 use utils::error;
-use utils::object_cache::ObjectCache;
+use utils::events;
+use utils::handle_token;
+use utils::json::check_json_limits;
+use utils::libindy::pool;
+use utils::object_cache::{ObjectCache, ObjectKeys};
+use utils::parallel;
+use utils::persistence;
 
 lazy_static! {
-    static ref PROOF_MAP: ObjectCache<Verifier> = ObjectCache::<Verifier>::new("proofs-cache");
+    static ref PROOF_MAP: ObjectCache<Verifier> = ObjectCache::<Verifier>::with_index("proofs-cache", |proof| ObjectKeys {
+        source_id: Some(proof.get_source_id()),
+        thread_id: Some(proof.get_thread_id()).filter(|thread_id| !thread_id.is_empty()),
+        metadata: proof.get_metadata().clone(),
+    }).with_scope(pool::enter_object_network);
+}
+
+fn _get_mut<F, R>(handle: u32, closure: F) -> VcxResult<R> where F: Fn(&mut Verifier) -> VcxResult<R> {
+    persistence::get_mut(&PROOF_MAP, events::PROOF, handle, |proof| outbound_queue::sending_state(proof.state(), &proof.get_thread_id()), |proof| Some(proof.get_thread_id()), to_string, closure)
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -22,8 +47,14 @@ pub fn create_proof(source_id: String,
                     revocation_details: String,
                     name: String) -> VcxResult<u32> {
     let verifier = Verifier::create(source_id, requested_attrs, requested_predicates, revocation_details, name)?;
-    PROOF_MAP.add(verifier)
-        .or(Err(VcxError::from(VcxErrorKind::CreateProof)))
+    persistence::add(&PROOF_MAP, events::PROOF, verifier, to_string)
+}
+
+pub fn create_proof_with_presentation_definition(source_id: String, presentation_definition: String, name: String) -> VcxResult<u32> {
+    check_json_limits(presentation_definition.as_bytes())?;
+
+    let verifier = Verifier::create_with_presentation_definition(source_id, presentation_definition, name)?;
+    persistence::add(&PROOF_MAP, events::PROOF, verifier, to_string)
 }
 
 pub fn is_valid_handle(handle: u32) -> bool {
@@ -31,15 +62,17 @@ pub fn is_valid_handle(handle: u32) -> bool {
 }
 
 pub fn update_state(handle: u32, message: Option<String>, connection_handle: Option<u32>) -> VcxResult<u32> {
-    PROOF_MAP.get_mut(handle, |proof| {
+    if let Some(ref message) = message { check_json_limits(message.as_bytes())?; }
+
+    _get_mut(handle, |proof| {
         proof.update_state(message.as_ref().map(String::as_str), connection_handle)?;
-        Ok(proof.state())
+        Ok(outbound_queue::sending_state(proof.state(), &proof.get_thread_id()))
     })
 }
 
 pub fn get_state(handle: u32) -> VcxResult<u32> {
     PROOF_MAP.get(handle, |proof| {
-        Ok(proof.state())
+        Ok(outbound_queue::sending_state(proof.state(), &proof.get_thread_id()))
     })
 }
 
@@ -50,13 +83,20 @@ pub fn get_proof_state(handle: u32) -> VcxResult<u32> {
 }
 
 pub fn release(handle: u32) -> VcxResult<()> {
-    PROOF_MAP.release(handle).or(Err(VcxError::from(VcxErrorKind::InvalidProofHandle)))
+    PROOF_MAP.release(handle).or(Err(VcxError::from(VcxErrorKind::InvalidProofHandle)))?;
+    persistence::forget(events::PROOF, handle);
+    Ok(())
 }
 
 pub fn release_all() {
+    persistence::forget_all(&PROOF_MAP, events::PROOF);
     PROOF_MAP.drain().ok();
 }
 
+pub fn get_handles() -> Vec<u32> {
+    PROOF_MAP.handles().unwrap_or_default()
+}
+
 pub fn to_string(handle: u32) -> VcxResult<String> {
     PROOF_MAP.get(handle, |proof| {
         serde_json::to_string(&Proofs::V3(proof.clone()))
@@ -70,6 +110,60 @@ pub fn get_source_id(handle: u32) -> VcxResult<String> {
     })
 }
 
+pub fn get_thread_id(handle: u32) -> VcxResult<String> {
+    PROOF_MAP.get(handle, |proof| {
+        Ok(proof.get_thread_id())
+    })
+}
+
+/// Connection the exchange of the object runs over, if any.
+pub fn get_connection_handle(handle: u32) -> VcxResult<Option<u32>> {
+    PROOF_MAP.get(handle, |proof| {
+        Ok(proof.get_connection_handle())
+    })
+}
+
+/// Time the offer or request of the exchange expires at, in RFC 3339 format, `None` if it does not expire.
+pub fn get_expiry(handle: u32) -> VcxResult<Option<String>> {
+    PROOF_MAP.get(handle, |proof| {
+        Ok(proof.get_expiry())
+    })
+}
+
+pub fn get_protocol_history(handle: u32) -> VcxResult<String> {
+    PROOF_MAP.get(handle, |proof| {
+        proof.get_protocol_history()
+    })
+}
+
+/// Handle of the proof with given source id, the most recent one when there are more of them.
+pub fn get_handle_by_source_id(source_id: &str) -> VcxResult<u32> {
+    PROOF_MAP.find_by_source_id(source_id)
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidProofHandle, format!("No proof with source id: {}", source_id)))
+}
+
+pub fn get_handle_by_thread_id(thread_id: &str) -> VcxResult<u32> {
+    PROOF_MAP.find_by_thread_id(thread_id)
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidProofHandle, format!("No proof with thread id: {}", thread_id)))
+}
+
+/// Sets metadata `key` of the proof to `value`, or removes the key when no value is given.
+pub fn set_metadata(handle: u32, key: &str, value: Option<&str>) -> VcxResult<()> {
+    _get_mut(handle, |proof| proof.set_metadata(key, value))
+}
+
+/// Metadata of the proof as JSON object.
+pub fn get_metadata(handle: u32) -> VcxResult<String> {
+    PROOF_MAP.get(handle, |proof| {
+        Ok(json!(proof.get_metadata()).to_string())
+    })
+}
+
+/// Handles of the proofs having metadata `key` set to `value`.
+pub fn find_handles_by_metadata(key: &str, value: &str) -> Vec<u32> {
+    PROOF_MAP.find_by_metadata(key, value)
+}
+
 pub fn from_string(proof_data: &str) -> VcxResult<u32> {
     let proof: Proofs = serde_json::from_str(proof_data)
         .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("cannot deserialize Proofs proofect: {:?}", err)))?;
@@ -79,30 +173,348 @@ pub fn from_string(proof_data: &str) -> VcxResult<u32> {
     }
 }
 
+/// Serializes the object into a token which can be turned back into a handle by another process via `from_token`.
+pub fn to_token(handle: u32) -> VcxResult<String> {
+    handle_token::seal("proof", &to_string(handle)?)
+}
+
+pub fn from_token(token: &str) -> VcxResult<u32> {
+    from_string(&handle_token::unseal("proof", token)?)
+}
+
 pub fn generate_proof_request_msg(handle: u32) -> VcxResult<String> {
-    PROOF_MAP.get_mut(handle, |proof| {
+    _get_mut(handle, |proof| {
         proof.generate_presentation_request_msg()
     })
 }
 
+/// Makes the proof request expire `expires_in_secs` after it is sent, has to be called before the request is sent.
+pub fn set_expiration(handle: u32, expires_in_secs: u64) -> VcxResult<()> {
+    _get_mut(handle, |proof| {
+        proof.set_expiration(expires_in_secs)
+    })
+}
+
+///
+/// Returns out-of-band invitation with the proof request attached, so that it can be answered without a connection.
+/// The presentation is answered to the service of a connection created for the proof, which is then used to receive it.
+///
+pub fn get_oob_proof_request(handle: u32) -> VcxResult<String> {
+    _get_mut(handle, |proof| {
+        if proof.state() == VcxStateType::VcxStateInitialized as u32 {
+            connection::create_out_of_band_connection(&proof.get_source_id(), |connection_handle| {
+                proof.prepare_out_of_band_request(connection_handle)
+            })?;
+        }
+
+        let invitation = proof.get_out_of_band_request()?;
+        Ok(json!(invitation).to_string())
+    })
+}
+
 pub fn send_proof_request(handle: u32, connection_handle: u32) -> VcxResult<u32> {
-    PROOF_MAP.get_mut(handle, |proof| {
+    _get_mut(handle, |proof| {
         proof.send_presentation_request(connection_handle)?;
         Ok(error::SUCCESS.code_num)
     })
 }
 
+/// Sends the pending proof request again over `connection_handle`, the thread of the exchange stays the same.
+pub fn resend_request(handle: u32, connection_handle: u32) -> VcxResult<()> {
+    _get_mut(handle, |proof| {
+        proof.resend_request(connection_handle)
+    })
+}
+
+///
+/// Sends a revised proof request superseding the pending one on the same thread.
+/// `request_update` may set `name`, `requested_attributes`, `requested_predicates` and `revocation_interval`
+/// in the format `create_proof` takes them, the parts not set are kept from the pending request.
+///
+pub fn update_request(handle: u32, request_update: &str) -> VcxResult<()> {
+    check_json_limits(request_update.as_bytes())?;
+
+    _get_mut(handle, |proof| {
+        proof.update_request(request_update)
+    })
+}
+
 pub fn get_proof(handle: u32) -> VcxResult<String> {
     PROOF_MAP.get(handle, |proof| {
         proof.get_presentation()
     })
 }
 
+pub fn get_verification_details(handle: u32) -> VcxResult<String> {
+    PROOF_MAP.get(handle, |proof| {
+        proof.get_verification_details()
+    })
+}
+
+/// Returns presentation status the stored presentation would have had when verified at `as_of`.
+pub fn verify_presentation_as_of(handle: u32, as_of: u64) -> VcxResult<u32> {
+    PROOF_MAP.get(handle, |proof| {
+        proof.verify_presentation_as_of(as_of)
+    })
+}
+
+/// Checks whether the revocation registry states used in the stored presentation fall into the requested
+/// `non_revoked` intervals and whether newer registry entries were published within them.
+pub fn verify_revocation(handle: u32) -> VcxResult<String> {
+    PROOF_MAP.get(handle, |proof| {
+        proof.verify_revocation()
+    })
+}
+
+///
+/// Verifies `presentation_json` against `request_json` it answers without creating proof or connection,
+/// for verifiers handling the transport on their own. Both are `aries` present-proof messages.
+/// Returns presentation status the same way `get_proof_state` does for proof objects.
+///
+pub fn verify_presentation_msg(request_json: &str, presentation_json: &str) -> VcxResult<u32> {
+    check_json_limits(request_json.as_bytes())?;
+    check_json_limits(presentation_json.as_bytes())?;
+
+    let presentation_request: PresentationRequest = serde_json::from_str(request_json)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize Presentation Request: {}", err)))?;
+    let presentation: Presentation = serde_json::from_str(presentation_json)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize Presentation: {}", err)))?;
+
+    if !presentation.from_thread(&presentation_request.thread_id()) {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidMessages, "Presentation does not answer the Presentation Request"));
+    }
+
+    match validate_presentation(&presentation_request, &presentation) {
+        Ok(()) => Ok(Status::Success.code()),
+        Err(ref err) if err.kind() == VcxErrorKind::InvalidProof => {
+            Ok(Status::Failed(ProblemReport::create().set_comment(err.to_string())).code())
+        }
+        Err(err) => Err(err)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct BatchVerificationItem {
+    request: serde_json::Value,
+    presentation: serde_json::Value,
+}
+
+/// Status of the verified presentation, or error the verification failed with.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct BatchVerificationResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BatchVerificationResult {
+    fn from_result(result: VcxResult<u32>) -> BatchVerificationResult {
+        match result {
+            Ok(status) => BatchVerificationResult { status: Some(status), error_code: None, error: None },
+            Err(err) => BatchVerificationResult { status: None, error: Some(err.to_string()), error_code: Some(err.into()) },
+        }
+    }
+}
+
+// request and presentation may be passed either as JSON objects or as strings containing them
+fn _json_string(value: serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(json) => json,
+        value => value.to_string()
+    }
+}
+
+///
+/// Verifies many connection-less presentations the same way `verify_presentation_msg` does.
+/// `items_json` is an array of `{"request": <presentation request>, "presentation": <presentation>}`,
+/// at most `verify_batch_parallelism` of them are verified at once.
+/// Returns array of results in the order of items, `{"status": <presentation status>}` for verified items
+/// and `{"error_code": <code>, "error": <message>}` for items the verification failed for.
+///
+pub fn verify_batch(items_json: &str) -> VcxResult<String> {
+    check_json_limits(items_json.as_bytes())?;
+
+    let items: Vec<BatchVerificationItem> = serde_json::from_str(items_json)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize batch verification items: {}", err)))?;
+
+    let results = parallel::map_results(items, settings::get_verify_batch_parallelism(), |item| {
+        verify_presentation_msg(&_json_string(item.request), &_json_string(item.presentation))
+    });
+    let results: Vec<BatchVerificationResult> = results.into_iter()
+        .map(BatchVerificationResult::from_result)
+        .collect();
+
+    serde_json::to_string(&results)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize batch verification results: {}", err)))
+}
+
+const PREDICATE_TYPES: [&str; 4] = [">=", "<=", ">", "<"];
+
+/// Typed alternative to passing raw JSON to `create_proof`.
+/// Every field is validated before the request is created so errors point at the offending field instead of failing in libindy.
+#[derive(Debug, Clone, Default)]
+pub struct ProofRequestBuilder {
+    source_id: String,
+    name: String,
+    requested_attributes: Vec<AttrInfo>,
+    requested_predicates: Vec<PredicateInfo>,
+    non_revoked: Option<NonRevokedInterval>,
+}
+
+impl ProofRequestBuilder {
+    pub fn create(source_id: &str, name: &str) -> ProofRequestBuilder {
+        ProofRequestBuilder {
+            source_id: source_id.to_string(),
+            name: name.to_string(),
+            ..ProofRequestBuilder::default()
+        }
+    }
+
+    pub fn add_attribute(mut self, attribute: AttrInfo) -> ProofRequestBuilder {
+        self.requested_attributes.push(attribute);
+        self
+    }
+
+    pub fn add_predicate(mut self, predicate: PredicateInfo) -> ProofRequestBuilder {
+        self.requested_predicates.push(predicate);
+        self
+    }
+
+    pub fn set_non_revoked(mut self, from: Option<u64>, to: Option<u64>) -> ProofRequestBuilder {
+        self.non_revoked = Some(NonRevokedInterval { from, to });
+        self
+    }
+
+    pub fn validate(&self) -> VcxResult<()> {
+        if self.name.trim().is_empty() {
+            return Err(_invalid_field("name", "must not be empty"));
+        }
+
+        if self.requested_attributes.is_empty() && self.requested_predicates.is_empty() {
+            return Err(_invalid_field("requested_attributes", "at least one attribute or predicate must be requested"));
+        }
+
+        for (index, attribute) in self.requested_attributes.iter().enumerate() {
+            let field = format!("requested_attributes[{}]", index);
+            match (&attribute.name, &attribute.names) {
+                (Some(name), None) => _validate_attribute_name(&format!("{}.name", field), name)?,
+                (None, Some(names)) if !names.is_empty() => {
+                    for (name_index, name) in names.iter().enumerate() {
+                        _validate_attribute_name(&format!("{}.names[{}]", field, name_index), name)?;
+                    }
+                }
+                (None, Some(_)) => return Err(_invalid_field(&format!("{}.names", field), "must not be empty")),
+                _ => return Err(_invalid_field(&field, "exactly one of `name` or `names` must be set"))
+            }
+            _validate_restrictions(&format!("{}.restrictions", field), &attribute.restrictions)?;
+            _validate_non_revoked(&format!("{}.non_revoked", field), &attribute.non_revoked)?;
+        }
+
+        for (index, predicate) in self.requested_predicates.iter().enumerate() {
+            let field = format!("requested_predicates[{}]", index);
+            _validate_attribute_name(&format!("{}.name", field), &predicate.name)?;
+            if !PREDICATE_TYPES.contains(&predicate.p_type.as_str()) {
+                return Err(_invalid_field(&format!("{}.p_type", field),
+                                          &format!("`{}` is not supported, expected one of {:?}", predicate.p_type, PREDICATE_TYPES)));
+            }
+            _validate_restrictions(&format!("{}.restrictions", field), &predicate.restrictions)?;
+            _validate_non_revoked(&format!("{}.non_revoked", field), &predicate.non_revoked)?;
+        }
+
+        _validate_non_revoked("non_revoked", &self.non_revoked)
+    }
+
+    /// Validates the builder and returns the proof request data, including a freshly generated nonce.
+    pub fn build_request_data(&self) -> VcxResult<ProofRequestData> {
+        self.validate()?;
+
+        let request_data = ProofRequestData::create()
+            .set_name(self.name.clone())
+            .set_requested_attributes(json!(self.requested_attributes).to_string())?
+            .set_requested_predicates(json!(self.requested_predicates).to_string())?
+            .set_not_revoked_interval(self.non_revoked_json())?
+            .set_nonce()?;
+
+        validation::validate_nonce(&request_data.nonce)
+            .map_err(|err| err.extend("Invalid field `nonce`"))?;
+
+        Ok(request_data)
+    }
+
+    /// Validates the builder and creates the proof object, returning its handle.
+    pub fn build(self) -> VcxResult<u32> {
+        self.validate()?;
+
+        create_proof(self.source_id.clone(),
+                     json!(self.requested_attributes).to_string(),
+                     json!(self.requested_predicates).to_string(),
+                     self.non_revoked_json(),
+                     self.name.clone())
+    }
+
+    fn non_revoked_json(&self) -> String {
+        match self.non_revoked {
+            Some(ref interval) => json!(interval).to_string(),
+            None => json!({}).to_string()
+        }
+    }
+}
+
+fn _invalid_field(field: &str, reason: &str) -> VcxError {
+    VcxError::from_msg(VcxErrorKind::InvalidProofRequest, format!("Invalid field `{}`: {}", field, reason))
+}
+
+fn _validate_attribute_name(field: &str, name: &str) -> VcxResult<()> {
+    if name.trim().is_empty() {
+        return Err(_invalid_field(field, "attribute name must not be empty"));
+    }
+    Ok(())
+}
+
+fn _validate_restrictions(field: &str, restrictions: &Option<Restrictions>) -> VcxResult<()> {
+    match restrictions {
+        Some(Restrictions::V1(filters)) => {
+            for (index, filter) in filters.iter().enumerate() {
+                if json!(filter).as_object().map(|map| map.values().all(|value| value.is_null())).unwrap_or(true) {
+                    return Err(_invalid_field(&format!("{}[{}]", field, index), "restriction must contain at least one condition"));
+                }
+            }
+            Ok(())
+        }
+        Some(Restrictions::V2(query)) => {
+            let valid = match query {
+                ::serde_json::Value::Object(_) => true,
+                ::serde_json::Value::Array(alternatives) => alternatives.iter().all(|alternative| alternative.is_object()),
+                _ => false
+            };
+            if !valid {
+                return Err(_invalid_field(field, "restrictions must be WQL object or array of objects"));
+            }
+            ::proof_utils::validate_attr_restrictions(query)
+                .map_err(|err| err.extend(format!("Invalid field `{}`", field)))
+        }
+        None => Ok(())
+    }
+}
+
+fn _validate_non_revoked(field: &str, non_revoked: &Option<NonRevokedInterval>) -> VcxResult<()> {
+    match non_revoked {
+        Some(NonRevokedInterval { from: None, to: None }) => Err(_invalid_field(field, "at least one of `from` or `to` must be set")),
+        Some(NonRevokedInterval { from: Some(from), to: Some(to) }) if from > to => {
+            Err(_invalid_field(field, &format!("`from` ({}) must not be greater than `to` ({})", from, to)))
+        }
+        _ => Ok(())
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use serde_json::Value;
 
-    use api::VcxStateType;
+    use api::{ProofStateType, VcxStateType};
     use aries::handlers::proof_presentation::verifier::verifier::Verifier;
     use aries::messages::proof_presentation::presentation::Presentation;
     use aries::messages::proof_presentation::presentation_request::PresentationRequestData;
@@ -202,6 +614,57 @@ pub mod tests {
         assert_eq!(proof_data, proof_data2);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_metadata_is_serialized_and_searchable() {
+        let _setup = SetupStrictAriesMocks::init();
+
+        let handle = create_proof("1".to_string(),
+                                  REQUESTED_ATTRS.to_owned(),
+                                  REQUESTED_PREDICATES.to_owned(),
+                                  r#"{"support_revocation":false}"#.to_string(),
+                                  "Optional".to_owned()).unwrap();
+        assert_eq!(get_metadata(handle).unwrap(), "{}");
+        assert!(!to_string(handle).unwrap().contains("metadata"));
+
+        set_metadata(handle, "customer", Some("42")).unwrap();
+        set_metadata(handle, "batch", Some("7")).unwrap();
+        assert_eq!(find_handles_by_metadata("customer", "42"), vec![handle]);
+
+        let proof_data = to_string(handle).unwrap();
+        release(handle).unwrap();
+        assert!(find_handles_by_metadata("customer", "42").is_empty());
+
+        let handle = from_string(&proof_data).unwrap();
+        assert_eq!(find_handles_by_metadata("customer", "42"), vec![handle]);
+        let metadata: Value = serde_json::from_str(&get_metadata(handle).unwrap()).unwrap();
+        assert_eq!(metadata, json!({"batch": "7", "customer": "42"}));
+
+        set_metadata(handle, "customer", None).unwrap();
+        assert!(find_handles_by_metadata("customer", "42").is_empty());
+        assert_eq!(find_handles_by_metadata("batch", "7"), vec![handle]);
+
+        assert_eq!(set_metadata(handle, "", Some("1")).unwrap_err().kind(), VcxErrorKind::InvalidOption);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_token_round_trip() {
+        let _setup = SetupStrictAriesMocks::init();
+        settings::set_config_value(settings::CONFIG_HANDLE_TOKEN_KEY, "secret");
+
+        let handle = create_proof("1".to_string(),
+                                  REQUESTED_ATTRS.to_owned(),
+                                  REQUESTED_PREDICATES.to_owned(),
+                                  r#"{"support_revocation":false}"#.to_string(),
+                                  "Optional".to_owned()).unwrap();
+        let token = to_token(handle).unwrap();
+        let handle2 = from_token(&token).unwrap();
+        assert_eq!(to_string(handle).unwrap(), to_string(handle2).unwrap());
+
+        assert_eq!(::credential::from_token(&token).unwrap_err().kind(), VcxErrorKind::InvalidHandleToken);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_release_proof() {
@@ -232,6 +695,84 @@ pub mod tests {
         assert_eq!(get_state(proof_handle).unwrap(), VcxStateType::VcxStateOfferSent as u32);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_oob_proof_request() {
+        let _setup = SetupAriesMocks::init();
+
+        let proof_handle = create_proof("1".to_string(),
+                                        REQUESTED_ATTRS.to_owned(),
+                                        REQUESTED_PREDICATES.to_owned(),
+                                        r#"{"support_revocation":false}"#.to_string(),
+                                        "Optional".to_owned()).unwrap();
+
+        let invitation: Value = serde_json::from_str(&get_oob_proof_request(proof_handle).unwrap()).unwrap();
+        assert_eq!(get_state(proof_handle).unwrap(), VcxStateType::VcxStateOfferSent as u32);
+        assert_eq!(invitation["request~attach"][0]["data"]["json"]["@id"], json!(get_thread_id(proof_handle).unwrap()));
+        assert!(invitation["request~attach"][0]["data"]["json"]["~service"].is_object());
+
+        // the request is prepared only once
+        let invitation_again: Value = serde_json::from_str(&get_oob_proof_request(proof_handle).unwrap()).unwrap();
+        assert_eq!(invitation["request~attach"], invitation_again["request~attach"]);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_verify_presentation_msg() {
+        let _setup = SetupAriesMocks::init();
+
+        let request = create_default_proof().generate_presentation_request_msg().unwrap();
+        let request_id = serde_json::from_str::<Value>(&request).unwrap()["@id"].as_str().unwrap().to_string();
+
+        let presentation: Presentation = serde_json::from_str(mockdata_proof::ARIES_PROOF_PRESENTATION).unwrap();
+        let other_presentation = json!(presentation.to_a2a_message()).to_string();
+        let presentation = json!(presentation.set_thread_id(&request_id).to_a2a_message()).to_string();
+
+        {
+            let _mock_builder = MockBuilder::init().set_mock_result_for_validate_indy_proof(Ok(true));
+            assert_eq!(verify_presentation_msg(&request, &presentation).unwrap(), ProofStateType::ProofValidated as u32);
+            assert_eq!(verify_presentation_msg(&request, &other_presentation).unwrap_err().kind(), VcxErrorKind::InvalidMessages);
+        }
+
+        {
+            let _mock_builder = MockBuilder::init().set_mock_result_for_validate_indy_proof(Ok(false));
+            assert_eq!(verify_presentation_msg(&request, &presentation).unwrap(), ProofStateType::ProofInvalid as u32);
+        }
+
+        assert_eq!(verify_presentation_msg("{}", &presentation).unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_verify_batch() {
+        let _setup = SetupAriesMocks::init();
+        let _mock_builder = MockBuilder::init().set_mock_result_for_validate_indy_proof(Ok(true));
+        settings::set_config_value(settings::CONFIG_VERIFY_BATCH_PARALLELISM, "2");
+
+        let request = create_default_proof().generate_presentation_request_msg().unwrap();
+        let request_id = serde_json::from_str::<Value>(&request).unwrap()["@id"].as_str().unwrap().to_string();
+
+        let presentation: Presentation = serde_json::from_str(mockdata_proof::ARIES_PROOF_PRESENTATION).unwrap();
+        let other_presentation = json!(presentation.to_a2a_message());
+        let presentation = json!(presentation.set_thread_id(&request_id).to_a2a_message());
+
+        let items = json!([
+            {"request": request, "presentation": presentation},
+            {"request": request, "presentation": other_presentation},
+            {"request": request, "presentation": presentation.to_string()},
+        ]).to_string();
+
+        let results: Value = serde_json::from_str(&verify_batch(&items).unwrap()).unwrap();
+        assert_eq!(results, json!([
+            {"status": ProofStateType::ProofValidated as u32},
+            {"error_code": error::INVALID_MESSAGES.code_num, "error": results[1]["error"]},
+            {"status": ProofStateType::ProofValidated as u32},
+        ]));
+
+        assert_eq!(verify_batch("[]").unwrap(), "[]");
+        assert_eq!(verify_batch("{}").unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_get_proof_fails_with_no_proof() {
@@ -296,6 +837,98 @@ pub mod tests {
         assert_eq!(proof_str, mockdata_proof::ARIES_PROOF_PRESENTATION.replace("\n", "").replace(" ", ""));
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_verification_details() {
+        let _setup = SetupStrictAriesMocks::init();
+        let _mock_builder = MockBuilder::init().
+            set_mock_result_for_validate_indy_proof(Ok(true));
+
+        let connection_handle = build_test_connection_inviter_requested();
+        let mut proof = create_default_proof();
+        let handle = PROOF_MAP.add(proof.clone()).unwrap();
+        assert_eq!(get_verification_details(handle).unwrap_err().kind(), VcxErrorKind::NotReady);
+
+        progress_proof_to_final_state(&mut proof, connection_handle, mockdata_proof::ARIES_PROOF_PRESENTATION);
+        let handle = PROOF_MAP.add(proof).unwrap();
+
+        let details: Value = serde_json::from_str(&get_verification_details(handle).unwrap()).unwrap();
+        assert_eq!(details["presentation_status"], json!(1));
+        assert_eq!(details["revealed_attrs"]["attribute_1"]["raw"], json!("05-2018"));
+        assert_eq!(details["revealed_attr_groups"]["attribute_0"]["values"]["name"], json!("alice"));
+        assert_eq!(details["self_attested_attrs"]["attribute_3"], json!("Smith"));
+        assert_eq!(details["predicates"]["predicate_0"]["identifier"]["cred_def_id"], json!("V4SGRU86Z58d6TV7PBUe6f:3:CL:67:tag1"));
+        assert_eq!(details["predicates"]["predicate_0"]["identifier"]["revocation_checked"], json!(true));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_verify_presentation_as_of() {
+        let _setup = SetupStrictAriesMocks::init();
+        let _mock_builder = MockBuilder::init().
+            set_mock_result_for_validate_indy_proof(Ok(true));
+
+        let connection_handle = build_test_connection_inviter_requested();
+        let mut proof = create_default_proof();
+        let handle = PROOF_MAP.add(proof.clone()).unwrap();
+        assert_eq!(verify_presentation_as_of(handle, 1599834693).unwrap_err().kind(), VcxErrorKind::NotReady);
+
+        progress_proof_to_final_state(&mut proof, connection_handle, mockdata_proof::ARIES_PROOF_PRESENTATION);
+        let handle = PROOF_MAP.add(proof).unwrap();
+
+        assert_eq!(verify_presentation_as_of(handle, 1599834693).unwrap(), 1);
+        assert_eq!(verify_presentation_as_of(handle, 1599834692).unwrap_err().kind(), VcxErrorKind::InvalidRevocationTimestamp);
+        assert_eq!(get_state(handle).unwrap(), VcxStateType::VcxStateAccepted as u32);
+    }
+
+    fn _attr(name: &str) -> AttrInfo {
+        AttrInfo { name: Some(name.to_string()), names: None, restrictions: None, non_revoked: None, self_attest_allowed: None }
+    }
+
+    fn _predicate(name: &str, p_type: &str) -> PredicateInfo {
+        PredicateInfo { name: name.to_string(), p_type: p_type.to_string(), p_value: 18, restrictions: None, non_revoked: None }
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_proof_request_builder() {
+        let _setup = SetupStrictAriesMocks::init();
+
+        let builder = ProofRequestBuilder::create("1", "proof")
+            .add_attribute(_attr("name"))
+            .add_attribute(AttrInfo { name: None, names: Some(vec!["street".to_string(), "city".to_string()]), .._attr("") })
+            .add_predicate(_predicate("age", ">="))
+            .set_non_revoked(None, Some(100));
+
+        let request_data = builder.build_request_data().unwrap();
+        assert_eq!(request_data.requested_attributes.len(), 2);
+        assert_eq!(request_data.requested_predicates.len(), 1);
+        assert_eq!(request_data.non_revoked, Some(NonRevokedInterval { from: None, to: Some(100) }));
+
+        let handle = builder.build().unwrap();
+        assert_eq!(get_state(handle).unwrap(), VcxStateType::VcxStateInitialized as u32);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_proof_request_builder_reports_invalid_fields() {
+        let _setup = SetupStrictAriesMocks::init();
+
+        let validate_err = |builder: ProofRequestBuilder| {
+            let err = builder.validate().unwrap_err();
+            assert_eq!(err.kind(), VcxErrorKind::InvalidProofRequest);
+            err.to_string()
+        };
+
+        assert!(validate_err(ProofRequestBuilder::create("1", "proof")).contains("requested_attributes"));
+        assert!(validate_err(ProofRequestBuilder::create("1", "proof").add_attribute(_attr(" "))).contains("requested_attributes[0].name"));
+        assert!(validate_err(ProofRequestBuilder::create("1", "proof").add_attribute(AttrInfo { names: Some(vec![]), name: None, .._attr("") })).contains("requested_attributes[0].names"));
+        assert!(validate_err(ProofRequestBuilder::create("1", "proof").add_predicate(_predicate("age", "GE"))).contains("requested_predicates[0].p_type"));
+        assert!(validate_err(ProofRequestBuilder::create("1", "proof").add_attribute(AttrInfo { restrictions: Some(Restrictions::V2(json!("abc"))), .._attr("name") })).contains("requested_attributes[0].restrictions"));
+        assert!(validate_err(ProofRequestBuilder::create("1", "proof").add_attribute(_attr("name")).set_non_revoked(Some(10), Some(1))).contains("non_revoked"));
+        assert!(validate_err(ProofRequestBuilder::create("1", "proof").add_attribute(AttrInfo { restrictions: Some(Restrictions::V2(json!({"attr::name::marker": "0"}))), .._attr("name") })).contains("requested_attributes[0].restrictions"));
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_release_all() {
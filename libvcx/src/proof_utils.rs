@@ -1,15 +1,40 @@
+use std::collections::{BTreeSet, HashMap};
+
 use serde_json;
 use serde_json::Value;
 
 use error::prelude::*;
+use aries::messages::proof_presentation::format::PresentationFormat;
+use aries::messages::proof_presentation::presentation::Presentation;
+use aries::messages::proof_presentation::presentation_exchange::{PresentationExchangeRequest, VerifiablePresentation};
+use aries::messages::proof_presentation::presentation_request::PresentationRequest;
 use messages::proofs::proof_message::{
     CredInfoVerifier,
     get_credential_info,
 };
+use messages::proofs::proof_request::{NonRevokedInterval, ProofRequestData, Restrictions};
 use settings;
 use utils::libindy::anoncreds;
+use utils::libindy::ledger;
 use utils::mockdata::mock_settings::get_mock_result_for_validate_indy_proof;
 use utils::openssl::encode;
+use utils::qualifier;
+use utils::wql;
+
+static IDENTIFIER_TAGS: [&str; 5] = ["schema_id", "schema_issuer_did", "issuer_did", "cred_def_id", "rev_reg_id"];
+
+fn _validate_encoded_value(referent: &str, info: &Value) -> VcxResult<()> {
+    let raw = info["raw"].as_str().ok_or(VcxError::from_msg(VcxErrorKind::InvalidProof, format!("Cannot get raw value for \"{}\" attribute", referent)))?;
+    let encoded_ = info["encoded"].as_str().ok_or(VcxError::from_msg(VcxErrorKind::InvalidProof, format!("Cannot get encoded value for \"{}\" attribute", referent)))?;
+
+    let expected_encoded = encode(&raw)?;
+
+    if expected_encoded != encoded_.to_string() {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidProof, format!("Encoded values are different. Expected: {}. From Proof: {}", expected_encoded, encoded_)));
+    }
+
+    Ok(())
+}
 
 fn validate_proof_revealed_attributes(proof_json: &str) -> VcxResult<()> {
     if settings::indy_mocks_enabled() { return Ok(()); }
@@ -17,25 +42,405 @@ fn validate_proof_revealed_attributes(proof_json: &str) -> VcxResult<()> {
     let proof: Value = serde_json::from_str(proof_json)
         .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize libndy proof: {}", err)))?;
 
-    let revealed_attrs = match proof["requested_proof"]["revealed_attrs"].as_object() {
-        Some(revealed_attrs) => revealed_attrs,
-        None => return Ok(())
-    };
+    for (attr1_referent, info) in _entries(&proof["requested_proof"]["revealed_attrs"]) {
+        _validate_encoded_value(attr1_referent, info)?;
+    }
+
+    for (group_referent, group) in _entries(&proof["requested_proof"]["revealed_attr_groups"]) {
+        for (name, info) in _entries(&group["values"]) {
+            _validate_encoded_value(&format!("{}.{}", group_referent, name), info)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Requested attribute or predicate answered by a credential which does not satisfy restrictions of the proof request,
+/// or restricted attribute answered self attested, in which case there is no credential to refer to.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RestrictionMismatch {
+    pub referent: String,
+    pub schema_id: Option<String>,
+    pub cred_def_id: Option<String>,
+    pub restrictions: Value,
+}
+
+// Ids may be fully qualified, the DID is the last segment preceding the object type marker
+fn _issuer_did(id: &str, marker: &str) -> Option<String> {
+    id.find(marker)
+        .and_then(|index| id[..index].rsplit(':').next())
+        .map(String::from)
+}
+
+fn _normalize_attr_name(name: &str) -> String {
+    name.replace(" ", "").to_lowercase()
+}
+
+// Identifiers are compared in the unqualified form, so that restrictions using `did:sov` or `did:indy`
+// identifiers match credentials referring to the same objects by legacy identifiers and vice versa
+fn _credential_tags(identifier: &CredInfoVerifier) -> HashMap<String, String> {
+    let mut tags = HashMap::new();
+
+    let schema_id = qualifier::unqualify(&identifier.schema_id);
+    let cred_def_id = qualifier::unqualify(&identifier.cred_def_id);
+
+    if let Some(ref rev_reg_id) = identifier.rev_reg_id {
+        tags.insert("rev_reg_id".to_string(), qualifier::unqualify(rev_reg_id));
+    }
+    if let Some(issuer_did) = _issuer_did(&cred_def_id, ":3:") {
+        tags.insert("issuer_did".to_string(), issuer_did);
+    }
+    if let Some(schema_issuer_did) = _issuer_did(&schema_id, ":2:") {
+        tags.insert("schema_issuer_did".to_string(), schema_issuer_did);
+    }
+
+    let mut schema_parts = schema_id.rsplit(':');
+    if let (Some(version), Some(name)) = (schema_parts.next(), schema_parts.next()) {
+        tags.insert("schema_version".to_string(), version.to_string());
+        tags.insert("schema_name".to_string(), name.to_string());
+    }
+
+    tags.insert("schema_id".to_string(), schema_id);
+    tags.insert("cred_def_id".to_string(), cred_def_id);
+
+    tags
+}
+
+fn _add_attr_tag(tags: &mut HashMap<String, String>, name: &str, raw: Option<&str>) {
+    let name = _normalize_attr_name(name);
+    tags.insert(format!("attr::{}::marker", name), "1".to_string());
+    if let Some(raw) = raw {
+        tags.insert(format!("attr::{}::value", name), raw.to_string());
+    }
+}
+
+// `attr::<name>::value` and `attr::<name>::marker` tags split into the attribute name and the tag kind
+fn _attr_tag(key: &str) -> Option<(&str, &str)> {
+    if !key.starts_with("attr::") { return None; }
+
+    let rest = &key["attr::".len()..];
+    rest.rfind("::").map(|index| (&rest[..index], &rest[index + 2..]))
+}
+
+// credential attribute names are compared case insensitive and ignoring spaces, the same way as libindy does
+fn _normalize_attr_tags(query: Value) -> Value {
+    match query {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    match _attr_tag(&key) {
+                        Some((name, "marker")) => (format!("attr::{}::marker", _normalize_attr_name(name)), _marker_condition(value)),
+                        Some((name, kind)) => (format!("attr::{}::{}", _normalize_attr_name(name), kind), value),
+                        None => (key, _normalize_attr_tags(value))
+                    }
+                })
+                .collect()),
+        Value::Array(items) => Value::Array(items.into_iter().map(_normalize_attr_tags).collect()),
+        value => value
+    }
+}
+
+// marker only requires presence of the attribute, empty value is taken the same as "1"
+fn _marker_condition(condition: Value) -> Value {
+    match condition {
+        Value::String(ref value) if value.is_empty() => json!("1"),
+        condition => condition
+    }
+}
+
+/// Checks `attr::<name>::value` and `attr::<name>::marker` conditions of restrictions query,
+/// the marker accepts only "1" (or empty value) since it just tells the attribute is present in the credential.
+pub fn validate_attr_restrictions(query: &Value) -> VcxResult<()> {
+    match query {
+        Value::Object(map) => {
+            for (key, value) in map {
+                if !key.starts_with("attr::") {
+                    validate_attr_restrictions(value)?;
+                    continue;
+                }
+                match (_attr_tag(key), value) {
+                    (Some((name, "value")), _) if !name.trim().is_empty() => {}
+                    (Some((name, "marker")), Value::String(marker)) if !name.trim().is_empty() && (marker == "1" || marker.is_empty()) => {}
+                    (Some((name, "marker")), _) if !name.trim().is_empty() => {
+                        return Err(VcxError::from_msg(VcxErrorKind::InvalidProofRequest,
+                                                      format!("Invalid restriction `{}: {}`, attribute marker must be \"1\"", key, value)));
+                    }
+                    _ => return Err(VcxError::from_msg(VcxErrorKind::InvalidProofRequest,
+                                                       format!("Invalid restriction `{}`, expected `attr::<name>::value` or `attr::<name>::marker`", key)))
+                }
+            }
+            Ok(())
+        }
+        Value::Array(items) => items.iter().map(validate_attr_restrictions).collect(),
+        _ => Ok(())
+    }
+}
+
+fn _unqualify_identifiers(query: Value) -> Value {
+    match query {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    let value = if IDENTIFIER_TAGS.contains(&key.as_str()) {
+                        _unqualify_values(value)
+                    } else {
+                        _unqualify_identifiers(value)
+                    };
+                    (key, value)
+                })
+                .collect()),
+        Value::Array(items) => Value::Array(items.into_iter().map(_unqualify_identifiers).collect()),
+        value => value
+    }
+}
+
+// identifier may be compared by an operator: {"$in": [..]}, {"$neq": ..}
+fn _unqualify_values(value: Value) -> Value {
+    match value {
+        Value::String(value) => Value::String(qualifier::unqualify(&value)),
+        Value::Array(items) => Value::Array(items.into_iter().map(_unqualify_values).collect()),
+        Value::Object(map) => Value::Object(map.into_iter().map(|(key, value)| (key, _unqualify_values(value))).collect()),
+        value => value
+    }
+}
+
+fn _restrictions_query(restrictions: &Restrictions) -> Value {
+    _normalize_attr_tags(_unqualify_identifiers(match restrictions {
+        Restrictions::V2(query) => query.clone(),
+        Restrictions::V1(filters) => Value::Array(
+            filters.iter()
+                .map(|filter| {
+                    let fields = json!(filter).as_object().cloned().unwrap_or_default()
+                        .into_iter()
+                        .filter(|(_, value)| !value.is_null())
+                        .collect();
+                    Value::Object(fields)
+                })
+                .collect())
+    }))
+}
+
+fn _sub_proof_index(referent: &str, value: &Value, identifiers: &[CredInfoVerifier]) -> VcxResult<usize> {
+    value["sub_proof_index"].as_u64()
+        .map(|index| index as usize)
+        .filter(|index| *index < identifiers.len())
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidProofCredentialData,
+                                  format!("Cannot find credential identifier for \"{}\"", referent)))
+}
+
+fn _entries(value: &Value) -> Vec<(&String, &Value)> {
+    value.as_object().map(|map| map.iter().collect()).unwrap_or_default()
+}
+
+/// Checks every credential used in the proof against restrictions placed on the referent it answers.
+/// Returns list of referents whose credential does not satisfy the restrictions,
+/// including restricted attributes answered self attested.
+pub fn get_restriction_mismatches(proof_json: &str, proof_req_json: &str) -> VcxResult<Vec<RestrictionMismatch>> {
+    let proof: Value = serde_json::from_str(proof_json)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize libndy proof: {}", err)))?;
+
+    let proof_req: ProofRequestData = serde_json::from_str(proof_req_json)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize proof request: {}", err)))?;
 
-    for (attr1_referent, info) in revealed_attrs.iter() {
-        let raw = info["raw"].as_str().ok_or(VcxError::from_msg(VcxErrorKind::InvalidProof, format!("Cannot get raw value for \"{}\" attribute", attr1_referent)))?;
-        let encoded_ = info["encoded"].as_str().ok_or(VcxError::from_msg(VcxErrorKind::InvalidProof, format!("Cannot get encoded value for \"{}\" attribute", attr1_referent)))?;
+    let identifiers = get_credential_info(proof_json)?;
+    let requested_proof = &proof["requested_proof"];
 
-        let expected_encoded = encode(&raw)?;
+    let attr_restrictions = |referent: &str| proof_req.requested_attributes.get(referent)
+        .and_then(|requested| requested.restrictions.as_ref());
+
+    // (referent, sub proof index, restrictions) of every answer backed by a credential
+    let mut answers: Vec<(&String, usize, Option<&Restrictions>)> = Vec::new();
+    let mut tags: Vec<HashMap<String, String>> = identifiers.iter().map(_credential_tags).collect();
+
+    for (referent, attr) in _entries(&requested_proof["revealed_attrs"]) {
+        let index = _sub_proof_index(referent, attr, &identifiers)?;
+        if let Some(name) = proof_req.requested_attributes.get(referent.as_str()).and_then(|requested| requested.name.as_ref()) {
+            _add_attr_tag(&mut tags[index], name, attr["raw"].as_str());
+        }
+        answers.push((referent, index, attr_restrictions(referent)));
+    }
+
+    for (referent, group) in _entries(&requested_proof["revealed_attr_groups"]) {
+        let index = _sub_proof_index(referent, group, &identifiers)?;
+        for (name, value) in _entries(&group["values"]) {
+            _add_attr_tag(&mut tags[index], name, value["raw"].as_str());
+        }
+        answers.push((referent, index, attr_restrictions(referent)));
+    }
 
-        if expected_encoded != encoded_.to_string() {
-            return Err(VcxError::from_msg(VcxErrorKind::InvalidProof, format!("Encoded values are different. Expected: {}. From Proof: {}", expected_encoded, encoded_)));
+    for (referent, attr) in _entries(&requested_proof["unrevealed_attrs"]) {
+        let index = _sub_proof_index(referent, attr, &identifiers)?;
+        if let Some(name) = proof_req.requested_attributes.get(referent.as_str()).and_then(|requested| requested.name.as_ref()) {
+            _add_attr_tag(&mut tags[index], name, None);
         }
+        answers.push((referent, index, attr_restrictions(referent)));
+    }
+
+    for (referent, predicate) in _entries(&requested_proof["predicates"]) {
+        let index = _sub_proof_index(referent, predicate, &identifiers)?;
+        let requested = proof_req.requested_predicates.get(referent.as_str());
+        if let Some(requested) = requested {
+            _add_attr_tag(&mut tags[index], &requested.name, None);
+        }
+        answers.push((referent, index, requested.and_then(|requested| requested.restrictions.as_ref())));
+    }
+
+    let mut mismatches = Vec::new();
+    for (referent, index, restrictions) in answers {
+        let query = match restrictions {
+            Some(restrictions) => _restrictions_query(restrictions),
+            None => continue
+        };
+        if !wql::matches(&query, &tags[index])? {
+            mismatches.push(RestrictionMismatch {
+                referent: referent.to_string(),
+                schema_id: Some(identifiers[index].schema_id.clone()),
+                cred_def_id: Some(identifiers[index].cred_def_id.clone()),
+                restrictions: query,
+            });
+        }
+    }
+
+    // self attested value has no credential behind it, so it only meets restrictions which accept anything (e.g. `{}`)
+    for (referent, _) in _entries(&requested_proof["self_attested_attrs"]) {
+        let query = match attr_restrictions(referent) {
+            Some(restrictions) => _restrictions_query(restrictions),
+            None => continue
+        };
+        if !wql::matches(&query, &HashMap::new())? {
+            mismatches.push(RestrictionMismatch {
+                referent: referent.to_string(),
+                schema_id: None,
+                cred_def_id: None,
+                restrictions: query,
+            });
+        }
+    }
+    mismatches.sort_by(|a, b| a.referent.cmp(&b.referent));
+
+    Ok(mismatches)
+}
+
+/// Fails with `ProofRestrictionsMismatch` when any credential used in the proof does not satisfy the request restrictions.
+/// The error message holds JSON list of the mismatches.
+pub fn validate_proof_restrictions(proof_json: &str, proof_req_json: &str) -> VcxResult<()> {
+    if settings::indy_mocks_enabled() { return Ok(()); }
+
+    let mismatches = get_restriction_mismatches(proof_json, proof_req_json)?;
+    if !mismatches.is_empty() {
+        return Err(VcxError::from_msg(VcxErrorKind::ProofRestrictionsMismatch, json!(mismatches).to_string()));
     }
 
     Ok(())
 }
 
+/// Problem found in the revocation data the prover used for a referent.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RevocationIssue {
+    /// Credential is revocable but the proof does not refer to any state of its revocation registry
+    MissingTimestamp,
+    /// State of the revocation registry lies outside of the requested `non_revoked` interval
+    OutOfInterval,
+    /// Revocation registry was updated after the state used by the prover and still within the interval
+    Stale,
+}
+
+/// Revocation check of a requested attribute or predicate with `non_revoked` interval.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RevocationCheck {
+    pub referent: String,
+    pub rev_reg_id: Option<String>,
+    pub timestamp: Option<u64>,
+    pub non_revoked: NonRevokedInterval,
+    /// Timestamp of the latest revocation registry entry on the ledger within the interval
+    pub latest_entry_timestamp: u64,
+    pub issues: Vec<RevocationIssue>,
+}
+
+///
+/// Compares timestamps the prover used for revocable credentials with `non_revoked` intervals of the proof request
+/// and with revocation registry entries on the ledger. Interval without `to` ends at `now`.
+/// Referents without interval and credentials which are not revocable are not checked.
+///
+pub fn get_revocation_checks(proof_json: &str, proof_req_json: &str, now: u64) -> VcxResult<Vec<RevocationCheck>> {
+    let proof: Value = serde_json::from_str(proof_json)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize libndy proof: {}", err)))?;
+
+    let proof_req: ProofRequestData = serde_json::from_str(proof_req_json)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize proof request: {}", err)))?;
+
+    let identifiers = get_credential_info(proof_json)?;
+    let requested_proof = &proof["requested_proof"];
+
+    let mut answers: Vec<(&String, usize, Option<NonRevokedInterval>)> = Vec::new();
+
+    for list in &["revealed_attrs", "revealed_attr_groups", "unrevealed_attrs"] {
+        for (referent, attr) in _entries(&requested_proof[*list]) {
+            let requested = proof_req.requested_attributes.get(referent.as_str());
+            let interval = requested.and_then(|requested| requested.non_revoked.clone());
+            answers.push((referent, _sub_proof_index(referent, attr, &identifiers)?, interval));
+        }
+    }
+
+    for (referent, predicate) in _entries(&requested_proof["predicates"]) {
+        let requested = proof_req.requested_predicates.get(referent.as_str());
+        let interval = requested.and_then(|requested| requested.non_revoked.clone());
+        answers.push((referent, _sub_proof_index(referent, predicate, &identifiers)?, interval));
+    }
+
+    // latest registry entries up to the end of interval, many referents usually share them
+    let mut latest_entries: HashMap<(String, u64), u64> = HashMap::new();
+    let mut checks = Vec::new();
+
+    for (referent, index, interval) in answers {
+        let non_revoked = match interval.or(proof_req.non_revoked.clone()) {
+            Some(non_revoked) => non_revoked,
+            None => continue
+        };
+        let identifier = &identifiers[index];
+        let rev_reg_id = match identifier.rev_reg_id {
+            Some(ref rev_reg_id) => rev_reg_id,
+            None => continue
+        };
+
+        let to = non_revoked.to.unwrap_or(now);
+        let mut issues = Vec::new();
+
+        let latest_entry_timestamp = match latest_entries.get(&(rev_reg_id.to_string(), to)) {
+            Some(timestamp) => *timestamp,
+            None => {
+                let (_, _, timestamp) = anoncreds::get_rev_reg(rev_reg_id, to)?;
+                latest_entries.insert((rev_reg_id.to_string(), to), timestamp);
+                timestamp
+            }
+        };
+
+        match identifier.timestamp {
+            Some(timestamp) => {
+                if timestamp < non_revoked.from.unwrap_or(0) || timestamp > to {
+                    issues.push(RevocationIssue::OutOfInterval);
+                } else if latest_entry_timestamp > timestamp {
+                    issues.push(RevocationIssue::Stale);
+                }
+            }
+            None => issues.push(RevocationIssue::MissingTimestamp)
+        }
+
+        checks.push(RevocationCheck {
+            referent: referent.to_string(),
+            rev_reg_id: identifier.rev_reg_id.clone(),
+            timestamp: identifier.timestamp,
+            non_revoked,
+            latest_entry_timestamp,
+            issues,
+        });
+    }
+    checks.sort_by(|a, b| a.referent.cmp(&b.referent));
+
+    Ok(checks)
+}
+
 fn build_cred_defs_json_verifier(credential_data: &Vec<CredInfoVerifier>) -> VcxResult<String> {
     debug!("building credential_def_json for proof validation");
     let mut credential_json = json!({});
@@ -136,17 +541,23 @@ pub fn validate_indy_proof(proof_json: &str, proof_req_json: &str) -> VcxResult<
     }
 
     validate_proof_revealed_attributes(&proof_json)?;
+    validate_proof_restrictions(&proof_json, &proof_req_json)?;
 
     let credential_data = get_credential_info(&proof_json)?;
 
+    let rev_regs_json = build_rev_reg_json(&credential_data)
+        .unwrap_or(json!({}).to_string());
+
+    _verify_indy_proof(proof_json, proof_req_json, &credential_data, &rev_regs_json)
+}
+
+fn _verify_indy_proof(proof_json: &str, proof_req_json: &str, credential_data: &Vec<CredInfoVerifier>, rev_regs_json: &str) -> VcxResult<bool> {
     let credential_defs_json = build_cred_defs_json_verifier(&credential_data)
         .unwrap_or(json!({}).to_string());
     let schemas_json = build_schemas_json_verifier(&credential_data)
         .unwrap_or(json!({}).to_string());
     let rev_reg_defs_json = build_rev_reg_defs_json(&credential_data)
         .unwrap_or(json!({}).to_string());
-    let rev_regs_json = build_rev_reg_json(&credential_data)
-        .unwrap_or(json!({}).to_string());
 
     debug!("*******\n{}\n********", credential_defs_json);
     debug!("*******\n{}\n********", schemas_json);
@@ -159,7 +570,125 @@ pub fn validate_indy_proof(proof_json: &str, proof_req_json: &str) -> VcxResult<
                                              &schemas_json,
                                              &credential_defs_json,
                                              &rev_reg_defs_json,
-                                             &rev_regs_json)
+                                             rev_regs_json)
+}
+
+///
+/// Verifies presentation against the request it answers, in whichever format was requested.
+/// Presentations which do not hold are reported as `InvalidProof`, other errors mean they could not be verified at all.
+/// The request is checked for expiration at the time the presentation was sent, not when it is verified.
+///
+pub fn validate_presentation(presentation_request: &PresentationRequest, presentation: &Presentation) -> VcxResult<()> {
+    if let Some(ref timing) = presentation_request.timing {
+        timing.check_not_expired_at(presentation.arrival_time())?;
+    }
+    validate_presentation_content(presentation_request, presentation)
+}
+
+/// Verifies that `presentation` answers `presentation_request`, regardless of the request expiration.
+pub fn validate_presentation_content(presentation_request: &PresentationRequest, presentation: &Presentation) -> VcxResult<()> {
+    if presentation.format() != presentation_request.format() {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidMessages, "Presentation is not in the requested format"));
+    }
+
+    match presentation_request.format() {
+        PresentationFormat::Indy => {
+            let valid = validate_indy_proof(&presentation.presentations_attach.content()?,
+                                            &presentation_request.request_presentations_attach.content()?)?;
+
+            if !valid {
+                return Err(VcxError::from_msg(VcxErrorKind::InvalidProof, "Presentation verification failed"));
+            }
+        }
+        PresentationFormat::PresentationExchange => {
+            validate_presentation_exchange(&presentation.presentations_attach.content()?,
+                                           &presentation_request.request_presentations_attach.content()?)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Verifies DIF Presentation Exchange submission against the request it was made for.
+pub fn validate_presentation_exchange(presentation_json: &str, request_json: &str) -> VcxResult<()> {
+    let request = PresentationExchangeRequest::from_str(request_json)?;
+    VerifiablePresentation::from_str(presentation_json)?.verify(&request)
+}
+
+fn check_proof_timestamps(credential_data: &Vec<CredInfoVerifier>, as_of: u64) -> VcxResult<()> {
+    for cred_info in credential_data.iter() {
+        if let Some(timestamp) = cred_info.timestamp {
+            if timestamp > as_of {
+                return Err(VcxError::from_msg(VcxErrorKind::InvalidRevocationTimestamp,
+                                              format!("Proof refers to state of revocation registry {:?} at {} which is later than {}", cred_info.rev_reg_id, timestamp, as_of)));
+            }
+        }
+    }
+    Ok(())
+}
+
+// revocation registries in the state the prover referred to, which precedes `as_of` (see `check_proof_timestamps`)
+fn build_rev_reg_json_as_of(credential_data: &Vec<CredInfoVerifier>, as_of: u64) -> VcxResult<String> {
+    debug!("building rev_reg_json as of {} for proof validation", as_of);
+
+    let mut rev_regs_json = json!({});
+
+    for ref cred_info in credential_data.iter() {
+        let (rev_reg_id, timestamp) = match (&cred_info.rev_reg_id, cred_info.timestamp) {
+            (Some(rev_reg_id), Some(timestamp)) => (rev_reg_id, timestamp),
+            (Some(_), None) => return Err(VcxError::from(VcxErrorKind::InvalidRevocationTimestamp)),
+            _ => continue
+        };
+
+        if rev_regs_json[rev_reg_id].get(timestamp.to_string()).is_none() {
+            let (_, json, _) = anoncreds::get_rev_reg(rev_reg_id, timestamp)
+                .or(Err(VcxError::from(VcxErrorKind::InvalidRevocationDetails)))?;
+
+            let rev_reg_json: Value = serde_json::from_str(&json)
+                .or(Err(VcxError::from(VcxErrorKind::InvalidJson)))?;
+
+            if rev_regs_json.get(rev_reg_id).is_none() {
+                rev_regs_json[rev_reg_id] = json!({});
+            }
+            rev_regs_json[rev_reg_id][timestamp.to_string()] = rev_reg_json;
+        }
+    }
+
+    Ok(rev_regs_json.to_string())
+}
+
+///
+/// Verifies previously received proof against the ledger as it was at `as_of` (unix timestamp).
+/// Schemas, credential definitions and revocation registry definitions are never changed once written,
+/// they only have to be written before `as_of`. Revocation registries are taken in the state the prover referred to,
+/// which has to precede `as_of`, so that changes of the registry (e.g. revocations) after that state do not matter.
+///
+pub fn validate_indy_proof_as_of(proof_json: &str, proof_req_json: &str, as_of: u64) -> VcxResult<bool> {
+    let credential_data = get_credential_info(&proof_json)?;
+    check_proof_timestamps(&credential_data, as_of)?;
+
+    if let Some(mock_result) = get_mock_result_for_validate_indy_proof() {
+        return mock_result;
+    }
+
+    validate_proof_revealed_attributes(&proof_json)?;
+    validate_proof_restrictions(&proof_json, &proof_req_json)?;
+
+    let mut ids = BTreeSet::new();
+    for cred_info in credential_data.iter() {
+        ids.insert(cred_info.schema_id.as_str());
+        ids.insert(cred_info.cred_def_id.as_str());
+        if let Some(ref rev_reg_id) = cred_info.rev_reg_id {
+            ids.insert(rev_reg_id.as_str());
+        }
+    }
+    for id in ids {
+        ledger::check_written_before(id, as_of)?;
+    }
+
+    let rev_regs_json = build_rev_reg_json_as_of(&credential_data, as_of)?;
+
+    _verify_indy_proof(proof_json, proof_req_json, &credential_data, &rev_regs_json)
 }
 
 #[cfg(test)]
@@ -453,4 +982,243 @@ pub mod tests {
             assert_eq!(validate_indy_proof(&prover_proof_json, &proof_req_json).unwrap_err().kind(), VcxErrorKind::InvalidProof);
         }
     }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_validate_revealed_attribute_groups() {
+        let _setup = SetupDefaults::init();
+
+        let mut proof = json!({
+            "requested_proof": {
+                "revealed_attrs": {"name_1": {"sub_proof_index": 0, "raw": "alice", "encoded": encode("alice").unwrap()}},
+                "revealed_attr_groups": {"address_2": {"sub_proof_index": 0, "values": {
+                    "street": {"raw": "Main", "encoded": encode("Main").unwrap()},
+                    "city": {"raw": "Prague", "encoded": encode("Prague").unwrap()}
+                }}}
+            }
+        });
+        validate_proof_revealed_attributes(&proof.to_string()).unwrap();
+
+        proof["requested_proof"]["revealed_attr_groups"]["address_2"]["values"]["city"]["raw"] = json!("Brno");
+        assert_eq!(validate_proof_revealed_attributes(&proof.to_string()).unwrap_err().kind(), VcxErrorKind::InvalidProof);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_check_proof_timestamps() {
+        let _setup = SetupDefaults::init();
+
+        let credential_data = vec![
+            CredInfoVerifier { schema_id: SCHEMA_ID.to_string(), cred_def_id: CRED_DEF_ID.to_string(), rev_reg_id: None, timestamp: None },
+            CredInfoVerifier { schema_id: SCHEMA_ID.to_string(), cred_def_id: CRED_DEF_ID.to_string(), rev_reg_id: Some(REV_REG_ID.to_string()), timestamp: Some(1000) },
+        ];
+
+        check_proof_timestamps(&credential_data, 1000).unwrap();
+        assert_eq!(check_proof_timestamps(&credential_data, 999).unwrap_err().kind(), VcxErrorKind::InvalidRevocationTimestamp);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_revocation_checks() {
+        let _setup = SetupIndyMocks::init();
+
+        let proof_req = json!({
+            "nonce": "123432421212",
+            "name": "proof_req_1",
+            "version": "0.1",
+            "requested_attributes": {
+                "a": {"name": "name"},
+                "b": {"name": "name", "non_revoked": {"from": 2, "to": 100}},
+                "d": {"name": "email"},
+                "e": {"name": "name"}
+            },
+            "requested_predicates": {
+                "c": {"name": "age", "p_type": ">=", "p_value": 18}
+            },
+            "non_revoked": {"to": 100}
+        }).to_string();
+
+        let identifier = |rev_reg_id: Option<&str>, timestamp: Option<u64>|
+            json!({"schema_id": SCHEMA_ID, "cred_def_id": CRED_DEF_ID, "rev_reg_id": rev_reg_id, "timestamp": timestamp});
+        let proof = json!({
+            "proof": {},
+            "requested_proof": {
+                "revealed_attrs": {
+                    "a": {"sub_proof_index": 0, "raw": "alice", "encoded": "1"},
+                    "b": {"sub_proof_index": 0, "raw": "alice", "encoded": "1"},
+                    "d": {"sub_proof_index": 2, "raw": "alice@example.com", "encoded": "2"}
+                },
+                "unrevealed_attrs": {"e": {"sub_proof_index": 3}},
+                "predicates": {"c": {"sub_proof_index": 1}},
+                "self_attested_attrs": {}
+            },
+            "identifiers": [
+                identifier(Some(REV_REG_ID), Some(1)),
+                identifier(Some(REV_REG_ID), None),
+                identifier(None, None),
+                identifier(Some(REV_REG_ID), Some(0))
+            ]
+        }).to_string();
+
+        let checks = get_revocation_checks(&proof, &proof_req, 1000).unwrap();
+        let issues: Vec<(&str, Vec<RevocationIssue>)> = checks.iter()
+            .map(|check| (check.referent.as_str(), check.issues.clone()))
+            .collect();
+
+        assert_eq!(issues, vec![
+            ("a", vec![]),
+            ("b", vec![RevocationIssue::OutOfInterval]),
+            ("c", vec![RevocationIssue::MissingTimestamp]),
+            ("e", vec![RevocationIssue::Stale]),
+        ]);
+        assert_eq!(checks[0].latest_entry_timestamp, 1);
+        assert_eq!(checks[1].non_revoked, NonRevokedInterval { from: Some(2), to: Some(100) });
+    }
+
+    fn _restricted_proof_req(name_restrictions: Value, age_restrictions: Value) -> String {
+        json!({
+            "nonce": "123432421212",
+            "name": "proof_req_1",
+            "version": "0.1",
+            "requested_attributes": {
+                "name_1": {"name": "Name", "restrictions": name_restrictions},
+                "address_2": {"names": ["street", "city"], "restrictions": {"attr::city::value": "Prague"}}
+            },
+            "requested_predicates": {
+                "age_3": {"name": "age", "p_type": ">=", "p_value": 18, "restrictions": age_restrictions}
+            }
+        }).to_string()
+    }
+
+    fn _restricted_proof() -> String {
+        json!({
+            "proof": {},
+            "requested_proof": {
+                "revealed_attrs": {"name_1": {"sub_proof_index": 0, "raw": "alice", "encoded": "123"}},
+                "revealed_attr_groups": {"address_2": {"sub_proof_index": 1, "values": {"street": {"raw": "Main", "encoded": "1"}, "city": {"raw": "Prague", "encoded": "2"}}}},
+                "self_attested_attrs": {},
+                "unrevealed_attrs": {},
+                "predicates": {"age_3": {"sub_proof_index": 0}}
+            },
+            "identifiers": [
+                {"schema_id": SCHEMA_ID, "cred_def_id": CRED_DEF_ID, "rev_reg_id": null, "timestamp": null},
+                {"schema_id": ADDRESS_SCHEMA_ID, "cred_def_id": ADDRESS_CRED_DEF_ID, "rev_reg_id": null, "timestamp": null}
+            ]
+        }).to_string()
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_restriction_mismatches_for_satisfied_restrictions() {
+        let _setup = SetupDefaults::init();
+
+        let proof_req = _restricted_proof_req(
+            json!([{"cred_def_id": "other"}, {"issuer_did": "2hoqvcwupRTUNkXn6ArYzs", "schema_name": "test-licence", "schema_version": "4.4.4"}]),
+            json!({"$and": [{"schema_id": SCHEMA_ID}, {"attr::name::value": "alice"}, {"attr::age::marker": "1"}]}));
+
+        assert!(get_restriction_mismatches(&_restricted_proof(), &proof_req).unwrap().is_empty());
+        validate_proof_restrictions(&_restricted_proof(), &proof_req).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_restriction_mismatches_for_violated_restrictions() {
+        let _setup = SetupDefaults::init();
+
+        let proof_req = _restricted_proof_req(
+            json!([{"cred_def_id": ADDRESS_CRED_DEF_ID}]),
+            json!({"attr::age::value": "20"}));
+
+        let mismatches = get_restriction_mismatches(&_restricted_proof(), &proof_req).unwrap();
+        assert_eq!(mismatches.len(), 2);
+        assert_eq!(mismatches[0].referent, "age_3");
+        assert_eq!(mismatches[1].referent, "name_1");
+        assert_eq!(mismatches[1].cred_def_id, Some(CRED_DEF_ID.to_string()));
+        assert_eq!(mismatches[1].restrictions, json!([{"cred_def_id": ADDRESS_CRED_DEF_ID}]));
+
+        let err = validate_proof_restrictions(&_restricted_proof(), &proof_req).unwrap_err();
+        assert_eq!(err.kind(), VcxErrorKind::ProofRestrictionsMismatch);
+        let reported: Vec<RestrictionMismatch> = serde_json::from_str(&::failure::Fail::find_root_cause(&err).to_string()).unwrap();
+        assert_eq!(reported, mismatches);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_restriction_mismatches_for_attribute_restrictions() {
+        let _setup = SetupDefaults::init();
+
+        // names are compared normalized, empty marker value only requires presence of the attribute
+        let proof_req = _restricted_proof_req(
+            json!({"attr::Name::value": "alice", "attr::street::marker": "", "attr::City::value": {"$in": ["Prague", "Brno"]}}),
+            json!({"attr::A ge::marker": "1"}));
+        assert!(get_restriction_mismatches(&_restricted_proof(), &proof_req).unwrap().is_empty());
+
+        // value of an attribute not revealed in the proof cannot be confirmed
+        let proof_req = _restricted_proof_req(json!({}), json!({"attr::age::value": ""}));
+        let mismatches = get_restriction_mismatches(&_restricted_proof(), &proof_req).unwrap();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].referent, "age_3");
+
+        let mut proof: Value = serde_json::from_str(&_restricted_proof()).unwrap();
+        proof["requested_proof"]["revealed_attrs"]["name_1"]["raw"] = json!("");
+        let proof_req = _restricted_proof_req(json!({"attr::name::value": ""}), json!({}));
+        assert!(get_restriction_mismatches(&proof.to_string(), &proof_req).unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_restriction_mismatches_for_self_attested_attribute() {
+        let _setup = SetupDefaults::init();
+
+        let mut proof: Value = serde_json::from_str(&_restricted_proof()).unwrap();
+        proof["requested_proof"]["revealed_attrs"] = json!({});
+        proof["requested_proof"]["self_attested_attrs"] = json!({"name_1": "alice"});
+        let proof = proof.to_string();
+
+        let proof_req = _restricted_proof_req(json!({}), json!({}));
+        assert!(get_restriction_mismatches(&proof, &proof_req).unwrap().is_empty());
+
+        let proof_req = _restricted_proof_req(json!([{"cred_def_id": CRED_DEF_ID}]), json!({}));
+        let mismatches = get_restriction_mismatches(&proof, &proof_req).unwrap();
+        assert_eq!(mismatches, vec![RestrictionMismatch {
+            referent: "name_1".to_string(),
+            schema_id: None,
+            cred_def_id: None,
+            restrictions: json!([{"cred_def_id": CRED_DEF_ID}]),
+        }]);
+        assert_eq!(validate_proof_restrictions(&proof, &proof_req).unwrap_err().kind(), VcxErrorKind::ProofRestrictionsMismatch);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_validate_attr_restrictions() {
+        validate_attr_restrictions(&json!({"attr::name::value": "alice", "attr::age::marker": "1"})).unwrap();
+        validate_attr_restrictions(&json!([{"$or": [{"attr::name::marker": ""}, {"issuer_did": "did"}]}])).unwrap();
+
+        assert_eq!(validate_attr_restrictions(&json!({"attr::name::marker": "0"})).unwrap_err().kind(), VcxErrorKind::InvalidProofRequest);
+        assert_eq!(validate_attr_restrictions(&json!({"$and": [{"attr::name": "alice"}]})).unwrap_err().kind(), VcxErrorKind::InvalidProofRequest);
+        assert_eq!(validate_attr_restrictions(&json!({"attr::::value": "alice"})).unwrap_err().kind(), VcxErrorKind::InvalidProofRequest);
+        assert_eq!(validate_attr_restrictions(&json!({"attr::name::raw": "alice"})).unwrap_err().kind(), VcxErrorKind::InvalidProofRequest);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_restriction_mismatches_compares_unqualified_identifiers() {
+        let _setup = SetupDefaults::init();
+
+        let proof_req = _restricted_proof_req(
+            json!([{"cred_def_id": format!("creddef:sov:did:sov:{}", CRED_DEF_ID)}]),
+            json!({"schema_issuer_did": {"$in": ["did:indy:sovrin:2hoqvcwupRTUNkXn6ArYzs"]}}));
+
+        assert!(get_restriction_mismatches(&_restricted_proof(), &proof_req).unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_validate_proof_restrictions_skipped_with_mocks() {
+        let _setup = SetupAriesMocks::init();
+
+        let proof_req = _restricted_proof_req(json!({"cred_def_id": "other"}), json!({}));
+        validate_proof_restrictions(&_restricted_proof(), &proof_req).unwrap();
+    }
 }
@@ -8,9 +8,14 @@ use messages::ObjectWithVersion;
 use utils::constants::DEFAULT_SERIALIZE_VERSION;
 use utils::libindy::anoncreds;
 use utils::libindy::ledger;
-use utils::libindy::payments::PaymentTxn;
+use utils::libindy::payments::{LedgerTxn, PaymentTxn};
+use utils::libindy::pool;
+use utils::libindy::wallet;
 use utils::object_cache::ObjectCache;
 
+static SCHEMA_RECORD_TYPE: &str = "VcxSchema";
+const LIST_PAGE_SIZE: usize = 50;
+
 lazy_static! {
     static ref SCHEMA_MAP: ObjectCache<CreateSchema> = ObjectCache::<CreateSchema>::new("schemas-cache");
 }
@@ -23,6 +28,19 @@ pub struct SchemaData {
     attr_names: Vec<String>,
 }
 
+/// Schema described by its ledger identifier, as listed by `list_created_schemas` and `get_schema_attrs_from_ledger`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SchemaInfo {
+    pub schema_id: String,
+    pub name: String,
+    pub version: String,
+    pub attr_names: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<PublicEntityStateType>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct CreateSchema {
     data: Vec<String>,
@@ -59,13 +77,58 @@ impl CreateSchema {
     }
 
     fn update_state(&mut self) -> VcxResult<u32> {
-        if anoncreds::get_schema_json(&self.schema_id).is_ok() {
-            self.state = PublicEntityStateType::Published
+        if self.state != PublicEntityStateType::Published && anoncreds::get_schema_json(&self.schema_id).is_ok() {
+            self.state = PublicEntityStateType::Published;
+            self.save_record();
         }
         Ok(self.state as u32)
     }
 
+    // records of created schemas only serve listing, failing to write one must not fail the schema itself
+    fn save_record(&self) {
+        let info = SchemaInfo {
+            schema_id: self.schema_id.clone(),
+            name: self.name.clone(),
+            version: self.version.clone(),
+            attr_names: self.data.clone(),
+            source_id: Some(self.source_id.clone()),
+            state: Some(self.state),
+        };
+
+        let result = serde_json::to_string(&info)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize schema record: {:?}", err)))
+            .and_then(|value| {
+                match wallet::update_record_value(SCHEMA_RECORD_TYPE, &self.schema_id, &value) {
+                    Err(ref err) if err.kind() == VcxErrorKind::WalletRecordNotFound => {
+                        let tags = json!({"name": self.name, "version": self.version}).to_string();
+                        wallet::add_record(SCHEMA_RECORD_TYPE, &self.schema_id, &value, Some(&tags))
+                    }
+                    result => result
+                }
+            });
+
+        if let Err(err) = result {
+            warn!("Cannot store record of schema {}: {}", self.schema_id, err);
+        }
+    }
+
     fn get_state(&self) -> u32 { self.state as u32 }
+
+    fn prepare_for_endorser(&self, endorser: &str) -> VcxResult<String> {
+        if self.state == PublicEntityStateType::Published {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidState, format!("Schema {} is already published", self.schema_id)));
+        }
+
+        // schema is recreated from the stored attributes, its id depends on the current Institution DID
+        let (schema_id, schema) = anoncreds::create_schema(&self.name, &self.version, &json!(self.data).to_string())?;
+        if schema_id != self.schema_id {
+            return Err(VcxError::from_msg(VcxErrorKind::InvalidState,
+                                          format!("Schema {} was not created by the current Institution DID", self.schema_id)));
+        }
+
+        let schema_request = anoncreds::build_schema_request(&schema)?;
+        ledger::set_endorser(&schema_request, endorser)
+    }
 }
 
 pub fn create_and_publish_schema(source_id: &str,
@@ -76,12 +139,11 @@ pub fn create_and_publish_schema(source_id: &str,
     trace!("create_new_schema >>> source_id: {}, issuer_did: {}, name: {}, version: {}, data: {}", source_id, issuer_did, name, version, data);
     debug!("creating schema with source_id: {}, name: {}, issuer_did: {}", source_id, name, issuer_did);
 
-    let (schema_id, schema) = anoncreds::create_schema(&name, &version, &data)?;
-    let payment_txn = anoncreds::publish_schema(&schema)?;
+    let (schema_id, schema_txn) = _publish_schema(&issuer_did, &name, &version, &data, false)?;
 
     debug!("created schema on ledger with id: {}", schema_id);
 
-    let schema_handle = _store_schema(source_id, name, version, schema_id, data, payment_txn, PublicEntityStateType::Published)?;
+    let schema_handle = _store_schema(source_id, name, version, schema_id, data, schema_txn.payment, PublicEntityStateType::Published)?;
 
     Ok(schema_handle)
 }
@@ -106,6 +168,36 @@ pub fn prepare_schema_for_endorser(source_id: &str,
     Ok((schema_handle, schema_request))
 }
 
+/// Runs `create_and_publish_schema` as a dry run: the schema transaction is built, signed and priced
+/// but not submitted. Nothing is stored in the handle map, so the returned JSON is the only outcome of the call.
+pub fn dry_run_schema(source_id: &str,
+                      issuer_did: String,
+                      name: String,
+                      version: String,
+                      data: String) -> VcxResult<String> {
+    trace!("dry_run_schema >>> source_id: {}, issuer_did: {}, name: {}, version: {}, data: {}", source_id, issuer_did, name, version, data);
+
+    let (schema_id, schema_txn) = _publish_schema(&issuer_did, &name, &version, &data, true)?;
+
+    debug!("prepared dry run of schema with id: {}", schema_id);
+
+    Ok(json!({
+        "schema_id": schema_id,
+        "schema_txn": schema_txn,
+        "total_price": schema_txn.price,
+    }).to_string())
+}
+
+fn _publish_schema(issuer_did: &str, name: &str, version: &str, data: &str, dry_run: bool) -> VcxResult<(String, LedgerTxn)> {
+    // written to the network recorded in the issuer DID
+    let _network = pool::enter_network_of(issuer_did)?;
+
+    let (schema_id, schema) = anoncreds::create_schema(name, version, data)?;
+    let schema_txn = anoncreds::write_schema(&schema, dry_run)?;
+
+    Ok((schema_id, schema_txn))
+}
+
 fn _store_schema(source_id: &str,
                  name: String,
                  version: String,
@@ -122,11 +214,64 @@ fn _store_schema(source_id: &str,
         payment_txn,
         state,
     };
+    schema.save_record();
 
     SCHEMA_MAP.add(schema)
         .or(Err(VcxError::from(VcxErrorKind::CreateSchema)))
 }
 
+pub fn get_attr_names(schema_id: &str) -> VcxResult<Vec<String>> {
+    let (_, schema_data_json) = anoncreds::get_schema_json(schema_id)
+        .map_err(|err| err.map(VcxErrorKind::InvalidSchemaSeqNo, "Schema not found"))?;
+
+    let schema_data: SchemaData = serde_json::from_str(&schema_data_json)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize schema: {}", err)))?;
+
+    Ok(schema_data.attr_names)
+}
+
+/// Schema attributes read from the ledger, unlike `get_schema_attrs` no handle is created.
+pub fn get_schema_attrs_from_ledger(schema_id: &str) -> VcxResult<String> {
+    trace!("get_schema_attrs_from_ledger >>> schema_id: {}", schema_id);
+
+    let (schema_id, schema_data_json) = anoncreds::get_schema_json(schema_id)
+        .map_err(|err| err.map(VcxErrorKind::InvalidSchemaSeqNo, "Schema not found"))?;
+
+    let schema_data: SchemaData = serde_json::from_str(&schema_data_json)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize schema: {}", err)))?;
+
+    let info = SchemaInfo {
+        schema_id,
+        name: schema_data.name,
+        version: schema_data.version,
+        attr_names: schema_data.attr_names,
+        source_id: None,
+        state: None,
+    };
+
+    serde_json::to_string(&info)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize schema: {:?}", err)))
+}
+
+/// Schemas created or prepared for an endorser by this issuer, read from the wallet.
+pub fn list_created_schemas() -> VcxResult<String> {
+    trace!("list_created_schemas >>>");
+
+    let options = json!({"retrieveType": false, "retrieveValue": true, "retrieveTags": false}).to_string();
+    let mut schemas: Vec<SchemaInfo> = Vec::new();
+
+    for record in wallet::search_records(SCHEMA_RECORD_TYPE, "{}", &options, LIST_PAGE_SIZE)? {
+        let record = record?;
+        let value = record["value"].as_str()
+            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Schema record {:?} has no value", record["id"])))?;
+        schemas.push(serde_json::from_str(value)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize schema record: {:?}", err)))?);
+    }
+
+    serde_json::to_string(&schemas)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize schemas: {:?}", err)))
+}
+
 pub fn get_schema_attrs(source_id: String, schema_id: String) -> VcxResult<(u32, String)> {
     trace!("get_schema_attrs >>> source_id: {}, schema_id: {}", source_id, schema_id);
 
@@ -208,6 +353,14 @@ pub fn get_state(handle: u32) -> VcxResult<u32> {
     })
 }
 
+/// Builds the schema transaction of a not yet published schema again, signed by the Institution DID
+/// and ready to be submitted by `endorser`.
+pub fn prepare_for_endorser(handle: u32, endorser: &str) -> VcxResult<String> {
+    SCHEMA_MAP.get(handle, |s| {
+        s.prepare_for_endorser(endorser)
+    })
+}
+
 #[cfg(test)]
 pub mod tests {
     extern crate rand;
@@ -277,6 +430,20 @@ pub mod tests {
         check_schema(handle, &create_schema_json, &schema_id, &data);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_dry_run_schema() {
+        let _setup = SetupMocks::init();
+
+        let (did, schema_name, schema_version, data) = prepare_schema_data();
+        let dry_run = dry_run_schema("1", did, schema_name, schema_version, data).unwrap();
+        let dry_run: serde_json::Value = serde_json::from_str(&dry_run).unwrap();
+
+        assert_eq!(dry_run["schema_id"], json!(SCHEMA_ID));
+        assert!(dry_run["schema_txn"]["request"].is_object());
+        assert_eq!(dry_run["total_price"], json!(0));
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_create_schema_success() {
@@ -314,6 +481,45 @@ pub mod tests {
         check_schema(handle, &schema_json, SCHEMA_ID, r#"["name","age","height","sex"]"#);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_schema_attrs_from_ledger_without_handle() {
+        let _setup = SetupAriesMocks::init();
+
+        let schema: SchemaInfo = serde_json::from_str(&get_schema_attrs_from_ledger(SCHEMA_ID).unwrap()).unwrap();
+        assert_eq!(schema.schema_id, SCHEMA_ID);
+        assert_eq!(schema.attr_names.len(), 4);
+        assert_eq!(schema.source_id, None);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_list_created_schemas() {
+        let _setup = SetupLibraryWallet::init();
+
+        let mut schema = CreateSchema {
+            data: data(),
+            version: "1.0".to_string(),
+            schema_id: SCHEMA_ID.to_string(),
+            name: "test_list_created_schemas".to_string(),
+            source_id: "source".to_string(),
+            payment_txn: None,
+            state: PublicEntityStateType::Built,
+        };
+        schema.save_record();
+
+        // saving the published schema replaces its record
+        schema.state = PublicEntityStateType::Published;
+        schema.save_record();
+
+        let schemas: Vec<SchemaInfo> = serde_json::from_str(&list_created_schemas().unwrap()).unwrap();
+        assert_eq!(schemas.len(), 1);
+        assert_eq!(schemas[0].schema_id, SCHEMA_ID);
+        assert_eq!(schemas[0].attr_names, data());
+        assert_eq!(schemas[0].source_id, Some("source".to_string()));
+        assert_eq!(schemas[0].state, Some(PublicEntityStateType::Published));
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_create_schema_fails() {
@@ -404,6 +610,19 @@ pub mod tests {
         assert_eq!(release(h5).unwrap_err().kind(), VcxErrorKind::InvalidSchemaHandle);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_prepare_for_endorser_with_handle() {
+        let _setup = SetupAriesMocks::init();
+
+        let (did, schema_name, schema_version, data) = prepare_schema_data();
+        let (handle, _) = prepare_schema_for_endorser("1", did.clone(), schema_name.clone(), schema_version.clone(), data.clone(), "V4SGRU86Z58d6TV7PBUe6f".to_string()).unwrap();
+        assert_eq!(prepare_for_endorser(handle, "V4SGRU86Z58d6TV7PBUe6f").unwrap(), ::utils::constants::REQUEST_WITH_ENDORSER);
+
+        let handle = create_and_publish_schema("1", did, schema_name, schema_version, data).unwrap();
+        assert_eq!(prepare_for_endorser(handle, "V4SGRU86Z58d6TV7PBUe6f").unwrap_err().kind(), VcxErrorKind::InvalidState);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_handle_errors() {
@@ -1,10 +1,13 @@
 extern crate serde_json;
 extern crate url;
 
-use std::borrow::Borrow;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
 use std::path::Path;
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 use indy_sys::INVALID_WALLET_HANDLE;
 use serde_json::Value;
@@ -13,8 +16,9 @@ use url::Url;
 
 use error::prelude::*;
 use messages::validation;
-use utils::{error, get_temp_dir_path};
+use utils::{agent_context, error, get_temp_dir_path, key_provider};
 use utils::file::read_file;
+use utils::secret::{self, SecretString};
 
 pub static CONFIG_POOL_NAME: &str = "pool_name";
 pub static CONFIG_PROTOCOL_TYPE: &str = "protocol_type";
@@ -32,6 +36,8 @@ pub static CONFIG_INSTITUTION_VERKEY: &str = "institution_verkey";
 // functionally not used
 pub static CONFIG_INSTITUTION_NAME: &str = "institution_name";
 pub static CONFIG_INSTITUTION_LOGO_URL: &str = "institution_logo_url";
+// `~l10n` json with the institution locale and labels attached to credential offers and basic messages
+pub static CONFIG_INSTITUTION_L10N: &str = "institution_l10n";
 pub static CONFIG_WEBHOOK_URL: &str = "webhook_url";
 pub static CONFIG_ENABLE_TEST_MODE: &str = "enable_test_mode";
 pub static CONFIG_GENESIS_PATH: &str = "genesis_path";
@@ -44,6 +50,8 @@ pub static CONFIG_WALLET_NAME: &'static str = "wallet_name";
 pub static CONFIG_WALLET_TYPE: &'static str = "wallet_type";
 pub static CONFIG_WALLET_STORAGE_CONFIG: &'static str = "storage_config";
 pub static CONFIG_WALLET_STORAGE_CREDS: &'static str = "storage_credentials";
+pub static CONFIG_WALLET_STORAGE_LIBRARY: &str = "storage_library";
+pub static CONFIG_WALLET_STORAGE_INIT_FUNCTION: &str = "storage_init_function";
 pub static CONFIG_WALLET_HANDLE: &'static str = "wallet_handle";
 pub static CONFIG_THREADPOOL_SIZE: &'static str = "threadpool_size";
 pub static CONFIG_WALLET_KEY_DERIVATION: &'static str = "wallet_key_derivation";
@@ -52,10 +60,60 @@ pub static CONFIG_PAYMENT_METHOD: &'static str = "payment_method";
 pub static CONFIG_TXN_AUTHOR_AGREEMENT: &'static str = "author_agreement";
 pub static CONFIG_USE_LATEST_PROTOCOLS: &'static str = "use_latest_protocols";
 pub static CONFIG_POOL_CONFIG: &'static str = "pool_config";
+// ledger networks opened next to the default pool: [{"name": .., "genesis_path": .., "pool_config": ..}]
+pub static CONFIG_NETWORKS: &str = "networks";
+// method of the DIDs created by the library (e.g. "sov"), DIDs are unqualified when not set
 pub static CONFIG_DID_METHOD: &str = "did_method";
 pub static COMMUNICATION_METHOD: &str = "communication_method";
 // proprietary or aries
 pub static CONFIG_ACTORS: &str = "actors";
+pub static CONFIG_DISABLED_PROTOCOLS: &str = "disabled_protocols";
+pub static CONFIG_TAILS_SERVER_URL: &str = "tails_server_url";
+pub static CONFIG_TAILS_DIR: &str = "tails_dir";
+pub static CONFIG_MAX_JSON_SIZE: &str = "max_json_size";
+pub static CONFIG_MAX_JSON_DEPTH: &str = "max_json_depth";
+pub static CONFIG_HANDLE_TOKEN_KEY: &str = "handle_token_key";
+// sov or peer
+pub static CONFIG_PAIRWISE_DID_METHOD: &str = "pairwise_did_method";
+pub static CONFIG_DID_KEY_ENCODING: &str = "did_key_encoding";
+// seconds, ledger cache is disabled if not set or 0
+pub static CONFIG_LEDGER_CACHE_TTL: &str = "ledger_cache_ttl";
+// maximal number of ledger objects cached for the agent, the oldest are evicted first
+pub static CONFIG_LEDGER_CACHE_CAPACITY: &str = "ledger_cache_capacity";
+pub static CONFIG_STATE_CHANGE_WEBHOOK_URL: &str = "state_change_webhook_url";
+// objects are saved to the wallet on every change when "true"
+pub static CONFIG_PERSIST_OBJECTS: &str = "persist_objects";
+// bytes, attachments above the size are encoded, decoded and downloaded in chunks
+pub static CONFIG_ATTACHMENT_STREAMING_THRESHOLD: &str = "attachment_streaming_threshold";
+// seconds, tolerated difference between the local clock and timestamps of received field signatures
+pub static CONFIG_SIGNATURE_CLOCK_SKEW: &str = "signature_clock_skew";
+// seconds, received field signatures older than this are rejected, no limit if not set
+pub static CONFIG_SIGNATURE_MAX_AGE: &str = "signature_max_age";
+// seconds, timeout of requests to the agency and other endpoints
+pub static CONFIG_HTTP_TIMEOUT: &str = "http_timeout";
+// seconds, timeout of establishing connection, only the request timeout applies if not set
+pub static CONFIG_HTTP_CONNECT_TIMEOUT: &str = "http_connect_timeout";
+// number of repeated attempts of idempotent agency requests failed on network errors
+pub static CONFIG_HTTP_RETRIES: &str = "http_retries";
+// milliseconds, delay before the first retry, doubled for every next one up to a minute
+pub static CONFIG_HTTP_RETRY_BACKOFF: &str = "http_retry_backoff";
+// url of HTTP/HTTPS proxy all requests go through
+pub static CONFIG_HTTP_PROXY: &str = "http_proxy";
+// JSON array of PEM certificates, when set all requests (agency, webhook, tails, attachment links) go to
+// https servers presenting a leaf certificate with the public key of one of these only
+pub static CONFIG_HTTP_PINNED_CERTIFICATES: &str = "http_pinned_certificates";
+// messages which could not be delivered due to network errors are kept in the wallet for later delivery when "true"
+pub static CONFIG_OUTBOUND_QUEUE: &str = "outbound_queue";
+// protocol objects keep all messages they sent and received when "true"
+pub static CONFIG_PROTOCOL_HISTORY: &str = "protocol_history";
+// seconds after which sent credential offers expire, offers do not expire when not set
+pub static CONFIG_CREDENTIAL_OFFER_EXPIRY: &str = "credential_offer_expiry";
+// seconds after which sent proof requests expire, requests do not expire when not set
+pub static CONFIG_PROOF_REQUEST_EXPIRY: &str = "proof_request_expiry";
+// counterparty is informed by problem report when an exchange expires if "true"
+pub static CONFIG_PROBLEM_REPORT_ON_EXPIRY: &str = "problem_report_on_expiry";
+// number of presentations verified at once by `proof::verify_batch`
+pub static CONFIG_VERIFY_BATCH_PARALLELISM: &str = "verify_batch_parallelism";
 
 pub static DEFAULT_PROTOCOL_VERSION: usize = 2;
 pub static MAX_SUPPORTED_PROTOCOL_VERSION: usize = 2;
@@ -74,6 +132,15 @@ pub static DEFAULT_ENABLE_TEST_MODE: &str = "false";
 pub static DEFAULT_WALLET_BACKUP_KEY: &str = "backup_wallet_key";
 pub static DEFAULT_WALLET_KEY: &str = "8dvfYSt5d1taSd6yJdpjq4emkwsPDDLYxkNFysFD2cZY";
 pub static DEFAULT_THREADPOOL_SIZE: usize = 8;
+pub static DEFAULT_MAX_JSON_SIZE: usize = 10 * 1024 * 1024;
+pub static DEFAULT_ATTACHMENT_STREAMING_THRESHOLD: usize = 1024 * 1024;
+pub static DEFAULT_SIGNATURE_CLOCK_SKEW: u64 = 300;
+pub static DEFAULT_MAX_JSON_DEPTH: usize = 64;
+pub static DEFAULT_HTTP_TIMEOUT: u64 = 50;
+pub static DEFAULT_HTTP_RETRIES: u32 = 2;
+pub static DEFAULT_HTTP_RETRY_BACKOFF: u64 = 500;
+pub static PAIRWISE_DID_METHOD_SOV: &str = "sov";
+pub static PAIRWISE_DID_METHOD_PEER: &str = "peer";
 pub static MASK_VALUE: &str = "********";
 pub static DEFAULT_WALLET_KEY_DERIVATION: &str = "RAW";
 #[cfg(not(target_os = "macos"))]
@@ -84,10 +151,178 @@ pub static DEFAULT_PAYMENT_INIT_FUNCTION: &str = "nullpay_init";
 pub static DEFAULT_USE_LATEST_PROTOCOLS: &str = "false";
 pub static DEFAULT_PAYMENT_METHOD: &str = "null";
 pub static MAX_THREADPOOL_SIZE: usize = 128;
+pub static DEFAULT_VERIFY_BATCH_PARALLELISM: usize = 4;
+pub static DEFAULT_LEDGER_CACHE_CAPACITY: usize = 1000;
 pub static MOCK_DEFAULT_INDY_PROOF_VALIDATION: &str = "true";
 
 lazy_static! {
-    static ref SETTINGS: RwLock<HashMap<String, String>> = RwLock::new(HashMap::new());
+    static ref SETTINGS: Arc<SettingsStore> = Default::default();
+}
+
+thread_local! {
+    static OVERRIDES: RefCell<Vec<HashMap<String, String>>> = RefCell::new(Vec::new());
+}
+
+/// Settings holding keys and credentials, their values are masked in logs and wiped from memory with the settings.
+pub fn is_secret_config_key(key: &str) -> bool {
+    [CONFIG_WALLET_KEY, CONFIG_WALLET_BACKUP_KEY, CONFIG_WALLET_STORAGE_CREDS, CONFIG_HANDLE_TOKEN_KEY].contains(&key)
+}
+
+// provisioning configs carry seeds of the keys besides the secret settings
+const SECRET_PROVISION_KEYS: &[&str] = &["agent_seed", "enterprise_seed"];
+
+/// `config` JSON with values of secret settings (see `is_secret_config_key`) and key seeds masked, to be logged.
+pub fn redact_config(config: &str) -> String {
+    match serde_json::from_str::<Value>(config) {
+        Ok(Value::Object(mut map)) => {
+            for (key, value) in map.iter_mut() {
+                if !value.is_null() && (is_secret_config_key(key) || SECRET_PROVISION_KEYS.contains(&key.as_str())) {
+                    _zeroize_value(value);
+                    *value = json!(MASK_VALUE);
+                }
+            }
+            Value::Object(map).to_string()
+        }
+        _ => MASK_VALUE.to_string()
+    }
+}
+
+// wipes all strings of the parsed JSON, serde_json does not do it when the value is dropped
+fn _zeroize_value(value: &mut Value) {
+    match value {
+        Value::String(string) => secret::zeroize_string(string),
+        Value::Array(values) => values.iter_mut().for_each(_zeroize_value),
+        Value::Object(map) => map.iter_mut().for_each(|(_, value)| _zeroize_value(value)),
+        _ => {}
+    }
+}
+
+/// Map of settings which wipes secret values when dropped and does not print them by `Debug`.
+#[derive(Default, Clone, PartialEq)]
+pub struct Settings(HashMap<String, String>);
+
+impl Deref for Settings {
+    type Target = HashMap<String, String>;
+
+    fn deref(&self) -> &HashMap<String, String> {
+        &self.0
+    }
+}
+
+impl DerefMut for Settings {
+    fn deref_mut(&mut self) -> &mut HashMap<String, String> {
+        &mut self.0
+    }
+}
+
+impl Drop for Settings {
+    fn drop(&mut self) {
+        for (key, value) in self.0.iter_mut() {
+            if is_secret_config_key(key) {
+                secret::zeroize_string(value);
+            }
+        }
+    }
+}
+
+impl Settings {
+    /// Reads `key` as `get_config_value` does, related keys are read from one snapshot this way.
+    pub fn get_value(&self, key: &str) -> VcxResult<String> {
+        self.get(key)
+            .map(|v| v.to_string())
+            .ok_or(VcxError::from_msg(VcxErrorKind::InvalidConfiguration, format!("Cannot read \"{}\" from settings", key)))
+    }
+}
+
+impl fmt::Debug for Settings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().map(|(key, value)| (key, if is_secret_config_key(key) { MASK_VALUE } else { value.as_str() })))
+            .finish()
+    }
+}
+
+///
+/// Settings which are never modified in place: every change builds a new map and swaps it in,
+/// so readers always work with a consistent snapshot even when other threads change the configuration.
+///
+#[derive(Default, Debug)]
+pub struct SettingsStore {
+    current: RwLock<Arc<Settings>>,
+}
+
+impl SettingsStore {
+    pub fn snapshot(&self) -> Arc<Settings> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Applies `change` to a copy of the settings and publishes the result, concurrent updates are serialized.
+    pub fn update<F, R>(&self, change: F) -> R where F: FnOnce(&mut HashMap<String, String>) -> R {
+        let mut current = self.current.write().unwrap();
+        let mut settings = (**current).clone();
+        let result = change(&mut settings.0);
+        *current = Arc::new(settings);
+        result
+    }
+}
+
+// settings of the agent running on this thread take place of the process wide ones
+fn _settings() -> Arc<SettingsStore> {
+    agent_context::current()
+        .map(|context| context.settings)
+        .unwrap_or_else(|| SETTINGS.clone())
+}
+
+/// Consistent view of the settings in effect on this thread, including scoped overrides.
+pub fn snapshot() -> Arc<Settings> {
+    let settings = _settings().snapshot();
+
+    OVERRIDES.with(|overrides| {
+        let overrides = overrides.borrow();
+        if overrides.is_empty() {
+            return settings;
+        }
+        let mut settings = (*settings).clone();
+        for scope in overrides.iter() {
+            settings.extend(scope.iter().map(|(key, value)| (key.clone(), value.clone())));
+        }
+        Arc::new(settings)
+    })
+}
+
+/// Settings of this thread's agent (or the process) without scoped overrides, to be put back by `restore`.
+pub fn save() -> Arc<Settings> {
+    _settings().snapshot()
+}
+
+/// Replaces the settings by ones taken by `save`, e.g. when a configuration change fails half way.
+pub fn restore(saved: &Settings) {
+    _settings().update(|settings| *settings = saved.0.clone());
+}
+
+/// Overrides in effect on the current thread, inner scopes take precedence.
+pub fn current_overrides() -> HashMap<String, String> {
+    OVERRIDES.with(|overrides| {
+        overrides.borrow().iter()
+            .flat_map(|scope| scope.iter().map(|(key, value)| (key.clone(), value.clone())))
+            .collect()
+    })
+}
+
+// removes overrides of the scope even when the closure panics
+struct OverridesScope;
+
+impl Drop for OverridesScope {
+    fn drop(&mut self) {
+        OVERRIDES.with(|overrides| overrides.borrow_mut().pop());
+    }
+}
+
+/// Runs `closure` with `overrides` taking precedence over the settings on the current thread, without changing them for others.
+pub fn with_overrides<F, R>(overrides: HashMap<String, String>, closure: F) -> R where F: FnOnce() -> R {
+    OVERRIDES.with(|scopes| scopes.borrow_mut().push(overrides));
+    let _scope = OverridesScope;
+    closure()
 }
 
 trait ToString {
@@ -96,52 +331,51 @@ trait ToString {
 
 impl ToString for HashMap<String, String> {
     fn to_string(&self) -> Self {
-        let mut v = self.clone();
-        v.insert(CONFIG_WALLET_KEY.to_string(), MASK_VALUE.to_string());
-        v
+        self.iter()
+            .map(|(key, value)| (key.clone(), if is_secret_config_key(key) { MASK_VALUE.to_string() } else { value.clone() }))
+            .collect()
     }
 }
 
 pub fn set_defaults() -> u32 {
     trace!("set_defaults >>>");
 
-    // if this fails the program should exit
-    let mut settings = SETTINGS.write().unwrap();
-
-    settings.insert(CONFIG_POOL_NAME.to_string(), DEFAULT_POOL_NAME.to_string());
-    settings.insert(CONFIG_WALLET_NAME.to_string(), DEFAULT_WALLET_NAME.to_string());
-    settings.insert(CONFIG_WALLET_TYPE.to_string(), DEFAULT_DEFAULT.to_string());
-    settings.insert(CONFIG_AGENCY_ENDPOINT.to_string(), DEFAULT_URL.to_string());
-    settings.insert(CONFIG_AGENCY_DID.to_string(), DEFAULT_DID.to_string());
-    settings.insert(CONFIG_AGENCY_VERKEY.to_string(), DEFAULT_VERKEY.to_string());
-    settings.insert(CONFIG_REMOTE_TO_SDK_DID.to_string(), DEFAULT_DID.to_string());
-    settings.insert(CONFIG_REMOTE_TO_SDK_VERKEY.to_string(), DEFAULT_VERKEY.to_string());
-    settings.insert(CONFIG_INSTITUTION_DID.to_string(), DEFAULT_DID.to_string());
-    settings.insert(CONFIG_INSTITUTION_NAME.to_string(), DEFAULT_DEFAULT.to_string());
-    settings.insert(CONFIG_INSTITUTION_LOGO_URL.to_string(), DEFAULT_URL.to_string());
-    settings.insert(CONFIG_WEBHOOK_URL.to_string(), DEFAULT_URL.to_string());
-    settings.insert(CONFIG_SDK_TO_REMOTE_DID.to_string(), DEFAULT_DID.to_string());
-    settings.insert(CONFIG_SDK_TO_REMOTE_VERKEY.to_string(), DEFAULT_VERKEY.to_string());
-    settings.insert(CONFIG_SDK_TO_REMOTE_ROLE.to_string(), DEFAULT_ROLE.to_string());
-    settings.insert(CONFIG_WALLET_KEY.to_string(), DEFAULT_WALLET_KEY.to_string());
-    settings.insert(CONFIG_WALLET_KEY_DERIVATION.to_string(), DEFAULT_WALLET_KEY_DERIVATION.to_string());
-    settings.insert(CONFIG_LINK_SECRET_ALIAS.to_string(), DEFAULT_LINK_SECRET_ALIAS.to_string());
-    settings.insert(CONFIG_PROTOCOL_VERSION.to_string(), DEFAULT_PROTOCOL_VERSION.to_string());
-    settings.insert(CONFIG_EXPORTED_WALLET_PATH.to_string(),
-                    get_temp_dir_path(DEFAULT_EXPORTED_WALLET_PATH).to_str().unwrap_or("").to_string());
-    settings.insert(CONFIG_WALLET_BACKUP_KEY.to_string(), DEFAULT_WALLET_BACKUP_KEY.to_string());
-    settings.insert(CONFIG_THREADPOOL_SIZE.to_string(), DEFAULT_THREADPOOL_SIZE.to_string());
-    settings.insert(CONFIG_PAYMENT_METHOD.to_string(), DEFAULT_PAYMENT_METHOD.to_string());
-    settings.insert(CONFIG_USE_LATEST_PROTOCOLS.to_string(), DEFAULT_USE_LATEST_PROTOCOLS.to_string());
+    _settings().update(|settings| {
+        settings.insert(CONFIG_POOL_NAME.to_string(), DEFAULT_POOL_NAME.to_string());
+        settings.insert(CONFIG_WALLET_NAME.to_string(), DEFAULT_WALLET_NAME.to_string());
+        settings.insert(CONFIG_WALLET_TYPE.to_string(), DEFAULT_DEFAULT.to_string());
+        settings.insert(CONFIG_AGENCY_ENDPOINT.to_string(), DEFAULT_URL.to_string());
+        settings.insert(CONFIG_AGENCY_DID.to_string(), DEFAULT_DID.to_string());
+        settings.insert(CONFIG_AGENCY_VERKEY.to_string(), DEFAULT_VERKEY.to_string());
+        settings.insert(CONFIG_REMOTE_TO_SDK_DID.to_string(), DEFAULT_DID.to_string());
+        settings.insert(CONFIG_REMOTE_TO_SDK_VERKEY.to_string(), DEFAULT_VERKEY.to_string());
+        settings.insert(CONFIG_INSTITUTION_DID.to_string(), DEFAULT_DID.to_string());
+        settings.insert(CONFIG_INSTITUTION_NAME.to_string(), DEFAULT_DEFAULT.to_string());
+        settings.insert(CONFIG_INSTITUTION_LOGO_URL.to_string(), DEFAULT_URL.to_string());
+        settings.insert(CONFIG_WEBHOOK_URL.to_string(), DEFAULT_URL.to_string());
+        settings.insert(CONFIG_SDK_TO_REMOTE_DID.to_string(), DEFAULT_DID.to_string());
+        settings.insert(CONFIG_SDK_TO_REMOTE_VERKEY.to_string(), DEFAULT_VERKEY.to_string());
+        settings.insert(CONFIG_SDK_TO_REMOTE_ROLE.to_string(), DEFAULT_ROLE.to_string());
+        settings.insert(CONFIG_WALLET_KEY.to_string(), DEFAULT_WALLET_KEY.to_string());
+        settings.insert(CONFIG_WALLET_KEY_DERIVATION.to_string(), DEFAULT_WALLET_KEY_DERIVATION.to_string());
+        settings.insert(CONFIG_LINK_SECRET_ALIAS.to_string(), DEFAULT_LINK_SECRET_ALIAS.to_string());
+        settings.insert(CONFIG_PROTOCOL_VERSION.to_string(), DEFAULT_PROTOCOL_VERSION.to_string());
+        settings.insert(CONFIG_EXPORTED_WALLET_PATH.to_string(),
+                        get_temp_dir_path(DEFAULT_EXPORTED_WALLET_PATH).to_str().unwrap_or("").to_string());
+        settings.insert(CONFIG_WALLET_BACKUP_KEY.to_string(), DEFAULT_WALLET_BACKUP_KEY.to_string());
+        settings.insert(CONFIG_THREADPOOL_SIZE.to_string(), DEFAULT_THREADPOOL_SIZE.to_string());
+        settings.insert(CONFIG_PAYMENT_METHOD.to_string(), DEFAULT_PAYMENT_METHOD.to_string());
+        settings.insert(CONFIG_USE_LATEST_PROTOCOLS.to_string(), DEFAULT_USE_LATEST_PROTOCOLS.to_string());
+    });
 
     error::SUCCESS.code_num
 }
 
 pub fn validate_config(config: &HashMap<String, String>) -> VcxResult<u32> {
-    trace!("validate_config >>> config: {:?}", config);
+    trace!("validate_config >>> config: {:?}", config.to_string());
 
-    //Mandatory parameters
-    if ::utils::libindy::wallet::get_wallet_handle() == INVALID_WALLET_HANDLE && config.get(CONFIG_WALLET_KEY).is_none() {
+    //Mandatory parameters, the wallet key may be supplied by key provider instead
+    if ::utils::libindy::wallet::get_wallet_handle() == INVALID_WALLET_HANDLE && config.get(CONFIG_WALLET_KEY).is_none() && !key_provider::is_registered() {
         return Err(VcxError::from(VcxErrorKind::MissingWalletKey));
     }
 
@@ -160,14 +394,74 @@ pub fn validate_config(config: &HashMap<String, String>) -> VcxResult<u32> {
 
     validate_optional_config_val(config.get(CONFIG_AGENCY_ENDPOINT), VcxErrorKind::InvalidUrl, Url::parse)?;
     validate_optional_config_val(config.get(CONFIG_INSTITUTION_LOGO_URL), VcxErrorKind::InvalidUrl, Url::parse)?;
+    validate_optional_config_val(config.get(CONFIG_INSTITUTION_L10N), VcxErrorKind::InvalidJson, ::aries::messages::localization::Localization::from_str)?;
 
     validate_optional_config_val(config.get(CONFIG_WEBHOOK_URL), VcxErrorKind::InvalidUrl, Url::parse)?;
+    validate_optional_config_val(config.get(CONFIG_TAILS_SERVER_URL), VcxErrorKind::InvalidUrl, Url::parse)?;
 
     validate_optional_config_val(config.get(CONFIG_ACTORS), VcxErrorKind::InvalidOption, validation::validate_actors)?;
+    validate_optional_config_val(config.get(CONFIG_DISABLED_PROTOCOLS), VcxErrorKind::InvalidOption, validation::validate_disabled_protocols)?;
+
+    validate_optional_config_val(config.get(CONFIG_MAX_JSON_SIZE), VcxErrorKind::InvalidOption, str::parse::<usize>)?;
+    validate_optional_config_val(config.get(CONFIG_MAX_JSON_DEPTH), VcxErrorKind::InvalidOption, str::parse::<usize>)?;
+
+    validate_optional_config_val(config.get(CONFIG_DID_METHOD), VcxErrorKind::InvalidOption, validation::validate_did_method)?;
+    validate_optional_config_val(config.get(CONFIG_PAIRWISE_DID_METHOD), VcxErrorKind::InvalidOption, validation::validate_pairwise_did_method)?;
+    validate_optional_config_val(config.get(CONFIG_DID_KEY_ENCODING), VcxErrorKind::InvalidOption, str::parse::<bool>)?;
+    validate_optional_config_val(config.get(CONFIG_LEDGER_CACHE_TTL), VcxErrorKind::InvalidOption, str::parse::<u64>)?;
+    validate_optional_config_val(config.get(CONFIG_LEDGER_CACHE_CAPACITY), VcxErrorKind::InvalidOption, str::parse::<usize>)?;
+    validate_optional_config_val(config.get(CONFIG_STATE_CHANGE_WEBHOOK_URL), VcxErrorKind::InvalidUrl, Url::parse)?;
+    validate_optional_config_val(config.get(CONFIG_PERSIST_OBJECTS), VcxErrorKind::InvalidOption, str::parse::<bool>)?;
+    validate_optional_config_val(config.get(CONFIG_ATTACHMENT_STREAMING_THRESHOLD), VcxErrorKind::InvalidOption, str::parse::<usize>)?;
+    validate_optional_config_val(config.get(CONFIG_SIGNATURE_CLOCK_SKEW), VcxErrorKind::InvalidOption, str::parse::<u64>)?;
+    validate_optional_config_val(config.get(CONFIG_SIGNATURE_MAX_AGE), VcxErrorKind::InvalidOption, str::parse::<u64>)?;
+    validate_optional_config_val(config.get(CONFIG_NETWORKS), VcxErrorKind::InvalidConfiguration, validation::validate_networks)?;
+    validate_optional_config_val(config.get(CONFIG_HTTP_TIMEOUT), VcxErrorKind::InvalidOption, str::parse::<u64>)?;
+    validate_optional_config_val(config.get(CONFIG_HTTP_CONNECT_TIMEOUT), VcxErrorKind::InvalidOption, str::parse::<u64>)?;
+    validate_optional_config_val(config.get(CONFIG_HTTP_RETRIES), VcxErrorKind::InvalidOption, str::parse::<u32>)?;
+    validate_optional_config_val(config.get(CONFIG_HTTP_RETRY_BACKOFF), VcxErrorKind::InvalidOption, str::parse::<u64>)?;
+    validate_optional_config_val(config.get(CONFIG_HTTP_PROXY), VcxErrorKind::InvalidUrl, Url::parse)?;
+    validate_optional_config_val(config.get(CONFIG_HTTP_PINNED_CERTIFICATES), VcxErrorKind::InvalidConfiguration, ::serde_json::from_str::<Vec<String>>)?;
+    validate_optional_config_val(config.get(CONFIG_OUTBOUND_QUEUE), VcxErrorKind::InvalidOption, str::parse::<bool>)?;
+    validate_optional_config_val(config.get(CONFIG_PROTOCOL_HISTORY), VcxErrorKind::InvalidOption, str::parse::<bool>)?;
+    validate_optional_config_val(config.get(CONFIG_CREDENTIAL_OFFER_EXPIRY), VcxErrorKind::InvalidOption, str::parse::<u64>)?;
+    validate_optional_config_val(config.get(CONFIG_PROOF_REQUEST_EXPIRY), VcxErrorKind::InvalidOption, str::parse::<u64>)?;
+    validate_optional_config_val(config.get(CONFIG_PROBLEM_REPORT_ON_EXPIRY), VcxErrorKind::InvalidOption, str::parse::<bool>)?;
+    validate_optional_config_val(config.get(CONFIG_VERIFY_BATCH_PARALLELISM), VcxErrorKind::InvalidOption, str::parse::<usize>)?;
+
+    if let Some(protocol_type) = config.get(CONFIG_PROTOCOL_TYPE) {
+        let protocol_type = ::serde_json::from_value::<ProtocolTypes>(json!(protocol_type))
+            .map_err(|_| VcxError::from_msg(VcxErrorKind::InvalidConfiguration,
+                                            format!("Unknown protocol_type {:?}, supported values are \"1.0\", \"2.0\", \"3.0\" and \"4.0\"", protocol_type)))?;
+        validate_protocol_mode(&protocol_type,
+                               config.get(COMMUNICATION_METHOD).map(String::as_str),
+                               config.get(CONFIG_USE_LATEST_PROTOCOLS).map(String::as_str))?;
+    }
 
     Ok(error::SUCCESS.code_num)
 }
 
+/// Rejects options selecting the proprietary protocols together with strict aries `protocol_type` "4.0",
+/// so that the misconfiguration shows up on init rather than in the middle of an exchange.
+pub fn validate_protocol_mode(protocol_type: &ProtocolTypes, communication_method: Option<&str>, use_latest_protocols: Option<&str>) -> VcxResult<()> {
+    if *protocol_type != ProtocolTypes::V4 {
+        return Ok(());
+    }
+
+    if let Some(communication_method) = communication_method.filter(|method| *method != ARIES_COMMUNICATION_METHOD) {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidConfiguration,
+                                      format!("communication_method {:?} cannot be used with strict aries protocol_type \"4.0\", set it to \"{}\" or remove it",
+                                              communication_method, ARIES_COMMUNICATION_METHOD)));
+    }
+
+    if use_latest_protocols.map(|value| value.eq_ignore_ascii_case("true")).unwrap_or(false) {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidConfiguration,
+                                      "use_latest_protocols selects the proprietary connection protocol which is not available with strict aries protocol_type \"4.0\", remove it"));
+    }
+
+    Ok(())
+}
+
 fn validate_mandatory_config_val<F, S, E>(val: Option<&String>, err: VcxErrorKind, closure: F) -> VcxResult<u32>
     where F: Fn(&str) -> Result<S, E> {
     closure(val.as_ref().ok_or(VcxError::from(err))?)
@@ -186,42 +480,41 @@ fn validate_optional_config_val<F, S, E>(val: Option<&String>, err: VcxErrorKind
     Ok(error::SUCCESS.code_num)
 }
 
+#[cfg(feature = "payments")]
 pub fn validate_payment_method() -> VcxResult<u32> {
     validate_mandatory_config_val(get_config_value(CONFIG_PAYMENT_METHOD).ok().as_ref(),
                                   VcxErrorKind::MissingPaymentMethod, validation::validate_payment_method)
 }
 
+#[cfg(not(feature = "payments"))]
+pub fn validate_payment_method() -> VcxResult<u32> {
+    Ok(error::SUCCESS.code_num)
+}
+
 pub fn settings_as_string() -> HashMap<String, String> {
-    SETTINGS.read().unwrap().to_string()
+    snapshot().to_string()
 }
 
 pub fn log_settings() {
-    let settings = SETTINGS.read().unwrap();
-    trace!("loaded settings: {:?}", settings.to_string());
+    trace!("loaded settings: {:?}", snapshot().to_string());
 }
 
 pub fn indy_mocks_enabled() -> bool {
-    let config = SETTINGS.read().unwrap();
-
-    match config.get(CONFIG_ENABLE_TEST_MODE) {
+    match snapshot().get(CONFIG_ENABLE_TEST_MODE) {
         None => false,
         Some(value) => value == "true" || value == "indy"
     }
 }
 
 pub fn agency_mocks_enabled() -> bool {
-    let config = SETTINGS.read().unwrap();
-
-    match config.get(CONFIG_ENABLE_TEST_MODE) {
+    match snapshot().get(CONFIG_ENABLE_TEST_MODE) {
         None => false,
         Some(value) => value == "true" || value == "agency"
     }
 }
 
 pub fn agency_decrypted_mocks_enabled() -> bool {
-    let config = SETTINGS.read().unwrap();
-
-    match config.get(CONFIG_ENABLE_TEST_MODE) {
+    match snapshot().get(CONFIG_ENABLE_TEST_MODE) {
         None => false,
         Some(value) => value == "true"
     }
@@ -230,30 +523,48 @@ pub fn agency_decrypted_mocks_enabled() -> bool {
 pub fn enable_mock_generate_indy_proof() {}
 
 pub fn process_config_string(config: &str, do_validation: bool) -> VcxResult<u32> {
-    trace!("process_config_string >>> config {}", config);
+    trace!("process_config_string >>>");
 
-    let configuration: Value = serde_json::from_str(config)
+    let mut configuration: Value = serde_json::from_str(config)
         .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot parse config: {}", err)))?;
+    let values = _config_values(&configuration);
+    _zeroize_value(&mut configuration);
+    let mut values = values?;
+    trace!("process_config_string >>> config {:?}", values);
+
+    // whole configuration is published at once and only when it is valid
+    _settings().update(|settings| {
+        let mut updated = Settings(settings.clone());
+        for (key, value) in values.drain() {
+            if let Some(mut previous) = updated.insert(key, value) {
+                secret::zeroize_string(&mut previous);
+            }
+        }
+
+        if do_validation {
+            validate_config(&updated)?;
+        }
+
+        ::std::mem::swap(settings, &mut updated.0);
+        Ok(error::SUCCESS.code_num)
+    })
+}
 
+fn _config_values(configuration: &Value) -> VcxResult<Settings> {
+    let mut values = Settings::default();
     if let Value::Object(ref map) = configuration {
         for (key, value) in map {
-            match value {
-                Value::String(value_) => set_config_value(key, &value_),
-                Value::Array(value_) => set_config_value(key, &json!(value_).to_string()),
-                Value::Object(value_) => set_config_value(key, &json!(value_).to_string()),
-                Value::Bool(value_) => set_config_value(key, &json!(value_).to_string()),
+            let value = match value {
+                Value::String(value_) => value_.to_string(),
+                Value::Array(value_) => json!(value_).to_string(),
+                Value::Object(value_) => json!(value_).to_string(),
+                Value::Bool(value_) => json!(value_).to_string(),
                 _ => return Err(VcxError::from(VcxErrorKind::InvalidJson)),
-            }
+            };
+            values.insert(key.to_string(), value);
         }
     }
-
-    if do_validation {
-        let setting = SETTINGS.read()
-            .or(Err(VcxError::from(VcxErrorKind::InvalidConfiguration)))?;
-        validate_config(&setting.borrow())
-    } else {
-        Ok(error::SUCCESS.code_num)
-    }
+    Ok(values)
 }
 
 pub fn process_config_file(path: &str) -> VcxResult<u32> {
@@ -271,19 +582,23 @@ pub fn process_config_file(path: &str) -> VcxResult<u32> {
 pub fn get_config_value(key: &str) -> VcxResult<String> {
     trace!("get_config_value >>> key: {}", key);
 
-    SETTINGS
-        .read()
-        .or(Err(VcxError::from_msg(VcxErrorKind::InvalidConfiguration, "Cannot read settings")))?
+    snapshot().get_value(key)
+}
+
+/// Reads setting holding a secret (see `is_secret_config_key`), the returned copy is wiped when dropped.
+pub fn get_secret_config_value(key: &str) -> VcxResult<SecretString> {
+    snapshot()
         .get(key)
-        .map(|v| v.to_string())
+        .map(|v| SecretString::new(v.to_string()))
         .ok_or(VcxError::from_msg(VcxErrorKind::InvalidConfiguration, format!("Cannot read \"{}\" from settings", key)))
 }
 
 pub fn set_config_value(key: &str, value: &str) {
-    trace!("set_config_value >>> key: {}, value: {}", key, value);
-    SETTINGS
-        .write().unwrap()
-        .insert(key.to_string(), value.to_string());
+    trace!("set_config_value >>> key: {}, value: {}", key, if is_secret_config_key(key) { MASK_VALUE } else { value });
+    // the replaced value is a copy made for the new settings, the original is wiped with the old ones
+    if let Some(mut previous) = _settings().update(|settings| settings.insert(key.to_string(), value.to_string())) {
+        secret::zeroize_string(&mut previous);
+    }
 }
 
 pub fn get_wallet_name() -> VcxResult<String> {
@@ -326,12 +641,7 @@ pub fn get_protocol_version() -> usize {
 
 pub fn get_opt_config_value(key: &str) -> Option<String> {
     trace!("get_opt_config_value >>> key: {}", key);
-    match SETTINGS.read() {
-        Ok(x) => x,
-        Err(_) => return None
-    }
-        .get(key)
-        .map(|v| v.to_string())
+    snapshot().get(key).map(|v| v.to_string())
 }
 
 pub fn set_opt_config_value(key: &str, value: &Option<String>) {
@@ -340,30 +650,49 @@ pub fn set_opt_config_value(key: &str, value: &Option<String>) {
     }
 }
 
-pub fn get_wallet_config(wallet_name: &str, wallet_type: Option<&str>, _storage_config: Option<&str>) -> String { // TODO: _storage_config must be used
+/// Explicitly passed storage config and credentials take precedence over the configured ones.
+pub fn get_wallet_config(wallet_name: &str, wallet_type: Option<&str>, storage_config: Option<&str>) -> VcxResult<String> {
     let mut config = json!({
         "id": wallet_name,
         "storage_type": wallet_type
     });
 
-    if let Ok(_config) = get_config_value(CONFIG_WALLET_STORAGE_CONFIG) {
-        config["storage_config"] = serde_json::from_str(&_config).unwrap();
+    let storage_config = storage_config.map(String::from).or(get_config_value(CONFIG_WALLET_STORAGE_CONFIG).ok());
+    if let Some(storage_config) = storage_config {
+        config["storage_config"] = serde_json::from_str(&storage_config)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidConfiguration, format!("Invalid {}: {:?}", CONFIG_WALLET_STORAGE_CONFIG, err)))?;
     }
 
-    config.to_string()
+    Ok(config.to_string())
 }
 
-pub fn get_wallet_credentials(_storage_creds: Option<&str>) -> String { // TODO: storage_creds must be used?
-    let key = get_config_value(CONFIG_WALLET_KEY).unwrap_or(UNINITIALIZED_WALLET_KEY.to_string());
-    let mut credentials = json!({"key": key});
+/// Credentials of the configured wallet. Explicit `wallet_key` takes precedence over the registered key provider,
+/// which is only asked for the key when the option is not set.
+pub fn get_wallet_credentials(storage_creds: Option<&str>) -> VcxResult<SecretString> {
+    let wallet_name = get_config_value(CONFIG_WALLET_NAME).unwrap_or(DEFAULT_WALLET_NAME.to_string());
+    let key = match get_secret_config_value(CONFIG_WALLET_KEY) {
+        Ok(key) => key,
+        Err(_) => match key_provider::get_key(&wallet_name) {
+            Some(key) => key?,
+            None => SecretString::from(UNINITIALIZED_WALLET_KEY)
+        }
+    };
+    let mut credentials = json!({"key": key.expose()});
 
     let key_derivation = get_config_value(CONFIG_WALLET_KEY_DERIVATION).ok();
     if let Some(_key) = key_derivation { credentials["key_derivation_method"] = json!(_key); }
 
-    let storage_creds = get_config_value(CONFIG_WALLET_STORAGE_CREDS).ok();
-    if let Some(_creds) = storage_creds { credentials["storage_credentials"] = serde_json::from_str(&_creds).unwrap(); }
+    let storage_creds = storage_creds.map(String::from).or(get_config_value(CONFIG_WALLET_STORAGE_CREDS).ok());
+    if let Some(storage_creds) = storage_creds {
+        credentials["storage_credentials"] = serde_json::from_str(&storage_creds)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidConfiguration, format!("Invalid {}: {:?}", CONFIG_WALLET_STORAGE_CREDS, err)))?;
+    }
 
-    credentials.to_string()
+    let serialized = SecretString::new(credentials.to_string());
+    if let Some(Value::String(ref mut key)) = credentials.get_mut("key") {
+        secret::zeroize_string(key);
+    }
+    Ok(serialized)
 }
 
 pub fn get_connecting_protocol_version() -> ProtocolTypes {
@@ -402,6 +731,147 @@ pub fn get_actors() -> Vec<Actors> {
         ).unwrap_or_else(|_| Actors::iter().collect())
 }
 
+pub fn get_max_json_size() -> usize {
+    get_config_value(CONFIG_MAX_JSON_SIZE).ok()
+        .and_then(|size| size.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MAX_JSON_SIZE)
+}
+
+pub fn get_attachment_streaming_threshold() -> usize {
+    get_config_value(CONFIG_ATTACHMENT_STREAMING_THRESHOLD).ok()
+        .and_then(|size| size.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_ATTACHMENT_STREAMING_THRESHOLD)
+}
+
+pub fn get_signature_clock_skew() -> u64 {
+    get_config_value(CONFIG_SIGNATURE_CLOCK_SKEW).ok()
+        .and_then(|skew| skew.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_SIGNATURE_CLOCK_SKEW)
+}
+
+pub fn get_signature_max_age() -> Option<u64> {
+    get_config_value(CONFIG_SIGNATURE_MAX_AGE).ok()
+        .and_then(|max_age| max_age.parse::<u64>().ok())
+}
+
+pub fn get_http_timeout() -> Duration {
+    let timeout = get_config_value(CONFIG_HTTP_TIMEOUT).ok()
+        .and_then(|timeout| timeout.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_HTTP_TIMEOUT);
+    Duration::from_secs(timeout)
+}
+
+pub fn get_http_connect_timeout() -> Option<Duration> {
+    get_config_value(CONFIG_HTTP_CONNECT_TIMEOUT).ok()
+        .and_then(|timeout| timeout.parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+pub fn get_http_retries() -> u32 {
+    get_config_value(CONFIG_HTTP_RETRIES).ok()
+        .and_then(|retries| retries.parse::<u32>().ok())
+        .unwrap_or(DEFAULT_HTTP_RETRIES)
+}
+
+pub fn get_http_retry_backoff() -> Duration {
+    let backoff = get_config_value(CONFIG_HTTP_RETRY_BACKOFF).ok()
+        .and_then(|backoff| backoff.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_HTTP_RETRY_BACKOFF);
+    Duration::from_millis(backoff)
+}
+
+pub fn get_http_proxy() -> Option<String> {
+    get_config_value(CONFIG_HTTP_PROXY).ok()
+}
+
+pub fn get_http_pinned_certificates() -> Vec<String> {
+    get_config_value(CONFIG_HTTP_PINNED_CERTIFICATES)
+        .and_then(|certificates|
+            ::serde_json::from_str(&certificates)
+                .map_err(|_| VcxError::from(VcxErrorKind::InvalidConfiguration))
+        ).unwrap_or_default()
+}
+
+pub fn is_outbound_queue_enabled() -> bool {
+    get_config_value(CONFIG_OUTBOUND_QUEUE).ok()
+        .and_then(|value| value.parse::<bool>().ok())
+        .unwrap_or(false)
+}
+
+pub fn is_protocol_history_enabled() -> bool {
+    get_config_value(CONFIG_PROTOCOL_HISTORY).ok()
+        .and_then(|value| value.parse::<bool>().ok())
+        .unwrap_or(false)
+}
+
+pub fn get_credential_offer_expiry() -> Option<u64> {
+    get_config_value(CONFIG_CREDENTIAL_OFFER_EXPIRY).ok()
+        .and_then(|expiry| expiry.parse::<u64>().ok())
+}
+
+pub fn get_proof_request_expiry() -> Option<u64> {
+    get_config_value(CONFIG_PROOF_REQUEST_EXPIRY).ok()
+        .and_then(|expiry| expiry.parse::<u64>().ok())
+}
+
+pub fn is_problem_report_on_expiry_enabled() -> bool {
+    get_config_value(CONFIG_PROBLEM_REPORT_ON_EXPIRY).ok()
+        .and_then(|value| value.parse::<bool>().ok())
+        .unwrap_or(false)
+}
+
+/// Parallelism of batch verification, at least one and at most `MAX_THREADPOOL_SIZE` presentations at once.
+pub fn get_verify_batch_parallelism() -> usize {
+    get_config_value(CONFIG_VERIFY_BATCH_PARALLELISM).ok()
+        .and_then(|parallelism| parallelism.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_VERIFY_BATCH_PARALLELISM)
+        .max(1)
+        .min(MAX_THREADPOOL_SIZE)
+}
+
+pub fn get_max_json_depth() -> usize {
+    get_config_value(CONFIG_MAX_JSON_DEPTH).ok()
+        .and_then(|depth| depth.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MAX_JSON_DEPTH)
+}
+
+/// Method DIDs created by the library are qualified with, see `CONFIG_DID_METHOD`.
+pub fn get_did_method() -> Option<String> {
+    get_config_value(CONFIG_DID_METHOD).ok()
+}
+
+pub fn is_did_peer_method_set() -> bool {
+    get_config_value(CONFIG_PAIRWISE_DID_METHOD).ok() == Some(PAIRWISE_DID_METHOD_PEER.to_string())
+}
+
+pub fn is_did_key_encoding_set() -> bool {
+    get_config_value(CONFIG_DID_KEY_ENCODING).ok()
+        .and_then(|value| value.parse::<bool>().ok())
+        .unwrap_or(false)
+}
+
+pub fn get_ledger_cache_ttl() -> Option<Duration> {
+    get_config_value(CONFIG_LEDGER_CACHE_TTL).ok()
+        .and_then(|ttl| ttl.parse::<u64>().ok())
+        .filter(|ttl| *ttl > 0)
+        .map(Duration::from_secs)
+}
+
+pub fn get_ledger_cache_capacity() -> usize {
+    get_config_value(CONFIG_LEDGER_CACHE_CAPACITY).ok()
+        .and_then(|capacity| capacity.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_LEDGER_CACHE_CAPACITY)
+        .max(1)
+}
+
+pub fn get_disabled_protocols() -> Vec<String> {
+    get_config_value(CONFIG_DISABLED_PROTOCOLS)
+        .and_then(|protocols|
+            ::serde_json::from_str(&protocols)
+                .map_err(|_| VcxError::from(VcxErrorKind::InvalidOption))
+        ).unwrap_or_default()
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq, EnumIter)]
 #[serde(rename_all = "lowercase")]
 pub enum Actors {
@@ -476,8 +946,7 @@ pub fn get_protocol_type() -> ProtocolTypes {
 
 pub fn clear_config() {
     trace!("clear_config >>>");
-    let mut config = SETTINGS.write().unwrap();
-    config.clear();
+    _settings().update(|settings| settings.clear());
 }
 
 #[cfg(test)]
@@ -625,6 +1094,62 @@ pub mod tests {
         let mut config = _mandatory_config();
         config.insert(CONFIG_WEBHOOK_URL.to_string(), invalid.to_string());
         assert_eq!(validate_config(&config).unwrap_err().kind(), VcxErrorKind::InvalidUrl);
+
+        let mut config = _mandatory_config();
+        config.insert(CONFIG_PAIRWISE_DID_METHOD.to_string(), invalid.to_string());
+        assert_eq!(validate_config(&config).unwrap_err().kind(), VcxErrorKind::InvalidOption);
+
+        let mut config = _mandatory_config();
+        config.insert(CONFIG_HTTP_TIMEOUT.to_string(), invalid.to_string());
+        assert_eq!(validate_config(&config).unwrap_err().kind(), VcxErrorKind::InvalidOption);
+
+        let mut config = _mandatory_config();
+        config.insert(CONFIG_HTTP_PROXY.to_string(), invalid.to_string());
+        assert_eq!(validate_config(&config).unwrap_err().kind(), VcxErrorKind::InvalidUrl);
+
+        let mut config = _mandatory_config();
+        config.insert(CONFIG_HTTP_PINNED_CERTIFICATES.to_string(), invalid.to_string());
+        assert_eq!(validate_config(&config).unwrap_err().kind(), VcxErrorKind::InvalidConfiguration);
+
+        let mut config = _mandatory_config();
+        config.insert(CONFIG_CREDENTIAL_OFFER_EXPIRY.to_string(), invalid.to_string());
+        assert_eq!(validate_config(&config).unwrap_err().kind(), VcxErrorKind::InvalidOption);
+
+        let mut config = _mandatory_config();
+        config.insert(CONFIG_PROOF_REQUEST_EXPIRY.to_string(), "-1".to_string());
+        assert_eq!(validate_config(&config).unwrap_err().kind(), VcxErrorKind::InvalidOption);
+
+        let mut config = _mandatory_config();
+        config.insert(CONFIG_VERIFY_BATCH_PARALLELISM.to_string(), "many".to_string());
+        assert_eq!(validate_config(&config).unwrap_err().kind(), VcxErrorKind::InvalidOption);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_validate_config_rejects_mixed_protocol_modes() {
+        let _setup = SetupDefaults::init();
+
+        let mut config = _mandatory_config();
+        config.insert(CONFIG_PROTOCOL_TYPE.to_string(), "5.0".to_string());
+        assert_eq!(validate_config(&config).unwrap_err().kind(), VcxErrorKind::InvalidConfiguration);
+
+        let mut config = _mandatory_config();
+        config.insert(CONFIG_PROTOCOL_TYPE.to_string(), "4.0".to_string());
+        config.insert(COMMUNICATION_METHOD.to_string(), ARIES_COMMUNICATION_METHOD.to_string());
+        config.insert(CONFIG_USE_LATEST_PROTOCOLS.to_string(), "false".to_string());
+        validate_config(&config).unwrap();
+
+        config.insert(COMMUNICATION_METHOD.to_string(), "evernym".to_string());
+        assert_eq!(validate_config(&config).unwrap_err().kind(), VcxErrorKind::InvalidConfiguration);
+
+        config.insert(COMMUNICATION_METHOD.to_string(), ARIES_COMMUNICATION_METHOD.to_string());
+        config.insert(CONFIG_USE_LATEST_PROTOCOLS.to_string(), "true".to_string());
+        assert_eq!(validate_config(&config).unwrap_err().kind(), VcxErrorKind::InvalidConfiguration);
+
+        // proprietary options are harmless for non strict protocol types
+        config.insert(CONFIG_PROTOCOL_TYPE.to_string(), "3.0".to_string());
+        config.insert(COMMUNICATION_METHOD.to_string(), "evernym".to_string());
+        validate_config(&config).unwrap();
     }
 
     #[test]
@@ -716,4 +1241,125 @@ pub mod tests {
         config["actors"] = json!(["wrong"]);
         assert_eq!(process_config_string(&config.to_string(), true).unwrap_err().kind(), VcxErrorKind::InvalidOption);
     }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_wallet_config_with_storage_plugin() {
+        let _setup = SetupDefaults::init();
+
+        set_config_value(CONFIG_WALLET_STORAGE_CONFIG, r#"{"url":"localhost:5432"}"#);
+        set_config_value(CONFIG_WALLET_STORAGE_CREDS, r#"{"account":"postgres","password":"secret"}"#);
+
+        let config: Value = serde_json::from_str(&get_wallet_config("wallet", Some("postgres_storage"), None).unwrap()).unwrap();
+        assert_eq!(config, json!({"id": "wallet", "storage_type": "postgres_storage", "storage_config": {"url": "localhost:5432"}}));
+
+        let config: Value = serde_json::from_str(&get_wallet_config("wallet", Some("postgres_storage"), Some(r#"{"url":"db:5432"}"#)).unwrap()).unwrap();
+        assert_eq!(config["storage_config"], json!({"url": "db:5432"}));
+
+        let credentials: Value = serde_json::from_str(get_wallet_credentials(None).unwrap().expose()).unwrap();
+        assert_eq!(credentials["storage_credentials"], json!({"account": "postgres", "password": "secret"}));
+
+        assert_eq!(get_wallet_config("wallet", None, Some("not json")).unwrap_err().kind(), VcxErrorKind::InvalidConfiguration);
+        assert_eq!(get_wallet_credentials(Some("not json")).unwrap_err().kind(), VcxErrorKind::InvalidConfiguration);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_with_overrides_is_scoped_to_thread_and_closure() {
+        let _setup = SetupDefaults::init();
+
+        set_config_value(CONFIG_INSTITUTION_NAME, "faber");
+        let overrides = vec![(CONFIG_INSTITUTION_NAME.to_string(), "acme".to_string())].into_iter().collect();
+
+        with_overrides(overrides, || {
+            assert_eq!(get_config_value(CONFIG_INSTITUTION_NAME).unwrap(), "acme");
+            let other_thread = ::std::thread::spawn(|| get_config_value(CONFIG_INSTITUTION_NAME).unwrap());
+            assert_eq!(other_thread.join().unwrap(), "faber");
+        });
+
+        assert_eq!(get_config_value(CONFIG_INSTITUTION_NAME).unwrap(), "faber");
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_invalid_config_is_not_applied() {
+        let _setup = SetupDefaults::init();
+
+        let before = snapshot();
+
+        let mut config = base_config();
+        config["institution_name"] = json!("changed");
+        config["agency_endpoint"] = json!("not a url");
+        assert_eq!(process_config_string(&config.to_string(), true).unwrap_err().kind(), VcxErrorKind::InvalidUrl);
+
+        assert_eq!(snapshot(), before);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_snapshot_is_not_affected_by_later_changes() {
+        let _setup = SetupDefaults::init();
+
+        set_config_value(CONFIG_INSTITUTION_NAME, "faber");
+        let snapshot = snapshot();
+
+        process_config_string(&json!({"institution_name": "alice", "institution_logo_url": "http://alice.org"}).to_string(), false).unwrap();
+
+        assert_eq!(snapshot.get_value(CONFIG_INSTITUTION_NAME).unwrap(), "faber");
+        assert_eq!(snapshot.get_value(CONFIG_TAILS_DIR).unwrap_err().kind(), VcxErrorKind::InvalidConfiguration);
+        assert_eq!(get_config_value(CONFIG_INSTITUTION_NAME).unwrap(), "alice");
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_secrets_are_masked() {
+        let _setup = SetupDefaults::init();
+
+        set_config_value(CONFIG_WALLET_KEY, "very secret key");
+
+        assert!(!format!("{:?}", snapshot()).contains("very secret key"));
+        assert_eq!(settings_as_string().get(CONFIG_WALLET_KEY).unwrap(), MASK_VALUE);
+        assert_eq!(get_secret_config_value(CONFIG_WALLET_KEY).unwrap().expose(), "very secret key");
+
+        let credentials: Value = serde_json::from_str(get_wallet_credentials(None).unwrap().expose()).unwrap();
+        assert_eq!(credentials["key"], json!("very secret key"));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_redact_config() {
+        let _setup = SetupDefaults::init();
+
+        let config = json!({
+            "wallet_name": "wallet",
+            "wallet_key": "very secret key",
+            "agent_seed": "00000000000000000000000000000000",
+            "enterprise_seed": null,
+            "storage_credentials": {"account": "user", "password": "secret password"},
+        }).to_string();
+
+        let redacted: Value = serde_json::from_str(&redact_config(&config)).unwrap();
+        assert_eq!(redacted, json!({
+            "wallet_name": "wallet",
+            "wallet_key": MASK_VALUE,
+            "agent_seed": MASK_VALUE,
+            "enterprise_seed": null,
+            "storage_credentials": MASK_VALUE,
+        }));
+        assert_eq!(redact_config("not json with very secret key"), MASK_VALUE);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_restore_puts_back_saved_settings() {
+        let _setup = SetupDefaults::init();
+
+        let saved = save();
+        set_config_value(CONFIG_INSTITUTION_DID, "changed_did");
+        set_config_value("not_saved_key", "value");
+
+        restore(&saved);
+        assert_eq!(get_config_value(CONFIG_INSTITUTION_DID).ok(), saved.get(CONFIG_INSTITUTION_DID).cloned());
+        assert!(get_config_value("not_saved_key").is_err());
+    }
 }
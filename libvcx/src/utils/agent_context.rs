@@ -0,0 +1,73 @@
+use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
+
+use indy::{INVALID_WALLET_HANDLE, WalletHandle};
+
+use settings::SettingsStore;
+
+thread_local! {
+    static CURRENT: RefCell<Option<AgentContext>> = RefCell::new(None);
+}
+
+/// State of one agent (see `agent::Agent`): its settings, wallet and the id its object handles are scoped to.
+#[derive(Clone, Debug)]
+pub struct AgentContext {
+    pub agent_id: u32,
+    pub settings: Arc<SettingsStore>,
+    pub wallet_handle: Arc<Mutex<WalletHandle>>,
+}
+
+impl AgentContext {
+    pub fn new(agent_id: u32) -> AgentContext {
+        AgentContext {
+            agent_id,
+            settings: Default::default(),
+            wallet_handle: Arc::new(Mutex::new(INVALID_WALLET_HANDLE)),
+        }
+    }
+}
+
+/// Context of the agent running on this thread, `None` when process wide settings and wallet are used.
+pub fn current() -> Option<AgentContext> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+pub fn current_agent_id() -> Option<u32> {
+    CURRENT.with(|current| current.borrow().as_ref().map(|context| context.agent_id))
+}
+
+// restores previously active context even when the closure panics
+struct Restore(Option<AgentContext>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Runs `closure` with `context` active on the current thread.
+pub fn with_context<F, R>(context: &AgentContext, closure: F) -> R where F: FnOnce() -> R {
+    let previous = CURRENT.with(|current| current.replace(Some(context.clone())));
+    let _restore = Restore(previous);
+    closure()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_with_context_nests_and_restores() {
+        assert_eq!(current_agent_id(), None);
+
+        with_context(&AgentContext::new(1), || {
+            assert_eq!(current_agent_id(), Some(1));
+            with_context(&AgentContext::new(2), || assert_eq!(current_agent_id(), Some(2)));
+            assert_eq!(current_agent_id(), Some(1));
+        });
+
+        assert_eq!(current_agent_id(), None);
+    }
+}
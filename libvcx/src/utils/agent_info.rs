@@ -1,7 +1,7 @@
 use connection::{get_agent_did, get_agent_verkey, get_pw_did, get_pw_verkey, get_their_pw_did, get_their_pw_verkey, get_version};
 use error::{VcxError, VcxErrorKind, VcxResult};
 use settings;
-use settings::{CONFIG_AGENCY_DID, CONFIG_AGENCY_VERKEY, CONFIG_REMOTE_TO_SDK_DID, CONFIG_REMOTE_TO_SDK_VERKEY, get_config_value, ProtocolTypes};
+use settings::{CONFIG_AGENCY_DID, CONFIG_AGENCY_VERKEY, CONFIG_REMOTE_TO_SDK_DID, CONFIG_REMOTE_TO_SDK_VERKEY, ProtocolTypes};
 use utils::option_util::get_or_err;
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -85,6 +85,7 @@ impl MyAgentInfo {
 }
 
 pub fn get_agent_info() -> VcxResult<MyAgentInfo> {
+    let settings = settings::snapshot();
     Ok(MyAgentInfo {
         connection_handle: None,
         my_pw_did: None,
@@ -94,10 +95,10 @@ pub fn get_agent_info() -> VcxResult<MyAgentInfo> {
         pw_agent_did: None,
         pw_agent_vk: None,
         version: None,
-        agent_did: get_config_value(CONFIG_REMOTE_TO_SDK_DID)?,
-        agent_vk: get_config_value(CONFIG_REMOTE_TO_SDK_VERKEY)?,
-        agency_did: get_config_value(CONFIG_AGENCY_DID)?,
-        agency_vk: get_config_value(CONFIG_AGENCY_VERKEY)?,
+        agent_did: settings.get_value(CONFIG_REMOTE_TO_SDK_DID)?,
+        agent_vk: settings.get_value(CONFIG_REMOTE_TO_SDK_VERKEY)?,
+        agency_did: settings.get_value(CONFIG_AGENCY_DID)?,
+        agency_vk: settings.get_value(CONFIG_AGENCY_VERKEY)?,
     })
 }
 
@@ -1,7 +1,9 @@
 use serde_json;
+use time;
 
 use error::{VcxError, VcxErrorKind, VcxResult};
 use settings;
+use utils::libindy::ledger;
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -37,6 +39,29 @@ pub fn set_txn_author_agreement(text: Option<String>,
     Ok(())
 }
 
+///
+/// Records acceptance of the agreement currently active on the ledger, so that it is appended to all write requests.
+/// `time_of_acceptance` defaults to now, `acc_mech_type` has to be one of the ledger acceptance mechanisms.
+///
+pub fn accept_ledger_txn_author_agreement(acc_mech_type: &str, time_of_acceptance: Option<u64>) -> VcxResult<()> {
+    trace!("accept_ledger_txn_author_agreement >>> acc_mech_type: {}, time_of_acceptance: {:?}", acc_mech_type, time_of_acceptance);
+
+    let author_agreement = ledger::get_txn_author_agreement()?
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidState, "Ledger has no transaction author agreement to accept"))?;
+
+    if !author_agreement.aml.contains_key(acc_mech_type) {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidOption,
+                                      format!("Acceptance mechanism {:?} is not allowed by the ledger, use one of {:?}", acc_mech_type, author_agreement.aml.keys().collect::<Vec<_>>())));
+    }
+
+    let time_of_acceptance = time_of_acceptance.unwrap_or(time::get_time().sec as u64);
+    set_txn_author_agreement(Some(author_agreement.text),
+                             Some(author_agreement.version),
+                             None,
+                             acc_mech_type.to_string(),
+                             time_of_acceptance)
+}
+
 pub fn get_txn_author_agreement() -> VcxResult<Option<TxnAuthorAgreementAcceptanceData>> {
     match settings::get_config_value(settings::CONFIG_TXN_AUTHOR_AGREEMENT) {
         Ok(value) => {
@@ -50,7 +75,7 @@ pub fn get_txn_author_agreement() -> VcxResult<Option<TxnAuthorAgreementAcceptan
 
 #[cfg(test)]
 mod tests {
-    use utils::devsetup::SetupDefaults;
+    use utils::devsetup::{SetupDefaults, SetupMocks};
 
     use super::*;
 
@@ -99,6 +124,23 @@ mod tests {
         assert_eq!(expected_meta, meta);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn accept_ledger_txn_author_agreement_works() {
+        let _setup = SetupMocks::init();
+
+        assert_eq!(accept_ledger_txn_author_agreement("unknown mechanism", None).unwrap_err().kind(), VcxErrorKind::InvalidOption);
+        assert!(get_txn_author_agreement().unwrap().is_none());
+
+        accept_ledger_txn_author_agreement("acceptance mechanism label1", Some(TIME_OF_ACCEPTANCE)).unwrap();
+
+        let meta = get_txn_author_agreement().unwrap().unwrap();
+        assert_eq!(meta.text, Some("Default indy agreement".to_string()));
+        assert_eq!(meta.version, Some("1.0.0".to_string()));
+        assert_eq!(meta.acceptance_mechanism_type, "acceptance mechanism label1");
+        assert_eq!(meta.time_of_acceptance, TIME_OF_ACCEPTANCE);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn get_txn_author_agreement_works_for_not_set() {
@@ -0,0 +1,128 @@
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use error::prelude::*;
+use utils::object_cache::ObjectCache;
+
+lazy_static! {
+    static ref TOKEN_MAP: ObjectCache<CancellationToken> = ObjectCache::<CancellationToken>::new("cancellation-tokens-cache");
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<CancellationToken>> = RefCell::new(None);
+}
+
+/// Flag shared by the caller and operations it may cancel, see `with_token`.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+pub fn create_token() -> VcxResult<u32> {
+    TOKEN_MAP.add(CancellationToken::new())
+}
+
+pub fn get_token(handle: u32) -> VcxResult<CancellationToken> {
+    TOKEN_MAP.get(handle, |token| Ok(token.clone()))
+}
+
+/// Cancels operations running with the token, a cancelled token stays cancelled.
+pub fn cancel(handle: u32) -> VcxResult<()> {
+    TOKEN_MAP.get(handle, |token| Ok(token.cancel()))
+}
+
+pub fn is_valid_handle(handle: u32) -> bool {
+    TOKEN_MAP.has_handle(handle)
+}
+
+pub fn release(handle: u32) -> VcxResult<()> {
+    TOKEN_MAP.release(handle)
+}
+
+pub fn release_all() {
+    TOKEN_MAP.drain().ok();
+}
+
+// restores previously active token even when the closure panics
+struct Restore(Option<CancellationToken>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Runs `closure` on the current thread, cancellation of `token` is observed by `check` calls made
+/// by the closure. The operation stops at the first check after the token was cancelled, network
+/// requests in flight stop waiting for the response, see `http_transport::Transport::exchange`.
+pub fn with_token<F, R>(token: &CancellationToken, closure: F) -> R where F: FnOnce() -> R {
+    let previous = CURRENT.with(|current| current.replace(Some(token.clone())));
+    let _restore = Restore(previous);
+    closure()
+}
+
+/// Token of the operation running on the current thread, to be entered by threads working for it.
+pub fn current() -> Option<CancellationToken> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Called by the running operation right before its first side effect (message sent, wallet
+/// updated, ...). The operation is not cancelled from this point on, so it is either completed or
+/// it has not changed anything.
+pub fn commit() {
+    CURRENT.with(|current| *current.borrow_mut() = None);
+}
+
+/// Fails with `Cancelled` when the token of the running operation was cancelled.
+pub fn check() -> VcxResult<()> {
+    let cancelled = CURRENT.with(|current| current.borrow().as_ref().map(CancellationToken::is_cancelled).unwrap_or(false));
+    if cancelled {
+        return Err(VcxError::from_msg(VcxErrorKind::Cancelled, "Operation was cancelled"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_check_observes_token_of_current_thread() {
+        check().unwrap();
+
+        let handle = create_token().unwrap();
+        let token = get_token(handle).unwrap();
+
+        with_token(&token, || {
+            check().unwrap();
+            cancel(handle).unwrap();
+            assert_eq!(check().unwrap_err().kind(), VcxErrorKind::Cancelled);
+            with_token(&CancellationToken::new(), || check().unwrap());
+            assert_eq!(check().unwrap_err().kind(), VcxErrorKind::Cancelled);
+
+            commit();
+            check().unwrap();
+        });
+
+        check().unwrap();
+        release(handle).unwrap();
+        assert!(!is_valid_handle(handle));
+    }
+}
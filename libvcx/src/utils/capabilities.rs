@@ -0,0 +1,102 @@
+use serde_json;
+
+use aries::messages::a2a::protocol_registry::ProtocolRegistry;
+use aries::messages::discovery::disclose::ProtocolDescriptor;
+use error::prelude::*;
+use settings::{self, ProtocolTypes};
+
+/// Protocols and modes active with the current settings.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Capabilities {
+    pub protocol_type: ProtocolTypes,
+    /// Aries protocols are used for connections, credentials and proofs.
+    pub aries: bool,
+    /// Set by `protocol_type` "4.0", proprietary messages and serialized objects are rejected.
+    pub strict_aries: bool,
+    /// Downloaded messages with proprietary payloads are decoded.
+    pub proprietary_messages: bool,
+    pub payments: PaymentCapabilities,
+    pub revocation: RevocationCapabilities,
+    /// Aries protocols disclosed to counterparties, `disabled_protocols` and `actors` taken into account.
+    pub protocols: Vec<ProtocolDescriptor>,
+    pub disabled_protocols: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PaymentCapabilities {
+    /// Libvcx is built with the `payments` feature.
+    pub enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_method: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RevocationCapabilities {
+    /// Tails files of new revocation registries are published to this server.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tails_server_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tails_dir: Option<String>,
+}
+
+impl Capabilities {
+    pub fn to_string(&self) -> VcxResult<String> {
+        serde_json::to_string(self)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize capabilities: {:?}", err)))
+    }
+}
+
+pub fn get_capabilities() -> Capabilities {
+    let strict_aries = settings::is_strict_aries_protocol_set();
+    let payments_enabled = cfg!(feature = "payments");
+    let settings = settings::snapshot();
+
+    Capabilities {
+        protocol_type: settings::get_protocol_type(),
+        aries: settings::is_aries_protocol_set(),
+        strict_aries,
+        proprietary_messages: !strict_aries,
+        payments: PaymentCapabilities {
+            enabled: payments_enabled,
+            payment_method: if payments_enabled { Some(settings::get_payment_method()) } else { None },
+        },
+        revocation: RevocationCapabilities {
+            tails_server_url: settings.get_value(settings::CONFIG_TAILS_SERVER_URL).ok(),
+            tails_dir: settings.get_value(settings::CONFIG_TAILS_DIR).ok(),
+        },
+        protocols: ProtocolRegistry::init().protocols(),
+        disabled_protocols: settings::get_disabled_protocols(),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use utils::devsetup::SetupDefaults;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_capabilities() {
+        let _setup = SetupDefaults::init();
+
+        settings::set_config_value(settings::CONFIG_PROTOCOL_TYPE, "4.0");
+        settings::set_config_value(settings::CONFIG_DISABLED_PROTOCOLS, r#"["basicmessage"]"#);
+
+        let capabilities = get_capabilities();
+        assert!(capabilities.aries);
+        assert!(capabilities.strict_aries);
+        assert!(!capabilities.proprietary_messages);
+        assert_eq!(capabilities.disabled_protocols, vec!["basicmessage".to_string()]);
+        assert!(!capabilities.protocols.is_empty());
+        assert!(capabilities.protocols.iter().all(|protocol| !protocol.pid.contains("basicmessage")));
+
+        let capabilities: serde_json::Value = serde_json::from_str(&capabilities.to_string().unwrap()).unwrap();
+        assert_eq!(capabilities["protocol_type"], "4.0");
+
+        settings::set_config_value(settings::CONFIG_PROTOCOL_TYPE, "3.0");
+        let capabilities = get_capabilities();
+        assert!(capabilities.aries);
+        assert!(!capabilities.strict_aries);
+        assert!(capabilities.proprietary_messages);
+    }
+}
@@ -41,6 +41,7 @@ pub const CONNECTED_RESPONSE: &'static [u8; 162] = &[129, 167, 98, 117, 110, 100
 pub const CONNECTED_RESPONSE_DECRYPTED: &str = r#"{"@type":"did:sov:123456789abcdefghi1234;spec/onboarding/1.0/CONNECTED","withPairwiseDID":"XSasL1cESeSJ2v9wMYeXBf","withPairwiseDIDVerKey":"HbJb8uKp4mtjhnNknP66GgmUMYta6XArNaA4WJDEyyv9"}"#;
 pub const AGENT_CREATED: &'static [u8; 166] = &[129, 167, 98, 117, 110, 100, 108, 101, 100, 145, 220, 0, 142, 208, 131, 208, 165, 64, 116, 121, 112, 101, 208, 130, 208, 164, 110, 97, 109, 101, 208, 173, 65, 71, 69, 78, 84, 95, 67, 82, 69, 65, 84, 69, 68, 208, 163, 118, 101, 114, 208, 163, 49, 46, 48, 208, 175, 119, 105, 116, 104, 80, 97, 105, 114, 119, 105, 115, 101, 68, 73, 68, 208, 182, 65, 52, 97, 54, 57, 113, 97, 102, 113, 90, 72, 80, 76, 80, 80, 117, 53, 74, 70, 81, 114, 99, 208, 181, 119, 105, 116, 104, 80, 97, 105, 114, 119, 105, 115, 101, 68, 73, 68, 86, 101, 114, 75, 101, 121, 208, 217, 44, 53, 119, 84, 75, 88, 114, 100, 102, 85, 105, 84, 81, 55, 102, 51, 115, 90, 74, 122, 118, 72, 112, 99, 83, 55, 88, 72, 72, 120, 105, 66, 107, 70, 116, 80, 67, 115, 121, 110, 90, 116, 118, 52, 107];
 pub const AGENT_CREATED_DECRYPTED: &str = r#"{"@type":"did:sov:123456789abcdefghi1234;spec/onboarding/1.0/AGENT_CREATED","withPairwiseDID":"DnEpUQJLupa5rKPkrKUpFd","withPairwiseDIDVerKey":"7y118tRW2EMJn18qs9MY5NJWYW2PLwV5QpaLyfoLHtgF"}"#;
+pub const AGENT_PROVISIONED_DECRYPTED: &str = r#"{"@type":"did:sov:123456789abcdefghi1234;spec/agent-provisioning/0.7/AGENT_CREATED","selfDID":"DnEpUQJLupa5rKPkrKUpFd","agentVerKey":"7y118tRW2EMJn18qs9MY5NJWYW2PLwV5QpaLyfoLHtgF"}"#;
 // The byte arrays come directly from the agency team's documentation
 pub const CREATE_KEYS_V2_RESPONSE: &'static [u8; 343] = &[123, 34, 109, 101, 115, 115, 97, 103, 101, 34, 58, 34, 123, 92, 34, 64, 116, 121, 112, 101, 92, 34, 58, 92, 34, 100, 105, 100, 58, 115, 111, 118, 58, 49, 50, 51, 52, 53, 54, 55, 56, 57, 97, 98, 99, 100, 101, 102, 103, 104, 105, 49, 50, 51, 52, 59, 115, 112, 101, 99, 47, 99, 111, 110, 110, 101, 99, 116, 105, 110, 103, 47, 48, 46, 54, 47, 75, 69, 89, 95, 67, 82, 69, 65, 84, 69, 68, 92, 34, 44, 92, 34, 119, 105, 116, 104, 80, 97, 105, 114, 119, 105, 115, 101, 68, 73, 68, 92, 34, 58, 92, 34, 77, 78, 101, 112, 101, 83, 87, 116, 71, 102, 104, 110, 118, 56, 106, 76, 66, 49, 115, 70, 90, 67, 92, 34, 44, 92, 34, 119, 105, 116, 104, 80, 97, 105, 114, 119, 105, 115, 101, 68, 73, 68, 86, 101, 114, 75, 101, 121, 92, 34, 58, 92, 34, 67, 55, 51, 77, 82, 110, 110, 115, 52, 113, 85, 106, 82, 53, 78, 52, 76, 82, 119, 84, 121, 105, 88, 86, 80, 75, 80, 114, 65, 53, 113, 52, 76, 67, 84, 56, 80, 90, 122, 120, 86, 100, 116, 57, 92, 34, 125, 34, 44, 34, 114, 101, 99, 105, 112, 105, 101, 110, 116, 95, 118, 101, 114, 107, 101, 121, 34, 58, 34, 50, 112, 70, 109, 113, 97, 98, 119, 75, 82, 82, 109, 80, 54, 76, 117, 67, 99, 121, 65, 70, 101, 107, 56, 77, 109, 68, 75, 107, 107, 102, 100, 72, 111, 100, 116, 57, 103, 90, 49, 67, 88, 74, 52, 34, 44, 34, 115, 101, 110, 100, 101, 114, 95, 118, 101, 114, 107, 101, 121, 34, 58, 34, 65, 66, 117, 89, 121, 84, 87, 90, 120, 113, 53, 88, 98, 105, 54, 90, 69, 119, 119, 121, 49, 97, 51, 69, 88, 51, 90, 68, 56, 100, 105, 112, 115, 109, 102, 106, 81, 53, 75, 116, 71, 116, 115, 120, 34, 125];
 pub const DELETE_CONNECTION_RESPONSE: &'static [u8; 81] = &[129, 167, 98, 117, 110, 100, 108, 101, 100, 145, 220, 0, 59, 204, 130, 204, 165, 64, 116, 121, 112, 101, 204, 130, 204, 164, 110, 97, 109, 101, 204, 179, 67, 79, 78, 78, 95, 83, 84, 65, 84, 85, 83, 95, 85, 80, 68, 65, 84, 69, 68, 204, 163, 118, 101, 114, 204, 163, 49, 46, 48, 204, 170, 115, 116, 97, 116, 117, 115, 67, 111, 100, 101, 204, 166, 67, 83, 45, 49, 48, 51];
@@ -110,6 +111,7 @@ pub static SUBMIT_SCHEMA_RESPONSE: &str = r#"{"op":"REPLY","result":{"reqId":152
 pub static GET_SCHEMA_REQ: &str = r#"{"reqId":1526055830436897605,"identifier":"2hoqvcwupRTUNkXn6ArYzs","operation":{"type":"107","dest":"2hoqvcwupRTUNkXn6ArYzs","data":{"name":"test-licence","version":"4.4.4"}},"protocolVersion":1}"#;
 pub static GET_SCHEMA_RESPONSE: &str = r#"{"result":{"state_proof":{"multi_signature":{"value":{"state_root_hash":"81s3UgVN47doEdWzg897EssC5aCMZ5g2bHAFHXgJyUGe","pool_state_root_hash":"DuhjUiR6QDsT4X3KFTGHgPnaCCTTVMhmmA8uRwkkhDwA","txn_root_hash":"49dVmci81excka4Ff17uUPY6NyK5Fkyb7mxKQxHXGUXT","timestamp":1526055830,"ledger_id":1},"signature":"R5DPg3mctNrPwbzb7qCbo5LkTC2nnLj1jvuRSZDypD4FdFoH5eHp3v5vYvQaLkGfPnG9bmPwXgdhwrybsrTm9RvXj15MYxcfnBDACCjFCeAxjEKaWU6ebyJKi8UsGPiQzJVgVNaD6yvLNtvwzn6r9UhZ3wFVjWRu3M9sBfLZPCTE1m","participants":["Node1","Node4","Node3"]},"root_hash":"81s3UgVN47doEdWzg897EssC5aCMZ5g2bHAFHXgJyUGe","proof_nodes":"+QiL+FyFIC40LjS4VPhSuFB7ImxzbiI6MjQ3MSwibHV0IjoxNTI2MDU1ODMwLCJ2YWwiOnsiYXR0cl9uYW1lcyI6WyJhZ2UiLCJoZWlnaHQiLCJuYW1lIiwic2V4Il19feIWoKS+yLqQrrlh0AHdXeEgErYxfmhpVzaJ+U4XV9IhNgFz+DmXAIb3F2Y3d1cFJUVU5rWG42QXJZenM6Cg0ey22y1Sw5R0Wr517KDVTtxr1h+dgvtZsXyDnQ5IEjL5AbGgT71lC6jsMTHl3Pa1Rj1nmP8643p6YpwARnmr8pcYPVSg0mYAZNq3qZ1kBfzZ4IkJ9JPoSY9sNt5pSiPy1Sesoy6giTaZI9nDaWnk1Z5+KCJl+Go7TXmmgTHqURkcvCaGJvmAoNyumIZbkdqKaRnLJHMvQHk66iXOKc0eO9fAg0S6IweyoKOlTCQeHHpiiRIqOWf+ZiyoE1RzGLYwYv\/ZT92bj5TUoDYsQFMS2a6ItdDw7pD7FMbhAFy50WgvpLtc5N3kUoPnoEFpsFh4\/nnPQ9a7uJuO1aOF\/0eLZZPbk61GqZrhuH1goPfPWQgWLjM3nhcoyL5\/2KsHiwGTaXLKfB7UvL5Yk3u9oAoTTzqKqJFLJXTlBzO3RoFrsMHHRV7XiX\/ZNGqkeZLdoP2ODKKueuDUgH+081Su3N5ByHvgMGaU9dg+JxJCH351oL8QgC6a78sPnRhjdbUStwqlPP4TOGrPyYC4FAYtN\/A9gKBH\/5JTxilhi+ZrkUrfkkO2KYas7GIQW+HUecA6Ng0sH4Cggh7qzc1C\/0n1Os53lZb9INU33xFXaUSGn9E8NKkFUoyA+FGAgKA8XWevrjMeCJVdfpqrFMz3kn4gCHY9zstqAaYG7QRex6Cug+aEkajVK5kcv41wZi\/8t5dMKsZLmiDeQEkYUiForYCAgICAgICAgICAgID5AXGgdHHcBnO5LERbEZl44uTuxdCRy3YCHcyvM\/iroJzNdACgz7b3Y6RqprH64dbOJiWxR13LjcOakD6+ZdicWWEXM8yg\/6deXgumdcqhse0H2bS5U411JnaRZBYIEMPp1xkFGtKgaNnmuK+WwSL9pcmIZFJI5osNECREaxf7K1eOEXc0RqKgTnhZ9UgjsuSSGru0N3GIAy8BMivi0gX\/AWH8qQDpstCgbNiTG4lUqf96EyWojSovUtrz+XUpH+8N2+QFCrKpIOygv2R1F2JXyEqh2AXalY2VldRciIPsr7EWFAabOG4RKzugyDzJ72wkDeDMP19UfN\/lfpDFo9XnSIV4iHHjgz3QU4igGQ0k1eP\/uLZvThw6V0SemmquhaNp77pRQoQwMEtkdVqgqy7KI1QOQoBZgJjBqJ\/i56QfzUF1gKUu6voQvyQf3fegLUg5S0QHBY4ldJxdLdWpVfksMNnDVoeIOk4YaoY9TsiAgICAgIDkggA6oEwv9bhu5R8nrnUt4Yx3+oB0SXXXuZBO3b28qAICCOSd7owXN0LWxpY2VuY2U6OgOFNinrJ5T+l1MMwSVWJf5WOoYjFNf\/XZ3zVKmxgUnHP4sYCAgKC0dldFCLyH+sCHRrFr5phR9NjdPw6AKv0zlZKTn3x2OaDTgNSzkxwEcmAnqqgFicXYnk+U1UR9Zr5GW\/kNcr\/QpaBSnbcCh57QsvePUsmWURFq3G2WYfLinvZvbN7djK1eJaAZ1CRb5ndo+3SSaqOyg0hBd1UVFWwvDv9WxWwXaoEz4qBt6QgRVzUf5A9QoRzs\/BqQN3HRYS9mbvGzr8i8gQBsvoCAgICAgICAgPhRgICAgICAoMmjsuJicY9FzZqlzDRPdp3\/d6e2ete+uTUuGlLZrfEkgICAgICAgKA+q5fnel\/\/ZFjKfWOn3PScO8k6Qdki79w+y2ZACYUTZoCA+FGAgICAgKC4hIGO4DPcaKYmRpzNYUAXu57xExF9yyxyGD03dg3nCoCAgKBrY\/1SacMf6LNQ\/VBpr9p\/hAkk1AY6XrpoAxZW4aaOrYCAgICAgID4kYCgj6RlWm2C5oUBN0vbyaI+Hxh7AzbmI1SYINZ8bGj7FHygSwYrTkPDB\/SU3Yi8+UJW60leF09Xf4NDHIxnkH+XR8egHPodFdkcnKxOiLXtlpOuzM\/THPYfTQ9xWanloSw8uU6g1iRB91Rq8zT7dTIl1FIPXg0ovtW\/gktKvMVp\/XNxoOOAgICAgICAgICAgID5AhGgZBqeju\/DK9w6gsSRyeuChMfPiz3O5CJOt05Cwx6+Zq6g\/qAj00WnUVvBv214PQY\/n6Hz3ge+kdXc7bRq2zC31cCgK9Hxi\/K6rqzGHb2hV1UhJwZV9RZh9kW\/ROGpoNzP26qgWiSIFqr4VJmXkDDFgkHrnTBOnefFNXuDTl9ug1V3K86gsnM3Tnk5\/vzBW5+41I7cwvW1\/77F2bdmGlnc0xmx4TegMZvW8Kl\/KJS0Z3NItqp8zhKrxs+VwSHasFQG6ySr\/ROgPZToxrJxpSH9egAT4t\/cIXgXmeM9Lgqnk7ZWXWPcbN+g0kXS+Xw39zJ5DUOqKiMIKl3hcaVTIgnAEmke90PmIo+gMKg2q8Gla7GKLXYh5ZBAvGaZM0zm8zZEsu1WRLYePSSgqvwhtqLvuNKkBdPMSDAO28IarXiYXDhae80T+EiDK32gmO6lIx+XA5ydY7qyl\/c41qlWvyLmJIyhd71wOGS2xhmgwDrBtPzkPBdieH2gS50vDXWH7zUgksxbCX4lBbnlJV6gS9n9ZPT3Q7ICigKtf7\/6y383dDp\/junzRl9OfxsgYEKgtc5LYJgXPhA6bX7ma\/iOGn3C6DOpahza9mEQxCb5APCgUKah9cJa5x9dtgVS6rymjOoNTbrSuw+AxvrqhZgYgI6gE+UVSRl8WVc4BZyM+zE9W5OEO58lpO97\/pTn2PDkiB+A"},"type":"107","reqId":1526055830436897605,"seqNo":2471,"data":{"version":"4.4.4","attr_names":["age","height","name","sex"],"name":"test-licence"},"txnTime":1526055830,"dest":"2hoqvcwupRTUNkXn6ArYzs","identifier":"2hoqvcwupRTUNkXn6ArYzs"},"op":"REPLY"}"#;
 pub static SCHEMA_JSON: &str = r#"{"ver":"1.0","id":"2hoqvcwupRTUNkXn6ArYzs:2:test-licence:4.4.4","name":"test-licence","version":"4.4.4","attrNames":["height","name","sex","age"],"seqNo":2471}"#;
+pub static CREDENTIAL_DATA: &str = r#"{"height": "170", "name": "alice", "sex": "female", "age": "25"}"#;
 pub static CRED_DEF_ID: &str = r#"2hoqvcwupRTUNkXn6ArYzs:3:CL:2471"#;
 pub static CRED_DEF_CREATE_JSON: &str = r#"{"ver":"1.0","id":"2hoqvcwupRTUNkXn6ArYzs:3:CL:2471","schemaId":"2471","type":"CL","tag":"tag_1","value":{"primary":{"n":"86724287350477751206656570979032966703329505889109970100672593600212159710122524558840987784946586074662132488236400738181991816412026591140984921370179767079606062874849350699068544007953394425621170412614434471437870413981961728213356970896311390324401528063993712079175929421151002066958792621968526123052543362726709351064982035483300909753311188927834431210143615918489181734829742678671085195486779344279087570275333764801067819032638761795735427697089756560490683732524286268242592541483561421003008848905312960263229239327096444856373881655366169784000740449827583427336247268796008957239930929940106124383441","s":"68565823032708474306514409710254746185097024905866456492400609548513882080060651547254886717209317635536647955345162687060898443709138147278667084384903589486496041039093776147061990629509796556903457618318946948477020316739616698285098064517065210309432231348425794038491136239768243653004282415622183771020331818660661942833453261537705421796886050819425946416534064887172474631878946648680969678450250632713392749852095439858412085632624401434183699289763190966729771549896627986528002008455144081216013915376906960616224100785288425229004552127157820129850247623860663716001315305824246521524314406787572201936159","rms":"57279696110727020230628302576589700618183762363882960191767791103858409422237116150822746386702678537060617035162158771261021205326414811089995155468452732347369957531042614743687780641303019056601049908281597307105484509385119697735678823173668624081938728770800068935332426199032434097694521029943581293515608277055518864299564909952705207331157426572681898162714820056622424494845195263946986591012537422825086117775467510208980394650070899723933489945234503492645580931663241570210025903493959090620866405770418934478358877241756698114261254562875594267266148011094739462828069179559394070466727563845901362557339","r":{"sex":"20022101741446570264557630399489699993011383357495333780962515915463701761385865327966524872646907887816919241795166812245521381526599594939708271733894974601885444697364264320467230587792262093722414489552649750887746173699408773581709694927717324336563255309885303903452791340447296644230298702852495919610048570998314143211678209843631329899528727189541506380135323384697685129288034139901316951883056009308816474794904871533784876333707034390658024899757223718233822416033410932755695918855013227912649125625613708302826940395050959425506559689930729589702715565613350872257404294852981485742693414175561560376878","name":"21470694100729869744261292006763813345150044183446029540177870124524039106316094174460389232832571532284810170007293388299473733141322565289343893281311432223107289867486737597507381929324412597111791890822982978607377646493268665410154158352332552430455410676424655538131806962743837140975034501945349610114128040516920426419752180449876110429631390074018339624366625706057577988994157784979295579958266166722663022918076663412107247120922543421973109778231583641494528977463571610522286622037737315024310026589510134042108138559662654575330826743445692198806461970153226357910705261181205457260549327597981951573985","age":"68324431017386373141723313379588123114122172733785498028129098771912332870161622586830226725832682089282797682478996628527458521203981066532148542867910727876973540501604298386964941331385945509447921573334640257131480681017298967411615851997278468982114318053852745062107685596645860574744820479443008568550713733647938784629517351521220040117482812057845591724691129906359015250113706837112935971415735663780322204054449465947028328022534972551186400163012220719718039176357586730457386744557089473465341563472760872536594611618365110919837962302554808225102415883187587908311141004150113254350300861684974387245524","height":"13939356647759609174485719802501773567276049548245424935504126500265586608662951980260718040791134755864486701853050225651855956737901595730976263505088594378380074293405559194748221759603581890598079117782820527925868489919600695104312261805970657325246956081208878862007439057712465023318674543859367201399080194572966833003881512520725074633033420930095211305635173717467241968613481516063152487980820555533755239133651769565133420156114776578165410212221881994925880629770028898705859268663836663647027677768478228049082189465977511902707373041793879289766830466362779198318255196657839703097701957436211432866940"},"rctxt":"28968968789316921956195020159043701485128084666788319688919703768305257857030853826651494435280634703130965749799263847281089261890586404514554128897545567559411392274924672850695923428556666839524191639557540925894441247523915911594915810062115573022491175505153277512476305377826383134343681670368530974886362266706187655064207938621808588725531887931062314044094064943783008218642721898688548716600959291789899808528982572786472183338043212505885146997913313141282478028502666021364027851488363355869491118603841447843857893374823843951923935275949332495109370644946403701670919133621008245580388712016814216268114","z":"86577229265747009560052615679615895419349481058284021085673929260636969170535560390660022683481326803220581815884464749038506104457182906050929596940529151731519096012460995580213574923115394453664191902057791953043346629148369777542113599040511920597866451649154423915282977750060280596799442262757753909295114858121438822608110664099684925371671089644086196379734044256966499814680946051154187391597679900273906654730748525684870945928000260002993274218927561839707361816863918092159839143300439497578075981381842189307826991559468115699410540879665465127893208243916471732332308562108973634353200261591256745812234"}}}"#;
 pub static CRED_DEF_REQ: &str = r#"{"reqId":1523973501515533537,"identifier":"2hoqvcwupRTUNkXn6ArYzs","operation":{"ref":1697,"data":{"primary":{"n":"105439650298099224472719164376596161045251617303168281910159687390977210338964410885966961049370963844109577113880208775752790942154227411982272072507400975072160645232526715327797164824882560229420816283794257946916740324879507082186216013343695279033274312621248936890473037663661888132238733207284797797060688270822706628589089845988721591601541996988579232618488160302787750229030765767569290666862257482182922041829167922591858800108229336185234275773072436922194471127622834341457625330335523970056008991104547541087181274952806206555492380014454388457015641855319853631730897968076440148482735658679509195973229","s":"60953486107303437254858098347112147855961082010529831543925121348034850203115179124876298317648430279679361478189647964535496561070909197001914193094619169504223685018738630978862147708598750398578637328530459556539705596731435008410479736235986865330309807581578290511666662695997158909186371964511139813592297961190597658676380863143274259740580857329319695392485506412357170157397251363465407140774676065910188698127696232880383421764518063443167071100572409790068476991316727051224978393235952176964765376235500533058252877890769219600806170662345836407755729775125521367096071093946270223902491749179598598240007","rms":"74927454739683193439836053944526071131189312874633507963810001217818493539308966842764274972289273602083051643813618047300322726400114830941907959912717178969765593604740488276899078109381885609788262983797782928349065607840985318164148517237153559638617138176498396271661087369503798847309432340923099252340037814242208995379813666930434450480744618281930932894264779695378264474657623220969389369551953686671141939280549610978755889072299795725034713880500651539126902925590569165941662431316992627881973207959883233408720167917915773917493866543309246115826765013674414949313549319840364505706466381318770512814513","r":{"age":"72390727859526948598769839682288390417473671455179114560673764547964449836342363303058321492471438760541948864022003709321744455120254768175223293596449996826595273724760729320481096733331991948430678036676757625299731983178650561399678877389754749619112078949918941391493061822465137113510854600441267452572649498335187207492469387033617053431889624608855193996184402156803341574574511582253212976919203777637748764743645048893306642529788023632615356942928857470649044023895302779761685893773882231358057913710209964999765674027089182383791272684258727765842629428098443892104875788252562359687604843567250668466205","height":"70543652063920845859154008892668197469045463230526467788992453250348274988780226904184587428157474155841194019499287047784141303197449207734445797115252509103173309018363853666184086317902822370905889636406504062785864995352868401033791533530804265504197284651856689740677707908492077183476817196045018228102285116176439517159284338817174978088675004826034802582049787189053101500675154143481246788606272330820806000588773724331753554396126402363549248687990840343827936617495186162626312787826658827586407676203229548227961504603200983549319856619144110464382075502272335203569685652507088214819921524626565054602963","name":"103527417395412238888732428105082823940624567978540514467897305199021605169073958599584563894272332281816005031812861208644363591031241317456947092556057770193455192272881863813069073457043008807897251607948372819249506183283490033835716773361393443808593327403475966545819207102769478475664467796886363393530041838279722696582611568824236467218032392449201020447076530962681867315415363010583982718641987445364085004038446070026640396028415200813760916865414789141811236715013530115289187565164408806242055105072455180019610563498271520111485109375480738234744593620273745298286777388987749644151152269778723686076551","sex":"17617387932224727732199032309657311959653464327684904565665262116333485725099438740880498162786199759421174664300234192351685303611995960534494608414353666969417211685468384065816318438447271709427463460177681252105735773652693767982232188452219217616374492905901821779824332438132561291598134824044704078907285050025183391837108116981759639815190415115487913965779048079428260169329853396984184424485339773406425546899648016014736200060341710952899752230800885097739382453047106269305723853221158955761756922247572130749739616875269361527280465929778539688076462942946174197729389230963102291452423078070870962973619"},"rctxt":"4404315251773590316656822787297959622496576836552374156390520232552296453794129216754026817555078815021049615728085929219015364921450431226188774309944820398259891709060280718051557490803398313760947065810439719714123819895408512016106880827892821233111458571519330166816136132697559648590250134807031359214726816199106700734839255716500676798699117354732874112511742243712694381626446626419345668001579277348432636527446529529969165947242879634940857763624341205912060086708313947345578092110014431367213490048611612229350045080032675964138831029782870097773300884700106789138689225969924786204294929718640563353955","z":"9968790886085769451934843209308305336514488713326446790495438688479920764117604659751410885142587285217733003414264977016448602413241027962458674221915402914676994791840660414497108187556909054907402395620183134598633811934865000705151053208456858874619150881386069361745000551586712840940266532760237223420286143567858962716749251821165461773542587677264987691089537728906914092795339930821335526641158054600048907638649153305825526849538353282672732507140412280831764364628783212076771504299767486342751820345866765313763582577366603726245983948166163461384231297824883993328529043873547398892685961181132345769239"}},"type":"102","signature_type":"CL"},"protocolVersion":1}"#;
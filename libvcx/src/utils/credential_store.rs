@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use error::prelude::*;
+use aries::messages::issuance::CredentialValue;
+use utils::agent_context;
+
+lazy_static! {
+    // stores are set per agent, see `agent::Agent`
+    static ref CREDENTIAL_STORES: RwLock<HashMap<Option<u32>, Arc<dyn CredentialMetadataStore>>> = Default::default();
+}
+
+/// Non-secret description of a credential held by the holder.
+/// It never contains the credential itself, only the data a wallet UI needs to list credentials.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct CredentialMetadata {
+    pub source_id: String,
+    pub thread_id: String,
+    pub state: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cred_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cred_def_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuer_did: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub attributes: Vec<CredentialValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issued_at: Option<u64>,
+    pub updated_at: u64,
+}
+
+/// Application provided store receiving credential metadata on every state change of a holder.
+pub trait CredentialMetadataStore: Send + Sync {
+    fn store(&self, metadata: &CredentialMetadata) -> VcxResult<()>;
+
+    fn remove(&self, thread_id: &str) -> VcxResult<()>;
+}
+
+/// Sets store of the current agent, `None` removes it.
+pub fn set_credential_metadata_store(store: Option<Box<dyn CredentialMetadataStore>>) {
+    let agent_id = agent_context::current_agent_id();
+    let mut stores = CREDENTIAL_STORES.write().unwrap();
+    match store {
+        Some(store) => stores.insert(agent_id, Arc::from(store)),
+        None => stores.remove(&agent_id)
+    };
+}
+
+pub fn is_credential_metadata_store_set() -> bool {
+    _current_store().is_some()
+}
+
+// store is cloned out so that the application can (un)set stores from within it
+fn _current_store() -> Option<Arc<dyn CredentialMetadataStore>> {
+    CREDENTIAL_STORES.read().ok()
+        .and_then(|stores| stores.get(&agent_context::current_agent_id()).cloned())
+}
+
+// Mirroring is best effort: failure of the application store must not break the credential exchange
+pub fn mirror_credential_metadata(metadata: &CredentialMetadata) {
+    trace!("mirror_credential_metadata >>> thread_id: {}, state: {}", metadata.thread_id, metadata.state);
+
+    if let Some(store) = _current_store() {
+        if let Err(err) = store.store(metadata) {
+            warn!("Cannot mirror credential metadata for thread_id: {}, err: {}", metadata.thread_id, err);
+        }
+    }
+}
+
+pub fn remove_credential_metadata(thread_id: &str) {
+    trace!("remove_credential_metadata >>> thread_id: {}", thread_id);
+
+    if let Some(store) = _current_store() {
+        if let Err(err) = store.remove(thread_id) {
+            warn!("Cannot remove credential metadata for thread_id: {}, err: {}", thread_id, err);
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    pub struct InMemoryCredentialStore {
+        pub records: Arc<Mutex<HashMap<String, CredentialMetadata>>>
+    }
+
+    impl CredentialMetadataStore for InMemoryCredentialStore {
+        fn store(&self, metadata: &CredentialMetadata) -> VcxResult<()> {
+            self.records.lock().unwrap().insert(metadata.thread_id.clone(), metadata.clone());
+            Ok(())
+        }
+
+        fn remove(&self, thread_id: &str) -> VcxResult<()> {
+            self.records.lock().unwrap().remove(thread_id);
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_mirror_credential_metadata() {
+        let store = InMemoryCredentialStore::default();
+        set_credential_metadata_store(Some(Box::new(store.clone())));
+        assert!(is_credential_metadata_store_set());
+
+        let metadata = CredentialMetadata { thread_id: "thread".to_string(), state: 3, ..CredentialMetadata::default() };
+        mirror_credential_metadata(&metadata);
+        assert_eq!(store.records.lock().unwrap().get("thread"), Some(&metadata));
+
+        remove_credential_metadata("thread");
+        assert!(store.records.lock().unwrap().is_empty());
+
+        set_credential_metadata_store(None);
+        assert!(!is_credential_metadata_store_set());
+    }
+}
@@ -59,6 +59,15 @@ macro_rules! check_useful_opt_c_str {
     }
 }
 
+/// Output pointer helpers
+macro_rules! check_useful_out_ptr {
+    ($ptr:ident, $e:expr) => {
+        if $ptr.is_null() {
+            return VcxError::from_msg($e, "Invalid pointer has been passed").into()
+        }
+    }
+}
+
 /// Vector helpers
 macro_rules! check_useful_c_byte_array {
     ($ptr:ident, $len:expr, $err1:expr, $err2:expr) => {
@@ -116,6 +116,17 @@ pub static INVALID_REDIRECT_DETAILS: Error = Error { code_num: 1104, message: "I
 pub static NO_AGENT_INFO: Error = Error { code_num: 1106, message: "Agent pairwise information not found" };
 pub static REV_REG_DEF_NOT_FOUND: Error = Error { code_num: 1107, message: "No revocation definition found" };
 pub static REV_DELTA_NOT_FOUND: Error = Error { code_num: 1108, message: "No revocation delta found in storage for this revocation registry. Were any credentials locally revoked?" };
+pub static JSON_LIMIT_EXCEEDED: Error = Error { code_num: 1109, message: "JSON exceeds maximal allowed size or nesting depth" };
+pub static PROOF_RESTRICTIONS_MISMATCH: Error = Error { code_num: 1110, message: "Presentation does not satisfy proof request restrictions" };
+pub static INVALID_HANDLE_TOKEN: Error = Error { code_num: 1111, message: "Handle token is invalid or was tampered with" };
+pub static UNROUTABLE_MESSAGE: Error = Error { code_num: 1112, message: "No object is handling the thread of the message" };
+pub static UNDELIVERED_MESSAGES: Error = Error { code_num: 1113, message: "Connection has messages which were not processed yet" };
+pub static NO_RESPONSE: Error = Error { code_num: 1114, message: "Connection counterparty did not respond in time" };
+pub static WALLET_STORAGE_PLUGIN: Error = Error { code_num: 1115, message: "Wallet storage plugin could not be loaded" };
+pub static TXN_AUTHOR_AGREEMENT_REQUIRED: Error = Error { code_num: 1116, message: "Ledger requires acceptance of its current transaction author agreement" };
+pub static EXPIRED_MESSAGE: Error = Error { code_num: 1117, message: "Protocol message has expired" };
+pub static INVALID_CREDENTIAL_TEMPLATE_HANDLE: Error = Error { code_num: 1118, message: "Invalid Credential Template handle" };
+pub static CANCELLED: Error = Error { code_num: 1119, message: "Operation was cancelled" };
 
 lazy_static! {
     static ref ERROR_C_MESSAGES: HashMap<u32, CString> = {
@@ -137,6 +148,16 @@ lazy_static! {
         insert_c_message(&mut m, &NOT_BASE58);
         insert_c_message(&mut m, &INVALID_ISSUER_CREDENTIAL_HANDLE);
         insert_c_message(&mut m, &INVALID_JSON);
+        insert_c_message(&mut m, &JSON_LIMIT_EXCEEDED);
+        insert_c_message(&mut m, &INVALID_HANDLE_TOKEN);
+        insert_c_message(&mut m, &UNROUTABLE_MESSAGE);
+        insert_c_message(&mut m, &UNDELIVERED_MESSAGES);
+        insert_c_message(&mut m, &NO_RESPONSE);
+        insert_c_message(&mut m, &WALLET_STORAGE_PLUGIN);
+        insert_c_message(&mut m, &TXN_AUTHOR_AGREEMENT_REQUIRED);
+        insert_c_message(&mut m, &EXPIRED_MESSAGE);
+        insert_c_message(&mut m, &INVALID_CREDENTIAL_TEMPLATE_HANDLE);
+        insert_c_message(&mut m, &CANCELLED);
         insert_c_message(&mut m, &INVALID_MESSAGES);
         insert_c_message(&mut m, &INVALID_MSGPACK);
         insert_c_message(&mut m, &INVALID_ATTRIBUTES_STRUCTURE);
@@ -144,6 +165,7 @@ lazy_static! {
         insert_c_message(&mut m, &INVALID_CREDENTIAL_REQUEST);
         insert_c_message(&mut m, &BIG_NUMBER_ERROR);
         insert_c_message(&mut m, &INVALID_PROOF);
+        insert_c_message(&mut m, &PROOF_RESTRICTIONS_MISMATCH);
         insert_c_message(&mut m, &INVALID_GENESIS_TXN_PATH);
         insert_c_message(&mut m, &CREATE_POOL_CONFIG);
         insert_c_message(&mut m, &INVALID_PROOF_CREDENTIAL_DATA);
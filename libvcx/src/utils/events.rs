@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use log::Level;
+
+use error::prelude::*;
+use settings;
+use utils::agent_context;
+use utils::httpclient::{HttpRequest, PostFailure, RequestOptions};
+use utils::object_cache::ObjectCache;
+use utils::structured_log::{self, Correlation};
+use utils::threadpool::spawn;
+use utils::timeout::TimeoutUtils;
+
+pub static CONNECTION: &str = "connection";
+pub static CREDENTIAL: &str = "credential";
+pub static CREDENTIAL_TEMPLATE: &str = "credential_template";
+pub static ISSUER_CREDENTIAL: &str = "issuer_credential";
+pub static DISCLOSED_PROOF: &str = "disclosed_proof";
+pub static PROOF: &str = "proof";
+
+pub type StateChangeCallback = Arc<dyn Fn(&StateChangeEvent) + Send + Sync>;
+
+lazy_static! {
+    // subscribers and callbacks are registered per agent, see `agent::Agent`
+    static ref SUBSCRIBERS: Mutex<Vec<(Option<u32>, Sender<StateChangeEvent>)>> = Default::default();
+    static ref CALLBACKS: Mutex<HashMap<Option<u32>, StateChangeCallback>> = Default::default();
+}
+
+/// Transition of a state machine from `old_state` to `new_state`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StateChangeEvent {
+    pub handle_type: String,
+    pub handle: u32,
+    pub old_state: u32,
+    pub new_state: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
+}
+
+/// Returns receiver getting all state changes of the current agent's objects which happen from now on.
+/// Subscription ends by dropping the receiver.
+pub fn subscribe() -> Receiver<StateChangeEvent> {
+    let (sender, receiver) = channel();
+    SUBSCRIBERS.lock().unwrap().push((agent_context::current_agent_id(), sender));
+    receiver
+}
+
+/// Sets callback called on every state change of the current agent's objects, `None` removes the callback.
+/// Callback is called on the thread which caused the transition, so it should return quickly.
+pub fn set_callback(callback: Option<StateChangeCallback>) {
+    let agent_id = agent_context::current_agent_id();
+    let mut callbacks = CALLBACKS.lock().unwrap();
+    match callback {
+        Some(callback) => callbacks.insert(agent_id, callback),
+        None => callbacks.remove(&agent_id)
+    };
+}
+
+///
+/// Publishes state change to subscribers, callback and webhook (`state_change_webhook_url`).
+/// Nothing is published when the state has not changed.
+///
+pub fn notify(handle_type: &str, handle: u32, old_state: u32, new_state: u32, thread_id: Option<String>) {
+    if old_state == new_state { return; }
+
+    let event = StateChangeEvent {
+        handle_type: handle_type.to_string(),
+        handle,
+        old_state,
+        new_state,
+        thread_id: thread_id.filter(|thread_id| !thread_id.is_empty()),
+    };
+    let _correlation = structured_log::enter(Correlation::new()
+        .thread_id(event.thread_id.clone())
+        .connection_handle(Some(handle).filter(|_| handle_type == CONNECTION)));
+    log_event!(Level::Debug, "state_changed", handle_type = handle_type, handle = handle, old_state = old_state, new_state = new_state);
+
+    let agent_id = agent_context::current_agent_id();
+    SUBSCRIBERS.lock().unwrap()
+        .retain(|&(ref agent, ref subscriber)| *agent != agent_id || subscriber.send(event.clone()).is_ok());
+
+    // callback is cloned out so that it can (un)register callbacks itself
+    let callback = CALLBACKS.lock().unwrap().get(&agent_id).cloned();
+    if let Some(callback) = callback {
+        callback(&event);
+    }
+
+    if let Ok(url) = settings::get_config_value(settings::CONFIG_STATE_CHANGE_WEBHOOK_URL) {
+        _post_to_webhook(url, event);
+    }
+}
+
+///
+/// Runs `closure` on the cached object and publishes the state change it caused.
+/// Notification is done after the object is unlocked so that listeners can query it.
+/// Closure cancelled by `cancellation::with_token` stops before its side effects, see `cancellation::commit`.
+///
+pub fn get_mut_notifying<T, F, R, S, I>(cache: &ObjectCache<T>, handle_type: &str, handle: u32, state: S, thread_id: I, closure: F) -> VcxResult<R>
+    where F: Fn(&mut T) -> VcxResult<R>, S: Fn(&T) -> u32, I: Fn(&T) -> Option<String> {
+    let (result, old_state, new_state, thread_id) = cache.get_mut(handle, |obj| {
+        let _correlation = structured_log::enter(Correlation::new()
+            .thread_id(thread_id(obj))
+            .connection_handle(Some(handle).filter(|_| handle_type == CONNECTION)));
+        let old_state = state(obj);
+        let result = closure(obj);
+        Ok((result, old_state, state(obj), thread_id(obj)))
+    })?;
+
+    let result = result.map_err(|err| _add_error_context(err, handle_type, thread_id.as_ref()));
+    notify(handle_type, handle, old_state, new_state, thread_id);
+    result
+}
+
+// errors keep the context set where they were raised, e.g. the thread of a received problem report
+fn _add_error_context(mut err: VcxError, handle_type: &str, thread_id: Option<&String>) -> VcxError {
+    if err.details().protocol.is_none() {
+        if let Some(protocol) = _protocol(handle_type) {
+            err = err.with_protocol(protocol);
+        }
+    }
+    if err.details().thread_id.is_none() {
+        if let Some(thread_id) = thread_id {
+            err = err.with_thread_id(thread_id);
+        }
+    }
+    err
+}
+
+fn _protocol(handle_type: &str) -> Option<&'static str> {
+    if handle_type == CONNECTION {
+        Some("connections")
+    } else if handle_type == CREDENTIAL || handle_type == ISSUER_CREDENTIAL {
+        Some("issue-credential")
+    } else if handle_type == DISCLOSED_PROOF || handle_type == PROOF {
+        Some("present-proof")
+    } else {
+        None
+    }
+}
+
+fn _post_to_webhook(url: String, event: StateChangeEvent) {
+    if settings::agency_mocks_enabled() { return; }
+
+    spawn(move || {
+        let result = HttpRequest::post(&url, "application/json", json!(event).to_string().into_bytes())
+            .map(|request| request.options(RequestOptions::from_settings().timeout(TimeoutUtils::long_timeout())))
+            .and_then(|request| request.send().map_err(PostFailure::into_error));
+
+        match result {
+            Ok(ref response) if response.is_success() => trace!("events::_post_to_webhook >>> event {:?} posted", event),
+            Ok(response) => warn!("events::_post_to_webhook >>> webhook {} responded with {}", url, response.status),
+            Err(err) => warn!("events::_post_to_webhook >>> posting to webhook {} failed: {}", url, err),
+        }
+        Ok(())
+    });
+}
+
+#[cfg(test)]
+pub mod tests {
+    use utils::cancellation;
+    use utils::devsetup::SetupAriesMocks;
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_notify_publishes_state_changes_only() {
+        let _setup = SetupAriesMocks::init();
+
+        let receiver = subscribe();
+
+        notify("test", 1, 2, 2, None);
+        notify("test", 1, 2, 4, Some("thid".to_string()));
+
+        // other tests may publish their events meanwhile
+        let events: Vec<StateChangeEvent> = receiver.try_iter().filter(|event| event.handle_type == "test").collect();
+        assert_eq!(events, vec![StateChangeEvent { handle_type: "test".to_string(), handle: 1, old_state: 2, new_state: 4, thread_id: Some("thid".to_string()) }]);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_mut_notifying_publishes_state_change() {
+        let _setup = SetupAriesMocks::init();
+
+        let receiver = subscribe();
+        let cache = ObjectCache::<u32>::new("events-test-cache");
+        let handle = cache.add(1).unwrap();
+
+        let result = get_mut_notifying(&cache, "cache_test", handle, |state| *state, |_| None, |state| {
+            *state = 2;
+            Ok("done")
+        }).unwrap();
+        assert_eq!(result, "done");
+
+        get_mut_notifying(&cache, "cache_test", handle, |state| *state, |_| None, |_| Ok(())).unwrap();
+
+        let events: Vec<StateChangeEvent> = receiver.try_iter().filter(|event| event.handle_type == "cache_test").collect();
+        assert_eq!(events, vec![StateChangeEvent { handle_type: "cache_test".to_string(), handle, old_state: 1, new_state: 2, thread_id: None }]);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_mut_notifying_completes_committed_closure() {
+        let _setup = SetupAriesMocks::init();
+
+        let cache = ObjectCache::<u32>::new("events-cancel-test-cache");
+        let handle = cache.add(1).unwrap();
+        let token = cancellation::CancellationToken::new();
+
+        let result: VcxResult<()> = cancellation::with_token(&token, || {
+            get_mut_notifying(&cache, "cache_test", handle, |state| *state, |_| None, |state| {
+                cancellation::commit();
+                *state = 2;
+                token.cancel();
+                cancellation::check()
+            })
+        });
+        result.unwrap();
+        assert_eq!(cache.get(handle, |state| Ok(*state)).unwrap(), 2);
+
+        let token = cancellation::CancellationToken::new();
+        token.cancel();
+        let result: VcxResult<()> = cancellation::with_token(&token, || {
+            get_mut_notifying(&cache, "cache_test", handle, |state| *state, |_| None, |state| {
+                cancellation::check()?;
+                *state = 3;
+                Ok(())
+            })
+        });
+        assert_eq!(result.unwrap_err().kind(), VcxErrorKind::Cancelled);
+        assert_eq!(cache.get(handle, |state| Ok(*state)).unwrap(), 2);
+    }
+}
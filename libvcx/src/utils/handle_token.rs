@@ -0,0 +1,115 @@
+use openssl::hash::MessageDigest;
+use openssl::memcmp;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+
+use error::prelude::*;
+use settings;
+
+const TOKEN_SEPARATOR: char = '.';
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TokenPayload {
+    #[serde(rename = "type")]
+    object_type: String,
+    data: String,
+}
+
+fn _mac(payload: &str) -> VcxResult<Vec<u8>> {
+    let key = settings::get_config_value(settings::CONFIG_HANDLE_TOKEN_KEY)
+        .map_err(|_| VcxError::from_msg(VcxErrorKind::InvalidConfiguration,
+                                        format!("`{}` must be configured to exchange handle tokens", settings::CONFIG_HANDLE_TOKEN_KEY)))?;
+
+    let key = PKey::hmac(key.as_bytes())
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::EncodeError, format!("Cannot create handle token key: {}", err)))?;
+
+    Signer::new(MessageDigest::sha256(), &key)
+        .and_then(|mut signer| {
+            signer.update(payload.as_bytes())?;
+            signer.sign_to_vec()
+        })
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::EncodeError, format!("Cannot compute handle token MAC: {}", err)))
+}
+
+/// Wraps serialized object into a token authenticated with the configured `handle_token_key`,
+/// so it can be handed over to another process sharing the same configuration.
+pub fn seal(object_type: &str, serialized: &str) -> VcxResult<String> {
+    let payload = json!(TokenPayload { object_type: object_type.to_string(), data: serialized.to_string() }).to_string();
+    let payload = base64::encode_config(payload.as_bytes(), base64::URL_SAFE_NO_PAD);
+    let mac = base64::encode_config(&_mac(&payload)?, base64::URL_SAFE_NO_PAD);
+
+    Ok(format!("{}{}{}", payload, TOKEN_SEPARATOR, mac))
+}
+
+/// Returns serialized object wrapped into the token after checking its MAC and object type.
+pub fn unseal(object_type: &str, token: &str) -> VcxResult<String> {
+    let mut parts = token.splitn(2, TOKEN_SEPARATOR);
+    let (payload, mac) = match (parts.next(), parts.next()) {
+        (Some(payload), Some(mac)) => (payload, mac),
+        _ => return Err(VcxError::from_msg(VcxErrorKind::InvalidHandleToken, "Malformed handle token"))
+    };
+
+    let mac = base64::decode_config(mac, base64::URL_SAFE_NO_PAD)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidHandleToken, format!("Cannot decode handle token MAC: {}", err)))?;
+    let expected_mac = _mac(payload)?;
+
+    if mac.len() != expected_mac.len() || !memcmp::eq(&mac, &expected_mac) {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidHandleToken, "Handle token MAC does not match"));
+    }
+
+    let payload = base64::decode_config(payload, base64::URL_SAFE_NO_PAD)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidHandleToken, format!("Cannot decode handle token: {}", err)))?;
+    let payload: TokenPayload = ::serde_json::from_slice(&payload)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidHandleToken, format!("Cannot deserialize handle token: {}", err)))?;
+
+    if payload.object_type != object_type {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidHandleToken,
+                                      format!("Handle token holds {} object, expected: {}", payload.object_type, object_type)));
+    }
+
+    Ok(payload.data)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use utils::devsetup::SetupDefaults;
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_handle_token_round_trip() {
+        let _setup = SetupDefaults::init();
+        settings::set_config_value(settings::CONFIG_HANDLE_TOKEN_KEY, "secret");
+
+        let token = seal("proof", r#"{"state":"finished"}"#).unwrap();
+        assert_eq!(unseal("proof", &token).unwrap(), r#"{"state":"finished"}"#);
+        assert_eq!(unseal("credential", &token).unwrap_err().kind(), VcxErrorKind::InvalidHandleToken);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_handle_token_rejects_tampering() {
+        let _setup = SetupDefaults::init();
+        settings::set_config_value(settings::CONFIG_HANDLE_TOKEN_KEY, "secret");
+
+        let token = seal("proof", r#"{"state":"finished"}"#).unwrap();
+        let forged = seal("proof", r#"{"state":"initial"}"#).unwrap();
+        let (_, mac) = token.split_at(token.find(TOKEN_SEPARATOR).unwrap());
+        let (payload, _) = forged.split_at(forged.find(TOKEN_SEPARATOR).unwrap());
+
+        assert_eq!(unseal("proof", &format!("{}{}", payload, mac)).unwrap_err().kind(), VcxErrorKind::InvalidHandleToken);
+        assert_eq!(unseal("proof", "garbage").unwrap_err().kind(), VcxErrorKind::InvalidHandleToken);
+
+        settings::set_config_value(settings::CONFIG_HANDLE_TOKEN_KEY, "other secret");
+        assert_eq!(unseal("proof", &token).unwrap_err().kind(), VcxErrorKind::InvalidHandleToken);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_handle_token_requires_key() {
+        let _setup = SetupDefaults::init();
+
+        assert_eq!(seal("proof", "{}").unwrap_err().kind(), VcxErrorKind::InvalidConfiguration);
+    }
+}
@@ -0,0 +1,579 @@
+use std::cmp;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use openssl::ssl::{HandshakeError, SslConnector, SslMethod, SslStream, SslVerifyMode};
+use openssl::x509::X509;
+use url::{Host, Url};
+
+use error::prelude::*;
+use utils::cancellation::{self, CancellationToken};
+use utils::httpclient::PostFailure;
+
+// limits of the response head, the body is limited by the caller
+const MAX_LINE_LENGTH: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+
+// how often reads waiting for the server observe cancellation of the operation
+const CANCELLATION_POLL: Duration = Duration::from_millis(500);
+
+///
+/// HTTP/1.1 transport all requests of the library are sent by. It is built on openssl directly,
+/// so that pinned keys are verified within the TLS session the request is sent over and the address
+/// a host was checked for is the one the request is sent to.
+///
+pub struct Transport {
+    pub proxy: Option<Url>,
+    pub connect_timeout: Duration,
+    pub timeout: Duration,
+    /// trusted in addition to the system roots
+    pub certificates: Vec<X509>,
+    /// DER encoded public keys (SPKI), the leaf certificate of the server has to carry one of them when set
+    pub pinned_keys: Vec<Vec<u8>>,
+    /// hosts resolving to private, loopback or link local addresses are refused
+    pub public_hosts_only: bool,
+}
+
+pub struct RawResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Box<dyn Read + Send>,
+}
+
+impl RawResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+fn permanent(msg: String) -> PostFailure {
+    PostFailure::Permanent(VcxError::from_msg(VcxErrorKind::PostMessageFailed, msg))
+}
+
+fn transient(msg: String) -> PostFailure {
+    PostFailure::Transient(VcxError::from_msg(VcxErrorKind::PostMessageFailed, msg))
+}
+
+fn _io_failure(err: io::Error) -> PostFailure {
+    transient(format!("Request failed: {}", err))
+}
+
+enum Stream {
+    Plain(TcpStream),
+    Tls(SslStream<TcpStream>),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.read(buf),
+            Stream::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Stream {
+    fn tcp(&self) -> &TcpStream {
+        match self {
+            Stream::Plain(stream) => stream,
+            Stream::Tls(stream) => stream.get_ref(),
+        }
+    }
+}
+
+// reads stop waiting for the server once the operation the request is sent for is cancelled,
+// the socket wakes the reader up every `CANCELLATION_POLL` to check it
+struct CancellableStream {
+    stream: Stream,
+    token: Option<CancellationToken>,
+    timeout: Duration,
+}
+
+impl Read for CancellableStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let started = Instant::now();
+        loop {
+            match self.stream.read(buf) {
+                Err(ref err) if self.token.is_some() && _is_timeout(err) && started.elapsed() < self.timeout => {
+                    if self.token.as_ref().map_or(false, CancellationToken::is_cancelled) {
+                        return Err(io::Error::new(io::ErrorKind::Other, "Operation was cancelled"));
+                    }
+                }
+                result => return result
+            }
+        }
+    }
+}
+
+impl Write for CancellableStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+fn _is_timeout(err: &io::Error) -> bool {
+    err.kind() == io::ErrorKind::WouldBlock || err.kind() == io::ErrorKind::TimedOut
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.write(buf),
+            Stream::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Plain(stream) => stream.flush(),
+            Stream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+impl Transport {
+    /// Sends single request over a new connection, redirects are not followed.
+    /// Waiting for the response ends with `Cancelled` error when the running operation is cancelled, see `cancellation::with_token`.
+    pub fn exchange(&self, method: &str, url: &Url, headers: &[(String, String)], body: &[u8]) -> Result<RawResponse, PostFailure> {
+        let tls = match url.scheme() {
+            "https" => true,
+            "http" if !self.pinned_keys.is_empty() => return Err(permanent(format!("Pinned certificates require https, url: {}", url))),
+            "http" => false,
+            scheme => return Err(permanent(format!("Unsupported url scheme {}: {}", scheme, url)))
+        };
+        let host = _host(url)?;
+        let port = url.port_or_known_default().ok_or_else(|| permanent(format!("Url has no port: {}", url)))?;
+
+        let (stream, absolute_target) = match self.proxy {
+            Some(ref proxy) => (self._connect_via_proxy(proxy, &host, port, tls)?, !tls),
+            None => (self._connect(&self._resolve(&host, port)?)?, false)
+        };
+        let stream = if tls { Stream::Tls(self._handshake(stream, &host)?) } else { Stream::Plain(stream) };
+
+        let token = cancellation::current();
+        if token.is_some() {
+            stream.tcp().set_read_timeout(Some(cmp::min(self.timeout, CANCELLATION_POLL))).map_err(_io_failure)?;
+        }
+        let or_cancelled = |failure: PostFailure| {
+            if token.as_ref().map_or(false, CancellationToken::is_cancelled) {
+                PostFailure::Permanent(VcxError::from_msg(VcxErrorKind::Cancelled, "Operation was cancelled"))
+            } else {
+                failure
+            }
+        };
+        let mut stream = CancellableStream { stream, token: token.clone(), timeout: self.timeout };
+
+        let target = if absolute_target { url.as_str().to_string() } else { _origin_form(url) };
+        _write_request(&mut stream, method, &target, &_authority(url, &host), headers, body).map_err(_io_failure).map_err(&or_cancelled)?;
+        _read_response(stream, method).map_err(or_cancelled)
+    }
+
+    // addresses are resolved once, so that the checked address is the one connected to
+    fn _resolve(&self, host: &str, port: u16) -> Result<Vec<SocketAddr>, PostFailure> {
+        let addresses: Vec<SocketAddr> = match host.parse::<IpAddr>() {
+            Ok(ip) => vec![SocketAddr::new(ip, port)],
+            Err(_) => (host, port).to_socket_addrs()
+                .map_err(|err| transient(format!("Cannot resolve {}: {}", host, err)))?
+                .collect()
+        };
+        if addresses.is_empty() {
+            return Err(transient(format!("Cannot resolve {}", host)));
+        }
+        if self.public_hosts_only {
+            if host.eq_ignore_ascii_case("localhost") || host.to_lowercase().ends_with(".localhost")
+                || addresses.iter().any(|address| !is_public_address(&address.ip())) {
+                return Err(PostFailure::Permanent(VcxError::from_msg(VcxErrorKind::InvalidUrl, format!("Host {} is not public", host))));
+            }
+        }
+        Ok(addresses)
+    }
+
+    fn _connect(&self, addresses: &[SocketAddr]) -> Result<TcpStream, PostFailure> {
+        let mut last_error = None;
+        for address in addresses {
+            match TcpStream::connect_timeout(address, self.connect_timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(self.timeout)).map_err(_io_failure)?;
+                    stream.set_write_timeout(Some(self.timeout)).map_err(_io_failure)?;
+                    return Ok(stream);
+                }
+                Err(err) => last_error = Some(err)
+            }
+        }
+        Err(transient(format!("Could not connect {:?}: {:?}", addresses, last_error)))
+    }
+
+    // https requests are tunneled, plain http ones are sent to the proxy in absolute form
+    fn _connect_via_proxy(&self, proxy: &Url, host: &str, port: u16, tls: bool) -> Result<TcpStream, PostFailure> {
+        // the proxy would resolve the host again, so it is given the checked address instead
+        let target = if self.public_hosts_only {
+            let address = self._resolve(host, port)?[0];
+            if !tls {
+                return Err(permanent(format!("Plain http request to {} cannot be sent through proxy", host)));
+            }
+            address.to_string()
+        } else {
+            format!("{}:{}", _bracketed(host), port)
+        };
+
+        let proxy_host = _host(proxy)?;
+        let proxy_port = proxy.port_or_known_default().unwrap_or(80);
+        let proxy_addresses: Vec<SocketAddr> = (proxy_host.as_str(), proxy_port).to_socket_addrs()
+            .map_err(|err| transient(format!("Cannot resolve proxy {}: {}", proxy_host, err)))?
+            .collect();
+        let mut stream = self._connect(&proxy_addresses)?;
+        if !tls { return Ok(stream); }
+
+        let connect = format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n\r\n", target = target);
+        stream.write_all(connect.as_bytes()).map_err(_io_failure)?;
+
+        let mut reader = BufReader::new(stream);
+        let status = _read_status(&mut reader)?;
+        _read_headers(&mut reader)?;
+        if status != 200 {
+            return Err(permanent(format!("Proxy refused tunnel to {} with status {}", target, status)));
+        }
+        if !reader.buffer().is_empty() {
+            return Err(permanent("Proxy sent data before the tunnel was established".to_string()));
+        }
+        Ok(reader.into_inner())
+    }
+
+    fn _handshake(&self, stream: TcpStream, host: &str) -> Result<SslStream<TcpStream>, PostFailure> {
+        let mut builder = SslConnector::builder(SslMethod::tls())
+            .map_err(|err| permanent(format!("Cannot create TLS connector: {:?}", err)))?;
+        for certificate in self.certificates.iter() {
+            // the same certificate may be trusted by the system already
+            builder.cert_store_mut().add_cert(certificate.clone()).ok();
+        }
+
+        if !self.pinned_keys.is_empty() {
+            let pinned_keys = self.pinned_keys.clone();
+            builder.set_verify_callback(SslVerifyMode::PEER, move |_, context| {
+                // the server is identified by the pinned key of its leaf certificate, whoever issued it
+                if context.error_depth() > 0 { return true; }
+                context.current_cert()
+                    .and_then(|certificate| certificate.public_key().ok())
+                    .and_then(|key| key.public_key_to_der().ok())
+                    .map_or(false, |key| pinned_keys.contains(&key))
+            });
+        }
+
+        builder.build().connect(host, stream)
+            .map_err(|err| match err {
+                HandshakeError::Failure(ref stream) if stream.error().io_error().is_some() =>
+                    transient(format!("TLS handshake with {} failed: {}", host, err)),
+                HandshakeError::WouldBlock(_) =>
+                    transient(format!("TLS handshake with {} timed out", host)),
+                err => permanent(format!("TLS handshake with {} failed: {}", host, err)),
+            })
+    }
+}
+
+fn _host(url: &Url) -> Result<String, PostFailure> {
+    match url.host() {
+        Some(Host::Domain(domain)) => Ok(domain.to_string()),
+        Some(Host::Ipv4(ip)) => Ok(ip.to_string()),
+        Some(Host::Ipv6(ip)) => Ok(ip.to_string()),
+        None => Err(permanent(format!("Url has no host: {}", url)))
+    }
+}
+
+fn _bracketed(host: &str) -> String {
+    if host.contains(':') { format!("[{}]", host) } else { host.to_string() }
+}
+
+fn _authority(url: &Url, host: &str) -> String {
+    match url.port() {
+        Some(port) => format!("{}:{}", _bracketed(host), port),
+        None => _bracketed(host)
+    }
+}
+
+fn _origin_form(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string()
+    }
+}
+
+fn _write_request<W: Write>(writer: &mut W, method: &str, target: &str, authority: &str, headers: &[(String, String)], body: &[u8]) -> io::Result<()> {
+    let mut head = format!("{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nContent-Length: {}\r\n", method, target, authority, body.len());
+    for (name, value) in headers {
+        if name.contains(|c: char| c == '\r' || c == '\n' || c == ':') || value.contains(|c: char| c == '\r' || c == '\n') {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid header {}", name)));
+        }
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+
+    writer.write_all(head.as_bytes())?;
+    writer.write_all(body)?;
+    writer.flush()
+}
+
+fn _read_line<R: BufRead>(reader: &mut R) -> Result<String, PostFailure> {
+    let mut line = String::new();
+    reader.take(MAX_LINE_LENGTH).read_line(&mut line).map_err(_io_failure)?;
+    if !line.ends_with('\n') {
+        return Err(transient("Invalid HTTP response: line is not terminated".to_string()));
+    }
+    Ok(line.trim_end_matches(|c| c == '\r' || c == '\n').to_string())
+}
+
+fn _read_status<R: BufRead>(reader: &mut R) -> Result<u16, PostFailure> {
+    let line = _read_line(reader)?;
+    let mut parts = line.splitn(3, ' ');
+    match (parts.next(), parts.next().and_then(|status| status.parse::<u16>().ok())) {
+        (Some(version), Some(status)) if version.starts_with("HTTP/1.") => Ok(status),
+        _ => Err(transient(format!("Invalid HTTP status line: {}", line)))
+    }
+}
+
+fn _read_headers<R: BufRead>(reader: &mut R) -> Result<Vec<(String, String)>, PostFailure> {
+    let mut headers = Vec::new();
+    loop {
+        let line = _read_line(reader)?;
+        if line.is_empty() { return Ok(headers); }
+        if headers.len() == MAX_HEADERS {
+            return Err(transient("Invalid HTTP response: too many headers".to_string()));
+        }
+        let mut parts = line.splitn(2, ':');
+        match (parts.next(), parts.next()) {
+            (Some(name), Some(value)) => headers.push((name.trim().to_string(), value.trim().to_string())),
+            _ => return Err(transient(format!("Invalid HTTP header: {}", line)))
+        }
+    }
+}
+
+fn _read_response(stream: CancellableStream, method: &str) -> Result<RawResponse, PostFailure> {
+    let mut reader = BufReader::new(stream);
+
+    // informational responses precede the final one
+    let (status, headers) = loop {
+        let status = _read_status(&mut reader)?;
+        let headers = _read_headers(&mut reader)?;
+        if status >= 200 { break (status, headers); }
+    };
+
+    let mut response = RawResponse { status, headers, body: Box::new(io::empty()) };
+
+    if method == "HEAD" || status == 204 || status == 304 {
+        return Ok(response);
+    }
+
+    let chunked = response.header("Transfer-Encoding")
+        .map(|encoding| encoding.to_lowercase().contains("chunked"))
+        .unwrap_or(false);
+    let content_length = response.header("Content-Length").map(|length| length.parse::<u64>());
+
+    response.body = match (chunked, content_length) {
+        (true, _) => Box::new(ChunkedReader { reader, remaining: 0, done: false }),
+        (false, Some(Ok(length))) => Box::new(LengthReader { reader, remaining: length }),
+        (false, Some(Err(_))) => return Err(transient("Invalid HTTP response: invalid Content-Length".to_string())),
+        (false, None) => Box::new(reader),
+    };
+    Ok(response)
+}
+
+struct LengthReader<R: Read> {
+    reader: R,
+    remaining: u64,
+}
+
+impl<R: Read> Read for LengthReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 || buf.is_empty() { return Ok(0); }
+
+        let max = cmp::min(buf.len() as u64, self.remaining) as usize;
+        let read = self.reader.read(&mut buf[..max])?;
+        if read == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Response body is shorter than its Content-Length"));
+        }
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+struct ChunkedReader<R: BufRead> {
+    reader: R,
+    remaining: u64,
+    done: bool,
+}
+
+impl<R: BufRead> ChunkedReader<R> {
+    fn _line(&mut self) -> io::Result<String> {
+        _read_line(&mut self.reader)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid chunked response body"))
+    }
+}
+
+impl<R: BufRead> Read for ChunkedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done || buf.is_empty() { return Ok(0); }
+
+        if self.remaining == 0 {
+            let line = self._line()?;
+            let size = line.split(';').next().unwrap_or_default().trim();
+            self.remaining = u64::from_str_radix(size, 16)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("Invalid chunk size: {}", size)))?;
+
+            if self.remaining == 0 {
+                // trailers are skipped
+                while !self._line()?.is_empty() {}
+                self.done = true;
+                return Ok(0);
+            }
+        }
+
+        let max = cmp::min(buf.len() as u64, self.remaining) as usize;
+        let read = self.reader.read(&mut buf[..max])?;
+        if read == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Chunked response body is truncated"));
+        }
+        self.remaining -= read as u64;
+        if self.remaining == 0 && !self._line()?.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Chunk is not terminated"));
+        }
+        Ok(read)
+    }
+}
+
+/// Whether the address is reachable from the internet, private, loopback, link local
+/// and other special purpose ranges are not.
+pub fn is_public_address(address: &IpAddr) -> bool {
+    match address {
+        IpAddr::V4(ip) => {
+            !(ip.is_private() || ip.is_loopback() || ip.is_link_local() || ip.is_unspecified()
+                || ip.is_broadcast() || ip.is_documentation() || ip.octets()[0] == 0
+                || (ip.octets()[0] == 100 && ip.octets()[1] & 0xc0 == 64)) // shared address space 100.64.0.0/10
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ipv4) = ip.to_ipv4() {
+                if ip.segments()[..5].iter().all(|segment| *segment == 0) {
+                    return is_public_address(&IpAddr::V4(ipv4));
+                }
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast()
+                || first & 0xfe00 == 0xfc00 // unique local fc00::/7
+                || first & 0xffc0 == 0xfe80) // link local fe80::/10
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::Cursor;
+
+    use utils::devsetup::SetupDefaults;
+
+    use super::*;
+
+    fn _response(raw: &str) -> (u16, String) {
+        let mut reader = BufReader::new(Cursor::new(raw.as_bytes().to_vec()));
+        let status = _read_status(&mut reader).unwrap();
+        let headers = _read_headers(&mut reader).unwrap();
+        let chunked = headers.iter().any(|(name, value)| name == "Transfer-Encoding" && value == "chunked");
+        let mut body = String::new();
+        if chunked {
+            ChunkedReader { reader, remaining: 0, done: false }.read_to_string(&mut body).unwrap();
+        } else {
+            reader.read_to_string(&mut body).unwrap();
+        }
+        (status, body)
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_read_chunked_response() {
+        let _setup = SetupDefaults::init();
+
+        let raw = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5;ext=1\r\nhello\r\n7\r\n, world\r\n0\r\nTrailer: x\r\n\r\n";
+        assert_eq!(_response(raw), (200, "hello, world".to_string()));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_length_reader_fails_for_truncated_body() {
+        let _setup = SetupDefaults::init();
+
+        let mut body = String::new();
+        let err = LengthReader { reader: Cursor::new(b"short".to_vec()), remaining: 10 }.read_to_string(&mut body).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_write_request_rejects_header_injection() {
+        let _setup = SetupDefaults::init();
+
+        let mut request = Vec::new();
+        _write_request(&mut request, "GET", "/path?query", "example.com", &[("Accept".to_string(), "application/json".to_string())], b"").unwrap();
+        assert_eq!(String::from_utf8(request).unwrap(),
+                   "GET /path?query HTTP/1.1\r\nHost: example.com\r\nConnection: close\r\nContent-Length: 0\r\nAccept: application/json\r\n\r\n");
+
+        let headers = vec![("Accept".to_string(), "json\r\nX-Injected: 1".to_string())];
+        assert!(_write_request(&mut Vec::new(), "GET", "/", "example.com", &headers, b"").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_is_public_address() {
+        let _setup = SetupDefaults::init();
+
+        for address in &["127.0.0.1", "10.0.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "::1", "fd00::1", "fe80::1", "::ffff:127.0.0.1"] {
+            assert!(!is_public_address(&address.parse().unwrap()), "{}", address);
+        }
+        for address in &["8.8.8.8", "2001:4860:4860::8888"] {
+            assert!(is_public_address(&address.parse().unwrap()), "{}", address);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_pinned_keys_require_https() {
+        let _setup = SetupDefaults::init();
+
+        let transport = Transport {
+            proxy: None,
+            connect_timeout: Duration::from_secs(1),
+            timeout: Duration::from_secs(1),
+            certificates: vec![],
+            pinned_keys: vec![vec![1, 2, 3]],
+            public_hosts_only: false,
+        };
+        match transport.exchange("GET", &Url::parse("http://localhost:8080/agency/msg").unwrap(), &[], b"") {
+            Err(PostFailure::Permanent(err)) => assert_eq!(VcxErrorKind::PostMessageFailed, err.kind()),
+            other => panic!("Unexpected result: {:?}", other.map(|response| response.status))
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_public_hosts_only_refuses_local_hosts() {
+        let _setup = SetupDefaults::init();
+
+        let transport = Transport {
+            proxy: None,
+            connect_timeout: Duration::from_secs(1),
+            timeout: Duration::from_secs(1),
+            certificates: vec![],
+            pinned_keys: vec![],
+            public_hosts_only: true,
+        };
+        for url in &["https://localhost/file", "https://127.0.0.1/file", "https://[::1]/file"] {
+            match transport.exchange("GET", &Url::parse(url).unwrap(), &[], b"") {
+                Err(PostFailure::Permanent(err)) => assert_eq!(VcxErrorKind::InvalidUrl, err.kind()),
+                other => panic!("Unexpected result for {}: {:?}", url, other.map(|response| response.status))
+            }
+        }
+    }
+}
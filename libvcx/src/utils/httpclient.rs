@@ -1,12 +1,20 @@
+use std::cmp;
 use std::env;
-use std::io::Read;
+use std::io::{self, Read, Write};
 use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use reqwest;
-use reqwest::header::CONTENT_TYPE;
+use openssl::x509::X509;
+use url::Url;
 
 use error::prelude::*;
 use settings;
+use utils::cancellation;
+use utils::http_transport::{RawResponse, Transport};
+use utils::metrics;
+
+pub const CONTENT_TYPE: &str = "Content-Type";
 
 lazy_static! {
     static ref AGENCY_MOCK: Mutex<AgencyMock> = Mutex::new(AgencyMock::default());
@@ -15,6 +23,8 @@ lazy_static! {
     static ref HTTPCLIENT_MOCK_RESPONSES: Mutex<HttpClientMockResponse> = Mutex::new(HttpClientMockResponse::default());
 }
 
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
 #[derive(Default)]
 pub struct AgencyMock {
     responses: Vec<Vec<u8>>
@@ -107,17 +117,80 @@ impl AgencyMockDecrypted {
     }
 }
 
+///
+/// Options of a single request, taken from settings unless overridden by the caller.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestOptions {
+    pub timeout: Duration,
+    pub retries: u32,
+    pub retry_backoff: Duration,
+}
+
+impl RequestOptions {
+    /// Options of request which must not be repeated, e.g. it creates something in the agency.
+    pub fn from_settings() -> RequestOptions {
+        RequestOptions {
+            timeout: settings::get_http_timeout(),
+            retries: 0,
+            retry_backoff: settings::get_http_retry_backoff(),
+        }
+    }
+
+    /// Options of request which can be safely repeated when it fails on network error.
+    pub fn idempotent() -> RequestOptions {
+        RequestOptions {
+            retries: settings::get_http_retries(),
+            ..RequestOptions::from_settings()
+        }
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> RequestOptions {
+        self.timeout = timeout;
+        self
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.retry_backoff.checked_mul(2u32.saturating_pow(attempt))
+            .map_or(MAX_RETRY_BACKOFF, |backoff| cmp::min(backoff, MAX_RETRY_BACKOFF))
+    }
+}
+
 //Todo: change this RC to a u32
 pub fn post_u8(body_content: &Vec<u8>) -> VcxResult<Vec<u8>> {
+    post_u8_with_options(body_content, &RequestOptions::from_settings())
+}
+
+/// Posts message to the agency, the request is repeated if it fails on network error.
+pub fn post_u8_idempotent(body_content: &Vec<u8>) -> VcxResult<Vec<u8>> {
+    post_u8_with_options(body_content, &RequestOptions::idempotent())
+}
+
+pub fn post_u8_with_options(body_content: &Vec<u8>, options: &RequestOptions) -> VcxResult<Vec<u8>> {
     let endpoint = format!("{}/agency/msg", settings::get_config_value(settings::CONFIG_AGENCY_ENDPOINT)?);
-    post_message(body_content, &endpoint)
+    post_message_with_options(body_content, &endpoint, options)
 }
 
 pub fn post_message(body_content: &Vec<u8>, url: &str) -> VcxResult<Vec<u8>> {
+    post_message_with_options(body_content, url, &RequestOptions::from_settings())
+}
+
+pub fn post_message_with_options(body_content: &Vec<u8>, url: &str, options: &RequestOptions) -> VcxResult<Vec<u8>> {
+    try_post_message(body_content, url, options).map_err(PostFailure::into_error)
+}
+
+/// Posts message, telling failures which may pass if the message is posted later apart from the others.
+pub fn try_post_message(body_content: &Vec<u8>, url: &str, options: &RequestOptions) -> Result<Vec<u8>, PostFailure> {
+    cancellation::check().map_err(PostFailure::Permanent)?;
+
     if settings::agency_mocks_enabled() {
         if HttpClientMockResponse::has_response() {
             warn!("HttpClient has mocked response");
-            return HttpClientMockResponse::get_response();
+            return HttpClientMockResponse::get_response()
+                .map_err(|err| match err.kind() {
+                    VcxErrorKind::PostMessageFailed => PostFailure::Transient(err),
+                    _ => PostFailure::Permanent(err)
+                });
         }
         if AgencyMockDecrypted::has_decrypted_mock_responses() {
             warn!("Agency requests returns empty response, decrypted mock response is available");
@@ -128,44 +201,277 @@ pub fn post_message(body_content: &Vec<u8>, url: &str) -> VcxResult<Vec<u8>> {
         return Ok(mocked_response);
     }
 
-    //Setting SSL Certs location. This is needed on android platform. Or openssl will fail to verify the certs
-    if cfg!(target_os = "android") {
-        info!("::Android code");
-        set_ssl_cert_location();
+    let start = Instant::now();
+    let result = _post_message_with_retries(body_content, url, options);
+    metrics::record(metrics::AGENCY_REQUEST, start.elapsed(), result.is_ok());
+    result
+}
+
+#[derive(Debug)]
+pub enum PostFailure {
+    // network errors and server side failures, the request may succeed if repeated
+    Transient(VcxError),
+    Permanent(VcxError),
+}
+
+impl PostFailure {
+    pub fn into_error(self) -> VcxError {
+        match self {
+            PostFailure::Transient(err) => err.with_retryable(true),
+            // cancelled request was not refused, it keeps retryability of its kind
+            PostFailure::Permanent(err) => {
+                if err.kind() == VcxErrorKind::Cancelled { err } else { err.with_retryable(false) }
+            }
+        }
+    }
+}
+
+fn _post_message_with_retries(body_content: &Vec<u8>, url: &str, options: &RequestOptions) -> Result<Vec<u8>, PostFailure> {
+    let request = HttpRequest::post(url, "application/ssi-agent-wire", body_content.to_owned())
+        .map_err(PostFailure::Permanent)?
+        .options(options.clone());
+
+    let mut attempt = 0;
+    loop {
+        match _post_message(&request) {
+            Ok(content) => return Ok(content),
+            Err(PostFailure::Transient(err)) if attempt < options.retries => {
+                let backoff = options.backoff(attempt);
+                warn!("Posting message to {} failed: {}, retrying in {:?}", url, err, backoff);
+                thread::sleep(backoff);
+                cancellation::check().map_err(PostFailure::Permanent)?;
+                attempt += 1;
+            }
+            Err(failure) => return Err(failure)
+        }
     }
-    let client = reqwest::ClientBuilder::new().timeout(::utils::timeout::TimeoutUtils::long_timeout()).build().map_err(|err| {
-        error!("error: {}", err);
-        VcxError::from_msg(VcxErrorKind::PostMessageFailed, format!("Building reqwest client failed: {:?}", err))
-    })?;
-    debug!("Posting encrypted bundle to: \"{}\"", url);
-
-    let mut response =
-        client.post(url)
-            .body(body_content.to_owned())
-            .header(CONTENT_TYPE, "application/ssi-agent-wire")
-            .send()
-            .map_err(|err| {
-                error!("error: {}", err);
-                VcxError::from_msg(VcxErrorKind::PostMessageFailed, format!("Could not connect {:?}", err))
-            })?;
-
-    trace!("Response Header: {:?}", response);
-    if !response.status().is_success() {
+}
+
+fn _post_message(request: &HttpRequest) -> Result<Vec<u8>, PostFailure> {
+    debug!("Posting encrypted bundle to: \"{}\"", request.url);
+
+    let mut response = request.send()?;
+
+    if !response.is_success() {
         let mut content = String::new();
         match response.read_to_string(&mut content) {
             Ok(_) => info!("Request failed: {}", content),
             Err(_) => info!("could not read response"),
         };
-        return Err(VcxError::from_msg(VcxErrorKind::PostMessageFailed, format!("POST failed with: {}", content)));
+        let err = VcxError::from_msg(VcxErrorKind::PostMessageFailed, format!("POST failed with: {}", content));
+        return if response.status >= 500 { Err(PostFailure::Transient(err)) } else { Err(PostFailure::Permanent(err)) };
     }
 
     let mut content = Vec::new();
     response.read_to_end(&mut content)
-        .or(Err(VcxError::from_msg(VcxErrorKind::PostMessageFailed, "could not read response")))?;
+        .or(Err(PostFailure::Transient(VcxError::from_msg(VcxErrorKind::PostMessageFailed, "could not read response"))))?;
 
     Ok(content)
 }
 
+///
+/// Outbound HTTP request. Every request of the library is sent through it, so that the proxy,
+/// timeouts and pinned certificates configured in settings apply to all of them.
+///
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    method: &'static str,
+    url: Url,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    options: RequestOptions,
+    max_redirects: usize,
+    public_hosts_only: bool,
+}
+
+impl HttpRequest {
+    fn new(method: &'static str, url: &str, body: Vec<u8>) -> VcxResult<HttpRequest> {
+        let url = Url::parse(url)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidUrl, format!("Invalid url {}: {}", url, err)))?;
+
+        Ok(HttpRequest {
+            method,
+            url,
+            headers: Vec::new(),
+            body,
+            options: RequestOptions::from_settings(),
+            max_redirects: 0,
+            public_hosts_only: false,
+        })
+    }
+
+    pub fn get(url: &str) -> VcxResult<HttpRequest> {
+        HttpRequest::new("GET", url, Vec::new())
+    }
+
+    pub fn post(url: &str, content_type: &str, body: Vec<u8>) -> VcxResult<HttpRequest> {
+        Ok(HttpRequest::new("POST", url, body)?.header(CONTENT_TYPE, content_type))
+    }
+
+    pub fn put(url: &str, content_type: &str, body: Vec<u8>) -> VcxResult<HttpRequest> {
+        Ok(HttpRequest::new("PUT", url, body)?.header(CONTENT_TYPE, content_type))
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> HttpRequest {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn options(mut self, options: RequestOptions) -> HttpRequest {
+        self.options = options;
+        self
+    }
+
+    /// Redirects to https locations are followed, up to `max_redirects` of them.
+    pub fn follow_redirects(mut self, max_redirects: usize) -> HttpRequest {
+        self.max_redirects = max_redirects;
+        self
+    }
+
+    /// Request goes to location given by the counterparty, only https hosts with public addresses are allowed.
+    pub fn public_hosts_only(mut self) -> HttpRequest {
+        self.public_hosts_only = true;
+        self
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    pub fn send(&self) -> Result<HttpResponse, PostFailure> {
+        let transport = _transport(&self.options, self.public_hosts_only).map_err(PostFailure::Permanent)?;
+
+        let mut method = self.method;
+        let mut body: &[u8] = &self.body;
+        let mut url = self.url.clone();
+        let mut redirects = 0;
+        loop {
+            if self.public_hosts_only && url.scheme() != "https" {
+                return Err(PostFailure::Permanent(VcxError::from_msg(VcxErrorKind::InvalidUrl, format!("Only https is allowed, url: {}", url))));
+            }
+
+            let response = transport.exchange(method, &url, &self.headers, body)?;
+
+            let location = match response.status {
+                301 | 302 | 303 | 307 | 308 => response.header("Location").map(String::from),
+                _ => None
+            };
+            let location = match location {
+                Some(location) => location,
+                None => return Ok(HttpResponse { status: response.status, url, raw: response })
+            };
+
+            if redirects == self.max_redirects {
+                return Err(PostFailure::Permanent(VcxError::from_msg(VcxErrorKind::InvalidHttpResponse, format!("Too many redirects, last location: {}", location))));
+            }
+            let next = url.join(&location)
+                .map_err(|err| PostFailure::Permanent(VcxError::from_msg(VcxErrorKind::InvalidHttpResponse, format!("Invalid redirect location {}: {}", location, err))))?;
+            if next.scheme() != "https" {
+                return Err(PostFailure::Permanent(VcxError::from_msg(VcxErrorKind::InvalidHttpResponse, format!("Redirect to non-https location {} is not followed", next))));
+            }
+
+            debug!("HttpRequest::send >>> {} redirected to {}", url, next);
+            if response.status == 303 {
+                method = "GET";
+                body = &[];
+            }
+            url = next;
+            redirects += 1;
+        }
+    }
+}
+
+/// Response of `HttpRequest`, the body is read from the connection as it is consumed.
+pub struct HttpResponse {
+    pub status: u16,
+    /// Final location, after redirects
+    pub url: Url,
+    raw: RawResponse,
+}
+
+impl HttpResponse {
+    pub fn is_success(&self) -> bool {
+        self.status >= 200 && self.status < 300
+    }
+
+    pub fn content_length(&self) -> Option<u64> {
+        self.raw.header("Content-Length").and_then(|length| length.parse().ok())
+    }
+
+    /// Copies body into `writer`, fails as soon as it exceeds `limit` bytes.
+    pub fn copy_limited<W: Write>(&mut self, writer: &mut W, limit: u64) -> VcxResult<u64> {
+        let url = self.url.clone();
+        let too_large = || VcxError::from_msg(VcxErrorKind::InvalidHttpResponse, format!("Response of {} exceeds maximal allowed size {} bytes", url, limit));
+        let unreadable = |err: io::Error| VcxError::from_msg(VcxErrorKind::InvalidHttpResponse, format!("Cannot read response of {}: {}", url, err));
+
+        if self.content_length().map(|length| length > limit).unwrap_or(false) {
+            return Err(too_large());
+        }
+
+        let copied = io::copy(&mut self.raw.body.by_ref().take(limit), writer).map_err(unreadable)?;
+
+        let mut probe = [0u8; 1];
+        match self.raw.body.read(&mut probe) {
+            Ok(0) => Ok(copied),
+            Ok(_) => Err(too_large()),
+            Err(err) => Err(unreadable(err))
+        }
+    }
+
+    /// Body as string of at most `limit` bytes.
+    pub fn text_limited(&mut self, limit: u64) -> VcxResult<String> {
+        let mut content = Vec::new();
+        self.copy_limited(&mut content, limit)?;
+        String::from_utf8(content)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidHttpResponse, format!("Response of {} is not UTF-8: {}", self.url, err)))
+    }
+}
+
+impl Read for HttpResponse {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.raw.body.read(buf)
+    }
+}
+
+fn _transport(options: &RequestOptions, public_hosts_only: bool) -> VcxResult<Transport> {
+    //Setting SSL Certs location. This is needed on android platform. Or openssl will fail to verify the certs
+    if cfg!(target_os = "android") {
+        info!("::Android code");
+        set_ssl_cert_location();
+    }
+
+    let proxy = settings::get_http_proxy()
+        .map(|proxy| Url::parse(&proxy)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidConfiguration, format!("Invalid HTTP proxy {}: {:?}", proxy, err))))
+        .map_or(Ok(None), |proxy| proxy.map(Some))?;
+
+    // pinned self-signed certificates have to be trusted, the pin itself is checked within the handshake
+    let certificates = settings::get_http_pinned_certificates().iter()
+        .map(|certificate| X509::from_pem(certificate.as_bytes())
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidConfiguration, format!("Invalid pinned certificate: {:?}", err))))
+        .collect::<VcxResult<Vec<X509>>>()?;
+
+    Ok(Transport {
+        proxy,
+        connect_timeout: settings::get_http_connect_timeout().unwrap_or(options.timeout),
+        timeout: options.timeout,
+        pinned_keys: _pinned_keys(&certificates)?,
+        certificates,
+        public_hosts_only,
+    })
+}
+
+// DER encoded public keys (SPKI) of pinned certificates
+fn _pinned_keys(pinned_certificates: &[X509]) -> VcxResult<Vec<Vec<u8>>> {
+    pinned_certificates.iter()
+        .map(|certificate|
+            certificate.public_key()
+                .and_then(|key| key.public_key_to_der())
+                .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidConfiguration, format!("Invalid pinned certificate: {:?}", err)))
+        )
+        .collect()
+}
+
 fn set_ssl_cert_location() {
     let ssl_cert_file = "SSL_CERT_FILE";
     env::set_var(ssl_cert_file, env::var("EXTERNAL_STORAGE").unwrap() + "/cacert.pem"); //TODO: CHANGE ME, HARDCODING FOR TESTING ONLY
@@ -176,3 +482,58 @@ fn set_ssl_cert_location() {
     }
     info!("::SSL_CERT_FILE has been set");
 }
+
+#[cfg(test)]
+pub mod tests {
+    use utils::devsetup::SetupDefaults;
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_request_options_from_settings() {
+        let _setup = SetupDefaults::init();
+
+        let options = RequestOptions::from_settings();
+        assert_eq!(Duration::from_secs(settings::DEFAULT_HTTP_TIMEOUT), options.timeout);
+        assert_eq!(0, options.retries);
+
+        settings::set_config_value(settings::CONFIG_HTTP_TIMEOUT, "10");
+        settings::set_config_value(settings::CONFIG_HTTP_RETRIES, "3");
+        settings::set_config_value(settings::CONFIG_HTTP_RETRY_BACKOFF, "100");
+
+        let options = RequestOptions::idempotent();
+        assert_eq!(Duration::from_secs(10), options.timeout);
+        assert_eq!(3, options.retries);
+        assert_eq!(Duration::from_millis(100), options.backoff(0));
+        assert_eq!(Duration::from_millis(400), options.backoff(2));
+        assert_eq!(MAX_RETRY_BACKOFF, options.backoff(20));
+        assert_eq!(MAX_RETRY_BACKOFF, options.backoff(u32::max_value()));
+
+        let options = options.timeout(Duration::from_secs(1));
+        assert_eq!(Duration::from_secs(1), options.timeout);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_pinned_certificates_must_be_valid() {
+        let _setup = SetupDefaults::init();
+
+        settings::set_config_value(settings::CONFIG_HTTP_PINNED_CERTIFICATES, r#"["not a certificate"]"#);
+        let err = _transport(&RequestOptions::from_settings(), false).unwrap_err();
+        assert_eq!(VcxErrorKind::InvalidConfiguration, err.kind());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_public_hosts_only_request_requires_https() {
+        let _setup = SetupDefaults::init();
+
+        let request = HttpRequest::get("http://example.com/file").unwrap().public_hosts_only();
+        match request.send() {
+            Err(PostFailure::Permanent(err)) => assert_eq!(VcxErrorKind::InvalidUrl, err.kind()),
+            Err(failure) => panic!("Unexpected failure: {:?}", failure),
+            Ok(response) => panic!("Unexpected response: {}", response.status)
+        }
+    }
+}
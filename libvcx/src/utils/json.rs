@@ -7,6 +7,7 @@ use serde_json::Map;
 use serde_json::Value;
 
 use error::prelude::*;
+use settings;
 
 pub trait KeyMatch {
     fn matches(&self, key: &String, context: &Vec<String>) -> bool;
@@ -67,11 +68,73 @@ fn _collect_keys(map: &Map<String, Value>) -> Vec<String> {
     rtn
 }
 
+/*
+Checks externally supplied JSON against configured `max_json_size` and `max_json_depth` before it
+is parsed. Nesting is measured by a single scan over raw bytes, so oversized input is rejected without
+allocating anything for it.
+*/
+pub fn check_json_limits(json: &[u8]) -> VcxResult<()> {
+    let max_size = settings::get_max_json_size();
+    if json.len() > max_size {
+        return Err(VcxError::from_msg(VcxErrorKind::JsonLimitExceeded,
+                                      format!("JSON size {} bytes exceeds maximal allowed size {} bytes", json.len(), max_size)));
+    }
+
+    let max_depth = settings::get_max_json_depth();
+    let mut depth: usize = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for byte in json {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'{' | b'[' => {
+                depth += 1;
+                if depth > max_depth {
+                    return Err(VcxError::from_msg(VcxErrorKind::JsonLimitExceeded,
+                                                  format!("JSON nesting exceeds maximal allowed depth {}", max_depth)));
+                }
+            }
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
 
 #[cfg(test)]
 mod tests {
+    use utils::devsetup::SetupDefaults;
+
     use super::*;
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_check_json_limits() {
+        let _setup = SetupDefaults::init();
+
+        check_json_limits(json!({"a": [{"b": "[[[{{{"}]}).to_string().as_bytes()).unwrap();
+
+        settings::set_config_value(settings::CONFIG_MAX_JSON_DEPTH, "2");
+        check_json_limits(br#"{"a": ["\"[[["]}"#).unwrap();
+        assert_eq!(check_json_limits(br#"{"a": [{}]}"#).unwrap_err().kind(), VcxErrorKind::JsonLimitExceeded);
+
+        settings::set_config_value(settings::CONFIG_MAX_JSON_SIZE, "8");
+        assert_eq!(check_json_limits(br#"{"a": "too long"}"#).unwrap_err().kind(), VcxErrorKind::JsonLimitExceeded);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn simple() {
@@ -0,0 +1,65 @@
+use std::sync::{Arc, RwLock};
+
+use error::prelude::*;
+use utils::secret::SecretString;
+
+/// Supplies the key of the wallet with given name, called every time the wallet credentials are needed.
+pub type KeyProvider = Arc<dyn Fn(&str) -> VcxResult<SecretString> + Send + Sync>;
+
+lazy_static! {
+    static ref PROVIDER: RwLock<Option<KeyProvider>> = Default::default();
+}
+
+///
+/// Sets provider of the wallet key, the key does not have to be passed in `wallet_key` config option then.
+/// It lets the key be kept in a platform keystore and unlocked only when the wallet is opened. `None` removes the provider.
+/// The provider is not asked while `wallet_key` is set, the explicit key is used.
+///
+pub fn set_provider(provider: Option<KeyProvider>) {
+    *PROVIDER.write().unwrap() = provider;
+}
+
+pub fn is_registered() -> bool {
+    PROVIDER.read().unwrap().is_some()
+}
+
+/// Key supplied by the registered provider, `None` when there is no provider.
+pub fn get_key(wallet_name: &str) -> Option<VcxResult<SecretString>> {
+    // the provider may block on user interaction, so it is not called under the lock
+    let provider = PROVIDER.read().unwrap().clone();
+    provider.map(|provider| provider(wallet_name))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use settings;
+    use utils::devsetup::SetupEmpty;
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_wallet_key_is_supplied_by_provider() {
+        let _setup = SetupEmpty::init();
+
+        let mut config = ::std::collections::HashMap::new();
+        config.insert(settings::CONFIG_WALLET_NAME.to_string(), "test_wallet".to_string());
+        assert_eq!(settings::validate_config(&config).unwrap_err().kind(), VcxErrorKind::MissingWalletKey);
+
+        set_provider(Some(Arc::new(|wallet_name: &str| Ok(SecretString::from(format!("{}_key", wallet_name))))));
+        settings::validate_config(&config).unwrap();
+
+        settings::set_config_value(settings::CONFIG_WALLET_NAME, "test_wallet");
+        let credentials = settings::get_wallet_credentials(None).unwrap();
+        let credentials: ::serde_json::Value = ::serde_json::from_str(credentials.expose()).unwrap();
+        assert_eq!(credentials["key"], json!("test_wallet_key"));
+
+        settings::set_config_value(settings::CONFIG_WALLET_KEY, "explicit_key");
+        let credentials = settings::get_wallet_credentials(None).unwrap();
+        let credentials: ::serde_json::Value = ::serde_json::from_str(credentials.expose()).unwrap();
+        assert_eq!(credentials["key"], json!("explicit_key"));
+
+        set_provider(None);
+        assert!(!is_registered());
+    }
+}
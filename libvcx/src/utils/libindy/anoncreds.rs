@@ -1,5 +1,7 @@
+use std::collections::BTreeSet;
+
 use futures::Future;
-use indy::{anoncreds, blob_storage, ledger};
+use indy::{anoncreds, blob_storage, ledger, SearchHandle, WalletHandle};
 use serde_json;
 use serde_json::{map::Map, Value};
 use time;
@@ -9,10 +11,15 @@ use settings;
 use utils::constants::{ATTRS, LIBINDY_CRED_OFFER, PROOF_REQUESTED_PREDICATES, REQUESTED_ATTRIBUTES, REV_STATE_JSON};
 use utils::constants::{CREATE_CRED_DEF_ACTION, CREATE_REV_REG_DEF_ACTION, CREATE_REV_REG_DELTA_ACTION, CREATE_SCHEMA_ACTION, CRED_DEF_ID, CRED_DEF_JSON, CRED_DEF_REQ, rev_def_json, REV_REG_DELTA_JSON, REV_REG_ID, REV_REG_JSON, REVOC_REG_TYPE, SCHEMA_ID, SCHEMA_JSON, SCHEMA_TXN};
 use utils::libindy::{LibindyMock, wallet::get_wallet_handle};
-use utils::libindy::cache::{clear_rev_reg_delta_cache, get_rev_reg_delta_cache, set_rev_reg_delta_cache};
+use utils::libindy::cache::{AccumulatedRevRegDelta, clear_rev_reg_delta_cache, get_accumulated_rev_reg_delta, get_rev_reg_cache, get_rev_reg_delta_cache, RevocationStatus, RevRegCache, set_accumulated_rev_reg_delta, set_rev_reg_cache, set_rev_reg_delta_cache};
 use utils::libindy::ledger::*;
-use utils::libindy::payments::{pay_for_txn, PaymentTxn};
+use utils::libindy::ledger_cache;
+use utils::libindy::payments::{LedgerTxn, PaymentTxn, write_txn};
+use utils::libindy::pool;
+use utils::metrics;
 use utils::mockdata::mock_settings::get_mock_creds_retrieved_for_proof_request;
+use utils::qualifier;
+use utils::wql;
 
 const BLOB_STORAGE_TYPE: &str = "default";
 const REVOCATION_REGISTRY_TYPE: &str = "ISSUANCE_BY_DEFAULT";
@@ -33,7 +40,7 @@ pub fn libindy_verifier_verify_proof(proof_req_json: &str,
         .map_err(VcxError::from)
 }
 
-pub fn libindy_create_and_store_revoc_reg(issuer_did: &str, cred_def_id: &str, tails_path: &str, max_creds: u32, tag: &str) -> VcxResult<(String, String, String)> {
+pub fn libindy_create_and_store_revoc_reg(wallet_handle: WalletHandle, issuer_did: &str, cred_def_id: &str, tails_path: &str, max_creds: u32, tag: &str) -> VcxResult<(String, String, String)> {
     trace!("creating revocation: {}, {}, {}", cred_def_id, tails_path, max_creds);
 
     let tails_config = json!({"base_dir": tails_path,"uri_pattern": ""}).to_string();
@@ -43,17 +50,18 @@ pub fn libindy_create_and_store_revoc_reg(issuer_did: &str, cred_def_id: &str, t
 
     let revoc_config = json!({"max_cred_num": max_creds, "issuance_type": REVOCATION_REGISTRY_TYPE}).to_string();
 
-    anoncreds::issuer_create_and_store_revoc_reg(get_wallet_handle(), issuer_did, None, tag, cred_def_id, &revoc_config, writer)
+    anoncreds::issuer_create_and_store_revoc_reg(wallet_handle, issuer_did, None, tag, cred_def_id, &revoc_config, writer)
         .wait()
         .map_err(VcxError::from)
 }
 
-pub fn libindy_create_and_store_credential_def(issuer_did: &str,
+pub fn libindy_create_and_store_credential_def(wallet_handle: WalletHandle,
+                                               issuer_did: &str,
                                                schema_json: &str,
                                                tag: &str,
                                                sig_type: Option<&str>,
                                                config_json: &str) -> VcxResult<(String, String)> {
-    anoncreds::issuer_create_and_store_credential_def(get_wallet_handle(),
+    anoncreds::issuer_create_and_store_credential_def(wallet_handle,
                                                       issuer_did,
                                                       schema_json,
                                                       tag,
@@ -114,15 +122,17 @@ pub fn libindy_prover_create_proof(proof_req_json: &str,
     if settings::indy_mocks_enabled() { return Ok(::utils::constants::PROOF_JSON.to_owned()); }
 
     let revoc_states_json = revoc_states_json.unwrap_or("{}");
-    anoncreds::prover_create_proof(get_wallet_handle(),
-                                   proof_req_json,
-                                   requested_credentials_json,
-                                   master_secret_id,
-                                   schemas_json,
-                                   credential_defs_json,
-                                   revoc_states_json)
-        .wait()
-        .map_err(VcxError::from)
+    metrics::measure(metrics::PROOF_GENERATION, || {
+        anoncreds::prover_create_proof(get_wallet_handle(),
+                                       proof_req_json,
+                                       requested_credentials_json,
+                                       master_secret_id,
+                                       schemas_json,
+                                       credential_defs_json,
+                                       revoc_states_json)
+            .wait()
+            .map_err(VcxError::from)
+    })
 }
 
 fn fetch_credentials(search_handle: i32, requested_attributes: Map<String, Value>) -> VcxResult<String> {
@@ -260,12 +270,102 @@ pub fn libindy_prover_store_credential(cred_id: Option<&str>,
 }
 
 pub fn libindy_prover_delete_credential(cred_id: &str) -> VcxResult<()> {
+    if settings::indy_mocks_enabled() { return Ok(()); }
+
     anoncreds::prover_delete_credential(get_wallet_handle(),
                                         cred_id)
         .wait()
         .map_err(VcxError::from)
 }
 
+/// Opens search for credentials matching WQL `query_json`, returns the search handle and number of found credentials.
+pub fn libindy_prover_search_credentials(query_json: &str) -> VcxResult<(SearchHandle, usize)> {
+    if settings::indy_mocks_enabled() { return Ok((1, 0)); }
+
+    anoncreds::prover_search_credentials(get_wallet_handle(), query_json)
+        .wait()
+        .map_err(VcxError::from)
+}
+
+pub fn libindy_prover_fetch_credentials(search_handle: SearchHandle, count: usize) -> VcxResult<String> {
+    if settings::indy_mocks_enabled() { return Ok("[]".to_string()); }
+
+    anoncreds::prover_fetch_credentials(search_handle, count)
+        .wait()
+        .map_err(VcxError::from)
+}
+
+pub fn libindy_prover_close_credentials_search(search_handle: SearchHandle) -> VcxResult<()> {
+    if settings::indy_mocks_enabled() { return Ok(()); }
+
+    anoncreds::prover_close_credentials_search(search_handle)
+        .wait()
+        .map_err(VcxError::from)
+}
+
+///
+/// Lists credentials held in the wallet matching WQL `filter_wql` (all credentials when None),
+/// at most `limit` of them starting at `offset`:
+/// `{"total": <number of matching credentials>, "credentials": [<credential info>, ...]}`
+///
+pub fn list_credentials(filter_wql: Option<&str>, offset: usize, limit: usize) -> VcxResult<String> {
+    trace!("list_credentials >>> filter_wql: {:?}, offset: {}, limit: {}", filter_wql, offset, limit);
+
+    if limit == 0 {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidOption, "Limit must be greater than 0"));
+    }
+
+    let filter_wql = filter_wql.unwrap_or("{}");
+    let filter: Value = serde_json::from_str(filter_wql)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize credentials filter: {:?}", err)))?;
+    wql::validate(&filter)?;
+
+    let (search_handle, total) = anoncreds::libindy_prover_search_credentials(filter_wql)?;
+    let credentials = _fetch_credentials_page(search_handle, offset, limit);
+    if let Err(err) = anoncreds::libindy_prover_close_credentials_search(search_handle) {
+        warn!("list_credentials >>> closing search {} failed: {}", search_handle, err);
+    }
+
+    Ok(json!({"total": total, "credentials": credentials?}).to_string())
+}
+
+// libindy credential search cannot skip, credentials before `offset` are fetched and dropped
+fn _fetch_credentials_page(search_handle: SearchHandle, offset: usize, limit: usize) -> VcxResult<Vec<Value>> {
+    let fetch = |count: usize| -> VcxResult<Vec<Value>> {
+        let credentials = anoncreds::libindy_prover_fetch_credentials(search_handle, count)?;
+        serde_json::from_str(&credentials)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize credentials: {:?}", err)))
+    };
+
+    let mut skipped = 0;
+    while skipped < offset {
+        let fetched = fetch(offset - skipped)?.len();
+        if fetched == 0 {
+            return Ok(Vec::new());
+        }
+        skipped += fetched;
+    }
+
+    fetch(limit)
+}
+
+pub fn libindy_prover_get_credential(cred_id: &str) -> VcxResult<String> {
+    if settings::indy_mocks_enabled() {
+        return Ok(json!({
+            "referent": cred_id,
+            "attrs": {},
+            "schema_id": SCHEMA_ID,
+            "cred_def_id": CRED_DEF_ID,
+            "rev_reg_id": REV_REG_ID,
+            "cred_rev_id": "1"
+        }).to_string());
+    }
+
+    anoncreds::prover_get_credential(get_wallet_handle(), cred_id)
+        .wait()
+        .map_err(VcxError::from)
+}
+
 pub fn libindy_prover_create_master_secret(master_secret_id: &str) -> VcxResult<String> {
     if settings::indy_mocks_enabled() { return Ok(settings::DEFAULT_LINK_SECRET_ALIAS.to_string()); }
 
@@ -399,13 +499,20 @@ pub fn publish_schema(schema: &str) -> VcxResult<Option<PaymentTxn>> {
         return Ok(Some(PaymentTxn::from_parts(inputs, outputs, 1, false)));
     }
 
+    write_schema(schema, false).map(|txn| txn.payment)
+}
+
+/// Writes the schema to the ledger, with `dry_run` the write is only prepared.
+pub fn write_schema(schema: &str, dry_run: bool) -> VcxResult<LedgerTxn> {
     let request = build_schema_request(schema)?;
 
-    let (payment, response) = pay_for_txn(&request, CREATE_SCHEMA_ACTION)?;
+    let txn = write_txn(&request, CREATE_SCHEMA_ACTION, dry_run)?;
 
-    _check_schema_response(&response)?;
+    if let Some(ref response) = txn.response {
+        _check_schema_response(response)?;
+    }
 
-    Ok(payment)
+    Ok(txn)
 }
 
 pub fn get_schema_json(schema_id: &str) -> VcxResult<(String, String)> {
@@ -413,7 +520,7 @@ pub fn get_schema_json(schema_id: &str) -> VcxResult<(String, String)> {
 
     let submitter_did = settings::get_config_value(settings::CONFIG_INSTITUTION_DID)?;
 
-    let schema_json = libindy_get_schema(&submitter_did, schema_id)?;
+    let schema_json = ledger_cache::get_or_fetch(ledger_cache::SCHEMA, schema_id, || libindy_get_schema(&submitter_did, schema_id))?;
 
     Ok((schema_id.to_string(), schema_json))
 }
@@ -423,13 +530,24 @@ pub fn generate_cred_def(issuer_did: &str,
                          tag: &str,
                          sig_type: Option<&str>,
                          support_revocation: Option<bool>) -> VcxResult<(String, String)> {
+    generate_cred_def_in(get_wallet_handle(), issuer_did, schema_json, tag, sig_type, support_revocation)
+}
+
+/// Same as `generate_cred_def`, the keys are stored in the wallet `wallet_handle`.
+pub fn generate_cred_def_in(wallet_handle: WalletHandle,
+                            issuer_did: &str,
+                            schema_json: &str,
+                            tag: &str,
+                            sig_type: Option<&str>,
+                            support_revocation: Option<bool>) -> VcxResult<(String, String)> {
     if settings::indy_mocks_enabled() {
         return Ok((CRED_DEF_ID.to_string(), CRED_DEF_JSON.to_string()));
     }
 
     let config_json = json!({"support_revocation": support_revocation.unwrap_or(false)}).to_string();
 
-    libindy_create_and_store_credential_def(issuer_did,
+    libindy_create_and_store_credential_def(wallet_handle,
+                                            issuer_did,
                                             schema_json,
                                             tag,
                                             sig_type,
@@ -455,27 +573,37 @@ pub fn publish_cred_def(issuer_did: &str, cred_def_json: &str) -> VcxResult<Opti
         return Ok(Some(PaymentTxn::from_parts(inputs, outputs, 1, false)));
     }
 
-    let cred_def_req = build_cred_def_request(issuer_did, &cred_def_json)?;
+    write_cred_def(issuer_did, cred_def_json, false).map(|txn| txn.payment)
+}
 
-    let (payment, _) = pay_for_txn(&cred_def_req, CREATE_CRED_DEF_ACTION)?;
+/// Writes the credential definition to the ledger, with `dry_run` the write is only prepared.
+pub fn write_cred_def(issuer_did: &str, cred_def_json: &str, dry_run: bool) -> VcxResult<LedgerTxn> {
+    let cred_def_req = build_cred_def_request(issuer_did, &cred_def_json)?;
 
-    Ok(payment)
+    write_txn(&cred_def_req, CREATE_CRED_DEF_ACTION, dry_run)
 }
 
 pub fn get_cred_def_json(cred_def_id: &str) -> VcxResult<(String, String)> {
     if settings::indy_mocks_enabled() { return Ok((CRED_DEF_ID.to_string(), CRED_DEF_JSON.to_string())); }
 
-    let cred_def_json = libindy_get_cred_def(cred_def_id)?;
+    let cred_def_json = ledger_cache::get_or_fetch(ledger_cache::CRED_DEF, cred_def_id, || libindy_get_cred_def(cred_def_id))?;
 
     Ok((cred_def_id.to_string(), cred_def_json))
 }
 
 pub fn generate_rev_reg(issuer_did: &str, cred_def_id: &str, tails_file: &str, max_creds: u32, tag: &str)
                         -> VcxResult<(String, String, String)> {
+    generate_rev_reg_in(get_wallet_handle(), issuer_did, cred_def_id, tails_file, max_creds, tag)
+}
+
+/// Same as `generate_rev_reg`, the registry is stored in the wallet `wallet_handle`.
+pub fn generate_rev_reg_in(wallet_handle: WalletHandle, issuer_did: &str, cred_def_id: &str, tails_file: &str, max_creds: u32, tag: &str)
+                           -> VcxResult<(String, String, String)> {
     if settings::indy_mocks_enabled() { return Ok((REV_REG_ID.to_string(), rev_def_json(), "".to_string())); }
 
     let (rev_reg_id, rev_reg_def_json, rev_reg_entry_json) =
-        libindy_create_and_store_revoc_reg(issuer_did,
+        libindy_create_and_store_revoc_reg(wallet_handle,
+                                           issuer_did,
                                            cred_def_id,
                                            tails_file,
                                            max_creds,
@@ -495,19 +623,29 @@ pub fn build_rev_reg_request(issuer_did: &str, rev_reg_def_json: &str) -> VcxRes
 pub fn publish_rev_reg_def(issuer_did: &str, rev_reg_def_json: &str) -> VcxResult<Option<PaymentTxn>> {
     if settings::indy_mocks_enabled() { return Ok(None); }
 
+    write_rev_reg_def(issuer_did, rev_reg_def_json, false).map(|txn| txn.payment)
+}
+
+/// Writes the revocation registry definition to the ledger, with `dry_run` the write is only prepared.
+pub fn write_rev_reg_def(issuer_did: &str, rev_reg_def_json: &str, dry_run: bool) -> VcxResult<LedgerTxn> {
     let rev_reg_def_req = build_rev_reg_request(issuer_did, &rev_reg_def_json)?;
-    let (payment, _) = pay_for_txn(&rev_reg_def_req, CREATE_REV_REG_DEF_ACTION)?;
-    Ok(payment)
+    write_txn(&rev_reg_def_req, CREATE_REV_REG_DEF_ACTION, dry_run)
 }
 
 pub fn get_rev_reg_def_json(rev_reg_id: &str) -> VcxResult<(String, String)> {
     if settings::indy_mocks_enabled() { return Ok((REV_REG_ID.to_string(), rev_def_json())); }
 
+    let _network = pool::enter_network_of(rev_reg_id)?;
     let submitter_did = settings::get_config_value(settings::CONFIG_INSTITUTION_DID)?;
 
-    libindy_build_get_revoc_reg_def_request(&submitter_did, rev_reg_id)
-        .and_then(|req| libindy_submit_request(&req))
-        .and_then(|response| libindy_parse_get_revoc_reg_def_response(&response))
+    let rev_reg_def_json = ledger_cache::get_or_fetch(ledger_cache::REV_REG_DEF, rev_reg_id, || {
+        libindy_build_get_revoc_reg_def_request(&qualifier::to_libindy(&submitter_did), &qualifier::to_libindy(rev_reg_id))
+            .and_then(|req| libindy_submit_request(&req))
+            .and_then(|response| libindy_parse_get_revoc_reg_def_response(&response))
+            .map(|(_, rev_reg_def_json)| rev_reg_def_json)
+    })?;
+
+    Ok((rev_reg_id.to_string(), rev_reg_def_json))
 }
 
 pub fn build_rev_reg_delta_request(issuer_did: &str, rev_reg_id: &str, rev_reg_entry_json: &str)
@@ -519,29 +657,152 @@ pub fn build_rev_reg_delta_request(issuer_did: &str, rev_reg_id: &str, rev_reg_e
 
 pub fn publish_rev_reg_delta(issuer_did: &str, rev_reg_id: &str, rev_reg_entry_json: &str)
                              -> VcxResult<(Option<PaymentTxn>, String)> {
+    let txn = write_rev_reg_delta(issuer_did, rev_reg_id, rev_reg_entry_json, false)?;
+    Ok((txn.payment, txn.response.unwrap_or_default()))
+}
+
+/// Writes the revocation registry delta to the ledger, with `dry_run` the write is only prepared.
+pub fn write_rev_reg_delta(issuer_did: &str, rev_reg_id: &str, rev_reg_entry_json: &str, dry_run: bool) -> VcxResult<LedgerTxn> {
+    if dry_run && settings::indy_mocks_enabled() { return write_txn(REV_REG_DELTA_JSON, CREATE_REV_REG_DELTA_ACTION, dry_run); }
+
     let request = build_rev_reg_delta_request(issuer_did, rev_reg_id, rev_reg_entry_json)?;
-    pay_for_txn(&request, CREATE_REV_REG_DELTA_ACTION)
+    write_txn(&request, CREATE_REV_REG_DELTA_ACTION, dry_run)
 }
 
 pub fn get_rev_reg_delta_json(rev_reg_id: &str, from: Option<u64>, to: Option<u64>)
                               -> VcxResult<(String, String, u64)> {
     if settings::indy_mocks_enabled() { return Ok((REV_REG_ID.to_string(), REV_REG_DELTA_JSON.to_string(), 1)); }
 
+    let _network = pool::enter_network_of(rev_reg_id)?;
     let submitter_did = settings::get_config_value(settings::CONFIG_INSTITUTION_DID)?;
     let from: i64 = if let Some(_from) = from { _from as i64 } else { -1 };
     let to = if let Some(_to) = to { _to as i64 } else { time::get_time().sec };
 
-    libindy_build_get_revoc_reg_delta_request(&submitter_did, rev_reg_id, from, to)
+    libindy_build_get_revoc_reg_delta_request(&qualifier::to_libindy(&submitter_did), &qualifier::to_libindy(rev_reg_id), from, to)
         .and_then(|req| libindy_submit_request(&req))
         .and_then(|response| libindy_parse_get_revoc_reg_delta_response(&response))
 }
 
+///
+/// Revocation registry delta from the creation of the registry up to `to` (now if not set).
+/// Only the changes made after the delta accumulated in the wallet cache are fetched from the ledger and merged into it,
+/// deltas up to `to` preceding the accumulated one are fetched as a whole and do not replace the cached delta.
+///
+pub fn get_accumulated_rev_reg_delta_json(rev_reg_id: &str, to: Option<u64>) -> VcxResult<(String, String, u64)> {
+    if settings::indy_mocks_enabled() { return Ok((REV_REG_ID.to_string(), REV_REG_DELTA_JSON.to_string(), 1)); }
+
+    let cached = get_accumulated_rev_reg_delta(rev_reg_id);
+    let cached_timestamp = cached.as_ref().map(|cached| cached.timestamp);
+    let accumulated = cached
+        .filter(|accumulated| to.map(|to| accumulated.timestamp <= to).unwrap_or(true));
+
+    let (rev_reg_id, rev_reg_delta_json, timestamp) = match accumulated {
+        Some(accumulated) => {
+            let (rev_reg_id, rev_reg_delta_json, timestamp) = get_rev_reg_delta_json(rev_reg_id, Some(accumulated.timestamp), to)?;
+            if timestamp <= accumulated.timestamp {
+                return Ok((rev_reg_id, accumulated.value, accumulated.timestamp));
+            }
+            (rev_reg_id, _merge_rev_reg_deltas(&accumulated.value, &rev_reg_delta_json)?, timestamp)
+        }
+        None => get_rev_reg_delta_json(rev_reg_id, None, to)?
+    };
+
+    // historical deltas must not replace the later one accumulated already
+    if cached_timestamp.map(|cached_timestamp| timestamp > cached_timestamp).unwrap_or(true) {
+        set_accumulated_rev_reg_delta(&rev_reg_id, &AccumulatedRevRegDelta { timestamp, value: rev_reg_delta_json.clone() });
+    }
+
+    Ok((rev_reg_id, rev_reg_delta_json, timestamp))
+}
+
+// Applies `rev_reg_delta_json` on top of `accumulated_json`, the accumulator value is taken from the later delta.
+fn _merge_rev_reg_deltas(accumulated_json: &str, rev_reg_delta_json: &str) -> VcxResult<String> {
+    let accumulated: Value = serde_json::from_str(accumulated_json)
+        .to_vcx(VcxErrorKind::InvalidJson, "Cannot deserialize accumulated RevocationRegistryDelta")?;
+    let delta: Value = serde_json::from_str(rev_reg_delta_json)
+        .to_vcx(VcxErrorKind::InvalidJson, "Cannot deserialize RevocationRegistryDelta")?;
+
+    let accum = delta["value"]["accum"].as_str()
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, "RevocationRegistryDelta has no accum"))?;
+
+    let ids = |delta: &Value, list: &str| -> BTreeSet<u64> {
+        delta["value"][list].as_array()
+            .map(|ids| ids.iter().filter_map(Value::as_u64).collect())
+            .unwrap_or_default()
+    };
+
+    let (issued, revoked) = (ids(&delta, "issued"), ids(&delta, "revoked"));
+
+    let merged_issued: Vec<u64> = ids(&accumulated, "issued").union(&issued)
+        .filter(|id| !revoked.contains(id))
+        .cloned()
+        .collect();
+    let merged_revoked: Vec<u64> = ids(&accumulated, "revoked").union(&revoked)
+        .filter(|id| !issued.contains(id))
+        .cloned()
+        .collect();
+
+    Ok(json!({
+        "ver": delta["ver"],
+        "value": {
+            "accum": accum,
+            "issued": merged_issued,
+            "revoked": merged_revoked,
+        }
+    }).to_string())
+}
+
+fn _is_revoked_in_delta(rev_reg_delta_json: &str, cred_rev_id: &str) -> VcxResult<Option<bool>> {
+    let delta: Value = serde_json::from_str(rev_reg_delta_json)
+        .to_vcx(VcxErrorKind::InvalidJson, "Cannot deserialize RevocationRegistryDelta")?;
+
+    let cred_rev_id: u64 = cred_rev_id.parse()
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidRevocationDetails, format!("Invalid cred_rev_id: {}, err: {:?}", cred_rev_id, err)))?;
+
+    let contains = |list: &str| delta["value"][list].as_array()
+        .map(|ids| ids.iter().any(|id| id.as_u64() == Some(cred_rev_id)))
+        .unwrap_or(false);
+
+    if contains("revoked") {
+        Ok(Some(true))
+    } else if contains("issued") {
+        Ok(Some(false))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Checks whether the credential identified by `cred_rev_id` is revoked in the revocation registry.
+/// With `use_cache` only the delta since the last check stored in `RevRegCache` is fetched.
+pub fn is_cred_revoked(rev_reg_id: &str, cred_rev_id: &str, use_cache: bool) -> VcxResult<bool> {
+    trace!("is_cred_revoked >>> rev_reg_id: {}, cred_rev_id: {}, use_cache: {}", rev_reg_id, cred_rev_id, use_cache);
+
+    let mut cache = if use_cache { get_rev_reg_cache(rev_reg_id, cred_rev_id) } else { RevRegCache::default() };
+
+    let (from, known_revoked) = match cache.revocation_status {
+        Some(ref status) => (Some(status.timestamp), status.revoked),
+        None => (None, false)
+    };
+
+    let (_, rev_reg_delta_json, timestamp) = get_rev_reg_delta_json(rev_reg_id, from, None)?;
+
+    let revoked = _is_revoked_in_delta(&rev_reg_delta_json, cred_rev_id)?.unwrap_or(known_revoked);
+
+    if use_cache {
+        cache.revocation_status = Some(RevocationStatus { timestamp, revoked });
+        set_rev_reg_cache(rev_reg_id, cred_rev_id, &cache);
+    }
+
+    Ok(revoked)
+}
+
 pub fn get_rev_reg(rev_reg_id: &str, timestamp: u64) -> VcxResult<(String, String, u64)> {
     if settings::indy_mocks_enabled() { return Ok((REV_REG_ID.to_string(), REV_REG_JSON.to_string(), 1)); }
 
+    let _network = pool::enter_network_of(rev_reg_id)?;
     let submitter_did = settings::get_config_value(settings::CONFIG_INSTITUTION_DID)?;
 
-    libindy_build_get_revoc_reg_request(&submitter_did, rev_reg_id, timestamp)
+    libindy_build_get_revoc_reg_request(&qualifier::to_libindy(&submitter_did), &qualifier::to_libindy(rev_reg_id), timestamp)
         .and_then(|req| libindy_submit_request(&req))
         .and_then(|response| libindy_parse_get_revoc_reg_response(&response))
 }
@@ -553,6 +814,7 @@ pub fn revoke_credential(tails_file: &str, rev_reg_id: &str, cred_rev_id: &str)
         return Ok((Some(PaymentTxn::from_parts(inputs, outputs, 1, false)), REV_REG_DELTA_JSON.to_string()));
     }
 
+    let _network = pool::enter_network_of(rev_reg_id)?;
     let submitter_did = settings::get_config_value(settings::CONFIG_INSTITUTION_DID)?;
 
     let delta = libindy_issuer_revoke_credential(tails_file, rev_reg_id, cred_rev_id)?;
@@ -571,12 +833,19 @@ pub fn revoke_credential_local(tails_file: &str, rev_reg_id: &str, cred_rev_id:
 
 pub fn publish_local_revocations(rev_reg_id: &str)
                                  -> VcxResult<(Option<PaymentTxn>, String)> {
+    let txn = write_local_revocations(rev_reg_id, false)?;
+    Ok((txn.payment, txn.response.unwrap_or_default()))
+}
+
+/// Writes the locally cached revocations to the ledger, with `dry_run` the write is only prepared
+/// and the cache is kept.
+pub fn write_local_revocations(rev_reg_id: &str, dry_run: bool) -> VcxResult<LedgerTxn> {
     let submitter_did = settings::get_config_value(settings::CONFIG_INSTITUTION_DID)?;
     if let Some(delta) = get_rev_reg_delta_cache(rev_reg_id) {
-        match clear_rev_reg_delta_cache(rev_reg_id) {
-            Ok(_) => Ok(publish_rev_reg_delta(&submitter_did, rev_reg_id, &delta)?),
-            Err(err) => Err(err)
+        if !dry_run {
+            clear_rev_reg_delta_cache(rev_reg_id)?;
         }
+        write_rev_reg_delta(&submitter_did, rev_reg_id, &delta, dry_run)
     } else {
         Err(VcxError::from(VcxErrorKind::RevDeltaNotFound))
     }
@@ -934,6 +1203,50 @@ pub mod tests {
         assert!(rc.is_ok());
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_merge_rev_reg_deltas() {
+        let accumulated = r#"{"ver":"1.0","value":{"accum":"1","issued":[1,2,3],"revoked":[4]}}"#;
+        let delta = r#"{"ver":"1.0","value":{"prevAccum":"1","accum":"2","issued":[4],"revoked":[2,5]}}"#;
+
+        let merged: Value = serde_json::from_str(&_merge_rev_reg_deltas(accumulated, delta).unwrap()).unwrap();
+        assert_eq!(merged, json!({"ver":"1.0","value":{"accum":"2","issued":[1,3,4],"revoked":[2,5]}}));
+
+        let full = r#"{"ver":"1.0","value":{"accum":"1"}}"#;
+        let merged: Value = serde_json::from_str(&_merge_rev_reg_deltas(full, delta).unwrap()).unwrap();
+        assert_eq!(merged, json!({"ver":"1.0","value":{"accum":"2","issued":[4],"revoked":[2,5]}}));
+
+        assert_eq!(_merge_rev_reg_deltas(accumulated, r#"{"ver":"1.0","value":{}}"#).unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_is_revoked_in_delta() {
+        let delta = json!({"ver": "1.0", "value": {"accum": "1", "issued": [2], "revoked": [1, 3]}}).to_string();
+
+        assert_eq!(_is_revoked_in_delta(&delta, "1").unwrap(), Some(true));
+        assert_eq!(_is_revoked_in_delta(&delta, "2").unwrap(), Some(false));
+        assert_eq!(_is_revoked_in_delta(&delta, "4").unwrap(), None);
+        assert_eq!(_is_revoked_in_delta(&delta, "abc").unwrap_err().kind(), VcxErrorKind::InvalidRevocationDetails);
+    }
+
+    #[cfg(feature = "pool_tests")]
+    #[test]
+    fn test_is_cred_revoked() {
+        let _setup = SetupLibraryWalletPool::init();
+
+        let (_, _, _, _, _, _, _, _, rev_reg_id, cred_rev_id)
+            = create_and_store_credential(::utils::constants::DEFAULT_SCHEMA_ATTRS, true);
+        let (rev_reg_id, cred_rev_id) = (rev_reg_id.unwrap(), cred_rev_id.unwrap());
+        assert!(!is_cred_revoked(&rev_reg_id, &cred_rev_id, true).unwrap());
+
+        revoke_credential(get_temp_dir_path(TEST_TAILS_FILE).to_str().unwrap(), &rev_reg_id, &cred_rev_id).unwrap();
+        thread::sleep(Duration::from_millis(500));
+
+        assert!(is_cred_revoked(&rev_reg_id, &cred_rev_id, false).unwrap());
+        assert!(is_cred_revoked(&rev_reg_id, &cred_rev_id, true).unwrap());
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_create_cred_def() {
@@ -1074,4 +1387,52 @@ pub mod tests {
         assert!(payment.is_some());
         assert_ne!(first_rev_reg_delta, second_rev_reg_delta);
     }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_list_credentials() {
+        let _setup = SetupLibraryWallet::init();
+
+        let credentials: Value = serde_json::from_str(&list_credentials(None, 0, 10).unwrap()).unwrap();
+        assert_eq!(credentials, json!({"total": 0, "credentials": []}));
+
+        let filter = json!({"schema_id": "V4SGRU86Z58d6TV7PBUe6f:2:gvt:1.0"}).to_string();
+        let credentials: Value = serde_json::from_str(&list_credentials(Some(&filter), 5, 10).unwrap()).unwrap();
+        assert_eq!(credentials["credentials"], json!([]));
+
+        assert_eq!(list_credentials(None, 0, 0).unwrap_err().kind(), VcxErrorKind::InvalidOption);
+        assert_eq!(list_credentials(Some(r#"{"schema_id": {"$regex": "gvt"}}"#), 0, 10).unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+
+    #[cfg(feature = "pool_tests")]
+    #[test]
+    fn test_list_credentials_of_populated_wallet() {
+        let _setup = SetupLibraryWalletPool::init();
+
+        let (schema_id, _, _, cred_def_json, offer, req, req_meta, cred_id, _, _) = create_and_store_credential(DEFAULT_SCHEMA_ATTRS, false);
+        let encoded_attributes = encode_attributes(r#"{"address1": ["123 Main St"], "address2": ["Suite 3"], "city": ["Draper"], "state": ["UT"], "zip": ["84000"]}"#).unwrap();
+        let mut cred_ids = vec![cred_id];
+        for _ in 0..2 {
+            let (cred, _, _) = libindy_issuer_create_credential(&offer, &req, &encoded_attributes, None, None).unwrap();
+            cred_ids.push(libindy_prover_store_credential(None, &req_meta, &cred, &cred_def_json, None).unwrap());
+        }
+
+        let filter = json!({"schema_id": schema_id}).to_string();
+        let first_page: Value = serde_json::from_str(&list_credentials(Some(&filter), 0, 2).unwrap()).unwrap();
+        let second_page: Value = serde_json::from_str(&list_credentials(Some(&filter), 2, 2).unwrap()).unwrap();
+        let past_end: Value = serde_json::from_str(&list_credentials(Some(&filter), 3, 2).unwrap()).unwrap();
+
+        assert_eq!(first_page["total"], 3);
+        assert_eq!(first_page["credentials"].as_array().unwrap().len(), 2);
+        assert_eq!(second_page["credentials"].as_array().unwrap().len(), 1);
+        assert_eq!(past_end["credentials"], json!([]));
+
+        let mut listed: Vec<String> = first_page["credentials"].as_array().unwrap().iter()
+            .chain(second_page["credentials"].as_array().unwrap().iter())
+            .map(|credential| credential["referent"].as_str().unwrap().to_string())
+            .collect();
+        listed.sort();
+        cred_ids.sort();
+        assert_eq!(listed, cred_ids);
+    }
 }
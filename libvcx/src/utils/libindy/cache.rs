@@ -7,6 +7,7 @@ static CACHE_TYPE: &str = "cache";
 static REV_REG_CACHE_PREFIX: &str = "rev_reg:";
 static REV_REG_DELTA_CACHE_PREFIX: &str = "rev_reg_delta:";
 static REV_REG_IDS_CACHE_PREFIX: &str = "rev_reg_ids:";
+static ACCUMULATED_REV_REG_DELTA_CACHE_PREFIX: &str = "accumulated_rev_reg_delta:";
 
 ///
 /// Cache object for rev reg cache
@@ -14,6 +15,8 @@ static REV_REG_IDS_CACHE_PREFIX: &str = "rev_reg_ids:";
 #[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
 pub struct RevRegCache {
     pub rev_state: Option<RevState>,
+    #[serde(default)]
+    pub revocation_status: Option<RevocationStatus>,
 }
 
 ///
@@ -25,6 +28,15 @@ pub struct RevState {
     pub value: String,
 }
 
+///
+/// Revocation status of the credential as known by the holder at the given timestamp.
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RevocationStatus {
+    pub timestamp: u64,
+    pub revoked: bool,
+}
+
 ///
 ///
 /// Cache object for rev reg delta cache
@@ -43,6 +55,15 @@ pub struct RevRegDeltaState {
     pub ver: String,
 }
 
+///
+/// Revocation registry delta from the creation of the registry up to `timestamp`, accumulated by the prover.
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AccumulatedRevRegDelta {
+    pub timestamp: u64,
+    pub value: String,
+}
+
 // TODO: Maybe we need to persist more info
 #[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
 pub struct RevRegIdsCache {
@@ -103,6 +124,64 @@ pub fn set_rev_reg_cache(rev_reg_id: &str, cred_rev_id: &str, cache: &RevRegCach
     }
 }
 
+///
+/// Removes the rev reg cache of a deleted credential.
+/// Errors are silently ignored.
+///
+pub fn delete_rev_reg_cache(rev_reg_id: &str, cred_rev_id: &str) {
+    let wallet_id = format!("{}{}:{}", REV_REG_CACHE_PREFIX, rev_reg_id, cred_rev_id);
+    match delete_record(CACHE_TYPE, &wallet_id) {
+        Ok(()) => {}
+        Err(ref err) if err.kind() == VcxErrorKind::WalletRecordNotFound => {}
+        Err(err) => warn!("Error when deleting rev reg cache {}, error: {:?}", wallet_id, err)
+    }
+}
+
+///
+/// Returns the rev reg delta accumulated by the prover.
+/// In case of error returns None and silently ignores error.
+///
+pub fn get_accumulated_rev_reg_delta(rev_reg_id: &str) -> Option<AccumulatedRevRegDelta> {
+    let wallet_id = format!("{}{}", ACCUMULATED_REV_REG_DELTA_CACHE_PREFIX, rev_reg_id);
+    match get_record(CACHE_TYPE, &wallet_id, &json!({"retrieveType": false, "retrieveValue": true, "retrieveTags": false}).to_string()) {
+        Ok(json) => {
+            match serde_json::from_str(&json)
+                .and_then(|x: serde_json::Value|
+                    serde_json::from_str(x.get("value").unwrap_or(&serde_json::Value::Null).as_str().unwrap_or(""))) {
+                Ok(delta) => Some(delta),
+                Err(err) => {
+                    warn!("Unable to convert accumulated rev_reg_delta for rev_reg_id: {}, error: {}", rev_reg_id, err);
+                    None
+                }
+            }
+        }
+        Err(err) => {
+            debug!("No accumulated rev_reg_delta for rev_reg_id: {}, error: {}", rev_reg_id, err);
+            None
+        }
+    }
+}
+
+///
+/// Saves the rev reg delta accumulated by the prover.
+/// Errors are silently ignored.
+///
+pub fn set_accumulated_rev_reg_delta(rev_reg_id: &str, delta: &AccumulatedRevRegDelta) {
+    match serde_json::to_string(delta) {
+        Ok(json) => {
+            let wallet_id = format!("{}{}", ACCUMULATED_REV_REG_DELTA_CACHE_PREFIX, rev_reg_id);
+            let result = update_record_value(CACHE_TYPE, &wallet_id, &json)
+                .or(add_record(CACHE_TYPE, &wallet_id, &json, None));
+            if result.is_err() {
+                warn!("Error when saving accumulated rev_reg_delta for rev_reg_id: {}, error: {:?}", rev_reg_id, result);
+            }
+        }
+        Err(err) => {
+            warn!("Unable to convert to JSON accumulated rev_reg_delta for rev_reg_id: {}, error: {:?}", rev_reg_id, err);
+        }
+    }
+}
+
 fn set_rev_reg_ids_cache(cred_def_id: &str, cache: &str) -> VcxResult<()> {
     debug!("Setting rev_reg_ids for cred_def_id {}, cache {}", cred_def_id, cache);
     match serde_json::to_string(cache) {
@@ -285,7 +364,8 @@ pub mod tests {
             rev_state: Some(RevState {
                 timestamp: 1000,
                 value: r#"{"key": "value1"}"#.to_string(),
-            })
+            }),
+            revocation_status: None,
         };
 
         set_rev_reg_cache(_rev_reg_id(), _cred_rev_id(), &data);
@@ -295,6 +375,22 @@ pub mod tests {
         assert_eq!(result, data);
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_accumulated_rev_reg_delta_set_than_get_works() {
+        let _setup = SetupLibraryWallet::init();
+
+        assert_eq!(get_accumulated_rev_reg_delta(_rev_reg_id()), None);
+
+        let delta1 = AccumulatedRevRegDelta { timestamp: 1000, value: r#"{"key": "value1"}"#.to_string() };
+        set_accumulated_rev_reg_delta(_rev_reg_id(), &delta1);
+        assert_eq!(get_accumulated_rev_reg_delta(_rev_reg_id()), Some(delta1));
+
+        let delta2 = AccumulatedRevRegDelta { timestamp: 2000, value: r#"{"key": "value2"}"#.to_string() };
+        set_accumulated_rev_reg_delta(_rev_reg_id(), &delta2);
+        assert_eq!(get_accumulated_rev_reg_delta(_rev_reg_id()), Some(delta2));
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_credential_cache_set_than_double_get_works() {
@@ -304,7 +400,8 @@ pub mod tests {
             rev_state: Some(RevState {
                 timestamp: 1000,
                 value: r#"{"key": "value1"}"#.to_string(),
-            })
+            }),
+            revocation_status: None,
         };
 
         set_rev_reg_cache(_rev_reg_id(), _cred_rev_id(), &data);
@@ -325,14 +422,16 @@ pub mod tests {
             rev_state: Some(RevState {
                 timestamp: 1000,
                 value: r#"{"key": "value1"}"#.to_string(),
-            })
+            }),
+            revocation_status: None,
         };
 
         let data2 = RevRegCache {
             rev_state: Some(RevState {
                 timestamp: 2000,
                 value: r#"{"key": "value2"}"#.to_string(),
-            })
+            }),
+            revocation_status: None,
         };
 
         set_rev_reg_cache(_rev_reg_id(), _cred_rev_id(), &data1);
@@ -5,6 +5,8 @@ use indy::crypto;
 use error::prelude::*;
 use settings;
 use utils::libindy::LibindyMock;
+use utils::metrics;
+use utils::secret::SecretBytes;
 
 pub fn prep_msg(sender_vk: &str, recipient_vk: &str, msg: &[u8]) -> VcxResult<Vec<u8>> {
     if settings::indy_mocks_enabled() {
@@ -61,17 +63,23 @@ pub fn verify(vk: &str, msg: &[u8], signature: &[u8]) -> VcxResult<bool> {
 pub fn pack_message(sender_vk: Option<&str>, receiver_keys: &str, msg: &[u8]) -> VcxResult<Vec<u8>> {
     if settings::indy_mocks_enabled() { return Ok(msg.to_vec()); }
 
-    crypto::pack_message(::utils::libindy::wallet::get_wallet_handle(), msg, receiver_keys, sender_vk)
-        .wait()
-        .map_err(VcxError::from)
+    metrics::measure(metrics::PACK_MESSAGE, || {
+        crypto::pack_message(::utils::libindy::wallet::get_wallet_handle(), msg, receiver_keys, sender_vk)
+            .wait()
+            .map_err(VcxError::from)
+    })
 }
 
-pub fn unpack_message(msg: &[u8]) -> VcxResult<Vec<u8>> {
-    if settings::indy_mocks_enabled() { return Ok(Vec::from(msg).to_owned()); }
+/// Unpacked message is wiped from memory once the caller drops it.
+pub fn unpack_message(msg: &[u8]) -> VcxResult<SecretBytes> {
+    if settings::indy_mocks_enabled() { return Ok(SecretBytes::from(msg.to_vec())); }
 
-    crypto::unpack_message(::utils::libindy::wallet::get_wallet_handle(), msg)
-        .wait()
-        .map_err(VcxError::from)
+    metrics::measure(metrics::UNPACK_MESSAGE, || {
+        crypto::unpack_message(::utils::libindy::wallet::get_wallet_handle(), msg)
+            .wait()
+            .map(SecretBytes::from)
+            .map_err(VcxError::from)
+    })
 }
 
 pub fn create_key(seed: Option<&str>) -> VcxResult<String> {
@@ -7,8 +7,12 @@ use serde_json;
 
 use error::prelude::*;
 use settings;
-use utils::libindy::pool::get_pool_handle;
+use utils::cancellation;
+use utils::constants::{CREATE_CRED_DEF_ACTION, CREATE_REV_REG_DEF_ACTION, CREATE_REV_REG_DELTA_ACTION, CREATE_SCHEMA_ACTION, CREATE_TRANSFER_ACTION};
+use utils::libindy::pool::{self, get_pool_handle};
 use utils::libindy::wallet::get_wallet_handle;
+use utils::metrics;
+use utils::qualifier;
 
 pub fn multisign_request(did: &str, request: &str) -> VcxResult<String> {
     ledger::multi_sign_request(get_wallet_handle(), did, request)
@@ -23,22 +27,42 @@ pub fn libindy_sign_request(did: &str, request: &str) -> VcxResult<String> {
 }
 
 pub fn libindy_sign_and_submit_request(issuer_did: &str, request_json: &str) -> VcxResult<String> {
+    cancellation::check()?;
     if settings::indy_mocks_enabled() { return Ok(r#"{"rc":"success"}"#.to_string()); }
 
     let pool_handle = get_pool_handle()?;
     let wallet_handle = get_wallet_handle();
 
-    ledger::sign_and_submit_request(pool_handle, wallet_handle, issuer_did, request_json)
-        .wait()
-        .map_err(VcxError::from)
+    let response = metrics::measure(metrics::LEDGER_REQUEST, || {
+        ledger::sign_and_submit_request(pool_handle, wallet_handle, issuer_did, request_json)
+            .wait()
+            .map_err(VcxError::from)
+    })?;
+    _check_txn_author_agreement_rejection(response)
 }
 
 pub fn libindy_submit_request(request_json: &str) -> VcxResult<String> {
+    cancellation::check()?;
     let pool_handle = get_pool_handle()?;
 
-    ledger::submit_request(pool_handle, request_json)
-        .wait()
-        .map_err(VcxError::from)
+    let response = metrics::measure(metrics::LEDGER_REQUEST, || {
+        ledger::submit_request(pool_handle, request_json)
+            .wait()
+            .map_err(VcxError::from)
+    })?;
+    _check_txn_author_agreement_rejection(response)
+}
+
+// write rejected for missing or outdated agreement acceptance is reported separately,
+// so that the caller knows to accept the current agreement and retry
+fn _check_txn_author_agreement_rejection(response: String) -> VcxResult<String> {
+    match serde_json::from_str::<Response>(&response) {
+        Ok(Response::Reject(ref reject)) | Ok(Response::ReqNACK(ref reject))
+        if reject.reason.to_lowercase().contains("txn author agreement") => {
+            Err(VcxError::from_msg(VcxErrorKind::TxnAuthorAgreementRequired, reject.reason.clone()))
+        }
+        _ => Ok(response)
+    }
 }
 
 pub fn libindy_build_schema_request(submitter_did: &str, data: &str) -> VcxResult<String> {
@@ -85,6 +109,36 @@ pub fn libindy_get_txn_author_agreement() -> VcxResult<String> {
     Ok(author_agreement_data.to_string())
 }
 
+/// Transaction author agreement currently active on the ledger with its acceptance mechanisms.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LedgerTxnAuthorAgreement {
+    pub text: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ratification_ts: Option<u64>,
+    /// acceptance mechanism label -> description
+    #[serde(default)]
+    pub aml: HashMap<String, String>,
+}
+
+/// Returns `None` when the ledger does not require accepting any agreement.
+pub fn get_txn_author_agreement() -> VcxResult<Option<LedgerTxnAuthorAgreement>> {
+    let author_agreement = libindy_get_txn_author_agreement()?;
+    let author_agreement: serde_json::Value = serde_json::from_str(&author_agreement)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidLedgerResponse, format!("Cannot deserialize transaction author agreement: {:?}", err)))?;
+
+    // disabled agreement is published with empty text
+    if author_agreement["text"].as_str().unwrap_or_default().is_empty() {
+        return Ok(None);
+    }
+
+    serde_json::from_value(author_agreement)
+        .map(Some)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidLedgerResponse, format!("Cannot deserialize transaction author agreement: {:?}", err)))
+}
+
 pub fn append_txn_author_agreement_to_request(request_json: &str) -> VcxResult<String> {
     if let Some(author_agreement) = ::utils::author_agreement::get_txn_author_agreement()? {
         ledger::append_txn_author_agreement_acceptance_to_request(request_json,
@@ -277,8 +331,11 @@ pub mod auth_rule {
 
         let auth_rules_request = libindy_build_auth_rules_request(submitter_did, &data)?;
 
-        let response = ledger::sign_and_submit_request(get_pool_handle()?, get_wallet_handle(), submitter_did, &auth_rules_request)
-            .wait()?;
+        let response = metrics::measure(metrics::LEDGER_REQUEST, || {
+            ledger::sign_and_submit_request(get_pool_handle()?, get_wallet_handle(), submitter_did, &auth_rules_request)
+                .wait()
+                .map_err(VcxError::from)
+        })?;
 
         let response: serde_json::Value = ::serde_json::from_str(&response)
             .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidLedgerResponse, format!("{:?}", err)))?;
@@ -360,28 +417,78 @@ pub fn get_role(did: &str) -> VcxResult<String> {
     Ok(role)
 }
 
+/// Tokens needed to write a transaction of given type (e.g. "101" for schema, "102" for credential definition)
+/// according to the fee schedule set on the ledger. Zero when the write is free or payments are not supported.
+pub fn estimate_fees(txn_type: &str) -> VcxResult<u64> {
+    let action = [CREATE_SCHEMA_ACTION, CREATE_CRED_DEF_ACTION, CREATE_REV_REG_DEF_ACTION, CREATE_REV_REG_DELTA_ACTION, CREATE_TRANSFER_ACTION]
+        .iter()
+        .find(|action| action.0 == txn_type)
+        .cloned()
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Unsupported transaction type: {}", txn_type)))?;
+
+    ::utils::libindy::payments::get_action_price(action, None)
+}
+
 pub fn parse_response(response: &str) -> VcxResult<Response> {
     serde_json::from_str::<Response>(response)
         .to_vcx(VcxErrorKind::InvalidJson, "Cannot deserialize transaction response")
 }
 
 pub fn libindy_get_schema(submitter_did: &str, schema_id: &str) -> VcxResult<String> {
+    let _network = pool::enter_network_of(schema_id)?;
     let pool_handle = get_pool_handle()?;
     let wallet_handle = get_wallet_handle();
 
-    cache::get_schema(pool_handle, wallet_handle, submitter_did, schema_id, "{}")
-        .wait()
-        .map_err(VcxError::from)
+    metrics::measure(metrics::LEDGER_REQUEST, || {
+        cache::get_schema(pool_handle, wallet_handle, &qualifier::to_libindy(submitter_did), &qualifier::to_libindy(schema_id), "{}")
+            .wait()
+            .map_err(VcxError::from)
+    })
 }
 
 pub fn libindy_get_cred_def(cred_def_id: &str) -> VcxResult<String> {
+    let _network = pool::enter_network_of(cred_def_id)?;
     let pool_handle = get_pool_handle()?;
     let wallet_handle = get_wallet_handle();
     let submitter_did = settings::get_config_value(settings::CONFIG_INSTITUTION_DID)?;
 
-    cache::get_cred_def(pool_handle, wallet_handle, &submitter_did, cred_def_id, "{}")
-        .wait()
-        .map_err(VcxError::from)
+    metrics::measure(metrics::LEDGER_REQUEST, || {
+        cache::get_cred_def(pool_handle, wallet_handle, &qualifier::to_libindy(&submitter_did), &qualifier::to_libindy(cred_def_id), "{}")
+            .wait()
+            .map_err(VcxError::from)
+    })
+}
+
+///
+/// Fails with `InvalidProofCredentialData` when the schema, credential definition or revocation registry definition `id`
+/// was written to the ledger after `as_of`. These objects are never changed once written,
+/// so their current content is the content they had at `as_of`.
+///
+pub fn check_written_before(id: &str, as_of: u64) -> VcxResult<()> {
+    if settings::indy_mocks_enabled() { return Ok(()); }
+
+    let _network = pool::enter_network_of(id)?;
+    let submitter_did = qualifier::to_libindy(&settings::get_config_value(settings::CONFIG_INSTITUTION_DID)?);
+    let ledger_id = qualifier::to_libindy(id);
+    let unqualified = qualifier::unqualify(id);
+
+    let request = if unqualified.contains(":4:") {
+        ledger::build_get_revoc_reg_def_request(Some(&submitter_did), &ledger_id).wait()?
+    } else if unqualified.contains(":3:") {
+        ledger::build_get_cred_def_request(Some(&submitter_did), &ledger_id).wait()?
+    } else {
+        ledger::build_get_schema_request(Some(&submitter_did), &ledger_id).wait()?
+    };
+
+    let response: serde_json::Value = serde_json::from_str(&libindy_submit_request(&request)?)
+        .to_vcx(VcxErrorKind::InvalidJson, "Cannot deserialize ledger response")?;
+
+    match response["result"]["txnTime"].as_u64() {
+        Some(txn_time) if txn_time <= as_of => Ok(()),
+        Some(txn_time) => Err(VcxError::from_msg(VcxErrorKind::InvalidProofCredentialData,
+                                                 format!("{} was written to the ledger at {}, after {}", id, txn_time, as_of))),
+        None => Err(VcxError::from_msg(VcxErrorKind::InvalidProofCredentialData, format!("{} was not found on the ledger", id)))
+    }
 }
 
 pub fn set_endorser(request: &str, endorser: &str) -> VcxResult<String> {
@@ -457,6 +564,42 @@ mod test {
         assert!(_verify_transaction_can_be_endorsed(transaction, "EbP4aYNeTHL6q385GuVpRV").is_err());
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_txn_author_agreement_rejection() {
+        let _setup = SetupDefaults::init();
+
+        let response = r#"{"op":"REJECT","reqId":1,"reason":"client request invalid: InvalidClientTaaAcceptanceError(1, 'Txn Author Agreement acceptance is required for ledger with id 1')"}"#;
+        assert_eq!(_check_txn_author_agreement_rejection(response.to_string()).unwrap_err().kind(), VcxErrorKind::TxnAuthorAgreementRequired);
+
+        let response = r#"{"op":"REJECT","reqId":1,"reason":"client request invalid: UnauthorizedClientRequest"}"#;
+        assert_eq!(_check_txn_author_agreement_rejection(response.to_string()).unwrap(), response);
+
+        let response = r#"{"op":"REPLY","result":{}}"#;
+        assert_eq!(_check_txn_author_agreement_rejection(response.to_string()).unwrap(), response);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_estimate_fees() {
+        let _setup = SetupAriesMocks::init();
+
+        assert_eq!(estimate_fees(::utils::constants::SCHEMA_TXN_TYPE).unwrap(), 2);
+        assert_eq!(estimate_fees(::utils::constants::CRED_DEF_TXN_TYPE).unwrap(), 42);
+        assert_eq!(estimate_fees("1").unwrap_err().kind(), VcxErrorKind::InvalidOption);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_txn_author_agreement() {
+        let _setup = SetupAriesMocks::init();
+
+        let author_agreement = get_txn_author_agreement().unwrap().unwrap();
+        assert_eq!(author_agreement.text, "Default indy agreement");
+        assert_eq!(author_agreement.version, "1.0.0");
+        assert!(author_agreement.aml.contains_key("acceptance mechanism label1"));
+    }
+
     #[cfg(feature = "pool_tests")]
     #[test]
     fn test_endorse_transaction() {
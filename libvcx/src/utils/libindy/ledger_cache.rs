@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use error::prelude::*;
+use settings;
+use utils::agent_context;
+use utils::libindy::{anoncreds, pool};
+
+pub static SCHEMA: &str = "schema";
+pub static CRED_DEF: &str = "cred_def";
+pub static REV_REG_DEF: &str = "rev_reg_def";
+
+lazy_static! {
+    static ref LEDGER_CACHE: Mutex<HashMap<(Option<u32>, Option<String>, String), CachedLedgerObject>> = Default::default();
+}
+
+struct CachedLedgerObject {
+    fetched_at: Instant,
+    value: String,
+}
+
+/// Identifiers of the ledger objects to be loaded into the cache ahead of time.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct LedgerCacheWarmUp {
+    #[serde(default)]
+    pub schema_ids: Vec<String>,
+    #[serde(default)]
+    pub cred_def_ids: Vec<String>,
+    #[serde(default)]
+    pub rev_reg_def_ids: Vec<String>,
+}
+
+// agents may use different ledgers and cache settings, so each has its own entries, see `agent::Agent`,
+// the same unqualified identifier may also name different objects on different networks
+fn _key(object_type: &str, id: &str) -> VcxResult<(Option<u32>, Option<String>, String)> {
+    Ok((agent_context::current_agent_id(), pool::resolve_network(id)?, format!("{}:{}", object_type, id)))
+}
+
+///
+/// Returns the ledger object from the in-memory cache if it is younger than `ledger_cache_ttl`,
+/// otherwise fetches it and stores it for the next calls, evicting the oldest objects of the agent
+/// above `ledger_cache_capacity`.
+/// Does nothing but calling `fetch` when the cache is disabled.
+///
+pub fn get_or_fetch<F>(object_type: &str, id: &str, fetch: F) -> VcxResult<String> where F: FnOnce() -> VcxResult<String> {
+    let ttl = match settings::get_ledger_cache_ttl() {
+        Some(ttl) => ttl,
+        None => return fetch()
+    };
+
+    let key = _key(object_type, id)?;
+
+    if let Some(cached) = LEDGER_CACHE.lock().unwrap().get(&key) {
+        if cached.fetched_at.elapsed() < ttl {
+            trace!("ledger_cache::get_or_fetch >>> using cached {}", key.2);
+            return Ok(cached.value.clone());
+        }
+    }
+
+    let value = fetch()?;
+    let mut cache = LEDGER_CACHE.lock().unwrap();
+    cache.insert(key.clone(), CachedLedgerObject { fetched_at: Instant::now(), value: value.clone() });
+    _evict(&mut cache, key.0, ttl, settings::get_ledger_cache_capacity());
+    Ok(value)
+}
+
+// drops expired objects of the agent and then the oldest ones until the agent fits into `capacity`
+fn _evict(cache: &mut HashMap<(Option<u32>, Option<String>, String), CachedLedgerObject>, agent_id: Option<u32>, ttl: Duration, capacity: usize) {
+    cache.retain(|&(agent, _, _), cached| agent != agent_id || cached.fetched_at.elapsed() < ttl);
+
+    let mut cached: Vec<_> = cache.iter()
+        .filter(|&(&(agent, _, _), _)| agent == agent_id)
+        .map(|(key, cached)| (cached.fetched_at, key.clone()))
+        .collect();
+    if cached.len() <= capacity {
+        return;
+    }
+    let excess = cached.len() - capacity;
+    cached.sort_by_key(|&(fetched_at, _)| fetched_at);
+    for (_, key) in cached.into_iter().take(excess) {
+        cache.remove(&key);
+    }
+}
+
+///
+/// Removes ledger objects cached for the current agent.
+///
+/// # Arguments
+/// `max_age`: only objects older than this are removed, all objects are removed if not set
+///
+pub fn purge(max_age: Option<Duration>) {
+    debug!("Purging ledger cache, max_age: {:?}", max_age);
+
+    let agent_id = agent_context::current_agent_id();
+    LEDGER_CACHE.lock().unwrap()
+        .retain(|&(agent, _, _), cached| agent != agent_id || max_age.map(|max_age| cached.fetched_at.elapsed() < max_age).unwrap_or(false));
+}
+
+///
+/// Fetches given ledger objects into the cache so that following proof generation or verification
+/// does not have to wait for the ledger.
+///
+pub fn warm(ids: &LedgerCacheWarmUp) -> VcxResult<()> {
+    debug!("Warming ledger cache up with {:?}", ids);
+
+    if settings::get_ledger_cache_ttl().is_none() {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidConfiguration, "Ledger cache is disabled, set `ledger_cache_ttl` to enable it"));
+    }
+
+    for schema_id in ids.schema_ids.iter() {
+        anoncreds::get_schema_json(schema_id)?;
+    }
+    for cred_def_id in ids.cred_def_ids.iter() {
+        anoncreds::get_cred_def_json(cred_def_id)?;
+    }
+    for rev_reg_def_id in ids.rev_reg_def_ids.iter() {
+        anoncreds::get_rev_reg_def_json(rev_reg_def_id)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::cell::Cell;
+
+    use utils::devsetup::SetupDefaults;
+
+    use super::*;
+
+    fn _fetch(calls: &Cell<u32>) -> VcxResult<String> {
+        calls.set(calls.get() + 1);
+        Ok(format!("value{}", calls.get()))
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_ledger_cache_works() {
+        let _setup = SetupDefaults::init();
+        purge(None);
+
+        let calls = Cell::new(0);
+
+        // disabled by default
+        assert_eq!(get_or_fetch(SCHEMA, "id1", || _fetch(&calls)).unwrap(), "value1");
+        assert_eq!(get_or_fetch(SCHEMA, "id1", || _fetch(&calls)).unwrap(), "value2");
+
+        settings::set_config_value(settings::CONFIG_LEDGER_CACHE_TTL, "60");
+        assert_eq!(get_or_fetch(SCHEMA, "id1", || _fetch(&calls)).unwrap(), "value3");
+        assert_eq!(get_or_fetch(SCHEMA, "id1", || _fetch(&calls)).unwrap(), "value3");
+        assert_eq!(get_or_fetch(CRED_DEF, "id1", || _fetch(&calls)).unwrap(), "value4");
+
+        purge(Some(Duration::from_secs(60)));
+        assert_eq!(get_or_fetch(SCHEMA, "id1", || _fetch(&calls)).unwrap(), "value3");
+
+        purge(None);
+        assert_eq!(get_or_fetch(SCHEMA, "id1", || _fetch(&calls)).unwrap(), "value5");
+
+        assert!(get_or_fetch(SCHEMA, "id2", || Err(VcxError::from(VcxErrorKind::InvalidSchema))).is_err());
+        assert_eq!(get_or_fetch(SCHEMA, "id2", || _fetch(&calls)).unwrap(), "value6");
+        purge(None);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_ledger_cache_evicts_oldest_objects_above_capacity() {
+        let _setup = SetupDefaults::init();
+        purge(None);
+
+        settings::set_config_value(settings::CONFIG_LEDGER_CACHE_TTL, "60");
+        settings::set_config_value(settings::CONFIG_LEDGER_CACHE_CAPACITY, "2");
+
+        let calls = Cell::new(0);
+        assert_eq!(get_or_fetch(SCHEMA, "id1", || _fetch(&calls)).unwrap(), "value1");
+        ::std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(get_or_fetch(SCHEMA, "id2", || _fetch(&calls)).unwrap(), "value2");
+        ::std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(get_or_fetch(SCHEMA, "id3", || _fetch(&calls)).unwrap(), "value3");
+
+        assert_eq!(get_or_fetch(SCHEMA, "id3", || _fetch(&calls)).unwrap(), "value3");
+        assert_eq!(get_or_fetch(SCHEMA, "id2", || _fetch(&calls)).unwrap(), "value2");
+        assert_eq!(get_or_fetch(SCHEMA, "id1", || _fetch(&calls)).unwrap(), "value4");
+        purge(None);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_ledger_cache_warm_requires_enabled_cache() {
+        let _setup = SetupDefaults::init();
+
+        assert_eq!(warm(&LedgerCacheWarmUp::default()).unwrap_err().kind(), VcxErrorKind::InvalidConfiguration);
+
+        settings::set_config_value(settings::CONFIG_LEDGER_CACHE_TTL, "60");
+        warm(&LedgerCacheWarmUp::default()).unwrap();
+    }
+}
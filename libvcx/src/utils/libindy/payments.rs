@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::fmt;
 
+use failure::Fail;
 use futures::Future;
 use indy::payments;
 use serde_json::Value;
@@ -8,7 +9,7 @@ use serde_json::Value;
 use error::prelude::*;
 use settings;
 use utils::constants::{CREATE_TRANSFER_ACTION, SUBMIT_SCHEMA_RESPONSE};
-use utils::libindy::ledger::{append_txn_author_agreement_to_request, auth_rule, libindy_sign_and_submit_request, libindy_sign_request, libindy_submit_request};
+use utils::libindy::ledger::{append_txn_author_agreement_to_request, auth_rule, libindy_sign_and_submit_request, libindy_sign_request, libindy_submit_request, Response};
 use utils::libindy::wallet::get_wallet_handle;
 
 static DEFAULT_FEES: &str = r#"{"0":0, "1":0, "3":0, "100":0, "101":2, "102":42, "103":0, "104":0, "105":0, "107":0, "108":0, "109":0, "110":0, "111":0, "112":0, "113":2, "114":2, "115":0, "116":0, "117":0, "118":0, "119":0, "10001":0}"#;
@@ -82,6 +83,15 @@ impl PaymentTxn {
     }
 }
 
+/// Fails for token operations when libvcx is built without the `payments` feature.
+fn ensure_payments_supported() -> VcxResult<()> {
+    if cfg!(feature = "payments") {
+        Ok(())
+    } else {
+        Err(VcxError::from_msg(VcxErrorKind::ActionNotSupported, "Libvcx was built without payments support"))
+    }
+}
+
 pub fn build_test_address(address: &str) -> String {
     format!("pay:{}:{}", ::settings::get_payment_method(), address)
 }
@@ -89,6 +99,8 @@ pub fn build_test_address(address: &str) -> String {
 pub fn create_address(seed: Option<String>) -> VcxResult<String> {
     trace!("create_address >>> seed: {:?}", seed);
 
+    ensure_payments_supported()?;
+
     if settings::indy_mocks_enabled() {
         return Ok(build_test_address("J81AxU9hVHYFtJc"));
     }
@@ -106,6 +118,8 @@ pub fn create_address(seed: Option<String>) -> VcxResult<String> {
 pub fn sign_with_address(address: &str, message: &[u8]) -> VcxResult<Vec<u8>> {
     trace!("sign_with_address >>> address: {:?}, message: {:?}", address, message);
 
+    ensure_payments_supported()?;
+
     if settings::indy_mocks_enabled() { return Ok(Vec::from(message).to_owned()); }
 
     payments::sign_with_address(get_wallet_handle(), address, message).wait().map_err(VcxError::from)
@@ -114,12 +128,16 @@ pub fn sign_with_address(address: &str, message: &[u8]) -> VcxResult<Vec<u8>> {
 pub fn verify_with_address(address: &str, message: &[u8], signature: &[u8]) -> VcxResult<bool> {
     trace!("sign_with_address >>> address: {:?}, message: {:?}", address, message);
 
+    ensure_payments_supported()?;
+
     if settings::indy_mocks_enabled() { return Ok(true); }
 
     payments::verify_with_address(address, message, signature).wait().map_err(VcxError::from)
 }
 
 pub fn get_address_info(address: &str) -> VcxResult<AddressInfo> {
+    ensure_payments_supported()?;
+
     if settings::indy_mocks_enabled() {
         let utxos = json!(
             [
@@ -178,6 +196,8 @@ pub fn get_address_info(address: &str) -> VcxResult<AddressInfo> {
 }
 
 pub fn list_addresses() -> VcxResult<Vec<String>> {
+    ensure_payments_supported()?;
+
     if settings::indy_mocks_enabled() {
         let addresses = json!([
                 build_test_address("9UFgyjuJxi1i1HD"),
@@ -230,6 +250,8 @@ pub fn get_wallet_token_info() -> VcxResult<WalletInfo> {
 pub fn get_ledger_fees() -> VcxResult<String> {
     trace!("get_ledger_fees >>>");
 
+    ensure_payments_supported()?;
+
     if settings::indy_mocks_enabled() { return Ok(DEFAULT_FEES.to_string()); }
 
     let did = settings::get_config_value(settings::CONFIG_INSTITUTION_DID)?;
@@ -245,26 +267,103 @@ pub fn get_ledger_fees() -> VcxResult<String> {
 }
 
 pub fn pay_for_txn(req: &str, txn_action: (&str, &str, &str, Option<&str>, Option<&str>)) -> VcxResult<(Option<PaymentTxn>, String)> {
-    debug!("pay_for_txn(req: {}, txn_action: {:?})", req, txn_action);
+    let txn = write_txn(req, txn_action, false)?;
+    Ok((txn.payment, txn.response.unwrap_or_default()))
+}
+
+/// Signs `req`, attaches the fees of `txn_action` and submits it to the ledger.
+/// With `dry_run` the transaction is prepared the same way but returned without being submitted.
+pub fn write_txn(req: &str, txn_action: (&str, &str, &str, Option<&str>, Option<&str>), dry_run: bool) -> VcxResult<LedgerTxn> {
+    debug!("write_txn(req: {}, txn_action: {:?}, dry_run: {})", req, txn_action, dry_run);
+
     if settings::indy_mocks_enabled() {
+        if dry_run { return Ok(LedgerTxn::new(req.to_string(), 0, None, None)); }
+
         let inputs = vec!["pay:null:9UFgyjuJxi1i1HD".to_string()];
         let outputs = serde_json::from_str::<Vec<::utils::libindy::payments::Output>>(r#"[{"amount":1,"extra":null,"recipient":"pay:null:xkIsxem0YNtHrRO"}]"#).unwrap();
-        return Ok((Some(PaymentTxn::from_parts(inputs, outputs, 1, false)), SUBMIT_SCHEMA_RESPONSE.to_string()));
+        let payment = PaymentTxn::from_parts(inputs, outputs, 1, false);
+        return Ok(LedgerTxn::new(req.to_string(), 1, Some(payment), Some(SUBMIT_SCHEMA_RESPONSE.to_string())));
     }
 
+    let did = settings::get_config_value(settings::CONFIG_INSTITUTION_DID)?;
     let txn_price = get_action_price(txn_action, None)?;
-    if txn_price == 0 {
-        let did = settings::get_config_value(settings::CONFIG_INSTITUTION_DID)?;
-        let txn_response = libindy_sign_and_submit_request(&did, req)?;
-        Ok((None, txn_response))
+
+    let (request, payment_method, payment) = if txn_price == 0 {
+        (libindy_sign_request(&did, req)?, None, None)
     } else {
-        let (refund, inputs, refund_address) = inputs(txn_price)?;
+        let (refund, inputs, refund_address) = inputs(txn_price, Some(txn_action.0))?;
         let output = outputs(refund, &refund_address, None, None)?;
+        let (request, payment_method) = _add_request_fees(&did, req, &inputs, &output)?;
+        (request, Some(payment_method), Some(PaymentTxn::from_parts(inputs, output, txn_price, false)))
+    };
+
+    let response = if dry_run {
+        None
+    } else {
+        let response = _submit_signed_request(&request, payment_method.as_ref().map(String::as_str))
+            .map_err(|err| RequiredFees::on_rejection(err, txn_action.0, txn_price))?;
+        Some(response)
+    };
+
+    Ok(LedgerTxn::new(request, txn_price, payment, response))
+}
+
+/// Fees required for a ledger write which could not be paid, serialized as the message of `InsufficientTokenAmount` error.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct RequiredFees {
+    pub txn_type: String,
+    pub required: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl RequiredFees {
+    // other errors of the submission are passed through unchanged
+    fn on_rejection(err: VcxError, txn_type: &str, required: u64) -> VcxError {
+        if err.kind() != VcxErrorKind::InsufficientTokenAmount { return err; }
+
+        let reason = Fail::find_root_cause(&err).to_string();
+        RequiredFees { txn_type: txn_type.to_string(), required, balance: None, reason: Some(reason) }.into_error()
+    }
+
+    fn into_error(self) -> VcxError {
+        VcxError::from_msg(VcxErrorKind::InsufficientTokenAmount, json!(self).to_string())
+    }
+}
+
+// ledger rejects the write when the attached payment does not cover the fees,
+// or when the fees are required but none was attached
+fn _check_fees_rejection(response: &str) -> VcxResult<()> {
+    match ::serde_json::from_str::<Response>(response) {
+        Ok(Response::Reject(ref reject)) | Ok(Response::ReqNACK(ref reject)) => {
+            let reason = reject.reason.to_lowercase();
+            if reason.contains("insufficientfunds") || reason.contains("fees are required") {
+                return Err(VcxError::from_msg(VcxErrorKind::InsufficientTokenAmount, reject.reason.clone()));
+            }
+            Ok(())
+        }
+        _ => Ok(())
+    }
+}
+
+/// Ledger write signed with its fees attached, `response` is missing when it was a dry run.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct LedgerTxn {
+    pub request: Value,
+    pub price: u64,
+    pub payment: Option<PaymentTxn>,
+    #[serde(skip)]
+    pub response: Option<String>,
+}
 
-        let (_fee_response, txn_response) = _submit_fees_request(req, &inputs, &output)?;
+impl LedgerTxn {
+    fn new(request: String, price: u64, payment: Option<PaymentTxn>, response: Option<String>) -> LedgerTxn {
+        let request = ::serde_json::from_str(&request)
+            .unwrap_or_else(|_| Value::String(request));
 
-        let payment = PaymentTxn::from_parts(inputs, output, txn_price, false);
-        Ok((Some(payment), txn_response))
+        LedgerTxn { request, price, payment, response }
     }
 }
 
@@ -276,37 +375,42 @@ fn _serialize_inputs_and_outputs(inputs: &Vec<String>, outputs: &Vec<Output>) ->
     Ok((inputs, outputs))
 }
 
-fn _submit_fees_request(req: &str, inputs: &Vec<String>, outputs: &Vec<Output>) -> VcxResult<(String, String)> {
-    let did = settings::get_config_value(settings::CONFIG_INSTITUTION_DID)?;
-
+fn _add_request_fees(did: &str, req: &str, inputs: &Vec<String>, outputs: &Vec<Output>) -> VcxResult<(String, String)> {
     let (inputs, outputs) = _serialize_inputs_and_outputs(inputs, outputs)?;
 
-    let req = libindy_sign_request(&did, req)?;
+    let req = libindy_sign_request(did, req)?;
 
-    let (req, payment_method) =
-        payments::add_request_fees(get_wallet_handle(),
-                                   Some(&did),
-                                   &req,
-                                   &inputs,
-                                   &outputs,
-                                   None)
-            .wait()?;
+    payments::add_request_fees(get_wallet_handle(),
+                               Some(did),
+                               &req,
+                               &inputs,
+                               &outputs,
+                               None)
+        .wait()
+        .map_err(VcxError::from)
+}
 
-    let response = libindy_submit_request(&req)?;
+fn _submit_signed_request(req: &str, payment_method: Option<&str>) -> VcxResult<String> {
+    let response = libindy_submit_request(req)?;
+    _check_fees_rejection(&response)?;
 
-    let parsed_response = payments::parse_response_with_fees(&payment_method, &response)
-        .wait()
-        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidLedgerResponse, format!("Cannot parse response: {}", err)))?;
+    if let Some(payment_method) = payment_method {
+        payments::parse_response_with_fees(payment_method, &response)
+            .wait()
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidLedgerResponse, format!("Cannot parse response: {}", err)))?;
+    }
 
-    Ok((parsed_response, response))
+    Ok(response)
 }
 
 pub fn pay_a_payee(price: u64, address: &str) -> VcxResult<(PaymentTxn, String)> {
     trace!("pay_a_payee >>> price: {}, address {}", price, address);
     debug!("sending {} tokens to address {}", price, address);
 
+    ensure_payments_supported()?;
+
     let ledger_cost = get_action_price(CREATE_TRANSFER_ACTION, None)?;
-    let (remainder, input, refund_address) = inputs(price + ledger_cost)?;
+    let (remainder, input, refund_address) = inputs(price + ledger_cost, None)?;
     let outputs = outputs(remainder, &refund_address, Some(address.to_string()), Some(price))?;
 
     let my_did = settings::get_config_value(settings::CONFIG_INSTITUTION_DID)?;
@@ -357,6 +461,8 @@ fn get_request_info(get_auth_rule_resp_json: &str, requester_info_json: &str, fe
 }
 
 pub fn get_request_price(action_json: String, requester_info_json: Option<String>) -> VcxResult<u64> {
+    ensure_payments_supported()?;
+
     let action: auth_rule::Action = ::serde_json::from_str(&action_json)
         .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize Action: {:?}", err)))?;
 
@@ -368,7 +474,10 @@ pub fn get_request_price(action_json: String, requester_info_json: Option<String
                      requester_info_json)
 }
 
-fn get_action_price(action: (&str, &str, &str, Option<&str>, Option<&str>), requester_info_json: Option<String>) -> VcxResult<u64> {
+pub fn get_action_price(action: (&str, &str, &str, Option<&str>, Option<&str>), requester_info_json: Option<String>) -> VcxResult<u64> {
+    // without a payment plugin the fees cannot be paid, so the transactions are sent as they are
+    if !cfg!(feature = "payments") { return Ok(0); }
+
     let get_auth_rule_resp = match auth_rule::get_action_auth_rule(action) {
         // TODO: Huck to save backward compatibility
         Ok(resp) => resp,
@@ -413,18 +522,26 @@ fn _address_balance(address: &Vec<UTXO>) -> u64 {
     address.iter().fold(0, |balance, utxo| balance + utxo.amount)
 }
 
-pub fn inputs(cost: u64) -> VcxResult<(u64, Vec<String>, String)> {
-    let mut inputs: Vec<String> = Vec::new();
-    let mut balance = 0;
-    let mut refund_address = String::new();
-
+/// Selects inputs paying `cost`, payment for a ledger write of `txn_type` fails with `RequiredFees` as the error message.
+pub fn inputs(cost: u64, txn_type: Option<&str>) -> VcxResult<(u64, Vec<String>, String)> {
     let wallet_info: WalletInfo = get_wallet_token_info()?;
 
     if wallet_info.balance < cost {
-        warn!("not enough tokens in wallet to pay: balance: {}, cost: {}", wallet_info.balance, cost);
-        return Err(VcxError::from_msg(VcxErrorKind::InsufficientTokenAmount, format!("Not enough tokens in wallet to pay: balance: {}, cost: {}", wallet_info.balance, cost)));
+        warn!("not enough tokens in wallet to pay {:?}: balance: {}, cost: {}", txn_type, wallet_info.balance, cost);
+        return Err(match txn_type {
+            Some(txn_type) => RequiredFees { txn_type: txn_type.to_string(), required: cost, balance: Some(wallet_info.balance), reason: None }.into_error(),
+            None => VcxError::from_msg(VcxErrorKind::InsufficientTokenAmount, format!("Not enough tokens in wallet to pay: balance: {}, cost: {}", wallet_info.balance, cost))
+        });
     }
 
+    _select_inputs(&wallet_info, cost)
+}
+
+fn _select_inputs(wallet_info: &WalletInfo, cost: u64) -> VcxResult<(u64, Vec<String>, String)> {
+    let mut inputs: Vec<String> = Vec::new();
+    let mut balance = 0;
+    let mut refund_address = String::new();
+
     // Todo: explore 'smarter' ways of selecting utxos ie bitcoin algorithms etc
     'outer: for address in wallet_info.addresses.iter() {
         refund_address = address.address.clone();
@@ -551,6 +668,15 @@ pub mod tests {
         mint_tokens_and_set_fees(number_of_addresses, tokens_per_address, Some(fees.to_string()), None).unwrap();
     }
 
+    #[allow(dead_code)]
+    fn _submit_fees_request(req: &str, inputs: &Vec<String>, outputs: &Vec<Output>) -> VcxResult<String> {
+        let did = settings::get_config_value(settings::CONFIG_INSTITUTION_DID)?;
+
+        let (req, payment_method) = _add_request_fees(&did, req, inputs, outputs)?;
+
+        _submit_signed_request(&req, Some(&payment_method))
+    }
+
     #[allow(dead_code)]
     fn get_my_balance() -> u64 {
         let info: WalletInfo = get_wallet_token_info().unwrap();
@@ -565,6 +691,17 @@ pub mod tests {
         create_address(None).unwrap();
     }
 
+    #[test]
+    #[cfg(all(feature = "general_test", not(feature = "payments")))]
+    fn test_token_operations_fail_without_payments_feature() {
+        let _setup = SetupAriesMocks::init();
+
+        assert_eq!(create_address(None).unwrap_err().kind(), VcxErrorKind::ActionNotSupported);
+        assert_eq!(get_wallet_token_info().unwrap_err().kind(), VcxErrorKind::ActionNotSupported);
+        assert_eq!(get_ledger_fees().unwrap_err().kind(), VcxErrorKind::ActionNotSupported);
+        assert_eq!(get_action_price(CREATE_TRANSFER_ACTION, None).unwrap(), 0);
+    }
+
 
     #[test]
     #[cfg(feature = "general_test")]
@@ -701,18 +838,18 @@ pub mod tests {
 
         // Success - Exact amount
         let expected_inputs = vec![pay_addr_1.clone(), pay_addr_2.clone(), pay_addr_1.clone(), pay_addr_2.clone()];
-        assert_eq!(inputs(6).unwrap(), (0, expected_inputs, build_test_address("zR3GN9lfbCVtHjp")));
+        assert_eq!(inputs(6, None).unwrap(), (0, expected_inputs, build_test_address("zR3GN9lfbCVtHjp")));
 
         // Success - utxo with remainder tokens
         let expected_inputs = vec![pay_addr_1.clone(), pay_addr_2.clone(), pay_addr_1.clone(), pay_addr_2.clone()];
-        assert_eq!(inputs(5).unwrap(), (1, expected_inputs, build_test_address("zR3GN9lfbCVtHjp")));
+        assert_eq!(inputs(5, None).unwrap(), (1, expected_inputs, build_test_address("zR3GN9lfbCVtHjp")));
 
         // Success - requesting amount that partial address (1 of 2 utxos) can satisfy
         let expected_inputs = vec![pay_addr_1.clone()];
-        assert_eq!(inputs(1).unwrap(), (0, expected_inputs, build_test_address("9UFgyjuJxi1i1HD")));
+        assert_eq!(inputs(1, None).unwrap(), (0, expected_inputs, build_test_address("9UFgyjuJxi1i1HD")));
 
         // Err - request more than wallet contains
-        assert_eq!(inputs(7).err().unwrap().kind(), VcxErrorKind::InsufficientTokenAmount);
+        assert_eq!(inputs(7, None).err().unwrap().kind(), VcxErrorKind::InsufficientTokenAmount);
     }
 
     #[test]
@@ -721,14 +858,14 @@ pub mod tests {
         let _setup = SetupAriesMocks::init();
 
         let mut cost = 5;
-        let (remainder, _, refund_address) = inputs(cost).unwrap();
+        let (remainder, _, refund_address) = inputs(cost, None).unwrap();
         let mut expected_output: Vec<Output> = ::serde_json::from_str(&format!(r#"[{{"amount":1,"recipient":"{}"}}]"#, refund_address)).unwrap();
         assert_eq!(outputs(remainder, &refund_address, None, None).unwrap(), expected_output);
 
         // No remainder so don't create an address in outputs
         cost = 6;
         expected_output = vec![];
-        let (remainder, _, refund_address) = inputs(cost).unwrap();
+        let (remainder, _, refund_address) = inputs(cost, None).unwrap();
         assert_eq!(remainder, 0);
         assert_eq!(outputs(remainder, &refund_address, None, None).unwrap(), expected_output);
     }
@@ -768,6 +905,29 @@ pub mod tests {
         assert_eq!(response, SUBMIT_SCHEMA_RESPONSE.to_string());
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_fees_rejection_is_reported_with_required_fees() {
+        let _setup = SetupAriesMocks::init();
+
+        let response = r#"{"op":"REJECT","reqId":1,"reason":"client request invalid: InsufficientFundsError(2, 5)"}"#;
+        let err = _check_fees_rejection(response)
+            .map_err(|err| RequiredFees::on_rejection(err, ::utils::constants::SCHEMA_TXN_TYPE, 5))
+            .unwrap_err();
+        assert_eq!(err.kind(), VcxErrorKind::InsufficientTokenAmount);
+
+        let required: RequiredFees = ::serde_json::from_str(&Fail::find_root_cause(&err).to_string()).unwrap();
+        assert_eq!(required.txn_type, ::utils::constants::SCHEMA_TXN_TYPE);
+        assert_eq!(required.required, 5);
+        assert!(required.reason.unwrap().contains("InsufficientFundsError"));
+
+        let response = r#"{"op":"REJECT","reqId":1,"reason":"client request invalid: UnauthorizedClientRequest"}"#;
+        _check_fees_rejection(response).unwrap();
+
+        let err = RequiredFees::on_rejection(VcxError::from(VcxErrorKind::PoolLedgerConnect), ::utils::constants::SCHEMA_TXN_TYPE, 5);
+        assert_eq!(err.kind(), VcxErrorKind::PoolLedgerConnect);
+    }
+
     #[cfg(feature = "pool_tests")]
     #[test]
     fn test_pay_for_txn_real() {
@@ -881,7 +1041,7 @@ pub mod tests {
 
         let (_, schema_json) = ::utils::libindy::anoncreds::tests::create_schema(::utils::constants::DEFAULT_SCHEMA_ATTRS);
         let req = ::utils::libindy::anoncreds::tests::create_schema_req(&schema_json);
-        let (remainder, inputs, refund_address) = inputs(2).unwrap();
+        let (remainder, inputs, refund_address) = inputs(2, None).unwrap();
         let output = outputs(remainder, &refund_address, None, None).unwrap();
         let start_wallet = get_wallet_token_info().unwrap();
 
@@ -904,7 +1064,7 @@ pub mod tests {
         let cost = get_action_price(::utils::constants::CREATE_SCHEMA_ACTION, None).unwrap();
         let start_wallet = get_wallet_token_info().unwrap();
         let remaining_balance = start_wallet.balance - cost;
-        let (remainder, inputs, refund_address) = inputs(cost).unwrap();
+        let (remainder, inputs, refund_address) = inputs(cost, None).unwrap();
         assert_eq!(remainder, remaining_balance);
 
         let output = outputs(remainder, &refund_address, None, None).unwrap();
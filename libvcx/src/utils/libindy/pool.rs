@@ -1,13 +1,34 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::sync::RwLock;
+use std::thread;
 
 use futures::Future;
 use indy::{ErrorCode, pool};
+use serde_json;
 
 use error::prelude::*;
 use settings;
+use utils::agent_context;
+use utils::metadata::{self, Metadata};
+use utils::object_cache::ScopeGuard;
 
 lazy_static! {
     static ref POOL_HANDLE: RwLock<Option<i32>> = RwLock::new(None);
+    static ref NETWORKS: RwLock<HashMap<String, i32>> = Default::default();
+}
+
+thread_local! {
+    static NETWORK_SCOPE: RefCell<Vec<Option<String>>> = RefCell::new(Vec::new());
+}
+
+/// Ledger network opened next to the default pool, configured by the `networks` config option.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NetworkConfig {
+    pub name: String,
+    pub genesis_path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_config: Option<serde_json::Value>,
 }
 
 pub fn set_pool_handle(handle: Option<i32>) {
@@ -15,12 +36,90 @@ pub fn set_pool_handle(handle: Option<i32>) {
     *h = handle;
 }
 
+/// Handle of the network selected by `enter_network`, the default pool is used when no network is selected.
 pub fn get_pool_handle() -> VcxResult<i32> {
+    match current_network() {
+        Some(name) => get_network_handle(&name),
+        None => get_default_pool_handle()
+    }
+}
+
+pub fn get_default_pool_handle() -> VcxResult<i32> {
     POOL_HANDLE.read()
         .or(Err(VcxError::from_msg(VcxErrorKind::NoPoolOpen, "There is no pool opened")))?
         .ok_or(VcxError::from_msg(VcxErrorKind::NoPoolOpen, "There is no pool opened"))
 }
 
+pub fn get_network_handle(name: &str) -> VcxResult<i32> {
+    NETWORKS.read().unwrap()
+        .get(name)
+        .cloned()
+        .ok_or(VcxError::from_msg(VcxErrorKind::NoPoolOpen, format!("Network \"{}\" is not opened", name)))
+}
+
+pub fn network_names() -> Vec<String> {
+    let mut names: Vec<String> = NETWORKS.read().unwrap().keys().cloned().collect();
+    names.sort();
+    names
+}
+
+///
+/// Network recorded in a qualified identifier (`did:indy:sovrin:staging:...`, `creddef:indy:did:indy:sovrin:...`),
+/// the longest namespace matching an opened network wins. Fails if no opened network matches the `did:indy` namespace.
+/// Unqualified identifiers and identifiers of other DID methods have no network recorded.
+///
+pub fn network_for_id(id: &str) -> VcxResult<Option<String>> {
+    let position = match id.find("did:indy:") {
+        Some(position) => position,
+        None => return Ok(None)
+    };
+    let parts: Vec<&str> = id[position + "did:indy:".len()..].split(':').collect();
+
+    let networks = NETWORKS.read().unwrap();
+    (1..parts.len()).rev()
+        .map(|end| parts[..end].join(":"))
+        .find(|namespace| networks.contains_key(namespace))
+        .map(Some)
+        .ok_or(VcxError::from_msg(VcxErrorKind::NoPoolOpen, format!("Network of identifier \"{}\" is not opened", id)))
+}
+
+/// Network requests about `id` go to, the network already selected is used for unqualified identifiers.
+pub fn resolve_network(id: &str) -> VcxResult<Option<String>> {
+    Ok(network_for_id(id)?.or_else(current_network))
+}
+
+/// Leaves the network scope when dropped.
+pub struct NetworkGuard(());
+
+impl Drop for NetworkGuard {
+    fn drop(&mut self) {
+        NETWORK_SCOPE.with(|scopes| { scopes.borrow_mut().pop(); });
+    }
+}
+
+/// Sends ledger requests of the current thread to `network` until the returned guard is dropped, `None` selects the default pool.
+pub fn enter_network(network: Option<String>) -> NetworkGuard {
+    NETWORK_SCOPE.with(|scopes| scopes.borrow_mut().push(network));
+    NetworkGuard(())
+}
+
+/// Selects the network recorded in `id` for the current thread, see `network_for_id`.
+/// Unqualified identifiers stay on the network already selected, e.g. the network of the object being processed.
+pub fn enter_network_of(id: &str) -> VcxResult<NetworkGuard> {
+    resolve_network(id).map(enter_network)
+}
+
+/// Selects the network of an object by its `metadata::NETWORK` entry, see `ObjectCache::with_scope`.
+pub fn enter_object_network(metadata: &Metadata) -> Option<ScopeGuard> {
+    metadata.get(metadata::NETWORK)
+        .map(|network| Box::new(enter_network(Some(network.clone()))) as ScopeGuard)
+}
+
+/// Network selected for the current thread, `None` is the default pool.
+pub fn current_network() -> Option<String> {
+    NETWORK_SCOPE.with(|scopes| scopes.borrow().last().cloned().unwrap_or(None))
+}
+
 pub fn is_pool_open() -> bool {
     get_pool_handle().is_ok()
 }
@@ -55,9 +154,16 @@ pub fn create_pool_ledger_config(pool_name: &str, path: &str) -> VcxResult<()> {
 }
 
 pub fn open_pool_ledger(pool_name: &str, config: Option<&str>) -> VcxResult<u32> {
+    let handle = _open_pool_ledger(pool_name, config)?;
+
+    set_pool_handle(Some(handle));
+    Ok(handle as u32)
+}
+
+fn _open_pool_ledger(pool_name: &str, config: Option<&str>) -> VcxResult<i32> {
     set_protocol_version()?;
 
-    let handle = pool::open_pool_ledger(pool_name, config)
+    pool::open_pool_ledger(pool_name, config)
         .wait()
         .map_err(|err|
             match err.error_code.clone() {
@@ -81,10 +187,7 @@ pub fn open_pool_ledger(pool_name: &str, config: Option<&str>) -> VcxResult<u32>
                 error_code => {
                     err.to_vcx(VcxErrorKind::LibndyError(error_code as u32), "Indy error occurred")
                 }
-            })?;
-
-    set_pool_handle(Some(handle));
-    Ok(handle as u32)
+            })
 }
 
 pub fn init_pool(pool_name: &str, path: &str, pool_config: Option<&str>) -> VcxResult<()> {
@@ -107,9 +210,123 @@ pub fn init_pool(pool_name: &str, path: &str, pool_config: Option<&str>) -> VcxR
     Ok(())
 }
 
+/// Opens ledger network `name` next to the default pool.
+pub fn add_network(name: &str, genesis_path: &str, pool_config: Option<&str>) -> VcxResult<()> {
+    info!("add_network >>> name={}, genesis_path={}, pool_config={:?}", name, genesis_path, pool_config);
+
+    if NETWORKS.read().unwrap().contains_key(name) {
+        return Err(_already_opened(name));
+    }
+
+    let handle = if settings::indy_mocks_enabled() {
+        0
+    } else {
+        create_pool_ledger_config(name, genesis_path)
+            .map_err(|err| err.extend(format!("Can not create Pool Ledger Config of network \"{}\"", name)))?;
+
+        _open_pool_ledger(name, pool_config)
+            .map_err(|err| err.extend(format!("Can not open network \"{}\"", name)))?
+    };
+
+    // the network is not locked while it is being opened, so concurrent call could have registered it meanwhile
+    {
+        let mut networks = NETWORKS.write().unwrap();
+        if !networks.contains_key(name) {
+            networks.insert(name.to_string(), handle);
+            info!("add_network ::: Network {} Opened Successfully", name);
+            return Ok(());
+        }
+    }
+
+    if !settings::indy_mocks_enabled() {
+        pool::close_pool_ledger(handle).wait()
+            .unwrap_or_else(|err| warn!("add_network >>> cannot close duplicate handle of network {}: {}", name, err));
+    }
+    Err(_already_opened(name))
+}
+
+fn _already_opened(name: &str) -> VcxError {
+    VcxError::from_msg(VcxErrorKind::AlreadyInitialized, format!("Network \"{}\" is already opened", name))
+}
+
+/// Opens all `networks` in parallel, the first failure is returned after all attempts finish.
+/// When any network fails to open, networks opened by this call are closed again.
+pub fn add_networks(networks: Vec<NetworkConfig>) -> VcxResult<()> {
+    let context = agent_context::current();
+
+    let opening: Vec<_> = networks.into_iter()
+        .map(|network| {
+            let context = context.clone();
+            let name = network.name.clone();
+            let thread = thread::spawn(move || {
+                let pool_config = network.pool_config.as_ref().map(|config| config.to_string());
+                let open = || add_network(&network.name, &network.genesis_path, pool_config.as_ref().map(String::as_str));
+                match context {
+                    Some(ref context) => agent_context::with_context(context, open),
+                    None => open()
+                }
+            });
+            (name, thread)
+        })
+        .collect();
+
+    let results: Vec<(String, VcxResult<()>)> = opening.into_iter()
+        .map(|(name, thread)| {
+            let opened = thread.join()
+                .unwrap_or(Err(VcxError::from_msg(VcxErrorKind::PoolLedgerConnect, "Opening of network panicked")));
+            (name, opened)
+        })
+        .collect();
+
+    if results.iter().all(|(_, opened)| opened.is_ok()) {
+        return Ok(());
+    }
+
+    for (name, opened) in results.iter() {
+        if opened.is_ok() {
+            close_network(name)
+                .unwrap_or_else(|err| warn!("add_networks >>> cannot close network {}: {}", name, err));
+        }
+    }
+
+    results.into_iter()
+        .map(|(_, opened)| opened)
+        .fold(Ok(()), |result, opened| result.and(opened))
+}
+
+/// Opens networks listed in the `networks` config option.
+pub fn init_networks() -> VcxResult<()> {
+    match settings::get_config_value(settings::CONFIG_NETWORKS) {
+        Ok(networks) => {
+            let networks: Vec<NetworkConfig> = serde_json::from_str(&networks)
+                .to_vcx(VcxErrorKind::InvalidConfiguration, format!("Cannot deserialize {}", settings::CONFIG_NETWORKS))?;
+            add_networks(networks)
+        }
+        Err(_) => Ok(())
+    }
+}
+
+pub fn close_network(name: &str) -> VcxResult<()> {
+    let handle = NETWORKS.write().unwrap()
+        .remove(name)
+        .ok_or(VcxError::from_msg(VcxErrorKind::NoPoolOpen, format!("Network \"{}\" is not opened", name)))?;
+
+    if settings::indy_mocks_enabled() { return Ok(()); }
+
+    pool::close_pool_ledger(handle).wait()?;
+
+    Ok(())
+}
+
+pub fn close_networks() {
+    for name in network_names() {
+        close_network(&name)
+            .unwrap_or_else(|err| warn!("close_networks >>> cannot close network {}: {}", name, err));
+    }
+}
 
 pub fn close() -> VcxResult<()> {
-    let handle = get_pool_handle()?;
+    let handle = get_default_pool_handle()?;
 
     //TODO there was timeout here (before future-based Rust wrapper)
     pool::close_pool_ledger(handle).wait()?;
@@ -143,6 +360,7 @@ pub mod tests {
     };
     #[cfg(feature = "pool_tests")]
     use utils::devsetup::SetupLibraryWalletPoolZeroFees;
+    use utils::devsetup::SetupMocks;
 
     use super::*;
 
@@ -181,6 +399,60 @@ pub mod tests {
         file_path
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_networks_are_resolved_by_identifiers() {
+        let _setup = SetupMocks::init();
+
+        add_networks(vec![
+            NetworkConfig { name: "sovrin".to_string(), genesis_path: "sovrin.txn".to_string(), pool_config: None },
+            NetworkConfig { name: "sovrin:staging".to_string(), genesis_path: "staging.txn".to_string(), pool_config: None },
+        ]).unwrap();
+        assert_eq!(add_network("sovrin", "sovrin.txn", None).unwrap_err().kind(), VcxErrorKind::AlreadyInitialized);
+
+        assert_eq!(network_for_id("did:indy:sovrin:V4SGRU86Z58d6TV7PBUe6f").unwrap(), Some("sovrin".to_string()));
+        assert_eq!(network_for_id("did:indy:sovrin:staging:V4SGRU86Z58d6TV7PBUe6f").unwrap(), Some("sovrin:staging".to_string()));
+        assert_eq!(network_for_id("schema:indy:did:indy:sovrin:staging:V4SGRU86Z58d6TV7PBUe6f:2:name:1.0").unwrap(), Some("sovrin:staging".to_string()));
+        assert_eq!(network_for_id("did:indy:bcovrin:V4SGRU86Z58d6TV7PBUe6f").unwrap_err().kind(), VcxErrorKind::NoPoolOpen);
+        assert_eq!(network_for_id("did:sov:V4SGRU86Z58d6TV7PBUe6f").unwrap(), None);
+        assert_eq!(network_for_id("V4SGRU86Z58d6TV7PBUe6f:2:name:1.0").unwrap(), None);
+        assert!(enter_network_of("did:indy:bcovrin:V4SGRU86Z58d6TV7PBUe6f").is_err());
+        assert_eq!(current_network(), None);
+
+        {
+            let _network = enter_network_of("did:indy:sovrin:staging:V4SGRU86Z58d6TV7PBUe6f").unwrap();
+            assert_eq!(current_network(), Some("sovrin:staging".to_string()));
+            {
+                let _default = enter_network(None);
+                assert_eq!(get_pool_handle().unwrap_err().kind(), VcxErrorKind::NoPoolOpen);
+            }
+            assert_eq!(get_pool_handle().unwrap(), 0);
+        }
+        assert_eq!(current_network(), None);
+
+        close_network("sovrin").unwrap();
+        close_network("sovrin:staging").unwrap();
+        assert_eq!(network_for_id("did:indy:sovrin:V4SGRU86Z58d6TV7PBUe6f").unwrap_err().kind(), VcxErrorKind::NoPoolOpen);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_add_networks_closes_opened_networks_on_failure() {
+        let _setup = SetupMocks::init();
+
+        add_network("rollback:existing", "existing.txn", None).unwrap();
+
+        let err = add_networks(vec![
+            NetworkConfig { name: "rollback:new".to_string(), genesis_path: "new.txn".to_string(), pool_config: None },
+            NetworkConfig { name: "rollback:existing".to_string(), genesis_path: "existing.txn".to_string(), pool_config: None },
+        ]).unwrap_err();
+        assert_eq!(err.kind(), VcxErrorKind::AlreadyInitialized);
+        assert!(get_network_handle("rollback:new").is_err());
+        assert!(get_network_handle("rollback:existing").is_ok());
+
+        close_network("rollback:existing").unwrap();
+    }
+
     #[cfg(feature = "pool_tests")]
     #[test]
     fn test_open_close_pool() {
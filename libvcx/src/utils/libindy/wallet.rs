@@ -1,9 +1,28 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
 use futures::Future;
 use indy::{ErrorCode, wallet};
 use indy::{INVALID_WALLET_HANDLE, SearchHandle, WalletHandle};
 
+use serde_json::{Map, Value};
+
 use error::prelude::*;
+use outbound_queue;
 use settings;
+use utils::agent_context;
+use utils::get_temp_dir_path;
+use utils::key_provider;
+use utils::plugins;
+use utils::secret::SecretString;
+use utils::wql;
+
+lazy_static! {
+    // tags to be returned by searches opened with `tagNames` option
+    static ref SEARCH_TAG_NAMES: Mutex<HashMap<SearchHandle, Vec<String>>> = Default::default();
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct WalletRecord {
@@ -29,9 +48,11 @@ impl WalletRecord {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RestoreWalletConfigs {
     pub wallet_name: String,
-    pub wallet_key: String,
+    // may be omitted when the key is supplied by key provider
+    #[serde(default)]
+    pub wallet_key: SecretString,
     pub exported_wallet_path: String,
-    pub backup_key: String,
+    pub backup_key: SecretString,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub key_derivation: Option<String>,
 }
@@ -51,28 +72,68 @@ impl RestoreWalletConfigs {
 pub static mut WALLET_HANDLE: WalletHandle = INVALID_WALLET_HANDLE;
 
 pub fn set_wallet_handle(handle: WalletHandle) -> WalletHandle {
+    if let Some(context) = agent_context::current() {
+        *context.wallet_handle.lock().unwrap() = handle;
+        return handle;
+    }
     unsafe { WALLET_HANDLE = handle; }
     unsafe { WALLET_HANDLE }
 }
 
-pub fn get_wallet_handle() -> WalletHandle { unsafe { WALLET_HANDLE } }
+pub fn get_wallet_handle() -> WalletHandle {
+    match agent_context::current() {
+        Some(context) => *context.wallet_handle.lock().unwrap(),
+        None => unsafe { WALLET_HANDLE }
+    }
+}
 
 pub fn reset_wallet_handle() { set_wallet_handle(INVALID_WALLET_HANDLE); }
 
+/// Loads storage plugin library and calls its `init_fn`, which registers the storage type
+/// (e.g. `postgres_storage`) to be used as `wallet_type` together with `storage_config` and `storage_credentials`.
+pub fn register_storage_library(path: &str, init_fn: &str) -> VcxResult<()> {
+    trace!("register_storage_library >>> path: {}, init_fn: {}", path, init_fn);
+
+    if settings::indy_mocks_enabled() {
+        return Ok(());
+    }
+
+    plugins::init_storage_plugin(path, init_fn)
+}
+
+fn register_configured_storage_library() -> VcxResult<()> {
+    let settings = settings::snapshot();
+    match settings.get_value(settings::CONFIG_WALLET_STORAGE_LIBRARY) {
+        Ok(path) => {
+            let init_fn = settings.get_value(settings::CONFIG_WALLET_STORAGE_INIT_FUNCTION)
+                .map_err(|_| VcxError::from_msg(VcxErrorKind::InvalidConfiguration,
+                                                format!("{} has to be set together with {}", settings::CONFIG_WALLET_STORAGE_INIT_FUNCTION, settings::CONFIG_WALLET_STORAGE_LIBRARY)))?;
+            register_storage_library(&path, &init_fn)
+        }
+        Err(_) => Ok(())
+    }
+}
+
 pub fn create_wallet(wallet_name: &str, wallet_type: Option<&str>, storage_config: Option<&str>, storage_creds: Option<&str>) -> VcxResult<()> {
+    create_wallet_if_missing(wallet_name, wallet_type, storage_config, storage_creds).map(|_| ())
+}
+
+/// Creates the wallet unless it exists already, tells whether it was created.
+pub fn create_wallet_if_missing(wallet_name: &str, wallet_type: Option<&str>, storage_config: Option<&str>, storage_creds: Option<&str>) -> VcxResult<bool> {
     trace!("creating wallet: {}", wallet_name);
 
-    let config = settings::get_wallet_config(wallet_name, wallet_type, storage_config);
-    let credentials = settings::get_wallet_credentials(storage_creds);
+    register_configured_storage_library()?;
+    let config = settings::get_wallet_config(wallet_name, wallet_type, storage_config)?;
+    let credentials = settings::get_wallet_credentials(storage_creds)?;
 
-    match wallet::create_wallet(&config, &credentials)
+    match wallet::create_wallet(&config, credentials.expose())
         .wait() {
-        Ok(()) => Ok(()),
+        Ok(()) => Ok(true),
         Err(err) => {
             match err.error_code.clone() {
                 ErrorCode::WalletAlreadyExistsError => {
                     warn!("wallet \"{}\" already exists. skipping creation", wallet_name);
-                    Ok(())
+                    Ok(false)
                 }
                 _ => {
                     warn!("could not create wallet {}: {:?}", wallet_name, err.message);
@@ -89,10 +150,11 @@ pub fn open_wallet(wallet_name: &str, wallet_type: Option<&str>, storage_config:
         return Ok(set_wallet_handle(WalletHandle(1)));
     }
 
-    let config = settings::get_wallet_config(wallet_name, wallet_type, storage_config);
-    let credentials = settings::get_wallet_credentials(storage_creds);
+    register_configured_storage_library()?;
+    let config = settings::get_wallet_config(wallet_name, wallet_type, storage_config)?;
+    let credentials = settings::get_wallet_credentials(storage_creds)?;
 
-    let handle = wallet::open_wallet(&config, &credentials)
+    let handle = wallet::open_wallet(&config, credentials.expose())
         .wait()
         .map_err(|err|
             match err.error_code.clone() {
@@ -135,9 +197,11 @@ pub fn close_wallet() -> VcxResult<()> {
         return Ok(());
     }
 
-    wallet::close_wallet(get_wallet_handle())
+    let wallet_handle = get_wallet_handle();
+    wallet::close_wallet(wallet_handle)
         .wait()?;
 
+    outbound_queue::forget_wallet(wallet_handle);
     reset_wallet_handle();
     Ok(())
 }
@@ -147,10 +211,11 @@ pub fn delete_wallet(wallet_name: &str, wallet_type: Option<&str>, storage_confi
 
     close_wallet().ok();
 
-    let config = settings::get_wallet_config(wallet_name, wallet_type, storage_config);
-    let credentials = settings::get_wallet_credentials(storage_creds);
+    register_configured_storage_library()?;
+    let config = settings::get_wallet_config(wallet_name, wallet_type, storage_config)?;
+    let credentials = settings::get_wallet_credentials(storage_creds)?;
 
-    wallet::delete_wallet(&config, &credentials)
+    wallet::delete_wallet(&config, credentials.expose())
         .wait()
         .map_err(|err|
             match err.error_code.clone() {
@@ -170,6 +235,56 @@ pub fn delete_wallet(wallet_name: &str, wallet_type: Option<&str>, storage_confi
     Ok(())
 }
 
+/// Throwaway wallet kept in its own temporary directory, everything created in it
+/// (including files written into `dir`) is deleted when it is dropped.
+pub struct ScratchWallet {
+    pub handle: WalletHandle,
+    pub dir: PathBuf,
+    config: String,
+    credentials: String,
+}
+
+impl ScratchWallet {
+    pub fn create() -> VcxResult<ScratchWallet> {
+        let dir = get_temp_dir_path(&format!("vcx_scratch_{}", ::utils::uuid::uuid()));
+
+        if settings::indy_mocks_enabled() {
+            return Ok(ScratchWallet { handle: WalletHandle(1), dir, config: String::new(), credentials: String::new() });
+        }
+
+        let config = json!({
+            "id": ::utils::uuid::uuid(),
+            "storage_config": {"path": dir.to_string_lossy()}
+        }).to_string();
+        let key = wallet::generate_wallet_key(None).wait()?;
+        let credentials = json!({"key": key, "key_derivation_method": "RAW"}).to_string();
+
+        wallet::create_wallet(&config, &credentials).wait()
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::WalletCreate, format!("Cannot create scratch wallet: {:?}", err.message)))?;
+
+        let mut scratch = ScratchWallet { handle: INVALID_WALLET_HANDLE, dir, config, credentials };
+        scratch.handle = wallet::open_wallet(&scratch.config, &scratch.credentials).wait()?;
+        Ok(scratch)
+    }
+}
+
+impl Drop for ScratchWallet {
+    fn drop(&mut self) {
+        if self.handle != INVALID_WALLET_HANDLE && !settings::indy_mocks_enabled() {
+            wallet::close_wallet(self.handle).wait()
+                .unwrap_or_else(|err| warn!("Cannot close scratch wallet: {:?}", err));
+        }
+        if !self.config.is_empty() {
+            wallet::delete_wallet(&self.config, &self.credentials).wait()
+                .unwrap_or_else(|err| warn!("Cannot delete scratch wallet: {:?}", err));
+        }
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)
+                .unwrap_or_else(|err| warn!("Cannot remove scratch wallet directory {:?}: {}", self.dir, err));
+        }
+    }
+}
+
 pub fn add_record(xtype: &str, id: &str, value: &str, tags: Option<&str>) -> VcxResult<()> {
     trace!("add_record >>> xtype: {}, id: {}, value: {}, tags: {:?}", secret!(&xtype), secret!(&id), secret!(&value), secret!(&tags));
 
@@ -249,16 +364,46 @@ pub fn delete_record_tags(xtype: &str, id: &str, tag_names: &str) -> VcxResult<(
         .map_err(VcxError::from)
 }
 
+/// Opens search of records of type `xtype` matching WQL `query`.
+/// Besides indy search options, `options` may contain `tagNames` to return only listed tags (implies `retrieveTags`).
 pub fn open_search(xtype: &str, query: &str, options: &str) -> VcxResult<SearchHandle> {
     trace!("open_search >>> xtype: {}, query: {}, options: {}", secret!(&xtype), query, options);
 
+    let wql: Value = serde_json::from_str(query)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize WQL query: {:?}", err)))?;
+    wql::validate(&wql)?;
+
+    let (options, tag_names) = _split_search_options(options)?;
+
     if settings::indy_mocks_enabled() {
         return Ok(1);
     }
 
-    wallet::open_wallet_search(get_wallet_handle(), xtype, query, options)
+    let search_handle = wallet::open_wallet_search(get_wallet_handle(), xtype, query, &options)
         .wait()
-        .map_err(VcxError::from)
+        .map_err(VcxError::from)?;
+
+    if let Some(tag_names) = tag_names {
+        SEARCH_TAG_NAMES.lock().unwrap().insert(search_handle, tag_names);
+    }
+    Ok(search_handle)
+}
+
+fn _split_search_options(options: &str) -> VcxResult<(String, Option<Vec<String>>)> {
+    let mut options: Map<String, Value> = serde_json::from_str(options)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize search options: {:?}", err)))?;
+
+    let tag_names = match options.remove("tagNames") {
+        Some(tag_names) => {
+            let tag_names: Vec<String> = serde_json::from_value(tag_names)
+                .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Invalid search option `tagNames`: {:?}", err)))?;
+            options.insert("retrieveTags".to_string(), Value::Bool(true));
+            Some(tag_names)
+        }
+        None => None
+    };
+
+    Ok((Value::Object(options).to_string(), tag_names))
 }
 
 pub fn fetch_next_records(search_handle: SearchHandle, count: usize) -> VcxResult<String> {
@@ -268,9 +413,33 @@ pub fn fetch_next_records(search_handle: SearchHandle, count: usize) -> VcxResul
         return Ok(String::from("{}"));
     }
 
-    wallet::fetch_wallet_search_next_records(get_wallet_handle(), search_handle, count)
+    let records = wallet::fetch_wallet_search_next_records(get_wallet_handle(), search_handle, count)
         .wait()
-        .map_err(VcxError::from)
+        .map_err(VcxError::from)?;
+
+    match SEARCH_TAG_NAMES.lock().unwrap().get(&search_handle) {
+        Some(tag_names) => _project_tags(&records, tag_names),
+        None => Ok(records)
+    }
+}
+
+fn _project_tags(records: &str, tag_names: &[String]) -> VcxResult<String> {
+    let mut records: Value = serde_json::from_str(records)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize wallet records: {:?}", err)))?;
+
+    if let Some(records) = records["records"].as_array_mut() {
+        for record in records {
+            let projected: Map<String, Value> = match record["tags"].as_object() {
+                Some(tags) => tags.iter()
+                    .filter(|(name, _)| tag_names.contains(name))
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect(),
+                None => continue
+            };
+            record["tags"] = Value::Object(projected);
+        }
+    }
+    Ok(records.to_string())
 }
 
 pub fn close_search(search_handle: SearchHandle) -> VcxResult<()> {
@@ -280,11 +449,80 @@ pub fn close_search(search_handle: SearchHandle) -> VcxResult<()> {
         return Ok(());
     }
 
+    SEARCH_TAG_NAMES.lock().unwrap().remove(&search_handle);
+
     wallet::close_wallet_search(search_handle)
         .wait()
         .map_err(VcxError::from)
 }
 
+/// Page of records fetched by `fetch_next_records`.
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchPage {
+    pub total_count: Option<usize>,
+    #[serde(default)]
+    pub records: Option<Vec<Value>>,
+}
+
+/// Iterator over records matching a search, fetched from the wallet by pages of `page_size` records.
+/// The search is closed when the iterator is dropped.
+pub struct RecordSearch {
+    search_handle: SearchHandle,
+    page_size: usize,
+    page: VecDeque<Value>,
+    exhausted: bool,
+}
+
+impl RecordSearch {
+    fn _fetch_page(&mut self) -> VcxResult<()> {
+        let page = fetch_next_records(self.search_handle, self.page_size)?;
+        let page: SearchPage = serde_json::from_str(&page)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize wallet records: {:?}", err)))?;
+
+        let records = page.records.unwrap_or_default();
+        self.exhausted = records.len() < self.page_size;
+        self.page.extend(records);
+        Ok(())
+    }
+}
+
+impl Iterator for RecordSearch {
+    type Item = VcxResult<Value>;
+
+    fn next(&mut self) -> Option<VcxResult<Value>> {
+        if self.page.is_empty() && !self.exhausted {
+            if let Err(err) = self._fetch_page() {
+                self.exhausted = true;
+                return Some(Err(err));
+            }
+        }
+        self.page.pop_front().map(Ok)
+    }
+}
+
+impl Drop for RecordSearch {
+    fn drop(&mut self) {
+        if let Err(err) = close_search(self.search_handle) {
+            warn!("RecordSearch::drop >>> closing search {} failed: {}", self.search_handle, err);
+        }
+    }
+}
+
+/// Searches records like `open_search` and returns them lazily, at most `page_size` of them held in memory.
+pub fn search_records(xtype: &str, query: &str, options: &str, page_size: usize) -> VcxResult<RecordSearch> {
+    if page_size == 0 {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidOption, "Page size must be greater than 0"));
+    }
+
+    Ok(RecordSearch {
+        search_handle: open_search(xtype, query, options)?,
+        page_size,
+        page: VecDeque::new(),
+        exhausted: false,
+    })
+}
+
 pub fn export(wallet_handle: WalletHandle, path: &str, backup_key: &str) -> VcxResult<()> {
     trace!("export >>> wallet_handle: {:?}, path: {:?}, backup_key: ****", wallet_handle, path);
 
@@ -295,17 +533,22 @@ pub fn export(wallet_handle: WalletHandle, path: &str, backup_key: &str) -> VcxR
 }
 
 pub fn import(config: &str) -> VcxResult<()> {
-    trace!("import >>> config {}", config);
+    trace!("import >>>");
 
     ::settings::process_config_string(config, false)?;
 
     let restore_config = RestoreWalletConfigs::from_str(config)?;
+    if restore_config.wallet_key.expose().is_empty() && !key_provider::is_registered() {
+        return Err(VcxError::from_msg(VcxErrorKind::MissingWalletKey, "Cannot import wallet: wallet_key is missing and no key provider is registered"));
+    }
 
-    let config = settings::get_wallet_config(&restore_config.wallet_name, None, None);
-    let credentials = settings::get_wallet_credentials(None);
-    let import_config = json!({"key": restore_config.backup_key, "path": restore_config.exported_wallet_path }).to_string();
+    register_configured_storage_library()?;
+    let wallet_type = settings::get_config_value(settings::CONFIG_WALLET_TYPE).ok();
+    let config = settings::get_wallet_config(&restore_config.wallet_name, wallet_type.as_ref().map(String::as_str), None)?;
+    let credentials = settings::get_wallet_credentials(None)?;
+    let import_config = SecretString::new(json!({"key": restore_config.backup_key.expose(), "path": restore_config.exported_wallet_path }).to_string());
 
-    wallet::import_wallet(&config, &credentials, &import_config)
+    wallet::import_wallet(&config, credentials.expose(), import_config.expose())
         .wait()
         .map_err(VcxError::from)
 }
@@ -355,6 +598,23 @@ pub mod tests {
         assert_eq!(VcxErrorKind::WalletCreate, init_wallet(&String::from(""), None, None, None).unwrap_err().kind());
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_scratch_wallet_is_removed_on_drop() {
+        let _setup = SetupLibraryWallet::init();
+
+        let wallet_handle = get_wallet_handle();
+        let scratch = ScratchWallet::create().unwrap();
+        assert_ne!(scratch.handle, wallet_handle);
+
+        let dir = scratch.dir.clone();
+        assert!(dir.exists());
+        drop(scratch);
+
+        assert!(!dir.exists());
+        assert_eq!(get_wallet_handle(), wallet_handle);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn test_wallet_for_unknown_type() {
@@ -487,6 +747,12 @@ pub mod tests {
         // Missing backup_key
         let res = import(&config.to_string()).unwrap_err();
         assert_eq!(res.kind(), VcxErrorKind::InvalidJson);
+        config[settings::CONFIG_WALLET_BACKUP_KEY] = serde_json::to_value("backup_key1").unwrap();
+
+        // Missing wallet_key and no key provider
+        config.as_object_mut().unwrap().remove(settings::CONFIG_WALLET_KEY);
+        let res = import(&config.to_string()).unwrap_err();
+        assert_eq!(res.kind(), VcxErrorKind::MissingWalletKey);
     }
 
     #[test]
@@ -686,4 +952,37 @@ pub mod tests {
         assert_eq!(initial_record, expected_initial_record);
         assert_eq!(changed_record, expected_updated_record);
     }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_search_records_by_pages_with_tag_projection() {
+        let _setup = SetupLibraryWallet::init();
+
+        for id in &["id1", "id2", "id3", "id4"] {
+            let tags = json!({"category": "contact", "name": id, "~age": "30"}).to_string();
+            add_record("search_type", id, "value", Some(&tags)).unwrap();
+        }
+        add_record("search_type", "id5", "value", Some(&json!({"category": "other"}).to_string())).unwrap();
+
+        let records: Vec<Value> = search_records("search_type",
+                                                 &json!({"category": "contact", "~age": {"$gte": "18"}}).to_string(),
+                                                 &json!({"tagNames": ["name"]}).to_string(),
+                                                 3).unwrap()
+            .collect::<VcxResult<Vec<Value>>>().unwrap();
+
+        assert_eq!(records.len(), 4);
+        for record in records {
+            assert_eq!(record["tags"], json!({"name": record["id"]}));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_open_search_fails_for_invalid_query() {
+        let _setup = SetupLibraryWallet::init();
+
+        assert_eq!(open_search("search_type", r#"{"category": {"$regex": "con"}}"#, "{}").unwrap_err().kind(), VcxErrorKind::InvalidJson);
+        assert_eq!(open_search("search_type", "{}", r#"{"tagNames": "name"}"#).unwrap_err().kind(), VcxErrorKind::InvalidJson);
+        assert_eq!(search_records("search_type", "{}", "{}", 0).err().unwrap().kind(), VcxErrorKind::InvalidOption);
+    }
 }
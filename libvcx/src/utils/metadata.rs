@@ -0,0 +1,48 @@
+use std::collections::BTreeMap;
+
+use error::prelude::*;
+
+/// Application defined key-value tags of an object, kept in its serialized form.
+pub type Metadata = BTreeMap<String, String>;
+
+/// Metadata key selecting the ledger network used for unqualified identifiers while the object is processed.
+pub const NETWORK: &str = "network";
+
+/// Metadata key set to "true" on connections converted from the legacy proprietary protocol, the peer
+/// does not speak aries on them, so they have to be established again before they are used.
+pub const REESTABLISH: &str = "reestablish";
+
+/// Sets `key` to `value`, or removes the key when no value is given.
+pub fn set(metadata: &mut Metadata, key: &str, value: Option<&str>) -> VcxResult<()> {
+    if key.is_empty() {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidOption, "Metadata key cannot be empty"));
+    }
+
+    match value {
+        Some(value) => { metadata.insert(key.to_string(), value.to_string()); }
+        None => { metadata.remove(key); }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utils::devsetup::SetupDefaults;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_set_metadata() {
+        let _setup = SetupDefaults::init();
+
+        let mut metadata = Metadata::new();
+        set(&mut metadata, "batch", Some("1")).unwrap();
+        set(&mut metadata, "batch", Some("2")).unwrap();
+        assert_eq!(metadata.get("batch"), Some(&"2".to_string()));
+
+        set(&mut metadata, "batch", None).unwrap();
+        assert!(metadata.is_empty());
+
+        assert_eq!(set(&mut metadata, "", Some("1")).unwrap_err().kind(), VcxErrorKind::InvalidOption);
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json;
+
+use error::prelude::*;
+
+pub static AGENCY_REQUEST: &str = "agency_request";
+pub static LEDGER_REQUEST: &str = "ledger_request";
+pub static PROOF_GENERATION: &str = "proof_generation";
+pub static PACK_MESSAGE: &str = "pack_message";
+pub static UNPACK_MESSAGE: &str = "unpack_message";
+
+/// Upper bounds of latency histogram buckets in milliseconds, one more bucket counts slower operations.
+pub const BUCKET_BOUNDS_MS: [u64; 11] = [1, 5, 10, 25, 50, 100, 250, 500, 1000, 5000, 10000];
+
+lazy_static! {
+    static ref OPERATIONS: Mutex<BTreeMap<String, OperationMetrics>> = Default::default();
+}
+
+/// Counts and latencies of one kind of operation.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OperationMetrics {
+    pub count: u64,
+    pub errors: u64,
+    pub total_ms: u64,
+    pub min_ms: u64,
+    pub max_ms: u64,
+    /// Operations per bucket of `bucket_bounds_ms`, the last item counts operations slower than all bounds.
+    pub histogram: Vec<u64>,
+}
+
+impl Default for OperationMetrics {
+    fn default() -> OperationMetrics {
+        OperationMetrics {
+            count: 0,
+            errors: 0,
+            total_ms: 0,
+            min_ms: 0,
+            max_ms: 0,
+            histogram: vec![0; BUCKET_BOUNDS_MS.len() + 1],
+        }
+    }
+}
+
+impl OperationMetrics {
+    fn add(&mut self, elapsed_ms: u64, success: bool) {
+        self.min_ms = if self.count == 0 { elapsed_ms } else { self.min_ms.min(elapsed_ms) };
+        self.max_ms = self.max_ms.max(elapsed_ms);
+        self.count += 1;
+        self.total_ms += elapsed_ms;
+        if !success {
+            self.errors += 1;
+        }
+
+        let bucket = BUCKET_BOUNDS_MS.iter()
+            .position(|bound| elapsed_ms <= *bound)
+            .unwrap_or(BUCKET_BOUNDS_MS.len());
+        self.histogram[bucket] += 1;
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Metrics {
+    pub bucket_bounds_ms: Vec<u64>,
+    pub operations: BTreeMap<String, OperationMetrics>,
+}
+
+impl Metrics {
+    pub fn to_string(&self) -> VcxResult<String> {
+        serde_json::to_string(self)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize metrics: {:?}", err)))
+    }
+}
+
+pub fn record(operation: &str, elapsed: Duration, success: bool) {
+    let elapsed_ms = elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis());
+    OPERATIONS.lock().unwrap()
+        .entry(operation.to_string())
+        .or_insert_with(OperationMetrics::default)
+        .add(elapsed_ms, success);
+}
+
+/// Runs `closure` and records its latency, failed results are counted as errors.
+pub fn measure<T, F>(operation: &str, closure: F) -> VcxResult<T> where F: FnOnce() -> VcxResult<T> {
+    let start = Instant::now();
+    let result = closure();
+    record(operation, start.elapsed(), result.is_ok());
+    result
+}
+
+/// Metrics collected since the library was loaded or since the last `reset`.
+pub fn snapshot() -> Metrics {
+    Metrics {
+        bucket_bounds_ms: BUCKET_BOUNDS_MS.to_vec(),
+        operations: OPERATIONS.lock().unwrap().clone(),
+    }
+}
+
+/// Returns collected metrics and starts collecting from scratch.
+pub fn take() -> Metrics {
+    let operations = ::std::mem::replace(&mut *OPERATIONS.lock().unwrap(), BTreeMap::new());
+    Metrics {
+        bucket_bounds_ms: BUCKET_BOUNDS_MS.to_vec(),
+        operations,
+    }
+}
+
+pub fn reset() {
+    OPERATIONS.lock().unwrap().clear();
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_operation_metrics() {
+        let mut metrics = OperationMetrics::default();
+        metrics.add(7, true);
+        metrics.add(3, false);
+        metrics.add(60_000, true);
+
+        assert_eq!(metrics.count, 3);
+        assert_eq!(metrics.errors, 1);
+        assert_eq!(metrics.total_ms, 60_010);
+        assert_eq!(metrics.min_ms, 3);
+        assert_eq!(metrics.max_ms, 60_000);
+        assert_eq!(metrics.histogram, vec![0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_measure_records_operations() {
+        let operation = "metrics_test_operation";
+
+        measure(operation, || Ok(())).unwrap();
+        measure::<(), _>(operation, || Err(VcxError::from(VcxErrorKind::PostMessageFailed))).unwrap_err();
+
+        let metrics = snapshot();
+        assert_eq!(metrics.bucket_bounds_ms, BUCKET_BOUNDS_MS.to_vec());
+        assert_eq!(metrics.operations[operation].count, 2);
+        assert_eq!(metrics.operations[operation].errors, 1);
+        assert_eq!(metrics.operations[operation].histogram.iter().sum::<u64>(), 2);
+    }
+}
@@ -18,3 +18,18 @@ pub const AGENCY_CONFIGS_UPDATED: &str = r#"
 }"#;
 
 
+
+pub const AGENCY_CONFIGS: &str = r#"
+{
+    "@type": "did:sov:123456789abcdefghi1234;spec/configs/1.0/CONFIGS",
+    "configs": [
+        {
+            "name": "name",
+            "value": "faber"
+        },
+        {
+            "name": "logoUrl",
+            "value": "https://example.org/faber.png"
+        }
+    ]
+}"#;
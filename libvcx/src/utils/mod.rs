@@ -10,6 +10,9 @@ pub mod cstring;
 #[macro_use]
 pub mod version_constants;
 
+#[macro_use]
+pub mod structured_log;
+
 #[macro_use]
 #[cfg(test)]
 pub mod devsetup;
@@ -41,10 +44,12 @@ macro_rules! map (
 
 pub mod error;
 pub mod httpclient;
+pub mod http_transport;
 pub mod constants;
 pub mod timeout;
 pub mod openssl;
 pub mod json;
+pub mod handle_token;
 pub mod libindy;
 pub mod threadpool;
 pub mod uuid;
@@ -54,8 +59,20 @@ pub mod file;
 pub mod option_util;
 pub mod agent_info;
 pub mod mockdata;
+pub mod credential_store;
+pub mod tails;
+pub mod wql;
+pub mod events;
+pub mod secret;
+pub mod key_provider;
+pub mod persistence;
+pub mod agent_context;
+pub mod metrics;
+pub mod parallel;
+pub mod metadata;
+pub mod capabilities;
+pub mod cancellation;
 
-#[cfg(test)]
 pub mod plugins;
 
 #[macro_use]
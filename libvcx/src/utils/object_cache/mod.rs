@@ -1,115 +1,371 @@
+use std::any::Any;
 use std::collections::HashMap;
+use std::hash::Hash;
 use std::ops::Deref;
 use std::ops::DerefMut;
-use std::sync::Mutex;
-use std::sync::MutexGuard;
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use rand::Rng;
 
 use error::prelude::*;
+use utils::agent_context;
+use utils::metadata::Metadata;
 
+/// Keys by which cached objects can be looked up besides their handle.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ObjectKeys {
+    pub source_id: Option<String>,
+    pub thread_id: Option<String>,
+    pub metadata: Metadata,
+}
+
+#[derive(Default)]
+struct Index {
+    keys: HashMap<u32, ObjectKeys>,
+    source_ids: HashMap<String, Vec<u32>>,
+    thread_ids: HashMap<String, Vec<u32>>,
+    metadata: HashMap<(String, String), Vec<u32>>,
+}
+
+impl Index {
+    fn insert(&mut self, handle: u32, keys: ObjectKeys) {
+        if self.keys.get(&handle) == Some(&keys) { return; }
+
+        self.remove(handle);
+        if let Some(ref source_id) = keys.source_id {
+            self.source_ids.entry(source_id.clone()).or_insert_with(Vec::new).push(handle);
+        }
+        if let Some(ref thread_id) = keys.thread_id {
+            self.thread_ids.entry(thread_id.clone()).or_insert_with(Vec::new).push(handle);
+        }
+        for (key, value) in keys.metadata.iter() {
+            self.metadata.entry((key.clone(), value.clone())).or_insert_with(Vec::new).push(handle);
+        }
+        self.keys.insert(handle, keys);
+    }
+
+    fn remove(&mut self, handle: u32) {
+        fn _remove_handle<K: Hash + Eq>(index: &mut HashMap<K, Vec<u32>>, key: Option<K>, handle: u32) {
+            if let Some(key) = key {
+                let now_empty = index.get_mut(&key)
+                    .map(|handles| {
+                        handles.retain(|h| *h != handle);
+                        handles.is_empty()
+                    })
+                    .unwrap_or(false);
+                if now_empty { index.remove(&key); }
+            }
+        }
+
+        if let Some(keys) = self.keys.remove(&handle) {
+            _remove_handle(&mut self.source_ids, keys.source_id, handle);
+            _remove_handle(&mut self.thread_ids, keys.thread_id, handle);
+            for entry in keys.metadata.into_iter() {
+                _remove_handle(&mut self.metadata, Some(entry), handle);
+            }
+        }
+    }
+}
+
+/// Scope entered for the time a closure runs on an object and left when dropped, see `ObjectCache::with_scope`.
+pub type ScopeGuard = Box<dyn Any>;
+
+// handles are random, so objects spread evenly over the shards
+const SHARD_COUNT: usize = 16;
+
+// object together with the keys it is indexed by, `released` is set when the object is removed from the cache
+// while somebody else still holds it
+struct Cached<T> {
+    obj: T,
+    keys: Option<ObjectKeys>,
+    released: bool,
+}
+
+type Object<T> = Arc<RwLock<Cached<T>>>;
+
+struct Entry<T> {
+    // agent which created the object, objects created outside of agents are visible to everyone
+    owner: Option<u32>,
+    object: Object<T>,
+}
+
+type Shard<T> = RwLock<HashMap<u32, Entry<T>>>;
+
+///
+/// Objects of one type (connections, proofs, ...) addressed by handles.
+///
+/// Objects are spread over shards so that adding or releasing an object locks only a fraction
+/// of the cache. Shard lock is held just for looking the object up, `get` then read-locks
+/// the object itself and `get_mut` write-locks it, so operations on different objects never wait
+/// for each other and reads of the same object run in parallel.
+/// `release` waits until the object is not used anymore.
+///
 pub struct ObjectCache<T> {
     pub cache_name: String,
-    pub store: Mutex<HashMap<u32, Mutex<T>>>,
+    shards: Vec<Shard<T>>,
+    // secondary indexes, maintained only for caches created by `with_index`,
+    // locked only when keys of an object change
+    object_keys: Option<fn(&T) -> ObjectKeys>,
+    index: Mutex<Index>,
+    // scope selected by the object metadata, e.g. ledger network of the object
+    scope: Option<fn(&Metadata) -> Option<ScopeGuard>>,
 }
 
 impl<T> ObjectCache<T> {
     pub fn new(cache_name: &str) -> ObjectCache<T> {
         ObjectCache {
-            store: Default::default(),
+            shards: (0..SHARD_COUNT).map(|_| Default::default()).collect(),
             cache_name: cache_name.to_string(),
+            object_keys: None,
+            index: Default::default(),
+            scope: None,
+        }
+    }
+
+    /// Creates cache whose objects can be found by `find_by_source_id` and `find_by_thread_id`.
+    /// Keys are read whenever an object is added or mutated.
+    pub fn with_index(cache_name: &str, object_keys: fn(&T) -> ObjectKeys) -> ObjectCache<T> {
+        ObjectCache {
+            object_keys: Some(object_keys),
+            ..ObjectCache::new(cache_name)
         }
     }
 
-    fn _lock_store(&self) -> VcxResult<MutexGuard<HashMap<u32, Mutex<T>>>> {
-        match self.store.lock() {
-            Ok(g) => Ok(g),
-            Err(e) => {
-                error!("Unable to lock Object Store: {:?}", e);
-                Err(VcxError::from_msg(VcxErrorKind::Common(10), format!("[ObjectCache: {}] Unable to lock Object Store: {:?}", self.cache_name, e)))
+    /// Enters `scope` selected by metadata of the object for the time a closure runs on it,
+    /// metadata is known only to caches created by `with_index`.
+    pub fn with_scope(mut self, scope: fn(&Metadata) -> Option<ScopeGuard>) -> ObjectCache<T> {
+        self.scope = Some(scope);
+        self
+    }
+
+    fn _cached(&self, handle: u32, obj: T) -> Cached<T> {
+        let keys = self.object_keys.map(|object_keys| object_keys(&obj));
+        if let Some(ref keys) = keys {
+            self.index.lock().unwrap().insert(handle, keys.clone());
+        }
+        Cached { obj, keys, released: false }
+    }
+
+    // re-indexes the object if its keys were changed
+    fn _reindex(&self, handle: u32, cached: &mut Cached<T>) {
+        if let Some(object_keys) = self.object_keys {
+            let keys = object_keys(&cached.obj);
+            if cached.keys.as_ref() != Some(&keys) {
+                self.index.lock().unwrap().insert(handle, keys.clone());
+                cached.keys = Some(keys);
             }
         }
     }
 
+    fn _shard(&self, handle: u32) -> &Shard<T> {
+        &self.shards[handle as usize % SHARD_COUNT]
+    }
+
+    fn _lock_error<E: ::std::fmt::Debug>(&self, err: E) -> VcxError {
+        error!("Unable to lock Object Store: {:?}", err);
+        VcxError::from_msg(VcxErrorKind::Common(10), format!("[ObjectCache: {}] Unable to lock Object Store: {:?}", self.cache_name, err))
+    }
+
+    fn _read_shard(&self, handle: u32) -> VcxResult<RwLockReadGuard<HashMap<u32, Entry<T>>>> {
+        self._shard(handle).read().map_err(|err| self._lock_error(err))
+    }
+
+    fn _write_shard(&self, handle: u32) -> VcxResult<RwLockWriteGuard<HashMap<u32, Entry<T>>>> {
+        self._shard(handle).write().map_err(|err| self._lock_error(err))
+    }
+
+    // object is cloned out of the shard so that the shard is not locked while the object is used
+    fn _object(&self, handle: u32) -> VcxResult<Object<T>> {
+        self._read_shard(handle)?
+            .get(&handle)
+            .filter(|entry| _is_visible(entry))
+            .map(|entry| entry.object.clone())
+            .ok_or(self._not_found(handle))
+    }
+
+    fn _is_visible(&self, handle: u32) -> bool {
+        match self._read_shard(handle) {
+            Ok(shard) => shard.get(&handle).map(_is_visible).unwrap_or(false),
+            Err(_) => false
+        }
+    }
+
+    fn _not_found(&self, handle: u32) -> VcxError {
+        VcxError::from_msg(VcxErrorKind::InvalidHandle, format!("[ObjectCache: {}] Object not found for handle: {}", self.cache_name, handle))
+    }
+
+    fn _enter_scope(&self, cached: &Cached<T>) -> Option<ScopeGuard> {
+        let scope = self.scope?;
+        cached.keys.as_ref().and_then(|keys| scope(&keys.metadata))
+    }
+
     pub fn has_handle(&self, handle: u32) -> bool {
-        let store = match self._lock_store() {
-            Ok(g) => g,
-            Err(_) => return false
-        };
-        store.contains_key(&handle)
+        self._is_visible(handle)
     }
 
     pub fn get<F, R>(&self, handle: u32, closure: F) -> VcxResult<R>
         where F: Fn(&T) -> VcxResult<R> {
-        let store = self._lock_store()?;
-        match store.get(&handle) {
-            Some(m) => match m.lock() {
-                Ok(obj) => closure(obj.deref()),
-                Err(_) => Err(VcxError::from_msg(VcxErrorKind::Common(10), format!("[ObjectCache: {}] Unable to lock Object Store", self.cache_name))) //TODO better error
-            },
-            None => Err(VcxError::from_msg(VcxErrorKind::InvalidHandle, format!("[ObjectCache: {}] Object not found for handle: {}", self.cache_name, handle)))
-        }
+        let object = self._object(handle)?;
+        let cached = object.read().map_err(|err| self._lock_error(err))?;
+        if cached.released { return Err(self._not_found(handle)); }
+
+        let _scope = self._enter_scope(cached.deref());
+        closure(&cached.obj)
     }
 
     pub fn get_mut<F, R>(&self, handle: u32, closure: F) -> VcxResult<R>
         where F: Fn(&mut T) -> VcxResult<R> {
-        let mut store = self._lock_store()?;
-        match store.get_mut(&handle) {
-            Some(m) => match m.lock() {
-                Ok(mut obj) => closure(obj.deref_mut()),
-                Err(_) => Err(VcxError::from_msg(VcxErrorKind::Common(10), format!("[ObjectCache: {}] Unable to lock Object Store", self.cache_name))) //TODO better error
-            },
-            None => Err(VcxError::from_msg(VcxErrorKind::InvalidHandle, format!("[ObjectCache: {}] Object not found for handle: {}", self.cache_name, handle)))
-        }
+        let object = self._object(handle)?;
+        let mut cached = object.write().map_err(|err| self._lock_error(err))?;
+        if cached.released { return Err(self._not_found(handle)); }
+
+        let result = {
+            let _scope = self._enter_scope(cached.deref());
+            closure(&mut cached.obj)
+        };
+        self._reindex(handle, cached.deref_mut());
+        result
     }
 
     pub fn add(&self, obj: T) -> VcxResult<u32> {
-        let mut store = self._lock_store()?;
-
-        let mut new_handle = rand::thread_rng().gen::<u32>();
         loop {
-            if !store.contains_key(&new_handle) {
-                break;
+            let new_handle = rand::thread_rng().gen::<u32>();
+            let mut shard = self._write_shard(new_handle)?;
+            if shard.contains_key(&new_handle) {
+                continue;
             }
-            new_handle = rand::thread_rng().gen::<u32>();
-        }
 
-        match store.insert(new_handle, Mutex::new(obj)) {
-            Some(_) => Ok(new_handle),
-            None => Ok(new_handle)
+            let cached = self._cached(new_handle, obj);
+            shard.insert(new_handle, _entry(cached));
+            return Ok(new_handle);
         }
     }
 
     pub fn insert(&self, handle: u32, obj: T) -> VcxResult<()> {
-        let mut store = self._lock_store()?;
-
-        match store.insert(handle, Mutex::new(obj)) {
-            _ => Ok(()),
+        let replaced = {
+            let mut shard = self._write_shard(handle)?;
+            let cached = self._cached(handle, obj);
+            shard.insert(handle, _entry(cached))
+        };
+        // holders of the replaced object must not write it back over the new one
+        if let Some(replaced) = replaced {
+            replaced.object.write().map_err(|err| self._lock_error(err))?.released = true;
         }
+        Ok(())
     }
 
     pub fn release(&self, handle: u32) -> VcxResult<()> {
-        let mut store = self._lock_store()?;
-        match store.remove(&handle) {
-            Some(_) => Ok(()),
-            None => Err(VcxError::from_msg(VcxErrorKind::InvalidHandle, format!("[ObjectCache: {}] Object not found for handle: {}", self.cache_name, handle)))
+        let entry = {
+            let mut shard = self._write_shard(handle)?;
+            match shard.get(&handle) {
+                Some(entry) if _is_visible(entry) => {}
+                _ => return Err(self._not_found(handle))
+            }
+            shard.remove(&handle)
+        };
+        match entry {
+            Some(entry) => self._release(handle, entry),
+            None => Err(self._not_found(handle))
+        }
+    }
+
+    // waits until running `get` and `get_mut` calls on the object finish, calls waiting for it fail afterwards
+    fn _release(&self, handle: u32, entry: Entry<T>) -> VcxResult<()> {
+        let mut cached = entry.object.write().map_err(|err| self._lock_error(err))?;
+        cached.released = true;
+        if cached.keys.is_some() {
+            self.index.lock().unwrap().remove(handle);
         }
+        Ok(())
     }
 
+    /// Releases objects visible to the current agent (see `has_handle`), objects of other agents are kept.
     pub fn drain(&self) -> VcxResult<()> {
-        let mut store = self._lock_store()?;
-        Ok(store.clear())
+        for shard in self.shards.iter() {
+            let entries: Vec<(u32, Entry<T>)> = {
+                let mut shard = shard.write().map_err(|err| self._lock_error(err))?;
+                let handles: Vec<u32> = shard.iter()
+                    .filter(|(_, entry)| _is_visible(entry))
+                    .map(|(handle, _)| *handle)
+                    .collect();
+                handles.into_iter()
+                    .filter_map(|handle| shard.remove(&handle).map(|entry| (handle, entry)))
+                    .collect()
+            };
+            for (handle, entry) in entries {
+                self._release(handle, entry)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles visible to the current agent (see `has_handle`).
+    pub fn handles(&self) -> VcxResult<Vec<u32>> {
+        let mut handles = Vec::new();
+        for shard in self.shards.iter() {
+            let shard = shard.read().map_err(|err| self._lock_error(err))?;
+            handles.extend(shard.iter().filter(|(_, entry)| _is_visible(entry)).map(|(handle, _)| *handle));
+        }
+        Ok(handles)
+    }
+
+    /// Most recently indexed object with given source id which is visible to the current agent.
+    pub fn find_by_source_id(&self, source_id: &str) -> Option<u32> {
+        let handles = self.index.lock().unwrap().source_ids.get(source_id).cloned();
+        self._find_visible(handles)
+    }
+
+    /// Most recently indexed object with given thread id which is visible to the current agent.
+    pub fn find_by_thread_id(&self, thread_id: &str) -> Option<u32> {
+        let handles = self.index.lock().unwrap().thread_ids.get(thread_id).cloned();
+        self._find_visible(handles)
+    }
+
+    /// Objects having metadata `key` set to `value` which are visible to the current agent, in order of indexing.
+    pub fn find_by_metadata(&self, key: &str, value: &str) -> Vec<u32> {
+        // index is not locked while shards are, see `add`
+        let handles = self.index.lock().unwrap().metadata.get(&(key.to_string(), value.to_string())).cloned();
+        handles.unwrap_or_default().into_iter().filter(|handle| self._is_visible(*handle)).collect()
+    }
+
+    fn _find_visible(&self, handles: Option<Vec<u32>>) -> Option<u32> {
+        handles.and_then(|handles| handles.into_iter().rev().find(|handle| self._is_visible(*handle)))
     }
 
     pub fn len(&self) -> VcxResult<usize> {
-        let store = self._lock_store()?;
-        Ok(store.len())
+        let mut len = 0;
+        for shard in self.shards.iter() {
+            len += shard.read().map_err(|err| self._lock_error(err))?.len();
+        }
+        Ok(len)
+    }
+}
+
+fn _entry<T>(cached: Cached<T>) -> Entry<T> {
+    Entry {
+        owner: agent_context::current_agent_id(),
+        object: Arc::new(RwLock::new(cached)),
+    }
+}
+
+fn _is_visible<T>(entry: &Entry<T>) -> bool {
+    match entry.owner {
+        Some(owner) => agent_context::current_agent_id() == Some(owner),
+        None => true
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use utils::object_cache::ObjectCache;
+    use std::sync::mpsc::channel;
+    use std::thread;
+    use std::time::Duration;
+
+    use error::prelude::*;
+    use utils::agent_context::{self, AgentContext};
+    use utils::libindy::pool;
+    use utils::metadata::{self, Metadata};
+    use utils::object_cache::{ObjectCache, ObjectKeys};
     use utils::devsetup::SetupDefaults;
 
     #[test]
@@ -120,6 +376,23 @@ mod tests {
         let _c: ObjectCache<u32> = ObjectCache::new("cache0-u32");
     }
 
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn handles_test() {
+        let _setup = SetupDefaults::init();
+
+        let test: ObjectCache<u32> = ObjectCache::new("cache-handles-u32");
+        assert!(test.handles().unwrap().is_empty());
+
+        let handle_1 = test.add(1).unwrap();
+        let handle_2 = test.add(2).unwrap();
+        let mut handles = test.handles().unwrap();
+        handles.sort();
+        let mut expected = vec![handle_1, handle_2];
+        expected.sort();
+        assert_eq!(handles, expected);
+    }
+
     #[test]
     #[cfg(feature = "general_test")]
     fn get_closure() {
@@ -164,4 +437,165 @@ mod tests {
 
         assert_eq!("TEST", string);
     }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn find_by_keys_test() {
+        let _setup = SetupDefaults::init();
+
+        let test: ObjectCache<(String, String)> = ObjectCache::with_index("cache-index", |obj| ObjectKeys {
+            source_id: Some(obj.0.clone()),
+            thread_id: Some(obj.1.clone()).filter(|thread_id| !thread_id.is_empty()),
+            ..ObjectKeys::default()
+        });
+        let handle_1 = test.add(("source".to_string(), String::new())).unwrap();
+        let handle_2 = test.add(("source".to_string(), "thread".to_string())).unwrap();
+
+        assert_eq!(test.find_by_source_id("source"), Some(handle_2));
+        assert_eq!(test.find_by_thread_id("thread"), Some(handle_2));
+
+        test.get_mut(handle_1, |obj| {
+            obj.1 = "other_thread".to_string();
+            Ok(())
+        }).unwrap();
+        assert_eq!(test.find_by_thread_id("other_thread"), Some(handle_1));
+
+        test.release(handle_2).unwrap();
+        assert_eq!(test.find_by_source_id("source"), Some(handle_1));
+        assert_eq!(test.find_by_thread_id("thread"), None);
+
+        test.drain().unwrap();
+        assert_eq!(test.find_by_source_id("source"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn drain_releases_objects_of_current_agent_only() {
+        let _setup = SetupDefaults::init();
+
+        let test: ObjectCache<u32> = ObjectCache::new("cache-drain-agents");
+        let agent = AgentContext::new(1001);
+        let other_agent = AgentContext::new(1002);
+
+        let handle = agent_context::with_context(&agent, || test.add(1).unwrap());
+        let other_handle = agent_context::with_context(&other_agent, || test.add(2).unwrap());
+        let process_handle = test.add(3).unwrap();
+
+        agent_context::with_context(&agent, || test.drain().unwrap());
+
+        assert!(!agent_context::with_context(&agent, || test.has_handle(handle)));
+        assert!(agent_context::with_context(&other_agent, || test.has_handle(other_handle)));
+        assert!(test.has_handle(process_handle));
+
+        test.drain().unwrap();
+        assert!(!test.has_handle(process_handle));
+        assert!(agent_context::with_context(&other_agent, || test.has_handle(other_handle)));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn find_by_metadata_test() {
+        let _setup = SetupDefaults::init();
+
+        let test: ObjectCache<Metadata> = ObjectCache::with_index("cache-metadata", |obj| ObjectKeys {
+            metadata: obj.clone(),
+            ..ObjectKeys::default()
+        });
+        let mut metadata = Metadata::new();
+        metadata.insert("batch".to_string(), "1".to_string());
+        let handle_1 = test.add(metadata.clone()).unwrap();
+        let handle_2 = test.add(metadata).unwrap();
+
+        assert_eq!(test.find_by_metadata("batch", "1"), vec![handle_1, handle_2]);
+        assert!(test.find_by_metadata("batch", "2").is_empty());
+
+        test.get_mut(handle_1, |obj| {
+            obj.insert("batch".to_string(), "2".to_string());
+            Ok(())
+        }).unwrap();
+        assert_eq!(test.find_by_metadata("batch", "1"), vec![handle_2]);
+        assert_eq!(test.find_by_metadata("batch", "2"), vec![handle_1]);
+
+        test.release(handle_2).unwrap();
+        assert!(test.find_by_metadata("batch", "1").is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn network_of_object_is_selected_test() {
+        let _setup = SetupDefaults::init();
+
+        let test: ObjectCache<Metadata> = ObjectCache::with_index("cache-network", |obj| ObjectKeys {
+            metadata: obj.clone(),
+            ..ObjectKeys::default()
+        }).with_scope(pool::enter_object_network);
+        let mut metadata = Metadata::new();
+        metadata.insert(metadata::NETWORK.to_string(), "sovrin".to_string());
+        let handle_1 = test.add(metadata).unwrap();
+        let handle_2 = test.add(Metadata::new()).unwrap();
+
+        assert_eq!(test.get(handle_1, |_| Ok(pool::current_network())).unwrap(), Some("sovrin".to_string()));
+        assert_eq!(test.get_mut(handle_1, |_| Ok(pool::current_network())).unwrap(), Some("sovrin".to_string()));
+        assert_eq!(test.get(handle_1, |_| {
+            let _network = pool::enter_network_of("V4SGRU86Z58d6TV7PBUe6f:2:name:1.0").unwrap();
+            Ok(pool::current_network())
+        }).unwrap(), Some("sovrin".to_string()));
+        assert_eq!(test.get(handle_2, |_| Ok(pool::current_network())).unwrap(), None);
+        assert_eq!(pool::current_network(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn objects_are_accessible_while_other_object_is_mutated() {
+        let _setup = SetupDefaults::init();
+
+        lazy_static! {
+            static ref TEST: ObjectCache<u32> = ObjectCache::new("cache-concurrent-u32");
+        }
+        let handle_1 = TEST.add(1).unwrap();
+        let handle_2 = TEST.add(2).unwrap();
+
+        let (sender, receiver) = channel();
+        TEST.get_mut(handle_1, |obj| {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                let value = TEST.get(handle_2, |obj| Ok(*obj)).unwrap();
+                let handle_3 = TEST.add(3).unwrap();
+                TEST.release(handle_3).unwrap();
+                sender.send(value).unwrap();
+            });
+            assert_eq!(receiver.recv_timeout(Duration::from_secs(5)).unwrap(), 2);
+            *obj = 10;
+            Ok(())
+        }).unwrap();
+
+        assert_eq!(TEST.get(handle_1, |obj| Ok(*obj)).unwrap(), 10);
+        assert_eq!(TEST.len().unwrap(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn release_waits_for_object_in_use() {
+        let _setup = SetupDefaults::init();
+
+        lazy_static! {
+            static ref TEST: ObjectCache<u32> = ObjectCache::new("cache-release-u32");
+        }
+        let handle = TEST.add(1).unwrap();
+
+        let (sender, receiver) = channel();
+        TEST.get_mut(handle, |obj| {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                sender.send(TEST.release(handle).is_ok()).unwrap();
+            });
+            assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+            *obj = 10;
+            Ok(())
+        }).unwrap();
+
+        assert!(receiver.recv_timeout(Duration::from_secs(5)).unwrap());
+        assert!(!TEST.has_handle(handle));
+        assert_eq!(TEST.get_mut(handle, |_| Ok(())).unwrap_err().kind(), VcxErrorKind::InvalidHandle);
+    }
 }
@@ -1,5 +1,7 @@
+use std::io::{self, Write};
+
 use openssl::bn::BigNum;
-use openssl::sha::sha256;
+use openssl::sha::{sha256, Sha256};
 
 use error::prelude::*;
 
@@ -20,6 +22,35 @@ pub fn encode(s: &str) -> VcxResult<String> {
     }
 }
 
+/// Computes `sha256` of the content written through it, so that streamed content does not have to be held in memory.
+pub struct HashingWriter<'a, W: Write + 'a> {
+    writer: &'a mut W,
+    hasher: Sha256,
+}
+
+impl<'a, W: Write> HashingWriter<'a, W> {
+    pub fn new(writer: &'a mut W) -> HashingWriter<'a, W> {
+        HashingWriter { writer, hasher: Sha256::new() }
+    }
+
+    /// Hash of the content written so far.
+    pub fn finish(self) -> [u8; 32] {
+        self.hasher.finish()
+    }
+}
+
+impl<'a, W: Write> Write for HashingWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use error::prelude::*;
+use settings;
+use utils::agent_context::{self, AgentContext};
+use utils::cancellation::{self, CancellationToken};
+use utils::libindy::pool;
+use utils::structured_log::{self, Correlation};
+
+/// Upper bound of threads started by one `try_map` call, more items wait for a free thread.
+pub const MAX_CONCURRENCY: usize = 8;
+
+// thread local state of the calling thread which the worker threads run with
+#[derive(Clone)]
+struct CallerContext {
+    agent: Option<AgentContext>,
+    overrides: HashMap<String, String>,
+    network: Option<String>,
+    correlation: Correlation,
+    cancellation_token: Option<CancellationToken>,
+}
+
+impl CallerContext {
+    fn current() -> CallerContext {
+        CallerContext {
+            agent: agent_context::current(),
+            overrides: settings::current_overrides(),
+            network: pool::current_network(),
+            correlation: structured_log::current(),
+            cancellation_token: cancellation::current(),
+        }
+    }
+
+    fn run<F, R>(self, closure: F) -> R where F: FnOnce() -> R {
+        let CallerContext { agent, overrides, network, correlation, cancellation_token } = self;
+        let run = move || {
+            let _network = pool::enter_network(network);
+            let _correlation = structured_log::enter(correlation);
+            match cancellation_token {
+                Some(ref token) => cancellation::with_token(token, || settings::with_overrides(overrides, closure)),
+                None => settings::with_overrides(overrides, closure)
+            }
+        };
+        match agent {
+            Some(ref agent) => agent_context::with_context(agent, run),
+            None => run()
+        }
+    }
+}
+
+///
+/// Applies `closure` on `items` using up to `MAX_CONCURRENCY` threads, which see the agent, settings overrides,
+/// network and cancellation token of the calling thread. Results keep the order of `items`, the error of the first failed item is returned.
+///
+pub fn try_map<T, R, F>(items: Vec<T>, closure: F) -> VcxResult<Vec<R>>
+    where T: Send + 'static, R: Send + 'static, F: Fn(T) -> VcxResult<R> + Send + Sync + 'static {
+    map_results(items, MAX_CONCURRENCY, closure).into_iter().collect()
+}
+
+///
+/// Like `try_map`, but with up to `concurrency` threads and the result of every item returned,
+/// items of a panicked thread fail with `UnknownError`.
+///
+pub fn map_results<T, R, F>(items: Vec<T>, concurrency: usize, closure: F) -> Vec<VcxResult<R>>
+    where T: Send + 'static, R: Send + 'static, F: Fn(T) -> VcxResult<R> + Send + Sync + 'static {
+    if items.len() <= 1 {
+        return items.into_iter().map(closure).collect();
+    }
+
+    let count = items.len();
+    let closure = Arc::new(closure);
+    let context = CallerContext::current();
+    let queue = Arc::new(Mutex::new(items.into_iter().enumerate()));
+
+    let workers: Vec<_> = (0..concurrency.max(1).min(count))
+        .map(|_| {
+            let closure = closure.clone();
+            let context = context.clone();
+            let queue = queue.clone();
+            thread::spawn(move || context.run(|| {
+                let mut results = Vec::new();
+                loop {
+                    let next = match queue.lock() {
+                        Ok(mut queue) => queue.next(),
+                        Err(_) => None
+                    };
+                    match next {
+                        Some((index, item)) => results.push((index, closure(item))),
+                        None => return results
+                    }
+                }
+            }))
+        })
+        .collect();
+
+    let mut results: Vec<Option<VcxResult<R>>> = (0..count).map(|_| None).collect();
+    for worker in workers {
+        match worker.join() {
+            Ok(worker_results) => for (index, result) in worker_results { results[index] = Some(result) },
+            Err(_) => warn!("Parallel operation panicked")
+        }
+    }
+
+    results.into_iter()
+        .map(|result| result.unwrap_or_else(|| Err(VcxError::from_msg(VcxErrorKind::UnknownError, "Parallel operation panicked"))))
+        .collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_try_map_keeps_order_and_caller_context() {
+        let items: Vec<usize> = (0..2 * MAX_CONCURRENCY + 1).collect();
+        let overrides = vec![("parallel_test_key".to_string(), "value".to_string())].into_iter().collect();
+
+        let results = settings::with_overrides(overrides, || {
+            try_map(items.clone(), |item| {
+                let value = settings::get_config_value("parallel_test_key")?;
+                Ok(format!("{}:{}", item, value))
+            })
+        }).unwrap();
+
+        assert_eq!(results, items.iter().map(|item| format!("{}:value", item)).collect::<Vec<String>>());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_try_map_returns_first_error() {
+        let err = try_map(vec![1, 2, 3], |item| {
+            if item > 1 {
+                Err(VcxError::from_msg(VcxErrorKind::InvalidLedgerResponse, format!("item {}", item)))
+            } else {
+                Ok(item)
+            }
+        }).unwrap_err();
+
+        assert_eq!(err.kind(), VcxErrorKind::InvalidLedgerResponse);
+        assert!(err.to_string().contains("item 2"));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_try_map_observes_caller_cancellation() {
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let err = cancellation::with_token(&token, || try_map(vec![1, 2, 3], |item| cancellation::check().map(|_| item))).unwrap_err();
+        assert_eq!(VcxErrorKind::Cancelled, err.kind());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_map_results_returns_result_of_every_item() {
+        let results = map_results(vec![1, 2, 3, 4], 2, |item| {
+            if item % 2 == 0 {
+                Err(VcxError::from_msg(VcxErrorKind::InvalidProof, format!("item {}", item)))
+            } else {
+                Ok(item)
+            }
+        });
+
+        assert_eq!(4, results.len());
+        assert_eq!(1, *results[0].as_ref().unwrap());
+        assert_eq!(VcxErrorKind::InvalidProof, results[1].as_ref().unwrap_err().kind());
+        assert_eq!(3, *results[2].as_ref().unwrap());
+        assert!(results[3].as_ref().unwrap_err().to_string().contains("item 4"));
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use openssl::sha::sha256;
+use serde_json;
+
+use connection;
+use credential;
+use credential_template;
+use disclosed_proof;
+use error::prelude::*;
+use issuer_credential;
+use proof;
+use settings;
+use utils::events;
+use utils::libindy::wallet;
+use utils::object_cache::ObjectCache;
+use utils::uuid;
+
+pub static OBJECT_RECORD_TYPE: &str = "VcxObject";
+const LOAD_PAGE_SIZE: usize = 50;
+
+lazy_static! {
+    // wallet records saving the objects, by object type and handle
+    static ref RECORDS: Mutex<HashMap<(String, u32), SavedRecord>> = Default::default();
+}
+
+struct SavedRecord {
+    id: String,
+    // hash of the saved state, objects are written to the wallet only when their state changes
+    digest: Option<[u8; 32]>,
+}
+
+/// Handles of objects restored by `load_all_objects`.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct LoadedObjects {
+    pub connections: Vec<u32>,
+    pub credentials: Vec<u32>,
+    pub issuer_credentials: Vec<u32>,
+    pub proofs: Vec<u32>,
+    pub disclosed_proofs: Vec<u32>,
+    pub credential_templates: Vec<u32>,
+}
+
+impl LoadedObjects {
+    pub fn to_string(&self) -> VcxResult<String> {
+        serde_json::to_string(self)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize loaded objects: {:?}", err)))
+    }
+}
+
+/// Objects are saved to the wallet only when `persist_objects` is enabled.
+pub fn enabled() -> bool {
+    settings::get_config_value(settings::CONFIG_PERSIST_OBJECTS).ok()
+        .and_then(|value| value.parse::<bool>().ok())
+        .unwrap_or(false)
+}
+
+///
+/// Stores serialized object into the wallet record `id`, replacing the previously saved state.
+///
+pub fn save(object_type: &str, id: &str, data: &str) -> VcxResult<()> {
+    trace!("persistence::save >>> object_type: {}, id: {}", object_type, id);
+
+    match wallet::update_record_value(OBJECT_RECORD_TYPE, id, data) {
+        Err(ref err) if err.kind() == VcxErrorKind::WalletRecordNotFound => {
+            let tags = json!({"object_type": object_type}).to_string();
+            wallet::add_record(OBJECT_RECORD_TYPE, id, data, Some(&tags))
+        }
+        result => result
+    }
+}
+
+/// Saves the object if persistence is enabled and its state differs from the saved one, `serialize` is called only then.
+pub fn persist<D>(object_type: &str, handle: u32, serialize: D) -> VcxResult<()>
+    where D: Fn(u32) -> VcxResult<String> {
+    if !enabled() { return Ok(()); }
+
+    let data = serialize(handle)?;
+    let digest = sha256(data.as_bytes());
+    let key = (object_type.to_string(), handle);
+
+    let id = {
+        let mut records = RECORDS.lock().unwrap();
+        let record = records.entry(key.clone()).or_insert_with(|| SavedRecord { id: uuid::uuid(), digest: None });
+        if record.digest == Some(digest) { return Ok(()); }
+        record.id.clone()
+    };
+
+    save(object_type, &id, &data)
+        .map_err(|err| err.extend(format!("Cannot persist {} with handle {}", object_type, handle)))?;
+
+    if let Some(record) = RECORDS.lock().unwrap().get_mut(&key) {
+        record.digest = Some(digest);
+    }
+    Ok(())
+}
+
+/// Forgets the record of the released object, the saved state is kept to be restored by `load_all_objects`.
+pub fn forget(object_type: &str, handle: u32) {
+    RECORDS.lock().unwrap().remove(&(object_type.to_string(), handle));
+}
+
+/// Deletes saved state of the object, it is not restored by `load_all_objects` anymore.
+pub fn remove(object_type: &str, handle: u32) -> VcxResult<()> {
+    trace!("persistence::remove >>> object_type: {}, handle: {}", object_type, handle);
+
+    let id = match RECORDS.lock().unwrap().remove(&(object_type.to_string(), handle)) {
+        Some(record) => record.id,
+        None => return Ok(())
+    };
+
+    match wallet::delete_record(OBJECT_RECORD_TYPE, &id) {
+        Err(ref err) if err.kind() == VcxErrorKind::WalletRecordNotFound => Ok(()),
+        result => result
+    }
+}
+
+/// Forgets records of the objects in `cache` visible to the current agent, which are released without being deleted, e.g. on shutdown.
+pub fn forget_all<T>(cache: &ObjectCache<T>, object_type: &str) {
+    let handles = cache.handles().unwrap_or_default();
+    RECORDS.lock().unwrap().retain(|&(ref record_type, handle), _| record_type != object_type || !handles.contains(&handle));
+}
+
+/// Adds the object to `cache` and saves it, the object is not added when it cannot be saved.
+pub fn add<T, D>(cache: &ObjectCache<T>, object_type: &str, obj: T, serialize: D) -> VcxResult<u32>
+    where D: Fn(u32) -> VcxResult<String> {
+    let handle = cache.add(obj)?;
+    if let Err(err) = persist(object_type, handle, serialize) {
+        forget(object_type, handle);
+        cache.release(handle).ok();
+        return Err(err);
+    }
+    Ok(handle)
+}
+
+///
+/// Changes the object like `events::get_mut_notifying` and saves it afterwards if it was changed.
+/// Failure to save the object is returned unless the change itself failed. The change stays
+/// applied in memory either way, saving is retried by the next change of the object.
+///
+pub fn get_mut<T, F, R, S, I, D>(cache: &ObjectCache<T>, object_type: &str, handle: u32, state: S, thread_id: I, serialize: D, closure: F) -> VcxResult<R>
+    where F: Fn(&mut T) -> VcxResult<R>, S: Fn(&T) -> u32, I: Fn(&T) -> Option<String>, D: Fn(u32) -> VcxResult<String> {
+    let result = events::get_mut_notifying(cache, object_type, handle, state, thread_id, closure);
+    if cache.has_handle(handle) {
+        if let Err(err) = persist(object_type, handle, serialize) {
+            return match result {
+                Ok(_) => Err(err),
+                Err(result_err) => {
+                    error!("{}", err);
+                    Err(result_err)
+                }
+            };
+        }
+    }
+    result
+}
+
+/// Deletes saved state of the object and releases its handle, see `vcx_delete_object`.
+pub fn delete_object(object_type: &str, handle: u32) -> VcxResult<()> {
+    let release: fn(u32) -> VcxResult<()> = match object_type {
+        "connection" => connection::release,
+        "credential" => credential::release,
+        "credential_template" => credential_template::release,
+        "disclosed_proof" => disclosed_proof::release,
+        "issuer_credential" => issuer_credential::release,
+        "proof" => proof::release,
+        _ => return Err(VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Unsupported object type: {}", object_type)))
+    };
+    remove(object_type, handle)?;
+    release(handle)
+}
+
+///
+/// Creates handles for all objects saved in the wallet. Records which cannot be restored are
+/// skipped, so that one broken object does not prevent the others from being used.
+/// Objects restored by a previous call keep their handles, they are not restored again.
+///
+pub fn load_all_objects() -> VcxResult<LoadedObjects> {
+    trace!("persistence::load_all_objects >>>");
+
+    let options = json!({"retrieveType": false, "retrieveValue": true, "retrieveTags": true}).to_string();
+    let mut loaded = LoadedObjects::default();
+    let mut created = LoadedObjects::default();
+
+    for record in wallet::search_records(OBJECT_RECORD_TYPE, "{}", &options, LOAD_PAGE_SIZE)? {
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                _release_loaded(&created);
+                return Err(err);
+            }
+        };
+
+        let id = record["id"].as_str().unwrap_or_default();
+        let object_type = record["tags"]["object_type"].as_str().unwrap_or_default();
+
+        if _handles_of(&mut loaded, object_type).is_none() {
+            warn!("persistence::load_all_objects >>> skipping record {:?} of unknown object type {:?}", id, object_type);
+            continue;
+        }
+
+        let handle = match _loaded_handle(object_type, id) {
+            Some(handle) => handle,
+            None => match _load(object_type, record["value"].as_str()) {
+                Ok(handle) => {
+                    let digest = record["value"].as_str().map(|value| sha256(value.as_bytes()));
+                    RECORDS.lock().unwrap().insert((object_type.to_string(), handle), SavedRecord { id: id.to_string(), digest });
+                    if let Some(handles) = _handles_of(&mut created, object_type) { handles.push(handle); }
+                    handle
+                }
+                Err(err) => {
+                    warn!("persistence::load_all_objects >>> skipping record {:?} which cannot be restored: {}", id, err);
+                    continue;
+                }
+            }
+        };
+        if let Some(handles) = _handles_of(&mut loaded, object_type) { handles.push(handle); }
+    }
+
+    debug!("persistence::load_all_objects <<< {:?}", loaded);
+    Ok(loaded)
+}
+
+// handle of the object already restored from the record `id`
+fn _loaded_handle(object_type: &str, id: &str) -> Option<u32> {
+    RECORDS.lock().unwrap().iter()
+        .find(|&(&(ref record_type, _), record)| record_type == object_type && record.id == id)
+        .map(|(&(_, handle), _)| handle)
+}
+
+fn _handles_of<'a>(loaded: &'a mut LoadedObjects, object_type: &str) -> Option<&'a mut Vec<u32>> {
+    match object_type {
+        "connection" => Some(&mut loaded.connections),
+        "credential" => Some(&mut loaded.credentials),
+        "issuer_credential" => Some(&mut loaded.issuer_credentials),
+        "proof" => Some(&mut loaded.proofs),
+        "disclosed_proof" => Some(&mut loaded.disclosed_proofs),
+        "credential_template" => Some(&mut loaded.credential_templates),
+        _ => None
+    }
+}
+
+fn _load(object_type: &str, data: Option<&str>) -> VcxResult<u32> {
+    let data = data.ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, "Persisted object has no value"))?;
+
+    match object_type {
+        "connection" => connection::from_string(data),
+        "credential" => credential::from_string(data),
+        "issuer_credential" => issuer_credential::from_string(data),
+        "proof" => proof::from_string(data),
+        "disclosed_proof" => disclosed_proof::from_string(data),
+        "credential_template" => credential_template::from_string(data),
+        _ => Err(VcxError::from_msg(VcxErrorKind::InvalidOption, format!("Unsupported object type: {}", object_type)))
+    }
+}
+
+// releasing the handles keeps the saved records
+fn _release_loaded(loaded: &LoadedObjects) {
+    loaded.connections.iter().for_each(|&handle| { connection::release(handle).ok(); });
+    loaded.credentials.iter().for_each(|&handle| { credential::release(handle).ok(); });
+    loaded.issuer_credentials.iter().for_each(|&handle| { issuer_credential::release(handle).ok(); });
+    loaded.proofs.iter().for_each(|&handle| { proof::release(handle).ok(); });
+    loaded.disclosed_proofs.iter().for_each(|&handle| { disclosed_proof::release(handle).ok(); });
+    loaded.credential_templates.iter().for_each(|&handle| { credential_template::release(handle).ok(); });
+}
+
+#[cfg(test)]
+pub mod tests {
+    use utils::constants::{REQUESTED_ATTRS, REQUESTED_PREDICATES};
+    use utils::devsetup::{SetupAriesMocks, SetupLibraryWallet};
+
+    use super::*;
+
+    fn _create_proof() -> u32 {
+        proof::create_proof("persisted_proof".to_string(),
+                            REQUESTED_ATTRS.to_owned(),
+                            REQUESTED_PREDICATES.to_owned(),
+                            r#"{"support_revocation":false}"#.to_string(),
+                            "Optional".to_owned()).unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_persistence_is_opt_in() {
+        let _setup = SetupAriesMocks::init();
+
+        assert!(!enabled());
+        persist(events::PROOF, 1, |_| panic!("object must not be serialized")).unwrap();
+
+        settings::set_config_value(settings::CONFIG_PERSIST_OBJECTS, "true");
+        assert!(enabled());
+        persist(events::PROOF, 1, |_| Ok("{}".to_string())).unwrap();
+
+        assert_eq!(load_all_objects().unwrap(), LoadedObjects::default());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_load_all_objects_restores_saved_objects() {
+        let _setup = SetupLibraryWallet::init();
+        settings::set_config_value(settings::CONFIG_PERSIST_OBJECTS, "true");
+
+        // objects with the same source id are saved separately
+        let serialized = vec![proof::to_string(_create_proof()).unwrap(), proof::to_string(_create_proof()).unwrap()];
+        proof::release_all();
+
+        let loaded = load_all_objects().unwrap();
+        assert_eq!(loaded.proofs.len(), 2);
+        assert!(loaded.connections.is_empty());
+        for handle in loaded.proofs {
+            assert!(serialized.contains(&proof::to_string(handle).unwrap()));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_release_keeps_saved_object_until_deleted() {
+        let _setup = SetupLibraryWallet::init();
+        settings::set_config_value(settings::CONFIG_PERSIST_OBJECTS, "true");
+
+        proof::release(_create_proof()).unwrap();
+        let handle = _create_proof();
+        delete_object(events::PROOF, handle).unwrap();
+        assert!(!proof::is_valid_handle(handle));
+
+        let loaded = load_all_objects().unwrap();
+        assert_eq!(loaded.proofs.len(), 1);
+
+        delete_object(events::PROOF, loaded.proofs[0]).unwrap();
+        assert_eq!(load_all_objects().unwrap(), LoadedObjects::default());
+        assert_eq!(delete_object("unknown", 1).unwrap_err().kind(), VcxErrorKind::InvalidOption);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_load_all_objects_is_idempotent() {
+        let _setup = SetupLibraryWallet::init();
+        settings::set_config_value(settings::CONFIG_PERSIST_OBJECTS, "true");
+
+        let handle = _create_proof();
+        assert_eq!(load_all_objects().unwrap().proofs, vec![handle]);
+
+        proof::release_all();
+        let loaded = load_all_objects().unwrap();
+        assert_eq!(loaded.proofs.len(), 1);
+        assert_eq!(load_all_objects().unwrap(), loaded);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_load_all_objects_skips_broken_records() {
+        let _setup = SetupLibraryWallet::init();
+        settings::set_config_value(settings::CONFIG_PERSIST_OBJECTS, "true");
+
+        let serialized = proof::to_string(_create_proof()).unwrap();
+        proof::release_all();
+        save(events::PROOF, "broken", "not a proof").unwrap();
+
+        let loaded = load_all_objects().unwrap();
+        assert_eq!(loaded.proofs.len(), 1);
+        assert_eq!(proof::to_string(loaded.proofs[0]).unwrap(), serialized);
+    }
+}
@@ -1,9 +1,20 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+
 use indy::ErrorCode;
 
+use error::prelude::*;
+#[cfg(test)]
 use settings;
 
+#[cfg(test)]
 static INIT_PLUGIN: std::sync::Once = std::sync::Once::new();
 
+lazy_static! {
+    static ref STORAGE_PLUGINS: Mutex<HashSet<String>> = Default::default();
+}
+
+#[cfg(test)]
 pub fn init_plugin(library: &str, initializer: &str) {
     settings::set_config_value(settings::CONFIG_PAYMENT_METHOD, settings::DEFAULT_PAYMENT_METHOD);
 
@@ -34,6 +45,34 @@ pub fn init_plugin(library: &str, initializer: &str) {
     });
 }
 
+/// Loads wallet storage plugin (e.g. `libindystrgpostgres.so` with `postgresstorage_init`),
+/// its init function registers the storage type in libindy. Loading the same library again does nothing.
+pub fn init_storage_plugin(library: &str, initializer: &str) -> VcxResult<()> {
+    let mut loaded = STORAGE_PLUGINS.lock().unwrap();
+    if loaded.contains(library) {
+        return Ok(());
+    }
+
+    let lib = _load_lib(library)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::WalletStoragePlugin, format!("Storage plugin {} not found: {}", library, err)))?;
+
+    unsafe {
+        let init_func: libloading::Symbol<unsafe extern fn() -> ErrorCode> = lib.get(initializer.as_bytes())
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::WalletStoragePlugin, format!("Init function {} not found: {}", initializer, err)))?;
+
+        match init_func() {
+            ErrorCode::Success => debug!("Storage plugin has been loaded: {:?}", library),
+            error_code => return Err(VcxError::from_msg(VcxErrorKind::WalletStoragePlugin,
+                                                        format!("Storage plugin {} failed to initialize: {:?}", library, error_code)))
+        }
+    }
+
+    // registered storage calls into the library for the rest of the process lifetime
+    ::std::mem::forget(lib);
+    loaded.insert(library.to_string());
+    Ok(())
+}
+
 #[cfg(all(unix, test, not(target_os = "android")))]
 fn _load_lib(library: &str) -> libloading::Result<libloading::Library> {
     libloading::os::unix::Library::open(Some(library), libc::RTLD_NOW | libc::RTLD_NODELETE)
@@ -1,13 +1,47 @@
-use regex::Regex;
+use regex::{Captures, Regex};
 
 lazy_static! {
     pub static ref REGEX: Regex = Regex::new("did:([a-z0-9]+):([a-zA-Z0-9:.-_]*)").unwrap();
+    // optional object prefix (`schema:sov:`, `creddef:indy:`, ...), DID method, network namespaces and the unqualified DID
+    static ref QUALIFIED_DID: Regex = Regex::new(r"(?:(?:schema|creddef|revreg):[a-z0-9]+:)?did:[a-z0-9]+:(?:[a-z0-9_.-]+:)*?([1-9A-HJ-NP-Za-km-z]{21,22}|[1-9A-HJ-NP-Za-km-z]{43,44})\b").unwrap();
 }
 
 pub fn is_fully_qualified(entity: &str) -> bool {
     REGEX.is_match(&entity)
 }
 
+/// `did:{method}:{did}`, DIDs which are qualified already are returned unchanged.
+pub fn qualify_did(did: &str, method: &str) -> String {
+    if is_fully_qualified(did) {
+        did.to_string()
+    } else {
+        format!("did:{}:{}", method, did)
+    }
+}
+
+///
+/// Replaces fully qualified DIDs and identifiers (`did:sov:..`, `did:indy:sovrin:staging:..`,
+/// `schema:sov:did:sov:..`, `creddef:indy:did:indy:..`) in `entity` by their unqualified form.
+/// Unqualified entities are returned unchanged.
+///
+pub fn unqualify(entity: &str) -> String {
+    QUALIFIED_DID.replace_all(entity, |captures: &Captures| captures[1].to_string()).to_string()
+}
+
+/// Form of `entity` accepted by libindy ledger requests: libindy resolves `did:sov` identifiers itself,
+/// identifiers of other methods (e.g. `did:indy`) are passed unqualified.
+pub fn to_libindy(entity: &str) -> String {
+    match method(entity) {
+        Some(ref method) if method != "sov" => unqualify(entity),
+        _ => entity.to_string()
+    }
+}
+
+/// DID method of a fully qualified DID or identifier.
+pub fn method(entity: &str) -> Option<String> {
+    REGEX.captures(entity).map(|captures| captures[1].to_string())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -19,4 +53,34 @@ mod test {
         assert!(!is_fully_qualified("did:indy"));
         assert!(!is_fully_qualified("indy:some"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn qualify_did_works() {
+        assert_eq!(qualify_did("V4SGRU86Z58d6TV7PBUe6f", "sov"), "did:sov:V4SGRU86Z58d6TV7PBUe6f");
+        assert_eq!(qualify_did("did:indy:sovrin:V4SGRU86Z58d6TV7PBUe6f", "sov"), "did:indy:sovrin:V4SGRU86Z58d6TV7PBUe6f");
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn method_works() {
+        assert_eq!(method("did:indy:sovrin:V4SGRU86Z58d6TV7PBUe6f"), Some("indy".to_string()));
+        assert_eq!(method("V4SGRU86Z58d6TV7PBUe6f"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn unqualify_works() {
+        assert_eq!(unqualify("V4SGRU86Z58d6TV7PBUe6f"), "V4SGRU86Z58d6TV7PBUe6f");
+        assert_eq!(unqualify("did:sov:V4SGRU86Z58d6TV7PBUe6f"), "V4SGRU86Z58d6TV7PBUe6f");
+        assert_eq!(unqualify("did:indy:sovrin:staging:V4SGRU86Z58d6TV7PBUe6f"), "V4SGRU86Z58d6TV7PBUe6f");
+        assert_eq!(unqualify("schema:sov:did:sov:V4SGRU86Z58d6TV7PBUe6f:2:gvt:1.0"), "V4SGRU86Z58d6TV7PBUe6f:2:gvt:1.0");
+        assert_eq!(unqualify("creddef:sov:did:sov:V4SGRU86Z58d6TV7PBUe6f:3:CL:schema:sov:did:sov:V4SGRU86Z58d6TV7PBUe6f:2:gvt:1.0:tag"),
+                   "V4SGRU86Z58d6TV7PBUe6f:3:CL:V4SGRU86Z58d6TV7PBUe6f:2:gvt:1.0:tag");
+        assert_eq!(unqualify("creddef:indy:did:indy:sovrin:V4SGRU86Z58d6TV7PBUe6f:3:CL:1015:tag"), "V4SGRU86Z58d6TV7PBUe6f:3:CL:1015:tag");
+        assert_eq!(unqualify(r#"{"issuer_did":"did:indy:sovrin:V4SGRU86Z58d6TV7PBUe6f"}"#), r#"{"issuer_did":"V4SGRU86Z58d6TV7PBUe6f"}"#);
+
+        assert_eq!(to_libindy("schema:sov:did:sov:V4SGRU86Z58d6TV7PBUe6f:2:gvt:1.0"), "schema:sov:did:sov:V4SGRU86Z58d6TV7PBUe6f:2:gvt:1.0");
+        assert_eq!(to_libindy("did:indy:sovrin:V4SGRU86Z58d6TV7PBUe6f"), "V4SGRU86Z58d6TV7PBUe6f");
+    }
+}
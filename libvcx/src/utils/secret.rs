@@ -0,0 +1,150 @@
+use std::fmt;
+use std::ops::Deref;
+use std::ptr;
+use std::sync::atomic::{compiler_fence, Ordering};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+static REDACTED: &str = "[redacted]";
+
+/// Overwrites `bytes` with zeros, the writes are not optimized out even though the buffer is freed right after.
+pub fn zeroize(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut() {
+        unsafe { ptr::write_volatile(byte, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+pub fn zeroize_string(value: &mut String) {
+    // zeros are valid UTF-8, so the string stays valid
+    zeroize(unsafe { value.as_bytes_mut() });
+    value.clear();
+}
+
+///
+/// String holding a secret (wallet key, seed, ...). It is wiped from memory when dropped
+/// and is never printed by `Debug` or `Display`, the value has to be read explicitly by `expose`.
+///
+#[derive(Clone, Default, PartialEq)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(value: String) -> SecretString {
+        SecretString(value)
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> SecretString {
+        SecretString(value)
+    }
+}
+
+impl<'a> From<&'a str> for SecretString {
+    fn from(value: &'a str) -> SecretString {
+        SecretString(value.to_string())
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        zeroize_string(&mut self.0);
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl fmt::Display for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl Serialize for SecretString {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D>(deserializer: D) -> Result<SecretString, D::Error> where D: Deserializer<'de> {
+        String::deserialize(deserializer).map(SecretString)
+    }
+}
+
+/// Buffer with sensitive content (e.g. unpacked message), wiped from memory when dropped.
+#[derive(Clone, Default, PartialEq)]
+pub struct SecretBytes(Vec<u8>);
+
+impl SecretBytes {
+    pub fn expose(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(value: Vec<u8>) -> SecretBytes {
+        SecretBytes(value)
+    }
+}
+
+impl Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        zeroize(&mut self.0);
+    }
+}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({} bytes)", REDACTED, self.0.len())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_secrets_are_not_printed() {
+        let secret = SecretString::from("wallet key");
+
+        assert_eq!(format!("{:?}", secret), REDACTED);
+        assert_eq!(format!("{}", secret), REDACTED);
+        assert_eq!(secret.expose(), "wallet key");
+        assert_eq!(format!("{:?}", SecretBytes::from(vec![1, 2, 3])), "[redacted] (3 bytes)");
+
+        let secret: SecretString = ::serde_json::from_str("\"seed\"").unwrap();
+        assert_eq!(::serde_json::to_string(&secret).unwrap(), "\"seed\"");
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_zeroize() {
+        let mut bytes = vec![1u8, 2, 3];
+        zeroize(&mut bytes);
+        assert_eq!(bytes, vec![0, 0, 0]);
+
+        let mut value = "secret".to_string();
+        let capacity = value.capacity();
+        zeroize_string(&mut value);
+        assert!(value.is_empty());
+        assert_eq!(value.capacity(), capacity);
+    }
+}
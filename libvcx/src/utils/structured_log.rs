@@ -0,0 +1,248 @@
+use std::cell::RefCell;
+use std::sync::{Arc, RwLock};
+
+use chrono::Utc;
+use log::{self, Level, Record};
+use serde_json::{Map, Value};
+
+use aries::messages::a2a::A2AMessage;
+
+pub type StructuredLogSink = Arc<dyn Fn(&StructuredRecord) + Send + Sync>;
+
+// records of protocol exchange points reach the sink regardless of the logger level, more detailed ones only when the logger enables them
+const SINK_LEVEL: Level = Level::Debug;
+
+lazy_static! {
+    static ref SINK: RwLock<Option<StructuredLogSink>> = Default::default();
+}
+
+thread_local! {
+    static CORRELATION: RefCell<Vec<Correlation>> = RefCell::new(Vec::new());
+}
+
+///
+/// Identifies the protocol exchange a log record belongs to.
+/// Fields which are not known at the place where a scope is entered are inherited from the enclosing scope.
+///
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Correlation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection_handle: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_type: Option<String>,
+}
+
+impl Correlation {
+    pub fn new() -> Correlation {
+        Correlation::default()
+    }
+
+    pub fn thread_id(mut self, thread_id: Option<String>) -> Correlation {
+        self.thread_id = thread_id.filter(|thread_id| !thread_id.is_empty());
+        self
+    }
+
+    pub fn connection_handle(mut self, connection_handle: Option<u32>) -> Correlation {
+        self.connection_handle = connection_handle.filter(|handle| *handle != 0);
+        self
+    }
+
+    pub fn message_type(mut self, message_type: Option<String>) -> Correlation {
+        self.message_type = message_type;
+        self
+    }
+
+    /// Takes thread id and type of the message being sent or handled.
+    pub fn message(self, message: &A2AMessage) -> Correlation {
+        let thread_id = message.thread_id();
+        self.thread_id(thread_id).message_type(message.type_name())
+    }
+
+    /// `{thread_id}:{connection_handle}:{message_type}`, unknown parts are replaced by `-`.
+    pub fn id(&self) -> String {
+        format!("{}:{}:{}",
+                self.thread_id.as_ref().map(String::as_str).unwrap_or("-"),
+                self.connection_handle.map(|handle| handle.to_string()).unwrap_or("-".to_string()),
+                self.message_type.as_ref().map(String::as_str).unwrap_or("-"))
+    }
+
+    fn inherit(self, outer: &Correlation) -> Correlation {
+        Correlation {
+            thread_id: self.thread_id.or(outer.thread_id.clone()),
+            connection_handle: self.connection_handle.or(outer.connection_handle),
+            message_type: self.message_type.or(outer.message_type.clone()),
+        }
+    }
+}
+
+/// Leaves the correlation scope when dropped.
+pub struct CorrelationGuard(());
+
+impl Drop for CorrelationGuard {
+    fn drop(&mut self) {
+        CORRELATION.with(|scopes| { scopes.borrow_mut().pop(); });
+    }
+}
+
+/// Attaches `correlation` to all records logged by the current thread until the returned guard is dropped.
+pub fn enter(correlation: Correlation) -> CorrelationGuard {
+    CORRELATION.with(|scopes| {
+        let mut scopes = scopes.borrow_mut();
+        let correlation = match scopes.last() {
+            Some(outer) => correlation.inherit(outer),
+            None => correlation
+        };
+        scopes.push(correlation);
+    });
+    CorrelationGuard(())
+}
+
+pub fn current() -> Correlation {
+    CORRELATION.with(|scopes| scopes.borrow().last().cloned().unwrap_or_default())
+}
+
+/// Record passed to the structured log sink.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StructuredRecord {
+    pub timestamp: String,
+    pub level: String,
+    pub target: String,
+    pub event: String,
+    pub correlation_id: String,
+    #[serde(flatten)]
+    pub correlation: Correlation,
+    pub fields: Map<String, Value>,
+}
+
+impl StructuredRecord {
+    // key=value pairs for loggers which accept plain messages only
+    fn message(&self) -> String {
+        let mut message = format!("{} correlation_id={}", self.event, self.correlation_id);
+        for (key, value) in self.fields.iter() {
+            match value {
+                Value::String(value) => message.push_str(&format!(" {}={:?}", key, value)),
+                value => message.push_str(&format!(" {}={}", key, value)),
+            }
+        }
+        message
+    }
+}
+
+/// Sets sink receiving structured records up to `Debug` level, and `Trace` ones when the logger enables them. `None` removes the sink.
+pub fn set_sink(sink: Option<StructuredLogSink>) {
+    *SINK.write().unwrap() = sink;
+}
+
+pub fn enabled(level: Level, target: &str) -> bool {
+    log_enabled!(target: target, level) || (level <= SINK_LEVEL && SINK.read().unwrap().is_some())
+}
+
+/// Passes the record to the structured log sink and to the `log` logger. Use `log_event!` instead of calling it directly.
+pub fn emit(level: Level, target: &str, event: &str, fields: Map<String, Value>, line: u32) {
+    let logger_enabled = log_enabled!(target: target, level);
+    // sink is cloned out so that it can log itself
+    let sink = SINK.read().unwrap().clone().filter(|_| logger_enabled || level <= SINK_LEVEL);
+    if sink.is_none() && !logger_enabled {
+        return;
+    }
+
+    let correlation = current();
+    let record = StructuredRecord {
+        timestamp: Utc::now().to_rfc3339(),
+        level: level.to_string(),
+        target: target.to_string(),
+        event: event.to_string(),
+        correlation_id: correlation.id(),
+        correlation,
+        fields,
+    };
+
+    if let Some(sink) = sink {
+        sink(&record);
+    }
+
+    if logger_enabled {
+        log::logger().log(&Record::builder()
+            .args(format_args!("{}", record.message()))
+            .level(level)
+            .target(target)
+            .module_path(Some(target))
+            .line(Some(line))
+            .build());
+    }
+}
+
+///
+/// Logs event with key-value fields and the correlation id of the current protocol exchange:
+/// `log_event!(Level::Debug, "message_sent", message_id = id, attempt = 1)`.
+/// Values have to be serializable.
+///
+macro_rules! log_event {
+    ($level:expr, $event:expr $(, $key:ident = $value:expr)* $(,)*) => {{
+        let level = $level;
+        if ::utils::structured_log::enabled(level, module_path!()) {
+            #[allow(unused_mut)]
+            let mut fields = ::serde_json::Map::new();
+            $( fields.insert(stringify!($key).to_string(), json!($value)); )*
+            ::utils::structured_log::emit(level, module_path!(), $event, fields, line!());
+        }
+    }};
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_correlation_scopes_are_nested() {
+        assert_eq!(current().id(), "-:-:-");
+        {
+            let _outer = enter(Correlation::new().connection_handle(Some(5)).thread_id(Some("thread".to_string())));
+            {
+                let _inner = enter(Correlation::new().message_type(Some("present-proof/1.0/presentation".to_string())));
+                assert_eq!(current().id(), "thread:5:present-proof/1.0/presentation");
+            }
+            assert_eq!(current().id(), "thread:5:-");
+        }
+        assert_eq!(current(), Correlation::default());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_sink_receives_structured_records() {
+        lazy_static! {
+            static ref RECORDS: Mutex<Vec<StructuredRecord>> = Default::default();
+        }
+        set_sink(Some(Arc::new(|record: &StructuredRecord| {
+            if record.event == "structured_log_test" {
+                RECORDS.lock().unwrap().push(record.clone());
+            }
+        })));
+
+        {
+            let _correlation = enter(Correlation::new().thread_id(Some("thread".to_string())).connection_handle(Some(1)));
+            log_event!(Level::Debug, "structured_log_test", handle = 2, source_id = "alice");
+            log_event!(Level::Trace, "structured_log_test", handle = 3);
+        }
+        set_sink(None);
+
+        let records = RECORDS.lock().unwrap();
+        let trace_enabled = log_enabled!(Level::Trace);
+        assert_eq!(records.len(), if trace_enabled { 2 } else { 1 });
+        assert_eq!(records[0].level, "DEBUG");
+        assert_eq!(records[0].correlation_id, "thread:1:-");
+        assert_eq!(records[0].fields["handle"], json!(2));
+        assert_eq!(records[0].fields["source_id"], json!("alice"));
+        assert_eq!(records[0].message(), "structured_log_test correlation_id=thread:1:- handle=2 source_id=\"alice\"");
+
+        let record = json!(records[0]);
+        assert_eq!(record["thread_id"], json!("thread"));
+        assert_eq!(record["connection_handle"], json!(1));
+        assert!(record.get("message_type").is_none());
+    }
+}
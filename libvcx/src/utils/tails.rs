@@ -0,0 +1,276 @@
+extern crate rust_base58;
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use openssl::sha::sha256;
+use serde_json::Value;
+use url::Url;
+
+use self::rust_base58::ToBase58;
+use error::prelude::*;
+use settings;
+use utils::get_temp_dir_path;
+use utils::httpclient::{HttpRequest, RequestOptions};
+use utils::libindy::anoncreds;
+use utils::openssl::HashingWriter;
+use utils::timeout::TimeoutUtils;
+
+static DEFAULT_TAILS_DIR: &str = "tails";
+
+// tails file holds 2 byte version and two G2 points of 128 bytes per credential of the registry,
+// one more pair is allowed for the points of the accumulator
+const TAILS_VERSION_SIZE: u64 = 2;
+const TAILS_SIZE_PER_CREDENTIAL: u64 = 2 * 128;
+
+/// Tails files are stored by libindy under the name of their hash, which is published in the revocation registry definition.
+pub fn get_tails_hash(rev_reg_def_json: &str) -> VcxResult<String> {
+    let rev_reg_def: Value = ::serde_json::from_str(rev_reg_def_json)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize RevocationRegistryDefinition: {}", err)))?;
+
+    rev_reg_def["value"]["tailsHash"].as_str()
+        .map(String::from)
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidRevocationDetails, "RevocationRegistryDefinition does not contain `tailsHash`"))
+}
+
+pub fn verify_tails_hash(content: &[u8], tails_hash: &str) -> VcxResult<()> {
+    _check_tails_hash(&sha256(content), tails_hash)
+}
+
+fn _check_tails_hash(hash: &[u8], tails_hash: &str) -> VcxResult<()> {
+    let hash = hash.to_base58();
+    if hash != tails_hash {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidRevocationDetails,
+                                      format!("Tails file hash mismatch, expected: {}, actual: {}", tails_hash, hash)));
+    }
+    Ok(())
+}
+
+fn _tails_server_url() -> Option<String> {
+    settings::get_config_value(settings::CONFIG_TAILS_SERVER_URL).ok()
+}
+
+fn _tails_file_url(tails_server_url: &str, tails_hash: &str) -> String {
+    format!("{}/{}", tails_server_url.trim_end_matches('/'), tails_hash)
+}
+
+fn _request_options() -> RequestOptions {
+    RequestOptions::from_settings().timeout(TimeoutUtils::long_timeout())
+}
+
+// tails file as the only part of `multipart/form-data` body, named `tails` as expected by tails servers
+fn _multipart_body(tails_path: &Path, tails_hash: &str) -> VcxResult<(String, Vec<u8>)> {
+    let mut content = Vec::new();
+    File::open(tails_path)
+        .and_then(|mut file| file.read_to_end(&mut content))
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::IOError, format!("Cannot read tails file {:?}: {}", tails_path, err)))?;
+
+    let boundary = format!("tails-{}", ::utils::uuid::uuid());
+    let mut body = format!("--{}\r\nContent-Disposition: form-data; name=\"tails\"; filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+                           boundary, tails_hash).into_bytes();
+    body.extend_from_slice(&content);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+    Ok((format!("multipart/form-data; boundary={}", boundary), body))
+}
+
+/// Uploads tails file generated for the revocation registry to the configured tails server.
+/// Returns the public location of the tails file, or None when no tails server is configured.
+pub fn upload_tails_file(tails_dir: &str, rev_reg_def_json: &str) -> VcxResult<Option<String>> {
+    trace!("upload_tails_file >>> tails_dir: {}", tails_dir);
+
+    let tails_server_url = match _tails_server_url() {
+        Some(url) => url,
+        None => return Ok(None)
+    };
+
+    let tails_hash = get_tails_hash(rev_reg_def_json)?;
+    let url = _tails_file_url(&tails_server_url, &tails_hash);
+
+    if settings::indy_mocks_enabled() { return Ok(Some(url)); }
+
+    let tails_path = Path::new(tails_dir).join(&tails_hash);
+    let (content_type, body) = _multipart_body(&tails_path, &tails_hash)?;
+
+    debug!("Uploading tails file {:?} to: \"{}\"", tails_path, url);
+    let response = HttpRequest::put(&url, &content_type, body)?
+        .options(_request_options())
+        .send()
+        .map_err(|failure| failure.into_error().extend("Could not upload tails file"))?;
+
+    if !response.is_success() {
+        return Err(VcxError::from_msg(VcxErrorKind::PostMessageFailed, format!("Tails file upload failed with status: {}", response.status)));
+    }
+
+    Ok(Some(url))
+}
+
+/// Uploads tails file and points `tailsLocation` of the revocation registry definition to the tails server.
+pub fn publish_tails_file(tails_dir: &str, rev_reg_def_json: &str) -> VcxResult<String> {
+    match upload_tails_file(tails_dir, rev_reg_def_json)? {
+        Some(tails_location) => {
+            let mut rev_reg_def: Value = ::serde_json::from_str(rev_reg_def_json)
+                .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize RevocationRegistryDefinition: {}", err)))?;
+            rev_reg_def["value"]["tailsLocation"] = json!(tails_location);
+            Ok(rev_reg_def.to_string())
+        }
+        None => Ok(rev_reg_def_json.to_string())
+    }
+}
+
+// tails file is downloaded from the location published by the issuer when it is a https URL, from the configured
+// tails server otherwise; the published location is given by the issuer, so only public hosts are accepted for it
+fn _tails_download_url(rev_reg_def: &Value, tails_server_url: &str, tails_hash: &str) -> (String, bool) {
+    match rev_reg_def["value"]["tailsLocation"].as_str().and_then(|location| Url::parse(location).ok()) {
+        Some(ref location) if location.scheme() == "https" => (location.to_string(), true),
+        _ => (_tails_file_url(tails_server_url, tails_hash), false)
+    }
+}
+
+fn _max_tails_size(rev_reg_def: &Value) -> VcxResult<u64> {
+    rev_reg_def["value"]["maxCredNum"].as_u64()
+        .map(|max_cred_num| TAILS_VERSION_SIZE + TAILS_SIZE_PER_CREDENTIAL * (max_cred_num + 1))
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidRevocationDetails, "RevocationRegistryDefinition does not contain `maxCredNum`"))
+}
+
+fn _download_tails_file(url: &str, public_host: bool, tails_hash: &str, max_size: u64, tails_path: &PathBuf) -> VcxResult<()> {
+    debug!("Downloading tails file from: \"{}\"", url);
+
+    let mut request = HttpRequest::get(url)?.options(_request_options());
+    if public_host {
+        request = request.public_hosts_only();
+    }
+    let mut response = request.send()
+        .map_err(|failure| failure.into_error().extend("Could not download tails file"))?;
+
+    if !response.is_success() {
+        return Err(VcxError::from_msg(VcxErrorKind::InvalidHttpResponse, format!("Tails file download failed with status: {}", response.status)));
+    }
+
+    if let Some(parent) = tails_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::IOError, format!("Cannot create tails directory: {}", err)))?;
+    }
+
+    // the file is streamed under temporary name, so that incomplete or altered content never takes place of the tails file
+    let download_path = tails_path.with_extension("download");
+    let result = File::create(&download_path)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::IOError, format!("Cannot store tails file: {}", err)))
+        .and_then(|mut file| {
+            let mut writer = HashingWriter::new(&mut file);
+            response.copy_limited(&mut writer, max_size)?;
+            _check_tails_hash(&writer.finish(), tails_hash)
+        })
+        .and_then(|_| fs::rename(&download_path, tails_path)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::IOError, format!("Cannot store tails file: {}", err))));
+
+    if result.is_err() {
+        fs::remove_file(&download_path).ok();
+    }
+    result
+}
+
+/// Returns directory containing tails file of the revocation registry.
+/// When the file is not present locally and a tails server is configured, it is downloaded from `tailsLocation`
+/// of the registry definition (https only) or from the tails server, and its size and hash are verified.
+/// Returns None when the tails file is neither available locally nor on the tails server.
+pub fn get_tails_dir(rev_reg_id: &str, tails_dir: Option<&str>) -> VcxResult<Option<String>> {
+    trace!("get_tails_dir >>> rev_reg_id: {}, tails_dir: {:?}", rev_reg_id, tails_dir);
+
+    let tails_server_url = match _tails_server_url() {
+        Some(url) => url,
+        None => return Ok(tails_dir.map(String::from))
+    };
+
+    let tails_dir = tails_dir.map(PathBuf::from)
+        .or(settings::get_config_value(settings::CONFIG_TAILS_DIR).ok().map(PathBuf::from))
+        .unwrap_or_else(|| get_temp_dir_path(DEFAULT_TAILS_DIR));
+
+    if settings::indy_mocks_enabled() { return Ok(Some(tails_dir.to_string_lossy().to_string())); }
+
+    let (_, rev_reg_def_json) = anoncreds::get_rev_reg_def_json(rev_reg_id)?;
+    let tails_hash = get_tails_hash(&rev_reg_def_json)?;
+    let tails_path = tails_dir.join(&tails_hash);
+
+    if !tails_path.exists() {
+        let rev_reg_def: Value = ::serde_json::from_str(&rev_reg_def_json)
+            .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize RevocationRegistryDefinition: {}", err)))?;
+        let (url, public_host) = _tails_download_url(&rev_reg_def, &tails_server_url, &tails_hash);
+        _download_tails_file(&url, public_host, &tails_hash, _max_tails_size(&rev_reg_def)?, &tails_path)?;
+    }
+
+    Ok(Some(tails_dir.to_string_lossy().to_string()))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use utils::constants::{rev_def_json, REV_REG_ID};
+    use utils::devsetup::*;
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_get_tails_hash() {
+        let _setup = SetupDefaults::init();
+
+        assert_eq!(get_tails_hash(&rev_def_json()).unwrap(), "5R6BWXL3vPrbJPKe9FsHAVG9hqKdDvVxonBuj3ETYuZh");
+        assert_eq!(get_tails_hash("{}").unwrap_err().kind(), VcxErrorKind::InvalidRevocationDetails);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_verify_tails_hash() {
+        let _setup = SetupDefaults::init();
+
+        let content = b"tails";
+        let hash = sha256(content).to_base58();
+
+        verify_tails_hash(content, &hash).unwrap();
+        assert_eq!(verify_tails_hash(b"modified", &hash).unwrap_err().kind(), VcxErrorKind::InvalidRevocationDetails);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_tails_download_url_and_size() {
+        let _setup = SetupDefaults::init();
+
+        let mut rev_reg_def: Value = ::serde_json::from_str(&rev_def_json()).unwrap();
+        assert_eq!(_tails_download_url(&rev_reg_def, "https://tails.example.com", "hash"), ("https://tails.example.com/hash".to_string(), false));
+        assert_eq!(_max_tails_size(&rev_reg_def).unwrap(), 2 + 256 * 11);
+
+        rev_reg_def["value"]["tailsLocation"] = json!("https://issuer.example.com/tails/hash");
+        assert_eq!(_tails_download_url(&rev_reg_def, "https://tails.example.com", "hash"), ("https://issuer.example.com/tails/hash".to_string(), true));
+
+        rev_reg_def["value"]["tailsLocation"] = json!("http://issuer.example.com/tails/hash");
+        assert_eq!(_tails_download_url(&rev_reg_def, "https://tails.example.com", "hash"), ("https://tails.example.com/hash".to_string(), false));
+
+        rev_reg_def["value"]["maxCredNum"] = Value::Null;
+        assert_eq!(_max_tails_size(&rev_reg_def).unwrap_err().kind(), VcxErrorKind::InvalidRevocationDetails);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_tails_server_not_configured() {
+        let _setup = SetupAriesMocks::init();
+
+        assert_eq!(upload_tails_file("tails", &rev_def_json()).unwrap(), None);
+        assert_eq!(get_tails_dir(REV_REG_ID, None).unwrap(), None);
+        assert_eq!(get_tails_dir(REV_REG_ID, Some("tails")).unwrap(), Some("tails".to_string()));
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_tails_server_configured() {
+        let _setup = SetupAriesMocks::init();
+        settings::set_config_value(settings::CONFIG_TAILS_SERVER_URL, "https://tails.example.com/");
+
+        assert_eq!(upload_tails_file("tails", &rev_def_json()).unwrap(),
+                   Some("https://tails.example.com/5R6BWXL3vPrbJPKe9FsHAVG9hqKdDvVxonBuj3ETYuZh".to_string()));
+        assert!(get_tails_dir(REV_REG_ID, None).unwrap().is_some());
+
+        let rev_reg_def: Value = ::serde_json::from_str(&publish_tails_file("tails", &rev_def_json()).unwrap()).unwrap();
+        assert_eq!(rev_reg_def["value"]["tailsLocation"], json!("https://tails.example.com/5R6BWXL3vPrbJPKe9FsHAVG9hqKdDvVxonBuj3ETYuZh"));
+    }
+}
@@ -10,6 +10,8 @@ use std::thread;
 use self::futures::Future;
 use self::tokio_threadpool::{Builder, ThreadPool};
 
+use utils::agent_context;
+
 lazy_static! {
     static ref THREADPOOL: Mutex<HashMap<u32, ThreadPool>> = Default::default();
 }
@@ -34,9 +36,16 @@ pub fn init() {
     }
 }
 
+/// Runs `future` on the threadpool (or a new thread), within the agent context of the calling thread.
 pub fn spawn<F>(future: F)
     where
         F: FnOnce() -> Result<(), ()> + Send + 'static {
+    let context = agent_context::current();
+    let future = move || match context {
+        Some(ref context) => agent_context::with_context(context, future),
+        None => future()
+    };
+
     let handle;
     unsafe { handle = TP_HANDLE; }
     if ::settings::get_threadpool_size() == 0 || handle == 0 {
@@ -58,3 +67,26 @@ fn spawn_thread_in_pool<F>(future: F)
         None => panic!("no threadpool!"),
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::mpsc::channel;
+    use std::time::Duration;
+
+    use utils::agent_context::AgentContext;
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_spawn_keeps_agent_context() {
+        let (sender, receiver) = channel();
+
+        agent_context::with_context(&AgentContext::new(1003), || spawn(move || {
+            sender.send(agent_context::current_agent_id()).ok();
+            Ok(())
+        }));
+
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)).unwrap(), Some(1003));
+    }
+}
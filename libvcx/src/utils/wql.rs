@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use error::prelude::*;
+
+/// Evaluates WQL query (as used by proof request restrictions) against a set of credential tags.
+/// A tag missing from `tags` never satisfies a condition placed on it.
+pub fn matches(query: &Value, tags: &HashMap<String, String>) -> VcxResult<bool> {
+    match query {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let satisfied = match key.as_str() {
+                    "$and" => _as_array(key, value)?.iter().map(|sub| matches(sub, tags)).collect::<VcxResult<Vec<bool>>>()?.iter().all(|x| *x),
+                    "$or" => _any(value, tags)?,
+                    "$not" => !matches(value, tags)?,
+                    tag_name => _matches_tag(tag_name, value, tags)?
+                };
+                if !satisfied { return Ok(false); }
+            }
+            Ok(true)
+        }
+        Value::Array(_) => _any(query, tags),
+        _ => Err(VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Invalid WQL query: {}", query)))
+    }
+}
+
+/// Checks that `query` uses only the conditions `matches` (and wallet search) understands.
+pub fn validate(query: &Value) -> VcxResult<()> {
+    match query {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match key.as_str() {
+                    "$and" | "$or" => for sub in _as_array(key, value)? { validate(sub)?; },
+                    "$not" => validate(value)?,
+                    tag_name => _validate_tag(tag_name, value)?
+                }
+            }
+            Ok(())
+        }
+        Value::Array(alternatives) => alternatives.iter().map(validate).collect(),
+        _ => Err(VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Invalid WQL query: {}", query)))
+    }
+}
+
+// evaluation stops at the first unsatisfied operator, so they are checked one by one
+fn _validate_tag(tag_name: &str, condition: &Value) -> VcxResult<()> {
+    let no_tags = HashMap::new();
+    match condition {
+        Value::Object(operators) => {
+            for (operator, operand) in operators {
+                let mut single = Map::new();
+                single.insert(operator.clone(), operand.clone());
+                _matches_tag(tag_name, &Value::Object(single), &no_tags)?;
+            }
+            Ok(())
+        }
+        condition => _matches_tag(tag_name, condition, &no_tags).map(|_| ())
+    }
+}
+
+fn _any(query: &Value, tags: &HashMap<String, String>) -> VcxResult<bool> {
+    let alternatives = _as_array("$or", query)?;
+    for alternative in alternatives {
+        if matches(alternative, tags)? { return Ok(true); }
+    }
+    Ok(alternatives.is_empty())
+}
+
+fn _as_array<'a>(operator: &str, value: &'a Value) -> VcxResult<&'a Vec<Value>> {
+    value.as_array()
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Invalid WQL query: `{}` expects an array", operator)))
+}
+
+fn _matches_tag(tag_name: &str, condition: &Value, tags: &HashMap<String, String>) -> VcxResult<bool> {
+    let tag_value = tags.get(tag_name);
+
+    match condition {
+        Value::String(expected) => Ok(tag_value == Some(expected)),
+        Value::Object(operators) => {
+            for (operator, operand) in operators {
+                let satisfied = match (operator.as_str(), operand) {
+                    ("$eq", Value::String(expected)) => tag_value == Some(expected),
+                    ("$neq", Value::String(expected)) => tag_value.map(|value| value != expected).unwrap_or(false),
+                    ("$in", Value::Array(expected)) => tag_value.map(|value| expected.iter().any(|item| item.as_str() == Some(value))).unwrap_or(false),
+                    ("$like", Value::String(pattern)) => tag_value.map(|value| _like(value, pattern)).unwrap_or(false),
+                    ("$gt", Value::String(expected)) => tag_value.map(|value| value > expected).unwrap_or(false),
+                    ("$gte", Value::String(expected)) => tag_value.map(|value| value >= expected).unwrap_or(false),
+                    ("$lt", Value::String(expected)) => tag_value.map(|value| value < expected).unwrap_or(false),
+                    ("$lte", Value::String(expected)) => tag_value.map(|value| value <= expected).unwrap_or(false),
+                    _ => return Err(VcxError::from_msg(VcxErrorKind::InvalidJson,
+                                                       format!("Invalid WQL query: unsupported condition `{}: {}` for tag `{}`", operator, operand, tag_name)))
+                };
+                if !satisfied { return Ok(false); }
+            }
+            Ok(true)
+        }
+        _ => Err(VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Invalid WQL query: unsupported value of tag `{}`: {}", tag_name, condition)))
+    }
+}
+
+// `%` matches any sequence of characters, the only wildcard supported by wallet search
+fn _like(value: &str, pattern: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('%').collect();
+    if parts.len() == 1 { return value == pattern; }
+
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !value.starts_with(first) || !value[first.len()..].ends_with(last) { return false; }
+
+    let mut rest = &value[first.len()..value.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn _tags() -> HashMap<String, String> {
+        vec![("issuer_did", "V4SGRU86Z58d6TV7PBUe6f"), ("schema_name", "degree"), ("attr::name::value", "alice")]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_wql_matches() {
+        let tags = _tags();
+
+        assert!(matches(&json!({}), &tags).unwrap());
+        assert!(matches(&json!({"issuer_did": "V4SGRU86Z58d6TV7PBUe6f", "schema_name": "degree"}), &tags).unwrap());
+        assert!(!matches(&json!({"issuer_did": "V4SGRU86Z58d6TV7PBUe6f", "schema_name": "passport"}), &tags).unwrap());
+        assert!(matches(&json!([{"schema_name": "passport"}, {"attr::name::value": "alice"}]), &tags).unwrap());
+        assert!(matches(&json!({"$or": [{"schema_name": "passport"}, {"schema_name": {"$in": ["degree", "diploma"]}}]}), &tags).unwrap());
+        assert!(matches(&json!({"$and": [{"schema_name": {"$like": "deg%"}}, {"$not": {"cred_def_id": "other"}}]}), &tags).unwrap());
+        assert!(!matches(&json!({"cred_def_id": {"$neq": "other"}}), &tags).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_wql_invalid_query() {
+        assert_eq!(matches(&json!("string"), &_tags()).unwrap_err().kind(), VcxErrorKind::InvalidJson);
+        assert_eq!(matches(&json!({"schema_name": {"$regex": "deg"}}), &_tags()).unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_wql_validate() {
+        validate(&json!({})).unwrap();
+        validate(&json!({"$or": [{"schema_name": "degree"}, {"$not": {"issuer_did": {"$neq": "did", "$like": "V4%"}}}]})).unwrap();
+
+        assert_eq!(validate(&json!({"schema_name": {"$eq": "degree", "$regex": "deg"}})).unwrap_err().kind(), VcxErrorKind::InvalidJson);
+        assert_eq!(validate(&json!({"$and": {"schema_name": "degree"}})).unwrap_err().kind(), VcxErrorKind::InvalidJson);
+        assert_eq!(validate(&json!([1])).unwrap_err().kind(), VcxErrorKind::InvalidJson);
+    }
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_like() {
+        assert!(_like("degree", "degree"));
+        assert!(_like("degree", "%"));
+        assert!(_like("degree", "d%e"));
+        assert!(_like("degree", "%gr%"));
+        assert!(!_like("degree", "%x%"));
+        assert!(!_like("de", "de%e"));
+    }
+}
@@ -0,0 +1,121 @@
+use serde_json;
+
+use error::prelude::*;
+use utils::libindy::wallet;
+use utils::uuid;
+use aries::messages::issuance::w3c::W3cCredential;
+
+static W3C_CREDENTIAL_RECORD_TYPE: &str = "VcxW3cCredential";
+const LIST_PAGE_SIZE: usize = 50;
+
+///
+/// Stores W3C credential received by the holder in the wallet, the credential is kept under its `id`
+/// or under a generated one if it has no `id`. Returns the id of the stored credential.
+///
+pub fn store(credential: &W3cCredential) -> VcxResult<String> {
+    trace!("w3c_credential::store >>> issuer: {}", credential.issuer);
+
+    let id = credential.id.clone().unwrap_or_else(uuid::uuid);
+
+    let value = serde_json::to_string(credential)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::SerializationError, format!("Cannot serialize W3C Credential: {:?}", err)))?;
+
+    let mut tags = json!({
+        "issuer": credential.issuer,
+        "subject_id": credential.subject_id().unwrap_or_default(),
+    });
+
+    // claims are tagged the same way as Indy credentials, so they can be searched by the same queries
+    for (name, value) in credential.credential_subject.iter().filter(|(name, _)| name.as_str() != "id") {
+        tags[format!("attr::{}::marker", name)] = json!("1");
+        if let Some(value) = value.as_str() {
+            tags[format!("attr::{}::value", name)] = json!(value);
+        }
+    }
+
+    wallet::add_record(W3C_CREDENTIAL_RECORD_TYPE, &id, &value, Some(&tags.to_string()))?;
+    Ok(id)
+}
+
+/// Returns W3C credential stored under `id`.
+pub fn get(id: &str) -> VcxResult<String> {
+    trace!("w3c_credential::get >>> id: {}", id);
+
+    let options = json!({"retrieveType": false, "retrieveValue": true, "retrieveTags": false}).to_string();
+
+    let record = wallet::get_record(W3C_CREDENTIAL_RECORD_TYPE, id, &options)?;
+
+    let record: serde_json::Value = serde_json::from_str(&record)
+        .map_err(|err| VcxError::from_msg(VcxErrorKind::InvalidJson, format!("Cannot deserialize W3C Credential record: {:?}", err)))?;
+
+    record["value"].as_str()
+        .map(String::from)
+        .ok_or(VcxError::from_msg(VcxErrorKind::InvalidJson, format!("W3C Credential record {} has no value", id)))
+}
+
+/// Lists W3C credentials held in the wallet: `[{"id": <id>, "credential": <credential>}, ...]`
+pub fn list() -> VcxResult<String> {
+    trace!("w3c_credential::list >>>");
+
+    let credentials: Vec<serde_json::Value> = search("{}")?.into_iter()
+        .map(|(id, credential)| json!({"id": id, "credential": credential}))
+        .collect();
+
+    Ok(json!(credentials).to_string())
+}
+
+///
+/// Searches W3C credentials by WQL `query` over their tags: `issuer`, `subject_id` and
+/// `attr::<claim>::marker`, `attr::<claim>::value` for claims of the credential subject.
+/// Returns pairs of credential id and credential.
+///
+pub fn search(query: &str) -> VcxResult<Vec<(String, W3cCredential)>> {
+    trace!("w3c_credential::search >>> query: {}", query);
+
+    let options = json!({"retrieveType": false, "retrieveValue": true, "retrieveTags": false}).to_string();
+    let mut credentials = Vec::new();
+
+    for record in wallet::search_records(W3C_CREDENTIAL_RECORD_TYPE, query, &options, LIST_PAGE_SIZE)? {
+        let record = record?;
+        let credential = W3cCredential::from_str(record["value"].as_str().unwrap_or_default())?;
+        credentials.push((record["id"].as_str().unwrap_or_default().to_string(), credential));
+    }
+
+    Ok(credentials)
+}
+
+pub fn delete(id: &str) -> VcxResult<()> {
+    trace!("w3c_credential::delete >>> id: {}", id);
+
+    wallet::delete_record(W3C_CREDENTIAL_RECORD_TYPE, id)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use utils::devsetup::SetupLibraryWallet;
+    use aries::messages::issuance::w3c::tests::_w3c_credential;
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "general_test")]
+    fn test_w3c_credential_store_get_list_delete() {
+        let _setup = SetupLibraryWallet::init();
+
+        let credential = _w3c_credential();
+        let id = store(&credential).unwrap();
+
+        assert_eq!(credential, W3cCredential::from_str(&get(&id).unwrap()).unwrap());
+
+        let credentials: serde_json::Value = serde_json::from_str(&list().unwrap()).unwrap();
+        assert_eq!(json!([{"id": id, "credential": credential}]), credentials);
+
+        assert_eq!(1, search(&json!({"attr::name::value": "Alice"}).to_string()).unwrap().len());
+        assert_eq!(0, search(&json!({"attr::name::value": "Bob"}).to_string()).unwrap().len());
+        assert_eq!(0, search(&json!({"attr::height::marker": "1"}).to_string()).unwrap().len());
+
+        delete(&id).unwrap();
+        assert_eq!(get(&id).unwrap_err().kind(), VcxErrorKind::WalletRecordNotFound);
+        assert_eq!("[]", list().unwrap());
+    }
+}
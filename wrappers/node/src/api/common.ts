@@ -115,7 +115,8 @@ export enum StateType {
     Expired = 6,
     Revoked = 7,
     Redirected = 8,
-    Rejected = 9
+    Rejected = 9,
+    Sending = 10
 }
 
 export interface IInitVCXOptions {
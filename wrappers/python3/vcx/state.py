@@ -12,6 +12,7 @@ class State(IntEnum):
     Revoked = 7,
     Redirected = 8,
     Rejected = 9,
+    Sending = 10,
 
 
 class ProofState(IntEnum):